@@ -140,6 +140,9 @@ from .models.sensors import (
 # !!DO NOT REMOVE!!
 from .ros_bridge import data_ontology  # noqa: F401
 
+# --- Spool ---
+from .spool import Spool as Spool, SpoolSyncWorker as SpoolSyncWorker
+
 # --- Types ---
 from .types import Time as Time
 
@@ -158,6 +161,9 @@ __all__ = [
     "TopicHandler",
     "TopicWriter",
     "TopicDataStreamer",
+    # Spool
+    "Spool",
+    "SpoolSyncWorker",
     # Core Models
     "BaseModel",
     "Serializable",
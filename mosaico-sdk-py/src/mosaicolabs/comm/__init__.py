@@ -2,5 +2,6 @@ from .connection import GRPCCompression as GRPCCompression
 from .mosaico_client import MosaicoClient as MosaicoClient
 from .notifications import (
     Notification as Notification,
+    NotificationSeverity as NotificationSeverity,
     NotificationType as NotificationType,
 )
@@ -11,6 +11,7 @@ providing stronger typing and validation than raw dictionaries.
 """
 
 import json
+import time
 from abc import ABC, abstractmethod
 from dataclasses import dataclass
 from typing import Any, ClassVar, Dict, Optional, Type, TypeVar
@@ -22,10 +23,17 @@ from mosaicolabs.comm.notifications import Notification
 from ..enum import FlightAction
 from ..logging_config import get_logger
 from ..models.query import QueryResponse, QueryResponseItem
+from .middlewares import _take_retry_after
 
 # Set the hierarchical logger
 logger = get_logger(__name__)
 
+# Actions failed because the server is temporarily unavailable are retried at most
+# this many times, waiting the delay suggested by the server or an exponential backoff
+_MAX_UNAVAILABLE_RETRIES = 3
+_DEFAULT_RETRY_DELAY_S = 0.5
+_MAX_RETRY_DELAY_S = 10.0
+
 # Generic TypeVar allowing _do_action to return the specific subclass requested
 T_DoActionResponse = TypeVar("T_DoActionResponse", bound="_DoActionResponse")
 
@@ -119,18 +127,7 @@ def _do_action(
         body = json.dumps(payload).encode("utf-8")
         logger.debug(f"Action request body: '{body}'")
 
-        # Execute Flight call
-        action_results = client.do_action(fl.Action(action_name, body))
-
-        # Process the result stream (usually contains 0 or 1 item)
-        # Accumulate bytes in a list
-        # (much faster than repeatedly concatenating immutable bytes objects)
-        chunks: list[bytes] = []
-
-        for result in action_results:
-            if result.body:
-                # result.body is a PyArrow Buffer; to_pybytes() is zero-copy or low-overhead
-                chunks.append(result.body.to_pybytes())
+        chunks = _execute_with_retries(client, action_name, body)
 
         # If no data was received
         if not chunks:
@@ -181,6 +178,51 @@ def _do_action(
         raise e
 
 
+def _execute(client: fl.FlightClient, action_name: str, body: bytes) -> list[bytes]:
+    """Executes the Flight call, returning the chunks of the response body."""
+    action_results = client.do_action(fl.Action(action_name, body))
+
+    # Process the result stream (usually contains 0 or 1 item)
+    # Accumulate bytes in a list
+    # (much faster than repeatedly concatenating immutable bytes objects)
+    chunks: list[bytes] = []
+
+    for result in action_results:
+        if result.body:
+            # result.body is a PyArrow Buffer; to_pybytes() is zero-copy or low-overhead
+            chunks.append(result.body.to_pybytes())
+
+    return chunks
+
+
+def _execute_with_retries(
+    client: fl.FlightClient, action_name: str, body: bytes
+) -> list[bytes]:
+    """
+    Executes the Flight call, retrying it while the server reports to be temporarily
+    unavailable (e.g. its database is overloaded).
+    """
+    attempt = 0
+    while True:
+        try:
+            return _execute(client, action_name, body)
+        except fl.FlightUnavailableError as e:
+            if attempt >= _MAX_UNAVAILABLE_RETRIES:
+                raise e
+
+            delay = _take_retry_after()
+            if delay is None:
+                delay = _DEFAULT_RETRY_DELAY_S * 2**attempt
+            delay = min(delay, _MAX_RETRY_DELAY_S)
+            attempt += 1
+
+            logger.warning(
+                f"Server unavailable for action '{action_name}', "
+                f"retry {attempt}/{_MAX_UNAVAILABLE_RETRIES} in {delay:.2f}s: '{e}'"
+            )
+            time.sleep(delay)
+
+
 # --- Concrete Response Dataclasses ---
 
 
@@ -240,6 +282,8 @@ class _DoActionNotificationList(_DoActionResponse):
         FlightAction.TOPIC_NOTIFICATION_LIST,
     ]
     notifications: list[Notification]
+    cursor: Optional[str] = None
+    """Cursor used to fetch the next page, `None` on the last page."""
 
     @classmethod
     def from_dict(cls, data: Dict[str, Any]) -> "_DoActionNotificationList":
@@ -249,10 +293,22 @@ class _DoActionNotificationList(_DoActionResponse):
         return _DoActionNotificationList(
             notifications=[
                 Notification._from_dict(notification) for notification in notifications
-            ]
+            ],
+            cursor=data.get("cursor"),
         )
 
 
+@dataclass
+class _DoActionNotificationStream(_DoActionResponse):
+    """Response containing the ticket streaming the notifications of a sequence."""
+
+    actions: ClassVar[list[FlightAction]] = [
+        FlightAction.SEQUENCE_NOTIFICATION_STREAM,
+    ]
+    ticket: str
+    """Ticket encoded in base64."""
+
+
 @dataclass
 class _DoActionResponseAPIKeyCreate(_DoActionResponse):
     """Response returned after creating a new API key.
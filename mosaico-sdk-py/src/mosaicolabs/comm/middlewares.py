@@ -1,9 +1,16 @@
-from typing import Dict, List
+import threading
+from typing import Dict, List, Optional
 
 import pyarrow.flight as fl
 
 from ..platform.api_key import _get_fingerprint
 
+# Metadata sent by the server along with UNAVAILABLE errors
+RETRY_AFTER_MS_HEADER = "retry-after-ms"
+
+# Retry delay received by the last call performed by the current thread
+_retry_info = threading.local()
+
 
 class MosaicoAuthMiddleware(fl.ClientMiddleware):
     """Middleware adding the API token to every flight request."""
@@ -72,3 +79,54 @@ class MosaicoAuthMiddlewareFactory(fl.ClientMiddlewareFactory):
             str: The fingerprint of the API key
         """
         return self._fingerprint
+
+
+class MosaicoRetryInfoMiddleware(fl.ClientMiddleware):
+    """Middleware recording the retry delay suggested by the server."""
+
+    def received_headers(self, headers: Dict[str, List[str] | List[bytes]]):
+        """
+        Called after receiving headers (or trailers) from the server
+
+        Args:
+            headers (Dict[str, List[str] | List[bytes]]): Headers received from the server
+        """
+        values = headers.get(RETRY_AFTER_MS_HEADER)
+        if not values:
+            return
+        value = values[0]
+        if isinstance(value, bytes):
+            value = value.decode()
+        try:
+            _retry_info.retry_after_ms = int(value)
+        except ValueError:
+            pass
+
+
+class MosaicoRetryInfoMiddlewareFactory(fl.ClientMiddlewareFactory):
+    """Factory to create istances of MosaicoRetryInfoMiddleware."""
+
+    def start_call(self, info: fl.CallInfo) -> MosaicoRetryInfoMiddleware:
+        """
+        Called at every flight client operation, clearing the delay of the previous call
+
+        Args:
+            info (fl.CallInfo): Information about the flight call
+
+        Returns:
+            MosaicoRetryInfoMiddleware: The middleware to be used for the flight call
+        """
+        _retry_info.retry_after_ms = None
+        return MosaicoRetryInfoMiddleware()
+
+
+def _take_retry_after() -> Optional[float]:
+    """
+    Returns the retry delay (in seconds) suggested by the server for the last call
+    performed by the current thread, if any, and clears it.
+    """
+    retry_after_ms = getattr(_retry_info, "retry_after_ms", None)
+    _retry_info.retry_after_ms = None
+    if retry_after_ms is None:
+        return None
+    return retry_after_ms / 1000
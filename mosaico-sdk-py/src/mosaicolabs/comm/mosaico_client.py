@@ -7,12 +7,14 @@ and serves as a factory for creating resource handlers (sequences, topics)
 and executing queries.
 """
 
+import base64
+import json
 import os
-from typing import Any, Dict, List, Optional, Type, Union
+from typing import Any, Dict, Iterator, List, Optional, Type, Union
 
 import pyarrow.flight as fl
 
-from mosaicolabs.comm.notifications import Notification
+from mosaicolabs.comm.notifications import Notification, NotificationSeverity
 from mosaicolabs.models.query import Query, QueryResponse
 from mosaicolabs.models.query.protocols import QueryableProtocol
 
@@ -27,6 +29,8 @@ from ..handlers.config import SessionWriterConfig
 from ..helpers import pack_topic_resource_name
 from ..logging_config import get_logger
 from ..platform.api_key import APIKeyStatus
+from ..spool import Spool
+from ..spool.sync import _sync_spool
 from .connection import (
     DEFAULT_MAX_BATCH_BYTES,
     DEFAULT_MAX_BATCH_SIZE_RECORDS,
@@ -37,11 +41,12 @@ from .connection import (
 from .do_action import (
     _do_action,
     _DoActionNotificationList,
+    _DoActionNotificationStream,
     _DoActionQueryResponse,
     _DoActionResponseAPIKeyCreate,
     _DoActionResponseAPIKeyStatus,
 )
-from .middlewares import MosaicoAuthMiddlewareFactory
+from .middlewares import MosaicoAuthMiddlewareFactory, MosaicoRetryInfoMiddlewareFactory
 
 # Set the hierarchical logger
 logger = get_logger(__name__)
@@ -222,7 +227,10 @@ class MosaicoClient:
 
         enable_tls = enable_tls or tls_cert_path is not None
 
-        middlewares = {}
+        # Records the retry delays suggested by the server on transient failures
+        middlewares: dict[str, fl.ClientMiddlewareFactory] = {
+            "mosaico_retry_info": MosaicoRetryInfoMiddlewareFactory()
+        }
         api_key_fingerprint = None
         if api_key:
             auth_mware = MosaicoAuthMiddlewareFactory(api_key=api_key)
@@ -682,7 +690,43 @@ class MosaicoClient:
             out_list.extend([p.decode("utf-8") for p in finfo.descriptor.path])
         return out_list
 
-    def list_sequence_notifications(self, sequence_name: str) -> List[Notification]:
+    def _list_notifications(
+        self,
+        action: FlightAction,
+        locator: str,
+        min_severity: Optional[NotificationSeverity],
+    ) -> List[Notification]:
+        """Retrieves all the pages of the notifications of a sequence or topic."""
+        notifications: List[Notification] = []
+        cursor: Optional[str] = None
+        while True:
+            payload: Dict[str, Any] = {"locator": locator}
+            if min_severity is not None:
+                payload["min_severity"] = min_severity.value
+            if cursor is not None:
+                payload["cursor"] = cursor
+
+            act_resp = _do_action(
+                client=self._control_client,
+                action=action,
+                payload=payload,
+                expected_type=_DoActionNotificationList,
+            )
+
+            if act_resp is None:
+                logger.error(f"Action '{action}' returned no response.")
+                return notifications
+
+            notifications.extend(act_resp.notifications)
+            if act_resp.cursor is None:
+                return notifications
+            cursor = act_resp.cursor
+
+    def list_sequence_notifications(
+        self,
+        sequence_name: str,
+        min_severity: Optional[NotificationSeverity] = None,
+    ) -> List[Notification]:
         """
         Retrieves a list of all notifications available on the server for a specific sequence.
 
@@ -693,9 +737,11 @@ class MosaicoClient:
 
         Args:
             sequence_name (str): The name of the sequence to list notifications for.
+            min_severity (Optional[NotificationSeverity]): If set, only the notifications
+                at least as severe are returned.
 
         Returns:
-            List[Notification]: The list of sequence notifications.
+            List[Notification]: The list of sequence notifications, sorted by creation time.
 
         Raises:
             Exception: If any error occurs during sequence notification listing.
@@ -714,24 +760,84 @@ class MosaicoClient:
         """
         ACTION = FlightAction.SEQUENCE_NOTIFICATION_LIST
 
+        try:
+            return self._list_notifications(ACTION, sequence_name, min_severity)
+
+        except Exception as e:
+            logger.error(f"Query returned an internal error: '{e}'")
+            raise
+
+    def stream_sequence_notifications(
+        self,
+        sequence_name: str,
+        min_severity: Optional[NotificationSeverity] = None,
+    ) -> Iterator[Notification]:
+        """
+        Streams the notifications of a sequence and of its topics as they are created.
+
+        Only the notifications created after the stream is opened are returned: the earlier
+        ones are retrieved with
+        [`list_sequence_notifications()`][mosaicolabs.comm.MosaicoClient.list_sequence_notifications].
+        The iteration blocks waiting for the next notification, and ends when the server
+        shuts down.
+
+        Note:
+            If using the Authorization middleware (via an API-Key), this method requires the minimum
+            [`APIKeyPermissionEnum.Read`][mosaicolabs.enum.APIKeyPermissionEnum.Read]
+            permission.
+
+        Args:
+            sequence_name (str): The name of the sequence to stream notifications for.
+            min_severity (Optional[NotificationSeverity]): If set, only the notifications
+                at least as severe are returned.
+
+        Returns:
+            Iterator[Notification]: The notifications, in creation order.
+
+        Raises:
+            Exception: If the stream cannot be opened, or if the client falls too far behind.
+
+        Example:
+            ```python
+            from mosaicolabs import MosaicoClient, NotificationSeverity
+
+            with MosaicoClient.connect("localhost", 6726) as client:
+                for notification in client.stream_sequence_notifications(
+                    "my_sequence", min_severity=NotificationSeverity.Warning
+                ):
+                    print(f"{notification.severity}: {notification.message}")
+            ```
+        """
+        ACTION = FlightAction.SEQUENCE_NOTIFICATION_STREAM
+
+        payload: Dict[str, Any] = {"locator": sequence_name}
+        if min_severity is not None:
+            payload["min_severity"] = min_severity.value
+
         try:
             act_resp = _do_action(
                 client=self._control_client,
                 action=ACTION,
-                payload={"locator": sequence_name},
-                expected_type=_DoActionNotificationList,
+                payload=payload,
+                expected_type=_DoActionNotificationStream,
             )
-
             if act_resp is None:
-                logger.error(f"Action '{ACTION}' returned no response.")
-                return []
+                raise Exception(f"Action '{ACTION}' returned no response.")
 
-            return act_resp.notifications
+            reader = self._control_client.do_get(
+                fl.Ticket(base64.b64decode(act_resp.ticket))
+            )
 
         except Exception as e:
             logger.error(f"Query returned an internal error: '{e}'")
             raise
 
+        # Each notification is sent as a single row batch
+        for chunk in reader:
+            for row in chunk.data.to_pylist():
+                row["params"] = json.loads(row["params"])
+                yield Notification._from_dict(row)
+
     def clear_sequence_notifications(self, sequence_name: str):
         """
         Clears the notifications for a specific sequence from the server.
@@ -762,7 +868,10 @@ class MosaicoClient:
             raise
 
     def list_topic_notifications(
-        self, sequence_name: str, topic_name: str
+        self,
+        sequence_name: str,
+        topic_name: str,
+        min_severity: Optional[NotificationSeverity] = None,
     ) -> List[Notification]:
         """
         Retrieves a list of all notifications available on the server for a specific topic
@@ -775,9 +884,11 @@ class MosaicoClient:
         Args:
             sequence_name (str): The name of the sequence to list notifications for.
             topic_name (str): The name of the topic to list notifications for.
+            min_severity (Optional[NotificationSeverity]): If set, only the notifications
+                at least as severe are returned.
 
         Returns:
-            List[Notification]: The list of topic notifications.
+            List[Notification]: The list of topic notifications, sorted by creation time.
 
         Raises:
             Exception: If any error occurs during topic notification listing.
@@ -797,23 +908,11 @@ class MosaicoClient:
         ACTION = FlightAction.TOPIC_NOTIFICATION_LIST
 
         try:
-            act_resp = _do_action(
-                client=self._control_client,
-                action=ACTION,
-                payload={
-                    "locator": pack_topic_resource_name(
-                        sequence_name=sequence_name,
-                        topic_name=topic_name,
-                    )
-                },
-                expected_type=_DoActionNotificationList,
+            locator = pack_topic_resource_name(
+                sequence_name=sequence_name,
+                topic_name=topic_name,
             )
-
-            if act_resp is None:
-                logger.error(f"Action '{ACTION}' returned no response.")
-                return []
-
-            return act_resp.notifications
+            return self._list_notifications(ACTION, locator, min_severity)
 
         except Exception as e:
             logger.error(f"Query returned an internal error: '{e}'")
@@ -1168,6 +1267,41 @@ class MosaicoClient:
             logger.error(f"API key revoke failed with error: '{e}'")
             raise
 
+    def spool_sync(self, spool: Spool) -> List[str]:
+        """
+        Uploads the sequences recorded in a [`Spool`][mosaicolabs.spool.Spool] while the
+        server was unreachable.
+
+        Sequences are uploaded in recording order and removed from the spool once their
+        session is finalized. If the upload is interrupted, e.g. by a connection loss, the
+        next call resumes it: the topics already uploaded are not sent again.
+
+        Args:
+            spool (Spool): The spool to upload.
+
+        Returns:
+            List[str]: The names of the uploaded sequences.
+
+        Raises:
+            Exception: If any error occurs during the upload, the sequences not yet uploaded
+                are left in the spool.
+
+        Example:
+            ```python
+            from mosaicolabs import MosaicoClient, Spool
+
+            spool = Spool("/var/spool/mosaico")
+            with MosaicoClient.connect("localhost", 6726) as client:
+                uploaded = client.spool_sync(spool)
+                print(f"Uploaded sequences: {uploaded}")
+            ```
+        """
+        try:
+            return _sync_spool(self._control_client, spool)
+        except Exception as e:
+            logger.error(f"Spool sync failed with error: '{e}'")
+            raise
+
     def close(self):
         """
         Gracefully shuts down the Mosaico client and releases all underlying resources.
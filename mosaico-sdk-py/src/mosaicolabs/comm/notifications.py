@@ -1,5 +1,5 @@
 import datetime
-from dataclasses import dataclass
+from dataclasses import dataclass, field
 from enum import Enum
 from typing import Any, Dict, Optional
 
@@ -30,6 +30,28 @@ class NotificationType(Enum):
     Error = "error"
     """Critical error notification."""
 
+    Alert = "alert"
+    """Event requiring attention, e.g. a scheduled query whose condition is satisfied."""
+
+
+class NotificationSeverity(Enum):
+    """
+    Severity of platform-level notifications, from the least to the most severe.
+
+    Notifications created without an explicit severity take the one of their type:
+    `Error` for [`NotificationType.Error`][mosaicolabs.comm.notifications.NotificationType],
+    `Warning` for `NotificationType.Alert`.
+    """
+
+    Info = "info"
+    """Informational notification."""
+
+    Warning = "warning"
+    """Notification requiring attention."""
+
+    Error = "error"
+    """Notification reporting a failure."""
+
 
 @dataclass
 class Notification:
@@ -65,6 +87,11 @@ class Notification:
         created_datetime: The timestamp when the server generated the notification.
         topic_name: Optional; the specific topic name if the notification is
             granular to a single data channel.
+        code: The machine readable code of the event (e.g. `ingest.missing_column`),
+            `message` for free text notifications.
+        params: The parameters of the event, keyed by name.
+        severity: The [`NotificationSeverity`][mosaicolabs.comm.notifications.NotificationSeverity]
+            of this event.
     """
 
     sequence_name: str
@@ -72,6 +99,9 @@ class Notification:
     message: str
     created_datetime: datetime.datetime
     topic_name: Optional[str] = None
+    code: str = "message"
+    params: Dict[str, str] = field(default_factory=dict)
+    severity: NotificationSeverity = NotificationSeverity.Error
 
     @classmethod
     def _from_dict(cls, data: Dict[str, Any]) -> "Notification":
@@ -96,4 +126,7 @@ class Notification:
             type=NotificationType(data["notification_type"]),
             message=data["msg"],
             created_datetime=_parse_datetime_str(data["created_datetime"]),
+            code=data.get("code", "message"),
+            params=data.get("params") or {},
+            severity=NotificationSeverity(data.get("severity", "error")),
         )
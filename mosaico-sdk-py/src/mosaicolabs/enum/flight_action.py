@@ -35,6 +35,9 @@ class FlightAction(Enum):
     SEQUENCE_NOTIFICATION_PURGE = "sequence_notification_purge"
     """Request the deletion of the list of notifications for a specific sequence"""
 
+    SEQUENCE_NOTIFICATION_STREAM = "sequence_notification_stream"
+    """Request the ticket streaming the notifications of a specific sequence as they are created"""
+
     SESSION_DELETE = "session_delete"
     """Requests the permanent removal of a session and all associated topics from the server."""
 
@@ -0,0 +1,2 @@
+from .spool import Spool as Spool, SpoolSequenceWriter as SpoolSequenceWriter
+from .sync import SpoolSyncWorker as SpoolSyncWorker
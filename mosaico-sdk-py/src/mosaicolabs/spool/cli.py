@@ -0,0 +1,95 @@
+import logging
+import sys
+import time
+
+import click
+
+from mosaicolabs.comm import MosaicoClient
+from mosaicolabs.spool import Spool, SpoolSyncWorker
+
+CONTEXT_SETTINGS = dict(help_option_names=["-h", "--help"])
+
+
+@click.command(context_settings=CONTEXT_SETTINGS)
+@click.argument(
+    "spool_dir",
+    type=click.Path(file_okay=False),
+)
+@click.option(
+    "--host",
+    default="localhost",
+    help="The Mosaico Server hostname.",
+    show_default=True,
+)
+@click.option(
+    "--port", default=6726, type=int, help="The Mosaico Server port.", show_default=True
+)
+@click.option("--tls", is_flag=True, help="Enables the TLS protocol.")
+@click.option(
+    "--api-key",
+    default=None,
+    help="The Mosaico API-Key (must have Write permission at least).",
+    show_default=True,
+)
+@click.option(
+    "--watch",
+    default=None,
+    type=float,
+    help="Keep running, retrying the upload every WATCH seconds.",
+)
+@click.option(
+    "--log-level",
+    "-l",
+    type=click.Choice(["DEBUG", "INFO", "WARNING", "ERROR"], case_sensitive=False),
+    default="INFO",
+    help="Set the logging level.",
+    show_default=True,
+)
+def spool_sync_cli(spool_dir, host, port, tls, api_key, watch, log_level):
+    """
+    Mosaico Spool Synchronizer.
+
+    Uploads the sequences recorded in SPOOL_DIR while the server was unreachable.
+    """
+    # Set global logging configuration
+    logging.basicConfig(level=getattr(logging, log_level.upper()))
+
+    spool = Spool(spool_dir)
+    worker = SpoolSyncWorker(
+        spool,
+        connect=lambda: MosaicoClient.connect(
+            host=host, port=port, enable_tls=tls, api_key=api_key
+        ),
+        interval=watch or 0,
+    )
+
+    if watch is not None:
+        click.secho(f"Watching spool '{spool_dir}'", fg="cyan", bold=True)
+        worker.start()
+        try:
+            while True:
+                time.sleep(1)
+        except KeyboardInterrupt:
+            worker.stop()
+        return
+
+    pending = spool.pending()
+    if not pending:
+        click.echo("Nothing to upload.")
+        return
+
+    try:
+        with MosaicoClient.connect(
+            host=host, port=port, enable_tls=tls, api_key=api_key
+        ) as client:
+            uploaded = client.spool_sync(spool)
+    except Exception as e:
+        click.secho(f"\nSync failed: {e}", fg="red", bold=True)
+        logging.exception("Detailed stack trace:")
+        sys.exit(1)
+
+    click.secho(f"Uploaded {len(uploaded)} sequence(s).", fg="green")
+
+
+if __name__ == "__main__":
+    spool_sync_cli()
@@ -0,0 +1,480 @@
+"""
+Offline Spool Module.
+
+This module allows recording sequences while the Mosaico server is unreachable.
+Batches are written to a local directory in the Arrow IPC stream format, along with
+a JSON manifest describing the sequence, its topics and the progress of the upload,
+so that they can be uploaded once the connectivity returns.
+
+Spool Layout:
+    Every spooled sequence is stored in its own folder, named after the creation time
+    so that sequences are uploaded in the order they were recorded:
+
+    ```
+    <spool_dir>/
+        <created_ns>_<id>/
+            manifest.json
+            0.arrows        # Arrow IPC stream of the first topic
+            1.arrows
+    ```
+"""
+
+import json
+import os
+import shutil
+import threading
+import time
+import uuid
+from dataclasses import asdict, dataclass, field
+from pathlib import Path
+from typing import Any, Dict, List, Optional, Type, Union
+
+import pyarrow as pa
+import pyarrow.ipc as pa_ipc
+
+from ..comm.connection import DEFAULT_MAX_BATCH_BYTES, DEFAULT_MAX_BATCH_SIZE_RECORDS
+from ..enum import SessionLevelErrorPolicy, TopicLevelErrorPolicy
+from ..handlers.config import TopicWriterConfig
+from ..handlers.helpers import (
+    _validate_metadata,
+    _validate_sequence_name,
+    _validate_topic_name,
+)
+from ..handlers.internal.topic_write_state import _TopicWriteState
+from ..handlers.topic_writer import TopicWriter
+from ..logging_config import get_logger
+from ..models import Serializable
+from ..models.message import Message
+
+# Set the hierarchical logger
+logger = get_logger(__name__)
+
+_MANIFEST_FILE = "manifest.json"
+_MANIFEST_VERSION = 1
+
+
+def _fsync_dir(path: Path):
+    """Persists the entries of a directory (e.g. after a rename)."""
+    fd = os.open(path, os.O_RDONLY)
+    try:
+        os.fsync(fd)
+    finally:
+        os.close(fd)
+
+
+@dataclass
+class _SpooledTopic:
+    """Description of a topic recorded in the spool."""
+
+    name: str
+    ontology_tag: str
+    serialization_format: str
+    metadata: Dict[str, Any]
+    file: str
+    """Name of the Arrow IPC stream file, relative to the sequence folder"""
+    errors: List[str] = field(default_factory=list)
+    """Errors reported by the topic writer, sent as notifications once uploaded"""
+    uuid: Optional[str] = None
+    """The uuid of the topic on the server, set once created during the upload"""
+    uploaded: bool = False
+    """True once all the batches of the topic have been uploaded"""
+
+
+@dataclass
+class _SpoolManifest:
+    """
+    Durable description of a spooled sequence.
+
+    The manifest is rewritten atomically after every step of the upload, allowing an
+    interrupted upload to resume from the last completed step.
+    """
+
+    sequence_name: str
+    metadata: Dict[str, Any]
+    created_ns: int
+    closed: bool = False
+    """True once the writer is closed, only closed sequences are uploaded"""
+    errors: List[str] = field(default_factory=list)
+    """Errors caught by the sequence writer, sent as notifications once uploaded"""
+    topics: List[_SpooledTopic] = field(default_factory=list)
+    sequence_created: bool = False
+    """True once the sequence has been created on the server"""
+    session_uuid: Optional[str] = None
+    """The uuid of the upload session on the server, resumed by later uploads"""
+    session_locator: Optional[str] = None
+    version: int = _MANIFEST_VERSION
+
+    @classmethod
+    def _load(cls, folder: Path) -> "_SpoolManifest":
+        data = json.loads((folder / _MANIFEST_FILE).read_text())
+        if data.get("version") != _MANIFEST_VERSION:
+            raise ValueError(
+                f"Unsupported spool manifest version '{data.get('version')}' in '{folder}'."
+            )
+        data["topics"] = [_SpooledTopic(**topic) for topic in data["topics"]]
+        return cls(**data)
+
+    def _store(self, folder: Path):
+        """Atomically replaces the manifest in `folder`."""
+        tmp_path = folder / f"{_MANIFEST_FILE}.tmp"
+        with open(tmp_path, "w") as f:
+            json.dump(asdict(self), f)
+            f.flush()
+            os.fsync(f.fileno())
+        os.replace(tmp_path, folder / _MANIFEST_FILE)
+        _fsync_dir(folder)
+
+
+class _SpoolStreamWriter:
+    """
+    Arrow IPC stream writer persisting every batch to disk.
+
+    Exposes the subset of the `FlightStreamWriter` interface used by `_TopicWriteState`,
+    so that the same buffering logic is shared by the online and the offline writers.
+    """
+
+    def __init__(self, path: Path, schema: pa.Schema):
+        self._sink = open(path, "wb")
+        self._writer = pa_ipc.new_stream(self._sink, schema)
+        self._sync()
+
+    def _sync(self):
+        self._sink.flush()
+        os.fsync(self._sink.fileno())
+
+    def write(self, batch: pa.RecordBatch):
+        self._writer.write_batch(batch)
+        self._sync()
+
+    def done_writing(self):
+        # Writes the end-of-stream marker
+        self._writer.close()
+        self._sync()
+
+    def close(self):
+        self._sink.close()
+
+
+class _SpoolTopicWriter(TopicWriter):
+    """
+    A `TopicWriter` appending the batches to a file of the spool.
+
+    Errors reported by the writer are kept in the manifest and sent to the server as
+    topic notifications once the topic is uploaded.
+    """
+
+    def __init__(
+        self,
+        *,
+        topic_name: str,
+        sequence_name: str,
+        state: _TopicWriteState,
+        config: TopicWriterConfig,
+        spooled_topic: _SpooledTopic,
+        sequence_writer: "SpoolSequenceWriter",
+    ):
+        super().__init__(
+            topic_name=topic_name,
+            sequence_name=sequence_name,
+            client=None,  # type: ignore[arg-type] # no server to talk to
+            state=state,
+            config=config,
+        )
+        self._spooled_topic = spooled_topic
+        self._sequence_writer = sequence_writer
+
+    def _error_report(self, err: str):
+        self._spooled_topic.errors.append(err)
+        self._sequence_writer._store_manifest()
+        logger.warning(f"TopicWriter '{self._name}' spooled error: '{err}'.")
+
+
+class SpoolSequenceWriter:
+    """
+    Records a new sequence in the local spool, to be uploaded later.
+
+    It exposes the same writing interface as the [`SequenceWriter`][mosaicolabs.handlers.SequenceWriter]:
+    topics are created with `topic_create()` and data are pushed through the returned
+    [`TopicWriter`][mosaicolabs.handlers.TopicWriter] instances.
+
+    Important: Obtaining a Writer
+        Do not instantiate this class directly. Use the
+        [`Spool.sequence_create()`][mosaicolabs.spool.Spool.sequence_create]
+        factory method.
+    """
+
+    def __init__(
+        self,
+        *,
+        spool: "Spool",
+        sequence_name: str,
+        metadata: Dict[str, Any],
+        on_error: SessionLevelErrorPolicy,
+        max_batch_size_bytes: int,
+        max_batch_size_records: int,
+    ):
+        _validate_sequence_name(sequence_name)
+        _validate_metadata(metadata)
+
+        self._spool = spool
+        self._on_error = on_error
+        self._max_batch_size_bytes = max_batch_size_bytes
+        self._max_batch_size_records = max_batch_size_records
+        self._folder: Optional[Path] = None
+        """The folder of the sequence in the spool, created when entering the context"""
+        self._manifest = _SpoolManifest(
+            sequence_name=sequence_name,
+            metadata=metadata,
+            created_ns=time.time_ns(),
+        )
+        self._topic_writers: Dict[str, TopicWriter] = {}
+
+    def _store_manifest(self):
+        assert self._folder is not None
+        self._manifest._store(self._folder)
+
+    def _check_entered(self):
+        if self._folder is None:
+            raise RuntimeError("SpoolSequenceWriter must be used within a 'with' block.")
+
+    def __enter__(self) -> "SpoolSequenceWriter":
+        self._folder = self._spool._new_sequence_folder(self._manifest.created_ns)
+        self._store_manifest()
+        logger.info(
+            f"Spooling sequence '{self._manifest.sequence_name}' in '{self._folder}'."
+        )
+        return self
+
+    def __exit__(
+        self,
+        exc_type: Optional[Type[BaseException]],
+        exc_val: Optional[BaseException],
+        exc_tb: Optional[Any],
+    ) -> None:
+        errors = []
+        for topic_name, twriter in self._topic_writers.items():
+            try:
+                if twriter.is_active:
+                    twriter._finalize(error=exc_val)
+            except Exception as e:
+                logger.error(f"Failed to close spooled topic '{topic_name}': '{e}'")
+                errors.append(e)
+        self._topic_writers = {}
+
+        assert self._folder is not None
+        if exc_val is not None and self._on_error == SessionLevelErrorPolicy.Delete:
+            logger.error(
+                f"Exception caught while spooling sequence '{self._manifest.sequence_name}', "
+                f"discarding the spooled data. Inner err: '{exc_val}'"
+            )
+            shutil.rmtree(self._folder)
+            return
+
+        if exc_val is not None:
+            self._manifest.errors.append(str(exc_val))
+        self._manifest.closed = True
+        self._store_manifest()
+
+        if exc_val is None and errors:
+            raise errors[0]
+
+    def topic_create(
+        self,
+        topic_name: str,
+        metadata: Dict[str, Any],
+        ontology_type: Type[Serializable],
+        on_error: TopicLevelErrorPolicy = TopicLevelErrorPolicy.Raise,
+    ) -> Optional[TopicWriter]:
+        """
+        Creates a new topic within the spooled sequence.
+
+        Args:
+            topic_name: The relative name of the new topic.
+            metadata: Topic-specific user metadata.
+            ontology_type: The `Serializable` data model class defining the topic's schema.
+            on_error: The error policy to use in the `TopicWriter`.
+
+        Returns:
+            A `TopicWriter` instance writing to the spool, or `None` if the topic already exists.
+
+        Raises:
+            RuntimeError: If called outside of a `with` block.
+        """
+        self._check_entered()
+        assert self._folder is not None
+
+        if topic_name in self._topic_writers:
+            logger.error(f"Topic '{topic_name}' already exists in this sequence.")
+            return None
+
+        _validate_topic_name(topic_name)
+        _validate_metadata(metadata)
+        TopicWriter._validate_ontology_type(ontology_type)
+        assert ontology_type.__ontology_tag__ is not None
+
+        spooled_topic = _SpooledTopic(
+            name=topic_name,
+            ontology_tag=ontology_type.__ontology_tag__,
+            serialization_format=ontology_type.__serialization_format__.value,
+            metadata=metadata,
+            file=f"{len(self._manifest.topics)}.arrows",
+        )
+
+        stream_writer = _SpoolStreamWriter(
+            self._folder / spooled_topic.file, Message._get_schema(ontology_type)
+        )
+        config = TopicWriterConfig(
+            on_error=on_error,
+            max_batch_size_bytes=self._max_batch_size_bytes,
+            max_batch_size_records=self._max_batch_size_records,
+        )
+        wrstate = _TopicWriteState(
+            topic_name=topic_name,
+            ontology_tag=spooled_topic.ontology_tag,
+            writer=stream_writer,  # type: ignore[arg-type]
+            max_batch_size_bytes=config.max_batch_size_bytes,
+            max_batch_size_records=config.max_batch_size_records,
+        )
+
+        self._manifest.topics.append(spooled_topic)
+        self._store_manifest()
+
+        writer = _SpoolTopicWriter(
+            topic_name=topic_name,
+            sequence_name=self._manifest.sequence_name,
+            state=wrstate,
+            config=config,
+            spooled_topic=spooled_topic,
+            sequence_writer=self,
+        )
+        self._topic_writers[topic_name] = writer
+        return writer
+
+    def get_topic_writer(self, topic_name: str) -> Optional[TopicWriter]:
+        """
+        Retrieves an existing `TopicWriter` of the spooled sequence, if any.
+        """
+        self._check_entered()
+        return self._topic_writers.get(topic_name)
+
+    def topic_writer_exists(self, topic_name: str) -> bool:
+        """Checks if a `TopicWriter` has already been created for the given name."""
+        self._check_entered()
+        return topic_name in self._topic_writers
+
+    def list_topic_writers(self) -> List[str]:
+        """Returns the list of all topic names currently managed by this writer."""
+        self._check_entered()
+        return list(self._topic_writers.keys())
+
+
+class Spool:
+    """
+    A local directory holding the sequences recorded while the server is unreachable.
+
+    Sequences are recorded with [`sequence_create()`][mosaicolabs.spool.Spool.sequence_create]
+    and uploaded by [`MosaicoClient.spool_sync()`][mosaicolabs.comm.MosaicoClient.spool_sync],
+    by a [`SpoolSyncWorker`][mosaicolabs.spool.SpoolSyncWorker] running in the background
+    or by the `mosaicolabs.spool_sync` command.
+
+    Example:
+        ```python
+        from mosaicolabs import MosaicoClient, Spool
+
+        spool = Spool("/var/spool/mosaico")
+
+        try:
+            client = MosaicoClient.connect("mosaico.local", 6726)
+            writer = client.sequence_create("drive_042", metadata={})
+        except ConnectionError:
+            client = None
+            writer = spool.sequence_create("drive_042", metadata={})
+
+        with writer as seq_writer:
+            imu_writer = seq_writer.topic_create("sensors/imu", {}, IMU)
+            imu_writer.push(message=Message(timestamp_ns=ts, data=imu))
+        ```
+    """
+
+    def __init__(self, path: Union[str, os.PathLike]):
+        """
+        Opens the spool stored in `path`, creating the directory if missing.
+
+        Args:
+            path: The directory of the spool.
+        """
+        self._path = Path(path)
+        self._path.mkdir(parents=True, exist_ok=True)
+        self._sync_lock = threading.Lock()
+        """Serializes the uploads of this spool"""
+
+    @property
+    def path(self) -> Path:
+        """The directory of the spool."""
+        return self._path
+
+    def _new_sequence_folder(self, created_ns: int) -> Path:
+        folder = self._path / f"{created_ns}_{uuid.uuid4().hex[:8]}"
+        folder.mkdir()
+        _fsync_dir(self._path)
+        return folder
+
+    def _sequence_folders(self) -> List[Path]:
+        """Returns the folders of the spooled sequences, in recording order."""
+        return sorted(
+            folder
+            for folder in self._path.iterdir()
+            if (folder / _MANIFEST_FILE).is_file()
+        )
+
+    def sequence_create(
+        self,
+        sequence_name: str,
+        metadata: Dict[str, Any],
+        on_error: SessionLevelErrorPolicy = SessionLevelErrorPolicy.Report,
+        max_batch_size_bytes: Optional[int] = None,
+        max_batch_size_records: Optional[int] = None,
+    ) -> SpoolSequenceWriter:
+        """
+        Records a new sequence in the spool, returning a writer to be used within a `with` block.
+
+        The sequence is uploaded by the next synchronization started after the writer is closed.
+        If an exception is raised in the `with` block and `on_error` is
+        [`SessionLevelErrorPolicy.Delete`][mosaicolabs.enum.SessionLevelErrorPolicy.Delete]
+        the spooled data are discarded, otherwise the error is reported to the server
+        once the sequence is uploaded.
+
+        Args:
+            sequence_name: Unique name for the sequence.
+            metadata: User-defined metadata to attach.
+            on_error: Behavior on write failure.
+            max_batch_size_bytes: Max bytes per Arrow batch.
+            max_batch_size_records: Max records per Arrow batch.
+
+        Returns:
+            SpoolSequenceWriter: The writer of the spooled sequence.
+        """
+        return SpoolSequenceWriter(
+            spool=self,
+            sequence_name=sequence_name,
+            metadata=metadata,
+            on_error=SessionLevelErrorPolicy(on_error.value),
+            max_batch_size_bytes=max_batch_size_bytes
+            if max_batch_size_bytes is not None
+            else DEFAULT_MAX_BATCH_BYTES,
+            max_batch_size_records=max_batch_size_records
+            if max_batch_size_records is not None
+            else DEFAULT_MAX_BATCH_SIZE_RECORDS,
+        )
+
+    def pending(self) -> List[str]:
+        """
+        Returns the names of the sequences waiting to be uploaded, in recording order.
+        Sequences still being recorded are not included.
+        """
+        names = []
+        for folder in self._sequence_folders():
+            manifest = _SpoolManifest._load(folder)
+            if manifest.closed:
+                names.append(manifest.sequence_name)
+        return names
@@ -0,0 +1,262 @@
+"""
+Spool Synchronization Module.
+
+This module uploads the sequences recorded in a [`Spool`][mosaicolabs.spool.Spool]
+once the Mosaico server is reachable again.
+
+Every step of the upload is recorded in the manifest of the spooled sequence, so that an
+upload interrupted by a connection loss resumes the same session on the next attempt:
+the topics already uploaded are skipped, while a partially uploaded topic is deleted and
+uploaded again. Once the session is finalized the sequence is removed from the spool.
+"""
+
+import json
+import shutil
+import threading
+from pathlib import Path
+from typing import TYPE_CHECKING, Callable, List, Optional
+
+import pyarrow as pa
+import pyarrow.flight as fl
+import pyarrow.ipc as pa_ipc
+
+from ..comm.do_action import (
+    _do_action,
+    _DoActionSessionCreateResponse,
+    _DoActionTopicCreateResponse,
+)
+from ..enum import FlightAction
+from ..helpers import pack_topic_resource_name
+from ..logging_config import get_logger
+from .spool import Spool, _SpooledTopic, _SpoolManifest
+
+if TYPE_CHECKING:
+    from ..comm.mosaico_client import MosaicoClient
+
+# Set the hierarchical logger
+logger = get_logger(__name__)
+
+
+def _upload_topic(
+    client: fl.FlightClient, path: Path, locator: str, topic: _SpooledTopic
+):
+    """Streams the batches of a spooled topic to the server with a single DoPut."""
+    descriptor = fl.FlightDescriptor.for_command(
+        json.dumps({"resource_locator": locator, "topic_uuid": topic.uuid})
+    )
+    with pa.OSFile(str(path)) as source:
+        reader = pa_ipc.open_stream(source)
+        writer, _ = client.do_put(descriptor, reader.schema)
+        try:
+            for batch in reader:
+                writer.write_batch(batch)
+            writer.done_writing()
+        finally:
+            writer.close()
+
+
+def _sync_sequence(client: fl.FlightClient, folder: Path) -> str:
+    """
+    Uploads a spooled sequence, resuming the previous attempt if any.
+
+    Returns:
+        The name of the uploaded sequence.
+    """
+    manifest = _SpoolManifest._load(folder)
+    name = manifest.sequence_name
+
+    if not manifest.sequence_created:
+        _do_action(
+            client=client,
+            action=FlightAction.SEQUENCE_CREATE,
+            payload={"locator": name, "user_metadata": manifest.metadata},
+            expected_type=None,
+        )
+        manifest.sequence_created = True
+        manifest._store(folder)
+
+    if manifest.session_uuid is None:
+        act_resp = _do_action(
+            client=client,
+            action=FlightAction.SESSION_CREATE,
+            payload={"locator": name},
+            expected_type=_DoActionSessionCreateResponse,
+        )
+        if act_resp is None:
+            raise Exception(
+                f"Action '{FlightAction.SESSION_CREATE.value}' returned no response, sequence '{name}'."
+            )
+        manifest.session_uuid = act_resp.uuid
+        manifest.session_locator = act_resp.locator
+        manifest._store(folder)
+
+    for topic in manifest.topics:
+        if topic.uploaded:
+            continue
+
+        locator = pack_topic_resource_name(name, topic.name)
+        if topic.uuid is not None:
+            # The previous upload of the topic was interrupted, its data are replaced
+            logger.info(f"Replacing the partially uploaded topic '{locator}'.")
+            _do_action(
+                client=client,
+                action=FlightAction.TOPIC_DELETE,
+                payload={"locator": locator},
+                expected_type=None,
+            )
+            topic.uuid = None
+            manifest._store(folder)
+
+        act_resp = _do_action(
+            client=client,
+            action=FlightAction.TOPIC_CREATE,
+            payload={
+                "session_uuid": manifest.session_uuid,
+                "locator": locator,
+                "serialization_format": topic.serialization_format,
+                "ontology_tag": topic.ontology_tag,
+                "user_metadata": topic.metadata,
+            },
+            expected_type=_DoActionTopicCreateResponse,
+        )
+        if act_resp is None:
+            raise Exception(
+                f"Action '{FlightAction.TOPIC_CREATE.value}' returned no response, topic '{locator}'."
+            )
+        topic.uuid = act_resp.uuid
+        manifest._store(folder)
+
+        _upload_topic(client, folder / topic.file, locator, topic)
+
+        for err in topic.errors:
+            _do_action(
+                client=client,
+                action=FlightAction.TOPIC_NOTIFICATION_CREATE,
+                payload={"locator": locator, "notification_type": "error", "msg": err},
+                expected_type=None,
+            )
+        topic.uploaded = True
+        manifest._store(folder)
+        logger.debug(f"Spooled topic '{locator}' uploaded.")
+
+    for err in manifest.errors:
+        _do_action(
+            client=client,
+            action=FlightAction.SEQUENCE_NOTIFICATION_CREATE,
+            payload={"locator": name, "notification_type": "error", "msg": err},
+            expected_type=None,
+        )
+
+    _do_action(
+        client=client,
+        action=FlightAction.SESSION_FINALIZE,
+        payload={"session_uuid": manifest.session_uuid},
+        expected_type=None,
+    )
+    shutil.rmtree(folder)
+
+    logger.info(f"Spooled sequence '{name}' uploaded.")
+    return name
+
+
+def _sync_spool(client: fl.FlightClient, spool: Spool) -> List[str]:
+    """
+    Uploads the closed sequences of the spool, in recording order.
+
+    The synchronization stops at the first failure, leaving the remaining sequences
+    in the spool for a later attempt.
+
+    Returns:
+        The names of the uploaded sequences.
+    """
+    synced = []
+    with spool._sync_lock:
+        for folder in spool._sequence_folders():
+            if not _SpoolManifest._load(folder).closed:
+                continue
+            synced.append(_sync_sequence(client, folder))
+    return synced
+
+
+class SpoolSyncWorker:
+    """
+    Background task uploading the sequences of a spool as soon as the server is reachable.
+
+    Every `interval` seconds, if the spool holds sequences to upload, the worker opens a
+    connection with the `connect` callable and synchronizes the spool. Failed attempts,
+    e.g. because the server is still unreachable, are retried at the next round.
+
+    Example:
+        ```python
+        from mosaicolabs import MosaicoClient, Spool, SpoolSyncWorker
+
+        spool = Spool("/var/spool/mosaico")
+        worker = SpoolSyncWorker(
+            spool,
+            connect=lambda: MosaicoClient.connect("mosaico.local", 6726),
+            interval=60,
+        )
+        worker.start()
+        # ... record sequences with spool.sequence_create() ...
+        worker.stop()
+        ```
+    """
+
+    def __init__(
+        self,
+        spool: Spool,
+        connect: Callable[[], "MosaicoClient"],
+        interval: float = 30.0,
+    ):
+        """
+        Args:
+            spool: The spool to synchronize.
+            connect: Returns a new connected client, raising `ConnectionError` if the
+                server is unreachable.
+            interval: Seconds between two synchronization attempts.
+        """
+        self._spool = spool
+        self._connect = connect
+        self._interval = interval
+        self._stop = threading.Event()
+        self._thread: Optional[threading.Thread] = None
+
+    def _run(self):
+        while not self._stop.is_set():
+            self.sync_once()
+            self._stop.wait(self._interval)
+
+    def sync_once(self) -> List[str]:
+        """
+        Runs a single synchronization attempt, without raising.
+
+        Returns:
+            The names of the uploaded sequences.
+        """
+        if not self._spool.pending():
+            return []
+        try:
+            with self._connect() as client:
+                return client.spool_sync(self._spool)
+        except ConnectionError as e:
+            logger.debug(f"Mosaico server unreachable, spool sync postponed: '{e}'")
+        except Exception as e:
+            logger.error(f"Spool sync failed, retrying later. Inner err: '{e}'")
+        return []
+
+    def start(self):
+        """Starts the worker thread."""
+        if self._thread is not None:
+            raise RuntimeError("SpoolSyncWorker already started.")
+        self._stop.clear()
+        self._thread = threading.Thread(
+            target=self._run, name="mosaico-spool-sync", daemon=True
+        )
+        self._thread.start()
+
+    def stop(self, timeout: Optional[float] = None):
+        """Stops the worker thread, waiting for the running synchronization to end."""
+        self._stop.set()
+        if self._thread is not None:
+            self._thread.join(timeout)
+            self._thread = None
@@ -0,0 +1,90 @@
+"""
+Tests for the offline spool.
+
+Validates that sequences recorded without a server connection are persisted
+as a manifest plus Arrow IPC streams, ready to be uploaded later.
+"""
+
+import json
+
+import pyarrow as pa
+import pyarrow.ipc as pa_ipc
+import pytest
+
+from mosaicolabs import Floating64, Message, SessionLevelErrorPolicy, Spool
+
+
+def _record(spool: Spool, name: str, count: int, **kwargs):
+    with spool.sequence_create(name, metadata={"vehicle": "v1"}, **kwargs) as swriter:
+        twriter = swriter.topic_create("sensors/value", {"unit": "m"}, Floating64)
+        assert twriter is not None
+        for i in range(count):
+            twriter.push(message=Message(timestamp_ns=i, data=Floating64(data=float(i))))
+
+
+def test_spool_writes_manifest_and_data(tmp_path):
+    spool = Spool(tmp_path)
+    _record(spool, "seq_spooled", 10)
+
+    folders = spool._sequence_folders()
+    assert len(folders) == 1
+
+    manifest = json.loads((folders[0] / "manifest.json").read_text())
+    assert manifest["sequence_name"] == "seq_spooled"
+    assert manifest["metadata"] == {"vehicle": "v1"}
+    assert manifest["closed"]
+    assert manifest["session_uuid"] is None
+    assert len(manifest["topics"]) == 1
+
+    topic = manifest["topics"][0]
+    assert topic["name"] == "sensors/value"
+    assert topic["metadata"] == {"unit": "m"}
+    assert not topic["uploaded"]
+
+    with pa.OSFile(str(folders[0] / topic["file"])) as source:
+        table = pa_ipc.open_stream(source).read_all()
+    assert table.num_rows == 10
+
+
+def test_spool_pending_in_recording_order(tmp_path):
+    spool = Spool(tmp_path)
+    assert spool.pending() == []
+
+    _record(spool, "seq_first", 1)
+    _record(spool, "seq_second", 1)
+
+    assert spool.pending() == ["seq_first", "seq_second"]
+    # The spool is reloaded from disk
+    assert Spool(tmp_path).pending() == ["seq_first", "seq_second"]
+
+
+def test_spool_open_sequence_is_not_pending(tmp_path):
+    spool = Spool(tmp_path)
+    with spool.sequence_create("seq_open", metadata={}):
+        assert spool.pending() == []
+    assert spool.pending() == ["seq_open"]
+
+
+def test_spool_delete_policy_discards_data(tmp_path):
+    spool = Spool(tmp_path)
+    with pytest.raises(ValueError):
+        with spool.sequence_create(
+            "seq_failed", metadata={}, on_error=SessionLevelErrorPolicy.Delete
+        ):
+            raise ValueError("recording failed")
+
+    assert spool.pending() == []
+    assert spool._sequence_folders() == []
+
+
+def test_spool_report_policy_keeps_error(tmp_path):
+    spool = Spool(tmp_path)
+    with pytest.raises(ValueError):
+        with spool.sequence_create("seq_failed", metadata={}):
+            raise ValueError("recording failed")
+
+    assert spool.pending() == ["seq_failed"]
+    manifest = json.loads(
+        (spool._sequence_folders()[0] / "manifest.json").read_text()
+    )
+    assert manifest["errors"] == ["recording failed"]
@@ -0,0 +1,13 @@
+mod sequence;
+pub use sequence::*;
+
+mod topic;
+pub use topic::*;
+
+mod notify;
+pub use notify::*;
+
+mod upload;
+pub use upload::*;
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
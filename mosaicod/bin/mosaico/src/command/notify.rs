@@ -0,0 +1,70 @@
+use super::Result;
+use arrow::array::{AsArray, RecordBatch};
+use clap::Subcommand;
+use colored::Colorize;
+use futures::TryStreamExt;
+use mosaico_client as client;
+
+#[derive(Subcommand, Debug)]
+pub enum Notify {
+    /// Print the notifications of a sequence as they are created, until interrupted
+    Tail {
+        sequence: String,
+
+        /// Print only the notifications at least as severe (`info`, `warning` or `error`)
+        #[arg(long)]
+        min_severity: Option<String>,
+    },
+}
+
+pub async fn notify(client: &mut client::Client, cmd: Notify) -> Result<()> {
+    match cmd {
+        Notify::Tail {
+            sequence,
+            min_severity,
+        } => {
+            let mut stream = client
+                .sequence_notification_stream(&sequence, min_severity.as_deref())
+                .await?;
+
+            while let Some(batch) = stream.try_next().await? {
+                print_notifications(&batch)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_notifications(batch: &RecordBatch) -> Result<()> {
+    let column = |name: &str| -> Result<_> {
+        Ok(batch
+            .column_by_name(name)
+            .ok_or_else(|| format!("missing column `{name}` in notifications"))?
+            .as_string::<i32>())
+    };
+
+    let created = column("created_datetime")?;
+    let severity = column("severity")?;
+    let name = column("name")?;
+    let code = column("code")?;
+    let msg = column("msg")?;
+
+    for row in 0..batch.num_rows() {
+        let level = match severity.value(row) {
+            "error" => "error".red(),
+            "warning" => "warning".yellow(),
+            other => other.normal(),
+        };
+        println!(
+            "{} {:7} {} {} {}",
+            created.value(row).dimmed(),
+            level,
+            name.value(row),
+            code.value(row).bold(),
+            msg.value(row),
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use super::Result;
+use clap::Subcommand;
+use mosaico_client as client;
+
+#[derive(Subcommand, Debug)]
+pub enum Sequence {
+    /// List the sequences
+    #[command(alias = "ls")]
+    List {
+        /// List only the sequences whose locator starts with the prefix
+        #[arg(long)]
+        prefix: Option<String>,
+    },
+
+    /// Create a new sequence
+    Create {
+        locator: String,
+
+        /// User metadata of the sequence, as a JSON object
+        #[arg(long, default_value = "{}")]
+        metadata: String,
+    },
+
+    /// Delete a sequence along with its topics
+    Delete { locator: String },
+}
+
+pub async fn sequence(client: &mut client::Client, cmd: Sequence) -> Result<()> {
+    match cmd {
+        Sequence::List { prefix } => {
+            let mut cursor: Option<String> = None;
+            loop {
+                let page = client
+                    .sequence_list(prefix.as_deref(), None, cursor.as_deref())
+                    .await?;
+                for locator in page.locators {
+                    println!("{locator}");
+                }
+
+                cursor = page.cursor;
+                if cursor.is_none() {
+                    break;
+                }
+            }
+        }
+        Sequence::Create { locator, metadata } => {
+            let metadata: serde_json::Value =
+                serde_json::from_str(&metadata).map_err(|e| format!("invalid metadata: {e}"))?;
+            client.sequence_create(&locator, metadata).await?;
+        }
+        Sequence::Delete { locator } => {
+            client.sequence_delete(&locator).await?;
+        }
+    }
+
+    Ok(())
+}
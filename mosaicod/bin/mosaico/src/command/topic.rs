@@ -0,0 +1,41 @@
+use super::Result;
+use clap::Subcommand;
+use colored::Colorize;
+use mosaico_client as client;
+
+#[derive(Subcommand, Debug)]
+pub enum Topic {
+    /// List the topics of a sequence
+    #[command(alias = "list")]
+    Ls { sequence: String },
+}
+
+pub async fn topic(client: &mut client::Client, cmd: Topic) -> Result<()> {
+    match cmd {
+        Topic::Ls { sequence } => {
+            let mut cursor: Option<String> = None;
+            loop {
+                let page = client
+                    .topic_list(&sequence, None, cursor.as_deref())
+                    .await?;
+                for topic in page.topics {
+                    let state = match topic.completed_at_ns {
+                        Some(_) => "finalized".green(),
+                        None => "open".yellow(),
+                    };
+                    println!(
+                        "{:40} {:20} {:10} {}",
+                        topic.locator, topic.ontology_tag, topic.serialization_format, state
+                    );
+                }
+
+                cursor = page.cursor;
+                if cursor.is_none() {
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
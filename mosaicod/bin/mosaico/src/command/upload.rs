@@ -0,0 +1,97 @@
+use super::Result;
+use arrow::array::RecordBatch;
+use clap::Args;
+use mosaico_client as client;
+use std::{fs::File, path::Path, path::PathBuf};
+
+#[derive(Args, Debug)]
+pub struct Upload {
+    /// Parquet (`.parquet`) or Arrow IPC (`.arrow`, `.ipc`) file to upload
+    pub file: PathBuf,
+
+    /// Locator of the topic to create (e.g. `run_1/imu`), its sequence is created if missing
+    #[arg(long)]
+    pub topic: String,
+
+    /// Ontology tag of the topic
+    #[arg(long)]
+    pub ontology_tag: String,
+
+    /// Column by which the uploaded data must be sorted
+    #[arg(long)]
+    pub sort_key: Option<String>,
+
+    /// User metadata of the topic, as a JSON object
+    #[arg(long, default_value = "{}")]
+    pub metadata: String,
+}
+
+/// Uploads the file into a new topic, in a dedicated session finalized once the upload
+/// completes or aborted if it fails.
+pub async fn upload(client: &mut client::Client, args: Upload) -> Result<()> {
+    let metadata: serde_json::Value =
+        serde_json::from_str(&args.metadata).map_err(|e| format!("invalid metadata: {e}"))?;
+    let batches = read_batches(&args.file)?;
+
+    let (sequence, _) = args
+        .topic
+        .split_once('/')
+        .ok_or_else(|| format!("`{}` is not a topic locator", args.topic))?;
+
+    match client
+        .sequence_create(sequence, serde_json::json!({}))
+        .await
+    {
+        Err(e) if e.kind() != client::ErrorKind::AlreadyExists => return Err(e.into()),
+        _ => {}
+    }
+
+    let session = client.session_create(sequence).await?;
+
+    let mut request =
+        client::TopicCreate::new(&args.topic, &args.ontology_tag).with_user_metadata(metadata);
+    if let Some(sort_key) = &args.sort_key {
+        request = request.with_sort_key(sort_key);
+    }
+
+    let rows: usize = batches.iter().map(RecordBatch::num_rows).sum();
+    let res = async {
+        let topic_uuid = client.topic_create(&session.uuid, request).await?;
+        client.upload(&args.topic, &topic_uuid, batches).await?;
+        client.session_finalize(&session.uuid).await
+    }
+    .await;
+
+    if let Err(e) = res {
+        // Best effort, the error of the upload is the one worth reporting
+        let _ = client.session_abort(&session.uuid).await;
+        return Err(e.into());
+    }
+
+    println!("uploaded {rows} rows into `{}`", args.topic);
+
+    Ok(())
+}
+
+fn read_batches(path: &Path) -> Result<Vec<RecordBatch>> {
+    let file = File::open(path).map_err(|e| format!("unable to open `{}`: {e}", path.display()))?;
+
+    let batches = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("parquet") => {
+            parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(file)?
+                .build()?
+                .collect::<std::result::Result<_, _>>()?
+        }
+        Some("arrow" | "ipc") => arrow::ipc::reader::FileReader::try_new(file, None)?
+            .collect::<std::result::Result<_, _>>()?,
+        _ => {
+            return Err(format!(
+                "unsupported file `{}`, expected a `.parquet`, `.arrow` or `.ipc` file",
+                path.display()
+            )
+            .into());
+        }
+    };
+
+    Ok(batches)
+}
@@ -0,0 +1,106 @@
+mod command;
+
+use clap::{Parser, Subcommand};
+use colored::Colorize;
+use mosaico_client as client;
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(about, long_about = None)]
+/// mosaico - Administration tool for mosaicod
+struct Cli {
+    /// Host of the mosaicod server
+    #[arg(long, global = true, env = "MOSAICO_HOST", default_value = "127.0.0.1")]
+    host: String,
+
+    /// Port of the mosaicod server
+    #[arg(long, global = true, env = "MOSAICO_PORT", default_value_t = 6726)]
+    port: u16,
+
+    /// API key used to authenticate the requests
+    #[arg(long, global = true, env = "MOSAICO_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+
+    /// Connect over TLS, trusting the certificate authority in the given PEM file
+    #[arg(long, global = true, env = "MOSAICO_TLS_CA_FILE")]
+    tls_ca_file: Option<PathBuf>,
+
+    #[command(subcommand)]
+    cmd: Commands,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Manage sequences
+    #[command(subcommand)]
+    Sequence(command::Sequence),
+
+    /// Inspect topics
+    #[command(subcommand)]
+    Topic(command::Topic),
+
+    /// Follow notifications
+    #[command(subcommand)]
+    Notify(command::Notify),
+
+    /// Upload a Parquet or Arrow IPC file into a new topic
+    Upload(command::Upload),
+}
+
+impl Cli {
+    async fn connect(&self) -> Result<client::Client, Box<dyn std::error::Error>> {
+        let mut builder = client::ClientBuilder::new(&self.host, self.port);
+
+        if let Some(file) = &self.tls_ca_file {
+            let pem = std::fs::read(file)
+                .map_err(|e| format!("unable to read `{}`: {e}", file.display()))?;
+            builder = builder.with_tls(
+                tonic::transport::ClientTlsConfig::new()
+                    .ca_certificate(tonic::transport::Certificate::from_pem(pem))
+                    .domain_name(&self.host),
+            );
+        }
+
+        if let Some(api_key) = &self.api_key {
+            builder = builder.with_api_key(api_key);
+        }
+
+        let endpoint = builder.endpoint();
+        let client = builder
+            .connect()
+            .await
+            .map_err(|e| match std::error::Error::source(&e) {
+                Some(source) => format!("unable to connect to `{endpoint}`: {source}"),
+                None => format!("unable to connect to `{endpoint}`: {e}"),
+            })?;
+
+        Ok(client)
+    }
+}
+
+async fn start(args: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = args.connect().await?;
+
+    match args.cmd {
+        Commands::Sequence(sub_args) => command::sequence(&mut client, sub_args).await?,
+        Commands::Topic(sub_args) => command::topic(&mut client, sub_args).await?,
+        Commands::Notify(sub_args) => command::notify(&mut client, sub_args).await?,
+        Commands::Upload(sub_args) => command::upload(&mut client, sub_args).await?,
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args = Cli::parse();
+
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("unable to start the runtime");
+
+    if let Err(e) = rt.block_on(start(args)) {
+        eprintln!("{} {e}", "error:".red().bold());
+        std::process::exit(1);
+    }
+}
@@ -8,6 +8,9 @@ use mosaicod_query as query;
 use std::sync::Arc;
 use tracing::error;
 
+/// Number of API keys read at once when listing the keys
+const PAGE_SIZE: usize = 256;
+
 #[derive(Subcommand, Debug)]
 pub enum ApiKey {
     /// Create a new API key with custom parameters
@@ -32,6 +35,10 @@ pub enum ApiKey {
         /// Define a datetime, using the rfc3339 format, after which the key in no longer valid (e.g 2026-03-27T12:20:00Z).
         #[arg(long)]
         expires_at: Option<String>,
+
+        /// Define the sequence searched by the queries performed with the key that don't filter sequences.
+        #[arg(long)]
+        default_sequence: Option<String>,
     },
 
     /// Revoke a key
@@ -87,11 +94,7 @@ pub fn auth(auth: ApiKey) -> Result<()> {
         },
     )?;
 
-    let context = facade::Context {
-        store: store.clone(),
-        db: db.clone(),
-        timeseries_querier: ts_gw.clone(),
-    };
+    let context = facade::Context::new(store.clone(), db.clone(), ts_gw.clone());
 
     match auth {
         ApiKey::Create {
@@ -99,9 +102,14 @@ pub fn auth(auth: ApiKey) -> Result<()> {
             description,
             expires_in,
             expires_at,
+            default_sequence,
         } => {
             let permissions = permissions.parse()?;
 
+            let default_sequence = default_sequence
+                .map(|sequence| sequence.parse::<types::SequenceLocator>())
+                .transpose()?;
+
             // Only one at a time between expires_at and expires_in can be set.
             let expiration_datetime: Option<types::Timestamp> = if let Some(expires_in) = expires_in
             {
@@ -134,9 +142,14 @@ pub fn auth(auth: ApiKey) -> Result<()> {
             let description = description.unwrap_or_default();
 
             let policy: core::error::PublicResult<types::ApiKey> = rt.block_on(async {
-                let handle =
-                    facade::auth::create(&context, permissions, description, expiration_datetime)
-                        .await?;
+                let handle = facade::auth::create(
+                    &context,
+                    permissions,
+                    description,
+                    expiration_datetime,
+                    default_sequence,
+                )
+                .await?;
                 Ok(handle.into())
             });
 
@@ -176,9 +189,19 @@ pub fn auth(auth: ApiKey) -> Result<()> {
 
         ApiKey::List => {
             let res: Result<()> = rt.block_on(async {
-                let policies = facade::auth::all_keys(&context).await?;
+                print_authz_policy_list_header();
+
+                let mut cursor = None;
+                loop {
+                    let page =
+                        facade::auth::key_page(&context, cursor.as_deref(), PAGE_SIZE).await?;
+                    print_authz_policy_list(page.keys);
 
-                print_authz_policy_list(policies);
+                    cursor = page.cursor;
+                    if cursor.is_none() {
+                        break;
+                    }
+                }
 
                 Ok(())
             });
@@ -189,21 +212,32 @@ pub fn auth(auth: ApiKey) -> Result<()> {
         ApiKey::Purge { all } => {
             let res: Result<()> = rt.block_on(async {
                 let mut errors = Vec::new();
-                let keys = facade::auth::all_keys(&context).await?;
-                for key in keys.iter().filter(|k| all || k.is_expired()) {
-                    let fingerprint = key.token().fingerprint();
-
-                    let result: Result<()> = async {
-                        let handle =
-                            facade::auth::Handle::try_from_fingerprint(&context, fingerprint)
-                                .await?;
-                        facade::auth::delete(&context, handle).await?;
-                        Ok(())
+                // Keys are deleted one page at a time, deletions do not affect the cursor
+                let mut cursor = None;
+                loop {
+                    let page =
+                        facade::auth::key_page(&context, cursor.as_deref(), PAGE_SIZE).await?;
+
+                    for key in page.keys.iter().filter(|k| all || k.is_expired()) {
+                        let fingerprint = key.token().fingerprint();
+
+                        let result: Result<()> = async {
+                            let handle =
+                                facade::auth::Handle::try_from_fingerprint(&context, fingerprint)
+                                    .await?;
+                            facade::auth::delete(&context, handle).await?;
+                            Ok(())
+                        }
+                        .await;
+
+                        if let Err(e) = result {
+                            errors.push((fingerprint.to_owned(), e));
+                        }
                     }
-                    .await;
 
-                    if let Err(e) = result {
-                        errors.push((fingerprint, e));
+                    cursor = page.cursor;
+                    if cursor.is_none() {
+                        break;
                     }
                 }
 
@@ -262,10 +296,13 @@ fn print_authz_policy_details(policy: types::ApiKey) {
     );
 
     println!("{:>13} {}", "DESCRIPTION:".bold(), policy.description);
+
+    if let Some(sequence) = &policy.default_sequence {
+        println!("{:>13} {}", "SEQUENCE:".bold(), sequence);
+    }
 }
 
-fn print_authz_policy_list(policies: Vec<types::ApiKey>) {
-    // Header
+fn print_authz_policy_list_header() {
     println!(
         "{:>12} {:>24} {:>24} {:>10} {:>14}    {}",
         "FINGERPRINT".bold(),
@@ -275,6 +312,9 @@ fn print_authz_policy_list(policies: Vec<types::ApiKey>) {
         "PERMISSIONS".bold(),
         "DESCRIPTION".bold()
     );
+}
+
+fn print_authz_policy_list(policies: Vec<types::ApiKey>) {
     for policy in policies {
         let datetime: types::DateTime = policy.created_at.into();
         let expired_datetime: Option<types::DateTime> = policy.expires_at.map(|t| t.into());
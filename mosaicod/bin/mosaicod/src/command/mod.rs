@@ -3,3 +3,6 @@ pub use run::*;
 
 mod api_key;
 pub use api_key::*;
+
+mod recover;
+pub use recover::*;
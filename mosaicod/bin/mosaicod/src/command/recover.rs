@@ -0,0 +1,79 @@
+use crate::common;
+use clap::Args;
+use colored::Colorize;
+use mosaicod_core::{self as core, error::PublicResult as Result, params};
+use mosaicod_db as db;
+use mosaicod_facade as facade;
+use mosaicod_query as query;
+use std::sync::Arc;
+
+#[derive(Args, Debug)]
+pub struct Recover {
+    /// Prefix of the store scanned for topic folders. It defaults to the whole store.
+    #[arg(long, default_value = "")]
+    pub prefix: String,
+
+    /// List the topics that would be registered without modifying the database.
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+}
+
+/// Registers the topics found in the store and missing from the database, together with
+/// their sequences and sessions.
+///
+/// Last-resort path when the database is lost and only the store survives.
+pub fn recover(args: Recover) -> Result<()> {
+    let rt = common::init_runtime()?;
+
+    let store = common::init_store()?;
+
+    let ts_gw = Arc::new(query::TimeseriesEngine::try_new(
+        store.clone(),
+        params::params().query_engine_memory_pool_size.value,
+    )?);
+
+    let db = common::init_db(
+        &rt,
+        &db::Config {
+            db_url: params::params().db_url.value.parse().map_err(|_| {
+                core::Error::invalid_configuration(
+                    params::params().db_url.env.clone(),
+                    "unable to parse".to_string(),
+                )
+            })?,
+            // Here we are using only one connection since it's a CLI command
+            max_connections: 1,
+        },
+    )?;
+
+    let context = facade::Context::new(store, db, ts_gw)
+        .with_store_layout(facade::relocation::configured_layout()?);
+
+    let report = rt.block_on(facade::recovery::import(
+        &context,
+        &args.prefix,
+        args.dry_run,
+    ))?;
+
+    for locator in &report.imported {
+        println!("{} {}", "imported".green().bold(), locator);
+    }
+    for (folder, reason) in &report.skipped {
+        println!("{} {} ({})", "skipped".yellow().bold(), folder, reason);
+    }
+
+    println!(
+        "{} {} topics, {} sequences and {} sessions {}",
+        "RECOVERY:".bold(),
+        report.imported.len(),
+        report.sequences_created,
+        report.sessions_created,
+        if args.dry_run {
+            "to register (dry run)"
+        } else {
+            "registered"
+        }
+    );
+
+    Ok(())
+}
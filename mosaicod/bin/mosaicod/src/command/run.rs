@@ -4,8 +4,15 @@ use mosaicod_core::{self as core, error::PublicResult as Result, params};
 use mosaicod_db as db;
 use mosaicod_server as server;
 use signal_hook::{consts::SIGINT, iterator::Signals};
-use std::thread;
-use tracing::{debug, info};
+use std::{thread, time::Duration};
+use tracing::{debug, info, warn};
+
+/// Interval between consecutive runs of the startup checks in strict mode.
+const STRICT_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Age after which the temporary files of the writes to a filesystem store are considered
+/// left by a crashed process.
+const STAGED_WRITES_MAX_AGE: Duration = Duration::from_secs(3600);
 
 #[derive(Args, Debug)]
 pub struct Run {
@@ -18,7 +25,8 @@ pub struct Run {
     pub port: u16,
 
     /// Enable TLS. When enabled, the following envirnoment variables needs to be set
-    /// MOSAICOD_TLS_CERT_FILE, MOSAICOD_TLS_PRIVATE_KEY_FILE.
+    /// MOSAICOD_TLS_CERT_FILE, MOSAICOD_TLS_PRIVATE_KEY_FILE. Set MOSAICOD_TLS_CLIENT_CA_FILE
+    /// to require client certificates (mutual TLS).
     #[arg(long, default_value_t = false)]
     pub tls: bool,
 
@@ -30,12 +38,20 @@ pub struct Run {
     /// perform any actions. See command `mosaicod api-key` for more info.
     #[arg(long, default_value_t = false)]
     pub api_key: bool,
+
+    /// Refuse to serve until all the startup checks (database, store, configuration and
+    /// clock) pass. Failed checks are reported and run again every few seconds.
+    #[arg(long, default_value_t = false)]
+    pub strict: bool,
 }
 
 fn tls_config() -> server::flight::TlsConfig {
     server::flight::TlsConfig {
         certificate_file: params::params().tls_certificate_file.value.clone().into(),
         private_key_file: params::params().tls_private_key_file.value.clone().into(),
+        client_ca_file: Some(&params::params().tls_client_ca_file.value)
+            .filter(|file| !file.is_empty())
+            .map(Into::into),
     }
 }
 
@@ -51,6 +67,15 @@ pub fn run(args: Run, json_format: bool) -> Result<()> {
     info!("startup multi-threaded runtime");
     let rt = common::init_runtime()?;
 
+    match rt.block_on(store.remove_staged_writes(STAGED_WRITES_MAX_AGE)) {
+        Ok(0) => {}
+        Ok(removed) => info!("removed {} temporary files of interrupted writes", removed),
+        Err(e) => warn!(
+            "unable to remove the temporary files of interrupted writes: {}",
+            e
+        ),
+    }
+
     let params = params::params();
 
     let db_config = db::Config {
@@ -64,7 +89,19 @@ pub fn run(args: Run, json_format: bool) -> Result<()> {
     };
 
     info!("startup database connection");
-    let db = common::init_db(&rt, &db_config)?;
+    let db = loop {
+        let start = std::time::Instant::now();
+        match common::init_db(&rt, &db_config) {
+            Ok(db) => break db,
+            Err(err) if args.strict => {
+                let mut report = server::checks::Report::default();
+                report.record("database", start, Err(err.to_string()));
+                print::startup_checks(&report, json_format);
+                wait_strict_retry();
+            }
+            Err(err) => return Err(err),
+        }
+    };
 
     // If not specified by the user use the default loopback address
     let host_is_specified = args.host.is_some();
@@ -84,6 +121,16 @@ pub fn run(args: Run, json_format: bool) -> Result<()> {
         server.flight_config.gzip(true);
     }
 
+    info!("startup checks");
+    loop {
+        let report = rt.block_on(server.checks());
+        print::startup_checks(&report, json_format);
+        if report.passed() || !args.strict {
+            break;
+        }
+        wait_strict_retry();
+    }
+
     // (cabba) NOTE: maybe we need to return a more specific error ?
     let mut signals = Signals::new([SIGINT]).map_err(|_| {
         core::Error::internal(Some("unable to create termination signal".to_owned()))
@@ -112,3 +159,11 @@ pub fn run(args: Run, json_format: bool) -> Result<()> {
 
     Ok(())
 }
+
+fn wait_strict_retry() {
+    warn!(
+        "startup checks failed in strict mode, retrying in {:?}",
+        STRICT_RETRY_INTERVAL
+    );
+    thread::sleep(STRICT_RETRY_INTERVAL);
+}
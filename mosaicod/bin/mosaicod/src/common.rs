@@ -48,7 +48,8 @@ pub fn init_store() -> Result<store::StoreRef> {
         )
     })?;
 
-    let mut builder = store::Builder::new(endpoint_url, params.store_bucket.value.clone());
+    let mut builder = store::Builder::new(endpoint_url, params.store_bucket.value.clone())
+        .with_read_ahead(params.store_read_ahead.value);
 
     let secret_key = params.store_secret_key.value.clone();
     let access_key = params.store_access_key.value.clone();
@@ -57,7 +58,30 @@ pub fn init_store() -> Result<store::StoreRef> {
         builder = builder.with_credentials(access_key, secret_key);
     }
 
-    Ok(Arc::new(builder.build()?))
+    if !params.store_region.value.is_empty() {
+        builder = builder.with_region(params.store_region.value.clone());
+    }
+
+    if !params.store_prefix.value.is_empty() {
+        builder = builder.with_prefix(params.store_prefix.value.clone());
+    }
+
+    if !params.store_cache_path.value.is_empty() {
+        let ttl = params.store_cache_ttl.value;
+        builder = builder.with_cache(store::CacheConfig {
+            path: params.store_cache_path.value.clone().into(),
+            capacity_bytes: params.store_cache_size.value,
+            ttl: (ttl > 0).then(|| std::time::Duration::from_secs(ttl)),
+        });
+    }
+
+    let store = builder.build()?;
+    store
+        .metrics()
+        .access()
+        .set_sample_rate(params.store_access_sample_rate.value);
+
+    Ok(Arc::new(store))
 }
 
 /// Load the defined env variables from the system.
@@ -1,4 +1,5 @@
 use clap::ValueEnum;
+use mosaicod_core::{self as core, error::PublicResult as Result};
 
 #[derive(Debug, Copy, Clone, ValueEnum)]
 pub enum LogLevel {
@@ -46,32 +47,90 @@ impl std::fmt::Display for LogFormat {
     }
 }
 
-pub fn init_logger(format: LogFormat, level: LogLevel) {
+/// Keeps the span exporter alive, the spans still buffered are flushed when dropped.
+pub struct Tracer {
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Drop for Tracer {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take()
+            && let Err(e) = provider.shutdown()
+        {
+            eprintln!("unable to flush the spans: {e}");
+        }
+    }
+}
+
+/// Installs the global subscriber. If `otlp_endpoint` is set, the spans of the daemon are
+/// also exported to it, and the W3C trace context of the incoming requests is accepted.
+pub fn init_logger(
+    format: LogFormat,
+    level: LogLevel,
+    otlp_endpoint: Option<&url::Url>,
+) -> Result<Tracer> {
     use tracing_subscriber::prelude::*;
 
     let filter = tracing_subscriber::EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(level.as_filter()));
 
-    match format {
-        LogFormat::Json => tracing_subscriber::FmtSubscriber::builder()
-            .with_max_level(tracing::Level::TRACE)
-            .json()
-            .finish()
-            .with(filter)
-            .init(),
-        LogFormat::Pretty => tracing_subscriber::FmtSubscriber::builder()
+    let fmt = match format {
+        LogFormat::Json => tracing_subscriber::fmt::layer().json().boxed(),
+        LogFormat::Pretty => tracing_subscriber::fmt::layer()
             .with_target(false)
             .with_ansi(true)
-            .with_max_level(tracing::Level::TRACE)
-            .finish()
-            .with(filter)
-            .init(),
-        LogFormat::Plain => tracing_subscriber::FmtSubscriber::builder()
+            .boxed(),
+        LogFormat::Plain => tracing_subscriber::fmt::layer()
             .with_target(false)
             .with_ansi(false)
-            .with_max_level(tracing::Level::TRACE)
-            .finish()
-            .with(filter)
-            .init(),
-    }
+            .boxed(),
+    };
+
+    let provider = otlp_endpoint.map(init_otlp).transpose()?;
+
+    // Spans are exported independently of the log level
+    let otel = provider.as_ref().map(|provider| {
+        use opentelemetry::trace::TracerProvider;
+
+        tracing_opentelemetry::layer()
+            .with_tracer(provider.tracer("mosaicod"))
+            .with_filter(
+                tracing_subscriber::filter::Targets::new()
+                    .with_target("mosaico", tracing::Level::DEBUG),
+            )
+    });
+
+    tracing_subscriber::registry()
+        .with(fmt.with_filter(filter))
+        .with(otel)
+        .init();
+
+    Ok(Tracer { provider })
+}
+
+fn init_otlp(endpoint: &url::Url) -> Result<opentelemetry_sdk::trace::SdkTracerProvider> {
+    use opentelemetry_otlp::WithExportConfig;
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint.as_str())
+        .build()
+        .map_err(|e| {
+            core::Error::internal(Some(format!("unable to start the OTLP exporter: {e}")))
+        })?;
+
+    let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            opentelemetry_sdk::Resource::builder()
+                .with_service_name("mosaicod")
+                .build(),
+        )
+        .build();
+
+    opentelemetry::global::set_text_map_propagator(
+        opentelemetry_sdk::propagation::TraceContextPropagator::new(),
+    );
+
+    Ok(provider)
 }
@@ -22,6 +22,11 @@ struct Cli {
     #[arg(long, global = true, default_value_t = log::LogLevel::Warning)]
     log_level: log::LogLevel,
 
+    /// Export the spans to an OpenTelemetry collector, at the given OTLP/HTTP endpoint
+    /// (e.g. `http://localhost:4318/v1/traces`)
+    #[arg(long, global = true)]
+    otlp_endpoint: Option<url::Url>,
+
     #[command(subcommand)]
     cmd: Commands,
 }
@@ -34,6 +39,9 @@ enum Commands {
     /// Manage mosaico API keys
     #[command(subcommand, name = "api-key")]
     Auth(command::ApiKey),
+
+    /// Register the topics found in the store and missing from the database
+    Recover(command::Recover),
 }
 
 fn start() -> Result<Option<String>> {
@@ -57,7 +65,8 @@ fn start() -> Result<Option<String>> {
     };
 
     print::set_colors(args.log_format);
-    log::init_logger(args.log_format, args.log_level);
+    // Flushes the exported spans when the command returns
+    let _tracer = log::init_logger(args.log_format, args.log_level, args.otlp_endpoint.as_ref())?;
 
     common::load_env_variables()?;
 
@@ -66,6 +75,7 @@ fn start() -> Result<Option<String>> {
     match args.cmd {
         Commands::Run(sub_args) => command::run(sub_args, is_json_output)?,
         Commands::Auth(sub_args) => command::auth(sub_args)?,
+        Commands::Recover(sub_args) => command::recover(sub_args)?,
     }
 
     Ok(None)
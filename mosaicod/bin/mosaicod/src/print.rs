@@ -2,9 +2,10 @@ use super::log;
 use colored::Colorize;
 use mosaicod_core::error::PublicError;
 use mosaicod_db as db;
+use mosaicod_server::checks;
 use mosaicod_store as store;
 use std::{net::IpAddr, time::Instant};
-use tracing::error;
+use tracing::{debug, error};
 
 fn format_addr(is_loopback: bool, msg: String) {
     println!(
@@ -88,6 +89,43 @@ pub fn error(err: impl AsRef<dyn PublicError + Send + Sync>) {
     }
 }
 
+/// Reports the outcome of the startup checks, listing them on the console if any failed.
+///
+/// Every check is also logged with its name, duration and failure reason as separate
+/// fields, so they can be processed when logs are in JSON format.
+pub fn startup_checks(report: &checks::Report, json_format: bool) {
+    for check in &report.checks {
+        let elapsed_ms = check.elapsed.as_millis() as u64;
+        match &check.failure {
+            Some(reason) => error!(
+                check = check.name,
+                elapsed_ms,
+                reason = reason.as_str(),
+                "startup check failed"
+            ),
+            None => debug!(check = check.name, elapsed_ms, "startup check passed"),
+        }
+    }
+
+    if report.passed() || json_format {
+        return;
+    }
+
+    eprintln!("{} startup checks failed", "error:".red());
+    for check in &report.checks {
+        match &check.failure {
+            Some(reason) => eprintln!(" {} {:20} {}", "✗".red(), check.name, reason),
+            None => eprintln!(
+                " {} {:20} {}",
+                "✓".green(),
+                check.name,
+                format!("{} ms", check.elapsed.as_millis()).dimmed()
+            ),
+        }
+    }
+    eprintln!();
+}
+
 fn format_db_host(db_config: &db::Config) -> String {
     // let schema = db_config.db_url.scheme();
     // let domain = db_config.db_url.domain().unwrap_or("???");
@@ -121,5 +159,14 @@ pub fn store_display_name(store: &store::StoreRef) -> String {
                 "]".dimmed(),
             )
         }
+        store::Target::Memory => {
+            format!(
+                "{} {}{}{}",
+                "memory".yellow(),
+                "[".dimmed(),
+                "volatile".cyan(),
+                "]".dimmed()
+            )
+        }
     }
 }
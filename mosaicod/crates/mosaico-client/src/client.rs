@@ -0,0 +1,1289 @@
+use crate::{Error, ErrorKind, Result, TimestampRange, requests, responses};
+use arrow::{
+    array::{BinaryArray, RecordBatch},
+    datatypes::Schema,
+};
+use arrow_flight::{
+    Action, FlightData, FlightDescriptor, Ticket, decode::FlightRecordBatchStream,
+    encode::FlightDataEncoderBuilder, flight_service_client::FlightServiceClient,
+};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use futures::TryStreamExt;
+use serde::{Deserialize, de::DeserializeOwned};
+use std::{collections::BTreeMap, time::Duration};
+use tonic::{
+    service::{Interceptor, interceptor::InterceptedService},
+    transport::{Channel, ClientTlsConfig},
+};
+
+/// Metadata key of the API key sent with every request.
+const API_KEY_HEADER: &str = "mosaico-api-key-token";
+
+/// Credentials sent in the metadata of every request.
+#[derive(Clone, Default)]
+pub struct Credentials {
+    api_key: Option<String>,
+    bearer_token: Option<String>,
+}
+
+impl Interceptor for Credentials {
+    fn call(
+        &mut self,
+        mut req: tonic::Request<()>,
+    ) -> std::result::Result<tonic::Request<()>, tonic::Status> {
+        if let Some(key) = &self.api_key {
+            let value = key
+                .parse()
+                .map_err(|_| tonic::Status::invalid_argument("invalid api key"))?;
+            req.metadata_mut().insert(API_KEY_HEADER, value);
+        }
+
+        if let Some(token) = &self.bearer_token {
+            let value = format!("Bearer {token}")
+                .parse()
+                .map_err(|_| tonic::Status::invalid_argument("invalid bearer token"))?;
+            req.metadata_mut().insert("authorization", value);
+        }
+
+        Ok(req)
+    }
+}
+
+/// Channel used by [`Client`], sending the configured [`Credentials`].
+pub type ClientChannel = InterceptedService<Channel, Credentials>;
+
+/// Retries of the requests failed because the server, or one of its dependencies, was
+/// temporarily unavailable.
+///
+/// A failed request is retried after the delay suggested by the server (see
+/// [`Error::retry_after`]), or after a backoff doubling at each attempt if the server did
+/// not suggest one.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts of a request, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    pub initial_backoff: Duration,
+    /// Maximum delay between two attempts, also bounding the delays suggested by the server
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Policy performing every request once.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the delay before the attempt following `attempt` (starting from 1) failed
+    /// with `err`, `None` if the request should not be retried.
+    fn delay(&self, attempt: u32, err: &Error) -> Option<Duration> {
+        if attempt >= self.max_attempts || err.kind() != ErrorKind::Unavailable {
+            return None;
+        }
+
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt - 1));
+        Some(err.retry_after().unwrap_or(backoff).min(self.max_backoff))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Configures and establishes the connection to a mosaicod server.
+pub struct ClientBuilder {
+    host: String,
+    port: u16,
+    tls: Option<ClientTlsConfig>,
+    credentials: Credentials,
+    retry: RetryPolicy,
+}
+
+impl ClientBuilder {
+    pub fn new(host: &str, port: u16) -> Self {
+        Self {
+            host: host.to_owned(),
+            port,
+            tls: None,
+            credentials: Credentials::default(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Connects to the server over TLS configured by `tls`.
+    pub fn with_tls(mut self, tls: ClientTlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Authenticates the requests with `api_key`.
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.credentials.api_key = Some(api_key.to_owned());
+        self
+    }
+
+    /// Sends `token` in the `authorization` header of every request.
+    pub fn with_bearer_token(mut self, token: &str) -> Self {
+        self.credentials.bearer_token = Some(token.to_owned());
+        self
+    }
+
+    /// Retries the requests failed because the server was unavailable according to
+    /// `retry`, [`RetryPolicy::default`] if not set.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Address of the server.
+    ///
+    /// FIXME:
+    /// We need to use `http` and `https` instead of `grpc` and `grpc+tls` since tonic has an
+    /// issue regarding this https://github.com/hyperium/tonic/issues/1496
+    pub fn endpoint(&self) -> String {
+        let scheme = if self.tls.is_some() { "https" } else { "http" };
+        format!("{scheme}://{}:{}", self.host, self.port)
+    }
+
+    /// Establishes the connection to the server.
+    pub async fn connect(self) -> Result<Client> {
+        let url = self.endpoint();
+
+        let mut channel =
+            Channel::from_shared(url.clone()).map_err(|_| Error::InvalidEndpoint(url))?;
+
+        if let Some(tls) = self.tls {
+            channel = channel.tls_config(tls)?;
+        }
+
+        let channel = channel.connect().await?;
+
+        Ok(Client {
+            client: FlightServiceClient::with_interceptor(channel, self.credentials),
+            retry: self.retry,
+        })
+    }
+}
+
+/// Envelope of the responses of the actions.
+#[derive(Deserialize)]
+struct ActionResponse {
+    action: String,
+    response: serde_json::Value,
+}
+
+/// Client of a mosaicod server, see [`ClientBuilder`].
+///
+/// Requests failed because the server was unavailable are retried according to the
+/// [`RetryPolicy`] of the client. Dereferences to the underlying [`FlightServiceClient`] to
+/// perform raw Flight calls, which are not retried.
+pub struct Client {
+    client: FlightServiceClient<ClientChannel>,
+    retry: RetryPolicy,
+}
+
+impl Client {
+    /// Performs `request` until it succeeds or the [`RetryPolicy`] of the client gives up.
+    async fn with_retry<T, F, Fut>(&self, mut request: F) -> Result<T>
+    where
+        F: FnMut(FlightServiceClient<ClientChannel>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            let err = match request(self.client.clone()).await {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+            let Some(delay) = self.retry.delay(attempt, &err) else {
+                return Err(err);
+            };
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Performs the action `action` with the JSON `body`, returning its response.
+    pub async fn action(
+        &mut self,
+        action: &str,
+        body: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let request = Action {
+            r#type: action.to_owned(),
+            body: body.to_string().into(),
+        };
+
+        let mut stream = self
+            .with_retry(|mut client| {
+                let request = request.clone();
+                async move { Ok(client.do_action(request).await?.into_inner()) }
+            })
+            .await?;
+
+        let mut response = serde_json::Value::Null;
+        while let Some(result) = stream.message().await? {
+            let r: ActionResponse = serde_json::from_slice(&result.body)
+                .map_err(|e| Error::unexpected_response(action, e))?;
+            if r.action != action {
+                return Err(Error::unexpected_response(
+                    action,
+                    format!("response of `{}`", r.action),
+                ));
+            }
+            response = r.response;
+        }
+
+        Ok(response)
+    }
+
+    /// Performs the action `action` with the JSON `body`, deserializing its response.
+    pub async fn action_as<T: DeserializeOwned>(
+        &mut self,
+        action: &str,
+        body: serde_json::Value,
+    ) -> Result<T> {
+        let response = self.action(action, body).await?;
+        serde_json::from_value(response).map_err(|e| Error::unexpected_response(action, e))
+    }
+
+    // ########
+    // Sequence
+    // ########
+
+    /// Creates the sequence `locator`.
+    pub async fn sequence_create(
+        &mut self,
+        locator: &str,
+        user_metadata: serde_json::Value,
+    ) -> Result<()> {
+        let body = serde_json::json!({ "locator": locator, "user_metadata": user_metadata });
+        self.action("sequence_create", body).await?;
+        Ok(())
+    }
+
+    /// Deletes the sequence `locator`.
+    pub async fn sequence_delete(&mut self, locator: &str) -> Result<()> {
+        let body = serde_json::json!({ "locator": locator });
+        self.action("sequence_delete", body).await?;
+        Ok(())
+    }
+
+    /// Lists a page of at most `limit` sequences whose locator starts with `prefix`, starting
+    /// from the `cursor` returned along with the previous page.
+    pub async fn sequence_list(
+        &mut self,
+        prefix: Option<&str>,
+        limit: Option<usize>,
+        cursor: Option<&str>,
+    ) -> Result<responses::SequenceList> {
+        let body = serde_json::json!({ "prefix": prefix, "limit": limit, "cursor": cursor });
+        self.action_as("sequence_list", body).await
+    }
+
+    /// Imports the MCAP recording stored at `path` into a new session of the sequence
+    /// `locator`, creating the sequence if missing. Each channel is imported into a topic,
+    /// only the channels publishing on `channels` if set.
+    pub async fn sequence_import_mcap(
+        &mut self,
+        locator: &str,
+        path: &str,
+        channels: Option<&[&str]>,
+    ) -> Result<responses::SequenceImportMcap> {
+        let body = serde_json::json!({ "locator": locator, "path": path, "channels": channels });
+        self.action_as("sequence_import_mcap", body).await
+    }
+
+    /// Writes the sequence `locator` as a portable archive in the store, under `prefix`.
+    pub async fn sequence_export(
+        &mut self,
+        locator: &str,
+        prefix: &str,
+    ) -> Result<responses::SequenceExport> {
+        let body = serde_json::json!({ "locator": locator, "prefix": prefix });
+        self.action_as("sequence_export", body).await
+    }
+
+    /// Recreates the sequence archived at `path`, named `locator` if set. With
+    /// `preserve_uuids` the sequence and its topics keep the UUIDs of the archive.
+    pub async fn sequence_import(
+        &mut self,
+        path: &str,
+        locator: Option<&str>,
+        preserve_uuids: bool,
+    ) -> Result<responses::SequenceImport> {
+        let body = serde_json::json!({
+            "path": path,
+            "locator": locator,
+            "preserve_uuids": preserve_uuids,
+        });
+        self.action_as("sequence_import", body).await
+    }
+
+    /// Restores the sequence `locator` deleted within the retention period.
+    pub async fn sequence_restore(&mut self, locator: &str) -> Result<()> {
+        let body = serde_json::json!({ "locator": locator });
+        self.action("sequence_restore", body).await?;
+        Ok(())
+    }
+
+    /// Permanently removes the deleted sequence `locator` and its data.
+    pub async fn sequence_purge(&mut self, locator: &str) -> Result<()> {
+        let body = serde_json::json!({ "locator": locator });
+        self.action("sequence_purge", body).await?;
+        Ok(())
+    }
+
+    /// Places (or releases) the legal hold of the sequence `locator`, preventing its
+    /// deletion while set.
+    pub async fn sequence_legal_hold(&mut self, locator: &str, legal_hold: bool) -> Result<()> {
+        let body = serde_json::json!({ "locator": locator, "legal_hold": legal_hold });
+        self.action("sequence_legal_hold", body).await?;
+        Ok(())
+    }
+
+    /// Sets the compaction policy of the sequence `locator`.
+    pub async fn sequence_compaction_policy_set(
+        &mut self,
+        locator: &str,
+        policy: requests::CompactionPolicy,
+    ) -> Result<()> {
+        const ACTION: &str = "sequence_compaction_policy_set";
+
+        let mut body =
+            serde_json::to_value(policy).map_err(|e| Error::unexpected_response(ACTION, e))?;
+        body["locator"] = locator.into();
+
+        self.action(ACTION, body).await?;
+        Ok(())
+    }
+
+    /// Returns the compaction policy of the sequence `locator`.
+    pub async fn sequence_compaction_policy_get(
+        &mut self,
+        locator: &str,
+    ) -> Result<requests::CompactionPolicy> {
+        let body = serde_json::json!({ "locator": locator });
+        self.action_as("sequence_compaction_policy_get", body).await
+    }
+
+    /// Compacts the data files of the topics of the sequence `locator`.
+    pub async fn sequence_compact(&mut self, locator: &str) -> Result<responses::SequenceCompact> {
+        let body = serde_json::json!({ "locator": locator });
+        self.action_as("sequence_compact", body).await
+    }
+
+    /// Updates the user metadata of the sequence `locator`, merging the top-level keys of
+    /// `user_metadata` into the current metadata if `merge`, replacing it otherwise.
+    pub async fn sequence_update_metadata(
+        &mut self,
+        locator: &str,
+        user_metadata: serde_json::Value,
+        merge: bool,
+    ) -> Result<()> {
+        let body = serde_json::json!({
+            "locator": locator,
+            "user_metadata": user_metadata,
+            "merge": merge,
+        });
+        self.action("sequence_update_metadata", body).await?;
+        Ok(())
+    }
+
+    /// Returns the attestations of the finalized sessions of the sequence `locator`.
+    pub async fn sequence_attestation(
+        &mut self,
+        locator: &str,
+    ) -> Result<responses::SequenceAttestation> {
+        let body = serde_json::json!({ "locator": locator });
+        self.action_as("sequence_attestation", body).await
+    }
+
+    /// Lists a page of at most `limit` activities of the sequence `locator`, only the ones
+    /// since `since_ns` if set, starting from the `cursor` returned along with the previous
+    /// page.
+    pub async fn activity_feed(
+        &mut self,
+        locator: &str,
+        since_ns: Option<i64>,
+        limit: Option<usize>,
+        cursor: Option<&str>,
+    ) -> Result<responses::ActivityFeed> {
+        let body = serde_json::json!({
+            "locator": locator,
+            "since_ns": since_ns,
+            "limit": limit,
+            "cursor": cursor,
+        });
+        self.action_as("activity_feed", body).await
+    }
+
+    // ########
+    // Session
+    // ########
+
+    /// Opens a session to upload topics into the sequence `locator`.
+    pub async fn session_create(&mut self, locator: &str) -> Result<responses::SessionCreate> {
+        let body = serde_json::json!({ "locator": locator });
+        self.action_as("session_create", body).await
+    }
+
+    /// Finalizes the session, making its topics readable.
+    pub async fn session_finalize(&mut self, session_uuid: &str) -> Result<()> {
+        let body = serde_json::json!({ "session_uuid": session_uuid });
+        self.action("session_finalize", body).await?;
+        Ok(())
+    }
+
+    /// Aborts the session, discarding the data uploaded into it.
+    pub async fn session_abort(&mut self, session_uuid: &str) -> Result<()> {
+        let body = serde_json::json!({ "session_uuid": session_uuid });
+        self.action("session_abort", body).await?;
+        Ok(())
+    }
+
+    /// Deletes the session `locator` along with its topics.
+    pub async fn session_delete(&mut self, locator: &str) -> Result<()> {
+        let body = serde_json::json!({ "locator": locator });
+        self.action("session_delete", body).await?;
+        Ok(())
+    }
+
+    /// Lists a page of at most `limit` sessions of the sequence `locator`, only the ones in
+    /// `state` (`open` or `finalized`) if set, starting from the `cursor` returned along with
+    /// the previous page.
+    pub async fn session_list(
+        &mut self,
+        locator: &str,
+        state: Option<&str>,
+        limit: Option<usize>,
+        cursor: Option<&str>,
+    ) -> Result<responses::SessionList> {
+        let body = serde_json::json!({
+            "locator": locator,
+            "state": state,
+            "limit": limit,
+            "cursor": cursor,
+        });
+        self.action_as("session_list", body).await
+    }
+
+    /// Allows the API key with fingerprint `api_key_fingerprint` to upload into the session.
+    pub async fn session_share(
+        &mut self,
+        session_uuid: &str,
+        api_key_fingerprint: &str,
+    ) -> Result<()> {
+        let body = serde_json::json!({
+            "session_uuid": session_uuid,
+            "api_key_fingerprint": api_key_fingerprint,
+        });
+        self.action("session_share", body).await?;
+        Ok(())
+    }
+
+    // ########
+    // Consumer Offset
+    // ########
+
+    /// Commits the `position` reached by `consumer` reading the sequence or topic `locator`.
+    pub async fn consumer_offset_commit(
+        &mut self,
+        consumer: &str,
+        locator: &str,
+        position: requests::ConsumerPosition,
+    ) -> Result<responses::ConsumerOffset> {
+        let (cursor, watermark_ns) = match position {
+            requests::ConsumerPosition::Cursor(cursor) => (Some(cursor), None),
+            requests::ConsumerPosition::Watermark(watermark_ns) => (None, Some(watermark_ns)),
+        };
+        let body = serde_json::json!({
+            "consumer": consumer,
+            "locator": locator,
+            "cursor": cursor,
+            "watermark_ns": watermark_ns,
+        });
+        self.action_as("consumer_offset_commit", body).await
+    }
+
+    /// Returns the offset committed by `consumer` for the sequence or topic `locator`.
+    pub async fn consumer_offset_get(
+        &mut self,
+        consumer: &str,
+        locator: &str,
+    ) -> Result<responses::ConsumerOffset> {
+        let body = serde_json::json!({ "consumer": consumer, "locator": locator });
+        self.action_as("consumer_offset_get", body).await
+    }
+
+    /// Deletes the offset committed by `consumer` for the sequence or topic `locator`.
+    pub async fn consumer_offset_delete(&mut self, consumer: &str, locator: &str) -> Result<()> {
+        let body = serde_json::json!({ "consumer": consumer, "locator": locator });
+        self.action("consumer_offset_delete", body).await?;
+        Ok(())
+    }
+
+    // ########
+    // Topic
+    // ########
+
+    /// Creates a topic in the session `session_uuid`, returning the uuid of the topic.
+    pub async fn topic_create(
+        &mut self,
+        session_uuid: &str,
+        request: requests::TopicCreate,
+    ) -> Result<String> {
+        let mut body = serde_json::to_value(request)
+            .map_err(|e| Error::unexpected_response("topic_create", e))?;
+        body["session_uuid"] = session_uuid.into();
+
+        let response: responses::ResourceUuid = self.action_as("topic_create", body).await?;
+        Ok(response.uuid)
+    }
+
+    /// Deletes the topic `locator`.
+    pub async fn topic_delete(&mut self, locator: &str) -> Result<()> {
+        let body = serde_json::json!({ "locator": locator });
+        self.action("topic_delete", body).await?;
+        Ok(())
+    }
+
+    /// Lists a page of at most `limit` topics of the sequence `locator`, starting from the
+    /// `cursor` returned along with the previous page.
+    pub async fn topic_list(
+        &mut self,
+        locator: &str,
+        limit: Option<usize>,
+        cursor: Option<&str>,
+    ) -> Result<responses::TopicList> {
+        let body = serde_json::json!({ "locator": locator, "limit": limit, "cursor": cursor });
+        self.action_as("topic_list", body).await
+    }
+
+    /// Copies the topic `from` and its data into the sequence `to_sequence`, within a new
+    /// session.
+    pub async fn topic_copy(
+        &mut self,
+        from: &str,
+        to_sequence: &str,
+    ) -> Result<responses::TopicCopy> {
+        let body = serde_json::json!({ "from": from, "to_sequence": to_sequence });
+        self.action_as("topic_copy", body).await
+    }
+
+    /// Returns the topics the data of the topic `locator` was copied from.
+    pub async fn topic_lineage(&mut self, locator: &str) -> Result<responses::TopicLineage> {
+        let body = serde_json::json!({ "locator": locator });
+        self.action_as("topic_lineage", body).await
+    }
+
+    /// Returns the segments of the media topic `locator` overlapping `range`.
+    pub async fn topic_media_index(
+        &mut self,
+        locator: &str,
+        range: TimestampRange,
+    ) -> Result<responses::MediaIndex> {
+        let body = serde_json::json!({
+            "locator": locator,
+            "timestamp_ns_start": range.start_ns,
+            "timestamp_ns_end": range.end_ns,
+        });
+        self.action_as("topic_media_index", body).await
+    }
+
+    /// Exports the data of the topic `locator` in `range` as a Parquet file written in the
+    /// store under `prefix`, with row groups of at most `row_group_size` rows if set.
+    pub async fn topic_export_to_store(
+        &mut self,
+        locator: &str,
+        range: TimestampRange,
+        row_group_size: Option<usize>,
+        prefix: &str,
+    ) -> Result<responses::TopicExportWritten> {
+        let body = serde_json::json!({
+            "locator": locator,
+            "timestamp_ns_start": range.start_ns,
+            "timestamp_ns_end": range.end_ns,
+            "row_group_size": row_group_size,
+            "prefix": prefix,
+        });
+        self.action_as("topic_export", body).await
+    }
+
+    /// Returns the schema registered for the topic `locator`.
+    pub async fn topic_schema_get(&mut self, locator: &str) -> Result<responses::TopicSchema> {
+        let body = serde_json::json!({ "locator": locator });
+        self.action_as("topic_schema_get", body).await
+    }
+
+    /// Registers `schema` as the new schema of the topic `locator`, returning its version.
+    pub async fn topic_schema_evolve(&mut self, locator: &str, schema: &Schema) -> Result<u32> {
+        let body = serde_json::json!({
+            "locator": locator,
+            "arrow_schema": requests::encode_schema(schema)?,
+        });
+        let response: responses::TopicSchemaVersion =
+            self.action_as("topic_schema_evolve", body).await?;
+        Ok(response.version)
+    }
+
+    /// Updates the description of the `columns` of the topic `locator`, indexed by name.
+    pub async fn topic_columns_update(
+        &mut self,
+        locator: &str,
+        columns: BTreeMap<String, requests::ColumnDescription>,
+    ) -> Result<()> {
+        let body = serde_json::json!({ "locator": locator, "columns": columns });
+        self.action("topic_columns_update", body).await?;
+        Ok(())
+    }
+
+    /// Returns the statistics of the `columns` of the topic `locator`, of all the columns
+    /// if empty.
+    pub async fn topic_column_stats(
+        &mut self,
+        locator: &str,
+        columns: &[&str],
+    ) -> Result<responses::TopicColumnStats> {
+        let body = serde_json::json!({ "locator": locator, "columns": columns });
+        self.action_as("topic_column_stats", body).await
+    }
+
+    /// Redacts the values of some columns of a topic, returning the recorded redaction.
+    pub async fn topic_redact(
+        &mut self,
+        request: requests::TopicRedact,
+    ) -> Result<responses::Redaction> {
+        let body = serde_json::to_value(request)
+            .map_err(|e| Error::unexpected_response("topic_redact", e))?;
+        self.action_as("topic_redact", body).await
+    }
+
+    /// Lists the redactions of the topic `locator`.
+    pub async fn topic_redaction_list(
+        &mut self,
+        locator: &str,
+    ) -> Result<responses::RedactionList> {
+        let body = serde_json::json!({ "locator": locator });
+        self.action_as("topic_redaction_list", body).await
+    }
+
+    /// Returns the watermark of the topic `locator`.
+    pub async fn topic_watermark(&mut self, locator: &str) -> Result<responses::TopicWatermark> {
+        let body = serde_json::json!({ "locator": locator });
+        self.action_as("topic_watermark", body).await
+    }
+
+    // ########
+    // Notifications
+    // ########
+
+    /// Creates a notification of the sequence `request.locator`.
+    pub async fn sequence_notification_create(
+        &mut self,
+        request: requests::NotificationCreate,
+    ) -> Result<()> {
+        self.notification_create("sequence_notification_create", request)
+            .await
+    }
+
+    /// Lists a page of at most `limit` notifications of the sequence `locator` selected by
+    /// `filter`, starting from the `cursor` returned along with the previous page.
+    pub async fn sequence_notification_list(
+        &mut self,
+        locator: &str,
+        filter: requests::NotificationFilter,
+        limit: Option<usize>,
+        cursor: Option<&str>,
+    ) -> Result<responses::NotificationList> {
+        self.notification_list("sequence_notification_list", locator, filter, limit, cursor)
+            .await
+    }
+
+    /// Deletes the notifications of the sequence `locator`.
+    pub async fn sequence_notification_purge(&mut self, locator: &str) -> Result<()> {
+        let body = serde_json::json!({ "locator": locator });
+        self.action("sequence_notification_purge", body).await?;
+        Ok(())
+    }
+
+    /// Creates a notification of the topic `request.locator`.
+    pub async fn topic_notification_create(
+        &mut self,
+        request: requests::NotificationCreate,
+    ) -> Result<()> {
+        self.notification_create("topic_notification_create", request)
+            .await
+    }
+
+    /// Lists a page of at most `limit` notifications of the topic `locator` selected by
+    /// `filter`, starting from the `cursor` returned along with the previous page.
+    pub async fn topic_notification_list(
+        &mut self,
+        locator: &str,
+        filter: requests::NotificationFilter,
+        limit: Option<usize>,
+        cursor: Option<&str>,
+    ) -> Result<responses::NotificationList> {
+        self.notification_list("topic_notification_list", locator, filter, limit, cursor)
+            .await
+    }
+
+    /// Deletes the notifications of the topic `locator`.
+    pub async fn topic_notification_purge(&mut self, locator: &str) -> Result<()> {
+        let body = serde_json::json!({ "locator": locator });
+        self.action("topic_notification_purge", body).await?;
+        Ok(())
+    }
+
+    async fn notification_create(
+        &mut self,
+        action: &str,
+        request: requests::NotificationCreate,
+    ) -> Result<()> {
+        let body =
+            serde_json::to_value(request).map_err(|e| Error::unexpected_response(action, e))?;
+        self.action(action, body).await?;
+        Ok(())
+    }
+
+    async fn notification_list(
+        &mut self,
+        action: &str,
+        locator: &str,
+        filter: requests::NotificationFilter,
+        limit: Option<usize>,
+        cursor: Option<&str>,
+    ) -> Result<responses::NotificationList> {
+        let mut body =
+            serde_json::to_value(filter).map_err(|e| Error::unexpected_response(action, e))?;
+        body["locator"] = locator.into();
+        body["limit"] = limit.into();
+        body["cursor"] = cursor.into();
+
+        self.action_as(action, body).await
+    }
+
+    /// Streams the notifications of the sequence `locator` created from now on, only the ones
+    /// at least as severe as `min_severity` (`info`, `warning` or `error`) if set.
+    ///
+    /// Each record batch holds the `name`, `notification_type`, `severity`, `code`,
+    /// `params`, `msg` and `created_datetime` columns, as strings. The stream ends when the
+    /// server shuts down.
+    pub async fn sequence_notification_stream(
+        &mut self,
+        locator: &str,
+        min_severity: Option<&str>,
+    ) -> Result<FlightRecordBatchStream> {
+        const ACTION: &str = "sequence_notification_stream";
+
+        let body = serde_json::json!({ "locator": locator, "min_severity": min_severity });
+        let response: responses::NotificationStream = self.action_as(ACTION, body).await?;
+        let ticket = BASE64
+            .decode(response.ticket)
+            .map_err(|e| Error::unexpected_response(ACTION, e))?;
+
+        self.stream_ticket(Ticket::new(ticket)).await
+    }
+
+    // ########
+    // Query
+    // ########
+
+    /// Searches the sequences and topics matching the query `filter`, returning at most
+    /// `max_rows` items if set, the next ones can be fetched with [`Client::query_fetch`].
+    pub async fn query(
+        &mut self,
+        filter: serde_json::Value,
+        max_rows: Option<usize>,
+    ) -> Result<responses::Query> {
+        let mut body = filter;
+        if let Some(max_rows) = max_rows {
+            body["max_rows"] = max_rows.into();
+        }
+        self.action_as("query", body).await
+    }
+
+    /// Fetches at most `max_rows` further items of the query with `handle`.
+    pub async fn query_fetch(&mut self, handle: &str, max_rows: usize) -> Result<responses::Query> {
+        let body = serde_json::json!({ "handle": handle, "max_rows": max_rows });
+        self.action_as("query_fetch", body).await
+    }
+
+    /// Runs the read-only SQL `statement` over the topics in `tables`, pairs of table name
+    /// and topic locator, streaming its result.
+    pub async fn sql_query(
+        &mut self,
+        statement: &str,
+        tables: &[(&str, &str)],
+    ) -> Result<FlightRecordBatchStream> {
+        const ACTION: &str = "sql_query";
+
+        let tables: BTreeMap<_, _> = tables.iter().copied().collect();
+        let body = serde_json::json!({ "statement": statement, "tables": tables });
+        let response: responses::SqlQuery = self.action_as(ACTION, body).await?;
+        let ticket = BASE64
+            .decode(response.ticket)
+            .map_err(|e| Error::unexpected_response(ACTION, e))?;
+
+        self.stream_ticket(Ticket::new(ticket)).await
+    }
+
+    /// Creates a query evaluated following a cron schedule.
+    pub async fn scheduled_query_create(
+        &mut self,
+        request: requests::ScheduledQueryCreate,
+    ) -> Result<responses::ScheduledQuery> {
+        let body = serde_json::to_value(request)
+            .map_err(|e| Error::unexpected_response("scheduled_query_create", e))?;
+        self.action_as("scheduled_query_create", body).await
+    }
+
+    /// Lists the scheduled queries.
+    pub async fn scheduled_query_list(&mut self) -> Result<responses::ScheduledQueryList> {
+        self.action_as("scheduled_query_list", serde_json::json!({}))
+            .await
+    }
+
+    /// Evaluates the scheduled query `name` now.
+    pub async fn scheduled_query_run(
+        &mut self,
+        name: &str,
+    ) -> Result<responses::ScheduledQueryRun> {
+        let body = serde_json::json!({ "name": name });
+        self.action_as("scheduled_query_run", body).await
+    }
+
+    /// Deletes the scheduled query `name`.
+    pub async fn scheduled_query_delete(&mut self, name: &str) -> Result<()> {
+        let body = serde_json::json!({ "name": name });
+        self.action("scheduled_query_delete", body).await?;
+        Ok(())
+    }
+
+    /// Saves the query `query` as the view `name` of the sequence `locator`.
+    pub async fn query_view_create(
+        &mut self,
+        locator: &str,
+        name: &str,
+        query: serde_json::Value,
+    ) -> Result<responses::QueryView> {
+        let body = serde_json::json!({ "locator": locator, "name": name, "query": query });
+        self.action_as("query_view_create", body).await
+    }
+
+    /// Lists the views of the sequence `locator`.
+    pub async fn query_view_list(&mut self, locator: &str) -> Result<responses::QueryViewList> {
+        let body = serde_json::json!({ "locator": locator });
+        self.action_as("query_view_list", body).await
+    }
+
+    /// Deletes the view `name` of the sequence `locator`.
+    pub async fn query_view_delete(&mut self, locator: &str, name: &str) -> Result<()> {
+        let body = serde_json::json!({ "locator": locator, "name": name });
+        self.action("query_view_delete", body).await?;
+        Ok(())
+    }
+
+    /// Registers the files at `location` in `format` (e.g. `parquet`) as the table `name`,
+    /// readable by [`Client::sql_query`].
+    pub async fn external_table_register(
+        &mut self,
+        name: &str,
+        location: &str,
+        format: &str,
+    ) -> Result<responses::ExternalTable> {
+        let body = serde_json::json!({ "name": name, "location": location, "format": format });
+        self.action_as("external_table_register", body).await
+    }
+
+    /// Lists the external tables.
+    pub async fn external_table_list(&mut self) -> Result<responses::ExternalTableList> {
+        self.action_as("external_table_list", serde_json::json!({}))
+            .await
+    }
+
+    /// Unregisters the external table `name`.
+    pub async fn external_table_unregister(&mut self, name: &str) -> Result<()> {
+        let body = serde_json::json!({ "name": name });
+        self.action("external_table_unregister", body).await?;
+        Ok(())
+    }
+
+    // ########
+    // Api Key
+    // ########
+
+    /// Creates an API key, returning its token.
+    pub async fn api_key_create(&mut self, request: requests::ApiKeyCreate) -> Result<String> {
+        let body = serde_json::to_value(request)
+            .map_err(|e| Error::unexpected_response("api_key_create", e))?;
+        let response: responses::ApiKeyToken = self.action_as("api_key_create", body).await?;
+        Ok(response.api_key_token)
+    }
+
+    /// Returns the status of the API key with fingerprint `api_key_fingerprint`.
+    pub async fn api_key_status(
+        &mut self,
+        api_key_fingerprint: &str,
+    ) -> Result<responses::ApiKeyStatus> {
+        let body = serde_json::json!({ "api_key_fingerprint": api_key_fingerprint });
+        self.action_as("api_key_status", body).await
+    }
+
+    /// Revokes the API key with fingerprint `api_key_fingerprint`.
+    pub async fn api_key_revoke(&mut self, api_key_fingerprint: &str) -> Result<()> {
+        let body = serde_json::json!({ "api_key_fingerprint": api_key_fingerprint });
+        self.action("api_key_revoke", body).await?;
+        Ok(())
+    }
+
+    // ########
+    // Server
+    // ########
+
+    /// Returns the version of the server.
+    pub async fn version(&mut self) -> Result<responses::ServerVersion> {
+        self.action_as("version", serde_json::json!({})).await
+    }
+
+    /// Returns the metrics of the store backend.
+    pub async fn store_metrics(&mut self) -> Result<responses::StoreMetrics> {
+        self.action_as("store_metrics", serde_json::json!({})).await
+    }
+
+    /// Returns at most `limit` data files not read for `cold_after` seconds and the savings
+    /// of moving them to the archive storage, settings not set follow the server
+    /// configuration.
+    pub async fn store_tiering_report(
+        &mut self,
+        cold_after: Option<u64>,
+        limit: Option<usize>,
+    ) -> Result<responses::StoreTieringReport> {
+        let body = serde_json::json!({ "cold_after": cold_after, "limit": limit });
+        self.action_as("store_tiering_report", body).await
+    }
+
+    /// Moves at most `limit` data files not read for `cold_after` seconds to the archive
+    /// storage, settings not set follow the server configuration.
+    pub async fn store_tiering_demote(
+        &mut self,
+        cold_after: Option<u64>,
+        limit: Option<usize>,
+    ) -> Result<responses::StoreTieringDemote> {
+        let body = serde_json::json!({ "cold_after": cold_after, "limit": limit });
+        self.action_as("store_tiering_demote", body).await
+    }
+
+    /// Estimates the monthly costs of the data of the sequences whose locator starts with
+    /// `scope`, of all the sequences if empty.
+    pub async fn cost_estimate(&mut self, scope: &str) -> Result<responses::CostEstimate> {
+        let body = serde_json::json!({ "scope": scope });
+        self.action_as("cost_estimate", body).await
+    }
+
+    /// Returns the ingestion metrics of the server.
+    pub async fn ingest_metrics(&mut self) -> Result<responses::IngestMetrics> {
+        self.action_as("ingest_metrics", serde_json::json!({}))
+            .await
+    }
+
+    /// Returns the statistics of the requests served by the server.
+    pub async fn server_stats(&mut self) -> Result<responses::ServerStats> {
+        self.action_as("server_stats", serde_json::json!({})).await
+    }
+
+    /// Runs the self test of the server, checking its dependencies.
+    pub async fn self_test(&mut self) -> Result<responses::SelfTest> {
+        self.action_as("self_test", serde_json::json!({})).await
+    }
+
+    /// Returns the failed authentications and permission checks seen by the server.
+    pub async fn security_report(&mut self) -> Result<responses::SecurityReport> {
+        self.action_as("security_report", serde_json::json!({}))
+            .await
+    }
+
+    /// Starts rebuilding the `scope` state derived from the topic data (`column_stats`,
+    /// `chunk_stats`, `data_info` or `all`), processing batches of `batch_size` topics if set.
+    pub async fn reindex(
+        &mut self,
+        scope: &str,
+        batch_size: Option<usize>,
+    ) -> Result<responses::ReindexStatus> {
+        let body = serde_json::json!({ "scope": scope, "batch_size": batch_size });
+        self.action_as("reindex", body).await
+    }
+
+    /// Returns the progress of the reindex jobs.
+    pub async fn reindex_status(&mut self) -> Result<responses::ReindexStatus> {
+        self.action_as("reindex_status", serde_json::json!({}))
+            .await
+    }
+
+    /// Records the requests received by the server for `duration_secs` seconds.
+    pub async fn replay_capture_start(
+        &mut self,
+        duration_secs: u64,
+    ) -> Result<responses::ReplayCapture> {
+        let body = serde_json::json!({ "duration_secs": duration_secs });
+        self.action_as("replay_capture_start", body).await
+    }
+
+    /// Returns the requests recorded by the replay capture.
+    pub async fn replay_capture_dump(&mut self) -> Result<responses::ReplayCapture> {
+        self.action_as("replay_capture_dump", serde_json::json!({}))
+            .await
+    }
+
+    // ########
+    // Data
+    // ########
+
+    /// Uploads `batches` into the topic `locator` created with uuid `topic_uuid`.
+    pub async fn upload(
+        &mut self,
+        locator: &str,
+        topic_uuid: &str,
+        batches: Vec<RecordBatch>,
+    ) -> Result<()> {
+        let cmd = serde_json::json!({ "resource_locator": locator, "topic_uuid": topic_uuid });
+
+        let input = futures::stream::iter(batches.into_iter().map(Ok));
+        let flight_data: Vec<_> = FlightDataEncoderBuilder::new()
+            .with_flight_descriptor(Some(FlightDescriptor::new_cmd(cmd.to_string())))
+            .build(input)
+            .try_collect()
+            .await?;
+
+        self.put(flight_data).await
+    }
+
+    /// Uploads the Parquet `files` into the topic `locator` created with uuid `topic_uuid`,
+    /// each file is sent in a message and its row groups are stored as batches.
+    pub async fn upload_parquet(
+        &mut self,
+        locator: &str,
+        topic_uuid: &str,
+        files: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        let cmd = serde_json::json!({
+            "resource_locator": locator,
+            "topic_uuid": topic_uuid,
+            "payload_format": "parquet",
+        });
+
+        let mut descriptor = Some(FlightDescriptor::new_cmd(cmd.to_string()));
+        let flight_data: Vec<_> = files
+            .into_iter()
+            .map(|file| {
+                let data = FlightData::new().with_data_body(file);
+                match descriptor.take() {
+                    Some(descriptor) => data.with_descriptor(descriptor),
+                    None => data,
+                }
+            })
+            .collect();
+
+        self.put(flight_data).await
+    }
+
+    /// Downloads the data of the topic `locator` in `range`.
+    pub async fn download(
+        &mut self,
+        locator: &str,
+        range: TimestampRange,
+    ) -> Result<Vec<RecordBatch>> {
+        let mut cmd = serde_json::json!({ "resource_locator": locator });
+        if let Some(start) = range.start_ns {
+            cmd["timestamp_ns_start"] = start.into();
+        }
+        if let Some(end) = range.end_ns {
+            cmd["timestamp_ns_end"] = end.into();
+        }
+
+        let descriptor = FlightDescriptor::new_cmd(cmd.to_string());
+        let info = self
+            .with_retry(|mut client| {
+                let descriptor = descriptor.clone();
+                async move { Ok(client.get_flight_info(descriptor).await?.into_inner()) }
+            })
+            .await?;
+
+        let mut batches = Vec::new();
+        for endpoint in info.endpoint {
+            let Some(ticket) = endpoint.ticket else {
+                continue;
+            };
+            batches.extend(self.download_ticket(ticket).await?);
+        }
+
+        Ok(batches)
+    }
+
+    /// Exports the data of the topic `locator` in `range` as a Parquet file, with row groups
+    /// of at most `row_group_size` rows if set, returning the bytes of the file.
+    pub async fn topic_export(
+        &mut self,
+        locator: &str,
+        range: TimestampRange,
+        row_group_size: Option<usize>,
+    ) -> Result<Vec<u8>> {
+        const ACTION: &str = "topic_export";
+
+        let body = serde_json::json!({
+            "locator": locator,
+            "timestamp_ns_start": range.start_ns,
+            "timestamp_ns_end": range.end_ns,
+            "row_group_size": row_group_size,
+        });
+        let response: responses::TopicExport = self.action_as(ACTION, body).await?;
+        let ticket = BASE64
+            .decode(response.ticket)
+            .map_err(|e| Error::unexpected_response(ACTION, e))?;
+
+        // The file is the concatenation of the values of the single binary column
+        let mut file = Vec::new();
+        for batch in self.download_ticket(Ticket::new(ticket)).await? {
+            let pieces = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<BinaryArray>()
+                .ok_or_else(|| Error::unexpected_response(ACTION, "expected a binary column"))?;
+            for piece in pieces.iter().flatten() {
+                file.extend_from_slice(piece);
+            }
+        }
+
+        Ok(file)
+    }
+
+    /// Streams the topics of the sequence `locator` time-merged for replay, only the topics
+    /// named in `topics` (relative to the sequence) if set, limited to `range`.
+    ///
+    /// Each record batch holds the `timestamp_ns` and `channel_id` columns, followed by a
+    /// struct column for each channel named after its topic and set only in the rows of
+    /// the channel. The channels are returned along with the stream.
+    pub async fn sequence_replay(
+        &mut self,
+        locator: &str,
+        topics: Option<&[&str]>,
+        range: TimestampRange,
+    ) -> Result<(Vec<responses::ReplayChannel>, FlightRecordBatchStream)> {
+        const ACTION: &str = "sequence_replay";
+
+        let body = serde_json::json!({
+            "locator": locator,
+            "topics": topics,
+            "timestamp_ns_start": range.start_ns,
+            "timestamp_ns_end": range.end_ns,
+        });
+        let response: responses::SequenceReplay = self.action_as(ACTION, body).await?;
+        let ticket = BASE64
+            .decode(response.ticket)
+            .map_err(|e| Error::unexpected_response(ACTION, e))?;
+
+        let stream = self.stream_ticket(Ticket::new(ticket)).await?;
+
+        Ok((response.channels, stream))
+    }
+
+    /// Downloads the data served for `ticket`, e.g. a ticket returned by an action.
+    pub async fn download_ticket(&mut self, ticket: Ticket) -> Result<Vec<RecordBatch>> {
+        let batches = self.stream_ticket(ticket).await?.try_collect().await?;
+        Ok(batches)
+    }
+
+    /// Streams the data served for `ticket`, e.g. a ticket returned by an action.
+    pub async fn stream_ticket(&mut self, ticket: Ticket) -> Result<FlightRecordBatchStream> {
+        let stream = self
+            .with_retry(|mut client| {
+                let ticket = ticket.clone();
+                async move { Ok(client.do_get(ticket).await?.into_inner()) }
+            })
+            .await?;
+
+        Ok(FlightRecordBatchStream::new_from_flight_data(
+            stream.map_err(Into::into),
+        ))
+    }
+
+    /// Sends `flight_data` with DoPut, consuming the results.
+    async fn put(&mut self, flight_data: Vec<FlightData>) -> Result<()> {
+        let mut results = self
+            .with_retry(|mut client| {
+                let flight_data = futures::stream::iter(flight_data.clone());
+                async move { Ok(client.do_put(flight_data).await?.into_inner()) }
+            })
+            .await?;
+        while results.message().await?.is_some() {}
+
+        Ok(())
+    }
+}
+
+impl std::ops::Deref for Client {
+    type Target = FlightServiceClient<ClientChannel>;
+    fn deref(&self) -> &Self::Target {
+        &self.client
+    }
+}
+
+impl std::ops::DerefMut for Client {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.client
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay() {
+        let retry = RetryPolicy {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(300),
+        };
+        let unavailable = Error::from(tonic::Status::unavailable("database unavailable"));
+
+        assert_eq!(
+            retry.delay(1, &unavailable),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            retry.delay(2, &unavailable),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(
+            retry.delay(3, &unavailable),
+            Some(Duration::from_millis(300))
+        );
+        assert_eq!(retry.delay(4, &unavailable), None);
+
+        let mut status = tonic::Status::unavailable("database unavailable");
+        status
+            .metadata_mut()
+            .insert("retry-after-ms", "50".parse().unwrap());
+        assert_eq!(
+            retry.delay(1, &Error::from(status)),
+            Some(Duration::from_millis(50))
+        );
+
+        let not_found = Error::from(tonic::Status::not_found("missing"));
+        assert_eq!(retry.delay(1, &not_found), None);
+        assert_eq!(RetryPolicy::none().delay(1, &unavailable), None);
+    }
+}
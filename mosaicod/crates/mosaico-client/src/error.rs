@@ -0,0 +1,153 @@
+use std::time::Duration;
+use tonic::Code;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Metadata key of the delay (in milliseconds) after which the requests failed with an
+/// `UNAVAILABLE` status should be retried.
+const RETRY_AFTER_MS: &str = "retry-after-ms";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("invalid endpoint `{0}`")]
+    InvalidEndpoint(String),
+    #[error("unable to connect: {0}")]
+    Connection(#[from] tonic::transport::Error),
+    #[error("server error ({:?}): {}", .0.code(), .0.message())]
+    Status(#[from] tonic::Status),
+    #[error("flight error: {0}")]
+    Flight(#[from] arrow_flight::error::FlightError),
+    #[error("arrow error: {0}")]
+    Arrow(#[from] arrow::error::ArrowError),
+    #[error("unexpected response to `{action}`: {reason}")]
+    UnexpectedResponse { action: String, reason: String },
+}
+
+/// Category of the errors returned by the server, derived from their gRPC status code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The resource does not exist.
+    NotFound,
+    /// A resource with the same locator already exists.
+    AlreadyExists,
+    /// The request is malformed or refers to an invalid resource.
+    InvalidArgument,
+    /// The credentials do not grant the permissions required by the request.
+    PermissionDenied,
+    /// The request carries no valid credentials.
+    Unauthenticated,
+    /// The resource is not in the state required by the request (e.g. a finalized session).
+    FailedPrecondition,
+    /// The request conflicts with a concurrent change, it can be retried after a refresh.
+    Conflict,
+    /// A limit of the server has been exceeded.
+    ResourceExhausted,
+    /// The request did not complete before its deadline.
+    DeadlineExceeded,
+    /// The server or one of its dependencies is temporarily unavailable.
+    Unavailable,
+    /// The server failed processing the request.
+    Internal,
+    /// Errors of the client (connection, decoding) and unmapped status codes.
+    Other,
+}
+
+impl From<Code> for ErrorKind {
+    fn from(code: Code) -> Self {
+        match code {
+            Code::NotFound => Self::NotFound,
+            Code::AlreadyExists => Self::AlreadyExists,
+            Code::InvalidArgument => Self::InvalidArgument,
+            Code::PermissionDenied => Self::PermissionDenied,
+            Code::Unauthenticated => Self::Unauthenticated,
+            Code::FailedPrecondition => Self::FailedPrecondition,
+            Code::Aborted => Self::Conflict,
+            Code::ResourceExhausted => Self::ResourceExhausted,
+            Code::DeadlineExceeded => Self::DeadlineExceeded,
+            Code::Unavailable => Self::Unavailable,
+            Code::Internal => Self::Internal,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl Error {
+    /// Category of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match self.status() {
+            Some(status) => status.code().into(),
+            None => ErrorKind::Other,
+        }
+    }
+
+    /// The status returned by the server, if the error comes from the server.
+    pub fn status(&self) -> Option<&tonic::Status> {
+        match self {
+            Self::Status(status) => Some(status),
+            Self::Flight(arrow_flight::error::FlightError::Tonic(status)) => Some(status.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// Delay suggested by the server before retrying a request failed because the server
+    /// was unavailable.
+    pub fn retry_after(&self) -> Option<Duration> {
+        let status = self.status()?;
+        if status.code() != Code::Unavailable {
+            return None;
+        }
+
+        status
+            .metadata()
+            .get(RETRY_AFTER_MS)?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()
+            .map(Duration::from_millis)
+    }
+
+    pub(crate) fn unexpected_response(action: &str, reason: impl ToString) -> Self {
+        Self::UnexpectedResponse {
+            action: action.to_owned(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl From<Error> for tonic::Status {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Status(status) => status,
+            Error::Flight(arrow_flight::error::FlightError::Tonic(status)) => *status,
+            e => tonic::Status::internal(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_kind() {
+        let e: Error = tonic::Status::aborted("version conflict").into();
+        assert_eq!(e.kind(), ErrorKind::Conflict);
+
+        let e = Error::unexpected_response("topic_create", "missing uuid");
+        assert_eq!(e.kind(), ErrorKind::Other);
+        assert_eq!(tonic::Status::from(e).code(), Code::Internal);
+    }
+
+    #[test]
+    fn retry_after() {
+        let mut status = tonic::Status::unavailable("database unavailable");
+        status
+            .metadata_mut()
+            .insert(RETRY_AFTER_MS, "250".parse().unwrap());
+
+        let e = Error::from(status);
+        assert_eq!(e.retry_after(), Some(Duration::from_millis(250)));
+        assert_eq!(Error::from(tonic::Status::internal("")).retry_after(), None);
+    }
+}
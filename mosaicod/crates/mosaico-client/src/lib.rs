@@ -0,0 +1,32 @@
+//! Client of the mosaicod Flight API.
+//!
+//! [`ClientBuilder`] configures the connection (TLS, credentials, [`RetryPolicy`]) and
+//! returns a [`Client`] exposing a typed method for each action of the platform, the upload
+//! of record batches into a topic and their download. The requests failed because the server
+//! was unavailable are retried, after the delay suggested by the server if any. Custom
+//! actions can be performed with [`Client::action`], and the raw Flight client is reachable
+//! through [`std::ops::Deref`].
+//!
+//! ```no_run
+//! # async fn run() -> mosaico_client::Result<()> {
+//! let mut client = mosaico_client::ClientBuilder::new("127.0.0.1", 6726)
+//!     .with_api_key("msco_...")
+//!     .connect()
+//!     .await?;
+//!
+//! client.sequence_create("run_1", serde_json::json!({})).await?;
+//! let session = client.session_create("run_1").await?;
+//! # Ok(())
+//! # }
+//! ```
+mod client;
+pub use client::*;
+
+mod error;
+pub use error::*;
+
+mod requests;
+pub use requests::*;
+
+mod responses;
+pub use responses::*;
@@ -0,0 +1,331 @@
+//! Requests of the actions with a typed method in [`crate::Client`] taking several options.
+use crate::Result;
+use arrow::datatypes::Schema;
+use arrow::ipc::writer::{DictionaryTracker, IpcDataGenerator, IpcWriteOptions, write_message};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Request used to create a topic in a session.
+///
+/// ```
+/// let request = mosaico_client::TopicCreate::new("run_1/imu", "imu")
+///     .with_sort_key("timestamp_ns")
+///     .with_user_metadata(serde_json::json!({ "rate_hz": 200 }));
+/// ```
+#[derive(Serialize, Debug, Clone)]
+pub struct TopicCreate {
+    pub locator: String,
+    pub ontology_tag: String,
+    /// Serialization format of the topic data, `default` if not changed
+    pub serialization_format: String,
+    /// Column by which the uploaded data must be sorted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_key: Option<String>,
+    /// Policy used to remove duplicated rows when reading the topic data
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dedup_policy: Option<String>,
+    /// Column identifying the rows of the topic, making it an upsert topic
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_key: Option<String>,
+    /// Version the upserted rows are based on, the upload is rejected if the existing
+    /// upsert topic changed in the meantime
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_version: Option<u64>,
+    /// Schema the uploaded data must match, encoded as a base64 Arrow IPC message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arrow_schema: Option<String>,
+    pub user_metadata: serde_json::Value,
+}
+
+impl TopicCreate {
+    pub fn new(locator: &str, ontology_tag: &str) -> Self {
+        Self {
+            locator: locator.to_owned(),
+            ontology_tag: ontology_tag.to_owned(),
+            serialization_format: "default".to_owned(),
+            sort_key: None,
+            dedup_policy: None,
+            primary_key: None,
+            base_version: None,
+            arrow_schema: None,
+            user_metadata: serde_json::json!({}),
+        }
+    }
+
+    pub fn with_serialization_format(mut self, format: &str) -> Self {
+        self.serialization_format = format.to_owned();
+        self
+    }
+
+    pub fn with_sort_key(mut self, column: &str) -> Self {
+        self.sort_key = Some(column.to_owned());
+        self
+    }
+
+    pub fn with_dedup_policy(mut self, policy: &str) -> Self {
+        self.dedup_policy = Some(policy.to_owned());
+        self
+    }
+
+    pub fn with_primary_key(mut self, column: &str) -> Self {
+        self.primary_key = Some(column.to_owned());
+        self
+    }
+
+    pub fn with_base_version(mut self, version: u64) -> Self {
+        self.base_version = Some(version);
+        self
+    }
+
+    /// Registers `schema` as the schema of the topic, the uploaded data must match it.
+    pub fn with_arrow_schema(mut self, schema: &Schema) -> Result<Self> {
+        self.arrow_schema = Some(encode_schema(schema)?);
+        Ok(self)
+    }
+
+    pub fn with_user_metadata(mut self, user_metadata: serde_json::Value) -> Self {
+        self.user_metadata = user_metadata;
+        self
+    }
+}
+
+/// Encodes `schema` as a base64 Arrow IPC message, as expected by the actions.
+pub(crate) fn encode_schema(schema: &Schema) -> Result<String> {
+    let options = IpcWriteOptions::default();
+    let mut tracker = DictionaryTracker::new(false);
+    let encoded = IpcDataGenerator::default().schema_to_bytes_with_dictionary_tracker(
+        schema,
+        &mut tracker,
+        &options,
+    );
+
+    let mut buffer = Vec::new();
+    write_message(&mut buffer, encoded, &options)?;
+    Ok(BASE64.encode(buffer))
+}
+
+/// Time range of the data downloaded from a topic, bounds in nanoseconds.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimestampRange {
+    pub start_ns: Option<i64>,
+    pub end_ns: Option<i64>,
+}
+
+/// Compaction policy of a sequence, settings not set follow the server configuration.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CompactionPolicy {
+    /// Size (in bytes) of the data files written by the compaction
+    pub target_object_size: Option<u64>,
+    /// Interval (in seconds) between consecutive compactions
+    pub interval: Option<u64>,
+    /// Minimum number of data files of a topic before it is compacted
+    pub min_chunks: Option<u64>,
+    /// Time (in seconds) since the last upload after which a topic is compacted
+    pub cold_after: Option<u64>,
+}
+
+/// Request used to create a notification of a sequence or topic.
+///
+/// ```
+/// let request = mosaico_client::NotificationCreate::new("run_1", "error")
+///     .with_severity("warning")
+///     .with_msg("battery low");
+/// ```
+#[derive(Serialize, Debug, Clone)]
+pub struct NotificationCreate {
+    pub locator: String,
+    pub notification_type: String,
+    /// Severity of the notification (`info`, `warning` or `error`), if `None` it depends
+    /// on the notification type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    /// Machine readable code of the notification, if `None` the notification carries only
+    /// the free text `msg`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// Values describing the event, referenced by the localized texts of the code
+    pub params: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub msg: Option<String>,
+}
+
+impl NotificationCreate {
+    pub fn new(locator: &str, notification_type: &str) -> Self {
+        Self {
+            locator: locator.to_owned(),
+            notification_type: notification_type.to_owned(),
+            severity: None,
+            code: None,
+            params: BTreeMap::new(),
+            msg: None,
+        }
+    }
+
+    pub fn with_severity(mut self, severity: &str) -> Self {
+        self.severity = Some(severity.to_owned());
+        self
+    }
+
+    pub fn with_code(mut self, code: &str, params: BTreeMap<String, String>) -> Self {
+        self.code = Some(code.to_owned());
+        self.params = params;
+        self
+    }
+
+    pub fn with_msg(mut self, msg: &str) -> Self {
+        self.msg = Some(msg.to_owned());
+        self
+    }
+}
+
+/// Criteria selecting the notifications listed, all the notifications by default.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct NotificationFilter {
+    /// If set, only the notifications at least as severe are returned
+    pub min_severity: Option<String>,
+    /// If set, only the notifications created since the timestamp are returned
+    pub since_ns: Option<i64>,
+    /// If set, only the notifications created until the timestamp are returned
+    pub until_ns: Option<i64>,
+}
+
+/// Description of a column of a topic, fields not set are left unchanged.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ColumnDescription {
+    pub description: Option<String>,
+    /// Unit of measurement of the values (e.g. `m/s`)
+    pub unit: Option<String>,
+}
+
+/// Request used to redact the values of some columns of a topic.
+///
+/// ```
+/// let request = mosaico_client::TopicRedact::new("run_1/gps", &["latitude"], "hash")
+///     .with_reason("privacy request");
+/// ```
+#[derive(Serialize, Debug, Clone)]
+pub struct TopicRedact {
+    pub locator: String,
+    pub timestamp_ns_start: Option<i64>,
+    pub timestamp_ns_end: Option<i64>,
+    pub columns: Vec<String>,
+    /// Either `null` or `hash`
+    pub mode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl TopicRedact {
+    pub fn new(locator: &str, columns: &[&str], mode: &str) -> Self {
+        Self {
+            locator: locator.to_owned(),
+            timestamp_ns_start: None,
+            timestamp_ns_end: None,
+            columns: columns.iter().map(|c| (*c).to_owned()).collect(),
+            mode: mode.to_owned(),
+            reason: None,
+        }
+    }
+
+    /// Redacts only the rows in `range`.
+    pub fn with_range(mut self, range: TimestampRange) -> Self {
+        self.timestamp_ns_start = range.start_ns;
+        self.timestamp_ns_end = range.end_ns;
+        self
+    }
+
+    pub fn with_reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_owned());
+        self
+    }
+}
+
+/// Offset committed by a consumer: the cursor of the activity feed for a sequence, the
+/// watermark for a topic.
+#[derive(Debug, Clone)]
+pub enum ConsumerPosition {
+    Cursor(String),
+    Watermark(i64),
+}
+
+/// Request used to create a scheduled query.
+///
+/// ```
+/// let request = mosaico_client::ScheduledQueryCreate::new(
+///     "hot_motors",
+///     "0 */5 * * * *",
+///     serde_json::json!({ "topic": { "locator": { "$ct": "motor" } } }),
+/// )
+/// .with_condition("$gt", 0);
+/// ```
+#[derive(Serialize, Debug, Clone)]
+pub struct ScheduledQueryCreate {
+    pub name: String,
+    /// Cron expression
+    pub schedule: String,
+    /// Query filter evaluated at every run
+    pub query: serde_json::Value,
+    /// Condition on the number of matching topics, satisfied as soon as a topic matches if
+    /// `None`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<BTreeMap<String, u64>>,
+}
+
+impl ScheduledQueryCreate {
+    pub fn new(name: &str, schedule: &str, query: serde_json::Value) -> Self {
+        Self {
+            name: name.to_owned(),
+            schedule: schedule.to_owned(),
+            query,
+            condition: None,
+        }
+    }
+
+    /// Triggers the query when the number of matching topics satisfies `op` (e.g. `$gt`)
+    /// against `value`.
+    pub fn with_condition(mut self, op: &str, value: u64) -> Self {
+        self.condition = Some(BTreeMap::from([(op.to_owned(), value)]));
+        self
+    }
+}
+
+/// Request used to create an API key.
+///
+/// ```
+/// let request = mosaico_client::ApiKeyCreate::new("write", "ingestion robot r1")
+///     .with_default_sequence("run_1");
+/// ```
+#[derive(Serialize, Debug, Clone)]
+pub struct ApiKeyCreate {
+    /// Permissions granted to the key (e.g. `read`, `write`, `delete`, `manage`)
+    pub permissions: String,
+    pub description: String,
+    /// `None` if the key does not expire
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at_ns: Option<i64>,
+    /// Sequence searched by the queries of the key not filtering sequences
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_sequence: Option<String>,
+}
+
+impl ApiKeyCreate {
+    pub fn new(permissions: &str, description: &str) -> Self {
+        Self {
+            permissions: permissions.to_owned(),
+            description: description.to_owned(),
+            expires_at_ns: None,
+            default_sequence: None,
+        }
+    }
+
+    pub fn with_expires_at_ns(mut self, expires_at_ns: i64) -> Self {
+        self.expires_at_ns = Some(expires_at_ns);
+        self
+    }
+
+    pub fn with_default_sequence(mut self, locator: &str) -> Self {
+        self.default_sequence = Some(locator.to_owned());
+        self
+    }
+}
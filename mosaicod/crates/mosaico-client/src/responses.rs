@@ -0,0 +1,728 @@
+//! Responses of the actions with a typed method in [`crate::Client`].
+use crate::{Error, Result};
+use arrow::datatypes::SchemaRef;
+use arrow::ipc::convert::try_schema_from_ipc_buffer;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde::Deserialize;
+use std::{collections::BTreeMap, sync::Arc};
+
+/// Response of the actions creating a resource identified by a uuid (e.g. a topic).
+#[derive(Deserialize, Debug, Clone)]
+pub struct ResourceUuid {
+    pub uuid: String,
+}
+
+// ########
+// Sequence
+// ########
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SequenceList {
+    pub locators: Vec<String>,
+    /// Cursor used to fetch the next page, `None` once all the sequences are returned
+    pub cursor: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ImportedChannel {
+    /// Topic the channel publishes on
+    pub channel: String,
+    pub locator: String,
+    pub message_count: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SequenceImportMcap {
+    pub session: String,
+    pub topics: Vec<ImportedChannel>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SequenceExport {
+    /// Path of the archive, relative to the root of the store
+    pub path: String,
+    pub topics: usize,
+    pub size_bytes: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SequenceImport {
+    pub locator: String,
+    pub uuid: String,
+    pub session: String,
+    pub topics: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReplayChannel {
+    pub channel_id: u32,
+    pub locator: String,
+}
+
+/// Response of `sequence_replay`, carrying the ticket to stream the replay with DoGet.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SequenceReplay {
+    /// Ticket encoded in base64
+    pub ticket: String,
+    pub channels: Vec<ReplayChannel>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SequenceCompact {
+    /// Number of topics compacted
+    pub compacted_topics: usize,
+}
+
+/// Attestation of a session, with the outcome of the check against its manifest. Binary
+/// fields are hex encoded.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SessionAttestation {
+    pub version: u32,
+    pub session_uuid: String,
+    pub manifest_sha256: String,
+    pub algorithm: String,
+    pub public_key: String,
+    pub signature: String,
+    pub created_at: i64,
+    pub valid: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SequenceAttestation {
+    pub attestations: Vec<SessionAttestation>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ActivityNotification {
+    pub notification_type: String,
+    pub severity: String,
+    pub code: String,
+    pub params: BTreeMap<String, String>,
+    /// Rendered text of the notification, empty if not available
+    pub msg: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ActivityItem {
+    pub timestamp_ns: i64,
+    pub event: String,
+    /// Locator of the sequence or topic, or uuid of the session, the event refers to
+    pub subject: String,
+    /// Watermark reached by the topic, only set for `topic_watermark` events
+    pub watermark_ns: Option<i64>,
+    /// `None` for lifecycle events
+    pub notification: Option<ActivityNotification>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ActivityFeed {
+    pub activities: Vec<ActivityItem>,
+    /// Cursor used to fetch the next page, `None` once all the entries are returned
+    pub cursor: Option<String>,
+    /// Cursor following the last returned entry, also returned with the last page so that
+    /// consumers can resume the feed from it
+    pub end_cursor: String,
+}
+
+// ########
+// Session
+// ########
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SessionCreate {
+    pub uuid: String,
+    pub locator: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SessionListItem {
+    pub uuid: String,
+    pub locator: String,
+    pub created_at_ns: i64,
+    /// `None` until the session is finalized
+    pub completed_at_ns: Option<i64>,
+    /// Finalized sessions are locked, no data can be added to them
+    pub locked: bool,
+    pub topic_count: usize,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SessionList {
+    pub sessions: Vec<SessionListItem>,
+    /// Cursor used to fetch the next page, `None` once all the sessions are returned
+    pub cursor: Option<String>,
+}
+
+// ########
+// Consumer Offset
+// ########
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ConsumerOffset {
+    pub consumer: String,
+    pub locator: String,
+    /// Cursor of the activity feed committed for a sequence
+    pub cursor: Option<String>,
+    /// Watermark committed for a topic
+    pub watermark_ns: Option<i64>,
+    /// `None` if no offset was committed
+    pub committed_at_ns: Option<i64>,
+}
+
+// ########
+// Topic
+// ########
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TopicListItem {
+    pub locator: String,
+    pub serialization_format: String,
+    pub ontology_tag: String,
+    pub created_at_ns: i64,
+    /// `None` until the topic is finalized
+    pub completed_at_ns: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TopicList {
+    pub topics: Vec<TopicListItem>,
+    /// Cursor used to fetch the next page, `None` once all the topics are returned
+    pub cursor: Option<String>,
+}
+
+/// Response of `topic_export` streaming the file, carrying the ticket to download it with
+/// DoGet.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TopicExport {
+    /// Ticket encoded in base64
+    pub ticket: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TopicCopy {
+    pub locator: String,
+    pub uuid: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TopicLineageItem {
+    pub locator: String,
+    pub uuid: String,
+    pub copied_at_ns: i64,
+}
+
+/// Topics the data of a topic was copied from, from the nearest.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TopicLineage {
+    pub lineage: Vec<TopicLineageItem>,
+}
+
+/// Response of `topic_export` writing the file in the store.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TopicExportWritten {
+    /// Path of the file, relative to the root of the store
+    pub path: String,
+    /// Size of the file in bytes
+    pub size: usize,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MediaSegment {
+    /// Path of the data file holding the segment, relative to the root of the store
+    pub file: String,
+    pub timestamp_ns_start: i64,
+    pub timestamp_ns_end: i64,
+    pub offset: u64,
+    pub length: u64,
+    pub row_count: u64,
+}
+
+/// Time index of a media topic.
+#[derive(Deserialize, Debug, Clone)]
+pub struct MediaIndex {
+    pub segments: Vec<MediaSegment>,
+}
+
+/// Schema registered for a topic.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TopicSchema {
+    /// Schema encoded as a base64 Arrow IPC message, `None` if no schema is registered
+    pub arrow_schema: Option<String>,
+    /// Version of the schema, starting from 1
+    pub version: Option<u32>,
+}
+
+impl TopicSchema {
+    /// Decodes the registered schema, `None` if no schema is registered.
+    pub fn schema(&self) -> Result<Option<SchemaRef>> {
+        let Some(encoded) = &self.arrow_schema else {
+            return Ok(None);
+        };
+        let buffer = BASE64
+            .decode(encoded)
+            .map_err(|e| Error::unexpected_response("topic_schema_get", e))?;
+        Ok(Some(Arc::new(try_schema_from_ipc_buffer(&buffer)?)))
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TopicSchemaVersion {
+    pub version: u32,
+}
+
+/// Minimum or maximum value of a column.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ColumnValue {
+    Numeric(f64),
+    Textual(String),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ColumnStats {
+    pub name: String,
+    pub row_count: u64,
+    pub null_ratio: f64,
+    pub min: Option<ColumnValue>,
+    pub max: Option<ColumnValue>,
+    pub mean: Option<f64>,
+    pub cardinality: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TopicColumnStats {
+    pub columns: Vec<ColumnStats>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Redaction {
+    pub uuid: String,
+    pub locator: String,
+    pub timestamp_ns_start: i64,
+    pub timestamp_ns_end: i64,
+    pub columns: Vec<String>,
+    pub mode: String,
+    /// `None` if the redaction was not authenticated
+    pub principal: Option<String>,
+    pub reason: Option<String>,
+    pub row_count: u64,
+    pub object_count: u64,
+    pub created_at_ns: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RedactionList {
+    pub redactions: Vec<Redaction>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TopicWatermark {
+    /// Latest timestamp of the published data, `None` if no data was published yet
+    pub watermark_ns: Option<i64>,
+    /// Time the watermark last advanced
+    pub updated_at_ns: Option<i64>,
+    /// Number of sessions not finalized yet writing into the topic
+    pub pending_sessions: usize,
+    /// True if no session is writing into the topic
+    pub complete: bool,
+}
+
+// ########
+// Notifications
+// ########
+
+/// Response of `sequence_notification_stream`, carrying the ticket to stream the
+/// notifications with DoGet.
+#[derive(Deserialize, Debug, Clone)]
+pub struct NotificationStream {
+    /// Ticket encoded in base64
+    pub ticket: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Notification {
+    /// Locator of the sequence or topic
+    pub name: String,
+    pub notification_type: String,
+    pub severity: String,
+    /// Machine readable code of the notification
+    pub code: String,
+    pub params: BTreeMap<String, String>,
+    /// Rendered text of the notification, empty if not available
+    pub msg: String,
+    pub created_datetime: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct NotificationList {
+    pub notifications: Vec<Notification>,
+    /// Cursor used to fetch the next page, `None` once all the notifications are returned
+    pub cursor: Option<String>,
+}
+
+// ########
+// Query
+// ########
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct QueryItemTopic {
+    pub locator: String,
+    /// Range of the matching data, bounds in nanoseconds
+    pub timestamp_range: Option<(i64, i64)>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct QueryItem {
+    pub sequence: String,
+    pub topics: Vec<QueryItemTopic>,
+}
+
+/// Fraction of the topic data searched by an approximate query.
+#[derive(Deserialize, Debug, Clone)]
+pub struct QuerySampling {
+    pub fraction: f64,
+    pub method: String,
+    pub stratum_ns: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Query {
+    pub items: Vec<QueryItem>,
+    /// `None` for exact queries
+    pub sampling: Option<QuerySampling>,
+    /// Handle used to fetch the next rows with `query_fetch`, `None` once all the rows are
+    /// returned
+    pub handle: Option<String>,
+    /// True if the result was served from the query cache
+    pub cache_hit: bool,
+}
+
+/// Response of `sql_query`, carrying the ticket to stream the result with DoGet.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SqlQuery {
+    /// Ticket encoded in base64
+    pub ticket: String,
+    /// Schema of the result, encoded as a base64 Arrow IPC message
+    pub arrow_schema: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ScheduledQuery {
+    pub name: String,
+    pub schedule: String,
+    pub query: serde_json::Value,
+    /// Condition on the number of matching topics, e.g. `{"$gt": 0}`
+    pub condition: serde_json::Value,
+    pub created_at_ns: i64,
+    pub next_run_ns: i64,
+    pub last_run_ns: Option<i64>,
+    pub last_match_count: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ScheduledQueryList {
+    pub scheduled_queries: Vec<ScheduledQuery>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ScheduledQueryRun {
+    /// Number of topics matched by the query
+    pub match_count: u64,
+    /// True if the condition was satisfied and the matching topics notified
+    pub triggered: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct QueryView {
+    pub name: String,
+    pub query: serde_json::Value,
+    pub created_at_ns: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct QueryViewList {
+    pub views: Vec<QueryView>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExternalTableColumn {
+    pub name: String,
+    pub data_type: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExternalTable {
+    pub name: String,
+    pub location: String,
+    pub format: String,
+    pub columns: Vec<ExternalTableColumn>,
+    pub registered_at_ns: i64,
+    pub expires_at_ns: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExternalTableList {
+    pub tables: Vec<ExternalTable>,
+}
+
+// ########
+// Api Key
+// ########
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApiKeyToken {
+    pub api_key_token: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApiKeyStatus {
+    pub api_key_fingerprint: String,
+    pub description: String,
+    pub created_at_ns: i64,
+    pub expires_at_ns: Option<i64>,
+    pub default_sequence: Option<String>,
+}
+
+// ########
+// Server
+// ########
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SemVer {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    /// Pre-release identifier, empty for releases
+    pub pre: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ServerVersion {
+    pub version: String,
+    pub semver: SemVer,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct StoreOperationMetrics {
+    pub operation: String,
+    pub count: u64,
+    pub errors: u64,
+    pub bytes: u64,
+    /// Estimated latency quantiles (in microseconds)
+    pub latency_us_p50: Option<u64>,
+    pub latency_us_p99: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct StoreMetrics {
+    pub backend: String,
+    pub operations: Vec<StoreOperationMetrics>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ColdDataFile {
+    pub path: String,
+    pub size_bytes: u64,
+    /// Estimated number of reads
+    pub read_count: u64,
+    /// `None` if no read was sampled
+    pub last_read_ns: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct StoreTieringReport {
+    pub data_files: u64,
+    pub size_bytes: u64,
+    pub monthly_savings: f64,
+    pub candidates: Vec<ColdDataFile>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct StoreTieringDemote {
+    pub demoted: usize,
+    pub size_bytes: u64,
+}
+
+/// Monthly storage and egress costs of some data files.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Cost {
+    pub standard_bytes: u64,
+    pub archived_bytes: u64,
+    pub storage_cost: f64,
+    /// Projected bytes read in a month
+    pub egress_bytes: u64,
+    pub egress_cost: f64,
+    pub total_cost: f64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SequenceCost {
+    pub locator: String,
+    #[serde(flatten)]
+    pub cost: Cost,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CostEstimate {
+    pub scope: String,
+    #[serde(flatten)]
+    pub total: Cost,
+    /// Sorted by locator
+    pub sequences: Vec<SequenceCost>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LatencyBucket {
+    /// Upper bound of the bucket (in milliseconds), `None` for the last bucket
+    pub le_ms: Option<u64>,
+    pub count: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TopicIngestMetrics {
+    pub topic: String,
+    /// Number of batches with a measured latency
+    pub count: u64,
+    /// Batches sent or timestamped later than their commit, not measured
+    pub skewed: u64,
+    /// Estimated latency quantiles (in milliseconds)
+    pub latency_ms_p50: Option<u64>,
+    pub latency_ms_p99: Option<u64>,
+    pub buckets: Vec<LatencyBucket>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct IngestMetrics {
+    /// Topics with batches uploaded since the server started, sorted by locator
+    pub topics: Vec<TopicIngestMetrics>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct QueryStats {
+    /// Maximum number of queries running concurrently, 0 if unlimited
+    pub max_running: usize,
+    pub running: usize,
+    pub max_queued: usize,
+    pub queued: usize,
+    /// Time (in milliseconds) spent in the queue by the oldest queued query
+    pub oldest_queued_ms: Option<u64>,
+    pub admitted: u64,
+    pub rejected: u64,
+    pub timed_out: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RequestStats {
+    pub requests: u64,
+    /// Requests answered with an error status
+    pub failed: u64,
+    /// Average time (in microseconds) spent answering a request
+    pub latency_us_avg: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SecurityStats {
+    /// Requests with a missing or invalid API key
+    pub unauthenticated: u64,
+    /// Requests without enough permissions or with an invalid resource key
+    pub permission_denied: u64,
+    /// Requests for resources not found
+    pub not_found: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ServerStats {
+    pub queries: QueryStats,
+    /// Requests received since the server started, by gRPC method
+    pub requests: BTreeMap<String, RequestStats>,
+    pub security: SecurityStats,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SelfTestStep {
+    pub step: String,
+    pub elapsed_us: u64,
+    /// `None` if the step completed
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SelfTest {
+    /// True if all the steps completed
+    pub passed: bool,
+    /// Steps performed, in order, up to the first failed one
+    pub steps: Vec<SelfTestStep>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PeerSecurityReport {
+    /// Address of the peer
+    pub peer: String,
+    #[serde(flatten)]
+    pub failures: SecurityStats,
+    pub last_failure_ns: Option<i64>,
+    /// Remaining time (in seconds) the peer is locked out, `None` if not locked out
+    pub locked_out_secs: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SecurityReport {
+    /// Peers with rejected requests, sorted by decreasing number of failures
+    pub peers: Vec<PeerSecurityReport>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReindexJob {
+    pub scope: String,
+    pub running: bool,
+    /// Number of topics whose derived state was rebuilt
+    pub processed: u64,
+    /// Number of topics whose derived state could not be rebuilt
+    pub failed: u64,
+    pub started_at_ns: i64,
+    /// `None` while the job is running
+    pub completed_at_ns: Option<i64>,
+    /// Reason the job was stopped before processing all the topics
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReindexStatus {
+    /// Jobs started since the server started
+    pub jobs: Vec<ReindexJob>,
+}
+
+/// Request recorded by the replay capture, strings are replaced by salted hashes.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReplayEntry {
+    pub at_ns: i64,
+    /// Either `action` or `do_put`
+    pub kind: String,
+    pub peer: Option<String>,
+    pub duration_us: u64,
+    /// gRPC status code the request was answered with
+    pub status: String,
+    /// Type of the action
+    pub action: Option<String>,
+    pub payload: Option<serde_json::Value>,
+    pub payload_bytes: Option<usize>,
+    /// Locator of the uploaded topic
+    pub locator: Option<String>,
+    pub messages: Option<u64>,
+    pub bytes: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReplayCapture {
+    /// True while the capture window is open
+    pub active: bool,
+    /// Maximum number of requests kept, the oldest are discarded
+    pub capacity: usize,
+    /// `None` if no capture was started since the server started
+    pub started_at_ns: Option<i64>,
+    pub until_ns: Option<i64>,
+    /// Number of requests discarded because the buffer was full
+    pub dropped: u64,
+    /// Requests recorded, from the oldest
+    pub entries: Vec<ReplayEntry>,
+}
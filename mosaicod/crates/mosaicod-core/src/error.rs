@@ -101,8 +101,20 @@ pub enum ErrorKind {
     MissingDoPut(String),
     #[error("Topic `{0}` is already uploading data.")]
     TopicUploadInProgress(String),
+    #[error("Data of topic `{0}` is not published, the session uploading it is not finalized.")]
+    TopicNotPublished(String),
+    #[error("Topic `{0}` is at version {2}, the upload is based on version {1}.")]
+    TopicVersionConflict(String, u64, u64),
+    #[error("Consumer `{0}` already committed an offset of `{1}` past the one being committed.")]
+    ConsumerOffsetBehind(String, String),
     #[error("Session `{0} is empty.`")]
     EmptySession(String),
+    #[error("Sequence `{0}` is under legal hold and cannot be deleted.")]
+    LegalHold(String),
+    #[error("Sequence `{0}` is locked, its sessions have been finalized.")]
+    SequenceLocked(String),
+    #[error("Data does not match the schema registered for topic `{0}`: {1}")]
+    TopicSchemaMismatch(String, String),
     #[error("{0} is not a valid {1} locator")]
     LocatorKindMismatch(String, String),
     #[error("{0} is not a valid locator")]
@@ -141,6 +153,16 @@ pub enum ErrorKind {
     Internal(String),
     #[error("Invalid fingerprint `{0}`")]
     InvalidFingerprint(String),
+    #[error("Query limit exceeded: {0}")]
+    QueryLimitExceeded(String),
+    #[error("Server overloaded: {0}")]
+    Overloaded(String),
+    #[error("Deadline exceeded: {0}")]
+    DeadlineExceeded(String),
+    /// A dependency, named by the first field (e.g. `database` or `store`), is temporarily
+    /// unavailable, the request can be retried after the provided delay, if any
+    #[error("Temporarily unavailable: {0}")]
+    Unavailable(String, Option<std::time::Duration>),
 }
 
 #[derive(Debug, Clone)]
@@ -167,6 +189,22 @@ impl Error {
         Self(ErrorKind::TopicUploadInProgress(locator))
     }
 
+    pub fn topic_not_published(locator: String) -> Self {
+        Self(ErrorKind::TopicNotPublished(locator))
+    }
+
+    pub fn topic_version_conflict(locator: String, base_version: u64, version: u64) -> Self {
+        Self(ErrorKind::TopicVersionConflict(
+            locator,
+            base_version,
+            version,
+        ))
+    }
+
+    pub fn consumer_offset_behind(consumer: String, locator: String) -> Self {
+        Self(ErrorKind::ConsumerOffsetBehind(consumer, locator))
+    }
+
     pub fn topic_already_finalized(locator: String) -> Self {
         Self(ErrorKind::TopicAlreadyFinalized(locator))
     }
@@ -179,6 +217,18 @@ impl Error {
         Self(ErrorKind::EmptySession(locator))
     }
 
+    pub fn legal_hold(locator: String) -> Self {
+        Self(ErrorKind::LegalHold(locator))
+    }
+
+    pub fn sequence_locked(locator: String) -> Self {
+        Self(ErrorKind::SequenceLocked(locator))
+    }
+
+    pub fn topic_schema_mismatch(locator: String, msg: String) -> Self {
+        Self(ErrorKind::TopicSchemaMismatch(locator, msg))
+    }
+
     pub fn stream_error(err: impl std::error::Error) -> Self {
         Self(ErrorKind::StreamError(err.to_string()))
     }
@@ -279,6 +329,32 @@ impl Error {
     pub fn invalid_fingerprint(fingerprint: String) -> Self {
         Self(ErrorKind::InvalidFingerprint(fingerprint))
     }
+
+    pub fn query_limit_exceeded(msg: String) -> Self {
+        Self(ErrorKind::QueryLimitExceeded(msg))
+    }
+
+    pub fn overloaded(msg: String) -> Self {
+        Self(ErrorKind::Overloaded(msg))
+    }
+
+    pub fn deadline_exceeded(msg: String) -> Self {
+        Self(ErrorKind::DeadlineExceeded(msg))
+    }
+
+    pub fn unavailable(msg: String) -> Self {
+        Self(ErrorKind::Unavailable(msg, None))
+    }
+
+    /// Creates an [`ErrorKind::Unavailable`] error suggesting to retry after `retry_after`.
+    pub fn unavailable_for(msg: String, retry_after: std::time::Duration) -> Self {
+        Self(ErrorKind::Unavailable(msg, Some(retry_after)))
+    }
+
+    /// Returns true if the error is transient and the request can be retried.
+    pub fn is_transient(&self) -> bool {
+        matches!(self.0, ErrorKind::Unavailable(_, _))
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -298,6 +374,11 @@ impl PublicError for Error {
             ErrorKind::InvalidConfiguration(_) => {
                 Some("https://docs.mosaico.dev/daemon/env".parse().unwrap())
             }
+            ErrorKind::QueryLimitExceeded(_) => Some(
+                "https://docs.mosaico.dev/daemon/query#resource-limits"
+                    .parse()
+                    .unwrap(),
+            ),
             ErrorKind::Internal(_) => Some("https://c.xkcd.com/random/comic/".parse().unwrap()),
             _ => None,
         }
@@ -10,6 +10,34 @@ pub trait AsyncWriteToPath {
     ) -> impl Future<Output = std::io::Result<()>>;
 }
 
+/// Used to write asynchronously an object to a path one part at a time (usually over
+/// network), so that the object does not need to be held in memory as a whole.
+pub trait AsyncStreamToPath {
+    type Writer: AsyncPartWriter;
+
+    /// Starts writing an object at the specified path, the object becomes visible once the
+    /// returned writer is finished.
+    fn stream_to_path(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> impl Future<Output = std::io::Result<Self::Writer>>;
+}
+
+/// Object being written part by part, see [`AsyncStreamToPath`].
+pub trait AsyncPartWriter {
+    /// Appends the provided buffer to the object.
+    fn write_part(
+        &mut self,
+        buf: impl Into<bytes::Bytes>,
+    ) -> impl Future<Output = std::io::Result<()>>;
+
+    /// Completes the object.
+    fn finish(self) -> impl Future<Output = std::io::Result<()>>;
+
+    /// Discards the parts written so far.
+    fn abort(self) -> impl Future<Output = std::io::Result<()>>;
+}
+
 /// A trait for converting a type into its **file extension** representation.
 ///
 /// This is typically implemented for structs that represent file formats or
@@ -0,0 +1,97 @@
+use super::*;
+use crate::Error;
+
+/// Event reported by the activity feed of a sequence.
+pub enum ActivityEvent {
+    SequenceCreated,
+    SessionCreated,
+    SessionFinalized,
+    TopicCreated,
+    TopicFinalized,
+    /// Advance of the ingestion watermark of a topic, see [`TopicWatermark`]
+    TopicWatermark {
+        watermark: Timestamp,
+    },
+    /// Notification attached to the sequence or to one of its topics
+    Notification {
+        notification_type: NotificationType,
+        severity: NotificationSeverity,
+        payload: NotificationPayload,
+        msg: Option<String>,
+    },
+}
+
+impl ActivityEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SequenceCreated => "sequence_created",
+            Self::SessionCreated => "session_created",
+            Self::SessionFinalized => "session_finalized",
+            Self::TopicCreated => "topic_created",
+            Self::TopicFinalized => "topic_finalized",
+            Self::TopicWatermark { .. } => "topic_watermark",
+            Self::Notification { .. } => "notification",
+        }
+    }
+}
+
+/// Entry of the activity feed of a sequence.
+pub struct Activity {
+    pub timestamp: Timestamp,
+    /// Locator of the sequence or topic, or uuid of the session, the event refers to
+    pub subject: String,
+    pub event: ActivityEvent,
+}
+
+/// Position in an activity feed, used to fetch the entries following a page.
+///
+/// Since several entries may share the same timestamp, the cursor points to the
+/// timestamp of the last returned entry along with the number of entries returned
+/// with that timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ActivityCursor {
+    pub timestamp: Timestamp,
+    pub skip: usize,
+}
+
+impl std::fmt::Display for ActivityCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.timestamp.as_i64(), self.skip)
+    }
+}
+
+impl std::str::FromStr for ActivityCursor {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::bad_request(format!("invalid activity cursor `{value}`"));
+
+        let (timestamp, skip) = value.split_once('.').ok_or_else(invalid)?;
+
+        Ok(Self {
+            timestamp: timestamp.parse::<i64>().map_err(|_| invalid())?.into(),
+            skip: skip.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn activity_cursor() {
+        let cursor = ActivityCursor {
+            timestamp: 1_700_000_000_000_000_000.into(),
+            skip: 3,
+        };
+        assert_eq!(
+            cursor.to_string().parse::<ActivityCursor>().unwrap(),
+            cursor
+        );
+
+        for value in ["", "12", "a.1", "12.b", "12.-1"] {
+            assert!(value.parse::<ActivityCursor>().is_err(), "{value}");
+        }
+    }
+}
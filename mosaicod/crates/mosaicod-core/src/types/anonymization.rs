@@ -0,0 +1,48 @@
+use std::collections::BTreeMap;
+
+/// Transforms applied to the data of the topics when they are exported, so that datasets can
+/// be shared without exposing sensitive values.
+///
+/// Columns are identified by their name in the topic schema, only top-level columns are
+/// supported.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Anonymization {
+    /// Columns removed from the data
+    pub drop: Vec<String>,
+    /// Columns whose values are replaced by their hex encoded SHA-256 digest, keeping equal
+    /// values comparable
+    pub hash: Vec<String>,
+    /// Floating point columns rounded to the given number of decimal digits, e.g. 3 decimal
+    /// digits of a coordinate in degrees give a precision of about 100 meters
+    pub coarsen: BTreeMap<String, u32>,
+}
+
+impl Anonymization {
+    /// Returns true if no transform is applied.
+    pub fn is_empty(&self) -> bool {
+        self.drop.is_empty() && self.hash.is_empty() && self.coarsen.is_empty()
+    }
+
+    /// Returns the names of the columns transformed.
+    pub fn columns(&self) -> impl Iterator<Item = &str> {
+        self.drop
+            .iter()
+            .chain(&self.hash)
+            .chain(self.coarsen.keys())
+            .map(String::as_str)
+    }
+
+    /// Returns the transforms of the columns for which `keep` returns true.
+    pub fn retain(&self, keep: impl Fn(&str) -> bool) -> Self {
+        Self {
+            drop: self.drop.iter().filter(|c| keep(c)).cloned().collect(),
+            hash: self.hash.iter().filter(|c| keep(c)).cloned().collect(),
+            coarsen: self
+                .coarsen
+                .iter()
+                .filter(|(c, _)| keep(c))
+                .map(|(c, d)| (c.clone(), *d))
+                .collect(),
+        }
+    }
+}
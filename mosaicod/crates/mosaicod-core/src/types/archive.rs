@@ -0,0 +1,22 @@
+/// Manifest of a portable archive of a sequence, holding the metadata of the sequence and of
+/// its topics. The rows of each topic are stored next to the manifest as Arrow IPC files.
+pub struct SequenceArchive<M> {
+    pub uuid: super::Uuid,
+    pub locator: super::SequenceLocator,
+    pub created_at: super::Timestamp,
+    pub user_metadata: Option<M>,
+    /// Published topics of the sequence, sorted by locator
+    pub topics: Vec<ArchivedTopic<M>>,
+}
+
+/// Topic stored in a [`SequenceArchive`].
+pub struct ArchivedTopic<M> {
+    pub uuid: super::Uuid,
+    pub locator: super::TopicLocator,
+    pub ontology_metadata: super::TopicOntologyMetadata<M>,
+    /// Schema registered for the topic, encoded as an Arrow IPC message
+    pub arrow_schema: Option<Vec<u8>>,
+    pub rows: u64,
+    /// Arrow IPC files holding the rows, relative to the root of the archive and in order
+    pub chunks: Vec<String>,
+}
@@ -31,6 +31,9 @@ pub enum ApiKeyError {
 
     #[error("missing permissions")]
     MissingPermissions,
+
+    #[error("bad default sequence")]
+    BadDefaultSequence,
 }
 
 impl PublicError for ApiKeyError {
@@ -304,6 +307,9 @@ pub struct ApiKey {
 
     /// Expiration timestamp
     pub expires_at: Option<Timestamp>,
+
+    /// Sequence searched by the queries not filtering sequences
+    pub default_sequence: Option<types::SequenceLocator>,
 }
 
 impl ApiKey {
@@ -333,9 +339,17 @@ impl ApiKey {
             created_at: Timestamp::now(),
             expires_at,
             description,
+            default_sequence: None,
         }
     }
 
+    /// Sets the sequence searched by the queries performed with the key that don't filter
+    /// sequences.
+    pub fn with_default_sequence(mut self, sequence: Option<types::SequenceLocator>) -> Self {
+        self.default_sequence = sequence;
+        self
+    }
+
     /// Get the token associated with this API key
     pub fn token(&self) -> &Token {
         &self.key
@@ -0,0 +1,164 @@
+use chrono::NaiveTime;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum BandwidthScheduleError {
+    #[error(
+        "bad bandwidth limit `{0}`, expected `bytes_per_second` or `HH:MM-HH:MM=bytes_per_second`"
+    )]
+    BadLimit(String),
+    #[error("bad time window `{0}`, expected `HH:MM-HH:MM`")]
+    BadWindow(String),
+}
+
+/// Time of the day window, from `start` (inclusive) to `end` (exclusive).
+///
+/// Windows with `end` preceding `start` span midnight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TimeWindow {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl TimeWindow {
+    fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Bandwidth a class of transfers is allowed to use, depending on the time of the day.
+///
+/// The schedule is parsed from a comma separated list of limits, in bytes per second. Each
+/// limit is either applied during a time window of the day (in UTC), e.g.
+/// `08:00-18:00=1000000`, or by default, e.g. `50000000`. The first window containing the
+/// current time applies, otherwise the default limit. Windows can span midnight, e.g.
+/// `22:00-06:00=100000000`.
+///
+/// A limit set to 0 is disabled, a schedule without a default limit leaves the transfers
+/// unlimited out of its windows.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BandwidthSchedule {
+    windows: Vec<(TimeWindow, u64)>,
+    default: Option<u64>,
+}
+
+impl BandwidthSchedule {
+    /// Returns a schedule without any restriction.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if the schedule never restricts the transfers.
+    pub fn is_unlimited(&self) -> bool {
+        self.default.unwrap_or_default() == 0 && self.windows.iter().all(|(_, limit)| *limit == 0)
+    }
+
+    /// Maximum number of bytes per second at the current time, [`None`] if unlimited.
+    pub fn current_limit(&self) -> Option<u64> {
+        self.limit_at(chrono::Utc::now().time())
+    }
+
+    /// Maximum number of bytes per second at `time` of the day, [`None`] if unlimited.
+    pub fn limit_at(&self, time: NaiveTime) -> Option<u64> {
+        self.windows
+            .iter()
+            .find(|(window, _)| window.contains(time))
+            .map(|(_, limit)| *limit)
+            .or(self.default)
+            .filter(|limit| *limit != 0)
+    }
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+impl FromStr for BandwidthSchedule {
+    type Err = BandwidthScheduleError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut schedule = Self::default();
+
+        for limit in value.split(',').map(str::trim).filter(|l| !l.is_empty()) {
+            let (window, rate) = match limit.split_once('=') {
+                Some((window, rate)) => (Some(window.trim()), rate),
+                None => (None, limit),
+            };
+            let rate = rate
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| BandwidthScheduleError::BadLimit(limit.to_owned()))?;
+
+            match window {
+                Some(window) => {
+                    let (start, end) = window
+                        .split_once('-')
+                        .and_then(|(start, end)| Some((parse_time(start)?, parse_time(end)?)))
+                        .ok_or_else(|| BandwidthScheduleError::BadWindow(window.to_owned()))?;
+                    schedule.windows.push((TimeWindow { start, end }, rate));
+                }
+                None => schedule.default = Some(rate),
+            }
+        }
+
+        Ok(schedule)
+    }
+}
+
+impl std::fmt::Display for BandwidthSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let limits: Vec<String> = self
+            .windows
+            .iter()
+            .map(|(window, limit)| {
+                format!(
+                    "{}-{}={limit}",
+                    window.start.format("%H:%M"),
+                    window.end.format("%H:%M")
+                )
+            })
+            .chain(self.default.map(|limit| limit.to_string()))
+            .collect();
+
+        write!(f, "{}", limits.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(value: &str) -> NaiveTime {
+        parse_time(value).unwrap()
+    }
+
+    #[test]
+    fn parse_bandwidth_schedule() {
+        let schedule: BandwidthSchedule = "08:00-18:00=1000, 22:00-06:00=0, 5000".parse().unwrap();
+        assert_eq!(schedule.limit_at(time("08:00")), Some(1000));
+        assert_eq!(schedule.limit_at(time("17:59")), Some(1000));
+        assert_eq!(schedule.limit_at(time("18:00")), Some(5000));
+        assert_eq!(schedule.limit_at(time("23:30")), None);
+        assert_eq!(schedule.limit_at(time("05:59")), None);
+        assert_eq!(schedule.limit_at(time("06:00")), Some(5000));
+        assert_eq!(schedule.to_string(), "08:00-18:00=1000,22:00-06:00=0,5000");
+        assert!(!schedule.is_unlimited());
+
+        let schedule: BandwidthSchedule = "08:00-18:00=1000".parse().unwrap();
+        assert_eq!(schedule.limit_at(time("12:00")), Some(1000));
+        assert_eq!(schedule.limit_at(time("20:00")), None);
+
+        assert!("".parse::<BandwidthSchedule>().unwrap().is_unlimited());
+        assert!("0".parse::<BandwidthSchedule>().unwrap().is_unlimited());
+
+        assert!("fast".parse::<BandwidthSchedule>().is_err());
+        assert!("-1".parse::<BandwidthSchedule>().is_err());
+        assert!("08:00=1000".parse::<BandwidthSchedule>().is_err());
+        assert!("08:00-25:00=1000".parse::<BandwidthSchedule>().is_err());
+    }
+}
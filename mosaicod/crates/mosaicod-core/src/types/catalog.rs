@@ -0,0 +1,29 @@
+/// Catalog of the data published in the store, letting external engines find the sequences,
+/// the topics and their data files without going through the server.
+pub struct CatalogManifest<M> {
+    /// Sequences not in the trash, sorted by locator
+    pub sequences: Vec<CatalogSequence<M>>,
+}
+
+/// Sequence listed in a [`CatalogManifest`].
+pub struct CatalogSequence<M> {
+    pub locator: super::SequenceLocator,
+    pub created_at: super::Timestamp,
+    pub path_in_store: super::SequencePathInStore,
+    pub user_metadata: Option<M>,
+    /// Published topics of the sequence, sorted by locator
+    pub topics: Vec<CatalogTopic<M>>,
+}
+
+/// Topic listed in a [`CatalogManifest`].
+pub struct CatalogTopic<M> {
+    pub locator: super::TopicLocator,
+    pub path_in_store: super::TopicPathInStore,
+    pub metadata: super::TopicMetadata<M>,
+    /// Fields of the schema of the data
+    pub fields: Vec<super::FieldDescription>,
+    /// Paths of the data files relative to the root of the store, sorted by chunk number.
+    /// Data files shared with other topics or archived are listed at the path of their
+    /// target, outside of the topic folder
+    pub data_files: Vec<String>,
+}
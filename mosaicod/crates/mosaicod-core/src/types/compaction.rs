@@ -0,0 +1,119 @@
+use super::Timestamp;
+use std::time::Duration;
+
+/// Tuning of the compaction of the upsert topics of a sequence.
+///
+/// Settings not set are inherited from another policy, usually the one configured for the
+/// whole server (see [`CompactionPolicy::or`]).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompactionPolicy {
+    /// Target size (in bytes) of the data files written by a compaction
+    pub target_object_size: Option<u64>,
+    /// Minimum interval (in seconds) between consecutive background compactions of a topic,
+    /// 0 disables the background compaction
+    pub interval: Option<u64>,
+    /// Minimum number of data files a topic is stored in to be compacted in background
+    pub min_chunks: Option<u64>,
+    /// If set, topics are compacted in background only once no rows were written to them
+    /// for this many seconds
+    pub cold_after: Option<u64>,
+}
+
+impl CompactionPolicy {
+    /// Returns this policy, taking the settings not set from `fallback`.
+    pub fn or(&self, fallback: &CompactionPolicy) -> Self {
+        Self {
+            target_object_size: self.target_object_size.or(fallback.target_object_size),
+            interval: self.interval.or(fallback.interval),
+            min_chunks: self.min_chunks.or(fallback.min_chunks),
+            cold_after: self.cold_after.or(fallback.cold_after),
+        }
+    }
+
+    /// Returns `true` if no setting is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns `true` if the background compaction is enabled by the policy.
+    pub fn is_scheduled(&self) -> bool {
+        self.interval != Some(0)
+    }
+
+    /// Returns `true` if a topic stored in `chunks` data files, last compacted at
+    /// `compacted_at` (or published at, if never compacted) and last written at
+    /// `written_at`, is due for a background compaction at `now`.
+    pub fn is_due(
+        &self,
+        chunks: u64,
+        compacted_at: Timestamp,
+        written_at: Timestamp,
+        now: Timestamp,
+    ) -> bool {
+        if !self.is_scheduled() || chunks < self.min_chunks.unwrap_or_default().max(2) {
+            return false;
+        }
+
+        let elapsed = |since: Timestamp, secs: u64| since + Duration::from_secs(secs) <= now;
+
+        elapsed(compacted_at, self.interval.unwrap_or_default())
+            && self
+                .cold_after
+                .is_none_or(|cold_after| elapsed(written_at, cold_after))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compaction_policy_fallback() {
+        let global = CompactionPolicy {
+            target_object_size: Some(1000),
+            interval: Some(3600),
+            min_chunks: Some(8),
+            cold_after: None,
+        };
+        let sequence = CompactionPolicy {
+            interval: Some(60),
+            cold_after: Some(600),
+            ..Default::default()
+        };
+
+        let policy = sequence.or(&global);
+        assert_eq!(policy.target_object_size, Some(1000));
+        assert_eq!(policy.interval, Some(60));
+        assert_eq!(policy.min_chunks, Some(8));
+        assert_eq!(policy.cold_after, Some(600));
+
+        assert!(CompactionPolicy::default().is_empty());
+        assert!(!sequence.is_empty());
+    }
+
+    #[test]
+    fn compaction_policy_schedule() {
+        let secs = |secs: i64| Timestamp::from(secs * 1_000_000_000);
+        let policy = CompactionPolicy {
+            interval: Some(60),
+            min_chunks: Some(4),
+            cold_after: Some(600),
+            ..Default::default()
+        };
+
+        assert!(policy.is_due(4, secs(0), secs(0), secs(600)));
+        // Not enough data files
+        assert!(!policy.is_due(3, secs(0), secs(0), secs(600)));
+        // Compacted too recently
+        assert!(!policy.is_due(4, secs(590), secs(0), secs(600)));
+        // Written too recently
+        assert!(!policy.is_due(4, secs(0), secs(100), secs(600)));
+
+        let disabled = CompactionPolicy {
+            interval: Some(0),
+            ..Default::default()
+        };
+        assert!(!disabled.is_scheduled());
+        assert!(!disabled.is_due(100, secs(0), secs(0), secs(600)));
+    }
+}
@@ -0,0 +1,19 @@
+use super::*;
+
+/// Position processed by a named downstream consumer.
+///
+/// Positions of the same kind are ordered, a committed offset can only move forward.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConsumerPosition {
+    /// Position in the activity feed of a sequence
+    Activity(ActivityCursor),
+    /// Ingestion watermark of a topic, see [`TopicWatermark`]
+    Watermark(Timestamp),
+}
+
+/// Offset committed by a named consumer of a sequence or topic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsumerOffset {
+    pub position: ConsumerPosition,
+    pub committed_at: Timestamp,
+}
@@ -0,0 +1,86 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DedupPolicyError {
+    #[error("unknown dedup policy `{0}`")]
+    UnknownPolicy(String),
+    #[error("dedup policy `{0}` requires a key column")]
+    MissingKey(String),
+}
+
+/// Policy used to remove duplicated rows (e.g. produced by re-uploading overlapping
+/// recordings) when the data of a topic is read.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub enum DedupPolicy {
+    /// Rows are returned as uploaded.
+    #[default]
+    None,
+
+    /// Rows having the same value in every column are returned once.
+    DropDuplicates,
+
+    /// Among the rows sharing the same value of the key column only the last written
+    /// one is returned.
+    LastWriteWins(String),
+}
+
+impl DedupPolicy {
+    /// Builds a policy from its name and, if required by the policy, its key column.
+    pub fn try_from_parts(name: &str, key: Option<String>) -> Result<Self, DedupPolicyError> {
+        match name {
+            "none" => Ok(Self::None),
+            "drop_duplicates" => Ok(Self::DropDuplicates),
+            "last_write_wins" => key
+                .map(Self::LastWriteWins)
+                .ok_or_else(|| DedupPolicyError::MissingKey(name.to_owned())),
+            _ => Err(DedupPolicyError::UnknownPolicy(name.to_owned())),
+        }
+    }
+
+    /// Returns the policy name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::DropDuplicates => "drop_duplicates",
+            Self::LastWriteWins(_) => "last_write_wins",
+        }
+    }
+
+    /// Returns the key column used to identify duplicated rows, if any.
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            Self::LastWriteWins(key) => Some(key),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for DedupPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.key() {
+            Some(key) => write!(f, "{}({})", self.name(), key),
+            None => write!(f, "{}", self.name()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_parts() {
+        for policy in [
+            DedupPolicy::None,
+            DedupPolicy::DropDuplicates,
+            DedupPolicy::LastWriteWins("timestamp_ns".to_owned()),
+        ] {
+            let parsed =
+                DedupPolicy::try_from_parts(policy.name(), policy.key().map(ToOwned::to_owned));
+            assert_eq!(parsed.unwrap(), policy);
+        }
+
+        assert!(DedupPolicy::try_from_parts("last_write_wins", None).is_err());
+        assert!(DedupPolicy::try_from_parts("unknown", None).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+use super::Timestamp;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ExternalTableError {
+    #[error("unknown external table format `{0}`, expected `parquet` or `csv`")]
+    UnknownFormat(String),
+}
+
+/// Format of the file backing an external table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExternalTableFormat {
+    Parquet,
+    /// Comma separated values, with a header row naming the columns
+    Csv,
+}
+
+impl FromStr for ExternalTableFormat {
+    type Err = ExternalTableError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "parquet" => Ok(Self::Parquet),
+            "csv" => Ok(Self::Csv),
+            _ => Err(ExternalTableError::UnknownFormat(s.to_owned())),
+        }
+    }
+}
+
+impl std::fmt::Display for ExternalTableFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parquet => write!(f, "parquet"),
+            Self::Csv => write!(f, "csv"),
+        }
+    }
+}
+
+/// Column of an external table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalTableColumn {
+    pub name: String,
+    /// Arrow data type of the column
+    pub data_type: String,
+}
+
+/// A file registered as a temporary table, whose columns can be referenced by queries
+/// without ingesting the file.
+#[derive(Debug, Clone)]
+pub struct ExternalTable {
+    pub name: String,
+    /// Path of the file in the store, or URL of the file
+    pub location: String,
+    pub format: ExternalTableFormat,
+    pub columns: Vec<ExternalTableColumn>,
+    pub registered_at: Timestamp,
+    pub expires_at: Timestamp,
+}
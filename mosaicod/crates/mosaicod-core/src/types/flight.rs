@@ -5,12 +5,48 @@ use crate::types::TimestampRange;
 pub struct DoPutCmd {
     pub resource_locator: String, //(cabba) TODO: replace this with a resource locator
     pub key: String,
+    /// Encoding of the data sent in the stream
+    pub payload_format: PayloadFormat,
+}
+
+/// Encoding of the data uploaded with a DoPut stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PayloadFormat {
+    /// Arrow IPC record batches, preceded by the schema
+    #[default]
+    ArrowIpc,
+    /// Parquet files, one for each message, carried in the body of the messages
+    Parquet,
 }
 
 /// Request info on a mosaico resource (topic or sequence)
 pub struct GetFlightInfoCmd {
     pub resource_locator: String, //(cabba) TODO: replace this with a resource locator
     pub timestamp_range: Option<TimestampRange>,
+    /// If true offloaded blobs are returned as references instead of being inlined
+    pub blob_references: bool,
+    /// If set only the changes of an upsert topic are returned
+    pub changes_since: Option<ChangesSince>,
+    /// If set only the data of the topic uploaded by the session is returned
+    pub session: Option<types::SessionLocator>,
+    /// If set the data is anonymized before being returned
+    pub anonymization: Option<types::Anonymization>,
+}
+
+/// Starting point of the changes requested for an upsert topic
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangesSince {
+    /// Changes completed after the given time
+    Timestamp(types::Timestamp),
+    /// Changes completed after the finalization of the given session
+    Session(types::SessionLocator),
+}
+
+/// Criteria used to list the available resources
+pub struct ListFlightsCriteria {
+    /// Pattern matched by the locators of the listed resources, all the sequences are listed
+    /// if not set
+    pub pattern: Option<types::LocatorPattern>,
 }
 
 pub struct TicketTopic {
@@ -18,4 +54,55 @@ pub struct TicketTopic {
     pub locator: types::TopicLocator,
     /// Optional timestamp range used to limit the data stream
     pub timestamp_range: Option<TimestampRange>,
+    /// If true offloaded blobs are returned as references instead of being inlined
+    pub blob_references: bool,
+    /// If set only the changes of an upsert topic completed after this time are returned
+    pub changes_since: Option<types::Timestamp>,
+    /// If set only the data uploaded by the session is returned
+    pub session: Option<types::SessionLocator>,
+    /// If set the data is anonymized before being returned
+    pub anonymization: Option<types::Anonymization>,
+}
+
+/// Ticket serving the result of a SQL statement run over the data of some topics
+pub struct TicketSqlQuery {
+    /// Read-only SQL statement
+    pub statement: String,
+    /// Topics read by the statement, each one with the name of its table
+    pub tables: Vec<(String, types::TopicLocator)>,
+}
+
+/// Ticket streaming the notifications of a sequence and of its topics as they are created
+pub struct TicketNotifications {
+    pub locator: types::SequenceLocator,
+    /// Only the notifications at least as severe are streamed
+    pub min_severity: types::NotificationSeverity,
+}
+
+/// Ticket streaming the data of a topic encoded as a Parquet file
+pub struct TicketTopicExport {
+    pub locator: types::TopicLocator,
+    /// Optional timestamp range used to limit the exported data
+    pub timestamp_range: Option<TimestampRange>,
+    /// Maximum number of rows of the row groups of the file
+    pub row_group_size: Option<usize>,
+    /// If true the metadata of the topic is written in the key/value metadata of the file
+    pub include_metadata: bool,
+}
+
+/// Ticket streaming the data of several topics time-merged in a single stream
+pub struct TicketReplay {
+    /// Replayed topics, the channel id of each topic is its position in the list
+    pub topics: Vec<types::TopicLocator>,
+    /// Optional timestamp range used to limit the replayed data
+    pub timestamp_range: Option<TimestampRange>,
+}
+
+/// Data served by a DoGet request
+pub enum Ticket {
+    Topic(TicketTopic),
+    SqlQuery(TicketSqlQuery),
+    Notifications(TicketNotifications),
+    TopicExport(TicketTopicExport),
+    Replay(TicketReplay),
 }
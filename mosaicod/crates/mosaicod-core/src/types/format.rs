@@ -39,6 +39,14 @@ impl Format {
             Format::Image => "image",
         }
     }
+
+    /// Returns true if topics using this format hold media streams (e.g. camera frames).
+    ///
+    /// Media topics are indexed by time, so that clients can seek the stream without
+    /// downloading whole data files.
+    pub fn is_media(&self) -> bool {
+        matches!(self, Format::Image)
+    }
 }
 
 impl std::str::FromStr for Format {
@@ -0,0 +1,144 @@
+use super::{Timestamp, TimestampRange};
+
+/// Contiguous byte range of a data file containing all the samples recorded in a
+/// time interval.
+///
+/// A segment can be fetched independently from the rest of the file, allowing
+/// clients to seek a media stream without downloading whole data files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaSegment {
+    /// Path of the data file containing the segment, relative to the topic root
+    pub file: String,
+    /// Timestamp of the first sample in the segment
+    pub start: Timestamp,
+    /// Timestamp of the last sample in the segment
+    pub end: Timestamp,
+    /// Offset (in bytes) of the segment from the beginning of the file
+    pub offset: u64,
+    /// Size (in bytes) of the segment
+    pub length: u64,
+    /// Number of samples stored in the segment
+    pub row_count: u64,
+}
+
+impl MediaSegment {
+    /// Returns true if the segment contains samples overlapping the provided range
+    pub fn overlaps(&self, range: &TimestampRange) -> bool {
+        self.start <= range.end && self.end >= range.start
+    }
+}
+
+/// Time index of a media topic, mapping timestamps to the [`MediaSegment`]s
+/// containing them. Segments are kept sorted by their start timestamp.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaIndex {
+    segments: Vec<MediaSegment>,
+}
+
+impl MediaIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a segment to the index
+    pub fn push(&mut self, segment: MediaSegment) {
+        let position = self.segments.partition_point(|s| s.start <= segment.start);
+        self.segments.insert(position, segment);
+    }
+
+    pub fn segments(&self) -> &[MediaSegment] {
+        &self.segments
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Returns the segment to read when seeking to `timestamp`.
+    ///
+    /// This is the latest segment starting at or before `timestamp` or, if the timestamp
+    /// precedes the whole stream, the first segment of the index.
+    pub fn seek(&self, timestamp: Timestamp) -> Option<&MediaSegment> {
+        let position = self.segments.partition_point(|s| s.start <= timestamp);
+        self.segments.get(position.saturating_sub(1))
+    }
+
+    /// Returns the segments containing samples in the provided timestamp range
+    pub fn range(&self, range: &TimestampRange) -> impl Iterator<Item = &MediaSegment> {
+        self.segments.iter().filter(move |s| s.overlaps(range))
+    }
+}
+
+impl From<Vec<MediaSegment>> for MediaIndex {
+    fn from(value: Vec<MediaSegment>) -> Self {
+        let mut index = Self::new();
+        value.into_iter().for_each(|segment| index.push(segment));
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(file: &str, start: i64, end: i64) -> MediaSegment {
+        MediaSegment {
+            file: file.to_owned(),
+            start: start.into(),
+            end: end.into(),
+            offset: 0,
+            length: 0,
+            row_count: 0,
+        }
+    }
+
+    #[test]
+    fn segments_are_sorted() {
+        let index = MediaIndex::from(vec![
+            segment("b", 20, 29),
+            segment("c", 30, 39),
+            segment("a", 10, 19),
+        ]);
+
+        let files: Vec<&str> = index.segments().iter().map(|s| s.file.as_str()).collect();
+        assert_eq!(files, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn seek() {
+        let index = MediaIndex::from(vec![
+            segment("a", 10, 19),
+            segment("b", 20, 29),
+            segment("c", 30, 39),
+        ]);
+
+        assert_eq!(index.seek(5.into()).unwrap().file, "a");
+        assert_eq!(index.seek(10.into()).unwrap().file, "a");
+        assert_eq!(index.seek(25.into()).unwrap().file, "b");
+        assert_eq!(index.seek(30.into()).unwrap().file, "c");
+        assert_eq!(index.seek(100.into()).unwrap().file, "c");
+
+        assert!(MediaIndex::new().seek(10.into()).is_none());
+    }
+
+    #[test]
+    fn range() {
+        let index = MediaIndex::from(vec![
+            segment("a", 10, 19),
+            segment("b", 20, 29),
+            segment("c", 30, 39),
+        ]);
+
+        let files: Vec<&str> = index
+            .range(&TimestampRange::between(15.into(), 25.into()))
+            .map(|s| s.file.as_str())
+            .collect();
+        assert_eq!(files, vec!["a", "b"]);
+
+        assert_eq!(index.range(&TimestampRange::unbounded()).count(), 3);
+        assert_eq!(
+            index.range(&TimestampRange::starting_at(40.into())).count(),
+            0
+        );
+    }
+}
@@ -9,24 +9,75 @@ pub use time::*;
 mod format;
 pub use format::*;
 
+mod dedup;
+pub use dedup::*;
+
 mod notification;
 pub use notification::*;
 
+mod activity;
+pub use activity::*;
+
+mod consumer;
+pub use consumer::*;
+
 mod resources;
 pub use resources::*;
 
+mod store_layout;
+pub use store_layout::*;
+
 mod tokens;
 pub use tokens::*;
 
 mod chunk;
 pub use chunk::*;
 
+mod media;
+pub use media::*;
+
 mod uuid;
 pub use uuid::*;
 
 mod session;
 pub use session::*;
 
+mod catalog;
+pub use catalog::*;
+
+mod archive;
+pub use archive::*;
+
+mod units;
+pub use units::*;
+
+mod scheduled_query;
+pub use scheduled_query::*;
+
+mod query_view;
+pub use query_view::*;
+
+mod external_table;
+pub use external_table::*;
+
+mod query_limits;
+pub use query_limits::*;
+
+mod compaction;
+pub use compaction::*;
+
+mod bandwidth;
+pub use bandwidth::*;
+
+mod redaction;
+pub use redaction::*;
+
+mod anonymization;
+pub use anonymization::*;
+
+mod relay;
+pub use relay::*;
+
 pub mod auth;
 pub use auth::ApiKey;
 pub use auth::ApiKeyError;
@@ -1,13 +1,20 @@
 use super::*;
+use crate::Error;
+use std::collections::BTreeMap;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NotificationType {
     Error,
+    /// Raised by events requiring attention, e.g. a scheduled query whose condition is
+    /// satisfied
+    Alert,
 }
 
 impl std::fmt::Display for NotificationType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Error => write!(f, "error"),
+            Self::Alert => write!(f, "alert"),
         }
     }
 }
@@ -18,6 +25,7 @@ impl std::str::FromStr for NotificationType {
     fn from_str(value: &str) -> Result<Self, Self::Err> {
         match value {
             "error" => Ok(Self::Error),
+            "alert" => Ok(Self::Alert),
             _ => Err(std::io::Error::other(format!(
                 "unknown notification type `{}`",
                 value
@@ -26,10 +34,306 @@ impl std::str::FromStr for NotificationType {
     }
 }
 
+impl NotificationType {
+    /// Severity of the notifications of this type created without an explicit one.
+    pub fn default_severity(&self) -> NotificationSeverity {
+        match self {
+            Self::Error => NotificationSeverity::Error,
+            Self::Alert => NotificationSeverity::Warning,
+        }
+    }
+}
+
+/// Severity of a notification, ordered from the least to the most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NotificationSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for NotificationSeverity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Info => write!(f, "info"),
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl std::str::FromStr for NotificationSeverity {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "info" => Ok(Self::Info),
+            "warning" => Ok(Self::Warning),
+            "error" => Ok(Self::Error),
+            _ => Err(Error::bad_request(format!(
+                "unknown notification severity `{value}`, expected `info`, `warning` or `error`"
+            ))),
+        }
+    }
+}
+
+/// Maximum length of a notification code.
+const MAX_CODE_LENGTH: usize = 64;
+
+/// Structured content of a notification.
+///
+/// The `code` identifies the kind of event and `params` the values describing it, so that
+/// clients can localize or aggregate the notifications and automated consumers can react
+/// to the code instead of parsing the rendered text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotificationPayload {
+    pub code: String,
+    pub params: BTreeMap<String, String>,
+}
+
+impl NotificationPayload {
+    /// Code of the notifications carrying only a free text message.
+    pub const MESSAGE: &str = "message";
+
+    /// Code of the notifications raised by a scheduled query whose condition is satisfied.
+    pub const SCHEDULED_QUERY_TRIGGERED: &str = "scheduled_query_triggered";
+
+    /// Code of the notifications raised by the relay when a relayed locator already exists
+    /// on the central instance.
+    pub const RELAY_CONFLICT: &str = "relay.conflict";
+
+    /// Creates a payload, the code must be made of lowercase ASCII letters, digits,
+    /// underscores and dots, starting with a letter.
+    pub fn try_new(code: String, params: BTreeMap<String, String>) -> Result<Self, Error> {
+        let valid = code.len() <= MAX_CODE_LENGTH
+            && code.starts_with(|c: char| c.is_ascii_lowercase())
+            && code
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '.');
+
+        if !valid {
+            return Err(Error::bad_request(format!(
+                "invalid notification code `{code}`, expected lowercase letters, digits, `_` \
+                 and `.` starting with a letter (max {MAX_CODE_LENGTH} characters)"
+            )));
+        }
+
+        Ok(Self { code, params })
+    }
+
+    /// Payload of a notification carrying only a free text message.
+    pub fn message() -> Self {
+        Self {
+            code: Self::MESSAGE.to_owned(),
+            params: BTreeMap::new(),
+        }
+    }
+
+    /// Payload of the notification raised by the scheduled query `name` matching
+    /// `match_count` topics, satisfying the condition `op value`.
+    pub fn scheduled_query_triggered(name: &str, match_count: u64, op: &str, value: u64) -> Self {
+        Self {
+            code: Self::SCHEDULED_QUERY_TRIGGERED.to_owned(),
+            params: BTreeMap::from([
+                ("name".to_owned(), name.to_owned()),
+                ("match_count".to_owned(), match_count.to_string()),
+                ("op".to_owned(), op.to_owned()),
+                ("value".to_owned(), value.to_string()),
+            ]),
+        }
+    }
+
+    /// Payload of the notification raised by the relay when `locator` already exists on the
+    /// central instance and the conflict is resolved with `policy`. `relayed_as` is the
+    /// central locator receiving the data, if any.
+    pub fn relay_conflict(
+        locator: &str,
+        policy: RelayConflictPolicy,
+        relayed_as: Option<&str>,
+    ) -> Self {
+        let mut params = BTreeMap::from([
+            ("locator".to_owned(), locator.to_owned()),
+            ("policy".to_owned(), policy.to_string()),
+        ]);
+        if let Some(relayed_as) = relayed_as {
+            params.insert("relayed_as".to_owned(), relayed_as.to_owned());
+        }
+
+        Self {
+            code: Self::RELAY_CONFLICT.to_owned(),
+            params,
+        }
+    }
+
+    /// Renders the English text of the payloads generated by the server, returns `None`
+    /// for the other codes.
+    pub fn render(&self) -> Option<String> {
+        let param = |name: &str| self.params.get(name).map(String::as_str).unwrap_or("?");
+
+        match self.code.as_str() {
+            Self::SCHEDULED_QUERY_TRIGGERED => Some(format!(
+                "scheduled query `{}` matched {} topics (condition: {} {})",
+                param("name"),
+                param("match_count"),
+                param("op"),
+                param("value")
+            )),
+            Self::RELAY_CONFLICT => {
+                let resolution = match self.params.get("relayed_as") {
+                    Some(relayed_as) => format!("data relayed to `{}`", relayed_as),
+                    None => "session not relayed".to_owned(),
+                };
+                Some(format!(
+                    "`{}` already exists on the central instance ({}: {})",
+                    param("locator"),
+                    param("policy"),
+                    resolution
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
 pub struct Notification<L: Locator> {
     pub uuid: Uuid,
     pub target: L,
     pub notification_type: NotificationType,
+    pub severity: NotificationSeverity,
+    pub payload: NotificationPayload,
+    /// Rendered text of the notification
     pub msg: Option<String>,
     pub created_at: DateTime,
 }
+
+/// Page of notifications returned by a listing.
+pub struct NotificationPage<L: Locator> {
+    pub notifications: Vec<Notification<L>>,
+    /// Cursor used to fetch the next page, `None` once all the notifications are returned.
+    pub cursor: Option<NotificationCursor>,
+}
+
+/// Criteria selecting the notifications returned by a listing.
+#[derive(Debug, Clone)]
+pub struct NotificationFilter {
+    /// Only the notifications at least as severe are returned
+    pub min_severity: NotificationSeverity,
+    /// Only the notifications created within the range are returned
+    pub created: TimestampRange,
+}
+
+impl Default for NotificationFilter {
+    fn default() -> Self {
+        Self {
+            min_severity: NotificationSeverity::Info,
+            created: TimestampRange::unbounded(),
+        }
+    }
+}
+
+/// Position in a listing of notifications, sorted by creation time, used to fetch the
+/// notifications following a page.
+///
+/// Notifications sharing the same creation time are sorted by uuid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationCursor {
+    pub timestamp: Timestamp,
+    pub uuid: Uuid,
+}
+
+impl std::fmt::Display for NotificationCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.timestamp.as_i64(), self.uuid)
+    }
+}
+
+impl std::str::FromStr for NotificationCursor {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::bad_request(format!("invalid notification cursor `{value}`"));
+
+        let (timestamp, uuid) = value.split_once('.').ok_or_else(invalid)?;
+
+        Ok(Self {
+            timestamp: timestamp.parse::<i64>().map_err(|_| invalid())?.into(),
+            uuid: uuid.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notification_payload() {
+        let params = BTreeMap::from([("column".to_owned(), "speed".to_owned())]);
+        let payload =
+            NotificationPayload::try_new("ingest.missing_column".to_owned(), params).unwrap();
+        assert_eq!(payload.code, "ingest.missing_column");
+        assert!(payload.render().is_none());
+
+        for code in ["", "Upper", "1st", "with space", "dash-ed", &"a".repeat(65)] {
+            assert!(
+                NotificationPayload::try_new(code.to_owned(), BTreeMap::new()).is_err(),
+                "{code}"
+            );
+        }
+
+        let payload = NotificationPayload::scheduled_query_triggered("hot", 3, "gt", 2);
+        assert_eq!(
+            payload.render().unwrap(),
+            "scheduled query `hot` matched 3 topics (condition: gt 2)"
+        );
+        assert_eq!(payload.params["match_count"], "3");
+
+        let payload = NotificationPayload::relay_conflict(
+            "edge@run",
+            RelayConflictPolicy::Rename,
+            Some("edge@run_1"),
+        );
+        assert_eq!(
+            payload.render().unwrap(),
+            "`edge@run` already exists on the central instance (rename: data relayed to `edge@run_1`)"
+        );
+        let payload =
+            NotificationPayload::relay_conflict("edge@run/imu", RelayConflictPolicy::Reject, None);
+        assert_eq!(
+            payload.render().unwrap(),
+            "`edge@run/imu` already exists on the central instance (reject: session not relayed)"
+        );
+    }
+
+    #[test]
+    fn notification_severity() {
+        assert!(NotificationSeverity::Info < NotificationSeverity::Warning);
+        assert!(NotificationSeverity::Warning < NotificationSeverity::Error);
+        assert_eq!(
+            "warning".parse::<NotificationSeverity>().unwrap(),
+            NotificationSeverity::Warning
+        );
+        assert!("critical".parse::<NotificationSeverity>().is_err());
+
+        assert_eq!(
+            NotificationType::Alert.default_severity(),
+            NotificationSeverity::Warning
+        );
+    }
+
+    #[test]
+    fn notification_cursor() {
+        let cursor = NotificationCursor {
+            timestamp: 42.into(),
+            uuid: Uuid::new(),
+        };
+        assert_eq!(
+            cursor.to_string().parse::<NotificationCursor>().unwrap(),
+            cursor
+        );
+
+        for value in ["", "42", "x.y", "42.not-a-uuid"] {
+            assert!(value.parse::<NotificationCursor>().is_err(), "{value}");
+        }
+    }
+}
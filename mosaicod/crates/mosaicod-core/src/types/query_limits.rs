@@ -0,0 +1,129 @@
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum QueryLimitsError {
+    #[error("bad query limit `{0}`, expected `name=value`")]
+    BadLimit(String),
+    #[error("unknown query limit `{0}`")]
+    UnknownLimit(String),
+}
+
+/// Resources a single query is allowed to use.
+///
+/// Limits are parsed from a comma separated list of `name=value` pairs, e.g.
+/// `scanned_bytes=1000000000,output_rows=10000,memory=500000000`, where:
+/// * `scanned_bytes` is the total size (in bytes) of the data files searched by the query
+/// * `output_rows` is the number of topics returned by the query
+/// * `memory` is the memory (in bytes) used by the query engine while searching the data files
+///
+/// A limit set to 0 is disabled, while a limit not set at all can be inherited from another
+/// set of limits (see [`QueryLimits::or`]).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryLimits {
+    pub scanned_bytes: Option<u64>,
+    pub output_rows: Option<u64>,
+    pub memory: Option<u64>,
+}
+
+impl QueryLimits {
+    /// Returns limits without any restriction.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Returns these limits, taking the ones not set from `fallback`.
+    pub fn or(&self, fallback: &QueryLimits) -> Self {
+        Self {
+            scanned_bytes: self.scanned_bytes.or(fallback.scanned_bytes),
+            output_rows: self.output_rows.or(fallback.output_rows),
+            memory: self.memory.or(fallback.memory),
+        }
+    }
+
+    /// Maximum number of bytes scanned, [`None`] if unlimited.
+    pub fn max_scanned_bytes(&self) -> Option<u64> {
+        self.scanned_bytes.filter(|limit| *limit != 0)
+    }
+
+    /// Maximum number of rows returned, [`None`] if unlimited.
+    pub fn max_output_rows(&self) -> Option<u64> {
+        self.output_rows.filter(|limit| *limit != 0)
+    }
+
+    /// Maximum number of bytes of memory used, [`None`] if unlimited.
+    pub fn max_memory(&self) -> Option<u64> {
+        self.memory.filter(|limit| *limit != 0)
+    }
+}
+
+impl FromStr for QueryLimits {
+    type Err = QueryLimitsError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut limits = Self::default();
+
+        for limit in value.split(',').map(str::trim).filter(|l| !l.is_empty()) {
+            let (name, value) = limit
+                .split_once('=')
+                .and_then(|(name, value)| Some((name.trim(), value.trim().parse::<u64>().ok()?)))
+                .ok_or_else(|| QueryLimitsError::BadLimit(limit.to_owned()))?;
+
+            match name {
+                "scanned_bytes" => limits.scanned_bytes = Some(value),
+                "output_rows" => limits.output_rows = Some(value),
+                "memory" => limits.memory = Some(value),
+                _ => return Err(QueryLimitsError::UnknownLimit(name.to_owned())),
+            }
+        }
+
+        Ok(limits)
+    }
+}
+
+impl std::fmt::Display for QueryLimits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let limits: Vec<String> = [
+            ("scanned_bytes", self.scanned_bytes),
+            ("output_rows", self.output_rows),
+            ("memory", self.memory),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some(format!("{name}={}", value?)))
+        .collect();
+
+        write!(f, "{}", limits.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_query_limits() {
+        let limits: QueryLimits = "scanned_bytes=1000, output_rows=0".parse().unwrap();
+        assert_eq!(limits.max_scanned_bytes(), Some(1000));
+        assert_eq!(limits.output_rows, Some(0));
+        assert_eq!(limits.max_output_rows(), None);
+        assert_eq!(limits.max_memory(), None);
+        assert_eq!(limits.to_string(), "scanned_bytes=1000,output_rows=0");
+
+        assert_eq!("".parse::<QueryLimits>().unwrap(), QueryLimits::unlimited());
+
+        assert!("scanned_bytes".parse::<QueryLimits>().is_err());
+        assert!("scanned_bytes=-1".parse::<QueryLimits>().is_err());
+        assert!("rows=10".parse::<QueryLimits>().is_err());
+    }
+
+    #[test]
+    fn query_limits_fallback() {
+        let global: QueryLimits = "scanned_bytes=1000,output_rows=10".parse().unwrap();
+        let class: QueryLimits = "scanned_bytes=0,memory=100".parse().unwrap();
+
+        let limits = class.or(&global);
+        assert_eq!(limits.max_scanned_bytes(), None);
+        assert_eq!(limits.max_output_rows(), Some(10));
+        assert_eq!(limits.max_memory(), Some(100));
+    }
+}
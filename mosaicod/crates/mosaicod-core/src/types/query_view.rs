@@ -0,0 +1,12 @@
+use super::{SequenceLocator, Timestamp};
+
+/// A named query defined within a sequence, searching the topics of that sequence only.
+///
+/// Views are referenced by subsequent queries, that extend them with further filters.
+#[derive(Debug)]
+pub struct QueryView<Q> {
+    pub sequence: SequenceLocator,
+    pub name: String,
+    pub query: Q,
+    pub created_at: Timestamp,
+}
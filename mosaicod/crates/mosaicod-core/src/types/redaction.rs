@@ -0,0 +1,54 @@
+use super::{Timestamp, TimestampRange, TopicLocator, Uuid};
+
+/// Replacement of the values removed from the data of a topic by a redaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedactionMode {
+    /// Values are replaced by nulls
+    Null,
+    /// Values are replaced by their SHA-256 digest, keeping equal values comparable. Only
+    /// string and binary columns can be hashed.
+    Hash,
+}
+
+impl std::fmt::Display for RedactionMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => write!(f, "null"),
+            Self::Hash => write!(f, "hash"),
+        }
+    }
+}
+
+impl std::str::FromStr for RedactionMode {
+    type Err = std::io::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "null" => Ok(Self::Null),
+            "hash" => Ok(Self::Hash),
+            _ => Err(std::io::Error::other(format!(
+                "unknown redaction mode `{}`",
+                value
+            ))),
+        }
+    }
+}
+
+/// Entry of the log of the redactions applied to the data of the topics.
+#[derive(Debug, Clone)]
+pub struct Redaction {
+    pub uuid: Uuid,
+    pub topic: TopicLocator,
+    /// Rows whose timestamp is in the range are redacted
+    pub range: TimestampRange,
+    pub columns: Vec<String>,
+    pub mode: RedactionMode,
+    /// Principal that requested the redaction, if authenticated
+    pub principal: Option<String>,
+    pub reason: Option<String>,
+    /// Number of redacted rows
+    pub row_count: u64,
+    /// Number of data files rewritten
+    pub object_count: u64,
+    pub created_at: Timestamp,
+}
@@ -0,0 +1,136 @@
+/// Resolution of the conflicts between the locators relayed by an edge instance and the
+/// locators created independently on the central instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RelayConflictPolicy {
+    /// The conflicting session is not relayed
+    #[default]
+    Reject,
+    /// The conflicting locator is relayed with a numeric suffix (e.g. `edge@run_1`)
+    Rename,
+    /// The session is added to the existing central sequence, topics that already exist
+    /// are still rejected
+    Merge,
+}
+
+impl std::fmt::Display for RelayConflictPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Reject => write!(f, "reject"),
+            Self::Rename => write!(f, "rename"),
+            Self::Merge => write!(f, "merge"),
+        }
+    }
+}
+
+impl std::str::FromStr for RelayConflictPolicy {
+    type Err = std::io::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "reject" => Ok(Self::Reject),
+            "rename" => Ok(Self::Rename),
+            "merge" => Ok(Self::Merge),
+            _ => Err(std::io::Error::other(format!(
+                "unknown relay conflict policy `{}`",
+                value
+            ))),
+        }
+    }
+}
+
+/// Comma separated list of glob patterns, see [`super::LocatorPattern`] for the syntax,
+/// e.g. `**/camera/*,**/lidar`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatternList(Vec<super::LocatorPattern>);
+
+impl PatternList {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns true if `value` is matched by any of the patterns.
+    pub fn matches(&self, value: &str) -> bool {
+        self.0.iter().any(|pattern| pattern.matches(value))
+    }
+}
+
+impl std::str::FromStr for PatternList {
+    type Err = crate::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Selection of the topics relayed to the central instance, e.g. to keep the raw camera
+/// frames on the edge instance.
+///
+/// A topic is relayed if its locator and its ontology tag match at least one of the
+/// include patterns, when set, and none of the exclude patterns.
+#[derive(Debug, Clone, Default)]
+pub struct RelayTopicFilter {
+    pub include_locators: PatternList,
+    pub exclude_locators: PatternList,
+    pub include_ontology_tags: PatternList,
+    pub exclude_ontology_tags: PatternList,
+}
+
+impl RelayTopicFilter {
+    /// Returns true if the topic at `locator`, tagged with `ontology_tag`, is relayed.
+    pub fn selects(&self, locator: &str, ontology_tag: &str) -> bool {
+        let included =
+            |patterns: &PatternList, value: &str| patterns.is_empty() || patterns.matches(value);
+
+        included(&self.include_locators, locator)
+            && included(&self.include_ontology_tags, ontology_tag)
+            && !self.exclude_locators.matches(locator)
+            && !self.exclude_ontology_tags.matches(ontology_tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relay_topic_filter() {
+        let patterns = |value: &str| value.parse::<PatternList>().unwrap();
+
+        let filter = RelayTopicFilter::default();
+        assert!(filter.selects("run/camera/front", "image"));
+
+        let filter = RelayTopicFilter {
+            exclude_locators: patterns("**/camera/*"),
+            ..Default::default()
+        };
+        assert!(!filter.selects("run/camera/front", "image"));
+        assert!(filter.selects("run/camera", "image"));
+        assert!(filter.selects("run/imu", "imu"));
+
+        let filter = RelayTopicFilter {
+            include_locators: patterns("run/*, other/gps"),
+            exclude_ontology_tags: patterns("image*"),
+            ..Default::default()
+        };
+        assert!(filter.selects("run/imu", "imu"));
+        assert!(filter.selects("other/gps", "gps"));
+        assert!(!filter.selects("other/imu", "imu"));
+        assert!(!filter.selects("run/camera", "image_compressed"));
+
+        let filter = RelayTopicFilter {
+            include_ontology_tags: patterns("imu,gps"),
+            ..Default::default()
+        };
+        assert!(filter.selects("run/front", "gps"));
+        assert!(!filter.selects("run/front", "image"));
+
+        assert!(patterns("").is_empty());
+        assert!("run//camera".parse::<PatternList>().is_err());
+    }
+}
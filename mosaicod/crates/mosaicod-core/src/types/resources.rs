@@ -1,4 +1,4 @@
-use super::{Format, SessionMetadata, TimestampRange, Uuid};
+use super::{DedupPolicy, Format, SessionMetadata, TimestampRange, Uuid};
 use crate::{Error, params, traits, types};
 use std::cmp::PartialEq;
 use std::ops::Deref;
@@ -74,6 +74,11 @@ impl TopicLocator {
         self.timestamp_range = Some(ts);
         self
     }
+
+    /// Returns the topic name, without the sequence prefix.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
 }
 
 impl Locator for TopicLocator {
@@ -153,7 +158,29 @@ impl TopicPathInStore {
 
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
-        Self(Self::generate_random_folder_name())
+        let folder = Self::generate_random_folder_name();
+        Self(
+            types::StoreLayout::default()
+                .path_of(&folder)
+                .unwrap_or(folder),
+        )
+    }
+
+    /// Creates the path of a new root folder for a topic with `ontology_tag`, stored
+    /// according to `layout`.
+    pub fn new_with_layout(layout: types::StoreLayout, ontology_tag: &str) -> Self {
+        let folder = Self::generate_random_folder_name();
+        Self(
+            layout
+                .topic_path_of(&folder, ontology_tag)
+                .unwrap_or(folder),
+        )
+    }
+
+    /// Returns the path of the root folder of a topic with `ontology_tag` in `layout`,
+    /// `None` if the folder is already stored according to `layout`.
+    pub fn relocated(&self, layout: types::StoreLayout, ontology_tag: &str) -> Option<Self> {
+        layout.relocate_topic(&self.0, ontology_tag).map(Self)
     }
 
     pub fn root(&self) -> &path::Path {
@@ -195,10 +222,64 @@ impl TopicPathInStore {
         self.root().join("data")
     }
 
+    /// Return the complete path of the folder containing the data files written by the
+    /// sessions not finalized yet
+    ///
+    /// # Example
+    /// ```txt, ignore
+    /// sequence/my/topic/staging
+    /// ```
+    pub fn staging_folder_path(&self) -> path::PathBuf {
+        self.root().join("staging")
+    }
+
+    /// Return the complete path of the folder containing the data files written by
+    /// `session`, moved to the data folder when the session is finalized
+    ///
+    /// # Example
+    /// ```txt, ignore
+    /// sequence/my/topic/staging/<session uuid>
+    /// ```
+    pub fn session_staging_folder_path(&self, session: &Uuid) -> path::PathBuf {
+        self.staging_folder_path().join(session.to_string())
+    }
+
+    /// Returns true if `path` is a data file written by a session not finalized yet
+    pub fn is_staged(&self, path: impl AsRef<path::Path>) -> bool {
+        path.as_ref().starts_with(self.staging_folder_path())
+    }
+
     /// Return the full path of the metadata file
     pub fn path_metadata(&self) -> path::PathBuf {
         self.root().join("metadata.json")
     }
+
+    /// Return the full path of the time index of media topics
+    pub fn path_media_index(&self) -> path::PathBuf {
+        self.root().join("media_index.json")
+    }
+
+    /// Generates a new unique filename for an offloaded blob.
+    ///
+    /// The filename is the reference stored in place of the blob inside the data files.
+    pub fn blob_file() -> String {
+        format!("{}.{}", ulid::Ulid::new(), params::ext::BLOB)
+    }
+
+    /// Return the complete path of the folder containing all offloaded blobs
+    ///
+    /// # Example
+    /// ```txt, ignore
+    /// sequence/my/topic/blobs
+    /// ```
+    pub fn blobs_folder_path(&self) -> path::PathBuf {
+        self.root().join("blobs")
+    }
+
+    /// Returns the complete path of the blob with the given reference
+    pub fn path_blob(&self, reference: &str) -> path::PathBuf {
+        self.blobs_folder_path().join(reference)
+    }
 }
 
 impl From<String> for TopicPathInStore {
@@ -224,6 +305,30 @@ impl std::fmt::Display for TopicPathInStore {
 pub struct TopicOntologyProperties {
     pub serialization_format: Format,
     pub ontology_tag: String,
+    /// Column by which the data of the topic is required to be sorted, if any
+    pub sort_key: Option<String>,
+    /// Policy used to remove duplicated rows when reading the data of the topic
+    pub dedup_policy: DedupPolicy,
+    /// Column identifying the rows of the topic, if any. Topics declaring a primary key are
+    /// upsert topics: rows uploaded by later sessions replace the ones sharing the same key.
+    pub primary_key: Option<String>,
+}
+
+impl TopicOntologyProperties {
+    /// Returns true if the topic declares a primary key.
+    pub fn is_upsert(&self) -> bool {
+        self.primary_key.is_some()
+    }
+
+    /// Returns the policy used to remove duplicated rows when reading the data of the topic.
+    ///
+    /// Rows of upsert topics are merged on read keeping the last written row for each key.
+    pub fn read_dedup_policy(&self) -> DedupPolicy {
+        match &self.primary_key {
+            Some(key) => DedupPolicy::LastWriteWins(key.clone()),
+            None => self.dedup_policy.clone(),
+        }
+    }
 }
 
 /// Properties defining the data semantic and encoding for a topic.
@@ -316,6 +421,86 @@ pub struct TopicDataInfo {
     pub timestamp_range: TimestampRange,
 }
 
+/// Ingestion watermark of a topic, telling up to which time its data is published.
+#[derive(Debug, Clone, Default)]
+pub struct TopicWatermark {
+    /// Latest timestamp of the data published by the finalized sessions, `None` if no data
+    /// was published yet
+    pub watermark: Option<types::Timestamp>,
+    /// Time the watermark last advanced
+    pub updated_at: Option<types::Timestamp>,
+    /// Number of sessions not finalized yet writing into the topic, which may publish data
+    /// older than the watermark
+    pub pending_sessions: usize,
+}
+
+impl TopicWatermark {
+    /// Returns true if no session is writing into the topic, so that no data older than the
+    /// watermark can be published until a new session starts writing.
+    pub fn is_complete(&self) -> bool {
+        self.pending_sessions == 0
+    }
+}
+
+/// Topic from which the data of a copied topic originates.
+///
+/// The lineage of a topic lists its sources from the nearest, i.e. the topic it was copied
+/// from, to the farthest. Sources are kept in the lineage even once deleted.
+#[derive(Debug, Clone)]
+pub struct TopicLineage {
+    pub locator: TopicLocator,
+    pub uuid: Uuid,
+    /// Time the data was copied from the source
+    pub copied_at: types::Timestamp,
+}
+
+/// Documentation of a column of the topic data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnDescription {
+    /// Name of the top-level column
+    pub name: String,
+    /// What the column values represent
+    pub description: Option<String>,
+    /// Unit of measure of the column values
+    pub unit: Option<String>,
+}
+
+/// Minimum or maximum value of a column.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnValue {
+    Numeric(f64),
+    Textual(String),
+}
+
+/// Statistics about the values of a column of the topic data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStats {
+    /// Name of the column, nested fields are separated by dots
+    pub name: String,
+    /// Number of rows of the topic
+    pub row_count: u64,
+    /// Number of rows with a null value in the column
+    pub null_count: u64,
+    /// Minimum non-null value, missing for columns that can't be ordered
+    pub min: Option<ColumnValue>,
+    /// Maximum non-null value, missing for columns that can't be ordered
+    pub max: Option<ColumnValue>,
+    /// Mean of the non-null values, only available for numeric columns
+    pub mean: Option<f64>,
+    /// Estimate of the number of distinct non-null values
+    pub cardinality: Option<u64>,
+}
+
+impl ColumnStats {
+    /// Returns the fraction of rows with a null value in the column.
+    pub fn null_ratio(&self) -> f64 {
+        if self.row_count == 0 {
+            return 0.0;
+        }
+        self.null_count as f64 / self.row_count as f64
+    }
+}
+
 // ////////////////////////////////////////////////////////////////////////////
 // SESSION
 // ////////////////////////////////////////////////////////////////////////////
@@ -458,7 +643,19 @@ pub struct SequencePathInStore(String);
 impl SequencePathInStore {
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
-        Self(Self::generate_random_folder_name())
+        Self::new_with_layout(types::StoreLayout::default())
+    }
+
+    /// Creates the path of a new root folder, stored according to `layout`.
+    pub fn new_with_layout(layout: types::StoreLayout) -> Self {
+        let folder = Self::generate_random_folder_name();
+        Self(layout.path_of(&folder).unwrap_or(folder))
+    }
+
+    /// Returns the path of the root folder in `layout`, `None` if the folder is already
+    /// stored according to `layout`.
+    pub fn relocated(&self, layout: types::StoreLayout) -> Option<Self> {
+        layout.relocate(&self.0).map(Self)
     }
 
     pub fn root(&self) -> &path::Path {
@@ -474,6 +671,35 @@ impl SequencePathInStore {
         path
     }
 
+    /// Returns the location of the manifest written when a session of the sequence is
+    /// finalized.
+    ///
+    /// # Example
+    /// ```txt, ignore
+    /// sq_01J.../sessions/0f3c...json
+    /// ```
+    pub fn path_session_manifest(&self, session_uuid: &Uuid) -> path::PathBuf {
+        let mut path = self.root().join("sessions").join(session_uuid.to_string());
+        path.set_extension(params::ext::JSON);
+        path
+    }
+
+    /// Returns the location of the attestation signed when a session of the sequence is
+    /// finalized.
+    ///
+    /// # Example
+    /// ```txt, ignore
+    /// sq_01J.../attestations/0f3c...json
+    /// ```
+    pub fn path_session_attestation(&self, session_uuid: &Uuid) -> path::PathBuf {
+        let mut path = self
+            .root()
+            .join("attestations")
+            .join(session_uuid.to_string());
+        path.set_extension(params::ext::JSON);
+        path
+    }
+
     fn generate_random_folder_name() -> String {
         let id = ulid::Ulid::new();
         format!("sq_{}", id)
@@ -514,7 +740,7 @@ pub struct SequenceMetadata<M> {
 /// Groups a specific sequence with its associated topics and an optional time filter.
 ///
 /// This structure acts as a container to link a [`SequenceLocator`] with multiple [`TopicLocator`]s.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SequenceTopicGroup {
     pub sequence: SequenceLocator,
     pub topics: Vec<TopicLocator>,
@@ -535,7 +761,7 @@ impl SequenceTopicGroup {
 ///
 /// This wrapper facilitates grouped management of topics associated with specific
 /// sequences, ensuring data consistency during complex merge operations.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SequenceTopicGroupSet(Vec<SequenceTopicGroup>);
 
 impl SequenceTopicGroupSet {
@@ -548,6 +774,10 @@ impl SequenceTopicGroupSet {
         Self(Vec::new())
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = &SequenceTopicGroup> {
+        self.0.iter()
+    }
+
     /// Merges two sets of groups by intersecting sequences and joining their topics.
     ///
     /// Only groups present in both `self` and `groups` are retained. Topics within
@@ -608,10 +838,117 @@ impl From<SequenceTopicGroupSet> for Vec<SequenceTopicGroup> {
     }
 }
 
+// ////////////////////////////////////////////////////////////////////////////
+// LOCATOR PATTERN
+// ////////////////////////////////////////////////////////////////////////////
+
+/// Glob pattern matching the locators of sequences or topics (e.g. `my_sequence/*`).
+///
+/// `*` matches any sequence of characters except `/`, `**` any sequence of characters
+/// and `?` a single character except `/`. Patterns containing a `/` match topic locators,
+/// the other ones sequence locators.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocatorPattern(String);
+
+impl LocatorPattern {
+    /// Returns the resources matched by the pattern.
+    pub fn kind(&self) -> ResourceKind {
+        if self.0.contains('/') {
+            ResourceKind::Topic
+        } else {
+            ResourceKind::Sequence
+        }
+    }
+
+    /// Returns the part of the pattern preceding the first wildcard, shared by all the
+    /// matching locators.
+    pub fn prefix(&self) -> &str {
+        let end = self.0.find(['*', '?']).unwrap_or(self.0.len());
+        &self.0[..end]
+    }
+
+    /// Returns true if `locator` is matched by the pattern.
+    pub fn matches(&self, locator: &str) -> bool {
+        glob_match(self.0.as_bytes(), locator.as_bytes())
+    }
+}
+
+fn glob_match(pattern: &[u8], value: &[u8]) -> bool {
+    match pattern {
+        [] => value.is_empty(),
+        [b'*', b'*', rest @ ..] => (0..=value.len()).any(|i| glob_match(rest, &value[i..])),
+        [b'*', rest @ ..] => {
+            let segment = value.iter().position(|&c| c == b'/').unwrap_or(value.len());
+            (0..=segment).any(|i| glob_match(rest, &value[i..]))
+        }
+        [b'?', rest @ ..] => match value {
+            [c, value @ ..] if *c != b'/' => glob_match(rest, value),
+            _ => false,
+        },
+        [p, rest @ ..] => match value {
+            [c, value @ ..] if c == p => glob_match(rest, value),
+            _ => false,
+        },
+    }
+}
+
+impl FromStr for LocatorPattern {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let s = s.trim_start_matches('/');
+
+        let literal = s.replace(['*', '?'], "");
+        if s.is_empty()
+            || s.contains("//")
+            || s.contains("***")
+            || has_invalid_symbols(&literal, Some(&[':']))
+        {
+            return Err(Error::bad_locator(s.to_owned()));
+        }
+
+        Ok(Self(s.to_owned()))
+    }
+}
+
+impl std::fmt::Display for LocatorPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn locator_pattern() {
+        let pattern: LocatorPattern = "my_sequence/*".parse().unwrap();
+        assert_eq!(pattern.kind(), ResourceKind::Topic);
+        assert_eq!(pattern.prefix(), "my_sequence/");
+        assert!(pattern.matches("my_sequence/camera"));
+        assert!(!pattern.matches("my_sequence/camera/front"));
+        assert!(!pattern.matches("my_sequence_2/camera"));
+
+        let pattern: LocatorPattern = "my_sequence/**".parse().unwrap();
+        assert!(pattern.matches("my_sequence/camera/front"));
+
+        let pattern: LocatorPattern = "*/camera/?ront".parse().unwrap();
+        assert_eq!(pattern.prefix(), "");
+        assert!(pattern.matches("run_1/camera/front"));
+        assert!(!pattern.matches("run_1/camera/fr"));
+
+        let pattern: LocatorPattern = "/run_*".parse().unwrap();
+        assert_eq!(pattern.kind(), ResourceKind::Sequence);
+        assert!(pattern.matches("run_1"));
+        assert!(!pattern.matches("run_1/camera"));
+        assert!(!pattern.matches("other"));
+
+        assert!("".parse::<LocatorPattern>().is_err());
+        assert!("seq//topic".parse::<LocatorPattern>().is_err());
+        assert!("seq/top.ic".parse::<LocatorPattern>().is_err());
+    }
+
     #[test]
     fn test_has_invalid_symbols() {
         assert!(has_invalid_symbols("/!\"my/resource/name", None));
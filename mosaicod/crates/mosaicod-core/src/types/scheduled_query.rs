@@ -0,0 +1,344 @@
+use super::Timestamp;
+use chrono::{Datelike, Duration, TimeZone, Timelike, Utc};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ScheduledQueryError {
+    #[error("bad schedule `{0}`: {1}")]
+    BadSchedule(String, String),
+    #[error("unknown condition operator `{0}`")]
+    UnknownConditionOp(String),
+}
+
+/// Maximum number of days searched for the next run of a schedule, enough to reach the
+/// next February 29th.
+const MAX_SEARCHED_DAYS: i64 = 366 * 9;
+
+/// Schedule of a query, expressed as a cron expression.
+///
+/// The expression is made of five fields `minute hour day-of-month month day-of-week`,
+/// evaluated in UTC. Each field is either `*`, a value, a range `a-b` or a list of them
+/// separated by commas, optionally followed by a step (e.g. `*/15` or `0-30/10`). Days of
+/// week go from `0` (Sunday) to `6`, `7` is accepted as Sunday as well.
+///
+/// As in the traditional cron, when both the day of month and the day of week are
+/// restricted a day matches if any of them matches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    expr: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    days_of_month_restricted: bool,
+    days_of_week_restricted: bool,
+}
+
+impl CronSchedule {
+    pub fn try_new(expr: &str) -> Result<Self, ScheduledQueryError> {
+        let bad_schedule = |why: String| ScheduledQueryError::BadSchedule(expr.to_owned(), why);
+
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(bad_schedule(format!(
+                "expected 5 fields, found {}",
+                fields.len()
+            )));
+        };
+
+        let mut days_of_week_bits = parse_field(days_of_week, 0, 7).map_err(&bad_schedule)?;
+        // Sunday can be expressed both as 0 and 7
+        if days_of_week_bits & (1 << 7) != 0 {
+            days_of_week_bits = (days_of_week_bits | 1) & !(1 << 7);
+        }
+
+        let schedule = Self {
+            expr: fields.join(" "),
+            minutes: parse_field(minutes, 0, 59).map_err(&bad_schedule)?,
+            hours: parse_field(hours, 0, 23).map_err(&bad_schedule)?,
+            days_of_month: parse_field(days_of_month, 1, 31).map_err(&bad_schedule)?,
+            months: parse_field(months, 1, 12).map_err(&bad_schedule)?,
+            days_of_week: days_of_week_bits,
+            days_of_month_restricted: !days_of_month.starts_with('*'),
+            days_of_week_restricted: !days_of_week.starts_with('*'),
+        };
+
+        if schedule.next_after(Timestamp::now()).is_none() {
+            return Err(bad_schedule("the schedule never runs".to_owned()));
+        }
+
+        Ok(schedule)
+    }
+
+    /// Returns the first time strictly after `after` matching the schedule, [`None`] if
+    /// the schedule never runs.
+    pub fn next_after(&self, after: Timestamp) -> Option<Timestamp> {
+        let after = Utc.timestamp_nanos(after.as_i64());
+        // Schedules have a resolution of one minute
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+
+        for day in 0..MAX_SEARCHED_DAYS {
+            let date = start.date_naive() + Duration::days(day);
+            if !self.matches_date(&date) {
+                continue;
+            }
+
+            let (first_hour, first_minute) = if day == 0 {
+                (start.hour(), start.minute())
+            } else {
+                (0, 0)
+            };
+
+            for hour in first_hour..24 {
+                if !has_bit(self.hours, hour) {
+                    continue;
+                }
+                let first_minute = if hour == first_hour { first_minute } else { 0 };
+                if let Some(minute) = (first_minute..60).find(|m| has_bit(self.minutes, *m)) {
+                    let time = date.and_hms_opt(hour, minute, 0)?.and_utc();
+                    return Some(time.into());
+                }
+            }
+        }
+
+        None
+    }
+
+    fn matches_date(&self, date: &chrono::NaiveDate) -> bool {
+        if !has_bit(self.months, date.month()) {
+            return false;
+        }
+
+        let day_of_month = has_bit(self.days_of_month, date.day());
+        let day_of_week = has_bit(self.days_of_week, date.weekday().num_days_from_sunday());
+
+        if self.days_of_month_restricted && self.days_of_week_restricted {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        }
+    }
+}
+
+impl std::fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expr)
+    }
+}
+
+fn has_bit(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+/// Parses a field of a cron expression into a bitset of the allowed values.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut bits = 0;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("bad step `{step}` in `{field}`"))?;
+                if step == 0 {
+                    return Err(format!("step can't be zero in `{field}`"));
+                }
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+
+        let parse_value = |value: &str| -> Result<u32, String> {
+            value
+                .parse::<u32>()
+                .ok()
+                .filter(|value| (min..=max).contains(value))
+                .ok_or_else(|| format!("value `{value}` out of range {min}-{max}"))
+        };
+
+        let (first, last) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((first, last)) => (parse_value(first)?, parse_value(last)?),
+                // A single value with a step runs up to the end of the range
+                None if step.is_some() => (parse_value(range)?, max),
+                None => {
+                    let value = parse_value(range)?;
+                    (value, value)
+                }
+            },
+        };
+
+        if first > last {
+            return Err(format!("bad range `{range}`"));
+        }
+
+        for value in (first..=last).step_by(step.unwrap_or(1) as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Ok(bits)
+}
+
+/// Operator used to compare the number of topics matched by a scheduled query.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConditionOp {
+    Eq,
+    Neq,
+    Gt,
+    Geq,
+    Lt,
+    Leq,
+}
+
+/// Condition on the number of topics matched by a scheduled query, triggering its
+/// notifications when satisfied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchCondition {
+    pub op: ConditionOp,
+    pub value: u64,
+}
+
+impl MatchCondition {
+    /// Builds a condition from the name of its operator (e.g. `$gt`).
+    pub fn try_new(op: &str, value: u64) -> Result<Self, ScheduledQueryError> {
+        let op = match op {
+            "$eq" => ConditionOp::Eq,
+            "$neq" => ConditionOp::Neq,
+            "$gt" => ConditionOp::Gt,
+            "$geq" => ConditionOp::Geq,
+            "$lt" => ConditionOp::Lt,
+            "$leq" => ConditionOp::Leq,
+            _ => return Err(ScheduledQueryError::UnknownConditionOp(op.to_owned())),
+        };
+        Ok(Self { op, value })
+    }
+
+    /// Returns the name of the operator.
+    pub fn op_name(&self) -> &'static str {
+        match self.op {
+            ConditionOp::Eq => "$eq",
+            ConditionOp::Neq => "$neq",
+            ConditionOp::Gt => "$gt",
+            ConditionOp::Geq => "$geq",
+            ConditionOp::Lt => "$lt",
+            ConditionOp::Leq => "$leq",
+        }
+    }
+
+    /// Returns true if `count` matching topics satisfy the condition.
+    pub fn matches(&self, count: u64) -> bool {
+        match self.op {
+            ConditionOp::Eq => count == self.value,
+            ConditionOp::Neq => count != self.value,
+            ConditionOp::Gt => count > self.value,
+            ConditionOp::Geq => count >= self.value,
+            ConditionOp::Lt => count < self.value,
+            ConditionOp::Leq => count <= self.value,
+        }
+    }
+}
+
+impl Default for MatchCondition {
+    /// Satisfied as soon as a topic matches.
+    fn default() -> Self {
+        Self {
+            op: ConditionOp::Gt,
+            value: 0,
+        }
+    }
+}
+
+/// A query evaluated periodically, notifying the matching topics when its condition is
+/// satisfied.
+#[derive(Debug)]
+pub struct ScheduledQuery<Q> {
+    pub name: String,
+    pub schedule: CronSchedule,
+    pub query: Q,
+    pub condition: MatchCondition,
+    pub created_at: Timestamp,
+    pub next_run_at: Timestamp,
+    /// Time of the last evaluation, [`None`] if never evaluated
+    pub last_run_at: Option<Timestamp>,
+    /// Number of topics matched by the last evaluation
+    pub last_match_count: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(datetime: &str) -> Timestamp {
+        chrono::DateTime::parse_from_rfc3339(datetime)
+            .unwrap()
+            .with_timezone(&Utc)
+            .into()
+    }
+
+    fn next(expr: &str, after: &str) -> Option<Timestamp> {
+        CronSchedule::try_new(expr).unwrap().next_after(ts(after))
+    }
+
+    #[test]
+    fn cron_next_run() {
+        assert_eq!(
+            next("* * * * *", "2026-03-10T10:15:30Z"),
+            Some(ts("2026-03-10T10:16:00Z"))
+        );
+        assert_eq!(
+            next("*/15 * * * *", "2026-03-10T10:15:00Z"),
+            Some(ts("2026-03-10T10:30:00Z"))
+        );
+        assert_eq!(
+            next("30 2 * * *", "2026-03-10T10:15:00Z"),
+            Some(ts("2026-03-11T02:30:00Z"))
+        );
+        assert_eq!(
+            next("0 0 1 1,7 *", "2026-03-10T10:15:00Z"),
+            Some(ts("2026-07-01T00:00:00Z"))
+        );
+        // 2026-03-10 is a Tuesday
+        assert_eq!(
+            next("0 9 * * 1-5", "2026-03-13T10:00:00Z"),
+            Some(ts("2026-03-16T09:00:00Z"))
+        );
+        assert_eq!(
+            next("0 0 * * 7", "2026-03-10T10:00:00Z"),
+            Some(ts("2026-03-15T00:00:00Z"))
+        );
+        // Day of month and day of week both restricted, any of them matches
+        assert_eq!(
+            next("0 0 20 * 0", "2026-03-10T10:00:00Z"),
+            Some(ts("2026-03-15T00:00:00Z"))
+        );
+        assert_eq!(
+            next("0 0 29 2 *", "2026-03-10T10:00:00Z"),
+            Some(ts("2028-02-29T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn cron_errors() {
+        assert!(CronSchedule::try_new("* * * *").is_err());
+        assert!(CronSchedule::try_new("60 * * * *").is_err());
+        assert!(CronSchedule::try_new("*/0 * * * *").is_err());
+        assert!(CronSchedule::try_new("10-5 * * * *").is_err());
+        assert!(CronSchedule::try_new("0 0 31 2 *").is_err());
+    }
+
+    #[test]
+    fn match_condition() {
+        let condition = MatchCondition::try_new("$geq", 2).unwrap();
+        assert!(!condition.matches(1));
+        assert!(condition.matches(2));
+        assert_eq!(condition.op_name(), "$geq");
+
+        assert!(MatchCondition::default().matches(1));
+        assert!(!MatchCondition::default().matches(0));
+
+        assert!(MatchCondition::try_new("$between", 2).is_err());
+    }
+}
@@ -5,3 +5,138 @@ pub struct SessionMetadata {
     pub created_at: super::Timestamp,
     pub completed_at: Option<super::Timestamp>,
 }
+
+/// Overview of a session, returned when listing the sessions of a sequence.
+pub struct SessionSummary {
+    pub locator: super::SessionLocator,
+    pub uuid: super::Uuid,
+    pub created_at: super::Timestamp,
+    pub completed_at: Option<super::Timestamp>,
+    /// Number of topics created by the session
+    pub topic_count: usize,
+}
+
+/// Page of session summaries returned by a listing.
+pub struct SessionPage {
+    pub sessions: Vec<SessionSummary>,
+    /// Cursor used to fetch the next page, `None` once all the sessions are returned.
+    pub cursor: Option<SessionCursor>,
+}
+
+/// Position in a listing of sessions, sorted by creation time, used to fetch the sessions
+/// following a page.
+///
+/// Sessions sharing the same creation time are sorted by uuid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionCursor {
+    pub timestamp: super::Timestamp,
+    pub uuid: super::Uuid,
+}
+
+impl std::fmt::Display for SessionCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.timestamp.as_i64(), self.uuid)
+    }
+}
+
+impl std::str::FromStr for SessionCursor {
+    type Err = crate::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || crate::Error::bad_request(format!("invalid session cursor `{value}`"));
+
+        let (timestamp, uuid) = value.split_once('.').ok_or_else(invalid)?;
+
+        Ok(Self {
+            timestamp: timestamp.parse::<i64>().map_err(|_| invalid())?.into(),
+            uuid: uuid.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Self-describing summary of a finalized session, stored alongside the data so that the
+/// content of the store can be interpreted without the database.
+pub struct SessionManifest<M> {
+    pub uuid: super::Uuid,
+    pub locator: super::SessionLocator,
+    pub created_at: super::Timestamp,
+    pub completed_at: super::Timestamp,
+    /// Snapshot of the sequence the session belongs to, taken at finalization
+    pub sequence: SequenceSnapshot<M>,
+    /// Topics created by the session
+    pub topics: Vec<TopicManifest<M>>,
+    /// Existing topics receiving rows from the session
+    pub upserted_topics: Vec<super::TopicLocator>,
+}
+
+/// Snapshot of a sequence stored in a [`SessionManifest`].
+pub struct SequenceSnapshot<M> {
+    pub locator: super::SequenceLocator,
+    pub created_at: super::Timestamp,
+    pub path_in_store: super::SequencePathInStore,
+    pub user_metadata: Option<M>,
+}
+
+/// Description of a topic stored in a [`SessionManifest`].
+pub struct TopicManifest<M> {
+    pub uuid: super::Uuid,
+    pub path_in_store: super::TopicPathInStore,
+    pub metadata: super::TopicMetadata<M>,
+    /// Fields of the schema of the data
+    pub fields: Vec<FieldDescription>,
+    /// Objects stored in the root folder of the topic
+    pub objects: Vec<ObjectDescription>,
+}
+
+/// Field of the schema of a topic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDescription {
+    pub name: String,
+    /// Arrow data type, e.g. `Int64` or `List(Float32)`
+    pub data_type: String,
+    pub nullable: bool,
+}
+
+/// Object stored in the root folder of a topic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectDescription {
+    /// Path relative to the root folder of the topic
+    pub path: String,
+    pub size: u64,
+    /// CRC-32 (IEEE) checksum of the content
+    pub crc32: u32,
+}
+
+/// Statement signed by the server over the manifest of a session, letting consumers verify
+/// that the data has not been altered since it was ingested.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionAttestation {
+    pub session_uuid: super::Uuid,
+    /// SHA-256 digest of the manifest, as written in the store
+    pub manifest_sha256: Vec<u8>,
+    /// Signature algorithm, e.g. `ed25519`
+    pub algorithm: String,
+    /// Public key of the server verifying the signature
+    pub public_key: Vec<u8>,
+    /// Signature of `manifest_sha256`
+    pub signature: Vec<u8>,
+    pub created_at: super::Timestamp,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_cursor() {
+        let cursor = SessionCursor {
+            timestamp: 42.into(),
+            uuid: crate::types::Uuid::new(),
+        };
+        assert_eq!(cursor.to_string().parse::<SessionCursor>().unwrap(), cursor);
+
+        for value in ["", "42", "x.y", "42.not-a-uuid"] {
+            assert!(value.parse::<SessionCursor>().is_err(), "{value}");
+        }
+    }
+}
@@ -0,0 +1,184 @@
+/// Organization of the root folders of the sequences and topics in the store.
+///
+/// Root folders are named after the kind of resource and a ULID (e.g. `tp_<ulid>`), the
+/// layout decides the path under which they are stored. Since the ULID encodes the creation
+/// time of the folder, the path of existing folders can be derived in any layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StoreLayout {
+    /// Root folders at the top level of the store (e.g. `tp_<ulid>`)
+    #[default]
+    Flat,
+    /// Root folders partitioned by the UTC day of their creation (e.g.
+    /// `2026/10/17/tp_<ulid>`)
+    Daily,
+    /// Root folders partitioned in hive-style `key=value` folders by the ontology tag of the
+    /// topics and by the UTC day of their creation (e.g.
+    /// `ontology_tag=imu/date=2026-10-17/tp_<ulid>`, `date=2026-10-17/sq_<ulid>`), exposing
+    /// them as partition columns to the engines reading the store as a dataset (e.g.
+    /// `pyarrow.dataset` or DuckDB)
+    Hive,
+}
+
+impl StoreLayout {
+    /// Returns whether the data files stored in the layout can be replaced with links to
+    /// shared objects (e.g. by the topic copies and the tiering).
+    ///
+    /// Engines reading the [`StoreLayout::Hive`] folders as a dataset match the data files
+    /// by extension, and would skip the link objects.
+    pub fn allows_links(&self) -> bool {
+        !matches!(self, Self::Hive)
+    }
+
+    /// Returns the path of the root folder named `folder` in the layout.
+    ///
+    /// Returns `None` if the name of the folder does not encode its creation time.
+    pub fn path_of(&self, folder: &str) -> Option<String> {
+        self.partitioned_path(folder, None)
+    }
+
+    /// Same as [`StoreLayout::path_of`], for the root folder of a topic with `ontology_tag`.
+    pub fn topic_path_of(&self, folder: &str, ontology_tag: &str) -> Option<String> {
+        self.partitioned_path(folder, Some(ontology_tag))
+    }
+
+    /// Returns the path of the root folder at `path` in the layout, `None` if the folder
+    /// is already stored according to the layout or if its path cannot be derived.
+    pub fn relocate(&self, path: &str) -> Option<String> {
+        let folder = path.rsplit('/').next()?;
+        self.path_of(folder).filter(|relocated| relocated != path)
+    }
+
+    /// Same as [`StoreLayout::relocate`], for the root folder of a topic with `ontology_tag`.
+    pub fn relocate_topic(&self, path: &str, ontology_tag: &str) -> Option<String> {
+        let folder = path.rsplit('/').next()?;
+        self.topic_path_of(folder, ontology_tag)
+            .filter(|relocated| relocated != path)
+    }
+
+    fn partitioned_path(&self, folder: &str, ontology_tag: Option<&str>) -> Option<String> {
+        match self {
+            Self::Flat => Some(folder.to_owned()),
+            Self::Daily => Some(format!(
+                "{}/{}",
+                created_at(folder)?.format("%Y/%m/%d"),
+                folder
+            )),
+            Self::Hive => {
+                let date = format!("date={}", created_at(folder)?.format("%Y-%m-%d"));
+                Some(match ontology_tag {
+                    Some(tag) => format!("ontology_tag={}/{date}/{folder}", partition_value(tag)),
+                    None => format!("{date}/{folder}"),
+                })
+            }
+        }
+    }
+}
+
+/// Escapes `value` to be used as the value of a hive partition folder, the characters other
+/// than ASCII alphanumerics, `_`, `-` and `.` are percent-encoded as expected by the engines
+/// reading the partitions.
+fn partition_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'-' | b'.') {
+            escaped.push(byte as char);
+        } else {
+            escaped.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    escaped
+}
+
+/// Returns the creation time encoded in the ULID of the root folder named `folder`.
+fn created_at(folder: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    let (_, id) = folder.split_once('_')?;
+    let id: ulid::Ulid = id.parse().ok()?;
+    Some(id.datetime().into())
+}
+
+impl std::fmt::Display for StoreLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Flat => write!(f, "flat"),
+            Self::Daily => write!(f, "daily"),
+            Self::Hive => write!(f, "hive"),
+        }
+    }
+}
+
+impl std::str::FromStr for StoreLayout {
+    type Err = std::io::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "flat" => Ok(Self::Flat),
+            "daily" => Ok(Self::Daily),
+            "hive" => Ok(Self::Hive),
+            _ => Err(std::io::Error::other(format!(
+                "unknown store layout `{}`",
+                value
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_layout() {
+        // 2026-10-17T08:00:00Z
+        let id = ulid::Ulid::from_parts(1_792_224_000_000, 42);
+        let folder = format!("tp_{id}");
+        let daily = format!("2026/10/17/{folder}");
+        let hive = format!("date=2026-10-17/{folder}");
+
+        assert_eq!(StoreLayout::Flat.path_of(&folder).unwrap(), folder);
+        assert_eq!(StoreLayout::Daily.path_of(&folder).unwrap(), daily);
+        assert!(StoreLayout::Daily.path_of("tp_invalid").is_none());
+        assert!(StoreLayout::Daily.path_of("folder").is_none());
+        assert_eq!(StoreLayout::Hive.path_of(&folder).unwrap(), hive);
+        assert!(StoreLayout::Hive.path_of("tp_invalid").is_none());
+
+        assert_eq!(StoreLayout::Daily.relocate(&folder).unwrap(), daily);
+        assert_eq!(StoreLayout::Flat.relocate(&daily).unwrap(), folder);
+        assert!(StoreLayout::Daily.relocate(&daily).is_none());
+        assert!(StoreLayout::Flat.relocate(&folder).is_none());
+        assert!(StoreLayout::Daily.relocate("tp_invalid").is_none());
+        assert_eq!(StoreLayout::Hive.relocate(&daily).unwrap(), hive);
+        assert_eq!(StoreLayout::Daily.relocate(&hive).unwrap(), daily);
+        assert!(StoreLayout::Hive.relocate(&hive).is_none());
+
+        // Topics are partitioned by ontology tag in the hive layout only
+        let tagged = format!("ontology_tag=imu/date=2026-10-17/{folder}");
+        assert_eq!(
+            StoreLayout::Hive.topic_path_of(&folder, "imu").unwrap(),
+            tagged
+        );
+        assert_eq!(
+            StoreLayout::Hive
+                .topic_path_of(&folder, "my/tag=1")
+                .unwrap(),
+            format!("ontology_tag=my%2Ftag%3D1/date=2026-10-17/{folder}")
+        );
+        assert_eq!(
+            StoreLayout::Daily.topic_path_of(&folder, "imu").unwrap(),
+            daily
+        );
+        assert_eq!(
+            StoreLayout::Hive.relocate_topic(&hive, "imu").unwrap(),
+            tagged
+        );
+        assert_eq!(
+            StoreLayout::Daily.relocate_topic(&tagged, "imu").unwrap(),
+            daily
+        );
+        assert!(StoreLayout::Hive.relocate_topic(&tagged, "imu").is_none());
+
+        for layout in [StoreLayout::Flat, StoreLayout::Daily, StoreLayout::Hive] {
+            assert_eq!(layout.to_string().parse::<StoreLayout>().unwrap(), layout);
+        }
+        assert!("hourly".parse::<StoreLayout>().is_err());
+    }
+}
@@ -0,0 +1,202 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum UnitError {
+    #[error("unknown unit `{0}`")]
+    UnknownUnit(String),
+    #[error("unit `{0}` can't be converted to `{1}`")]
+    IncompatibleUnits(String, String),
+}
+
+/// Physical quantity measured by a [`Unit`], only units of the same dimension can be
+/// converted into each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    Length,
+    Time,
+    Mass,
+    Speed,
+    Acceleration,
+    AngularVelocity,
+    Angle,
+    Temperature,
+    Pressure,
+    Frequency,
+    Current,
+    Voltage,
+    Power,
+    Ratio,
+}
+
+/// A unit of measure of the units registry.
+///
+/// A value `v` expressed in the unit corresponds to `v * scale + offset` in the reference
+/// unit of its dimension (e.g. meters for lengths).
+#[derive(Debug, PartialEq)]
+pub struct Unit {
+    pub symbol: &'static str,
+    pub dimension: Dimension,
+    scale: f64,
+    offset: f64,
+}
+
+impl Unit {
+    const fn new(symbol: &'static str, dimension: Dimension, scale: f64) -> Self {
+        Self {
+            symbol,
+            dimension,
+            scale,
+            offset: 0.0,
+        }
+    }
+
+    const fn with_offset(mut self, offset: f64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Looks up a unit in the registry by its symbol.
+    pub fn try_from_symbol(symbol: &str) -> Result<&'static Unit, UnitError> {
+        REGISTRY
+            .iter()
+            .find(|unit| unit.symbol == symbol)
+            .ok_or_else(|| UnitError::UnknownUnit(symbol.to_owned()))
+    }
+
+    /// Converts a value expressed in this unit into the `target` unit.
+    pub fn convert(&self, value: f64, target: &Unit) -> Result<f64, UnitError> {
+        Ok(self.converter(target)?(value))
+    }
+
+    /// Returns a function converting values expressed in this unit into the `target` unit.
+    ///
+    /// The conversion is monotonically increasing, so it preserves the ordering of values.
+    pub fn converter(&self, target: &Unit) -> Result<impl Fn(f64) -> f64 + use<>, UnitError> {
+        if self.dimension != target.dimension {
+            return Err(UnitError::IncompatibleUnits(
+                self.symbol.to_owned(),
+                target.symbol.to_owned(),
+            ));
+        }
+
+        let (scale, offset) = (
+            self.scale / target.scale,
+            (self.offset - target.offset) / target.scale,
+        );
+        Ok(move |value| value * scale + offset)
+    }
+}
+
+/// Returns the units known by the platform.
+pub fn units() -> &'static [Unit] {
+    REGISTRY
+}
+
+static REGISTRY: &[Unit] = &[
+    // Length
+    Unit::new("m", Dimension::Length, 1.0),
+    Unit::new("km", Dimension::Length, 1e3),
+    Unit::new("cm", Dimension::Length, 1e-2),
+    Unit::new("mm", Dimension::Length, 1e-3),
+    Unit::new("in", Dimension::Length, 0.0254),
+    Unit::new("ft", Dimension::Length, 0.3048),
+    Unit::new("mi", Dimension::Length, 1609.344),
+    // Time
+    Unit::new("s", Dimension::Time, 1.0),
+    Unit::new("ms", Dimension::Time, 1e-3),
+    Unit::new("us", Dimension::Time, 1e-6),
+    Unit::new("ns", Dimension::Time, 1e-9),
+    Unit::new("min", Dimension::Time, 60.0),
+    Unit::new("h", Dimension::Time, 3600.0),
+    // Mass
+    Unit::new("kg", Dimension::Mass, 1.0),
+    Unit::new("g", Dimension::Mass, 1e-3),
+    Unit::new("lb", Dimension::Mass, 0.45359237),
+    // Speed
+    Unit::new("m/s", Dimension::Speed, 1.0),
+    Unit::new("km/h", Dimension::Speed, 1.0 / 3.6),
+    Unit::new("mph", Dimension::Speed, 0.44704),
+    Unit::new("kn", Dimension::Speed, 1852.0 / 3600.0),
+    // Acceleration
+    Unit::new("m/s^2", Dimension::Acceleration, 1.0),
+    Unit::new("g0", Dimension::Acceleration, 9.80665),
+    // Angular velocity
+    Unit::new("rad/s", Dimension::AngularVelocity, 1.0),
+    Unit::new(
+        "deg/s",
+        Dimension::AngularVelocity,
+        std::f64::consts::PI / 180.0,
+    ),
+    Unit::new(
+        "rpm",
+        Dimension::AngularVelocity,
+        std::f64::consts::PI / 30.0,
+    ),
+    // Angle
+    Unit::new("rad", Dimension::Angle, 1.0),
+    Unit::new("deg", Dimension::Angle, std::f64::consts::PI / 180.0),
+    // Temperature
+    Unit::new("K", Dimension::Temperature, 1.0),
+    Unit::new("degC", Dimension::Temperature, 1.0).with_offset(273.15),
+    Unit::new("degF", Dimension::Temperature, 5.0 / 9.0).with_offset(273.15 - 32.0 * 5.0 / 9.0),
+    // Pressure
+    Unit::new("Pa", Dimension::Pressure, 1.0),
+    Unit::new("kPa", Dimension::Pressure, 1e3),
+    Unit::new("bar", Dimension::Pressure, 1e5),
+    Unit::new("psi", Dimension::Pressure, 6894.757293168),
+    // Frequency
+    Unit::new("Hz", Dimension::Frequency, 1.0),
+    Unit::new("kHz", Dimension::Frequency, 1e3),
+    // Electric
+    Unit::new("A", Dimension::Current, 1.0),
+    Unit::new("mA", Dimension::Current, 1e-3),
+    Unit::new("V", Dimension::Voltage, 1.0),
+    Unit::new("mV", Dimension::Voltage, 1e-3),
+    Unit::new("W", Dimension::Power, 1.0),
+    Unit::new("kW", Dimension::Power, 1e3),
+    // Ratio
+    Unit::new("1", Dimension::Ratio, 1.0),
+    Unit::new("%", Dimension::Ratio, 1e-2),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(value: f64, from: &str, to: &str) -> f64 {
+        let from = Unit::try_from_symbol(from).unwrap();
+        let to = Unit::try_from_symbol(to).unwrap();
+        from.convert(value, to).unwrap()
+    }
+
+    #[test]
+    fn conversions() {
+        assert!((convert(36.0, "km/h", "m/s") - 10.0).abs() < 1e-9);
+        assert!((convert(10.0, "m/s", "km/h") - 36.0).abs() < 1e-9);
+        assert!((convert(100.0, "degC", "degF") - 212.0).abs() < 1e-9);
+        assert!((convert(0.0, "degC", "K") - 273.15).abs() < 1e-9);
+        assert!((convert(1.0, "mi", "km") - 1.609344).abs() < 1e-9);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            Unit::try_from_symbol("furlong"),
+            Err(UnitError::UnknownUnit(_))
+        ));
+
+        let meters = Unit::try_from_symbol("m").unwrap();
+        let seconds = Unit::try_from_symbol("s").unwrap();
+        assert!(matches!(
+            meters.convert(1.0, seconds),
+            Err(UnitError::IncompatibleUnits(_, _))
+        ));
+    }
+
+    #[test]
+    fn unique_symbols() {
+        for (i, unit) in units().iter().enumerate() {
+            assert!(units()[i + 1..].iter().all(|u| u.symbol != unit.symbol));
+        }
+    }
+}
@@ -29,6 +29,19 @@ impl Uuid {
     pub fn non_hyphened_string(&self) -> String {
         self.0.simple().to_string()
     }
+
+    /// Compares two UUIDs in constant time.
+    ///
+    /// Used when the UUID acts as a secret key, so that the time taken does not reveal how
+    /// many bytes of a guessed key are correct.
+    pub fn ct_eq(&self, other: &Self) -> bool {
+        self.0
+            .as_bytes()
+            .iter()
+            .zip(other.0.as_bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+    }
 }
 
 impl AsRef<uuid::Uuid> for Uuid {
@@ -61,3 +74,19 @@ impl FromStr for Uuid {
         Ok(Self(uuid::Uuid::try_parse(s).map_err(|e| e.to_string())?))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ct_eq() {
+        let uuid = Uuid::new();
+        assert!(uuid.ct_eq(&uuid.clone()));
+        assert!(!uuid.ct_eq(&Uuid::new()));
+
+        let mut bytes = *uuid.as_ref().as_bytes();
+        bytes[15] ^= 1;
+        assert!(!uuid.ct_eq(&uuid::Uuid::from_bytes(bytes).into()));
+    }
+}
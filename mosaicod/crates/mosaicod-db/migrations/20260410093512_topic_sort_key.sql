@@ -0,0 +1,6 @@
+-- Column by which the topic data is required to be sorted (NULL if no requirement)
+ALTER TABLE topic_t ADD COLUMN sort_key TEXT;
+
+-- Whether the uploaded data turned out sorted by the sort key (or by timestamp when
+-- no sort key is declared). NULL until the topic is finalized.
+ALTER TABLE topic_t ADD COLUMN is_sorted BOOLEAN;
@@ -0,0 +1,5 @@
+-- Policy used to remove duplicated rows when reading the topic data
+ALTER TABLE topic_t ADD COLUMN dedup_policy TEXT NOT NULL DEFAULT 'none';
+
+-- Column identifying duplicated rows, required by the `last_write_wins` policy
+ALTER TABLE topic_t ADD COLUMN dedup_key TEXT;
@@ -0,0 +1,25 @@
+-- Column identifying the rows of upsert topics (NULL for append-only topics)
+ALTER TABLE topic_t ADD COLUMN primary_key TEXT;
+
+-- Uploads of rows into an existing upsert topic by a later session
+CREATE TABLE topic_upsert_t(
+  upsert_id     SERIAL PRIMARY KEY,
+  upsert_uuid   UUID UNIQUE NOT NULL,
+  topic_id      INTEGER NOT NULL,
+  session_id    INTEGER NOT NULL,
+
+  creation_unix_tstamp    BIGINT NOT NULL,
+  -- Set when the upload of data starts
+  upload_unix_tstamp      BIGINT,
+  completion_unix_tstamp  BIGINT,
+
+  CONSTRAINT fk_topic
+      FOREIGN KEY (topic_id)
+      REFERENCES topic_t (topic_id)
+      ON DELETE CASCADE,
+
+  CONSTRAINT fk_session
+      FOREIGN KEY (session_id)
+      REFERENCES session_t (session_id)
+      ON DELETE CASCADE
+);
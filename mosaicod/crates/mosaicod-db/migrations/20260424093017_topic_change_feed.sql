@@ -0,0 +1,5 @@
+-- First data file written by the upsert, set when the upload of data starts
+ALTER TABLE topic_upsert_t ADD COLUMN first_chunk_number BIGINT;
+
+-- Last time the data files of an upsert topic were compacted
+ALTER TABLE topic_t ADD COLUMN compaction_unix_tstamp BIGINT;
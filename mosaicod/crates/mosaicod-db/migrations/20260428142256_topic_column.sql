@@ -0,0 +1,14 @@
+-- Documentation of the columns of a topic
+CREATE TABLE topic_column_t(
+  topic_id      INTEGER NOT NULL,
+  column_name   TEXT NOT NULL,
+  description   TEXT,
+  unit          TEXT,
+
+  PRIMARY KEY (topic_id, column_name),
+
+  CONSTRAINT fk_topic
+      FOREIGN KEY (topic_id)
+      REFERENCES topic_t (topic_id)
+      ON DELETE CASCADE
+);
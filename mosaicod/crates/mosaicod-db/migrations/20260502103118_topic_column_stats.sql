@@ -0,0 +1,20 @@
+-- Statistics of the columns of a topic, computed on request and cached
+CREATE TABLE topic_column_stats_t(
+  topic_id      INTEGER NOT NULL,
+  column_name   TEXT NOT NULL,
+  row_count     BIGINT NOT NULL,
+  null_count    BIGINT NOT NULL,
+  min_numeric   DOUBLE PRECISION,
+  max_numeric   DOUBLE PRECISION,
+  min_textual   TEXT,
+  max_textual   TEXT,
+  mean          DOUBLE PRECISION,
+  cardinality   BIGINT,
+
+  PRIMARY KEY (topic_id, column_name),
+
+  CONSTRAINT fk_topic
+      FOREIGN KEY (topic_id)
+      REFERENCES topic_t (topic_id)
+      ON DELETE CASCADE
+);
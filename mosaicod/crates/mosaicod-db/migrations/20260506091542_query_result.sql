@@ -0,0 +1,26 @@
+-- Results of a query retrieved in pages
+CREATE TABLE query_result_t(
+  query_result_id       SERIAL PRIMARY KEY,
+  query_result_uuid     UUID UNIQUE NOT NULL,
+
+  -- Number of rows already fetched
+  fetched_count         INTEGER NOT NULL,
+
+  -- The result is discarded if not fetched before its expiration
+  expiration_unix_tstamp BIGINT NOT NULL
+);
+
+CREATE TABLE query_result_row_t(
+  query_result_id INTEGER NOT NULL,
+  row_index       INTEGER NOT NULL,
+  locator_name    TEXT NOT NULL,
+  start_index_timestamp BIGINT,
+  end_index_timestamp   BIGINT,
+
+  PRIMARY KEY (query_result_id, row_index),
+
+  CONSTRAINT fk_query_result
+      FOREIGN KEY (query_result_id)
+      REFERENCES query_result_t (query_result_id)
+      ON DELETE CASCADE
+);
@@ -0,0 +1,31 @@
+-- Version of the data searched by queries, increased by every change to it.
+-- A sequence is used instead of a counter row so that concurrent writers never wait
+-- on each other to increase it.
+CREATE SEQUENCE data_version_seq;
+
+CREATE FUNCTION data_version_increase() RETURNS TRIGGER AS $$
+BEGIN
+  PERFORM nextval('data_version_seq');
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER sequence_data_version
+  AFTER INSERT OR UPDATE OR DELETE ON sequence_t
+  FOR EACH STATEMENT EXECUTE FUNCTION data_version_increase();
+
+CREATE TRIGGER session_data_version
+  AFTER INSERT OR UPDATE OR DELETE ON session_t
+  FOR EACH STATEMENT EXECUTE FUNCTION data_version_increase();
+
+CREATE TRIGGER topic_data_version
+  AFTER INSERT OR UPDATE OR DELETE ON topic_t
+  FOR EACH STATEMENT EXECUTE FUNCTION data_version_increase();
+
+CREATE TRIGGER topic_column_data_version
+  AFTER INSERT OR UPDATE OR DELETE ON topic_column_t
+  FOR EACH STATEMENT EXECUTE FUNCTION data_version_increase();
+
+CREATE TRIGGER chunk_data_version
+  AFTER INSERT OR UPDATE OR DELETE ON chunk_t
+  FOR EACH STATEMENT EXECUTE FUNCTION data_version_increase();
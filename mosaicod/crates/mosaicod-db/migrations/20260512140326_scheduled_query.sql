@@ -0,0 +1,20 @@
+-- Queries evaluated periodically, notifying the matching topics when their condition is
+-- satisfied
+CREATE TABLE scheduled_query_t(
+  scheduled_query_id  SERIAL PRIMARY KEY,
+  name                TEXT UNIQUE NOT NULL,
+  -- Cron expression
+  schedule            TEXT NOT NULL,
+  query               JSONB NOT NULL,
+
+  -- Condition on the number of matching topics (e.g. `$gt` 0)
+  condition_op        TEXT NOT NULL,
+  condition_value     BIGINT NOT NULL,
+
+  creation_unix_tstamp  BIGINT NOT NULL,
+  next_run_unix_tstamp  BIGINT NOT NULL,
+  last_run_unix_tstamp  BIGINT,
+  last_match_count      BIGINT
+);
+
+CREATE INDEX scheduled_query_next_run_idx ON scheduled_query_t(next_run_unix_tstamp);
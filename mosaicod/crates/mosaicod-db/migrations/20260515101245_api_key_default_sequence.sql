@@ -0,0 +1,2 @@
+-- Sequence searched by the queries of the key not filtering sequences
+ALTER TABLE api_key_t ADD COLUMN default_sequence TEXT;
@@ -0,0 +1,16 @@
+-- Named queries defined within a sequence, resolvable by subsequent queries
+CREATE TABLE query_view_t(
+  query_view_id         SERIAL PRIMARY KEY,
+  sequence_id           INTEGER NOT NULL, -- Constraint on sequences defined below
+  name                  TEXT NOT NULL,
+  query                 JSONB NOT NULL,
+  creation_unix_tstamp  BIGINT NOT NULL,
+
+  UNIQUE (sequence_id, name),
+
+  -- Views are deleted along with their sequence
+  CONSTRAINT fk_sequence
+    FOREIGN KEY (sequence_id)
+      REFERENCES sequence_t (sequence_id)
+      ON DELETE CASCADE
+);
@@ -0,0 +1,6 @@
+-- Principal (i.e. the fingerprint of the API key) that created the session, NULL if API
+-- keys were disabled
+ALTER TABLE session_t ADD COLUMN owner TEXT;
+
+-- Principals allowed to write into the session besides its owner
+ALTER TABLE session_t ADD COLUMN shared_with TEXT[] NOT NULL DEFAULT '{}';
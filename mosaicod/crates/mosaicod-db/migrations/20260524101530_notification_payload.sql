@@ -0,0 +1,7 @@
+-- Structured payload of the notifications: a machine readable code and the parameters
+-- describing the event. Notifications created before carry only a free text message.
+ALTER TABLE sequence_notification_t ADD COLUMN code TEXT NOT NULL DEFAULT 'message';
+ALTER TABLE sequence_notification_t ADD COLUMN params JSONB NOT NULL DEFAULT '{}';
+
+ALTER TABLE topic_notification_t ADD COLUMN code TEXT NOT NULL DEFAULT 'message';
+ALTER TABLE topic_notification_t ADD COLUMN params JSONB NOT NULL DEFAULT '{}';
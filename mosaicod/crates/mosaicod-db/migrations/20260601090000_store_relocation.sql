@@ -0,0 +1,15 @@
+-- Root folders being moved to a new path in the store, after a change of the store
+-- layout. The record is created before copying the objects and deleted after removing the
+-- source objects, so that an interrupted relocation can be resumed.
+CREATE TABLE store_relocation_t(
+  relocation_id   SERIAL PRIMARY KEY,
+  -- `sequence` or `topic`, no foreign key so that the record outlives the resource
+  resource_kind   TEXT NOT NULL,
+  resource_id     INTEGER NOT NULL,
+  source_path     TEXT NOT NULL,
+  target_path     TEXT NOT NULL,
+
+  creation_unix_tstamp  BIGINT NOT NULL,
+
+  UNIQUE(resource_kind, resource_id)
+);
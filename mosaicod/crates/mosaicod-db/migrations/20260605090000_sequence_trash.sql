@@ -0,0 +1,3 @@
+-- Sequences deleted by users are moved to the trash, hidden from the lookups until they
+-- are restored or purged. NULL if the sequence is not trashed.
+ALTER TABLE sequence_t ADD COLUMN trashed_unix_tstamp BIGINT;
@@ -0,0 +1,3 @@
+-- Sequences under legal hold cannot be deleted, trashed or purged until the hold is
+-- released by an administrator.
+ALTER TABLE sequence_t ADD COLUMN legal_hold BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,33 @@
+-- Log of the redactions applied to the data of the topics. The topic is identified by its
+-- locator, so that entries are kept after the topic is deleted.
+CREATE TABLE redaction_t(
+  redaction_id          SERIAL PRIMARY KEY,
+  redaction_uuid        UUID UNIQUE NOT NULL,
+  topic_locator         TEXT NOT NULL,
+  start_index_timestamp BIGINT NOT NULL,
+  end_index_timestamp   BIGINT NOT NULL,
+  columns               TEXT[] NOT NULL,
+  mode                  TEXT NOT NULL,
+  principal             TEXT,
+  reason                TEXT,
+  row_count             BIGINT NOT NULL,
+  object_count          BIGINT NOT NULL,
+  creation_unix_tstamp  BIGINT NOT NULL
+);
+
+CREATE INDEX redaction_topic_locator_idx ON redaction_t (topic_locator);
+
+-- Entries of the log can only be inserted
+CREATE FUNCTION redaction_immutable() RETURNS TRIGGER AS $$
+BEGIN
+  RAISE EXCEPTION 'redaction log entries can not be modified or deleted';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER redaction_immutable
+  BEFORE UPDATE OR DELETE ON redaction_t
+  FOR EACH ROW EXECUTE FUNCTION redaction_immutable();
+
+CREATE TRIGGER redaction_immutable_truncate
+  BEFORE TRUNCATE ON redaction_t
+  FOR EACH STATEMENT EXECUTE FUNCTION redaction_immutable();
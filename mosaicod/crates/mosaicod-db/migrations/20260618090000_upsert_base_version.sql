@@ -0,0 +1,3 @@
+-- Version of the topic the upserted rows were computed from, set when the client asserts
+-- it. The upload is rejected if other upserts completed in the meantime.
+ALTER TABLE topic_upsert_t ADD COLUMN base_version BIGINT;
@@ -0,0 +1,3 @@
+-- Arrow schema registered when the topic is created, encoded as an Arrow IPC message.
+-- When set, the uploaded data must match it.
+ALTER TABLE topic_t ADD COLUMN arrow_schema BYTEA;
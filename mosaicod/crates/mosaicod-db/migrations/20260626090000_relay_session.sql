@@ -0,0 +1,21 @@
+-- State of the finalized sessions forwarded to the central instance by the relay. Sessions
+-- without a record have not been processed yet.
+CREATE TABLE relay_session_t(
+  session_id    INTEGER PRIMARY KEY,
+
+  -- Session created on the central instance by the last attempt, deleted before retrying
+  remote_locator  TEXT,
+  attempts        INTEGER NOT NULL DEFAULT 0,
+  last_error      TEXT,
+  -- Set when the session can not be relayed without conflicting with the central data,
+  -- rejected sessions are not retried
+  rejected        BOOLEAN NOT NULL DEFAULT FALSE,
+
+  -- UNIX timestamp in nanoseconds of the completed relay, NULL while pending
+  relay_unix_tstamp  BIGINT,
+
+  CONSTRAINT fk_session
+      FOREIGN KEY (session_id)
+      REFERENCES session_t (session_id)
+      ON DELETE CASCADE
+);
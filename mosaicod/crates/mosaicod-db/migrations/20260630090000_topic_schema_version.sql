@@ -0,0 +1,28 @@
+-- Versions of the schema registered for a topic. The schema registered when the topic is
+-- created is version 1, every compatible evolution adds a new version. The current schema
+-- is also kept in `topic_t.arrow_schema`.
+CREATE TABLE topic_schema_t(
+  topic_id      INTEGER NOT NULL,
+  version       INTEGER NOT NULL,
+  -- Schema encoded as an Arrow IPC message
+  arrow_schema  BYTEA   NOT NULL,
+
+  -- UNIX timestamp in nanoseconds
+  creation_unix_tstamp  BIGINT NOT NULL,
+
+  PRIMARY KEY (topic_id, version),
+
+  CONSTRAINT fk_topic
+      FOREIGN KEY (topic_id)
+      REFERENCES topic_t (topic_id)
+      ON DELETE CASCADE
+);
+
+INSERT INTO topic_schema_t (topic_id, version, arrow_schema, creation_unix_tstamp)
+  SELECT topic_id, 1, arrow_schema, creation_unix_tstamp
+  FROM topic_t
+  WHERE arrow_schema IS NOT NULL;
+
+-- Version of the registered schema the chunk was written under, NULL if the topic has no
+-- registered schema or the version is unknown
+ALTER TABLE chunk_t ADD COLUMN schema_version INTEGER;
@@ -0,0 +1,6 @@
+-- Timestamp range of the rows stored in the chunk, used to skip the chunks outside the
+-- time range requested by a read. Both are NULL for chunks written before the range was
+-- tracked, which are always read.
+-- UNIX timestamps in nanoseconds, both inclusive
+ALTER TABLE chunk_t ADD COLUMN start_index_timestamp BIGINT;
+ALTER TABLE chunk_t ADD COLUMN end_index_timestamp BIGINT;
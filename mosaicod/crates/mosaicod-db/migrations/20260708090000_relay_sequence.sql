@@ -0,0 +1,22 @@
+-- Central sequence receiving the sessions of a sequence relayed by this instance, recorded
+-- when the first session is relayed. Later sessions of the sequence are relayed to the same
+-- central sequence, sequences found on the central instance without a record here are
+-- conflicts.
+CREATE TABLE relay_sequence_t(
+  sequence_id     INTEGER PRIMARY KEY,
+
+  -- Locator of the central sequence, NULL for the namespaced locator of the sequence
+  remote_locator  TEXT,
+
+  CONSTRAINT fk_sequence
+      FOREIGN KEY (sequence_id)
+      REFERENCES sequence_t (sequence_id)
+      ON DELETE CASCADE
+);
+
+-- The sequences already relayed use their namespaced locator
+INSERT INTO relay_sequence_t (sequence_id)
+  SELECT DISTINCT session.sequence_id
+  FROM session_t AS session
+  JOIN relay_session_t AS relay ON session.session_id = relay.session_id
+  WHERE relay.relay_unix_tstamp IS NOT NULL OR relay.remote_locator IS NOT NULL;
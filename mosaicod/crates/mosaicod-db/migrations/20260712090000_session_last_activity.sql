@@ -0,0 +1,8 @@
+-- Time of the last upload into the session. Sessions not finalized and inactive for longer
+-- than the session TTL are aborted.
+ALTER TABLE session_t ADD COLUMN last_activity_unix_tstamp BIGINT;
+UPDATE session_t SET last_activity_unix_tstamp = COALESCE(completion_unix_tstamp, creation_unix_tstamp);
+ALTER TABLE session_t ALTER COLUMN last_activity_unix_tstamp SET NOT NULL;
+
+CREATE INDEX session_last_activity_idx ON session_t (last_activity_unix_tstamp)
+    WHERE completion_unix_tstamp IS NULL;
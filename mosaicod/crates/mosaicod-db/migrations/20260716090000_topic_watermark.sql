@@ -0,0 +1,18 @@
+-- Advances of the ingestion watermark of a topic, i.e. the latest timestamp of the data
+-- published by the finalized sessions. A new entry is recorded each time the watermark
+-- moves forward, the current watermark is the latest one.
+CREATE TABLE topic_watermark_t(
+  topic_id                INTEGER NOT NULL,
+  -- Latest timestamp of the published data, in nanoseconds
+  watermark_unix_tstamp   BIGINT  NOT NULL,
+
+  -- UNIX timestamp in nanoseconds
+  creation_unix_tstamp    BIGINT  NOT NULL,
+
+  PRIMARY KEY (topic_id, watermark_unix_tstamp),
+
+  CONSTRAINT fk_topic
+      FOREIGN KEY (topic_id)
+      REFERENCES topic_t (topic_id)
+      ON DELETE CASCADE
+);
@@ -0,0 +1,39 @@
+-- Offsets committed by named downstream consumers, recording the last position they
+-- processed. Offsets only move forward, a consumer resumes from the committed one.
+
+-- Position in the activity feed of a sequence, see `ActivityCursor`
+CREATE TABLE sequence_consumer_offset_t(
+  consumer_name           TEXT    NOT NULL,
+  sequence_id             INTEGER NOT NULL,
+
+  cursor_unix_tstamp      BIGINT  NOT NULL,
+  cursor_skip             BIGINT  NOT NULL,
+
+  -- UNIX timestamp in nanoseconds
+  commit_unix_tstamp      BIGINT  NOT NULL,
+
+  PRIMARY KEY (consumer_name, sequence_id),
+
+  CONSTRAINT fk_sequence
+      FOREIGN KEY (sequence_id)
+      REFERENCES sequence_t (sequence_id)
+      ON DELETE CASCADE
+);
+
+-- Ingestion watermark of a topic processed by the consumer
+CREATE TABLE topic_consumer_offset_t(
+  consumer_name           TEXT    NOT NULL,
+  topic_id                INTEGER NOT NULL,
+
+  watermark_unix_tstamp   BIGINT  NOT NULL,
+
+  -- UNIX timestamp in nanoseconds
+  commit_unix_tstamp      BIGINT  NOT NULL,
+
+  PRIMARY KEY (consumer_name, topic_id),
+
+  CONSTRAINT fk_topic
+      FOREIGN KEY (topic_id)
+      REFERENCES topic_t (topic_id)
+      ON DELETE CASCADE
+);
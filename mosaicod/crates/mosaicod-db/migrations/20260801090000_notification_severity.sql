@@ -0,0 +1,15 @@
+-- Severity of the notifications, used to filter the listings. Notifications created before
+-- take the default severity of their type: `error` for errors, `warning` for alerts.
+ALTER TABLE sequence_notification_t ADD COLUMN severity TEXT NOT NULL DEFAULT 'warning';
+UPDATE sequence_notification_t SET severity = 'error' WHERE notification_type = 'error';
+ALTER TABLE sequence_notification_t ALTER COLUMN severity DROP DEFAULT;
+
+ALTER TABLE topic_notification_t ADD COLUMN severity TEXT NOT NULL DEFAULT 'warning';
+UPDATE topic_notification_t SET severity = 'error' WHERE notification_type = 'error';
+ALTER TABLE topic_notification_t ALTER COLUMN severity DROP DEFAULT;
+
+-- Listings are paged by creation time
+CREATE INDEX sequence_notification_creation_idx
+    ON sequence_notification_t (sequence_id, creation_unix_tstamp, sequence_notification_uuid);
+CREATE INDEX topic_notification_creation_idx
+    ON topic_notification_t (topic_id, creation_unix_tstamp, topic_notification_uuid);
@@ -0,0 +1,22 @@
+-- Lineage of the topics copied from other topics, listing the sources of the data of a
+-- topic from the nearest (depth 1, the topic it was copied from) to the farthest. The
+-- lineage of the source is copied along with its data, so that the links are preserved
+-- when the intermediate topics are deleted.
+CREATE TABLE topic_lineage_t(
+  topic_id                INTEGER NOT NULL,
+  depth                   INTEGER NOT NULL,
+
+  -- Source of the data, not referenced since it can be deleted
+  source_topic_uuid       UUID    NOT NULL,
+  source_locator          TEXT    NOT NULL,
+
+  -- UNIX timestamp in nanoseconds of the copy from the source
+  creation_unix_tstamp    BIGINT  NOT NULL,
+
+  PRIMARY KEY (topic_id, depth),
+
+  CONSTRAINT fk_topic
+      FOREIGN KEY (topic_id)
+      REFERENCES topic_t (topic_id)
+      ON DELETE CASCADE
+);
@@ -0,0 +1,28 @@
+-- Objects of the store shared by the data files of several topics (e.g. a topic and its
+-- copies), linked from the data folders of the topics. The references to an object are
+-- the chunks whose data file is linked to it, the object is deleted from the store once
+-- no chunk references it.
+CREATE TABLE shared_object_t(
+  shared_object_id      SERIAL  PRIMARY KEY,
+  object_path           TEXT    NOT NULL UNIQUE,
+
+  -- UNIX timestamp in nanoseconds
+  creation_unix_tstamp  BIGINT  NOT NULL
+);
+
+CREATE TABLE chunk_shared_object_t(
+  chunk_id              INTEGER PRIMARY KEY,
+  shared_object_id      INTEGER NOT NULL,
+
+  CONSTRAINT fk_chunk
+      FOREIGN KEY (chunk_id)
+      REFERENCES chunk_t (chunk_id)
+      ON DELETE CASCADE,
+
+  -- Referenced objects can not be deleted
+  CONSTRAINT fk_shared_object
+      FOREIGN KEY (shared_object_id)
+      REFERENCES shared_object_t (shared_object_id)
+);
+
+CREATE INDEX chunk_shared_object_idx ON chunk_shared_object_t(shared_object_id);
@@ -0,0 +1,22 @@
+-- Compaction tuning of the upsert topics of a sequence, overriding the server
+-- configuration. Settings left NULL are inherited from the server configuration.
+CREATE TABLE sequence_compaction_policy_t(
+  sequence_id               INTEGER PRIMARY KEY,
+
+  -- Target size (in bytes) of the data files written by a compaction
+  target_object_size_bytes  BIGINT,
+  -- Minimum interval (in seconds) between background compactions, 0 disables them
+  interval_secs             BIGINT,
+  -- Minimum number of data files of a topic compacted in background
+  min_chunks                BIGINT,
+  -- Topics are compacted in background only once not written for this many seconds
+  cold_after_secs           BIGINT,
+
+  -- UNIX timestamp in nanoseconds
+  update_unix_tstamp        BIGINT  NOT NULL,
+
+  CONSTRAINT fk_sequence
+      FOREIGN KEY (sequence_id)
+      REFERENCES sequence_t (sequence_id)
+      ON DELETE CASCADE
+);
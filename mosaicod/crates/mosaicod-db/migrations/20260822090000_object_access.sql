@@ -0,0 +1,10 @@
+-- Reads of the objects of the store, sampled by the daemon and merged periodically. The
+-- read counts are estimated from the sampled reads. Used by the tiering to find the data
+-- files not read recently.
+CREATE TABLE object_access_t(
+  object_path            TEXT    PRIMARY KEY,
+  read_count             BIGINT  NOT NULL,
+
+  -- UNIX timestamp in nanoseconds
+  last_read_unix_tstamp  BIGINT  NOT NULL
+);
@@ -0,0 +1,15 @@
+-- Schema of the SQLite backend, limited to the sequence table of the PostgreSQL
+-- migrations. Types are mapped to the SQLite storage classes: UUIDs are stored as
+-- 16-byte BLOBs, JSON documents as TEXT and booleans as INTEGER.
+CREATE TABLE sequence_t(
+  sequence_id   INTEGER PRIMARY KEY,
+  sequence_uuid BLOB UNIQUE NOT NULL,
+  locator_name  TEXT UNIQUE NOT NULL,
+  user_metadata TEXT,
+
+  path_in_store TEXT NOT NULL,
+
+  creation_unix_tstamp INTEGER NOT NULL,
+  trashed_unix_tstamp  INTEGER,
+  legal_hold           INTEGER NOT NULL DEFAULT FALSE
+);
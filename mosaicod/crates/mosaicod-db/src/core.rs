@@ -5,15 +5,31 @@
 //! methods for interacting with the database. Error handling is unified through the
 //! [`DatabaseError`] enum.
 
-use log::debug;
 use sqlx::Pool;
+use tracing::debug;
 use url::Url;
 
 use super::Error;
 
+// The backend is PostgreSQL unless only the `sqlite` feature is enabled, so that enabling
+// both features (e.g. with `--all-features`) builds the PostgreSQL backend.
+
 /// The concrete database type used throughout this module.
+#[cfg(any(feature = "postgres", not(feature = "sqlite")))]
 pub type DatabaseType = sqlx::Postgres;
 
+/// The concrete database type used throughout this module.
+#[cfg(all(feature = "sqlite", not(feature = "postgres")))]
+pub type DatabaseType = sqlx::Sqlite;
+
+/// Migrations of the database schema.
+#[cfg(any(feature = "postgres", not(feature = "sqlite")))]
+pub static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("./migrations");
+
+/// Migrations of the database schema, kept in parallel with the PostgreSQL ones.
+#[cfg(all(feature = "sqlite", not(feature = "postgres")))]
+pub static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("./migrations_sqlite");
+
 /// If the record has this id is not registered in the database
 pub const UNREGISTERED: i32 = -1;
 
@@ -85,17 +101,42 @@ pub struct Database {
 impl Database {
     pub async fn try_new(config: &Config) -> Result<Self, Error> {
         debug!("creating database connection pool");
-        let pool = sqlx::postgres::PgPoolOptions::new()
-            .max_connections(config.max_connections)
-            .connect(config.db_url.as_str())
-            .await?;
+        let pool = Self::connect(config).await?;
 
         debug!("running migrations");
-        sqlx::migrate!().run(&pool).await?;
+        MIGRATOR.run(&pool).await?;
 
         Ok(Self { pool })
     }
 
+    #[cfg(any(feature = "postgres", not(feature = "sqlite")))]
+    async fn connect(config: &Config) -> Result<Pool<DatabaseType>, Error> {
+        Ok(sqlx::postgres::PgPoolOptions::new()
+            .max_connections(config.max_connections)
+            .connect(config.db_url.as_str())
+            .await?)
+    }
+
+    /// Connects to the SQLite database file of the URL (e.g. `sqlite://mosaico.db`),
+    /// creating it if missing.
+    #[cfg(all(feature = "sqlite", not(feature = "postgres")))]
+    async fn connect(config: &Config) -> Result<Pool<DatabaseType>, Error> {
+        use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+        use std::str::FromStr;
+
+        // WAL lets readers proceed while a transaction writes, foreign keys are enforced
+        // per connection by SQLite
+        let options = SqliteConnectOptions::from_str(config.db_url.as_str())?
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .foreign_keys(true);
+
+        Ok(SqlitePoolOptions::new()
+            .max_connections(config.max_connections)
+            .connect_with(options)
+            .await?)
+    }
+
     /// Builds a transaction.
     ///
     /// This call should be used when performing **write** operations on the
@@ -106,18 +147,77 @@ impl Database {
         })
     }
 
+    /// Builds a read-only transaction whose reads all observe the same snapshot of the
+    /// database.
+    ///
+    /// This call should be used when combining the results of several **read** operations,
+    /// that would otherwise observe the writes committed between them.
+    #[cfg(any(feature = "postgres", not(feature = "sqlite")))]
+    pub async fn snapshot(&self) -> Result<Tx<'_>, Error> {
+        let mut inner = self.pool.begin().await?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+            .execute(&mut *inner)
+            .await?;
+
+        Ok(Tx { inner })
+    }
+
+    /// Builds a read-only transaction whose reads all observe the same snapshot of the
+    /// database.
+    ///
+    /// SQLite transactions are serializable, so a plain transaction already reads from a
+    /// single snapshot.
+    #[cfg(all(feature = "sqlite", not(feature = "postgres")))]
+    pub async fn snapshot(&self) -> Result<Tx<'_>, Error> {
+        self.transaction().await
+    }
+
     /// Returns a connection to perform operations on the database.
     ///
     /// This call should be used when performing **read-only** operations on the database.
     pub fn connection(&self) -> Cx<'_> {
         Cx { inner: &self.pool }
     }
+
+    /// Returns the current time of the database server, failing if it is not reachable.
+    pub async fn now(&self) -> Result<std::time::SystemTime, Error> {
+        #[cfg(any(feature = "postgres", not(feature = "sqlite")))]
+        const NOW: &str = "SELECT EXTRACT(EPOCH FROM clock_timestamp())::float8";
+        #[cfg(all(feature = "sqlite", not(feature = "postgres")))]
+        const NOW: &str = "SELECT (julianday('now') - 2440587.5) * 86400.0";
+
+        let secs: f64 = sqlx::query_scalar(NOW).fetch_one(&self.pool).await?;
+
+        Ok(std::time::UNIX_EPOCH + std::time::Duration::from_secs_f64(secs))
+    }
+
+    /// Returns the descriptions of the migrations known to this version and not yet
+    /// applied to the database.
+    pub async fn pending_migrations(&self) -> Result<Vec<String>, Error> {
+        use sqlx::migrate::Migrate;
+
+        let mut conn = self.pool.acquire().await?;
+        conn.ensure_migrations_table().await?;
+        let applied: std::collections::HashSet<i64> = conn
+            .list_applied_migrations()
+            .await?
+            .into_iter()
+            .map(|migration| migration.version)
+            .collect();
+
+        Ok(MIGRATOR
+            .iter()
+            .filter(|migration| migration.migration_type.is_up_migration())
+            .filter(|migration| !applied.contains(&migration.version))
+            .map(|migration| format!("{} {}", migration.version, migration.description))
+            .collect())
+    }
 }
 
 /// Testing utilities for the database module.
 #[cfg(any(test, feature = "testing"))]
 pub mod testing {
-    pub static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("./migrations");
+    pub use super::MIGRATOR;
 
     use std::ops::Deref;
 
@@ -149,3 +249,43 @@ pub mod testing {
         }
     }
 }
+
+#[cfg(all(test, feature = "sqlite", not(feature = "postgres")))]
+mod tests {
+    use super::*;
+    use crate::{schema, sequence_create, sequence_find_by_locator};
+    use mosaicod_core::types;
+
+    /// Opens a database file the way the daemon does, then runs the ported queries on it.
+    #[sqlx::test]
+    async fn sqlite_database() {
+        let path = std::env::temp_dir().join(format!("mosaicod-{}.db", uuid::Uuid::new_v4()));
+        let config = Config {
+            db_url: Url::parse(&format!("sqlite://{}", path.display())).unwrap(),
+            max_connections: 2,
+        };
+
+        let database = Database::try_new(&config).await.unwrap();
+        assert!(database.pending_migrations().await.unwrap().is_empty());
+        database.now().await.unwrap();
+
+        let locator: types::SequenceLocator = "my_sequence".parse().unwrap();
+        let record = schema::SequenceRecord::new(locator.clone(), "my_sequence".to_owned().into());
+        let mut tx = database.transaction().await.unwrap();
+        let record = sequence_create(&mut tx, &record).await.unwrap();
+        tx.commit().await.unwrap();
+
+        // Records are persisted in the file, opening it again does not rerun the migrations
+        drop(database);
+        let database = Database::try_new(&config).await.unwrap();
+        let mut snapshot = database.snapshot().await.unwrap();
+        let found = sequence_find_by_locator(&mut snapshot, &locator)
+            .await
+            .unwrap();
+        assert_eq!(found.sequence_id, record.sequence_id);
+        snapshot.rollback().await.unwrap();
+
+        database.pool.close().await;
+        std::fs::remove_file(path).unwrap();
+    }
+}
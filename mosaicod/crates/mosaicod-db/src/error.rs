@@ -51,6 +51,35 @@ impl std::fmt::Display for Error {
     }
 }
 
+impl Error {
+    /// Returns true if the error is caused by the database being temporarily unavailable
+    /// (e.g. connection pool exhausted, connection lost or server shutting down), so the
+    /// operation can be retried.
+    pub fn is_transient(&self) -> bool {
+        let Self::BackendError(err) = self else {
+            return false;
+        };
+
+        match err {
+            sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed
+            | sqlx::Error::Io(_) => true,
+            sqlx::Error::Database(err) => err.code().is_some_and(|code| {
+                // Connection exceptions, insufficient resources, server shutting down,
+                // serialization failures and deadlocks
+                code.starts_with("08")
+                    || code.starts_with("53")
+                    || matches!(
+                        code.as_ref(),
+                        "57P01" | "57P02" | "57P03" | "40001" | "40P01"
+                    )
+            }),
+            _ => false,
+        }
+    }
+}
+
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
@@ -103,7 +132,30 @@ impl core::error::PublicError for Error {
         match self {
             Self::NotFound => core::Error::not_found(String::new()),
             Self::AlreadyExists => core::Error::already_exists(String::new()),
+            _ if self.is_transient() => core::Error::unavailable("database".to_owned()),
             _ => core::Error::internal(Some("database failure".to_owned())),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mosaicod_core::error::PublicError;
+
+    #[test]
+    fn transient_errors() {
+        let err: Error = sqlx::Error::PoolTimedOut.into();
+        assert!(err.is_transient());
+        assert!(err.error().is_transient());
+
+        let err: Error = sqlx::Error::Io(std::io::ErrorKind::ConnectionReset.into()).into();
+        assert!(err.is_transient());
+
+        let err: Error = sqlx::Error::ColumnNotFound("id".to_owned()).into();
+        assert!(!err.is_transient());
+        assert!(!err.error().is_transient());
+
+        assert!(!Error::NotFound.is_transient());
+    }
+}
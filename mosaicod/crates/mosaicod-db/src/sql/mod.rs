@@ -5,3 +5,8 @@ pub use schema::*;
 pub mod pg_queries;
 #[cfg(feature = "postgres")]
 pub use pg_queries::*;
+
+#[cfg(all(feature = "sqlite", not(feature = "postgres")))]
+pub mod sqlite_queries;
+#[cfg(all(feature = "sqlite", not(feature = "postgres")))]
+pub use sqlite_queries::*;
@@ -7,6 +7,7 @@ fn convert(record: schema::ApiKeyRecord) -> Result<types::ApiKey, Error> {
         .map_err(|e: types::auth::ApiKeyError| Error::BadData(e.to_string()))
 }
 
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn api_key_create(
     exe: &mut impl AsExec,
     policy: types::ApiKey,
@@ -23,10 +24,11 @@ pub async fn api_key_create(
                 permissions,
                 description,
                 creation_unix_timestamp,
-                expiration_unix_timestamp
+                expiration_unix_timestamp,
+                default_sequence
             )
         VALUES
-            ($1, $2, $3, $4, $5, $6)
+            ($1, $2, $3, $4, $5, $6, $7)
         RETURNING
             *
         "#,
@@ -35,7 +37,8 @@ pub async fn api_key_create(
         key.permissions,
         key.description,
         key.creation_unix_timestamp,
-        key.expiration_unix_timestamp
+        key.expiration_unix_timestamp,
+        key.default_sequence
     )
     .fetch_one(exe.as_exec())
     .await?;
@@ -43,6 +46,7 @@ pub async fn api_key_create(
     convert(res)
 }
 
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn api_key_find_by_fingerprint(
     exe: &mut impl AsExec,
     fingerprint: &str,
@@ -62,6 +66,7 @@ pub async fn api_key_find_by_fingerprint(
     convert(res)
 }
 
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn api_key_delete(exe: &mut impl AsExec, fingerprint: &str) -> Result<(), Error> {
     let res = sqlx::query!(
         "DELETE FROM api_key_t WHERE fingerprint=$1",
@@ -77,10 +82,26 @@ pub async fn api_key_delete(exe: &mut impl AsExec, fingerprint: &str) -> Result<
     Ok(())
 }
 
-pub async fn api_key_find_all(exe: &mut impl AsExec) -> Result<Vec<types::ApiKey>, Error> {
-    let keys = sqlx::query_as!(schema::ApiKeyRecord, "SELECT * FROM api_key_t")
-        .fetch_all(exe.as_exec())
-        .await?;
+/// Return at most `limit` API keys whose fingerprint follows `after`, sorted by fingerprint
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn api_key_find_page(
+    exe: &mut impl AsExec,
+    after: Option<&str>,
+    limit: i64,
+) -> Result<Vec<types::ApiKey>, Error> {
+    let keys = sqlx::query_as!(
+        schema::ApiKeyRecord,
+        r#"
+        SELECT * FROM api_key_t
+        WHERE $1::BYTEA IS NULL OR fingerprint > $1
+        ORDER BY fingerprint
+        LIMIT $2
+        "#,
+        after.map(str::as_bytes),
+        limit
+    )
+    .fetch_all(exe.as_exec())
+    .await?;
 
     let keys = keys
         .into_iter()
@@ -152,7 +152,31 @@ impl query::CompileClause for ChunkQueryBuilder {
                 query::CompiledClause::new(build_clause(clause, &vmin), vec![vmin, vmax])
             }
 
-            query::Op::In(_) => return Err(query::Error::unsupported_op(field.into())),
+            query::Op::In(items) => {
+                // Chunks are selected if their values overlap the range spanned by the items
+                let mut items = items.into_iter().map(Into::<query::Value>::into);
+                let Some(first) = items.next() else {
+                    return Err(query::Error::unsupported_op(field.into()));
+                };
+                let (vmin, vmax) = items.fold((first.clone(), first), |(vmin, vmax), v| {
+                    if v < vmin {
+                        (v, vmax)
+                    } else if v > vmax {
+                        (vmin, v)
+                    } else {
+                        (vmin, vmax)
+                    }
+                });
+                let pmin = self.consume_placeholder();
+                let pmax = self.consume_placeholder();
+                let column_name = column_table_name_by_value(&vmin);
+
+                let clause = format!(
+                    "{column_name} = {field} AND __stats__.min_value <= {pmax} AND __stats__.max_value >= {pmin}"
+                );
+
+                query::CompiledClause::new(build_clause(clause, &vmin), vec![vmin, vmax])
+            }
             query::Op::Match(_) => return Err(query::Error::unsupported_op(field.into())),
         };
 
@@ -0,0 +1,76 @@
+use crate::{Error, core::AsExec, sql::schema};
+use tracing::trace;
+
+/// Sets the compaction policy of a sequence, replacing the current one.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_compaction_policy_upsert(
+    exe: &mut impl AsExec,
+    record: &schema::SequenceCompactionPolicyRecord,
+) -> Result<(), Error> {
+    trace!(
+        "setting compaction policy of sequence with id `{}`",
+        record.sequence_id
+    );
+    sqlx::query!(
+        r#"
+            INSERT INTO sequence_compaction_policy_t
+                (sequence_id, target_object_size_bytes, interval_secs, min_chunks,
+                 cold_after_secs, update_unix_tstamp)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (sequence_id) DO UPDATE SET
+                target_object_size_bytes = EXCLUDED.target_object_size_bytes,
+                interval_secs = EXCLUDED.interval_secs,
+                min_chunks = EXCLUDED.min_chunks,
+                cold_after_secs = EXCLUDED.cold_after_secs,
+                update_unix_tstamp = EXCLUDED.update_unix_tstamp
+    "#,
+        record.sequence_id,
+        record.target_object_size_bytes,
+        record.interval_secs,
+        record.min_chunks,
+        record.cold_after_secs,
+        record.update_unix_tstamp,
+    )
+    .execute(exe.as_exec())
+    .await?;
+    Ok(())
+}
+
+/// Returns the compaction policy of a sequence, `None` if the sequence follows the server
+/// configuration.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_compaction_policy_find(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+) -> Result<Option<schema::SequenceCompactionPolicyRecord>, Error> {
+    trace!(
+        "retrieving compaction policy of sequence with id `{}`",
+        sequence_id
+    );
+    Ok(sqlx::query_as!(
+        schema::SequenceCompactionPolicyRecord,
+        "SELECT * FROM sequence_compaction_policy_t WHERE sequence_id = $1",
+        sequence_id
+    )
+    .fetch_optional(exe.as_exec())
+    .await?)
+}
+
+/// Deletes the compaction policy of a sequence, which then follows the server configuration.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_compaction_policy_delete(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+) -> Result<(), Error> {
+    trace!(
+        "deleting compaction policy of sequence with id `{}`",
+        sequence_id
+    );
+    sqlx::query!(
+        "DELETE FROM sequence_compaction_policy_t WHERE sequence_id = $1",
+        sequence_id
+    )
+    .execute(exe.as_exec())
+    .await?;
+    Ok(())
+}
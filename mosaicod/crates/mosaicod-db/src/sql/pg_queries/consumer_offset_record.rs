@@ -0,0 +1,159 @@
+use crate::{Error, core::AsExec, sql::schema};
+use tracing::trace;
+
+/// Commits the position of a consumer in the activity feed of a sequence, unless the
+/// consumer already committed the same or a later position. Returns the committed record,
+/// `None` if the offset was not moved.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_consumer_offset_try_advance(
+    exe: &mut impl AsExec,
+    record: &schema::SequenceConsumerOffsetRecord,
+) -> Result<Option<schema::SequenceConsumerOffsetRecord>, Error> {
+    trace!(
+        "advancing offset of consumer `{}` on sequence with id `{}`",
+        record.consumer_name, record.sequence_id
+    );
+    Ok(sqlx::query_as!(
+        schema::SequenceConsumerOffsetRecord,
+        r#"
+            INSERT INTO sequence_consumer_offset_t
+                (consumer_name, sequence_id, cursor_unix_tstamp, cursor_skip, commit_unix_tstamp)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (consumer_name, sequence_id) DO UPDATE SET
+                cursor_unix_tstamp = EXCLUDED.cursor_unix_tstamp,
+                cursor_skip = EXCLUDED.cursor_skip,
+                commit_unix_tstamp = EXCLUDED.commit_unix_tstamp
+            WHERE (sequence_consumer_offset_t.cursor_unix_tstamp, sequence_consumer_offset_t.cursor_skip)
+                < (EXCLUDED.cursor_unix_tstamp, EXCLUDED.cursor_skip)
+            RETURNING *
+    "#,
+        record.consumer_name,
+        record.sequence_id,
+        record.cursor_unix_tstamp,
+        record.cursor_skip,
+        record.commit_unix_tstamp,
+    )
+    .fetch_optional(exe.as_exec())
+    .await?)
+}
+
+/// Returns the position committed by a consumer in the activity feed of a sequence.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_consumer_offset_find(
+    exe: &mut impl AsExec,
+    consumer_name: &str,
+    sequence_id: i32,
+) -> Result<Option<schema::SequenceConsumerOffsetRecord>, Error> {
+    trace!(
+        "searching offset of consumer `{}` on sequence with id `{}`",
+        consumer_name, sequence_id
+    );
+    Ok(sqlx::query_as!(
+        schema::SequenceConsumerOffsetRecord,
+        "SELECT * FROM sequence_consumer_offset_t WHERE consumer_name = $1 AND sequence_id = $2",
+        consumer_name,
+        sequence_id
+    )
+    .fetch_optional(exe.as_exec())
+    .await?)
+}
+
+/// Deletes the position committed by a consumer in the activity feed of a sequence.
+/// Returns False if the consumer did not commit any position.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_consumer_offset_delete(
+    exe: &mut impl AsExec,
+    consumer_name: &str,
+    sequence_id: i32,
+) -> Result<bool, Error> {
+    trace!(
+        "deleting offset of consumer `{}` on sequence with id `{}`",
+        consumer_name, sequence_id
+    );
+    let res = sqlx::query!(
+        "DELETE FROM sequence_consumer_offset_t WHERE consumer_name = $1 AND sequence_id = $2",
+        consumer_name,
+        sequence_id
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    Ok(res.rows_affected() != 0)
+}
+
+/// Commits the watermark of a topic processed by a consumer, unless the consumer already
+/// committed the same or a later watermark. Returns the committed record, `None` if the
+/// offset was not moved.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_consumer_offset_try_advance(
+    exe: &mut impl AsExec,
+    record: &schema::TopicConsumerOffsetRecord,
+) -> Result<Option<schema::TopicConsumerOffsetRecord>, Error> {
+    trace!(
+        "advancing offset of consumer `{}` on topic with id `{}`",
+        record.consumer_name, record.topic_id
+    );
+    Ok(sqlx::query_as!(
+        schema::TopicConsumerOffsetRecord,
+        r#"
+            INSERT INTO topic_consumer_offset_t
+                (consumer_name, topic_id, watermark_unix_tstamp, commit_unix_tstamp)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (consumer_name, topic_id) DO UPDATE SET
+                watermark_unix_tstamp = EXCLUDED.watermark_unix_tstamp,
+                commit_unix_tstamp = EXCLUDED.commit_unix_tstamp
+            WHERE topic_consumer_offset_t.watermark_unix_tstamp < EXCLUDED.watermark_unix_tstamp
+            RETURNING *
+    "#,
+        record.consumer_name,
+        record.topic_id,
+        record.watermark_unix_tstamp,
+        record.commit_unix_tstamp,
+    )
+    .fetch_optional(exe.as_exec())
+    .await?)
+}
+
+/// Returns the watermark of a topic committed by a consumer.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_consumer_offset_find(
+    exe: &mut impl AsExec,
+    consumer_name: &str,
+    topic_id: i32,
+) -> Result<Option<schema::TopicConsumerOffsetRecord>, Error> {
+    trace!(
+        "searching offset of consumer `{}` on topic with id `{}`",
+        consumer_name, topic_id
+    );
+    Ok(sqlx::query_as!(
+        schema::TopicConsumerOffsetRecord,
+        "SELECT * FROM topic_consumer_offset_t WHERE consumer_name = $1 AND topic_id = $2",
+        consumer_name,
+        topic_id
+    )
+    .fetch_optional(exe.as_exec())
+    .await?)
+}
+
+/// Deletes the watermark of a topic committed by a consumer. Returns False if the consumer
+/// did not commit any watermark.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_consumer_offset_delete(
+    exe: &mut impl AsExec,
+    consumer_name: &str,
+    topic_id: i32,
+) -> Result<bool, Error> {
+    trace!(
+        "deleting offset of consumer `{}` on topic with id `{}`",
+        consumer_name, topic_id
+    );
+    let res = sqlx::query!(
+        "DELETE FROM topic_consumer_offset_t WHERE consumer_name = $1 AND topic_id = $2",
+        consumer_name,
+        topic_id
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    Ok(res.rows_affected() != 0)
+}
@@ -1,9 +1,10 @@
 use crate::{Error, core::AsExec, sql::schema};
-use log::trace;
 use mosaicod_core::types::{self};
 use mosaicod_query as query;
 use sqlx::{Row, postgres::PgRow};
+use tracing::trace;
 
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn column_get_or_create(
     exec: &mut impl AsExec,
     column_name: &str,
@@ -27,26 +28,173 @@ pub async fn column_get_or_create(
     Ok(res)
 }
 
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn chunk_create(
     exec: &mut impl AsExec,
     chunk: &schema::ChunkRecord,
 ) -> Result<schema::ChunkRecord, Error> {
     let res = sqlx::query_as!(
         schema::ChunkRecord,
-        r#"INSERT INTO chunk_t(chunk_uuid, topic_id, data_file, size_bytes, row_count)
-        VALUES ($1, $2, $3, $4, $5)
+        r#"INSERT INTO chunk_t(chunk_uuid, topic_id, data_file, size_bytes, row_count, schema_version,
+                start_index_timestamp, end_index_timestamp)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
         RETURNING *"#,
         chunk.chunk_uuid,
         chunk.topic_id,
         chunk.data_file,
         chunk.size_bytes,
         chunk.row_count,
+        chunk.schema_version,
+        chunk.start_index_timestamp,
+        chunk.end_index_timestamp,
     )
     .fetch_one(exec.as_exec())
     .await?;
     Ok(res)
 }
 
+/// Deletes the chunks of a topic stored in the given data files, together with their
+/// column statistics.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn chunk_delete_by_data_files(
+    exec: &mut impl AsExec,
+    topic_id: i32,
+    data_files: &[String],
+) -> Result<u64, Error> {
+    trace!(
+        "deleting {} chunks of topic with id `{}`",
+        data_files.len(),
+        topic_id
+    );
+    let res = sqlx::query!(
+        "DELETE FROM chunk_t WHERE topic_id=$1 AND data_file = ANY($2)",
+        topic_id,
+        data_files
+    )
+    .execute(exec.as_exec())
+    .await?;
+    Ok(res.rows_affected())
+}
+
+/// Returns all the chunks of a topic, sorted by data file.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn chunk_find_all_by_topic(
+    exec: &mut impl AsExec,
+    topic_id: i32,
+) -> Result<Vec<schema::ChunkRecord>, Error> {
+    trace!("retrieving chunks of topic with id `{}`", topic_id);
+    let res = sqlx::query_as!(
+        schema::ChunkRecord,
+        "SELECT * FROM chunk_t WHERE topic_id=$1 ORDER BY data_file",
+        topic_id
+    )
+    .fetch_all(exec.as_exec())
+    .await?;
+    Ok(res)
+}
+
+/// Returns the chunks of a topic with rows in `range` (`[start, end)`), sorted by data file.
+/// Chunks without a known timestamp range are always returned.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn chunk_find_all_by_topic_in_range(
+    exec: &mut impl AsExec,
+    topic_id: i32,
+    range: &types::TimestampRange,
+) -> Result<Vec<schema::ChunkRecord>, Error> {
+    trace!(
+        "retrieving chunks of topic with id `{}` in range {}",
+        topic_id, range
+    );
+    let res = sqlx::query_as!(
+        schema::ChunkRecord,
+        r#"
+        SELECT * FROM chunk_t
+        WHERE topic_id=$1
+            AND (start_index_timestamp IS NULL OR start_index_timestamp < $3)
+            AND (end_index_timestamp IS NULL OR end_index_timestamp >= $2)
+        ORDER BY data_file
+        "#,
+        topic_id,
+        range.start.as_i64(),
+        range.end.as_i64(),
+    )
+    .fetch_all(exec.as_exec())
+    .await?;
+    Ok(res)
+}
+
+/// Updates the size of a chunk whose data file was rewritten.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn chunk_update_size(
+    exec: &mut impl AsExec,
+    chunk_id: i32,
+    size_bytes: i64,
+) -> Result<(), Error> {
+    trace!(
+        "updating size of chunk with id `{}` to {} bytes",
+        chunk_id, size_bytes
+    );
+    sqlx::query!(
+        "UPDATE chunk_t SET size_bytes=$2 WHERE chunk_id=$1",
+        chunk_id,
+        size_bytes
+    )
+    .execute(exec.as_exec())
+    .await?;
+    Ok(())
+}
+
+/// Replaces the `source` prefix of the data files of a topic with `target`, returns the
+/// number of chunks updated. Prefixes are expected to end with a path separator.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn chunk_update_data_file_prefix(
+    exec: &mut impl AsExec,
+    topic_id: i32,
+    source: &str,
+    target: &str,
+) -> Result<u64, Error> {
+    trace!(
+        "moving data files of topic with id `{}` from `{}` to `{}`",
+        topic_id, source, target
+    );
+    let res = sqlx::query!(
+        r#"
+        UPDATE chunk_t
+        SET data_file = $3 || substr(data_file, length($2) + 1)
+        WHERE topic_id = $1 AND starts_with(data_file, $2)
+        "#,
+        topic_id,
+        source,
+        target
+    )
+    .execute(exec.as_exec())
+    .await?;
+    Ok(res.rows_affected())
+}
+
+/// Deletes the numeric and textual column statistics of a chunk.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn column_chunk_delete_by_chunk(
+    exec: &mut impl AsExec,
+    chunk_id: i32,
+) -> Result<u64, Error> {
+    trace!("deleting column statistics of chunk with id `{}`", chunk_id);
+    let numeric = sqlx::query!(
+        "DELETE FROM column_chunk_numeric_t WHERE chunk_id=$1",
+        chunk_id
+    )
+    .execute(exec.as_exec())
+    .await?;
+    let textual = sqlx::query!(
+        "DELETE FROM column_chunk_textual_t WHERE chunk_id=$1",
+        chunk_id
+    )
+    .execute(exec.as_exec())
+    .await?;
+    Ok(numeric.rows_affected() + textual.rows_affected())
+}
+
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn column_chunk_textual_create(
     exec: &mut impl AsExec,
     val: &schema::ColumnChunkTextualRecord,
@@ -71,6 +219,7 @@ pub async fn column_chunk_textual_create(
     Ok(res)
 }
 
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn column_chunk_numeric_create(
     exec: &mut impl AsExec,
     val: &schema::ColumnChunkNumericRecord,
@@ -98,6 +247,7 @@ pub async fn column_chunk_numeric_create(
 
 /// Batch insert multiple numeric column chunk stats in a single query.
 /// More efficient than individual inserts when inserting many stats.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn column_chunk_numeric_create_batch(
     exec: &mut impl AsExec,
     values: &[schema::ColumnChunkNumericRecord],
@@ -125,6 +275,7 @@ pub async fn column_chunk_numeric_create_batch(
 
 /// Batch insert multiple textual column chunk stats in a single query.
 /// More efficient than individual inserts when inserting many stats.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn column_chunk_textual_create_batch(
     exec: &mut impl AsExec,
     values: &[schema::ColumnChunkTextualRecord],
@@ -151,6 +302,7 @@ pub async fn column_chunk_textual_create_batch(
 
 /// Returns the list of chunks matching the provided `filter` criteria.
 /// Optionally the query can be fitlered across a list of topics (`on_topics`).
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn chunks_from_filters(
     exec: &mut impl AsExec,
     filter: query::OntologyExprGroup<query::Value>,
@@ -192,10 +344,14 @@ fn cast_chunk_data(row: PgRow) -> Result<schema::ChunkRecord, Error> {
         data_file: row.try_get("data_file")?,
         size_bytes: row.try_get("size_bytes")?,
         row_count: row.try_get("row_count")?,
+        schema_version: row.try_get("schema_version")?,
+        start_index_timestamp: row.try_get("start_index_timestamp")?,
+        end_index_timestamp: row.try_get("end_index_timestamp")?,
     })
 }
 
 /// Returns aggregated size and row count statistics for all chunks belonging to a topic.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn topic_get_stats(
     exec: &mut impl AsExec,
     loc: &types::TopicLocator,
@@ -0,0 +1,19 @@
+use crate::{Error, core::AsExec};
+
+/// Returns the version of the data searched by queries.
+///
+/// The version is increased by every change to sequences, sessions, topics and their data
+/// files, so two queries reading the same version search the same data.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn data_version(exe: &mut impl AsExec) -> Result<i64, Error> {
+    let res = sqlx::query_scalar!(
+        r#"
+            SELECT
+                CASE WHEN is_called THEN last_value ELSE 0 END AS "version!"
+            FROM data_version_seq
+    "#
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(res)
+}
@@ -3,6 +3,7 @@ use crate::{Error, core::AsExec, sql::schema};
 use mosaicod_core::types;
 use std::collections::HashMap;
 
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn sequences_group_from_topics(
     exe: &mut impl AsExec,
     topics: impl Iterator<Item = &schema::TopicRecord>,
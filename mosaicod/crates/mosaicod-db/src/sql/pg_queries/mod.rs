@@ -4,12 +4,30 @@ pub use sequence_record::*;
 mod topic_record;
 pub use topic_record::*;
 
+mod topic_upsert_record;
+pub use topic_upsert_record::*;
+
+mod topic_column_record;
+pub use topic_column_record::*;
+
 mod notifications;
 pub use notifications::*;
 
 mod data_catalog;
 pub use data_catalog::*;
 
+mod data_version;
+pub use data_version::*;
+
+mod scheduled_query_record;
+pub use scheduled_query_record::*;
+
+mod query_view_record;
+pub use query_view_record::*;
+
+mod query_result_record;
+pub use query_result_record::*;
+
 mod session_record;
 pub use session_record::*;
 
@@ -22,5 +40,44 @@ use compilers::*;
 mod api_key_record;
 pub use api_key_record::*;
 
+mod store_relocation_record;
+pub use store_relocation_record::*;
+
+mod redaction_record;
+pub use redaction_record::*;
+
+mod relay_session_record;
+pub use relay_session_record::*;
+
+mod topic_schema_record;
+pub use topic_schema_record::*;
+
+mod topic_watermark_record;
+pub use topic_watermark_record::*;
+
+mod topic_lineage_record;
+pub use topic_lineage_record::*;
+
+mod consumer_offset_record;
+pub use consumer_offset_record::*;
+
+mod shared_object_record;
+pub use shared_object_record::*;
+
+mod compaction_policy_record;
+pub use compaction_policy_record::*;
+
+mod object_access_record;
+pub use object_access_record::*;
+
 mod builders;
 use builders::*;
+
+/// Returns a `LIKE` pattern matching the values starting with `prefix`.
+fn like_prefix(prefix: &str) -> String {
+    let escaped = prefix
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("{escaped}%")
+}
@@ -1,8 +1,9 @@
 use crate::{Error, core::AsExec, sql::schema};
-use log::trace;
 use mosaicod_core::types;
+use tracing::trace;
 
 /// Creates a new notification associated with a topic
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn topic_notification_create(
     exe: &mut impl AsExec,
     notification: &schema::TopicNotificationRecord,
@@ -12,9 +13,9 @@ pub async fn topic_notification_create(
         schema::TopicNotificationRecord,
         r#"
             INSERT INTO topic_notification_t
-                (topic_notification_uuid, topic_id, notification_type, msg, creation_unix_tstamp)
+                (topic_notification_uuid, topic_id, notification_type, msg, creation_unix_tstamp, code, params, severity)
             VALUES 
-                ($1, $2, $3, $4, $5)
+                ($1, $2, $3, $4, $5, $6, $7, $8)
             RETURNING 
                 *
     "#,
@@ -23,6 +24,9 @@ pub async fn topic_notification_create(
         notification.notification_type,
         notification.msg,
         notification.creation_unix_tstamp,
+        notification.code,
+        notification.params,
+        notification.severity,
     )
     .fetch_one(exe.as_exec())
     .await?;
@@ -30,6 +34,7 @@ pub async fn topic_notification_create(
 }
 
 /// Find all notifications associated with a topic name
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn topic_notifications_find_by_locator(
     exe: &mut impl AsExec,
     loc: &types::TopicLocator,
@@ -49,9 +54,47 @@ pub async fn topic_notifications_find_by_locator(
     Ok(res)
 }
 
+/// Returns at most `limit` notifications of the topic matching `filter` and following
+/// `after`, sorted by creation time.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_notifications_find_page(
+    exe: &mut impl AsExec,
+    topic_id: i32,
+    filter: &types::NotificationFilter,
+    after: Option<&types::NotificationCursor>,
+    limit: i64,
+) -> Result<Vec<schema::TopicNotificationRecord>, Error> {
+    trace!(
+        "retrieving {} notifications of topic with id `{}` after {:?}",
+        limit, topic_id, after
+    );
+    let res = sqlx::query_as!(
+        schema::TopicNotificationRecord,
+        r#"
+          SELECT * FROM topic_notification_t
+          WHERE topic_id=$1 AND severity = ANY($2)
+            AND creation_unix_tstamp BETWEEN $3 AND $4
+            AND ($5::BIGINT IS NULL OR (creation_unix_tstamp, topic_notification_uuid) > ($5, $6))
+          ORDER BY creation_unix_tstamp, topic_notification_uuid
+          LIMIT $7
+    "#,
+        topic_id,
+        &severities(filter) as &[String],
+        filter.created.start.as_i64(),
+        filter.created.end.as_i64(),
+        after.map(|cursor| cursor.timestamp.as_i64()),
+        after.map(|cursor| uuid::Uuid::from(cursor.uuid.clone())),
+        limit,
+    )
+    .fetch_all(exe.as_exec())
+    .await?;
+    Ok(res)
+}
+
 /// Deletes a sequence notification from the database
 ///
 /// If the notification does not exist, the operation has no effect.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn topic_notification_delete(exe: &mut impl AsExec, id: i32) -> Result<(), Error> {
     trace!("deleting topic report `{}`", id);
     sqlx::query!(
@@ -63,6 +106,7 @@ pub async fn topic_notification_delete(exe: &mut impl AsExec, id: i32) -> Result
     Ok(())
 }
 
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn sequence_notification_create(
     exe: &mut impl AsExec,
     notification: &schema::SequenceNotificationRecord,
@@ -72,9 +116,9 @@ pub async fn sequence_notification_create(
         schema::SequenceNotificationRecord,
         r#"
             INSERT INTO sequence_notification_t
-                (sequence_notification_uuid, sequence_id, notification_type, msg, creation_unix_tstamp)
+                (sequence_notification_uuid, sequence_id, notification_type, msg, creation_unix_tstamp, code, params, severity)
             VALUES 
-                ($1, $2, $3, $4, $5)
+                ($1, $2, $3, $4, $5, $6, $7, $8)
             RETURNING 
                 *
     "#,
@@ -83,6 +127,9 @@ pub async fn sequence_notification_create(
         notification.notification_type,
         notification.msg,
         notification.creation_unix_tstamp,
+        notification.code,
+        notification.params,
+        notification.severity,
     )
     .fetch_one(exe.as_exec())
     .await?;
@@ -90,6 +137,7 @@ pub async fn sequence_notification_create(
 }
 
 /// Find all reports associated to the sequence with the given name
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn sequence_notifications_find_by_name(
     exe: &mut impl AsExec,
     loc: &types::SequenceLocator,
@@ -110,6 +158,7 @@ pub async fn sequence_notifications_find_by_name(
 }
 
 /// Find all reports associated to the sequence with the given id
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn sequence_notifications_find_by_sequence_id(
     exe: &mut impl AsExec,
     sequence_id: i32,
@@ -132,9 +181,47 @@ pub async fn sequence_notifications_find_by_sequence_id(
     Ok(res)
 }
 
+/// Returns at most `limit` notifications of the sequence matching `filter` and following
+/// `after`, sorted by creation time.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_notifications_find_page(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+    filter: &types::NotificationFilter,
+    after: Option<&types::NotificationCursor>,
+    limit: i64,
+) -> Result<Vec<schema::SequenceNotificationRecord>, Error> {
+    trace!(
+        "retrieving {} notifications of sequence with id `{}` after {:?}",
+        limit, sequence_id, after
+    );
+    let res = sqlx::query_as!(
+        schema::SequenceNotificationRecord,
+        r#"
+          SELECT * FROM sequence_notification_t
+          WHERE sequence_id=$1 AND severity = ANY($2)
+            AND creation_unix_tstamp BETWEEN $3 AND $4
+            AND ($5::BIGINT IS NULL OR (creation_unix_tstamp, sequence_notification_uuid) > ($5, $6))
+          ORDER BY creation_unix_tstamp, sequence_notification_uuid
+          LIMIT $7
+    "#,
+        sequence_id,
+        &severities(filter) as &[String],
+        filter.created.start.as_i64(),
+        filter.created.end.as_i64(),
+        after.map(|cursor| cursor.timestamp.as_i64()),
+        after.map(|cursor| uuid::Uuid::from(cursor.uuid.clone())),
+        limit,
+    )
+    .fetch_all(exe.as_exec())
+    .await?;
+    Ok(res)
+}
+
 /// Deletes a sequence report from the database
 ///
 /// If the report does not exist, the operation has no effect.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn sequence_notification_delete(exe: &mut impl AsExec, id: i32) -> Result<(), Error> {
     trace!("deleting sequence notification `{}`", id);
     sqlx::query!(
@@ -145,3 +232,16 @@ pub async fn sequence_notification_delete(exe: &mut impl AsExec, id: i32) -> Res
     .await?;
     Ok(())
 }
+
+/// Returns the severities selected by the filter, as stored in the notification records.
+fn severities(filter: &types::NotificationFilter) -> Vec<String> {
+    [
+        types::NotificationSeverity::Info,
+        types::NotificationSeverity::Warning,
+        types::NotificationSeverity::Error,
+    ]
+    .into_iter()
+    .filter(|severity| *severity >= filter.min_severity)
+    .map(|severity| severity.to_string())
+    .collect()
+}
@@ -0,0 +1,225 @@
+use crate::{Error, core::AsExec, sql::schema};
+use tracing::trace;
+
+/// Maximum number of records merged by a single statement, bounded by the number of bind
+/// parameters of a statement.
+const MERGE_BATCH_SIZE: usize = 10_000;
+
+/// Adds the reads in `records` to the reads recorded for their objects.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn object_access_merge(
+    exe: &mut impl AsExec,
+    records: &[schema::ObjectAccessRecord],
+) -> Result<(), Error> {
+    trace!("merging the reads of {} objects", records.len());
+
+    for batch in records.chunks(MERGE_BATCH_SIZE) {
+        let mut query_builder: sqlx::QueryBuilder<sqlx::Postgres> = sqlx::QueryBuilder::new(
+            "INSERT INTO object_access_t(object_path, read_count, last_read_unix_tstamp) ",
+        );
+
+        query_builder.push_values(batch, |mut b, record| {
+            b.push_bind(&record.object_path)
+                .push_bind(record.read_count)
+                .push_bind(record.last_read_unix_tstamp);
+        });
+        query_builder.push(
+            " ON CONFLICT (object_path) DO UPDATE SET
+                read_count = object_access_t.read_count + EXCLUDED.read_count,
+                last_read_unix_tstamp = GREATEST(
+                    object_access_t.last_read_unix_tstamp,
+                    EXCLUDED.last_read_unix_tstamp
+                )",
+        );
+
+        query_builder.build().execute(exe.as_exec()).await?;
+    }
+
+    Ok(())
+}
+
+/// Deletes the reads recorded for the object at `object_path`.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn object_access_delete(exe: &mut impl AsExec, object_path: &str) -> Result<(), Error> {
+    trace!("deleting the reads of `{}`", object_path);
+    sqlx::query!(
+        "DELETE FROM object_access_t WHERE object_path = $1",
+        object_path
+    )
+    .execute(exe.as_exec())
+    .await?;
+    Ok(())
+}
+
+/// Deletes the reads recorded for the objects that are neither the data file of a chunk nor
+/// a shared object (e.g. objects deleted since their reads were recorded). Returns the number
+/// of records deleted.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn object_access_delete_unreferenced(exe: &mut impl AsExec) -> Result<u64, Error> {
+    trace!("deleting the reads of unreferenced objects");
+    let res = sqlx::query!(
+        r#"
+            DELETE FROM object_access_t access
+            WHERE NOT EXISTS (
+                SELECT 1 FROM chunk_t chunk WHERE chunk.data_file = access.object_path
+            ) AND NOT EXISTS (
+                SELECT 1 FROM shared_object_t shared WHERE shared.object_path = access.object_path
+            )
+    "#
+    )
+    .execute(exe.as_exec())
+    .await?;
+    Ok(res.rows_affected())
+}
+
+/// Returns at most `limit` data files, sorted by chunk, of the chunks after `after_chunk_id`
+/// that are cold, i.e. not read since `cold_before` and published before it.
+///
+/// Data files already linked to a shared object (shared with the copies of their topic or
+/// already archived) are not returned.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn cold_data_file_find_page(
+    exe: &mut impl AsExec,
+    cold_before: i64,
+    after_chunk_id: Option<i32>,
+    limit: i64,
+) -> Result<Vec<schema::ColdDataFileRecord>, Error> {
+    trace!(
+        "retrieving {} data files cold before {} after chunk {:?}",
+        limit, cold_before, after_chunk_id
+    );
+    Ok(sqlx::query_as!(
+        schema::ColdDataFileRecord,
+        r#"
+            SELECT chunk.chunk_id, chunk.topic_id, chunk.data_file, chunk.size_bytes,
+                COALESCE(access.read_count, 0) AS "read_count!",
+                access.last_read_unix_tstamp AS "last_read_unix_tstamp?"
+            FROM chunk_t chunk
+            JOIN topic_t topic USING (topic_id)
+            LEFT JOIN object_access_t access ON access.object_path = chunk.data_file
+            WHERE chunk.chunk_id > $2
+                AND topic.completion_unix_tstamp < $1
+                AND (access.last_read_unix_tstamp IS NULL OR access.last_read_unix_tstamp < $1)
+                AND NOT EXISTS (
+                    SELECT 1 FROM chunk_shared_object_t shared
+                    WHERE shared.chunk_id = chunk.chunk_id
+                )
+            ORDER BY chunk.chunk_id
+            LIMIT $3
+    "#,
+        cold_before,
+        after_chunk_id.unwrap_or(0),
+        limit,
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Returns the data file of a chunk if it is cold, see [`cold_data_file_find_page`].
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn cold_data_file_find_by_chunk(
+    exe: &mut impl AsExec,
+    chunk_id: i32,
+    cold_before: i64,
+) -> Result<schema::ColdDataFileRecord, Error> {
+    trace!(
+        "retrieving data file of chunk {} if cold before {}",
+        chunk_id, cold_before
+    );
+    Ok(sqlx::query_as!(
+        schema::ColdDataFileRecord,
+        r#"
+            SELECT chunk.chunk_id, chunk.topic_id, chunk.data_file, chunk.size_bytes,
+                COALESCE(access.read_count, 0) AS "read_count!",
+                access.last_read_unix_tstamp AS "last_read_unix_tstamp?"
+            FROM chunk_t chunk
+            JOIN topic_t topic USING (topic_id)
+            LEFT JOIN object_access_t access ON access.object_path = chunk.data_file
+            WHERE chunk.chunk_id = $2
+                AND topic.completion_unix_tstamp < $1
+                AND (access.last_read_unix_tstamp IS NULL OR access.last_read_unix_tstamp < $1)
+                AND NOT EXISTS (
+                    SELECT 1 FROM chunk_shared_object_t shared
+                    WHERE shared.chunk_id = chunk.chunk_id
+                )
+    "#,
+        cold_before,
+        chunk_id,
+    )
+    .fetch_one(exe.as_exec())
+    .await?)
+}
+
+/// Returns the number and the total size (in bytes) of the cold data files, see
+/// [`cold_data_file_find_page`].
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn cold_data_file_summary(
+    exe: &mut impl AsExec,
+    cold_before: i64,
+) -> Result<(u64, u64), Error> {
+    trace!("summarizing data files cold before {}", cold_before);
+    let res = sqlx::query!(
+        r#"
+            SELECT COUNT(*) AS "count!", COALESCE(SUM(chunk.size_bytes), 0)::BIGINT AS "size_bytes!"
+            FROM chunk_t chunk
+            JOIN topic_t topic USING (topic_id)
+            LEFT JOIN object_access_t access ON access.object_path = chunk.data_file
+            WHERE topic.completion_unix_tstamp < $1
+                AND (access.last_read_unix_tstamp IS NULL OR access.last_read_unix_tstamp < $1)
+                AND NOT EXISTS (
+                    SELECT 1 FROM chunk_shared_object_t shared
+                    WHERE shared.chunk_id = chunk.chunk_id
+                )
+    "#,
+        cold_before,
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok((res.count as u64, res.size_bytes as u64))
+}
+
+/// Returns the store usage of the sequences, including the trashed ones, whose locator
+/// starts with `prefix`, sorted by locator.
+///
+/// Data files linked to a shared object whose path starts with `archive_prefix` are in the
+/// archive storage. Data files shared by several topics are accounted to each of them.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_usage_find_by_prefix(
+    exe: &mut impl AsExec,
+    prefix: &str,
+    archive_prefix: &str,
+) -> Result<Vec<schema::SequenceUsageRecord>, Error> {
+    trace!(
+        "retrieving the store usage of sequences starting with `{}`",
+        prefix
+    );
+    Ok(sqlx::query_as!(
+        schema::SequenceUsageRecord,
+        r#"
+            SELECT seq.locator_name AS "locator_name!",
+                seq.creation_unix_tstamp AS "creation_unix_tstamp!",
+                COALESCE(SUM(chunk.size_bytes) FILTER (
+                    WHERE shared.object_path IS NULL OR shared.object_path NOT LIKE $2
+                ), 0)::BIGINT AS "standard_bytes!",
+                COALESCE(SUM(chunk.size_bytes) FILTER (
+                    WHERE shared.object_path LIKE $2
+                ), 0)::BIGINT AS "archived_bytes!",
+                COALESCE(SUM(chunk.size_bytes::FLOAT8 * access.read_count), 0)::FLOAT8
+                    AS "read_bytes!"
+            FROM sequence_t seq
+            LEFT JOIN topic_t topic USING (sequence_id)
+            LEFT JOIN chunk_t chunk USING (topic_id)
+            LEFT JOIN chunk_shared_object_t chunk_shared USING (chunk_id)
+            LEFT JOIN shared_object_t shared USING (shared_object_id)
+            LEFT JOIN object_access_t access
+                ON access.object_path = COALESCE(shared.object_path, chunk.data_file)
+            WHERE seq.locator_name LIKE $1
+            GROUP BY seq.sequence_id
+            ORDER BY seq.locator_name
+    "#,
+        super::like_prefix(prefix),
+        super::like_prefix(archive_prefix),
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
@@ -0,0 +1,178 @@
+use crate::{Error, core::AsExec, sql::schema};
+use mosaicod_core::types;
+use tracing::trace;
+
+/// Maximum number of rows inserted by a single statement, to keep the number of bound
+/// parameters below the limit of the database.
+const ROWS_PER_INSERT: usize = 1000;
+
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn query_result_create(
+    exe: &mut impl AsExec,
+    record: &schema::QueryResultRecord,
+) -> Result<schema::QueryResultRecord, Error> {
+    trace!("creating a new query result record {:?}", record);
+    let res = sqlx::query_as!(
+        schema::QueryResultRecord,
+        r#"
+            INSERT INTO query_result_t
+                (query_result_uuid, fetched_count, expiration_unix_tstamp)
+            VALUES
+                ($1, $2, $3)
+            RETURNING
+                *
+    "#,
+        record.query_result_uuid,
+        record.fetched_count,
+        record.expiration_unix_tstamp,
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(res)
+}
+
+/// Batch insert the rows of a query result.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn query_result_row_create_batch(
+    exe: &mut impl AsExec,
+    values: &[schema::QueryResultRowRecord],
+) -> Result<(), Error> {
+    for values in values.chunks(ROWS_PER_INSERT) {
+        let mut query_builder: sqlx::QueryBuilder<sqlx::Postgres> = sqlx::QueryBuilder::new(
+            "INSERT INTO query_result_row_t(query_result_id, row_index, locator_name, start_index_timestamp, end_index_timestamp) ",
+        );
+
+        query_builder.push_values(values, |mut b, val| {
+            b.push_bind(val.query_result_id)
+                .push_bind(val.row_index)
+                .push_bind(&val.locator_name)
+                .push_bind(val.start_index_timestamp)
+                .push_bind(val.end_index_timestamp);
+        });
+
+        query_builder.build().execute(exe.as_exec()).await?;
+    }
+    Ok(())
+}
+
+/// Find a query result given its UUID, locking it until the end of the transaction.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn query_result_find_by_uuid(
+    exe: &mut impl AsExec,
+    uuid: &types::Uuid,
+) -> Result<schema::QueryResultRecord, Error> {
+    trace!("searching query result by uuid `{}`", uuid);
+    let res = sqlx::query_as!(
+        schema::QueryResultRecord,
+        "SELECT * FROM query_result_t WHERE query_result_uuid=$1 FOR UPDATE",
+        uuid.as_ref()
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(res)
+}
+
+/// Returns up to `limit` rows of a query result, starting from the row at `offset`.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn query_result_find_rows(
+    exe: &mut impl AsExec,
+    query_result_id: i32,
+    offset: usize,
+    limit: usize,
+) -> Result<Vec<schema::QueryResultRowRecord>, Error> {
+    trace!(
+        "searching {} rows of query result with id `{}` from row {}",
+        limit, query_result_id, offset
+    );
+    Ok(sqlx::query_as!(
+        schema::QueryResultRowRecord,
+        r#"
+            SELECT * FROM query_result_row_t
+            WHERE query_result_id = $1 AND row_index >= $2
+            ORDER BY row_index
+            LIMIT $3
+    "#,
+        query_result_id,
+        offset as i32,
+        limit as i64
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Records the number of rows fetched from a query result and postpones its expiration.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn query_result_update_fetched(
+    exe: &mut impl AsExec,
+    query_result_id: i32,
+    fetched_count: usize,
+    expiration: types::Timestamp,
+) -> Result<(), Error> {
+    trace!(
+        "updating fetched rows of query result with id `{}` to {}",
+        query_result_id, fetched_count
+    );
+    sqlx::query!(
+        r#"
+            UPDATE query_result_t
+            SET fetched_count = $2, expiration_unix_tstamp = $3
+            WHERE query_result_id = $1
+    "#,
+        query_result_id,
+        fetched_count as i32,
+        expiration.as_i64()
+    )
+    .execute(exe.as_exec())
+    .await?;
+    Ok(())
+}
+
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn query_result_delete(exe: &mut impl AsExec, query_result_id: i32) -> Result<(), Error> {
+    trace!("deleting query result with id `{}`", query_result_id);
+    sqlx::query!(
+        "DELETE FROM query_result_t WHERE query_result_id=$1",
+        query_result_id
+    )
+    .execute(exe.as_exec())
+    .await?;
+    Ok(())
+}
+
+/// Deletes the query results expired before `now`, returning the number of deleted results.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn query_result_delete_expired(
+    exe: &mut impl AsExec,
+    now: types::Timestamp,
+) -> Result<u64, Error> {
+    trace!("deleting query results expired before {}", now);
+    let res = sqlx::query!(
+        "DELETE FROM query_result_t WHERE expiration_unix_tstamp < $1",
+        now.as_i64()
+    )
+    .execute(exe.as_exec())
+    .await?;
+    Ok(res.rows_affected())
+}
+
+/// Deletes the query results with rows referencing the topic `locator`, returning the number
+/// of deleted results.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn query_result_delete_by_topic(
+    exe: &mut impl AsExec,
+    locator: &types::TopicLocator,
+) -> Result<u64, Error> {
+    trace!("deleting query results referencing topic `{}`", locator);
+    let res = sqlx::query!(
+        r#"
+            DELETE FROM query_result_t
+            WHERE query_result_id IN (
+                SELECT query_result_id FROM query_result_row_t WHERE locator_name = $1
+            )
+    "#,
+        locator.to_string()
+    )
+    .execute(exe.as_exec())
+    .await?;
+    Ok(res.rows_affected())
+}
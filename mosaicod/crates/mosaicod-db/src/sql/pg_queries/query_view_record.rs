@@ -0,0 +1,89 @@
+use crate::{Error, core::AsExec, sql::schema};
+use mosaicod_core::types;
+use tracing::trace;
+
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn query_view_create(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+    name: &str,
+    query: &serde_json::Value,
+    created_at: types::Timestamp,
+) -> Result<schema::QueryViewRecord, Error> {
+    trace!("creating query view `{}` in sequence {}", name, sequence_id);
+    let res = sqlx::query_as!(
+        schema::QueryViewRecord,
+        r#"
+            INSERT INTO query_view_t
+                (sequence_id, name, query, creation_unix_tstamp)
+            VALUES
+                ($1, $2, $3, $4)
+            RETURNING
+                *
+    "#,
+        sequence_id,
+        name,
+        query,
+        created_at.as_i64(),
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+
+    Ok(res)
+}
+
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn query_view_find_by_name(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+    name: &str,
+) -> Result<schema::QueryViewRecord, Error> {
+    let res = sqlx::query_as!(
+        schema::QueryViewRecord,
+        "SELECT * FROM query_view_t WHERE sequence_id = $1 AND name = $2",
+        sequence_id,
+        name
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+
+    Ok(res)
+}
+
+/// Returns the views defined in a sequence, sorted by name.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn query_view_find_all_by_sequence(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+) -> Result<Vec<schema::QueryViewRecord>, Error> {
+    let res = sqlx::query_as!(
+        schema::QueryViewRecord,
+        "SELECT * FROM query_view_t WHERE sequence_id = $1 ORDER BY name",
+        sequence_id
+    )
+    .fetch_all(exe.as_exec())
+    .await?;
+
+    Ok(res)
+}
+
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn query_view_delete(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+    name: &str,
+) -> Result<(), Error> {
+    let res = sqlx::query!(
+        "DELETE FROM query_view_t WHERE sequence_id = $1 AND name = $2",
+        sequence_id,
+        name
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(Error::NotFound);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use crate::{Error, core::AsExec, sql::schema};
+use mosaicod_core::types;
+use tracing::trace;
+
+/// Appends a redaction to the log. Entries of the log can not be modified or deleted.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn redaction_create(
+    exe: &mut impl AsExec,
+    record: &schema::RedactionRecord,
+) -> Result<schema::RedactionRecord, Error> {
+    trace!("recording redaction {:?}", record);
+    let res = sqlx::query_as!(
+        schema::RedactionRecord,
+        r#"
+            INSERT INTO redaction_t
+                (redaction_uuid, topic_locator, start_index_timestamp, end_index_timestamp,
+                 columns, mode, principal, reason, row_count, object_count,
+                 creation_unix_tstamp)
+            VALUES
+                ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            RETURNING
+                *
+    "#,
+        record.redaction_uuid,
+        record.topic_locator,
+        record.start_index_timestamp,
+        record.end_index_timestamp,
+        &record.columns,
+        record.mode,
+        record.principal,
+        record.reason,
+        record.row_count,
+        record.object_count,
+        record.creation_unix_tstamp,
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(res)
+}
+
+/// Returns the redactions applied to the topic, oldest first.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn redaction_find_all_by_topic(
+    exe: &mut impl AsExec,
+    locator: &types::TopicLocator,
+) -> Result<Vec<schema::RedactionRecord>, Error> {
+    trace!("searching redactions of topic `{}`", locator);
+    let res = sqlx::query_as!(
+        schema::RedactionRecord,
+        "SELECT * FROM redaction_t WHERE topic_locator=$1 ORDER BY redaction_id",
+        locator.to_string(),
+    )
+    .fetch_all(exe.as_exec())
+    .await?;
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{DatabaseType, testing};
+    use sqlx::Pool;
+
+    #[sqlx::test]
+    async fn test_redaction_log_is_immutable(pool: Pool<DatabaseType>) -> sqlx::Result<()> {
+        let database = testing::Database::new(pool);
+
+        let redaction = types::Redaction {
+            uuid: types::Uuid::new(),
+            topic: "sequence/topic".parse().unwrap(),
+            range: types::TimestampRange::between(10.into(), 20.into()),
+            columns: vec!["name".to_owned()],
+            mode: types::RedactionMode::Hash,
+            principal: Some("admin".to_owned()),
+            reason: None,
+            row_count: 3,
+            object_count: 1,
+            created_at: types::Timestamp::now(),
+        };
+        redaction_create(&mut database.connection(), &(&redaction).into())
+            .await
+            .unwrap();
+
+        let log = redaction_find_all_by_topic(&mut database.connection(), &redaction.topic)
+            .await
+            .unwrap();
+        assert_eq!(log.len(), 1);
+        let logged: types::Redaction = log[0].clone().try_into().unwrap();
+        assert_eq!(logged.uuid, redaction.uuid);
+        assert_eq!(logged.columns, redaction.columns);
+        assert_eq!(logged.mode, types::RedactionMode::Hash);
+
+        for statement in [
+            "UPDATE redaction_t SET reason='altered'",
+            "DELETE FROM redaction_t",
+            "TRUNCATE redaction_t",
+        ] {
+            let res = sqlx::query(statement)
+                .execute(database.connection().as_exec())
+                .await;
+            assert!(res.is_err(), "{statement} succeeded");
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{Error, core::AsExec, sql::schema};
+use mosaicod_core::types;
+use tracing::trace;
+
+/// Returns at most `limit` finalized sessions not relayed yet, in completion order.
+///
+/// Rejected sessions and the sessions of the sequences in the trash are not returned.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn relay_session_find_pending(
+    exe: &mut impl AsExec,
+    limit: i64,
+) -> Result<Vec<schema::SessionRecord>, Error> {
+    trace!("retrieving {} sessions to relay", limit);
+    Ok(sqlx::query_as!(
+        schema::SessionRecord,
+        r#"
+        SELECT session.*
+        FROM session_t AS session
+        JOIN sequence_t AS sequence
+            ON session.sequence_id = sequence.sequence_id
+        LEFT JOIN relay_session_t AS relay
+            ON session.session_id = relay.session_id
+        WHERE session.completion_unix_tstamp IS NOT NULL
+            AND sequence.trashed_unix_tstamp IS NULL
+            AND relay.relay_unix_tstamp IS NULL
+            AND (relay.rejected IS NULL OR NOT relay.rejected)
+        ORDER BY session.completion_unix_tstamp, session.session_id
+        LIMIT $1
+        "#,
+        limit
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Returns the relay state of the session, `None` if it was never processed.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn relay_session_find(
+    exe: &mut impl AsExec,
+    session_id: i32,
+) -> Result<Option<schema::RelaySessionRecord>, Error> {
+    trace!("searching relay state of session `{}`", session_id);
+    Ok(sqlx::query_as!(
+        schema::RelaySessionRecord,
+        "SELECT * FROM relay_session_t WHERE session_id=$1",
+        session_id
+    )
+    .fetch_optional(exe.as_exec())
+    .await?)
+}
+
+/// Records the session created on the central instance by the running attempt.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn relay_session_set_remote_locator(
+    exe: &mut impl AsExec,
+    session_id: i32,
+    remote_locator: Option<&str>,
+) -> Result<(), Error> {
+    trace!(
+        "setting remote locator of session `{}` to {:?}",
+        session_id, remote_locator
+    );
+    sqlx::query!(
+        r#"
+            INSERT INTO relay_session_t (session_id, remote_locator)
+            VALUES ($1, $2)
+            ON CONFLICT (session_id) DO UPDATE SET remote_locator = EXCLUDED.remote_locator
+    "#,
+        session_id,
+        remote_locator,
+    )
+    .execute(exe.as_exec())
+    .await?;
+    Ok(())
+}
+
+/// Records a failed attempt, `rejected` sessions are not retried.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn relay_session_fail(
+    exe: &mut impl AsExec,
+    session_id: i32,
+    error: &str,
+    rejected: bool,
+) -> Result<(), Error> {
+    trace!("recording failed relay of session `{}`", session_id);
+    sqlx::query!(
+        r#"
+            INSERT INTO relay_session_t (session_id, attempts, last_error, rejected)
+            VALUES ($1, 1, $2, $3)
+            ON CONFLICT (session_id) DO UPDATE SET
+                attempts = relay_session_t.attempts + 1,
+                last_error = EXCLUDED.last_error,
+                rejected = EXCLUDED.rejected
+    "#,
+        session_id,
+        error,
+        rejected,
+    )
+    .execute(exe.as_exec())
+    .await?;
+    Ok(())
+}
+
+/// Marks the session as relayed.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn relay_session_complete(exe: &mut impl AsExec, session_id: i32) -> Result<(), Error> {
+    trace!("recording completed relay of session `{}`", session_id);
+    sqlx::query!(
+        r#"
+            INSERT INTO relay_session_t (session_id, relay_unix_tstamp)
+            VALUES ($1, $2)
+            ON CONFLICT (session_id) DO UPDATE SET
+                relay_unix_tstamp = EXCLUDED.relay_unix_tstamp,
+                last_error = NULL
+    "#,
+        session_id,
+        types::Timestamp::now().as_i64(),
+    )
+    .execute(exe.as_exec())
+    .await?;
+    Ok(())
+}
+
+/// Returns the central sequence receiving the sessions of the sequence, `None` if no session
+/// of the sequence was relayed yet.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn relay_sequence_find(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+) -> Result<Option<schema::RelaySequenceRecord>, Error> {
+    trace!("searching relayed sequence `{}`", sequence_id);
+    Ok(sqlx::query_as!(
+        schema::RelaySequenceRecord,
+        "SELECT * FROM relay_sequence_t WHERE sequence_id=$1",
+        sequence_id
+    )
+    .fetch_optional(exe.as_exec())
+    .await?)
+}
+
+/// Records the central sequence receiving the sessions of the sequence.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn relay_sequence_set(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+    remote_locator: &str,
+) -> Result<(), Error> {
+    trace!(
+        "setting remote locator of sequence `{}` to `{}`",
+        sequence_id, remote_locator
+    );
+    sqlx::query!(
+        r#"
+            INSERT INTO relay_sequence_t (sequence_id, remote_locator)
+            VALUES ($1, $2)
+            ON CONFLICT (sequence_id) DO UPDATE SET remote_locator = EXCLUDED.remote_locator
+    "#,
+        sequence_id,
+        remote_locator,
+    )
+    .execute(exe.as_exec())
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use crate::{Error, core::AsExec, sql::schema};
+use mosaicod_core::types;
+use tracing::trace;
+
+fn convert(
+    record: schema::ScheduledQueryRecord,
+) -> Result<types::ScheduledQuery<serde_json::Value>, Error> {
+    record
+        .try_into()
+        .map_err(|e: types::ScheduledQueryError| Error::BadData(e.to_string()))
+}
+
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn scheduled_query_create(
+    exe: &mut impl AsExec,
+    scheduled_query: &types::ScheduledQuery<serde_json::Value>,
+) -> Result<types::ScheduledQuery<serde_json::Value>, Error> {
+    trace!("creating scheduled query `{}`", scheduled_query.name);
+    let res = sqlx::query_as!(
+        schema::ScheduledQueryRecord,
+        r#"
+            INSERT INTO scheduled_query_t
+                (
+                    name, schedule, query, condition_op, condition_value,
+                    creation_unix_tstamp, next_run_unix_tstamp
+                )
+            VALUES
+                ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING
+                *
+    "#,
+        scheduled_query.name,
+        scheduled_query.schedule.to_string(),
+        scheduled_query.query,
+        scheduled_query.condition.op_name(),
+        scheduled_query.condition.value as i64,
+        scheduled_query.created_at.as_i64(),
+        scheduled_query.next_run_at.as_i64(),
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+
+    convert(res)
+}
+
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn scheduled_query_find_by_name(
+    exe: &mut impl AsExec,
+    name: &str,
+) -> Result<types::ScheduledQuery<serde_json::Value>, Error> {
+    let res = sqlx::query_as!(
+        schema::ScheduledQueryRecord,
+        "SELECT * FROM scheduled_query_t WHERE name = $1",
+        name
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+
+    convert(res)
+}
+
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn scheduled_query_find_all(
+    exe: &mut impl AsExec,
+) -> Result<Vec<types::ScheduledQuery<serde_json::Value>>, Error> {
+    let res = sqlx::query_as!(
+        schema::ScheduledQueryRecord,
+        "SELECT * FROM scheduled_query_t ORDER BY name"
+    )
+    .fetch_all(exe.as_exec())
+    .await?;
+
+    res.into_iter().map(convert).collect()
+}
+
+/// Returns the scheduled queries whose next run is not after `now`.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn scheduled_query_find_due(
+    exe: &mut impl AsExec,
+    now: types::Timestamp,
+) -> Result<Vec<types::ScheduledQuery<serde_json::Value>>, Error> {
+    let res = sqlx::query_as!(
+        schema::ScheduledQueryRecord,
+        r#"
+            SELECT * FROM scheduled_query_t
+            WHERE next_run_unix_tstamp <= $1
+            ORDER BY next_run_unix_tstamp
+    "#,
+        now.as_i64()
+    )
+    .fetch_all(exe.as_exec())
+    .await?;
+
+    res.into_iter().map(convert).collect()
+}
+
+/// Moves the next run of a scheduled query from `current` to `next`.
+///
+/// Returns false if the next run is no longer `current`, meaning that the run was already
+/// claimed by someone else.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn scheduled_query_claim_run(
+    exe: &mut impl AsExec,
+    name: &str,
+    current: types::Timestamp,
+    next: types::Timestamp,
+) -> Result<bool, Error> {
+    let res = sqlx::query!(
+        r#"
+            UPDATE scheduled_query_t
+            SET next_run_unix_tstamp = $3
+            WHERE name = $1 AND next_run_unix_tstamp = $2
+    "#,
+        name,
+        current.as_i64(),
+        next.as_i64(),
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    Ok(res.rows_affected() > 0)
+}
+
+/// Records the outcome of an evaluation of a scheduled query.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn scheduled_query_update_last_run(
+    exe: &mut impl AsExec,
+    name: &str,
+    run_at: types::Timestamp,
+    match_count: u64,
+) -> Result<(), Error> {
+    sqlx::query!(
+        r#"
+            UPDATE scheduled_query_t
+            SET last_run_unix_tstamp = $2, last_match_count = $3
+            WHERE name = $1
+    "#,
+        name,
+        run_at.as_i64(),
+        match_count as i64,
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    Ok(())
+}
+
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn scheduled_query_delete(exe: &mut impl AsExec, name: &str) -> Result<(), Error> {
+    let res = sqlx::query!("DELETE FROM scheduled_query_t WHERE name = $1", name)
+        .execute(exe.as_exec())
+        .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(Error::NotFound);
+    }
+
+    Ok(())
+}
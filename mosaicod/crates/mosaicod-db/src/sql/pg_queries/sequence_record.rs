@@ -1,8 +1,9 @@
 use crate::{Error, core::AsExec, sql::schema};
-use log::{trace, warn};
 use mosaicod_core::types;
+use tracing::{trace, warn};
 
 /// Find a sequence given its id.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn sequence_find_by_id(
     exe: &mut impl AsExec,
     id: i32,
@@ -18,7 +19,8 @@ pub async fn sequence_find_by_id(
     Ok(res)
 }
 
-/// Find a sequence given its uuid.
+/// Find a sequence not in the trash given its uuid.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn sequence_find_by_uuid(
     exe: &mut impl AsExec,
     uuid: &types::Uuid,
@@ -26,7 +28,7 @@ pub async fn sequence_find_by_uuid(
     trace!("searching sequence by uuid `{}`", uuid);
     let res = sqlx::query_as!(
         schema::SequenceRecord,
-        "SELECT * FROM sequence_t WHERE sequence_uuid=$1",
+        "SELECT * FROM sequence_t WHERE sequence_uuid=$1 AND trashed_unix_tstamp IS NULL",
         uuid.as_ref()
     )
     .fetch_one(exe.as_exec())
@@ -34,7 +36,8 @@ pub async fn sequence_find_by_uuid(
     Ok(res)
 }
 
-/// Find a sequence given its name.
+/// Find a sequence not in the trash given its name.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn sequence_find_by_locator(
     exe: &mut impl AsExec,
     loc: &types::SequenceLocator,
@@ -42,7 +45,7 @@ pub async fn sequence_find_by_locator(
     trace!("searching sequence by locator name `{}`", loc);
     let res = sqlx::query_as!(
         schema::SequenceRecord,
-        "SELECT * FROM sequence_t WHERE locator_name=$1",
+        "SELECT * FROM sequence_t WHERE locator_name=$1 AND trashed_unix_tstamp IS NULL",
         loc as &str,
     )
     .fetch_one(exe.as_exec())
@@ -51,54 +54,279 @@ pub async fn sequence_find_by_locator(
     Ok(res)
 }
 
-pub async fn sequence_find_all_topics(
+/// Find a sequence in the trash given its name.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_find_trashed_by_locator(
     exe: &mut impl AsExec,
     loc: &types::SequenceLocator,
-) -> Result<Vec<schema::TopicRecord>, Error> {
-    trace!("searching topics for sequence `{}`", loc);
+) -> Result<schema::SequenceRecord, Error> {
+    trace!("searching trashed sequence by locator name `{}`", loc);
+    let res = sqlx::query_as!(
+        schema::SequenceRecord,
+        "SELECT * FROM sequence_t WHERE locator_name=$1 AND trashed_unix_tstamp IS NOT NULL",
+        loc as &str,
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+
+    Ok(res)
+}
+
+/// Return the sequences moved to the trash before `before`, sorted by trash time.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_find_all_trashed_before(
+    exe: &mut impl AsExec,
+    before: types::Timestamp,
+) -> Result<Vec<schema::SequenceRecord>, Error> {
+    trace!("retrieving sequences trashed before {}", before);
     Ok(sqlx::query_as!(
-        schema::TopicRecord,
+        schema::SequenceRecord,
         r#"
-        SELECT topic.*
-        FROM topic_t AS topic
-        JOIN sequence_t AS sequence ON topic.sequence_id = sequence.sequence_id
-        WHERE sequence.locator_name = $1
+        SELECT * FROM sequence_t
+        WHERE trashed_unix_tstamp < $1
+        ORDER BY trashed_unix_tstamp
         "#,
-        loc as &str
+        before.as_i64()
     )
     .fetch_all(exe.as_exec())
     .await?)
 }
 
-pub async fn sequence_find_all_sessions(
+/// Return at most `limit` sessions of a sequence whose id follows `after`, sorted by id
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_find_sessions_page(
     exe: &mut impl AsExec,
-    loc: &types::SequenceLocator,
+    sequence_id: i32,
+    after: Option<i32>,
+    limit: i64,
 ) -> Result<Vec<schema::SessionRecord>, Error> {
-    trace!("searching sessions for sequence `{}`", loc);
+    trace!(
+        "retrieving {} sessions of sequence with id `{}` after {:?}",
+        limit, sequence_id, after
+    );
     Ok(sqlx::query_as!(
         schema::SessionRecord,
         r#"
-        SELECT session.*
-        FROM session_t AS session 
-        JOIN sequence_t AS sequence ON session.sequence_id = sequence.sequence_id
-        WHERE sequence.locator_name = $1
+        SELECT * FROM session_t
+        WHERE sequence_id = $1 AND ($2::INTEGER IS NULL OR session_id > $2)
+        ORDER BY session_id
+        LIMIT $3
         "#,
-        loc as &str
+        sequence_id,
+        after,
+        limit
     )
     .fetch_all(exe.as_exec())
     .await?)
 }
 
-/// Return all sequences
-pub async fn sequence_find_all(
+/// Return at most `limit` sequences not in the trash whose locator starts with `prefix`
+/// and follows `after`, sorted by locator
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_find_page(
     exe: &mut impl AsExec,
+    prefix: &str,
+    after: Option<&str>,
+    limit: i64,
 ) -> Result<Vec<schema::SequenceRecord>, Error> {
-    trace!("retrieving all sequences");
-    Ok(
-        sqlx::query_as!(schema::SequenceRecord, "SELECT * FROM sequence_t")
-            .fetch_all(exe.as_exec())
-            .await?,
+    trace!(
+        "retrieving {} sequences starting with `{}` after `{:?}`",
+        limit, prefix, after
+    );
+    Ok(sqlx::query_as!(
+        schema::SequenceRecord,
+        r#"
+        SELECT * FROM sequence_t
+        WHERE locator_name LIKE $1 AND ($2::TEXT IS NULL OR locator_name > $2)
+            AND trashed_unix_tstamp IS NULL
+        ORDER BY locator_name
+        LIMIT $3
+        "#,
+        super::like_prefix(prefix),
+        after,
+        limit
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Return at most `limit` sequences following the one with id `after`, sorted by id.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_find_page_by_id(
+    exe: &mut impl AsExec,
+    after: Option<i32>,
+    limit: i64,
+) -> Result<Vec<schema::SequenceRecord>, Error> {
+    trace!("retrieving {} sequences after id {:?}", limit, after);
+    Ok(sqlx::query_as!(
+        schema::SequenceRecord,
+        r#"
+        SELECT * FROM sequence_t
+        WHERE $1::INTEGER IS NULL OR sequence_id > $1
+        ORDER BY sequence_id
+        LIMIT $2
+        "#,
+        after,
+        limit
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Locks the sequence record until the end of the transaction, returns
+/// [`Error::NotFound`] if the sequence does not exist.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_lock(exe: &mut impl AsExec, sequence_id: i32) -> Result<(), Error> {
+    trace!("locking sequence with id `{}`", sequence_id);
+    sqlx::query!(
+        "SELECT sequence_id FROM sequence_t WHERE sequence_id=$1 FOR NO KEY UPDATE",
+        sequence_id
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(())
+}
+
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_update_path_in_store(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+    path_in_store: types::SequencePathInStore,
+) -> Result<(), Error> {
+    trace!(
+        "updating path_in_store to `{}` for sequence with id {}",
+        path_in_store, sequence_id
+    );
+    sqlx::query!(
+        "UPDATE sequence_t SET path_in_store = $1 WHERE sequence_id = $2",
+        String::from(path_in_store),
+        sequence_id,
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    Ok(())
+}
+
+/// Moves a sequence to the trash, returns [`Error::NotFound`] if the sequence does not
+/// exist or is already in the trash.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_trash(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+    trashed_at: types::Timestamp,
+) -> Result<(), Error> {
+    trace!("moving sequence with id `{}` to the trash", sequence_id);
+    let result = sqlx::query!(
+        r#"
+        UPDATE sequence_t SET trashed_unix_tstamp = $1
+        WHERE sequence_id = $2 AND trashed_unix_tstamp IS NULL
+        "#,
+        trashed_at.as_i64(),
+        sequence_id,
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound);
+    }
+
+    Ok(())
+}
+
+/// Restores a sequence from the trash, returns [`Error::NotFound`] if the sequence is not
+/// in the trash.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_restore(exe: &mut impl AsExec, sequence_id: i32) -> Result<(), Error> {
+    trace!(
+        "restoring sequence with id `{}` from the trash",
+        sequence_id
+    );
+    let result = sqlx::query!(
+        r#"
+        UPDATE sequence_t SET trashed_unix_tstamp = NULL
+        WHERE sequence_id = $1 AND trashed_unix_tstamp IS NOT NULL
+        "#,
+        sequence_id,
     )
+    .execute(exe.as_exec())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound);
+    }
+
+    Ok(())
+}
+
+/// Sets or releases the legal hold of a sequence, returns [`Error::NotFound`] if the
+/// sequence does not exist.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_set_legal_hold(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+    legal_hold: bool,
+) -> Result<(), Error> {
+    trace!(
+        "setting legal hold of sequence with id `{}` to {}",
+        sequence_id, legal_hold
+    );
+    let result = sqlx::query!(
+        "UPDATE sequence_t SET legal_hold = $1 WHERE sequence_id = $2",
+        legal_hold,
+        sequence_id,
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound);
+    }
+
+    Ok(())
+}
+
+/// Tries to update the user metadata of a sequence, merging the top-level keys of
+/// `user_metadata` into the current ones if `merge` is set, replacing them otherwise.
+///
+/// Returns `None` if the sequence is locked, that is when one of its sessions has been
+/// finalized or the sequence is in the trash.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_try_update_user_metadata(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+    user_metadata: &serde_json::Value,
+    merge: bool,
+) -> Result<Option<schema::SequenceRecord>, Error> {
+    trace!(
+        "updating user_metadata of sequence with id `{}` (merge: {})",
+        sequence_id, merge
+    );
+    let res = sqlx::query_as!(
+        schema::SequenceRecord,
+        r#"
+            UPDATE sequence_t
+            SET user_metadata = CASE
+                WHEN $3 THEN COALESCE(user_metadata, '{}'::jsonb) || $2
+                ELSE $2
+            END
+            WHERE sequence_id = $1
+                AND trashed_unix_tstamp IS NULL
+                AND NOT EXISTS (
+                    SELECT 1 FROM session_t
+                    WHERE session_t.sequence_id = $1 AND completion_unix_tstamp IS NOT NULL
+                )
+            RETURNING *
+    "#,
+        sequence_id,
+        user_metadata,
+        merge,
+    )
+    .fetch_optional(exe.as_exec())
+    .await?;
+
+    Ok(res)
 }
 
 /// Deletes a sequence record from the database by its name.
@@ -106,6 +334,7 @@ pub async fn sequence_find_all(
 /// This function requires a [`DataLossToken`] because it permanently removes the record
 /// from the database without checking if it's referenced elsewhere.
 /// Improper use can lead to data inconsistency or loss.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn sequence_delete_by_locator(
     exe: &mut impl AsExec,
     loc: &types::SequenceLocator,
@@ -123,6 +352,7 @@ pub async fn sequence_delete_by_locator(
 /// This function requires a [`DataLossToken`] because it permanently removes the record
 /// from the database without checking if it's referenced elsewhere.
 /// Improper use can lead to data inconsistency or loss.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn sequence_delete_by_id(
     exe: &mut impl AsExec,
     sequence_id: i32,
@@ -140,6 +370,39 @@ pub async fn sequence_delete_by_id(
     Ok(())
 }
 
+/// Deletes a sequence record in the trash from the database by its id, returns
+/// [`Error::NotFound`] if the sequence is not in the trash or is under legal hold.
+///
+/// This function requires a [`DataLossToken`] because it permanently removes the record
+/// and, by cascade, the records of its sessions and topics.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_delete_trashed_by_id(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+    _: types::DataLossToken,
+) -> Result<(), Error> {
+    warn!(
+        "(data loss) deleting trashed sequence with id `{}`",
+        sequence_id
+    );
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM sequence_t
+        WHERE sequence_id=$1 AND trashed_unix_tstamp IS NOT NULL AND NOT legal_hold
+        "#,
+        sequence_id
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound);
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn sequence_create(
     exe: &mut impl AsExec,
     record: &schema::SequenceRecord,
@@ -190,5 +453,267 @@ mod tests {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn test_find_page_by_prefix(pool: Pool<DatabaseType>) -> sqlx::Result<()> {
+        let database = testing::Database::new(pool);
+        for name in ["run_1", "run_2", "runa", "other"] {
+            let record = schema::SequenceRecord::new(name.parse().unwrap(), name.to_owned().into());
+            sequence_create(&mut database.connection(), &record)
+                .await
+                .unwrap();
+        }
+
+        let names = |records: Vec<schema::SequenceRecord>| {
+            records
+                .into_iter()
+                .map(|r| r.locator_name)
+                .collect::<Vec<_>>()
+        };
+
+        // `_` is matched literally
+        let records = sequence_find_page(&mut database.connection(), "run_", None, 10)
+            .await
+            .unwrap();
+        assert_eq!(names(records), vec!["run_1", "run_2"]);
+
+        let records = sequence_find_page(&mut database.connection(), "", None, 10)
+            .await
+            .unwrap();
+        assert_eq!(names(records).len(), 4);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_find_page(pool: Pool<DatabaseType>) -> sqlx::Result<()> {
+        let database = testing::Database::new(pool);
+        for name in ["run_3", "run_1", "other", "run_2"] {
+            let record = schema::SequenceRecord::new(name.parse().unwrap(), name.to_owned().into());
+            sequence_create(&mut database.connection(), &record)
+                .await
+                .unwrap();
+        }
+
+        let names = |records: Vec<schema::SequenceRecord>| {
+            records
+                .into_iter()
+                .map(|r| r.locator_name)
+                .collect::<Vec<_>>()
+        };
+
+        let records = sequence_find_page(&mut database.connection(), "run", None, 2)
+            .await
+            .unwrap();
+        assert_eq!(names(records), vec!["run_1", "run_2"]);
+
+        let records = sequence_find_page(&mut database.connection(), "run", Some("run_2"), 2)
+            .await
+            .unwrap();
+        assert_eq!(names(records), vec!["run_3"]);
+
+        let records = sequence_find_page(&mut database.connection(), "", Some("run_3"), 2)
+            .await
+            .unwrap();
+        assert!(records.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_trash_and_restore(pool: Pool<DatabaseType>) -> sqlx::Result<()> {
+        let database = testing::Database::new(pool);
+        let locator: types::SequenceLocator = "my_sequence".parse().unwrap();
+        let record = schema::SequenceRecord::new(locator.clone(), "my_sequence".to_owned().into());
+        let record = sequence_create(&mut database.connection(), &record)
+            .await
+            .unwrap();
+
+        let trashed_at = types::Timestamp::now();
+        sequence_trash(&mut database.connection(), record.sequence_id, trashed_at)
+            .await
+            .unwrap();
+
+        // Trashed sequences are hidden from the lookups
+        assert!(matches!(
+            sequence_find_by_locator(&mut database.connection(), &locator).await,
+            Err(Error::NotFound)
+        ));
+        let records = sequence_find_page(&mut database.connection(), "", None, 10)
+            .await
+            .unwrap();
+        assert!(records.is_empty());
+
+        let trashed = sequence_find_trashed_by_locator(&mut database.connection(), &locator)
+            .await
+            .unwrap();
+        assert_eq!(trashed.trashed_at(), Some(trashed_at));
+
+        let records = sequence_find_all_trashed_before(&mut database.connection(), trashed_at)
+            .await
+            .unwrap();
+        assert!(records.is_empty());
+        let records = sequence_find_all_trashed_before(
+            &mut database.connection(),
+            (trashed_at.as_i64() + 1).into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(records.len(), 1);
+
+        // A sequence can be trashed only once
+        assert!(matches!(
+            sequence_trash(&mut database.connection(), record.sequence_id, trashed_at).await,
+            Err(Error::NotFound)
+        ));
+
+        sequence_restore(&mut database.connection(), record.sequence_id)
+            .await
+            .unwrap();
+
+        // Only sequences in the trash are purged
+        assert!(matches!(
+            sequence_delete_trashed_by_id(
+                &mut database.connection(),
+                record.sequence_id,
+                types::allow_data_loss()
+            )
+            .await,
+            Err(Error::NotFound)
+        ));
+        let restored = sequence_find_by_locator(&mut database.connection(), &locator)
+            .await
+            .unwrap();
+        assert_eq!(restored.trashed_at(), None);
+        assert!(matches!(
+            sequence_restore(&mut database.connection(), record.sequence_id).await,
+            Err(Error::NotFound)
+        ));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_legal_hold(pool: Pool<DatabaseType>) -> sqlx::Result<()> {
+        let database = testing::Database::new(pool);
+        let record = sequence_create(
+            &mut database.connection(),
+            &schema::SequenceRecord::new("held".parse().unwrap(), "held".to_owned().into()),
+        )
+        .await
+        .unwrap();
+        assert!(!record.legal_hold());
+
+        sequence_set_legal_hold(&mut database.connection(), record.sequence_id, true)
+            .await
+            .unwrap();
+        let held = sequence_find_by_id(&mut database.connection(), record.sequence_id)
+            .await
+            .unwrap();
+        assert!(held.legal_hold());
+
+        // Sequences under legal hold are never purged from the trash
+        sequence_trash(
+            &mut database.connection(),
+            record.sequence_id,
+            types::Timestamp::now(),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            sequence_delete_trashed_by_id(
+                &mut database.connection(),
+                record.sequence_id,
+                types::allow_data_loss()
+            )
+            .await,
+            Err(Error::NotFound)
+        ));
+
+        sequence_set_legal_hold(&mut database.connection(), record.sequence_id, false)
+            .await
+            .unwrap();
+        sequence_delete_trashed_by_id(
+            &mut database.connection(),
+            record.sequence_id,
+            types::allow_data_loss(),
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            sequence_set_legal_hold(&mut database.connection(), record.sequence_id, true).await,
+            Err(Error::NotFound)
+        ));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_update_user_metadata(pool: Pool<DatabaseType>) -> sqlx::Result<()> {
+        let database = testing::Database::new(pool);
+        let locator: types::SequenceLocator = "patched".parse().unwrap();
+        let record = sequence_create(
+            &mut database.connection(),
+            &schema::SequenceRecord::new(locator.clone(), "patched".to_owned().into())
+                .with_user_metadata(serde_json::json!({"a": 1, "b": 2}).into()),
+        )
+        .await
+        .unwrap();
+
+        let merged = sequence_try_update_user_metadata(
+            &mut database.connection(),
+            record.sequence_id,
+            &serde_json::json!({"b": 3, "c": 4}),
+            true,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            merged.user_metadata,
+            Some(serde_json::json!({"a": 1, "b": 3, "c": 4}))
+        );
+
+        let replaced = sequence_try_update_user_metadata(
+            &mut database.connection(),
+            record.sequence_id,
+            &serde_json::json!({"d": 5}),
+            false,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(replaced.user_metadata, Some(serde_json::json!({"d": 5})));
+
+        // Once a session is finalized the sequence is locked
+        let session = crate::session_create(
+            &mut database.connection(),
+            &schema::SessionRecord::new(types::SessionLocator::new(locator), record.sequence_id),
+        )
+        .await
+        .unwrap();
+        crate::session_try_update_completion_tstamp(
+            &mut database.connection(),
+            session.session_id,
+            types::Timestamp::now().as_i64(),
+        )
+        .await
+        .unwrap();
+
+        assert!(
+            sequence_try_update_user_metadata(
+                &mut database.connection(),
+                record.sequence_id,
+                &serde_json::json!({"e": 6}),
+                true,
+            )
+            .await
+            .unwrap()
+            .is_none()
+        );
+
+        Ok(())
+    }
+
     // (cabba) TODO: extend tests
 }
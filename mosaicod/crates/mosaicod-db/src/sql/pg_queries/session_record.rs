@@ -1,7 +1,8 @@
 use crate::{Error, core::AsExec, sql::schema};
-use log::{trace, warn};
 use mosaicod_core::types;
+use tracing::{trace, warn};
 
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn session_create(
     exe: &mut impl AsExec,
     record: &schema::SessionRecord,
@@ -13,10 +14,11 @@ pub async fn session_create(
             INSERT INTO session_t 
                 (
                     locator_name, session_uuid, sequence_id,
-                    creation_unix_tstamp, completion_unix_tstamp
+                    creation_unix_tstamp, completion_unix_tstamp, owner,
+                    last_activity_unix_tstamp
                 ) 
             VALUES 
-                ($1, $2, $3, $4, $5)
+                ($1, $2, $3, $4, $5, $6, $7)
             RETURNING 
                 *
     "#,
@@ -25,6 +27,8 @@ pub async fn session_create(
         record.sequence_id,
         record.creation_unix_tstamp,
         record.completion_unix_tstamp,
+        record.owner,
+        record.last_activity_unix_tstamp,
     )
     .fetch_one(exe.as_exec())
     .await?;
@@ -32,6 +36,7 @@ pub async fn session_create(
 }
 
 /// Find a sequence given its id.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn session_find_by_id(
     exe: &mut impl AsExec,
     id: i32,
@@ -47,7 +52,8 @@ pub async fn session_find_by_id(
     Ok(res)
 }
 
-/// Find a sequence given its uuid.
+/// Find a session given its uuid, sessions of the sequences in the trash are not returned.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn session_find_by_uuid(
     exe: &mut impl AsExec,
     uuid: &types::Uuid,
@@ -55,7 +61,12 @@ pub async fn session_find_by_uuid(
     trace!("searching session by uuid `{}`", uuid);
     let res = sqlx::query_as!(
         schema::SessionRecord,
-        "SELECT * FROM session_t WHERE session_uuid=$1",
+        r#"
+        SELECT session.*
+        FROM session_t AS session
+        JOIN sequence_t AS sequence ON session.sequence_id = sequence.sequence_id
+        WHERE session.session_uuid = $1 AND sequence.trashed_unix_tstamp IS NULL
+        "#,
         uuid.as_ref()
     )
     .fetch_one(exe.as_exec())
@@ -63,7 +74,9 @@ pub async fn session_find_by_uuid(
     Ok(res)
 }
 
-/// Find a sequence given its locator.
+/// Find a session given its locator, sessions of the sequences in the trash are not
+/// returned.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn session_find_by_locator(
     exe: &mut impl AsExec,
     session_locator: &types::SessionLocator,
@@ -71,7 +84,12 @@ pub async fn session_find_by_locator(
     trace!("searching session by locator name `{}`", session_locator);
     let res = sqlx::query_as!(
         schema::SessionRecord,
-        "SELECT * FROM session_t WHERE locator_name=$1",
+        r#"
+        SELECT session.*
+        FROM session_t AS session
+        JOIN sequence_t AS sequence ON session.sequence_id = sequence.sequence_id
+        WHERE session.locator_name = $1 AND sequence.trashed_unix_tstamp IS NULL
+        "#,
         session_locator.to_string()
     )
     .fetch_one(exe.as_exec())
@@ -79,7 +97,22 @@ pub async fn session_find_by_locator(
     Ok(res)
 }
 
+/// Locks the session record until the end of the transaction, concurrent transactions
+/// trying to lock the same session wait for the lock to be released.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn session_lock(exe: &mut impl AsExec, session_id: i32) -> Result<(), Error> {
+    trace!("locking session with id `{}`", session_id);
+    sqlx::query!(
+        "SELECT session_id FROM session_t WHERE session_id=$1 FOR NO KEY UPDATE",
+        session_id
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(())
+}
+
 /// Returns true if the session has already been finalized.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn session_finalized(exe: &mut impl AsExec, session_id: i32) -> Result<bool, Error> {
     trace!("session (id=`{}`) locked? ", session_id);
     let finalized = sqlx::query_scalar!(
@@ -91,10 +124,33 @@ pub async fn session_finalized(exe: &mut impl AsExec, session_id: i32) -> Result
     Ok(finalized)
 }
 
+/// Allows `principal` to write into the session, sharing it twice has no effect.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn session_share(
+    exe: &mut impl AsExec,
+    session_id: i32,
+    principal: &str,
+) -> Result<(), Error> {
+    trace!("sharing session (id=`{}`) with `{}`", session_id, principal);
+    sqlx::query!(
+        r#"
+            UPDATE session_t
+            SET shared_with = array_append(shared_with, $2)
+            WHERE session_id = $1 AND NOT ($2 = ANY(shared_with))
+    "#,
+        session_id,
+        principal,
+    )
+    .execute(exe.as_exec())
+    .await?;
+    Ok(())
+}
+
 /// Deletes a session record from the database by its name, **bypassing any lock state**.
 ///
 /// This function requires a [`DataLossToken`] because it permanently removes the record from the database
 /// elsewhere. Improper use can lead to data inconsistency or loss.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn session_delete(
     exe: &mut impl AsExec,
     uuid: &types::Uuid,
@@ -112,22 +168,34 @@ pub async fn session_delete(
     Ok(())
 }
 
-/// Find all topic associated with a session
-pub async fn session_find_all_topics(
+/// Return at most `limit` topics associated with a session whose locator follows `after`,
+/// sorted by locator
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn session_find_topics_page(
     exe: &mut impl AsExec,
     uuid: &types::Uuid,
+    after: Option<&str>,
+    limit: i64,
 ) -> Result<Vec<schema::TopicRecord>, Error> {
-    trace!("searching topics for session `{}`", uuid);
+    trace!(
+        "retrieving {} topics of session `{}` after `{:?}`",
+        limit, uuid, after
+    );
     Ok(sqlx::query_as!(
         schema::TopicRecord,
         r#"
         SELECT topic.*
         FROM topic_t AS topic
-        JOIN session_t AS session 
+        JOIN session_t AS session
             ON topic.session_id = session.session_id
         WHERE session.session_uuid = $1
+            AND ($2::TEXT IS NULL OR topic.locator_name > $2)
+        ORDER BY topic.locator_name
+        LIMIT $3
         "#,
         uuid.as_ref(),
+        after,
+        limit
     )
     .fetch_all(exe.as_exec())
     .await?)
@@ -136,6 +204,7 @@ pub async fn session_find_all_topics(
 /// Tries to update completion_unix_tstamp column for the given session.
 ///
 /// Returns False if the value was already set, otherwise True.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn session_try_update_completion_tstamp(
     exe: &mut impl AsExec,
     session_id: i32,
@@ -159,3 +228,106 @@ pub async fn session_try_update_completion_tstamp(
 
     Ok(res.rows_affected() != 0)
 }
+
+/// Records an upload into a session not finalized yet at `activity_ts`.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn session_update_last_activity(
+    exe: &mut impl AsExec,
+    session_id: i32,
+    activity_ts: i64,
+) -> Result<(), Error> {
+    trace!(
+        "updating last activity timestamp to `{}` for session `{}`",
+        activity_ts, session_id
+    );
+    sqlx::query!(
+        r#"
+            UPDATE session_t
+            SET last_activity_unix_tstamp = GREATEST(last_activity_unix_tstamp, $1)
+            WHERE session_id = $2 AND completion_unix_tstamp IS NULL
+    "#,
+        activity_ts,
+        session_id,
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    Ok(())
+}
+
+/// Returns the sessions not finalized and without uploads since `before`.
+///
+/// The sessions of the sequences in the trash or under legal hold are not returned.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn session_find_all_inactive_before(
+    exe: &mut impl AsExec,
+    before: i64,
+) -> Result<Vec<schema::SessionRecord>, Error> {
+    trace!("searching sessions inactive since `{}`", before);
+    Ok(sqlx::query_as!(
+        schema::SessionRecord,
+        r#"
+        SELECT session.*
+        FROM session_t AS session
+        JOIN sequence_t AS sequence
+            ON session.sequence_id = sequence.sequence_id
+        WHERE session.completion_unix_tstamp IS NULL
+            AND session.last_activity_unix_tstamp < $1
+            AND sequence.trashed_unix_tstamp IS NULL
+            AND NOT sequence.legal_hold
+        ORDER BY session.last_activity_unix_tstamp
+        "#,
+        before
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Returns at most `limit` sessions of a sequence following `after`, sorted by creation
+/// time, along with the number of topics they created.
+///
+/// If `finalized` is set, only the finalized sessions (`true`) or the sessions still open
+/// (`false`) are returned.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn session_find_summaries_page_by_sequence(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+    finalized: Option<bool>,
+    after: Option<&types::SessionCursor>,
+    limit: i64,
+) -> Result<Vec<types::SessionSummary>, Error> {
+    trace!(
+        "retrieving {} sessions for sequence with id `{}` after {:?}",
+        limit, sequence_id, after
+    );
+    let records = sqlx::query_as!(
+        schema::SessionSummaryRecord,
+        r#"
+        SELECT
+            session.session_id,
+            session.locator_name,
+            session.session_uuid,
+            session.creation_unix_tstamp,
+            session.completion_unix_tstamp,
+            COUNT(topic.topic_id) AS "topic_count!"
+        FROM session_t AS session
+        LEFT JOIN topic_t AS topic
+            ON topic.session_id = session.session_id
+        WHERE session.sequence_id = $1
+            AND ($2::BOOLEAN IS NULL OR (session.completion_unix_tstamp IS NOT NULL) = $2)
+            AND ($3::BIGINT IS NULL OR (session.creation_unix_tstamp, session.session_uuid) > ($3, $4))
+        GROUP BY session.session_id
+        ORDER BY session.creation_unix_tstamp, session.session_uuid
+        LIMIT $5
+        "#,
+        sequence_id,
+        finalized,
+        after.map(|cursor| cursor.timestamp.as_i64()),
+        after.map(|cursor| uuid::Uuid::from(cursor.uuid.clone())),
+        limit,
+    )
+    .fetch_all(exe.as_exec())
+    .await?;
+
+    records.into_iter().map(TryInto::try_into).collect()
+}
@@ -0,0 +1,136 @@
+use crate::{Error, core::AsExec, sql::schema};
+use mosaicod_core::types;
+use tracing::trace;
+
+/// Records a new shared object stored at `object_path`.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn shared_object_create(
+    exe: &mut impl AsExec,
+    object_path: &str,
+) -> Result<schema::SharedObjectRecord, Error> {
+    trace!("recording shared object `{}`", object_path);
+    Ok(sqlx::query_as!(
+        schema::SharedObjectRecord,
+        r#"
+            INSERT INTO shared_object_t (object_path, creation_unix_tstamp)
+            VALUES ($1, $2)
+            RETURNING *
+    "#,
+        object_path,
+        types::Timestamp::now().as_i64(),
+    )
+    .fetch_one(exe.as_exec())
+    .await?)
+}
+
+/// Returns the shared object the data file of a chunk is linked to.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn shared_object_find_by_chunk(
+    exe: &mut impl AsExec,
+    chunk_id: i32,
+) -> Result<schema::SharedObjectRecord, Error> {
+    trace!("retrieving shared object of chunk with id `{}`", chunk_id);
+    Ok(sqlx::query_as!(
+        schema::SharedObjectRecord,
+        r#"
+            SELECT shared.*
+            FROM shared_object_t shared
+            JOIN chunk_shared_object_t chunk USING (shared_object_id)
+            WHERE chunk.chunk_id = $1
+    "#,
+        chunk_id
+    )
+    .fetch_one(exe.as_exec())
+    .await?)
+}
+
+/// Records that the data file of a chunk is linked to a shared object.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn chunk_shared_object_create(
+    exe: &mut impl AsExec,
+    chunk_id: i32,
+    shared_object_id: i32,
+) -> Result<(), Error> {
+    trace!(
+        "linking chunk with id `{}` to shared object with id `{}`",
+        chunk_id, shared_object_id
+    );
+    sqlx::query!(
+        "INSERT INTO chunk_shared_object_t (chunk_id, shared_object_id) VALUES ($1, $2)",
+        chunk_id,
+        shared_object_id
+    )
+    .execute(exe.as_exec())
+    .await?;
+    Ok(())
+}
+
+/// Returns the number of chunks referencing a shared object.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn shared_object_ref_count(
+    exe: &mut impl AsExec,
+    shared_object_id: i32,
+) -> Result<u64, Error> {
+    trace!(
+        "counting references to shared object with id `{}`",
+        shared_object_id
+    );
+    let count = sqlx::query_scalar!(
+        r#"
+            SELECT COUNT(*) AS "count!" FROM chunk_shared_object_t
+            WHERE shared_object_id = $1
+    "#,
+        shared_object_id
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(count as u64)
+}
+
+/// Returns at most `limit` shared objects no longer referenced by any chunk.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn shared_object_find_unreferenced(
+    exe: &mut impl AsExec,
+    limit: i64,
+) -> Result<Vec<schema::SharedObjectRecord>, Error> {
+    trace!("retrieving {} unreferenced shared objects", limit);
+    Ok(sqlx::query_as!(
+        schema::SharedObjectRecord,
+        r#"
+            SELECT * FROM shared_object_t shared
+            WHERE NOT EXISTS (
+                SELECT 1 FROM chunk_shared_object_t chunk
+                WHERE chunk.shared_object_id = shared.shared_object_id
+            )
+            ORDER BY shared_object_id
+            LIMIT $1
+    "#,
+        limit
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Deletes a shared object unless it is referenced by a chunk. Returns False if the
+/// object is referenced or was already deleted.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn shared_object_delete_unreferenced(
+    exe: &mut impl AsExec,
+    shared_object_id: i32,
+) -> Result<bool, Error> {
+    trace!("deleting shared object with id `{}`", shared_object_id);
+    let res = sqlx::query!(
+        r#"
+            DELETE FROM shared_object_t shared
+            WHERE shared_object_id = $1 AND NOT EXISTS (
+                SELECT 1 FROM chunk_shared_object_t chunk
+                WHERE chunk.shared_object_id = shared.shared_object_id
+            )
+    "#,
+        shared_object_id
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    Ok(res.rows_affected() != 0)
+}
@@ -0,0 +1,77 @@
+use crate::{Error, core::AsExec, sql::schema};
+use mosaicod_core::types;
+use tracing::trace;
+
+/// Records the relocation of the root folder of a resource from `source_path` to
+/// `target_path`.
+///
+/// Returns the relocation already recorded for the resource, if any.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn store_relocation_create(
+    exe: &mut impl AsExec,
+    kind: types::ResourceKind,
+    resource_id: i32,
+    source_path: &str,
+    target_path: &str,
+) -> Result<schema::StoreRelocationRecord, Error> {
+    trace!(
+        "recording relocation of {} with id {} from `{}` to `{}`",
+        kind, resource_id, source_path, target_path
+    );
+    let kind = schema::StoreRelocationRecord::kind_name(kind);
+    sqlx::query!(
+        r#"
+            INSERT INTO store_relocation_t
+                (resource_kind, resource_id, source_path, target_path, creation_unix_tstamp)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (resource_kind, resource_id) DO NOTHING
+    "#,
+        kind,
+        resource_id,
+        source_path,
+        target_path,
+        types::Timestamp::now().as_i64(),
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    Ok(sqlx::query_as!(
+        schema::StoreRelocationRecord,
+        "SELECT * FROM store_relocation_t WHERE resource_kind=$1 AND resource_id=$2",
+        kind,
+        resource_id,
+    )
+    .fetch_one(exe.as_exec())
+    .await?)
+}
+
+/// Returns at most `limit` relocations, oldest first.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn store_relocation_find_page(
+    exe: &mut impl AsExec,
+    limit: i64,
+) -> Result<Vec<schema::StoreRelocationRecord>, Error> {
+    trace!("retrieving {} store relocations", limit);
+    Ok(sqlx::query_as!(
+        schema::StoreRelocationRecord,
+        "SELECT * FROM store_relocation_t ORDER BY relocation_id LIMIT $1",
+        limit
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn store_relocation_delete(
+    exe: &mut impl AsExec,
+    relocation_id: i32,
+) -> Result<(), Error> {
+    trace!("deleting store relocation with id {}", relocation_id);
+    sqlx::query!(
+        "DELETE FROM store_relocation_t WHERE relocation_id=$1",
+        relocation_id
+    )
+    .execute(exe.as_exec())
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use crate::{Error, core::AsExec, sql::schema};
+use tracing::trace;
+
+/// Creates the documentation of a topic column, replacing the existing one if any.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_column_upsert(
+    exe: &mut impl AsExec,
+    record: &schema::TopicColumnRecord,
+) -> Result<schema::TopicColumnRecord, Error> {
+    trace!("upserting topic column record {:?}", record);
+    let res = sqlx::query_as!(
+        schema::TopicColumnRecord,
+        r#"
+            INSERT INTO topic_column_t
+                (topic_id, column_name, description, unit)
+            VALUES
+                ($1, $2, $3, $4)
+            ON CONFLICT (topic_id, column_name) DO UPDATE
+            SET
+                description = EXCLUDED.description,
+                unit = EXCLUDED.unit
+            RETURNING
+                *
+    "#,
+        record.topic_id,
+        record.column_name,
+        record.description,
+        record.unit,
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(res)
+}
+
+/// Find the documentation of all the columns of a topic, sorted by column name.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_find_all_columns(
+    exe: &mut impl AsExec,
+    topic_id: i32,
+) -> Result<Vec<schema::TopicColumnRecord>, Error> {
+    trace!("searching columns of topic with id `{}`", topic_id);
+    Ok(sqlx::query_as!(
+        schema::TopicColumnRecord,
+        "SELECT * FROM topic_column_t WHERE topic_id=$1 ORDER BY column_name",
+        topic_id
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Find the columns with a unit of the topics having the given ontology tag, among the
+/// given column names.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_find_columns_with_unit(
+    exe: &mut impl AsExec,
+    ontology_tag: &str,
+    column_names: &[String],
+) -> Result<Vec<schema::TopicColumnRecord>, Error> {
+    trace!(
+        "searching columns {:?} with unit of topics with ontology tag `{}`",
+        column_names, ontology_tag
+    );
+    Ok(sqlx::query_as!(
+        schema::TopicColumnRecord,
+        r#"
+            SELECT col.* FROM topic_column_t col
+            JOIN topic_t topic USING (topic_id)
+            WHERE topic.ontology_tag = $1
+                AND col.column_name = ANY($2)
+                AND col.unit IS NOT NULL
+    "#,
+        ontology_tag,
+        column_names
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Caches the statistics of a topic column, replacing the existing ones if any.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_column_stats_upsert(
+    exe: &mut impl AsExec,
+    record: &schema::TopicColumnStatsRecord,
+) -> Result<schema::TopicColumnStatsRecord, Error> {
+    trace!("upserting topic column stats record {:?}", record);
+    let res = sqlx::query_as!(
+        schema::TopicColumnStatsRecord,
+        r#"
+            INSERT INTO topic_column_stats_t
+                (topic_id, column_name, row_count, null_count, min_numeric, max_numeric,
+                 min_textual, max_textual, mean, cardinality)
+            VALUES
+                ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (topic_id, column_name) DO UPDATE
+            SET
+                row_count = EXCLUDED.row_count,
+                null_count = EXCLUDED.null_count,
+                min_numeric = EXCLUDED.min_numeric,
+                max_numeric = EXCLUDED.max_numeric,
+                min_textual = EXCLUDED.min_textual,
+                max_textual = EXCLUDED.max_textual,
+                mean = EXCLUDED.mean,
+                cardinality = EXCLUDED.cardinality
+            RETURNING
+                *
+    "#,
+        record.topic_id,
+        record.column_name,
+        record.row_count,
+        record.null_count,
+        record.min_numeric,
+        record.max_numeric,
+        record.min_textual,
+        record.max_textual,
+        record.mean,
+        record.cardinality,
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(res)
+}
+
+/// Find the cached statistics of a topic, among the given column names.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_find_column_stats(
+    exe: &mut impl AsExec,
+    topic_id: i32,
+    column_names: &[String],
+) -> Result<Vec<schema::TopicColumnStatsRecord>, Error> {
+    trace!(
+        "searching stats of columns {:?} of topic with id `{}`",
+        column_names, topic_id
+    );
+    Ok(sqlx::query_as!(
+        schema::TopicColumnStatsRecord,
+        r#"
+            SELECT * FROM topic_column_stats_t
+            WHERE topic_id = $1 AND column_name = ANY($2)
+    "#,
+        topic_id,
+        column_names
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Deletes the cached statistics of all the columns of a topic.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_delete_column_stats(exe: &mut impl AsExec, topic_id: i32) -> Result<(), Error> {
+    trace!("deleting column stats of topic with id `{}`", topic_id);
+    sqlx::query!(
+        "DELETE FROM topic_column_stats_t WHERE topic_id=$1",
+        topic_id
+    )
+    .execute(exe.as_exec())
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use crate::{Error, core::AsExec, sql::schema};
+use tracing::trace;
+
+/// Records a source in the lineage of a topic.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_lineage_create(
+    exe: &mut impl AsExec,
+    record: &schema::TopicLineageRecord,
+) -> Result<(), Error> {
+    trace!(
+        "recording source `{}` of topic with id `{}`",
+        record.source_locator, record.topic_id
+    );
+    sqlx::query!(
+        r#"
+            INSERT INTO topic_lineage_t
+                (topic_id, depth, source_topic_uuid, source_locator, creation_unix_tstamp)
+            VALUES
+                ($1, $2, $3, $4, $5)
+    "#,
+        record.topic_id,
+        record.depth,
+        record.source_topic_uuid,
+        record.source_locator,
+        record.creation_unix_tstamp,
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    Ok(())
+}
+
+/// Returns the lineage of a topic, from the nearest source.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_lineage_find_all(
+    exe: &mut impl AsExec,
+    topic_id: i32,
+) -> Result<Vec<schema::TopicLineageRecord>, Error> {
+    trace!("retrieving lineage of topic with id `{}`", topic_id);
+    Ok(sqlx::query_as!(
+        schema::TopicLineageRecord,
+        "SELECT * FROM topic_lineage_t WHERE topic_id = $1 ORDER BY depth",
+        topic_id
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
@@ -1,9 +1,9 @@
 use crate::{Error, core::AsExec, sql::schema};
-use log::{trace, warn};
 use mosaicod_core::types;
 use mosaicod_marshal as marshal;
 use mosaicod_query as query;
 use sqlx::{Row, postgres::PgRow};
+use tracing::{trace, warn};
 
 fn cast_topic_data(row: PgRow) -> Result<schema::TopicRecord, Error> {
     Ok(schema::TopicRecord {
@@ -22,10 +22,18 @@ fn cast_topic_data(row: PgRow) -> Result<schema::TopicRecord, Error> {
         total_bytes: row.try_get("total_bytes")?,
         start_index_timestamp: row.try_get("start_index_timestamp")?,
         end_index_timestamp: row.try_get("end_index_timestamp")?,
+        sort_key: row.try_get("sort_key")?,
+        is_sorted: row.try_get("is_sorted")?,
+        dedup_policy: row.try_get("dedup_policy")?,
+        dedup_key: row.try_get("dedup_key")?,
+        primary_key: row.try_get("primary_key")?,
+        compaction_unix_tstamp: row.try_get("compaction_unix_tstamp")?,
+        arrow_schema: row.try_get("arrow_schema")?,
     })
 }
 
 /// Find a topic given its uuid.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn topic_find_by_ids(
     exe: &mut impl AsExec,
     ids: &[i32],
@@ -41,7 +49,8 @@ pub async fn topic_find_by_ids(
     Ok(res)
 }
 
-/// Find a topic given its name.
+/// Find a topic given its name, topics of the sequences in the trash are not returned.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn topic_find_by_locator(
     exe: &mut impl AsExec,
     topic: &types::TopicLocator,
@@ -49,7 +58,12 @@ pub async fn topic_find_by_locator(
     trace!("searching topic by locator name `{}`", topic);
     let res = sqlx::query_as!(
         schema::TopicRecord,
-        "SELECT * FROM topic_t WHERE locator_name=$1",
+        r#"
+        SELECT topic.*
+        FROM topic_t AS topic
+        JOIN sequence_t AS sequence ON topic.sequence_id = sequence.sequence_id
+        WHERE topic.locator_name = $1 AND sequence.trashed_unix_tstamp IS NULL
+        "#,
         topic.to_string()
     )
     .fetch_one(exe.as_exec())
@@ -58,6 +72,7 @@ pub async fn topic_find_by_locator(
 }
 
 /// Find a topic given its UUID.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn topic_find_by_uuid(
     exe: &mut impl AsExec,
     uuid: &types::Uuid,
@@ -74,6 +89,7 @@ pub async fn topic_find_by_uuid(
 }
 
 /// Find a topic given its id.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn topic_find_by_id(
     exe: &mut impl AsExec,
     topic_id: i32,
@@ -89,14 +105,131 @@ pub async fn topic_find_by_id(
     Ok(res)
 }
 
-/// Return all topics
-pub async fn topic_find_all(exe: &mut impl AsExec) -> Result<Vec<schema::TopicRecord>, Error> {
-    trace!("retrieving all topics");
-    Ok(
-        sqlx::query_as!(schema::TopicRecord, "SELECT * FROM topic_t")
-            .fetch_all(exe.as_exec())
-            .await?,
+/// Return at most `limit` topics whose locator starts with `prefix` and follows `after`,
+/// sorted by locator. Topics of the sequences in the trash are not returned.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_find_page(
+    exe: &mut impl AsExec,
+    prefix: &str,
+    after: Option<&str>,
+    limit: i64,
+) -> Result<Vec<schema::TopicRecord>, Error> {
+    trace!(
+        "retrieving {} topics starting with `{}` after `{:?}`",
+        limit, prefix, after
+    );
+    Ok(sqlx::query_as!(
+        schema::TopicRecord,
+        r#"
+        SELECT topic.*
+        FROM topic_t AS topic
+        JOIN sequence_t AS sequence ON topic.sequence_id = sequence.sequence_id
+        WHERE topic.locator_name LIKE $1 AND ($2::TEXT IS NULL OR topic.locator_name > $2)
+            AND sequence.trashed_unix_tstamp IS NULL
+        ORDER BY topic.locator_name
+        LIMIT $3
+        "#,
+        super::like_prefix(prefix),
+        after,
+        limit
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Return at most `limit` topics of a sequence whose locator follows `after`, sorted by
+/// locator
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_find_page_by_sequence(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+    after: Option<&str>,
+    limit: i64,
+) -> Result<Vec<schema::TopicRecord>, Error> {
+    trace!(
+        "retrieving {} topics of sequence with id `{}` after `{:?}`",
+        limit, sequence_id, after
+    );
+    Ok(sqlx::query_as!(
+        schema::TopicRecord,
+        r#"
+        SELECT * FROM topic_t
+        WHERE sequence_id = $1 AND ($2::TEXT IS NULL OR locator_name > $2)
+        ORDER BY locator_name
+        LIMIT $3
+        "#,
+        sequence_id,
+        after,
+        limit
     )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Return all finalized upsert topics stored in at least `min_chunks` data files
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_find_all_compactable(
+    exe: &mut impl AsExec,
+    min_chunks: i64,
+) -> Result<Vec<schema::TopicRecord>, Error> {
+    trace!(
+        "retrieving upsert topics with at least {} chunks",
+        min_chunks
+    );
+    Ok(sqlx::query_as!(
+        schema::TopicRecord,
+        r#"
+            SELECT * FROM topic_t
+            WHERE primary_key IS NOT NULL
+                AND completion_unix_tstamp IS NOT NULL
+                AND chunks_number >= $1
+    "#,
+        min_chunks
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Return up to `limit` finalized topics with an id greater than `after`, sorted by id.
+///
+/// Used to scan all the finalized topics in batches.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_find_finalized_page(
+    exe: &mut impl AsExec,
+    after: Option<i32>,
+    limit: i64,
+) -> Result<Vec<schema::TopicRecord>, Error> {
+    trace!("retrieving {} finalized topics after id {:?}", limit, after);
+    Ok(sqlx::query_as!(
+        schema::TopicRecord,
+        r#"
+            SELECT * FROM topic_t
+            WHERE completion_unix_tstamp IS NOT NULL
+                AND ($1::INTEGER IS NULL OR topic_id > $1)
+            ORDER BY topic_id
+            LIMIT $2
+    "#,
+        after,
+        limit
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Locks the topic record until the end of the transaction, concurrent transactions trying
+/// to lock the same topic wait for the lock to be released.
+///
+/// The lock does not prevent the creation of records referencing the topic (e.g. chunks).
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_lock(exe: &mut impl AsExec, topic_id: i32) -> Result<(), Error> {
+    trace!("locking topic with id `{}`", topic_id);
+    sqlx::query!(
+        "SELECT topic_id FROM topic_t WHERE topic_id=$1 FOR NO KEY UPDATE",
+        topic_id
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(())
 }
 
 /// Deletes a topic record from the database by its id, **bypassing any lock state**.
@@ -104,6 +237,7 @@ pub async fn topic_find_all(exe: &mut impl AsExec) -> Result<Vec<schema::TopicRe
 /// This function requires a [`DataLossToken`] since permanently removes the record
 /// from the database without checking whether it is locked or referenced
 /// elsewhere. Improper use can lead to data inconsistency or loss.
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn topic_delete(
     exe: &mut impl AsExec,
     topic_id: i32,
@@ -121,6 +255,7 @@ pub async fn topic_delete(
     Ok(())
 }
 
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn topic_create(
     exe: &mut impl AsExec,
     record: &schema::TopicRecord,
@@ -133,10 +268,11 @@ pub async fn topic_create(
                 (
                     topic_uuid, sequence_id, session_id, locator_name, creation_unix_tstamp,
                     serialization_format, ontology_tag, user_metadata, chunks_number,
-                    total_bytes, start_index_timestamp, end_index_timestamp, path_in_store
+                    total_bytes, start_index_timestamp, end_index_timestamp, path_in_store,
+                    sort_key, dedup_policy, dedup_key, primary_key, arrow_schema
                 ) 
             VALUES 
-                ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+                ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
             RETURNING 
                 *
     "#,
@@ -153,12 +289,18 @@ pub async fn topic_create(
         record.start_index_timestamp,
         record.end_index_timestamp,
         record.path_in_store,
+        record.sort_key,
+        record.dedup_policy,
+        record.dedup_key,
+        record.primary_key,
+        record.arrow_schema,
     )
     .fetch_one(exe.as_exec())
     .await?;
     Ok(res)
 }
 
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn topic_update_serialization_format(
     exe: &mut impl AsExec,
     loc: &types::TopicLocator,
@@ -184,6 +326,7 @@ pub async fn topic_update_serialization_format(
     Ok(res)
 }
 
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn topic_update_ontology_tag(
     exe: &mut impl AsExec,
     loc: &types::TopicLocator,
@@ -207,6 +350,7 @@ pub async fn topic_update_ontology_tag(
     Ok(res)
 }
 
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn topic_update_user_metadata(
     exe: &mut impl AsExec,
     loc: &types::TopicLocator,
@@ -231,6 +375,7 @@ pub async fn topic_update_user_metadata(
     Ok(res)
 }
 
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn topic_update_system_info(
     exe: &mut impl AsExec,
     loc: &types::TopicLocator,
@@ -257,6 +402,7 @@ pub async fn topic_update_system_info(
     Ok(res)
 }
 
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn topic_update_completion_tstamp(
     exe: &mut impl AsExec,
     topic_id: i32,
@@ -281,6 +427,57 @@ pub async fn topic_update_completion_tstamp(
     Ok(())
 }
 
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_update_compaction_tstamp(
+    exe: &mut impl AsExec,
+    topic_id: i32,
+    compaction_ts: i64,
+) -> Result<(), Error> {
+    trace!(
+        "updating compaction timestamp to `{}` for topic `{}`",
+        compaction_ts, topic_id
+    );
+    sqlx::query!(
+        r#"
+            UPDATE topic_t
+            SET compaction_unix_tstamp = $1
+            WHERE topic_id = $2
+    "#,
+        compaction_ts,
+        topic_id,
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    Ok(())
+}
+
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_update_sorted(
+    exe: &mut impl AsExec,
+    topic_id: i32,
+    is_sorted: bool,
+) -> Result<(), Error> {
+    trace!(
+        "updating is_sorted to `{}` for topic with id {}",
+        is_sorted, topic_id
+    );
+    sqlx::query!(
+        r#"
+            UPDATE topic_t
+            SET is_sorted = $1
+            WHERE topic_id = $2
+    "#,
+        is_sorted,
+        topic_id,
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    Ok(())
+}
+
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn topic_update_path_in_store(
     exe: &mut impl AsExec,
     topic_id: i32,
@@ -305,6 +502,7 @@ pub async fn topic_update_path_in_store(
     Ok(())
 }
 
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn topic_from_query_filter(
     exe: &mut impl AsExec,
     filter_seq: Option<query::SequenceFilter>,
@@ -375,6 +573,29 @@ pub async fn topic_from_query_filter(
         for (field, op) in top.user_metadata {
             qb = qb.expr(&field, op, fmt);
         }
+
+        // Conditions on columns need to be satisfied by the same column of the topic
+        if let Some(column) = top.column {
+            let mut column_qb = query::ClausesCompiler::new();
+
+            if let Some(op) = column.name {
+                column_qb = column_qb.expr("col.column_name", op, &mut sql_fmt);
+            }
+
+            if let Some(op) = column.description {
+                column_qb = column_qb.expr("col.description", op, &mut sql_fmt);
+            }
+
+            if let Some(op) = column.unit {
+                column_qb = column_qb.expr("col.unit", op, &mut sql_fmt);
+            }
+
+            qb = qb.nested(column_qb, |clauses| {
+                format!(
+                    "EXISTS (SELECT 1 FROM topic_column_t col WHERE col.topic_id = topic.topic_id AND {clauses})"
+                )
+            });
+        }
     }
 
     let qr = qb.compile()?;
@@ -385,7 +606,10 @@ pub async fn topic_from_query_filter(
     }
 
     // Since we have do an early-return is the query is unfiltered there is always a WHERE clause
-    let query = format!("{select} WHERE {}", qr.clauses.join(" AND "));
+    let query = format!(
+        "{select} WHERE sequence.trashed_unix_tstamp IS NULL AND {}",
+        qr.clauses.join(" AND ")
+    );
 
     trace!("query values: {:?}", qr.values);
     trace!("generated SQL query: {}", query);
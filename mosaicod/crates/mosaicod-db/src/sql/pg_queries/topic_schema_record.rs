@@ -0,0 +1,71 @@
+use crate::{Error, core::AsExec, sql::schema};
+use tracing::trace;
+
+/// Registers a new version of the schema of a topic, making it the current schema.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_schema_create(
+    exe: &mut impl AsExec,
+    record: &schema::TopicSchemaRecord,
+) -> Result<schema::TopicSchemaRecord, Error> {
+    trace!(
+        "registering schema version {} of topic with id `{}`",
+        record.version, record.topic_id
+    );
+    let res = sqlx::query_as!(
+        schema::TopicSchemaRecord,
+        r#"
+            INSERT INTO topic_schema_t (topic_id, version, arrow_schema, creation_unix_tstamp)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+    "#,
+        record.topic_id,
+        record.version,
+        record.arrow_schema,
+        record.creation_unix_tstamp,
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+
+    sqlx::query!(
+        "UPDATE topic_t SET arrow_schema = $2 WHERE topic_id = $1",
+        record.topic_id,
+        record.arrow_schema,
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    Ok(res)
+}
+
+/// Returns the versions of the schema of a topic, from the oldest.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_schema_find_all(
+    exe: &mut impl AsExec,
+    topic_id: i32,
+) -> Result<Vec<schema::TopicSchemaRecord>, Error> {
+    trace!("retrieving schema versions of topic with id `{}`", topic_id);
+    Ok(sqlx::query_as!(
+        schema::TopicSchemaRecord,
+        "SELECT * FROM topic_schema_t WHERE topic_id=$1 ORDER BY version",
+        topic_id
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Returns the current version of the schema of a topic, `None` if the topic has no
+/// registered schema.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_schema_find_latest(
+    exe: &mut impl AsExec,
+    topic_id: i32,
+) -> Result<Option<schema::TopicSchemaRecord>, Error> {
+    trace!("retrieving current schema of topic with id `{}`", topic_id);
+    Ok(sqlx::query_as!(
+        schema::TopicSchemaRecord,
+        "SELECT * FROM topic_schema_t WHERE topic_id=$1 ORDER BY version DESC LIMIT 1",
+        topic_id
+    )
+    .fetch_optional(exe.as_exec())
+    .await?)
+}
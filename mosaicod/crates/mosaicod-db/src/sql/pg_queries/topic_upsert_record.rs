@@ -0,0 +1,253 @@
+use crate::{Error, core::AsExec, sql::schema};
+use mosaicod_core::types;
+use tracing::trace;
+
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_upsert_create(
+    exe: &mut impl AsExec,
+    record: &schema::TopicUpsertRecord,
+) -> Result<schema::TopicUpsertRecord, Error> {
+    trace!("creating a new topic upsert record {:?}", record);
+    let res = sqlx::query_as!(
+        schema::TopicUpsertRecord,
+        r#"
+            INSERT INTO topic_upsert_t
+                (upsert_uuid, topic_id, session_id, creation_unix_tstamp, base_version)
+            VALUES
+                ($1, $2, $3, $4, $5)
+            RETURNING
+                *
+    "#,
+        record.upsert_uuid,
+        record.topic_id,
+        record.session_id,
+        record.creation_unix_tstamp,
+        record.base_version,
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(res)
+}
+
+/// Find a topic upsert given its UUID.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_upsert_find_by_uuid(
+    exe: &mut impl AsExec,
+    uuid: &types::Uuid,
+) -> Result<schema::TopicUpsertRecord, Error> {
+    trace!("searching topic upsert by uuid `{}`", uuid);
+    let res = sqlx::query_as!(
+        schema::TopicUpsertRecord,
+        "SELECT * FROM topic_upsert_t WHERE upsert_uuid=$1",
+        uuid.as_ref()
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(res)
+}
+
+/// Find a topic upsert given its id.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_upsert_find_by_id(
+    exe: &mut impl AsExec,
+    upsert_id: i32,
+) -> Result<schema::TopicUpsertRecord, Error> {
+    trace!("searching topic upsert by id `{}`", upsert_id);
+    let res = sqlx::query_as!(
+        schema::TopicUpsertRecord,
+        "SELECT * FROM topic_upsert_t WHERE upsert_id=$1",
+        upsert_id
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(res)
+}
+
+/// Find all the upserts performed by a session
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn session_find_all_upserts(
+    exe: &mut impl AsExec,
+    session_id: i32,
+) -> Result<Vec<schema::TopicUpsertRecord>, Error> {
+    trace!(
+        "searching topic upserts for session with id `{}`",
+        session_id
+    );
+    Ok(sqlx::query_as!(
+        schema::TopicUpsertRecord,
+        "SELECT * FROM topic_upsert_t WHERE session_id=$1",
+        session_id
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Returns true if an upload of data is in progress for any upsert of the topic.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_upsert_upload_in_progress(
+    exe: &mut impl AsExec,
+    topic_id: i32,
+) -> Result<bool, Error> {
+    trace!("topic (id=`{}`) upsert in progress?", topic_id);
+    let in_progress = sqlx::query_scalar!(
+        r#"
+            SELECT EXISTS(
+                SELECT 1 FROM topic_upsert_t
+                WHERE topic_id = $1
+                    AND upload_unix_tstamp IS NOT NULL
+                    AND completion_unix_tstamp IS NULL
+            ) AS "in_progress!"
+    "#,
+        topic_id
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(in_progress)
+}
+
+/// Returns the version of an upsert topic, i.e. the number of upserts completed on it.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_upsert_version(exe: &mut impl AsExec, topic_id: i32) -> Result<u64, Error> {
+    trace!("version of topic (id=`{}`)", topic_id);
+    let version = sqlx::query_scalar!(
+        r#"
+            SELECT COUNT(*) AS "version!" FROM topic_upsert_t
+            WHERE topic_id = $1 AND completion_unix_tstamp IS NOT NULL
+    "#,
+        topic_id
+    )
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(version as u64)
+}
+
+/// Find all the upserts of a topic completed after the given timestamp, sorted by completion.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_find_all_upserts_completed_after(
+    exe: &mut impl AsExec,
+    topic_id: i32,
+    after_ts: i64,
+) -> Result<Vec<schema::TopicUpsertRecord>, Error> {
+    trace!(
+        "searching upserts of topic with id `{}` completed after `{}`",
+        topic_id, after_ts
+    );
+    Ok(sqlx::query_as!(
+        schema::TopicUpsertRecord,
+        r#"
+            SELECT * FROM topic_upsert_t
+            WHERE topic_id = $1 AND completion_unix_tstamp > $2
+            ORDER BY completion_unix_tstamp
+    "#,
+        topic_id,
+        after_ts
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Find all the upserts of a topic, sorted by the first data file they wrote. Upserts that
+/// did not start uploading data come last.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_find_all_upserts(
+    exe: &mut impl AsExec,
+    topic_id: i32,
+) -> Result<Vec<schema::TopicUpsertRecord>, Error> {
+    trace!("searching upserts of topic with id `{}`", topic_id);
+    Ok(sqlx::query_as!(
+        schema::TopicUpsertRecord,
+        r#"
+            SELECT * FROM topic_upsert_t
+            WHERE topic_id = $1
+            ORDER BY first_chunk_number NULLS LAST, upload_unix_tstamp, upsert_id
+    "#,
+        topic_id
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Find the upsert of a topic whose upload of data is in progress, if any.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_find_upsert_uploading(
+    exe: &mut impl AsExec,
+    topic_id: i32,
+) -> Result<Option<schema::TopicUpsertRecord>, Error> {
+    trace!("searching uploading upsert of topic with id `{}`", topic_id);
+    Ok(sqlx::query_as!(
+        schema::TopicUpsertRecord,
+        r#"
+            SELECT * FROM topic_upsert_t
+            WHERE topic_id = $1
+                AND upload_unix_tstamp IS NOT NULL
+                AND completion_unix_tstamp IS NULL
+    "#,
+        topic_id
+    )
+    .fetch_optional(exe.as_exec())
+    .await?)
+}
+
+/// Tries to mark the start of the data upload for the given upsert, writing data files
+/// starting from `first_chunk`.
+///
+/// Returns False if the upload was already started or if another upsert of the same topic is
+/// uploading data, otherwise True.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_upsert_try_start_upload(
+    exe: &mut impl AsExec,
+    upsert_id: i32,
+    upload_ts: i64,
+    first_chunk: i64,
+) -> Result<bool, Error> {
+    trace!(
+        "updating upload timestamp to `{}` for topic upsert `{}`",
+        upload_ts, upsert_id
+    );
+    let res = sqlx::query!(
+        r#"
+            UPDATE topic_upsert_t AS upsert
+            SET upload_unix_tstamp = $1, first_chunk_number = $3
+            WHERE upsert.upsert_id = $2
+                AND upsert.upload_unix_tstamp IS NULL
+                AND NOT EXISTS(
+                    SELECT 1 FROM topic_upsert_t AS other
+                    WHERE other.topic_id = upsert.topic_id
+                        AND other.upload_unix_tstamp IS NOT NULL
+                        AND other.completion_unix_tstamp IS NULL
+                )
+    "#,
+        upload_ts,
+        upsert_id,
+        first_chunk,
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    Ok(res.rows_affected() != 0)
+}
+
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_upsert_update_completion_tstamp(
+    exe: &mut impl AsExec,
+    upsert_id: i32,
+    completion_ts: i64,
+) -> Result<(), Error> {
+    trace!(
+        "updating completion timestamp to `{}` for topic upsert `{}`",
+        completion_ts, upsert_id
+    );
+    sqlx::query!(
+        r#"
+            UPDATE topic_upsert_t
+            SET completion_unix_tstamp = $1
+            WHERE upsert_id = $2
+    "#,
+        completion_ts,
+        upsert_id,
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    Ok(())
+}
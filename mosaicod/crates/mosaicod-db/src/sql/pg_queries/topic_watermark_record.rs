@@ -0,0 +1,69 @@
+use crate::{Error, core::AsExec, sql::schema};
+use tracing::trace;
+
+/// Records an advance of the watermark of a topic. Returns False if the watermark of the
+/// topic is already at or past the one of the record, otherwise True.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_watermark_try_advance(
+    exe: &mut impl AsExec,
+    record: &schema::TopicWatermarkRecord,
+) -> Result<bool, Error> {
+    trace!(
+        "advancing watermark of topic with id `{}` to `{}`",
+        record.topic_id, record.watermark_unix_tstamp
+    );
+    let res = sqlx::query!(
+        r#"
+            INSERT INTO topic_watermark_t (topic_id, watermark_unix_tstamp, creation_unix_tstamp)
+            SELECT $1, $2, $3
+            WHERE NOT EXISTS(
+                SELECT 1 FROM topic_watermark_t
+                WHERE topic_id = $1 AND watermark_unix_tstamp >= $2
+            )
+    "#,
+        record.topic_id,
+        record.watermark_unix_tstamp,
+        record.creation_unix_tstamp,
+    )
+    .execute(exe.as_exec())
+    .await?;
+
+    Ok(res.rows_affected() != 0)
+}
+
+/// Returns the current watermark of a topic, `None` if no data was published yet.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_watermark_find_latest(
+    exe: &mut impl AsExec,
+    topic_id: i32,
+) -> Result<Option<schema::TopicWatermarkRecord>, Error> {
+    trace!("retrieving watermark of topic with id `{}`", topic_id);
+    Ok(sqlx::query_as!(
+        schema::TopicWatermarkRecord,
+        r#"
+            SELECT * FROM topic_watermark_t
+            WHERE topic_id = $1
+            ORDER BY watermark_unix_tstamp DESC
+            LIMIT 1
+    "#,
+        topic_id
+    )
+    .fetch_optional(exe.as_exec())
+    .await?)
+}
+
+/// Returns the advances of the watermark of a topic, from the oldest.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn topic_watermark_find_all(
+    exe: &mut impl AsExec,
+    topic_id: i32,
+) -> Result<Vec<schema::TopicWatermarkRecord>, Error> {
+    trace!("retrieving watermarks of topic with id `{}`", topic_id);
+    Ok(sqlx::query_as!(
+        schema::TopicWatermarkRecord,
+        "SELECT * FROM topic_watermark_t WHERE topic_id = $1 ORDER BY watermark_unix_tstamp",
+        topic_id
+    )
+    .fetch_all(exe.as_exec())
+    .await?)
+}
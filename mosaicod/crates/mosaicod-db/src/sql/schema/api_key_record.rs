@@ -22,6 +22,9 @@ pub struct ApiKeyRecord {
 
     /// UNIX timestamp in milliseconds of the expiration date
     pub(crate) expiration_unix_timestamp: Option<i64>,
+
+    /// Sequence searched by the queries not filtering sequences
+    pub(crate) default_sequence: Option<String>,
 }
 
 impl TryFrom<ApiKeyRecord> for types::ApiKey {
@@ -44,6 +47,11 @@ impl TryFrom<ApiKeyRecord> for types::ApiKey {
             description: value.description,
             created_at: value.creation_unix_timestamp.into(),
             expires_at: value.expiration_unix_timestamp.map(Into::into),
+            default_sequence: value
+                .default_sequence
+                .map(|sequence| sequence.parse())
+                .transpose()
+                .map_err(|_| types::auth::ApiKeyError::BadDefaultSequence)?,
         })
     }
 }
@@ -57,6 +65,7 @@ impl From<types::ApiKey> for ApiKeyRecord {
             description: value.description,
             creation_unix_timestamp: value.created_at.into(),
             expiration_unix_timestamp: value.expires_at.map(|v| v.into()),
+            default_sequence: value.default_sequence.map(Into::into),
         }
     }
 }
@@ -0,0 +1,41 @@
+use mosaicod_core::types;
+
+/// Compaction tuning of the upsert topics of a sequence.
+#[derive(Debug, Clone)]
+pub struct SequenceCompactionPolicyRecord {
+    pub sequence_id: i32,
+
+    pub(crate) target_object_size_bytes: Option<i64>,
+    pub(crate) interval_secs: Option<i64>,
+    pub(crate) min_chunks: Option<i64>,
+    pub(crate) cold_after_secs: Option<i64>,
+
+    /// UNIX timestamp in nanoseconds
+    pub(crate) update_unix_tstamp: i64,
+}
+
+impl SequenceCompactionPolicyRecord {
+    pub fn new(sequence_id: i32, policy: &types::CompactionPolicy) -> Self {
+        Self {
+            sequence_id,
+            target_object_size_bytes: policy.target_object_size.map(|v| v as i64),
+            interval_secs: policy.interval.map(|v| v as i64),
+            min_chunks: policy.min_chunks.map(|v| v as i64),
+            cold_after_secs: policy.cold_after.map(|v| v as i64),
+            update_unix_tstamp: types::Timestamp::now().as_i64(),
+        }
+    }
+
+    pub fn policy(&self) -> types::CompactionPolicy {
+        types::CompactionPolicy {
+            target_object_size: self.target_object_size_bytes.map(|v| v as u64),
+            interval: self.interval_secs.map(|v| v as u64),
+            min_chunks: self.min_chunks.map(|v| v as u64),
+            cold_after: self.cold_after_secs.map(|v| v as u64),
+        }
+    }
+
+    pub fn update_timestamp(&self) -> types::Timestamp {
+        self.update_unix_tstamp.into()
+    }
+}
@@ -0,0 +1,96 @@
+use mosaicod_core::types;
+
+/// Position in the activity feed of a sequence committed by a named consumer.
+#[derive(Debug, Clone)]
+pub struct SequenceConsumerOffsetRecord {
+    pub consumer_name: String,
+    pub sequence_id: i32,
+
+    pub(crate) cursor_unix_tstamp: i64,
+    pub(crate) cursor_skip: i64,
+
+    /// UNIX timestamp in nanoseconds
+    pub(crate) commit_unix_tstamp: i64,
+}
+
+impl SequenceConsumerOffsetRecord {
+    pub fn new(
+        consumer_name: String,
+        sequence_id: i32,
+        cursor: types::ActivityCursor,
+        committed_at: types::Timestamp,
+    ) -> Self {
+        Self {
+            consumer_name,
+            sequence_id,
+            cursor_unix_tstamp: cursor.timestamp.as_i64(),
+            cursor_skip: cursor.skip as i64,
+            commit_unix_tstamp: committed_at.as_i64(),
+        }
+    }
+
+    pub fn cursor(&self) -> types::ActivityCursor {
+        types::ActivityCursor {
+            timestamp: self.cursor_unix_tstamp.into(),
+            skip: self.cursor_skip as usize,
+        }
+    }
+
+    pub fn commit_timestamp(&self) -> types::Timestamp {
+        self.commit_unix_tstamp.into()
+    }
+}
+
+impl From<SequenceConsumerOffsetRecord> for types::ConsumerOffset {
+    fn from(record: SequenceConsumerOffsetRecord) -> Self {
+        Self {
+            position: types::ConsumerPosition::Activity(record.cursor()),
+            committed_at: record.commit_timestamp(),
+        }
+    }
+}
+
+/// Ingestion watermark of a topic committed by a named consumer.
+#[derive(Debug, Clone)]
+pub struct TopicConsumerOffsetRecord {
+    pub consumer_name: String,
+    pub topic_id: i32,
+
+    pub(crate) watermark_unix_tstamp: i64,
+
+    /// UNIX timestamp in nanoseconds
+    pub(crate) commit_unix_tstamp: i64,
+}
+
+impl TopicConsumerOffsetRecord {
+    pub fn new(
+        consumer_name: String,
+        topic_id: i32,
+        watermark: types::Timestamp,
+        committed_at: types::Timestamp,
+    ) -> Self {
+        Self {
+            consumer_name,
+            topic_id,
+            watermark_unix_tstamp: watermark.as_i64(),
+            commit_unix_tstamp: committed_at.as_i64(),
+        }
+    }
+
+    pub fn watermark(&self) -> types::Timestamp {
+        self.watermark_unix_tstamp.into()
+    }
+
+    pub fn commit_timestamp(&self) -> types::Timestamp {
+        self.commit_unix_tstamp.into()
+    }
+}
+
+impl From<TopicConsumerOffsetRecord> for types::ConsumerOffset {
+    fn from(record: TopicConsumerOffsetRecord) -> Self {
+        Self {
+            position: types::ConsumerPosition::Watermark(record.watermark()),
+            committed_at: record.commit_timestamp(),
+        }
+    }
+}
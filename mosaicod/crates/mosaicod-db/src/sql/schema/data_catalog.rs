@@ -1,4 +1,5 @@
 use crate as db;
+use mosaicod_core::types;
 
 #[derive(Debug)]
 pub struct ColumnRecord {
@@ -29,6 +30,11 @@ pub struct ChunkRecord {
     pub(crate) data_file: String,
     pub size_bytes: i64,
     pub row_count: i64,
+    /// Version of the registered schema of the topic the chunk was written under
+    pub(crate) schema_version: Option<i32>,
+    /// Timestamp range of the rows in the chunk, both inclusive
+    pub(crate) start_index_timestamp: Option<i64>,
+    pub(crate) end_index_timestamp: Option<i64>,
 }
 
 impl ChunkRecord {
@@ -45,9 +51,38 @@ impl ChunkRecord {
             data_file: data_file.as_ref().to_string_lossy().to_string(),
             size_bytes,
             row_count,
+            schema_version: None,
+            start_index_timestamp: None,
+            end_index_timestamp: None,
         }
     }
 
+    pub fn with_schema_version(mut self, schema_version: Option<u32>) -> Self {
+        self.schema_version = schema_version.map(|version| version as i32);
+        self
+    }
+
+    /// Returns the version of the registered schema the chunk was written under, `None` if
+    /// the topic had no registered schema or the version is unknown.
+    pub fn schema_version(&self) -> Option<u32> {
+        self.schema_version.map(|version| version as u32)
+    }
+
+    pub fn with_timestamp_range(mut self, range: Option<types::TimestampRange>) -> Self {
+        self.start_index_timestamp = range.as_ref().map(|r| r.start.as_i64());
+        self.end_index_timestamp = range.as_ref().map(|r| r.end.as_i64());
+        self
+    }
+
+    /// Returns the timestamp range of the rows in the chunk, `None` if the chunk was written
+    /// before the range was tracked.
+    pub fn timestamp_range(&self) -> Option<types::TimestampRange> {
+        Some(types::TimestampRange::between(
+            self.start_index_timestamp?.into(),
+            self.end_index_timestamp?.into(),
+        ))
+    }
+
     pub fn data_file(&self) -> &std::path::Path {
         std::path::Path::new(&self.data_file)
     }
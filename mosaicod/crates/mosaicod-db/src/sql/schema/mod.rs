@@ -12,8 +12,56 @@ pub use sequence_record::*;
 mod topic_record;
 pub use topic_record::*;
 
+mod topic_upsert_record;
+pub use topic_upsert_record::*;
+
+mod topic_column_record;
+pub use topic_column_record::*;
+
+mod scheduled_query_record;
+pub use scheduled_query_record::*;
+
+mod query_view_record;
+pub use query_view_record::*;
+
+mod query_result_record;
+pub use query_result_record::*;
+
 mod session_record;
 pub use session_record::*;
 
 mod api_key_record;
 pub use api_key_record::*;
+
+mod store_relocation_record;
+pub use store_relocation_record::*;
+
+mod redaction_record;
+pub use redaction_record::*;
+
+mod relay_session_record;
+pub use relay_session_record::*;
+
+mod relay_sequence_record;
+pub use relay_sequence_record::*;
+
+mod topic_schema_record;
+pub use topic_schema_record::*;
+
+mod topic_watermark_record;
+pub use topic_watermark_record::*;
+
+mod topic_lineage_record;
+pub use topic_lineage_record::*;
+
+mod consumer_offset_record;
+pub use consumer_offset_record::*;
+
+mod shared_object_record;
+pub use shared_object_record::*;
+
+mod object_access_record;
+pub use object_access_record::*;
+
+mod compaction_policy_record;
+pub use compaction_policy_record::*;
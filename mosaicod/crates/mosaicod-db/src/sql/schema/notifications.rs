@@ -12,9 +12,15 @@ pub struct SequenceNotificationRecord {
     /// the underlying [`NotificationType`], this filed is stored as a raw String
     /// since the sqlx driver cannot interact directly with enums.
     pub(crate) notification_type: String,
+    /// String representation of the [`types::NotificationSeverity`]
+    pub(crate) severity: String,
     pub msg: Option<String>,
     /// UNIX timestamp in milliseconds from the creation
     pub(crate) creation_unix_tstamp: i64,
+    /// Code of the [`types::NotificationPayload`]
+    pub(crate) code: String,
+    /// Parameters of the [`types::NotificationPayload`], stored as a JSON object of strings
+    pub(crate) params: serde_json::Value,
 }
 
 impl SequenceNotificationRecord {
@@ -25,6 +31,8 @@ impl SequenceNotificationRecord {
     pub fn new(
         sequence_id: i32,
         notification_type: types::NotificationType,
+        severity: types::NotificationSeverity,
+        payload: types::NotificationPayload,
         msg: Option<String>,
     ) -> Self {
         Self {
@@ -32,8 +40,17 @@ impl SequenceNotificationRecord {
             sequence_notification_uuid: types::Uuid::new().into(),
             sequence_id,
             notification_type: notification_type.to_string(),
+            severity: severity.to_string(),
             msg,
             creation_unix_tstamp: types::Timestamp::now().into(),
+            code: payload.code,
+            params: serde_json::Value::Object(
+                payload
+                    .params
+                    .into_iter()
+                    .map(|(k, v)| (k, v.into()))
+                    .collect(),
+            ),
         }
     }
 
@@ -45,6 +62,8 @@ impl SequenceNotificationRecord {
             uuid: self.sequence_notification_uuid.into(),
             target: loc,
             notification_type: self.notification_type(),
+            severity: self.severity(),
+            payload: self.payload(),
             msg: self.msg,
             created_at: types::Timestamp::from(self.creation_unix_tstamp).into(),
         }
@@ -67,6 +86,10 @@ impl SequenceNotificationRecord {
         types::NotificationType::from_str(&self.notification_type).unwrap()
     }
 
+    pub fn severity(&self) -> types::NotificationSeverity {
+        types::NotificationSeverity::from_str(&self.severity).unwrap()
+    }
+
     pub fn creation_timestamp(&self) -> types::Timestamp {
         types::Timestamp::from(self.creation_unix_tstamp)
     }
@@ -74,6 +97,10 @@ impl SequenceNotificationRecord {
     pub fn uuid(&self) -> types::Uuid {
         self.sequence_notification_uuid.into()
     }
+
+    pub fn payload(&self) -> types::NotificationPayload {
+        payload(&self.code, &self.params)
+    }
 }
 
 #[derive(Debug)]
@@ -85,9 +112,15 @@ pub struct TopicNotificationRecord {
     /// the underlying [`NotificationType`], this filed is stored as a raw String
     /// since the sqlx driver cannot interact directly with enums.
     pub(crate) notification_type: String,
+    /// String representation of the [`types::NotificationSeverity`]
+    pub(crate) severity: String,
     pub msg: Option<String>,
     /// UNIX timestamp in milliseconds from the creation
     pub(crate) creation_unix_tstamp: i64,
+    /// Code of the [`types::NotificationPayload`]
+    pub(crate) code: String,
+    /// Parameters of the [`types::NotificationPayload`], stored as a JSON object of strings
+    pub(crate) params: serde_json::Value,
 }
 
 impl TopicNotificationRecord {
@@ -98,6 +131,8 @@ impl TopicNotificationRecord {
     pub fn new(
         topic_id: i32,
         notification_type: types::NotificationType,
+        severity: types::NotificationSeverity,
+        payload: types::NotificationPayload,
         msg: Option<String>,
     ) -> Self {
         Self {
@@ -105,8 +140,17 @@ impl TopicNotificationRecord {
             topic_notification_uuid: types::Uuid::new().into(),
             topic_id,
             notification_type: notification_type.to_string(),
+            severity: severity.to_string(),
             msg,
             creation_unix_tstamp: types::Timestamp::now().into(),
+            code: payload.code,
+            params: serde_json::Value::Object(
+                payload
+                    .params
+                    .into_iter()
+                    .map(|(k, v)| (k, v.into()))
+                    .collect(),
+            ),
         }
     }
 
@@ -118,6 +162,8 @@ impl TopicNotificationRecord {
             uuid: self.topic_notification_uuid.into(),
             target: loc,
             notification_type: self.notification_type(),
+            severity: self.severity(),
+            payload: self.payload(),
             msg: self.msg,
             created_at: types::Timestamp::from(self.creation_unix_tstamp).into(),
         }
@@ -138,6 +184,10 @@ impl TopicNotificationRecord {
         types::NotificationType::from_str(&self.notification_type).unwrap()
     }
 
+    pub fn severity(&self) -> types::NotificationSeverity {
+        types::NotificationSeverity::from_str(&self.severity).unwrap()
+    }
+
     pub fn creation_timestamp(&self) -> types::Timestamp {
         types::Timestamp::from(self.creation_unix_tstamp)
     }
@@ -145,4 +195,32 @@ impl TopicNotificationRecord {
     pub fn uuid(&self) -> types::Uuid {
         self.topic_notification_uuid.into()
     }
+
+    pub fn payload(&self) -> types::NotificationPayload {
+        payload(&self.code, &self.params)
+    }
+}
+
+/// Builds the payload stored in a notification record.
+///
+/// Parameters that are not strings are not expected, since the records are only written from
+/// a [`types::NotificationPayload`], and are stored as JSON text.
+fn payload(code: &str, params: &serde_json::Value) -> types::NotificationPayload {
+    let params = params
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(key, value)| {
+            let value = match value {
+                serde_json::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            (key.clone(), value)
+        })
+        .collect();
+
+    types::NotificationPayload {
+        code: code.to_owned(),
+        params,
+    }
 }
@@ -0,0 +1,74 @@
+use mosaicod_core::types;
+
+/// Reads of an object of the store.
+#[derive(Debug, Clone)]
+pub struct ObjectAccessRecord {
+    pub(crate) object_path: String,
+    /// Estimated number of reads
+    pub(crate) read_count: i64,
+    /// UNIX timestamp in nanoseconds
+    pub(crate) last_read_unix_tstamp: i64,
+}
+
+impl ObjectAccessRecord {
+    pub fn new(object_path: String, read_count: u64, last_read: types::Timestamp) -> Self {
+        Self {
+            object_path,
+            read_count: read_count as i64,
+            last_read_unix_tstamp: last_read.as_i64(),
+        }
+    }
+}
+
+/// Data file of a chunk not read recently, which can be demoted to the archive storage.
+#[derive(Debug, Clone)]
+pub struct ColdDataFileRecord {
+    pub chunk_id: i32,
+    pub topic_id: i32,
+    pub(crate) data_file: String,
+    pub size_bytes: i64,
+
+    /// Estimated number of reads of the data file
+    pub(crate) read_count: i64,
+    /// UNIX timestamp in nanoseconds, `None` if never read
+    pub(crate) last_read_unix_tstamp: Option<i64>,
+}
+
+impl ColdDataFileRecord {
+    pub fn data_file(&self) -> &std::path::Path {
+        std::path::Path::new(&self.data_file)
+    }
+
+    pub fn read_count(&self) -> u64 {
+        self.read_count as u64
+    }
+
+    pub fn last_read_timestamp(&self) -> Option<types::Timestamp> {
+        self.last_read_unix_tstamp.map(Into::into)
+    }
+}
+
+/// Store usage of the data files of a sequence.
+#[derive(Debug, Clone)]
+pub struct SequenceUsageRecord {
+    pub(crate) locator_name: String,
+    /// UNIX timestamp in nanoseconds
+    pub(crate) creation_unix_tstamp: i64,
+
+    /// Size of the data files in the standard storage
+    pub standard_bytes: i64,
+    /// Size of the data files moved to the archive storage
+    pub archived_bytes: i64,
+    /// Estimated bytes read from the data files since their reads are sampled
+    pub read_bytes: f64,
+}
+
+impl SequenceUsageRecord {
+    pub fn locator(&self) -> &str {
+        &self.locator_name
+    }
+
+    pub fn creation_timestamp(&self) -> types::Timestamp {
+        self.creation_unix_tstamp.into()
+    }
+}
@@ -0,0 +1,74 @@
+//! A query result record holds the rows of a query not yet fetched by the client, which
+//! retrieves them in pages.
+
+use crate as db;
+use mosaicod_core::types;
+
+#[derive(Debug, Clone)]
+pub struct QueryResultRecord {
+    pub query_result_id: i32,
+    pub(crate) query_result_uuid: uuid::Uuid,
+
+    /// Number of rows already fetched
+    pub(crate) fetched_count: i32,
+
+    /// UNIX timestamp in nanoseconds after which the result is discarded
+    pub(crate) expiration_unix_tstamp: i64,
+}
+
+impl QueryResultRecord {
+    pub fn new(expiration: types::Timestamp) -> Self {
+        Self {
+            query_result_id: db::UNREGISTERED,
+            query_result_uuid: types::Uuid::new().into(),
+            fetched_count: 0,
+            expiration_unix_tstamp: expiration.into(),
+        }
+    }
+
+    pub fn uuid(&self) -> types::Uuid {
+        self.query_result_uuid.into()
+    }
+
+    pub fn fetched_count(&self) -> usize {
+        self.fetched_count as usize
+    }
+
+    pub fn expiration_timestamp(&self) -> types::Timestamp {
+        types::Timestamp::from(self.expiration_unix_tstamp)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryResultRowRecord {
+    pub query_result_id: i32,
+    pub(crate) row_index: i32,
+    pub(crate) locator_name: String,
+    pub(crate) start_index_timestamp: Option<i64>,
+    pub(crate) end_index_timestamp: Option<i64>,
+}
+
+impl QueryResultRowRecord {
+    pub fn new(query_result_id: i32, row_index: usize, locator: &types::TopicLocator) -> Self {
+        Self {
+            query_result_id,
+            row_index: row_index as i32,
+            locator_name: locator.to_string(),
+            start_index_timestamp: locator.timestamp_range.as_ref().map(|r| r.start.into()),
+            end_index_timestamp: locator.timestamp_range.as_ref().map(|r| r.end.into()),
+        }
+    }
+
+    pub fn locator(&self) -> types::TopicLocator {
+        let locator: types::TopicLocator = self
+            .locator_name
+            .parse()
+            .unwrap_or_else(|_| panic!("Invalid topic locator in DB {}", self.locator_name));
+
+        match (self.start_index_timestamp, self.end_index_timestamp) {
+            (Some(start), Some(end)) => locator
+                .with_timestamp_range(types::TimestampRange::between(start.into(), end.into())),
+            _ => locator,
+        }
+    }
+}
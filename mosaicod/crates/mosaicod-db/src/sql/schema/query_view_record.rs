@@ -0,0 +1,27 @@
+use mosaicod_core::types;
+
+/// To inspect inner fields this type needs to be converted in a [`types::QueryView`], along
+/// with the locator of its sequence.
+pub struct QueryViewRecord {
+    pub(crate) query_view_id: i32,
+    pub(crate) sequence_id: i32,
+    pub(crate) name: String,
+    pub(crate) query: serde_json::Value,
+
+    /// UNIX timestamp in nanoseconds
+    pub(crate) creation_unix_tstamp: i64,
+}
+
+impl QueryViewRecord {
+    pub fn into_query_view(
+        self,
+        sequence: types::SequenceLocator,
+    ) -> types::QueryView<serde_json::Value> {
+        types::QueryView {
+            sequence,
+            name: self.name,
+            query: self.query,
+            created_at: self.creation_unix_tstamp.into(),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! A redaction record is an entry of the immutable log of the redactions applied to the
+//! data of the topics.
+
+use crate as db;
+use mosaicod_core::types;
+
+#[derive(Debug, Clone)]
+pub struct RedactionRecord {
+    pub redaction_id: i32,
+    pub(crate) redaction_uuid: uuid::Uuid,
+    pub(crate) topic_locator: String,
+    pub(crate) start_index_timestamp: i64,
+    pub(crate) end_index_timestamp: i64,
+    pub(crate) columns: Vec<String>,
+    pub(crate) mode: String,
+    pub(crate) principal: Option<String>,
+    pub(crate) reason: Option<String>,
+    pub(crate) row_count: i64,
+    pub(crate) object_count: i64,
+
+    /// UNIX timestamp in nanoseconds
+    pub(crate) creation_unix_tstamp: i64,
+}
+
+impl From<&types::Redaction> for RedactionRecord {
+    fn from(value: &types::Redaction) -> Self {
+        Self {
+            redaction_id: db::UNREGISTERED,
+            redaction_uuid: value.uuid.clone().into(),
+            topic_locator: value.topic.to_string(),
+            start_index_timestamp: value.range.start.as_i64(),
+            end_index_timestamp: value.range.end.as_i64(),
+            columns: value.columns.clone(),
+            mode: value.mode.to_string(),
+            principal: value.principal.clone(),
+            reason: value.reason.clone(),
+            row_count: value.row_count as i64,
+            object_count: value.object_count as i64,
+            creation_unix_tstamp: value.created_at.as_i64(),
+        }
+    }
+}
+
+impl TryFrom<RedactionRecord> for types::Redaction {
+    type Error = db::Error;
+
+    fn try_from(value: RedactionRecord) -> Result<Self, Self::Error> {
+        let bad_data = |e: &dyn std::fmt::Display| {
+            db::Error::BadData(format!("redaction `{}`: {}", value.redaction_uuid, e))
+        };
+
+        Ok(Self {
+            uuid: value.redaction_uuid.into(),
+            topic: value.topic_locator.parse().map_err(|e| bad_data(&e))?,
+            range: types::TimestampRange::between(
+                value.start_index_timestamp.into(),
+                value.end_index_timestamp.into(),
+            ),
+            mode: value.mode.parse().map_err(|e| bad_data(&e))?,
+            columns: value.columns,
+            principal: value.principal,
+            reason: value.reason,
+            row_count: value.row_count as u64,
+            object_count: value.object_count as u64,
+            created_at: value.creation_unix_tstamp.into(),
+        })
+    }
+}
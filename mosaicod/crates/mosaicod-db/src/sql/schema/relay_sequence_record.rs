@@ -0,0 +1,15 @@
+/// Central sequence receiving the sessions of a sequence relayed by this instance.
+#[derive(Debug, Clone)]
+pub struct RelaySequenceRecord {
+    pub sequence_id: i32,
+
+    pub(crate) remote_locator: Option<String>,
+}
+
+impl RelaySequenceRecord {
+    /// Returns the locator of the central sequence, `None` if the sequence is relayed to its
+    /// namespaced locator.
+    pub fn remote_locator(&self) -> Option<&str> {
+        self.remote_locator.as_deref()
+    }
+}
@@ -0,0 +1,43 @@
+use mosaicod_core::types;
+
+/// State of a finalized session forwarded to the central instance by the relay.
+#[derive(Debug, Clone)]
+pub struct RelaySessionRecord {
+    pub session_id: i32,
+
+    pub(crate) remote_locator: Option<String>,
+    pub(crate) attempts: i32,
+    pub(crate) last_error: Option<String>,
+    pub(crate) rejected: bool,
+
+    /// UNIX timestamp in nanoseconds
+    pub(crate) relay_unix_tstamp: Option<i64>,
+}
+
+impl RelaySessionRecord {
+    /// Returns the locator of the session created on the central instance by the last
+    /// attempt, if any.
+    pub fn remote_locator(&self) -> Option<&str> {
+        self.remote_locator.as_deref()
+    }
+
+    /// Returns the number of failed attempts.
+    pub fn attempts(&self) -> u32 {
+        self.attempts as u32
+    }
+
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Returns `true` if the session can not be relayed without conflicting with the data
+    /// of the central instance.
+    pub fn rejected(&self) -> bool {
+        self.rejected
+    }
+
+    /// Returns the timestamp of the completed relay, `None` if still pending.
+    pub fn relay_timestamp(&self) -> Option<types::Timestamp> {
+        self.relay_unix_tstamp.map(types::Timestamp::from)
+    }
+}
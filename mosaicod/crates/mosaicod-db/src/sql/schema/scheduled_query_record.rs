@@ -0,0 +1,41 @@
+use mosaicod_core::types;
+
+/// To inspect inner fields this type needs to be converted in a [`types::ScheduledQuery`].
+pub struct ScheduledQueryRecord {
+    pub(crate) scheduled_query_id: i32,
+    pub(crate) name: String,
+
+    /// Cron expression
+    pub(crate) schedule: String,
+    pub(crate) query: serde_json::Value,
+
+    pub(crate) condition_op: String,
+    pub(crate) condition_value: i64,
+
+    /// UNIX timestamps in nanoseconds
+    pub(crate) creation_unix_tstamp: i64,
+    pub(crate) next_run_unix_tstamp: i64,
+    pub(crate) last_run_unix_tstamp: Option<i64>,
+
+    pub(crate) last_match_count: Option<i64>,
+}
+
+impl TryFrom<ScheduledQueryRecord> for types::ScheduledQuery<serde_json::Value> {
+    type Error = types::ScheduledQueryError;
+
+    fn try_from(value: ScheduledQueryRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: value.name,
+            schedule: types::CronSchedule::try_new(&value.schedule)?,
+            query: value.query,
+            condition: types::MatchCondition::try_new(
+                &value.condition_op,
+                value.condition_value as u64,
+            )?,
+            created_at: value.creation_unix_tstamp.into(),
+            next_run_at: value.next_run_unix_tstamp.into(),
+            last_run_at: value.last_run_unix_tstamp.map(Into::into),
+            last_match_count: value.last_match_count.map(|count| count as u64),
+        })
+    }
+}
@@ -27,6 +27,12 @@ pub struct SequenceRecord {
 
     /// Path inside Object store where to find backup files and other sequence info.
     pub(crate) path_in_store: String,
+
+    /// UNIX timestamp of the move to the trash, `None` if the sequence is not trashed
+    pub(crate) trashed_unix_tstamp: Option<i64>,
+
+    /// If `true` the sequence cannot be deleted
+    pub(crate) legal_hold: bool,
 }
 
 impl SequenceRecord {
@@ -48,9 +54,23 @@ impl SequenceRecord {
             creation_unix_tstamp: types::Timestamp::now().into(),
             user_metadata: None,
             path_in_store: path_in_store.into(),
+            trashed_unix_tstamp: None,
+            legal_hold: false,
         }
     }
 
+    /// Sets the UUID of the sequence, e.g. to recreate a sequence imported from another
+    /// instance.
+    pub fn with_uuid(mut self, uuid: types::Uuid) -> Self {
+        self.sequence_uuid = uuid.into();
+        self
+    }
+
+    pub fn with_creation_timestamp(mut self, created_at: types::Timestamp) -> Self {
+        self.creation_unix_tstamp = created_at.into();
+        self
+    }
+
     pub fn with_user_metadata(mut self, user_metadata: marshal::JsonMetadataBlob) -> Self {
         self.user_metadata = Some(user_metadata.into());
         self
@@ -60,6 +80,16 @@ impl SequenceRecord {
         types::Timestamp::from(self.creation_unix_tstamp)
     }
 
+    /// Returns the time the sequence was moved to the trash, `None` if not trashed.
+    pub fn trashed_at(&self) -> Option<types::Timestamp> {
+        self.trashed_unix_tstamp.map(types::Timestamp::from)
+    }
+
+    /// Returns `true` if the sequence is under legal hold.
+    pub fn legal_hold(&self) -> bool {
+        self.legal_hold
+    }
+
     pub fn user_metadata(&self) -> Option<marshal::JsonMetadataBlob> {
         self.user_metadata.clone().map(Into::into)
     }
@@ -22,6 +22,15 @@ pub struct SessionRecord {
 
     /// UNIX timestamp in milliseconds since the completion
     pub(crate) completion_unix_tstamp: Option<i64>,
+
+    /// UNIX timestamp of the last upload into the session
+    pub(crate) last_activity_unix_tstamp: i64,
+
+    /// Principal that created the session, `None` if API keys were disabled
+    pub(crate) owner: Option<String>,
+
+    /// Principals allowed to write into the session besides its owner
+    pub(crate) shared_with: Vec<String>,
 }
 
 impl SessionRecord {
@@ -29,16 +38,37 @@ impl SessionRecord {
     ///
     /// The record is not persisted until an explicit database operation is called.
     pub fn new(locator: types::SessionLocator, sequence_id: i32) -> Self {
+        let now: i64 = types::Timestamp::now().into();
         Self {
             session_id: db::UNREGISTERED,
             session_uuid: types::Uuid::new().into(),
             sequence_id,
             locator_name: locator.to_string(),
-            creation_unix_tstamp: types::Timestamp::now().into(),
+            creation_unix_tstamp: now,
             completion_unix_tstamp: None,
+            last_activity_unix_tstamp: now,
+            owner: None,
+            shared_with: Vec::new(),
         }
     }
 
+    /// Sets the UUID of the session, e.g. to register again a session known by its UUID.
+    pub fn with_uuid(mut self, uuid: types::Uuid) -> Self {
+        self.session_uuid = uuid.into();
+        self
+    }
+
+    pub fn with_creation_timestamp(mut self, created_at: types::Timestamp) -> Self {
+        self.creation_unix_tstamp = created_at.into();
+        self
+    }
+
+    /// Sets the principal owning the session.
+    pub fn with_owner(mut self, owner: Option<String>) -> Self {
+        self.owner = owner;
+        self
+    }
+
     /// Returns the resource locator for this session.
     ///
     /// Because a [`SessionRecord`] should only be created using [`SessionRecord::new`], that requires a [`types::SessionLocator`],
@@ -60,7 +90,50 @@ impl SessionRecord {
         self.completion_unix_tstamp.map(types::Timestamp::from)
     }
 
+    /// Returns the time of the last upload into the session, or of its creation if no data
+    /// was uploaded yet.
+    pub fn last_activity_timestamp(&self) -> types::Timestamp {
+        types::Timestamp::from(self.last_activity_unix_tstamp)
+    }
+
     pub fn uuid(&self) -> types::Uuid {
         self.session_uuid.into()
     }
+
+    /// Returns the principal that created the session, `None` if API keys were disabled.
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    /// Returns the principals the session is shared with.
+    pub fn shared_with(&self) -> &[String] {
+        &self.shared_with
+    }
+}
+
+/// A session along with the number of topics it created.
+#[derive(Debug)]
+pub struct SessionSummaryRecord {
+    pub session_id: i32,
+    pub(crate) locator_name: String,
+    pub(crate) session_uuid: uuid::Uuid,
+    pub(crate) creation_unix_tstamp: i64,
+    pub(crate) completion_unix_tstamp: Option<i64>,
+    pub(crate) topic_count: i64,
+}
+
+impl TryFrom<SessionSummaryRecord> for types::SessionSummary {
+    type Error = db::Error;
+
+    fn try_from(value: SessionSummaryRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            locator: value.locator_name.parse().map_err(|e| {
+                db::Error::BadData(format!("session `{}`: {}", value.session_uuid, e))
+            })?,
+            uuid: value.session_uuid.into(),
+            created_at: value.creation_unix_tstamp.into(),
+            completed_at: value.completion_unix_tstamp.map(Into::into),
+            topic_count: value.topic_count as usize,
+        })
+    }
 }
@@ -0,0 +1,25 @@
+use mosaicod_core::types;
+
+/// Object of the store shared by the data files of several chunks.
+#[derive(Debug, Clone)]
+pub struct SharedObjectRecord {
+    pub(crate) shared_object_id: i32,
+    pub(crate) object_path: String,
+
+    /// UNIX timestamp in nanoseconds
+    pub(crate) creation_unix_tstamp: i64,
+}
+
+impl SharedObjectRecord {
+    pub fn id(&self) -> i32 {
+        self.shared_object_id
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        std::path::Path::new(&self.object_path)
+    }
+
+    pub fn creation_timestamp(&self) -> types::Timestamp {
+        self.creation_unix_tstamp.into()
+    }
+}
@@ -0,0 +1,55 @@
+use mosaicod_core::types;
+
+/// Relocation of the root folder of a sequence or a topic in the store.
+#[derive(Debug, Clone)]
+pub struct StoreRelocationRecord {
+    pub(crate) relocation_id: i32,
+    pub(crate) resource_kind: String,
+    pub(crate) resource_id: i32,
+    pub(crate) source_path: String,
+    pub(crate) target_path: String,
+
+    /// UNIX timestamp in nanoseconds
+    pub(crate) creation_unix_tstamp: i64,
+}
+
+impl StoreRelocationRecord {
+    pub(crate) fn kind_name(kind: types::ResourceKind) -> &'static str {
+        match kind {
+            types::ResourceKind::Sequence => "sequence",
+            types::ResourceKind::Session => "session",
+            types::ResourceKind::Topic => "topic",
+        }
+    }
+
+    pub fn id(&self) -> i32 {
+        self.relocation_id
+    }
+
+    /// Returns `None` if the kind stored in the database is unknown.
+    pub fn resource_kind(&self) -> Option<types::ResourceKind> {
+        [
+            types::ResourceKind::Sequence,
+            types::ResourceKind::Session,
+            types::ResourceKind::Topic,
+        ]
+        .into_iter()
+        .find(|kind| Self::kind_name(*kind) == self.resource_kind)
+    }
+
+    pub fn resource_id(&self) -> i32 {
+        self.resource_id
+    }
+
+    pub fn source_path(&self) -> &str {
+        &self.source_path
+    }
+
+    pub fn target_path(&self) -> &str {
+        &self.target_path
+    }
+
+    pub fn creation_timestamp(&self) -> types::Timestamp {
+        self.creation_unix_tstamp.into()
+    }
+}
@@ -0,0 +1,92 @@
+//! A column record documents a column of the topic data, e.g. its meaning and unit.
+
+use mosaicod_core::types;
+
+#[derive(Debug, Clone)]
+pub struct TopicColumnRecord {
+    pub topic_id: i32,
+    pub column_name: String,
+    pub description: Option<String>,
+    pub unit: Option<String>,
+}
+
+impl TopicColumnRecord {
+    pub fn new(topic_id: i32, column: types::ColumnDescription) -> Self {
+        Self {
+            topic_id,
+            column_name: column.name,
+            description: column.description,
+            unit: column.unit,
+        }
+    }
+}
+
+impl From<TopicColumnRecord> for types::ColumnDescription {
+    fn from(value: TopicColumnRecord) -> Self {
+        Self {
+            name: value.column_name,
+            description: value.description,
+            unit: value.unit,
+        }
+    }
+}
+
+/// Cached statistics of a column of the topic data.
+#[derive(Debug, Clone)]
+pub struct TopicColumnStatsRecord {
+    pub topic_id: i32,
+    pub column_name: String,
+    pub row_count: i64,
+    pub null_count: i64,
+    pub min_numeric: Option<f64>,
+    pub max_numeric: Option<f64>,
+    pub min_textual: Option<String>,
+    pub max_textual: Option<String>,
+    pub mean: Option<f64>,
+    pub cardinality: Option<i64>,
+}
+
+impl TopicColumnStatsRecord {
+    pub fn new(topic_id: i32, stats: types::ColumnStats) -> Self {
+        let split = |value: Option<types::ColumnValue>| match value {
+            Some(types::ColumnValue::Numeric(v)) => (Some(v), None),
+            Some(types::ColumnValue::Textual(v)) => (None, Some(v)),
+            None => (None, None),
+        };
+        let (min_numeric, min_textual) = split(stats.min);
+        let (max_numeric, max_textual) = split(stats.max);
+
+        Self {
+            topic_id,
+            column_name: stats.name,
+            row_count: stats.row_count as i64,
+            null_count: stats.null_count as i64,
+            min_numeric,
+            max_numeric,
+            min_textual,
+            max_textual,
+            mean: stats.mean,
+            cardinality: stats.cardinality.map(|v| v as i64),
+        }
+    }
+}
+
+impl From<TopicColumnStatsRecord> for types::ColumnStats {
+    fn from(value: TopicColumnStatsRecord) -> Self {
+        let join = |numeric: Option<f64>, textual: Option<String>| {
+            numeric
+                .map(types::ColumnValue::Numeric)
+                .or(textual.map(types::ColumnValue::Textual))
+        };
+
+        Self {
+            name: value.column_name,
+            row_count: value.row_count as u64,
+            null_count: value.null_count as u64,
+            min: join(value.min_numeric, value.min_textual),
+            max: join(value.max_numeric, value.max_textual),
+            mean: value.mean,
+            cardinality: value.cardinality.map(|v| v as u64),
+        }
+    }
+}
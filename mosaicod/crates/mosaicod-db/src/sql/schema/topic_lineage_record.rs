@@ -0,0 +1,46 @@
+use crate as db;
+use mosaicod_core::types;
+
+/// Source of the data of a copied topic, at `depth` in its lineage.
+#[derive(Debug, Clone)]
+pub struct TopicLineageRecord {
+    pub topic_id: i32,
+
+    /// Distance from the topic, 1 for the topic it was copied from
+    pub depth: i32,
+
+    pub(crate) source_topic_uuid: uuid::Uuid,
+    pub(crate) source_locator: String,
+
+    /// UNIX timestamp in nanoseconds
+    pub(crate) creation_unix_tstamp: i64,
+}
+
+impl TopicLineageRecord {
+    pub fn new(topic_id: i32, depth: i32, source: &types::TopicLineage) -> Self {
+        Self {
+            topic_id,
+            depth,
+            source_topic_uuid: source.uuid.clone().into(),
+            source_locator: source.locator.to_string(),
+            creation_unix_tstamp: source.copied_at.as_i64(),
+        }
+    }
+}
+
+impl TryFrom<TopicLineageRecord> for types::TopicLineage {
+    type Error = db::Error;
+
+    fn try_from(value: TopicLineageRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            locator: value.source_locator.parse().map_err(|e| {
+                db::Error::BadData(format!(
+                    "lineage of topic with id `{}`: {}",
+                    value.topic_id, e
+                ))
+            })?,
+            uuid: value.source_topic_uuid.into(),
+            copied_at: value.creation_unix_tstamp.into(),
+        })
+    }
+}
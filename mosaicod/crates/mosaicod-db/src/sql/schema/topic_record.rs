@@ -1,7 +1,7 @@
 use crate as db;
-use log::error;
 use mosaicod_core::types;
 use mosaicod_marshal as marshal;
+use tracing::error;
 
 #[derive(Debug, Clone)]
 pub struct TopicRecord {
@@ -33,6 +33,24 @@ pub struct TopicRecord {
     /// First and last timestamps stored inside topic's data.
     pub(crate) start_index_timestamp: Option<i64>,
     pub(crate) end_index_timestamp: Option<i64>,
+
+    /// Column by which the topic data is required to be sorted.
+    pub(crate) sort_key: Option<String>,
+    /// Whether the topic data is sorted by its sort key (or by timestamp if no sort key
+    /// is declared), set when the topic is finalized.
+    pub(crate) is_sorted: Option<bool>,
+
+    /// Name of the policy used to remove duplicated rows and its key column, if any.
+    pub(crate) dedup_policy: String,
+    pub(crate) dedup_key: Option<String>,
+
+    /// Column identifying the rows of upsert topics.
+    pub(crate) primary_key: Option<String>,
+    /// UNIX timestamp of the last compaction of the data files of upsert topics.
+    pub(crate) compaction_unix_tstamp: Option<i64>,
+
+    /// Arrow schema registered at creation, encoded as an Arrow IPC message.
+    pub(crate) arrow_schema: Option<Vec<u8>>,
 }
 
 impl TopicRecord {
@@ -60,14 +78,53 @@ impl TopicRecord {
             total_bytes: None,
             start_index_timestamp: None,
             end_index_timestamp: None,
+            sort_key: None,
+            is_sorted: None,
+            dedup_policy: types::DedupPolicy::None.name().to_owned(),
+            dedup_key: None,
+            primary_key: None,
+            compaction_unix_tstamp: None,
+            arrow_schema: None,
         }
     }
 
+    /// Sets the UUID of the topic, e.g. to recreate a topic imported from another instance.
+    pub fn with_uuid(mut self, uuid: types::Uuid) -> Self {
+        self.topic_uuid = uuid.into();
+        self
+    }
+
+    pub fn with_sort_key(mut self, sort_key: Option<String>) -> Self {
+        self.sort_key = sort_key;
+        self
+    }
+
+    pub fn with_primary_key(mut self, primary_key: Option<String>) -> Self {
+        self.primary_key = primary_key;
+        self
+    }
+
+    pub fn with_arrow_schema(mut self, arrow_schema: Option<Vec<u8>>) -> Self {
+        self.arrow_schema = arrow_schema;
+        self
+    }
+
     pub fn with_user_metadata(mut self, user_metadata: marshal::JsonMetadataBlob) -> Self {
         self.user_metadata = Some(user_metadata.into());
         self
     }
 
+    pub fn with_creation_timestamp(mut self, created_at: types::Timestamp) -> Self {
+        self.creation_unix_tstamp = created_at.into();
+        self
+    }
+
+    pub fn with_dedup_policy(mut self, policy: &types::DedupPolicy) -> Self {
+        self.dedup_policy = policy.name().to_owned();
+        self.dedup_key = policy.key().map(ToOwned::to_owned);
+        self
+    }
+
     pub fn uuid(&self) -> types::Uuid {
         self.topic_uuid.into()
     }
@@ -94,6 +151,12 @@ impl TopicRecord {
             .ok()
     }
 
+    pub fn dedup_policy(&self) -> Option<types::DedupPolicy> {
+        types::DedupPolicy::try_from_parts(&self.dedup_policy, self.dedup_key.clone())
+            .inspect_err(|e| error!("BUG: invalid dedup policy in database: {}", e))
+            .ok()
+    }
+
     pub fn user_metadata(&self) -> Option<marshal::JsonMetadataBlob> {
         self.user_metadata.clone().map(Into::into)
     }
@@ -106,6 +169,32 @@ impl TopicRecord {
         self.completion_unix_tstamp.map(|ts| ts.into())
     }
 
+    pub fn sort_key(&self) -> Option<&str> {
+        self.sort_key.as_deref()
+    }
+
+    /// Returns the primary key of upsert topics, `None` for append-only topics.
+    pub fn primary_key(&self) -> Option<&str> {
+        self.primary_key.as_deref()
+    }
+
+    /// Returns the Arrow IPC encoding of the schema registered for the topic, if any.
+    pub fn arrow_schema(&self) -> Option<&[u8]> {
+        self.arrow_schema.as_deref()
+    }
+
+    /// Returns the time of the last compaction of the data of upsert topics.
+    pub fn compaction_timestamp(&self) -> Option<types::Timestamp> {
+        self.compaction_unix_tstamp.map(types::Timestamp::from)
+    }
+
+    /// Returns whether the topic data turned out sorted by its sort key (or by timestamp if
+    /// no sort key is declared). Returns `None` if the topic is not finalized yet or if it was
+    /// finalized before the ordering was tracked.
+    pub fn is_sorted(&self) -> Option<bool> {
+        self.is_sorted
+    }
+
     /// Either all the fields are set, or none.
     /// Mixed combinations are a symptom that something went wrong
     /// and most likely these metrics need to be recalculated.
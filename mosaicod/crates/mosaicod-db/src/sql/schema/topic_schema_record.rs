@@ -0,0 +1,38 @@
+use mosaicod_core::types;
+
+/// Version of the schema registered for a topic.
+#[derive(Debug, Clone)]
+pub struct TopicSchemaRecord {
+    pub topic_id: i32,
+    pub(crate) version: i32,
+
+    /// Schema encoded as an Arrow IPC message
+    pub(crate) arrow_schema: Vec<u8>,
+
+    /// UNIX timestamp in nanoseconds
+    pub(crate) creation_unix_tstamp: i64,
+}
+
+impl TopicSchemaRecord {
+    pub fn new(topic_id: i32, version: u32, arrow_schema: Vec<u8>) -> Self {
+        Self {
+            topic_id,
+            version: version as i32,
+            arrow_schema,
+            creation_unix_tstamp: types::Timestamp::now().into(),
+        }
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version as u32
+    }
+
+    /// Returns the Arrow IPC encoding of the schema.
+    pub fn arrow_schema(&self) -> &[u8] {
+        &self.arrow_schema
+    }
+
+    pub fn creation_timestamp(&self) -> types::Timestamp {
+        self.creation_unix_tstamp.into()
+    }
+}
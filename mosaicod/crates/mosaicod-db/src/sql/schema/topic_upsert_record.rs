@@ -0,0 +1,72 @@
+//! An upsert record tracks the upload of rows into an existing upsert topic by a session
+//! other than the one that created the topic.
+
+use crate as db;
+use mosaicod_core::types;
+
+#[derive(Debug, Clone)]
+pub struct TopicUpsertRecord {
+    pub upsert_id: i32,
+    pub(crate) upsert_uuid: uuid::Uuid,
+    pub topic_id: i32,
+    pub session_id: i32,
+
+    /// UNIX timestamp in milliseconds from the creation
+    pub(crate) creation_unix_tstamp: i64,
+    /// UNIX timestamp in milliseconds from the start of the data upload
+    pub(crate) upload_unix_tstamp: Option<i64>,
+    pub(crate) completion_unix_tstamp: Option<i64>,
+
+    /// Number of the first data file written by the upsert
+    pub(crate) first_chunk_number: Option<i64>,
+
+    /// Version of the topic the upserted rows were computed from, if asserted by the client
+    pub(crate) base_version: Option<i64>,
+}
+
+impl TopicUpsertRecord {
+    pub fn new(topic_id: i32, session_id: i32) -> Self {
+        Self {
+            upsert_id: db::UNREGISTERED,
+            upsert_uuid: types::Uuid::new().into(),
+            topic_id,
+            session_id,
+            creation_unix_tstamp: types::Timestamp::now().into(),
+            upload_unix_tstamp: None,
+            completion_unix_tstamp: None,
+            first_chunk_number: None,
+            base_version: None,
+        }
+    }
+
+    pub fn with_base_version(mut self, base_version: Option<u64>) -> Self {
+        self.base_version = base_version.map(|v| v as i64);
+        self
+    }
+
+    pub fn uuid(&self) -> types::Uuid {
+        self.upsert_uuid.into()
+    }
+
+    pub fn creation_timestamp(&self) -> types::Timestamp {
+        types::Timestamp::from(self.creation_unix_tstamp)
+    }
+
+    pub fn upload_timestamp(&self) -> Option<types::Timestamp> {
+        self.upload_unix_tstamp.map(Into::into)
+    }
+
+    pub fn completion_timestamp(&self) -> Option<types::Timestamp> {
+        self.completion_unix_tstamp.map(Into::into)
+    }
+
+    /// Returns the number of the first data file written by the upsert, `None` if the
+    /// upload of data did not start yet.
+    pub fn first_chunk_number(&self) -> Option<usize> {
+        self.first_chunk_number.map(|n| n as usize)
+    }
+
+    pub fn base_version(&self) -> Option<u64> {
+        self.base_version.map(|v| v as u64)
+    }
+}
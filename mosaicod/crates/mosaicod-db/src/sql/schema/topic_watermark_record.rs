@@ -0,0 +1,31 @@
+use mosaicod_core::types;
+
+/// Advance of the ingestion watermark of a topic.
+#[derive(Debug, Clone)]
+pub struct TopicWatermarkRecord {
+    pub topic_id: i32,
+
+    /// Latest timestamp of the published data, in nanoseconds
+    pub(crate) watermark_unix_tstamp: i64,
+
+    /// UNIX timestamp in nanoseconds
+    pub(crate) creation_unix_tstamp: i64,
+}
+
+impl TopicWatermarkRecord {
+    pub fn new(topic_id: i32, watermark: types::Timestamp, created_at: types::Timestamp) -> Self {
+        Self {
+            topic_id,
+            watermark_unix_tstamp: watermark.as_i64(),
+            creation_unix_tstamp: created_at.as_i64(),
+        }
+    }
+
+    pub fn watermark(&self) -> types::Timestamp {
+        self.watermark_unix_tstamp.into()
+    }
+
+    pub fn creation_timestamp(&self) -> types::Timestamp {
+        self.creation_unix_tstamp.into()
+    }
+}
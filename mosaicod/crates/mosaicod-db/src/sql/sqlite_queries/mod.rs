@@ -0,0 +1,14 @@
+//! Queries of the SQLite backend, limited to the sequence records. They mirror the
+//! PostgreSQL queries (`pg_queries`) of the sequence records, the other records are only
+//! available on PostgreSQL.
+//!
+//! SQLite lacks some of the PostgreSQL features the queries rely on, so the queries differ
+//! from their PostgreSQL counterparts where:
+//! * parameters are bound with `?NNN` placeholders;
+//! * rows are locked with the whole database by the first write of a transaction, so
+//!   `SELECT ... FOR UPDATE` only checks that the row exists;
+//! * prefixes are matched with `substr`, since `LIKE` ignores the case of ASCII letters;
+//! * records are decoded at runtime (see [`sqlx::FromRow`]) rather than checked at compile
+//!   time against the offline query metadata, which is generated for PostgreSQL.
+mod sequence_record;
+pub use sequence_record::*;
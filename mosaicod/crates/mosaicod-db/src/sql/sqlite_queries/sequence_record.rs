@@ -0,0 +1,481 @@
+use crate::{Error, core::AsExec, sql::schema};
+use mosaicod_core::types;
+use sqlx::{Row, sqlite::SqliteRow};
+use tracing::{trace, warn};
+
+impl<'r> sqlx::FromRow<'r, SqliteRow> for schema::SequenceRecord {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            sequence_id: row.try_get("sequence_id")?,
+            sequence_uuid: row.try_get("sequence_uuid")?,
+            locator_name: row.try_get("locator_name")?,
+            user_metadata: row.try_get("user_metadata")?,
+            creation_unix_tstamp: row.try_get("creation_unix_tstamp")?,
+            path_in_store: row.try_get("path_in_store")?,
+            trashed_unix_tstamp: row.try_get("trashed_unix_tstamp")?,
+            legal_hold: row.try_get("legal_hold")?,
+        })
+    }
+}
+
+/// Find a sequence given its id.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_find_by_id(
+    exe: &mut impl AsExec,
+    id: i32,
+) -> Result<schema::SequenceRecord, Error> {
+    trace!("searching sequence by id `{}`", id);
+    let res = sqlx::query_as("SELECT * FROM sequence_t WHERE sequence_id = ?1")
+        .bind(id)
+        .fetch_one(exe.as_exec())
+        .await?;
+    Ok(res)
+}
+
+/// Find a sequence not in the trash given its uuid.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_find_by_uuid(
+    exe: &mut impl AsExec,
+    uuid: &types::Uuid,
+) -> Result<schema::SequenceRecord, Error> {
+    trace!("searching sequence by uuid `{}`", uuid);
+    let res = sqlx::query_as(
+        "SELECT * FROM sequence_t WHERE sequence_uuid = ?1 AND trashed_unix_tstamp IS NULL",
+    )
+    .bind(uuid.as_ref())
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(res)
+}
+
+/// Find a sequence not in the trash given its name.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_find_by_locator(
+    exe: &mut impl AsExec,
+    loc: &types::SequenceLocator,
+) -> Result<schema::SequenceRecord, Error> {
+    trace!("searching sequence by locator name `{}`", loc);
+    let res = sqlx::query_as(
+        "SELECT * FROM sequence_t WHERE locator_name = ?1 AND trashed_unix_tstamp IS NULL",
+    )
+    .bind(loc.as_str())
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(res)
+}
+
+/// Find a sequence in the trash given its name.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_find_trashed_by_locator(
+    exe: &mut impl AsExec,
+    loc: &types::SequenceLocator,
+) -> Result<schema::SequenceRecord, Error> {
+    trace!("searching trashed sequence by locator name `{}`", loc);
+    let res = sqlx::query_as(
+        "SELECT * FROM sequence_t WHERE locator_name = ?1 AND trashed_unix_tstamp IS NOT NULL",
+    )
+    .bind(loc.as_str())
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(res)
+}
+
+/// Return the sequences moved to the trash before `before`, sorted by trash time.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_find_all_trashed_before(
+    exe: &mut impl AsExec,
+    before: types::Timestamp,
+) -> Result<Vec<schema::SequenceRecord>, Error> {
+    trace!("retrieving sequences trashed before {}", before);
+    Ok(sqlx::query_as(
+        r#"
+        SELECT * FROM sequence_t
+        WHERE trashed_unix_tstamp < ?1
+        ORDER BY trashed_unix_tstamp
+        "#,
+    )
+    .bind(before.as_i64())
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Return at most `limit` sequences not in the trash whose locator starts with `prefix`
+/// and follows `after`, sorted by locator
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_find_page(
+    exe: &mut impl AsExec,
+    prefix: &str,
+    after: Option<&str>,
+    limit: i64,
+) -> Result<Vec<schema::SequenceRecord>, Error> {
+    trace!(
+        "retrieving {} sequences starting with `{}` after `{:?}`",
+        limit, prefix, after
+    );
+    Ok(sqlx::query_as(
+        r#"
+        SELECT * FROM sequence_t
+        WHERE substr(locator_name, 1, length(?1)) = ?1
+            AND (?2 IS NULL OR locator_name > ?2)
+            AND trashed_unix_tstamp IS NULL
+        ORDER BY locator_name
+        LIMIT ?3
+        "#,
+    )
+    .bind(prefix)
+    .bind(after)
+    .bind(limit)
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Return at most `limit` sequences following the one with id `after`, sorted by id.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_find_page_by_id(
+    exe: &mut impl AsExec,
+    after: Option<i32>,
+    limit: i64,
+) -> Result<Vec<schema::SequenceRecord>, Error> {
+    trace!("retrieving {} sequences after id {:?}", limit, after);
+    Ok(sqlx::query_as(
+        r#"
+        SELECT * FROM sequence_t
+        WHERE ?1 IS NULL OR sequence_id > ?1
+        ORDER BY sequence_id
+        LIMIT ?2
+        "#,
+    )
+    .bind(after)
+    .bind(limit)
+    .fetch_all(exe.as_exec())
+    .await?)
+}
+
+/// Checks that the sequence record exists, returns [`Error::NotFound`] otherwise.
+///
+/// SQLite has no row locks: the first write of the transaction locks the whole database
+/// until the end of the transaction.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_lock(exe: &mut impl AsExec, sequence_id: i32) -> Result<(), Error> {
+    trace!("locking sequence with id `{}`", sequence_id);
+    sqlx::query("SELECT sequence_id FROM sequence_t WHERE sequence_id = ?1")
+        .bind(sequence_id)
+        .fetch_one(exe.as_exec())
+        .await?;
+    Ok(())
+}
+
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_update_path_in_store(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+    path_in_store: types::SequencePathInStore,
+) -> Result<(), Error> {
+    trace!(
+        "updating path_in_store to `{}` for sequence with id {}",
+        path_in_store, sequence_id
+    );
+    sqlx::query("UPDATE sequence_t SET path_in_store = ?1 WHERE sequence_id = ?2")
+        .bind(String::from(path_in_store))
+        .bind(sequence_id)
+        .execute(exe.as_exec())
+        .await?;
+
+    Ok(())
+}
+
+/// Moves a sequence to the trash, returns [`Error::NotFound`] if the sequence does not
+/// exist or is already in the trash.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_trash(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+    trashed_at: types::Timestamp,
+) -> Result<(), Error> {
+    trace!("moving sequence with id `{}` to the trash", sequence_id);
+    let result = sqlx::query(
+        r#"
+        UPDATE sequence_t SET trashed_unix_tstamp = ?1
+        WHERE sequence_id = ?2 AND trashed_unix_tstamp IS NULL
+        "#,
+    )
+    .bind(trashed_at.as_i64())
+    .bind(sequence_id)
+    .execute(exe.as_exec())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound);
+    }
+
+    Ok(())
+}
+
+/// Restores a sequence from the trash, returns [`Error::NotFound`] if the sequence is not
+/// in the trash.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_restore(exe: &mut impl AsExec, sequence_id: i32) -> Result<(), Error> {
+    trace!(
+        "restoring sequence with id `{}` from the trash",
+        sequence_id
+    );
+    let result = sqlx::query(
+        r#"
+        UPDATE sequence_t SET trashed_unix_tstamp = NULL
+        WHERE sequence_id = ?1 AND trashed_unix_tstamp IS NOT NULL
+        "#,
+    )
+    .bind(sequence_id)
+    .execute(exe.as_exec())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound);
+    }
+
+    Ok(())
+}
+
+/// Sets or releases the legal hold of a sequence, returns [`Error::NotFound`] if the
+/// sequence does not exist.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_set_legal_hold(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+    legal_hold: bool,
+) -> Result<(), Error> {
+    trace!(
+        "setting legal hold of sequence with id `{}` to {}",
+        sequence_id, legal_hold
+    );
+    let result = sqlx::query("UPDATE sequence_t SET legal_hold = ?1 WHERE sequence_id = ?2")
+        .bind(legal_hold)
+        .bind(sequence_id)
+        .execute(exe.as_exec())
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound);
+    }
+
+    Ok(())
+}
+
+/// Deletes a sequence record from the database by its name.
+///
+/// This function requires a [`DataLossToken`] because it permanently removes the record
+/// from the database without checking if it's referenced elsewhere.
+/// Improper use can lead to data inconsistency or loss.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_delete_by_locator(
+    exe: &mut impl AsExec,
+    loc: &types::SequenceLocator,
+    _: types::DataLossToken,
+) -> Result<(), Error> {
+    warn!("(data loss) deleting sequence `{}`", loc);
+    sqlx::query("DELETE FROM sequence_t WHERE locator_name = ?1")
+        .bind(loc.as_str())
+        .execute(exe.as_exec())
+        .await?;
+    Ok(())
+}
+
+/// Deletes a sequence record from the database by its id.
+///
+/// This function requires a [`DataLossToken`] because it permanently removes the record
+/// from the database without checking if it's referenced elsewhere.
+/// Improper use can lead to data inconsistency or loss.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_delete_by_id(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+    _: types::DataLossToken,
+) -> Result<(), Error> {
+    warn!("(data loss) deleting sequence with id `{}`", sequence_id);
+    let result = sqlx::query("DELETE FROM sequence_t WHERE sequence_id = ?1")
+        .bind(sequence_id)
+        .execute(exe.as_exec())
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound);
+    }
+
+    Ok(())
+}
+
+/// Deletes a sequence record in the trash from the database by its id, returns
+/// [`Error::NotFound`] if the sequence is not in the trash or is under legal hold.
+///
+/// This function requires a [`DataLossToken`] because it permanently removes the record
+/// and, by cascade, the records of its sessions and topics.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_delete_trashed_by_id(
+    exe: &mut impl AsExec,
+    sequence_id: i32,
+    _: types::DataLossToken,
+) -> Result<(), Error> {
+    warn!(
+        "(data loss) deleting trashed sequence with id `{}`",
+        sequence_id
+    );
+    let result = sqlx::query(
+        r#"
+        DELETE FROM sequence_t
+        WHERE sequence_id = ?1 AND trashed_unix_tstamp IS NOT NULL AND NOT legal_hold
+        "#,
+    )
+    .bind(sequence_id)
+    .execute(exe.as_exec())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound);
+    }
+
+    Ok(())
+}
+
+/// Creates a sequence record, `RETURNING` requires SQLite 3.35 or later.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn sequence_create(
+    exe: &mut impl AsExec,
+    record: &schema::SequenceRecord,
+) -> Result<schema::SequenceRecord, Error> {
+    trace!("creating a new sequence record {:?}", record);
+    let res = sqlx::query_as(
+        r#"
+            INSERT INTO sequence_t
+                (sequence_uuid, locator_name, creation_unix_tstamp, user_metadata, path_in_store)
+            VALUES
+                (?1, ?2, ?3, ?4, ?5)
+            RETURNING
+                *
+    "#,
+    )
+    .bind(record.sequence_uuid)
+    .bind(&record.locator_name)
+    .bind(record.creation_unix_tstamp)
+    .bind(&record.user_metadata)
+    .bind(&record.path_in_store)
+    .fetch_one(exe.as_exec())
+    .await?;
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{DatabaseType, testing};
+    use sqlx::Pool;
+
+    #[sqlx::test(migrations = "./migrations_sqlite")]
+    async fn test_create(pool: Pool<DatabaseType>) -> sqlx::Result<()> {
+        let record = schema::SequenceRecord::new(
+            "my_sequence".parse().unwrap(),
+            "/my/path/in/store".to_owned().into(),
+        )
+        .with_user_metadata(serde_json::json!({ "robot": "r2" }).into());
+        let database = testing::Database::new(pool);
+        let rrecord = sequence_create(&mut database.connection(), &record)
+            .await
+            .unwrap();
+
+        assert_eq!(record.sequence_uuid, rrecord.sequence_uuid);
+        assert_eq!(record.locator_name, rrecord.locator_name);
+        assert_eq!(record.creation_unix_tstamp, rrecord.creation_unix_tstamp);
+        assert_eq!(record.user_metadata, rrecord.user_metadata);
+
+        let found = sequence_find_by_uuid(&mut database.connection(), &record.uuid())
+            .await
+            .unwrap();
+        assert_eq!(found, rrecord);
+
+        assert!(matches!(
+            sequence_create(&mut database.connection(), &record).await,
+            Err(Error::AlreadyExists)
+        ));
+
+        Ok(())
+    }
+
+    #[sqlx::test(migrations = "./migrations_sqlite")]
+    async fn test_find_page(pool: Pool<DatabaseType>) -> sqlx::Result<()> {
+        let database = testing::Database::new(pool);
+        for name in ["run_3", "run_1", "Run_4", "other", "run_2"] {
+            let record = schema::SequenceRecord::new(name.parse().unwrap(), name.to_owned().into());
+            sequence_create(&mut database.connection(), &record)
+                .await
+                .unwrap();
+        }
+
+        let names = |records: Vec<schema::SequenceRecord>| {
+            records
+                .into_iter()
+                .map(|r| r.locator_name)
+                .collect::<Vec<_>>()
+        };
+
+        // Prefixes are case sensitive
+        let records = sequence_find_page(&mut database.connection(), "run", None, 2)
+            .await
+            .unwrap();
+        assert_eq!(names(records), vec!["run_1", "run_2"]);
+
+        let records = sequence_find_page(&mut database.connection(), "run", Some("run_2"), 2)
+            .await
+            .unwrap();
+        assert_eq!(names(records), vec!["run_3"]);
+
+        let records = sequence_find_page(&mut database.connection(), "", None, 10)
+            .await
+            .unwrap();
+        assert_eq!(names(records).len(), 5);
+
+        Ok(())
+    }
+
+    #[sqlx::test(migrations = "./migrations_sqlite")]
+    async fn test_trash_and_restore(pool: Pool<DatabaseType>) -> sqlx::Result<()> {
+        let database = testing::Database::new(pool);
+        let locator: types::SequenceLocator = "my_sequence".parse().unwrap();
+        let record = schema::SequenceRecord::new(locator.clone(), "my_sequence".to_owned().into());
+        let record = sequence_create(&mut database.connection(), &record)
+            .await
+            .unwrap();
+
+        let trashed_at = types::Timestamp::now();
+        sequence_trash(&mut database.connection(), record.sequence_id, trashed_at)
+            .await
+            .unwrap();
+        assert!(matches!(
+            sequence_find_by_locator(&mut database.connection(), &locator).await,
+            Err(Error::NotFound)
+        ));
+
+        // Sequences under legal hold are not deleted
+        sequence_set_legal_hold(&mut database.connection(), record.sequence_id, true)
+            .await
+            .unwrap();
+        assert!(matches!(
+            sequence_delete_trashed_by_id(
+                &mut database.connection(),
+                record.sequence_id,
+                types::allow_data_loss()
+            )
+            .await,
+            Err(Error::NotFound)
+        ));
+
+        sequence_restore(&mut database.connection(), record.sequence_id)
+            .await
+            .unwrap();
+        let restored = sequence_find_by_locator(&mut database.connection(), &locator)
+            .await
+            .unwrap();
+        assert!(restored.legal_hold());
+        assert_eq!(restored.trashed_at(), None);
+
+        Ok(())
+    }
+}
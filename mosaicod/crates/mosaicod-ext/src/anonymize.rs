@@ -0,0 +1,278 @@
+//! Anonymization of the record batches exported from the topics.
+//!
+//! The transforms are described by a [`types::Anonymization`]: columns can be dropped,
+//! hashed, with the digest computed by the caller, or coarsened to a lower precision.
+//! Hashed columns are returned as hex encoded strings, whatever their original type.
+use crate::arrow::Error;
+use arrow::array::{Array, ArrayRef, AsArray, RecordBatch, StringArray};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Field, Float32Type, Float64Type, Schema};
+use arrow::error::ArrowError;
+use mosaicod_core::{params, types};
+use std::sync::Arc;
+
+/// Returns the schema of the data anonymized with `anonymization`, or the reason why it
+/// can't be applied to `schema`.
+pub fn anonymize_schema(
+    schema: &Schema,
+    anonymization: &types::Anonymization,
+) -> Result<Schema, String> {
+    let mut columns: Vec<&str> = anonymization.columns().collect();
+    for column in &columns {
+        if *column == params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP {
+            return Err(format!("column `{}` can not be anonymized", column));
+        }
+        if schema.field_with_name(column).is_err() {
+            return Err(format!("column `{}` not found", column));
+        }
+    }
+
+    columns.sort_unstable();
+    if let Some(column) = columns.windows(2).find(|w| w[0] == w[1]) {
+        return Err(format!(
+            "column `{}` is anonymized more than once",
+            column[0]
+        ));
+    }
+
+    let mut fields = Vec::with_capacity(schema.fields().len());
+    for field in schema.fields() {
+        let name = field.name();
+
+        if anonymization.drop.contains(name) {
+            continue;
+        }
+
+        if anonymization.hash.contains(name) {
+            if !is_hashable(field.data_type()) {
+                return Err(format!(
+                    "column `{}` can not be hashed, only string, binary and integer columns are supported",
+                    name
+                ));
+            }
+            fields.push(Arc::new(
+                Field::new(name, DataType::Utf8, field.is_nullable())
+                    .with_metadata(field.metadata().clone()),
+            ));
+            continue;
+        }
+
+        if anonymization.coarsen.contains_key(name)
+            && !matches!(field.data_type(), DataType::Float32 | DataType::Float64)
+        {
+            return Err(format!(
+                "column `{}` can not be coarsened, only floating point columns are supported",
+                name
+            ));
+        }
+
+        fields.push(field.clone());
+    }
+
+    Ok(Schema::new_with_metadata(fields, schema.metadata().clone()))
+}
+
+fn is_hashable(data_type: &DataType) -> bool {
+    data_type.is_integer()
+        || matches!(
+            data_type,
+            DataType::Utf8
+                | DataType::LargeUtf8
+                | DataType::Utf8View
+                | DataType::Binary
+                | DataType::LargeBinary
+                | DataType::BinaryView
+        )
+}
+
+/// Anonymizes the batch, hashing values with `hash`.
+pub fn anonymize_batch<H>(
+    batch: &RecordBatch,
+    anonymization: &types::Anonymization,
+    mut hash: H,
+) -> Result<RecordBatch, Error>
+where
+    H: FnMut(&[u8]) -> Vec<u8>,
+{
+    let schema = anonymize_schema(&batch.schema(), anonymization)
+        .map_err(ArrowError::InvalidArgumentError)?;
+
+    let mut arrays = Vec::with_capacity(schema.fields().len());
+    for field in schema.fields() {
+        let name = field.name();
+        let array = batch
+            .column_by_name(name)
+            .ok_or_else(|| ArrowError::SchemaError(format!("can't find column `{name}`")))?;
+
+        let array = if anonymization.hash.contains(name) {
+            hash_array(array, &mut hash)?
+        } else if let Some(decimals) = anonymization.coarsen.get(name) {
+            coarsen_array(array, *decimals)?
+        } else {
+            array.clone()
+        };
+        arrays.push(array);
+    }
+
+    Ok(RecordBatch::try_new(Arc::new(schema), arrays)?)
+}
+
+fn hash_array<H>(array: &ArrayRef, hash: &mut H) -> Result<ArrayRef, Error>
+where
+    H: FnMut(&[u8]) -> Vec<u8>,
+{
+    let hashed: StringArray = match array.data_type() {
+        DataType::Binary | DataType::LargeBinary | DataType::BinaryView => {
+            let array = cast(array, &DataType::LargeBinary)?;
+            array
+                .as_binary::<i64>()
+                .iter()
+                .map(|value| value.map(|value| hex(&hash(value))))
+                .collect()
+        }
+        _ => {
+            // Integers are hashed in their decimal representation
+            let array = cast(array, &DataType::LargeUtf8)?;
+            array
+                .as_string::<i64>()
+                .iter()
+                .map(|value| value.map(|value| hex(&hash(value.as_bytes()))))
+                .collect()
+        }
+    };
+
+    Ok(Arc::new(hashed))
+}
+
+fn coarsen_array(array: &ArrayRef, decimals: u32) -> Result<ArrayRef, Error> {
+    let scale = 10f64.powi(decimals as i32);
+
+    let array: ArrayRef = match array.data_type() {
+        DataType::Float32 => Arc::new(
+            array
+                .as_primitive::<Float32Type>()
+                .unary::<_, Float32Type>(|v| ((v as f64 * scale).round() / scale) as f32),
+        ),
+        DataType::Float64 => Arc::new(
+            array
+                .as_primitive::<Float64Type>()
+                .unary::<_, Float64Type>(|v| (v * scale).round() / scale),
+        ),
+        data_type => Err(ArrowError::InvalidArgumentError(format!(
+            "values of type {data_type} can not be coarsened"
+        )))?,
+    };
+
+    Ok(array)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{BinaryArray, Float64Array, Int64Array, UInt32Array};
+
+    fn gnss_batch() -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("latitude", DataType::Float64, false),
+            Field::new("longitude", DataType::Float64, true),
+            Field::new("vehicle_id", DataType::UInt32, false),
+            Field::new("driver", DataType::Utf8, true),
+            Field::new("frame", DataType::Binary, true),
+        ]));
+
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![10, 20])),
+                Arc::new(Float64Array::from(vec![45.123456, 45.987654])),
+                Arc::new(Float64Array::from(vec![Some(9.123456), None])),
+                Arc::new(UInt32Array::from(vec![7, 12])),
+                Arc::new(StringArray::from(vec![Some("ann"), None])),
+                Arc::new(BinaryArray::from(vec![Some(&[1u8, 2][..]), None])),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn anonymize() {
+        let anonymization = types::Anonymization {
+            drop: vec!["frame".to_owned()],
+            hash: vec!["vehicle_id".to_owned(), "driver".to_owned()],
+            coarsen: [("latitude".to_owned(), 2), ("longitude".to_owned(), 0)].into(),
+        };
+
+        let batch = anonymize_batch(&gnss_batch(), &anonymization, |value| value.to_vec()).unwrap();
+
+        let names: Vec<&str> = batch
+            .schema_ref()
+            .fields()
+            .iter()
+            .map(|f| f.name().as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "timestamp_ns",
+                "latitude",
+                "longitude",
+                "vehicle_id",
+                "driver"
+            ]
+        );
+
+        let latitude = batch.column(1).as_primitive::<Float64Type>();
+        assert_eq!(latitude.values(), &[45.12, 45.99]);
+        let longitude = batch.column(2).as_primitive::<Float64Type>();
+        assert_eq!(longitude.value(0), 9.0);
+        assert!(longitude.is_null(1));
+
+        // The digest of the decimal representation, hex encoded
+        let vehicles = batch.column(3).as_string::<i32>();
+        assert_eq!(vehicles.value(0), "37");
+        assert_eq!(vehicles.value(1), "3132");
+        let drivers = batch.column(4).as_string::<i32>();
+        assert_eq!(drivers.value(0), "616e6e");
+        assert!(drivers.is_null(1));
+    }
+
+    #[test]
+    fn anonymize_schema_errors() {
+        let schema = gnss_batch().schema();
+
+        let check =
+            |anonymization: types::Anonymization| anonymize_schema(&schema, &anonymization).is_ok();
+
+        assert!(check(types::Anonymization::default()));
+        assert!(check(types::Anonymization {
+            hash: vec!["frame".to_owned()],
+            ..Default::default()
+        }));
+        assert!(!check(types::Anonymization {
+            drop: vec!["missing".to_owned()],
+            ..Default::default()
+        }));
+        assert!(!check(types::Anonymization {
+            drop: vec!["timestamp_ns".to_owned()],
+            ..Default::default()
+        }));
+        assert!(!check(types::Anonymization {
+            hash: vec!["latitude".to_owned()],
+            ..Default::default()
+        }));
+        assert!(!check(types::Anonymization {
+            coarsen: [("driver".to_owned(), 2)].into(),
+            ..Default::default()
+        }));
+        assert!(!check(types::Anonymization {
+            drop: vec!["driver".to_owned()],
+            hash: vec!["driver".to_owned()],
+            ..Default::default()
+        }));
+    }
+}
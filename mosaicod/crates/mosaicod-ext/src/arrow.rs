@@ -1,5 +1,11 @@
 use arrow::array::{ArrayRef, AsArray, RecordBatch, StructArray};
 use arrow::datatypes::{DataType, Field, FieldRef, Schema, SchemaRef};
+use arrow::ipc::convert::try_schema_from_ipc_buffer;
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::{
+    DictionaryTracker, FileWriter, IpcDataGenerator, IpcWriteOptions, write_message,
+};
+use arrow::row::{OwnedRow, RowConverter, SortField};
 use mosaicod_core::{self as core, params, types};
 use parquet::arrow::async_reader::{AsyncFileReader, ParquetObjectReader};
 use parquet::arrow::parquet_to_arrow_schema;
@@ -13,6 +19,26 @@ pub enum SchemaError {
     MissingTimestampInSchema,
     #[error("wrong timestamp field type, expected int64")]
     WrongTimestampType,
+    #[error("key column `{0}` not found in schema")]
+    MissingKeyColumn(String),
+    #[error("key column `{0}` has a type that can not be ordered")]
+    UnorderableKeyColumn(String),
+    #[error("column `{0}` is missing")]
+    MissingColumn(String),
+    #[error("column `{0}` is not part of the schema")]
+    UnexpectedColumn(String),
+    #[error("column `{column}` has type `{found}`, expected `{expected}`")]
+    WrongColumnType {
+        column: String,
+        expected: DataType,
+        found: DataType,
+    },
+    #[error("invalid schema encoding: {0}")]
+    InvalidEncoding(String),
+    #[error("added column `{0}` must be nullable")]
+    RequiredColumnAdded(String),
+    #[error("nullable column `{0}` can not become required")]
+    ColumnMadeRequired(String),
 }
 
 impl core::error::PublicError for SchemaError {
@@ -74,6 +100,224 @@ pub fn check_schema(schema: &SchemaRef) -> Result<(), SchemaError> {
     Ok(())
 }
 
+/// Validates that `schema` matches the `registered` schema of a topic.
+///
+/// The schemas must have the same top-level columns with the same types, regardless of
+/// their order. Nullable columns of the registered schema can be omitted.
+///
+/// # Errors
+///
+/// Returns [`SchemaError`] describing the first mismatching column.
+pub fn check_registered_schema(registered: &Schema, schema: &Schema) -> Result<(), SchemaError> {
+    for expected in registered.fields() {
+        match schema.field_with_name(expected.name()) {
+            Ok(field) if field.data_type() != expected.data_type() => {
+                return Err(SchemaError::WrongColumnType {
+                    column: expected.name().clone(),
+                    expected: expected.data_type().clone(),
+                    found: field.data_type().clone(),
+                });
+            }
+            Ok(_) => {}
+            Err(_) if expected.is_nullable() => {}
+            Err(_) => return Err(SchemaError::MissingColumn(expected.name().clone())),
+        }
+    }
+
+    if let Some(field) = schema
+        .fields()
+        .iter()
+        .find(|field| registered.field_with_name(field.name()).is_err())
+    {
+        return Err(SchemaError::UnexpectedColumn(field.name().clone()));
+    }
+
+    Ok(())
+}
+
+/// Validates that `evolved` is a backward compatible evolution of the `current` schema of
+/// a topic, so that the data written under `current` can still be read under `evolved`.
+///
+/// Columns can only be added, as nullable columns. Columns can not be removed, change type
+/// or become required.
+///
+/// # Errors
+///
+/// Returns [`SchemaError`] describing the first incompatible column.
+pub fn check_schema_evolution(current: &Schema, evolved: &Schema) -> Result<(), SchemaError> {
+    for field in current.fields() {
+        match evolved.field_with_name(field.name()) {
+            Ok(evolved) if evolved.data_type() != field.data_type() => {
+                return Err(SchemaError::WrongColumnType {
+                    column: field.name().clone(),
+                    expected: field.data_type().clone(),
+                    found: evolved.data_type().clone(),
+                });
+            }
+            Ok(evolved) if field.is_nullable() && !evolved.is_nullable() => {
+                return Err(SchemaError::ColumnMadeRequired(field.name().clone()));
+            }
+            Ok(_) => {}
+            Err(_) => return Err(SchemaError::MissingColumn(field.name().clone())),
+        }
+    }
+
+    if let Some(field) = evolved
+        .fields()
+        .iter()
+        .find(|field| current.field_with_name(field.name()).is_err() && !field.is_nullable())
+    {
+        return Err(SchemaError::RequiredColumnAdded(field.name().clone()));
+    }
+
+    Ok(())
+}
+
+/// Serializes a schema as an encapsulated Arrow IPC message, the same format produced by
+/// `pyarrow.Schema.serialize()`.
+pub fn schema_to_ipc(schema: &Schema) -> Result<Vec<u8>, Error> {
+    let options = IpcWriteOptions::default();
+    let encoded = IpcDataGenerator::default().schema_to_bytes_with_dictionary_tracker(
+        schema,
+        &mut DictionaryTracker::new(false),
+        &options,
+    );
+
+    let mut buffer = Vec::new();
+    write_message(&mut buffer, encoded, &options)?;
+    Ok(buffer)
+}
+
+/// Deserializes a schema encoded as an encapsulated Arrow IPC message, see [`schema_to_ipc`].
+pub fn schema_from_ipc(buffer: &[u8]) -> Result<SchemaRef, SchemaError> {
+    try_schema_from_ipc_buffer(buffer)
+        .map(Arc::new)
+        .map_err(|e| SchemaError::InvalidEncoding(e.to_string()))
+}
+
+/// Encodes the batches as an Arrow IPC file, with buffers compressed using zstd.
+pub fn batches_to_ipc_file(schema: &SchemaRef, batches: &[RecordBatch]) -> Result<Vec<u8>, Error> {
+    let options =
+        IpcWriteOptions::default().try_with_compression(Some(arrow::ipc::CompressionType::ZSTD))?;
+
+    let mut writer = FileWriter::try_new_with_options(Vec::new(), schema, options)?;
+    for batch in batches {
+        writer.write(batch)?;
+    }
+    writer.finish()?;
+
+    Ok(writer.into_inner()?)
+}
+
+/// Decodes the batches of an Arrow IPC file, see [`batches_to_ipc_file`].
+pub fn batches_from_ipc_file(bytes: Vec<u8>) -> Result<Vec<RecordBatch>, Error> {
+    let reader = FileReader::try_new(std::io::Cursor::new(bytes), None)?;
+    Ok(reader.collect::<Result<_, _>>()?)
+}
+
+/// Validates that `column` is a top-level column of the schema whose values can be ordered,
+/// as required by columns used as keys (e.g. sort keys).
+///
+/// # Errors
+///
+/// Returns [`SchemaError`] if the column is missing or can not be ordered.
+pub fn check_key_column(schema: &SchemaRef, column: &str) -> Result<(), SchemaError> {
+    let field = schema
+        .field_with_name(column)
+        .map_err(|_| SchemaError::MissingKeyColumn(column.to_owned()))?;
+
+    if !RowConverter::supports_fields(&[SortField::new(field.data_type().clone())]) {
+        return Err(SchemaError::UnorderableKeyColumn(column.to_owned()));
+    }
+
+    Ok(())
+}
+
+/// Returns true if the schemas have the same top-level columns, with the same names and
+/// data types in the same order. Nullability and metadata are ignored.
+pub fn has_same_columns(lhs: &SchemaRef, rhs: &SchemaRef) -> bool {
+    lhs.fields().len() == rhs.fields().len()
+        && lhs
+            .fields()
+            .iter()
+            .zip(rhs.fields())
+            .all(|(l, r)| l.name() == r.name() && l.data_type() == r.data_type())
+}
+
+/// Tracks whether the values of a column are in non-decreasing order across a sequence
+/// of record batches.
+///
+/// Null values are considered smaller than any other value.
+pub struct SortOrderTracker {
+    column: String,
+    converter: Option<RowConverter>,
+    /// Last value inspected so far
+    last: Option<OwnedRow>,
+    sorted: bool,
+}
+
+impl SortOrderTracker {
+    pub fn new(column: impl Into<String>) -> Self {
+        Self {
+            column: column.into(),
+            converter: None,
+            last: None,
+            sorted: true,
+        }
+    }
+
+    /// Column whose ordering is tracked
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    /// Returns true if all the values inspected so far are sorted
+    pub fn is_sorted(&self) -> bool {
+        self.sorted
+    }
+
+    /// Inspects the next batch of the sequence, returning true if all the values inspected
+    /// so far (this batch included) are sorted.
+    ///
+    /// Once the ordering is broken the following batches are no longer inspected.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the tracked column is missing from the batch or if its values
+    /// can not be ordered.
+    pub fn inspect(&mut self, batch: &RecordBatch) -> Result<bool, Error> {
+        if !self.sorted || batch.num_rows() == 0 {
+            return Ok(self.sorted);
+        }
+
+        let array = batch.column_by_name(&self.column).ok_or_else(|| {
+            arrow::error::ArrowError::SchemaError(format!("missing column `{}`", self.column))
+        })?;
+
+        let converter = match &mut self.converter {
+            Some(converter) => converter,
+            converter => converter.insert(RowConverter::new(vec![SortField::new(
+                array.data_type().clone(),
+            )])?),
+        };
+
+        let rows = converter.convert_columns(std::slice::from_ref(array))?;
+
+        let mut previous = self.last.as_ref().map(|row| row.row());
+        for row in rows.iter() {
+            if previous.is_some_and(|previous| row < previous) {
+                self.sorted = false;
+                break;
+            }
+            previous = Some(row);
+        }
+
+        self.last = Some(rows.row(rows.num_rows() - 1).owned());
+
+        Ok(self.sorted)
+    }
+}
+
 /// Return a arrow empty schema
 pub fn empty_schema_ref() -> Arc<Schema> {
     Arc::new(Schema::empty())
@@ -357,6 +601,19 @@ pub fn ontology_model_stats_inspect_record_batch(
     Ok(())
 }
 
+/// Returns the range of the timestamps in the batch, `None` if the batch is empty or has no
+/// valid timestamp column.
+pub fn timestamp_range(batch: &RecordBatch) -> Option<types::TimestampRange> {
+    let timestamps = batch
+        .column_by_name(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP)?
+        .as_primitive_opt::<arrow::datatypes::Int64Type>()?;
+
+    Some(types::TimestampRange::between(
+        arrow::compute::min(timestamps)?.into(),
+        arrow::compute::max(timestamps)?.into(),
+    ))
+}
+
 /// Creates an empty chunk that holds al schema fields.
 ///
 /// The schema fields are flattened inside the chunk.
@@ -436,6 +693,16 @@ mod tests {
         Arc::new(Schema::new(fields))
     }
 
+    #[test]
+    fn ipc_file_roundtrip() {
+        let batch = testing::dummy_batch();
+
+        let bytes = batches_to_ipc_file(&batch.schema(), &[batch.clone(), batch.clone()]).unwrap();
+        let decoded = batches_from_ipc_file(bytes).unwrap();
+
+        assert_eq!(decoded, [batch.clone(), batch]);
+    }
+
     /// Schema with the required 'timestamp' field.
     #[test]
     fn valid_schema_with_timestamp() {
@@ -484,6 +751,67 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn key_column_in_schema() {
+        let schema = create_schema(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float64, true),
+        ]);
+
+        assert!(check_key_column(&schema, "value").is_ok());
+        assert!(matches!(
+            check_key_column(&schema, "missing"),
+            Err(SchemaError::MissingKeyColumn(_))
+        ));
+    }
+
+    #[test]
+    fn same_columns() {
+        let schema = create_schema(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float64, true),
+        ]);
+        let nullable = create_schema(vec![
+            Field::new("timestamp_ns", DataType::Int64, true),
+            Field::new("value", DataType::Float64, true),
+        ]);
+        let retyped = create_schema(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Int64, true),
+        ]);
+
+        assert!(has_same_columns(&schema, &nullable));
+        assert!(!has_same_columns(&schema, &retyped));
+        assert!(!has_same_columns(&schema, &empty_schema_ref()));
+    }
+
+    #[test]
+    fn sort_order_across_batches() {
+        use arrow::array::Int64Array;
+
+        let schema = create_schema(vec![Field::new("timestamp_ns", DataType::Int64, false)]);
+        let batch = |values: Vec<i64>| {
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(values))]).unwrap()
+        };
+
+        let mut tracker = SortOrderTracker::new("timestamp_ns");
+        assert!(tracker.inspect(&batch(vec![1, 2, 2, 5])).unwrap());
+        assert!(tracker.inspect(&batch(vec![])).unwrap());
+        assert!(tracker.inspect(&batch(vec![5, 8])).unwrap());
+
+        // The ordering is broken across the batch boundary
+        assert!(!tracker.inspect(&batch(vec![7, 9])).unwrap());
+        assert!(!tracker.inspect(&batch(vec![10])).unwrap());
+        assert!(!tracker.is_sorted());
+
+        // The ordering is broken within the batch
+        let mut tracker = SortOrderTracker::new("timestamp_ns");
+        assert!(!tracker.inspect(&batch(vec![1, 3, 2])).unwrap());
+
+        let mut tracker = SortOrderTracker::new("missing");
+        assert!(tracker.inspect(&batch(vec![1])).is_err());
+    }
+
     // Helper function to create a simplified schema reference
     fn create_schema_ref(fields: Vec<Field>) -> Arc<Schema> {
         Arc::new(Schema::new(fields))
@@ -611,4 +939,119 @@ mod tests {
             vec!["list_of_ints".to_owned(), "map_data".to_owned(),]
         );
     }
+
+    #[test]
+    fn registered_schema_check() {
+        let registered = create_schema(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float64, false),
+            Field::new("note", DataType::Utf8, true),
+        ]);
+
+        // Columns can be reordered and nullable ones omitted
+        let schema = create_schema(vec![
+            Field::new("value", DataType::Float64, false),
+            Field::new("timestamp_ns", DataType::Int64, false),
+        ]);
+        assert!(check_registered_schema(&registered, &schema).is_ok());
+
+        let schema = create_schema(vec![Field::new("timestamp_ns", DataType::Int64, false)]);
+        assert!(matches!(
+            check_registered_schema(&registered, &schema),
+            Err(SchemaError::MissingColumn(column)) if column == "value"
+        ));
+
+        let schema = create_schema(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Int64, false),
+        ]);
+        assert!(matches!(
+            check_registered_schema(&registered, &schema),
+            Err(SchemaError::WrongColumnType { column, .. }) if column == "value"
+        ));
+
+        let schema = create_schema(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float64, false),
+            Field::new("extra", DataType::Utf8, true),
+        ]);
+        assert!(matches!(
+            check_registered_schema(&registered, &schema),
+            Err(SchemaError::UnexpectedColumn(column)) if column == "extra"
+        ));
+    }
+
+    #[test]
+    fn schema_evolution_check() {
+        let current = create_schema(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float64, false),
+            Field::new("note", DataType::Utf8, true),
+        ]);
+
+        // Nullable columns can be added and required columns relaxed
+        let evolved = create_schema(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float64, true),
+            Field::new("note", DataType::Utf8, true),
+            Field::new("extra", DataType::Utf8, true),
+        ]);
+        assert!(check_schema_evolution(&current, &evolved).is_ok());
+
+        let evolved = create_schema(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float64, false),
+            Field::new("note", DataType::Utf8, true),
+            Field::new("extra", DataType::Utf8, false),
+        ]);
+        assert!(matches!(
+            check_schema_evolution(&current, &evolved),
+            Err(SchemaError::RequiredColumnAdded(column)) if column == "extra"
+        ));
+
+        let evolved = create_schema(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float32, false),
+            Field::new("note", DataType::Utf8, true),
+        ]);
+        assert!(matches!(
+            check_schema_evolution(&current, &evolved),
+            Err(SchemaError::WrongColumnType { column, .. }) if column == "value"
+        ));
+
+        let evolved = create_schema(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float64, false),
+        ]);
+        assert!(matches!(
+            check_schema_evolution(&current, &evolved),
+            Err(SchemaError::MissingColumn(column)) if column == "note"
+        ));
+
+        let evolved = create_schema(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float64, false),
+            Field::new("note", DataType::Utf8, false),
+        ]);
+        assert!(matches!(
+            check_schema_evolution(&current, &evolved),
+            Err(SchemaError::ColumnMadeRequired(column)) if column == "note"
+        ));
+    }
+
+    #[test]
+    fn schema_ipc_roundtrip() {
+        let schema = create_schema(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("value", DataType::Float64, true),
+        ]);
+
+        let buffer = schema_to_ipc(&schema).unwrap();
+        assert_eq!(schema_from_ipc(&buffer).unwrap(), schema);
+
+        assert!(matches!(
+            schema_from_ipc(b"not a schema"),
+            Err(SchemaError::InvalidEncoding(_))
+        ));
+    }
 }
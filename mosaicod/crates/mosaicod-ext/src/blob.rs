@@ -0,0 +1,417 @@
+//! Helpers used to handle large binary columns.
+//!
+//! Camera frames and point clouds are usually stored as binary columns holding
+//! several megabytes per row, this module provides the utilities required to keep
+//! record batches of these columns within reasonable sizes:
+//!
+//! * [`split_by_binary_size`] slices a batch so that each slice holds a bounded amount of binary data.
+//! * [`offload_column`] moves the blobs above a size threshold out of the batch, leaving a reference
+//!   in their place, so they can be saved as standalone objects.
+//! * [`BlobColumn`] gives access to an offloaded column, loading external blobs only when requested.
+use crate::arrow::Error;
+use arrow::array::{
+    Array, ArrayRef, AsArray, BinaryArray, BinaryBuilder, RecordBatch, StringArray, StructArray,
+};
+use arrow::datatypes::{DataType, Field, Fields, Schema};
+use arrow::error::ArrowError;
+use std::sync::Arc;
+
+/// Name of the struct field holding blobs stored inside the batch
+pub const BLOB_INLINE_FIELD: &str = "inline";
+
+/// Name of the struct field holding the reference of blobs stored outside the batch
+pub const BLOB_REFERENCE_FIELD: &str = "reference";
+
+/// Data type used for columns with offloaded blobs.
+///
+/// Each row holds either the inline blob or the reference to the external object.
+pub fn blob_data_type() -> DataType {
+    DataType::Struct(blob_fields())
+}
+
+fn blob_fields() -> Fields {
+    Fields::from(vec![
+        Field::new(BLOB_INLINE_FIELD, DataType::Binary, true),
+        Field::new(BLOB_REFERENCE_FIELD, DataType::Utf8, true),
+    ])
+}
+
+/// Returns true if the data type is the one used for offloaded blob columns
+pub fn is_blob(data_type: &DataType) -> bool {
+    *data_type == blob_data_type()
+}
+
+/// Returns true if the data type is a binary type
+pub fn is_binary(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Binary | DataType::LargeBinary | DataType::BinaryView
+    )
+}
+
+/// Returns the schema used to store batches with offloaded blobs, where every top level
+/// binary field is replaced with a field of type [`blob_data_type`].
+pub fn offload_schema(schema: &Schema) -> Schema {
+    map_top_level_fields(schema, is_binary, blob_data_type())
+}
+
+/// Returns the schema of batches with offloaded blobs once all the blobs are inlined again,
+/// this is the inverse of [`offload_schema`].
+pub fn inline_schema(schema: &Schema) -> Schema {
+    map_top_level_fields(schema, is_blob, DataType::Binary)
+}
+
+fn map_top_level_fields(schema: &Schema, filter: fn(&DataType) -> bool, to: DataType) -> Schema {
+    let fields: Vec<Field> = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            if filter(field.data_type()) {
+                Field::new(field.name(), to.clone(), field.is_nullable())
+            } else {
+                field.as_ref().clone()
+            }
+        })
+        .collect();
+
+    Schema::new_with_metadata(fields, schema.metadata().clone())
+}
+
+/// Computes the amount of binary data held by each row of the batch.
+///
+/// Only top level binary columns are considered.
+pub fn binary_size_per_row(batch: &RecordBatch) -> Result<Vec<usize>, Error> {
+    let mut sizes = vec![0usize; batch.num_rows()];
+
+    for column in batch.columns() {
+        if !is_binary(column.data_type()) {
+            continue;
+        }
+
+        let array = arrow_cast::cast(column, &DataType::LargeBinary)?;
+        let array = array.as_binary::<i64>();
+
+        for (row, size) in sizes.iter_mut().enumerate() {
+            if array.is_valid(row) {
+                *size += array.value_length(row) as usize;
+            }
+        }
+    }
+
+    Ok(sizes)
+}
+
+/// Splits the batch into zero-copy slices holding at most `max_bytes` of binary data.
+///
+/// Rows are never split, so a slice exceeds the limit only if it is made by a single row
+/// larger than `max_bytes`. If `max_bytes` is zero the batch is returned untouched.
+pub fn split_by_binary_size(
+    batch: &RecordBatch,
+    max_bytes: usize,
+) -> Result<Vec<RecordBatch>, Error> {
+    if max_bytes == 0 || batch.num_rows() == 0 {
+        return Ok(vec![batch.clone()]);
+    }
+
+    let mut slices = Vec::new();
+    let mut offset = 0;
+    let mut accumulated = 0;
+
+    for (row, size) in binary_size_per_row(batch)?.into_iter().enumerate() {
+        if row > offset && accumulated + size > max_bytes {
+            slices.push(batch.slice(offset, row - offset));
+            offset = row;
+            accumulated = 0;
+        }
+        accumulated += size;
+    }
+    slices.push(batch.slice(offset, batch.num_rows() - offset));
+
+    Ok(slices)
+}
+
+/// A blob removed from a batch by [`offload_column`]
+#[derive(Debug)]
+pub struct OffloadedBlob {
+    /// Reference left in the batch in place of the blob
+    pub reference: String,
+    pub data: Vec<u8>,
+}
+
+/// Moves the blobs of a binary column larger than `threshold` bytes out of the batch.
+///
+/// The column is replaced with a column of type [`blob_data_type`], where the blobs above the
+/// threshold are replaced by the reference returned by `make_reference` (called with the row
+/// index and the blob). The removed blobs are returned along with the new batch, and need to be
+/// stored by the caller.
+pub fn offload_column<F>(
+    batch: &RecordBatch,
+    column: &str,
+    threshold: usize,
+    mut make_reference: F,
+) -> Result<(RecordBatch, Vec<OffloadedBlob>), Error>
+where
+    F: FnMut(usize, &[u8]) -> String,
+{
+    let schema = batch.schema();
+    let (index, field) = schema
+        .column_with_name(column)
+        .ok_or_else(|| ArrowError::SchemaError(format!("can't find binary column `{column}`")))?;
+
+    if !is_binary(field.data_type()) {
+        Err(ArrowError::SchemaError(format!(
+            "column `{column}` is not a binary column"
+        )))?;
+    }
+
+    let array = arrow_cast::cast(batch.column(index), &DataType::LargeBinary)?;
+    let array = array.as_binary::<i64>();
+
+    let mut inline = BinaryBuilder::new();
+    let mut references = Vec::with_capacity(array.len());
+    let mut blobs = Vec::new();
+
+    for row in 0..array.len() {
+        if array.is_null(row) {
+            inline.append_null();
+            references.push(None);
+            continue;
+        }
+
+        let value = array.value(row);
+        if value.len() > threshold {
+            let reference = make_reference(row, value);
+            inline.append_null();
+            references.push(Some(reference.clone()));
+            blobs.push(OffloadedBlob {
+                reference,
+                data: value.to_vec(),
+            });
+        } else {
+            inline.append_value(value);
+            references.push(None);
+        }
+    }
+
+    let blob_array = StructArray::try_new(
+        blob_fields(),
+        vec![
+            Arc::new(inline.finish()),
+            Arc::new(StringArray::from(references)),
+        ],
+        array.nulls().cloned(),
+    )?;
+
+    let mut fields: Vec<Field> = schema.fields().iter().map(|f| f.as_ref().clone()).collect();
+    fields[index] = Field::new(column, blob_data_type(), field.is_nullable());
+
+    let mut columns: Vec<ArrayRef> = batch.columns().to_vec();
+    columns[index] = Arc::new(blob_array);
+
+    let batch = RecordBatch::try_new(
+        Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone())),
+        columns,
+    )?;
+
+    Ok((batch, blobs))
+}
+
+/// Value of a row in a [`BlobColumn`]
+#[derive(Debug, PartialEq)]
+pub enum BlobValue<'a> {
+    Null,
+    Inline(&'a [u8]),
+    External(&'a str),
+}
+
+impl BlobValue<'_> {
+    /// Returns the blob content, using `fetch` to load external blobs.
+    pub fn load<F>(&self, fetch: F) -> Result<Option<Vec<u8>>, Error>
+    where
+        F: FnOnce(&str) -> Result<Vec<u8>, Error>,
+    {
+        match self {
+            Self::Null => Ok(None),
+            Self::Inline(data) => Ok(Some(data.to_vec())),
+            Self::External(reference) => Ok(Some(fetch(reference)?)),
+        }
+    }
+}
+
+/// Read access to a column produced by [`offload_column`].
+///
+/// External blobs are never loaded by the column itself, allowing callers to
+/// fetch them lazily only for the rows they are interested in.
+pub struct BlobColumn<'a> {
+    array: &'a StructArray,
+    inline: &'a BinaryArray,
+    references: &'a StringArray,
+}
+
+impl<'a> BlobColumn<'a> {
+    pub fn try_new(array: &'a ArrayRef) -> Result<Self, Error> {
+        if !is_blob(array.data_type()) {
+            Err(ArrowError::SchemaError(
+                "array is not an offloaded blob column".to_owned(),
+            ))?;
+        }
+
+        let array = array.as_struct();
+        Ok(Self {
+            array,
+            inline: array.column(0).as_binary::<i32>(),
+            references: array.column(1).as_string::<i32>(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.array.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.array.is_empty()
+    }
+
+    pub fn value(&self, row: usize) -> BlobValue<'a> {
+        if self.array.is_null(row) {
+            BlobValue::Null
+        } else if self.references.is_valid(row) {
+            BlobValue::External(self.references.value(row))
+        } else if self.inline.is_valid(row) {
+            BlobValue::Inline(self.inline.value(row))
+        } else {
+            BlobValue::Null
+        }
+    }
+
+    /// Returns the references of all the external blobs in the column
+    pub fn references(&self) -> impl Iterator<Item = &'a str> + '_ {
+        (0..self.len()).filter_map(|row| match self.value(row) {
+            BlobValue::External(reference) => Some(reference),
+            _ => None,
+        })
+    }
+
+    /// Rebuilds a plain binary array, loading every external blob with `fetch`.
+    pub fn resolve<F>(&self, mut fetch: F) -> Result<ArrayRef, Error>
+    where
+        F: FnMut(&str) -> Result<Vec<u8>, Error>,
+    {
+        let mut builder = BinaryBuilder::new();
+        for row in 0..self.len() {
+            match self.value(row).load(&mut fetch)? {
+                Some(data) => builder.append_value(data),
+                None => builder.append_null(),
+            }
+        }
+        Ok(Arc::new(builder.finish()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int64Array;
+    use std::collections::HashMap;
+
+    fn frames_batch() -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("frame", DataType::Binary, true),
+        ]));
+
+        let frames: Vec<Option<&[u8]>> = vec![
+            Some(&[0; 10]),
+            Some(&[1; 100]),
+            None,
+            Some(&[3; 60]),
+            Some(&[4; 5]),
+        ];
+
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4, 5])),
+                Arc::new(BinaryArray::from(frames)),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn split_batch() {
+        let batch = frames_batch();
+
+        assert_eq!(
+            binary_size_per_row(&batch).unwrap(),
+            vec![10, 100, 0, 60, 5]
+        );
+
+        let slices = split_by_binary_size(&batch, 64).unwrap();
+        let rows: Vec<usize> = slices.iter().map(|s| s.num_rows()).collect();
+        assert_eq!(rows, vec![1, 1, 2, 1]);
+
+        let slices = split_by_binary_size(&batch, 0).unwrap();
+        assert_eq!(slices.len(), 1);
+        assert_eq!(slices[0].num_rows(), 5);
+    }
+
+    #[test]
+    fn offload_and_resolve() {
+        let batch = frames_batch();
+
+        let (offloaded, blobs) =
+            offload_column(&batch, "frame", 50, |row, _| format!("blob_{row}")).unwrap();
+
+        assert_eq!(blobs.len(), 2);
+        assert_eq!(blobs[0].reference, "blob_1");
+        assert_eq!(blobs[1].reference, "blob_3");
+        assert!(is_blob(
+            offloaded
+                .schema()
+                .field_with_name("frame")
+                .unwrap()
+                .data_type()
+        ));
+
+        let column = offloaded.column_by_name("frame").unwrap();
+        let column = BlobColumn::try_new(column).unwrap();
+        assert_eq!(column.value(0), BlobValue::Inline(&[0; 10]));
+        assert_eq!(column.value(1), BlobValue::External("blob_1"));
+        assert_eq!(column.value(2), BlobValue::Null);
+        assert_eq!(
+            column.references().collect::<Vec<_>>(),
+            ["blob_1", "blob_3"]
+        );
+
+        let objects: HashMap<String, Vec<u8>> = blobs
+            .into_iter()
+            .map(|blob| (blob.reference, blob.data))
+            .collect();
+
+        let resolved = column
+            .resolve(|reference| Ok(objects[reference].clone()))
+            .unwrap();
+
+        assert_eq!(resolved.as_ref(), batch.column(1).as_ref());
+    }
+
+    #[test]
+    fn offload_and_inline_schema() {
+        let batch = frames_batch();
+        let schema = batch.schema();
+
+        let offloaded = offload_schema(&schema);
+        assert!(is_blob(offloaded.field(1).data_type()));
+        assert_eq!(offloaded.field(0), schema.field(0));
+
+        assert_eq!(inline_schema(&offloaded), *schema);
+    }
+
+    #[test]
+    fn offload_wrong_column() {
+        let batch = frames_batch();
+        assert!(offload_column(&batch, "timestamp_ns", 50, |_, _| String::new()).is_err());
+        assert!(offload_column(&batch, "missing", 50, |_, _| String::new()).is_err());
+    }
+}
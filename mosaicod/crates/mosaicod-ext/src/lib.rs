@@ -1,2 +1,7 @@
+pub mod anonymize;
 pub mod arrow;
+pub mod blob;
+pub mod mcap;
+pub mod ontology;
+pub mod redact;
 pub mod tonic;
@@ -0,0 +1,453 @@
+//! Conversion of MCAP recordings into record batches.
+//!
+//! Every channel of a recording is converted on its own. Messages of JSON channels described
+//! by a JSON schema are decoded into typed columns, while the messages of any other encoding
+//! (e.g. ROS or Protobuf) are stored as they are in the binary [`COLUMN_DATA`] column, leaving
+//! to the readers their decoding with the schema of the channel. The log time of the messages
+//! becomes the timestamp index, their publish time and sequence number are stored in the
+//! [`COLUMN_PUBLISH_TIME`] and [`COLUMN_SEQUENCE`] columns.
+use arrow::array::{ArrayRef, BinaryBuilder, Int64Builder, RecordBatch, UInt32Builder};
+use arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef};
+use arrow::json::{ReaderBuilder, reader::Decoder};
+use mosaicod_core::{self as core, params};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Column holding the publish time of the messages, in nanoseconds.
+pub const COLUMN_PUBLISH_TIME: &str = "publish_time_ns";
+/// Column holding the sequence number of the messages.
+pub const COLUMN_SEQUENCE: &str = "sequence";
+/// Column holding the messages of the channels not decoded into typed columns.
+pub const COLUMN_DATA: &str = "data";
+
+/// Message encoding of the channels decoded into typed columns, along with the encoding of
+/// their schema.
+const JSON_MESSAGE_ENCODING: &str = "json";
+const JSON_SCHEMA_ENCODING: &str = "jsonschema";
+
+#[derive(thiserror::Error, Debug)]
+pub enum McapError {
+    #[error("invalid MCAP recording: {0}")]
+    InvalidRecording(String),
+
+    #[error("unsupported schema of channel `{channel}`: {msg}")]
+    UnsupportedSchema { channel: String, msg: String },
+
+    #[error("unable to decode the messages of channel `{channel}`: {msg}")]
+    InvalidMessage { channel: String, msg: String },
+}
+
+impl core::error::PublicError for McapError {
+    fn error(&self) -> core::Error {
+        match self {
+            Self::UnsupportedSchema { .. } => core::Error::unsupported_schema(self.to_string()),
+            _ => core::Error::bad_request(self.to_string()),
+        }
+    }
+}
+
+impl From<mcap::McapError> for McapError {
+    fn from(value: mcap::McapError) -> Self {
+        Self::InvalidRecording(value.to_string())
+    }
+}
+
+/// Returns the messages of an MCAP recording, in the order they are stored.
+pub fn messages(
+    recording: &[u8],
+) -> Result<impl Iterator<Item = Result<mcap::Message<'_>, McapError>>, McapError> {
+    Ok(mcap::MessageStream::new(recording)?.map(|message| message.map_err(Into::into)))
+}
+
+/// Returns the name of the topic the channel `channel_id` publishing on `topic` is imported
+/// to. Empty path segments are dropped and the symbols other than ASCII letters, digits, `_`
+/// and `-` are replaced by `_`, e.g. `/camera/front.raw` becomes `camera/front_raw`.
+pub fn topic_name(topic: &str, channel_id: u16) -> String {
+    let name = topic
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            segment
+                .chars()
+                .map(|c| match c {
+                    'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' => c,
+                    _ => '_',
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+
+    if name.is_empty() {
+        format!("channel_{channel_id}")
+    } else {
+        name
+    }
+}
+
+/// Encoding of the messages of a channel and of their schema.
+#[derive(Debug, Clone, Copy)]
+pub struct ChannelEncoding<'a> {
+    pub message_encoding: &'a str,
+    pub schema_encoding: Option<&'a str>,
+    pub schema: &'a [u8],
+}
+
+impl<'a> From<&'a mcap::Channel<'_>> for ChannelEncoding<'a> {
+    fn from(channel: &'a mcap::Channel<'_>) -> Self {
+        Self {
+            message_encoding: &channel.message_encoding,
+            schema_encoding: channel
+                .schema
+                .as_ref()
+                .map(|schema| schema.encoding.as_str()),
+            schema: channel
+                .schema
+                .as_ref()
+                .map(|schema| schema.data.as_ref())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl ChannelEncoding<'_> {
+    /// Returns true if the messages are decoded into typed columns.
+    pub fn is_typed(&self) -> bool {
+        self.message_encoding == JSON_MESSAGE_ENCODING
+            && self.schema_encoding == Some(JSON_SCHEMA_ENCODING)
+    }
+}
+
+/// Decodes the messages of a channel into record batches of at most `batch_rows` rows,
+/// returning a batch as soon as its messages are at least `batch_bytes` bytes.
+pub struct ChannelDecoder {
+    channel: String,
+    schema: SchemaRef,
+    batch_rows: usize,
+    batch_bytes: usize,
+    rows: usize,
+    bytes: usize,
+    timestamps: Int64Builder,
+    publish_times: Int64Builder,
+    sequences: UInt32Builder,
+    payload: Payload,
+}
+
+enum Payload {
+    /// Messages decoded into the columns following the index ones
+    Json(Decoder),
+    /// Messages stored as they are
+    Raw(BinaryBuilder),
+}
+
+impl ChannelDecoder {
+    pub fn try_new(
+        channel: &str,
+        encoding: ChannelEncoding,
+        batch_rows: usize,
+        batch_bytes: usize,
+    ) -> Result<Self, McapError> {
+        let batch_rows = batch_rows.max(1);
+        let unsupported = |msg: String| McapError::UnsupportedSchema {
+            channel: channel.to_owned(),
+            msg,
+        };
+
+        let mut fields = vec![
+            Field::new(
+                params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+                DataType::Int64,
+                false,
+            ),
+            Field::new(COLUMN_PUBLISH_TIME, DataType::Int64, false),
+            Field::new(COLUMN_SEQUENCE, DataType::UInt32, false),
+        ];
+
+        let payload = if encoding.is_typed() {
+            let properties = json_schema_fields(encoding.schema).map_err(unsupported)?;
+            if let Some(property) = properties
+                .iter()
+                .find(|property| fields.iter().any(|f| f.name() == property.name()))
+            {
+                return Err(unsupported(format!(
+                    "property `{}` clashes with a column of the topic",
+                    property.name()
+                )));
+            }
+
+            let decoder = ReaderBuilder::new(Arc::new(Schema::new(properties.clone())))
+                .with_batch_size(batch_rows)
+                .build_decoder()
+                .map_err(|e| unsupported(e.to_string()))?;
+            fields.extend(properties);
+            Payload::Json(decoder)
+        } else {
+            fields.push(Field::new(COLUMN_DATA, DataType::Binary, false));
+            Payload::Raw(BinaryBuilder::new())
+        };
+
+        Ok(Self {
+            channel: channel.to_owned(),
+            schema: Arc::new(Schema::new(fields)),
+            batch_rows,
+            batch_bytes,
+            rows: 0,
+            bytes: 0,
+            timestamps: Int64Builder::new(),
+            publish_times: Int64Builder::new(),
+            sequences: UInt32Builder::new(),
+            payload,
+        })
+    }
+
+    /// Schema of the batches returned by the decoder.
+    pub fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    /// Adds a message to the batch being built, returning the batch once it is full.
+    pub fn push(
+        &mut self,
+        log_time: u64,
+        publish_time: u64,
+        sequence: u32,
+        data: &[u8],
+    ) -> Result<Option<RecordBatch>, McapError> {
+        match &mut self.payload {
+            Payload::Json(decoder) => {
+                let decoded = decoder
+                    .decode(data)
+                    .map_err(|e| McapError::InvalidMessage {
+                        channel: self.channel.clone(),
+                        msg: e.to_string(),
+                    })?;
+                if decoded != data.len() {
+                    return Err(self.invalid_message("message not entirely decoded"));
+                }
+            }
+            Payload::Raw(builder) => builder.append_value(data),
+        }
+
+        self.timestamps.append_value(log_time as i64);
+        self.publish_times.append_value(publish_time as i64);
+        self.sequences.append_value(sequence);
+        self.rows += 1;
+        self.bytes += data.len();
+
+        if self.rows >= self.batch_rows || self.bytes >= self.batch_bytes {
+            return self.flush();
+        }
+        Ok(None)
+    }
+
+    /// Returns the batch of the messages added since the last one returned, if any.
+    pub fn flush(&mut self) -> Result<Option<RecordBatch>, McapError> {
+        if self.rows == 0 {
+            return Ok(None);
+        }
+        self.rows = 0;
+        self.bytes = 0;
+
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(self.timestamps.finish()),
+            Arc::new(self.publish_times.finish()),
+            Arc::new(self.sequences.finish()),
+        ];
+        match &mut self.payload {
+            Payload::Json(decoder) => {
+                let batch = decoder
+                    .flush()
+                    .map_err(|e| McapError::InvalidMessage {
+                        channel: self.channel.clone(),
+                        msg: e.to_string(),
+                    })?
+                    .ok_or_else(|| self.invalid_message("no message decoded"))?;
+                columns.extend(batch.columns().iter().cloned());
+            }
+            Payload::Raw(builder) => columns.push(Arc::new(builder.finish())),
+        }
+
+        let batch = RecordBatch::try_new(self.schema.clone(), columns)
+            .map_err(|e| self.invalid_message(&e.to_string()))?;
+        Ok(Some(batch))
+    }
+
+    fn invalid_message(&self, msg: &str) -> McapError {
+        McapError::InvalidMessage {
+            channel: self.channel.clone(),
+            msg: msg.to_owned(),
+        }
+    }
+}
+
+/// Maps the properties of a JSON schema describing an object to Arrow fields. Properties
+/// whose type can not be mapped are skipped, and ignored when decoding the messages.
+fn json_schema_fields(schema: &[u8]) -> Result<Vec<Field>, String> {
+    let schema: Value = serde_json::from_slice(schema).map_err(|e| e.to_string())?;
+
+    if json_type(&schema) != Some("object") {
+        return Err("the schema does not describe an object".to_owned());
+    }
+
+    Ok(object_fields(&schema))
+}
+
+/// Returns the type of a JSON schema, ignoring `null` in unions of types.
+fn json_type(schema: &Value) -> Option<&str> {
+    match schema.get("type")? {
+        Value::String(name) => Some(name),
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|name| *name != "null"),
+        _ => None,
+    }
+}
+
+fn object_fields(schema: &Value) -> Vec<Field> {
+    schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .map(|properties| {
+            properties
+                .iter()
+                .filter_map(|(name, property)| Some(Field::new(name, data_type(property)?, true)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn data_type(schema: &Value) -> Option<DataType> {
+    match json_type(schema)? {
+        "boolean" => Some(DataType::Boolean),
+        "integer" => Some(DataType::Int64),
+        "number" => Some(DataType::Float64),
+        "string" => Some(DataType::Utf8),
+        "array" => {
+            let item = data_type(schema.get("items")?)?;
+            Some(DataType::List(Arc::new(Field::new_list_field(item, true))))
+        }
+        "object" => {
+            let fields = object_fields(schema);
+            (!fields.is_empty()).then(|| DataType::Struct(Fields::from(fields)))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{AsArray, BinaryArray, Int64Array};
+    use arrow::datatypes::{Float64Type, Int64Type};
+
+    const POSE_SCHEMA: &str = r#"{
+        "type": "object",
+        "properties": {
+            "frame_id": { "type": "string" },
+            "position": {
+                "type": "object",
+                "properties": {
+                    "x": { "type": "number" },
+                    "y": { "type": "number" }
+                }
+            },
+            "covariance": { "type": "array", "items": { "type": "number" } },
+            "quality": { "type": ["integer", "null"] },
+            "extra": { "type": "object" }
+        }
+    }"#;
+
+    #[test]
+    fn topic_names() {
+        assert_eq!(topic_name("/camera/front.raw", 1), "camera/front_raw");
+        assert_eq!(topic_name("imu//data/", 1), "imu/data");
+        assert_eq!(topic_name("/tf static", 1), "tf_static");
+        assert_eq!(topic_name("/", 7), "channel_7");
+    }
+
+    #[test]
+    fn map_json_schema() {
+        let fields = json_schema_fields(POSE_SCHEMA.as_bytes()).unwrap();
+        let names: Vec<&str> = fields.iter().map(|f| f.name().as_str()).collect();
+        // Objects without properties can not be mapped
+        assert_eq!(names, vec!["covariance", "frame_id", "position", "quality"]);
+
+        assert!(matches!(fields[0].data_type(), DataType::List(_)));
+        assert_eq!(fields[1].data_type(), &DataType::Utf8);
+        assert!(matches!(fields[2].data_type(), DataType::Struct(f) if f.len() == 2));
+        assert_eq!(fields[3].data_type(), &DataType::Int64);
+
+        assert!(json_schema_fields(br#"{ "type": "string" }"#).is_err());
+        assert!(json_schema_fields(b"not a schema").is_err());
+    }
+
+    #[test]
+    fn decode_json_messages() {
+        let encoding = ChannelEncoding {
+            message_encoding: "json",
+            schema_encoding: Some("jsonschema"),
+            schema: POSE_SCHEMA.as_bytes(),
+        };
+        assert!(encoding.is_typed());
+
+        let mut decoder = ChannelDecoder::try_new("/pose", encoding, 2, usize::MAX).unwrap();
+        assert_eq!(decoder.schema().fields().len(), 7);
+
+        let first = br#"{ "frame_id": "map", "position": { "x": 1.5, "y": 2 }, "quality": 3 }"#;
+        assert!(decoder.push(10, 11, 0, first).unwrap().is_none());
+
+        let second = br#"{ "frame_id": "odom", "covariance": [0.1, 0.2], "extra": {} }"#;
+        let batch = decoder.push(20, 21, 1, second).unwrap().unwrap();
+        assert_eq!(batch.num_rows(), 2);
+
+        let timestamps = batch.column(0).as_primitive::<Int64Type>();
+        assert_eq!(timestamps.values(), &[10, 20]);
+        let publish_times = batch.column(1).as_primitive::<Int64Type>();
+        assert_eq!(publish_times.values(), &[11, 21]);
+
+        let frames = batch.column_by_name("frame_id").unwrap().as_string::<i32>();
+        assert_eq!(frames.value(1), "odom");
+        let position = batch.column_by_name("position").unwrap().as_struct();
+        assert_eq!(
+            position.column(0).as_primitive::<Float64Type>().value(0),
+            1.5
+        );
+        let quality = batch.column_by_name("quality").unwrap();
+        assert!(quality.is_null(1));
+
+        // The decoder is empty after a batch is returned
+        assert!(decoder.flush().unwrap().is_none());
+    }
+
+    #[test]
+    fn store_raw_messages() {
+        let encoding = ChannelEncoding {
+            message_encoding: "cdr",
+            schema_encoding: Some("ros2msg"),
+            schema: b"float64 x\nfloat64 y",
+        };
+        assert!(!encoding.is_typed());
+
+        let mut decoder = ChannelDecoder::try_new("/odom", encoding, 10, 4).unwrap();
+        assert!(decoder.push(10, 10, 0, &[1, 2, 3]).unwrap().is_none());
+
+        // The batch is returned once its messages reach the size limit
+        let batch = decoder.push(20, 25, 1, &[4, 5]).unwrap().unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema().field(3).name(), COLUMN_DATA);
+
+        let data = batch
+            .column(3)
+            .as_any()
+            .downcast_ref::<BinaryArray>()
+            .unwrap();
+        assert_eq!(data.value(1), &[4, 5]);
+        let publish_times = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(publish_times.values(), &[10, 25]);
+    }
+}
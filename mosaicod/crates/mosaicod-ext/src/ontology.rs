@@ -0,0 +1,364 @@
+//! Well-known ontology models.
+//!
+//! This module ships a curated set of sensor ontologies (imu, gnss, pose, point cloud and
+//! compressed image) together with their canonical Arrow schemas. Topics declaring one of
+//! these ontology tags are validated against the canonical schema, while topics using any
+//! other tag are left untouched.
+use arrow::array::{ArrayRef, RecordBatch, new_null_array};
+use arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef};
+use mosaicod_core::{self as core, params};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+#[derive(thiserror::Error, Debug)]
+pub enum OntologyError {
+    #[error("missing field `{field}` required by ontology `{tag}`")]
+    MissingField { tag: String, field: String },
+
+    #[error("field `{field}` of ontology `{tag}` has type `{found}`, expected `{expected}`")]
+    WrongFieldType {
+        tag: String,
+        field: String,
+        expected: DataType,
+        found: DataType,
+    },
+
+    #[error("unable to convert record batch to ontology `{tag}`: {msg}")]
+    ConversionError { tag: String, msg: String },
+
+    #[error("ontology `{0}` is already registered")]
+    AlreadyRegistered(String),
+}
+
+impl core::error::PublicError for OntologyError {
+    fn error(&self) -> core::Error {
+        match self {
+            Self::AlreadyRegistered(_) => core::Error::internal(Some(self.to_string())),
+            _ => core::Error::unsupported_schema(self.to_string()),
+        }
+    }
+}
+
+/// An ontology model with its canonical Arrow schema.
+#[derive(Debug, Clone)]
+pub struct Ontology {
+    tag: String,
+    schema: SchemaRef,
+}
+
+impl Ontology {
+    /// Creates a new ontology model.
+    ///
+    /// The timestamp index column is automatically prepended to the provided fields.
+    pub fn new(tag: impl Into<String>, fields: Vec<Field>) -> Self {
+        let mut all_fields = vec![Field::new(
+            params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+            DataType::Int64,
+            false,
+        )];
+        all_fields.extend(fields);
+
+        Self {
+            tag: tag.into(),
+            schema: Arc::new(Schema::new(all_fields)),
+        }
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Canonical schema of the ontology
+    pub fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    /// Checks if the provided schema is compatible with the canonical one.
+    ///
+    /// Every non-nullable canonical field must be present, and every canonical field that is
+    /// present must have the canonical data type. Additional fields are allowed.
+    pub fn check_schema(&self, schema: &SchemaRef) -> Result<(), OntologyError> {
+        for canonical in self.schema.fields() {
+            match schema.field_with_name(canonical.name()) {
+                Ok(field) => {
+                    if field.data_type() != canonical.data_type() {
+                        return Err(OntologyError::WrongFieldType {
+                            tag: self.tag.clone(),
+                            field: canonical.name().clone(),
+                            expected: canonical.data_type().clone(),
+                            found: field.data_type().clone(),
+                        });
+                    }
+                }
+                Err(_) if canonical.is_nullable() => {}
+                Err(_) => {
+                    return Err(OntologyError::MissingField {
+                        tag: self.tag.clone(),
+                        field: canonical.name().clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts a record batch to the canonical schema of the ontology.
+    ///
+    /// Columns are selected by name and casted to the canonical data type when required,
+    /// missing nullable columns are filled with nulls and additional columns are dropped.
+    pub fn conform(&self, batch: &RecordBatch) -> Result<RecordBatch, OntologyError> {
+        let conversion_error = |msg: String| OntologyError::ConversionError {
+            tag: self.tag.clone(),
+            msg,
+        };
+
+        let mut columns: Vec<ArrayRef> = Vec::with_capacity(self.schema.fields().len());
+
+        for canonical in self.schema.fields() {
+            let column = match batch.column_by_name(canonical.name()) {
+                Some(column) if column.data_type() == canonical.data_type() => column.clone(),
+                Some(column) => arrow_cast::cast(column, canonical.data_type())
+                    .map_err(|e| conversion_error(e.to_string()))?,
+                None if canonical.is_nullable() => {
+                    new_null_array(canonical.data_type(), batch.num_rows())
+                }
+                None => {
+                    return Err(OntologyError::MissingField {
+                        tag: self.tag.clone(),
+                        field: canonical.name().clone(),
+                    });
+                }
+            };
+            columns.push(column);
+        }
+
+        RecordBatch::try_new(self.schema.clone(), columns)
+            .map_err(|e| conversion_error(e.to_string()))
+    }
+}
+
+/// Collection of ontology models indexed by tag.
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    models: HashMap<String, Ontology>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry containing all the well-known ontologies.
+    pub fn with_well_known() -> Self {
+        let mut registry = Self::new();
+        for ontology in well_known() {
+            // Well-known tags are unique, the registration can not fail
+            registry.register(ontology).unwrap();
+        }
+        registry
+    }
+
+    /// Registers a new ontology model, fails if the tag is already registered.
+    pub fn register(&mut self, ontology: Ontology) -> Result<(), OntologyError> {
+        if self.models.contains_key(ontology.tag()) {
+            return Err(OntologyError::AlreadyRegistered(ontology.tag().to_owned()));
+        }
+        self.models.insert(ontology.tag().to_owned(), ontology);
+        Ok(())
+    }
+
+    pub fn get(&self, tag: &str) -> Option<&Ontology> {
+        self.models.get(tag)
+    }
+
+    /// Returns the registered ontology tags
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.models.keys().map(String::as_str)
+    }
+}
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+/// Returns the global ontology registry.
+///
+/// The registry is initialized on first access with the well-known ontologies.
+pub fn registry() -> &'static Registry {
+    REGISTRY.get_or_init(Registry::with_well_known)
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Well-known ontologies
+// ////////////////////////////////////////////////////////////////////////////
+
+pub const IMU: &str = "imu";
+pub const GNSS: &str = "gnss";
+pub const POSE: &str = "pose";
+pub const POINT_CLOUD: &str = "point_cloud";
+pub const COMPRESSED_IMAGE: &str = "compressed_image";
+
+fn vector3d() -> DataType {
+    DataType::Struct(Fields::from(vec![
+        Field::new("x", DataType::Float64, false),
+        Field::new("y", DataType::Float64, false),
+        Field::new("z", DataType::Float64, false),
+    ]))
+}
+
+fn quaternion() -> DataType {
+    DataType::Struct(Fields::from(vec![
+        Field::new("x", DataType::Float64, false),
+        Field::new("y", DataType::Float64, false),
+        Field::new("z", DataType::Float64, false),
+        Field::new("w", DataType::Float64, false),
+    ]))
+}
+
+fn float32_list() -> DataType {
+    DataType::List(Arc::new(Field::new_list_field(DataType::Float32, false)))
+}
+
+/// Returns the list of well-known ontologies
+pub fn well_known() -> Vec<Ontology> {
+    vec![
+        Ontology::new(
+            IMU,
+            vec![
+                Field::new("acceleration", vector3d(), false),
+                Field::new("angular_velocity", vector3d(), false),
+                Field::new("orientation", quaternion(), true),
+            ],
+        ),
+        Ontology::new(
+            GNSS,
+            vec![
+                Field::new("latitude", DataType::Float64, false),
+                Field::new("longitude", DataType::Float64, false),
+                Field::new("altitude", DataType::Float64, true),
+                Field::new("velocity", vector3d(), true),
+            ],
+        ),
+        Ontology::new(
+            POSE,
+            vec![
+                Field::new("position", vector3d(), false),
+                Field::new("orientation", quaternion(), false),
+            ],
+        ),
+        Ontology::new(
+            POINT_CLOUD,
+            vec![
+                Field::new("x", float32_list(), false),
+                Field::new("y", float32_list(), false),
+                Field::new("z", float32_list(), false),
+                Field::new("intensity", float32_list(), true),
+            ],
+        ),
+        Ontology::new(
+            COMPRESSED_IMAGE,
+            vec![
+                Field::new("data", DataType::Binary, false),
+                Field::new("format", DataType::Utf8, false),
+            ],
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Float32Array, Float64Array, Int64Array};
+
+    #[test]
+    fn well_known_registry() {
+        let registry = registry();
+        for tag in [IMU, GNSS, POSE, POINT_CLOUD, COMPRESSED_IMAGE] {
+            let ontology = registry.get(tag).expect("missing well-known ontology");
+            assert!(crate::arrow::check_schema(ontology.schema()).is_ok());
+        }
+        assert!(registry.get("my_sensor").is_none());
+
+        let mut registry = Registry::with_well_known();
+        assert!(registry.register(Ontology::new(IMU, vec![])).is_err());
+    }
+
+    #[test]
+    fn check_schema() {
+        let gnss = registry().get(GNSS).unwrap();
+
+        // Nullable fields can be omitted and additional fields are allowed
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+                DataType::Int64,
+                false,
+            ),
+            Field::new("latitude", DataType::Float64, false),
+            Field::new("longitude", DataType::Float64, false),
+            Field::new("fix", DataType::Utf8, true),
+        ]));
+        assert!(gnss.check_schema(&schema).is_ok());
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+                DataType::Int64,
+                false,
+            ),
+            Field::new("latitude", DataType::Float64, false),
+        ]));
+        assert!(matches!(
+            gnss.check_schema(&schema),
+            Err(OntologyError::MissingField { .. })
+        ));
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+                DataType::Int64,
+                false,
+            ),
+            Field::new("latitude", DataType::Float32, false),
+            Field::new("longitude", DataType::Float64, false),
+        ]));
+        assert!(matches!(
+            gnss.check_schema(&schema),
+            Err(OntologyError::WrongFieldType { .. })
+        ));
+    }
+
+    #[test]
+    fn conform_batch() {
+        let gnss = registry().get(GNSS).unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+                DataType::Int64,
+                false,
+            ),
+            Field::new("latitude", DataType::Float32, false),
+            Field::new("longitude", DataType::Float64, false),
+            Field::new("fix", DataType::Float64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![10, 20])),
+                Arc::new(Float32Array::from(vec![45.0, 45.5])),
+                Arc::new(Float64Array::from(vec![9.0, 9.5])),
+                Arc::new(Float64Array::from(vec![1.0, 1.0])),
+            ],
+        )
+        .unwrap();
+
+        let conformed = gnss.conform(&batch).unwrap();
+
+        assert_eq!(conformed.schema(), *gnss.schema());
+        assert!(gnss.check_schema(&conformed.schema()).is_ok());
+        assert_eq!(conformed.num_rows(), 2);
+        assert_eq!(
+            conformed.column_by_name("altitude").unwrap().null_count(),
+            2
+        );
+    }
+}
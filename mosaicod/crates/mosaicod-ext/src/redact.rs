@@ -0,0 +1,413 @@
+//! Redaction of the values of record batches.
+//!
+//! Redacted values are replaced either by nulls or by a digest computed by the caller, see
+//! [`types::RedactionMode`]. Columns holding offloaded blobs (see [`crate::blob`]) are
+//! supported, the references of the redacted external blobs are returned so that the caller
+//! can delete the original objects.
+use crate::arrow::Error;
+use crate::blob::{self, BlobColumn, BlobValue};
+use arrow::array::{
+    Array, ArrayRef, AsArray, BinaryBuilder, BooleanArray, GenericBinaryArray,
+    GenericBinaryBuilder, GenericStringArray, GenericStringBuilder, Int64Array, OffsetSizeTrait,
+    RecordBatch, StringArray, StructArray,
+};
+use arrow::compute::kernels::{boolean, cmp};
+use arrow::datatypes::{DataType, Field};
+use arrow::error::ArrowError;
+use mosaicod_core::{params, types};
+use std::sync::Arc;
+
+/// A batch with redacted values.
+pub struct RedactedBatch {
+    pub batch: RecordBatch,
+    /// Number of redacted rows
+    pub row_count: usize,
+    /// References of the external blobs of the redacted rows, no longer referenced by the
+    /// batch
+    pub references: Vec<String>,
+}
+
+/// Checks that the values of `field` can be redacted with `mode`, returning the reason why
+/// they can't otherwise.
+pub fn check_redactable(field: &Field, mode: types::RedactionMode) -> Result<(), String> {
+    if field.name() == params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP {
+        return Err(format!("column `{}` can not be redacted", field.name()));
+    }
+
+    match mode {
+        types::RedactionMode::Null if !field.is_nullable() => {
+            Err(format!("column `{}` is not nullable", field.name()))
+        }
+        types::RedactionMode::Hash if !is_hashable(field.data_type()) => Err(format!(
+            "column `{}` can not be hashed, only string and binary columns are supported",
+            field.name()
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn is_hashable(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Binary | DataType::LargeBinary
+    ) || blob::is_blob(data_type)
+}
+
+/// Returns the mask of the rows of the batch whose timestamp is in `range`.
+pub fn rows_in_range(
+    batch: &RecordBatch,
+    range: &types::TimestampRange,
+) -> Result<BooleanArray, Error> {
+    let timestamps = batch
+        .column_by_name(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP)
+        .ok_or_else(|| ArrowError::SchemaError("missing timestamp column".to_owned()))?;
+
+    let start = Int64Array::new_scalar(range.start.as_i64());
+    let end = Int64Array::new_scalar(range.end.as_i64());
+
+    let mask = boolean::and(
+        &cmp::gt_eq(timestamps, &start)?,
+        &cmp::lt_eq(timestamps, &end)?,
+    )?;
+
+    Ok(mask)
+}
+
+/// Redacts the values of `columns` in the rows selected by `rows`.
+///
+/// In [`types::RedactionMode::Hash`] mode the values are replaced by the digest returned by
+/// `hash`, hex encoded for string columns. External blobs are loaded with `fetch` to be
+/// hashed. The columns need to be checked with [`check_redactable`] first.
+pub fn redact_batch<H, F>(
+    batch: &RecordBatch,
+    rows: &BooleanArray,
+    columns: &[String],
+    mode: types::RedactionMode,
+    mut hash: H,
+    mut fetch: F,
+) -> Result<RedactedBatch, Error>
+where
+    H: FnMut(&[u8]) -> Vec<u8>,
+    F: FnMut(&str) -> Result<Vec<u8>, Error>,
+{
+    let schema = batch.schema();
+    let mut arrays = batch.columns().to_vec();
+    let references = external_references(batch, rows, columns)?;
+
+    for column in columns {
+        let (index, _) = schema
+            .column_with_name(column)
+            .ok_or_else(|| ArrowError::SchemaError(format!("can't find column `{column}`")))?;
+        let array = batch.column(index);
+
+        arrays[index] = match mode {
+            types::RedactionMode::Null => arrow::compute::nullif(array, rows)?,
+            types::RedactionMode::Hash => hash_array(array, rows, &mut hash, &mut fetch)?,
+        };
+    }
+
+    Ok(RedactedBatch {
+        batch: RecordBatch::try_new(schema, arrays)?,
+        row_count: rows.true_count(),
+        references,
+    })
+}
+
+/// Returns the references of the external blobs of `columns` in the rows selected by `rows`.
+pub fn external_references(
+    batch: &RecordBatch,
+    rows: &BooleanArray,
+    columns: &[String],
+) -> Result<Vec<String>, Error> {
+    let mut references = Vec::new();
+
+    for column in columns {
+        let array = batch
+            .column_by_name(column)
+            .ok_or_else(|| ArrowError::SchemaError(format!("can't find column `{column}`")))?;
+
+        if blob::is_blob(array.data_type()) {
+            let blobs = BlobColumn::try_new(array)?;
+            references.extend((0..blobs.len()).filter_map(|row| match blobs.value(row) {
+                BlobValue::External(reference) if rows.value(row) => Some(reference.to_owned()),
+                _ => None,
+            }));
+        }
+    }
+
+    Ok(references)
+}
+
+fn hash_array<H, F>(
+    array: &ArrayRef,
+    rows: &BooleanArray,
+    hash: &mut H,
+    fetch: &mut F,
+) -> Result<ArrayRef, Error>
+where
+    H: FnMut(&[u8]) -> Vec<u8>,
+    F: FnMut(&str) -> Result<Vec<u8>, Error>,
+{
+    let array: ArrayRef = match array.data_type() {
+        DataType::Utf8 => Arc::new(hash_strings(array.as_string::<i32>(), rows, hash)),
+        DataType::LargeUtf8 => Arc::new(hash_strings(array.as_string::<i64>(), rows, hash)),
+        DataType::Binary => Arc::new(hash_binaries(array.as_binary::<i32>(), rows, hash)),
+        DataType::LargeBinary => Arc::new(hash_binaries(array.as_binary::<i64>(), rows, hash)),
+        data_type if blob::is_blob(data_type) => hash_blobs(array, rows, hash, fetch)?,
+        data_type => Err(ArrowError::InvalidArgumentError(format!(
+            "values of type {data_type} can not be hashed"
+        )))?,
+    };
+
+    Ok(array)
+}
+
+fn hash_strings<O: OffsetSizeTrait>(
+    array: &GenericStringArray<O>,
+    rows: &BooleanArray,
+    hash: &mut impl FnMut(&[u8]) -> Vec<u8>,
+) -> GenericStringArray<O> {
+    let mut builder = GenericStringBuilder::<O>::new();
+    for row in 0..array.len() {
+        if array.is_null(row) {
+            builder.append_null();
+        } else if rows.value(row) {
+            builder.append_value(hex(&hash(array.value(row).as_bytes())));
+        } else {
+            builder.append_value(array.value(row));
+        }
+    }
+    builder.finish()
+}
+
+fn hash_binaries<O: OffsetSizeTrait>(
+    array: &GenericBinaryArray<O>,
+    rows: &BooleanArray,
+    hash: &mut impl FnMut(&[u8]) -> Vec<u8>,
+) -> GenericBinaryArray<O> {
+    let mut builder = GenericBinaryBuilder::<O>::new();
+    for row in 0..array.len() {
+        if array.is_null(row) {
+            builder.append_null();
+        } else if rows.value(row) {
+            builder.append_value(hash(array.value(row)));
+        } else {
+            builder.append_value(array.value(row));
+        }
+    }
+    builder.finish()
+}
+
+/// Hashes the blobs of the redacted rows, the digests are stored inline.
+fn hash_blobs<H, F>(
+    array: &ArrayRef,
+    rows: &BooleanArray,
+    hash: &mut H,
+    fetch: &mut F,
+) -> Result<ArrayRef, Error>
+where
+    H: FnMut(&[u8]) -> Vec<u8>,
+    F: FnMut(&str) -> Result<Vec<u8>, Error>,
+{
+    let blobs = BlobColumn::try_new(array)?;
+
+    let mut inline = BinaryBuilder::new();
+    let mut references = Vec::with_capacity(blobs.len());
+
+    for row in 0..blobs.len() {
+        match blobs.value(row) {
+            BlobValue::Null => {
+                inline.append_null();
+                references.push(None);
+            }
+            value if rows.value(row) => {
+                let data = value.load(&mut *fetch)?.unwrap_or_default();
+                inline.append_value(hash(&data));
+                references.push(None);
+            }
+            BlobValue::Inline(data) => {
+                inline.append_value(data);
+                references.push(None);
+            }
+            BlobValue::External(reference) => {
+                inline.append_null();
+                references.push(Some(reference));
+            }
+        }
+    }
+
+    let struct_array = array.as_struct();
+    let array = StructArray::try_new(
+        struct_array.fields().clone(),
+        vec![
+            Arc::new(inline.finish()),
+            Arc::new(StringArray::from(references)),
+        ],
+        struct_array.nulls().cloned(),
+    )?;
+
+    Ok(Arc::new(array))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::BinaryArray;
+    use arrow::datatypes::Schema;
+
+    fn people_batch() -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("face", DataType::Binary, true),
+            Field::new("score", DataType::Int64, false),
+        ]));
+
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![10, 20, 30, 40])),
+                Arc::new(StringArray::from(vec![
+                    Some("ann"),
+                    None,
+                    Some("bob"),
+                    Some("eve"),
+                ])),
+                Arc::new(BinaryArray::from(vec![
+                    Some(&[1u8; 10][..]),
+                    Some(&[2u8; 100][..]),
+                    Some(&[3u8; 100][..]),
+                    None,
+                ])),
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn no_fetch(reference: &str) -> Result<Vec<u8>, Error> {
+        panic!("unexpected fetch of `{reference}`")
+    }
+
+    #[test]
+    fn redact_with_nulls() {
+        let batch = people_batch();
+        let range = types::TimestampRange::between(20.into(), 30.into());
+        let rows = rows_in_range(&batch, &range).unwrap();
+        assert_eq!(rows, BooleanArray::from(vec![false, true, true, false]));
+
+        let columns = vec!["name".to_owned(), "face".to_owned()];
+        let redacted = redact_batch(
+            &batch,
+            &rows,
+            &columns,
+            types::RedactionMode::Null,
+            |_| unreachable!(),
+            no_fetch,
+        )
+        .unwrap();
+
+        assert_eq!(redacted.row_count, 2);
+        assert!(redacted.references.is_empty());
+
+        let names = redacted.batch.column(1).as_string::<i32>();
+        assert_eq!(names.value(0), "ann");
+        assert!(names.is_null(2));
+        let faces = redacted.batch.column(2).as_binary::<i32>();
+        assert!(faces.is_valid(0));
+        assert!(faces.is_null(1) && faces.is_null(2));
+
+        // Other columns are untouched
+        assert_eq!(redacted.batch.column(3), batch.column(3));
+    }
+
+    #[test]
+    fn redact_with_hashes() {
+        let batch = people_batch();
+        let rows = BooleanArray::from(vec![true, true, false, true]);
+        let columns = vec!["name".to_owned(), "face".to_owned()];
+
+        let redacted = redact_batch(
+            &batch,
+            &rows,
+            &columns,
+            types::RedactionMode::Hash,
+            |value| vec![value.len() as u8],
+            no_fetch,
+        )
+        .unwrap();
+
+        let names = redacted.batch.column(1).as_string::<i32>();
+        assert_eq!(names.value(0), "03");
+        assert!(names.is_null(1));
+        assert_eq!(names.value(2), "bob");
+        assert_eq!(names.value(3), "03");
+
+        let faces = redacted.batch.column(2).as_binary::<i32>();
+        assert_eq!(faces.value(0), [10]);
+        assert_eq!(faces.value(1), [100]);
+        assert_eq!(faces.value(2), [3u8; 100]);
+        assert!(faces.is_null(3));
+    }
+
+    #[test]
+    fn redact_offloaded_blobs() {
+        let batch = people_batch();
+        let (batch, blobs) =
+            blob::offload_column(&batch, "face", 50, |row, _| format!("blob_{row}")).unwrap();
+        assert_eq!(blobs.len(), 2);
+
+        let rows = BooleanArray::from(vec![false, false, true, false]);
+        let columns = vec!["face".to_owned()];
+
+        let redacted = redact_batch(
+            &batch,
+            &rows,
+            &columns,
+            types::RedactionMode::Null,
+            |_| unreachable!(),
+            no_fetch,
+        )
+        .unwrap();
+        assert_eq!(redacted.references, ["blob_2"]);
+
+        let redacted = redact_batch(
+            &batch,
+            &rows,
+            &columns,
+            types::RedactionMode::Hash,
+            |value| vec![value[0]],
+            |reference| {
+                assert_eq!(reference, "blob_2");
+                Ok(vec![3; 100])
+            },
+        )
+        .unwrap();
+        assert_eq!(redacted.references, ["blob_2"]);
+
+        let face = redacted.batch.column(2);
+        let face = BlobColumn::try_new(face).unwrap();
+        assert_eq!(face.value(1), BlobValue::External("blob_1"));
+        assert_eq!(face.value(2), BlobValue::Inline(&[3]));
+    }
+
+    #[test]
+    fn check_columns() {
+        let batch = people_batch();
+        let schema = batch.schema();
+
+        let check = |column: &str, mode| {
+            check_redactable(schema.field_with_name(column).unwrap(), mode).is_ok()
+        };
+
+        assert!(check("name", types::RedactionMode::Null));
+        assert!(check("name", types::RedactionMode::Hash));
+        assert!(!check("score", types::RedactionMode::Null));
+        assert!(!check("score", types::RedactionMode::Hash));
+        assert!(!check("timestamp_ns", types::RedactionMode::Null));
+    }
+}
@@ -1,10 +1,13 @@
 use std::fs;
 
-use tonic::transport::{Identity, ServerTlsConfig};
+use tonic::transport::{Certificate, Identity, ServerTlsConfig};
 
+/// Loads the TLS configuration of the server. If `tls_client_ca_file` is set, clients are
+/// required to present a certificate signed by that authority.
 pub fn load_tls_config(
     tls_certificate_file: impl AsRef<std::path::Path>,
     tls_private_key_file: impl AsRef<std::path::Path>,
+    tls_client_ca_file: Option<impl AsRef<std::path::Path>>,
 ) -> Result<ServerTlsConfig, String> {
     let cert = fs::read_to_string(tls_certificate_file)
         .map_err(|e| format!("Unable to read certificate. error: {}", e))?;
@@ -12,5 +15,13 @@ pub fn load_tls_config(
         .map_err(|e| format!("Unable to read private key. error: {}", e))?;
 
     let identity = Identity::from_pem(cert, key);
-    Ok(ServerTlsConfig::new().identity(identity))
+    let mut config = ServerTlsConfig::new().identity(identity);
+
+    if let Some(tls_client_ca_file) = tls_client_ca_file {
+        let ca = fs::read_to_string(tls_client_ca_file)
+            .map_err(|e| format!("Unable to read client CA certificate. error: {}", e))?;
+        config = config.client_ca_root(Certificate::from_pem(ca));
+    }
+
+    Ok(config)
 }
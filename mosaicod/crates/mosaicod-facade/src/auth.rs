@@ -27,22 +27,47 @@ impl From<Handle> for types::ApiKey {
 }
 
 /// Creates a new API key in the system
+///
+/// Queries performed with the key that don't filter sequences search `default_sequence`, if
+/// provided.
 pub async fn create(
     context: &Context,
     permissions: types::auth::Permission,
     description: String,
     expires_at: Option<types::Timestamp>,
+    default_sequence: Option<types::SequenceLocator>,
 ) -> Result<Handle> {
-    let api_key = types::ApiKey::new(permissions, description, expires_at);
+    let api_key = types::ApiKey::new(permissions, description, expires_at)
+        .with_default_sequence(default_sequence);
     let mut cx = context.db.connection();
     let api_key = db::api_key_create(&mut cx, api_key).await?;
     Ok(Handle { api_key })
 }
 
-/// Returns a list of all API keys in the system
-pub async fn all_keys(context: &Context) -> Result<Vec<types::ApiKey>> {
+/// Page of API keys returned by [`key_page`].
+pub struct KeyPage {
+    pub keys: Vec<types::ApiKey>,
+    /// Cursor used to fetch the next page, `None` once all the keys are returned.
+    pub cursor: Option<String>,
+}
+
+/// Returns at most `limit` API keys of the system, sorted by fingerprint.
+///
+/// The page starts after `cursor`, the value returned along with the previous page.
+pub async fn key_page(context: &Context, cursor: Option<&str>, limit: usize) -> Result<KeyPage> {
     let mut cx = context.db.connection();
-    Ok(db::api_key_find_all(&mut cx).await?)
+
+    // One extra record is fetched to know if other pages follow
+    let mut keys = db::api_key_find_page(&mut cx, cursor, limit.saturating_add(1) as i64).await?;
+
+    let cursor = if keys.len() > limit {
+        keys.truncate(limit);
+        keys.last().map(|key| key.token().fingerprint().to_owned())
+    } else {
+        None
+    };
+
+    Ok(KeyPage { keys, cursor })
 }
 
 /// Deletes the current API key
@@ -61,10 +86,10 @@ mod tests {
 
     fn test_context(pool: sqlx::Pool<db::DatabaseType>) -> Context {
         let database = db::testing::Database::new(pool);
-        let store = store::testing::Store::new_random_on_tmp().unwrap();
-        let ts_gw = Arc::new(query::TimeseriesEngine::try_new((*store).clone(), 0).unwrap());
+        let store = Arc::new(store::Store::in_memory());
+        let ts_gw = Arc::new(query::TimeseriesEngine::try_new(store.clone(), 0).unwrap());
 
-        Context::new((*store).clone(), (*database).clone(), ts_gw)
+        Context::new(store, (*database).clone(), ts_gw)
     }
 
     #[sqlx::test(migrator = "db::testing::MIGRATOR")]
@@ -76,6 +101,7 @@ mod tests {
             types::auth::Permission::Read,
             "some text".to_owned(),
             None,
+            Some("my_sequence".parse().unwrap()),
         )
         .await
         .unwrap();
@@ -91,6 +117,7 @@ mod tests {
 
             assert_eq!(res_key.permission, key.permission);
             assert_eq!(res_key.token(), key.token());
+            assert_eq!(res_key.default_sequence, key.default_sequence);
         }
 
         delete(&context, handle).await.unwrap();
@@ -106,4 +133,44 @@ mod tests {
 
         Ok(())
     }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn auth_key_pages(pool: sqlx::Pool<db::DatabaseType>) -> sqlx::Result<()> {
+        let context = test_context(pool);
+
+        let mut fingerprints = Vec::new();
+        for i in 0..5 {
+            let handle = create(
+                &context,
+                types::auth::Permission::Read,
+                format!("key {i}"),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+            fingerprints.push(handle.api_key().token().fingerprint().to_owned());
+        }
+        fingerprints.sort();
+
+        let mut listed = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = key_page(&context, cursor.as_deref(), 2).await.unwrap();
+            assert!(page.keys.len() <= 2);
+            listed.extend(
+                page.keys
+                    .iter()
+                    .map(|key| key.token().fingerprint().to_owned()),
+            );
+
+            cursor = page.cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(listed, fingerprints);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,341 @@
+//! Catalog manifest published in the store.
+//!
+//! The manifest lists the sequences with their published topics, the schema of each topic
+//! and its data files, so that external engines (e.g. a DuckDB extension) can attach to the
+//! store read-only without talking to the server. It is written as JSON at
+//! [`MANIFEST_PATH`], relative to the root of the store, and rewritten by [`publish`] only
+//! when its content changes, so readers can cache it by its last modification time.
+//!
+//! Topics of sessions not finalized yet and topics without data are not listed. The data
+//! files of upsert topics hold the rows replaced by later uploads: they are listed in chunk
+//! order, the current version of a row being in the last data file holding its key. Data
+//! files shared with a copy of the topic or archived by the tiering are listed at the path of
+//! the object they link to.
+use super::{Context, sequence, topic};
+use log::trace;
+use mosaicod_core::{error::PublicResult as Result, types};
+use mosaicod_db as db;
+use mosaicod_marshal as marshal;
+
+/// Location of the catalog manifest, relative to the root of the store.
+pub const MANIFEST_PATH: &str = "catalog.json";
+
+/// Number of sequences read from the database at once.
+const PAGE_SIZE: i64 = 256;
+
+type CatalogManifest = types::CatalogManifest<marshal::JsonMetadataBlob>;
+
+/// Builds the catalog manifest from the current content of the platform.
+pub async fn manifest(context: &Context) -> Result<CatalogManifest> {
+    let mut cx = context.db.connection();
+
+    // Sequences are read one page at a time, sorted by locator
+    let mut sequences = Vec::new();
+    let mut after = None;
+    loop {
+        let records = db::sequence_find_page(&mut cx, "", after.as_deref(), PAGE_SIZE).await?;
+        let Some(last) = records.last() else {
+            break;
+        };
+        after = Some(last.locator().to_string());
+
+        for record in records {
+            sequences.push(catalog_sequence(context, &mut cx, record).await?);
+        }
+    }
+
+    Ok(types::CatalogManifest { sequences })
+}
+
+/// Describes a sequence in the catalog, along with its published topics.
+async fn catalog_sequence(
+    context: &Context,
+    cx: &mut impl db::AsExec,
+    record: db::SequenceRecord,
+) -> Result<types::CatalogSequence<marshal::JsonMetadataBlob>> {
+    let locator = record.locator();
+
+    let topic_records = sequence::topic_records(cx, record.sequence_id).await?;
+
+    let mut topics = Vec::with_capacity(topic_records.len());
+    for topic_record in topic_records {
+        let handle = topic::Handle::new(
+            topic_record.locator(),
+            topic_record.topic_id,
+            topic_record.uuid(),
+            topic_record.path_in_store(),
+        );
+        if let Some(topic) = catalog_topic(context, handle).await? {
+            topics.push(topic);
+        }
+    }
+
+    Ok(types::CatalogSequence {
+        locator,
+        created_at: record.creation_timestamp(),
+        path_in_store: record.path_in_store(),
+        user_metadata: record.user_metadata(),
+        topics,
+    })
+}
+
+/// Describes a topic in the catalog, `None` if the topic has no published data.
+async fn catalog_topic(
+    context: &Context,
+    handle: topic::Handle,
+) -> Result<Option<types::CatalogTopic<marshal::JsonMetadataBlob>>> {
+    let Some(path_in_store) = handle.path_in_store().cloned() else {
+        return Ok(None);
+    };
+
+    if !topic::is_published(context, &handle).await? {
+        return Ok(None);
+    }
+
+    let metadata = topic::metadata(context, &handle).await?;
+    let format = metadata.ontology_metadata.properties.serialization_format;
+
+    // Link objects are not readable by the engines, linked data files are listed at the
+    // path of their target
+    let mut data_files = Vec::new();
+    for (_, file) in topic::data_files(context, &path_in_store, format).await? {
+        match context.store.link_target(&file).await? {
+            Some(target) => data_files.push(target.to_string_lossy().into_owned()),
+            None => data_files.push(file),
+        }
+    }
+    if data_files.is_empty() {
+        return Ok(None);
+    }
+
+    let schema = match topic::registered_schema(context, &handle).await? {
+        Some(schema) => schema,
+        None => topic::arrow_schema(context, &handle, format).await?,
+    };
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| types::FieldDescription {
+            name: field.name().clone(),
+            data_type: field.data_type().to_string(),
+            nullable: field.is_nullable(),
+        })
+        .collect();
+
+    Ok(Some(types::CatalogTopic {
+        locator: handle.locator().clone(),
+        path_in_store,
+        metadata,
+        fields,
+        data_files,
+    }))
+}
+
+/// Writes the catalog manifest to the store, returning whether it changed since the last
+/// publication.
+pub async fn publish(context: &Context) -> Result<bool> {
+    let manifest = manifest(context).await?;
+    let bytes: Vec<u8> = marshal::JsonCatalogManifest::from(manifest).try_into()?;
+
+    if context.store.exists(MANIFEST_PATH).await?
+        && context.store.read_bytes(MANIFEST_PATH).await? == bytes
+    {
+        trace!("catalog manifest is up to date");
+        return Ok(false);
+    }
+
+    trace!("writing catalog manifest `{}` to store", MANIFEST_PATH);
+    context.store.write_bytes(MANIFEST_PATH, bytes).await?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sequence, session};
+    use arrow::array::{Int64Array, RecordBatch};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use mosaicod_core::params;
+    use mosaicod_query as query;
+    use mosaicod_store as store;
+    use std::sync::Arc;
+
+    fn test_context(pool: sqlx::Pool<db::DatabaseType>) -> Context {
+        let database = db::testing::Database::new(pool);
+        let store = Arc::new(store::Store::in_memory());
+        let ts_gw = Arc::new(query::TimeseriesEngine::try_new(store.clone(), 0).unwrap());
+
+        Context::new(store, (*database).clone(), ts_gw)
+    }
+
+    fn ontology_metadata() -> types::TopicOntologyMetadata<marshal::JsonMetadataBlob> {
+        types::TopicOntologyMetadata::new(
+            types::TopicOntologyProperties {
+                ontology_tag: "dummy".to_owned(),
+                serialization_format: types::Format::Default,
+                sort_key: None,
+                dedup_policy: types::DedupPolicy::None,
+                primary_key: None,
+            },
+            None,
+        )
+    }
+
+    /// Uploads a topic in a new session, finalized if `finalize` is set.
+    async fn upload_topic(context: &Context, locator: &str, finalize: bool) {
+        let locator: types::TopicLocator = locator.parse().unwrap();
+        let session = session::try_create(context, locator.sequence.clone(), None)
+            .await
+            .unwrap();
+        let handle = topic::try_create(context, locator.clone(), &session, ontology_metadata())
+            .await
+            .unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+                DataType::Int64,
+                false,
+            ),
+            Field::new("value", DataType::Int64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3])),
+                Arc::new(Int64Array::from(vec![10, 20, 30])),
+            ],
+        )
+        .unwrap();
+
+        let mut writer = topic::writer(context.clone(), handle, schema)
+            .await
+            .unwrap();
+        let chunk = writer.write(batch).await.unwrap();
+        writer.record_chunk(chunk).await.unwrap();
+        writer.finalize().await.unwrap();
+
+        if finalize {
+            session::finalize(context, &session).await.unwrap();
+        }
+    }
+
+    async fn read_manifest(context: &Context) -> marshal::JsonCatalogManifest {
+        context
+            .store
+            .read_bytes(MANIFEST_PATH)
+            .await
+            .unwrap()
+            .try_into()
+            .unwrap()
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn catalog_manifest(pool: sqlx::Pool<db::DatabaseType>) {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        for name in ["run_b", "run_a"] {
+            let locator = name.parse::<types::SequenceLocator>().unwrap();
+            sequence::try_create(&context, locator, None).await.unwrap();
+        }
+        assert!(publish(&context).await.unwrap());
+
+        let manifest = read_manifest(&context).await;
+        let sequences: Vec<_> = manifest.sequences.iter().map(|s| &s.locator).collect();
+        assert_eq!(sequences, ["run_a", "run_b"]);
+        assert!(manifest.sequences.iter().all(|s| s.topics.is_empty()));
+
+        // The topics of the sessions not finalized are not listed
+        upload_topic(&context, "run_a/imu", true).await;
+        upload_topic(&context, "run_a/gps", false).await;
+        assert!(publish(&context).await.unwrap());
+        assert!(!publish(&context).await.unwrap());
+
+        let manifest = read_manifest(&context).await;
+        let topics = &manifest.sequences[0].topics;
+        assert_eq!(topics.len(), 1);
+
+        let topic = &topics[0];
+        assert_eq!(topic.locator, "run_a/imu");
+        let fields: Vec<_> = topic.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            fields,
+            [params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP, "value"]
+        );
+        assert_eq!(topic.data_files.len(), 1);
+        for file in &topic.data_files {
+            assert!(file.starts_with(&topic.path_in_store));
+            assert!(context.store.exists(file).await.unwrap());
+        }
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn catalog_manifest_links(pool: sqlx::Pool<db::DatabaseType>) {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        for name in ["run_a", "run_b"] {
+            let locator = name.parse::<types::SequenceLocator>().unwrap();
+            sequence::try_create(&context, locator, None).await.unwrap();
+        }
+        upload_topic(&context, "run_a/imu", true).await;
+
+        let source = topic::Handle::try_from_locator(&context, "run_a/imu".parse().unwrap())
+            .await
+            .unwrap();
+        topic::copy(&context, &source, "run_b".parse().unwrap(), None)
+            .await
+            .unwrap();
+        assert!(publish(&context).await.unwrap());
+
+        // The data files shared by the copy are listed at the path of the shared object
+        let manifest = read_manifest(&context).await;
+        let files: Vec<_> = manifest
+            .sequences
+            .iter()
+            .flat_map(|sequence| &sequence.topics)
+            .flat_map(|topic| &topic.data_files)
+            .collect();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0], files[1]);
+        assert!(files[0].starts_with(store::SHARED_FOLDER));
+        assert!(context.store.exists(files[0]).await.unwrap());
+        assert_eq!(context.store.link_target(files[0]).await.unwrap(), None);
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn catalog_manifest_mirror(pool: sqlx::Pool<db::DatabaseType>) {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        assert!(crate::mirror::manifest(&context).await.unwrap().is_none());
+
+        let locator = "run_a".parse::<types::SequenceLocator>().unwrap();
+        sequence::try_create(&context, locator.clone(), None)
+            .await
+            .unwrap();
+        upload_topic(&context, "run_a/imu", true).await;
+        assert!(publish(&context).await.unwrap());
+
+        let manifest = crate::mirror::manifest(&context).await.unwrap().unwrap();
+        let sequence = crate::mirror::sequence(manifest, &locator).unwrap();
+        assert_eq!(sequence.topics.len(), 1);
+
+        let manifest = crate::mirror::manifest(&context).await.unwrap().unwrap();
+        let missing = "run_a/gps".parse::<types::TopicLocator>().unwrap();
+        assert!(crate::mirror::topic(manifest, &missing).is_err());
+
+        let manifest = crate::mirror::manifest(&context).await.unwrap().unwrap();
+        let topic_locator = "run_a/imu".parse::<types::TopicLocator>().unwrap();
+        let topic = crate::mirror::topic(manifest, &topic_locator).unwrap();
+        let rows = crate::mirror::read(&context, &topic, 1024)
+            .await
+            .unwrap()
+            .count()
+            .await
+            .unwrap();
+        assert_eq!(rows, 3);
+    }
+}
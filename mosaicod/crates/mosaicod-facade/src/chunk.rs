@@ -13,6 +13,8 @@ impl<'a> Chunk<'a> {
         datafile: impl AsRef<std::path::Path>,
         size_bytes: i64,
         row_count: i64,
+        schema_version: Option<u32>,
+        timestamp_range: Option<types::TimestampRange>,
         context: &'a Context,
     ) -> Result<Self> {
         let topic_id = topic::Handle::try_from_uuid(context, topic_uuid)
@@ -23,13 +25,24 @@ impl<'a> Chunk<'a> {
 
         let chunk = db::chunk_create(
             &mut tx,
-            &db::ChunkRecord::new(topic_id, datafile, size_bytes, row_count),
+            &db::ChunkRecord::new(topic_id, datafile, size_bytes, row_count)
+                .with_schema_version(schema_version)
+                .with_timestamp_range(timestamp_range),
         )
         .await?;
 
         Ok(Self { tx, chunk })
     }
 
+    /// Opens an existing chunk to replace its column statistics, the current statistics
+    /// are deleted.
+    pub async fn reopen(chunk: db::ChunkRecord, context: &'a Context) -> Result<Self> {
+        let mut tx = context.db.transaction().await?;
+        db::column_chunk_delete_by_chunk(&mut tx, chunk.chunk_id).await?;
+
+        Ok(Self { tx, chunk })
+    }
+
     /// Push all column statistics using batch inserts for better performance.
     /// This method collects all stats, resolves column IDs, then performs
     /// two batch INSERT operations (one for numeric, one for textual stats).
@@ -80,6 +93,13 @@ impl<'a> Chunk<'a> {
         Ok(())
     }
 
+    /// Records that the data file of the chunk is a link to the `shared` object, which is
+    /// kept in the store as long as the chunk exists.
+    pub async fn link_shared_object(&mut self, shared: &db::SharedObjectRecord) -> Result<()> {
+        db::chunk_shared_object_create(&mut self.tx, self.chunk.chunk_id, shared.id()).await?;
+        Ok(())
+    }
+
     pub async fn finalize(self) -> Result<()> {
         self.tx.commit().await?;
         Ok(())
@@ -0,0 +1,184 @@
+//! Offsets committed by named downstream consumers.
+//!
+//! A consumer processing the activity feed of a sequence commits the cursor of the last
+//! page it processed, a consumer processing the data of a topic commits the watermark up to
+//! which the data was processed. After a restart the consumer resumes from the committed
+//! offset.
+//!
+//! Offsets only move forward: committing again the current offset has no effect, so that
+//! retried commits are harmless, while committing an earlier offset fails, so that a stale
+//! instance of the consumer can not rewind it. An offset is reset by deleting it.
+use super::{Context, sequence, topic};
+use log::trace;
+use mosaicod_core::{self as core, error::PublicResult as Result, types};
+use mosaicod_db as db;
+
+/// Maximum length of a consumer name.
+const MAX_CONSUMER_NAME_LEN: usize = 128;
+
+fn validate_name(consumer: &str) -> Result<()> {
+    let valid = !consumer.is_empty()
+        && consumer.len() <= MAX_CONSUMER_NAME_LEN
+        && consumer
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+
+    if !valid {
+        Err(core::Error::bad_request(format!(
+            "bad consumer name `{consumer}`, names must have at most {MAX_CONSUMER_NAME_LEN} \
+             alphanumeric characters, `_`, `-` or `.`"
+        )))?;
+    }
+
+    Ok(())
+}
+
+/// Commits the position of `consumer` in the activity feed of the sequence.
+pub async fn commit_activity(
+    context: &Context,
+    sequence: &sequence::Handle,
+    consumer: &str,
+    cursor: types::ActivityCursor,
+) -> Result<types::ConsumerOffset> {
+    validate_name(consumer)?;
+    trace!(
+        "committing cursor {} of consumer `{}` on `{}`",
+        cursor,
+        consumer,
+        sequence.locator()
+    );
+
+    let mut tx = context.db.transaction().await?;
+
+    let record = db::SequenceConsumerOffsetRecord::new(
+        consumer.to_owned(),
+        sequence.id(),
+        cursor,
+        types::Timestamp::now(),
+    );
+    let committed = match db::sequence_consumer_offset_try_advance(&mut tx, &record).await? {
+        Some(committed) => committed,
+        None => db::sequence_consumer_offset_find(&mut tx, consumer, sequence.id())
+            .await?
+            .filter(|current| current.cursor() == cursor)
+            .ok_or_else(|| {
+                core::Error::consumer_offset_behind(
+                    consumer.to_owned(),
+                    sequence.locator().to_string(),
+                )
+            })?,
+    };
+
+    tx.commit().await?;
+
+    Ok(committed.into())
+}
+
+/// Commits the watermark of the topic up to which `consumer` processed the data.
+///
+/// The watermark can not be past the current watermark of the topic, since the data
+/// following it is not published yet.
+pub async fn commit_watermark(
+    context: &Context,
+    topic: &topic::Handle,
+    consumer: &str,
+    watermark: types::Timestamp,
+) -> Result<types::ConsumerOffset> {
+    validate_name(consumer)?;
+    trace!(
+        "committing watermark {} of consumer `{}` on `{}`",
+        watermark.as_i64(),
+        consumer,
+        topic.locator()
+    );
+
+    let mut tx = context.db.transaction().await?;
+
+    let current = db::topic_watermark_find_latest(&mut tx, topic.id()).await?;
+    if current.is_none_or(|current| current.watermark() < watermark) {
+        Err(core::Error::bad_request(format!(
+            "watermark {} is past the watermark of topic `{}`",
+            watermark.as_i64(),
+            topic.locator()
+        )))?;
+    }
+
+    let record = db::TopicConsumerOffsetRecord::new(
+        consumer.to_owned(),
+        topic.id(),
+        watermark,
+        types::Timestamp::now(),
+    );
+    let committed = match db::topic_consumer_offset_try_advance(&mut tx, &record).await? {
+        Some(committed) => committed,
+        None => db::topic_consumer_offset_find(&mut tx, consumer, topic.id())
+            .await?
+            .filter(|current| current.watermark() == watermark)
+            .ok_or_else(|| {
+                core::Error::consumer_offset_behind(
+                    consumer.to_owned(),
+                    topic.locator().to_string(),
+                )
+            })?,
+    };
+
+    tx.commit().await?;
+
+    Ok(committed.into())
+}
+
+/// Returns the position committed by `consumer` in the activity feed of the sequence,
+/// `None` if the consumer never committed one.
+pub async fn sequence_offset(
+    context: &Context,
+    sequence: &sequence::Handle,
+    consumer: &str,
+) -> Result<Option<types::ConsumerOffset>> {
+    let mut cx = context.db.connection();
+    let record = db::sequence_consumer_offset_find(&mut cx, consumer, sequence.id()).await?;
+    Ok(record.map(Into::into))
+}
+
+/// Returns the watermark of the topic committed by `consumer`, `None` if the consumer never
+/// committed one.
+pub async fn topic_offset(
+    context: &Context,
+    topic: &topic::Handle,
+    consumer: &str,
+) -> Result<Option<types::ConsumerOffset>> {
+    let mut cx = context.db.connection();
+    let record = db::topic_consumer_offset_find(&mut cx, consumer, topic.id()).await?;
+    Ok(record.map(Into::into))
+}
+
+/// Deletes the position committed by `consumer` in the activity feed of the sequence.
+pub async fn delete_sequence_offset(
+    context: &Context,
+    sequence: &sequence::Handle,
+    consumer: &str,
+) -> Result<()> {
+    let mut cx = context.db.connection();
+    if !db::sequence_consumer_offset_delete(&mut cx, consumer, sequence.id()).await? {
+        Err(core::Error::not_found(format!(
+            "offset of consumer `{consumer}` on `{}`",
+            sequence.locator()
+        )))?;
+    }
+    Ok(())
+}
+
+/// Deletes the watermark of the topic committed by `consumer`.
+pub async fn delete_topic_offset(
+    context: &Context,
+    topic: &topic::Handle,
+    consumer: &str,
+) -> Result<()> {
+    let mut cx = context.db.connection();
+    if !db::topic_consumer_offset_delete(&mut cx, consumer, topic.id()).await? {
+        Err(core::Error::not_found(format!(
+            "offset of consumer `{consumer}` on `{}`",
+            topic.locator()
+        )))?;
+    }
+    Ok(())
+}
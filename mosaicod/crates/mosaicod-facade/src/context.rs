@@ -1,16 +1,47 @@
+use crate::{
+    AttestationSigner, ExternalTables, NotificationBus, NotificationDispatcher, QueryAdmission,
+    QueryCache, QueryLimitPolicy, TableExporter, TransferLimits,
+};
+use mosaicod_core::types;
 use mosaicod_db as db;
 use mosaicod_query as query;
 use mosaicod_store as store;
+use std::sync::Arc;
 
 /// Shared context for all facade functions.
 ///
 /// Contains references to the store, database, and timeseries engine
 /// that facade functions require to perform their operations.
+///
+/// Query results are not cached unless a cache is provided with
+/// [`Context::with_query_cache`], and queries are not limited unless a policy is provided
+/// with [`Context::with_query_limits`] or admitted by a shared control provided with
+/// [`Context::with_query_admission`]. External tables are disabled unless a registry is
+/// provided with [`Context::with_external_tables`]. Notifications are not delivered to
+/// external sinks unless a dispatcher is provided with
+/// [`Context::with_notification_dispatcher`], and are streamed only to the subscribers of
+/// the bus provided with [`Context::with_notification_bus`]. New resources are stored
+/// according to the flat layout unless another one is provided with
+/// [`Context::with_store_layout`].
+/// Finalized sessions are not attested unless a signer is provided with
+/// [`Context::with_attestation_signer`]. Background transfers are not throttled unless
+/// limits are provided with [`Context::with_transfer_limits`]. Topics are not exported as
+/// tables unless an exporter is provided with [`Context::with_table_exporter`].
 #[derive(Clone)]
 pub struct Context {
     pub store: store::StoreRef,
     pub db: db::Database,
     pub timeseries_querier: query::TimeseriesEngineRef,
+    pub query_cache: Arc<QueryCache>,
+    pub query_limits: Arc<QueryLimitPolicy>,
+    pub query_admission: Arc<QueryAdmission>,
+    pub external_tables: Arc<ExternalTables>,
+    pub notification_dispatcher: Arc<NotificationDispatcher>,
+    pub notification_bus: Arc<NotificationBus>,
+    pub store_layout: types::StoreLayout,
+    pub attestation_signer: Arc<AttestationSigner>,
+    pub transfer_limits: Arc<TransferLimits>,
+    pub table_exporter: Arc<TableExporter>,
 }
 
 impl Context {
@@ -23,6 +54,81 @@ impl Context {
             store,
             db,
             timeseries_querier: ts_gw,
+            query_cache: Arc::new(QueryCache::disabled()),
+            query_limits: Arc::new(QueryLimitPolicy::unlimited()),
+            query_admission: Arc::new(QueryAdmission::unlimited()),
+            external_tables: Arc::new(ExternalTables::disabled()),
+            notification_dispatcher: Arc::new(NotificationDispatcher::disabled()),
+            notification_bus: Arc::new(NotificationBus::default()),
+            store_layout: types::StoreLayout::default(),
+            attestation_signer: Arc::new(AttestationSigner::disabled()),
+            transfer_limits: Arc::new(TransferLimits::unlimited()),
+            table_exporter: Arc::new(TableExporter::disabled()),
         }
     }
+
+    /// Shares the query results cached by `query_cache` with the context.
+    pub fn with_query_cache(mut self, query_cache: Arc<QueryCache>) -> Self {
+        self.query_cache = query_cache;
+        self
+    }
+
+    /// Limits the resources used by queries according to `query_limits`.
+    pub fn with_query_limits(mut self, query_limits: Arc<QueryLimitPolicy>) -> Self {
+        self.query_limits = query_limits;
+        self
+    }
+
+    /// Shares the admission control of the queries `query_admission` with the context.
+    pub fn with_query_admission(mut self, query_admission: Arc<QueryAdmission>) -> Self {
+        self.query_admission = query_admission;
+        self
+    }
+
+    /// Shares the external tables registered in `external_tables` with the context.
+    pub fn with_external_tables(mut self, external_tables: Arc<ExternalTables>) -> Self {
+        self.external_tables = external_tables;
+        self
+    }
+
+    /// Delivers the notifications created in the context with `notification_dispatcher`.
+    pub fn with_notification_dispatcher(
+        mut self,
+        notification_dispatcher: Arc<NotificationDispatcher>,
+    ) -> Self {
+        self.notification_dispatcher = notification_dispatcher;
+        self
+    }
+
+    /// Publishes the notifications created in the context on `notification_bus`.
+    pub fn with_notification_bus(mut self, notification_bus: Arc<NotificationBus>) -> Self {
+        self.notification_bus = notification_bus;
+        self
+    }
+
+    /// Stores the resources created in the context according to `store_layout`.
+    pub fn with_store_layout(mut self, store_layout: types::StoreLayout) -> Self {
+        self.store_layout = store_layout;
+        self
+    }
+
+    /// Signs the attestations of the sessions finalized in the context with
+    /// `attestation_signer`.
+    pub fn with_attestation_signer(mut self, attestation_signer: Arc<AttestationSigner>) -> Self {
+        self.attestation_signer = attestation_signer;
+        self
+    }
+
+    /// Throttles the background transfers performed in the context according to
+    /// `transfer_limits`.
+    pub fn with_transfer_limits(mut self, transfer_limits: Arc<TransferLimits>) -> Self {
+        self.transfer_limits = transfer_limits;
+        self
+    }
+
+    /// Exports the topics finalized or redacted in the context with `table_exporter`.
+    pub fn with_table_exporter(mut self, table_exporter: Arc<TableExporter>) -> Self {
+        self.table_exporter = table_exporter;
+        self
+    }
 }
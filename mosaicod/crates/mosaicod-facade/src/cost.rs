@@ -0,0 +1,121 @@
+//! Estimation of the storage and egress costs of the sequences.
+//!
+//! The storage cost is the monthly price of the data files, in the standard or in the
+//! archive storage (see [`super::tiering`]). The egress cost is projected over a month from
+//! the reads sampled by the store (see [`store::AccessLog`]) since the creation of each
+//! sequence, so it only accounts for the reads served by the store backend and is a rough
+//! estimate for recently created sequences or when the access statistics are disabled.
+//!
+//! Costs are estimated for a scope, a prefix of the sequence locators: sequences sharing a
+//! prefix (e.g. `lab_a_`) are accounted together as a project.
+use super::{Context, tiering};
+use mosaicod_core::{error::PublicResult as Result, params, types};
+use mosaicod_db as db;
+use mosaicod_store as store;
+use std::time::Duration;
+
+/// Bytes in a GB, the unit of the prices.
+const GB: f64 = 1e9;
+
+/// Period over which the costs are estimated.
+const MONTH: Duration = Duration::from_secs(30 * 24 * 3600);
+
+/// Minimum period over which the reads of a sequence are projected, so that the reads of
+/// a sequence just created are not projected over a month.
+const MIN_READ_PERIOD: Duration = Duration::from_secs(24 * 3600);
+
+/// Unit prices of the estimates.
+#[derive(Debug, Clone)]
+pub struct CostConfig {
+    /// Price of a GB stored for a month in the standard storage
+    pub standard_price: f64,
+    /// Price of a GB stored for a month in the archive storage
+    pub archive_price: f64,
+    /// Price of a GB read from the store
+    pub egress_price: f64,
+}
+
+impl CostConfig {
+    /// Returns the unit prices of the server configuration.
+    pub fn configured() -> Self {
+        let tiering = tiering::TieringConfig::configured();
+        Self {
+            standard_price: tiering.standard_price,
+            archive_price: tiering.archive_price,
+            egress_price: params::params().cost_egress_price.value,
+        }
+    }
+}
+
+/// Monthly costs of some data files.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Cost {
+    /// Size of the data files in the standard storage
+    pub standard_bytes: u64,
+    /// Size of the data files in the archive storage
+    pub archived_bytes: u64,
+    pub storage_cost: f64,
+    /// Projected bytes read in a month
+    pub egress_bytes: u64,
+    pub egress_cost: f64,
+}
+
+impl Cost {
+    pub fn total_cost(&self) -> f64 {
+        self.storage_cost + self.egress_cost
+    }
+
+    fn add(&mut self, other: &Cost) {
+        self.standard_bytes += other.standard_bytes;
+        self.archived_bytes += other.archived_bytes;
+        self.storage_cost += other.storage_cost;
+        self.egress_bytes += other.egress_bytes;
+        self.egress_cost += other.egress_cost;
+    }
+}
+
+/// Costs of the sequences of a scope.
+#[derive(Debug, Clone)]
+pub struct Estimate {
+    pub total: Cost,
+    /// Costs of each sequence, sorted by locator
+    pub sequences: Vec<(types::SequenceLocator, Cost)>,
+}
+
+/// Estimates the monthly costs of the sequences, including the trashed ones, whose locator
+/// starts with `scope`. An empty scope estimates the costs of all the sequences.
+pub async fn estimate(context: &Context, config: &CostConfig, scope: &str) -> Result<Estimate> {
+    tiering::flush_access_log(context).await?;
+
+    let mut cx = context.db.connection();
+    let archive_prefix = format!("{}/", store::ARCHIVE_FOLDER);
+    let usages = db::sequence_usage_find_by_prefix(&mut cx, scope, &archive_prefix).await?;
+
+    let now = types::Timestamp::now();
+    let mut total = Cost::default();
+    let mut sequences = Vec::with_capacity(usages.len());
+
+    for usage in usages {
+        let elapsed = Duration::from_nanos(
+            (now.as_i64() - usage.creation_timestamp().as_i64()).max(0) as u64,
+        );
+        let read_period = elapsed.max(MIN_READ_PERIOD);
+        let egress_bytes = usage.read_bytes * MONTH.as_secs_f64() / read_period.as_secs_f64();
+
+        let standard_bytes = usage.standard_bytes as u64;
+        let archived_bytes = usage.archived_bytes as u64;
+        let cost = Cost {
+            standard_bytes,
+            archived_bytes,
+            storage_cost: standard_bytes as f64 / GB * config.standard_price
+                + archived_bytes as f64 / GB * config.archive_price,
+            egress_bytes: egress_bytes as u64,
+            egress_cost: egress_bytes / GB * config.egress_price,
+        };
+
+        total.add(&cost);
+        sequences.push((usage.locator().parse()?, cost));
+    }
+
+    Ok(Estimate { total, sequences })
+}
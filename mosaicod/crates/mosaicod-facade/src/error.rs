@@ -3,6 +3,9 @@ use mosaicod_core::{self as core};
 #[derive(Debug)]
 pub enum Error {
     MissingDbData(String),
+    QueryLimitExceeded(String),
+    /// A dependency, identified by the first field, is temporarily unavailable
+    Unavailable(String, Box<dyn std::error::Error + Send + Sync>),
     Internal(Box<dyn std::error::Error + Send + Sync>),
 }
 
@@ -10,6 +13,8 @@ impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::MissingDbData(msg) => write!(f, "{msg}"),
+            Self::QueryLimitExceeded(msg) => write!(f, "{msg}"),
+            Self::Unavailable(dependency, _) => write!(f, "{dependency} unavailable"),
             Self::Internal(_) => write!(f, "internal"),
         }
     }
@@ -23,12 +28,22 @@ impl From<String> for Error {
 
 impl From<mosaicod_db::Error> for Error {
     fn from(err: mosaicod_db::Error) -> Self {
+        if err.is_transient() {
+            return Self::Unavailable("database".to_owned(), Box::new(err));
+        }
         Self::Internal(Box::new(err))
     }
 }
 
 impl From<mosaicod_query::Error> for Error {
     fn from(err: mosaicod_query::Error) -> Self {
+        // Queries running out of memory are reported to the user
+        if let Some(reason) = err.resources_exhausted() {
+            return Self::QueryLimitExceeded(reason.to_owned());
+        }
+        if err.is_transient() {
+            return Self::Unavailable("store".to_owned(), Box::new(err));
+        }
         Self::Internal(Box::new(err))
     }
 }
@@ -41,7 +56,11 @@ impl From<tokio::sync::AcquireError> for Error {
 
 impl core::error::PublicError for Error {
     fn error(&self) -> core::Error {
-        core::Error::internal(None)
+        match self {
+            Self::QueryLimitExceeded(msg) => core::Error::query_limit_exceeded(msg.clone()),
+            Self::Unavailable(dependency, _) => core::Error::unavailable(dependency.clone()),
+            _ => core::Error::internal(None),
+        }
     }
 }
 
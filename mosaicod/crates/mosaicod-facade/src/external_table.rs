@@ -0,0 +1,350 @@
+//! External tables are Parquet or CSV files registered as temporary tables, so that their
+//! columns can be referenced by queries without ingesting the files.
+//!
+//! Tables are kept in memory, are visible only to the principal (i.e. the API key) that
+//! registered them and expire after the configured time to live. Queries reference the
+//! column of a table with the `$in` operator, e.g. `{"$in": {"table": "stations", "column":
+//! "id"}}`, that is replaced by the distinct values of the column before the query is
+//! performed.
+use super::Context;
+use log::{info, trace};
+use mosaicod_core::{self as core, error::PublicResult as Result, params, types};
+use mosaicod_query as query;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Identifies a table registered by a principal, [`None`] if API keys are disabled.
+type TableKey = (Option<String>, String);
+
+/// Registry of the external tables.
+pub struct ExternalTables {
+    ttl: Duration,
+    max_values: usize,
+    tables: Mutex<HashMap<TableKey, types::ExternalTable>>,
+}
+
+impl ExternalTables {
+    /// Creates a registry whose tables expire after `ttl`, a zero `ttl` disables external
+    /// tables. At most `max_values` distinct values are read from a column referenced by a
+    /// query.
+    pub fn new(ttl: Duration, max_values: usize) -> Self {
+        Self {
+            ttl,
+            max_values,
+            tables: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Creates a registry configured with [`params::Params::external_table_ttl`] and
+    /// [`params::Params::external_table_max_values`].
+    pub fn from_params() -> Self {
+        let params = params::params();
+        Self::new(
+            Duration::from_secs(params.external_table_ttl.value),
+            params.external_table_max_values.value,
+        )
+    }
+
+    /// Creates a registry rejecting every table.
+    pub fn disabled() -> Self {
+        Self::new(Duration::ZERO, 0)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    fn insert(&self, principal: Option<&str>, table: types::ExternalTable) -> bool {
+        let mut tables = self.tables.lock().unwrap();
+        purge_expired(&mut tables);
+
+        let key = (principal.map(str::to_owned), table.name.clone());
+        if tables.contains_key(&key) {
+            return false;
+        }
+        tables.insert(key, table);
+        true
+    }
+
+    fn get(&self, principal: Option<&str>, name: &str) -> Option<types::ExternalTable> {
+        let mut tables = self.tables.lock().unwrap();
+        purge_expired(&mut tables);
+        tables
+            .get(&(principal.map(str::to_owned), name.to_owned()))
+            .cloned()
+    }
+
+    fn list(&self, principal: Option<&str>) -> Vec<types::ExternalTable> {
+        let mut tables = self.tables.lock().unwrap();
+        purge_expired(&mut tables);
+
+        let mut list: Vec<types::ExternalTable> = tables
+            .iter()
+            .filter(|((owner, _), _)| owner.as_deref() == principal)
+            .map(|(_, table)| table.clone())
+            .collect();
+        list.sort_by(|a, b| a.name.cmp(&b.name));
+        list
+    }
+
+    fn remove(&self, principal: Option<&str>, name: &str) -> bool {
+        let mut tables = self.tables.lock().unwrap();
+        purge_expired(&mut tables);
+        tables
+            .remove(&(principal.map(str::to_owned), name.to_owned()))
+            .is_some()
+    }
+}
+
+fn purge_expired(tables: &mut HashMap<TableKey, types::ExternalTable>) {
+    let now = types::Timestamp::now();
+    tables.retain(|_, table| table.expires_at > now);
+}
+
+/// Registers the file at `location` as the external table `name` of `principal`.
+///
+/// The file is read to ensure it exists and to collect its columns.
+pub async fn register(
+    context: &Context,
+    principal: Option<&str>,
+    name: String,
+    location: String,
+    format: types::ExternalTableFormat,
+) -> Result<types::ExternalTable> {
+    let tables = &context.external_tables;
+    if !tables.is_enabled() {
+        Err(core::Error::bad_request(
+            "external tables are disabled".to_owned(),
+        ))?;
+    }
+
+    if name.is_empty() {
+        Err(core::Error::bad_request(
+            "external table name can't be empty".to_owned(),
+        ))?;
+    }
+
+    let data = context
+        .timeseries_querier
+        .read_external(&location, format)
+        .await
+        .map_err(|e| {
+            trace!("unable to read external table `{}`: {:?}", location, e);
+            core::Error::bad_request(format!("unable to read `{location}` as a {format} file"))
+        })?;
+
+    let columns: Vec<types::ExternalTableColumn> = data
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| types::ExternalTableColumn {
+            name: field.name().clone(),
+            data_type: field.data_type().to_string(),
+        })
+        .collect();
+
+    // Locations matching no file are read as tables without columns
+    if columns.is_empty() {
+        Err(core::Error::bad_request(format!(
+            "no columns found in `{location}`"
+        )))?;
+    }
+
+    let registered_at = types::Timestamp::now();
+    let table = types::ExternalTable {
+        name,
+        location,
+        format,
+        columns,
+        registered_at,
+        expires_at: registered_at + tables.ttl,
+    };
+
+    if !tables.insert(principal, table.clone()) {
+        Err(core::Error::already_exists(format!(
+            "external table `{}`",
+            table.name
+        )))?;
+    }
+
+    info!(
+        "registered external table `{}` reading `{}`",
+        table.name, table.location
+    );
+
+    Ok(table)
+}
+
+/// Returns the external tables of `principal`, sorted by name.
+pub fn list(context: &Context, principal: Option<&str>) -> Vec<types::ExternalTable> {
+    context.external_tables.list(principal)
+}
+
+/// Removes the external table `name` of `principal`, the file is left untouched.
+pub fn unregister(context: &Context, principal: Option<&str>, name: &str) -> Result<()> {
+    if !context.external_tables.remove(principal, name) {
+        Err(not_found(name))?;
+    }
+    Ok(())
+}
+
+/// Replaces the references to the columns of the external tables of `principal` in `query`
+/// with the distinct values of the columns.
+pub async fn resolve(
+    context: &Context,
+    mut query: serde_json::Value,
+    principal: Option<&str>,
+) -> Result<serde_json::Value> {
+    let mut references = Vec::new();
+    collect_references(&mut query, &mut references);
+
+    for reference in references {
+        let Some(columns) = reference.as_object() else {
+            unreachable!("only objects are collected as references");
+        };
+
+        let (Some(table), Some(column)) = (
+            columns.get("table").and_then(serde_json::Value::as_str),
+            columns.get("column").and_then(serde_json::Value::as_str),
+        ) else {
+            Err(core::Error::bad_request(
+                "external columns must be referenced as `{\"table\": <name>, \"column\": <name>}`"
+                    .to_owned(),
+            ))?
+        };
+
+        let values = column_values(context, principal, table, column).await?;
+        *reference = serde_json::Value::Array(values);
+    }
+
+    Ok(query)
+}
+
+/// Collects the values of the `$in` operators referencing an external table.
+fn collect_references<'a>(
+    value: &'a mut serde_json::Value,
+    references: &mut Vec<&'a mut serde_json::Value>,
+) {
+    let serde_json::Value::Object(fields) = value else {
+        return;
+    };
+
+    for (key, value) in fields.iter_mut() {
+        if key == "$in" && value.is_object() {
+            references.push(value);
+        } else {
+            collect_references(value, references);
+        }
+    }
+}
+
+async fn column_values(
+    context: &Context,
+    principal: Option<&str>,
+    table: &str,
+    column: &str,
+) -> Result<Vec<serde_json::Value>> {
+    let tables = &context.external_tables;
+    let Some(table) = tables.get(principal, table) else {
+        Err(not_found(table))?
+    };
+
+    trace!(
+        "reading column `{}` of external table `{}`",
+        column, table.name
+    );
+
+    let data = context
+        .timeseries_querier
+        .read_external(&table.location, table.format)
+        .await?;
+
+    let values = match data.distinct_values(column, tables.max_values).await {
+        Ok(values) => values,
+        Err(query::Error::BadField { .. }) => Err(core::Error::bad_request(format!(
+            "column `{column}` of external table `{}` can't be used in a query",
+            table.name
+        )))?,
+        Err(e @ query::Error::TooManyValues { .. }) => Err(core::Error::bad_request(format!(
+            "external table `{}`: {e}",
+            table.name
+        )))?,
+        Err(e) => Err(e)?,
+    };
+
+    Ok(values
+        .into_iter()
+        .map(|value| match value {
+            query::Value::Integer(v) => v.into(),
+            query::Value::Float(v) => v.into(),
+            query::Value::Text(v) => v.into(),
+            query::Value::Boolean(v) => v.into(),
+        })
+        .collect())
+}
+
+fn not_found(name: &str) -> core::Error {
+    core::Error::not_found(format!("external table `{name}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect_external_references() {
+        let mut query = serde_json::json!({
+            "topic": { "locator": { "$in": ["a", "b"] } },
+            "ontology": {
+                "gps.station": { "$in": { "table": "stations", "column": "id" } },
+                "gps.status": { "$eq": 1 },
+            },
+        });
+
+        let mut references = Vec::new();
+        collect_references(&mut query, &mut references);
+        assert_eq!(references.len(), 1);
+        *references[0] = serde_json::json!([1, 2]);
+
+        assert_eq!(
+            query["ontology"]["gps.station"],
+            serde_json::json!({ "$in": [1, 2] })
+        );
+        assert_eq!(
+            query["topic"]["locator"],
+            serde_json::json!({ "$in": ["a", "b"] })
+        );
+    }
+
+    #[test]
+    fn tables_are_scoped_to_principals() {
+        let tables = ExternalTables::new(Duration::from_secs(60), 10);
+        let table = |name: &str| types::ExternalTable {
+            name: name.to_owned(),
+            location: "stations.csv".to_owned(),
+            format: types::ExternalTableFormat::Csv,
+            columns: vec![],
+            registered_at: types::Timestamp::now(),
+            expires_at: types::Timestamp::now() + Duration::from_secs(60),
+        };
+
+        assert!(tables.insert(Some("key_a"), table("stations")));
+        assert!(!tables.insert(Some("key_a"), table("stations")));
+        assert!(tables.insert(Some("key_b"), table("stations")));
+
+        assert!(tables.get(Some("key_a"), "stations").is_some());
+        assert!(tables.get(None, "stations").is_none());
+        assert_eq!(tables.list(Some("key_b")).len(), 1);
+
+        assert!(tables.remove(Some("key_a"), "stations"));
+        assert!(!tables.remove(Some("key_a"), "stations"));
+        assert!(tables.get(Some("key_b"), "stations").is_some());
+
+        // Expired tables are purged
+        let mut expired = table("expired");
+        expired.expires_at = types::Timestamp::now();
+        assert!(tables.insert(None, expired));
+        assert!(tables.get(None, "expired").is_none());
+    }
+}
@@ -21,6 +21,39 @@ pub mod session;
 
 pub mod topic;
 
+pub mod topic_export;
+
+pub mod mcap_import;
+
+pub mod sequence_archive;
+
+pub mod sequence_replay;
+
+pub mod scheduled_query;
+
+pub mod query_view;
+
+pub mod sql_query;
+
+pub mod recovery;
+
+pub mod relocation;
+
+pub mod tiering;
+
+pub mod cost;
+
+pub mod catalog;
+
+pub mod mirror;
+
+pub mod relay;
+
+pub mod consumer;
+
+pub mod external_table;
+pub use external_table::ExternalTables;
+
 mod error;
 pub use error::*;
 
@@ -30,6 +63,30 @@ pub use chunk::*;
 mod query;
 pub use query::*;
 
+mod query_cache;
+pub use query_cache::*;
+
+mod query_limits;
+pub use query_limits::*;
+
+mod query_admission;
+pub use query_admission::*;
+
+mod transfer_limits;
+pub use transfer_limits::*;
+
+mod notification_sinks;
+pub use notification_sinks::*;
+
+mod notification_bus;
+pub use notification_bus::*;
+
+mod attestation;
+pub use attestation::*;
+
+mod table_export;
+pub use table_export::*;
+
 pub mod auth;
 
 mod context;
@@ -0,0 +1,346 @@
+//! Import of MCAP recordings available in the store.
+//!
+//! A recording is imported into a new session of a sequence, which is created if missing,
+//! with a topic for each channel having messages. The messages are mapped to rows as described
+//! in [`ext::mcap`], while the channel (its topic, encodings, schema and metadata) is kept
+//! in the user metadata of the topic under the [`METADATA_KEY`] key.
+//!
+//! The recording is decoded on a blocking thread, while the decoded batches are written into
+//! the topics. Recordings are read in memory, so large recordings are better split into
+//! several files.
+use super::{Context, sequence, session, topic};
+use arrow::array::RecordBatch;
+use arrow::datatypes::SchemaRef;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use log::{debug, info, warn};
+use mosaicod_core::{self as core, error::PublicResult as Result, params, types};
+use mosaicod_db as db;
+use mosaicod_ext::{self as ext, mcap::McapError};
+use mosaicod_marshal as marshal;
+use std::collections::{HashMap, HashSet, hash_map::Entry};
+use tokio::sync::mpsc;
+
+/// Key of the user metadata of the imported topics describing their channel.
+pub const METADATA_KEY: &str = "mcap";
+
+/// Ontology tag of the topics imported from channels without schema.
+const DEFAULT_ONTOLOGY_TAG: &str = "mcap";
+
+/// Maximum number of messages of a channel written in a data file.
+const BATCH_ROWS: usize = 65536;
+
+/// Number of decoded batches waiting to be written.
+const PENDING_BATCHES: usize = 4;
+
+/// Options of an MCAP import.
+#[derive(Debug, Default)]
+pub struct ImportOptions {
+    /// If set only the channels publishing on these topics are imported
+    pub channels: Option<Vec<String>>,
+    /// User metadata of the sequence, used if the sequence is created by the import
+    pub user_metadata: Option<marshal::JsonMetadataBlob>,
+    /// Owner of the session of the imported topics
+    pub owner: Option<String>,
+}
+
+/// A channel of a recording imported into a topic.
+#[derive(Debug, Clone)]
+pub struct ImportedChannel {
+    /// Topic the channel publishes on
+    pub channel: String,
+    pub locator: types::TopicLocator,
+    pub message_count: u64,
+}
+
+/// Result of the import of a recording.
+#[derive(Debug)]
+pub struct Import {
+    pub session: types::SessionLocator,
+    pub channels: Vec<ImportedChannel>,
+}
+
+/// Events sent by the thread decoding a recording.
+enum Event {
+    /// First message of a channel to import
+    Channel { id: u16, channel: Channel },
+    /// Batch of messages of a channel
+    Batch { id: u16, batch: RecordBatch },
+}
+
+/// Description of a channel, used to create its topic.
+struct Channel {
+    topic: String,
+    name: String,
+    ontology_tag: String,
+    schema: SchemaRef,
+    user_metadata: serde_json::Value,
+}
+
+/// Imports the MCAP recording stored at `path` into a new session of the sequence `locator`,
+/// creating the sequence if missing. The session is finalized once all the channels are
+/// imported, and aborted if the import fails.
+pub async fn import(
+    context: &Context,
+    path: &str,
+    locator: types::SequenceLocator,
+    options: ImportOptions,
+) -> Result<Import> {
+    let recording = context.store.read_bytes(path).await?;
+
+    let mut cx = context.db.connection();
+    match db::sequence_find_by_locator(&mut cx, &locator).await {
+        Ok(_) => (),
+        Err(db::Error::NotFound) => {
+            info!(
+                "creating sequence `{}` for the import of `{}`",
+                locator, path
+            );
+            sequence::try_create(context, locator.clone(), options.user_metadata).await?;
+        }
+        Err(e) => Err(e)?,
+    }
+
+    let session = session::try_create(context, locator.clone(), options.owner).await?;
+
+    let imported = match import_into(context, recording, &session, options.channels).await {
+        Ok(channels) => session::finalize(context, &session).await.map(|_| channels),
+        Err(e) => Err(e),
+    };
+
+    let channels = match imported {
+        Ok(channels) => channels,
+        Err(e) => {
+            // Removes the topics along with the data already written
+            if let Err(e) = session::abort(context, &session, types::allow_data_loss()).await {
+                warn!(
+                    "unable to abort session `{}` of failed import of `{}`: {}",
+                    session.locator(),
+                    path,
+                    e
+                );
+            }
+            return Err(e);
+        }
+    };
+
+    Ok(Import {
+        session: session.locator().clone(),
+        channels,
+    })
+}
+
+/// Writes the channels of the recording into topics of `session`.
+async fn import_into(
+    context: &Context,
+    recording: Vec<u8>,
+    session: &session::Handle,
+    channels: Option<Vec<String>>,
+) -> Result<Vec<ImportedChannel>> {
+    let batch_bytes = params::params()
+        .parquet_in_memory_encoding_buffer_size
+        .value;
+    let filter = channels.map(HashSet::from_iter);
+
+    let (events, mut received) = mpsc::channel(PENDING_BATCHES);
+    let decoding = tokio::task::spawn_blocking(move || {
+        decode(&recording, filter.as_ref(), batch_bytes, &events)
+    });
+
+    let sequence = &session.locator().sequence;
+    let mut writers = HashMap::new();
+    let mut imported = HashMap::new();
+
+    while let Some(event) = received.recv().await {
+        match event {
+            Event::Channel { id, channel } => {
+                let locator: types::TopicLocator =
+                    format!("{}/{}", sequence, channel.name).parse()?;
+                let handle = create_topic(context, locator.clone(), session, &channel).await?;
+
+                writers.insert(
+                    id,
+                    topic::writer(context.clone(), handle, channel.schema).await?,
+                );
+                imported.insert(
+                    id,
+                    ImportedChannel {
+                        channel: channel.topic,
+                        locator,
+                        message_count: 0,
+                    },
+                );
+            }
+            Event::Batch { id, batch } => {
+                let (Some(writer), Some(channel)) = (writers.get_mut(&id), imported.get_mut(&id))
+                else {
+                    continue;
+                };
+                channel.message_count += batch.num_rows() as u64;
+                let chunk = writer.write(batch).await?;
+                writer.record_chunk(chunk).await?;
+            }
+        }
+    }
+
+    decoding
+        .await
+        .map_err(|e| core::Error::internal(Some(e.to_string())))??;
+
+    for writer in writers.into_values() {
+        writer.finalize().await?;
+    }
+
+    let mut channels: Vec<ImportedChannel> = imported.into_values().collect();
+    channels.sort_by(|a, b| a.locator.cmp(&b.locator));
+
+    for channel in &channels {
+        debug!(
+            "imported {} messages of channel `{}` into `{}`",
+            channel.message_count, channel.channel, channel.locator
+        );
+    }
+
+    Ok(channels)
+}
+
+async fn create_topic(
+    context: &Context,
+    locator: types::TopicLocator,
+    session: &session::Handle,
+    channel: &Channel,
+) -> Result<topic::Handle> {
+    let properties = types::TopicOntologyProperties {
+        ontology_tag: channel.ontology_tag.clone(),
+        serialization_format: types::Format::Default,
+        sort_key: None,
+        dedup_policy: types::DedupPolicy::None,
+        primary_key: None,
+    };
+    let ontology_metadata =
+        types::TopicOntologyMetadata::new(properties, Some(channel.user_metadata.clone().into()));
+    let options = topic::CreateOptions {
+        base_version: None,
+        arrow_schema: Some(channel.schema.clone()),
+        uuid: None,
+    };
+
+    topic::try_create_with_options(context, locator, session, ontology_metadata, options).await
+}
+
+/// Decodes the messages of the recording, sending the batches of the channels to import.
+/// Decoding stops early if the receiver is dropped.
+fn decode(
+    recording: &[u8],
+    filter: Option<&HashSet<String>>,
+    batch_bytes: usize,
+    events: &mpsc::Sender<Event>,
+) -> std::result::Result<(), McapError> {
+    let mut decoders: HashMap<u16, Option<ext::mcap::ChannelDecoder>> = HashMap::new();
+    let mut names = HashSet::new();
+
+    for message in ext::mcap::messages(recording)? {
+        let message = message?;
+        let channel = message.channel.as_ref();
+
+        let decoder = match decoders.entry(channel.id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                if filter.is_some_and(|filter| !filter.contains(&channel.topic)) {
+                    entry.insert(None)
+                } else {
+                    let decoder = ext::mcap::ChannelDecoder::try_new(
+                        &channel.topic,
+                        channel.into(),
+                        BATCH_ROWS,
+                        batch_bytes,
+                    )?;
+
+                    // Channels sharing the name of their topic are told apart by their id
+                    let mut name = ext::mcap::topic_name(&channel.topic, channel.id);
+                    if !names.insert(name.clone()) {
+                        name = format!("{}_{}", name, channel.id);
+                        names.insert(name.clone());
+                    }
+
+                    let event = Event::Channel {
+                        id: channel.id,
+                        channel: describe(channel, name, decoder.schema().clone()),
+                    };
+                    if events.blocking_send(event).is_err() {
+                        return Ok(());
+                    }
+                    entry.insert(Some(decoder))
+                }
+            }
+        };
+
+        let Some(decoder) = decoder else {
+            continue;
+        };
+
+        let batch = decoder.push(
+            message.log_time,
+            message.publish_time,
+            message.sequence,
+            &message.data,
+        )?;
+        if let Some(batch) = batch {
+            let event = Event::Batch {
+                id: channel.id,
+                batch,
+            };
+            if events.blocking_send(event).is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    for (id, decoder) in decoders.iter_mut() {
+        let Some(decoder) = decoder else {
+            continue;
+        };
+        if let Some(batch) = decoder.flush()?
+            && events
+                .blocking_send(Event::Batch { id: *id, batch })
+                .is_err()
+        {
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+/// Describes a channel, its schema is kept as text if possible, encoded in base64 otherwise
+/// (e.g. Protobuf descriptors).
+fn describe(channel: &mcap::Channel, name: String, schema: SchemaRef) -> Channel {
+    let mut description = serde_json::json!({
+        "topic": channel.topic,
+        "message_encoding": channel.message_encoding,
+        "metadata": channel.metadata,
+    });
+
+    if let Some(mcap_schema) = &channel.schema {
+        description["schema_name"] = mcap_schema.name.clone().into();
+        description["schema_encoding"] = mcap_schema.encoding.clone().into();
+        match std::str::from_utf8(&mcap_schema.data) {
+            Ok(text) => description["schema"] = text.into(),
+            Err(_) => description["schema_base64"] = BASE64.encode(&mcap_schema.data).into(),
+        }
+    }
+
+    let ontology_tag = channel
+        .schema
+        .as_ref()
+        .map(|mcap_schema| mcap_schema.name.clone())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| DEFAULT_ONTOLOGY_TAG.to_owned());
+
+    Channel {
+        topic: channel.topic.clone(),
+        name,
+        ontology_tag,
+        schema,
+        user_metadata: serde_json::json!({ METADATA_KEY: description }),
+    }
+}
@@ -0,0 +1,91 @@
+//! Read-only mirror of the published data, served from the catalog manifest.
+//!
+//! When the database is down the reads of the sequences and topics can be answered from
+//! the last [`catalog`] manifest published in the store, which lists the published topics
+//! along with their metadata and data files. The mirror may lag behind the database by up
+//! to the publication interval of the manifest, and does not serve the data of upsert
+//! topics, whose rows are merged according to the chunk versions kept in the database.
+use super::{Context, catalog};
+use log::trace;
+use mosaicod_core::{self as core, error::PublicResult as Result, types};
+use mosaicod_marshal as marshal;
+use mosaicod_query as query;
+
+type CatalogManifest = types::CatalogManifest<marshal::JsonMetadataBlob>;
+type CatalogSequence = types::CatalogSequence<marshal::JsonMetadataBlob>;
+type CatalogTopic = types::CatalogTopic<marshal::JsonMetadataBlob>;
+
+/// Returns true if `error` is caused by the database being unavailable, the case in which
+/// the reads are served by the mirror.
+pub fn is_database_unavailable(error: &core::error::BoxPublicError) -> bool {
+    matches!(
+        error.error().kind(),
+        core::error::ErrorKind::Unavailable(dependency, _) if dependency == "database"
+    )
+}
+
+/// Reads the catalog manifest from the store, `None` if it was never published.
+pub async fn manifest(context: &Context) -> Result<Option<CatalogManifest>> {
+    if !context.store.exists(catalog::MANIFEST_PATH).await? {
+        return Ok(None);
+    }
+
+    trace!("reading catalog manifest `{}`", catalog::MANIFEST_PATH);
+    let bytes = context.store.read_bytes(catalog::MANIFEST_PATH).await?;
+    let manifest: marshal::JsonCatalogManifest = bytes.try_into()?;
+
+    Ok(Some(manifest.try_into()?))
+}
+
+/// Returns the sequence listed in `manifest`.
+pub fn sequence(
+    manifest: CatalogManifest,
+    locator: &types::SequenceLocator,
+) -> Result<CatalogSequence> {
+    manifest
+        .sequences
+        .into_iter()
+        .find(|sequence| &sequence.locator == locator)
+        .ok_or_else(|| core::Error::not_found(locator.to_string()).into())
+}
+
+/// Returns the topic listed in `manifest`.
+pub fn topic(manifest: CatalogManifest, locator: &types::TopicLocator) -> Result<CatalogTopic> {
+    manifest
+        .sequences
+        .into_iter()
+        .filter(|sequence| sequence.locator == locator.sequence)
+        .flat_map(|sequence| sequence.topics)
+        .find(|topic| &topic.locator == locator)
+        .ok_or_else(|| core::Error::not_found(locator.to_string()).into())
+}
+
+/// Reads the data files of the topic listed in the manifest.
+pub async fn read(
+    context: &Context,
+    topic: &CatalogTopic,
+    batch_size: usize,
+) -> Result<query::TimeseriesResult> {
+    let properties = &topic.metadata.ontology_metadata.properties;
+
+    if properties.read_dedup_policy() != types::DedupPolicy::None {
+        return Err(core::Error::unavailable("database".to_owned()).into());
+    }
+
+    // Data files sorted by another column carry no known ordering across each other
+    let ordering = match properties.sort_key {
+        Some(_) => query::FileOrdering::Unsorted,
+        None => query::FileOrdering::default(),
+    };
+
+    Ok(context
+        .timeseries_querier
+        .read_files(
+            topic.path_in_store.data_folder_path(),
+            properties.serialization_format,
+            Some(batch_size),
+            ordering,
+            &topic.data_files,
+        )
+        .await?)
+}
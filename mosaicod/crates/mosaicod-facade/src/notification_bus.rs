@@ -0,0 +1,200 @@
+//! In-process broadcast of the notifications.
+//!
+//! Every notification created in the platform is published on the [`NotificationBus`], that
+//! hands it to the subscriptions of its sequence, so that clients can be streamed the
+//! notifications as they are created instead of polling the listings. Notifications are
+//! not persisted by the bus: subscribers only receive the ones created while subscribed.
+use mosaicod_core::{self as core, error::PublicResult as Result, params, types};
+use std::sync::Arc;
+use tokio::sync::{broadcast, watch};
+
+/// Notification published on the bus.
+#[derive(Debug, Clone)]
+pub struct LiveNotification {
+    /// Sequence the notification belongs to, directly or through one of its topics
+    pub sequence: types::SequenceLocator,
+    /// Locator of the sequence or topic the notification is attached to
+    pub target: String,
+    pub notification_type: types::NotificationType,
+    pub severity: types::NotificationSeverity,
+    pub payload: types::NotificationPayload,
+    /// Rendered text of the notification, if available
+    pub msg: Option<String>,
+    pub created_at: types::DateTime,
+}
+
+impl From<&types::Notification<types::SequenceLocator>> for LiveNotification {
+    fn from(value: &types::Notification<types::SequenceLocator>) -> Self {
+        Self::new(value.target.clone(), value)
+    }
+}
+
+impl From<&types::Notification<types::TopicLocator>> for LiveNotification {
+    fn from(value: &types::Notification<types::TopicLocator>) -> Self {
+        Self::new(value.target.sequence.clone(), value)
+    }
+}
+
+impl LiveNotification {
+    fn new<L: types::Locator>(
+        sequence: types::SequenceLocator,
+        value: &types::Notification<L>,
+    ) -> Self {
+        Self {
+            sequence,
+            target: value.target.to_string(),
+            notification_type: value.notification_type,
+            severity: value.severity,
+            payload: value.payload.clone(),
+            msg: value.msg.clone().or_else(|| value.payload.render()),
+            created_at: value.created_at.clone(),
+        }
+    }
+}
+
+/// Broadcasts the notifications to the subscribed clients.
+pub struct NotificationBus {
+    sender: broadcast::Sender<Arc<LiveNotification>>,
+    closed: watch::Sender<bool>,
+}
+
+impl Default for NotificationBus {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+impl NotificationBus {
+    /// Creates a bus buffering up to `buffer_size` notifications for each subscription.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buffer_size` is zero.
+    pub fn new(buffer_size: usize) -> Self {
+        Self {
+            sender: broadcast::channel(buffer_size).0,
+            closed: watch::channel(false).0,
+        }
+    }
+
+    /// Creates a bus with the buffer size configured in the parameters.
+    ///
+    /// See [`params::Params::notify_stream_buffer_size`].
+    pub fn from_params() -> std::result::Result<Self, core::Error> {
+        let param = &params::params().notify_stream_buffer_size;
+
+        if param.value == 0 {
+            return Err(core::Error::invalid_configuration(
+                param.env.clone(),
+                "the buffer size must be greater than zero".to_owned(),
+            ));
+        }
+
+        Ok(Self::new(param.value))
+    }
+
+    /// Hands the notification to the subscriptions of its sequence.
+    pub fn publish(&self, notification: LiveNotification) {
+        // Notifications created while nobody is subscribed are dropped
+        let _ = self.sender.send(Arc::new(notification));
+    }
+
+    /// Subscribes to the notifications of `sequence` and of its topics at least as severe
+    /// as `min_severity`, created from now on.
+    pub fn subscribe(
+        &self,
+        sequence: types::SequenceLocator,
+        min_severity: types::NotificationSeverity,
+    ) -> NotificationSubscription {
+        NotificationSubscription {
+            receiver: self.sender.subscribe(),
+            closed: self.closed.subscribe(),
+            sequence,
+            min_severity,
+        }
+    }
+
+    /// Ends all the subscriptions, e.g. when the server is shutting down.
+    pub fn close(&self) {
+        self.closed.send_replace(true);
+    }
+}
+
+/// Notifications of a sequence received from the [`NotificationBus`].
+pub struct NotificationSubscription {
+    receiver: broadcast::Receiver<Arc<LiveNotification>>,
+    closed: watch::Receiver<bool>,
+    sequence: types::SequenceLocator,
+    min_severity: types::NotificationSeverity,
+}
+
+impl NotificationSubscription {
+    /// Waits for the next notification of the subscription, returns `None` once the bus
+    /// is closed.
+    ///
+    /// Returns an error if the subscription fell too far behind and missed some
+    /// notifications.
+    pub async fn next(&mut self) -> Result<Option<Arc<LiveNotification>>> {
+        loop {
+            let received = tokio::select! {
+                received = self.receiver.recv() => received,
+                _ = self.closed.wait_for(|closed| *closed) => return Ok(None),
+            };
+
+            match received {
+                Ok(notification) => {
+                    if notification.sequence == self.sequence
+                        && notification.severity >= self.min_severity
+                    {
+                        return Ok(Some(notification));
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    return Err(core::Error::bad_request(format!(
+                        "notification stream of `{}` fell behind, {} notifications were missed",
+                        self.sequence, missed
+                    )))?;
+                }
+                Err(broadcast::error::RecvError::Closed) => return Ok(None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(sequence: &str, severity: types::NotificationSeverity) -> LiveNotification {
+        LiveNotification {
+            sequence: sequence.parse().unwrap(),
+            target: sequence.to_owned(),
+            notification_type: types::NotificationType::Alert,
+            severity,
+            payload: types::NotificationPayload::message(),
+            msg: None,
+            created_at: types::DateTime::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn notification_bus() {
+        let bus = NotificationBus::new(2);
+        let mut subscription = bus.subscribe(
+            "sequence".parse().unwrap(),
+            types::NotificationSeverity::Warning,
+        );
+
+        bus.publish(notification("other", types::NotificationSeverity::Error));
+        bus.publish(notification("sequence", types::NotificationSeverity::Info));
+        bus.publish(notification("sequence", types::NotificationSeverity::Error));
+
+        // The first notification overflowed the buffer
+        assert!(subscription.next().await.is_err());
+        let received = subscription.next().await.unwrap().unwrap();
+        assert_eq!(received.severity, types::NotificationSeverity::Error);
+
+        bus.close();
+        assert!(subscription.next().await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,409 @@
+//! Delivery of the notifications to external sinks.
+//!
+//! Every notification created in the platform is handed to the [`NotificationDispatcher`],
+//! that forwards it to the sinks routed for its type (e.g. critical ingest errors by email,
+//! alerts and errors to a Slack channel). Deliveries run in background, failures are
+//! logged and never affect the request creating the notification.
+use futures::future::BoxFuture;
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor, message::Mailbox,
+    message::header::ContentType,
+};
+use log::{debug, warn};
+use mosaicod_core::{self as core, params, types};
+use std::sync::Arc;
+
+type DeliveryResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+/// Notification delivered to the sinks.
+#[derive(Debug, Clone)]
+pub struct SinkNotification {
+    /// Locator of the sequence or topic the notification is attached to
+    pub target: String,
+    pub notification_type: types::NotificationType,
+    pub payload: types::NotificationPayload,
+    /// Rendered text of the notification, or its code if no text is available
+    pub text: String,
+}
+
+impl<L: types::Locator> From<&types::Notification<L>> for SinkNotification {
+    fn from(value: &types::Notification<L>) -> Self {
+        let text = value
+            .msg
+            .clone()
+            .or_else(|| value.payload.render())
+            .unwrap_or_else(|| value.payload.code.clone());
+
+        Self {
+            target: value.target.to_string(),
+            notification_type: value.notification_type,
+            payload: value.payload.clone(),
+            text,
+        }
+    }
+}
+
+/// Destination of the notifications.
+pub trait NotificationSink: Send + Sync {
+    /// Name of the sink, used in logs.
+    fn name(&self) -> &str;
+
+    /// Delivers the notification.
+    fn deliver<'a>(&'a self, notification: &'a SinkNotification) -> BoxFuture<'a, DeliveryResult>;
+}
+
+struct Route {
+    sink: Arc<dyn NotificationSink>,
+    types: Vec<types::NotificationType>,
+}
+
+/// Forwards the notifications to the sinks routed for their type.
+#[derive(Default)]
+pub struct NotificationDispatcher {
+    routes: Vec<Route>,
+}
+
+impl NotificationDispatcher {
+    /// Creates a dispatcher without sinks, that drops every notification.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Creates a dispatcher with the sinks configured in the parameters.
+    ///
+    /// See [`params::Params::notify_slack_webhook_url`] and
+    /// [`params::Params::notify_smtp_url`].
+    pub fn from_params() -> Result<Self, core::Error> {
+        let params = params::params();
+        let mut dispatcher = Self::disabled();
+
+        if !params.notify_slack_webhook_url.value.is_empty() {
+            dispatcher.route(
+                SlackSink::new(params.notify_slack_webhook_url.value.clone()),
+                parse_types(
+                    &params.notify_slack_types.env,
+                    &params.notify_slack_types.value,
+                )?,
+            );
+        }
+
+        if !params.notify_smtp_url.value.is_empty() {
+            let sink = SmtpSink::try_new(
+                &params.notify_smtp_url.value,
+                &params.notify_smtp_from.value,
+                &params.notify_smtp_to.value,
+            )
+            .map_err(|e| {
+                core::Error::invalid_configuration(params.notify_smtp_url.env.clone(), e)
+            })?;
+
+            dispatcher.route(
+                sink,
+                parse_types(
+                    &params.notify_smtp_types.env,
+                    &params.notify_smtp_types.value,
+                )?,
+            );
+        }
+
+        Ok(dispatcher)
+    }
+
+    /// Routes to `sink` the notifications whose type is in `types`.
+    pub fn route<S>(&mut self, sink: S, types: Vec<types::NotificationType>)
+    where
+        S: NotificationSink + 'static,
+    {
+        self.routes.push(Route {
+            sink: Arc::new(sink),
+            types,
+        });
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.routes.is_empty()
+    }
+
+    /// Delivers the notification in background to the sinks routed for its type.
+    pub fn dispatch(&self, notification: SinkNotification) {
+        let routes = self
+            .routes
+            .iter()
+            .filter(|route| route.types.contains(&notification.notification_type));
+
+        for route in routes {
+            let sink = route.sink.clone();
+            let notification = notification.clone();
+            tokio::spawn(async move {
+                match sink.deliver(&notification).await {
+                    Ok(()) => debug!(
+                        "notification for `{}` delivered to {}",
+                        notification.target,
+                        sink.name()
+                    ),
+                    Err(e) => warn!(
+                        "unable to deliver notification for `{}` to {}: {}",
+                        notification.target,
+                        sink.name(),
+                        e
+                    ),
+                }
+            });
+        }
+    }
+}
+
+/// Parses a comma separated list of notification types.
+fn parse_types(env: &str, value: &str) -> Result<Vec<types::NotificationType>, core::Error> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            value.parse().map_err(|e: std::io::Error| {
+                core::Error::invalid_configuration(env.to_owned(), e.to_string())
+            })
+        })
+        .collect()
+}
+
+/// Posts the notifications to a Slack incoming webhook.
+pub struct SlackSink {
+    webhook_url: String,
+    client: reqwest::Client,
+}
+
+impl SlackSink {
+    pub fn new(webhook_url: String) -> Self {
+        Self {
+            webhook_url,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+impl NotificationSink for SlackSink {
+    fn name(&self) -> &str {
+        "slack"
+    }
+
+    fn deliver<'a>(&'a self, notification: &'a SinkNotification) -> BoxFuture<'a, DeliveryResult> {
+        Box::pin(async move {
+            let text = format!(
+                "*[{}]* `{}`: {}",
+                notification.notification_type, notification.target, notification.text
+            );
+
+            self.client
+                .post(&self.webhook_url)
+                .json(&serde_json::json!({ "text": text }))
+                .send()
+                .await?
+                .error_for_status()?;
+
+            Ok(())
+        })
+    }
+}
+
+/// Emails the notifications through an SMTP server.
+pub struct SmtpSink {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+impl SmtpSink {
+    /// Creates a sink sending the emails through the server at `url`, from the address
+    /// `from` to the comma separated addresses `to`.
+    pub fn try_new(url: &str, from: &str, to: &str) -> Result<Self, String> {
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::from_url(url)
+            .map_err(|e| format!("invalid SMTP url: {e}"))?
+            .build();
+
+        let from = from
+            .parse()
+            .map_err(|e| format!("invalid sender address `{from}`: {e}"))?;
+
+        let to = to
+            .split(',')
+            .map(str::trim)
+            .filter(|address| !address.is_empty())
+            .map(|address| {
+                address
+                    .parse()
+                    .map_err(|e| format!("invalid recipient address `{address}`: {e}"))
+            })
+            .collect::<Result<Vec<Mailbox>, _>>()?;
+
+        if to.is_empty() {
+            return Err("no recipient address".to_owned());
+        }
+
+        Ok(Self {
+            transport,
+            from,
+            to,
+        })
+    }
+}
+
+impl NotificationSink for SmtpSink {
+    fn name(&self) -> &str {
+        "smtp"
+    }
+
+    fn deliver<'a>(&'a self, notification: &'a SinkNotification) -> BoxFuture<'a, DeliveryResult> {
+        Box::pin(async move {
+            let mut builder = Message::builder()
+                .from(self.from.clone())
+                .subject(format!(
+                    "[mosaico] {} on {}",
+                    notification.notification_type, notification.target
+                ))
+                .header(ContentType::TEXT_PLAIN);
+            for to in &self.to {
+                builder = builder.to(to.clone());
+            }
+
+            let mut body = format!(
+                "{}\n\ncode: {}\n",
+                notification.text, notification.payload.code
+            );
+            for (name, value) in &notification.payload.params {
+                body.push_str(&format!("{name}: {value}\n"));
+            }
+
+            self.transport.send(builder.body(body)?).await?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::sync::mpsc;
+
+    struct RecordingSink(mpsc::UnboundedSender<String>);
+
+    impl NotificationSink for RecordingSink {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        fn deliver<'a>(
+            &'a self,
+            notification: &'a SinkNotification,
+        ) -> BoxFuture<'a, DeliveryResult> {
+            Box::pin(async move {
+                self.0.send(notification.text.clone())?;
+                Ok(())
+            })
+        }
+    }
+
+    fn notification(notification_type: types::NotificationType, text: &str) -> SinkNotification {
+        SinkNotification {
+            target: "sequence".to_owned(),
+            notification_type,
+            payload: types::NotificationPayload::message(),
+            text: text.to_owned(),
+        }
+    }
+
+    #[tokio::test]
+    async fn route_by_type() {
+        let (errors_tx, mut errors) = mpsc::unbounded_channel();
+        let (all_tx, mut all) = mpsc::unbounded_channel();
+
+        let mut dispatcher = NotificationDispatcher::disabled();
+        assert!(!dispatcher.is_enabled());
+        dispatcher.route(
+            RecordingSink(errors_tx),
+            vec![types::NotificationType::Error],
+        );
+        dispatcher.route(
+            RecordingSink(all_tx),
+            vec![
+                types::NotificationType::Error,
+                types::NotificationType::Alert,
+            ],
+        );
+        assert!(dispatcher.is_enabled());
+
+        dispatcher.dispatch(notification(types::NotificationType::Alert, "alert"));
+        dispatcher.dispatch(notification(types::NotificationType::Error, "error"));
+
+        assert_eq!(errors.recv().await.unwrap(), "error");
+
+        let mut received = vec![all.recv().await.unwrap(), all.recv().await.unwrap()];
+        received.sort();
+        assert_eq!(received, ["alert", "error"]);
+
+        drop(dispatcher);
+        assert!(errors.recv().await.is_none());
+    }
+
+    #[test]
+    fn types() {
+        assert_eq!(
+            parse_types("ENV", " error, alert ,").unwrap(),
+            vec![
+                types::NotificationType::Error,
+                types::NotificationType::Alert
+            ]
+        );
+        assert!(parse_types("ENV", "").unwrap().is_empty());
+        assert!(parse_types("ENV", "error,critical").is_err());
+    }
+
+    #[test]
+    fn smtp_configuration() {
+        let url = "smtp://localhost:2525";
+        assert!(
+            SmtpSink::try_new(url, "mosaico@example.com", "a@example.com, b@example.com").is_ok()
+        );
+        assert!(SmtpSink::try_new(url, "mosaico@example.com", "").is_err());
+        assert!(SmtpSink::try_new(url, "not an address", "a@example.com").is_err());
+        assert!(
+            SmtpSink::try_new("http://localhost", "mosaico@example.com", "a@example.com").is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn slack_webhook() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            // Read until the end of the JSON body
+            while !request.ends_with(b"}") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let sink = SlackSink::new(url);
+        sink.deliver(&notification(
+            types::NotificationType::Error,
+            "upload failed",
+        ))
+        .await
+        .unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /hook"));
+        assert!(request.ends_with(r#"{"text":"*[error]* `sequence`: upload failed"}"#));
+    }
+}
@@ -1,11 +1,12 @@
-use super::Error;
+use super::{Error, QueryCache, QueryCacheKey};
 use futures::stream::{FuturesUnordered, StreamExt};
 use log::{debug, trace};
-use mosaicod_core::{error::PublicResult as Result, params, types};
+use mosaicod_core::{self as core, error::PublicResult as Result, params, types};
 use mosaicod_db as db;
 use mosaicod_query as query;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Instant;
 use tokio::sync::Semaphore;
 
@@ -17,13 +18,29 @@ pub struct Query {}
 
 impl Query {
     /// Perform a query in the system
+    ///
+    /// The query fails with a [`core::Error::query_limit_exceeded`] error as soon as it
+    /// uses more resources than allowed by `limits`.
     pub async fn query(
         filter: query::Filter,
         ts_gw: query::TimeseriesEngineRef,
         db: db::Database,
+        limits: &types::QueryLimits,
     ) -> Result<types::SequenceTopicGroupSet> {
         let mut result: Option<types::SequenceTopicGroupSet> = None;
 
+        let ts_gw = match limits.max_memory() {
+            Some(max_memory) => Arc::new(ts_gw.with_memory_limit(max_memory as usize)?),
+            None => ts_gw,
+        };
+
+        // Bytes of the data files searched so far, shared by all the expression groups
+        let scanned_bytes = Arc::new(AtomicU64::new(0));
+        let max_scanned_bytes = limits.max_scanned_bytes();
+
+        // Approximate queries search a sample of the rows of each chunk
+        let sampling = filter.sampling.clone();
+
         let (seq_filt, top_filt, on_filt) = filter.into_parts();
 
         let no_topic_filter = (seq_filt.is_none() || seq_filt.as_ref().unwrap().is_empty())
@@ -71,6 +88,12 @@ impl Query {
                     &expression_groups_count,
                 );
 
+                // Fields compared in another unit are resolved against the unit of the
+                // columns of each topic
+                let searches =
+                    resolve_units(&db, ontology_tag_exprs.clone(), &on_topics, no_topic_filter)
+                        .await?;
+
                 let ts_engine = ts_gw.clone();
                 let max_concurrent = params::params().max_concurrent_chunk_queries.value;
                 let semaphore = Arc::new(Semaphore::new(max_concurrent));
@@ -84,17 +107,32 @@ impl Query {
 
                 let db_clone = db.clone();
                 let on_topics = on_topics.clone();
+                let sampling = sampling.clone();
+                let scanned_bytes = scanned_bytes.clone();
 
                 search_jobs.push(async move {
                     let _permit = permit; // sentinel lock
 
                     let mut cx = db_clone.connection();
-                    let chunks = db::chunks_from_filters(
-                        &mut cx,
-                        ontology_tag_exprs.clone(),
-                        Some(&on_topics),
-                    )
-                    .await?;
+
+                    // Expressions used to search the topics compared in another unit
+                    let mut topic_exprs: HashMap<i32, query::OntologyExprGroup<query::Value>> =
+                        HashMap::new();
+
+                    let mut chunks = Vec::new();
+                    for (exprs, topics) in searches {
+                        let topics = match topics {
+                            Some(topics) => {
+                                for topic in &topics {
+                                    topic_exprs.insert(topic.topic_id, exprs.clone());
+                                }
+                                Arc::new(topics)
+                            }
+                            None => on_topics.clone(),
+                        };
+                        chunks
+                            .extend(db::chunks_from_filters(&mut cx, exprs, Some(&topics)).await?);
+                    }
 
                     trace!("found {} chunks for provided filter", chunks.len());
 
@@ -106,6 +144,17 @@ impl Query {
                     };
                     let topics_map = pre_fetch_topics(&mut cx, &chunks, on_topics).await?;
 
+                    // Chunks staged by sessions not finalized yet are not visible
+                    chunks.retain(|chunk| {
+                        !topics_map
+                            .get(&chunk.topic_id)
+                            .and_then(|topic| topic.path_in_store())
+                            .is_some_and(|path| path.is_staged(chunk.data_file()))
+                    });
+
+                    // Chunks are searched one after the other, let the store fetch them ahead
+                    ts_engine.plan_reads(chunks.iter().map(|chunk| chunk.data_file()));
+
                     // Store which topic had a positive data file search
                     let mut topics_with_data: HashSet<i32> = HashSet::new();
 
@@ -129,6 +178,17 @@ impl Query {
                             chunk.data_file().to_string_lossy()
                         );
 
+                        let chunk_size = chunk.size_bytes as u64;
+                        let scanned = scanned_bytes.fetch_add(chunk_size, Ordering::Relaxed);
+                        let exceeded =
+                            max_scanned_bytes.filter(|limit| scanned + chunk_size > *limit);
+                        if let Some(limit) = exceeded {
+                            return Err(Error::QueryLimitExceeded(format!(
+                                "query scanned {scanned} bytes, the next data file \
+                                 ({chunk_size} bytes) exceeds the limit of {limit} scanned bytes"
+                            )));
+                        }
+
                         let serialization_format =
                             topic.serialization_format().ok_or_else(|| {
                                 Error::MissingDbData(format!(
@@ -137,11 +197,18 @@ impl Query {
                                 ))
                             })?;
 
-                        let qr = ts_engine
+                        let mut qr = ts_engine
                             .read(chunk.data_file(), serialization_format, None)
                             .await?;
 
-                        let qr = qr.filter(ontology_tag_exprs.to_owned())?;
+                        if let Some(sampling) = &sampling {
+                            qr = qr.sample(sampling)?;
+                        }
+
+                        let exprs = topic_exprs
+                            .get(&topic.topic_id)
+                            .unwrap_or(&ontology_tag_exprs);
+                        let qr = qr.filter(exprs.to_owned())?;
 
                         // Set this to true to print a log message that the chunk will be discared
                         let mut is_discarded = false;
@@ -218,8 +285,224 @@ impl Query {
             result = Some(group.into());
         }
 
-        Ok(result.unwrap_or_default())
+        let result = result.unwrap_or_default();
+        check_output_rows(&result, limits)?;
+
+        debug!(
+            "query scanned {} bytes",
+            scanned_bytes.load(Ordering::Relaxed)
+        );
+
+        Ok(result)
     }
+
+    /// Performs a query, serving it from `cache` if the same query was already performed on
+    /// the current version of the data.
+    ///
+    /// `normalized` is the normalized representation of `filter`, equivalent queries are
+    /// expected to share it. Returns the matching groups along with a flag reporting if they
+    /// were served from the cache. Approximate queries search a random sample of the data,
+    /// so they are never cached.
+    ///
+    /// Cached results are subject to the output limits only, since they don't search any
+    /// data.
+    pub async fn query_cached(
+        normalized: String,
+        filter: query::Filter,
+        ts_gw: query::TimeseriesEngineRef,
+        db: db::Database,
+        cache: &QueryCache,
+        limits: &types::QueryLimits,
+    ) -> Result<(types::SequenceTopicGroupSet, bool)> {
+        if !cache.is_enabled() || filter.sampling.is_some() {
+            return Ok((Self::query(filter, ts_gw, db, limits).await?, false));
+        }
+
+        let data_version = db::data_version(&mut db.connection()).await?;
+        let key = QueryCacheKey::new(normalized, data_version);
+
+        if let Some(groups) = cache.get(&key) {
+            debug!("query result served from cache (data version {data_version})");
+            check_output_rows(&groups, limits)?;
+            return Ok((groups, true));
+        }
+
+        let groups = Self::query(filter, ts_gw, db.clone(), limits).await?;
+
+        // Data changed during the search may be partially reflected in the result
+        if db::data_version(&mut db.connection()).await? == data_version {
+            cache.insert(key, groups.clone());
+        }
+
+        Ok((groups, false))
+    }
+
+    /// Returns the first `max_rows` topics of the query result, the remaining ones are stored
+    /// and can be retrieved using [`Query::fetch`] with the returned handle.
+    ///
+    /// Stored rows are discarded if not fetched within [`params::Params::query_result_ttl`]
+    /// seconds.
+    pub async fn paginate(
+        groups: types::SequenceTopicGroupSet,
+        max_rows: usize,
+        db: db::Database,
+    ) -> Result<QueryPage> {
+        check_max_rows(max_rows)?;
+
+        let mut rows: Vec<types::TopicLocator> = Vec::<types::SequenceTopicGroup>::from(groups)
+            .into_iter()
+            .flat_map(|group| group.into_parts().1)
+            .collect();
+
+        if rows.len() <= max_rows {
+            return Ok(QueryPage {
+                groups: group_rows(rows),
+                handle: None,
+            });
+        }
+
+        let remaining = rows.split_off(max_rows);
+
+        let mut tx = db.transaction().await?;
+        let record =
+            db::query_result_create(&mut tx, &db::QueryResultRecord::new(expiration())).await?;
+        let records: Vec<db::QueryResultRowRecord> = remaining
+            .iter()
+            .enumerate()
+            .map(|(index, locator)| {
+                db::QueryResultRowRecord::new(record.query_result_id, index, locator)
+            })
+            .collect();
+        db::query_result_row_create_batch(&mut tx, &records).await?;
+        tx.commit().await?;
+
+        debug!(
+            "stored {} rows of query result `{}`",
+            records.len(),
+            record.uuid()
+        );
+
+        Ok(QueryPage {
+            groups: group_rows(rows),
+            handle: Some(record.uuid()),
+        })
+    }
+
+    /// Returns the next `max_rows` topics of a query result stored by [`Query::paginate`].
+    ///
+    /// Once all the rows are fetched the query result is discarded, and no handle is returned.
+    pub async fn fetch(
+        handle: &types::Uuid,
+        max_rows: usize,
+        db: db::Database,
+    ) -> Result<QueryPage> {
+        check_max_rows(max_rows)?;
+
+        let not_found = || core::Error::not_found(format!("query result `{handle}`"));
+
+        let mut tx = db.transaction().await?;
+
+        let record = match db::query_result_find_by_uuid(&mut tx, handle).await {
+            Ok(record) => record,
+            Err(db::Error::NotFound) => Err(not_found())?,
+            Err(e) => Err(e)?,
+        };
+
+        if record.expiration_timestamp() < types::Timestamp::now() {
+            Err(not_found())?;
+        }
+
+        // An additional row is requested to know if the result has more rows
+        let offset = record.fetched_count();
+        let mut rows: Vec<types::TopicLocator> =
+            db::query_result_find_rows(&mut tx, record.query_result_id, offset, max_rows + 1)
+                .await?
+                .iter()
+                .map(db::QueryResultRowRecord::locator)
+                .collect();
+
+        let handle = if rows.len() > max_rows {
+            rows.truncate(max_rows);
+            db::query_result_update_fetched(
+                &mut tx,
+                record.query_result_id,
+                offset + max_rows,
+                expiration(),
+            )
+            .await?;
+            Some(record.uuid())
+        } else {
+            db::query_result_delete(&mut tx, record.query_result_id).await?;
+            None
+        };
+
+        tx.commit().await?;
+
+        Ok(QueryPage {
+            groups: group_rows(rows),
+            handle,
+        })
+    }
+
+    /// Discards the stored query results not fetched before their expiration, returning the
+    /// number of discarded results.
+    pub async fn purge_expired_results(db: db::Database) -> Result<u64> {
+        let mut cx = db.connection();
+        Ok(db::query_result_delete_expired(&mut cx, types::Timestamp::now()).await?)
+    }
+}
+
+/// A page of the topics matching a query.
+pub struct QueryPage {
+    pub groups: types::SequenceTopicGroupSet,
+    /// Handle used to fetch the next page, [`None`] if this is the last page
+    pub handle: Option<types::Uuid>,
+}
+
+/// Ensures that the rows (i.e. topics) of a query result don't exceed the limits.
+fn check_output_rows(
+    groups: &types::SequenceTopicGroupSet,
+    limits: &types::QueryLimits,
+) -> Result<()> {
+    if let Some(limit) = limits.max_output_rows() {
+        let rows: usize = groups.iter().map(|group| group.topics.len()).sum();
+        if rows as u64 > limit {
+            Err(core::Error::query_limit_exceeded(format!(
+                "query returned {rows} rows, exceeding the limit of {limit} rows"
+            )))?;
+        }
+    }
+    Ok(())
+}
+
+fn check_max_rows(max_rows: usize) -> Result<()> {
+    if max_rows == 0 {
+        Err(core::Error::bad_request(
+            "`max_rows` must be greater than zero".to_owned(),
+        ))?;
+    }
+    Ok(())
+}
+
+/// Returns the expiration of a query result fetched now.
+fn expiration() -> types::Timestamp {
+    let ttl = params::params().query_result_ttl.value as i64;
+    (types::Timestamp::now().as_i64() + ttl * 1_000_000_000).into()
+}
+
+/// Groups rows by sequence, rows of the same sequence are expected to be contiguous.
+fn group_rows(rows: Vec<types::TopicLocator>) -> types::SequenceTopicGroupSet {
+    let mut groups: Vec<types::SequenceTopicGroup> = Vec::new();
+    for row in rows {
+        match groups.last_mut() {
+            Some(group) if group.sequence == row.sequence => group.topics.push(row),
+            _ => groups.push(types::SequenceTopicGroup::new(
+                row.sequence.clone(),
+                vec![row],
+            )),
+        }
+    }
+    groups.into()
 }
 
 /// A map holding pairs of (topic_id, topic_record) for easy lookup
@@ -244,3 +527,106 @@ async fn pre_fetch_topics(
 
     Ok(topic_map)
 }
+
+/// Expressions searched on a set of topics, [`None`] if the topics are not restricted.
+type UnitSearch = (
+    query::OntologyExprGroup<query::Value>,
+    Option<Vec<db::TopicRecord>>,
+);
+
+/// Resolves the fields of an expression group compared in another unit.
+///
+/// Values compared with a converted field are converted into the unit of the column of each
+/// topic, so the topics are grouped by the units of their converted columns and each group
+/// is searched with its own expressions. Topics missing the unit of a converted column are
+/// not searched.
+async fn resolve_units(
+    db: &db::Database,
+    exprs: query::OntologyExprGroup<query::Value>,
+    on_topics: &[db::TopicRecord],
+    no_topic_filter: bool,
+) -> Result<Vec<UnitSearch>> {
+    let converted: Vec<String> = exprs
+        .group
+        .iter()
+        .filter(|expr| expr.ontology_field().unit().is_some())
+        .map(|expr| expr.ontology_field().field().to_owned())
+        .collect();
+
+    if converted.is_empty() {
+        return Ok(vec![(exprs, None)]);
+    }
+
+    // Expression groups are never empty
+    let ontology_tag = exprs.group[0].ontology_field().ontology_tag().to_owned();
+
+    let mut cx = db.connection();
+    let columns = db::topic_find_columns_with_unit(&mut cx, &ontology_tag, &converted).await?;
+
+    let mut topic_units: HashMap<i32, HashMap<String, String>> = HashMap::new();
+    for column in columns {
+        if let Some(unit) = column.unit {
+            topic_units
+                .entry(column.topic_id)
+                .or_default()
+                .insert(column.column_name, unit);
+        }
+    }
+
+    // Topics grouped by the units of their converted columns (in the order of `converted`)
+    let mut groups: HashMap<Vec<String>, Vec<i32>> = HashMap::new();
+    for (topic_id, units) in topic_units {
+        if !no_topic_filter && !on_topics.iter().any(|topic| topic.topic_id == topic_id) {
+            continue;
+        }
+
+        let units: Option<Vec<String>> = converted
+            .iter()
+            .map(|column| units.get(column).cloned())
+            .collect();
+
+        match units {
+            Some(units) => groups.entry(units).or_default().push(topic_id),
+            None => trace!("skipping topic with id `{topic_id}`, missing column units"),
+        }
+    }
+
+    let mut searches = Vec::with_capacity(groups.len());
+    for (units, topic_ids) in groups {
+        let column_units: HashMap<&str, &str> = converted
+            .iter()
+            .map(String::as_str)
+            .zip(units.iter().map(String::as_str))
+            .collect();
+
+        let group = exprs
+            .group
+            .iter()
+            .cloned()
+            .map(|expr| {
+                let (field, op) = expr.into_parts();
+                let Some(unit) = field.unit() else {
+                    return Ok((field, op).into());
+                };
+
+                let bad_request = |e: &dyn std::fmt::Display| {
+                    core::Error::bad_request(format!("field `{}`: {e}", field.value()))
+                };
+
+                let from = types::Unit::try_from_symbol(unit).map_err(|e| bad_request(&e))?;
+                let to = types::Unit::try_from_symbol(column_units[field.field()])
+                    .map_err(|e| bad_request(&e))?;
+                let converter = from.converter(to).map_err(|e| bad_request(&e))?;
+                let op = op.try_map_numeric(converter).map_err(|e| bad_request(&e))?;
+
+                Ok((field.without_unit(), op).into())
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let topics = db::topic_find_by_ids(&mut cx, &topic_ids).await?;
+
+        searches.push((query::OntologyExprGroup::new(group), Some(topics)));
+    }
+
+    Ok(searches)
+}
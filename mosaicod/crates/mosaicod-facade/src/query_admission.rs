@@ -0,0 +1,256 @@
+//! Admission control of the queries.
+//!
+//! At most a configured number of queries run concurrently, the others wait in a bounded
+//! queue for a running query to complete. Queries are rejected when the queue is full or
+//! when they wait in the queue longer than the configured timeout.
+use log::{debug, warn};
+use mosaicod_core::{self as core, error::PublicResult as Result, params};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Queries waiting to be admitted, identified by increasing tickets.
+#[derive(Default)]
+struct Queue {
+    next_ticket: u64,
+    waiting: BTreeMap<u64, Instant>,
+}
+
+/// Limits the number of queries running concurrently.
+pub struct QueryAdmission {
+    max_running: usize,
+    max_queued: usize,
+    timeout: Duration,
+    semaphore: Arc<Semaphore>,
+    queue: Mutex<Queue>,
+    running: Arc<AtomicUsize>,
+    admitted: AtomicU64,
+    rejected: AtomicU64,
+    timed_out: AtomicU64,
+}
+
+impl QueryAdmission {
+    /// Creates an admission control running at most `max_running` queries concurrently,
+    /// with up to `max_queued` queries waiting at most `timeout` to be admitted.
+    ///
+    /// A zero `max_running` admits every query immediately.
+    pub fn new(max_running: usize, max_queued: usize, timeout: Duration) -> Self {
+        Self {
+            max_running,
+            max_queued,
+            timeout,
+            semaphore: Arc::new(Semaphore::new(max_running)),
+            queue: Mutex::new(Queue::default()),
+            running: Arc::new(AtomicUsize::new(0)),
+            admitted: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            timed_out: AtomicU64::new(0),
+        }
+    }
+
+    /// Creates an admission control configured with
+    /// [`params::Params::max_concurrent_queries`], [`params::Params::query_queue_size`] and
+    /// [`params::Params::query_queue_timeout`].
+    pub fn from_params() -> Self {
+        let params = params::params();
+        Self::new(
+            params.max_concurrent_queries.value,
+            params.query_queue_size.value,
+            Duration::from_secs(params.query_queue_timeout.value),
+        )
+    }
+
+    /// Creates an admission control admitting every query immediately.
+    pub fn unlimited() -> Self {
+        Self::new(0, 0, Duration::ZERO)
+    }
+
+    /// Waits for a query to be admitted, the query is considered running until the returned
+    /// permit is dropped.
+    ///
+    /// Fails if the queue is full or if the query is not admitted within the timeout.
+    pub async fn admit(&self) -> Result<QueryPermit> {
+        if self.max_running == 0 {
+            return Ok(self.permit(None));
+        }
+
+        if let Ok(permit) = self.semaphore.clone().try_acquire_owned() {
+            return Ok(self.permit(Some(permit)));
+        }
+
+        let _queued = self.enqueue()?;
+
+        match tokio::time::timeout(self.timeout, self.semaphore.clone().acquire_owned()).await {
+            Ok(Ok(permit)) => Ok(self.permit(Some(permit))),
+            Ok(Err(_)) => Err(core::Error::internal(Some(
+                "query admission closed".to_owned(),
+            )))?,
+            Err(_) => {
+                self.timed_out.fetch_add(1, Ordering::Relaxed);
+                warn!(
+                    "query not admitted after waiting {}s in the queue",
+                    self.timeout.as_secs()
+                );
+                Err(core::Error::overloaded(format!(
+                    "query not admitted after waiting {}s, {} queries running",
+                    self.timeout.as_secs(),
+                    self.max_running
+                )))?
+            }
+        }
+    }
+
+    /// Returns the current state of the admission control.
+    pub fn metrics(&self) -> QueryAdmissionMetrics {
+        let queue = self.queue.lock().unwrap();
+        QueryAdmissionMetrics {
+            max_running: self.max_running,
+            running: self.running.load(Ordering::Relaxed),
+            max_queued: self.max_queued,
+            queued: queue.waiting.len(),
+            // Tickets are increasing, the first one waits since the longest time
+            oldest_queued_age: queue.waiting.values().next().map(Instant::elapsed),
+            admitted: self.admitted.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+            timed_out: self.timed_out.load(Ordering::Relaxed),
+        }
+    }
+
+    fn permit(&self, permit: Option<OwnedSemaphorePermit>) -> QueryPermit {
+        self.admitted.fetch_add(1, Ordering::Relaxed);
+        self.running.fetch_add(1, Ordering::Relaxed);
+        QueryPermit {
+            _permit: permit,
+            running: self.running.clone(),
+        }
+    }
+
+    /// Adds a query to the queue, it is removed when the returned guard is dropped.
+    fn enqueue(&self) -> Result<Queued<'_>> {
+        let mut queue = self.queue.lock().unwrap();
+
+        if queue.waiting.len() >= self.max_queued {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            warn!("query rejected, {} queries already queued", self.max_queued);
+            Err(core::Error::overloaded(format!(
+                "{} queries running and {} queries queued",
+                self.max_running, self.max_queued
+            )))?;
+        }
+
+        let ticket = queue.next_ticket;
+        queue.next_ticket += 1;
+        queue.waiting.insert(ticket, Instant::now());
+
+        debug!("query queued ({} queries waiting)", queue.waiting.len());
+
+        Ok(Queued {
+            admission: self,
+            ticket,
+        })
+    }
+}
+
+/// Permit of an admitted query.
+pub struct QueryPermit {
+    _permit: Option<OwnedSemaphorePermit>,
+    running: Arc<AtomicUsize>,
+}
+
+impl Drop for QueryPermit {
+    fn drop(&mut self) {
+        self.running.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Query waiting in the queue, removed from the queue once admitted, rejected or cancelled.
+struct Queued<'a> {
+    admission: &'a QueryAdmission,
+    ticket: u64,
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        let mut queue = self.admission.queue.lock().unwrap();
+        queue.waiting.remove(&self.ticket);
+    }
+}
+
+/// State of the query admission control.
+#[derive(Debug, Clone)]
+pub struct QueryAdmissionMetrics {
+    /// Maximum number of queries running concurrently, 0 if unlimited
+    pub max_running: usize,
+    pub running: usize,
+    /// Maximum number of queries waiting to be admitted
+    pub max_queued: usize,
+    pub queued: usize,
+    /// Time spent in the queue by the query waiting since the longest time
+    pub oldest_queued_age: Option<Duration>,
+    /// Number of queries admitted since startup
+    pub admitted: u64,
+    /// Number of queries rejected since startup because the queue was full
+    pub rejected: u64,
+    /// Number of queries rejected since startup because not admitted within the timeout
+    pub timed_out: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn query_admission_queue() {
+        let admission = Arc::new(QueryAdmission::new(1, 1, Duration::from_secs(10)));
+
+        let running = admission.admit().await.unwrap();
+
+        // The second query waits for the first one to complete
+        let queued = tokio::spawn({
+            let admission = admission.clone();
+            async move { admission.admit().await.map(|_| ()).is_ok() }
+        });
+        while admission.metrics().queued == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        // The queue is full
+        assert!(admission.admit().await.is_err());
+
+        let metrics = admission.metrics();
+        assert_eq!(metrics.running, 1);
+        assert_eq!(metrics.queued, 1);
+        assert!(metrics.oldest_queued_age.is_some());
+        assert_eq!(metrics.rejected, 1);
+
+        drop(running);
+        assert!(queued.await.unwrap());
+
+        let metrics = admission.metrics();
+        assert_eq!(metrics.running, 0);
+        assert_eq!(metrics.queued, 0);
+        assert_eq!(metrics.admitted, 2);
+    }
+
+    #[tokio::test]
+    async fn query_admission_timeout() {
+        let admission = QueryAdmission::new(1, 1, Duration::from_millis(10));
+
+        let _running = admission.admit().await.unwrap();
+        assert!(admission.admit().await.is_err());
+
+        let metrics = admission.metrics();
+        assert_eq!(metrics.timed_out, 1);
+        assert_eq!(metrics.queued, 0);
+
+        // Unlimited admission never waits
+        let admission = QueryAdmission::unlimited();
+        let _permits = [
+            admission.admit().await.unwrap(),
+            admission.admit().await.unwrap(),
+        ];
+        assert_eq!(admission.metrics().running, 2);
+    }
+}
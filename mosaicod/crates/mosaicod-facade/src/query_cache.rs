@@ -0,0 +1,230 @@
+//! In-memory cache of query results.
+//!
+//! Results are keyed by the normalized query and by the version of the data it searched
+//! (see [`mosaicod_db::data_version`]), so a cached result is served only while the data
+//! is unchanged. Entries are evicted in least recently used order once the configured
+//! capacity is exceeded and expire after the configured time to live.
+use mosaicod_core::{params, types};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Identifies a query performed on a version of the data.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryCacheKey {
+    /// Normalized query, equivalent queries are expected to share the same representation
+    query: String,
+    data_version: i64,
+}
+
+impl QueryCacheKey {
+    pub fn new(query: String, data_version: i64) -> Self {
+        Self {
+            query,
+            data_version,
+        }
+    }
+}
+
+struct Entry {
+    groups: types::SequenceTopicGroupSet,
+    size_bytes: usize,
+    inserted_at: Instant,
+    /// Logical clock of the last access, used to find the least recently used entry
+    last_access: u64,
+}
+
+#[derive(Default)]
+struct Index {
+    entries: HashMap<QueryCacheKey, Entry>,
+    size_bytes: usize,
+    clock: u64,
+}
+
+impl Index {
+    fn remove(&mut self, key: &QueryCacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.size_bytes -= entry.size_bytes;
+        }
+    }
+
+    fn evict_lru(&mut self) -> bool {
+        let key = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_access)
+            .map(|(key, _)| key.clone());
+
+        match key {
+            Some(key) => {
+                self.remove(&key);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Bytes-bounded LRU cache of query results.
+pub struct QueryCache {
+    capacity_bytes: usize,
+    ttl: Duration,
+    index: Mutex<Index>,
+}
+
+impl QueryCache {
+    /// Creates a cache holding at most `capacity_bytes`, a zero capacity disables the cache.
+    pub fn new(capacity_bytes: usize, ttl: Duration) -> Self {
+        Self {
+            capacity_bytes,
+            ttl,
+            index: Mutex::new(Index::default()),
+        }
+    }
+
+    /// Creates a cache configured with [`params::Params::query_cache_size`] and
+    /// [`params::Params::query_cache_ttl`].
+    pub fn from_params() -> Self {
+        let params = params::params();
+        Self::new(
+            params.query_cache_size.value,
+            Duration::from_secs(params.query_cache_ttl.value),
+        )
+    }
+
+    /// Creates a cache never storing any result.
+    pub fn disabled() -> Self {
+        Self::new(0, Duration::ZERO)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity_bytes > 0
+    }
+
+    /// Returns the cached result of a query, if present and not expired.
+    pub fn get(&self, key: &QueryCacheKey) -> Option<types::SequenceTopicGroupSet> {
+        let mut index = self.index.lock().unwrap();
+        index.clock += 1;
+        let clock = index.clock;
+
+        match index.entries.get_mut(key) {
+            Some(entry) if entry.inserted_at.elapsed() > self.ttl => {
+                index.remove(key);
+                None
+            }
+            Some(entry) => {
+                entry.last_access = clock;
+                Some(entry.groups.clone())
+            }
+            None => None,
+        }
+    }
+
+    /// Stores the result of a query, evicting the least recently used results if required.
+    pub fn insert(&self, key: QueryCacheKey, groups: types::SequenceTopicGroupSet) {
+        let size_bytes = entry_size(&key, &groups);
+        if size_bytes > self.capacity_bytes {
+            return;
+        }
+
+        let mut index = self.index.lock().unwrap();
+        index.remove(&key);
+
+        while index.size_bytes + size_bytes > self.capacity_bytes {
+            if !index.evict_lru() {
+                break;
+            }
+        }
+
+        index.clock += 1;
+        index.size_bytes += size_bytes;
+        let entry = Entry {
+            groups,
+            size_bytes,
+            inserted_at: Instant::now(),
+            last_access: index.clock,
+        };
+        index.entries.insert(key, entry);
+    }
+}
+
+/// Estimates the memory used by a cache entry.
+fn entry_size(key: &QueryCacheKey, groups: &types::SequenceTopicGroupSet) -> usize {
+    let groups_size: usize = groups
+        .iter()
+        .map(|group| {
+            std::mem::size_of::<types::SequenceTopicGroup>()
+                + group.sequence.len()
+                + group
+                    .topics
+                    .iter()
+                    .map(|topic| {
+                        std::mem::size_of::<types::TopicLocator>() + topic.to_string().len()
+                    })
+                    .sum::<usize>()
+        })
+        .sum();
+
+    std::mem::size_of::<Entry>() + key.query.len() + groups_size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn groups(topic: &str) -> types::SequenceTopicGroupSet {
+        let topic = types::TopicLocator::from_str(topic).unwrap();
+        vec![types::SequenceTopicGroup::new(
+            topic.sequence.clone(),
+            vec![topic],
+        )]
+        .into()
+    }
+
+    fn topics(groups: types::SequenceTopicGroupSet) -> Vec<String> {
+        groups
+            .iter()
+            .flat_map(|group| group.topics.iter().map(ToString::to_string))
+            .collect()
+    }
+
+    #[test]
+    fn query_cache_lookup() {
+        let cache = QueryCache::new(1_000_000, Duration::from_secs(60));
+
+        let key = QueryCacheKey::new("{}".to_owned(), 1);
+        assert!(cache.get(&key).is_none());
+
+        cache.insert(key.clone(), groups("sequence/topic"));
+        assert_eq!(topics(cache.get(&key).unwrap()), vec!["sequence/topic"]);
+
+        // Results are not shared across data versions
+        assert!(cache.get(&QueryCacheKey::new("{}".to_owned(), 2)).is_none());
+    }
+
+    #[test]
+    fn query_cache_eviction() {
+        let key_a = QueryCacheKey::new("a".to_owned(), 1);
+        let key_b = QueryCacheKey::new("b".to_owned(), 1);
+        let size = entry_size(&key_a, &groups("sequence/topic"));
+
+        // Room for a single entry, the least recently used one is evicted
+        let cache = QueryCache::new(size, Duration::from_secs(60));
+        cache.insert(key_a.clone(), groups("sequence/topic"));
+        cache.insert(key_b.clone(), groups("sequence/topic"));
+        assert!(cache.get(&key_a).is_none());
+        assert!(cache.get(&key_b).is_some());
+
+        // Results larger than the cache are never stored
+        let cache = QueryCache::new(size - 1, Duration::from_secs(60));
+        cache.insert(key_a.clone(), groups("sequence/topic"));
+        assert!(cache.get(&key_a).is_none());
+
+        // Expired results are discarded
+        let cache = QueryCache::new(size, Duration::ZERO);
+        cache.insert(key_a.clone(), groups("sequence/topic"));
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(cache.get(&key_a).is_none());
+    }
+}
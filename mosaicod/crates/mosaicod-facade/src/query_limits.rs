@@ -0,0 +1,75 @@
+//! Limits on the resources used by queries.
+//!
+//! Limits are configured globally and can be overridden for each class of principals,
+//! identified by the permission of the API key performing the query.
+use mosaicod_core::{params, types, types::auth::Permission};
+
+/// Limits applied to the queries of each class of principals.
+#[derive(Debug, Clone, Default)]
+pub struct QueryLimitPolicy {
+    global: types::QueryLimits,
+    read: types::QueryLimits,
+    write: types::QueryLimits,
+    delete: types::QueryLimits,
+    manage: types::QueryLimits,
+}
+
+impl QueryLimitPolicy {
+    /// Creates a policy applying `global` limits to every principal.
+    pub fn new(global: types::QueryLimits) -> Self {
+        Self {
+            global,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a policy configured with [`params::Params::query_limits`] and its per-class
+    /// overrides.
+    pub fn from_params() -> Self {
+        let params = params::params();
+        Self {
+            global: params.query_limits.value.clone(),
+            read: params.query_limits_read.value.clone(),
+            write: params.query_limits_write.value.clone(),
+            delete: params.query_limits_delete.value.clone(),
+            manage: params.query_limits_manage.value.clone(),
+        }
+    }
+
+    /// Creates a policy without any limit.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Overrides the global limits for the principals having `permission`.
+    pub fn with_class_limits(mut self, permission: Permission, limits: types::QueryLimits) -> Self {
+        *self.class_limits_mut(permission) = limits;
+        self
+    }
+
+    /// Returns the limits of the queries not performed on behalf of a principal (e.g.
+    /// scheduled queries).
+    pub fn global_limits(&self) -> types::QueryLimits {
+        self.global.clone()
+    }
+
+    /// Returns the limits of the queries performed by principals having `permission`.
+    pub fn limits_for(&self, permission: Permission) -> types::QueryLimits {
+        let limits = match permission {
+            Permission::Read => &self.read,
+            Permission::Write => &self.write,
+            Permission::Delete => &self.delete,
+            Permission::Manage => &self.manage,
+        };
+        limits.or(&self.global)
+    }
+
+    fn class_limits_mut(&mut self, permission: Permission) -> &mut types::QueryLimits {
+        match permission {
+            Permission::Read => &mut self.read,
+            Permission::Write => &mut self.write,
+            Permission::Delete => &mut self.delete,
+            Permission::Manage => &mut self.manage,
+        }
+    }
+}
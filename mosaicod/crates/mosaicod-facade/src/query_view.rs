@@ -0,0 +1,274 @@
+//! Views are named queries defined within a sequence, searching the topics of that sequence
+//! only.
+//!
+//! Subsequent queries reference a view with the `view` key, either as `<sequence>/<name>`
+//! or by name only, resolved in the default sequence of the principal. The filters of the
+//! referencing query are added to the ones of the view, so common filters are defined once
+//! and reused.
+use super::{Context, sequence};
+use log::trace;
+use mosaicod_core::{self as core, error::PublicResult as Result, types};
+use mosaicod_db as db;
+use mosaicod_marshal as marshal;
+
+/// Query definition as provided by the user, see [`marshal::query_filter_from_serde_value`].
+pub type QueryView = types::QueryView<serde_json::Value>;
+
+/// Key of the queries referencing a view.
+const VIEW_KEY: &str = "view";
+
+/// Creates a new view in the sequence.
+pub async fn create(
+    context: &Context,
+    sequence: &sequence::Handle,
+    name: String,
+    query: serde_json::Value,
+) -> Result<QueryView> {
+    if name.is_empty() || name.contains('/') {
+        Err(core::Error::bad_request(format!(
+            "bad view name `{name}`, names can't be empty or contain `/`"
+        )))?;
+    }
+
+    let Some(filters) = query.as_object() else {
+        Err(core::Error::bad_request(
+            "view query must be an object".to_owned(),
+        ))?
+    };
+
+    if filters.contains_key(VIEW_KEY) {
+        Err(core::Error::bad_request(
+            "views can't reference other views".to_owned(),
+        ))?;
+    }
+
+    let filters_locator = filters
+        .get("sequence")
+        .and_then(serde_json::Value::as_object)
+        .is_some_and(|filter| filter.contains_key("locator"));
+    if filters_locator {
+        Err(core::Error::bad_request(
+            "views search their own sequence, they can't filter sequence locators".to_owned(),
+        ))?;
+    }
+
+    // Ensure the query is valid before storing it
+    marshal::query_filter_from_serde_value(scoped(query.clone(), sequence.locator()))?;
+
+    let mut cx = context.db.connection();
+    let now = types::Timestamp::now();
+    match db::query_view_create(&mut cx, sequence.id(), &name, &query, now).await {
+        Ok(record) => Ok(record.into_query_view(sequence.locator().clone())),
+        Err(db::Error::AlreadyExists) => Err(core::Error::already_exists(format!(
+            "view `{}/{}`",
+            sequence.locator(),
+            name
+        )))?,
+        Err(e) => Err(e)?,
+    }
+}
+
+/// Returns the views defined in the sequence, sorted by name.
+pub async fn list(context: &Context, sequence: &sequence::Handle) -> Result<Vec<QueryView>> {
+    let mut cx = context.db.connection();
+    let records = db::query_view_find_all_by_sequence(&mut cx, sequence.id()).await?;
+
+    Ok(records
+        .into_iter()
+        .map(|record| record.into_query_view(sequence.locator().clone()))
+        .collect())
+}
+
+pub async fn delete(context: &Context, sequence: &sequence::Handle, name: &str) -> Result<()> {
+    let mut cx = context.db.connection();
+    match db::query_view_delete(&mut cx, sequence.id(), name).await {
+        Ok(()) => Ok(()),
+        Err(db::Error::NotFound) => Err(not_found(sequence.locator(), name))?,
+        Err(e) => Err(e)?,
+    }
+}
+
+/// Replaces the view referenced by `query`, if any, with the view query extended with the
+/// filters of `query`.
+///
+/// Views referenced by name only are searched in `default_sequence`.
+pub async fn resolve(
+    context: &Context,
+    mut query: serde_json::Value,
+    default_sequence: Option<&types::SequenceLocator>,
+) -> Result<serde_json::Value> {
+    let Some(reference) = query
+        .as_object_mut()
+        .and_then(|filters| filters.remove(VIEW_KEY))
+    else {
+        return Ok(query);
+    };
+
+    let Some(reference) = reference.as_str() else {
+        Err(core::Error::bad_request(
+            "view must be referenced as `<sequence>/<name>`".to_owned(),
+        ))?
+    };
+
+    let (sequence, name) = match (reference.rsplit_once('/'), default_sequence) {
+        (Some((sequence, name)), _) => (
+            sequence
+                .parse::<types::SequenceLocator>()
+                .map_err(|e| core::Error::bad_request(e.to_string()))?,
+            name,
+        ),
+        (None, Some(default_sequence)) => (default_sequence.clone(), reference),
+        (None, None) => Err(core::Error::bad_request(format!(
+            "view `{reference}` must be referenced as `<sequence>/<name>`, no default sequence is set"
+        )))?,
+    };
+
+    trace!("resolving view `{}/{}`", sequence, name);
+
+    let handle = sequence::Handle::try_from_locator(context, sequence).await?;
+
+    let mut cx = context.db.connection();
+    let view = match db::query_view_find_by_name(&mut cx, handle.id(), name).await {
+        Ok(record) => record.into_query_view(handle.locator().clone()),
+        Err(db::Error::NotFound) => Err(not_found(handle.locator(), name))?,
+        Err(e) => Err(e)?,
+    };
+
+    let mut resolved = scoped(view.query, &view.sequence);
+    if let (Some(target), serde_json::Value::Object(source)) = (resolved.as_object_mut(), query) {
+        merge(target, source, "").map_err(|field| {
+            core::Error::bad_request(format!(
+                "`{field}` is already filtered by view `{}/{}`",
+                view.sequence, view.name
+            ))
+        })?;
+    }
+
+    Ok(resolved)
+}
+
+/// Restricts a view query to its sequence.
+fn scoped(mut query: serde_json::Value, sequence: &types::SequenceLocator) -> serde_json::Value {
+    if let Some(filters) = query.as_object_mut() {
+        let sequence_filter = filters
+            .entry("sequence")
+            .or_insert_with(|| serde_json::json!({}));
+        if let Some(sequence_filter) = sequence_filter.as_object_mut() {
+            sequence_filter.insert(
+                "locator".to_owned(),
+                serde_json::json!({ "$eq": sequence.to_string() }),
+            );
+        }
+    }
+    query
+}
+
+/// Adds the filters of `source` to `target`, returning the path of the first field filtered
+/// by both.
+fn merge(
+    target: &mut serde_json::Map<String, serde_json::Value>,
+    source: serde_json::Map<String, serde_json::Value>,
+    path: &str,
+) -> std::result::Result<(), String> {
+    for (key, value) in source {
+        let field = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{path}.{key}")
+        };
+
+        match (target.get_mut(&key), value) {
+            (None, value) => {
+                target.insert(key, value);
+            }
+            // Operators (e.g. `{"$gt": 3}`) are leaves, only nested filters are merged
+            (Some(serde_json::Value::Object(target)), serde_json::Value::Object(source))
+                if !is_op(target) && !is_op(&source) =>
+            {
+                merge(target, source, &field)?;
+            }
+            _ => return Err(field),
+        }
+    }
+    Ok(())
+}
+
+fn is_op(filter: &serde_json::Map<String, serde_json::Value>) -> bool {
+    filter.keys().any(|key| key.starts_with('$'))
+}
+
+fn not_found(sequence: &types::SequenceLocator, name: &str) -> core::Error {
+    core::Error::not_found(format!("view `{sequence}/{name}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merged(
+        target: serde_json::Value,
+        source: serde_json::Value,
+    ) -> std::result::Result<serde_json::Value, String> {
+        let mut target = target.as_object().unwrap().clone();
+        merge(&mut target, source.as_object().unwrap().clone(), "")?;
+        Ok(target.into())
+    }
+
+    #[test]
+    fn merge_filters() {
+        let view = serde_json::json!({
+            "sequence": { "locator": { "$eq": "my_sequence" } },
+            "ontology": { "mock.value": { "$gt": 3 } },
+        });
+
+        let res = merged(
+            view.clone(),
+            serde_json::json!({
+                "topic": { "locator": { "$match": "%imu%" } },
+                "ontology": { "mock.other": { "$lt": 1 } },
+            }),
+        )
+        .unwrap();
+        assert_eq!(
+            res,
+            serde_json::json!({
+                "sequence": { "locator": { "$eq": "my_sequence" } },
+                "topic": { "locator": { "$match": "%imu%" } },
+                "ontology": { "mock.value": { "$gt": 3 }, "mock.other": { "$lt": 1 } },
+            })
+        );
+
+        let err = merged(
+            view.clone(),
+            serde_json::json!({ "ontology": { "mock.value": { "$lt": 10 } } }),
+        )
+        .unwrap_err();
+        assert_eq!(err, "ontology.mock.value");
+
+        let err = merged(
+            view,
+            serde_json::json!({ "sequence": { "locator": { "$eq": "other" } } }),
+        )
+        .unwrap_err();
+        assert_eq!(err, "sequence.locator");
+    }
+
+    #[test]
+    fn scope_to_sequence() {
+        let sequence: types::SequenceLocator = "my_sequence".parse().unwrap();
+
+        let res = scoped(
+            serde_json::json!({ "sequence": { "created_at_ns": { "$gt": 10 } } }),
+            &sequence,
+        );
+        assert_eq!(
+            res,
+            serde_json::json!({
+                "sequence": {
+                    "created_at_ns": { "$gt": 10 },
+                    "locator": { "$eq": "my_sequence" },
+                },
+            })
+        );
+    }
+}
@@ -0,0 +1,481 @@
+//! Recovery of the records of a lost database from the store.
+//!
+//! The root folder of every topic contains a manifest (`metadata.json`) saved when the
+//! topic is finalized, holding the locator of the topic, the UUID of the session that
+//! uploaded it and its ontology metadata. The data files embed the schema of the topic.
+//! When only the store survives, the topics found under a prefix of the store are
+//! registered again, together with the sequences and sessions they belong to.
+//!
+//! The manifests written in the sequence folders when the sessions are finalized restore
+//! the user metadata and the folder of the sequences. Sequences without any manifest are
+//! recovered without user metadata, in a new folder.
+//!
+//! This is a last-resort path: the notifications and the API keys are not stored and can
+//! not be recovered.
+use super::{Context, session, topic};
+use log::{debug, warn};
+use mosaicod_core::{error::PublicResult as Result, types};
+use mosaicod_db as db;
+use mosaicod_marshal as marshal;
+use std::collections::{HashMap, HashSet};
+
+/// Outcome of an import.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Topics registered, or that would be registered by a dry run
+    pub imported: Vec<types::TopicLocator>,
+    /// Topic folders not registered, with the reason
+    pub skipped: Vec<(String, String)>,
+    /// Sequences created to hold the imported topics
+    pub sequences_created: usize,
+    /// Sessions created to hold the imported topics
+    pub sessions_created: usize,
+}
+
+/// Topic folder found in the store.
+struct StoredTopic {
+    path_in_store: types::TopicPathInStore,
+    metadata: topic::TopicMetadata,
+}
+
+/// Registers the finalized topics stored under `prefix` and missing from the database.
+///
+/// Topics already registered, not finalized, belonging to a sequence in the trash or
+/// whose manifest can not be read are skipped. With `dry_run` set the database is not modified, the report lists the topics
+/// that would be registered.
+pub async fn import(context: &Context, prefix: &str, dry_run: bool) -> Result<Report> {
+    let mut report = Report::default();
+
+    let mut topics = Vec::new();
+    let mut sequences_by_session = HashMap::new();
+    for object in context.store.list(prefix, None).await? {
+        if let Some(sequence_root) = session_manifest_folder(&object) {
+            match session::read_manifest(context, &object).await {
+                Ok(mut manifest) => {
+                    // The folder may have been relocated after the manifest was written
+                    manifest.sequence.path_in_store = sequence_root.to_owned().into();
+                    sequences_by_session.insert(manifest.uuid.clone(), manifest.sequence);
+                }
+                Err(e) => warn!("unable to read session manifest `{}`: {}", object, e),
+            }
+            continue;
+        }
+
+        let Some(root) = object.strip_suffix("/metadata.json") else {
+            continue;
+        };
+        let is_topic_folder = root
+            .rsplit('/')
+            .next()
+            .is_some_and(|folder| folder.starts_with("tp_"));
+        if !is_topic_folder {
+            continue;
+        }
+
+        match read_manifest(context, &object).await {
+            Ok(metadata) => topics.push(StoredTopic {
+                path_in_store: root.to_owned().into(),
+                metadata,
+            }),
+            Err(e) => report.skipped.push((root.to_owned(), e.to_string())),
+        }
+    }
+
+    // Sessions and sequences are created with the creation time of their first topic
+    topics.sort_by_key(|topic| topic.metadata.properties.created_at.as_i64());
+
+    let mut sequences = HashSet::new();
+    let mut sessions = HashSet::new();
+
+    for stored in topics {
+        let root = stored.path_in_store.to_string();
+        let properties = &stored.metadata.properties;
+        let locator = properties.resource_locator.clone();
+
+        if properties.completed_at.is_none() {
+            report
+                .skipped
+                .push((root, "topic upload not completed".to_owned()));
+            continue;
+        }
+
+        let mut cx = context.db.connection();
+        if db::topic_find_by_locator(&mut cx, &locator).await.is_ok() {
+            report
+                .skipped
+                .push((root, format!("topic `{}` already registered", locator)));
+            continue;
+        }
+
+        if find(db::sequence_find_trashed_by_locator(&mut cx, &locator.sequence).await)?.is_some() {
+            report.skipped.push((
+                root,
+                format!("sequence `{}` is in the trash", locator.sequence),
+            ));
+            continue;
+        }
+
+        if dry_run {
+            if sequences.insert(locator.sequence.to_string())
+                && find(db::sequence_find_by_locator(&mut cx, &locator.sequence).await)?.is_none()
+            {
+                report.sequences_created += 1;
+            }
+            if sessions.insert(properties.session_uuid.clone())
+                && find(db::session_find_by_uuid(&mut cx, &properties.session_uuid).await)?
+                    .is_none()
+            {
+                report.sessions_created += 1;
+            }
+            report.imported.push(locator);
+            continue;
+        }
+
+        let snapshot = sequences_by_session.get(&properties.session_uuid);
+        let session = match ensure_session(context, &stored, snapshot, &mut report).await {
+            Ok(session) => session,
+            Err(e) => {
+                report.skipped.push((root, e.to_string()));
+                continue;
+            }
+        };
+
+        match topic::register_stored(context, &session, stored.path_in_store, stored.metadata).await
+        {
+            Ok(_) => {
+                debug!("topic `{}` registered from `{}`", locator, root);
+                report.imported.push(locator);
+            }
+            Err(e) => {
+                warn!("unable to register topic `{}`: {}", locator, e);
+                report.skipped.push((root, e.to_string()));
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+async fn read_manifest(context: &Context, path: &str) -> Result<topic::TopicMetadata> {
+    let bytes = context.store.read_bytes(path).await?;
+    let manifest = marshal::JsonTopicMetadata::try_from(bytes)?;
+    Ok(manifest.try_into()?)
+}
+
+/// Returns the sequence folder containing `path`, if `path` is the manifest of a session.
+fn session_manifest_folder(path: &str) -> Option<&str> {
+    let (folder, file) = path.rsplit_once('/')?;
+    let root = folder.strip_suffix("/sessions")?;
+    let is_sequence_folder = root
+        .rsplit('/')
+        .next()
+        .is_some_and(|folder| folder.starts_with("sq_"));
+    (file.ends_with(".json") && is_sequence_folder).then_some(root)
+}
+
+/// Returns the session that uploaded the topic, creating it together with its sequence if
+/// missing.
+///
+/// The sequence is created from `snapshot`, if the manifest of the session was found.
+async fn ensure_session(
+    context: &Context,
+    stored: &StoredTopic,
+    snapshot: Option<&session::SequenceSnapshot>,
+    report: &mut Report,
+) -> Result<session::Handle> {
+    let properties = &stored.metadata.properties;
+    let sequence_locator = &properties.resource_locator.sequence;
+
+    let mut tx = context.db.transaction().await?;
+
+    let sequence = match find(db::sequence_find_by_locator(&mut tx, sequence_locator).await)? {
+        Some(sequence) => sequence,
+        None => {
+            let record = match snapshot {
+                Some(snapshot) => {
+                    let record = db::SequenceRecord::new(
+                        sequence_locator.clone(),
+                        snapshot.path_in_store.clone(),
+                    )
+                    .with_creation_timestamp(snapshot.created_at);
+                    match snapshot.user_metadata.clone() {
+                        Some(mdata) => record.with_user_metadata(mdata),
+                        None => record,
+                    }
+                }
+                None => db::SequenceRecord::new(
+                    sequence_locator.clone(),
+                    types::SequencePathInStore::new_with_layout(context.store_layout),
+                )
+                .with_creation_timestamp(properties.created_at),
+            };
+            report.sequences_created += 1;
+            db::sequence_create(&mut tx, &record).await?
+        }
+    };
+
+    match find(db::session_find_by_uuid(&mut tx, &properties.session_uuid).await)? {
+        Some(session) if session.sequence_id != sequence.sequence_id => {
+            Err(mosaicod_core::Error::bad_request(format!(
+                "session `{}` belongs to another sequence",
+                properties.session_uuid
+            )))?;
+        }
+        Some(_) => {}
+        None => {
+            let record = db::SessionRecord::new(
+                types::SessionLocator::new(sequence_locator.clone()),
+                sequence.sequence_id,
+            )
+            .with_uuid(properties.session_uuid.clone())
+            .with_creation_timestamp(properties.created_at);
+            let record = db::session_create(&mut tx, &record).await?;
+
+            // Only finalized topics are imported, so their sessions were finalized too
+            if let Some(completed_at) = properties.completed_at {
+                db::session_try_update_completion_tstamp(
+                    &mut tx,
+                    record.session_id,
+                    completed_at.as_i64(),
+                )
+                .await?;
+            }
+            report.sessions_created += 1;
+        }
+    }
+
+    tx.commit().await?;
+
+    session::Handle::try_from_uuid(context, &properties.session_uuid).await
+}
+
+/// Maps a record not found to `None`.
+fn find<T>(res: std::result::Result<T, db::Error>) -> Result<Option<T>> {
+    match res {
+        Ok(record) => Ok(Some(record)),
+        Err(db::Error::NotFound) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence;
+    use arrow::array::{Int64Array, RecordBatch};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use mosaicod_core::params;
+    use mosaicod_query as query;
+    use mosaicod_store as store;
+    use std::sync::Arc;
+
+    fn test_context(pool: sqlx::Pool<db::DatabaseType>) -> Context {
+        let database = db::testing::Database::new(pool);
+        let store = Arc::new(store::Store::in_memory());
+        let ts_gw = Arc::new(query::TimeseriesEngine::try_new(store.clone(), 0).unwrap());
+
+        Context::new(store, (*database).clone(), ts_gw)
+    }
+
+    async fn upload_topic(context: &Context, session: &session::Handle, locator: &str) {
+        let ontology_metadata = types::TopicOntologyMetadata::new(
+            types::TopicOntologyProperties {
+                ontology_tag: "dummy".to_owned(),
+                serialization_format: types::Format::Default,
+                sort_key: None,
+                dedup_policy: types::DedupPolicy::None,
+                primary_key: None,
+            },
+            None,
+        );
+        let handle = topic::try_create(
+            context,
+            locator.parse().unwrap(),
+            session,
+            ontology_metadata,
+        )
+        .await
+        .unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+                DataType::Int64,
+                false,
+            ),
+            Field::new("value", DataType::Int64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3])),
+                Arc::new(Int64Array::from(vec![10, 20, 30])),
+            ],
+        )
+        .unwrap();
+
+        let mut writer = topic::writer(context.clone(), handle, schema)
+            .await
+            .unwrap();
+        writer.write(batch).await.unwrap();
+        writer.finalize().await.unwrap();
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn import_lost_sequence(pool: sqlx::Pool<db::DatabaseType>) {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        let seq_locator = "test_sequence".parse::<types::SequenceLocator>().unwrap();
+        let mdata = marshal::JsonMetadataBlob::from(serde_json::json!({ "driver": "jon" }));
+        let seq_handle = sequence::try_create(&context, seq_locator.clone(), Some(mdata))
+            .await
+            .unwrap();
+        let seq_path = sequence::path_in_store(&context, &seq_handle)
+            .await
+            .unwrap();
+        let session = session::try_create(&context, seq_locator.clone(), None)
+            .await
+            .unwrap();
+        upload_topic(&context, &session, "test_sequence/camera").await;
+        upload_topic(&context, &session, "test_sequence/lidar").await;
+        session::finalize(&context, &session).await.unwrap();
+
+        // The finalized session is described by its manifest
+        let manifest =
+            session::read_manifest(&context, seq_path.path_session_manifest(session.uuid()))
+                .await
+                .unwrap();
+        assert_eq!(manifest.uuid, *session.uuid());
+        assert_eq!(manifest.sequence.locator, seq_locator);
+        assert!(manifest.sequence.user_metadata.is_some());
+        assert_eq!(manifest.topics.len(), 2);
+        let camera = manifest
+            .topics
+            .iter()
+            .find(|topic| {
+                topic.metadata.properties.resource_locator.to_string() == "test_sequence/camera"
+            })
+            .unwrap();
+        assert_eq!(
+            camera
+                .fields
+                .iter()
+                .map(|field| field.name.as_str())
+                .collect::<Vec<_>>(),
+            [params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP, "value"]
+        );
+        assert!(
+            camera
+                .objects
+                .iter()
+                .any(|object| object.path == "metadata.json")
+        );
+        for object in &camera.objects {
+            let path = camera.path_in_store.root().join(&object.path);
+            let bytes = context.store.read_bytes(path).await.unwrap();
+            assert_eq!(object.size, bytes.len() as u64);
+            assert_eq!(object.crc32, crc32fast::hash(&bytes));
+        }
+
+        // Unfinalized topics are not imported
+        let open = session::try_create(&context, seq_locator.clone(), None)
+            .await
+            .unwrap();
+        let ontology_metadata = types::TopicOntologyMetadata::new(
+            types::TopicOntologyProperties {
+                ontology_tag: "dummy".to_owned(),
+                serialization_format: types::Format::Default,
+                sort_key: None,
+                dedup_policy: types::DedupPolicy::None,
+                primary_key: None,
+            },
+            None,
+        );
+        let open_topic = topic::try_create(
+            &context,
+            "test_sequence/open".parse().unwrap(),
+            &open,
+            ontology_metadata,
+        )
+        .await
+        .unwrap();
+        let open_path = types::TopicPathInStore::new();
+        let manifest: Vec<u8> =
+            marshal::JsonTopicMetadata::from(topic::metadata(&context, &open_topic).await.unwrap())
+                .try_into()
+                .unwrap();
+        context
+            .store
+            .write_bytes(open_path.path_metadata(), manifest)
+            .await
+            .unwrap();
+
+        // The records are lost, only the store survives
+        sequence::delete(&context, seq_handle, types::allow_data_loss())
+            .await
+            .unwrap();
+
+        let report = import(&context, "", true).await.unwrap();
+        assert_eq!(report.imported.len(), 2);
+        assert_eq!(report.sequences_created, 1);
+        assert_eq!(report.sessions_created, 1);
+        assert_eq!(report.skipped.len(), 1);
+        let mut cx = context.db.connection();
+        assert!(
+            db::sequence_find_by_locator(&mut cx, &seq_locator)
+                .await
+                .is_err()
+        );
+
+        let report = import(&context, "", false).await.unwrap();
+        assert_eq!(report.imported.len(), 2);
+        assert_eq!(report.sequences_created, 1);
+        assert_eq!(report.sessions_created, 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].0, open_path.to_string());
+
+        let recovered = session::Handle::try_from_uuid(&context, session.uuid())
+            .await
+            .unwrap();
+        assert_eq!(recovered.locator().sequence, seq_locator);
+
+        // The sequence is restored from the session manifest
+        let sequence = db::sequence_find_by_locator(&mut cx, &seq_locator)
+            .await
+            .unwrap();
+        assert_eq!(sequence.path_in_store().to_string(), seq_path.to_string());
+        let user_mdata: serde_json::Value = sequence.user_metadata().unwrap().into();
+        assert_eq!(user_mdata["driver"], "jon");
+        assert!(
+            db::session_finalized(&mut cx, recovered.id())
+                .await
+                .unwrap()
+        );
+
+        let handle =
+            topic::Handle::try_from_locator(&context, "test_sequence/camera".parse().unwrap())
+                .await
+                .unwrap();
+        assert!(matches!(
+            topic::status(&context, &handle).await.unwrap(),
+            topic::Status::Finalized
+        ));
+        let info = topic::data_info(&context, &handle).await.unwrap();
+        assert_eq!(info.chunks_number, 1);
+        assert_eq!(
+            topic::chunks_stats(&context, &handle)
+                .await
+                .unwrap()
+                .total_row_count,
+            3
+        );
+        let metadata = topic::metadata(&context, &handle).await.unwrap();
+        assert_eq!(metadata.properties.session_uuid, *session.uuid());
+
+        // Registered topics are skipped
+        let report = import(&context, "", false).await.unwrap();
+        assert!(report.imported.is_empty());
+        assert_eq!(report.skipped.len(), 3);
+    }
+}
@@ -0,0 +1,270 @@
+//! State of the relay forwarding the finalized sessions of an edge instance to a central
+//! instance.
+//!
+//! Sessions are relayed one at a time, in completion order. Each attempt records the
+//! session created on the central instance, so that a session left incomplete by an
+//! interrupted attempt is deleted before retrying. Sessions conflicting with the data of
+//! the central instance are rejected and never retried. Only the topics selected by the
+//! [`types::RelayTopicFilter`] are relayed.
+//!
+//! The sequences of an edge instance are relayed under a namespace, the central sequence
+//! of `my_sequence` being `<namespace>@my_sequence`, so that the sequences uploaded by
+//! different edge instances do not collide.
+//!
+//! Locators created independently on the central instance can still conflict with the
+//! relayed ones, conflicts are resolved according to the [`types::RelayConflictPolicy`]
+//! and notified on the local sequence or topic.
+use crate::{Context, sequence, session, topic};
+use mosaicod_core::{self as core, error::PublicResult as Result, types};
+use mosaicod_db as db;
+
+/// Separator between the namespace and the name of the relayed sequences.
+pub const NAMESPACE_SEPARATOR: char = '@';
+
+/// Checks that `namespace` can prefix the locators of the relayed sequences.
+pub fn check_namespace(namespace: &str) -> Result<()> {
+    if namespace.contains(NAMESPACE_SEPARATOR)
+        || namespace.parse::<types::SequenceLocator>().is_err()
+    {
+        Err(core::Error::bad_locator(namespace.to_owned()))?;
+    }
+    Ok(())
+}
+
+/// Returns the locator of `sequence` on the central instance.
+pub fn remote_sequence(
+    namespace: &str,
+    sequence: &types::SequenceLocator,
+) -> Result<types::SequenceLocator> {
+    Ok(format!("{namespace}{NAMESPACE_SEPARATOR}{sequence}").parse()?)
+}
+
+/// Returns the locator of a relayed resource renamed after a conflict, e.g. `edge@run_1`.
+pub fn renamed(locator: &str, attempt: u32) -> String {
+    format!("{locator}_{attempt}")
+}
+
+/// Returns the central sequence receiving the sessions of `handle`, `None` if no session of
+/// the sequence was relayed yet.
+pub async fn relayed_sequence(
+    context: &Context,
+    namespace: &str,
+    handle: &sequence::Handle,
+) -> Result<Option<types::SequenceLocator>> {
+    let mut cx = context.db.connection();
+
+    let Some(record) = db::relay_sequence_find(&mut cx, handle.id()).await? else {
+        return Ok(None);
+    };
+
+    Ok(Some(match record.remote_locator() {
+        Some(locator) => locator.parse()?,
+        None => remote_sequence(namespace, handle.locator())?,
+    }))
+}
+
+/// Records the central sequence receiving the sessions of `handle`.
+pub async fn set_relayed_sequence(
+    context: &Context,
+    handle: &sequence::Handle,
+    remote_locator: &types::SequenceLocator,
+) -> Result<()> {
+    let mut cx = context.db.connection();
+    db::relay_sequence_set(&mut cx, handle.id(), &remote_locator.to_string()).await?;
+    Ok(())
+}
+
+/// Notifies on the local sequence that `remote_locator` already exists on the central
+/// instance, see [`types::NotificationPayload::relay_conflict`].
+pub async fn notify_sequence_conflict(
+    context: &Context,
+    handle: &sequence::Handle,
+    remote_locator: &str,
+    policy: types::RelayConflictPolicy,
+    relayed_as: Option<&str>,
+) -> Result<()> {
+    let (ntype, payload) = conflict_payload(remote_locator, policy, relayed_as);
+    let msg = payload.render();
+    sequence::notify(
+        context,
+        handle,
+        ntype,
+        ntype.default_severity(),
+        payload,
+        msg,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Notifies on the local topic that `remote_locator` already exists on the central
+/// instance, see [`types::NotificationPayload::relay_conflict`].
+pub async fn notify_topic_conflict(
+    context: &Context,
+    handle: &topic::Handle,
+    remote_locator: &str,
+    policy: types::RelayConflictPolicy,
+    relayed_as: Option<&str>,
+) -> Result<()> {
+    let (ntype, payload) = conflict_payload(remote_locator, policy, relayed_as);
+    let msg = payload.render();
+    topic::notify(
+        context,
+        handle,
+        ntype,
+        ntype.default_severity(),
+        payload,
+        msg,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Conflicts leaving the data unrelayed are errors, the resolved ones are alerts.
+fn conflict_payload(
+    remote_locator: &str,
+    policy: types::RelayConflictPolicy,
+    relayed_as: Option<&str>,
+) -> (types::NotificationType, types::NotificationPayload) {
+    let ntype = match relayed_as {
+        Some(_) => types::NotificationType::Alert,
+        None => types::NotificationType::Error,
+    };
+    (
+        ntype,
+        types::NotificationPayload::relay_conflict(remote_locator, policy, relayed_as),
+    )
+}
+
+/// Finalized session waiting to be relayed.
+pub struct PendingSession {
+    pub handle: session::Handle,
+    /// Session created on the central instance by the previous attempt
+    pub remote_locator: Option<String>,
+    /// Number of failed attempts
+    pub attempts: u32,
+}
+
+/// Returns at most `limit` finalized sessions to relay, in completion order.
+pub async fn pending(context: &Context, limit: usize) -> Result<Vec<PendingSession>> {
+    let mut cx = context.db.connection();
+
+    let mut pending = Vec::new();
+    for record in db::relay_session_find_pending(&mut cx, limit as i64).await? {
+        let state = db::relay_session_find(&mut cx, record.session_id).await?;
+        pending.push(PendingSession {
+            handle: session::Handle::new(record.locator(), record.session_id, record.uuid()),
+            remote_locator: state
+                .as_ref()
+                .and_then(|state| state.remote_locator().map(ToOwned::to_owned)),
+            attempts: state.map(|state| state.attempts()).unwrap_or_default(),
+        });
+    }
+
+    Ok(pending)
+}
+
+/// Returns the topics created by the session and selected by `filter`, or `None` if the
+/// session also upserted rows into selected topics created by other sessions, which can
+/// not be relayed.
+pub async fn topics(
+    context: &Context,
+    handle: &session::Handle,
+    filter: &types::RelayTopicFilter,
+) -> Result<Option<Vec<topic::Handle>>> {
+    let mut cx = context.db.connection();
+
+    let selected = |record: &db::TopicRecord| {
+        filter.selects(&record.locator().to_string(), &record.ontology_tag)
+    };
+
+    let upserted: Vec<i32> = db::session_find_all_upserts(&mut cx, handle.id())
+        .await?
+        .into_iter()
+        .map(|upsert| upsert.topic_id)
+        .collect();
+    if !upserted.is_empty()
+        && db::topic_find_by_ids(&mut cx, &upserted)
+            .await?
+            .iter()
+            .any(selected)
+    {
+        return Ok(None);
+    }
+
+    let topics = session::topic_records(&mut cx, handle.uuid()).await?;
+    Ok(Some(
+        topics
+            .into_iter()
+            .filter(selected)
+            .map(|record| {
+                topic::Handle::new(
+                    record.locator(),
+                    record.topic_id,
+                    record.uuid(),
+                    record.path_in_store(),
+                )
+            })
+            .collect(),
+    ))
+}
+
+/// Records the session created on the central instance by the running attempt, `None` once
+/// it has been deleted.
+pub async fn set_remote_locator(
+    context: &Context,
+    handle: &session::Handle,
+    remote_locator: Option<&str>,
+) -> Result<()> {
+    let mut cx = context.db.connection();
+    db::relay_session_set_remote_locator(&mut cx, handle.id(), remote_locator).await?;
+    Ok(())
+}
+
+/// Records a failed attempt, `rejected` sessions are not retried.
+pub async fn fail(
+    context: &Context,
+    handle: &session::Handle,
+    error: &str,
+    rejected: bool,
+) -> Result<()> {
+    let mut cx = context.db.connection();
+    db::relay_session_fail(&mut cx, handle.id(), error, rejected).await?;
+    Ok(())
+}
+
+/// Marks the session as relayed.
+pub async fn complete(context: &Context, handle: &session::Handle) -> Result<()> {
+    let mut cx = context.db.connection();
+    db::relay_session_complete(&mut cx, handle.id()).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn namespaced_sequence() {
+        let sequence: types::SequenceLocator = "run_1".parse().unwrap();
+        assert_eq!(
+            remote_sequence("edge_01", &sequence).unwrap().to_string(),
+            "edge_01@run_1"
+        );
+
+        assert!(check_namespace("edge_01").is_ok());
+        assert!(check_namespace("").is_err());
+        assert!(check_namespace("edge@01").is_err());
+        assert!(check_namespace("edge/01").is_err());
+    }
+
+    #[test]
+    fn renamed_locator() {
+        assert_eq!(renamed("edge_01@run_1", 2), "edge_01@run_1_2");
+        assert!(
+            renamed("edge_01@run", 1)
+                .parse::<types::SequenceLocator>()
+                .is_ok()
+        );
+    }
+}
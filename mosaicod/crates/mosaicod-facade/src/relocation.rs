@@ -0,0 +1,515 @@
+//! Relocation of the root folders of sequences and topics after a change of the store
+//! layout.
+//!
+//! The layout configured when a resource is created decides the path of its root folder.
+//! When the layout changes, the folders of the existing resources are moved online, one
+//! resource at a time:
+//!
+//! 1. the relocation is recorded in the database, so that it can be resumed if interrupted;
+//! 2. with the resource locked, the objects are copied backend-side to the new folder and
+//!    the references to the folder are updated in the same transaction;
+//! 3. in a later pass, when the requests started before the relocation have completed, the
+//!    objects in the old folder are deleted together with the relocation record.
+//!
+//! Every step can be repeated, so an interrupted relocation is completed by the next pass.
+use super::Context;
+use log::{debug, trace};
+use mosaicod_core::{self as core, error::PublicResult as Result, params, types};
+use mosaicod_db as db;
+
+/// Returns the store layout configured for the new resources.
+pub fn configured_layout() -> Result<types::StoreLayout> {
+    let param = &params::params().store_layout;
+    Ok(param.value.parse().map_err(|e: std::io::Error| {
+        core::Error::invalid_configuration(param.env.to_owned(), e.to_string())
+    })?)
+}
+
+/// Outcome of a relocation pass.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    /// Resources whose folder was moved
+    pub relocated: usize,
+    /// Relocations completed by deleting the old folder
+    pub completed: usize,
+    /// Resources not relocated since their data is being updated
+    pub skipped: usize,
+}
+
+impl Stats {
+    fn processed(&self) -> usize {
+        self.relocated + self.completed + self.skipped
+    }
+}
+
+/// Runs a relocation pass, processing at most `limit` resources.
+///
+/// The relocations pending from previous passes are completed first, then the folders of
+/// the sequences and finalized topics not stored according to the layout of the context are
+/// moved.
+pub async fn relocate(context: &Context, limit: usize) -> Result<Stats> {
+    let layout = context.store_layout;
+    let mut stats = Stats::default();
+
+    let mut cx = context.db.connection();
+    let pending = db::store_relocation_find_page(&mut cx, limit as i64).await?;
+    for record in pending {
+        resume(context, &record, &mut stats).await?;
+    }
+
+    let mut after = None;
+    while stats.processed() < limit {
+        let sequences = db::sequence_find_page_by_id(&mut cx, after, limit as i64).await?;
+        let Some(last) = sequences.last() else {
+            break;
+        };
+        after = Some(last.sequence_id);
+
+        for sequence in sequences {
+            if stats.processed() >= limit {
+                break;
+            }
+            let source = sequence.path_in_store();
+            let Some(target) = source.relocated(layout) else {
+                continue;
+            };
+            let record = db::store_relocation_create(
+                &mut cx,
+                types::ResourceKind::Sequence,
+                sequence.sequence_id,
+                &source.to_string(),
+                &target.to_string(),
+            )
+            .await?;
+            resume(context, &record, &mut stats).await?;
+        }
+    }
+
+    let mut after = None;
+    while stats.processed() < limit {
+        let topics = db::topic_find_finalized_page(&mut cx, after, limit as i64).await?;
+        let Some(last) = topics.last() else {
+            break;
+        };
+        after = Some(last.topic_id);
+
+        for topic in topics {
+            if stats.processed() >= limit {
+                break;
+            }
+            let Some(source) = topic.path_in_store() else {
+                continue;
+            };
+            let Some(target) = source.relocated(layout, &topic.ontology_tag) else {
+                continue;
+            };
+            let record = db::store_relocation_create(
+                &mut cx,
+                types::ResourceKind::Topic,
+                topic.topic_id,
+                &source.to_string(),
+                &target.to_string(),
+            )
+            .await?;
+            resume(context, &record, &mut stats).await?;
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Current state of a recorded relocation.
+enum State {
+    /// The resource references the old folder, the objects have to be moved
+    Pending,
+    /// The resource references the new folder, the old folder has to be deleted
+    Moved,
+    /// The resource has been deleted or moved elsewhere, the new folder has to be deleted
+    Abandoned,
+}
+
+async fn resume(
+    context: &Context,
+    record: &db::StoreRelocationRecord,
+    stats: &mut Stats,
+) -> Result<()> {
+    trace!(
+        "resuming relocation from `{}` to `{}`",
+        record.source_path(),
+        record.target_path()
+    );
+
+    let mut tx = context.db.transaction().await?;
+    let state = match record.resource_kind() {
+        Some(types::ResourceKind::Sequence) => lock_sequence(&mut tx, record).await?,
+        Some(types::ResourceKind::Topic) => lock_topic(&mut tx, record).await?,
+        _ => State::Abandoned,
+    };
+
+    match state {
+        State::Pending => {
+            if record.resource_kind() == Some(types::ResourceKind::Topic)
+                && db::topic_upsert_upload_in_progress(&mut tx, record.resource_id()).await?
+            {
+                debug!("topic folder `{}` is being updated", record.source_path());
+                stats.skipped += 1;
+                return Ok(());
+            }
+
+            context
+                .store
+                .copy_recursive(record.source_path(), record.target_path())
+                .await?;
+
+            match record.resource_kind() {
+                Some(types::ResourceKind::Sequence) => {
+                    db::sequence_update_path_in_store(
+                        &mut tx,
+                        record.resource_id(),
+                        record.target_path().to_owned().into(),
+                    )
+                    .await?;
+                }
+                _ => {
+                    db::topic_update_path_in_store(
+                        &mut tx,
+                        record.resource_id(),
+                        record.target_path().to_owned().into(),
+                    )
+                    .await?;
+                    db::chunk_update_data_file_prefix(
+                        &mut tx,
+                        record.resource_id(),
+                        &format!("{}/", record.source_path()),
+                        &format!("{}/", record.target_path()),
+                    )
+                    .await?;
+                }
+            }
+            tx.commit().await?;
+
+            debug!(
+                "folder `{}` moved to `{}`",
+                record.source_path(),
+                record.target_path()
+            );
+            stats.relocated += 1;
+        }
+        State::Moved => {
+            tx.commit().await?;
+
+            context.store.delete_recursive(record.source_path()).await?;
+            let mut cx = context.db.connection();
+            db::store_relocation_delete(&mut cx, record.id()).await?;
+            stats.completed += 1;
+        }
+        State::Abandoned => {
+            tx.commit().await?;
+
+            context.store.delete_recursive(record.target_path()).await?;
+            let mut cx = context.db.connection();
+            db::store_relocation_delete(&mut cx, record.id()).await?;
+            stats.completed += 1;
+        }
+    }
+
+    Ok(())
+}
+
+async fn lock_sequence(tx: &mut db::Tx<'_>, record: &db::StoreRelocationRecord) -> Result<State> {
+    match db::sequence_lock(tx, record.resource_id()).await {
+        Err(db::Error::NotFound) => return Ok(State::Abandoned),
+        res => res?,
+    }
+    let path = db::sequence_find_by_id(tx, record.resource_id())
+        .await?
+        .path_in_store()
+        .to_string();
+    Ok(state_of(record, Some(path)))
+}
+
+async fn lock_topic(tx: &mut db::Tx<'_>, record: &db::StoreRelocationRecord) -> Result<State> {
+    match db::topic_lock(tx, record.resource_id()).await {
+        Err(db::Error::NotFound) => return Ok(State::Abandoned),
+        res => res?,
+    }
+    let path = db::topic_find_by_id(tx, record.resource_id())
+        .await?
+        .path_in_store()
+        .map(|path| path.to_string());
+    Ok(state_of(record, path))
+}
+
+fn state_of(record: &db::StoreRelocationRecord, path: Option<String>) -> State {
+    match path {
+        Some(path) if path == record.source_path() => State::Pending,
+        Some(path) if path == record.target_path() => State::Moved,
+        _ => State::Abandoned,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sequence, session, topic};
+    use arrow::array::{Int64Array, RecordBatch};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use mosaicod_query as query;
+    use mosaicod_store as store;
+    use std::sync::Arc;
+
+    fn test_context(pool: sqlx::Pool<db::DatabaseType>) -> Context {
+        let database = db::testing::Database::new(pool);
+        let store = Arc::new(store::Store::in_memory());
+        let ts_gw = Arc::new(query::TimeseriesEngine::try_new(store.clone(), 0).unwrap());
+
+        Context::new(store, (*database).clone(), ts_gw)
+    }
+
+    /// Uploads a topic, returns its id.
+    async fn upload_topic(context: &Context, locator: &str) -> i32 {
+        let locator: types::TopicLocator = locator.parse().unwrap();
+        let session = session::try_create(context, locator.sequence.clone(), None)
+            .await
+            .unwrap();
+        let ontology_metadata = types::TopicOntologyMetadata::new(
+            types::TopicOntologyProperties {
+                ontology_tag: "dummy".to_owned(),
+                serialization_format: types::Format::Default,
+                sort_key: None,
+                dedup_policy: types::DedupPolicy::None,
+                primary_key: None,
+            },
+            None,
+        );
+        let handle = topic::try_create(context, locator.clone(), &session, ontology_metadata)
+            .await
+            .unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+                DataType::Int64,
+                false,
+            ),
+            Field::new("value", DataType::Int64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3])),
+                Arc::new(Int64Array::from(vec![10, 20, 30])),
+            ],
+        )
+        .unwrap();
+
+        let mut writer = topic::writer(context.clone(), handle, schema)
+            .await
+            .unwrap();
+        writer.write(batch).await.unwrap();
+        writer.finalize().await.unwrap();
+
+        let mut cx = context.db.connection();
+        let record = db::topic_find_by_locator(&mut cx, &locator).await.unwrap();
+
+        // Chunks are recorded by the endpoint receiving the data
+        let path = record.path_in_store().unwrap();
+        for file in context
+            .store
+            .list(path.session_staging_folder_path(session.uuid()), None)
+            .await
+            .unwrap()
+        {
+            db::chunk_create(&mut cx, &db::ChunkRecord::new(record.topic_id, file, 0, 3))
+                .await
+                .unwrap();
+        }
+
+        session::finalize(context, &session).await.unwrap();
+
+        record.topic_id
+    }
+
+    async fn topic_path(context: &Context, topic_id: i32) -> types::TopicPathInStore {
+        let mut cx = context.db.connection();
+        db::topic_find_by_id(&mut cx, topic_id)
+            .await
+            .unwrap()
+            .path_in_store()
+            .unwrap()
+    }
+
+    async fn row_count(context: &Context, path: &types::TopicPathInStore) -> usize {
+        context
+            .timeseries_querier
+            .read(path.data_folder_path(), types::Format::Default, None)
+            .await
+            .unwrap()
+            .count()
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn relocate_to_daily_layout(pool: sqlx::Pool<db::DatabaseType>) {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        let seq_locator = "test_sequence".parse::<types::SequenceLocator>().unwrap();
+        sequence::try_create(&context, seq_locator, None)
+            .await
+            .unwrap();
+        let topic_id = upload_topic(&context, "test_sequence/topic").await;
+        let flat_path = topic_path(&context, topic_id).await;
+        assert!(!flat_path.to_string().contains('/'));
+
+        let context = context.with_store_layout(types::StoreLayout::Daily);
+
+        // Folders are moved, the old ones are kept for the requests in progress
+        let stats = relocate(&context, 10).await.unwrap();
+        assert_eq!(stats.relocated, 2);
+        assert_eq!(stats.completed, 0);
+
+        let daily_path = topic_path(&context, topic_id).await;
+        assert_eq!(
+            flat_path
+                .relocated(types::StoreLayout::Daily, "dummy")
+                .unwrap()
+                .to_string(),
+            daily_path.to_string()
+        );
+        assert_eq!(row_count(&context, &daily_path).await, 3);
+        assert!(
+            !context
+                .store
+                .list(flat_path.root(), None)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let mut cx = context.db.connection();
+        let chunks = db::chunk_find_all_by_topic(&mut cx, topic_id)
+            .await
+            .unwrap();
+        assert!(!chunks.is_empty());
+        for chunk in &chunks {
+            assert!(chunk.data_file().starts_with(daily_path.root()));
+        }
+
+        let sequence = db::sequence_find_by_locator(&mut cx, &"test_sequence".parse().unwrap())
+            .await
+            .unwrap();
+        assert!(
+            types::StoreLayout::Daily
+                .relocate(&sequence.path_in_store().to_string())
+                .is_none()
+        );
+
+        // The next pass deletes the old folders
+        let stats = relocate(&context, 10).await.unwrap();
+        assert_eq!(stats.relocated, 0);
+        assert_eq!(stats.completed, 2);
+        assert!(
+            context
+                .store
+                .list(flat_path.root(), None)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            db::store_relocation_find_page(&mut cx, 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        assert_eq!(relocate(&context, 10).await.unwrap(), Stats::default());
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn relocate_resume(pool: sqlx::Pool<db::DatabaseType>) {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        let seq_locator = "test_sequence".parse::<types::SequenceLocator>().unwrap();
+        sequence::try_create(&context, seq_locator, None)
+            .await
+            .unwrap();
+        let topic_id = upload_topic(&context, "test_sequence/topic").await;
+        let deleted_id = upload_topic(&context, "test_sequence/deleted").await;
+        let flat_path = topic_path(&context, topic_id).await;
+        let daily_path = flat_path
+            .relocated(types::StoreLayout::Daily, "dummy")
+            .unwrap();
+
+        // Relocation interrupted after copying some objects
+        let mut cx = context.db.connection();
+        db::store_relocation_create(
+            &mut cx,
+            types::ResourceKind::Topic,
+            topic_id,
+            &flat_path.to_string(),
+            &daily_path.to_string(),
+        )
+        .await
+        .unwrap();
+        context
+            .store
+            .copy_recursive(flat_path.path_metadata(), daily_path.path_metadata())
+            .await
+            .unwrap();
+
+        // Relocation of a topic deleted after copying its objects
+        let deleted_path = topic_path(&context, deleted_id).await;
+        let deleted_target = deleted_path
+            .relocated(types::StoreLayout::Daily, "dummy")
+            .unwrap();
+        db::store_relocation_create(
+            &mut cx,
+            types::ResourceKind::Topic,
+            deleted_id,
+            &deleted_path.to_string(),
+            &deleted_target.to_string(),
+        )
+        .await
+        .unwrap();
+        context
+            .store
+            .copy_recursive(deleted_path.root(), deleted_target.root())
+            .await
+            .unwrap();
+        let deleted =
+            topic::Handle::try_from_locator(&context, "test_sequence/deleted".parse().unwrap())
+                .await
+                .unwrap();
+        topic::delete(&context, deleted, types::allow_data_loss())
+            .await
+            .unwrap();
+
+        // Pending relocations are processed first
+        let context = context.clone().with_store_layout(types::StoreLayout::Daily);
+        let stats = relocate(&context, 2).await.unwrap();
+        assert_eq!(stats.relocated, 1);
+        assert_eq!(stats.completed, 1);
+
+        assert_eq!(
+            topic_path(&context, topic_id).await.to_string(),
+            daily_path.to_string()
+        );
+        assert_eq!(row_count(&context, &daily_path).await, 3);
+        assert!(
+            context
+                .store
+                .list(deleted_target.root(), None)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let pending = db::store_relocation_find_page(&mut cx, 10).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].resource_id(), topic_id);
+    }
+}
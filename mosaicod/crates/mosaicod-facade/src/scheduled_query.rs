@@ -0,0 +1,194 @@
+//! Scheduled queries are queries evaluated periodically, following a cron schedule.
+//!
+//! When the number of topics matched by an evaluation satisfies the condition of the
+//! scheduled query, an [`types::NotificationType::Alert`] notification is created for each
+//! matching topic, effectively providing alerting rules over the recorded data.
+use super::{Context, Query, query_view};
+use log::{debug, trace};
+use mosaicod_core::{self as core, error::PublicResult as Result, types};
+use mosaicod_db as db;
+use mosaicod_marshal as marshal;
+
+/// Query definition as provided by the user, see [`marshal::query_filter_from_serde_value`].
+pub type ScheduledQuery = types::ScheduledQuery<serde_json::Value>;
+
+/// Outcome of the evaluation of a scheduled query.
+#[derive(Debug)]
+pub struct Evaluation {
+    /// Number of topics matched by the query
+    pub match_count: u64,
+    /// True if the condition was satisfied and the matching topics notified
+    pub triggered: bool,
+}
+
+/// Creates a new scheduled query, first evaluated at the next run of its schedule.
+pub async fn create(
+    context: &Context,
+    name: String,
+    schedule: &str,
+    query: serde_json::Value,
+    condition: types::MatchCondition,
+) -> Result<ScheduledQuery> {
+    if name.is_empty() {
+        Err(core::Error::bad_request(
+            "scheduled query name can't be empty".to_owned(),
+        ))?;
+    }
+
+    let schedule = types::CronSchedule::try_new(schedule)
+        .map_err(|e| core::Error::bad_request(e.to_string()))?;
+
+    // Ensure the query is valid before storing it
+    let resolved = query_view::resolve(context, query.clone(), None).await?;
+    marshal::query_filter_from_serde_value(resolved)?;
+
+    let now = types::Timestamp::now();
+    let scheduled_query = ScheduledQuery {
+        name,
+        // Schedules are validated to run at least once
+        next_run_at: schedule.next_after(now).unwrap(),
+        schedule,
+        query,
+        condition,
+        created_at: now,
+        last_run_at: None,
+        last_match_count: None,
+    };
+
+    let mut cx = context.db.connection();
+    match db::scheduled_query_create(&mut cx, &scheduled_query).await {
+        Ok(scheduled_query) => Ok(scheduled_query),
+        Err(db::Error::AlreadyExists) => Err(core::Error::already_exists(format!(
+            "scheduled query `{}`",
+            scheduled_query.name
+        )))?,
+        Err(e) => Err(e)?,
+    }
+}
+
+/// Returns all the scheduled queries, sorted by name.
+pub async fn list(context: &Context) -> Result<Vec<ScheduledQuery>> {
+    let mut cx = context.db.connection();
+    Ok(db::scheduled_query_find_all(&mut cx).await?)
+}
+
+pub async fn delete(context: &Context, name: &str) -> Result<()> {
+    let mut cx = context.db.connection();
+    match db::scheduled_query_delete(&mut cx, name).await {
+        Ok(()) => Ok(()),
+        Err(db::Error::NotFound) => Err(not_found(name))?,
+        Err(e) => Err(e)?,
+    }
+}
+
+/// Evaluates a scheduled query immediately, regardless of its schedule.
+pub async fn run(context: &Context, name: &str) -> Result<Evaluation> {
+    let mut cx = context.db.connection();
+    let scheduled_query = match db::scheduled_query_find_by_name(&mut cx, name).await {
+        Ok(scheduled_query) => scheduled_query,
+        Err(db::Error::NotFound) => Err(not_found(name))?,
+        Err(e) => Err(e)?,
+    };
+
+    evaluate(context, &scheduled_query).await
+}
+
+/// Evaluates the scheduled queries whose run is due, returning the outcome of each
+/// evaluation.
+///
+/// Each run is claimed before being evaluated, so that it is evaluated once even if
+/// multiple instances share the same database.
+pub async fn run_due(context: &Context) -> Result<Vec<(String, Result<Evaluation>)>> {
+    let now = types::Timestamp::now();
+
+    let mut cx = context.db.connection();
+    let due = db::scheduled_query_find_due(&mut cx, now).await?;
+
+    let mut evaluations = Vec::with_capacity(due.len());
+    for scheduled_query in due {
+        // Runs missed while the daemon was down are not recovered
+        let Some(next_run_at) = scheduled_query.schedule.next_after(now) else {
+            continue;
+        };
+
+        let claimed = db::scheduled_query_claim_run(
+            &mut cx,
+            &scheduled_query.name,
+            scheduled_query.next_run_at,
+            next_run_at,
+        )
+        .await?;
+        if !claimed {
+            trace!("run of `{}` already claimed", scheduled_query.name);
+            continue;
+        }
+
+        let evaluation = evaluate(context, &scheduled_query).await;
+        evaluations.push((scheduled_query.name, evaluation));
+    }
+
+    Ok(evaluations)
+}
+
+async fn evaluate(context: &Context, scheduled_query: &ScheduledQuery) -> Result<Evaluation> {
+    let run_at = types::Timestamp::now();
+
+    let query = query_view::resolve(context, scheduled_query.query.clone(), None).await?;
+    let filter = marshal::query_filter_from_serde_value(query)?;
+    let groups = Query::query(
+        filter,
+        context.timeseries_querier.clone(),
+        context.db.clone(),
+        &context.query_limits.global_limits(),
+    )
+    .await?;
+
+    let topics: Vec<types::TopicLocator> = Vec::<types::SequenceTopicGroup>::from(groups)
+        .into_iter()
+        .flat_map(|group| group.into_parts().1)
+        .collect();
+    let match_count = topics.len() as u64;
+    let triggered = scheduled_query.condition.matches(match_count);
+
+    debug!(
+        "scheduled query `{}` matched {} topics (triggered: {})",
+        scheduled_query.name, match_count, triggered
+    );
+
+    let mut tx = context.db.transaction().await?;
+
+    if triggered {
+        let payload = types::NotificationPayload::scheduled_query_triggered(
+            &scheduled_query.name,
+            match_count,
+            scheduled_query.condition.op_name(),
+            scheduled_query.condition.value,
+        );
+        let msg = payload.render();
+        for topic in &topics {
+            let record = db::topic_find_by_locator(&mut tx, topic).await?;
+            let notification = db::TopicNotificationRecord::new(
+                record.topic_id,
+                types::NotificationType::Alert,
+                types::NotificationSeverity::Warning,
+                payload.clone(),
+                msg.clone(),
+            );
+            db::topic_notification_create(&mut tx, &notification).await?;
+        }
+    }
+
+    db::scheduled_query_update_last_run(&mut tx, &scheduled_query.name, run_at, match_count)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Evaluation {
+        match_count,
+        triggered,
+    })
+}
+
+fn not_found(name: &str) -> core::Error {
+    core::Error::not_found(format!("scheduled query `{name}`"))
+}
@@ -1,9 +1,10 @@
 //! This module provides the high-level API for managing a persistent **Sequence**
 //! entity within the application.
 
-use super::{Context, session, topic};
+use super::{CheckedAttestation, Context, session, topic};
 use log::trace;
 use mosaicod_core::{
+    self as core,
     error::PublicResult as Result,
     types::{self, SequencePathInStore},
 };
@@ -11,11 +12,18 @@ use mosaicod_db as db;
 use mosaicod_marshal as marshal;
 use std::path;
 
+/// Target of the audit entries about the legal hold of the sequences.
+pub(crate) const AUDIT_TARGET: &str = "mosaicod::audit";
+
 /// Define sequence metadata type contaning json user metadata
 type SequenceUserMetadata = marshal::JsonMetadataBlob;
 
 type SequenceMetadata = types::SequenceMetadata<marshal::JsonMetadataBlob>;
 
+/// Number of records read from the database at once when walking the topics or the
+/// sessions of a sequence.
+const PAGE_SIZE: i64 = 256;
+
 /// Handle containing sequence identifiers.
 /// It's used by all functions (except creation) in this module to indicate the sequence to operate on.
 pub struct Handle {
@@ -80,9 +88,28 @@ pub async fn try_create(
     context: &Context,
     locator: types::SequenceLocator,
     metadata: Option<SequenceUserMetadata>,
+) -> Result<Handle> {
+    try_create_with_options(context, locator, metadata, CreateOptions::default()).await
+}
+
+/// Optional settings of a sequence creation, see [`try_create_with_options`].
+#[derive(Default)]
+pub struct CreateOptions {
+    /// UUID of the new sequence, a random one is generated if not set.
+    pub uuid: Option<types::Uuid>,
+    /// Creation time of the new sequence, the current time if not set.
+    pub created_at: Option<types::Timestamp>,
+}
+
+/// Same as [`try_create`], with the additional settings in `options`.
+pub async fn try_create_with_options(
+    context: &Context,
+    locator: types::SequenceLocator,
+    metadata: Option<SequenceUserMetadata>,
+    options: CreateOptions,
 ) -> Result<Handle> {
     // 1. Creates a random name for the folder on Object Store and save metadata file (optional).
-    let path_in_store = SequencePathInStore::new();
+    let path_in_store = SequencePathInStore::new_with_layout(context.store_layout);
 
     if let Some(mdata) = &metadata {
         metadata_write_to_store(
@@ -98,6 +125,12 @@ pub async fn try_create(
 
     let mut record = db::SequenceRecord::new(locator.clone(), path_in_store.clone());
 
+    if let Some(uuid) = options.uuid {
+        record = record.with_uuid(uuid);
+    }
+    if let Some(created_at) = options.created_at {
+        record = record.with_creation_timestamp(created_at);
+    }
     if let Some(mdata) = metadata {
         record = record.with_user_metadata(mdata);
     }
@@ -113,22 +146,220 @@ pub async fn try_create(
     })
 }
 
-/// Retrieves all sequences from the database.
+/// Returns the sequences whose locator matches `pattern` among the next `limit` sequences
+/// starting with the prefix of the pattern, sorted by locator.
+///
+/// The page starts after `cursor`, the value returned along with the previous page. Pages
+/// may hold fewer than `limit` sequences, even none, while other pages follow.
+pub async fn find_by_pattern(
+    context: &Context,
+    pattern: &types::LocatorPattern,
+    cursor: Option<&str>,
+    limit: usize,
+) -> Result<Page> {
+    let mut page = page(context, pattern.prefix(), cursor, limit).await?;
+    page.handles
+        .retain(|handle| pattern.matches(&handle.locator.to_string()));
+    Ok(page)
+}
+
+/// Page of sequences returned by [`page`].
+pub struct Page {
+    pub handles: Vec<Handle>,
+    /// Cursor used to fetch the next page, `None` once all the sequences are returned.
+    pub cursor: Option<String>,
+}
+
+/// Returns at most `limit` sequences whose locator starts with `prefix`, sorted by locator.
 ///
-/// Returns a list of all available sequences as [`Handle`] objects.
-/// This is primarily used for catalog discovery operations.
-pub async fn all(context: &Context) -> Result<Vec<Handle>> {
+/// The page starts after `cursor`, the value returned along with the previous page.
+pub async fn page(
+    context: &Context,
+    prefix: &str,
+    cursor: Option<&str>,
+    limit: usize,
+) -> Result<Page> {
     let mut cx = context.db.connection();
-    let records = db::sequence_find_all(&mut cx).await?;
 
-    Ok(records
+    // One extra record is fetched to know if other pages follow
+    let mut records =
+        db::sequence_find_page(&mut cx, prefix, cursor, limit.saturating_add(1) as i64).await?;
+
+    let cursor = if records.len() > limit {
+        records.truncate(limit);
+        records.last().map(|record| record.locator().to_string())
+    } else {
+        None
+    };
+
+    Ok(Page {
+        handles: records
+            .into_iter()
+            .map(|record| Handle {
+                id: record.sequence_id,
+                uuid: record.uuid(),
+                locator: record.locator(),
+            })
+            .collect(),
+        cursor,
+    })
+}
+
+/// Page of the activity feed returned by [`activity_feed`].
+pub struct ActivityPage {
+    pub activities: Vec<types::Activity>,
+    /// Cursor used to fetch the next page, `None` once all the entries are returned.
+    pub cursor: Option<types::ActivityCursor>,
+    /// Cursor following the last entry of the page, used to resume the feed once new
+    /// entries are added.
+    pub end_cursor: types::ActivityCursor,
+}
+
+/// Returns at most `limit` entries of the activity feed of the sequence, merging its
+/// lifecycle events with the notifications of the sequence and of its topics.
+///
+/// Entries are sorted chronologically, starting from `since` or, if set, after the
+/// `cursor` returned along with the previous page.
+pub async fn activity_feed(
+    context: &Context,
+    handle: &Handle,
+    since: types::Timestamp,
+    cursor: Option<types::ActivityCursor>,
+    limit: usize,
+) -> Result<ActivityPage> {
+    // Entries are read from a single snapshot, otherwise a session finalized while reading
+    // could be reported with its topics finalized but not yet itself, and the cursor
+    // returned after the topics would skip it
+    let mut cx = context.db.snapshot().await?;
+
+    let sequence = db::sequence_find_by_id(&mut cx, handle.id()).await?;
+    let mut activities = vec![types::Activity {
+        timestamp: sequence.creation_timestamp(),
+        subject: handle.locator.to_string(),
+        event: types::ActivityEvent::SequenceCreated,
+    }];
+
+    for session in session_records(&mut cx, handle.id()).await? {
+        let subject = session.uuid().to_string();
+        if let Some(timestamp) = session.completion_timestamp() {
+            activities.push(types::Activity {
+                timestamp,
+                subject: subject.clone(),
+                event: types::ActivityEvent::SessionFinalized,
+            });
+        }
+        activities.push(types::Activity {
+            timestamp: session.creation_timestamp(),
+            subject,
+            event: types::ActivityEvent::SessionCreated,
+        });
+    }
+
+    for notification in db::sequence_notifications_find_by_sequence_id(&mut cx, handle.id()).await?
+    {
+        activities.push(types::Activity {
+            timestamp: notification.creation_timestamp(),
+            subject: handle.locator.to_string(),
+            event: types::ActivityEvent::Notification {
+                notification_type: notification.notification_type(),
+                severity: notification.severity(),
+                payload: notification.payload(),
+                msg: notification.msg,
+            },
+        });
+    }
+
+    for topic in topic_records(&mut cx, handle.id()).await? {
+        let locator = topic.locator();
+        let subject = locator.to_string();
+        if let Some(timestamp) = topic.completion_timestamp() {
+            activities.push(types::Activity {
+                timestamp,
+                subject: subject.clone(),
+                event: types::ActivityEvent::TopicFinalized,
+            });
+        }
+        activities.push(types::Activity {
+            timestamp: topic.creation_timestamp(),
+            subject: subject.clone(),
+            event: types::ActivityEvent::TopicCreated,
+        });
+
+        for watermark in db::topic_watermark_find_all(&mut cx, topic.topic_id).await? {
+            activities.push(types::Activity {
+                timestamp: watermark.creation_timestamp(),
+                subject: subject.clone(),
+                event: types::ActivityEvent::TopicWatermark {
+                    watermark: watermark.watermark(),
+                },
+            });
+        }
+
+        for notification in db::topic_notifications_find_by_locator(&mut cx, &locator).await? {
+            activities.push(types::Activity {
+                timestamp: notification.creation_timestamp(),
+                subject: subject.clone(),
+                event: types::ActivityEvent::Notification {
+                    notification_type: notification.notification_type(),
+                    severity: notification.severity(),
+                    payload: notification.payload(),
+                    msg: notification.msg,
+                },
+            });
+        }
+    }
+
+    // Ties are broken by event and subject, so that pages are stable between calls
+    activities.sort_by(|a, b| {
+        (a.timestamp, a.event.name(), &a.subject).cmp(&(b.timestamp, b.event.name(), &b.subject))
+    });
+
+    let (start, skip) = match cursor {
+        Some(cursor) => (cursor.timestamp, cursor.skip),
+        None => (since, 0),
+    };
+    // Only the entries sharing the timestamp of the cursor are skipped, so that the entries
+    // following it are still returned if some of the skipped ones moved in the meantime
+    let mut skipped = 0;
+    let mut activities: Vec<_> = activities
         .into_iter()
-        .map(|record| Handle {
-            id: record.sequence_id,
-            uuid: record.uuid(),
-            locator: record.locator(),
+        .skip_while(|activity| activity.timestamp < start)
+        .skip_while(|activity| {
+            let skip = activity.timestamp == start && skipped < skip;
+            skipped += usize::from(skip);
+            skip
         })
-        .collect())
+        .take(limit.saturating_add(1))
+        .collect();
+
+    let more = activities.len() > limit;
+    activities.truncate(limit);
+
+    let end_cursor = match activities.last().map(|activity| activity.timestamp) {
+        Some(timestamp) => {
+            // Entries of the page sharing the last timestamp, including the skipped ones
+            let returned = activities
+                .iter()
+                .filter(|activity| activity.timestamp == timestamp)
+                .count();
+            let skip = if timestamp == start {
+                skip + returned
+            } else {
+                returned
+            };
+            types::ActivityCursor { timestamp, skip }
+        }
+        None => types::ActivityCursor {
+            timestamp: start,
+            skip,
+        },
+    };
+
+    Ok(ActivityPage {
+        activities,
+        cursor: more.then_some(end_cursor),
+        end_cursor,
+    })
 }
 
 async fn metadata_write_to_store(
@@ -149,38 +380,74 @@ async fn metadata_write_to_store(
     Ok(())
 }
 
-/// Add a notification to the sequence
+/// Add a notification to the sequence, `msg` is the rendered text of the payload.
 pub async fn notify(
     context: &Context,
     handle: &Handle,
     ntype: types::NotificationType,
-    msg: String,
+    severity: types::NotificationSeverity,
+    payload: types::NotificationPayload,
+    msg: Option<String>,
 ) -> Result<types::Notification<types::SequenceLocator>> {
     let mut tx = context.db.transaction().await?;
 
     // Note: no need to check the sequence existence for it is already done internally
     // by the DB constraints checks on the foreign key.
-    let notification = db::SequenceNotificationRecord::new(handle.id(), ntype, Some(msg));
+    let notification =
+        db::SequenceNotificationRecord::new(handle.id(), ntype, severity, payload, msg);
     let notification = db::sequence_notification_create(&mut tx, &notification).await?;
 
     tx.commit().await?;
 
-    Ok(notification.into_notification(handle.locator.clone()))
+    let notification = notification.into_notification(handle.locator.clone());
+    context
+        .notification_dispatcher
+        .dispatch((&notification).into());
+    context.notification_bus.publish((&notification).into());
+
+    Ok(notification)
 }
 
-/// Returns a list of all notifications for the sequence
+/// Returns at most `limit` notifications of the sequence matching `filter`, sorted by
+/// creation time.
+///
+/// The page starts after `cursor`, the value returned along with the previous page.
 pub async fn notification_list(
     context: &Context,
     handle: &Handle,
-) -> Result<Vec<types::Notification<types::SequenceLocator>>> {
-    let mut trans = context.db.transaction().await?;
-    let notifications =
-        db::sequence_notifications_find_by_sequence_id(&mut trans, handle.id()).await?;
-    trans.commit().await?;
-    Ok(notifications
-        .into_iter()
-        .map(|n| n.into_notification(handle.locator.clone()))
-        .collect())
+    filter: &types::NotificationFilter,
+    cursor: Option<&types::NotificationCursor>,
+    limit: usize,
+) -> Result<types::NotificationPage<types::SequenceLocator>> {
+    let mut cx = context.db.connection();
+
+    // One extra record is fetched to know if other pages follow
+    let mut records = db::sequence_notifications_find_page(
+        &mut cx,
+        handle.id(),
+        filter,
+        cursor,
+        limit.saturating_add(1) as i64,
+    )
+    .await?;
+
+    let cursor = if records.len() > limit {
+        records.truncate(limit);
+        records.last().map(|record| types::NotificationCursor {
+            timestamp: record.creation_timestamp(),
+            uuid: record.uuid(),
+        })
+    } else {
+        None
+    };
+
+    Ok(types::NotificationPage {
+        notifications: records
+            .into_iter()
+            .map(|record| record.into_notification(handle.locator.clone()))
+            .collect(),
+        cursor,
+    })
 }
 
 /// Deletes all the notifications associated with the sequence
@@ -223,11 +490,87 @@ pub async fn metadata(context: &Context, handle: &Handle) -> Result<SequenceMeta
     Ok(sequence_metadata)
 }
 
+/// Updates the user metadata of a sequence, merging the top-level keys of `metadata` into
+/// the current ones if `merge` is set, replacing them otherwise.
+///
+/// Returns an error if the sequence is locked, that is if one of its sessions has been finalized.
+pub async fn update_user_metadata(
+    context: &Context,
+    handle: &Handle,
+    metadata: SequenceUserMetadata,
+    merge: bool,
+) -> Result<()> {
+    let mut tx = context.db.transaction().await?;
+
+    let record =
+        db::sequence_try_update_user_metadata(&mut tx, handle.id(), &metadata.into(), merge)
+            .await?
+            .ok_or_else(|| core::Error::sequence_locked(handle.locator().to_string()))?;
+
+    // The metadata file is rewritten before committing, so that it never lags behind
+    // the database
+    if let Some(mdata) = record.user_metadata() {
+        metadata_write_to_store(
+            context,
+            record.path_in_store().path_metadata().as_path(),
+            mdata,
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Returns the records of the topics of the sequence `sequence_id`, sorted by locator.
+///
+/// Records are read from the database one page at a time.
+pub(crate) async fn topic_records(
+    exe: &mut impl db::AsExec,
+    sequence_id: i32,
+) -> Result<Vec<db::TopicRecord>> {
+    let mut records = Vec::new();
+    loop {
+        let after = records
+            .last()
+            .map(|record: &db::TopicRecord| record.locator().to_string());
+        let page =
+            db::topic_find_page_by_sequence(exe, sequence_id, after.as_deref(), PAGE_SIZE).await?;
+        let done = page.len() < PAGE_SIZE as usize;
+        records.extend(page);
+        if done {
+            return Ok(records);
+        }
+    }
+}
+
+/// Returns the records of the sessions of the sequence `sequence_id`, sorted by id.
+///
+/// Records are read from the database one page at a time.
+async fn session_records(
+    exe: &mut impl db::AsExec,
+    sequence_id: i32,
+) -> Result<Vec<db::SessionRecord>> {
+    let mut records = Vec::new();
+    loop {
+        let after = records
+            .last()
+            .map(|record: &db::SessionRecord| record.session_id);
+        let page = db::sequence_find_sessions_page(exe, sequence_id, after, PAGE_SIZE).await?;
+        let done = page.len() < PAGE_SIZE as usize;
+        records.extend(page);
+        if done {
+            return Ok(records);
+        }
+    }
+}
+
 /// Returns the topic list for the given sequence
 pub async fn topic_list(context: &Context, handle: &Handle) -> Result<Vec<topic::Handle>> {
     let mut cx = context.db.connection();
 
-    Ok(db::sequence_find_all_topics(&mut cx, &handle.locator)
+    Ok(topic_records(&mut cx, handle.id())
         .await?
         .into_iter()
         .map(|record| {
@@ -241,19 +584,270 @@ pub async fn topic_list(context: &Context, handle: &Handle) -> Result<Vec<topic:
         .collect())
 }
 
+/// Page of topics returned by [`topic_page`] and [`topic::find_by_pattern`].
+pub struct TopicPage {
+    pub handles: Vec<topic::Handle>,
+    /// Cursor used to fetch the next page, `None` once all the topics are returned.
+    pub cursor: Option<String>,
+}
+
+/// Returns at most `limit` topics of the sequence, sorted by locator.
+///
+/// The page starts after `cursor`, the value returned along with the previous page.
+pub async fn topic_page(
+    context: &Context,
+    handle: &Handle,
+    cursor: Option<&str>,
+    limit: usize,
+) -> Result<TopicPage> {
+    let mut cx = context.db.connection();
+
+    // One extra record is fetched to know if other pages follow
+    let mut records = db::topic_find_page_by_sequence(
+        &mut cx,
+        handle.id(),
+        cursor,
+        limit.saturating_add(1) as i64,
+    )
+    .await?;
+
+    let cursor = if records.len() > limit {
+        records.truncate(limit);
+        records.last().map(|record| record.locator().to_string())
+    } else {
+        None
+    };
+
+    Ok(TopicPage {
+        handles: records
+            .into_iter()
+            .map(|record| {
+                topic::Handle::new(
+                    record.locator(),
+                    record.topic_id,
+                    record.uuid(),
+                    record.path_in_store(),
+                )
+            })
+            .collect(),
+        cursor,
+    })
+}
+
+/// Returns the root folder of the sequence in the store.
+pub async fn path_in_store(context: &Context, handle: &Handle) -> Result<SequencePathInStore> {
+    let mut cx = context.db.connection();
+    Ok(db::sequence_find_by_id(&mut cx, handle.id())
+        .await?
+        .path_in_store())
+}
+
+/// Returns the attestations of the sessions of the sequence, checked against their
+/// manifests. Sessions finalized without attestation are skipped.
+pub async fn attestations(context: &Context, handle: &Handle) -> Result<Vec<CheckedAttestation>> {
+    let sequence_path = path_in_store(context, handle).await?;
+
+    let sessions = session_list(handle, &mut context.db.connection()).await?;
+
+    let mut attestations = Vec::new();
+    for session_handle in &sessions {
+        if let Some(attestation) =
+            session::attestation(context, &sequence_path, session_handle).await?
+        {
+            attestations.push(attestation);
+        }
+    }
+
+    Ok(attestations)
+}
+
+/// Sets or releases the legal hold of a sequence, also when it is in the trash.
+///
+/// While the hold is set the sequence, its sessions and topics cannot be deleted, trashed
+/// or purged. `principal` is reported in the audit entry.
+pub async fn set_legal_hold(
+    context: &Context,
+    locator: &types::SequenceLocator,
+    legal_hold: bool,
+    principal: Option<&str>,
+) -> Result<()> {
+    let mut cx = context.db.connection();
+
+    let record = match db::sequence_find_by_locator(&mut cx, locator).await {
+        Err(db::Error::NotFound) => db::sequence_find_trashed_by_locator(&mut cx, locator).await?,
+        res => res?,
+    };
+    db::sequence_set_legal_hold(&mut cx, record.sequence_id, legal_hold).await?;
+
+    tracing::warn!(
+        target: AUDIT_TARGET,
+        sequence = %locator,
+        principal = principal.unwrap_or_default(),
+        legal_hold,
+        "legal hold updated"
+    );
+
+    Ok(())
+}
+
+/// Returns the compaction policy of the sequence, empty if the sequence follows the server
+/// configuration.
+pub async fn compaction_policy(
+    context: &Context,
+    handle: &Handle,
+) -> Result<types::CompactionPolicy> {
+    let mut cx = context.db.connection();
+
+    Ok(db::sequence_compaction_policy_find(&mut cx, handle.id())
+        .await?
+        .map(|record| record.policy())
+        .unwrap_or_default())
+}
+
+/// Sets the compaction policy of the upsert topics of the sequence, the settings not set are
+/// inherited from the server configuration. An empty policy restores the server
+/// configuration.
+pub async fn set_compaction_policy(
+    context: &Context,
+    handle: &Handle,
+    policy: &types::CompactionPolicy,
+) -> Result<()> {
+    let mut cx = context.db.connection();
+
+    if policy.is_empty() {
+        db::sequence_compaction_policy_delete(&mut cx, handle.id()).await?;
+    } else {
+        db::sequence_compaction_policy_upsert(
+            &mut cx,
+            &db::SequenceCompactionPolicyRecord::new(handle.id(), policy),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Compacts the finalized upsert topics of the sequence stored in more than one data file,
+/// regardless of the schedule of the compaction policy.
+///
+/// Returns the number of topics compacted, topics being upserted are skipped.
+pub async fn compact(context: &Context, handle: &Handle) -> Result<usize> {
+    let topics = {
+        let mut cx = context.db.connection();
+        topic_records(&mut cx, handle.id()).await?
+    };
+
+    let mut compacted = 0;
+    for record in topics {
+        let compactable = record.primary_key().is_some()
+            && record.completion_timestamp().is_some()
+            && record.info().is_some_and(|info| info.chunks_number > 1);
+        if !compactable {
+            continue;
+        }
+
+        let topic = topic::Handle::new(
+            record.locator(),
+            record.topic_id,
+            record.uuid(),
+            record.path_in_store(),
+        );
+        if topic::compact(context, &topic).await? {
+            compacted += 1;
+        } else {
+            trace!("topic `{}` is being updated", topic.locator());
+        }
+    }
+
+    Ok(compacted)
+}
+
+/// Locks the sequence until the end of the transaction, returning an error if it is under
+/// legal hold. The refused `operation` on `resource` is reported as an audit entry.
+///
+/// Holding the lock prevents the legal hold from being set while the operation completes.
+pub(crate) async fn lock_unless_held(
+    tx: &mut impl db::AsExec,
+    sequence_id: i32,
+    operation: &str,
+    resource: &(dyn std::fmt::Display + Sync),
+) -> Result<()> {
+    db::sequence_lock(tx, sequence_id).await?;
+
+    let record = db::sequence_find_by_id(tx, sequence_id).await?;
+    if record.legal_hold() {
+        audit_refused(operation, &record.locator(), resource);
+        Err(core::Error::legal_hold(record.locator().to_string()))?;
+    }
+
+    Ok(())
+}
+
+fn audit_refused(
+    operation: &str,
+    sequence: &types::SequenceLocator,
+    resource: &dyn std::fmt::Display,
+) {
+    tracing::warn!(
+        target: AUDIT_TARGET,
+        %sequence,
+        %resource,
+        operation,
+        "refused operation on sequence under legal hold"
+    );
+}
+
 /// Returns the session list associated with this sequence as vector of session UUIDs
 pub async fn session_list(
     handle: &Handle,
     exe: &mut impl db::AsExec,
 ) -> Result<Vec<session::Handle>> {
-    Ok(db::sequence_find_all_sessions(exe, &handle.locator)
+    Ok(session_records(exe, handle.id())
         .await?
         .into_iter()
         .map(|record| session::Handle::new(record.locator(), record.session_id, record.uuid()))
         .collect())
 }
 
-/// Deletes a sequence and all its associated sessions and topics from the database.
+/// Returns at most `limit` sessions of the sequence following `cursor`, sorted by creation
+/// time, along with the number of topics they created.
+///
+/// If `finalized` is set, only the finalized sessions (`true`) or the sessions still open
+/// (`false`) are returned.
+pub async fn session_page(
+    context: &Context,
+    handle: &Handle,
+    finalized: Option<bool>,
+    cursor: Option<&types::SessionCursor>,
+    limit: usize,
+) -> Result<types::SessionPage> {
+    let mut cx = context.db.connection();
+
+    // One extra record is fetched to know if other pages follow
+    let mut sessions = db::session_find_summaries_page_by_sequence(
+        &mut cx,
+        handle.id(),
+        finalized,
+        cursor,
+        limit.saturating_add(1) as i64,
+    )
+    .await?;
+
+    let cursor = if sessions.len() > limit {
+        sessions.truncate(limit);
+        sessions.last().map(|session| types::SessionCursor {
+            timestamp: session.created_at,
+            uuid: session.uuid.clone(),
+        })
+    } else {
+        None
+    };
+
+    Ok(types::SessionPage { sessions, cursor })
+}
+
+/// Deletes a sequence and all its associated sessions and topics from the database,
+/// without moving it to the trash.
 ///
 /// The [`types::DataLossToken`] is required since this function will lead to data loss.
 pub async fn delete(
@@ -261,15 +855,97 @@ pub async fn delete(
     handle: Handle,
     allow_data_loss: types::DataLossToken,
 ) -> Result<()> {
+    let mut tx = context.db.transaction().await?;
+    lock_unless_held(&mut tx, handle.id(), "sequence_delete", handle.locator()).await?;
+    db::sequence_delete_by_id(&mut tx, handle.id(), allow_data_loss).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Moves a sequence to the trash.
+///
+/// The sequence, its sessions and topics are hidden until the sequence is [`restore`]d or
+/// [`purge`]d, no data is deleted.
+pub async fn trash(context: &Context, handle: Handle) -> Result<()> {
+    let mut tx = context.db.transaction().await?;
+    lock_unless_held(&mut tx, handle.id(), "sequence_trash", handle.locator()).await?;
+    db::sequence_trash(&mut tx, handle.id(), types::Timestamp::now()).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Restores a sequence from the trash.
+///
+/// Returns an error if there is no sequence in the trash with the given locator.
+pub async fn restore(context: &Context, locator: types::SequenceLocator) -> Result<Handle> {
     let mut cx = context.db.connection();
-    db::sequence_delete_by_id(&mut cx, handle.id(), allow_data_loss).await?;
+
+    let record = db::sequence_find_trashed_by_locator(&mut cx, &locator).await?;
+    db::sequence_restore(&mut cx, record.sequence_id).await?;
+
+    Ok(Handle {
+        locator,
+        id: record.sequence_id,
+        uuid: record.uuid(),
+    })
+}
+
+/// Permanently deletes a sequence in the trash, along with its sessions and topics.
+///
+/// Returns an error if there is no sequence in the trash with the given locator.
+pub async fn purge(
+    context: &Context,
+    locator: &types::SequenceLocator,
+    allow_data_loss: types::DataLossToken,
+) -> Result<()> {
+    let mut tx = context.db.transaction().await?;
+
+    let record = db::sequence_find_trashed_by_locator(&mut tx, locator).await?;
+    lock_unless_held(&mut tx, record.sequence_id, "sequence_purge", locator).await?;
+    db::sequence_delete_trashed_by_id(&mut tx, record.sequence_id, allow_data_loss).await?;
+
+    tx.commit().await?;
+
     Ok(())
 }
 
+/// Permanently deletes the sequences moved to the trash before `before`, returns the
+/// number of sequences deleted. Sequences under legal hold are kept.
+pub async fn purge_trashed(
+    context: &Context,
+    before: types::Timestamp,
+    allow_data_loss: types::DataLossToken,
+) -> Result<usize> {
+    let mut cx = context.db.connection();
+
+    let mut purged = 0;
+    for record in db::sequence_find_all_trashed_before(&mut cx, before).await? {
+        if record.legal_hold() {
+            audit_refused("sequence_purge", &record.locator(), &record.locator());
+            continue;
+        }
+
+        // The sequence may have been restored, purged or put under legal hold in the meantime
+        let res =
+            db::sequence_delete_trashed_by_id(&mut cx, record.sequence_id, allow_data_loss.clone())
+                .await;
+        match res {
+            Ok(()) => {
+                trace!("sequence `{}` purged from the trash", record.locator());
+                purged += 1;
+            }
+            Err(db::Error::NotFound) => {}
+            Err(e) => Err(e)?,
+        }
+    }
+
+    Ok(purged)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use mosaicod_core::types::NotificationType;
+    use mosaicod_core::types::{NotificationPayload, NotificationSeverity, NotificationType};
     use mosaicod_query as query;
     use mosaicod_store as store;
     use std::sync::Arc;
@@ -278,10 +954,10 @@ mod tests {
 
     fn test_context(pool: sqlx::Pool<db::DatabaseType>) -> Context {
         let database = db::testing::Database::new(pool);
-        let store = store::testing::Store::new_random_on_tmp().unwrap();
-        let ts_gw = Arc::new(query::TimeseriesEngine::try_new((*store).clone(), 0).unwrap());
+        let store = Arc::new(store::Store::in_memory());
+        let ts_gw = Arc::new(query::TimeseriesEngine::try_new(store.clone(), 0).unwrap());
 
-        Context::new((*store).clone(), (*database).clone(), ts_gw)
+        Context::new(store, (*database).clone(), ts_gw)
     }
 
     #[sqlx::test(migrator = "db::testing::MIGRATOR")]
@@ -350,6 +1026,114 @@ mod tests {
         Ok(())
     }
 
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn sequence_trash_and_purge(pool: sqlx::Pool<db::DatabaseType>) {
+        let context = test_context(pool);
+
+        let seq_locator = "test_sequence".parse::<types::SequenceLocator>().unwrap();
+        let handle = try_create(&context, seq_locator.clone(), None)
+            .await
+            .unwrap();
+
+        trash(&context, handle).await.unwrap();
+        assert!(
+            Handle::try_from_locator(&context, seq_locator.clone())
+                .await
+                .is_err()
+        );
+
+        let handle = restore(&context, seq_locator.clone()).await.unwrap();
+        assert!(restore(&context, seq_locator.clone()).await.is_err());
+        assert!(
+            purge(&context, &seq_locator, types::allow_data_loss())
+                .await
+                .is_err()
+        );
+
+        let trashed_at = types::Timestamp::now();
+        trash(&context, handle).await.unwrap();
+
+        // Only the sequences trashed before the given time are purged
+        let purged = purge_trashed(&context, trashed_at, types::allow_data_loss())
+            .await
+            .unwrap();
+        assert_eq!(purged, 0);
+        let purged = purge_trashed(&context, types::Timestamp::now(), types::allow_data_loss())
+            .await
+            .unwrap();
+        assert_eq!(purged, 1);
+
+        assert!(restore(&context, seq_locator.clone()).await.is_err());
+        try_create(&context, seq_locator, None).await.unwrap();
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn sequence_legal_hold(pool: sqlx::Pool<db::DatabaseType>) {
+        let context = test_context(pool);
+
+        let seq_locator = "test_sequence".parse::<types::SequenceLocator>().unwrap();
+        try_create(&context, seq_locator.clone(), None)
+            .await
+            .unwrap();
+        let session = session::try_create(&context, seq_locator.clone(), None)
+            .await
+            .unwrap();
+
+        set_legal_hold(&context, &seq_locator, true, Some("admin"))
+            .await
+            .unwrap();
+
+        let is_legal_hold = |res: Result<()>| {
+            matches!(
+                res.unwrap_err().error().kind(),
+                core::error::ErrorKind::LegalHold(_)
+            )
+        };
+
+        let handle = Handle::try_from_locator(&context, seq_locator.clone())
+            .await
+            .unwrap();
+        assert!(is_legal_hold(trash(&context, handle).await));
+
+        let handle = Handle::try_from_locator(&context, seq_locator.clone())
+            .await
+            .unwrap();
+        assert!(is_legal_hold(
+            delete(&context, handle, types::allow_data_loss()).await
+        ));
+
+        assert!(is_legal_hold(
+            session::delete(&context, session, types::allow_data_loss()).await
+        ));
+
+        // Once released the sequence can be trashed, and held again in the trash
+        set_legal_hold(&context, &seq_locator, false, None)
+            .await
+            .unwrap();
+        let handle = Handle::try_from_locator(&context, seq_locator.clone())
+            .await
+            .unwrap();
+        trash(&context, handle).await.unwrap();
+
+        set_legal_hold(&context, &seq_locator, true, None)
+            .await
+            .unwrap();
+        assert!(is_legal_hold(
+            purge(&context, &seq_locator, types::allow_data_loss()).await
+        ));
+        let purged = purge_trashed(&context, types::Timestamp::now(), types::allow_data_loss())
+            .await
+            .unwrap();
+        assert_eq!(purged, 0);
+
+        set_legal_hold(&context, &seq_locator, false, None)
+            .await
+            .unwrap();
+        purge(&context, &seq_locator, types::allow_data_loss())
+            .await
+            .unwrap();
+    }
+
     #[sqlx::test(migrator = "db::testing::MIGRATOR")]
     async fn sequence_notify_and_notification_purge(pool: sqlx::Pool<db::DatabaseType>) {
         let context = test_context(pool);
@@ -369,7 +1153,9 @@ mod tests {
             &context,
             &handle,
             NotificationType::Error,
-            "test notification message".to_owned(),
+            NotificationSeverity::Error,
+            NotificationPayload::message(),
+            Some("test notification message".to_owned()),
         )
         .await
         .expect("Error creating notification");
@@ -378,7 +1164,9 @@ mod tests {
             &context,
             &handle,
             NotificationType::Error,
-            "test notification message 2".to_owned(),
+            NotificationSeverity::Info,
+            NotificationPayload::message(),
+            Some("test notification message 2".to_owned()),
         )
         .await
         .expect("Error creating notification");
@@ -398,6 +1186,7 @@ mod tests {
         );
         assert!(first_notification.uuid().is_valid());
         assert_eq!(first_notification.sequence_id, handle.id());
+        assert_eq!(first_notification.payload(), NotificationPayload::message());
 
         let second_notification = notifications.last().unwrap();
         assert_eq!(
@@ -406,6 +1195,35 @@ mod tests {
         );
         assert!(second_notification.uuid().is_valid());
         assert_eq!(second_notification.sequence_id, handle.id());
+        assert_eq!(second_notification.severity(), NotificationSeverity::Info);
+
+        // Notifications are listed one page at a time, filtered by severity
+        let first = notification_list(&context, &handle, &Default::default(), None, 1)
+            .await
+            .unwrap();
+        assert_eq!(first.notifications.len(), 1);
+        let second = notification_list(
+            &context,
+            &handle,
+            &Default::default(),
+            first.cursor.as_ref(),
+            1,
+        )
+        .await
+        .unwrap();
+        assert_eq!(second.notifications.len(), 1);
+        assert_ne!(first.notifications[0].uuid, second.notifications[0].uuid);
+        assert!(second.cursor.is_none());
+
+        let filter = types::NotificationFilter {
+            min_severity: NotificationSeverity::Warning,
+            ..Default::default()
+        };
+        let page = notification_list(&context, &handle, &filter, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(page.notifications.len(), 1);
+        assert_eq!(page.notifications[0].severity, NotificationSeverity::Error);
 
         notification_purge(&context, &handle)
             .await
@@ -0,0 +1,405 @@
+//! Portable archives of the sequences, used to move recordings between instances.
+//!
+//! An archive is a folder of the store holding a manifest ([`MANIFEST_FILE`]) with the
+//! metadata of the sequence and of its published topics, along with the rows of each topic
+//! as a list of Arrow IPC files. The rows are the ones served by DoGet: rows of upsert topics
+//! are merged according to their deduplication policy and offloaded blobs are inlined, so
+//! that the archive does not depend on the store it was exported from.
+//!
+//! Archives are written under the [`ARCHIVES_FOLDER`] and can be copied to the store of
+//! another instance, where [`import`] recreates the sequence with a single session holding
+//! all the topics. The UUIDs of the sequence and of the topics can be preserved.
+use super::{Context, sequence, session, topic, topic_export};
+use futures::TryStreamExt;
+use log::{debug, info, warn};
+use mosaicod_core::{self as core, error::PublicResult as Result, params, types};
+use mosaicod_ext as ext;
+use mosaicod_marshal as marshal;
+use mosaicod_query as query;
+use std::sync::Arc;
+
+/// Folder of the store holding the sequence archives.
+pub const ARCHIVES_FOLDER: &str = "archives";
+
+/// Name of the manifest at the root of an archive.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+type SequenceArchive = types::SequenceArchive<marshal::JsonMetadataBlob>;
+type ArchivedTopic = types::ArchivedTopic<marshal::JsonMetadataBlob>;
+
+/// Result of the export of a sequence.
+#[derive(Debug)]
+pub struct Export {
+    /// Root folder of the archive, relative to the root of the store
+    pub path: String,
+    pub topics: usize,
+    /// Size of the Arrow IPC files in bytes
+    pub size_bytes: u64,
+}
+
+/// Options of a sequence import.
+#[derive(Debug, Default)]
+pub struct ImportOptions {
+    /// Locator of the new sequence, the one of the archive if not set
+    pub locator: Option<types::SequenceLocator>,
+    /// If true the sequence and the topics keep the UUIDs of the archive
+    pub preserve_uuids: bool,
+    /// Owner of the session of the imported topics
+    pub owner: Option<String>,
+}
+
+/// Result of the import of an archive.
+#[derive(Debug)]
+pub struct Import {
+    pub sequence: types::SequenceLocator,
+    pub uuid: types::Uuid,
+    pub session: types::SessionLocator,
+    pub topics: Vec<types::TopicLocator>,
+}
+
+/// Exports the published topics of a sequence as an archive written in the store under
+/// `prefix`, in the [`ARCHIVES_FOLDER`]. Topics of sessions not finalized yet are skipped.
+///
+/// Fails if an archive of the sequence already exists under `prefix`.
+pub async fn export(context: &Context, handle: &sequence::Handle, prefix: &str) -> Result<Export> {
+    let root = archive_path(prefix, handle.locator())?;
+    let manifest_path = format!("{root}/{MANIFEST_FILE}");
+
+    if context.store.exists(&manifest_path).await? {
+        return Err(core::Error::already_exists(root).into());
+    }
+
+    let exported = match export_into(context, handle, &root).await {
+        Ok(exported) => exported,
+        Err(e) => {
+            if let Err(e) = context.store.delete_recursive(&root).await {
+                warn!("unable to delete incomplete archive `{}`: {}", root, e);
+            }
+            return Err(e);
+        }
+    };
+    let (archive, size_bytes) = exported;
+
+    let topics = archive.topics.len();
+    let manifest: Vec<u8> = marshal::JsonSequenceArchive::from(archive).try_into()?;
+    context.store.write_bytes(&manifest_path, manifest).await?;
+
+    info!(
+        "sequence `{}` exported to `{}` ({} topics, {} bytes)",
+        handle.locator(),
+        root,
+        topics,
+        size_bytes
+    );
+
+    Ok(Export {
+        path: root,
+        topics,
+        size_bytes,
+    })
+}
+
+async fn export_into(
+    context: &Context,
+    handle: &sequence::Handle,
+    root: &str,
+) -> Result<(SequenceArchive, u64)> {
+    let metadata = sequence::metadata(context, handle).await?;
+
+    let mut handles = sequence::topic_list(context, handle).await?;
+    handles.sort_by(|a, b| a.locator().cmp(b.locator()));
+
+    let mut topics = Vec::new();
+    let mut size_bytes = 0;
+    for topic_handle in &handles {
+        if topic::status(context, topic_handle).await? == topic::Status::Empty
+            || !topic::is_published(context, topic_handle).await?
+        {
+            debug!(
+                "topic `{}` not published, skipped from the archive",
+                topic_handle.locator()
+            );
+            continue;
+        }
+
+        let (topic, size) = export_topic(context, topic_handle, root, topics.len()).await?;
+        topics.push(topic);
+        size_bytes += size;
+    }
+
+    let archive = SequenceArchive {
+        uuid: handle.uuid().clone(),
+        locator: handle.locator().clone(),
+        created_at: metadata.created_at,
+        user_metadata: metadata.user_metadata,
+        topics,
+    };
+
+    Ok((archive, size_bytes))
+}
+
+/// Writes the rows of a topic in Arrow IPC files of about
+/// [`params::Params::parquet_in_memory_encoding_buffer_size`] bytes, before compression.
+async fn export_topic(
+    context: &Context,
+    handle: &topic::Handle,
+    root: &str,
+    index: usize,
+) -> Result<(ArchivedTopic, u64)> {
+    let path_in_store = handle.path_in_store().cloned().ok_or_else(|| {
+        core::Error::internal(Some(format!(
+            "Path in store not set for topic {}",
+            handle.locator()
+        )))
+    })?;
+
+    let metadata = topic::metadata(context, handle).await?;
+    let arrow_schema = topic::registered_schema(context, handle)
+        .await?
+        .map(|schema| ext::arrow::schema_to_ipc(&schema))
+        .transpose()?;
+
+    let batch_size = topic::compute_optimal_batch_size(context, handle).await?;
+    let properties = &metadata.ontology_metadata.properties;
+    let result = topic::read(context, handle, properties, batch_size).await?;
+    let schema = Arc::new(ext::blob::inline_schema(&result.schema()));
+    let mut batches = result.stream().await?;
+
+    let chunk_bytes = params::params()
+        .parquet_in_memory_encoding_buffer_size
+        .value;
+
+    let mut chunks = Vec::new();
+    let mut pending = Vec::new();
+    let mut pending_bytes = 0;
+    let mut rows = 0;
+    let mut size_bytes = 0;
+    loop {
+        let batch = batches.try_next().await.map_err(query::Error::from)?;
+        let done = batch.is_none();
+
+        if let Some(batch) = batch {
+            let batch = topic::inline_blobs(context, &path_in_store, batch).await?;
+            rows += batch.num_rows() as u64;
+            pending_bytes += batch.get_array_memory_size();
+            pending.push(batch);
+        }
+
+        if !pending.is_empty() && (done || pending_bytes >= chunk_bytes) {
+            let chunk = format!("topics/{index}/{:05}.{}", chunks.len(), params::ext::ARROW);
+            let bytes = ext::arrow::batches_to_ipc_file(&schema, &pending)?;
+            size_bytes += bytes.len() as u64;
+            context
+                .store
+                .write_bytes(format!("{root}/{chunk}"), bytes)
+                .await?;
+
+            chunks.push(chunk);
+            pending.clear();
+            pending_bytes = 0;
+        }
+
+        if done {
+            break;
+        }
+    }
+
+    debug!(
+        "topic `{}` archived in {} files ({} rows)",
+        handle.locator(),
+        chunks.len(),
+        rows
+    );
+
+    let topic = ArchivedTopic {
+        uuid: handle.uuid().clone(),
+        locator: handle.locator().clone(),
+        ontology_metadata: metadata.ontology_metadata,
+        arrow_schema,
+        rows,
+        chunks,
+    };
+
+    Ok((topic, size_bytes))
+}
+
+/// Recreates the sequence of the archive stored at `path`, with a new session holding all
+/// its topics. The session is finalized once all the topics are imported. If the import
+/// fails the sequence is deleted.
+///
+/// Fails if a sequence with the same locator, or the same UUID when the UUIDs are preserved,
+/// already exists.
+pub async fn import(context: &Context, path: &str, options: ImportOptions) -> Result<Import> {
+    let root = path.trim_end_matches('/');
+
+    let bytes = context
+        .store
+        .read_bytes(format!("{root}/{MANIFEST_FILE}"))
+        .await?;
+    let archive: SequenceArchive = marshal::JsonSequenceArchive::try_from(bytes)?.try_into()?;
+
+    let locator = options.locator.unwrap_or_else(|| archive.locator.clone());
+    let create_options = sequence::CreateOptions {
+        uuid: options.preserve_uuids.then(|| archive.uuid.clone()),
+        created_at: Some(archive.created_at),
+    };
+    let sequence = sequence::try_create_with_options(
+        context,
+        locator.clone(),
+        archive.user_metadata,
+        create_options,
+    )
+    .await?;
+
+    let imported = match session::try_create(context, locator.clone(), options.owner).await {
+        Ok(session) => {
+            let topics = import_topics(
+                context,
+                root,
+                archive.topics,
+                &session,
+                options.preserve_uuids,
+            )
+            .await;
+            match topics {
+                Ok(topics) => session::finalize(context, &session)
+                    .await
+                    .map(|_| (session, topics)),
+                Err(e) => Err(e),
+            }
+        }
+        Err(e) => Err(e),
+    };
+
+    let (session, topics) = match imported {
+        Ok(imported) => imported,
+        Err(e) => {
+            // Removes the topics along with the data already written
+            let uuid = sequence.uuid().clone();
+            if let Err(e) = sequence::delete(context, sequence, types::allow_data_loss()).await {
+                warn!(
+                    "unable to delete sequence `{}` ({}) of failed import of `{}`: {}",
+                    locator, uuid, root, e
+                );
+            }
+            return Err(e);
+        }
+    };
+
+    info!(
+        "archive `{}` imported into sequence `{}` ({} topics)",
+        root,
+        locator,
+        topics.len()
+    );
+
+    Ok(Import {
+        sequence: locator,
+        uuid: sequence.uuid().clone(),
+        session: session.locator().clone(),
+        topics,
+    })
+}
+
+async fn import_topics(
+    context: &Context,
+    root: &str,
+    archived_topics: Vec<ArchivedTopic>,
+    session: &session::Handle,
+    preserve_uuids: bool,
+) -> Result<Vec<types::TopicLocator>> {
+    let sequence = &session.locator().sequence;
+
+    let mut topics = Vec::with_capacity(archived_topics.len());
+    for archived in archived_topics {
+        let locator: types::TopicLocator =
+            format!("{}/{}", sequence, archived.locator.name()).parse()?;
+
+        let options = topic::CreateOptions {
+            base_version: None,
+            arrow_schema: archived
+                .arrow_schema
+                .as_deref()
+                .map(ext::arrow::schema_from_ipc)
+                .transpose()?,
+            uuid: preserve_uuids.then_some(archived.uuid),
+        };
+        let handle = topic::try_create_with_options(
+            context,
+            locator.clone(),
+            session,
+            archived.ontology_metadata,
+            options,
+        )
+        .await?;
+
+        let rows = import_topic(context, root, &archived.chunks, handle).await?;
+        if rows != archived.rows {
+            return Err(core::Error::bad_request(format!(
+                "archive `{}` holds {} rows of topic `{}`, {} expected",
+                root, rows, archived.locator, archived.rows
+            ))
+            .into());
+        }
+
+        debug!("imported {} rows into `{}`", rows, locator);
+        topics.push(locator);
+    }
+
+    Ok(topics)
+}
+
+/// Writes the rows of the Arrow IPC files of a topic, returning the number of rows written.
+async fn import_topic(
+    context: &Context,
+    root: &str,
+    chunks: &[String],
+    handle: topic::Handle,
+) -> Result<u64> {
+    let mut handle = Some(handle);
+    let mut writer = None;
+    let mut rows = 0;
+
+    for chunk in chunks {
+        let bytes = context.store.read_bytes(format!("{root}/{chunk}")).await?;
+
+        for batch in ext::arrow::batches_from_ipc_file(bytes)? {
+            if let Some(handle) = handle.take() {
+                writer = Some(topic::writer(context.clone(), handle, batch.schema()).await?);
+            }
+            if let Some(writer) = writer.as_mut() {
+                rows += batch.num_rows() as u64;
+                let chunk = writer.write(batch).await?;
+                writer.record_chunk(chunk).await?;
+            }
+        }
+    }
+
+    if let Some(writer) = writer {
+        writer.finalize().await?;
+    }
+
+    Ok(rows)
+}
+
+/// Returns the root folder of the archive of the sequence `locator` under `prefix`.
+fn archive_path(prefix: &str, locator: &types::SequenceLocator) -> Result<String> {
+    let prefix = topic_export::check_prefix(prefix)?;
+    Ok(format!("{ARCHIVES_FOLDER}/{prefix}/{locator}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_paths() {
+        let locator: types::SequenceLocator = "run_a".parse().unwrap();
+
+        assert_eq!(
+            archive_path("lab_b/", &locator).unwrap(),
+            "archives/lab_b/run_a"
+        );
+        assert!(archive_path("../secrets", &locator).is_err());
+    }
+}
@@ -0,0 +1,331 @@
+//! Replay of several topics of a sequence as a single stream, time-merged across the topics,
+//! suitable to feed the recorded data into simulators.
+//!
+//! The published rows of the topics, read as DoGet serves them, are interleaved by timestamp.
+//! Each topic is a channel whose id is the position of the topic in the replayed list, rows
+//! sharing a timestamp are ordered by channel id so that the same replay always yields the
+//! same stream.
+//!
+//! The batches of the stream have the [`params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP`]
+//! and [`params::ARROW_SCHEMA_COLUMN_NAME_REPLAY_CHANNEL`] columns, followed by a nullable
+//! struct column for each channel, named after the locator of its topic. The struct column
+//! of a channel holds the row of the topic in the rows of that channel and is null in the
+//! other rows. Offloaded blobs are inlined.
+use super::{Context, sequence, topic};
+use arrow::array::{ArrayRef, Int64Array, RecordBatch, StructArray, UInt32Array, new_null_array};
+use arrow::compute::{concat_batches, interleave};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use futures::{StreamExt, TryStreamExt, stream::BoxStream};
+use log::debug;
+use mosaicod_core::{self as core, error::PublicResult as Result, params, types};
+use mosaicod_ext as ext;
+use mosaicod_query as query;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::sync::Arc;
+
+/// Maximum number of rows of the replayed batches.
+const BATCH_ROWS: usize = 8192;
+
+/// Options of a replay.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayOptions {
+    /// If set only the rows in the range are replayed
+    pub timestamp_range: Option<types::TimestampRange>,
+}
+
+/// Batches of a replay, see the [module documentation](self).
+pub type ReplayStream = BoxStream<'static, Result<RecordBatch>>;
+
+/// Returns the topics of the sequence to replay, in channel order: the topics named in
+/// `names` (relative to the sequence) in the given order, or all the topics with published
+/// data sorted by locator.
+pub async fn channels(
+    context: &Context,
+    handle: &sequence::Handle,
+    names: Option<Vec<String>>,
+) -> Result<Vec<topic::Handle>> {
+    let Some(names) = names else {
+        let mut handles = Vec::new();
+        for topic in sequence::topic_list(context, handle).await? {
+            let readable = topic::status(context, &topic).await? != topic::Status::Empty
+                && topic::is_published(context, &topic).await?;
+            if readable {
+                handles.push(topic);
+            }
+        }
+        handles.sort_by(|a, b| a.locator().cmp(b.locator()));
+        return Ok(handles);
+    };
+
+    if names.is_empty() {
+        return Err(core::Error::bad_request("no topics to replay".to_owned()).into());
+    }
+
+    let mut seen = HashSet::new();
+    let mut handles = Vec::with_capacity(names.len());
+    for name in names {
+        if !seen.insert(name.clone()) {
+            return Err(core::Error::bad_request(format!("topic `{name}` replayed twice")).into());
+        }
+
+        let locator: types::TopicLocator = format!("{}/{}", handle.locator(), name).parse()?;
+        let topic = topic::Handle::try_from_locator(context, locator).await?;
+        check_readable(context, &topic).await?;
+        handles.push(topic);
+    }
+
+    Ok(handles)
+}
+
+/// Replays the published data of the topics, the channel ids are the positions of the
+/// topics in `handles`. Returns the schema of the replayed batches along with their stream.
+pub async fn replay(
+    context: &Context,
+    handles: Vec<topic::Handle>,
+    options: ReplayOptions,
+) -> Result<(SchemaRef, ReplayStream)> {
+    let mut fields = vec![
+        Field::new(
+            params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+            DataType::Int64,
+            false,
+        ),
+        Field::new(
+            params::ARROW_SCHEMA_COLUMN_NAME_REPLAY_CHANNEL,
+            DataType::UInt32,
+            false,
+        ),
+    ];
+
+    let mut channels = Vec::with_capacity(handles.len());
+    for handle in &handles {
+        let channel = Channel::open(context, handle, options.timestamp_range.clone()).await?;
+        fields.push(Field::new(
+            handle.locator().to_string(),
+            DataType::Struct(channel.schema.fields().clone()),
+            true,
+        ));
+        channels.push(channel);
+    }
+
+    debug!(
+        "replaying {} topics in range {:?}",
+        channels.len(),
+        options.timestamp_range
+    );
+
+    let schema = Arc::new(Schema::new(fields));
+
+    let mut merger = Merger {
+        context: context.clone(),
+        schema: schema.clone(),
+        channels,
+        next: BinaryHeap::new(),
+    };
+    for id in 0..merger.channels.len() {
+        if let Some(timestamp) = merger.channels[id].advance(context).await? {
+            merger.next.push(Reverse((timestamp, id)));
+        }
+    }
+
+    let stream = futures::stream::try_unfold(merger, |mut merger| async move {
+        Ok(merger.next_batch().await?.map(|batch| (batch, merger)))
+    });
+
+    Ok((schema, Box::pin(stream)))
+}
+
+/// Fails if the topic has no published data.
+async fn check_readable(context: &Context, handle: &topic::Handle) -> Result<()> {
+    if topic::status(context, handle).await? == topic::Status::Empty {
+        return Err(core::Error::missing_doput(handle.locator().to_string()).into());
+    }
+    if !topic::is_published(context, handle).await? {
+        return Err(core::Error::topic_not_published(handle.locator().to_string()).into());
+    }
+    Ok(())
+}
+
+/// Rows of a topic being merged.
+struct Channel {
+    /// Schema of the rows, with the blobs inlined
+    schema: SchemaRef,
+    path_in_store: types::TopicPathInStore,
+    batches: BoxStream<'static, std::result::Result<RecordBatch, query::Error>>,
+    /// Batch being merged along with its timestamps, `None` once all the rows are merged
+    batch: Option<(RecordBatch, Int64Array)>,
+    /// Next row of the batch to merge
+    row: usize,
+    /// First merged row of the batch not yet replayed
+    start: usize,
+    /// Merged rows of the previous batches not yet replayed
+    merged: Vec<RecordBatch>,
+}
+
+impl Channel {
+    async fn open(
+        context: &Context,
+        handle: &topic::Handle,
+        timestamp_range: Option<types::TimestampRange>,
+    ) -> Result<Self> {
+        check_readable(context, handle).await?;
+
+        let path_in_store = handle.path_in_store().cloned().ok_or_else(|| {
+            core::Error::internal(Some(format!(
+                "Path in store not set for topic {}",
+                handle.locator()
+            )))
+        })?;
+
+        let metadata = topic::metadata(context, handle).await?;
+        let properties = &metadata.ontology_metadata.properties;
+        let batch_size = topic::compute_optimal_batch_size(context, handle).await?;
+
+        let mut result = match &timestamp_range {
+            Some(range) => {
+                topic::read_range(context, handle, properties, batch_size, range).await?
+            }
+            None => topic::read(context, handle, properties, batch_size).await?,
+        };
+        if let Some(range) = timestamp_range {
+            result = result.filter_by_timestamp_range(range)?;
+        }
+        // Topics with a custom sort key are not stored in timestamp order
+        let result = result.sort_by_timestamp()?;
+
+        let schema = Arc::new(ext::blob::inline_schema(&result.schema()));
+        let batches = result.stream().await?.map_err(query::Error::from).boxed();
+
+        Ok(Self {
+            schema,
+            path_in_store,
+            batches,
+            batch: None,
+            row: 0,
+            start: 0,
+            merged: Vec::new(),
+        })
+    }
+
+    /// Returns the timestamp of the next row to merge, reading the next batch once the
+    /// current one is merged. Returns `None` once all the rows are merged.
+    async fn advance(&mut self, context: &Context) -> Result<Option<i64>> {
+        if let Some((batch, timestamps)) = &self.batch {
+            if self.row < batch.num_rows() {
+                return Ok(Some(timestamps.value(self.row)));
+            }
+            if self.row > self.start {
+                self.merged
+                    .push(batch.slice(self.start, self.row - self.start));
+            }
+        }
+        self.batch = None;
+
+        while let Some(batch) = self.batches.try_next().await? {
+            if batch.num_rows() == 0 {
+                continue;
+            }
+
+            let batch = topic::inline_blobs(context, &self.path_in_store, batch).await?;
+            let timestamps = batch
+                .column_by_name(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP)
+                .and_then(|column| column.as_any().downcast_ref::<Int64Array>())
+                .cloned()
+                .ok_or_else(|| {
+                    core::Error::internal(Some("missing timestamp column".to_owned()))
+                })?;
+
+            let timestamp = timestamps.value(0);
+            self.batch = Some((batch, timestamps));
+            self.row = 0;
+            self.start = 0;
+            return Ok(Some(timestamp));
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the merged rows not yet replayed, as a struct array.
+    fn take_merged(&mut self) -> Result<ArrayRef> {
+        if let Some((batch, _)) = &self.batch
+            && self.row > self.start
+        {
+            self.merged
+                .push(batch.slice(self.start, self.row - self.start));
+            self.start = self.row;
+        }
+
+        let merged = std::mem::take(&mut self.merged);
+        let rows = concat_batches(&self.schema, &merged).map_err(ext::arrow::Error::from)?;
+
+        Ok(Arc::new(StructArray::from(rows)))
+    }
+}
+
+/// Merges the rows of the channels in timestamp order.
+struct Merger {
+    context: Context,
+    schema: SchemaRef,
+    channels: Vec<Channel>,
+    /// Timestamp of the next row of the channels with rows left, the earliest first
+    next: BinaryHeap<Reverse<(i64, usize)>>,
+}
+
+impl Merger {
+    /// Returns the next batch of merged rows, `None` once all the rows are replayed.
+    async fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        let mut timestamps = Vec::new();
+        // Channel of each row, along with the position of the row among the merged rows
+        // of the channel
+        let mut rows: Vec<(usize, usize)> = Vec::new();
+        let mut counts = vec![0; self.channels.len()];
+
+        while rows.len() < BATCH_ROWS {
+            let Some(Reverse((timestamp, id))) = self.next.pop() else {
+                break;
+            };
+
+            timestamps.push(timestamp);
+            rows.push((id, counts[id]));
+            counts[id] += 1;
+
+            let channel = &mut self.channels[id];
+            channel.row += 1;
+            if let Some(timestamp) = channel.advance(&self.context).await? {
+                self.next.push(Reverse((timestamp, id)));
+            }
+        }
+
+        if rows.is_empty() {
+            return Ok(None);
+        }
+
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(timestamps)),
+            Arc::new(UInt32Array::from_iter_values(
+                rows.iter().map(|(id, _)| *id as u32),
+            )),
+        ];
+
+        for (id, channel) in self.channels.iter_mut().enumerate() {
+            let values = channel.take_merged()?;
+            let null = new_null_array(values.data_type(), 1);
+
+            // Rows of the other channels pick the null value
+            let indices: Vec<(usize, usize)> = rows
+                .iter()
+                .map(|(row_id, index)| if *row_id == id { (0, *index) } else { (1, 0) })
+                .collect();
+            columns.push(
+                interleave(&[values.as_ref(), null.as_ref()], &indices)
+                    .map_err(ext::arrow::Error::from)?,
+            );
+        }
+
+        let batch =
+            RecordBatch::try_new(self.schema.clone(), columns).map_err(ext::arrow::Error::from)?;
+
+        Ok(Some(batch))
+    }
+}
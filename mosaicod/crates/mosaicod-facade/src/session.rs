@@ -6,9 +6,17 @@
 //! Multiple sessions can occur in parallel for the same sequence. Once a session is
 //! finalized, all data associated with it becomes immutable.
 
-use crate::{Context, topic};
+use crate::{CheckedAttestation, Context, sequence, topic, verify_attestation};
+use log::{trace, warn};
 use mosaicod_core::{self as core, error::PublicResult as Result, types};
 use mosaicod_db as db;
+use mosaicod_marshal as marshal;
+
+pub type SessionManifest = types::SessionManifest<marshal::JsonMetadataBlob>;
+pub type SequenceSnapshot = types::SequenceSnapshot<marshal::JsonMetadataBlob>;
+
+/// Number of records read from the database at once when walking the topics of a session.
+const PAGE_SIZE: i64 = 256;
 
 /// Handle containing session identifiers.
 /// It's used by all functions (except creation) in this module to indicate the session to operate on.
@@ -35,7 +43,7 @@ impl Handle {
 
         Ok(Self {
             locator,
-            id: db_session.sequence_id,
+            id: db_session.session_id,
             uuid: db_session.uuid(),
         })
     }
@@ -67,10 +75,14 @@ impl Handle {
     }
 }
 
-/// Creates a new session in the database for the given sequence.
+/// Creates a new session in the database for the given sequence, owned by `owner`.
+///
+/// Only the owner of a session, and the principals it is shared with, can write into it,
+/// see [`check_principal`]. Sessions without owner can be written by anyone.
 pub async fn try_create(
     context: &Context,
     sequence_locator: types::SequenceLocator,
+    owner: Option<String>,
 ) -> Result<Handle> {
     let mut tx = context.db.transaction().await?;
 
@@ -78,7 +90,7 @@ pub async fn try_create(
 
     let locator = types::SessionLocator::new(sequence_locator);
 
-    let session = db::SessionRecord::new(locator.clone(), sequence.sequence_id);
+    let session = db::SessionRecord::new(locator.clone(), sequence.sequence_id).with_owner(owner);
     let session = db::session_create(&mut tx, &session).await?;
 
     tx.commit().await?;
@@ -90,15 +102,79 @@ pub async fn try_create(
     })
 }
 
+/// Checks that `principal` can write into the session, i.e. that it is the owner of the
+/// session or that the session was shared with it.
+///
+/// Sessions without owner and requests without principal (i.e. with API keys disabled) are
+/// not checked.
+pub async fn check_principal(
+    context: &Context,
+    handle: &Handle,
+    principal: Option<&str>,
+) -> Result<()> {
+    let Some(principal) = principal else {
+        return Ok(());
+    };
+
+    let mut cx = context.db.connection();
+    let record = db::session_find_by_id(&mut cx, handle.id()).await?;
+
+    let allowed = match record.owner() {
+        None => true,
+        Some(owner) => owner == principal || record.shared_with().iter().any(|p| p == principal),
+    };
+
+    if !allowed {
+        Err(core::Error::unauthorized(format!(
+            "session `{}` is owned by another API key and was not shared with this one",
+            handle.locator()
+        )))?;
+    }
+
+    Ok(())
+}
+
+/// Shares the session with `principal`, allowing it to write into the session.
+///
+/// Only the owner of the session, identified by `owner`, can share it.
+pub async fn share(
+    context: &Context,
+    handle: &Handle,
+    owner: Option<&str>,
+    principal: &str,
+) -> Result<()> {
+    let mut tx = context.db.transaction().await?;
+    let record = db::session_find_by_id(&mut tx, handle.id()).await?;
+
+    match (record.owner(), owner) {
+        (Some(session_owner), Some(owner)) if session_owner == owner => {}
+        (Some(_), _) => Err(core::Error::unauthorized(format!(
+            "only the owner of session `{}` can share it",
+            handle.locator()
+        )))?,
+        (None, _) => Err(core::Error::bad_request(format!(
+            "session `{}` has no owner, it can already be written by anyone",
+            handle.locator()
+        )))?,
+    }
+
+    db::session_share(&mut tx, handle.id(), principal).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
 /// Finalizes the session, making it and all its associated data immutable.
 ///
 /// Once a session is finalized, no more topics can be added to it.
 pub async fn finalize(context: &Context, handle: &Handle) -> Result<()> {
     let mut tx = context.db.transaction().await?;
 
+    // Concurrent finalizations of the session wait here, otherwise they would publish the
+    // staged data files while the first one removes them
+    db::session_lock(&mut tx, handle.id()).await?;
+
     // Return an error if session has already been finalized.
-    // Note: here two concurrent finalized could pass this check,
-    // that's why we need later to update the completion timestamp if not already present atomically.
     if db::session_finalized(&mut tx, handle.id()).await? {
         Err(core::Error::session_already_finalized(
             handle.locator().to_string(),
@@ -106,9 +182,10 @@ pub async fn finalize(context: &Context, handle: &Handle) -> Result<()> {
     }
 
     let topics = topic_list(handle, &mut tx).await?;
+    let upserts = db::session_find_all_upserts(&mut tx, handle.id()).await?;
 
     // If the session does not contain any topic, return an error and leave the session unlocked.
-    if topics.is_empty() {
+    if topics.is_empty() && upserts.is_empty() {
         Err(core::Error::empty_session(handle.locator().to_string()))?
     }
 
@@ -135,13 +212,107 @@ pub async fn finalize(context: &Context, handle: &Handle) -> Result<()> {
         }
     }
 
-    // If updating the completion timestamp fails it means somebody else did it in the meantime.
-    let finalize_ok = db::session_try_update_completion_tstamp(
+    // All the rows upserted into existing topics need to be uploaded
+    if let Some(upsert) = upserts
+        .iter()
+        .find(|upsert| upsert.completion_timestamp().is_none())
+    {
+        let locator = db::topic_find_by_id(&mut tx, upsert.topic_id)
+            .await?
+            .locator()
+            .to_string();
+
+        if upsert.upload_timestamp().is_none() {
+            Err(core::Error::missing_doput(locator))?
+        } else {
+            Err(core::Error::topic_upload_in_progress(locator))?
+        }
+    }
+
+    // The data files staged by the session are published along with the finalization, the
+    // copies are removed if the session is not finalized
+    let exported: Vec<_> = topics.iter().map(|t| t.locator().clone()).collect();
+    let mut publications = Vec::new();
+    let mut res = publish(
+        context,
         &mut tx,
-        handle.id(),
-        types::Timestamp::now().as_i64(),
+        handle,
+        topics,
+        &upserts,
+        &mut publications,
     )
-    .await?;
+    .await;
+    if res.is_ok() {
+        res = tx.commit().await.map_err(Into::into);
+    }
+
+    if let Err(e) = res {
+        for file in publications.iter().flat_map(|p| &p.published) {
+            if let Err(e) = context.store.delete(file).await {
+                warn!(
+                    "unable to remove unpublished data file `{}`: {e}",
+                    file.display()
+                );
+            }
+        }
+        return Err(e);
+    }
+
+    // The session is finalized at this point, a leftover staging folder only wastes space
+    for publication in &publications {
+        if let Err(e) = context.store.delete_recursive(&publication.staging).await {
+            warn!(
+                "unable to remove staging folder `{}`: {e}",
+                publication.staging.display()
+            );
+        }
+    }
+
+    context.table_exporter.export_all(context, exported).await;
+
+    Ok(())
+}
+
+/// Publishes the data files staged by the session into its topics and marks the session as
+/// finalized in `tx`, writing its manifest.
+///
+/// Topics and upserts are marked as completed at the finalization, so that the published data
+/// is reported as changed from then on.
+async fn publish(
+    context: &Context,
+    tx: &mut db::Tx<'_>,
+    handle: &Handle,
+    topics: Vec<topic::Handle>,
+    upserts: &[db::TopicUpsertRecord],
+    publications: &mut Vec<topic::Publication>,
+) -> Result<()> {
+    let completed_at = types::Timestamp::now();
+
+    for topic in &topics {
+        publications
+            .extend(topic::publish(context, tx, topic.id(), handle.uuid(), completed_at).await?);
+        db::topic_update_completion_tstamp(tx, topic.id(), completed_at.as_i64()).await?;
+    }
+    for upsert in upserts {
+        publications.extend(
+            topic::publish(context, tx, upsert.topic_id, handle.uuid(), completed_at).await?,
+        );
+        db::topic_upsert_update_completion_tstamp(tx, upsert.upsert_id, completed_at.as_i64())
+            .await?;
+    }
+
+    // The manifest is written before committing, so that every finalized session has one
+    let manifest = manifest(context, tx, handle, topics, upserts, completed_at).await?;
+    let sequence_path = manifest.sequence.path_in_store.clone();
+    let manifest = manifest_write_to_store(context, manifest).await?;
+
+    if let Some(attestation) = context.attestation_signer.sign(handle.uuid(), &manifest) {
+        attestation_write_to_store(context, &sequence_path, attestation).await?;
+    }
+
+    // If updating the completion timestamp fails it means somebody else did it in the meantime.
+    let finalize_ok =
+        db::session_try_update_completion_tstamp(tx, handle.id(), completed_at.as_i64()).await?;
 
     if !finalize_ok {
         Err(core::Error::session_already_finalized(
@@ -149,25 +320,371 @@ pub async fn finalize(context: &Context, handle: &Handle) -> Result<()> {
         ))?;
     }
 
-    tx.commit().await?;
+    Ok(())
+}
+
+/// Builds the manifest of a session being finalized at `completed_at`.
+///
+/// The objects stored by the topics of the session are read to compute their checksum.
+async fn manifest(
+    context: &Context,
+    tx: &mut impl db::AsExec,
+    handle: &Handle,
+    topics: Vec<topic::Handle>,
+    upserts: &[db::TopicUpsertRecord],
+    completed_at: types::Timestamp,
+) -> Result<SessionManifest> {
+    let session = db::session_find_by_id(tx, handle.id()).await?;
+    let sequence = db::sequence_find_by_id(tx, session.sequence_id).await?;
+
+    let mut upserted_topics = Vec::new();
+    for upsert in upserts {
+        upserted_topics.push(db::topic_find_by_id(tx, upsert.topic_id).await?.locator());
+    }
+
+    let mut topic_manifests = Vec::with_capacity(topics.len());
+    for topic in topics {
+        let Some(path_in_store) = topic.path_in_store().cloned() else {
+            continue;
+        };
+
+        let metadata = topic::metadata(context, &topic).await?;
+        let format = metadata.ontology_metadata.properties.serialization_format;
+        let fields = topic::arrow_schema(context, &topic, format)
+            .await?
+            .fields()
+            .iter()
+            .map(|field| types::FieldDescription {
+                name: field.name().clone(),
+                data_type: field.data_type().to_string(),
+                nullable: field.is_nullable(),
+            })
+            .collect();
+
+        // Staged copies of the published data files are removed after the commit
+        let root = format!("{}/", path_in_store);
+        let mut objects = Vec::new();
+        for object in context.store.list(path_in_store.root(), None).await? {
+            if path_in_store.is_staged(&object) {
+                continue;
+            }
+            let path = object.strip_prefix(&root).unwrap_or(&object).to_owned();
+            objects.push(object_description(context, &path_in_store, path).await?);
+        }
+        objects.sort_by(|a, b| a.path.cmp(&b.path));
+
+        topic_manifests.push(types::TopicManifest {
+            uuid: topic.uuid().clone(),
+            path_in_store,
+            metadata,
+            fields,
+            objects,
+        });
+    }
+
+    Ok(SessionManifest {
+        uuid: handle.uuid().clone(),
+        locator: handle.locator().clone(),
+        created_at: session.creation_timestamp(),
+        completed_at,
+        sequence: types::SequenceSnapshot {
+            locator: sequence.locator(),
+            created_at: sequence.creation_timestamp(),
+            path_in_store: sequence.path_in_store(),
+            user_metadata: sequence.user_metadata(),
+        },
+        topics: topic_manifests,
+        upserted_topics,
+    })
+}
+
+/// Describes the object of a topic at `path`, relative to the topic root.
+async fn object_description(
+    context: &Context,
+    path_in_store: &types::TopicPathInStore,
+    path: String,
+) -> Result<types::ObjectDescription> {
+    let bytes = context
+        .store
+        .read_bytes(path_in_store.root().join(&path))
+        .await?;
+
+    Ok(types::ObjectDescription {
+        path,
+        size: bytes.len() as u64,
+        crc32: crc32fast::hash(&bytes),
+    })
+}
+
+/// Updates the objects of a topic listed in the manifest of the session that created it,
+/// after its data files were rewritten in place. Objects deleted from the store are removed
+/// from the manifest, which is signed again if attestations are enabled.
+///
+/// Nothing is done if the session was not finalized, since it has no manifest yet.
+pub(super) async fn manifest_refresh_topic(
+    context: &Context,
+    exe: &mut impl db::AsExec,
+    session_id: i32,
+    topic: &types::Uuid,
+) -> Result<()> {
+    let session = db::session_find_by_id(exe, session_id).await?;
+    let sequence = db::sequence_find_by_id(exe, session.sequence_id).await?;
+    let sequence_path = sequence.path_in_store();
+
+    let path = sequence_path.path_session_manifest(&session.uuid());
+    if !context.store.exists(&path).await? {
+        return Ok(());
+    }
+
+    let mut manifest = read_manifest(context, &path).await?;
+    let Some(topic) = manifest.topics.iter_mut().find(|t| &t.uuid == topic) else {
+        return Ok(());
+    };
+
+    let mut objects = Vec::with_capacity(topic.objects.len());
+    for object in std::mem::take(&mut topic.objects) {
+        let location = topic.path_in_store.root().join(&object.path);
+        if context.store.exists(&location).await? {
+            objects.push(object_description(context, &topic.path_in_store, object.path).await?);
+        }
+    }
+    topic.objects = objects;
+
+    trace!("refreshing manifest of session `{}`", session.locator());
+    let manifest = manifest_write_to_store(context, manifest).await?;
+    if let Some(attestation) = context.attestation_signer.sign(&session.uuid(), &manifest) {
+        attestation_write_to_store(context, &sequence_path, attestation).await?;
+    }
 
     Ok(())
 }
 
+/// Writes the manifest to the store, returning the bytes written.
+async fn manifest_write_to_store(context: &Context, manifest: SessionManifest) -> Result<Vec<u8>> {
+    let path = manifest
+        .sequence
+        .path_in_store
+        .path_session_manifest(&manifest.uuid);
+    trace!("writing session manifest `{}` to store", path.display());
+
+    let bytes: Vec<u8> = marshal::JsonSessionManifest::from(manifest).try_into()?;
+    context.store.write_bytes(path, bytes.clone()).await?;
+
+    Ok(bytes)
+}
+
+async fn attestation_write_to_store(
+    context: &Context,
+    sequence_path: &types::SequencePathInStore,
+    attestation: types::SessionAttestation,
+) -> Result<()> {
+    let path = sequence_path.path_session_attestation(&attestation.session_uuid);
+    trace!("writing session attestation `{}` to store", path.display());
+
+    let bytes: Vec<u8> = marshal::JsonSessionAttestation::from(attestation).try_into()?;
+    context.store.write_bytes(path, bytes).await?;
+
+    Ok(())
+}
+
+/// Reads the attestation signed when the session was finalized and checks it against the
+/// manifest in the store, [`None`] if the session was not attested.
+pub async fn attestation(
+    context: &Context,
+    sequence_path: &types::SequencePathInStore,
+    handle: &Handle,
+) -> Result<Option<CheckedAttestation>> {
+    let path = sequence_path.path_session_attestation(handle.uuid());
+    if !context.store.exists(&path).await? {
+        return Ok(None);
+    }
+
+    let bytes = context.store.read_bytes(path).await?;
+    let attestation: types::SessionAttestation =
+        marshal::JsonSessionAttestation::try_from(bytes)?.try_into()?;
+
+    // A missing manifest means that the data was altered after the attestation
+    let manifest_path = sequence_path.path_session_manifest(handle.uuid());
+    let valid = context.store.exists(&manifest_path).await?
+        && verify_attestation(
+            &attestation,
+            &context.store.read_bytes(manifest_path).await?,
+        );
+
+    Ok(Some(CheckedAttestation { attestation, valid }))
+}
+
+/// Reads the manifest written in the store when the session was finalized.
+pub async fn read_manifest(
+    context: &Context,
+    path: impl AsRef<std::path::Path>,
+) -> Result<SessionManifest> {
+    let bytes = context.store.read_bytes(path).await?;
+    let manifest = marshal::JsonSessionManifest::try_from(bytes)?;
+    Ok(manifest.try_into()?)
+}
+
 /// Deletes the session from the database.
 pub async fn delete(
     context: &Context,
     handle: Handle,
     allow_data_loss: types::DataLossToken,
 ) -> Result<()> {
+    let mut tx = context.db.transaction().await?;
+
+    let record = db::session_find_by_id(&mut tx, handle.id()).await?;
+    sequence::lock_unless_held(
+        &mut tx,
+        record.sequence_id,
+        "session_delete",
+        handle.locator(),
+    )
+    .await?;
+
+    db::session_delete(&mut tx, handle.uuid(), allow_data_loss).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Records an upload into the session, postponing its expiration.
+pub async fn touch(context: &Context, handle: &Handle) -> Result<()> {
+    let mut cx = context.db.connection();
+    db::session_update_last_activity(&mut cx, handle.id(), types::Timestamp::now().as_i64())
+        .await?;
+    Ok(())
+}
+
+/// Aborts a session not finalized yet, deleting it along with its topics and the data
+/// already uploaded.
+///
+/// The records are deleted in a single transaction, the objects written to the store are
+/// removed once it is committed.
+pub async fn abort(
+    context: &Context,
+    handle: &Handle,
+    allow_data_loss: types::DataLossToken,
+) -> Result<()> {
+    let mut tx = context.db.transaction().await?;
+
+    let record = db::session_find_by_id(&mut tx, handle.id()).await?;
+    sequence::lock_unless_held(
+        &mut tx,
+        record.sequence_id,
+        "session_abort",
+        handle.locator(),
+    )
+    .await?;
+
+    // Read again under the lock, the session may have been finalized in the meantime
+    let record = db::session_find_by_id(&mut tx, handle.id()).await?;
+    if record.completion_timestamp().is_some() {
+        Err(core::Error::session_already_finalized(
+            handle.locator().to_string(),
+        ))?;
+    }
+
+    abort_locked(context, tx, &record, allow_data_loss).await
+}
+
+/// Aborts the sessions not finalized and without uploads since `before`, returns the
+/// number of sessions aborted. Sessions of sequences in the trash or under legal hold are
+/// kept.
+///
+/// Aborted sessions are deleted along with their topics, whose objects are removed from the
+/// store. Data files written by upserts whose upload was not completed are removed as well.
+pub async fn abort_expired(
+    context: &Context,
+    before: types::Timestamp,
+    allow_data_loss: types::DataLossToken,
+) -> Result<usize> {
     let mut cx = context.db.connection();
-    db::session_delete(&mut cx, handle.uuid(), allow_data_loss).await?;
+
+    let mut aborted = 0;
+    for record in db::session_find_all_inactive_before(&mut cx, before.as_i64()).await? {
+        let mut tx = context.db.transaction().await?;
+        sequence::lock_unless_held(
+            &mut tx,
+            record.sequence_id,
+            "session_abort",
+            &record.locator(),
+        )
+        .await?;
+
+        // The session may have been finalized, used or deleted in the meantime
+        let record = match db::session_find_by_id(&mut tx, record.session_id).await {
+            Ok(record) => record,
+            Err(db::Error::NotFound) => continue,
+            Err(e) => Err(e)?,
+        };
+        if record.completion_timestamp().is_some() || record.last_activity_timestamp() >= before {
+            continue;
+        }
+
+        abort_locked(context, tx, &record, allow_data_loss.clone()).await?;
+        aborted += 1;
+    }
+
+    Ok(aborted)
+}
+
+/// Deletes the session in `tx`, holding the lock of its sequence, and removes the objects
+/// of its topics and of its incomplete upserts from the store.
+async fn abort_locked(
+    context: &Context,
+    mut tx: db::Tx<'_>,
+    record: &db::SessionRecord,
+    allow_data_loss: types::DataLossToken,
+) -> Result<()> {
+    let folders: Vec<types::TopicPathInStore> = topic_records(&mut tx, &record.uuid())
+        .await?
+        .iter()
+        .filter_map(db::TopicRecord::path_in_store)
+        .collect();
+
+    let mut files = Vec::new();
+    for upsert in db::session_find_all_upserts(&mut tx, record.session_id).await? {
+        files.extend(topic::staged_upsert_files(context, &mut tx, &upsert).await?);
+    }
+
+    db::session_delete(&mut tx, &record.uuid(), allow_data_loss).await?;
+    tx.commit().await?;
+
+    for folder in &folders {
+        context.store.delete_recursive(folder.root()).await?;
+    }
+    for file in &files {
+        context.store.delete(file).await?;
+    }
+
+    trace!("session `{}` aborted", record.locator());
     Ok(())
 }
 
+/// Returns the records of the topics associated with the session `uuid`, sorted by locator.
+///
+/// Records are read from the database one page at a time.
+pub(crate) async fn topic_records(
+    exe: &mut impl db::AsExec,
+    uuid: &types::Uuid,
+) -> Result<Vec<db::TopicRecord>> {
+    let mut records = Vec::new();
+    loop {
+        let after = records
+            .last()
+            .map(|record: &db::TopicRecord| record.locator().to_string());
+        let page = db::session_find_topics_page(exe, uuid, after.as_deref(), PAGE_SIZE).await?;
+        let done = page.len() < PAGE_SIZE as usize;
+        records.extend(page);
+        if done {
+            return Ok(records);
+        }
+    }
+}
+
 /// Returns the topic list associated with this session.
 async fn topic_list(handle: &Handle, exe: &mut impl db::AsExec) -> Result<Vec<topic::Handle>> {
-    let topics = db::session_find_all_topics(exe, handle.uuid()).await?;
+    let topics = topic_records(exe, handle.uuid()).await?;
 
     Ok(topics
         .into_iter()
@@ -187,12 +704,21 @@ pub async fn metadata(context: &Context, handle: &Handle) -> Result<types::Sessi
 
     let db_session = db::session_find_by_id(&mut tx, handle.id()).await?;
 
-    let topics = topic_list(handle, &mut tx)
+    let mut topics: Vec<types::TopicLocator> = topic_list(handle, &mut tx)
         .await?
         .into_iter()
         .map(|handle| handle.locator().clone())
         .collect();
 
+    // Include the existing topics receiving rows from this session
+    for upsert in db::session_find_all_upserts(&mut tx, handle.id()).await? {
+        topics.push(
+            db::topic_find_by_id(&mut tx, upsert.topic_id)
+                .await?
+                .locator(),
+        );
+    }
+
     Ok(types::SessionMetadata {
         locator: db_session.locator(),
         created_at: db_session.creation_timestamp(),
@@ -212,10 +738,10 @@ mod tests {
 
     fn test_context(pool: sqlx::Pool<db::DatabaseType>) -> Context {
         let database = db::testing::Database::new(pool);
-        let store = store::testing::Store::new_random_on_tmp().unwrap();
-        let ts_gw = Arc::new(query::TimeseriesEngine::try_new((*store).clone(), 0).unwrap());
+        let store = Arc::new(store::Store::in_memory());
+        let ts_gw = Arc::new(query::TimeseriesEngine::try_new(store.clone(), 0).unwrap());
 
-        Context::new((*store).clone(), (*database).clone(), ts_gw)
+        Context::new(store, (*database).clone(), ts_gw)
     }
 
     #[sqlx::test(migrator = "db::testing::MIGRATOR")]
@@ -230,7 +756,7 @@ mod tests {
             .await
             .expect("Error creating sequence");
 
-        let session_handle = session::try_create(&context, seq_handle.locator().clone())
+        let session_handle = session::try_create(&context, seq_handle.locator().clone(), None)
             .await
             .expect("Error creating session");
 
@@ -0,0 +1,79 @@
+//! SQL queries run over the data of the topics.
+//!
+//! Each topic read by a statement is registered as a table, named by the client, holding the
+//! rows served by DoGet for the whole topic (e.g. the rows of upsert topics are merged by
+//! their primary key). Offloaded blobs are returned as references.
+//!
+//! Statements are planned when the query is requested, so that errors are reported
+//! immediately, and run again when the result is streamed with the returned ticket.
+use super::{Context, Error, topic};
+use log::trace;
+use mosaicod_core::{self as core, error::PublicResult as Result, types};
+use mosaicod_query as query;
+
+/// Plans `statement` over `tables`, each one mapping the name of a table to the topic it
+/// reads, returning the result to be streamed.
+///
+/// Fails with a bad request error if the statement is not a read-only query over the provided
+/// tables. The memory used by the query is bounded by `limits`.
+pub async fn plan(
+    context: &Context,
+    tables: &[(String, types::TopicLocator)],
+    statement: &str,
+    limits: &types::QueryLimits,
+) -> Result<query::TimeseriesResult> {
+    let limited_engine;
+    let engine = match limits.max_memory() {
+        Some(max_memory) => {
+            limited_engine = context
+                .timeseries_querier
+                .with_memory_limit(max_memory as usize)?;
+            &limited_engine
+        }
+        None => context.timeseries_querier.as_ref(),
+    };
+
+    let mut batch_size: Option<usize> = None;
+    let mut results = Vec::with_capacity(tables.len());
+
+    for (name, locator) in tables {
+        if name.is_empty() {
+            Err(core::Error::bad_request(format!(
+                "missing table name for topic `{locator}`"
+            )))?;
+        }
+
+        let handle = topic::Handle::try_from_locator(context, locator.clone()).await?;
+
+        if topic::status(context, &handle).await? == topic::Status::Empty {
+            Err(core::Error::missing_doput(locator.to_string()))?;
+        }
+
+        let metadata = topic::metadata(context, &handle).await?;
+        let topic_batch_size = topic::compute_optimal_batch_size(context, &handle).await?;
+        let result = topic::read(
+            context,
+            &handle,
+            &metadata.ontology_metadata.properties,
+            topic_batch_size,
+        )
+        .await?;
+
+        trace!("table `{}` reads topic `{}`", name, locator);
+
+        // Batches are sized for the topic with the largest rows
+        batch_size = Some(batch_size.map_or(topic_batch_size, |b| b.min(topic_batch_size)));
+        results.push((name.clone(), result));
+    }
+
+    let result = engine.sql(results, statement, batch_size).await.map_err(
+        |e| -> core::error::BoxPublicError {
+            match e {
+                query::Error::BadStatement(msg) => core::Error::bad_request(msg).into(),
+                e => Error::from(e).into(),
+            }
+        },
+    )?;
+
+    Ok(result)
+}
@@ -0,0 +1,565 @@
+//! Export of the topics as Delta Lake tables, so that engines reading the object store
+//! directly (e.g. Spark, Trino or DuckDB) can query the data without going through Flight.
+//!
+//! The table of a topic is rooted at the topic folder: its transaction log is written in the
+//! `_delta_log` folder next to the data files, which are referenced by the log without being
+//! copied. Data files linked to a shared or archived object are referenced by the absolute
+//! URL of the object, since engines can not follow the links. The log is synchronized with the data files when a session is finalized and when
+//! a topic is redacted, each synchronization committing a new version of the table that adds
+//! the new or rewritten data files and removes the deleted ones.
+//!
+//! Only the append-only topics are exported: the data files of upsert topics hold the rows
+//! replaced by later uploads, which engines reading the files directly would return. Rows are
+//! exported as uploaded, regardless of the deduplication policy of the topic.
+use super::{Context, topic};
+use arrow::datatypes::{DataType, Field, Schema};
+use log::{trace, warn};
+use mosaicod_core::{self as core, error::PublicResult as Result, params, types};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::path;
+
+/// Folder of the transaction log, relative to the root of the table.
+const LOG_FOLDER: &str = "_delta_log";
+
+/// Attempts to commit a version of a table, several synchronizations of the same table may
+/// try to commit the same version concurrently.
+const MAX_COMMIT_ATTEMPTS: usize = 5;
+
+/// Engine reported in the commits of the exported tables.
+const ENGINE_INFO: &str = concat!("mosaicod/", env!("CARGO_PKG_VERSION"));
+
+/// Format of the exported tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    Delta,
+}
+
+impl std::str::FromStr for TableFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "delta" => Ok(Self::Delta),
+            _ => Err(format!(
+                "unsupported table format `{value}`, expected `delta`"
+            )),
+        }
+    }
+}
+
+/// Exports the topics as tables readable by external engines.
+#[derive(Default)]
+pub struct TableExporter {
+    format: Option<TableFormat>,
+}
+
+impl TableExporter {
+    /// Creates an exporter that exports no topic.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Creates an exporter writing the tables in `format`.
+    pub fn new(format: TableFormat) -> Self {
+        Self {
+            format: Some(format),
+        }
+    }
+
+    /// Creates an exporter with the format configured in the parameters.
+    ///
+    /// See [`params::Params::table_export_format`].
+    pub fn from_params() -> std::result::Result<Self, core::Error> {
+        let param = &params::params().table_export_format;
+
+        if param.value.is_empty() {
+            return Ok(Self::disabled());
+        }
+
+        let format = param
+            .value
+            .parse()
+            .map_err(|e| core::Error::invalid_configuration(param.env.clone(), e))?;
+
+        Ok(Self::new(format))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.format.is_some()
+    }
+
+    /// Synchronizes the table of the topic with its data files, returning the committed
+    /// version of the table.
+    ///
+    /// Returns `None` if the table is already up to date, or if the topic is not exported.
+    pub async fn export(&self, context: &Context, handle: &topic::Handle) -> Result<Option<u64>> {
+        let Some(TableFormat::Delta) = self.format else {
+            return Ok(None);
+        };
+
+        let Some(path_in_store) = handle.path_in_store() else {
+            return Ok(None);
+        };
+
+        let mdata = topic::metadata(context, handle).await?;
+        let properties = &mdata.ontology_metadata.properties;
+        if properties.is_upsert() {
+            trace!("upsert topic `{}` is not exported", handle.locator());
+            return Ok(None);
+        }
+        let format = properties.serialization_format;
+
+        for _ in 0..MAX_COMMIT_ATTEMPTS {
+            let table = DeltaTable::read(context, path_in_store).await?;
+
+            let files = topic::data_files(context, path_in_store, format).await?;
+            if files.is_empty() {
+                return Ok(None);
+            }
+
+            let schema = match topic::registered_schema(context, handle).await? {
+                Some(schema) => schema,
+                None => topic::arrow_schema(context, handle, format).await?,
+            };
+            let (schema, timestamp_ntz) = delta_schema(&schema).map_err(|e| {
+                core::Error::bad_request(format!(
+                    "topic `{}` can not be exported: {e}",
+                    handle.locator()
+                ))
+            })?;
+            let schema = schema.to_string();
+
+            let now = types::Timestamp::now().as_i64() / 1_000_000;
+            let mut actions = Vec::new();
+
+            if table.schema.as_ref() != Some(&schema) {
+                actions.push(protocol(timestamp_ntz));
+                actions.push(json!({
+                    "metaData": {
+                        "id": handle.uuid().to_string(),
+                        "name": handle.locator().to_string(),
+                        "format": { "provider": "parquet", "options": {} },
+                        "schemaString": schema,
+                        "partitionColumns": [],
+                        "configuration": {},
+                        "createdTime": now,
+                    }
+                }));
+            }
+
+            let mut current = BTreeMap::new();
+            for (_, file) in files {
+                let relative = table_path(context, path_in_store, &file).await?;
+
+                let entry = DataFile {
+                    size: context.store.size(&file).await? as i64,
+                    modification_time: modification_time(context, &file).await?,
+                };
+
+                // Data files rewritten in place (e.g. by a redaction) are added again
+                if table.files.get(&relative) != Some(&entry) {
+                    actions.push(json!({
+                        "add": {
+                            "path": relative,
+                            "partitionValues": {},
+                            "size": entry.size,
+                            "modificationTime": entry.modification_time,
+                            "dataChange": true,
+                        }
+                    }));
+                }
+                current.insert(relative, entry);
+            }
+
+            for removed in table
+                .files
+                .keys()
+                .filter(|path| !current.contains_key(*path))
+            {
+                actions.push(json!({
+                    "remove": {
+                        "path": removed,
+                        "deletionTimestamp": now,
+                        "dataChange": true,
+                    }
+                }));
+            }
+
+            if actions.is_empty() {
+                return Ok(None);
+            }
+
+            actions.insert(
+                0,
+                json!({
+                    "commitInfo": {
+                        "timestamp": now,
+                        "operation": "WRITE",
+                        "engineInfo": ENGINE_INFO,
+                    }
+                }),
+            );
+
+            let version = table.version.map_or(0, |version| version + 1);
+            let mut commit = String::new();
+            for action in actions {
+                commit.push_str(&action.to_string());
+                commit.push('\n');
+            }
+
+            if context
+                .store
+                .write_bytes_if_absent(commit_path(path_in_store, version), commit)
+                .await?
+            {
+                trace!(
+                    "exported version {} of topic `{}`",
+                    version,
+                    handle.locator()
+                );
+                return Ok(Some(version));
+            }
+
+            trace!(
+                "version {} of topic `{}` committed concurrently, retrying",
+                version,
+                handle.locator()
+            );
+        }
+
+        Err(core::Error::internal(Some(format!(
+            "unable to commit a new version of the table of topic `{}`",
+            handle.locator()
+        ))))?
+    }
+
+    /// Exports the topics, a topic failing to be exported is reported in the logs and does
+    /// not prevent exporting the others.
+    pub(crate) async fn export_all(&self, context: &Context, locators: Vec<types::TopicLocator>) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        for locator in locators {
+            let res = match topic::Handle::try_from_locator(context, locator.clone()).await {
+                Ok(handle) => self.export(context, &handle).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = res {
+                warn!("unable to export topic `{}`: {}", locator, e);
+            }
+        }
+    }
+}
+
+/// Size and modification time of a data file added to a table.
+#[derive(Debug, PartialEq, Eq)]
+struct DataFile {
+    size: i64,
+    /// UNIX timestamp in milliseconds
+    modification_time: i64,
+}
+
+/// State of a Delta table, obtained by replaying its transaction log.
+#[derive(Default)]
+struct DeltaTable {
+    /// Latest committed version, `None` if the table does not exist yet
+    version: Option<u64>,
+    /// Schema of the latest metadata of the table
+    schema: Option<String>,
+    /// Data files of the table, by path relative to the root of the table (or absolute URL)
+    files: BTreeMap<String, DataFile>,
+}
+
+impl DeltaTable {
+    async fn read(context: &Context, path_in_store: &types::TopicPathInStore) -> Result<Self> {
+        let commits = context
+            .store
+            .list(path_in_store.root().join(LOG_FOLDER), Some("json"))
+            .await?;
+
+        let mut commits: Vec<(u64, String)> = commits
+            .into_iter()
+            .filter_map(|commit| {
+                let version = path::Path::new(&commit)
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| stem.parse().ok())?;
+                Some((version, commit))
+            })
+            .collect();
+        commits.sort_unstable();
+
+        let mut table = Self::default();
+        for (version, commit) in commits {
+            let bytes = context.store.read_bytes(&commit).await?;
+            table.replay(&bytes).map_err(|e| {
+                core::Error::internal(Some(format!("invalid table commit `{commit}`: {e}")))
+            })?;
+            table.version = Some(version);
+        }
+
+        Ok(table)
+    }
+
+    /// Applies the actions of a commit to the state of the table.
+    fn replay(&mut self, commit: &[u8]) -> std::result::Result<(), serde_json::Error> {
+        for line in commit.split(|byte| *byte == b'\n') {
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+
+            let action: Value = serde_json::from_slice(line)?;
+            if let Some(add) = action.get("add") {
+                if let Some(path) = add["path"].as_str() {
+                    self.files.insert(
+                        path.to_owned(),
+                        DataFile {
+                            size: add["size"].as_i64().unwrap_or_default(),
+                            modification_time: add["modificationTime"].as_i64().unwrap_or_default(),
+                        },
+                    );
+                }
+            } else if let Some(remove) = action.get("remove") {
+                if let Some(path) = remove["path"].as_str() {
+                    self.files.remove(path);
+                }
+            } else if let Some(metadata) = action.get("metaData") {
+                self.schema = metadata["schemaString"].as_str().map(str::to_owned);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Path of `file`, a data file of the topic, in the table of the topic.
+///
+/// Data files are referenced relative to the root of the table, except the ones linked to
+/// a shared or archived object (see [`mosaicod_store::LINK_EXTENSION`]): their link object
+/// is not readable by the engines, which are given the absolute URL of the target instead.
+async fn table_path(
+    context: &Context,
+    path_in_store: &types::TopicPathInStore,
+    file: &str,
+) -> Result<String> {
+    if let Some(target) = context.store.link_target(file).await? {
+        return Ok(context.store.object_url(target)?.to_string());
+    }
+
+    Ok(path::Path::new(file)
+        .strip_prefix(path_in_store.root())
+        .map_err(|_| {
+            core::Error::internal(Some(format!(
+                "data file `{file}` is not stored in the topic folder"
+            )))
+        })?
+        .to_string_lossy()
+        .into_owned())
+}
+
+/// Path of the commit of `version` of the table of the topic.
+fn commit_path(path_in_store: &types::TopicPathInStore, version: u64) -> path::PathBuf {
+    path_in_store
+        .root()
+        .join(LOG_FOLDER)
+        .join(format!("{version:020}.json"))
+}
+
+async fn modification_time(context: &Context, file: &str) -> Result<i64> {
+    let modified = context.store.last_modified(file).await?;
+    Ok(modified
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as i64))
+}
+
+/// Returns the protocol required to read and write the table.
+///
+/// Timestamps without time zone are only supported by the readers of the `timestampNtz`
+/// table feature.
+fn protocol(timestamp_ntz: bool) -> Value {
+    if timestamp_ntz {
+        json!({
+            "protocol": {
+                "minReaderVersion": 3,
+                "minWriterVersion": 7,
+                "readerFeatures": ["timestampNtz"],
+                "writerFeatures": ["timestampNtz"],
+            }
+        })
+    } else {
+        json!({ "protocol": { "minReaderVersion": 1, "minWriterVersion": 2 } })
+    }
+}
+
+/// Converts an arrow schema into the schema of a Delta table, returning whether it contains
+/// timestamps without time zone.
+fn delta_schema(schema: &Schema) -> std::result::Result<(Value, bool), String> {
+    let mut timestamp_ntz = false;
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| delta_field(field, &mut timestamp_ntz))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok((json!({ "type": "struct", "fields": fields }), timestamp_ntz))
+}
+
+fn delta_field(field: &Field, timestamp_ntz: &mut bool) -> std::result::Result<Value, String> {
+    Ok(json!({
+        "name": field.name(),
+        "type": delta_type(field.data_type(), timestamp_ntz)
+            .map_err(|e| format!("column `{}`: {e}", field.name()))?,
+        "nullable": field.is_nullable(),
+        "metadata": {},
+    }))
+}
+
+fn delta_type(
+    data_type: &DataType,
+    timestamp_ntz: &mut bool,
+) -> std::result::Result<Value, String> {
+    let primitive = match data_type {
+        DataType::Boolean => "boolean",
+        DataType::Int8 => "byte",
+        DataType::Int16 | DataType::UInt8 => "short",
+        DataType::Int32 | DataType::UInt16 => "integer",
+        DataType::Int64 | DataType::UInt32 => "long",
+        DataType::Float32 => "float",
+        DataType::Float64 => "double",
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => "string",
+        DataType::Binary
+        | DataType::LargeBinary
+        | DataType::BinaryView
+        | DataType::FixedSizeBinary(_) => "binary",
+        DataType::Date32 => "date",
+        DataType::Timestamp(_, Some(_)) => "timestamp",
+        DataType::Timestamp(_, None) => {
+            *timestamp_ntz = true;
+            "timestamp_ntz"
+        }
+        DataType::Decimal128(precision, scale) if *precision <= 38 && *scale >= 0 => {
+            return Ok(json!(format!("decimal({precision},{scale})")));
+        }
+        DataType::List(item) | DataType::LargeList(item) | DataType::FixedSizeList(item, _) => {
+            return Ok(json!({
+                "type": "array",
+                "elementType": delta_type(item.data_type(), timestamp_ntz)?,
+                "containsNull": item.is_nullable(),
+            }));
+        }
+        DataType::Struct(fields) => {
+            let fields = fields
+                .iter()
+                .map(|field| delta_field(field, timestamp_ntz))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            return Ok(json!({ "type": "struct", "fields": fields }));
+        }
+        DataType::Map(entries, _) => {
+            let DataType::Struct(fields) = entries.data_type() else {
+                return Err(format!("unsupported map entries `{}`", entries.data_type()));
+            };
+            let [key, value] = fields.iter().collect::<Vec<_>>()[..] else {
+                return Err(format!("unsupported map entries `{}`", entries.data_type()));
+            };
+            return Ok(json!({
+                "type": "map",
+                "keyType": delta_type(key.data_type(), timestamp_ntz)?,
+                "valueType": delta_type(value.data_type(), timestamp_ntz)?,
+                "valueContainsNull": value.is_nullable(),
+            }));
+        }
+        other => return Err(format!("unsupported type `{other}`")),
+    };
+
+    Ok(json!(primitive))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::TimeUnit;
+    use std::sync::Arc;
+
+    #[test]
+    fn delta_schema_conversion() {
+        let schema = Schema::new(vec![
+            Field::new("timestamp_ns", DataType::Int64, false),
+            Field::new("speed", DataType::Float32, true),
+            Field::new(
+                "samples",
+                DataType::List(Arc::new(Field::new("item", DataType::Float64, true))),
+                true,
+            ),
+            Field::new(
+                "position",
+                DataType::Struct(
+                    vec![
+                        Field::new("x", DataType::Float64, false),
+                        Field::new("y", DataType::Float64, false),
+                    ]
+                    .into(),
+                ),
+                true,
+            ),
+        ]);
+
+        let (delta, timestamp_ntz) = delta_schema(&schema).unwrap();
+        assert!(!timestamp_ntz);
+        assert_eq!(
+            delta["fields"][0],
+            json!({ "name": "timestamp_ns", "type": "long", "nullable": false, "metadata": {} })
+        );
+        assert_eq!(delta["fields"][1]["type"], "float");
+        assert_eq!(
+            delta["fields"][2]["type"],
+            json!({ "type": "array", "elementType": "double", "containsNull": true })
+        );
+        assert_eq!(delta["fields"][3]["type"]["fields"][1]["name"], "y");
+
+        let schema = Schema::new(vec![Field::new(
+            "created",
+            DataType::Timestamp(TimeUnit::Microsecond, None),
+            false,
+        )]);
+        let (delta, timestamp_ntz) = delta_schema(&schema).unwrap();
+        assert!(timestamp_ntz);
+        assert_eq!(delta["fields"][0]["type"], "timestamp_ntz");
+
+        let schema = Schema::new(vec![Field::new("value", DataType::UInt64, false)]);
+        assert!(delta_schema(&schema).is_err());
+    }
+
+    #[test]
+    fn delta_log_replay() {
+        let mut table = DeltaTable::default();
+        table
+            .replay(
+                br#"{"commitInfo":{"timestamp":1}}
+{"metaData":{"id":"x","schemaString":"s1"}}
+{"add":{"path":"data/00000.parquet","size":10,"modificationTime":1}}
+{"add":{"path":"data/00001.parquet","size":20,"modificationTime":1}}
+"#,
+            )
+            .unwrap();
+        table
+            .replay(
+                br#"{"remove":{"path":"data/00000.parquet"}}
+{"add":{"path":"data/00001.parquet","size":30,"modificationTime":2}}
+"#,
+            )
+            .unwrap();
+
+        assert_eq!(table.schema.as_deref(), Some("s1"));
+        assert_eq!(table.files.len(), 1);
+        assert_eq!(
+            table.files["data/00001.parquet"],
+            DataFile {
+                size: 30,
+                modification_time: 2
+            }
+        );
+        assert!(table.replay(b"not json").is_err());
+    }
+}
@@ -0,0 +1,264 @@
+//! Tiering of the data files between the standard and the archive storage.
+//!
+//! The store samples the reads of its objects (see [`store::AccessLog`]), which are merged
+//! in the database by [`flush_access_log`]. Data files not read for a while, or never read
+//! since their topic was published, are cold: [`demote`] moves them to the archive folder of
+//! the store ([`store::ARCHIVE_FOLDER`]) and replaces them with a link, so that they can
+//! still be read, and synchronizes the exported tables of their topics (see
+//! [`super::TableExporter`]). [`report`] estimates the savings of demoting the cold data files before
+//! acting.
+//!
+//! Archived data files are recorded as shared objects referenced by their chunk, so they are
+//! deleted along with the chunk by [`super::topic::collect_shared_objects`].
+use super::Context;
+use log::{debug, trace};
+use mosaicod_core::{error::PublicResult as Result, params, types};
+use mosaicod_db as db;
+use mosaicod_store as store;
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+/// Number of cold data files retrieved at a time.
+const PAGE_SIZE: usize = 256;
+
+/// Bytes in a GB, the unit of the storage prices.
+const GB: f64 = 1e9;
+
+/// Settings of the tiering.
+#[derive(Debug, Clone)]
+pub struct TieringConfig {
+    /// Time since the last read after which a data file is cold
+    pub cold_after: Duration,
+    /// Price of a GB stored for a month in the standard storage
+    pub standard_price: f64,
+    /// Price of a GB stored for a month in the archive storage
+    pub archive_price: f64,
+}
+
+impl TieringConfig {
+    /// Returns the tiering settings of the server configuration.
+    pub fn configured() -> Self {
+        let params = params::params();
+        Self {
+            cold_after: Duration::from_secs(params.tiering_cold_after.value),
+            standard_price: params.tiering_standard_price.value,
+            archive_price: params.tiering_archive_price.value,
+        }
+    }
+
+    /// Returns the settings with a different time after which a data file is cold.
+    pub fn with_cold_after(mut self, cold_after: Duration) -> Self {
+        self.cold_after = cold_after;
+        self
+    }
+
+    /// Data files not read since the returned time are cold.
+    fn cold_before(&self) -> types::Timestamp {
+        types::Timestamp::from(types::Timestamp::now().as_i64() - self.cold_after.as_nanos() as i64)
+    }
+
+    /// Returns the estimated monthly savings of moving `size_bytes` to the archive storage.
+    pub fn monthly_savings(&self, size_bytes: u64) -> f64 {
+        size_bytes as f64 / GB * (self.standard_price - self.archive_price)
+    }
+}
+
+/// A data file which can be demoted to the archive storage.
+#[derive(Debug, Clone)]
+pub struct ColdDataFile {
+    pub path: String,
+    pub size_bytes: u64,
+    /// Estimated number of reads
+    pub read_count: u64,
+    /// `None` if the data file was never read since the reads are sampled
+    pub last_read_at: Option<types::Timestamp>,
+}
+
+/// Savings of demoting the cold data files to the archive storage.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub data_files: u64,
+    pub size_bytes: u64,
+    /// Estimated savings for each month the data files are archived
+    pub monthly_savings: f64,
+    /// First cold data files
+    pub candidates: Vec<ColdDataFile>,
+}
+
+/// Outcome of a demotion.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    /// Data files moved to the archive storage
+    pub demoted: usize,
+    pub size_bytes: u64,
+}
+
+/// Merges the reads sampled by the store since the previous flush in the database,
+/// returning the number of objects read.
+///
+/// The sampled reads are discarded if they can't be merged, losing at most the reads since
+/// the previous flush.
+pub async fn flush_access_log(context: &Context) -> Result<usize> {
+    let records: Vec<_> = context
+        .store
+        .metrics()
+        .access()
+        .drain()
+        .into_iter()
+        .map(|(path, access)| db::ObjectAccessRecord::new(path, access.reads, access.last_read_at))
+        .collect();
+
+    if records.is_empty() {
+        return Ok(0);
+    }
+
+    let mut cx = context.db.connection();
+    db::object_access_merge(&mut cx, &records).await?;
+
+    trace!("merged the reads of {} objects", records.len());
+
+    Ok(records.len())
+}
+
+/// Deletes the reads recorded for the objects no longer stored, returning the number of
+/// objects forgotten.
+pub async fn forget_deleted_objects(context: &Context) -> Result<u64> {
+    let mut cx = context.db.connection();
+    Ok(db::object_access_delete_unreferenced(&mut cx).await?)
+}
+
+/// Returns the savings of demoting the data files cold according to `config`, along with
+/// at most `limit` of them.
+pub async fn report(context: &Context, config: &TieringConfig, limit: usize) -> Result<Report> {
+    flush_access_log(context).await?;
+
+    let cold_before = config.cold_before().as_i64();
+
+    let mut cx = context.db.connection();
+    let (data_files, size_bytes) = db::cold_data_file_summary(&mut cx, cold_before).await?;
+    let candidates = db::cold_data_file_find_page(&mut cx, cold_before, None, limit as i64)
+        .await?
+        .into_iter()
+        .map(|record| ColdDataFile {
+            path: record.data_file().to_string_lossy().into_owned(),
+            size_bytes: record.size_bytes as u64,
+            read_count: record.read_count(),
+            last_read_at: record.last_read_timestamp(),
+        })
+        .collect();
+
+    Ok(Report {
+        data_files,
+        size_bytes,
+        monthly_savings: config.monthly_savings(size_bytes),
+        candidates,
+    })
+}
+
+/// Moves at most `limit` data files cold according to `config` to the archive storage.
+///
+/// Nothing is moved if the store layout does not allow links (see
+/// [`types::StoreLayout::allows_links`]).
+pub async fn demote(context: &Context, config: &TieringConfig, limit: usize) -> Result<Stats> {
+    flush_access_log(context).await?;
+
+    if !context.store_layout.allows_links() {
+        trace!("data files not moved, the store layout does not allow links");
+        return Ok(Stats::default());
+    }
+
+    let cold_before = config.cold_before().as_i64();
+    let mut stats = Stats::default();
+    let mut topic_ids = BTreeSet::new();
+
+    let mut cx = context.db.connection();
+    let mut after = None;
+    while stats.demoted < limit {
+        let page =
+            db::cold_data_file_find_page(&mut cx, cold_before, after, PAGE_SIZE as i64).await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        after = Some(last.chunk_id);
+
+        for cold in page {
+            if stats.demoted >= limit {
+                break;
+            }
+            if archive_data_file(context, &cold, cold_before).await? {
+                stats.demoted += 1;
+                stats.size_bytes += cold.size_bytes as u64;
+                topic_ids.insert(cold.topic_id);
+            }
+        }
+    }
+
+    // Archived data files are replaced with links, which the tables of their topics have
+    // to reference by their target
+    let mut locators = Vec::with_capacity(topic_ids.len());
+    for topic_id in topic_ids {
+        match db::topic_find_by_id(&mut cx, topic_id).await {
+            Ok(record) => locators.push(record.locator()),
+            Err(db::Error::NotFound) => {}
+            Err(e) => Err(e)?,
+        }
+    }
+    context.table_exporter.export_all(context, locators).await;
+
+    if stats.demoted > 0 {
+        debug!(
+            "{} data files ({} bytes) moved to the archive storage",
+            stats.demoted, stats.size_bytes
+        );
+    }
+
+    Ok(stats)
+}
+
+/// Moves the data file of a chunk to the archive folder, replacing it with a link. Returns
+/// false if the data file is no longer cold (e.g. it was read or shared meanwhile).
+async fn archive_data_file(
+    context: &Context,
+    cold: &db::ColdDataFileRecord,
+    cold_before: i64,
+) -> Result<bool> {
+    let mut tx = context.db.transaction().await?;
+
+    // The lock prevents a concurrent compaction, copy or relocation of the topic
+    match db::topic_lock(&mut tx, cold.topic_id).await {
+        Ok(()) => {}
+        Err(db::Error::NotFound) => return Ok(false),
+        Err(e) => Err(e)?,
+    }
+    match db::cold_data_file_find_by_chunk(&mut tx, cold.chunk_id, cold_before).await {
+        Ok(_) => {}
+        Err(db::Error::NotFound) => return Ok(false),
+        Err(e) => Err(e)?,
+    }
+
+    let data_file = cold.data_file();
+    let extension = data_file.extension().and_then(|ext| ext.to_str());
+    let path = store::archived_object_path(extension);
+    let archived = db::shared_object_create(&mut tx, &path.to_string_lossy()).await?;
+    db::chunk_shared_object_create(&mut tx, cold.chunk_id, archived.id()).await?;
+    db::object_access_delete(&mut tx, &data_file.to_string_lossy()).await?;
+
+    // Data files linked to an unrecorded object (e.g. by a demotion interrupted before
+    // committing) are archived from the target, since links to links are not resolved
+    let target = context.store.link_target(data_file).await?;
+    context
+        .store
+        .copy(target.as_deref().unwrap_or(data_file), &path)
+        .await?;
+    context.store.replace_with_link(data_file, &path).await?;
+
+    tx.commit().await?;
+
+    trace!(
+        "data file `{}` archived as `{}`",
+        data_file.display(),
+        path.display()
+    );
+
+    Ok(true)
+}
@@ -1,18 +1,23 @@
-use super::{Context, Error, session};
+use super::{Chunk, Context, Error, sequence, session};
+use arrow::array::RecordBatch;
 use arrow::datatypes::SchemaRef;
-use log::{trace, warn};
+use futures::TryStreamExt;
+use log::{debug, trace, warn};
 use mosaicod_core::types::TopicMetadataProperties;
 use mosaicod_core::{self as core, error::PublicResult as Result, params, types};
 use mosaicod_db as db;
 use mosaicod_ext as ext;
 use mosaicod_marshal as marshal;
+use mosaicod_query as query;
 use mosaicod_rw::{self as rw, ToProperties};
 use mosaicod_store as store;
+use std::collections::{HashMap, hash_map::Entry};
+use std::ops::Range;
 use std::path;
 use std::sync::Arc;
 
 /// Define topic metadata type containing JSON user metadata
-type TopicMetadata = types::TopicMetadata<marshal::JsonMetadataBlob>;
+pub type TopicMetadata = types::TopicMetadata<marshal::JsonMetadataBlob>;
 type TopicOntologyMetadata = types::TopicOntologyMetadata<marshal::JsonMetadataBlob>;
 
 #[derive(PartialEq)]
@@ -25,6 +30,13 @@ pub enum Status {
     Finalized,
 }
 
+/// Upload of rows into an existing upsert topic by a session other than the one that
+/// created the topic.
+struct Upsert {
+    id: i32,
+    uuid: types::Uuid,
+}
+
 /// Handle containing topic identifiers.
 /// It's used by all functions (except creation) in this module to indicate the topic to operate on.
 pub struct Handle {
@@ -32,6 +44,9 @@ pub struct Handle {
     uuid: types::Uuid,
     locator: types::TopicLocator,
     path_in_store: Option<types::TopicPathInStore>,
+
+    /// Set if the handle is used to upsert rows into the topic
+    upsert: Option<Upsert>,
 }
 
 impl Handle {
@@ -46,6 +61,7 @@ impl Handle {
             id,
             uuid,
             path_in_store,
+            upsert: None,
         }
     }
 
@@ -61,6 +77,7 @@ impl Handle {
             id: db_topic.topic_id,
             uuid: db_topic.uuid(),
             path_in_store: db_topic.path_in_store(),
+            upsert: None,
         })
     }
 
@@ -76,6 +93,7 @@ impl Handle {
             id: db_topic.topic_id,
             uuid: db_topic.uuid(),
             path_in_store: db_topic.path_in_store(),
+            upsert: None,
         })
     }
 
@@ -83,6 +101,43 @@ impl Handle {
         &self.uuid
     }
 
+    /// Returns the key required to upload data into the topic: the topic UUID, or the upsert
+    /// UUID if the handle is used to upsert rows into an existing topic.
+    pub fn upload_key(&self) -> &types::Uuid {
+        self.upsert
+            .as_ref()
+            .map_or(&self.uuid, |upsert| &upsert.uuid)
+    }
+
+    /// Checks that `key` allows to upload data into the topic.
+    ///
+    /// The key is either the topic UUID or the UUID of an upsert of the topic, in that case
+    /// the returned handle is used to upsert rows into the topic.
+    pub async fn with_upload_key(mut self, context: &Context, key: &types::Uuid) -> Result<Self> {
+        if key.ct_eq(&self.uuid) {
+            return Ok(self);
+        }
+
+        let mut cx = context.db.connection();
+        let upsert = db::topic_upsert_find_by_uuid(&mut cx, key)
+            .await
+            .ok()
+            .filter(|upsert| upsert.topic_id == self.id);
+
+        let Some(upsert) = upsert else {
+            return Err(core::Error::unauthorized(
+                "received uuid does not match the topic uuid.".to_string(),
+            ))?;
+        };
+
+        self.upsert = Some(Upsert {
+            id: upsert.upsert_id,
+            uuid: upsert.uuid(),
+        });
+
+        Ok(self)
+    }
+
     pub fn locator(&self) -> &types::TopicLocator {
         &self.locator
     }
@@ -98,7 +153,9 @@ impl Handle {
 
 /// Creates a new database entry for this topic.
 ///
-/// If a record with the same name already exists an error [`Error::TopicAlreadyExists`] is returned.
+/// If a record with the same name already exists an error [`Error::TopicAlreadyExists`] is returned,
+/// unless the existing topic is an upsert topic created by a previous session. In that case
+/// the returned handle is used to upsert rows into the existing topic.
 ///
 /// Additional checks about the scope of the topic are performed. If the topic locator is
 /// not a child of the related sequence locator an error [`Error::Unauthorized`] is returned.
@@ -108,6 +165,43 @@ pub async fn try_create(
     session_handle: &session::Handle,
     ontology_metadata: TopicOntologyMetadata,
 ) -> Result<Handle> {
+    try_create_with_options(
+        context,
+        locator,
+        session_handle,
+        ontology_metadata,
+        CreateOptions::default(),
+    )
+    .await
+}
+
+/// Optional settings of a topic creation, see [`try_create_with_options`].
+#[derive(Default)]
+pub struct CreateOptions {
+    /// If set, the rows are upserted only if the existing upsert topic is still at that
+    /// [`version`] when the upload starts. This lets clients upload only the rows changed
+    /// since the version they hold.
+    pub base_version: Option<u64>,
+    /// Schema registered for the topic, the uploaded data are rejected if they don't match it.
+    pub arrow_schema: Option<SchemaRef>,
+    /// UUID of the new topic, a random one is generated if not set.
+    pub uuid: Option<types::Uuid>,
+}
+
+/// Same as [`try_create`], with the additional settings in `options`.
+pub async fn try_create_with_options(
+    context: &Context,
+    locator: types::TopicLocator,
+    session_handle: &session::Handle,
+    ontology_metadata: TopicOntologyMetadata,
+    options: CreateOptions,
+) -> Result<Handle> {
+    let CreateOptions {
+        base_version,
+        arrow_schema,
+        uuid,
+    } = options;
+
     let mut tx = context.db.transaction().await?;
 
     // Session must not be already finalized.
@@ -131,6 +225,70 @@ pub async fn try_create(
         ))?;
     }
 
+    let properties = &ontology_metadata.properties;
+
+    // Upsert topics created by a previous session receive the rows of the new session
+    let existing = db::topic_find_by_locator(&mut tx, &locator)
+        .await
+        .ok()
+        .filter(|topic| topic.primary_key().is_some());
+
+    if let Some(existing) = existing {
+        if let Some(arrow_schema) = &arrow_schema {
+            check_upsert_schema(&existing, arrow_schema)?;
+        }
+
+        let upsert =
+            try_create_upsert(&mut tx, &existing, session_handle, properties, base_version).await?;
+        tx.commit().await?;
+
+        return Ok(Handle {
+            locator,
+            id: existing.topic_id,
+            uuid: existing.uuid(),
+            path_in_store: existing.path_in_store(),
+            upsert: Some(upsert),
+        });
+    }
+
+    if base_version.is_some() {
+        Err(core::Error::bad_request(
+            "a base version can only be set when upserting rows into an existing topic".to_owned(),
+        ))?;
+    }
+
+    if properties.is_upsert() {
+        if properties.dedup_policy != types::DedupPolicy::None {
+            Err(core::Error::bad_request(
+                "upsert topics can not declare a dedup policy".to_owned(),
+            ))?;
+        }
+        if properties.serialization_format.is_media() {
+            Err(core::Error::bad_request(
+                "media topics can not declare a primary key".to_owned(),
+            ))?;
+        }
+    }
+
+    // The registered schema must be valid for the data of the topic
+    let encoded_schema = match &arrow_schema {
+        Some(arrow_schema) => {
+            ext::arrow::check_schema(arrow_schema)?;
+            for key in [
+                properties.sort_key.as_deref(),
+                properties.dedup_policy.key(),
+                properties.primary_key.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            {
+                ext::arrow::check_key_column(arrow_schema, key)?;
+            }
+            Some(ext::arrow::schema_to_ipc(arrow_schema)?)
+        }
+        None => None,
+    };
+
     let mut record = db::TopicRecord::new(
         locator.clone(),
         seq_rec.sequence_id,
@@ -141,7 +299,15 @@ pub async fn try_create(
             .serialization_format
             .to_string(),
         None,
-    );
+    )
+    .with_sort_key(ontology_metadata.properties.sort_key.clone())
+    .with_dedup_policy(&ontology_metadata.properties.dedup_policy)
+    .with_primary_key(ontology_metadata.properties.primary_key.clone())
+    .with_arrow_schema(encoded_schema);
+
+    if let Some(uuid) = uuid {
+        record = record.with_uuid(uuid);
+    }
 
     if let Some(user_metadata) = &ontology_metadata.user_metadata {
         record = record.with_user_metadata(user_metadata.clone());
@@ -149,6 +315,14 @@ pub async fn try_create(
 
     let record = db::topic_create(&mut tx, &record).await?;
 
+    if let Some(encoded_schema) = record.arrow_schema() {
+        db::topic_schema_create(
+            &mut tx,
+            &db::TopicSchemaRecord::new(record.topic_id, 1, encoded_schema.to_vec()),
+        )
+        .await?;
+    }
+
     tx.commit().await?;
 
     let topic_handle = Handle {
@@ -156,11 +330,103 @@ pub async fn try_create(
         id: record.topic_id,
         uuid: record.uuid(),
         path_in_store: None,
+        upsert: None,
     };
 
     Ok(topic_handle)
 }
 
+/// Fails if a schema different from the one registered for the upsert topic `topic` is
+/// provided when upserting rows.
+fn check_upsert_schema(topic: &db::TopicRecord, arrow_schema: &SchemaRef) -> Result<()> {
+    let locator = topic.locator();
+    let Some(registered) = topic.arrow_schema() else {
+        Err(core::Error::bad_request(format!(
+            "topic `{}` has no registered schema, a schema can only be registered when the topic is created",
+            locator
+        )))?
+    };
+
+    let registered = ext::arrow::schema_from_ipc(registered)?;
+    ext::arrow::check_registered_schema(&registered, arrow_schema)
+        .map_err(|e| core::Error::topic_schema_mismatch(locator.to_string(), e.to_string()))?;
+
+    Ok(())
+}
+
+/// Registers the upsert of rows into the existing upsert topic `topic` by a new session.
+async fn try_create_upsert(
+    exe: &mut impl db::AsExec,
+    topic: &db::TopicRecord,
+    session_handle: &session::Handle,
+    properties: &types::TopicOntologyProperties,
+    base_version: Option<u64>,
+) -> Result<Upsert> {
+    let locator = topic.locator();
+
+    if topic.session_id == session_handle.id() {
+        Err(core::Error::already_exists(locator.to_string()))?;
+    }
+
+    if topic.primary_key() != properties.primary_key.as_deref() {
+        Err(core::Error::bad_request(format!(
+            "topic `{}` is an upsert topic with primary key `{}`",
+            locator,
+            topic.primary_key().unwrap_or_default()
+        )))?;
+    }
+
+    // Rows are upserted only once the first upload of the topic is completed
+    if topic.completion_timestamp().is_none() {
+        Err(core::Error::topic_upload_in_progress(locator.to_string()))?;
+    }
+
+    // Checked again when the upload starts, fails early if the topic already changed
+    if let Some(base_version) = base_version {
+        check_base_version(exe, topic.topic_id, &locator, base_version).await?;
+    }
+
+    let record = db::TopicUpsertRecord::new(topic.topic_id, session_handle.id())
+        .with_base_version(base_version);
+    let record = db::topic_upsert_create(exe, &record).await?;
+
+    Ok(Upsert {
+        id: record.upsert_id,
+        uuid: record.uuid(),
+    })
+}
+
+/// Fails if the upsert topic is not at version `base_version`.
+async fn check_base_version(
+    exe: &mut impl db::AsExec,
+    topic_id: i32,
+    locator: &types::TopicLocator,
+    base_version: u64,
+) -> Result<()> {
+    let version = db::topic_upsert_version(exe, topic_id).await?;
+    if version != base_version {
+        Err(core::Error::topic_version_conflict(
+            locator.to_string(),
+            base_version,
+            version,
+        ))?;
+    }
+    Ok(())
+}
+
+/// Returns the version of an upsert topic, i.e. the number of upserts completed on it, or
+/// `None` if the topic is not an upsert topic.
+pub async fn version(context: &Context, handle: &Handle) -> Result<Option<u64>> {
+    let mut cx = context.db.connection();
+
+    let record = db::topic_find_by_id(&mut cx, handle.id).await?;
+    if record.primary_key().is_none() {
+        return Ok(None);
+    }
+
+    Ok(Some(db::topic_upsert_version(&mut cx, handle.id).await?))
+}
+
 /// Private method to tell the topic status (just created, uploading data, finalized).
 ///
 /// Note: please use this function instead of [`status`] if you need to call it internally
@@ -188,6 +454,17 @@ pub async fn status(context: &Context, handle: &Handle) -> Result<Status> {
     impl_status(handle, &mut cx).await
 }
 
+/// Returns true if the data of the topic is visible to the readers, i.e. the session that
+/// created it has been finalized.
+///
+/// Rows upserted by sessions not finalized yet are not visible, but don't prevent reading
+/// the published ones.
+pub async fn is_published(context: &Context, handle: &Handle) -> Result<bool> {
+    let mut cx = context.db.connection();
+    let record = db::topic_find_by_id(&mut cx, handle.id()).await?;
+    Ok(db::session_finalized(&mut cx, record.session_id).await?)
+}
+
 /// Creates [`TopicMetadata`] associated to the given topic [`Handle`].
 pub async fn metadata(context: &Context, handle: &Handle) -> Result<TopicMetadata> {
     let mut cx = context.db.connection();
@@ -210,12 +487,109 @@ pub async fn metadata(context: &Context, handle: &Handle) -> Result<TopicMetadat
                     .serialization_format()
                     .ok_or_else(|| Error::MissingDbData("serialization_format".to_owned()))?,
                 ontology_tag: db_topic.ontology_tag.clone(),
+                sort_key: db_topic.sort_key().map(ToOwned::to_owned),
+                dedup_policy: db_topic
+                    .dedup_policy()
+                    .ok_or_else(|| Error::MissingDbData("dedup_policy".to_owned()))?,
+                primary_key: db_topic.primary_key().map(ToOwned::to_owned),
             },
             user_metadata: db_topic.user_metadata(),
         },
     })
 }
 
+/// Returns the current version of the schema registered for the topic, if any.
+pub async fn registered_schema(context: &Context, handle: &Handle) -> Result<Option<SchemaRef>> {
+    let mut cx = context.db.connection();
+
+    let record = db::topic_find_by_id(&mut cx, handle.id).await?;
+    Ok(record
+        .arrow_schema()
+        .map(ext::arrow::schema_from_ipc)
+        .transpose()?)
+}
+
+/// Version of the schema registered for a topic.
+pub struct SchemaVersion {
+    /// Version number, starting from 1 for the schema registered when the topic is created
+    pub version: u32,
+    pub schema: SchemaRef,
+    pub creation_timestamp: types::Timestamp,
+}
+
+impl SchemaVersion {
+    fn try_from_record(record: db::TopicSchemaRecord) -> Result<Self> {
+        Ok(Self {
+            version: record.version(),
+            schema: ext::arrow::schema_from_ipc(record.arrow_schema())?,
+            creation_timestamp: record.creation_timestamp(),
+        })
+    }
+}
+
+/// Returns the versions of the schema registered for the topic, from the oldest.
+pub async fn schema_versions(context: &Context, handle: &Handle) -> Result<Vec<SchemaVersion>> {
+    let mut cx = context.db.connection();
+
+    db::topic_schema_find_all(&mut cx, handle.id)
+        .await?
+        .into_iter()
+        .map(SchemaVersion::try_from_record)
+        .collect()
+}
+
+/// Returns the current version of the schema registered for the topic, if any.
+pub async fn current_schema_version(
+    context: &Context,
+    handle: &Handle,
+) -> Result<Option<SchemaVersion>> {
+    let mut cx = context.db.connection();
+
+    db::topic_schema_find_latest(&mut cx, handle.id)
+        .await?
+        .map(SchemaVersion::try_from_record)
+        .transpose()
+}
+
+/// Registers `schema` as a new version of the schema of the topic, returning its version.
+///
+/// The new schema must be a backward compatible evolution of the current one: columns can
+/// only be added as nullable columns, while the existing columns keep their type. The data
+/// uploaded afterwards must match the new schema, the data already stored is left untouched.
+pub async fn evolve_schema(context: &Context, handle: &Handle, schema: SchemaRef) -> Result<u32> {
+    let mut tx = context.db.transaction().await?;
+
+    // The lock serializes concurrent evolutions of the same topic
+    db::topic_lock(&mut tx, handle.id).await?;
+
+    let Some(current) = db::topic_schema_find_latest(&mut tx, handle.id).await? else {
+        Err(core::Error::bad_request(format!(
+            "topic `{}` has no registered schema, a schema can only be registered when the topic is created",
+            handle.locator
+        )))?
+    };
+    let current = SchemaVersion::try_from_record(current)?;
+
+    ext::arrow::check_schema(&schema)?;
+    ext::arrow::check_schema_evolution(&current.schema, &schema).map_err(|e| {
+        core::Error::bad_request(format!(
+            "schema is not a compatible evolution of the schema of topic `{}`: {}",
+            handle.locator, e
+        ))
+    })?;
+
+    let version = current.version + 1;
+    db::topic_schema_create(
+        &mut tx,
+        &db::TopicSchemaRecord::new(handle.id, version, ext::arrow::schema_to_ipc(&schema)?),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(version)
+}
+
 /// Returns the topic arrow schema.
 /// The serialization format is required to extract the schema.
 /// It can be retrieved using [`metadata`] function.
@@ -230,12 +604,14 @@ pub async fn arrow_schema(
         return Ok(mosaicod_ext::arrow::empty_schema_ref());
     };
 
-    // Get chunk 0 since this chunk needs to exist always
-    let path = path_in_store.path_data(0, format.to_properties().as_ref());
-
-    if !context.store.exists(&path).await? {
+    // Get the first data file, chunk 0 may be missing if the topic has been compacted
+    let Some((_, path)) = data_files(context, path_in_store, format)
+        .await?
+        .into_iter()
+        .next()
+    else {
         return Ok(mosaicod_ext::arrow::empty_schema_ref());
-    }
+    };
 
     // Build a parquet reader reading in memory a file
     let mut parquet_reader = context.store.parquet_reader(path);
@@ -264,23 +640,58 @@ async fn metadata_write_to_store(
     Ok(())
 }
 
+/// Returns the session uploading data through the handle: the session that created the
+/// topic, or the one upserting rows into it.
+pub async fn upload_session(context: &Context, handle: &Handle) -> Result<session::Handle> {
+    let mut cx = context.db.connection();
+
+    let session_id = match &handle.upsert {
+        Some(upsert) => {
+            db::topic_upsert_find_by_id(&mut cx, upsert.id)
+                .await?
+                .session_id
+        }
+        None => db::topic_find_by_id(&mut cx, handle.id).await?.session_id,
+    };
+    let record = db::session_find_by_id(&mut cx, session_id).await?;
+
+    Ok(session::Handle::new(
+        record.locator(),
+        record.session_id,
+        record.uuid(),
+    ))
+}
+
 /// Returns a writer used to write chunked record batches using a specified serialization
 /// format `format`.
+///
+/// If the handle is used to upsert rows into an existing topic, the data is written after
+/// the data already stored in the topic.
 pub async fn writer(
     context: Context,
     mut handle: Handle,
     schema: SchemaRef,
 ) -> Result<HandleWriter> {
     // Precondition: check if topic has already been finalized or if someone else is already uploading data.
-    let topic_status = status(&context, &handle).await?;
-    match topic_status {
-        Status::Empty => (),
-        Status::Uploading => Err(core::Error::topic_upload_in_progress(
-            handle.locator.to_string(),
-        ))?,
-        Status::Finalized => Err(core::Error::topic_already_finalized(
-            handle.locator.to_string(),
-        ))?,
+    if let Some(upsert) = &handle.upsert {
+        let mut cx = context.db.connection();
+        let record = db::topic_upsert_find_by_id(&mut cx, upsert.id).await?;
+        if record.completion_timestamp().is_some() {
+            Err(core::Error::topic_already_finalized(
+                handle.locator.to_string(),
+            ))?;
+        }
+    } else {
+        let topic_status = status(&context, &handle).await?;
+        match topic_status {
+            Status::Empty => (),
+            Status::Uploading => Err(core::Error::topic_upload_in_progress(
+                handle.locator.to_string(),
+            ))?,
+            Status::Finalized => Err(core::Error::topic_already_finalized(
+                handle.locator.to_string(),
+            ))?,
+        }
     }
 
     let mdata = metadata(&context, &handle).await?;
@@ -290,27 +701,138 @@ pub async fn writer(
     let ontology_tag = mdata.ontology_metadata.properties.ontology_tag.clone();
     let format = mdata.ontology_metadata.properties.serialization_format;
 
-    // 1. Create folder in Store and save metadata.
-    let path_in_store = types::TopicPathInStore::new();
+    // Topics using a well-known ontology need to be compatible with its canonical schema
+    if let Some(ontology) = ext::ontology::registry().get(&ontology_tag) {
+        ontology.check_schema(&schema)?;
+    }
+
+    // Data of topics with a registered schema must match its current version, checked again
+    // on every batch
+    let (schema_version, registered_schema) =
+        match current_schema_version(&context, &handle).await? {
+            Some(current) => (Some(current.version), Some(current.schema)),
+            None => (None, None),
+        };
+    if let Some(registered) = &registered_schema {
+        check_data_schema(&handle.locator, registered, &schema)?;
+    }
+
+    // Data of topics declaring a sort key must be sorted by it, otherwise only the ordering
+    // by timestamp is tracked to record whether the topic can be read without sorting
+    let sort_key = mdata.ontology_metadata.properties.sort_key.clone();
+    if let Some(sort_key) = &sort_key {
+        ext::arrow::check_key_column(&schema, sort_key)?;
+    }
 
-    metadata_write_to_store(&context, path_in_store.path_metadata().as_path(), mdata).await?;
+    // Duplicated rows are identified by the dedup key when data is read
+    if let Some(dedup_key) = mdata.ontology_metadata.properties.dedup_policy.key() {
+        ext::arrow::check_key_column(&schema, dedup_key)?;
+    }
+    if let Some(primary_key) = &mdata.ontology_metadata.properties.primary_key {
+        ext::arrow::check_key_column(&schema, primary_key)?;
+    }
 
-    let data_folder = path_in_store.data_folder_path();
+    // Columns can be documented in the field metadata, units must be known
+    let columns: Vec<types::ColumnDescription> = schema
+        .fields()
+        .iter()
+        .filter_map(|field| marshal::column_from_field_metadata(field.name(), field.metadata()))
+        .collect();
+    check_units(&columns)?;
+
+    let enforce_sort_order = sort_key.is_some();
+    let sort_order = ext::arrow::SortOrderTracker::new(
+        sort_key.unwrap_or_else(|| params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP.to_owned()),
+    );
 
-    // 2. Save path_in_store on DB.
-    let mut cx = context.db.connection();
-    db::topic_update_path_in_store(&mut cx, handle.id, path_in_store.clone()).await?;
+    // When blob offloading is enabled binary columns are stored as references to
+    // standalone objects, so the stored schema differs from the received one.
+    let blob_offload_threshold = params::params().blob_offload_threshold.value;
+    let schema = if blob_offload_threshold > 0 {
+        Arc::new(ext::blob::offload_schema(&schema))
+    } else {
+        schema
+    };
+
+    let (path_in_store, first_chunk) = match &handle.upsert {
+        Some(upsert) => {
+            let path_in_store = handle.path_in_store.clone().ok_or_else(|| {
+                Error::MissingDbData(format!("No path in store set for topic {}", handle.locator))
+            })?;
+
+            // Upserted rows are merged with the stored ones, so they need the same columns,
+            // unless the stored columns are known from the versions of a registered schema
+            let stored_schema = arrow_schema(&context, &handle, format).await?;
+            if registered_schema.is_none() && !ext::arrow::has_same_columns(&stored_schema, &schema)
+            {
+                Err(core::Error::bad_request(format!(
+                    "schema of upserted rows does not match the schema of topic `{}`",
+                    handle.locator
+                )))?;
+            }
+
+            // The topic is locked to prevent a concurrent compaction while the first chunk
+            // number is computed
+            let mut tx = context.db.transaction().await?;
+            db::topic_lock(&mut tx, handle.id).await?;
+
+            // The topic may have been relocated after the handle was retrieved
+            let path_in_store = locked_path_in_store(&mut tx, &handle)
+                .await?
+                .unwrap_or(path_in_store);
+
+            // The version can't change before the upload starts: the start fails while other
+            // uploads are in progress, and new ones can't start while the topic is locked
+            let record = db::topic_upsert_find_by_id(&mut tx, upsert.id).await?;
+            if let Some(base_version) = record.base_version() {
+                check_base_version(&mut tx, handle.id, &handle.locator, base_version).await?;
+            }
+
+            let first_chunk = next_chunk_number(&context, &path_in_store, format).await?;
+
+            let started = db::topic_upsert_try_start_upload(
+                &mut tx,
+                upsert.id,
+                types::Timestamp::now().as_i64(),
+                first_chunk as i64,
+            )
+            .await?;
+            if !started {
+                Err(core::Error::topic_upload_in_progress(
+                    handle.locator.to_string(),
+                ))?;
+            }
+
+            tx.commit().await?;
+
+            (path_in_store, first_chunk)
+        }
+        None => {
+            // 1. Create folder in Store and save metadata.
+            let path_in_store =
+                types::TopicPathInStore::new_with_layout(context.store_layout, &ontology_tag);
+
+            metadata_write_to_store(&context, path_in_store.path_metadata().as_path(), mdata)
+                .await?;
+
+            // 2. Save path_in_store on DB.
+            let mut cx = context.db.connection();
+            db::topic_update_path_in_store(&mut cx, handle.id, path_in_store.clone()).await?;
+
+            (path_in_store, 0)
+        }
+    };
 
-    let writer = rw::ChunkWriter::new(
-        context.store.clone(),
+    columns_record(&context, &handle, columns).await?;
+
+    // Data files are staged until the session is finalized, see [`publish`]
+    let session = upload_session(&context, &handle).await?;
+    let writer = chunk_writer(
+        &context,
+        path_in_store.session_staging_folder_path(session.uuid()),
         format,
-        schema.clone(),
-        move |chunk_number| {
-            data_folder.join(types::TopicPathInStore::data_file(
-                chunk_number,
-                format.to_properties().as_ref(),
-            ))
-        },
+        schema,
+        first_chunk,
     );
 
     handle.path_in_store = Some(path_in_store);
@@ -319,11 +841,199 @@ pub async fn writer(
         handle,
         format,
         ontology_tag,
+        blob_offload_threshold,
+        media_index: format.is_media().then(types::MediaIndex::new),
+        sort_order,
+        enforce_sort_order,
+        registered_schema,
+        schema_version,
         writer,
         context,
     })
 }
 
+/// Fails if the schema of the uploaded data does not match the schema registered for the topic.
+fn check_data_schema(
+    locator: &types::TopicLocator,
+    registered: &SchemaRef,
+    schema: &SchemaRef,
+) -> Result<()> {
+    ext::arrow::check_registered_schema(registered, schema)
+        .map_err(|e| core::Error::topic_schema_mismatch(locator.to_string(), e.to_string()))?;
+    Ok(())
+}
+
+/// Returns a writer serializing data files in `folder`, starting from chunk number
+/// `first_chunk`.
+fn chunk_writer(
+    context: &Context,
+    folder: path::PathBuf,
+    format: types::Format,
+    schema: SchemaRef,
+    first_chunk: usize,
+) -> rw::ChunkWriter<Arc<store::Store>> {
+    rw::ChunkWriter::new(context.store.clone(), format, schema, move |chunk_number| {
+        folder.join(types::TopicPathInStore::data_file(
+            first_chunk + chunk_number,
+            format.to_properties().as_ref(),
+        ))
+    })
+}
+
+/// Returns the data files of the topic, sorted by chunk number.
+pub(super) async fn data_files(
+    context: &Context,
+    path_in_store: &types::TopicPathInStore,
+    format: types::Format,
+) -> Result<Vec<(usize, String)>> {
+    data_files_in(context, path_in_store.data_folder_path(), format).await
+}
+
+/// Returns the data files stored under `folder`, sorted by chunk number.
+async fn data_files_in(
+    context: &Context,
+    folder: impl AsRef<path::Path>,
+    format: types::Format,
+) -> Result<Vec<(usize, String)>> {
+    let files = context
+        .store
+        .list(folder, Some(&format.to_properties().as_extension()))
+        .await?;
+
+    let mut files: Vec<(usize, String)> = files
+        .into_iter()
+        .filter_map(|file| {
+            let chunk_number = path::Path::new(&file)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse().ok())?;
+            Some((chunk_number, file))
+        })
+        .collect();
+    files.sort_unstable();
+
+    Ok(files)
+}
+
+/// Returns the number of the chunk following the last data file of the topic, including the
+/// data files staged by the sessions not finalized yet.
+async fn next_chunk_number(
+    context: &Context,
+    path_in_store: &types::TopicPathInStore,
+    format: types::Format,
+) -> Result<usize> {
+    let published = data_files(context, path_in_store, format).await?;
+    let staged = data_files_in(context, path_in_store.staging_folder_path(), format).await?;
+
+    Ok(published
+        .iter()
+        .chain(&staged)
+        .map(|(chunk_number, _)| chunk_number + 1)
+        .max()
+        .unwrap_or(0))
+}
+
+/// Removes the chunks staged by an upsert of a session not finalized yet, returning the data
+/// files to delete from the store once the transaction is committed.
+pub(crate) async fn staged_upsert_files(
+    context: &Context,
+    exe: &mut impl db::AsExec,
+    upsert: &db::TopicUpsertRecord,
+) -> Result<Vec<String>> {
+    if upsert.first_chunk_number().is_none() {
+        return Ok(Vec::new());
+    }
+
+    let record = db::topic_find_by_id(exe, upsert.topic_id).await?;
+    let (Some(path_in_store), Some(format)) =
+        (record.path_in_store(), record.serialization_format())
+    else {
+        return Ok(Vec::new());
+    };
+
+    let session = db::session_find_by_id(exe, upsert.session_id).await?;
+    let files: Vec<String> = data_files_in(
+        context,
+        path_in_store.session_staging_folder_path(&session.uuid()),
+        format,
+    )
+    .await?
+    .into_iter()
+    .map(|(_, file)| file)
+    .collect();
+    db::chunk_delete_by_data_files(exe, upsert.topic_id, &files).await?;
+
+    Ok(files)
+}
+
+/// Data files of a session moved into the data folder of a topic by [`publish`].
+pub(crate) struct Publication {
+    /// Folder of the staged data files, to delete once the publication is committed
+    pub staging: path::PathBuf,
+    /// Data files copied into the data folder, to delete if the publication is discarded
+    pub published: Vec<path::PathBuf>,
+}
+
+/// Moves the data files staged by `session` into the data folder of the topic, making them
+/// visible to the readers once `tx` is committed at `published_at`. The watermark of the
+/// topic is advanced to the latest timestamp of its data.
+///
+/// The staged data files are copied, and left in place until the transaction is committed.
+pub(crate) async fn publish(
+    context: &Context,
+    tx: &mut impl db::AsExec,
+    topic_id: i32,
+    session: &types::Uuid,
+    published_at: types::Timestamp,
+) -> Result<Option<Publication>> {
+    // The lock prevents a concurrent compaction or relocation of the topic
+    db::topic_lock(tx, topic_id).await?;
+
+    let record = db::topic_find_by_id(tx, topic_id).await?;
+    let (Some(path_in_store), Some(format)) =
+        (record.path_in_store(), record.serialization_format())
+    else {
+        return Ok(None);
+    };
+
+    let staging = path_in_store.session_staging_folder_path(session);
+    let data_folder = path_in_store.data_folder_path();
+
+    let published = data_files_in(context, &staging, format)
+        .await?
+        .into_iter()
+        .filter_map(|(_, file)| Some(data_folder.join(path::Path::new(&file).file_name()?)))
+        .collect();
+    context.store.copy_recursive(&staging, &data_folder).await?;
+
+    db::chunk_update_data_file_prefix(
+        tx,
+        topic_id,
+        &format!("{}/", staging.display()),
+        &format!("{}/", data_folder.display()),
+    )
+    .await?;
+
+    let handle = Handle::new(
+        record.locator(),
+        topic_id,
+        record.uuid(),
+        Some(path_in_store),
+    );
+    let info = compute_data_info(context, &handle, tx, format).await?;
+    db::topic_update_system_info(tx, &handle.locator, &info).await?;
+
+    let watermark = info.timestamp_range.end;
+    if !watermark.is_unbounded() {
+        let record = db::TopicWatermarkRecord::new(topic_id, watermark, published_at);
+        db::topic_watermark_try_advance(tx, &record).await?;
+    }
+
+    trace!("published data files of topic `{}`", handle.locator);
+
+    Ok(Some(Publication { staging, published }))
+}
+
 /// Permanently deletes a topic and all its data, be caution
 ///
 /// A [`types::DataLossToken`] is required since this call will lead to data losses.
@@ -332,9 +1042,16 @@ pub async fn delete(
     handle: Handle,
     allowed_data_loss: types::DataLossToken,
 ) -> Result<()> {
+    let mut tx = context.db.transaction().await?;
+
+    let record = db::topic_find_by_id(&mut tx, handle.id).await?;
+    sequence::lock_unless_held(&mut tx, record.sequence_id, "topic_delete", &handle.locator)
+        .await?;
+
     warn!("(data loss) deleting topic '{}'", handle.locator);
-    let mut cx = context.db.connection();
-    db::topic_delete(&mut cx, handle.id, allowed_data_loss).await?;
+    db::topic_delete(&mut tx, handle.id, allowed_data_loss).await?;
+
+    tx.commit().await?;
     Ok(())
 }
 
@@ -343,30 +1060,68 @@ pub async fn notify(
     context: &Context,
     handle: &Handle,
     ntype: types::NotificationType,
-    msg: String,
+    severity: types::NotificationSeverity,
+    payload: types::NotificationPayload,
+    msg: Option<String>,
 ) -> Result<types::Notification<types::TopicLocator>> {
     let mut tx = context.db.transaction().await?;
 
     let record = db::topic_find_by_locator(&mut tx, &handle.locator).await?;
-    let notification = db::TopicNotificationRecord::new(record.topic_id, ntype, Some(msg));
+    let notification =
+        db::TopicNotificationRecord::new(record.topic_id, ntype, severity, payload, msg);
     let notification = db::topic_notification_create(&mut tx, &notification).await?;
 
     tx.commit().await?;
 
-    Ok(notification.into_notification(handle.locator.clone()))
+    let notification = notification.into_notification(handle.locator.clone());
+    context
+        .notification_dispatcher
+        .dispatch((&notification).into());
+    context.notification_bus.publish((&notification).into());
+
+    Ok(notification)
 }
 
-/// Returns a list of all notifications for the topic
+/// Returns at most `limit` notifications of the topic matching `filter`, sorted by
+/// creation time.
+///
+/// The page starts after `cursor`, the value returned along with the previous page.
 pub async fn notification_list(
     context: &Context,
     handle: &Handle,
-) -> Result<Vec<types::Notification<types::TopicLocator>>> {
+    filter: &types::NotificationFilter,
+    cursor: Option<&types::NotificationCursor>,
+    limit: usize,
+) -> Result<types::NotificationPage<types::TopicLocator>> {
     let mut cx = context.db.connection();
-    let notifications = db::topic_notifications_find_by_locator(&mut cx, &handle.locator).await?;
-    Ok(notifications
-        .into_iter()
-        .map(|e| e.into_notification(handle.locator.clone()))
-        .collect())
+
+    // One extra record is fetched to know if other pages follow
+    let mut records = db::topic_notifications_find_page(
+        &mut cx,
+        handle.id(),
+        filter,
+        cursor,
+        limit.saturating_add(1) as i64,
+    )
+    .await?;
+
+    let cursor = if records.len() > limit {
+        records.truncate(limit);
+        records.last().map(|record| types::NotificationCursor {
+            timestamp: record.creation_timestamp(),
+            uuid: record.uuid(),
+        })
+    } else {
+        None
+    };
+
+    Ok(types::NotificationPage {
+        notifications: records
+            .into_iter()
+            .map(|record| record.into_notification(handle.locator.clone()))
+            .collect(),
+        cursor,
+    })
 }
 
 /// Deletes all the notifications associated with the sequence
@@ -383,6 +1138,183 @@ pub async fn notification_purge(context: &Context, handle: &Handle) -> Result<()
     Ok(())
 }
 
+/// Sets the description and unit of the given columns of the topic, replacing the existing
+/// ones.
+///
+/// If the topic already contains data, every column must be a field of its schema. Units
+/// must be known by the units registry.
+pub async fn columns_update(
+    context: &Context,
+    handle: &Handle,
+    columns: Vec<types::ColumnDescription>,
+) -> Result<()> {
+    check_units(&columns)?;
+
+    let format = metadata(context, handle)
+        .await?
+        .ontology_metadata
+        .properties
+        .serialization_format;
+    let schema = arrow_schema(context, handle, format).await?;
+
+    // Topics without data have an empty schema, any column is accepted
+    let missing = columns.iter().find(|column| {
+        !schema.fields().is_empty() && schema.field_with_name(&column.name).is_err()
+    });
+    if let Some(column) = missing {
+        Err(core::Error::bad_request(format!(
+            "column `{}` not found in topic `{}`",
+            column.name, handle.locator
+        )))?;
+    }
+
+    let mut tx = context.db.transaction().await?;
+    for column in columns {
+        db::topic_column_upsert(&mut tx, &db::TopicColumnRecord::new(handle.id, column)).await?;
+    }
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Records the columns documented in the metadata of the fields of the uploaded data.
+///
+/// Description and unit missing from the field metadata are kept from the existing
+/// documentation of the column.
+async fn columns_record(
+    context: &Context,
+    handle: &Handle,
+    columns: Vec<types::ColumnDescription>,
+) -> Result<()> {
+    if columns.is_empty() {
+        return Ok(());
+    }
+
+    let mut tx = context.db.transaction().await?;
+
+    let mut existing: HashMap<String, types::ColumnDescription> =
+        db::topic_find_all_columns(&mut tx, handle.id)
+            .await?
+            .into_iter()
+            .map(|record| (record.column_name.clone(), record.into()))
+            .collect();
+
+    for mut column in columns {
+        if let Some(previous) = existing.remove(&column.name) {
+            column.description = column.description.or(previous.description);
+            column.unit = column.unit.or(previous.unit);
+        }
+        db::topic_column_upsert(&mut tx, &db::TopicColumnRecord::new(handle.id, column)).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Checks that the units of the columns are known by the units registry.
+fn check_units(columns: &[types::ColumnDescription]) -> Result<()> {
+    for unit in columns.iter().filter_map(|column| column.unit.as_deref()) {
+        types::Unit::try_from_symbol(unit).map_err(|e| core::Error::bad_request(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Returns the description and unit of the documented columns of the topic.
+pub async fn columns(context: &Context, handle: &Handle) -> Result<Vec<types::ColumnDescription>> {
+    let mut cx = context.db.connection();
+    let records = db::topic_find_all_columns(&mut cx, handle.id).await?;
+    Ok(records.into_iter().map(Into::into).collect())
+}
+
+/// Returns the statistics of the given columns of a finalized topic, nested fields are
+/// separated by dots. If no column is given, the statistics of all the top-level columns
+/// are returned.
+///
+/// Statistics are computed on the first request and cached, the cache of upsert topics is
+/// cleared every time new rows are upserted.
+pub async fn column_stats(
+    context: &Context,
+    handle: &Handle,
+    mut columns: Vec<String>,
+) -> Result<Vec<types::ColumnStats>> {
+    if status(context, handle).await? != Status::Finalized {
+        return Err(core::Error::bad_request(format!(
+            "topic `{}` is not finalized",
+            handle.locator
+        )))?;
+    }
+
+    let mdata = metadata(context, handle).await?;
+    let properties = &mdata.ontology_metadata.properties;
+
+    if columns.is_empty() {
+        let schema = arrow_schema(context, handle, properties.serialization_format).await?;
+        columns = schema.fields().iter().map(|f| f.name().clone()).collect();
+    }
+    columns.dedup();
+
+    let mut cx = context.db.connection();
+    let mut cached: HashMap<String, types::ColumnStats> =
+        db::topic_find_column_stats(&mut cx, handle.id, &columns)
+            .await?
+            .into_iter()
+            .map(|record| (record.column_name.clone(), record.into()))
+            .collect();
+
+    let missing: Vec<String> = columns
+        .iter()
+        .filter(|column| !cached.contains_key(*column))
+        .cloned()
+        .collect();
+
+    if !missing.is_empty() {
+        debug!(
+            "computing stats of columns {:?} of topic `{}`",
+            missing, handle.locator
+        );
+
+        let path_in_store = handle.path_in_store.as_ref().ok_or_else(|| {
+            Error::MissingDbData(format!("No path in store set for topic {}", handle.locator))
+        })?;
+
+        let stats = context
+            .timeseries_querier
+            .read_deduplicated(
+                path_in_store.data_folder_path(),
+                properties.serialization_format,
+                None,
+                &properties.read_dedup_policy(),
+            )
+            .await?
+            .column_stats(&missing)
+            .await
+            .map_err(|e| -> core::error::BoxPublicError {
+                match e {
+                    query::Error::BadField { field } => core::Error::bad_request(format!(
+                        "column `{}` not found in topic `{}`",
+                        field, handle.locator
+                    ))
+                    .into(),
+                    e => e.into(),
+                }
+            })?;
+
+        let mut tx = context.db.transaction().await?;
+        for column in stats {
+            let record = db::TopicColumnStatsRecord::new(handle.id, column.clone());
+            db::topic_column_stats_upsert(&mut tx, &record).await?;
+            cached.insert(column.name.clone(), column);
+        }
+        tx.commit().await?;
+    }
+
+    Ok(columns
+        .iter()
+        .filter_map(|column| cached.remove(column))
+        .collect())
+}
+
 /// Returns the statistics about topic's chunks
 pub async fn chunks_stats(context: &Context, handle: &Handle) -> Result<types::TopicChunksStats> {
     let mut cx = context.db.connection();
@@ -406,38 +1338,67 @@ async fn compute_data_info(
             handle.locator
         )))?;
 
-    let timeseries_res = context
-        .timeseries_querier
-        .read(path_in_store.data_folder_path(), format, None)
-        .await;
-
-    let timestamp_range = match timeseries_res {
-        Ok(res) => {
-            let ts_range = res.timestamp_range().await;
-            ts_range.unwrap_or(types::TimestampRange::unbounded())
-        }
-        Err(_) => types::TimestampRange::unbounded(),
-    };
-
     let record = db::topic_find_by_locator(exe, &handle.locator).await?;
 
-    let format = record
+    let record_format = record
         .serialization_format()
         .ok_or_else(|| Error::MissingDbData("serialization_format".to_owned()))?;
 
     let datafiles = context
         .store
         .list(
-            path_in_store.root(),
-            Some(&format.to_properties().as_extension()),
+            path_in_store.data_folder_path(),
+            Some(&record_format.to_properties().as_extension()),
         )
         .await?;
 
+    // The timestamp range is read from the footers of the data files, the data is scanned
+    // only if some of them have no statistics on the timestamps
     let mut total_bytes = 0;
+    let mut footer_range: Option<(types::Timestamp, types::Timestamp)> = None;
+    let mut has_stats = !datafiles.is_empty();
     for file in &datafiles {
-        total_bytes += context.store.size(file).await? as u64;
+        let size = context.store.size(file).await? as u64;
+        total_bytes += size;
+
+        if !has_stats {
+            continue;
+        }
+        match chunk_footer_metadata(context, file, size).await {
+            Ok(chunk) => match chunk.timestamp_range() {
+                Some(range) => {
+                    footer_range = Some(match footer_range {
+                        Some((start, end)) => (start.min(range.start), end.max(range.end)),
+                        None => (range.start, range.end),
+                    });
+                }
+                None => has_stats = false,
+            },
+            Err(e) => {
+                debug!("unable to read the footer of `{}`: {}", file, e);
+                has_stats = false;
+            }
+        }
     }
 
+    let timestamp_range = match footer_range {
+        Some((start, end)) if has_stats => types::TimestampRange::between(start, end),
+        _ => {
+            let timeseries_res = context
+                .timeseries_querier
+                .read(path_in_store.data_folder_path(), format, None)
+                .await;
+
+            match timeseries_res {
+                Ok(res) => {
+                    let ts_range = res.timestamp_range().await;
+                    ts_range.unwrap_or(types::TimestampRange::unbounded())
+                }
+                Err(_) => types::TimestampRange::unbounded(),
+            }
+        }
+    };
+
     Ok(types::TopicDataInfo {
         chunks_number: datafiles.len() as u64,
         total_bytes,
@@ -445,6 +1406,56 @@ async fn compute_data_info(
     })
 }
 
+/// Reads the metadata of the data file at `path`, of `size` bytes, from its footer, so that
+/// only the end of the file is fetched from the store.
+async fn chunk_footer_metadata(
+    context: &Context,
+    path: &str,
+    size: u64,
+) -> Result<rw::ChunkMetadata> {
+    let tail_offset = size.saturating_sub(rw::FOOTER_TAIL_SIZE);
+    let tail = context
+        .store
+        .get_range(path, tail_offset, rw::FOOTER_TAIL_SIZE)
+        .await?;
+    let footer_size = rw::ChunkMetadata::footer_size(&tail)?;
+
+    let footer = context
+        .store
+        .get_range(path, tail_offset.saturating_sub(footer_size), footer_size)
+        .await?;
+
+    Ok(rw::ChunkMetadata::from_footer(size as usize, &footer)?)
+}
+
+/// Returns the ingestion watermark of the topic, along with the number of sessions still
+/// writing into it.
+pub async fn watermark(context: &Context, handle: &Handle) -> Result<types::TopicWatermark> {
+    let mut cx = context.db.connection();
+    let record = db::topic_find_by_id(&mut cx, handle.id).await?;
+
+    let mut sessions = vec![record.session_id];
+    for upsert in db::topic_find_all_upserts(&mut cx, handle.id).await? {
+        sessions.push(upsert.session_id);
+    }
+    let mut pending_sessions = 0;
+    for session in sessions {
+        if !db::session_finalized(&mut cx, session).await? {
+            pending_sessions += 1;
+        }
+    }
+
+    let latest = db::topic_watermark_find_latest(&mut cx, handle.id).await?;
+
+    Ok(types::TopicWatermark {
+        watermark: latest.as_ref().map(db::TopicWatermarkRecord::watermark),
+        updated_at: latest
+            .as_ref()
+            .map(db::TopicWatermarkRecord::creation_timestamp),
+        pending_sessions,
+    })
+}
+
 /// Retrieves system info for the topic from db. Returns an error if not present.
 pub async fn data_info(context: &Context, handle: &Handle) -> Result<types::TopicDataInfo> {
     let mut cx = context.db.connection();
@@ -455,6 +1466,110 @@ pub async fn data_info(context: &Context, handle: &Handle) -> Result<types::Topi
     )
 }
 
+/// Returns the known ordering of the data files of the topic.
+///
+/// The ordering is recorded when the topic is finalized, topics still being uploaded (or
+/// finalized before the ordering was tracked) fall back to the default ordering by timestamp.
+pub async fn file_ordering(context: &Context, handle: &Handle) -> Result<query::FileOrdering> {
+    let mut cx = context.db.connection();
+    let record = db::topic_find_by_id(&mut cx, handle.id()).await?;
+
+    Ok(match record.is_sorted() {
+        Some(true) => query::FileOrdering::SortedBy(
+            record
+                .sort_key()
+                .unwrap_or(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP)
+                .to_owned(),
+        ),
+        Some(false) => query::FileOrdering::Unsorted,
+        None => query::FileOrdering::default(),
+    })
+}
+
+/// Reads the whole data of the topic as served to the clients: rows of upsert topics are
+/// merged according to their deduplication policy, the data files of the other topics are
+/// read exploiting their known ordering.
+pub async fn read(
+    context: &Context,
+    handle: &Handle,
+    properties: &types::TopicOntologyProperties,
+    batch_size: usize,
+) -> Result<query::TimeseriesResult> {
+    let path_in_store = handle.path_in_store().ok_or_else(|| {
+        core::Error::internal(Some(format!(
+            "Path in store not set for topic {}",
+            handle.locator
+        )))
+    })?;
+
+    let data_folder = path_in_store.data_folder_path();
+    let format = properties.serialization_format;
+    let dedup_policy = properties.read_dedup_policy();
+
+    if dedup_policy != types::DedupPolicy::None {
+        debug!("removing duplicated rows with policy `{}`", dedup_policy);
+        return Ok(context
+            .timeseries_querier
+            .read_deduplicated(&data_folder, format, Some(batch_size), &dedup_policy)
+            .await?);
+    }
+
+    // Data files sorted across each other are merged by the query engine instead of sorted
+    let ordering = file_ordering(context, handle).await?;
+    trace!("topic data ordering {:?}", ordering);
+
+    Ok(context
+        .timeseries_querier
+        .read_ordered(&data_folder, format, Some(batch_size), ordering)
+        .await?)
+}
+
+/// Reads the data of the topic as [`read`] does, skipping the data files with no rows in
+/// `range` (`[start, end)`). Rows outside the range are not filtered.
+///
+/// Upsert topics are read whole, since rows in the range can be replaced by rows outside it.
+pub async fn read_range(
+    context: &Context,
+    handle: &Handle,
+    properties: &types::TopicOntologyProperties,
+    batch_size: usize,
+    range: &types::TimestampRange,
+) -> Result<query::TimeseriesResult> {
+    if properties.read_dedup_policy() != types::DedupPolicy::None {
+        return read(context, handle, properties, batch_size).await;
+    }
+
+    let path_in_store = handle.path_in_store().ok_or_else(|| {
+        core::Error::internal(Some(format!(
+            "Path in store not set for topic {}",
+            handle.locator
+        )))
+    })?;
+
+    // Chunks staged by sessions not finalized yet are not visible
+    let mut cx = context.db.connection();
+    let files: Vec<path::PathBuf> = db::chunk_find_all_by_topic_in_range(&mut cx, handle.id, range)
+        .await?
+        .iter()
+        .map(|chunk| chunk.data_file().to_path_buf())
+        .filter(|file| !path_in_store.is_staged(file))
+        .collect();
+    debug!("reading range {} from data files {:?}", range, files);
+
+    let ordering = file_ordering(context, handle).await?;
+
+    Ok(context
+        .timeseries_querier
+        .read_files(
+            path_in_store.data_folder_path(),
+            properties.serialization_format,
+            Some(batch_size),
+            ordering,
+            &files,
+        )
+        .await?)
+}
+
 /// Computes the optimal batch size based on topic statistics from the database.
 /// Batch size is the minimum between the computed batch size and
 /// [`params::ConfigurablesParams::max_batch_size`].
@@ -478,19 +1593,1297 @@ pub async fn compute_optimal_batch_size(context: &Context, handle: &Handle) -> R
     Ok((batch_size as usize).min(params.max_batch_size.value))
 }
 
-/// A guard ensuring exclusive write access to [`Handle`].
+/// Returns the time index of a media topic.
 ///
-/// While this struct exists, the underlying topic is mutably borrowed, preventing
-/// any other operations (such as locking or concurrent reads) until [`HandleWriter::finalize`] is called.
-pub struct HandleWriter {
-    /// Anchors the exclusive borrow of the handle, strictly tying the writer's lifetime
-    /// to the topic's availability.
-    handle: Handle,
+/// The index is built while data is uploaded and becomes available once the topic is
+/// finalized. Topics without uploaded data return an empty index.
+pub async fn media_index(context: &Context, handle: &Handle) -> Result<types::MediaIndex> {
+    let mdata = metadata(context, handle).await?;
+    if !mdata
+        .ontology_metadata
+        .properties
+        .serialization_format
+        .is_media()
+    {
+        return Err(core::Error::bad_request(format!(
+            "topic `{}` is not a media topic",
+            handle.locator
+        )))?;
+    }
 
-    /// Serialization format used to write
-    format: types::Format,
+    if status(context, handle).await? != Status::Finalized {
+        return Err(core::Error::not_found(format!(
+            "media index for topic `{}`",
+            handle.locator
+        )))?;
+    }
 
-    ontology_tag: String,
+    let Some(path_in_store) = handle.path_in_store() else {
+        return Ok(types::MediaIndex::new());
+    };
+
+    let bytes = context
+        .store
+        .read_bytes(path_in_store.path_media_index())
+        .await?;
+
+    Ok(marshal::JsonMediaIndex::try_from(bytes)?.into())
+}
+
+/// Returns the compaction policy configured for the whole server, followed by the sequences
+/// without a policy of their own.
+pub fn server_compaction_policy() -> types::CompactionPolicy {
+    let params = params::params();
+    types::CompactionPolicy {
+        target_object_size: Some(params.parquet_in_memory_encoding_buffer_size.value as u64),
+        interval: Some(params.upsert_compaction_interval.value),
+        min_chunks: Some(params.upsert_compaction_min_chunks.value as u64),
+        cold_after: None,
+    }
+}
+
+/// Returns the compaction policy applied to the topics of a sequence, i.e. the policy of
+/// the sequence completed by the server one.
+async fn compaction_policy(
+    exe: &mut impl db::AsExec,
+    sequence_id: i32,
+) -> Result<types::CompactionPolicy> {
+    let policy = db::sequence_compaction_policy_find(exe, sequence_id)
+        .await?
+        .map(|record| record.policy())
+        .unwrap_or_default();
+
+    Ok(policy.or(&server_compaction_policy()))
+}
+
+/// Returns the finalized upsert topics due for a background compaction according to the
+/// compaction policy of their sequence.
+pub async fn compaction_candidates(context: &Context) -> Result<Vec<Handle>> {
+    let mut cx = context.db.connection();
+    let topics = db::topic_find_all_compactable(&mut cx, 2).await?;
+
+    let now = types::Timestamp::now();
+    let mut policies = HashMap::new();
+    let mut candidates = Vec::new();
+
+    for record in topics {
+        let (Some(info), Some(published)) = (record.info(), record.completion_timestamp()) else {
+            continue;
+        };
+
+        if let Entry::Vacant(entry) = policies.entry(record.sequence_id) {
+            entry.insert(compaction_policy(&mut cx, record.sequence_id).await?);
+        }
+        let policy = &policies[&record.sequence_id];
+
+        let compacted_at = record.compaction_timestamp().unwrap_or(published);
+
+        // The last write is looked up only when needed, since it requires the upserts
+        let written_at = if policy.cold_after.is_some() {
+            db::topic_find_all_upserts_completed_after(&mut cx, record.topic_id, published.as_i64())
+                .await?
+                .last()
+                .and_then(|upsert| upsert.completion_timestamp())
+                .unwrap_or(published)
+        } else {
+            published
+        };
+
+        if policy.is_due(info.chunks_number, compacted_at, written_at, now) {
+            candidates.push(Handle::new(
+                record.locator(),
+                record.topic_id,
+                record.uuid(),
+                record.path_in_store(),
+            ));
+        }
+    }
+
+    Ok(candidates)
+}
+
+/// Returns the path in store of a topic locked by the transaction, which cannot be changed
+/// by a relocation until the end of the transaction.
+async fn locked_path_in_store(
+    tx: &mut db::Tx<'_>,
+    handle: &Handle,
+) -> Result<Option<types::TopicPathInStore>> {
+    Ok(db::topic_find_by_id(tx, handle.id).await?.path_in_store())
+}
+
+/// Returns up to `limit` finalized topics following the topic `after`, in creation order.
+///
+/// Used to scan all the finalized topics in batches, starting with `after` set to `None`.
+pub async fn finalized_page(
+    context: &Context,
+    after: Option<&Handle>,
+    limit: usize,
+) -> Result<Vec<Handle>> {
+    let mut cx = context.db.connection();
+    let topics =
+        db::topic_find_finalized_page(&mut cx, after.map(|handle| handle.id), limit as i64).await?;
+
+    Ok(topics
+        .into_iter()
+        .map(|record| {
+            Handle::new(
+                record.locator(),
+                record.topic_id,
+                record.uuid(),
+                record.path_in_store(),
+            )
+        })
+        .collect())
+}
+
+/// Clears the column statistics cached for a finalized topic and computes again the
+/// statistics of its top-level columns.
+pub async fn rebuild_column_stats(context: &Context, handle: &Handle) -> Result<()> {
+    let mut cx = context.db.connection();
+    db::topic_delete_column_stats(&mut cx, handle.id).await?;
+
+    column_stats(context, handle, Vec::new()).await?;
+
+    Ok(())
+}
+
+/// Computes again the column statistics of the data files of a topic, used to select the
+/// chunks read by queries.
+///
+/// Returns the number of data files whose statistics were computed.
+pub async fn rebuild_chunk_stats(context: &Context, handle: &Handle) -> Result<usize> {
+    let mdata = metadata(context, handle).await?;
+    let properties = &mdata.ontology_metadata.properties;
+
+    let mut cx = context.db.connection();
+    let chunks = db::chunk_find_all_by_topic(&mut cx, handle.id).await?;
+    let count = chunks.len();
+
+    for record in chunks {
+        let bytes = context.store.read_bytes(record.data_file()).await?;
+        let reader = rw::ChunkReader::new(properties.serialization_format, bytes.into())?;
+
+        let mut stats = ext::arrow::ontology_model_stats_from_schema(&reader.schema());
+        for batch in reader {
+            ext::arrow::ontology_model_stats_inspect_record_batch(&mut stats, &batch?)?;
+        }
+
+        let mut chunk = Chunk::reopen(record, context).await?;
+        chunk
+            .push_ontology_model_stats(&properties.ontology_tag, stats)
+            .await?;
+        chunk.finalize().await?;
+    }
+
+    Ok(count)
+}
+
+/// Computes again the data info of a finalized topic (number of data files, size and
+/// timestamp range), see [`data_info`].
+pub async fn rebuild_data_info(context: &Context, handle: &Handle) -> Result<()> {
+    let mdata = metadata(context, handle).await?;
+    let format = mdata.ontology_metadata.properties.serialization_format;
+
+    // The lock prevents upserts from writing data files while the info is computed
+    let mut tx = context.db.transaction().await?;
+    db::topic_lock(&mut tx, handle.id).await?;
+
+    let info = compute_data_info(context, handle, &mut tx, format).await?;
+    db::topic_update_system_info(&mut tx, &handle.locator, &info).await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Registers a finalized topic found in the store without a record in the database, e.g.
+/// when recovering the records of a lost database from the store.
+///
+/// The topic is created in the given session from the metadata saved in its root folder,
+/// then its data files, columns and data info are derived from the stored data. If the
+/// data can not be registered, the topic record is deleted.
+pub async fn register_stored(
+    context: &Context,
+    session_handle: &session::Handle,
+    path_in_store: types::TopicPathInStore,
+    mdata: TopicMetadata,
+) -> Result<Handle> {
+    let locator = mdata.properties.resource_locator;
+    let properties = mdata.ontology_metadata.properties;
+    let completed_at = mdata
+        .properties
+        .completed_at
+        .ok_or_else(|| core::Error::topic_upload_in_progress(locator.to_string()))?;
+
+    let mut tx = context.db.transaction().await?;
+
+    let seq_rec = db::sequence_find_by_locator(&mut tx, &locator.sequence).await?;
+
+    let mut record = db::TopicRecord::new(
+        locator.clone(),
+        seq_rec.sequence_id,
+        session_handle.id(),
+        &properties.ontology_tag,
+        &properties.serialization_format.to_string(),
+        Some(path_in_store.clone()),
+    )
+    .with_creation_timestamp(mdata.properties.created_at)
+    .with_sort_key(properties.sort_key.clone())
+    .with_dedup_policy(&properties.dedup_policy)
+    .with_primary_key(properties.primary_key.clone());
+
+    if let Some(user_metadata) = mdata.ontology_metadata.user_metadata {
+        record = record.with_user_metadata(user_metadata);
+    }
+
+    let record = db::topic_create(&mut tx, &record).await?;
+    db::topic_update_completion_tstamp(&mut tx, record.topic_id, completed_at.as_i64()).await?;
+
+    tx.commit().await?;
+
+    let handle = Handle {
+        locator,
+        id: record.topic_id,
+        uuid: record.uuid(),
+        path_in_store: Some(path_in_store),
+        upsert: None,
+    };
+
+    if let Err(e) = register_stored_data(context, &handle, &properties).await {
+        let mut cx = context.db.connection();
+        db::topic_delete(&mut cx, handle.id, types::allow_data_loss()).await?;
+        return Err(e);
+    }
+
+    Ok(handle)
+}
+
+async fn register_stored_data(
+    context: &Context,
+    handle: &Handle,
+    properties: &types::TopicOntologyProperties,
+) -> Result<()> {
+    let format = properties.serialization_format;
+    let Some(path_in_store) = &handle.path_in_store else {
+        return Ok(());
+    };
+
+    for (_, file) in data_files(context, path_in_store, format).await? {
+        let bytes = context.store.read_bytes(&file).await?;
+        let size_bytes = bytes.len() as i64;
+        let reader = rw::ChunkReader::new(format, bytes.into())?;
+
+        let mut stats = ext::arrow::ontology_model_stats_from_schema(&reader.schema());
+        let mut row_count = 0;
+        let mut timestamp_range: Option<types::TimestampRange> = None;
+        for batch in reader {
+            let batch = batch?;
+            row_count += batch.num_rows() as i64;
+            ext::arrow::ontology_model_stats_inspect_record_batch(&mut stats, &batch)?;
+            if let Some(range) = ext::arrow::timestamp_range(&batch) {
+                timestamp_range = Some(match timestamp_range {
+                    Some(r) => types::TimestampRange::between(
+                        r.start.min(range.start),
+                        r.end.max(range.end),
+                    ),
+                    None => range,
+                });
+            }
+        }
+
+        let mut chunk = Chunk::create(
+            &handle.uuid,
+            &file,
+            size_bytes,
+            row_count,
+            None,
+            timestamp_range,
+            context,
+        )
+        .await?;
+        chunk
+            .push_ontology_model_stats(&properties.ontology_tag, stats)
+            .await?;
+        chunk.finalize().await?;
+    }
+
+    let schema = arrow_schema(context, handle, format).await?;
+    let columns = schema
+        .fields()
+        .iter()
+        .filter_map(|field| marshal::column_from_field_metadata(field.name(), field.metadata()))
+        .collect();
+    columns_record(context, handle, columns).await?;
+
+    let mut tx = context.db.transaction().await?;
+    let info = compute_data_info(context, handle, &mut tx, format).await?;
+    db::topic_update_system_info(&mut tx, &handle.locator, &info).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Copies a published topic and its data into the sequence `to_sequence`, under the same
+/// name, along with its metadata, registered schema and documented columns. The copy is
+/// uploaded by a new session owned by `owner`, finalized once the data is copied, and
+/// aborted if the copy fails.
+///
+/// Data files are shared with the source instead of being duplicated (see
+/// [`share_data_file`]), unless the store layout does not allow links (see
+/// [`types::StoreLayout::allows_links`]); blobs are copied backend-side. The source and its own [`lineage`]
+/// are recorded in the lineage of the copy. Upsert topics can not be copied.
+pub async fn copy(
+    context: &Context,
+    source: &Handle,
+    to_sequence: types::SequenceLocator,
+    owner: Option<String>,
+) -> Result<Handle> {
+    if !is_published(context, source).await? {
+        Err(core::Error::topic_not_published(source.locator.to_string()))?;
+    }
+
+    let mut cx = context.db.connection();
+    let record = db::topic_find_by_id(&mut cx, source.id).await?;
+    if record.primary_key().is_some() {
+        Err(core::Error::bad_request(format!(
+            "topic `{}` is an upsert topic and can not be copied",
+            source.locator
+        )))?;
+    }
+
+    let locator: types::TopicLocator =
+        format!("{}/{}", to_sequence, source.locator.name()).parse()?;
+
+    let session = session::try_create(context, to_sequence, owner).await?;
+
+    let copied = match copy_into(context, source, &record, locator, &session).await {
+        Ok(handle) => session::finalize(context, &session).await.map(|_| handle),
+        Err(e) => Err(e),
+    };
+
+    // Data files of the source shared by the copy are replaced with links, which its table
+    // has to reference by their target
+    context
+        .table_exporter
+        .export_all(context, vec![source.locator.clone()])
+        .await;
+
+    if copied.is_err() {
+        // Removes the copy along with the objects already copied
+        if let Err(e) = session::abort(context, &session, types::allow_data_loss()).await {
+            warn!(
+                "unable to abort session `{}` of failed copy of topic `{}`: {}",
+                session.locator(),
+                source.locator,
+                e
+            );
+        }
+    }
+
+    copied
+}
+
+/// Creates the copy of the topic `source` as `locator` in `session` and copies its data.
+async fn copy_into(
+    context: &Context,
+    source: &Handle,
+    record: &db::TopicRecord,
+    locator: types::TopicLocator,
+    session: &session::Handle,
+) -> Result<Handle> {
+    let source_path = source.path_in_store.clone().ok_or_else(|| {
+        Error::MissingDbData(format!("No path in store set for topic {}", source.locator))
+    })?;
+
+    let mdata = metadata(context, source).await?;
+    let source_lineage = lineage(context, source).await?;
+
+    // The first version of the schema is registered on creation, the following ones are
+    // copied verbatim since data files may have been written with any of them
+    let versions = schema_versions(context, source).await?;
+    let options = CreateOptions {
+        base_version: None,
+        arrow_schema: versions.first().map(|version| version.schema.clone()),
+        uuid: None,
+    };
+    let mut handle =
+        try_create_with_options(context, locator, session, mdata.ontology_metadata, options)
+            .await?;
+
+    let path_in_store =
+        types::TopicPathInStore::new_with_layout(context.store_layout, &record.ontology_tag);
+
+    let mut tx = context.db.transaction().await?;
+    for version in versions.iter().skip(1) {
+        let encoded = ext::arrow::schema_to_ipc(&version.schema)?;
+        db::topic_schema_create(
+            &mut tx,
+            &db::TopicSchemaRecord::new(handle.id, version.version, encoded),
+        )
+        .await?;
+    }
+    db::topic_update_path_in_store(&mut tx, handle.id, path_in_store.clone()).await?;
+    tx.commit().await?;
+
+    handle.path_in_store = Some(path_in_store.clone());
+
+    // Links to the data files are staged like the uploaded files, and published when the
+    // session is finalized
+    let staging = path_in_store.session_staging_folder_path(session.uuid());
+    let mut cx = context.db.connection();
+    for chunk in db::chunk_find_all_by_topic(&mut cx, source.id).await? {
+        let Some(file_name) = chunk.data_file().file_name() else {
+            continue;
+        };
+        let data_file = staging.join(file_name);
+        let shared = if context.store_layout.allows_links() {
+            let shared = share_data_file(context, source, &chunk).await?;
+            context.store.link(shared.path(), &data_file).await?;
+            Some(shared)
+        } else {
+            // Data files are materialized, resolving the source data file since copying a
+            // link copies the link itself
+            let source_file = chunk.data_file();
+            let target = context.store.link_target(source_file).await?;
+            context
+                .store
+                .copy(target.as_deref().unwrap_or(source_file), &data_file)
+                .await?;
+            None
+        };
+
+        let mut copied = Chunk::create(
+            &handle.uuid,
+            &data_file,
+            chunk.size_bytes,
+            chunk.row_count,
+            chunk.schema_version(),
+            chunk.timestamp_range(),
+            context,
+        )
+        .await?;
+        if let Some(shared) = &shared {
+            copied.link_shared_object(shared).await?;
+        }
+        copied.finalize().await?;
+    }
+    rebuild_chunk_stats(context, &handle).await?;
+
+    context
+        .store
+        .copy_recursive(
+            source_path.blobs_folder_path(),
+            path_in_store.blobs_folder_path(),
+        )
+        .await?;
+    if context.store.exists(source_path.path_media_index()).await? {
+        context
+            .store
+            .copy(
+                source_path.path_media_index(),
+                path_in_store.path_media_index(),
+            )
+            .await?;
+    }
+
+    columns_record(context, &handle, columns(context, source).await?).await?;
+
+    let copied_at = types::Timestamp::now();
+    let nearest = types::TopicLineage {
+        locator: source.locator.clone(),
+        uuid: source.uuid.clone(),
+        copied_at,
+    };
+
+    let mut tx = context.db.transaction().await?;
+    if let Some(is_sorted) = record.is_sorted() {
+        db::topic_update_sorted(&mut tx, handle.id, is_sorted).await?;
+    }
+    db::topic_update_completion_tstamp(&mut tx, handle.id, copied_at.as_i64()).await?;
+    for (depth, source) in std::iter::once(nearest).chain(source_lineage).enumerate() {
+        let record = db::TopicLineageRecord::new(handle.id, depth as i32 + 1, &source);
+        db::topic_lineage_create(&mut tx, &record).await?;
+    }
+    tx.commit().await?;
+
+    let metadata = metadata(context, &handle).await?;
+    metadata_write_to_store(context, path_in_store.path_metadata().as_path(), metadata).await?;
+
+    debug!(
+        "topic `{}` copied into `{}`",
+        source.locator, handle.locator
+    );
+
+    Ok(handle)
+}
+
+/// Returns the shared object holding the data file of `chunk`, a chunk of the topic
+/// `source`.
+///
+/// Data files not shared yet are moved to a new shared object, and replaced with a link
+/// to it. The shared object is referenced by the chunks linked to it, and deleted by
+/// [`collect_shared_objects`] once they are all deleted.
+async fn share_data_file(
+    context: &Context,
+    source: &Handle,
+    chunk: &db::ChunkRecord,
+) -> Result<db::SharedObjectRecord> {
+    let mut tx = context.db.transaction().await?;
+
+    // The lock prevents a concurrent compaction or relocation of the source
+    db::topic_lock(&mut tx, source.id).await?;
+
+    match db::shared_object_find_by_chunk(&mut tx, chunk.chunk_id).await {
+        Ok(shared) => return Ok(shared),
+        Err(db::Error::NotFound) => {}
+        Err(e) => Err(e)?,
+    }
+
+    let data_file = chunk.data_file();
+    let extension = data_file.extension().and_then(|ext| ext.to_str());
+    let path = store::shared_object_path(extension);
+    let shared = db::shared_object_create(&mut tx, &path.to_string_lossy()).await?;
+    db::chunk_shared_object_create(&mut tx, chunk.chunk_id, shared.id()).await?;
+
+    // Data files linked to an unrecorded object (e.g. by a share interrupted before
+    // committing) are shared from the target, since links to links are not resolved
+    let target = context.store.link_target(data_file).await?;
+    context
+        .store
+        .copy(target.as_deref().unwrap_or(data_file), &path)
+        .await?;
+    context.store.replace_with_link(data_file, &path).await?;
+
+    tx.commit().await?;
+
+    trace!(
+        "data file `{}` of topic `{}` shared as `{}`",
+        data_file.display(),
+        source.locator,
+        path.display()
+    );
+
+    Ok(shared)
+}
+
+/// Deletes the shared objects no longer referenced by any chunk, from the database and
+/// from the store. Returns the number of objects deleted.
+pub async fn collect_shared_objects(context: &Context) -> Result<usize> {
+    const PAGE_SIZE: i64 = 1000;
+
+    let mut cx = context.db.connection();
+    let mut collected = 0;
+    loop {
+        let page = db::shared_object_find_unreferenced(&mut cx, PAGE_SIZE).await?;
+        let last_page = (page.len() as i64) < PAGE_SIZE;
+
+        for shared in page {
+            // The record is deleted first, a failure leaves an unrecorded object in the
+            // store rather than a record of a missing object
+            if !db::shared_object_delete_unreferenced(&mut cx, shared.id()).await? {
+                continue;
+            }
+            context.store.delete(shared.path()).await?;
+            collected += 1;
+        }
+
+        if last_page {
+            break;
+        }
+    }
+
+    Ok(collected)
+}
+
+/// Returns the lineage of a topic, i.e. the topics its data was copied from, from the
+/// nearest. Topics not created by a [`copy`] have an empty lineage.
+pub async fn lineage(context: &Context, handle: &Handle) -> Result<Vec<types::TopicLineage>> {
+    let mut cx = context.db.connection();
+    let records = db::topic_lineage_find_all(&mut cx, handle.id).await?;
+
+    Ok(records
+        .into_iter()
+        .map(types::TopicLineage::try_from)
+        .collect::<std::result::Result<_, _>>()?)
+}
+
+/// Returns the topics whose locator matches `pattern` among the next `limit` topics
+/// starting with the prefix of the pattern, sorted by locator.
+///
+/// The page starts after `cursor`, the value returned along with the previous page. Pages
+/// may hold fewer than `limit` topics, even none, while other pages follow.
+pub async fn find_by_pattern(
+    context: &Context,
+    pattern: &types::LocatorPattern,
+    cursor: Option<&str>,
+    limit: usize,
+) -> Result<sequence::TopicPage> {
+    let mut cx = context.db.connection();
+
+    // One extra record is fetched to know if other pages follow
+    let mut records = db::topic_find_page(
+        &mut cx,
+        pattern.prefix(),
+        cursor,
+        limit.saturating_add(1) as i64,
+    )
+    .await?;
+
+    let cursor = if records.len() > limit {
+        records.truncate(limit);
+        records.last().map(|record| record.locator().to_string())
+    } else {
+        None
+    };
+
+    Ok(sequence::TopicPage {
+        handles: records
+            .into_iter()
+            .filter(|record| pattern.matches(&record.locator().to_string()))
+            .map(|record| {
+                Handle::new(
+                    record.locator(),
+                    record.topic_id,
+                    record.uuid(),
+                    record.path_in_store(),
+                )
+            })
+            .collect(),
+        cursor,
+    })
+}
+
+/// Compacts the data of an upsert topic: the rows merged by primary key are rewritten into
+/// new data files, replacing the existing ones. Data files are written up to the target size
+/// of the compaction policy of the sequence.
+///
+/// Returns `false` if the topic was not compacted since rows are being upserted.
+pub async fn compact(context: &Context, handle: &Handle) -> Result<bool> {
+    let mdata = metadata(context, handle).await?;
+    let properties = &mdata.ontology_metadata.properties;
+
+    if !properties.is_upsert() {
+        return Err(core::Error::bad_request(format!(
+            "topic `{}` is not an upsert topic",
+            handle.locator
+        )))?;
+    }
+
+    let format = properties.serialization_format;
+
+    // The lock prevents upserts from starting while the topic is compacted
+    let mut tx = context.db.transaction().await?;
+    db::topic_lock(&mut tx, handle.id).await?;
+
+    if db::topic_upsert_upload_in_progress(&mut tx, handle.id).await? {
+        return Ok(false);
+    }
+
+    // The topic may have been relocated after the handle was retrieved
+    let record = db::topic_find_by_id(&mut tx, handle.id).await?;
+    let Some(path_in_store) = &record.path_in_store() else {
+        return Ok(false);
+    };
+
+    // Compacted data files follow the ones staged by the sessions not finalized yet
+    let files = data_files(context, path_in_store, format).await?;
+    let first_chunk = next_chunk_number(context, path_in_store, format).await?;
+
+    // Compacted data files have the columns of all the schema versions merged
+    let schema_version = db::topic_schema_find_latest(&mut tx, handle.id)
+        .await?
+        .map(|record| record.version());
+
+    let data_folder = path_in_store.data_folder_path();
+    let merged = context
+        .timeseries_querier
+        .read_deduplicated(&data_folder, format, None, &properties.read_dedup_policy())
+        .await?;
+    let mut stream = merged.stream().await?;
+    let schema = stream.schema();
+
+    // Merged rows are buffered up to the target size of the data files of the sequence
+    let max_chunk_size = compaction_policy(&mut tx, record.sequence_id)
+        .await?
+        .target_object_size
+        .unwrap_or_default()
+        .max(1) as usize;
+    let mut writer = chunk_writer(context, data_folder, format, schema.clone(), first_chunk);
+    let mut buffer = Vec::new();
+    let mut buffer_size = 0;
+
+    while let Some(batch) = stream.try_next().await.map_err(query::Error::from)? {
+        buffer_size += batch.get_array_memory_size();
+        buffer.push(batch);
+
+        if buffer_size >= max_chunk_size {
+            let batch = concat_batches(&schema, &buffer)?;
+            write_compacted_chunk(
+                context,
+                handle,
+                &properties.ontology_tag,
+                schema_version,
+                &mut writer,
+                batch,
+            )
+            .await?;
+            buffer.clear();
+            buffer_size = 0;
+        }
+    }
+    if !buffer.is_empty() {
+        let batch = concat_batches(&schema, &buffer)?;
+        write_compacted_chunk(
+            context,
+            handle,
+            &properties.ontology_tag,
+            schema_version,
+            &mut writer,
+            batch,
+        )
+        .await?;
+    }
+
+    // Replace the compacted data files
+    let files: Vec<String> = files.into_iter().map(|(_, file)| file).collect();
+    db::chunk_delete_by_data_files(&mut tx, handle.id, &files).await?;
+    for file in &files {
+        context.store.delete(file).await?;
+    }
+
+    let info = compute_data_info(context, handle, &mut tx, format).await?;
+    db::topic_update_system_info(&mut tx, &handle.locator, &info).await?;
+    db::topic_update_compaction_tstamp(&mut tx, handle.id, types::Timestamp::now().as_i64())
+        .await?;
+
+    tx.commit().await?;
+
+    debug!(
+        "topic `{}` compacted from {} to {} data files",
+        handle.locator,
+        files.len(),
+        writer.chunk_count
+    );
+
+    Ok(true)
+}
+
+/// Redacts the values of `columns` in the rows of a finalized topic whose timestamp is in
+/// `range`. Values are replaced according to `mode` and the data files containing redacted
+/// rows are rewritten in place.
+///
+/// The redaction is recorded in the redaction log of the topic. Stored query results
+/// referencing the topic are discarded, while the manifest of the session that created the
+/// topic is updated to describe the rewritten objects.
+pub async fn redact(
+    context: &Context,
+    handle: &Handle,
+    range: types::TimestampRange,
+    columns: Vec<String>,
+    mode: types::RedactionMode,
+    reason: Option<String>,
+    principal: Option<&str>,
+) -> Result<types::Redaction> {
+    if status(context, handle).await? != Status::Finalized {
+        return Err(core::Error::bad_request(format!(
+            "topic `{}` is not finalized",
+            handle.locator
+        )))?;
+    }
+
+    let mdata = metadata(context, handle).await?;
+    let properties = &mdata.ontology_metadata.properties;
+    let format = properties.serialization_format;
+
+    // Media data files are indexed by byte offset, rewriting them would break the index
+    if format.is_media() {
+        return Err(core::Error::bad_request(format!(
+            "media topic `{}` can not be redacted",
+            handle.locator
+        )))?;
+    }
+
+    if columns.is_empty() {
+        return Err(core::Error::bad_request("no column to redact".to_owned()))?;
+    }
+
+    let schema = arrow_schema(context, handle, format).await?;
+    for column in &columns {
+        let field = schema.field_with_name(column).map_err(|_| {
+            core::Error::bad_request(format!(
+                "column `{}` not found in topic `{}`",
+                column, handle.locator
+            ))
+        })?;
+        ext::redact::check_redactable(field, mode).map_err(core::Error::bad_request)?;
+
+        if properties.primary_key.as_ref() == Some(column) {
+            return Err(core::Error::bad_request(format!(
+                "primary key `{}` can not be redacted",
+                column
+            )))?;
+        }
+    }
+
+    let mut tx = context.db.transaction().await?;
+
+    let record = db::topic_find_by_id(&mut tx, handle.id).await?;
+    sequence::lock_unless_held(&mut tx, record.sequence_id, "topic_redact", &handle.locator)
+        .await?;
+
+    // The lock prevents upserts and compactions from rewriting data files during the redaction
+    db::topic_lock(&mut tx, handle.id).await?;
+
+    if db::topic_upsert_upload_in_progress(&mut tx, handle.id).await? {
+        return Err(core::Error::topic_upload_in_progress(
+            handle.locator.to_string(),
+        ))?;
+    }
+
+    // The topic may have been relocated after the handle was retrieved
+    let Some(path_in_store) = locked_path_in_store(&mut tx, handle).await? else {
+        return Err(Error::MissingDbData(format!(
+            "No path in store set for topic {}",
+            handle.locator
+        )))?;
+    };
+
+    let mut row_count = 0;
+    let mut object_count = 0;
+    let mut references = Vec::new();
+
+    for chunk in db::chunk_find_all_by_topic(&mut tx, handle.id).await? {
+        let bytes = context.store.read_bytes(chunk.data_file()).await?;
+        let reader = rw::ChunkReader::new(format, bytes.into())?;
+        let schema = reader.schema();
+
+        let mut batches = Vec::new();
+        let mut chunk_row_count = 0;
+        for batch in reader {
+            let batch = batch?;
+            let rows = ext::redact::rows_in_range(&batch, &range)?;
+            if rows.true_count() == 0 {
+                batches.push(batch);
+                continue;
+            }
+
+            // Offloaded blobs are loaded upfront to be hashed
+            let mut blobs = HashMap::new();
+            if mode == types::RedactionMode::Hash {
+                for reference in ext::redact::external_references(&batch, &rows, &columns)? {
+                    let data = context
+                        .store
+                        .read_bytes(path_in_store.path_blob(&reference))
+                        .await?;
+                    blobs.insert(reference, data);
+                }
+            }
+
+            let redacted = ext::redact::redact_batch(
+                &batch,
+                &rows,
+                &columns,
+                mode,
+                |data| {
+                    ring::digest::digest(&ring::digest::SHA256, data)
+                        .as_ref()
+                        .to_vec()
+                },
+                |reference| {
+                    blobs.get(reference).cloned().ok_or_else(|| {
+                        arrow::error::ArrowError::ComputeError(format!(
+                            "missing blob `{reference}`"
+                        ))
+                        .into()
+                    })
+                },
+            )?;
+
+            chunk_row_count += redacted.row_count;
+            references.extend(redacted.references);
+            batches.push(redacted.batch);
+        }
+
+        if chunk_row_count == 0 {
+            continue;
+        }
+
+        trace!(
+            "redacting {} rows of data file `{}`",
+            chunk_row_count,
+            chunk.data_file().display()
+        );
+
+        // The data file is rewritten at the same path to keep the chunk numbering
+        let data_file = chunk.data_file().to_path_buf();
+        let mut writer =
+            rw::ChunkWriter::new(context.store.clone(), format, schema.clone(), move |_| {
+                data_file.clone()
+            });
+        let serialized = writer.write(concat_batches(&schema, &batches)?).await?;
+
+        db::chunk_update_size(
+            &mut tx,
+            chunk.chunk_id,
+            serialized.metadata.size_bytes as i64,
+        )
+        .await?;
+
+        let mut stats = Chunk::reopen(chunk, context).await?;
+        stats
+            .push_ontology_model_stats(&properties.ontology_tag, serialized.ontology_stats)
+            .await?;
+        stats.finalize().await?;
+
+        row_count += chunk_row_count as u64;
+        object_count += 1;
+    }
+
+    // Statistics may expose the redacted values
+    db::topic_delete_column_stats(&mut tx, handle.id).await?;
+
+    let info = compute_data_info(context, handle, &mut tx, format).await?;
+    db::topic_update_system_info(&mut tx, &handle.locator, &info).await?;
+
+    let deleted = db::query_result_delete_by_topic(&mut tx, &handle.locator).await?;
+    if deleted > 0 {
+        debug!(
+            "discarded {} query results referencing topic `{}`",
+            deleted, handle.locator
+        );
+    }
+
+    let redaction = types::Redaction {
+        uuid: types::Uuid::new(),
+        topic: handle.locator.clone(),
+        range,
+        columns,
+        mode,
+        principal: principal.map(str::to_owned),
+        reason,
+        row_count,
+        object_count,
+        created_at: types::Timestamp::now(),
+    };
+    db::redaction_create(&mut tx, &(&redaction).into()).await?;
+
+    tx.commit().await?;
+
+    // Offloaded blobs are replaced by inline values in the rewritten data files
+    for reference in references {
+        context
+            .store
+            .delete(path_in_store.path_blob(&reference))
+            .await?;
+    }
+
+    if object_count > 0 {
+        let mut cx = context.db.connection();
+        session::manifest_refresh_topic(context, &mut cx, record.session_id, &handle.uuid).await?;
+
+        context
+            .table_exporter
+            .export_all(context, vec![handle.locator.clone()])
+            .await;
+    }
+
+    tracing::warn!(
+        target: sequence::AUDIT_TARGET,
+        topic = %handle.locator,
+        principal = principal.unwrap_or_default(),
+        columns = ?redaction.columns,
+        %mode,
+        row_count,
+        object_count,
+        reason = redaction.reason.as_deref().unwrap_or_default(),
+        "topic redacted"
+    );
+
+    Ok(redaction)
+}
+
+/// Returns the redactions applied to the data of the topic, oldest first.
+pub async fn redactions(context: &Context, handle: &Handle) -> Result<Vec<types::Redaction>> {
+    let mut cx = context.db.connection();
+    let records = db::redaction_find_all_by_topic(&mut cx, &handle.locator).await?;
+
+    Ok(records
+        .into_iter()
+        .map(types::Redaction::try_from)
+        .collect::<std::result::Result<_, _>>()?)
+}
+
+/// Returns the range of chunk numbers of the data files written by the upserts completed
+/// after `since`, used to read the changes of an upsert topic.
+///
+/// The range is empty if no upsert was completed after `since`. Data files written by an
+/// upsert still in progress are excluded from the range.
+pub async fn changed_chunks(
+    context: &Context,
+    handle: &Handle,
+    since: types::Timestamp,
+) -> Result<Range<usize>> {
+    let mut cx = context.db.connection();
+    let record = db::topic_find_by_id(&mut cx, handle.id).await?;
+
+    if record.primary_key().is_none() {
+        Err(core::Error::bad_request(format!(
+            "changes are only available for upsert topics, `{}` is not an upsert topic",
+            handle.locator
+        )))?;
+    }
+
+    // Compacted data files mix the rows written before and after the compaction
+    if record
+        .compaction_timestamp()
+        .is_some_and(|compaction| compaction >= since)
+    {
+        Err(core::Error::bad_request(format!(
+            "data of topic `{}` was compacted after the requested time, the whole topic needs to be read",
+            handle.locator
+        )))?;
+    }
+
+    let path_in_store = handle.path_in_store().ok_or_else(|| {
+        Error::MissingDbData(format!("No path in store set for topic {}", handle.locator))
+    })?;
+    let format = record.serialization_format().ok_or_else(|| {
+        Error::MissingDbData(format!("No format set for topic {}", handle.locator))
+    })?;
+
+    let end = match db::topic_find_upsert_uploading(&mut cx, handle.id).await? {
+        Some(upsert) => upsert.first_chunk_number().unwrap_or_default(),
+        None => next_chunk_number(context, path_in_store, format).await?,
+    };
+
+    // The whole topic changed if it was created after the requested time
+    let topic_completed_after = record
+        .completion_timestamp()
+        .is_some_and(|completion| completion > since);
+    let start = if topic_completed_after {
+        0
+    } else {
+        db::topic_find_all_upserts_completed_after(&mut cx, handle.id, since.as_i64())
+            .await?
+            .iter()
+            .filter_map(|upsert| upsert.first_chunk_number())
+            .min()
+            .unwrap_or(end)
+    };
+
+    Ok(start.min(end)..end)
+}
+
+/// Returns the range of chunk numbers of the data files written by `session`, used to read
+/// the data uploaded into the topic by a single session.
+///
+/// Data files are written first by the session that created the topic and then, for upsert
+/// topics, by the sessions upserting rows into it, each one after the previous. The session
+/// must be finalized.
+pub async fn session_chunks(
+    context: &Context,
+    handle: &Handle,
+    session: &session::Handle,
+) -> Result<Range<usize>> {
+    let mut cx = context.db.connection();
+    let record = db::topic_find_by_id(&mut cx, handle.id).await?;
+
+    let not_finalized =
+        || core::Error::bad_request(format!("session `{}` is not finalized", session.locator()));
+
+    // Upserts that uploaded data, in the order of the data files they wrote
+    let upserts: Vec<db::TopicUpsertRecord> = db::topic_find_all_upserts(&mut cx, handle.id)
+        .await?
+        .into_iter()
+        .filter(|upsert| upsert.first_chunk_number().is_some())
+        .collect();
+
+    let (start, written_at, next) = if record.session_id == session.id() {
+        record.completion_timestamp().ok_or_else(not_finalized)?;
+        (0, record.creation_timestamp(), upserts.first())
+    } else {
+        let upsert = db::session_find_all_upserts(&mut cx, session.id())
+            .await?
+            .into_iter()
+            .find(|upsert| upsert.topic_id == handle.id)
+            .ok_or_else(|| {
+                core::Error::bad_request(format!(
+                    "topic `{}` was not written by session `{}`",
+                    handle.locator,
+                    session.locator()
+                ))
+            })?;
+        upsert.completion_timestamp().ok_or_else(not_finalized)?;
+
+        // The session did not upload any data
+        let Some(start) = upsert.first_chunk_number() else {
+            return Ok(0..0);
+        };
+        let position = upserts
+            .iter()
+            .position(|u| u.upsert_id == upsert.upsert_id)
+            .unwrap_or(upserts.len());
+        (
+            start,
+            upsert
+                .upload_timestamp()
+                .unwrap_or(upsert.creation_timestamp()),
+            upserts.get(position + 1),
+        )
+    };
+
+    // Compacted data files mix the rows written by different sessions
+    if record
+        .compaction_timestamp()
+        .is_some_and(|compaction| compaction >= written_at)
+    {
+        Err(core::Error::bad_request(format!(
+            "data of topic `{}` was compacted after being written by session `{}`",
+            handle.locator,
+            session.locator()
+        )))?;
+    }
+
+    let end = match next.and_then(|upsert| upsert.first_chunk_number()) {
+        Some(end) => end,
+        None => {
+            let path_in_store = handle.path_in_store().ok_or_else(|| {
+                Error::MissingDbData(format!("No path in store set for topic {}", handle.locator))
+            })?;
+            let format = record.serialization_format().ok_or_else(|| {
+                Error::MissingDbData(format!("No format set for topic {}", handle.locator))
+            })?;
+            next_chunk_number(context, path_in_store, format).await?
+        }
+    };
+
+    Ok(start.min(end)..end)
+}
+
+fn concat_batches(schema: &SchemaRef, batches: &[RecordBatch]) -> Result<RecordBatch> {
+    Ok(arrow::compute::concat_batches(schema, batches).map_err(ext::arrow::Error::from)?)
+}
+
+/// Writes a data file of the topic, registering the chunk in the data catalog.
+async fn write_compacted_chunk(
+    context: &Context,
+    handle: &Handle,
+    ontology_tag: &str,
+    schema_version: Option<u32>,
+    writer: &mut rw::ChunkWriter<Arc<store::Store>>,
+    batch: RecordBatch,
+) -> Result<()> {
+    let chunk = writer.write(batch).await?;
+
+    let mut record = Chunk::create(
+        &handle.uuid,
+        &chunk.path,
+        chunk.metadata.size_bytes as i64,
+        chunk.metadata.row_count as i64,
+        schema_version,
+        chunk.metadata.timestamp_range(),
+        context,
+    )
+    .await?;
+    record
+        .push_ontology_model_stats(ontology_tag, chunk.ontology_stats)
+        .await?;
+    record.finalize().await?;
+
+    Ok(())
+}
+
+/// Replaces the references of offloaded blobs with the blobs content.
+///
+/// Batches without offloaded blobs are returned untouched.
+pub async fn inline_blobs(
+    context: &Context,
+    path_in_store: &types::TopicPathInStore,
+    batch: RecordBatch,
+) -> Result<RecordBatch> {
+    let schema = batch.schema();
+    if !schema
+        .fields()
+        .iter()
+        .any(|field| ext::blob::is_blob(field.data_type()))
+    {
+        return Ok(batch);
+    }
+
+    let mut columns = Vec::with_capacity(batch.num_columns());
+    for (field, array) in schema.fields().iter().zip(batch.columns()) {
+        if !ext::blob::is_blob(field.data_type()) {
+            columns.push(array.clone());
+            continue;
+        }
+
+        let column = ext::blob::BlobColumn::try_new(array)?;
+
+        let mut blobs = HashMap::new();
+        for reference in column.references() {
+            let data = context
+                .store
+                .read_bytes(path_in_store.path_blob(reference))
+                .await?;
+            blobs.insert(reference.to_owned(), data);
+        }
+
+        columns.push(column.resolve(|reference| {
+            blobs.get(reference).cloned().ok_or_else(|| {
+                arrow::error::ArrowError::ComputeError(format!("missing blob `{reference}`")).into()
+            })
+        })?);
+    }
+
+    let batch = RecordBatch::try_new(Arc::new(ext::blob::inline_schema(&schema)), columns)
+        .map_err(ext::arrow::Error::from)?;
+
+    Ok(batch)
+}
+
+/// Applies the anonymization transforms to a batch exported from a topic, identifiers are
+/// hashed with SHA-256.
+pub fn anonymize(batch: RecordBatch, anonymization: &types::Anonymization) -> Result<RecordBatch> {
+    let batch = ext::anonymize::anonymize_batch(&batch, anonymization, |data| {
+        ring::digest::digest(&ring::digest::SHA256, data)
+            .as_ref()
+            .to_vec()
+    })?;
+
+    Ok(batch)
+}
+
+/// A guard ensuring exclusive write access to [`Handle`].
+///
+/// While this struct exists, the underlying topic is mutably borrowed, preventing
+/// any other operations (such as locking or concurrent reads) until [`HandleWriter::finalize`] is called.
+pub struct HandleWriter {
+    /// Anchors the exclusive borrow of the handle, strictly tying the writer's lifetime
+    /// to the topic's availability.
+    handle: Handle,
+
+    /// Serialization format used to write
+    format: types::Format,
+
+    ontology_tag: String,
+
+    /// Binary cells larger than this value (in bytes) are offloaded, 0 disables offloading
+    blob_offload_threshold: usize,
+
+    /// Time index of the segments written so far, available only for media topics
+    media_index: Option<types::MediaIndex>,
+
+    /// Tracks the ordering of the written data by the sort key (or by timestamp)
+    sort_order: ext::arrow::SortOrderTracker,
+
+    /// If true batches breaking the ordering by the sort key are rejected
+    enforce_sort_order: bool,
+
+    /// Schema registered for the topic, batches not matching it are rejected
+    registered_schema: Option<SchemaRef>,
+
+    /// Version of the registered schema the data are written under
+    schema_version: Option<u32>,
 
     /// The underlying writer handling the actual data operations.
     writer: rw::ChunkWriter<Arc<store::Store>>,
@@ -504,6 +2897,134 @@ impl HandleWriter {
         &self.ontology_tag
     }
 
+    /// Returns the version of the registered schema the data are written under, `None` if
+    /// the topic has no registered schema.
+    pub fn schema_version(&self) -> Option<u32> {
+        self.schema_version
+    }
+
+    /// Writes a [`RecordBatch`] into the topic.
+    ///
+    /// If blob offloading is enabled, binary cells larger than the configured threshold are
+    /// stored as standalone objects and replaced by a reference before the batch is serialized.
+    ///
+    /// For media topics the segments of the serialized chunk are added to the topic time index.
+    ///
+    /// If the topic declares a sort key, batches whose values are not sorted by it (within the
+    /// batch or with respect to the data already written) are rejected.
+    pub async fn write(&mut self, batch: RecordBatch) -> Result<rw::SerializedChunk> {
+        if let Some(registered) = &self.registered_schema {
+            check_data_schema(&self.handle.locator, registered, &batch.schema())?;
+        }
+
+        if !self.sort_order.inspect(&batch)? && self.enforce_sort_order {
+            return Err(core::Error::bad_request(format!(
+                "data of topic `{}` is not sorted by `{}`",
+                self.handle.locator,
+                self.sort_order.column()
+            )))?;
+        }
+
+        let batch = if self.blob_offload_threshold > 0 {
+            self.offload_blobs(batch).await?
+        } else {
+            batch
+        };
+
+        let chunk = self.writer.write(batch).await?;
+
+        if let Some(index) = &mut self.media_index {
+            // Segment files are relative to the topic root, so the index remains valid
+            // if the topic folder is moved, and refer to the data files once published
+            let published = self.handle.path_in_store.as_ref().and_then(|p| {
+                let filename = chunk.path.file_name()?;
+                let data_file = p.data_folder_path().join(filename);
+                Some(data_file.strip_prefix(p.root()).ok()?.to_owned())
+            });
+            let file = published
+                .as_deref()
+                .unwrap_or(&chunk.path)
+                .to_string_lossy()
+                .to_string();
+
+            for rg in &chunk.metadata.row_groups {
+                index.push(types::MediaSegment {
+                    file: file.clone(),
+                    start: rg.start,
+                    end: rg.end,
+                    offset: rg.offset,
+                    length: rg.length,
+                    row_count: rg.row_count,
+                });
+            }
+        }
+
+        Ok(chunk)
+    }
+
+    /// Registers a chunk returned by [`HandleWriter::write`] in the data catalog, along with
+    /// the statistics of its columns.
+    ///
+    /// Uploads received by the server register their chunks on their own, this is meant for
+    /// the data written by the server itself (e.g. imports).
+    pub async fn record_chunk(&mut self, chunk: rw::SerializedChunk) -> Result<()> {
+        let mut record = Chunk::create(
+            &self.handle.uuid,
+            &chunk.path,
+            chunk.metadata.size_bytes as i64,
+            chunk.metadata.row_count as i64,
+            self.schema_version,
+            chunk.metadata.timestamp_range(),
+            &self.context,
+        )
+        .await?;
+        record
+            .push_ontology_model_stats(&self.ontology_tag, chunk.ontology_stats)
+            .await?;
+        record.finalize().await?;
+
+        Ok(())
+    }
+
+    async fn offload_blobs(&mut self, mut batch: RecordBatch) -> Result<RecordBatch> {
+        // Path in store is set inside handle while creating the HandleWriter
+        let Some(path_in_store) = &self.handle.path_in_store else {
+            panic!("No path in store set for topic {}", self.handle.locator);
+        };
+
+        let binary_columns: Vec<String> = batch
+            .schema()
+            .fields()
+            .iter()
+            .filter(|field| ext::blob::is_binary(field.data_type()))
+            .map(|field| field.name().clone())
+            .collect();
+
+        for column in binary_columns {
+            let (offloaded, blobs) =
+                ext::blob::offload_column(&batch, &column, self.blob_offload_threshold, |_, _| {
+                    types::TopicPathInStore::blob_file()
+                })?;
+
+            for blob in blobs {
+                trace!(
+                    "offloading blob `{}` ({} bytes) for topic `{}`",
+                    blob.reference,
+                    blob.data.len(),
+                    self.handle.locator
+                );
+                self.context
+                    .store
+                    .write_bytes(path_in_store.path_blob(&blob.reference), blob.data)
+                    .await?;
+            }
+
+            batch = offloaded;
+        }
+
+        Ok(batch)
+    }
+
     /// Finalize the write procedure of the topic. The topic is locked and additional data are
     /// consolidated (e.g. metadata, timestamp bounds).
     pub async fn finalize(self) -> Result<()> {
@@ -513,21 +3034,44 @@ impl HandleWriter {
         let info = compute_data_info(&self.context, &self.handle, &mut tx, self.format).await?;
         db::topic_update_system_info(&mut tx, &self.handle.locator, &info).await?;
 
-        // Check if topic has already been uploaded and finalized.
-        if let Status::Finalized = impl_status(&self.handle, &mut tx).await? {
-            return Err(core::Error::topic_already_finalized(
-                self.handle.locator().to_string(),
-            ))?;
+        if let Some(upsert) = &self.handle.upsert {
+            // Check if the upsert has already been finalized.
+            let record = db::topic_upsert_find_by_id(&mut tx, upsert.id).await?;
+            if record.completion_timestamp().is_some() {
+                return Err(core::Error::topic_already_finalized(
+                    self.handle.locator().to_string(),
+                ))?;
+            }
+
+            // Data of upsert topics is sorted when merged on read, the ordering is not tracked
+            db::topic_upsert_update_completion_tstamp(
+                &mut tx,
+                upsert.id,
+                types::Timestamp::now().as_i64(),
+            )
+            .await?;
+
+            // Cached column statistics don't account for the upserted rows
+            db::topic_delete_column_stats(&mut tx, self.handle.id()).await?;
+        } else {
+            // Check if topic has already been uploaded and finalized.
+            if let Status::Finalized = impl_status(&self.handle, &mut tx).await? {
+                return Err(core::Error::topic_already_finalized(
+                    self.handle.locator().to_string(),
+                ))?;
+            }
+
+            db::topic_update_sorted(&mut tx, self.handle.id(), self.sort_order.is_sorted()).await?;
+
+            // Update completion timestamp
+            db::topic_update_completion_tstamp(
+                &mut tx,
+                self.handle.id(),
+                types::Timestamp::now().as_i64(),
+            )
+            .await?;
         }
 
-        // Update completion timestamp
-        db::topic_update_completion_tstamp(
-            &mut tx,
-            self.handle.id(),
-            types::Timestamp::now().as_i64(),
-        )
-        .await?;
-
         tx.commit().await?;
 
         // 2. Update metadata in Store (read entirely from DB and save to Store).
@@ -546,6 +3090,19 @@ impl HandleWriter {
         )
         .await?;
 
+        // 3. Save the time index of media topics.
+        if let Some(index) = &self.media_index {
+            let bytes: Vec<u8> = index
+                .segments()
+                .iter()
+                .collect::<marshal::JsonMediaIndex>()
+                .try_into()?;
+            self.context
+                .store
+                .write_bytes(path_in_store.path_media_index(), bytes)
+                .await?;
+        }
+
         Ok(())
     }
 }
@@ -568,15 +3125,14 @@ impl std::ops::DerefMut for HandleWriter {
 mod tests {
     use super::*;
     use crate::sequence;
-    use mosaicod_core::types::NotificationType;
-    use mosaicod_query as query;
+    use mosaicod_core::types::{NotificationPayload, NotificationSeverity, NotificationType};
 
     fn test_context(pool: sqlx::Pool<db::DatabaseType>) -> Context {
         let database = db::testing::Database::new(pool);
-        let store = store::testing::Store::new_random_on_tmp().unwrap();
-        let ts_gw = Arc::new(query::TimeseriesEngine::try_new((*store).clone(), 0).unwrap());
+        let store = Arc::new(store::Store::in_memory());
+        let ts_gw = Arc::new(query::TimeseriesEngine::try_new(store.clone(), 0).unwrap());
 
-        Context::new((*store).clone(), (*database).clone(), ts_gw)
+        Context::new(store, (*database).clone(), ts_gw)
     }
 
     fn dummy_ontology_metadata() -> TopicOntologyMetadata {
@@ -584,6 +3140,9 @@ mod tests {
             types::TopicOntologyProperties {
                 ontology_tag: "dummy".to_owned(),
                 serialization_format: types::Format::Default,
+                sort_key: None,
+                dedup_policy: types::DedupPolicy::None,
+                primary_key: None,
             },
             None,
         )
@@ -608,7 +3167,7 @@ mod tests {
         // Check sequence locator
         assert_eq!(*seq_handle.locator(), sequence.locator());
 
-        let session_handle = session::try_create(&context, seq_handle.locator().clone())
+        let session_handle = session::try_create(&context, seq_handle.locator().clone(), None)
             .await
             .unwrap();
 
@@ -649,6 +3208,308 @@ mod tests {
         );
     }
 
+    fn batch(timestamps: Vec<i64>, values: Vec<i64>) -> RecordBatch {
+        use arrow::array::Int64Array;
+        use arrow::datatypes::{DataType, Field, Schema};
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+                DataType::Int64,
+                false,
+            ),
+            Field::new("value", DataType::Int64, false),
+        ]));
+
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(timestamps)),
+                Arc::new(Int64Array::from(values)),
+            ],
+        )
+        .unwrap()
+    }
+
+    async fn upload(context: &Context, handle: Handle, batch: RecordBatch) {
+        let mut writer = writer(context.clone(), handle, batch.schema())
+            .await
+            .unwrap();
+        let chunk = writer.write(batch).await.unwrap();
+        writer.record_chunk(chunk).await.unwrap();
+        writer.finalize().await.unwrap();
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn topic_upsert_and_compact(pool: sqlx::Pool<db::DatabaseType>) {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        let seq_locator = "test_sequence".parse::<types::SequenceLocator>().unwrap();
+        let seq_handle = sequence::try_create(&context, seq_locator, None)
+            .await
+            .unwrap();
+
+        let topic_locator: types::TopicLocator = "test_sequence/calibration".parse().unwrap();
+        let mut ontology_metadata = dummy_ontology_metadata();
+        ontology_metadata.properties.primary_key = Some("timestamp_ns".to_owned());
+
+        let session = session::try_create(&context, seq_handle.locator().clone(), None)
+            .await
+            .unwrap();
+        let handle = try_create(&context, topic_locator.clone(), &session, ontology_metadata)
+            .await
+            .unwrap();
+        assert_eq!(handle.upload_key(), handle.uuid());
+        upload(&context, handle, batch(vec![1, 2, 3], vec![10, 20, 30])).await;
+
+        // Data files are staged until the session is finalized
+        let handle = Handle::try_from_locator(&context, topic_locator.clone())
+            .await
+            .unwrap();
+        let path_in_store = handle.path_in_store().unwrap().clone();
+        let files = data_files(&context, &path_in_store, types::Format::Default)
+            .await
+            .unwrap();
+        assert!(files.is_empty());
+        assert!(!is_published(&context, &handle).await.unwrap());
+
+        session::finalize(&context, &session).await.unwrap();
+        assert!(is_published(&context, &handle).await.unwrap());
+        let staged = context
+            .store
+            .list(path_in_store.staging_folder_path(), None)
+            .await
+            .unwrap();
+        assert!(staged.is_empty());
+
+        // A later session upserts rows into the same topic
+        let mut ontology_metadata = dummy_ontology_metadata();
+        ontology_metadata.properties.primary_key = Some("timestamp_ns".to_owned());
+
+        let session = session::try_create(&context, seq_handle.locator().clone(), None)
+            .await
+            .unwrap();
+        let handle = try_create(&context, topic_locator.clone(), &session, ontology_metadata)
+            .await
+            .unwrap();
+        assert_ne!(handle.upload_key(), handle.uuid());
+        upload(&context, handle, batch(vec![2, 4], vec![21, 40])).await;
+
+        let handle = Handle::try_from_locator(&context, topic_locator.clone())
+            .await
+            .unwrap();
+        assert_eq!(data_info(&context, &handle).await.unwrap().chunks_number, 1);
+
+        session::finalize(&context, &session).await.unwrap();
+        assert_eq!(data_info(&context, &handle).await.unwrap().chunks_number, 2);
+
+        assert!(compact(&context, &handle).await.unwrap());
+        assert_eq!(data_info(&context, &handle).await.unwrap().chunks_number, 1);
+
+        let files = data_files(&context, &path_in_store, types::Format::Default)
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].0, 2);
+
+        let schema = arrow_schema(&context, &handle, types::Format::Default)
+            .await
+            .unwrap();
+        assert_eq!(schema.fields().len(), 2);
+
+        let rows = context
+            .timeseries_querier
+            .read(
+                path_in_store.data_folder_path(),
+                types::Format::Default,
+                None,
+            )
+            .await
+            .unwrap()
+            .count()
+            .await
+            .unwrap();
+        assert_eq!(rows, 4);
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn topic_data_of_expired_sessions(pool: sqlx::Pool<db::DatabaseType>) {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        let seq_locator = "test_sequence".parse::<types::SequenceLocator>().unwrap();
+        let seq_handle = sequence::try_create(&context, seq_locator, None)
+            .await
+            .unwrap();
+
+        let upsert_locator: types::TopicLocator = "test_sequence/calibration".parse().unwrap();
+        let upsert_metadata = || {
+            let mut ontology_metadata = dummy_ontology_metadata();
+            ontology_metadata.properties.primary_key = Some("timestamp_ns".to_owned());
+            ontology_metadata
+        };
+
+        let session = session::try_create(&context, seq_handle.locator().clone(), None)
+            .await
+            .unwrap();
+        let handle = try_create(
+            &context,
+            upsert_locator.clone(),
+            &session,
+            upsert_metadata(),
+        )
+        .await
+        .unwrap();
+        upload(&context, handle, batch(vec![1, 2, 3], vec![10, 20, 30])).await;
+        session::finalize(&context, &session).await.unwrap();
+
+        // The upload of the upsert is never completed
+        let session = session::try_create(&context, seq_handle.locator().clone(), None)
+            .await
+            .unwrap();
+        let handle = try_create(
+            &context,
+            upsert_locator.clone(),
+            &session,
+            upsert_metadata(),
+        )
+        .await
+        .unwrap();
+        let batch_upserted = batch(vec![2, 4], vec![21, 40]);
+        let mut upsert_writer = writer(context.clone(), handle, batch_upserted.schema())
+            .await
+            .unwrap();
+        upsert_writer.write(batch_upserted).await.unwrap();
+
+        // A topic of a session never finalized
+        let session = session::try_create(&context, seq_handle.locator().clone(), None)
+            .await
+            .unwrap();
+        let handle = try_create(
+            &context,
+            "test_sequence/gps".parse().unwrap(),
+            &session,
+            dummy_ontology_metadata(),
+        )
+        .await
+        .unwrap();
+        upload(&context, handle, batch(vec![1, 2], vec![1, 2])).await;
+        let abandoned = Handle::try_from_locator(&context, "test_sequence/gps".parse().unwrap())
+            .await
+            .unwrap();
+
+        let before = types::Timestamp::now();
+
+        // Sessions used after `before` are kept
+        let active = session::try_create(&context, seq_handle.locator().clone(), None)
+            .await
+            .unwrap();
+
+        let aborted = session::abort_expired(&context, before, types::allow_data_loss())
+            .await
+            .unwrap();
+        assert_eq!(aborted, 2);
+
+        session::Handle::try_from_uuid(&context, active.uuid())
+            .await
+            .unwrap();
+        assert!(
+            Handle::try_from_locator(&context, "test_sequence/gps".parse().unwrap())
+                .await
+                .is_err()
+        );
+        let abandoned_files = context
+            .store
+            .list(abandoned.path_in_store().unwrap().root(), None)
+            .await
+            .unwrap();
+        assert!(abandoned_files.is_empty());
+
+        // Only the rows of the completed upload are left in the upsert topic
+        let handle = Handle::try_from_locator(&context, upsert_locator)
+            .await
+            .unwrap();
+        let path_in_store = handle.path_in_store().unwrap();
+        let files = data_files(&context, path_in_store, types::Format::Default)
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].0, 0);
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn topic_data_of_aborted_session(pool: sqlx::Pool<db::DatabaseType>) {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        let seq_locator = "test_sequence".parse::<types::SequenceLocator>().unwrap();
+        let seq_handle = sequence::try_create(&context, seq_locator, None)
+            .await
+            .unwrap();
+
+        let session = session::try_create(&context, seq_handle.locator().clone(), None)
+            .await
+            .unwrap();
+        let handle = try_create(
+            &context,
+            "test_sequence/gps".parse().unwrap(),
+            &session,
+            dummy_ontology_metadata(),
+        )
+        .await
+        .unwrap();
+        upload(&context, handle, batch(vec![1, 2], vec![1, 2])).await;
+        let aborted = Handle::try_from_locator(&context, "test_sequence/gps".parse().unwrap())
+            .await
+            .unwrap();
+
+        session::abort(&context, &session, types::allow_data_loss())
+            .await
+            .unwrap();
+
+        assert!(
+            session::Handle::try_from_uuid(&context, session.uuid())
+                .await
+                .is_err()
+        );
+        assert!(
+            Handle::try_from_locator(&context, "test_sequence/gps".parse().unwrap())
+                .await
+                .is_err()
+        );
+        let files = context
+            .store
+            .list(aborted.path_in_store().unwrap().root(), None)
+            .await
+            .unwrap();
+        assert!(files.is_empty());
+
+        // Finalized sessions are kept
+        let session = session::try_create(&context, seq_handle.locator().clone(), None)
+            .await
+            .unwrap();
+        let handle = try_create(
+            &context,
+            "test_sequence/gps".parse().unwrap(),
+            &session,
+            dummy_ontology_metadata(),
+        )
+        .await
+        .unwrap();
+        upload(&context, handle, batch(vec![1, 2], vec![1, 2])).await;
+        session::finalize(&context, &session).await.unwrap();
+
+        assert!(
+            session::abort(&context, &session, types::allow_data_loss())
+                .await
+                .is_err()
+        );
+        session::Handle::try_from_uuid(&context, session.uuid())
+            .await
+            .unwrap();
+    }
+
     #[sqlx::test(migrator = "db::testing::MIGRATOR")]
     async fn topic_notify_and_notify_purge(pool: sqlx::Pool<db::DatabaseType>) {
         let context = test_context(pool);
@@ -669,7 +3530,7 @@ mod tests {
         // Check sequence locator
         assert_eq!(*seq_handle.locator(), sequence.locator());
 
-        let session_handle = session::try_create(&context, seq_handle.locator().clone())
+        let session_handle = session::try_create(&context, seq_handle.locator().clone(), None)
             .await
             .expect("Unable to create session");
         assert!(session_handle.uuid().is_valid());
@@ -689,7 +3550,9 @@ mod tests {
             &context,
             &topic_handle,
             NotificationType::Error,
-            "test notification message".to_owned(),
+            NotificationSeverity::Error,
+            NotificationPayload::message(),
+            Some("test notification message".to_owned()),
         )
         .await
         .expect("Error creating notification message");
@@ -698,7 +3561,9 @@ mod tests {
             &context,
             &topic_handle,
             NotificationType::Error,
-            "test notification message 2".to_owned(),
+            NotificationSeverity::Info,
+            NotificationPayload::message(),
+            Some("test notification message 2".to_owned()),
         )
         .await
         .expect("Error creating notification message");
@@ -743,4 +3608,156 @@ mod tests {
                 .is_empty()
         );
     }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn topic_copy_shares_data_files(pool: sqlx::Pool<db::DatabaseType>) {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool);
+
+        for sequence in ["run_a", "run_b", "run_c"] {
+            sequence::try_create(&context, sequence.parse().unwrap(), None)
+                .await
+                .unwrap();
+        }
+
+        let session = session::try_create(&context, "run_a".parse().unwrap(), None)
+            .await
+            .unwrap();
+        let source = try_create(
+            &context,
+            "run_a/imu".parse().unwrap(),
+            &session,
+            dummy_ontology_metadata(),
+        )
+        .await
+        .unwrap();
+        upload(&context, source, batch(vec![1, 2, 3], vec![10, 20, 30])).await;
+        session::finalize(&context, &session).await.unwrap();
+
+        // Copies of copies share the same object
+        let source = Handle::try_from_locator(&context, "run_a/imu".parse().unwrap())
+            .await
+            .unwrap();
+        let copied = copy(&context, &source, "run_b".parse().unwrap(), None)
+            .await
+            .unwrap();
+        let copied_twice = copy(&context, &copied, "run_c".parse().unwrap(), None)
+            .await
+            .unwrap();
+
+        let mut targets = Vec::new();
+        for handle in [&source, &copied, &copied_twice] {
+            let path_in_store = handle.path_in_store().unwrap();
+            let files = data_files(&context, path_in_store, types::Format::Default)
+                .await
+                .unwrap();
+            assert_eq!(files.len(), 1);
+            targets.push(context.store.link_target(&files[0].1).await.unwrap());
+
+            let rows = context
+                .timeseries_querier
+                .read(
+                    path_in_store.data_folder_path(),
+                    types::Format::Default,
+                    None,
+                )
+                .await
+                .unwrap()
+                .count()
+                .await
+                .unwrap();
+            assert_eq!(rows, 3);
+        }
+        assert!(targets[0].is_some());
+        assert!(targets.iter().all(|target| *target == targets[0]));
+
+        let mut cx = context.db.connection();
+        let chunk = db::chunk_find_all_by_topic(&mut cx, source.id)
+            .await
+            .unwrap()
+            .remove(0);
+        let shared = db::shared_object_find_by_chunk(&mut cx, chunk.chunk_id)
+            .await
+            .unwrap();
+        assert_eq!(Some(shared.path().to_owned()), targets[0]);
+        assert_eq!(
+            db::shared_object_ref_count(&mut cx, shared.id())
+                .await
+                .unwrap(),
+            3
+        );
+
+        // The shared object is deleted once no topic references it
+        delete(&context, source, types::allow_data_loss())
+            .await
+            .unwrap();
+        delete(&context, copied, types::allow_data_loss())
+            .await
+            .unwrap();
+        assert_eq!(collect_shared_objects(&context).await.unwrap(), 0);
+        assert!(context.store.exists(shared.path()).await.unwrap());
+
+        delete(&context, copied_twice, types::allow_data_loss())
+            .await
+            .unwrap();
+        assert_eq!(collect_shared_objects(&context).await.unwrap(), 1);
+        assert!(!context.store.exists(shared.path()).await.unwrap());
+    }
+
+    #[sqlx::test(migrator = "db::testing::MIGRATOR")]
+    async fn topic_copy_hive_layout(pool: sqlx::Pool<db::DatabaseType>) {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+        let context = test_context(pool).with_store_layout(types::StoreLayout::Hive);
+
+        for sequence in ["run_a", "run_b"] {
+            sequence::try_create(&context, sequence.parse().unwrap(), None)
+                .await
+                .unwrap();
+        }
+
+        let session = session::try_create(&context, "run_a".parse().unwrap(), None)
+            .await
+            .unwrap();
+        let source = try_create(
+            &context,
+            "run_a/imu".parse().unwrap(),
+            &session,
+            dummy_ontology_metadata(),
+        )
+        .await
+        .unwrap();
+        upload(&context, source, batch(vec![1, 2, 3], vec![10, 20, 30])).await;
+        session::finalize(&context, &session).await.unwrap();
+
+        // Data files are copied, neither the source nor the copy is replaced with a link
+        let source = Handle::try_from_locator(&context, "run_a/imu".parse().unwrap())
+            .await
+            .unwrap();
+        let copied = copy(&context, &source, "run_b".parse().unwrap(), None)
+            .await
+            .unwrap();
+
+        for handle in [&source, &copied] {
+            let files = data_files(
+                &context,
+                handle.path_in_store().unwrap(),
+                types::Format::Default,
+            )
+            .await
+            .unwrap();
+            assert_eq!(files.len(), 1);
+            assert_eq!(context.store.link_target(&files[0].1).await.unwrap(), None);
+        }
+        assert!(context.store.list("shared", None).await.unwrap().is_empty());
+
+        let mut cx = context.db.connection();
+        let chunk = db::chunk_find_all_by_topic(&mut cx, copied.id)
+            .await
+            .unwrap()
+            .remove(0);
+        assert!(matches!(
+            db::shared_object_find_by_chunk(&mut cx, chunk.chunk_id).await,
+            Err(db::Error::NotFound)
+        ));
+    }
 }
@@ -0,0 +1,204 @@
+//! Export of the data of a topic as a self-contained Parquet file, streamed to the clients or
+//! written in the store under the [`EXPORTS_FOLDER`].
+//!
+//! The exported rows are the ones served by DoGet: rows of upsert topics are merged according
+//! to their deduplication policy and offloaded blobs are inlined. The metadata of the topic
+//! can be written in the key/value metadata of the file, with the same `mosaico:` keys of the
+//! schema metadata served by DoGet.
+use super::{Context, topic};
+use bytes::Bytes;
+use futures::{TryStreamExt, stream::BoxStream};
+use log::{debug, info};
+use mosaicod_core::{self as core, error::PublicResult as Result, params, types};
+use mosaicod_ext as ext;
+use mosaicod_marshal as marshal;
+use mosaicod_query as query;
+use mosaicod_rw as rw;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Folder of the store holding the exported files.
+pub const EXPORTS_FOLDER: &str = "exports";
+
+/// Options of a topic export.
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// If set only the rows in the range are exported
+    pub timestamp_range: Option<types::TimestampRange>,
+    /// Maximum number of rows of the row groups of the file
+    pub row_group_size: Option<usize>,
+    /// If true the metadata of the topic is written in the key/value metadata of the file
+    pub include_metadata: bool,
+}
+
+/// Chunks of an exported Parquet file, the file is their concatenation.
+pub type ExportStream = BoxStream<'static, Result<Bytes>>;
+
+/// Encodes the published data of a topic as a Parquet file.
+pub async fn encode(
+    context: &Context,
+    handle: &topic::Handle,
+    options: ExportOptions,
+) -> Result<ExportStream> {
+    if options.row_group_size == Some(0) {
+        return Err(core::Error::bad_request("row group size must be positive".to_owned()).into());
+    }
+
+    if topic::status(context, handle).await? == topic::Status::Empty {
+        return Err(core::Error::missing_doput(handle.locator().to_string()).into());
+    }
+    if !topic::is_published(context, handle).await? {
+        return Err(core::Error::topic_not_published(handle.locator().to_string()).into());
+    }
+
+    let path_in_store = handle.path_in_store().cloned().ok_or_else(|| {
+        core::Error::internal(Some(format!(
+            "Path in store not set for topic {}",
+            handle.locator()
+        )))
+    })?;
+
+    let metadata = topic::metadata(context, handle).await?;
+    let properties = &metadata.ontology_metadata.properties;
+    let batch_size = topic::compute_optimal_batch_size(context, handle).await?;
+
+    let mut result = match &options.timestamp_range {
+        Some(range) => topic::read_range(context, handle, properties, batch_size, range).await?,
+        None => topic::read(context, handle, properties, batch_size).await?,
+    };
+    if let Some(range) = options.timestamp_range {
+        result = result.filter_by_timestamp_range(range)?;
+    }
+
+    let schema = Arc::new(ext::blob::inline_schema(&result.schema()));
+
+    let key_value_metadata = if options.include_metadata {
+        let mut flat = marshal::JsonTopicMetadata::from(metadata)
+            .ontology_metadata
+            .to_flat_hashmap()?;
+        flat.insert("mosaico:locator".to_owned(), handle.locator().to_string());
+        flat
+    } else {
+        HashMap::new()
+    };
+
+    debug!(
+        "exporting topic `{}` with row groups of {:?} rows",
+        handle.locator(),
+        options.row_group_size
+    );
+    let writer = rw::ExportWriter::try_new(schema, options.row_group_size, key_value_metadata)?;
+    let batches = result.stream().await?;
+
+    let context = context.clone();
+    let stream =
+        futures::stream::try_unfold((batches, Some(writer)), move |(mut batches, mut writer)| {
+            let context = context.clone();
+            let path_in_store = path_in_store.clone();
+            async move {
+                loop {
+                    let Some(current) = writer.as_mut() else {
+                        return Ok(None);
+                    };
+
+                    let Some(batch) = batches.try_next().await.map_err(query::Error::from)? else {
+                        let bytes = writer.take().map(rw::ExportWriter::finish).transpose()?;
+                        return Ok(bytes.map(|bytes| (bytes, (batches, None))));
+                    };
+
+                    let batch = topic::inline_blobs(&context, &path_in_store, batch).await?;
+                    if let Some(bytes) = current.write(&batch)? {
+                        return Ok(Some((bytes, (batches, writer))));
+                    }
+                }
+            }
+        });
+
+    Ok(Box::pin(stream))
+}
+
+/// Exports the published data of a topic as a Parquet file written in the store under
+/// `prefix`, in the [`EXPORTS_FOLDER`]. Returns the path of the file, relative to the root
+/// of the store, and its size in bytes.
+pub async fn write(
+    context: &Context,
+    handle: &topic::Handle,
+    options: ExportOptions,
+    prefix: &str,
+) -> Result<(String, usize)> {
+    let path = export_path(prefix, handle.locator())?;
+
+    let mut stream = encode(context, handle, options).await?;
+
+    let mut writer = context.store.writer(&path).await?;
+    loop {
+        match stream.try_next().await {
+            Ok(Some(bytes)) => writer.write_chunk(bytes).await?,
+            Ok(None) => break,
+            Err(e) => {
+                writer.abort().await?;
+                return Err(e);
+            }
+        }
+    }
+    let size = writer.finish().await?;
+
+    info!(
+        "topic `{}` exported to `{}` ({} bytes)",
+        handle.locator(),
+        path,
+        size
+    );
+
+    Ok((path, size))
+}
+
+/// Returns the path of the file exporting the topic `locator` under `prefix`.
+fn export_path(prefix: &str, locator: &types::TopicLocator) -> Result<String> {
+    let prefix = check_prefix(prefix)?;
+
+    Ok(format!(
+        "{EXPORTS_FOLDER}/{prefix}/{locator}.{}",
+        params::ext::PARQUET
+    ))
+}
+
+/// Returns `prefix` without its leading and trailing slashes, failing if it is empty or if
+/// it has empty or relative segments.
+pub(crate) fn check_prefix(prefix: &str) -> Result<&str> {
+    let prefix = prefix.trim_matches('/');
+
+    let valid = !prefix.is_empty()
+        && prefix
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != "..");
+    if !valid {
+        return Err(core::Error::bad_request(format!("invalid export prefix `{prefix}`")).into());
+    }
+
+    Ok(prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_paths() {
+        let locator: types::TopicLocator = "run_a/camera/front".parse().unwrap();
+
+        assert_eq!(
+            export_path("nightly/", &locator).unwrap(),
+            "exports/nightly/run_a/camera/front.parquet"
+        );
+        assert_eq!(
+            export_path("2024/01", &locator).unwrap(),
+            "exports/2024/01/run_a/camera/front.parquet"
+        );
+
+        assert!(export_path("", &locator).is_err());
+        assert!(export_path("/", &locator).is_err());
+        assert!(export_path("a//b", &locator).is_err());
+        assert!(export_path("../secrets", &locator).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+//! Limits on the bandwidth used by the background transfers.
+//!
+//! Each class of background jobs moving data over the network (e.g. the relay uploads)
+//! shares a [`store::Throttle`], so that the jobs of the class together do not exceed the
+//! bandwidth configured for the time of the day. Objects copied within the store (e.g. by
+//! the relocation of the store folders) are copied backend-side and are not limited.
+use mosaicod_core::{params, types};
+use mosaicod_store as store;
+
+/// Class of background jobs transferring data over the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferClass {
+    /// Upload of the finalized sessions to the central instance
+    Relay,
+}
+
+/// Throttles applied to the transfers of each class of background jobs.
+#[derive(Debug, Default)]
+pub struct TransferLimits {
+    relay: store::Throttle,
+}
+
+impl TransferLimits {
+    /// Creates limits configured with [`params::Params::bandwidth_limit_relay`].
+    pub fn from_params() -> Self {
+        let params = params::params();
+        Self {
+            relay: store::Throttle::new(params.bandwidth_limit_relay.value.clone()),
+        }
+    }
+
+    /// Creates limits without any restriction.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Limits the bandwidth of the transfers of `class` according to `schedule`.
+    pub fn with_class_limit(
+        mut self,
+        class: TransferClass,
+        schedule: types::BandwidthSchedule,
+    ) -> Self {
+        *self.throttle_mut(class) = store::Throttle::new(schedule);
+        self
+    }
+
+    /// Returns the throttle shared by the transfers of `class`.
+    pub fn throttle(&self, class: TransferClass) -> &store::Throttle {
+        match class {
+            TransferClass::Relay => &self.relay,
+        }
+    }
+
+    fn throttle_mut(&mut self, class: TransferClass) -> &mut store::Throttle {
+        match class {
+            TransferClass::Relay => &mut self.relay,
+        }
+    }
+}
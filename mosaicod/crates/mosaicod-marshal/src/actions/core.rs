@@ -16,6 +16,10 @@ pub enum ActionError {
     #[error("body deserialization error")]
     BodyDeserializationError(#[from] serde_json::Error),
 
+    /// A field of the request body is not correctly encoded.
+    #[error("bad encoding of field {0}")]
+    BadEncoding(String),
+
     /// Failed to serialize the response.
     #[error("response serialization error: {0}")]
     ResponseSerializationError(String),
@@ -24,7 +28,7 @@ pub enum ActionError {
 impl core::error::PublicError for ActionError {
     fn error(&self) -> core::Error {
         match self {
-            Self::MissingAction(_) | Self::BodyDeserializationError(_) => {
+            Self::MissingAction(_) | Self::BodyDeserializationError(_) | Self::BadEncoding(_) => {
                 core::Error::bad_request(self.to_string())
             }
             Self::ResponseSerializationError(_) => {
@@ -57,45 +61,182 @@ pub enum ActionRequest {
     /// If the action completes successfully, a new (empty) sequence will be available.
     SequenceCreate(requests::SequenceCreate),
 
-    /// Deletes an unlocked sequence from the system.
+    /// Moves an unlocked sequence to the trash.
     SequenceDelete(requests::ResourceLocator),
 
+    /// Restores a sequence from the trash.
+    SequenceRestore(requests::ResourceLocator),
+
+    /// Permanently deletes a sequence in the trash.
+    SequencePurge(requests::ResourceLocator),
+
+    /// Sets or releases the legal hold of a sequence, blocking any deletion while set.
+    SequenceLegalHold(requests::SequenceLegalHold),
+
+    /// Sets the compaction policy of the upsert topics of a sequence.
+    SequenceCompactionPolicySet(requests::SequenceCompactionPolicy),
+
+    /// Get the compaction policy of a sequence
+    SequenceCompactionPolicyGet(requests::ResourceLocator),
+
+    /// Compacts the upsert topics of a sequence on demand.
+    SequenceCompact(requests::ResourceLocator),
+
+    /// Imports an MCAP recording available in the store into a new session of a sequence,
+    /// creating the sequence if missing.
+    SequenceImportMcap(requests::SequenceImportMcap),
+
+    /// Writes a sequence and its published topics as a portable archive in the store.
+    SequenceExport(requests::SequenceExport),
+
+    /// Recreates a sequence from a portable archive available in the store.
+    SequenceImport(requests::SequenceImport),
+
+    /// Returns the ticket streaming some topics of a sequence time-merged for replay.
+    SequenceReplay(requests::SequenceReplay),
+
+    /// Merges or replaces the user metadata of a sequence whose sessions are not finalized.
+    SequenceUpdateMetadata(requests::SequenceUpdateMetadata),
+
+    /// Lists the sequences in the system, one page at a time.
+    SequenceList(requests::SequenceList),
+
+    /// Get the signed attestations of the sessions of a sequence
+    SequenceAttestation(requests::ResourceLocator),
+
+    /// Get the chronological feed of the events and notifications of a sequence
+    ActivityFeed(requests::ActivityFeed),
+
     /// Creates a notification associated with a sequence.
     SequenceNotificationCreate(requests::NotificationCreate),
 
     /// Get all notifications for a given sequence
-    SequenceNotificationList(requests::ResourceLocator),
+    SequenceNotificationList(requests::NotificationList),
 
     /// Deletes all notifications associated with a sequence
     SequenceNotificationPurge(requests::ResourceLocator),
 
+    /// Get a ticket streaming the notifications of a sequence as they are created
+    SequenceNotificationStream(requests::NotificationStream),
+
     /// Creates a new topic in the system without any data.
     TopicCreate(requests::TopicCreate),
 
     /// Deletes an unlocked topic from the system.
     TopicDelete(requests::ResourceLocator),
 
+    /// Get a page of the topics of a given sequence
+    TopicList(requests::TopicList),
+
     /// Creates a notification associated with a topic.
     TopicNotificationCreate(requests::NotificationCreate),
 
     /// Get all notifications for a given topic
-    TopicNotificationList(requests::ResourceLocator),
+    TopicNotificationList(requests::NotificationList),
 
     /// Deletes all notifications associated with a topic
     TopicNotificationPurge(requests::ResourceLocator),
 
+    /// Get the time index of a media topic
+    TopicMediaIndex(requests::TopicMediaIndex),
+
+    /// Exports the data of a topic as a Parquet file, streamed or written in the store
+    TopicExport(requests::TopicExport),
+
+    /// Copies a topic and its data into another sequence, within a new session
+    TopicCopy(requests::TopicCopy),
+
+    /// Get the topics the data of a topic was copied from
+    TopicLineage(requests::ResourceLocator),
+
+    /// Get the schema registered for a topic
+    TopicSchemaGet(requests::ResourceLocator),
+
+    /// Registers a new version of the schema of a topic
+    TopicSchemaEvolve(requests::TopicSchemaEvolve),
+
+    /// Sets the description and unit of the columns of a topic
+    TopicColumnsUpdate(requests::TopicColumnsUpdate),
+
+    /// Get the statistics of the columns of a topic
+    TopicColumnStats(requests::TopicColumnStats),
+
+    /// Redacts the values of some columns of a topic, recording it in the redaction log
+    TopicRedact(requests::TopicRedact),
+
+    /// Get the redactions applied to a topic
+    TopicRedactionList(requests::ResourceLocator),
+
+    /// Get the ingestion watermark of a topic
+    TopicWatermark(requests::ResourceLocator),
+
     /// Creates a new upload session for the given sequence.
     SessionCreate(requests::ResourceLocator),
 
     /// Finalizes the upload session
     SessionFinalize(requests::SessionUuid),
 
+    /// Aborts an upload session not finalized yet, discarding the data uploaded
+    SessionAbort(requests::SessionUuid),
+
+    /// Allows another API key to write into the session
+    SessionShare(requests::SessionShare),
+
     /// Deletes the selected session.
     SessionDelete(requests::ResourceLocator),
 
+    /// Get the sessions of a given sequence, optionally only the open or finalized ones
+    SessionList(requests::SessionList),
+
+    /// Commits the offset processed by a named consumer of a sequence or topic
+    ConsumerOffsetCommit(requests::ConsumerOffsetCommit),
+
+    /// Get the offset committed by a named consumer of a sequence or topic
+    ConsumerOffsetGet(requests::ConsumerOffset),
+
+    /// Deletes the offset committed by a named consumer, so that it starts over
+    ConsumerOffsetDelete(requests::ConsumerOffset),
+
     /// Perform a query in the system
     Query(requests::Query),
 
+    /// Fetches the next rows of a paginated query
+    QueryFetch(requests::QueryFetch),
+
+    /// Runs a SQL statement over the data of some topics, returning a ticket to stream
+    /// its result
+    SqlQuery(requests::SqlQuery),
+
+    /// Creates a query evaluated following a cron schedule.
+    ScheduledQueryCreate(requests::ScheduledQueryCreate),
+
+    /// Get all the scheduled queries
+    ScheduledQueryList(requests::Empty),
+
+    /// Evaluates a scheduled query immediately
+    ScheduledQueryRun(requests::ScheduledQueryName),
+
+    /// Deletes a scheduled query
+    ScheduledQueryDelete(requests::ScheduledQueryName),
+
+    /// Creates a named query within a sequence, resolvable by subsequent queries.
+    QueryViewCreate(requests::QueryViewCreate),
+
+    /// Get all the views of a sequence
+    QueryViewList(requests::ResourceLocator),
+
+    /// Deletes a view
+    QueryViewDelete(requests::QueryViewName),
+
+    /// Registers a file as a temporary table, referenced by the queries of the principal.
+    ExternalTableRegister(requests::ExternalTableRegister),
+
+    /// Get all the external tables of the principal
+    ExternalTableList(requests::Empty),
+
+    /// Unregisters an external table
+    ExternalTableUnregister(requests::ExternalTableName),
+
     /// Ask to create a new api key with given permissions and duration.
     ApiKeyCreate(requests::ApiKeyCreate),
 
@@ -106,6 +247,47 @@ pub enum ActionRequest {
     ApiKeyRevoke(requests::ApiKeyFingerprint),
 
     Version(requests::Empty),
+
+    /// Ask for the metrics of the store backend
+    StoreMetrics(requests::Empty),
+
+    /// Ask for the cold data files and the savings of moving them to the archive storage
+    StoreTieringReport(requests::StoreTiering),
+
+    /// Moves the cold data files to the archive storage
+    StoreTieringDemote(requests::StoreTiering),
+
+    /// Ask for the estimated storage and egress costs of the sequences
+    CostEstimate(requests::CostEstimate),
+
+    /// Ask for the latency from the data to its commit, by topic
+    IngestMetrics(requests::Empty),
+
+    /// Ask for the statistics of the server (e.g. running and queued queries)
+    ServerStats(requests::Empty),
+
+    /// Ask to exercise the lifecycle of a temporary sequence against the dependencies of
+    /// the server, timing each step
+    SelfTest(requests::Empty),
+
+    /// Ask for the requests rejected because of the credentials or of the resources
+    /// requested, by peer
+    SecurityReport(requests::Empty),
+
+    /// Ask to rebuild in background the state derived from the data of the topics
+    Reindex(requests::Reindex),
+
+    /// Ask for the progress of the reindex jobs
+    ReindexStatus(requests::Empty),
+
+    /// Ask to record the requests received for a time window, to debug client issues
+    ReplayCaptureStart(requests::ReplayCaptureStart),
+
+    /// Ask for the requests recorded by the replay capture
+    ReplayCaptureDump(requests::Empty),
+
+    /// Action handled by a custom handler registered by the embedder.
+    Custom(requests::Custom),
 }
 
 impl std::fmt::Display for ActionRequest {
@@ -113,24 +295,82 @@ impl std::fmt::Display for ActionRequest {
         match self {
             Self::SequenceCreate(_) => write!(f, "SequenceCreate"),
             Self::SequenceDelete(_) => write!(f, "SequenceDelete"),
+            Self::SequenceRestore(_) => write!(f, "SequenceRestore"),
+            Self::SequencePurge(_) => write!(f, "SequencePurge"),
+            Self::SequenceLegalHold(_) => write!(f, "SequenceLegalHold"),
+            Self::SequenceCompactionPolicySet(_) => write!(f, "SequenceCompactionPolicySet"),
+            Self::SequenceCompactionPolicyGet(_) => write!(f, "SequenceCompactionPolicyGet"),
+            Self::SequenceCompact(_) => write!(f, "SequenceCompact"),
+            Self::SequenceImportMcap(_) => write!(f, "SequenceImportMcap"),
+            Self::SequenceExport(_) => write!(f, "SequenceExport"),
+            Self::SequenceImport(_) => write!(f, "SequenceImport"),
+            Self::SequenceReplay(_) => write!(f, "SequenceReplay"),
+            Self::SequenceUpdateMetadata(_) => write!(f, "SequenceUpdateMetadata"),
+            Self::SequenceList(_) => write!(f, "SequenceList"),
+            Self::SequenceAttestation(_) => write!(f, "SequenceAttestation"),
+            Self::ActivityFeed(_) => write!(f, "ActivityFeed"),
             Self::SequenceNotificationCreate(_) => {
                 write!(f, "SequenceNotificationCreate")
             }
             Self::SequenceNotificationList(_) => write!(f, "SequenceNotificationList"),
             Self::SequenceNotificationPurge(_) => write!(f, "SequenceNotificationPurge"),
+            Self::SequenceNotificationStream(_) => write!(f, "SequenceNotificationStream"),
             Self::TopicCreate(_) => write!(f, "TopicCreate"),
             Self::TopicDelete(_) => write!(f, "TopicDelete"),
+            Self::TopicList(_) => write!(f, "TopicList"),
             Self::TopicNotificationCreate(_) => write!(f, "TopicNotificationCreate"),
             Self::TopicNotificationList(_) => write!(f, "TopicNotificationList"),
             Self::TopicNotificationPurge(_) => write!(f, "TopicNotificationPurge"),
+            Self::TopicMediaIndex(_) => write!(f, "TopicMediaIndex"),
+            Self::TopicExport(_) => write!(f, "TopicExport"),
+            Self::TopicCopy(_) => write!(f, "TopicCopy"),
+            Self::TopicLineage(_) => write!(f, "TopicLineage"),
+            Self::TopicSchemaGet(_) => write!(f, "TopicSchemaGet"),
+            Self::TopicSchemaEvolve(_) => write!(f, "TopicSchemaEvolve"),
+            Self::TopicColumnsUpdate(_) => write!(f, "TopicColumnsUpdate"),
+            Self::TopicColumnStats(_) => write!(f, "TopicColumnStats"),
+            Self::TopicRedact(_) => write!(f, "TopicRedact"),
+            Self::TopicRedactionList(_) => write!(f, "TopicRedactionList"),
+            Self::TopicWatermark(_) => write!(f, "TopicWatermark"),
             Self::SessionCreate(_) => write!(f, "SessionCreate"),
             Self::SessionFinalize(_) => write!(f, "SessionFinalize"),
+            Self::SessionAbort(_) => write!(f, "SessionAbort"),
+            Self::SessionShare(_) => write!(f, "SessionShare"),
             Self::SessionDelete(_) => write!(f, "SessionDelete"),
+            Self::SessionList(_) => write!(f, "SessionList"),
+            Self::ConsumerOffsetCommit(_) => write!(f, "ConsumerOffsetCommit"),
+            Self::ConsumerOffsetGet(_) => write!(f, "ConsumerOffsetGet"),
+            Self::ConsumerOffsetDelete(_) => write!(f, "ConsumerOffsetDelete"),
             Self::Query(_) => write!(f, "Query"),
+            Self::QueryFetch(_) => write!(f, "QueryFetch"),
+            Self::SqlQuery(_) => write!(f, "SqlQuery"),
+            Self::ScheduledQueryCreate(_) => write!(f, "ScheduledQueryCreate"),
+            Self::ScheduledQueryList(_) => write!(f, "ScheduledQueryList"),
+            Self::ScheduledQueryRun(_) => write!(f, "ScheduledQueryRun"),
+            Self::ScheduledQueryDelete(_) => write!(f, "ScheduledQueryDelete"),
+            Self::QueryViewCreate(_) => write!(f, "QueryViewCreate"),
+            Self::QueryViewList(_) => write!(f, "QueryViewList"),
+            Self::QueryViewDelete(_) => write!(f, "QueryViewDelete"),
+            Self::ExternalTableRegister(_) => write!(f, "ExternalTableRegister"),
+            Self::ExternalTableList(_) => write!(f, "ExternalTableList"),
+            Self::ExternalTableUnregister(_) => write!(f, "ExternalTableUnregister"),
             Self::ApiKeyCreate(_) => write!(f, "ApiKeyCreate"),
             Self::ApiKeyStatus(_) => write!(f, "ApiKeyStatus"),
             Self::ApiKeyRevoke(_) => write!(f, "ApiKeyRevoke"),
             Self::Version(_) => write!(f, "Version"),
+            Self::StoreMetrics(_) => write!(f, "StoreMetrics"),
+            Self::StoreTieringReport(_) => write!(f, "StoreTieringReport"),
+            Self::StoreTieringDemote(_) => write!(f, "StoreTieringDemote"),
+            Self::CostEstimate(_) => write!(f, "CostEstimate"),
+            Self::IngestMetrics(_) => write!(f, "IngestMetrics"),
+            Self::ServerStats(_) => write!(f, "ServerStats"),
+            Self::SelfTest(_) => write!(f, "SelfTest"),
+            Self::SecurityReport(_) => write!(f, "SecurityReport"),
+            Self::Reindex(_) => write!(f, "Reindex"),
+            Self::ReindexStatus(_) => write!(f, "ReindexStatus"),
+            Self::ReplayCaptureStart(_) => write!(f, "ReplayCaptureStart"),
+            Self::ReplayCaptureDump(_) => write!(f, "ReplayCaptureDump"),
+            Self::Custom(custom) => write!(f, "Custom({})", custom),
         }
     }
 }
@@ -147,29 +387,99 @@ impl ActionRequest {
         match value {
             "sequence_create" => parse_action_req!(SequenceCreate, body),
             "sequence_delete" => parse_action_req!(SequenceDelete, body),
+            "sequence_restore" => parse_action_req!(SequenceRestore, body),
+            "sequence_purge" => parse_action_req!(SequencePurge, body),
+            "sequence_legal_hold" => parse_action_req!(SequenceLegalHold, body),
+            "sequence_compaction_policy_set" => {
+                parse_action_req!(SequenceCompactionPolicySet, body)
+            }
+            "sequence_compaction_policy_get" => {
+                parse_action_req!(SequenceCompactionPolicyGet, body)
+            }
+            "sequence_compact" => parse_action_req!(SequenceCompact, body),
+            "sequence_import_mcap" => parse_action_req!(SequenceImportMcap, body),
+            "sequence_export" => parse_action_req!(SequenceExport, body),
+            "sequence_import" => parse_action_req!(SequenceImport, body),
+            "sequence_replay" => parse_action_req!(SequenceReplay, body),
+            "sequence_update_metadata" => parse_action_req!(SequenceUpdateMetadata, body),
+            "sequence_list" => parse_action_req!(SequenceList, body),
+            "sequence_attestation" => parse_action_req!(SequenceAttestation, body),
+            "activity_feed" => parse_action_req!(ActivityFeed, body),
             "sequence_notification_create" => parse_action_req!(SequenceNotificationCreate, body),
             "sequence_notification_list" => parse_action_req!(SequenceNotificationList, body),
             "sequence_notification_purge" => parse_action_req!(SequenceNotificationPurge, body),
+            "sequence_notification_stream" => {
+                parse_action_req!(SequenceNotificationStream, body)
+            }
 
             "topic_create" => parse_action_req!(TopicCreate, body),
             "topic_delete" => parse_action_req!(TopicDelete, body),
+            "topic_list" => parse_action_req!(TopicList, body),
             "topic_notification_create" => parse_action_req!(TopicNotificationCreate, body),
             "topic_notification_list" => parse_action_req!(TopicNotificationList, body),
             "topic_notification_purge" => parse_action_req!(TopicNotificationPurge, body),
+            "topic_media_index" => parse_action_req!(TopicMediaIndex, body),
+            "topic_export" => parse_action_req!(TopicExport, body),
+            "topic_copy" => parse_action_req!(TopicCopy, body),
+            "topic_lineage" => parse_action_req!(TopicLineage, body),
+            "topic_schema_get" => parse_action_req!(TopicSchemaGet, body),
+            "topic_schema_evolve" => parse_action_req!(TopicSchemaEvolve, body),
+            "topic_columns_update" => parse_action_req!(TopicColumnsUpdate, body),
+            "topic_column_stats" => parse_action_req!(TopicColumnStats, body),
+            "topic_redact" => parse_action_req!(TopicRedact, body),
+            "topic_redaction_list" => parse_action_req!(TopicRedactionList, body),
+            "topic_watermark" => parse_action_req!(TopicWatermark, body),
 
             "session_create" => parse_action_req!(SessionCreate, body),
             "session_finalize" => parse_action_req!(SessionFinalize, body),
+            "session_abort" => parse_action_req!(SessionAbort, body),
+            "session_share" => parse_action_req!(SessionShare, body),
             "session_delete" => parse_action_req!(SessionDelete, body),
+            "session_list" => parse_action_req!(SessionList, body),
+
+            "consumer_offset_commit" => parse_action_req!(ConsumerOffsetCommit, body),
+            "consumer_offset_get" => parse_action_req!(ConsumerOffsetGet, body),
+            "consumer_offset_delete" => parse_action_req!(ConsumerOffsetDelete, body),
 
             "query" => parse_action_req!(Query, body),
+            "query_fetch" => parse_action_req!(QueryFetch, body),
+            "sql_query" => parse_action_req!(SqlQuery, body),
+
+            "scheduled_query_create" => parse_action_req!(ScheduledQueryCreate, body),
+            "scheduled_query_list" => parse_action_req!(ScheduledQueryList, body),
+            "scheduled_query_run" => parse_action_req!(ScheduledQueryRun, body),
+            "scheduled_query_delete" => parse_action_req!(ScheduledQueryDelete, body),
+
+            "query_view_create" => parse_action_req!(QueryViewCreate, body),
+            "query_view_list" => parse_action_req!(QueryViewList, body),
+            "query_view_delete" => parse_action_req!(QueryViewDelete, body),
+
+            "external_table_register" => parse_action_req!(ExternalTableRegister, body),
+            "external_table_list" => parse_action_req!(ExternalTableList, body),
+            "external_table_unregister" => parse_action_req!(ExternalTableUnregister, body),
 
             "api_key_create" => parse_action_req!(ApiKeyCreate, body),
             "api_key_status" => parse_action_req!(ApiKeyStatus, body),
             "api_key_revoke" => parse_action_req!(ApiKeyRevoke, body),
 
             "version" => parse_action_req!(Version, body),
-
-            _ => Err(ActionError::MissingAction(value.to_owned())),
+            "store_metrics" => parse_action_req!(StoreMetrics, body),
+            "store_tiering_report" => parse_action_req!(StoreTieringReport, body),
+            "store_tiering_demote" => parse_action_req!(StoreTieringDemote, body),
+            "cost_estimate" => parse_action_req!(CostEstimate, body),
+            "ingest_metrics" => parse_action_req!(IngestMetrics, body),
+            "server_stats" => parse_action_req!(ServerStats, body),
+            "self_test" => parse_action_req!(SelfTest, body),
+            "security_report" => parse_action_req!(SecurityReport, body),
+            "reindex" => parse_action_req!(Reindex, body),
+            "reindex_status" => parse_action_req!(ReindexStatus, body),
+            "replay_capture_start" => parse_action_req!(ReplayCaptureStart, body),
+            "replay_capture_dump" => parse_action_req!(ReplayCaptureDump, body),
+
+            // Namespaced action types are forwarded to custom handlers
+            _ => requests::Custom::try_new(value, body)
+                .map(ActionRequest::Custom)
+                .ok_or_else(|| ActionError::MissingAction(value.to_owned())),
         }
     }
 }
@@ -179,28 +489,92 @@ impl ActionRequest {
 pub enum ActionResponse {
     SequenceCreate(()),
     SequenceDelete(()),
+    SequenceRestore(()),
+    SequencePurge(()),
+    SequenceLegalHold(()),
+    SequenceCompactionPolicySet(()),
+    SequenceCompactionPolicyGet(responses::CompactionPolicy),
+    SequenceCompact(responses::SequenceCompact),
+    SequenceImportMcap(responses::SequenceImportMcap),
+    SequenceExport(responses::SequenceExport),
+    SequenceImport(responses::SequenceImport),
+    SequenceReplay(responses::SequenceReplay),
+    SequenceUpdateMetadata(()),
+    SequenceList(responses::SequenceList),
+    SequenceAttestation(responses::SessionAttestationList),
+    ActivityFeed(responses::ActivityFeed),
     SequenceNotificationCreate(()),
     SequenceNotificationPurge(()),
     SequenceNotificationList(responses::NotificationList),
+    SequenceNotificationStream(responses::NotificationStream),
 
     TopicCreate(responses::ResourceUuid),
     TopicDelete(()),
+    TopicList(responses::TopicList),
     TopicNotificationCreate(()),
     TopicNotificationPurge(()),
     TopicNotificationList(responses::NotificationList),
+    TopicMediaIndex(crate::JsonMediaIndex),
+    TopicExport(responses::TopicExport),
+    TopicCopy(responses::TopicCopy),
+    TopicLineage(responses::TopicLineage),
+    TopicSchemaGet(responses::TopicSchema),
+    TopicSchemaEvolve(responses::TopicSchemaVersion),
+    TopicColumnsUpdate(()),
+    TopicColumnStats(responses::TopicColumnStats),
+    TopicRedact(responses::Redaction),
+    TopicRedactionList(responses::RedactionList),
+    TopicWatermark(responses::TopicWatermark),
 
     /// Returns the response key associated with the session just created
     SessionCreate(responses::SessionCreate),
     SessionFinalize(()),
+    SessionAbort(()),
+    SessionShare(()),
     SessionDelete(()),
+    SessionList(responses::SessionList),
+
+    ConsumerOffsetCommit(responses::ConsumerOffset),
+    ConsumerOffsetGet(responses::ConsumerOffset),
+    ConsumerOffsetDelete(()),
 
     Query(responses::Query),
+    QueryFetch(responses::Query),
+    SqlQuery(responses::SqlQuery),
+
+    ScheduledQueryCreate(responses::ScheduledQueryItem),
+    ScheduledQueryList(responses::ScheduledQueryList),
+    ScheduledQueryRun(responses::ScheduledQueryRun),
+    ScheduledQueryDelete(()),
+
+    QueryViewCreate(responses::QueryViewItem),
+    QueryViewList(responses::QueryViewList),
+    QueryViewDelete(()),
+
+    ExternalTableRegister(responses::ExternalTableItem),
+    ExternalTableList(responses::ExternalTableList),
+    ExternalTableUnregister(()),
 
     ApiKeyCreate(responses::ApiKeyToken),
     ApiKeyStatus(responses::ApiKeyStatus),
     ApiKeyRevoke(()),
 
     Version(responses::ServerVersion),
+    StoreMetrics(responses::StoreMetrics),
+    StoreTieringReport(responses::StoreTieringReport),
+    StoreTieringDemote(responses::StoreTieringDemote),
+    CostEstimate(responses::CostEstimate),
+    IngestMetrics(responses::IngestMetrics),
+    ServerStats(responses::ServerStats),
+    SelfTest(responses::SelfTest),
+    SecurityReport(responses::SecurityReport),
+    Reindex(responses::ReindexStatus),
+    ReindexStatus(responses::ReindexStatus),
+    ReplayCaptureStart(responses::ReplayCapture),
+    ReplayCaptureDump(responses::ReplayCapture),
+
+    /// Response generated by a custom action handler
+    Custom(serde_json::Value),
 
     // Empty response, no data to send
     Empty,
@@ -220,6 +594,62 @@ impl ActionResponse {
         Self::SequenceDelete(())
     }
 
+    pub fn sequence_restore() -> Self {
+        Self::SequenceRestore(())
+    }
+
+    pub fn sequence_purge() -> Self {
+        Self::SequencePurge(())
+    }
+
+    pub fn sequence_legal_hold() -> Self {
+        Self::SequenceLegalHold(())
+    }
+
+    pub fn sequence_compaction_policy_set() -> Self {
+        Self::SequenceCompactionPolicySet(())
+    }
+
+    pub fn sequence_compaction_policy_get(response: responses::CompactionPolicy) -> Self {
+        Self::SequenceCompactionPolicyGet(response)
+    }
+
+    pub fn sequence_compact(response: responses::SequenceCompact) -> Self {
+        Self::SequenceCompact(response)
+    }
+
+    pub fn sequence_import_mcap(response: responses::SequenceImportMcap) -> Self {
+        Self::SequenceImportMcap(response)
+    }
+
+    pub fn sequence_export(response: responses::SequenceExport) -> Self {
+        Self::SequenceExport(response)
+    }
+
+    pub fn sequence_import(response: responses::SequenceImport) -> Self {
+        Self::SequenceImport(response)
+    }
+
+    pub fn sequence_replay(response: responses::SequenceReplay) -> Self {
+        Self::SequenceReplay(response)
+    }
+
+    pub fn sequence_update_metadata() -> Self {
+        Self::SequenceUpdateMetadata(())
+    }
+
+    pub fn sequence_list(response: responses::SequenceList) -> Self {
+        Self::SequenceList(response)
+    }
+
+    pub fn sequence_attestation(response: responses::SessionAttestationList) -> Self {
+        Self::SequenceAttestation(response)
+    }
+
+    pub fn activity_feed(response: responses::ActivityFeed) -> Self {
+        Self::ActivityFeed(response)
+    }
+
     pub fn sequence_notification_create() -> Self {
         Self::SequenceNotificationCreate(())
     }
@@ -232,6 +662,10 @@ impl ActionResponse {
         Self::SequenceNotificationList(response)
     }
 
+    pub fn sequence_notification_stream(response: responses::NotificationStream) -> Self {
+        Self::SequenceNotificationStream(response)
+    }
+
     pub fn topic_create(response: responses::ResourceUuid) -> Self {
         Self::TopicCreate(response)
     }
@@ -240,6 +674,10 @@ impl ActionResponse {
         Self::TopicDelete(())
     }
 
+    pub fn topic_list(response: responses::TopicList) -> Self {
+        Self::TopicList(response)
+    }
+
     pub fn topic_notification_create() -> Self {
         Self::TopicNotificationCreate(())
     }
@@ -248,10 +686,54 @@ impl ActionResponse {
         Self::TopicNotificationPurge(())
     }
 
+    pub fn topic_columns_update() -> Self {
+        Self::TopicColumnsUpdate(())
+    }
+
+    pub fn topic_column_stats(response: responses::TopicColumnStats) -> Self {
+        Self::TopicColumnStats(response)
+    }
+
+    pub fn topic_redact(response: responses::Redaction) -> Self {
+        Self::TopicRedact(response)
+    }
+
+    pub fn topic_redaction_list(response: responses::RedactionList) -> Self {
+        Self::TopicRedactionList(response)
+    }
+
+    pub fn topic_watermark(response: responses::TopicWatermark) -> Self {
+        Self::TopicWatermark(response)
+    }
+
     pub fn topic_notification_list(response: responses::NotificationList) -> Self {
         Self::TopicNotificationList(response)
     }
 
+    pub fn topic_media_index(response: crate::JsonMediaIndex) -> Self {
+        Self::TopicMediaIndex(response)
+    }
+
+    pub fn topic_export(response: responses::TopicExport) -> Self {
+        Self::TopicExport(response)
+    }
+
+    pub fn topic_copy(locator: String, uuid: String) -> Self {
+        Self::TopicCopy(responses::TopicCopy { locator, uuid })
+    }
+
+    pub fn topic_lineage(response: responses::TopicLineage) -> Self {
+        Self::TopicLineage(response)
+    }
+
+    pub fn topic_schema_get(response: responses::TopicSchema) -> Self {
+        Self::TopicSchemaGet(response)
+    }
+
+    pub fn topic_schema_evolve(version: u32) -> Self {
+        Self::TopicSchemaEvolve(responses::TopicSchemaVersion { version })
+    }
+
     pub fn session_create(
         session_locator: core::types::SessionLocator,
         session_uuid: core::types::Uuid,
@@ -266,10 +748,78 @@ impl ActionResponse {
         Self::SessionFinalize(())
     }
 
+    pub fn session_abort() -> Self {
+        Self::SessionAbort(())
+    }
+
+    pub fn session_share() -> Self {
+        Self::SessionShare(())
+    }
+
     pub fn session_delete() -> Self {
         Self::SessionDelete(())
     }
 
+    pub fn session_list(response: responses::SessionList) -> Self {
+        Self::SessionList(response)
+    }
+
+    pub fn consumer_offset_commit(response: responses::ConsumerOffset) -> Self {
+        Self::ConsumerOffsetCommit(response)
+    }
+
+    pub fn consumer_offset_get(response: responses::ConsumerOffset) -> Self {
+        Self::ConsumerOffsetGet(response)
+    }
+
+    pub fn consumer_offset_delete() -> Self {
+        Self::ConsumerOffsetDelete(())
+    }
+
+    pub fn scheduled_query_create(response: responses::ScheduledQueryItem) -> Self {
+        Self::ScheduledQueryCreate(response)
+    }
+
+    pub fn scheduled_query_list(response: responses::ScheduledQueryList) -> Self {
+        Self::ScheduledQueryList(response)
+    }
+
+    pub fn scheduled_query_run(response: responses::ScheduledQueryRun) -> Self {
+        Self::ScheduledQueryRun(response)
+    }
+
+    pub fn scheduled_query_delete() -> Self {
+        Self::ScheduledQueryDelete(())
+    }
+
+    pub fn sql_query(response: responses::SqlQuery) -> Self {
+        Self::SqlQuery(response)
+    }
+
+    pub fn query_view_create(response: responses::QueryViewItem) -> Self {
+        Self::QueryViewCreate(response)
+    }
+
+    pub fn query_view_list(response: responses::QueryViewList) -> Self {
+        Self::QueryViewList(response)
+    }
+
+    pub fn query_view_delete() -> Self {
+        Self::QueryViewDelete(())
+    }
+
+    pub fn external_table_register(response: responses::ExternalTableItem) -> Self {
+        Self::ExternalTableRegister(response)
+    }
+
+    pub fn external_table_list(response: responses::ExternalTableList) -> Self {
+        Self::ExternalTableList(response)
+    }
+
+    pub fn external_table_unregister() -> Self {
+        Self::ExternalTableUnregister(())
+    }
+
     pub fn api_key_create(response: responses::ApiKeyToken) -> Self {
         Self::ApiKeyCreate(response)
     }
@@ -281,6 +831,58 @@ impl ActionResponse {
     pub fn api_key_revoke() -> Self {
         Self::ApiKeyRevoke(())
     }
+
+    pub fn store_metrics(response: responses::StoreMetrics) -> Self {
+        Self::StoreMetrics(response)
+    }
+
+    pub fn store_tiering_report(response: responses::StoreTieringReport) -> Self {
+        Self::StoreTieringReport(response)
+    }
+
+    pub fn store_tiering_demote(response: responses::StoreTieringDemote) -> Self {
+        Self::StoreTieringDemote(response)
+    }
+
+    pub fn cost_estimate(response: responses::CostEstimate) -> Self {
+        Self::CostEstimate(response)
+    }
+
+    pub fn ingest_metrics(response: responses::IngestMetrics) -> Self {
+        Self::IngestMetrics(response)
+    }
+
+    pub fn server_stats(response: responses::ServerStats) -> Self {
+        Self::ServerStats(response)
+    }
+
+    pub fn self_test(response: responses::SelfTest) -> Self {
+        Self::SelfTest(response)
+    }
+
+    pub fn security_report(response: responses::SecurityReport) -> Self {
+        Self::SecurityReport(response)
+    }
+
+    pub fn reindex(response: responses::ReindexStatus) -> Self {
+        Self::Reindex(response)
+    }
+
+    pub fn reindex_status(response: responses::ReindexStatus) -> Self {
+        Self::ReindexStatus(response)
+    }
+
+    pub fn replay_capture_start(response: responses::ReplayCapture) -> Self {
+        Self::ReplayCaptureStart(response)
+    }
+
+    pub fn replay_capture_dump(response: responses::ReplayCapture) -> Self {
+        Self::ReplayCaptureDump(response)
+    }
+
+    pub fn custom(response: serde_json::Value) -> Self {
+        Self::Custom(response)
+    }
 }
 
 #[cfg(test)]
@@ -320,6 +922,12 @@ mod tests {
             assert_eq!(action.session_uuid, "some_uuid");
             assert_eq!(action.serialization_format, Format::Default);
             assert_eq!(action.ontology_tag, "my_sensor");
+            assert!(
+                action
+                    .arrow_schema()
+                    .expect("Unable to get `arrow_schema`")
+                    .is_none()
+            );
             let raw_json = action
                 .user_metadata()
                 .expect("Unable to get `user_metadata`");
@@ -333,4 +941,23 @@ mod tests {
             panic!("Wrong action request, expecting `topic_create`")
         }
     }
+
+    #[test]
+    fn request_custom() {
+        let action = ActionRequest::try_new("acme.reindex", br#"{"force":true}"#)
+            .expect("Problem parsing custom action request");
+
+        if let ActionRequest::Custom(custom) = action {
+            assert_eq!(custom.namespace, "acme");
+            assert_eq!(custom.name, "reindex");
+            assert_eq!(custom.body, br#"{"force":true}"#);
+        } else {
+            panic!("Wrong action request, expecting a custom action")
+        }
+
+        assert!(ActionRequest::try_new("not_an_action", b"{}").is_err());
+        assert!(ActionRequest::try_new(".reindex", b"{}").is_err());
+        assert!(ActionRequest::try_new("acme.", b"{}").is_err());
+        assert!(ActionRequest::try_new("Acme.reindex", b"{}").is_err());
+    }
 }
@@ -1,6 +1,9 @@
 use super::ActionError;
-use crate::Format;
+use crate::{DedupPolicy, Format};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use mosaicod_core as core;
 use serde::Deserialize;
+use std::collections::HashMap;
 
 #[derive(Deserialize, Debug)]
 pub struct Empty {}
@@ -22,6 +25,29 @@ impl SequenceCreate {
     }
 }
 
+/// Request used to list the sequences, one page at a time.
+#[derive(Deserialize, Debug)]
+pub struct SequenceList {
+    /// If set, only the sequences whose locator starts with the prefix are listed
+    pub prefix: Option<String>,
+    /// Maximum number of sequences returned
+    pub limit: Option<usize>,
+    /// Cursor returned along with the previous page
+    pub cursor: Option<String>,
+}
+
+/// Request used to read the activity feed of a sequence, one page at a time.
+#[derive(Deserialize, Debug)]
+pub struct ActivityFeed {
+    pub locator: String,
+    /// If set, only the entries created since the timestamp are returned
+    pub since_ns: Option<i64>,
+    /// Maximum number of entries returned
+    pub limit: Option<usize>,
+    /// Cursor returned along with the previous page
+    pub cursor: Option<String>,
+}
+
 // ////////////////////////////////////////////////////////////////////////////
 // Topic
 // ////////////////////////////////////////////////////////////////////////////
@@ -33,6 +59,17 @@ pub struct TopicCreate {
     pub session_uuid: String,
     pub serialization_format: Format,
     pub ontology_tag: String,
+    /// Column by which the uploaded data must be sorted
+    pub sort_key: Option<String>,
+    /// Policy used to remove duplicated rows when reading the topic data
+    pub dedup_policy: Option<DedupPolicy>,
+    /// Column identifying the rows of the topic, making it an upsert topic
+    pub primary_key: Option<String>,
+    /// Version the upserted rows are based on, the upload is rejected if the existing
+    /// upsert topic changed in the meantime
+    pub base_version: Option<u64>,
+    /// Schema the uploaded data must match, encoded as a base64 Arrow IPC message
+    arrow_schema: Option<String>,
 
     user_metadata: serde_json::Value,
 }
@@ -41,6 +78,127 @@ impl TopicCreate {
     pub fn user_metadata(&self) -> Result<String, ActionError> {
         Ok(serde_json::to_string(&self.user_metadata)?)
     }
+
+    /// Returns the Arrow IPC message encoding the schema to register, if any.
+    pub fn arrow_schema(&self) -> Result<Option<Vec<u8>>, ActionError> {
+        self.arrow_schema
+            .as_deref()
+            .map(decode_arrow_schema)
+            .transpose()
+    }
+}
+
+/// Request used to list the topics of a sequence, one page at a time.
+#[derive(Deserialize, Debug)]
+pub struct TopicList {
+    pub locator: String,
+    /// Maximum number of topics returned
+    pub limit: Option<usize>,
+    /// Cursor returned along with the previous page
+    pub cursor: Option<String>,
+}
+
+/// Request used to register a new version of the schema of a topic.
+#[derive(Deserialize, Debug)]
+pub struct TopicSchemaEvolve {
+    pub locator: String,
+    /// New schema, encoded as a base64 Arrow IPC message
+    arrow_schema: String,
+}
+
+impl TopicSchemaEvolve {
+    /// Returns the Arrow IPC message encoding the new schema.
+    pub fn arrow_schema(&self) -> Result<Vec<u8>, ActionError> {
+        decode_arrow_schema(&self.arrow_schema)
+    }
+}
+
+fn decode_arrow_schema(encoded: &str) -> Result<Vec<u8>, ActionError> {
+    BASE64
+        .decode(encoded)
+        .map_err(|e| ActionError::BadEncoding(format!("arrow_schema: {}", e)))
+}
+
+/// Request used to retrieve the time index of a media topic, optionally limited
+/// to the segments overlapping a timestamp range.
+#[derive(Deserialize, Debug)]
+pub struct TopicMediaIndex {
+    pub locator: String,
+    pub timestamp_ns_start: Option<i64>,
+    pub timestamp_ns_end: Option<i64>,
+}
+
+/// Request used to export the data of a topic as a Parquet file, optionally limited to a
+/// timestamp range.
+///
+/// If `prefix` is set the file is written in the store under the export prefix, otherwise a
+/// ticket streaming the file with DoGet is returned.
+#[derive(Deserialize, Debug)]
+pub struct TopicExport {
+    pub locator: String,
+    pub timestamp_ns_start: Option<i64>,
+    pub timestamp_ns_end: Option<i64>,
+    /// Maximum number of rows of the row groups of the file
+    pub row_group_size: Option<usize>,
+    /// If true (the default) the metadata of the topic is written in the key/value metadata
+    /// of the file
+    pub include_metadata: Option<bool>,
+    pub prefix: Option<String>,
+}
+
+/// Request used to copy the topic `from` and its data into the sequence `to_sequence`.
+#[derive(Deserialize, Debug)]
+pub struct TopicCopy {
+    pub from: String,
+    pub to_sequence: String,
+}
+
+/// Description of a single column of a topic schema.
+#[derive(Deserialize, Debug)]
+pub struct ColumnDescription {
+    pub description: Option<String>,
+    pub unit: Option<String>,
+}
+
+/// Request used to set the description and unit of the columns of a topic.
+#[derive(Deserialize, Debug)]
+pub struct TopicColumnsUpdate {
+    pub locator: String,
+    pub columns: std::collections::BTreeMap<String, ColumnDescription>,
+}
+
+impl TopicColumnsUpdate {
+    pub fn columns(self) -> Vec<core::types::ColumnDescription> {
+        self.columns
+            .into_iter()
+            .map(|(name, column)| core::types::ColumnDescription {
+                name,
+                description: column.description,
+                unit: column.unit,
+            })
+            .collect()
+    }
+}
+
+/// Request used to retrieve the statistics of the columns of a topic, all the top-level
+/// columns are selected if none is given.
+#[derive(Deserialize, Debug)]
+pub struct TopicColumnStats {
+    pub locator: String,
+    #[serde(default)]
+    pub columns: Vec<String>,
+}
+
+/// Request used to redact the values of some columns of a topic in a timestamp range.
+#[derive(Deserialize, Debug)]
+pub struct TopicRedact {
+    pub locator: String,
+    pub timestamp_ns_start: Option<i64>,
+    pub timestamp_ns_end: Option<i64>,
+    pub columns: Vec<String>,
+    /// Either `null` or `hash`
+    pub mode: String,
+    pub reason: Option<String>,
 }
 
 // ////////////////////////////////////////////////////////////////////////////
@@ -53,6 +211,94 @@ pub struct ResourceLocator {
     pub locator: String,
 }
 
+/// Request used to set or release the legal hold of a sequence.
+#[derive(Deserialize, Debug)]
+pub struct SequenceLegalHold {
+    pub locator: String,
+    pub legal_hold: bool,
+}
+
+/// Request used to set the compaction policy of a sequence, settings not set follow the
+/// server configuration.
+#[derive(Deserialize, Debug)]
+pub struct SequenceCompactionPolicy {
+    pub locator: String,
+    pub target_object_size: Option<u64>,
+    pub interval: Option<u64>,
+    pub min_chunks: Option<u64>,
+    pub cold_after: Option<u64>,
+}
+
+impl SequenceCompactionPolicy {
+    pub fn policy(&self) -> core::types::CompactionPolicy {
+        core::types::CompactionPolicy {
+            target_object_size: self.target_object_size,
+            interval: self.interval,
+            min_chunks: self.min_chunks,
+            cold_after: self.cold_after,
+        }
+    }
+}
+
+/// Request used to import the MCAP recording stored at `path` into the sequence `locator`.
+#[derive(Deserialize, Debug)]
+pub struct SequenceImportMcap {
+    pub locator: String,
+    pub path: String,
+    /// If set only the channels publishing on these topics are imported
+    pub channels: Option<Vec<String>>,
+    /// User metadata of the sequence, used if the sequence is created by the import
+    pub user_metadata: Option<serde_json::Value>,
+}
+
+/// Request used to write a sequence as a portable archive in the store under `prefix`.
+#[derive(Deserialize, Debug)]
+pub struct SequenceExport {
+    pub locator: String,
+    pub prefix: String,
+}
+
+/// Request used to recreate a sequence from the archive stored at `path`.
+#[derive(Deserialize, Debug)]
+pub struct SequenceImport {
+    pub path: String,
+    /// Locator of the new sequence, defaults to the locator of the archived sequence
+    pub locator: Option<String>,
+    /// If true the sequence and its topics keep the UUIDs of the archive
+    #[serde(default)]
+    pub preserve_uuids: bool,
+}
+
+/// Request used to replay some topics of a sequence as a single stream, optionally limited
+/// to a timestamp range.
+#[derive(Deserialize, Debug)]
+pub struct SequenceReplay {
+    pub locator: String,
+    /// Names of the replayed topics, relative to the sequence, all the topics with published
+    /// data are replayed if not set
+    pub topics: Option<Vec<String>>,
+    pub timestamp_ns_start: Option<i64>,
+    pub timestamp_ns_end: Option<i64>,
+}
+
+/// Request used to update the user metadata of a sequence.
+#[derive(Deserialize, Debug)]
+pub struct SequenceUpdateMetadata {
+    pub locator: String,
+    /// If `true` the top-level keys are merged into the current metadata, otherwise the
+    /// metadata is replaced
+    #[serde(default)]
+    pub merge: bool,
+
+    user_metadata: serde_json::Value,
+}
+
+impl SequenceUpdateMetadata {
+    pub fn user_metadata(&self) -> Result<String, ActionError> {
+        Ok(serde_json::to_string(&self.user_metadata)?)
+    }
+}
+
 // ////////////////////////////////////////////////////////////////////////////
 // Session
 // ////////////////////////////////////////////////////////////////////////////
@@ -63,6 +309,56 @@ pub struct SessionUuid {
     pub session_uuid: String,
 }
 
+/// State of the sessions listed by [`SessionList`].
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionState {
+    /// Sessions not finalized yet
+    Open,
+    /// Finalized sessions
+    Finalized,
+}
+
+/// Request used to list the sessions of a sequence, one page at a time.
+#[derive(Deserialize, Debug)]
+pub struct SessionList {
+    pub locator: String,
+    /// If set, only the sessions in the given state are listed
+    pub state: Option<SessionState>,
+    /// Maximum number of sessions returned
+    pub limit: Option<usize>,
+    /// Cursor returned along with the previous page
+    pub cursor: Option<String>,
+}
+
+/// Request used to share a session with another API key.
+#[derive(Deserialize, Debug)]
+pub struct SessionShare {
+    pub session_uuid: String,
+    pub api_key_fingerprint: String,
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Consumer Offset
+// ////////////////////////////////////////////////////////////////////////////
+
+/// Request used to commit the offset processed by a named consumer. The `cursor` of the
+/// activity feed is committed for a sequence, the `watermark_ns` for a topic.
+#[derive(Deserialize, Debug)]
+pub struct ConsumerOffsetCommit {
+    pub consumer: String,
+    pub locator: String,
+    pub cursor: Option<String>,
+    pub watermark_ns: Option<i64>,
+}
+
+/// Request used to locate the offset of a named consumer on a sequence or topic.
+#[derive(Deserialize, Debug)]
+pub struct ConsumerOffset {
+    pub consumer: String,
+    pub locator: String,
+}
+
 // ////////////////////////////////////////////////////////////////////////////
 // Notifications
 // ////////////////////////////////////////////////////////////////////////////
@@ -72,7 +368,94 @@ pub struct SessionUuid {
 pub struct NotificationCreate {
     pub locator: String,
     pub notification_type: String,
-    pub msg: String,
+    /// Severity of the notification, `info`, `warning` or `error`, if missing it depends on
+    /// the notification type
+    pub severity: Option<String>,
+    /// Machine readable code of the notification, if missing the notification carries
+    /// only the free text `msg`
+    pub code: Option<String>,
+    /// Values describing the event, referenced by the localized texts of the code
+    #[serde(default)]
+    pub params: std::collections::BTreeMap<String, String>,
+    /// Rendered text of the notification
+    pub msg: Option<String>,
+}
+
+/// Request used to list the notifications of a sequence or topic, one page at a time.
+#[derive(Deserialize, Debug)]
+pub struct NotificationList {
+    pub locator: String,
+    /// If set, only the notifications at least as severe are returned
+    pub min_severity: Option<String>,
+    /// If set, only the notifications created since the timestamp are returned
+    pub since_ns: Option<i64>,
+    /// If set, only the notifications created until the timestamp are returned
+    pub until_ns: Option<i64>,
+    /// Maximum number of notifications returned
+    pub limit: Option<usize>,
+    /// Cursor returned along with the previous page
+    pub cursor: Option<String>,
+}
+
+impl NotificationList {
+    /// Returns the criteria selecting the listed notifications.
+    pub fn filter(&self) -> Result<core::types::NotificationFilter, core::Error> {
+        let min_severity = match &self.min_severity {
+            Some(severity) => severity.parse()?,
+            None => core::types::NotificationSeverity::Info,
+        };
+        let created = core::types::TimestampRange::between(
+            self.since_ns
+                .map_or_else(core::types::Timestamp::unbounded_neg, Into::into),
+            self.until_ns
+                .map_or_else(core::types::Timestamp::unbounded_pos, Into::into),
+        );
+        Ok(core::types::NotificationFilter {
+            min_severity,
+            created,
+        })
+    }
+}
+
+/// Request used to stream the notifications of a sequence and of its topics as they are
+/// created.
+#[derive(Deserialize, Debug)]
+pub struct NotificationStream {
+    pub locator: String,
+    /// If set, only the notifications at least as severe are streamed
+    pub min_severity: Option<String>,
+}
+
+impl NotificationStream {
+    /// Returns the minimum severity of the streamed notifications.
+    pub fn min_severity(&self) -> Result<core::types::NotificationSeverity, core::Error> {
+        match &self.min_severity {
+            Some(severity) => severity.parse(),
+            None => Ok(core::types::NotificationSeverity::Info),
+        }
+    }
+}
+
+impl NotificationCreate {
+    /// Returns the severity of the notification, `None` if it was not requested.
+    pub fn severity(&self) -> Result<Option<core::types::NotificationSeverity>, core::Error> {
+        self.severity.as_deref().map(str::parse).transpose()
+    }
+
+    /// Returns the structured payload of the notification.
+    pub fn payload(&self) -> Result<core::types::NotificationPayload, core::Error> {
+        match &self.code {
+            Some(code) => {
+                core::types::NotificationPayload::try_new(code.clone(), self.params.clone())
+            }
+            None if self.msg.is_some() && self.params.is_empty() => {
+                Ok(core::types::NotificationPayload::message())
+            }
+            None => Err(core::Error::bad_request(
+                "notifications without `code` need a `msg` and no `params`".to_owned(),
+            )),
+        }
+    }
 }
 
 // ////////////////////////////////////////////////////////////////////////////
@@ -81,11 +464,105 @@ pub struct NotificationCreate {
 
 #[derive(Deserialize, Debug)]
 pub struct Query {
+    /// If set, only the first rows are returned along with a handle to fetch the others
+    pub max_rows: Option<usize>,
     #[serde(flatten)]
     /// Query filter used to find matches in the system
     pub query: serde_json::Value,
 }
 
+/// Request used to fetch the next rows of a paginated query.
+#[derive(Deserialize, Debug)]
+pub struct QueryFetch {
+    pub handle: String,
+    pub max_rows: usize,
+}
+
+/// Request used to run a SQL statement over the data of some topics.
+#[derive(Deserialize, Debug)]
+pub struct SqlQuery {
+    /// Read-only SQL statement
+    pub statement: String,
+    /// Locators of the topics read by the statement, indexed by the name of their table
+    pub tables: HashMap<String, String>,
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Scheduled Query
+// ////////////////////////////////////////////////////////////////////////////
+
+#[derive(Deserialize, Debug)]
+pub struct ScheduledQueryCreate {
+    pub name: String,
+    /// Cron expression
+    pub schedule: String,
+    /// Query filter evaluated at every run
+    pub query: serde_json::Value,
+    /// Condition on the number of matching topics (e.g. `{"$gt": 0}`), satisfied as soon
+    /// as a topic matches if missing
+    pub condition: Option<HashMap<String, u64>>,
+}
+
+impl ScheduledQueryCreate {
+    pub fn condition(&self) -> Result<core::types::MatchCondition, core::Error> {
+        let Some(condition) = &self.condition else {
+            return Ok(core::types::MatchCondition::default());
+        };
+
+        let mut ops = condition.iter();
+        match (ops.next(), ops.next()) {
+            (Some((op, value)), None) => core::types::MatchCondition::try_new(op, *value)
+                .map_err(|e| core::Error::bad_request(e.to_string())),
+            _ => Err(core::Error::bad_request(
+                "condition requires a single operator".to_owned(),
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ScheduledQueryName {
+    pub name: String,
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Query View
+// ////////////////////////////////////////////////////////////////////////////
+
+#[derive(Deserialize, Debug)]
+pub struct QueryViewCreate {
+    /// Locator of the sequence defining the view
+    pub locator: String,
+    pub name: String,
+    /// Query filter searched in the sequence
+    pub query: serde_json::Value,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct QueryViewName {
+    /// Locator of the sequence defining the view
+    pub locator: String,
+    pub name: String,
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// External Table
+// ////////////////////////////////////////////////////////////////////////////
+
+#[derive(Deserialize, Debug)]
+pub struct ExternalTableRegister {
+    pub name: String,
+    /// Path of the file in the store, or URL of the file
+    pub location: String,
+    /// Either `parquet` or `csv`
+    pub format: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ExternalTableName {
+    pub name: String,
+}
+
 // ////////////////////////////////////////////////////////////////////////////
 // Api Key
 // ////////////////////////////////////////////////////////////////////////////
@@ -95,9 +572,113 @@ pub struct ApiKeyCreate {
     pub permissions: String,
     pub expires_at_ns: Option<i64>,
     pub description: String,
+    /// Sequence searched by the queries of the key not filtering sequences
+    pub default_sequence: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct ApiKeyFingerprint {
     pub api_key_fingerprint: String,
 }
+
+// ////////////////////////////////////////////////////////////////////////////
+// Reindex
+// ////////////////////////////////////////////////////////////////////////////
+
+/// Request used to rebuild the state derived from the data of the topics.
+#[derive(Deserialize, Debug)]
+pub struct Reindex {
+    /// Derived state to rebuild (`column_stats`, `chunk_stats`, `data_info` or `all`)
+    pub scope: String,
+    /// Maximum number of topics processed in a batch
+    pub batch_size: Option<usize>,
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tiering
+// ////////////////////////////////////////////////////////////////////////////
+
+/// Request used to report or demote the cold data files.
+#[derive(Deserialize, Debug)]
+pub struct StoreTiering {
+    /// Time (in seconds) since the last read after which a data file is cold, defaults to
+    /// the server configuration
+    pub cold_after: Option<u64>,
+    /// Maximum number of data files listed or demoted
+    pub limit: Option<usize>,
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Cost
+// ////////////////////////////////////////////////////////////////////////////
+
+/// Request used to estimate the storage and egress costs of the sequences.
+#[derive(Deserialize, Debug)]
+pub struct CostEstimate {
+    /// Prefix of the locators of the sequences estimated, all the sequences if empty
+    #[serde(default)]
+    pub scope: String,
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Replay Capture
+// ////////////////////////////////////////////////////////////////////////////
+
+/// Request used to start recording the requests received by the server.
+#[derive(Deserialize, Debug)]
+pub struct ReplayCaptureStart {
+    /// Duration (in seconds) of the capture window
+    pub duration_secs: u64,
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Custom
+// ////////////////////////////////////////////////////////////////////////////
+
+/// Request addressed to an action registered outside the core server.
+///
+/// Custom action types are namespaced as `<namespace>.<name>`, the body is
+/// kept as raw bytes and forwarded untouched to the registered handler.
+#[derive(Debug)]
+pub struct Custom {
+    pub namespace: String,
+    pub name: String,
+    pub body: Vec<u8>,
+}
+
+impl Custom {
+    /// Separator used between namespace and action name
+    pub const SEPARATOR: char = '.';
+
+    /// Tries to build a custom request from a namespaced action type.
+    ///
+    /// Returns `None` if the action type is not in the `<namespace>.<name>` form.
+    pub fn try_new(action_type: &str, body: &[u8]) -> Option<Self> {
+        let (namespace, name) = action_type.split_once(Self::SEPARATOR)?;
+
+        if !is_valid_identifier(namespace) || !is_valid_identifier(name) {
+            return None;
+        }
+
+        Some(Self {
+            namespace: namespace.to_owned(),
+            name: name.to_owned(),
+            body: body.to_vec(),
+        })
+    }
+}
+
+impl std::fmt::Display for Custom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}{}", self.namespace, Self::SEPARATOR, self.name)
+    }
+}
+
+/// Returns true if the string can be used as namespace or name of a custom action.
+///
+/// Only lowercase ascii letters, digits and underscores are allowed.
+pub fn is_valid_identifier(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
@@ -1,7 +1,9 @@
 //! This module defines the formatting structure for
 //! responses.
 
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
 use mosaicod_core::types::{self, Locator, auth};
+use mosaicod_query as query;
 use semver;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
@@ -13,6 +15,30 @@ pub struct ResourceUuid {
     pub uuid: String,
 }
 
+/// Response message containing the schema registered for a topic, encoded as a base64
+/// Arrow IPC message.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TopicSchema {
+    pub arrow_schema: Option<String>,
+    /// Version of the returned schema, starting from 1
+    pub version: Option<u32>,
+}
+
+impl TopicSchema {
+    pub fn new(arrow_schema: Option<Vec<u8>>, version: Option<u32>) -> Self {
+        Self {
+            arrow_schema: arrow_schema.map(|buffer| BASE64.encode(buffer)),
+            version,
+        }
+    }
+}
+
+/// Response message containing the version of the schema registered by a schema evolution.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TopicSchemaVersion {
+    pub version: u32,
+}
+
 impl From<types::Uuid> for ResourceUuid {
     fn from(value: types::Uuid) -> Self {
         Self {
@@ -21,6 +47,125 @@ impl From<types::Uuid> for ResourceUuid {
     }
 }
 
+// ########
+// Sequence
+// ########
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SequenceList {
+    pub locators: Vec<String>,
+    /// Cursor used to fetch the next page, omitted from the output once all the
+    /// sequences are returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ActivityItem {
+    pub timestamp_ns: i64,
+    pub event: String,
+    /// Locator of the sequence or topic, or uuid of the session, the event refers to
+    pub subject: String,
+    /// Watermark reached by the topic, only set for `topic_watermark` events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub watermark_ns: Option<i64>,
+    /// Notification details, omitted from the output for lifecycle events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notification: Option<ActivityNotification>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ActivityNotification {
+    pub notification_type: String,
+    pub severity: String,
+    pub code: String,
+    pub params: std::collections::BTreeMap<String, String>,
+    /// Rendered text of the notification, empty if not available
+    pub msg: String,
+}
+
+impl From<types::Activity> for ActivityItem {
+    fn from(value: types::Activity) -> Self {
+        let event = value.event.name().to_owned();
+        let watermark_ns = match &value.event {
+            types::ActivityEvent::TopicWatermark { watermark } => Some(watermark.as_i64()),
+            _ => None,
+        };
+        let notification = match value.event {
+            types::ActivityEvent::Notification {
+                notification_type,
+                severity,
+                payload,
+                msg,
+            } => Some(ActivityNotification {
+                notification_type: notification_type.to_string(),
+                severity: severity.to_string(),
+                msg: msg.or_else(|| payload.render()).unwrap_or_default(),
+                code: payload.code,
+                params: payload.params,
+            }),
+            _ => None,
+        };
+
+        Self {
+            timestamp_ns: value.timestamp.as_i64(),
+            event,
+            subject: value.subject,
+            watermark_ns,
+            notification,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ActivityFeed {
+    pub activities: Vec<ActivityItem>,
+    /// Cursor used to fetch the next page, omitted from the output once all the
+    /// entries are returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// Cursor following the last returned entry, also returned with the last page so that
+    /// consumers can resume the feed from it.
+    pub end_cursor: String,
+}
+
+// ########
+// Topic
+// ########
+
+#[derive(Serialize, Debug)]
+pub struct TopicListItem {
+    pub locator: String,
+    pub serialization_format: String,
+    pub ontology_tag: String,
+    pub created_at_ns: i64,
+    /// Omitted from the output until the topic is finalized
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at_ns: Option<i64>,
+}
+
+impl<M> From<types::TopicMetadata<M>> for TopicListItem {
+    fn from(value: types::TopicMetadata<M>) -> Self {
+        let ontology = value.ontology_metadata.properties;
+        Self {
+            locator: value.properties.resource_locator.to_string(),
+            serialization_format: ontology.serialization_format.to_string(),
+            ontology_tag: ontology.ontology_tag,
+            created_at_ns: value.properties.created_at.as_i64(),
+            completed_at_ns: value.properties.completed_at.map(Into::into),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct TopicList {
+    pub topics: Vec<TopicListItem>,
+    /// Cursor used to fetch the next page, omitted from the output once all the topics
+    /// are returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
 // ########
 // Session
 // ########
@@ -31,6 +176,78 @@ pub struct SessionCreate {
     pub locator: String,
 }
 
+#[derive(Serialize, Debug)]
+pub struct SessionListItem {
+    pub uuid: String,
+    pub locator: String,
+    pub created_at_ns: i64,
+    /// Omitted from the output until the session is finalized
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at_ns: Option<i64>,
+    /// Finalized sessions are locked, no data can be added to them
+    pub locked: bool,
+    pub topic_count: usize,
+}
+
+impl From<types::SessionSummary> for SessionListItem {
+    fn from(value: types::SessionSummary) -> Self {
+        Self {
+            uuid: value.uuid.to_string(),
+            locator: value.locator.to_string(),
+            created_at_ns: value.created_at.as_i64(),
+            completed_at_ns: value.completed_at.map(Into::into),
+            locked: value.completed_at.is_some(),
+            topic_count: value.topic_count,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct SessionList {
+    pub sessions: Vec<SessionListItem>,
+    /// Cursor used to fetch the next page, omitted from the output once all the sessions
+    /// are returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+// ########
+// Consumer Offset
+// ########
+
+#[derive(Serialize, Debug)]
+pub struct ConsumerOffset {
+    pub consumer: String,
+    pub locator: String,
+    /// Cursor of the activity feed committed for a sequence
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// Watermark committed for a topic
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub watermark_ns: Option<i64>,
+    /// Time of the commit, omitted from the output if no offset was committed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub committed_at_ns: Option<i64>,
+}
+
+impl ConsumerOffset {
+    pub fn new(consumer: String, locator: String, offset: Option<types::ConsumerOffset>) -> Self {
+        let (cursor, watermark_ns) = match offset.map(|offset| offset.position) {
+            Some(types::ConsumerPosition::Activity(cursor)) => (Some(cursor.to_string()), None),
+            Some(types::ConsumerPosition::Watermark(watermark)) => (None, Some(watermark.as_i64())),
+            None => (None, None),
+        };
+
+        Self {
+            consumer,
+            locator,
+            cursor,
+            watermark_ns,
+            committed_at_ns: offset.map(|offset| offset.committed_at.as_i64()),
+        }
+    }
+}
+
 // ########
 // Notifications
 // ########
@@ -39,16 +256,25 @@ pub struct SessionCreate {
 pub struct ResponseNotificationItem {
     pub name: String,
     pub notification_type: String,
+    pub severity: String,
+    /// Machine readable code of the notification
+    pub code: String,
+    pub params: std::collections::BTreeMap<String, String>,
+    /// Rendered text of the notification, empty if not available
     pub msg: String,
     pub created_datetime: String,
 }
 
 impl<L: Locator> From<types::Notification<L>> for ResponseNotificationItem {
     fn from(value: types::Notification<L>) -> Self {
+        let msg = value.msg.or_else(|| value.payload.render());
         Self {
             name: value.target.to_string(),
             notification_type: value.notification_type.to_string(),
-            msg: value.msg.unwrap_or_default(),
+            severity: value.severity.to_string(),
+            code: value.payload.code,
+            params: value.payload.params,
+            msg: msg.unwrap_or_default(),
             created_datetime: value.created_at.to_string(),
         }
     }
@@ -57,12 +283,342 @@ impl<L: Locator> From<types::Notification<L>> for ResponseNotificationItem {
 #[derive(Serialize, Debug)]
 pub struct NotificationList {
     pub notifications: Vec<ResponseNotificationItem>,
+    /// Cursor used to fetch the next page, omitted from the output once all the
+    /// notifications are returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+impl<L: Locator> From<types::NotificationPage<L>> for NotificationList {
+    fn from(value: types::NotificationPage<L>) -> Self {
+        Self {
+            notifications: value.notifications.into_iter().map(Into::into).collect(),
+            cursor: value.cursor.map(|cursor| cursor.to_string()),
+        }
+    }
+}
+
+/// Response message containing the ticket used to stream the notifications of a sequence
+/// with DoGet.
+#[derive(Serialize, Debug)]
+pub struct NotificationStream {
+    /// Ticket encoded in base64
+    pub ticket: String,
+}
+
+impl NotificationStream {
+    pub fn new(ticket: Vec<u8>) -> Self {
+        Self {
+            ticket: BASE64.encode(ticket),
+        }
+    }
+}
+
+/// Response message of a topic export, holding either the ticket used to stream the file
+/// with DoGet or the location of the file written in the store.
+#[derive(Serialize, Debug)]
+pub struct TopicExport {
+    /// Ticket encoded in base64
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ticket: Option<String>,
+    /// Path of the file, relative to the root of the store
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Size of the file in bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<usize>,
+}
+
+impl TopicExport {
+    pub fn streamed(ticket: Vec<u8>) -> Self {
+        Self {
+            ticket: Some(BASE64.encode(ticket)),
+            path: None,
+            size: None,
+        }
+    }
+
+    pub fn written(path: String, size: usize) -> Self {
+        Self {
+            ticket: None,
+            path: Some(path),
+            size: Some(size),
+        }
+    }
+}
+
+// ########
+// Attestations
+// ########
+
+/// Attestation of a session, with the outcome of the check against its manifest.
+#[derive(Serialize, Debug)]
+pub struct ResponseSessionAttestation {
+    #[serde(flatten)]
+    pub attestation: crate::JsonSessionAttestation,
+    pub valid: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SessionAttestationList {
+    pub attestations: Vec<ResponseSessionAttestation>,
+}
+
+// ########
+// Column statistics
+// ########
+
+/// Minimum or maximum value of a column, serialized as a JSON number or string.
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum ColumnValue {
+    Numeric(f64),
+    Textual(String),
+}
+
+impl From<types::ColumnValue> for ColumnValue {
+    fn from(value: types::ColumnValue) -> Self {
+        match value {
+            types::ColumnValue::Numeric(v) => Self::Numeric(v),
+            types::ColumnValue::Textual(v) => Self::Textual(v),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ColumnStats {
+    pub name: String,
+    pub row_count: u64,
+    pub null_ratio: f64,
+    pub min: Option<ColumnValue>,
+    pub max: Option<ColumnValue>,
+    pub mean: Option<f64>,
+    pub cardinality: Option<u64>,
+}
+
+impl From<types::ColumnStats> for ColumnStats {
+    fn from(value: types::ColumnStats) -> Self {
+        Self {
+            null_ratio: value.null_ratio(),
+            name: value.name,
+            row_count: value.row_count,
+            min: value.min.map(Into::into),
+            max: value.max.map(Into::into),
+            mean: value.mean,
+            cardinality: value.cardinality,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct TopicColumnStats {
+    pub columns: Vec<ColumnStats>,
+}
+
+impl From<Vec<types::ColumnStats>> for TopicColumnStats {
+    fn from(value: Vec<types::ColumnStats>) -> Self {
+        Self {
+            columns: value.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+// ########
+// Redactions
+// ########
+
+#[derive(Serialize, Debug)]
+pub struct Redaction {
+    pub uuid: String,
+    pub locator: String,
+    pub timestamp_ns_start: i64,
+    pub timestamp_ns_end: i64,
+    pub columns: Vec<String>,
+    pub mode: String,
+    /// Omitted from the output if the redaction was not authenticated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub principal: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub row_count: u64,
+    pub object_count: u64,
+    pub created_at_ns: i64,
+}
+
+impl From<types::Redaction> for Redaction {
+    fn from(value: types::Redaction) -> Self {
+        Self {
+            uuid: value.uuid.to_string(),
+            locator: value.topic.to_string(),
+            timestamp_ns_start: value.range.start.as_i64(),
+            timestamp_ns_end: value.range.end.as_i64(),
+            columns: value.columns,
+            mode: value.mode.to_string(),
+            principal: value.principal,
+            reason: value.reason,
+            row_count: value.row_count,
+            object_count: value.object_count,
+            created_at_ns: value.created_at.as_i64(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct RedactionList {
+    pub redactions: Vec<Redaction>,
+}
+
+impl From<Vec<types::Redaction>> for RedactionList {
+    fn from(value: Vec<types::Redaction>) -> Self {
+        Self {
+            redactions: value.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct TopicWatermark {
+    /// Latest timestamp of the published data, omitted from the output if no data was
+    /// published yet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub watermark_ns: Option<i64>,
+    /// Time the watermark last advanced
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at_ns: Option<i64>,
+    /// Number of sessions not finalized yet writing into the topic
+    pub pending_sessions: usize,
+    /// True if no session is writing into the topic
+    pub complete: bool,
+}
+
+impl From<types::TopicWatermark> for TopicWatermark {
+    fn from(value: types::TopicWatermark) -> Self {
+        Self {
+            watermark_ns: value.watermark.map(|ts| ts.as_i64()),
+            updated_at_ns: value.updated_at.map(|ts| ts.as_i64()),
+            pending_sessions: value.pending_sessions,
+            complete: value.is_complete(),
+        }
+    }
+}
+
+/// Compaction policy of a sequence, settings not set follow the server configuration.
+#[derive(Serialize, Debug)]
+pub struct CompactionPolicy {
+    pub target_object_size: Option<u64>,
+    pub interval: Option<u64>,
+    pub min_chunks: Option<u64>,
+    pub cold_after: Option<u64>,
+}
+
+impl From<types::CompactionPolicy> for CompactionPolicy {
+    fn from(value: types::CompactionPolicy) -> Self {
+        Self {
+            target_object_size: value.target_object_size,
+            interval: value.interval,
+            min_chunks: value.min_chunks,
+            cold_after: value.cold_after,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct SequenceCompact {
+    /// Number of topics compacted
+    pub compacted_topics: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ImportedChannel {
+    /// Topic the channel publishes on
+    pub channel: String,
+    pub locator: String,
+    pub message_count: u64,
+}
+
+/// Response message containing the session of an MCAP import and the topics its channels
+/// were imported into.
+#[derive(Serialize, Debug)]
+pub struct SequenceImportMcap {
+    pub session: String,
+    pub topics: Vec<ImportedChannel>,
+}
+
+/// Response message containing the root folder of a sequence archive.
+#[derive(Serialize, Debug)]
+pub struct SequenceExport {
+    /// Path of the archive, relative to the root of the store
+    pub path: String,
+    pub topics: usize,
+    pub size_bytes: u64,
+}
+
+/// Response message containing the sequence recreated from an archive.
+#[derive(Serialize, Debug)]
+pub struct SequenceImport {
+    pub locator: String,
+    pub uuid: String,
+    pub session: String,
+    pub topics: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReplayChannel {
+    pub channel_id: u32,
+    pub locator: String,
+}
+
+/// Response message containing the ticket used to stream a replay with DoGet, along with
+/// the topic of each channel of the stream.
+#[derive(Serialize, Debug)]
+pub struct SequenceReplay {
+    /// Ticket encoded in base64
+    pub ticket: String,
+    pub channels: Vec<ReplayChannel>,
 }
 
-impl<L: Locator> From<Vec<types::Notification<L>>> for NotificationList {
-    fn from(value: Vec<types::Notification<L>>) -> Self {
+impl SequenceReplay {
+    pub fn new(ticket: Vec<u8>, channels: Vec<ReplayChannel>) -> Self {
         Self {
-            notifications: value.into_iter().map(Into::into).collect(),
+            ticket: BASE64.encode(ticket),
+            channels,
+        }
+    }
+}
+
+/// Response message containing the locator and the unique key of the copy of a topic.
+#[derive(Serialize, Debug)]
+pub struct TopicCopy {
+    pub locator: String,
+    pub uuid: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TopicLineageItem {
+    pub locator: String,
+    pub uuid: String,
+    /// Time the data was copied from the topic
+    pub copied_at_ns: i64,
+}
+
+/// Response message containing the topics the data of a topic was copied from, from the
+/// nearest.
+#[derive(Serialize, Debug)]
+pub struct TopicLineage {
+    pub lineage: Vec<TopicLineageItem>,
+}
+
+impl From<Vec<types::TopicLineage>> for TopicLineage {
+    fn from(value: Vec<types::TopicLineage>) -> Self {
+        Self {
+            lineage: value
+                .into_iter()
+                .map(|source| TopicLineageItem {
+                    locator: source.locator.to_string(),
+                    uuid: source.uuid.to_string(),
+                    copied_at_ns: source.copied_at.as_i64(),
+                })
+                .collect(),
         }
     }
 }
@@ -74,6 +630,77 @@ impl<L: Locator> From<Vec<types::Notification<L>>> for NotificationList {
 #[derive(Serialize, Debug)]
 pub struct Query {
     pub items: Vec<ResponseQueryItem>,
+    /// Sampling of approximate queries, omitted from the output for exact queries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<QuerySampling>,
+    /// Handle used to fetch the next rows of paginated queries, omitted from the output
+    /// once all the rows are returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handle: Option<String>,
+    /// True if the result was served from the query cache.
+    pub cache_hit: bool,
+}
+
+impl Query {
+    pub fn with_sampling(mut self, sampling: Option<&query::Sampling>) -> Self {
+        self.sampling = sampling.map(Into::into);
+        self
+    }
+
+    pub fn with_handle(mut self, handle: Option<types::Uuid>) -> Self {
+        self.handle = handle.map(|h| h.to_string());
+        self
+    }
+
+    pub fn with_cache_hit(mut self, cache_hit: bool) -> Self {
+        self.cache_hit = cache_hit;
+        self
+    }
+}
+
+/// Response message containing the ticket used to stream the result of a SQL query with
+/// DoGet, along with the schema of the result.
+#[derive(Serialize, Debug)]
+pub struct SqlQuery {
+    /// Ticket encoded in base64
+    pub ticket: String,
+    /// Schema of the result, encoded as a base64 Arrow IPC message
+    pub arrow_schema: String,
+}
+
+impl SqlQuery {
+    pub fn new(ticket: Vec<u8>, arrow_schema: Vec<u8>) -> Self {
+        Self {
+            ticket: BASE64.encode(ticket),
+            arrow_schema: BASE64.encode(arrow_schema),
+        }
+    }
+}
+
+/// Reports which fraction of the topic data was searched by an approximate query.
+#[derive(Serialize, Debug)]
+pub struct QuerySampling {
+    pub fraction: f64,
+    pub method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stratum_ns: Option<i64>,
+}
+
+impl From<&query::Sampling> for QuerySampling {
+    fn from(value: &query::Sampling) -> Self {
+        let (method, stratum_ns) = match value.method() {
+            query::SamplingMethod::Uniform => ("uniform", None),
+            query::SamplingMethod::TimeStratified { stratum_ns } => {
+                ("time_stratified", Some(*stratum_ns))
+            }
+        };
+
+        Self {
+            fraction: value.fraction(),
+            method: method.to_owned(),
+            stratum_ns,
+        }
+    }
 }
 
 /// Holds topic data: locator and optional timestamp.
@@ -116,6 +743,9 @@ impl From<types::SequenceTopicGroupSet> for Query {
         let vec: Vec<types::SequenceTopicGroup> = value.into();
         Self {
             items: vec.into_iter().map(Into::into).collect(),
+            sampling: None,
+            handle: None,
+            cache_hit: false,
         }
     }
 }
@@ -143,6 +773,7 @@ pub struct ApiKeyStatus {
     pub description: String,
     pub created_at_ns: i64,
     pub expires_at_ns: Option<i64>,
+    pub default_sequence: Option<String>,
 }
 
 impl From<&auth::ApiKey> for ApiKeyStatus {
@@ -152,6 +783,147 @@ impl From<&auth::ApiKey> for ApiKeyStatus {
             description: value.description.clone(),
             created_at_ns: value.created_at.as_i64(),
             expires_at_ns: value.expires_at.map(Into::into),
+            default_sequence: value.default_sequence.clone().map(Into::into),
+        }
+    }
+}
+
+// ####
+// Scheduled Query
+// ####
+
+#[derive(Serialize, Debug)]
+pub struct ScheduledQueryItem {
+    pub name: String,
+    pub schedule: String,
+    pub query: serde_json::Value,
+    /// Condition on the number of matching topics, e.g. `{"$gt": 0}`
+    pub condition: serde_json::Value,
+    pub created_at_ns: i64,
+    pub next_run_ns: i64,
+    pub last_run_ns: Option<i64>,
+    pub last_match_count: Option<u64>,
+}
+
+impl From<types::ScheduledQuery<serde_json::Value>> for ScheduledQueryItem {
+    fn from(value: types::ScheduledQuery<serde_json::Value>) -> Self {
+        Self {
+            name: value.name,
+            schedule: value.schedule.to_string(),
+            query: value.query,
+            condition: serde_json::json!({ value.condition.op_name(): value.condition.value }),
+            created_at_ns: value.created_at.as_i64(),
+            next_run_ns: value.next_run_at.as_i64(),
+            last_run_ns: value.last_run_at.map(Into::into),
+            last_match_count: value.last_match_count,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ScheduledQueryList {
+    pub scheduled_queries: Vec<ScheduledQueryItem>,
+}
+
+impl From<Vec<types::ScheduledQuery<serde_json::Value>>> for ScheduledQueryList {
+    fn from(value: Vec<types::ScheduledQuery<serde_json::Value>>) -> Self {
+        Self {
+            scheduled_queries: value.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ScheduledQueryRun {
+    /// Number of topics matched by the query
+    pub match_count: u64,
+    /// True if the condition was satisfied and the matching topics notified
+    pub triggered: bool,
+}
+
+// ####
+// Query View
+// ####
+
+#[derive(Serialize, Debug)]
+pub struct QueryViewItem {
+    pub name: String,
+    pub query: serde_json::Value,
+    pub created_at_ns: i64,
+}
+
+impl From<types::QueryView<serde_json::Value>> for QueryViewItem {
+    fn from(value: types::QueryView<serde_json::Value>) -> Self {
+        Self {
+            name: value.name,
+            query: value.query,
+            created_at_ns: value.created_at.as_i64(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct QueryViewList {
+    pub views: Vec<QueryViewItem>,
+}
+
+impl From<Vec<types::QueryView<serde_json::Value>>> for QueryViewList {
+    fn from(value: Vec<types::QueryView<serde_json::Value>>) -> Self {
+        Self {
+            views: value.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+// ####
+// External Table
+// ####
+
+#[derive(Serialize, Debug)]
+pub struct ExternalTableColumn {
+    pub name: String,
+    pub data_type: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ExternalTableItem {
+    pub name: String,
+    pub location: String,
+    pub format: String,
+    pub columns: Vec<ExternalTableColumn>,
+    pub registered_at_ns: i64,
+    pub expires_at_ns: i64,
+}
+
+impl From<types::ExternalTable> for ExternalTableItem {
+    fn from(value: types::ExternalTable) -> Self {
+        Self {
+            name: value.name,
+            location: value.location,
+            format: value.format.to_string(),
+            columns: value
+                .columns
+                .into_iter()
+                .map(|column| ExternalTableColumn {
+                    name: column.name,
+                    data_type: column.data_type,
+                })
+                .collect(),
+            registered_at_ns: value.registered_at.as_i64(),
+            expires_at_ns: value.expires_at.as_i64(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ExternalTableList {
+    pub tables: Vec<ExternalTableItem>,
+}
+
+impl From<Vec<types::ExternalTable>> for ExternalTableList {
+    fn from(value: Vec<types::ExternalTable>) -> Self {
+        Self {
+            tables: value.into_iter().map(Into::into).collect(),
         }
     }
 }
@@ -196,6 +968,259 @@ impl FromStr for ServerVersion {
     }
 }
 
+#[derive(Serialize, Debug)]
+pub struct StoreMetrics {
+    pub backend: String,
+    pub operations: Vec<StoreOperationMetrics>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct StoreOperationMetrics {
+    pub operation: String,
+    pub count: u64,
+    pub errors: u64,
+    pub bytes: u64,
+    /// Estimated latency quantiles (in microseconds), missing if no request was timed or
+    /// if the latency exceeds the largest tracked value
+    pub latency_us_p50: Option<u64>,
+    pub latency_us_p99: Option<u64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ColdDataFile {
+    pub path: String,
+    pub size_bytes: u64,
+    /// Estimated number of reads
+    pub read_count: u64,
+    /// Missing if no read was sampled
+    pub last_read_ns: Option<i64>,
+}
+
+/// Response message containing the cold data files and the estimated savings of moving
+/// them to the archive storage.
+#[derive(Serialize, Debug)]
+pub struct StoreTieringReport {
+    pub data_files: u64,
+    pub size_bytes: u64,
+    pub monthly_savings: f64,
+    pub candidates: Vec<ColdDataFile>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct StoreTieringDemote {
+    pub demoted: usize,
+    pub size_bytes: u64,
+}
+
+/// Monthly storage and egress costs of some data files.
+#[derive(Serialize, Debug)]
+pub struct Cost {
+    pub standard_bytes: u64,
+    pub archived_bytes: u64,
+    pub storage_cost: f64,
+    /// Projected bytes read in a month
+    pub egress_bytes: u64,
+    pub egress_cost: f64,
+    pub total_cost: f64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SequenceCost {
+    pub locator: String,
+    #[serde(flatten)]
+    pub cost: Cost,
+}
+
+/// Response message containing the estimated monthly costs of the sequences of a scope.
+#[derive(Serialize, Debug)]
+pub struct CostEstimate {
+    pub scope: String,
+    #[serde(flatten)]
+    pub total: Cost,
+    /// Sorted by locator
+    pub sequences: Vec<SequenceCost>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct IngestMetrics {
+    /// Topics with batches uploaded since startup, sorted by locator
+    pub topics: Vec<TopicIngestMetrics>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TopicIngestMetrics {
+    pub topic: String,
+    /// Number of batches with a measured latency
+    pub count: u64,
+    /// Batches sent or timestamped later than their commit, not measured
+    pub skewed: u64,
+    /// Estimated latency quantiles (in milliseconds), missing if no batch was measured or
+    /// if the latency exceeds the largest tracked value
+    pub latency_ms_p50: Option<u64>,
+    pub latency_ms_p99: Option<u64>,
+    pub buckets: Vec<LatencyBucket>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct LatencyBucket {
+    /// Upper bound of the bucket (in milliseconds), missing for the last bucket
+    pub le_ms: Option<u64>,
+    pub count: u64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ServerStats {
+    pub queries: QueryStats,
+    /// Counters of the requests received since startup, by gRPC method
+    pub requests: std::collections::BTreeMap<String, RequestStats>,
+    /// Requests rejected because of the credentials or of the resources requested
+    pub security: SecurityStats,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SecurityStats {
+    /// Requests with a missing or invalid API key
+    pub unauthenticated: u64,
+    /// Requests without enough permissions or with an invalid resource key
+    pub permission_denied: u64,
+    /// Requests for resources not found
+    pub not_found: u64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SecurityReport {
+    /// Peers with rejected requests, sorted by decreasing number of failures
+    pub peers: Vec<PeerSecurityReport>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PeerSecurityReport {
+    /// Address of the peer
+    pub peer: String,
+    #[serde(flatten)]
+    pub failures: SecurityStats,
+    /// Time of the last rejected request, in nanoseconds since the epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_failure_ns: Option<i64>,
+    /// Remaining time (in seconds) the peer is locked out for sending invalid resource
+    /// keys, missing if not locked out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locked_out_secs: Option<u64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReindexStatus {
+    /// Jobs started since startup, by scope
+    pub jobs: Vec<ReindexJob>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReindexJob {
+    pub scope: String,
+    pub running: bool,
+    /// Number of topics whose derived state was rebuilt
+    pub processed: u64,
+    /// Number of topics whose derived state could not be rebuilt
+    pub failed: u64,
+    pub started_at_ns: i64,
+    /// Missing if the job is running
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at_ns: Option<i64>,
+    /// Reason the job was stopped before processing all the topics
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReplayCapture {
+    /// True while the capture window is open
+    pub active: bool,
+    /// Maximum number of requests kept, the oldest are discarded
+    pub capacity: usize,
+    /// Missing if no capture was started since startup
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at_ns: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until_ns: Option<i64>,
+    /// Number of requests discarded because the buffer was full
+    pub dropped: u64,
+    /// Requests recorded, from the oldest
+    pub entries: Vec<ReplayEntry>,
+}
+
+/// Request recorded by the replay capture, strings are replaced by salted hashes.
+#[derive(Serialize, Debug)]
+pub struct ReplayEntry {
+    pub at_ns: i64,
+    /// Either `action` or `do_put`
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peer: Option<String>,
+    pub duration_us: u64,
+    /// gRPC status code the request was answered with
+    pub status: String,
+    /// Type of the action
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    /// Payload of the action, missing if not JSON or too large
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload_bytes: Option<usize>,
+    /// Locator of the uploaded topic
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locator: Option<String>,
+    /// Number of messages of the DoPut stream, including the header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub messages: Option<u64>,
+    /// Bytes of the data sent in the DoPut stream
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SelfTest {
+    /// True if all the steps completed
+    pub passed: bool,
+    /// Steps performed, in order, up to the first failed one
+    pub steps: Vec<SelfTestStep>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SelfTestStep {
+    pub step: String,
+    pub elapsed_us: u64,
+    /// Reason of the failure, missing if the step completed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RequestStats {
+    pub requests: u64,
+    /// Requests answered with an error status
+    pub failed: u64,
+    /// Average time (in microseconds) spent answering a request, up to the start of the
+    /// response
+    pub latency_us_avg: u64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct QueryStats {
+    /// Maximum number of queries running concurrently, 0 if unlimited
+    pub max_running: usize,
+    pub running: usize,
+    pub max_queued: usize,
+    pub queued: usize,
+    /// Time (in milliseconds) spent in the queue by the query waiting since the longest
+    /// time, missing if no query is queued
+    pub oldest_queued_ms: Option<u64>,
+    /// Counters of the queries admitted and rejected since startup
+    pub admitted: u64,
+    pub rejected: u64,
+    pub timed_out: u64,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
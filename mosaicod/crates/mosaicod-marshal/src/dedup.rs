@@ -0,0 +1,30 @@
+use mosaicod_core::types;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum DedupPolicy {
+    None,
+    DropDuplicates,
+    LastWriteWins { key: String },
+}
+
+impl From<types::DedupPolicy> for DedupPolicy {
+    fn from(value: types::DedupPolicy) -> Self {
+        match value {
+            types::DedupPolicy::None => Self::None,
+            types::DedupPolicy::DropDuplicates => Self::DropDuplicates,
+            types::DedupPolicy::LastWriteWins(key) => Self::LastWriteWins { key },
+        }
+    }
+}
+
+impl From<DedupPolicy> for types::DedupPolicy {
+    fn from(value: DedupPolicy) -> Self {
+        match value {
+            DedupPolicy::None => types::DedupPolicy::None,
+            DedupPolicy::DropDuplicates => types::DedupPolicy::DropDuplicates,
+            DedupPolicy::LastWriteWins { key } => types::DedupPolicy::LastWriteWins(key),
+        }
+    }
+}
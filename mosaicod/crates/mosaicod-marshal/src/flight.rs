@@ -3,6 +3,7 @@ use bincode::{Decode, Encode};
 use mosaicod_core::types;
 use mosaicod_core::types::{SessionMetadata, TopicLocator};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 // ////////////////////////////////////////////////////////////////////////////
 // GET FLIGHT INFO CMD
 // ////////////////////////////////////////////////////////////////////////////
@@ -13,10 +14,50 @@ struct GetFlightInfoCmd {
     resource_locator: String,
     timestamp_ns_start: Option<i64>,
     timestamp_ns_end: Option<i64>,
+    #[serde(default)]
+    blob_references: bool,
+    changes_since_ns: Option<i64>,
+    changes_since_session: Option<String>,
+    session: Option<String>,
+    anonymization: Option<Anonymization>,
 }
 
-impl From<GetFlightInfoCmd> for types::flight::GetFlightInfoCmd {
-    fn from(value: GetFlightInfoCmd) -> Self {
+/// Non-exported type for deserialize [`types::Anonymization`]
+#[derive(Deserialize, Encode, Decode, Default)]
+struct Anonymization {
+    #[serde(default)]
+    drop: Vec<String>,
+    #[serde(default)]
+    hash: Vec<String>,
+    /// Number of decimal digits kept by each coarsened column
+    #[serde(default)]
+    coarsen: BTreeMap<String, u32>,
+}
+
+impl From<types::Anonymization> for Anonymization {
+    fn from(value: types::Anonymization) -> Self {
+        Self {
+            drop: value.drop,
+            hash: value.hash,
+            coarsen: value.coarsen,
+        }
+    }
+}
+
+impl From<Anonymization> for types::Anonymization {
+    fn from(value: Anonymization) -> Self {
+        Self {
+            drop: value.drop,
+            hash: value.hash,
+            coarsen: value.coarsen,
+        }
+    }
+}
+
+impl TryFrom<GetFlightInfoCmd> for types::flight::GetFlightInfoCmd {
+    type Error = super::Error;
+
+    fn try_from(value: GetFlightInfoCmd) -> Result<Self, Self::Error> {
         let up = value
             .timestamp_ns_end
             .map_or_else(types::Timestamp::unbounded_pos, |e| e.into());
@@ -30,18 +71,95 @@ impl From<GetFlightInfoCmd> for types::flight::GetFlightInfoCmd {
             ts_range = Some(types::TimestampRange::between(lb, up));
         }
 
-        types::flight::GetFlightInfoCmd {
+        let changes_since = match (value.changes_since_ns, value.changes_since_session) {
+            (None, None) => None,
+            (Some(ts), None) => Some(types::flight::ChangesSince::Timestamp(ts.into())),
+            (None, Some(session)) => Some(types::flight::ChangesSince::Session(
+                session
+                    .parse()
+                    .map_err(|_| Error::DeserializationError(session))?,
+            )),
+            (Some(_), Some(_)) => {
+                return Err(Error::DeserializationError(
+                    "only one of `changes_since_ns` and `changes_since_session` can be set"
+                        .to_owned(),
+                ));
+            }
+        };
+
+        if changes_since.is_some() && value.session.is_some() {
+            return Err(Error::DeserializationError(
+                "changes can't be requested for the data of a single `session`".to_owned(),
+            ));
+        }
+
+        let session = value
+            .session
+            .map(|session| {
+                session
+                    .parse()
+                    .map_err(|_| Error::DeserializationError(session))
+            })
+            .transpose()?;
+
+        Ok(types::flight::GetFlightInfoCmd {
             resource_locator: value.resource_locator,
             timestamp_range: ts_range,
-        }
+            blob_references: value.blob_references,
+            changes_since,
+            session,
+            anonymization: value
+                .anonymization
+                .map(types::Anonymization::from)
+                .filter(|anonymization| !anonymization.is_empty()),
+        })
     }
 }
 
 /// Convert a raw flight command into a [`GetFlightInfoCmd`]
 pub fn get_flight_info_cmd(v: &[u8]) -> Result<types::flight::GetFlightInfoCmd, super::Error> {
     serde_json::from_slice::<GetFlightInfoCmd>(v)
-        .map_err(|e| super::Error::DeserializationError(e.to_string()))
-        .map(|v| v.into())
+        .map_err(|e| super::Error::DeserializationError(e.to_string()))?
+        .try_into()
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// LIST FLIGHTS CRITERIA
+// ////////////////////////////////////////////////////////////////////////////
+
+/// Non-exported type for deserialize [`ListFlightsCriteria`]
+#[derive(Deserialize)]
+struct ListFlightsCriteria {
+    pattern: Option<String>,
+}
+
+/// Convert the expression of a list flights criteria into a [`ListFlightsCriteria`].
+///
+/// The expression is either a JSON object (e.g. `{"pattern": "my_sequence/*"}`) or the bare
+/// pattern. An empty pattern or `/` lists all the sequences.
+pub fn list_flights_criteria(v: &[u8]) -> Result<types::flight::ListFlightsCriteria, super::Error> {
+    let expression = std::str::from_utf8(v)
+        .map_err(|e| super::Error::DeserializationError(e.to_string()))?
+        .trim();
+
+    let pattern = if expression.starts_with('{') {
+        serde_json::from_str::<ListFlightsCriteria>(expression)
+            .map_err(|e| super::Error::DeserializationError(e.to_string()))?
+            .pattern
+            .unwrap_or_default()
+    } else {
+        expression.to_owned()
+    };
+
+    let pattern =
+        match pattern.as_str() {
+            "" | "/" => None,
+            pattern => Some(pattern.parse().map_err(|_| {
+                Error::DeserializationError(format!("invalid pattern `{pattern}`"))
+            })?),
+        };
+
+    Ok(types::flight::ListFlightsCriteria { pattern })
 }
 
 // ////////////////////////////////////////////////////////////////////////////
@@ -51,6 +169,26 @@ pub fn get_flight_info_cmd(v: &[u8]) -> Result<types::flight::GetFlightInfoCmd,
 struct DoPutCmd {
     resource_locator: String,
     topic_uuid: String,
+    #[serde(default)]
+    payload_format: PayloadFormat,
+}
+
+/// Non-exported type for deserialize [`types::flight::PayloadFormat`]
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum PayloadFormat {
+    #[default]
+    ArrowIpc,
+    Parquet,
+}
+
+impl From<PayloadFormat> for types::flight::PayloadFormat {
+    fn from(value: PayloadFormat) -> Self {
+        match value {
+            PayloadFormat::ArrowIpc => Self::ArrowIpc,
+            PayloadFormat::Parquet => Self::Parquet,
+        }
+    }
 }
 
 impl From<DoPutCmd> for types::flight::DoPutCmd {
@@ -58,6 +196,7 @@ impl From<DoPutCmd> for types::flight::DoPutCmd {
         types::flight::DoPutCmd {
             resource_locator: value.resource_locator,
             key: value.topic_uuid,
+            payload_format: value.payload_format.into(),
         }
     }
 }
@@ -68,6 +207,25 @@ pub fn do_put_cmd(v: &[u8]) -> Result<types::flight::DoPutCmd, super::Error> {
         .map(|v| v.into())
 }
 
+/// Non-exported type for deserialize the app metadata of the batches uploaded
+#[derive(Deserialize)]
+struct DoPutBatchAppMetadata {
+    sent_at_ns: Option<i64>,
+}
+
+/// Returns the time the client sent a batch, declared in the app metadata of the batch
+/// message as `{"sent_at_ns": <nanoseconds since the epoch>}`. Messages without app
+/// metadata return `None`.
+pub fn do_put_batch_sent_at(v: &[u8]) -> Result<Option<types::Timestamp>, super::Error> {
+    if v.is_empty() {
+        return Ok(None);
+    }
+
+    serde_json::from_slice::<DoPutBatchAppMetadata>(v)
+        .map_err(|e| super::Error::DeserializationError(e.to_string()))
+        .map(|v| v.sent_at_ns.map(Into::into))
+}
+
 // ////////////////////////////////////////////////////////////////////////////
 // SEQUENCE APP METADATA
 // ////////////////////////////////////////////////////////////////////////////
@@ -78,6 +236,20 @@ pub struct SequenceAppMetadata {
     created_at_ns: i64,
     resource_locator: String,
     sessions: Vec<SessionAppMetadata>,
+    /// Names of the query views defined in the sequence
+    #[serde(default)]
+    views: Vec<String>,
+}
+
+impl SequenceAppMetadata {
+    pub fn with_views(mut self, views: Vec<String>) -> Self {
+        self.views = views;
+        self
+    }
+
+    pub fn views(&self) -> &[String] {
+        &self.views
+    }
 }
 
 impl<M> From<types::SequenceMetadata<M>> for SequenceAppMetadata {
@@ -86,6 +258,7 @@ impl<M> From<types::SequenceMetadata<M>> for SequenceAppMetadata {
             created_at_ns: value.created_at.as_i64(),
             resource_locator: value.resource_locator.to_string(),
             sessions: value.sessions.into_iter().map(Into::into).collect(),
+            views: Vec::new(),
         }
     }
 }
@@ -183,6 +356,10 @@ struct TicketTopic {
     locator: String,
     timestamp_ns_start: Option<i64>,
     timestamp_ns_end: Option<i64>,
+    blob_references: bool,
+    changes_since_ns: Option<i64>,
+    session: Option<String>,
+    anonymization: Option<Anonymization>,
 }
 
 impl From<types::flight::TicketTopic> for TicketTopic {
@@ -191,6 +368,10 @@ impl From<types::flight::TicketTopic> for TicketTopic {
             locator: value.locator.to_string(),
             timestamp_ns_start: value.timestamp_range.as_ref().map(|tsr| tsr.start.into()),
             timestamp_ns_end: value.timestamp_range.map(|tsr| tsr.end.into()),
+            blob_references: value.blob_references,
+            changes_since_ns: value.changes_since.map(Into::into),
+            session: value.session.map(|session| session.to_string()),
+            anonymization: value.anonymization.map(Into::into),
         }
     }
 }
@@ -216,24 +397,253 @@ impl TryFrom<TicketTopic> for types::flight::TicketTopic {
                 .parse::<types::TopicLocator>()
                 .map_err(|_| Error::DeserializationError(value.locator))?,
             timestamp_range,
+            blob_references: value.blob_references,
+            changes_since: value.changes_since_ns.map(Into::into),
+            session: value
+                .session
+                .map(|session| {
+                    session
+                        .parse()
+                        .map_err(|_| Error::DeserializationError(session))
+                })
+                .transpose()?,
+            anonymization: value.anonymization.map(Into::into),
         })
     }
 }
 
-pub fn ticket_topic_to_binary(tt: types::flight::TicketTopic) -> Result<Vec<u8>, super::Error> {
-    let tt: TicketTopic = tt.into();
+// ////////////////////////////////////////////////////////////////////////////
+// TICKET SQL QUERY
+// ////////////////////////////////////////////////////////////////////////////
+#[derive(Encode, Decode)]
+struct TicketSqlQuery {
+    statement: String,
+    tables: Vec<(String, String)>,
+}
+
+impl From<types::flight::TicketSqlQuery> for TicketSqlQuery {
+    fn from(value: types::flight::TicketSqlQuery) -> Self {
+        Self {
+            statement: value.statement,
+            tables: value
+                .tables
+                .into_iter()
+                .map(|(name, locator)| (name, locator.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<TicketSqlQuery> for types::flight::TicketSqlQuery {
+    type Error = super::Error;
+
+    fn try_from(value: TicketSqlQuery) -> Result<Self, Error> {
+        Ok(Self {
+            statement: value.statement,
+            tables: value
+                .tables
+                .into_iter()
+                .map(|(name, locator)| {
+                    let locator = locator
+                        .parse::<types::TopicLocator>()
+                        .map_err(|_| Error::DeserializationError(locator))?;
+                    Ok((name, locator))
+                })
+                .collect::<Result<_, Error>>()?,
+        })
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// TICKET NOTIFICATIONS
+// ////////////////////////////////////////////////////////////////////////////
+#[derive(Encode, Decode)]
+struct TicketNotifications {
+    locator: String,
+    min_severity: String,
+}
+
+impl From<types::flight::TicketNotifications> for TicketNotifications {
+    fn from(value: types::flight::TicketNotifications) -> Self {
+        Self {
+            locator: value.locator.to_string(),
+            min_severity: value.min_severity.to_string(),
+        }
+    }
+}
+
+impl TryFrom<TicketNotifications> for types::flight::TicketNotifications {
+    type Error = super::Error;
+
+    fn try_from(value: TicketNotifications) -> Result<Self, Error> {
+        Ok(Self {
+            locator: value
+                .locator
+                .parse()
+                .map_err(|_| Error::DeserializationError(value.locator))?,
+            min_severity: value
+                .min_severity
+                .parse()
+                .map_err(|_| Error::DeserializationError(value.min_severity))?,
+        })
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// TICKET TOPIC EXPORT
+// ////////////////////////////////////////////////////////////////////////////
+#[derive(Encode, Decode)]
+struct TicketTopicExport {
+    locator: String,
+    timestamp_ns_start: Option<i64>,
+    timestamp_ns_end: Option<i64>,
+    row_group_size: Option<u64>,
+    include_metadata: bool,
+}
+
+impl From<types::flight::TicketTopicExport> for TicketTopicExport {
+    fn from(value: types::flight::TicketTopicExport) -> Self {
+        Self {
+            locator: value.locator.to_string(),
+            timestamp_ns_start: value.timestamp_range.as_ref().map(|tsr| tsr.start.into()),
+            timestamp_ns_end: value.timestamp_range.map(|tsr| tsr.end.into()),
+            row_group_size: value.row_group_size.map(|size| size as u64),
+            include_metadata: value.include_metadata,
+        }
+    }
+}
+
+impl TryFrom<TicketTopicExport> for types::flight::TicketTopicExport {
+    type Error = super::Error;
+
+    fn try_from(value: TicketTopicExport) -> Result<Self, Error> {
+        let ts = types::TimestampRange::between(
+            value
+                .timestamp_ns_start
+                .map_or_else(types::Timestamp::unbounded_neg, |v| v.into()),
+            value
+                .timestamp_ns_end
+                .map_or_else(types::Timestamp::unbounded_pos, |v| v.into()),
+        );
+
+        Ok(Self {
+            locator: value
+                .locator
+                .parse()
+                .map_err(|_| Error::DeserializationError(value.locator))?,
+            timestamp_range: if ts.is_unbounded() { None } else { Some(ts) },
+            row_group_size: value.row_group_size.map(|size| size as usize),
+            include_metadata: value.include_metadata,
+        })
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// TICKET REPLAY
+// ////////////////////////////////////////////////////////////////////////////
+#[derive(Encode, Decode)]
+struct TicketReplay {
+    topics: Vec<String>,
+    timestamp_ns_start: Option<i64>,
+    timestamp_ns_end: Option<i64>,
+}
+
+impl From<types::flight::TicketReplay> for TicketReplay {
+    fn from(value: types::flight::TicketReplay) -> Self {
+        Self {
+            topics: value.topics.iter().map(ToString::to_string).collect(),
+            timestamp_ns_start: value.timestamp_range.as_ref().map(|tsr| tsr.start.into()),
+            timestamp_ns_end: value.timestamp_range.map(|tsr| tsr.end.into()),
+        }
+    }
+}
+
+impl TryFrom<TicketReplay> for types::flight::TicketReplay {
+    type Error = super::Error;
+
+    fn try_from(value: TicketReplay) -> Result<Self, Error> {
+        let ts = types::TimestampRange::between(
+            value
+                .timestamp_ns_start
+                .map_or_else(types::Timestamp::unbounded_neg, |v| v.into()),
+            value
+                .timestamp_ns_end
+                .map_or_else(types::Timestamp::unbounded_pos, |v| v.into()),
+        );
+
+        Ok(Self {
+            topics: value
+                .topics
+                .into_iter()
+                .map(|locator| {
+                    locator
+                        .parse::<types::TopicLocator>()
+                        .map_err(|_| Error::DeserializationError(locator))
+                })
+                .collect::<Result<_, Error>>()?,
+            timestamp_range: if ts.is_unbounded() { None } else { Some(ts) },
+        })
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// TICKET
+// ////////////////////////////////////////////////////////////////////////////
+#[derive(Encode, Decode)]
+enum Ticket {
+    Topic(TicketTopic),
+    SqlQuery(TicketSqlQuery),
+    Notifications(TicketNotifications),
+    TopicExport(TicketTopicExport),
+    Replay(TicketReplay),
+}
+
+fn ticket_to_binary(ticket: Ticket) -> Result<Vec<u8>, super::Error> {
     let config = bincode::config::standard();
 
-    bincode::encode_to_vec(tt, config).map_err(|e| super::Error::SerializationError(e.to_string()))
+    bincode::encode_to_vec(ticket, config)
+        .map_err(|e| super::Error::SerializationError(e.to_string()))
 }
 
-pub fn ticket_topic_from_binary(v: &[u8]) -> Result<types::flight::TicketTopic, super::Error> {
+pub fn ticket_topic_to_binary(tt: types::flight::TicketTopic) -> Result<Vec<u8>, super::Error> {
+    ticket_to_binary(Ticket::Topic(tt.into()))
+}
+
+pub fn ticket_sql_query_to_binary(
+    tq: types::flight::TicketSqlQuery,
+) -> Result<Vec<u8>, super::Error> {
+    ticket_to_binary(Ticket::SqlQuery(tq.into()))
+}
+
+pub fn ticket_notifications_to_binary(
+    tn: types::flight::TicketNotifications,
+) -> Result<Vec<u8>, super::Error> {
+    ticket_to_binary(Ticket::Notifications(tn.into()))
+}
+
+pub fn ticket_topic_export_to_binary(
+    te: types::flight::TicketTopicExport,
+) -> Result<Vec<u8>, super::Error> {
+    ticket_to_binary(Ticket::TopicExport(te.into()))
+}
+
+pub fn ticket_replay_to_binary(tr: types::flight::TicketReplay) -> Result<Vec<u8>, super::Error> {
+    ticket_to_binary(Ticket::Replay(tr.into()))
+}
+
+pub fn ticket_from_binary(v: &[u8]) -> Result<types::flight::Ticket, super::Error> {
     let config = bincode::config::standard();
 
-    let (ticket, _): (TicketTopic, usize) = bincode::decode_from_slice(v, config)
+    let (ticket, _): (Ticket, usize) = bincode::decode_from_slice(v, config)
         .map_err(|e| super::Error::DeserializationError(e.to_string()))?;
 
-    ticket.try_into()
+    Ok(match ticket {
+        Ticket::Topic(tt) => types::flight::Ticket::Topic(tt.try_into()?),
+        Ticket::SqlQuery(tq) => types::flight::Ticket::SqlQuery(tq.try_into()?),
+        Ticket::Notifications(tn) => types::flight::Ticket::Notifications(tn.try_into()?),
+        Ticket::TopicExport(te) => types::flight::Ticket::TopicExport(te.try_into()?),
+        Ticket::Replay(tr) => types::flight::Ticket::Replay(tr.try_into()?),
+    })
 }
 
 // ////////////////////////////////////////////////////////////////////////////
@@ -281,6 +691,8 @@ pub struct TopicAppMetadata {
     pub locked: bool,
     pub resource_locator: String,
     pub info: Option<TopicAppMetadataInfo>,
+    /// Version of upsert topics, to be asserted when upserting only the changed rows
+    pub version: Option<u64>,
 }
 
 impl TopicAppMetadata {
@@ -291,9 +703,15 @@ impl TopicAppMetadata {
             locked: metadata.completed_at.is_some(),
             resource_locator: metadata.resource_locator.to_string(),
             info: None,
+            version: None,
         }
     }
 
+    pub fn with_version(mut self, version: u64) -> Self {
+        self.version = Some(version);
+        self
+    }
+
     pub fn with_info(mut self, info: types::TopicDataInfo) -> Self {
         self.info = Some(TopicAppMetadataInfo {
             chunks_number: info.chunks_number,
@@ -329,6 +747,40 @@ impl TryFrom<bytes::Bytes> for TopicAppMetadata {
 mod tests {
     use mosaicod_core::types;
 
+    /// Check that the send time of the batches is read from their app metadata.
+    #[test]
+    fn do_put_batch_sent_at() {
+        assert_eq!(super::do_put_batch_sent_at(b"").unwrap(), None);
+        assert_eq!(super::do_put_batch_sent_at(b"{}").unwrap(), None);
+        assert_eq!(
+            super::do_put_batch_sent_at(br#"{"sent_at_ns": 1000}"#)
+                .unwrap()
+                .map(|ts| ts.as_i64()),
+            Some(1000)
+        );
+        assert!(super::do_put_batch_sent_at(b"not json").is_err());
+    }
+
+    /// Check that the payload format of a DoPut stream defaults to Arrow IPC.
+    #[test]
+    fn do_put_cmd_payload_format() {
+        let cmd = super::do_put_cmd(br#"{"resource_locator": "s/t", "topic_uuid": "k"}"#).unwrap();
+        assert_eq!(cmd.payload_format, types::flight::PayloadFormat::ArrowIpc);
+
+        let cmd = super::do_put_cmd(
+            br#"{"resource_locator": "s/t", "topic_uuid": "k", "payload_format": "parquet"}"#,
+        )
+        .unwrap();
+        assert_eq!(cmd.payload_format, types::flight::PayloadFormat::Parquet);
+
+        assert!(
+            super::do_put_cmd(
+                br#"{"resource_locator": "s/t", "topic_uuid": "k", "payload_format": "csv"}"#
+            )
+            .is_err()
+        );
+    }
+
     /// Check that the conversion between [`super::GetFlightInfoCmd`] and
     /// [`types::flight::GetFlightInfoCmd`] is correct from a fully bounded info message.
     #[test]
@@ -337,13 +789,18 @@ mod tests {
             resource_locator: "test_sequence/topic/a".to_owned(),
             timestamp_ns_start: Some(100000),
             timestamp_ns_end: Some(110000),
+            blob_references: false,
+            changes_since_ns: None,
+            changes_since_session: None,
+            session: None,
+            anonymization: None,
         };
 
         let name = src.resource_locator.clone();
         let start = src.timestamp_ns_start.unwrap();
         let end = src.timestamp_ns_end.unwrap();
 
-        let dest: types::flight::GetFlightInfoCmd = src.into();
+        let dest: types::flight::GetFlightInfoCmd = src.try_into().unwrap();
 
         assert_eq!(dest.resource_locator, name);
         assert_eq!(dest.timestamp_range.as_ref().unwrap().start.as_i64(), start);
@@ -358,12 +815,17 @@ mod tests {
             resource_locator: "test_sequence/topic/a".to_owned(),
             timestamp_ns_start: Some(100000),
             timestamp_ns_end: None,
+            blob_references: false,
+            changes_since_ns: None,
+            changes_since_session: None,
+            session: None,
+            anonymization: None,
         };
 
         let name = src.resource_locator.clone();
         let start = src.timestamp_ns_start.unwrap();
 
-        let dest: types::flight::GetFlightInfoCmd = src.into();
+        let dest: types::flight::GetFlightInfoCmd = src.try_into().unwrap();
 
         assert_eq!(dest.resource_locator, name);
         assert_eq!(dest.timestamp_range.as_ref().unwrap().start.as_i64(), start);
@@ -378,12 +840,17 @@ mod tests {
             resource_locator: "test_sequence/topic/a".to_owned(),
             timestamp_ns_start: None,
             timestamp_ns_end: Some(110000),
+            blob_references: false,
+            changes_since_ns: None,
+            changes_since_session: None,
+            session: None,
+            anonymization: None,
         };
 
         let name = src.resource_locator.clone();
         let end = src.timestamp_ns_end.unwrap();
 
-        let dest: types::flight::GetFlightInfoCmd = src.into();
+        let dest: types::flight::GetFlightInfoCmd = src.try_into().unwrap();
 
         assert_eq!(dest.resource_locator, name);
         assert!(dest.timestamp_range.as_ref().unwrap().start.is_unbounded());
@@ -398,12 +865,226 @@ mod tests {
             resource_locator: "test_sequence/topic/a".to_owned(),
             timestamp_ns_start: None,
             timestamp_ns_end: None,
+            blob_references: false,
+            changes_since_ns: None,
+            changes_since_session: None,
+            session: None,
+            anonymization: None,
         };
 
         let name = src.resource_locator.clone();
-        let dest: types::flight::GetFlightInfoCmd = src.into();
+        let dest: types::flight::GetFlightInfoCmd = src.try_into().unwrap();
 
         assert_eq!(dest.resource_locator, name);
         assert!(dest.timestamp_range.is_none());
+        assert!(!dest.blob_references);
+    }
+
+    /// Check that the blob references flag is optional and defaults to false.
+    #[test]
+    fn get_flight_info_cmd_blob_references() {
+        let cmd = super::get_flight_info_cmd(br#"{"resource_locator": "seq/topic"}"#).unwrap();
+        assert!(!cmd.blob_references);
+
+        let cmd = super::get_flight_info_cmd(
+            br#"{"resource_locator": "seq/topic", "blob_references": true}"#,
+        )
+        .unwrap();
+        assert!(cmd.blob_references);
+    }
+
+    /// Check that the changes of upsert topics are requested either since a timestamp or
+    /// since a session.
+    #[test]
+    fn get_flight_info_cmd_changes_since() {
+        let cmd = super::get_flight_info_cmd(br#"{"resource_locator": "seq/topic"}"#).unwrap();
+        assert!(cmd.changes_since.is_none());
+
+        let cmd = super::get_flight_info_cmd(
+            br#"{"resource_locator": "seq/topic", "changes_since_ns": 1000}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            cmd.changes_since,
+            Some(types::flight::ChangesSince::Timestamp(1000.into()))
+        );
+
+        let cmd = super::get_flight_info_cmd(
+            br#"{"resource_locator": "seq/topic", "changes_since_session": "seq:01ARZ3NDEKTSV4RRFFQ69G5FAV"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            cmd.changes_since,
+            Some(types::flight::ChangesSince::Session(
+                "seq:01ARZ3NDEKTSV4RRFFQ69G5FAV".parse().unwrap()
+            ))
+        );
+
+        assert!(
+            super::get_flight_info_cmd(
+                br#"{"resource_locator": "seq/topic", "changes_since_ns": 1000, "changes_since_session": "seq:01ARZ3NDEKTSV4RRFFQ69G5FAV"}"#,
+            )
+            .is_err()
+        );
+    }
+
+    fn ticket_topic_roundtrip(ticket: types::flight::TicketTopic) -> types::flight::TicketTopic {
+        let binary = super::ticket_topic_to_binary(ticket).unwrap();
+        match super::ticket_from_binary(&binary).unwrap() {
+            types::flight::Ticket::Topic(ticket) => ticket,
+            _ => panic!("expecting a topic ticket"),
+        }
+    }
+
+    /// Check that the data of a single session can't be requested together with changes
+    /// and that the session is preserved in the ticket.
+    #[test]
+    fn get_flight_info_cmd_session() {
+        let cmd = super::get_flight_info_cmd(
+            br#"{"resource_locator": "seq/topic", "session": "seq:01ARZ3NDEKTSV4RRFFQ69G5FAV"}"#,
+        )
+        .unwrap();
+        let session: types::SessionLocator = "seq:01ARZ3NDEKTSV4RRFFQ69G5FAV".parse().unwrap();
+        assert_eq!(cmd.session, Some(session.clone()));
+
+        assert!(
+            super::get_flight_info_cmd(
+                br#"{"resource_locator": "seq/topic", "changes_since_ns": 1000, "session": "seq:01ARZ3NDEKTSV4RRFFQ69G5FAV"}"#,
+            )
+            .is_err()
+        );
+
+        let ticket = types::flight::TicketTopic {
+            locator: "seq/topic".parse().unwrap(),
+            timestamp_range: None,
+            blob_references: false,
+            changes_since: None,
+            session: Some(session.clone()),
+            anonymization: None,
+        };
+        let ticket = ticket_topic_roundtrip(ticket);
+        assert_eq!(ticket.session, Some(session));
+    }
+
+    /// Check that the anonymization is optional, empty transforms are ignored, and that it
+    /// is preserved in the ticket.
+    #[test]
+    fn get_flight_info_cmd_anonymization() {
+        let cmd = super::get_flight_info_cmd(
+            br#"{"resource_locator": "seq/topic", "anonymization": {}}"#,
+        )
+        .unwrap();
+        assert!(cmd.anonymization.is_none());
+
+        let cmd = super::get_flight_info_cmd(
+            br#"{"resource_locator": "seq/topic", "anonymization": {"hash": ["vin"], "coarsen": {"latitude": 3}}}"#,
+        )
+        .unwrap();
+        let anonymization = cmd.anonymization.unwrap();
+        assert!(anonymization.drop.is_empty());
+        assert_eq!(anonymization.hash, ["vin"]);
+        assert_eq!(anonymization.coarsen["latitude"], 3);
+
+        let ticket = types::flight::TicketTopic {
+            locator: "seq/topic".parse().unwrap(),
+            timestamp_range: None,
+            blob_references: false,
+            changes_since: None,
+            session: None,
+            anonymization: Some(anonymization.clone()),
+        };
+        let ticket = ticket_topic_roundtrip(ticket);
+        assert_eq!(ticket.anonymization, Some(anonymization));
+    }
+
+    /// Check that the tables of a SQL query are preserved in the ticket.
+    #[test]
+    fn ticket_sql_query() {
+        let ticket = types::flight::TicketSqlQuery {
+            statement: "SELECT * FROM imu".to_owned(),
+            tables: vec![("imu".to_owned(), "seq/imu".parse().unwrap())],
+        };
+        let binary = super::ticket_sql_query_to_binary(ticket).unwrap();
+
+        let types::flight::Ticket::SqlQuery(ticket) = super::ticket_from_binary(&binary).unwrap()
+        else {
+            panic!("expecting a SQL query ticket");
+        };
+        assert_eq!(ticket.statement, "SELECT * FROM imu");
+        assert_eq!(ticket.tables[0].0, "imu");
+        assert_eq!(ticket.tables[0].1.to_string(), "seq/imu");
+
+        assert!(super::ticket_from_binary(b"garbage").is_err());
+    }
+
+    /// Check that the options of a topic export are preserved in the ticket.
+    #[test]
+    fn ticket_topic_export() {
+        let ticket = types::flight::TicketTopicExport {
+            locator: "seq/imu".parse().unwrap(),
+            timestamp_range: Some(types::TimestampRange::between(10.into(), 20.into())),
+            row_group_size: Some(1024),
+            include_metadata: false,
+        };
+        let binary = super::ticket_topic_export_to_binary(ticket).unwrap();
+
+        let types::flight::Ticket::TopicExport(ticket) =
+            super::ticket_from_binary(&binary).unwrap()
+        else {
+            panic!("expecting a topic export ticket");
+        };
+        assert_eq!(ticket.locator.to_string(), "seq/imu");
+        let range = ticket.timestamp_range.unwrap();
+        assert_eq!(range.start, 10.into());
+        assert_eq!(range.end, 20.into());
+        assert_eq!(ticket.row_group_size, Some(1024));
+        assert!(!ticket.include_metadata);
+    }
+
+    /// Check that the topics of a replay are preserved in order in the ticket.
+    #[test]
+    fn ticket_replay() {
+        let ticket = types::flight::TicketReplay {
+            topics: vec!["seq/lidar".parse().unwrap(), "seq/imu".parse().unwrap()],
+            timestamp_range: Some(types::TimestampRange::starting_at(10.into())),
+        };
+        let binary = super::ticket_replay_to_binary(ticket).unwrap();
+
+        let types::flight::Ticket::Replay(ticket) = super::ticket_from_binary(&binary).unwrap()
+        else {
+            panic!("expecting a replay ticket");
+        };
+        let topics: Vec<String> = ticket.topics.iter().map(ToString::to_string).collect();
+        assert_eq!(topics, ["seq/lidar", "seq/imu"]);
+        let range = ticket.timestamp_range.unwrap();
+        assert_eq!(range.start, 10.into());
+        assert!(range.end.is_unbounded());
+    }
+
+    /// Check that the criteria of list flights accept both bare and JSON patterns.
+    #[test]
+    fn list_flights_criteria() {
+        assert!(super::list_flights_criteria(b"").unwrap().pattern.is_none());
+        assert!(
+            super::list_flights_criteria(b"/")
+                .unwrap()
+                .pattern
+                .is_none()
+        );
+        assert!(
+            super::list_flights_criteria(b"{}")
+                .unwrap()
+                .pattern
+                .is_none()
+        );
+
+        let criteria = super::list_flights_criteria(b"my_sequence/*").unwrap();
+        assert_eq!(criteria.pattern.unwrap().to_string(), "my_sequence/*");
+
+        let criteria = super::list_flights_criteria(br#"{"pattern": "run_*"}"#).unwrap();
+        assert_eq!(criteria.pattern.unwrap().to_string(), "run_*");
+
+        assert!(super::list_flights_criteria(b"seq//topic").is_err());
+        assert!(super::list_flights_criteria(br#"{"pattern": 1}"#).is_err());
     }
 }
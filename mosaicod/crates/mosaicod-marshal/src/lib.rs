@@ -3,9 +3,18 @@
 mod metadata;
 pub use metadata::*;
 
+mod manifest;
+pub use manifest::*;
+
+mod media;
+pub use media::*;
+
 mod format;
 pub use format::*;
 
+mod dedup;
+pub use dedup::*;
+
 mod actions;
 pub use actions::*;
 
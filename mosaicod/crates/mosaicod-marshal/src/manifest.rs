@@ -0,0 +1,691 @@
+use super::{JsonMetadataBlob, JsonTopicMetadata, JsonTopicOntologyMetadata};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use mosaicod_core::types::{self, MetadataError};
+use serde::{Deserialize, Serialize};
+
+type Error = MetadataError;
+
+/// Version of the layout of the session manifests written by this build.
+const SESSION_MANIFEST_VERSION: u32 = 1;
+
+/// JSON representation of a [`types::SessionManifest`], persisted in the store when a
+/// session is finalized.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonSessionManifest {
+    pub version: u32,
+    pub uuid: String,
+    pub locator: String,
+    pub created_at: i64,
+    pub completed_at: i64,
+    pub sequence: JsonSequenceSnapshot,
+    pub topics: Vec<JsonTopicManifest>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub upserted_topics: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonSequenceSnapshot {
+    pub locator: String,
+    pub created_at: i64,
+    pub path_in_store: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_metadata: Option<JsonMetadataBlob>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonTopicManifest {
+    pub uuid: String,
+    pub path_in_store: String,
+    pub metadata: JsonTopicMetadata,
+    pub fields: Vec<JsonFieldDescription>,
+    pub objects: Vec<JsonObjectDescription>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonFieldDescription {
+    pub name: String,
+    pub data_type: String,
+    pub nullable: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonObjectDescription {
+    pub path: String,
+    pub size: u64,
+    pub crc32: u32,
+}
+
+impl From<types::SessionManifest<JsonMetadataBlob>> for JsonSessionManifest {
+    fn from(value: types::SessionManifest<JsonMetadataBlob>) -> Self {
+        Self {
+            version: SESSION_MANIFEST_VERSION,
+            uuid: value.uuid.to_string(),
+            locator: value.locator.to_string(),
+            created_at: value.created_at.as_i64(),
+            completed_at: value.completed_at.as_i64(),
+            sequence: JsonSequenceSnapshot {
+                locator: value.sequence.locator.to_string(),
+                created_at: value.sequence.created_at.as_i64(),
+                path_in_store: value.sequence.path_in_store.into(),
+                user_metadata: value.sequence.user_metadata,
+            },
+            topics: value.topics.into_iter().map(Into::into).collect(),
+            upserted_topics: value
+                .upserted_topics
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+}
+
+impl From<types::TopicManifest<JsonMetadataBlob>> for JsonTopicManifest {
+    fn from(value: types::TopicManifest<JsonMetadataBlob>) -> Self {
+        Self {
+            uuid: value.uuid.to_string(),
+            path_in_store: value.path_in_store.into(),
+            metadata: value.metadata.into(),
+            fields: value
+                .fields
+                .into_iter()
+                .map(|field| JsonFieldDescription {
+                    name: field.name,
+                    data_type: field.data_type,
+                    nullable: field.nullable,
+                })
+                .collect(),
+            objects: value
+                .objects
+                .into_iter()
+                .map(|object| JsonObjectDescription {
+                    path: object.path,
+                    size: object.size,
+                    crc32: object.crc32,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<JsonSessionManifest> for types::SessionManifest<JsonMetadataBlob> {
+    type Error = Error;
+
+    fn try_from(value: JsonSessionManifest) -> Result<Self, Error> {
+        if value.version > SESSION_MANIFEST_VERSION {
+            return Err(Error::DeserializationError(format!(
+                "unsupported session manifest version {}",
+                value.version
+            )));
+        }
+
+        Ok(Self {
+            uuid: parse(&value.uuid, "session UUID")?,
+            locator: parse(&value.locator, "session locator")?,
+            created_at: value.created_at.into(),
+            completed_at: value.completed_at.into(),
+            sequence: types::SequenceSnapshot {
+                locator: parse(&value.sequence.locator, "sequence locator")?,
+                created_at: value.sequence.created_at.into(),
+                path_in_store: value.sequence.path_in_store.into(),
+                user_metadata: value.sequence.user_metadata,
+            },
+            topics: value
+                .topics
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            upserted_topics: value
+                .upserted_topics
+                .iter()
+                .map(|locator| parse(locator, "topic locator"))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl TryFrom<JsonTopicManifest> for types::TopicManifest<JsonMetadataBlob> {
+    type Error = Error;
+
+    fn try_from(value: JsonTopicManifest) -> Result<Self, Error> {
+        Ok(Self {
+            uuid: parse(&value.uuid, "topic UUID")?,
+            path_in_store: value.path_in_store.into(),
+            metadata: value.metadata.try_into()?,
+            fields: value
+                .fields
+                .into_iter()
+                .map(|field| types::FieldDescription {
+                    name: field.name,
+                    data_type: field.data_type,
+                    nullable: field.nullable,
+                })
+                .collect(),
+            objects: value
+                .objects
+                .into_iter()
+                .map(|object| types::ObjectDescription {
+                    path: object.path,
+                    size: object.size,
+                    crc32: object.crc32,
+                })
+                .collect(),
+        })
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str, what: &str) -> Result<T, Error> {
+    value
+        .parse()
+        .map_err(|_| Error::DeserializationError(format!("error parsing {}: {}", what, value)))
+}
+
+impl TryFrom<Vec<u8>> for JsonSessionManifest {
+    type Error = Error;
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        serde_json::from_slice(&bytes).map_err(|e| Error::DeserializationError(e.to_string()))
+    }
+}
+
+impl TryInto<Vec<u8>> for JsonSessionManifest {
+    type Error = Error;
+    fn try_into(self) -> Result<Vec<u8>, Self::Error> {
+        serde_json::to_vec(&self).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+}
+
+/// Version of the layout of the catalog manifests written by this build.
+const CATALOG_MANIFEST_VERSION: u32 = 1;
+
+/// JSON representation of a [`types::CatalogManifest`], published in the store for the
+/// external engines.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonCatalogManifest {
+    pub version: u32,
+    pub sequences: Vec<JsonCatalogSequence>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonCatalogSequence {
+    pub locator: String,
+    pub created_at: i64,
+    pub path_in_store: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_metadata: Option<JsonMetadataBlob>,
+    pub topics: Vec<JsonCatalogTopic>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonCatalogTopic {
+    pub locator: String,
+    pub path_in_store: String,
+    pub metadata: JsonTopicMetadata,
+    pub fields: Vec<JsonFieldDescription>,
+    pub data_files: Vec<String>,
+}
+
+impl From<types::CatalogManifest<JsonMetadataBlob>> for JsonCatalogManifest {
+    fn from(value: types::CatalogManifest<JsonMetadataBlob>) -> Self {
+        Self {
+            version: CATALOG_MANIFEST_VERSION,
+            sequences: value
+                .sequences
+                .into_iter()
+                .map(|sequence| JsonCatalogSequence {
+                    locator: sequence.locator.to_string(),
+                    created_at: sequence.created_at.as_i64(),
+                    path_in_store: sequence.path_in_store.into(),
+                    user_metadata: sequence.user_metadata,
+                    topics: sequence.topics.into_iter().map(Into::into).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<types::CatalogTopic<JsonMetadataBlob>> for JsonCatalogTopic {
+    fn from(value: types::CatalogTopic<JsonMetadataBlob>) -> Self {
+        Self {
+            locator: value.locator.to_string(),
+            path_in_store: value.path_in_store.into(),
+            metadata: value.metadata.into(),
+            fields: value
+                .fields
+                .into_iter()
+                .map(|field| JsonFieldDescription {
+                    name: field.name,
+                    data_type: field.data_type,
+                    nullable: field.nullable,
+                })
+                .collect(),
+            data_files: value.data_files,
+        }
+    }
+}
+
+impl TryFrom<JsonCatalogManifest> for types::CatalogManifest<JsonMetadataBlob> {
+    type Error = Error;
+
+    fn try_from(value: JsonCatalogManifest) -> Result<Self, Error> {
+        if value.version > CATALOG_MANIFEST_VERSION {
+            return Err(Error::DeserializationError(format!(
+                "unsupported catalog manifest version {}",
+                value.version
+            )));
+        }
+
+        Ok(Self {
+            sequences: value
+                .sequences
+                .into_iter()
+                .map(|sequence| {
+                    Ok(types::CatalogSequence {
+                        locator: parse(&sequence.locator, "sequence locator")?,
+                        created_at: sequence.created_at.into(),
+                        path_in_store: sequence.path_in_store.into(),
+                        user_metadata: sequence.user_metadata,
+                        topics: sequence
+                            .topics
+                            .into_iter()
+                            .map(TryInto::try_into)
+                            .collect::<Result<_, _>>()?,
+                    })
+                })
+                .collect::<Result<_, Error>>()?,
+        })
+    }
+}
+
+impl TryFrom<JsonCatalogTopic> for types::CatalogTopic<JsonMetadataBlob> {
+    type Error = Error;
+
+    fn try_from(value: JsonCatalogTopic) -> Result<Self, Error> {
+        Ok(Self {
+            locator: parse(&value.locator, "topic locator")?,
+            path_in_store: value.path_in_store.into(),
+            metadata: value.metadata.try_into()?,
+            fields: value
+                .fields
+                .into_iter()
+                .map(|field| types::FieldDescription {
+                    name: field.name,
+                    data_type: field.data_type,
+                    nullable: field.nullable,
+                })
+                .collect(),
+            data_files: value.data_files,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for JsonCatalogManifest {
+    type Error = Error;
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        serde_json::from_slice(&bytes).map_err(|e| Error::DeserializationError(e.to_string()))
+    }
+}
+
+impl TryInto<Vec<u8>> for JsonCatalogManifest {
+    type Error = Error;
+    fn try_into(self) -> Result<Vec<u8>, Self::Error> {
+        serde_json::to_vec(&self).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+}
+
+/// Version of the layout of the sequence archives written by this build.
+const SEQUENCE_ARCHIVE_VERSION: u32 = 1;
+
+/// JSON representation of a [`types::SequenceArchive`], written at the root of the archive.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonSequenceArchive {
+    pub version: u32,
+    pub uuid: String,
+    pub locator: String,
+    pub created_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_metadata: Option<JsonMetadataBlob>,
+    pub topics: Vec<JsonArchivedTopic>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonArchivedTopic {
+    pub uuid: String,
+    pub locator: String,
+    pub ontology_metadata: JsonTopicOntologyMetadata,
+    /// Registered schema, encoded as a base64 Arrow IPC message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arrow_schema: Option<String>,
+    pub rows: u64,
+    pub chunks: Vec<String>,
+}
+
+impl From<types::SequenceArchive<JsonMetadataBlob>> for JsonSequenceArchive {
+    fn from(value: types::SequenceArchive<JsonMetadataBlob>) -> Self {
+        Self {
+            version: SEQUENCE_ARCHIVE_VERSION,
+            uuid: value.uuid.to_string(),
+            locator: value.locator.to_string(),
+            created_at: value.created_at.as_i64(),
+            user_metadata: value.user_metadata,
+            topics: value
+                .topics
+                .into_iter()
+                .map(|topic| JsonArchivedTopic {
+                    uuid: topic.uuid.to_string(),
+                    locator: topic.locator.to_string(),
+                    ontology_metadata: topic.ontology_metadata.into(),
+                    arrow_schema: topic.arrow_schema.map(|schema| BASE64.encode(schema)),
+                    rows: topic.rows,
+                    chunks: topic.chunks,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<JsonSequenceArchive> for types::SequenceArchive<JsonMetadataBlob> {
+    type Error = Error;
+
+    fn try_from(value: JsonSequenceArchive) -> Result<Self, Error> {
+        if value.version > SEQUENCE_ARCHIVE_VERSION {
+            return Err(Error::DeserializationError(format!(
+                "unsupported sequence archive version {}",
+                value.version
+            )));
+        }
+
+        Ok(Self {
+            uuid: parse(&value.uuid, "sequence UUID")?,
+            locator: parse(&value.locator, "sequence locator")?,
+            created_at: value.created_at.into(),
+            user_metadata: value.user_metadata,
+            topics: value
+                .topics
+                .into_iter()
+                .map(|topic| {
+                    Ok(types::ArchivedTopic {
+                        uuid: parse(&topic.uuid, "topic UUID")?,
+                        locator: parse(&topic.locator, "topic locator")?,
+                        ontology_metadata: topic.ontology_metadata.into(),
+                        arrow_schema: topic
+                            .arrow_schema
+                            .map(|schema| {
+                                BASE64.decode(schema).map_err(|e| {
+                                    Error::DeserializationError(format!(
+                                        "error parsing arrow schema: {}",
+                                        e
+                                    ))
+                                })
+                            })
+                            .transpose()?,
+                        rows: topic.rows,
+                        chunks: topic.chunks,
+                    })
+                })
+                .collect::<Result<_, Error>>()?,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for JsonSequenceArchive {
+    type Error = Error;
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        serde_json::from_slice(&bytes).map_err(|e| Error::DeserializationError(e.to_string()))
+    }
+}
+
+impl TryInto<Vec<u8>> for JsonSequenceArchive {
+    type Error = Error;
+    fn try_into(self) -> Result<Vec<u8>, Self::Error> {
+        serde_json::to_vec(&self).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+}
+
+/// Version of the layout of the session attestations written by this build.
+const SESSION_ATTESTATION_VERSION: u32 = 1;
+
+/// JSON representation of a [`types::SessionAttestation`], persisted in the store next to
+/// the manifests. Binary fields are hex encoded.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonSessionAttestation {
+    pub version: u32,
+    pub session_uuid: String,
+    pub manifest_sha256: String,
+    pub algorithm: String,
+    pub public_key: String,
+    pub signature: String,
+    pub created_at: i64,
+}
+
+impl From<types::SessionAttestation> for JsonSessionAttestation {
+    fn from(value: types::SessionAttestation) -> Self {
+        Self {
+            version: SESSION_ATTESTATION_VERSION,
+            session_uuid: value.session_uuid.to_string(),
+            manifest_sha256: hex_encode(&value.manifest_sha256),
+            algorithm: value.algorithm,
+            public_key: hex_encode(&value.public_key),
+            signature: hex_encode(&value.signature),
+            created_at: value.created_at.as_i64(),
+        }
+    }
+}
+
+impl TryFrom<JsonSessionAttestation> for types::SessionAttestation {
+    type Error = Error;
+
+    fn try_from(value: JsonSessionAttestation) -> Result<Self, Error> {
+        if value.version > SESSION_ATTESTATION_VERSION {
+            return Err(Error::DeserializationError(format!(
+                "unsupported session attestation version {}",
+                value.version
+            )));
+        }
+
+        Ok(Self {
+            session_uuid: parse(&value.session_uuid, "session UUID")?,
+            manifest_sha256: hex_decode(&value.manifest_sha256, "manifest digest")?,
+            algorithm: value.algorithm,
+            public_key: hex_decode(&value.public_key, "public key")?,
+            signature: hex_decode(&value.signature, "signature")?,
+            created_at: value.created_at.into(),
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for JsonSessionAttestation {
+    type Error = Error;
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        serde_json::from_slice(&bytes).map_err(|e| Error::DeserializationError(e.to_string()))
+    }
+}
+
+impl TryInto<Vec<u8>> for JsonSessionAttestation {
+    type Error = Error;
+    fn try_into(self) -> Result<Vec<u8>, Self::Error> {
+        serde_json::to_vec(&self).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(value: &str, what: &str) -> Result<Vec<u8>, Error> {
+    let invalid = || Error::DeserializationError(format!("error parsing {}: {}", what, value));
+
+    if !value.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+
+    (0..value.len())
+        .step_by(2)
+        .map(|i| {
+            value
+                .get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_manifest_roundtrip() {
+        let sequence: types::SequenceLocator = "run".parse().unwrap();
+        let session_uuid = types::Uuid::new();
+        let topic_locator: types::TopicLocator = "run/camera".parse().unwrap();
+
+        let manifest = types::SessionManifest {
+            uuid: session_uuid.clone(),
+            locator: types::SessionLocator::new(sequence.clone()),
+            created_at: 10.into(),
+            completed_at: 20.into(),
+            sequence: types::SequenceSnapshot {
+                locator: sequence,
+                created_at: 5.into(),
+                path_in_store: "sq_folder".to_owned().into(),
+                user_metadata: Some(serde_json::json!({ "driver": "jon" }).into()),
+            },
+            topics: vec![types::TopicManifest {
+                uuid: types::Uuid::new(),
+                path_in_store: "tp_folder".to_owned().into(),
+                metadata: types::TopicMetadata::new(
+                    types::TopicMetadataProperties::new(
+                        topic_locator.clone(),
+                        session_uuid.clone(),
+                    ),
+                    types::TopicOntologyMetadata::new(
+                        types::TopicOntologyProperties {
+                            ontology_tag: "image".to_owned(),
+                            serialization_format: types::Format::Default,
+                            sort_key: None,
+                            dedup_policy: types::DedupPolicy::None,
+                            primary_key: None,
+                        },
+                        None,
+                    ),
+                ),
+                fields: vec![types::FieldDescription {
+                    name: "timestamp_ns".to_owned(),
+                    data_type: "Int64".to_owned(),
+                    nullable: false,
+                }],
+                objects: vec![types::ObjectDescription {
+                    path: "data/00000.parquet".to_owned(),
+                    size: 128,
+                    crc32: 42,
+                }],
+            }],
+            upserted_topics: vec!["run/lidar".parse().unwrap()],
+        };
+
+        let bytes: Vec<u8> = JsonSessionManifest::from(manifest).try_into().unwrap();
+        let decoded: types::SessionManifest<JsonMetadataBlob> =
+            JsonSessionManifest::try_from(bytes)
+                .unwrap()
+                .try_into()
+                .unwrap();
+
+        assert_eq!(decoded.uuid, session_uuid);
+        assert_eq!(decoded.sequence.locator, "run");
+        assert_eq!(
+            serde_json::Value::from(decoded.sequence.user_metadata.unwrap())["driver"],
+            "jon"
+        );
+        assert_eq!(
+            decoded.topics[0].metadata.properties.resource_locator,
+            topic_locator
+        );
+        assert_eq!(decoded.topics[0].objects[0].crc32, 42);
+        assert_eq!(decoded.topics[0].fields[0].data_type, "Int64");
+        assert_eq!(decoded.upserted_topics[0].to_string(), "run/lidar");
+
+        // Manifests written by a newer build are rejected
+        let json = JsonSessionManifest::try_from(
+            serde_json::to_vec(&serde_json::json!({
+                "version": SESSION_MANIFEST_VERSION + 1,
+                "uuid": session_uuid.to_string(),
+                "locator": decoded.locator.to_string(),
+                "created_at": 0,
+                "completed_at": 0,
+                "sequence": { "locator": "run", "created_at": 0, "path_in_store": "sq" },
+                "topics": [],
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(types::SessionManifest::<JsonMetadataBlob>::try_from(json).is_err());
+    }
+
+    #[test]
+    fn sequence_archive_roundtrip() {
+        let archive = types::SequenceArchive {
+            uuid: types::Uuid::new(),
+            locator: "run".parse().unwrap(),
+            created_at: 5.into(),
+            user_metadata: Some(serde_json::json!({ "driver": "jon" }).into()),
+            topics: vec![types::ArchivedTopic {
+                uuid: types::Uuid::new(),
+                locator: "run/imu".parse().unwrap(),
+                ontology_metadata: types::TopicOntologyMetadata::new(
+                    types::TopicOntologyProperties {
+                        ontology_tag: "imu".to_owned(),
+                        serialization_format: types::Format::Default,
+                        sort_key: None,
+                        dedup_policy: types::DedupPolicy::None,
+                        primary_key: None,
+                    },
+                    None,
+                ),
+                arrow_schema: Some(vec![0xff, 0x00, 0x2a]),
+                rows: 10,
+                chunks: vec!["topics/0/00000.arrow".to_owned()],
+            }],
+        };
+        let topic_uuid = archive.topics[0].uuid.clone();
+
+        let bytes: Vec<u8> = JsonSequenceArchive::from(archive).try_into().unwrap();
+        let decoded: types::SequenceArchive<JsonMetadataBlob> =
+            JsonSequenceArchive::try_from(bytes)
+                .unwrap()
+                .try_into()
+                .unwrap();
+
+        assert_eq!(decoded.locator, "run");
+        assert_eq!(decoded.topics[0].uuid, topic_uuid);
+        assert_eq!(
+            decoded.topics[0].ontology_metadata.properties.ontology_tag,
+            "imu"
+        );
+        assert_eq!(decoded.topics[0].arrow_schema, Some(vec![0xff, 0x00, 0x2a]));
+        assert_eq!(decoded.topics[0].chunks, ["topics/0/00000.arrow"]);
+    }
+
+    #[test]
+    fn session_attestation_roundtrip() {
+        let attestation = types::SessionAttestation {
+            session_uuid: types::Uuid::new(),
+            manifest_sha256: vec![0x00, 0x1f, 0xab, 0xff],
+            algorithm: "ed25519".to_owned(),
+            public_key: vec![1, 2, 3],
+            signature: vec![4, 5, 6],
+            created_at: 42.into(),
+        };
+
+        let json = JsonSessionAttestation::from(attestation.clone());
+        assert_eq!(json.manifest_sha256, "001fabff");
+
+        let bytes: Vec<u8> = json.try_into().unwrap();
+        let decoded: types::SessionAttestation = JsonSessionAttestation::try_from(bytes)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(decoded, attestation);
+
+        assert!(hex_decode("abc", "digest").is_err());
+        assert!(hex_decode("zz", "digest").is_err());
+    }
+}
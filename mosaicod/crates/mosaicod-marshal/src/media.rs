@@ -0,0 +1,108 @@
+use mosaicod_core::types::{self, MetadataError};
+use serde::{Deserialize, Serialize};
+
+type Error = MetadataError;
+
+/// JSON representation of a [`types::MediaIndex`], used both to persist the index in the
+/// store and to send it to clients.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonMediaIndex {
+    pub segments: Vec<JsonMediaSegment>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JsonMediaSegment {
+    pub file: String,
+    pub timestamp_ns_start: i64,
+    pub timestamp_ns_end: i64,
+    pub offset: u64,
+    pub length: u64,
+    pub row_count: u64,
+}
+
+impl From<types::MediaSegment> for JsonMediaSegment {
+    fn from(value: types::MediaSegment) -> Self {
+        Self {
+            file: value.file,
+            timestamp_ns_start: value.start.as_i64(),
+            timestamp_ns_end: value.end.as_i64(),
+            offset: value.offset,
+            length: value.length,
+            row_count: value.row_count,
+        }
+    }
+}
+
+impl From<JsonMediaSegment> for types::MediaSegment {
+    fn from(value: JsonMediaSegment) -> Self {
+        Self {
+            file: value.file,
+            start: value.timestamp_ns_start.into(),
+            end: value.timestamp_ns_end.into(),
+            offset: value.offset,
+            length: value.length,
+            row_count: value.row_count,
+        }
+    }
+}
+
+impl<'a> FromIterator<&'a types::MediaSegment> for JsonMediaIndex {
+    fn from_iter<I: IntoIterator<Item = &'a types::MediaSegment>>(iter: I) -> Self {
+        Self {
+            segments: iter.into_iter().cloned().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<JsonMediaIndex> for types::MediaIndex {
+    fn from(value: JsonMediaIndex) -> Self {
+        value
+            .segments
+            .into_iter()
+            .map(Into::into)
+            .collect::<Vec<types::MediaSegment>>()
+            .into()
+    }
+}
+
+impl TryFrom<Vec<u8>> for JsonMediaIndex {
+    type Error = Error;
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        serde_json::from_slice(&bytes).map_err(|e| Error::DeserializationError(e.to_string()))
+    }
+}
+
+impl TryInto<Vec<u8>> for JsonMediaIndex {
+    type Error = Error;
+    fn try_into(self) -> Result<Vec<u8>, Self::Error> {
+        serde_json::to_vec(&self).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn media_index_roundtrip() {
+        let segment = types::MediaSegment {
+            file: "data/00000.parquet".to_owned(),
+            start: 10.into(),
+            end: 20.into(),
+            offset: 4,
+            length: 128,
+            row_count: 2,
+        };
+        let index = types::MediaIndex::from(vec![segment]);
+
+        let bytes: Vec<u8> = index
+            .segments()
+            .iter()
+            .collect::<JsonMediaIndex>()
+            .try_into()
+            .unwrap();
+        let decoded: types::MediaIndex = JsonMediaIndex::try_from(bytes).unwrap().into();
+
+        assert_eq!(decoded, index);
+    }
+}
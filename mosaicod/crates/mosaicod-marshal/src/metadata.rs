@@ -1,4 +1,4 @@
-use super::Format;
+use super::{DedupPolicy, Format};
 use mosaicod_core::types::{self, MetadataBlob, MetadataError};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -76,6 +76,12 @@ impl JsonSequenceMetadata {
 pub struct JsonTopicOntologyProperties {
     pub serialization_format: Format,
     pub ontology_tag: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dedup_policy: Option<DedupPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary_key: Option<String>,
 }
 
 impl From<JsonTopicOntologyProperties> for types::TopicOntologyProperties {
@@ -83,6 +89,9 @@ impl From<JsonTopicOntologyProperties> for types::TopicOntologyProperties {
         Self {
             ontology_tag: value.ontology_tag,
             serialization_format: value.serialization_format.into(),
+            sort_key: value.sort_key,
+            dedup_policy: value.dedup_policy.map(Into::into).unwrap_or_default(),
+            primary_key: value.primary_key,
         }
     }
 }
@@ -92,6 +101,10 @@ impl From<types::TopicOntologyProperties> for JsonTopicOntologyProperties {
         Self {
             ontology_tag: value.ontology_tag,
             serialization_format: value.serialization_format.into(),
+            sort_key: value.sort_key,
+            dedup_policy: (value.dedup_policy != types::DedupPolicy::None)
+                .then(|| value.dedup_policy.into()),
+            primary_key: value.primary_key,
         }
     }
 }
@@ -122,6 +135,41 @@ impl JsonTopicOntologyMetadata {
     }
 }
 
+/// Field metadata key holding the description of a column
+const FIELD_METADATA_DESCRIPTION: &str = "mosaico:description";
+/// Field metadata key holding the unit of measure of a column
+const FIELD_METADATA_UNIT: &str = "mosaico:unit";
+
+/// Converts the documentation of a column into the metadata of its arrow field.
+///
+/// Missing description and unit are omitted from the metadata.
+pub fn column_field_metadata(column: types::ColumnDescription) -> HashMap<String, String> {
+    [
+        (FIELD_METADATA_DESCRIPTION, column.description),
+        (FIELD_METADATA_UNIT, column.unit),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| value.map(|value| (key.to_owned(), value)))
+    .collect()
+}
+
+/// Extracts the documentation of a column from the metadata of its arrow field.
+///
+/// Returns [`None`] if the metadata contains neither a description nor a unit.
+pub fn column_from_field_metadata(
+    name: &str,
+    metadata: &HashMap<String, String>,
+) -> Option<types::ColumnDescription> {
+    let description = metadata.get(FIELD_METADATA_DESCRIPTION).cloned();
+    let unit = metadata.get(FIELD_METADATA_UNIT).cloned();
+
+    (description.is_some() || unit.is_some()).then(|| types::ColumnDescription {
+        name: name.to_owned(),
+        description,
+        unit,
+    })
+}
+
 impl From<JsonTopicOntologyMetadata> for types::TopicOntologyMetadata<JsonMetadataBlob> {
     fn from(value: JsonTopicOntologyMetadata) -> Self {
         Self {
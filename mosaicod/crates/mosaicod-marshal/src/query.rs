@@ -163,6 +163,7 @@ struct Query {
     sequence: Option<Sequence>,
     topic: Option<Topic>,
     ontology: Option<Ontology>,
+    approximate: Option<Approximate>,
 }
 
 impl TryInto<query::Filter> for Query {
@@ -172,10 +173,38 @@ impl TryInto<query::Filter> for Query {
             sequence: self.sequence.map(|v| v.try_into()).transpose()?,
             topic: self.topic.map(|v| v.try_into()).transpose()?,
             ontology: self.ontology.map(|v| v.try_into()).transpose()?,
+            sampling: self.approximate.map(|v| v.try_into()).transpose()?,
         })
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct Approximate {
+    fraction: f64,
+    #[serde(flatten)]
+    method: SamplingMethod,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum SamplingMethod {
+    Uniform,
+    TimeStratified { stratum_ns: i64 },
+}
+
+impl TryInto<query::Sampling> for Approximate {
+    type Error = query::Error;
+    fn try_into(self) -> Result<query::Sampling, Self::Error> {
+        let method = match self.method {
+            SamplingMethod::Uniform => query::SamplingMethod::Uniform,
+            SamplingMethod::TimeStratified { stratum_ns } => {
+                query::SamplingMethod::TimeStratified { stratum_ns }
+            }
+        };
+        query::Sampling::try_new(self.fraction, method)
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct Ontology {
     #[serde(flatten)]
@@ -267,6 +296,7 @@ pub struct Topic {
     ontology_tag: Option<Op>,
     serialization_format: Option<Op>,
     user_metadata: Option<HashMap<String, Op>>,
+    column: Option<Column>,
 }
 
 impl TryInto<query::TopicFilter> for Topic {
@@ -311,6 +341,36 @@ impl TryInto<query::TopicFilter> for Topic {
                 })?,
 
             user_metadata: convert_user_metadata(self.user_metadata)?,
+
+            column: self.column.map(|v| v.try_into()).transpose()?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Column {
+    name: Option<Op>,
+    description: Option<Op>,
+    unit: Option<Op>,
+}
+
+impl TryInto<query::ColumnFilter> for Column {
+    type Error = query::Error;
+
+    fn try_into(self) -> Result<query::ColumnFilter, Self::Error> {
+        let convert = |op: Option<Op>, field: &str| {
+            op.map(|v| v.try_into())
+                .transpose()
+                .map_err(|e| query::Error::OpError {
+                    field: format!("topic.column.{field}"),
+                    err: e,
+                })
+        };
+
+        Ok(query::ColumnFilter {
+            name: convert(self.name, "name")?,
+            description: convert(self.description, "description")?,
+            unit: convert(self.unit, "unit")?,
         })
     }
 }
@@ -102,6 +102,29 @@ impl ClausesCompiler {
         self
     }
 
+    /// Adds the clauses compiled by `nested` as a single clause built by `wrap` from their
+    /// conjunction, e.g. to match the clauses inside a subquery.
+    ///
+    /// If `nested` has no clauses nothing is added.
+    pub fn nested(mut self, nested: ClausesCompiler, wrap: impl FnOnce(&str) -> String) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+
+        match nested.compile() {
+            Ok(mut nested) => {
+                if !nested.is_unfiltered() {
+                    let clause = wrap(&nested.clauses.join(" AND "));
+                    self.result.clauses.push(clause);
+                    self.result.values.append(&mut nested.values);
+                }
+            }
+            Err(err) => self.error = Some(err),
+        }
+
+        self
+    }
+
     // es: field = topic.user_metadata
     pub fn ontology_expr_group<F, V>(
         mut self,
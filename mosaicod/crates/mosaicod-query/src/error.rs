@@ -13,6 +13,18 @@ pub enum Error {
     #[error("bad field `{field}`")]
     BadField { field: String },
 
+    #[error("field `{field}` has more than {limit} distinct values")]
+    TooManyValues { field: String, limit: usize },
+
+    #[error("bad unit :: {0}")]
+    BadUnit(#[from] core::types::UnitError),
+
+    #[error("bad sampling :: {0}")]
+    BadSampling(String),
+
+    #[error("bad statement :: {0}")]
+    BadStatement(String),
+
     #[error("datafusion backend error")]
     DataFusion(#[from] datafusion::error::DataFusionError),
 
@@ -37,10 +49,39 @@ impl Error {
     pub fn bad_field(field_name: String) -> Self {
         Self::BadField { field: field_name }
     }
+
+    /// Returns true if the error is caused by the store being temporarily unavailable.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::StoreError(err) => err.is_transient(),
+            Self::DataFusion(err) => match err.find_root() {
+                datafusion::error::DataFusionError::ObjectStore(err) => {
+                    mosaicod_store::is_transient(err)
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Returns the reason why the query engine ran out of resources (e.g. memory), [`None`]
+    /// if the error has a different cause.
+    pub fn resources_exhausted(&self) -> Option<&str> {
+        match self {
+            Self::DataFusion(err) => match err.find_root() {
+                datafusion::error::DataFusionError::ResourcesExhausted(reason) => Some(reason),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
 }
 
 impl core::error::PublicError for Error {
     fn error(&self) -> core::Error {
+        if self.is_transient() {
+            return core::Error::unavailable("store".to_owned());
+        }
         core::Error::internal(Some("query engine failed".to_owned()))
     }
 }
@@ -129,7 +129,7 @@ impl IsSupportedOp for Value {
     }
 
     fn support_in(&self) -> bool {
-        matches!(self, Self::Boolean(_))
+        matches!(self, Self::Integer(_) | Self::Text(_))
     }
 
     fn support_match(&self) -> bool {
@@ -199,14 +199,41 @@ where
     }
 }
 
+/// An ontology field, optionally compared in a unit different from the one of the stored
+/// data.
+///
+/// Fields are written as `<ontology_tag>.<field_path>`, or as
+/// `convert(<ontology_tag>.<field_path>, '<unit>')` to express the compared values in `unit`.
 #[derive(Debug, Clone)]
 pub struct OntologyField {
     value: String,
     tag_offset: usize,
+    unit: Option<String>,
 }
 
 impl OntologyField {
     pub fn try_new(v: String) -> Result<Self, super::Error> {
+        if let Some(args) = v.strip_prefix("convert(").and_then(|v| v.strip_suffix(')')) {
+            let (field, unit) = args
+                .split_once(',')
+                .map(|(field, unit)| (field.trim(), unit.trim()))
+                .ok_or_else(|| super::Error::bad_field(v.clone()))?;
+            let unit = unit
+                .strip_prefix('\'')
+                .and_then(|unit| unit.strip_suffix('\''))
+                .ok_or_else(|| super::Error::bad_field(v.clone()))?;
+
+            types::Unit::try_from_symbol(unit)?;
+
+            let mut field = Self::try_new_plain(field.to_owned())?;
+            field.unit = Some(unit.to_owned());
+            return Ok(field);
+        }
+
+        Self::try_new_plain(v)
+    }
+
+    fn try_new_plain(v: String) -> Result<Self, super::Error> {
         let ontology_tag = v.split(".").next().ok_or_else(|| super::Error::BadField {
             field: v.to_string(),
         })?;
@@ -215,9 +242,22 @@ impl OntologyField {
         Ok(Self {
             value: v,
             tag_offset: len,
+            unit: None,
         })
     }
 
+    /// Unit in which the values compared with the field are expressed, if it differs from
+    /// the unit of the stored data.
+    pub fn unit(&self) -> Option<&str> {
+        self.unit.as_deref()
+    }
+
+    /// Returns the field without unit conversion.
+    pub fn without_unit(mut self) -> Self {
+        self.unit = None;
+        self
+    }
+
     pub fn ontology_tag(&self) -> &str {
         &self.value[..self.tag_offset]
     }
@@ -436,6 +476,36 @@ where
     }
 }
 
+impl Op<Value> {
+    /// Applies `f` to the values of the operation, e.g. to convert them into another unit.
+    ///
+    /// `f` needs to be monotonically increasing to preserve the meaning of ordering operations.
+    /// Only numeric values can be mapped.
+    pub fn try_map_numeric(self, f: impl Fn(Float) -> Float) -> Result<Self, OpError> {
+        let map = |v: Value| match v {
+            Value::Integer(v) => Ok(Value::Float(f(v as Float))),
+            Value::Float(v) => Ok(Value::Float(f(v))),
+            Value::Text(_) | Value::Boolean(_) => Err(OpError::WrongType),
+        };
+
+        Ok(match self {
+            Self::Eq(v) => Self::Eq(map(v)?),
+            Self::Neq(v) => Self::Neq(map(v)?),
+            Self::Leq(v) => Self::Leq(map(v)?),
+            Self::Geq(v) => Self::Geq(map(v)?),
+            Self::Lt(v) => Self::Lt(map(v)?),
+            Self::Gt(v) => Self::Gt(map(v)?),
+            Self::Ex => Self::Ex,
+            Self::Nex => Self::Nex,
+            Self::Between(range) => {
+                Self::Between(Range::try_new(map(range.min)?, map(range.max)?)?)
+            }
+            Self::In(items) => Self::In(items.into_iter().map(map).collect::<Result<_, _>>()?),
+            Self::Match(_) => return Err(OpError::WrongType),
+        })
+    }
+}
+
 /// The root object representing a complete search query.
 ///
 /// A query allows filtering across three distinct domains:
@@ -449,6 +519,8 @@ pub struct Filter {
     pub sequence: Option<SequenceFilter>,
     pub topic: Option<TopicFilter>,
     pub ontology: Option<OntologyFilter>,
+    /// If set, the query is approximated searching a sample of the topic data
+    pub sampling: Option<Sampling>,
 }
 
 impl Filter {
@@ -468,6 +540,51 @@ impl Filter {
     }
 }
 
+/// Sampling of the topic data searched by an approximate query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sampling {
+    fraction: f64,
+    method: SamplingMethod,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SamplingMethod {
+    /// Each row is sampled independently from the others
+    Uniform,
+    /// The same fraction of rows is sampled in each time interval of `stratum_ns`
+    /// nanoseconds, so that no interval is left out from the sample
+    TimeStratified { stratum_ns: i64 },
+}
+
+impl Sampling {
+    /// Creates a sampling of the given fraction of the rows, in the range `(0, 1]`.
+    pub fn try_new(fraction: f64, method: SamplingMethod) -> Result<Self, super::Error> {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            return Err(super::Error::BadSampling(format!(
+                "fraction {fraction} is not in the range (0, 1]"
+            )));
+        }
+
+        if let SamplingMethod::TimeStratified { stratum_ns } = method
+            && stratum_ns <= 0
+        {
+            return Err(super::Error::BadSampling(format!(
+                "stratum of {stratum_ns}ns is not positive"
+            )));
+        }
+
+        Ok(Self { fraction, method })
+    }
+
+    pub fn fraction(&self) -> f64 {
+        self.fraction
+    }
+
+    pub fn method(&self) -> &SamplingMethod {
+        &self.method
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SequenceFilter {
     pub name: Option<Op<Text>>,
@@ -488,6 +605,8 @@ pub struct TopicFilter {
     pub ontology_tag: Option<Op<Text>>,
     pub serialization_format: Option<Op<Text>>,
     pub user_metadata: HashMap<String, Op<Value>>,
+    /// Matches the topics having at least one column satisfying the filter
+    pub column: Option<ColumnFilter>,
 }
 
 impl TopicFilter {
@@ -497,6 +616,21 @@ impl TopicFilter {
             && self.user_metadata.is_empty()
             && self.ontology_tag.is_none()
             && self.serialization_format.is_none()
+            && self.column.as_ref().is_none_or(ColumnFilter::is_empty)
+    }
+}
+
+/// Filter on the documentation of the columns of a topic
+#[derive(Debug, Clone, Default)]
+pub struct ColumnFilter {
+    pub name: Option<Op<Text>>,
+    pub description: Option<Op<Text>>,
+    pub unit: Option<Op<Text>>,
+}
+
+impl ColumnFilter {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.description.is_none() && self.unit.is_none()
     }
 }
 
@@ -511,6 +645,44 @@ mod tests {
         assert_eq!(oc.field(), "info.height");
         assert_eq!(oc.ontology_tag(), "image");
         assert_eq!(oc.value(), "image.info.height");
+        assert!(oc.unit().is_none());
+    }
+
+    #[test]
+    fn ontology_field_convert() {
+        let oc = OntologyField::try_new("convert(car.speed, 'km/h')".into()).unwrap();
+
+        assert_eq!(oc.field(), "speed");
+        assert_eq!(oc.ontology_tag(), "car");
+        assert_eq!(oc.unit(), Some("km/h"));
+
+        assert!(OntologyField::try_new("convert(car.speed, 'furlong')".into()).is_err());
+        assert!(OntologyField::try_new("convert(car.speed)".into()).is_err());
+        assert!(OntologyField::try_new("convert(car.speed, km/h)".into()).is_err());
+    }
+
+    #[test]
+    fn op_map_numeric() {
+        let op = Op::Between(Range::try_new(Value::Integer(36), Value::Float(72.0)).unwrap());
+        let op = op.try_map_numeric(|v| v / 3.6).unwrap();
+        assert_eq!(
+            op,
+            Op::Between(Range::try_new(Value::Float(10.0), Value::Float(20.0)).unwrap())
+        );
+
+        assert!(
+            Op::Eq(Value::Text("a".into()))
+                .try_map_numeric(|v| v)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn op_in_supported_values() {
+        assert!(Op::In(vec![Value::Integer(1), Value::Integer(2)]).is_supported_op());
+        assert!(Op::In(vec![Value::Text("a".into())]).is_supported_op());
+        assert!(!Op::In(vec![Value::Float(1.0)]).is_supported_op());
+        assert!(!Op::In(Vec::<Value>::new()).is_supported_op());
     }
 
     #[test]
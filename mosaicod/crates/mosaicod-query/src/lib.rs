@@ -12,5 +12,7 @@ pub use builder::*;
 mod timeseries;
 pub use timeseries::*;
 
+mod memory;
+
 mod error;
 pub use error::*;
@@ -0,0 +1,112 @@
+use datafusion::common::resources_datafusion_err;
+use datafusion::error::Result;
+use datafusion::execution::memory_pool::{
+    MemoryConsumer, MemoryLimit, MemoryPool, MemoryReservation,
+};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Memory pool limiting the memory used by a single query.
+///
+/// Allocations are forwarded to the pool shared by all the queries, so that the limits of
+/// the engine keep being enforced.
+#[derive(Debug)]
+pub(crate) struct QueryMemoryPool {
+    inner: Arc<dyn MemoryPool>,
+    limit: usize,
+    reserved: AtomicUsize,
+}
+
+impl QueryMemoryPool {
+    pub fn new(inner: Arc<dyn MemoryPool>, limit: usize) -> Self {
+        Self {
+            inner,
+            limit,
+            reserved: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl MemoryPool for QueryMemoryPool {
+    fn register(&self, consumer: &MemoryConsumer) {
+        self.inner.register(consumer);
+    }
+
+    fn unregister(&self, consumer: &MemoryConsumer) {
+        self.inner.unregister(consumer);
+    }
+
+    fn grow(&self, reservation: &MemoryReservation, additional: usize) {
+        self.inner.grow(reservation, additional);
+        self.reserved.fetch_add(additional, Ordering::Relaxed);
+    }
+
+    fn shrink(&self, reservation: &MemoryReservation, shrink: usize) {
+        self.inner.shrink(reservation, shrink);
+        self.reserved.fetch_sub(shrink, Ordering::Relaxed);
+    }
+
+    fn try_grow(&self, reservation: &MemoryReservation, additional: usize) -> Result<()> {
+        self.reserved
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |reserved| {
+                let reserved = reserved + additional;
+                (reserved <= self.limit).then_some(reserved)
+            })
+            .map_err(|reserved| {
+                resources_datafusion_err!(
+                    "query memory limit of {} bytes exceeded ({} bytes in use, {} more bytes requested by {})",
+                    self.limit,
+                    reserved,
+                    additional,
+                    reservation.consumer().name()
+                )
+            })?;
+
+        if let Err(e) = self.inner.try_grow(reservation, additional) {
+            self.reserved.fetch_sub(additional, Ordering::Relaxed);
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    fn reserved(&self) -> usize {
+        self.reserved.load(Ordering::Relaxed)
+    }
+
+    fn memory_limit(&self) -> MemoryLimit {
+        MemoryLimit::Finite(self.limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::execution::memory_pool::UnboundedMemoryPool;
+
+    #[test]
+    fn query_memory_limit() {
+        let shared: Arc<dyn MemoryPool> = Arc::new(UnboundedMemoryPool::default());
+        let pool: Arc<dyn MemoryPool> = Arc::new(QueryMemoryPool::new(shared.clone(), 100));
+
+        let reservation = MemoryConsumer::new("test").register(&pool);
+        reservation.try_grow(60).unwrap();
+        assert_eq!(pool.reserved(), 60);
+        assert_eq!(shared.reserved(), 60);
+
+        let err = reservation.try_grow(50).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("60 bytes in use, 50 more bytes requested")
+        );
+        assert_eq!(shared.reserved(), 60);
+
+        reservation.shrink(20);
+        reservation.try_grow(50).unwrap();
+        assert_eq!(pool.reserved(), 90);
+
+        drop(reservation);
+        assert_eq!(pool.reserved(), 0);
+        assert_eq!(shared.reserved(), 0);
+    }
+}
@@ -4,14 +4,24 @@
 //!
 //! The engine integrates directly with the configured [`store::Store`] to resolve
 //! paths and access data sources like Parquet files efficiently.
-use super::{Error, OntologyExprGroup, OntologyField, Op, Value};
-use arrow::datatypes::{Schema, SchemaRef};
+use super::memory::QueryMemoryPool;
+use super::{Error, OntologyExprGroup, OntologyField, Op, Sampling, SamplingMethod, Value};
+use arrow::datatypes::{DataType, FieldRef, Schema, SchemaRef};
+use datafusion::common::{Column, TableReference};
+use datafusion::datasource::listing::{ListingTable, ListingTableConfig, ListingTableUrl};
 use datafusion::execution::SendableRecordBatchStream;
+use datafusion::execution::cache::cache_manager::CacheManagerConfig;
+use datafusion::execution::context::SQLOptions;
 use datafusion::execution::disk_manager::DiskManagerBuilder;
 use datafusion::execution::memory_pool::FairSpillPool;
 use datafusion::execution::runtime_env::{RuntimeEnv, RuntimeEnvBuilder};
 use datafusion::functions::core::expr_ext::FieldAccessor;
-use datafusion::functions_aggregate::expr_fn::{max, min};
+use datafusion::functions_aggregate::count::{count_all, count_all_window};
+use datafusion::functions_aggregate::expr_fn::{approx_distinct, avg, count, max, min};
+use datafusion::functions_aggregate::min_max::min_udaf;
+use datafusion::functions_window::expr_fn::row_number;
+use datafusion::logical_expr::expr::WindowFunction;
+use datafusion::logical_expr::{ExprFunctionExt, ExprSchemable, SortExpr};
 use datafusion::prelude::*;
 use datafusion::scalar::ScalarValue;
 use log::trace;
@@ -19,11 +29,27 @@ use mosaicod_core::{params, types};
 use mosaicod_rw::ToParquetProperties;
 use mosaicod_store as store;
 use std::collections::HashMap;
+use std::ops::{Range, RangeBounds};
 use std::path::Path;
 use std::sync::Arc;
 
 pub type TimeseriesEngineRef = Arc<TimeseriesEngine>;
 
+/// Internal columns used to track the write order of the rows while deduplicating
+const WRITE_ORDER_FILE: &str = "__mosaico_write_file";
+const WRITE_ORDER_ROW: &str = "__mosaico_write_row";
+const WRITE_ORDER_RANK: &str = "__mosaico_write_rank";
+const WRITE_ORDER_FIRST_FILE: &str = "__mosaico_write_first_file";
+
+/// Internal columns used to sample the rows of each time stratum
+const SAMPLE_STRATUM: &str = "__mosaico_sample_stratum";
+const SAMPLE_RANK: &str = "__mosaico_sample_rank";
+const SAMPLE_SIZE: &str = "__mosaico_sample_size";
+
+/// Kinds of change reported when reading the changes of a topic
+const CHANGE_INSERT: &str = "insert";
+const CHANGE_UPDATE: &str = "update";
+
 pub struct TimeseriesEngine {
     runtime: Arc<RuntimeEnv>,
     store: Arc<store::Store>,
@@ -37,7 +63,13 @@ impl TimeseriesEngine {
             None
         };
 
-        let mut builder = RuntimeEnvBuilder::new().with_object_store_registry(store.registry());
+        // Data files of upsert topics are added and replaced after creation, listings of the
+        // data folders cannot be cached
+        let cache_config = CacheManagerConfig::default().with_list_files_cache_limit(0);
+
+        let mut builder = RuntimeEnvBuilder::new()
+            .with_object_store_registry(store.registry())
+            .with_cache_manager(cache_config);
 
         if let Some(memory_pool) = memory_pool {
             builder = builder
@@ -53,6 +85,24 @@ impl TimeseriesEngine {
         })
     }
 
+    /// Returns an engine sharing the resources of this one, whose queries can use at most
+    /// `limit_bytes` of memory (in addition to the memory limit of this engine).
+    ///
+    /// Queries exceeding the limit fail with an error reporting the memory in use, see
+    /// [`Error::resources_exhausted`].
+    pub fn with_memory_limit(&self, limit_bytes: usize) -> Result<Self, Error> {
+        let memory_pool = QueryMemoryPool::new(self.runtime.memory_pool.clone(), limit_bytes);
+
+        let runtime = RuntimeEnvBuilder::from_runtime_env(&self.runtime)
+            .with_memory_pool(Arc::new(memory_pool))
+            .build()?;
+
+        Ok(TimeseriesEngine {
+            runtime: Arc::new(runtime),
+            store: self.store.clone(),
+        })
+    }
+
     /// Read time-series data from a path.
     ///
     /// All files in the provided path will be included in the read.
@@ -65,12 +115,314 @@ impl TimeseriesEngine {
         path: impl AsRef<Path>,
         format: types::Format,
         batch_size: Option<usize>,
+    ) -> Result<TimeseriesResult, Error> {
+        self.read_impl(path, format, batch_size, FileOrdering::default())
+            .await
+    }
+
+    /// Read time-series data from a path whose files have a known [`FileOrdering`].
+    ///
+    /// The ordering is exploited by the query engine: files sorted across each other are
+    /// merged instead of sorting the whole data, and the sort is skipped entirely if they
+    /// are sorted by timestamp.
+    pub async fn read_ordered(
+        &self,
+        path: impl AsRef<Path>,
+        format: types::Format,
+        batch_size: Option<usize>,
+        ordering: FileOrdering,
+    ) -> Result<TimeseriesResult, Error> {
+        self.read_impl(path, format, batch_size, ordering).await
+    }
+
+    /// Read time-series data from a path removing the duplicated rows according to `policy`.
+    ///
+    /// Rows are deduplicated at read time, the stored data is left untouched. With
+    /// [`types::DedupPolicy::LastWriteWins`] the write order of the rows is given by the
+    /// order of the data files (i.e. chunk numbers) and by the position of the rows in each
+    /// file.
+    pub async fn read_deduplicated(
+        &self,
+        path: impl AsRef<Path>,
+        format: types::Format,
+        batch_size: Option<usize>,
+        policy: &types::DedupPolicy,
+    ) -> Result<TimeseriesResult, Error> {
+        let key = match policy {
+            types::DedupPolicy::None => {
+                return self
+                    .read_impl(path, format, batch_size, FileOrdering::default())
+                    .await;
+            }
+            types::DedupPolicy::DropDuplicates => {
+                let res = self
+                    .read_impl(path, format, batch_size, FileOrdering::Unsorted)
+                    .await?;
+                return Ok(TimeseriesResult {
+                    data_frame: res.data_frame.distinct()?.sort(vec![timestamp_order()])?,
+                });
+            }
+            types::DedupPolicy::LastWriteWins(key) => key,
+        };
+
+        let Some(data_frame) = self
+            .read_write_ordered(&path, format, batch_size, ..)
+            .await?
+        else {
+            return self
+                .read_impl(path, format, batch_size, FileOrdering::default())
+                .await;
+        };
+
+        let data_frame = data_frame
+            .with_column(WRITE_ORDER_RANK, last_write_rank(key)?)?
+            .filter(col(WRITE_ORDER_RANK).eq(lit(1u64)))?
+            .drop_columns(&[WRITE_ORDER_FILE, WRITE_ORDER_ROW, WRITE_ORDER_RANK])?
+            .sort(vec![timestamp_order()])?;
+
+        Ok(TimeseriesResult { data_frame })
+    }
+
+    /// Read the rows changed by the data files with chunk number in `chunks`, where rows are
+    /// identified by the `key` column.
+    ///
+    /// For each key only the last written row is returned, provided it was written in the
+    /// requested chunks. Data files following the requested chunks are ignored. The change
+    /// is reported in the [`params::ARROW_SCHEMA_COLUMN_NAME_CHANGE`] column, an `update`
+    /// if the key was already written in a previous data file, an `insert` otherwise.
+    pub async fn read_changes(
+        &self,
+        path: impl AsRef<Path>,
+        format: types::Format,
+        batch_size: Option<usize>,
+        key: &str,
+        chunks: Range<usize>,
+    ) -> Result<TimeseriesResult, Error> {
+        let data_frame = self
+            .read_write_ordered(&path, format, batch_size, ..chunks.end)
+            .await?;
+
+        // Without data files the empty result of a regular read is returned
+        let Some(data_frame) = data_frame else {
+            let res = self
+                .read_impl(path, format, batch_size, FileOrdering::default())
+                .await?;
+            return Ok(TimeseriesResult {
+                data_frame: res
+                    .data_frame
+                    .limit(0, Some(0))?
+                    .with_column(params::ARROW_SCHEMA_COLUMN_NAME_CHANGE, lit(CHANGE_INSERT))?,
+            });
+        };
+
+        let key_column = || Expr::Column(Column::new_unqualified(key));
+        let first_write = Expr::from(WindowFunction::new(min_udaf(), vec![col(WRITE_ORDER_FILE)]))
+            .partition_by(vec![key_column()])
+            .build()?;
+
+        let change = when(
+            col(WRITE_ORDER_FIRST_FILE).lt(lit(chunks.start as u64)),
+            lit(CHANGE_UPDATE),
+        )
+        .otherwise(lit(CHANGE_INSERT))?;
+
+        let data_frame = data_frame
+            .with_column(WRITE_ORDER_RANK, last_write_rank(key)?)?
+            .with_column(WRITE_ORDER_FIRST_FILE, first_write)?
+            .filter(
+                col(WRITE_ORDER_RANK)
+                    .eq(lit(1u64))
+                    .and(col(WRITE_ORDER_FILE).gt_eq(lit(chunks.start as u64))),
+            )?
+            .with_column(params::ARROW_SCHEMA_COLUMN_NAME_CHANGE, change)?
+            .drop_columns(&[
+                WRITE_ORDER_FILE,
+                WRITE_ORDER_ROW,
+                WRITE_ORDER_RANK,
+                WRITE_ORDER_FIRST_FILE,
+            ])?
+            .sort(vec![timestamp_order()])?;
+
+        Ok(TimeseriesResult { data_frame })
+    }
+
+    /// Read the rows of the data files with chunk number in `chunks`, sorted by timestamp.
+    ///
+    /// Rows are returned as written, without removing the duplicated ones.
+    pub async fn read_chunks(
+        &self,
+        path: impl AsRef<Path>,
+        format: types::Format,
+        batch_size: Option<usize>,
+        chunks: Range<usize>,
+    ) -> Result<TimeseriesResult, Error> {
+        let data_frame = self
+            .read_write_ordered(&path, format, batch_size, chunks)
+            .await?;
+
+        // Without data files the empty result of a regular read is returned
+        let Some(data_frame) = data_frame else {
+            let res = self
+                .read_impl(path, format, batch_size, FileOrdering::default())
+                .await?;
+            return Ok(TimeseriesResult {
+                data_frame: res.data_frame.limit(0, Some(0))?,
+            });
+        };
+
+        let data_frame = data_frame
+            .drop_columns(&[WRITE_ORDER_FILE, WRITE_ORDER_ROW])?
+            .sort(vec![timestamp_order()])?;
+
+        Ok(TimeseriesResult { data_frame })
+    }
+
+    /// Reads the data files with chunk number in `chunks`, numbering the rows in write order
+    /// in the [`WRITE_ORDER_FILE`] (i.e. the chunk number) and [`WRITE_ORDER_ROW`] columns.
+    ///
+    /// Returns `None` if there are no data files to read.
+    async fn read_write_ordered(
+        &self,
+        path: impl AsRef<Path>,
+        format: types::Format,
+        batch_size: Option<usize>,
+        chunks: impl RangeBounds<usize>,
+    ) -> Result<Option<DataFrame>, Error> {
+        let parquet_strategy = format
+            .to_parquet_properties()
+            .expect("TimeseriesGateway::read requires a Parquet-based format");
+        let listing_options = parquet_strategy
+            .listing_options()
+            .with_file_sort_order(vec![]);
+
+        // Data files are named after their chunk number
+        let files = self
+            .store
+            .list(&path, Some(&parquet_strategy.as_extension()))
+            .await?;
+        let mut files: Vec<(usize, String)> = files
+            .into_iter()
+            .filter_map(|file| {
+                let chunk_number = Path::new(&file)
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| stem.parse::<usize>().ok())?;
+                chunks
+                    .contains(&chunk_number)
+                    .then_some((chunk_number, file))
+            })
+            .collect();
+        files.sort_unstable();
+
+        // Rows are numbered in scan order, that needs to match the file order
+        let mut conf = SessionConfig::new()
+            .with_target_partitions(1)
+            .with_repartition_file_scans(false);
+        if let Some(batch_size) = batch_size {
+            conf = conf.with_batch_size(batch_size);
+        }
+
+        let ctx = SessionContext::new_with_config_rt(conf, self.runtime.clone());
+
+        // Data files written under different versions of the topic schema can have different
+        // columns, the files are read with all the columns, missing values being null
+        let mut fields: Vec<FieldRef> = Vec::new();
+        let mut metadata = HashMap::new();
+        for (_, file) in &files {
+            let url = ListingTableUrl::parse(self.datafile_url(file)?)?;
+            let schema = listing_options.infer_schema(&ctx.state(), &url).await?;
+            for field in schema.fields() {
+                if !fields.iter().any(|f| f.name() == field.name()) {
+                    fields.push(field.clone());
+                }
+            }
+            if metadata.is_empty() {
+                metadata = schema.metadata().clone();
+            }
+        }
+        let schema: SchemaRef = Arc::new(Schema::new_with_metadata(fields, metadata));
+
+        let mut data_frame: Option<DataFrame> = None;
+        for (chunk_number, file) in files {
+            let table = format!("chunk_{chunk_number}");
+            ctx.register_listing_table(
+                &table,
+                self.datafile_url(file)?,
+                listing_options.clone(),
+                Some(schema.clone()),
+                None,
+            )
+            .await?;
+
+            let chunk = ctx
+                .table(&table)
+                .await?
+                .with_column(WRITE_ORDER_FILE, lit(chunk_number as u64))?
+                .with_column(WRITE_ORDER_ROW, row_number())?;
+
+            data_frame = Some(match data_frame {
+                Some(df) => df.union(chunk)?,
+                None => chunk,
+            });
+        }
+
+        Ok(data_frame)
+    }
+
+    /// Read time-series data from the data `files` of a path, with a known [`FileOrdering`].
+    ///
+    /// The schema is inferred from all the data files in `path`, so that reading a subset of
+    /// them returns the same columns of a whole read.
+    pub async fn read_files(
+        &self,
+        path: impl AsRef<Path>,
+        format: types::Format,
+        batch_size: Option<usize>,
+        ordering: FileOrdering,
+        files: &[impl AsRef<Path>],
+    ) -> Result<TimeseriesResult, Error> {
+        // Without data files the empty result of a regular read is returned
+        if files.is_empty() {
+            let res = self.read_impl(path, format, batch_size, ordering).await?;
+            return Ok(TimeseriesResult {
+                data_frame: res.data_frame.limit(0, Some(0))?,
+            });
+        }
+
+        let files = files
+            .iter()
+            .map(|file| Ok(ListingTableUrl::parse(self.datafile_url(file)?)?))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        self.read_impl_from(path, format, batch_size, ordering, Some(files))
+            .await
+    }
+
+    async fn read_impl(
+        &self,
+        path: impl AsRef<Path>,
+        format: types::Format,
+        batch_size: Option<usize>,
+        ordering: FileOrdering,
+    ) -> Result<TimeseriesResult, Error> {
+        self.read_impl_from(path, format, batch_size, ordering, None)
+            .await
+    }
+
+    /// Reads the data files in `path`, or only `files` if provided.
+    async fn read_impl_from(
+        &self,
+        path: impl AsRef<Path>,
+        format: types::Format,
+        batch_size: Option<usize>,
+        ordering: FileOrdering,
+        files: Option<Vec<ListingTableUrl>>,
     ) -> Result<TimeseriesResult, Error> {
         // Use Parquet format strategy for listing options
         let parquet_strategy = format
             .to_parquet_properties()
             .expect("TimeseriesGateway::read requires a Parquet-based format");
-        let listing_options = parquet_strategy.listing_options();
+        let mut listing_options = parquet_strategy.listing_options();
 
         let mut conf = SessionConfig::new();
         if let Some(batch_size) = batch_size {
@@ -83,17 +435,43 @@ impl TimeseriesEngine {
                 .set_bool("datafusion.execution.parquet.reorder_filters", true);
         }
 
+        match ordering {
+            FileOrdering::Timestamp => (),
+            FileOrdering::SortedBy(sort_key) => {
+                // Nulls first matches the ordering checked at ingest
+                let order = Expr::Column(Column::new_unqualified(sort_key)).sort(true, true);
+                listing_options = listing_options.with_file_sort_order(vec![vec![order]]);
+                // Group files with non-overlapping ranges so that they can be read in sequence
+                conf = conf.set_bool("datafusion.execution.split_file_groups_by_statistics", true);
+            }
+            FileOrdering::Unsorted => {
+                listing_options = listing_options.with_file_sort_order(vec![]);
+            }
+        }
+
         let ctx = SessionContext::new_with_config_rt(conf, self.runtime.clone());
 
         // we use `data` as internal reference for this context
-        ctx.register_listing_table(
-            "data",
-            self.datafile_url(path)?,
-            listing_options,
-            None,
-            None,
-        )
-        .await?;
+        match files {
+            Some(files) => {
+                let url = ListingTableUrl::parse(self.datafile_url(path)?)?;
+                let schema = listing_options.infer_schema(&ctx.state(), &url).await?;
+                let config = ListingTableConfig::new_with_multi_paths(files)
+                    .with_listing_options(listing_options)
+                    .with_schema(schema);
+                ctx.register_table("data", Arc::new(ListingTable::try_new(config)?))?;
+            }
+            None => {
+                ctx.register_listing_table(
+                    "data",
+                    self.datafile_url(path)?,
+                    listing_options,
+                    None,
+                    None,
+                )
+                .await?;
+            }
+        }
 
         let select = format!(
             "SELECT * FROM data ORDER BY {}",
@@ -105,6 +483,81 @@ impl TimeseriesEngine {
         Ok(TimeseriesResult { data_frame: df })
     }
 
+    /// Reads a single Parquet or CSV file, not belonging to any topic.
+    ///
+    /// `location` is either a path in the store or the URL of a file in an object store known
+    /// to the engine.
+    pub async fn read_external(
+        &self,
+        location: &str,
+        format: types::ExternalTableFormat,
+    ) -> Result<TimeseriesResult, Error> {
+        let url = match url::Url::parse(location) {
+            Ok(url) => url,
+            Err(_) => self.datafile_url(location)?,
+        };
+
+        let ctx = SessionContext::new_with_config_rt(SessionConfig::new(), self.runtime.clone());
+
+        let data_frame = match format {
+            types::ExternalTableFormat::Parquet => {
+                ctx.read_parquet(url.as_str(), ParquetReadOptions::default())
+                    .await?
+            }
+            types::ExternalTableFormat::Csv => {
+                ctx.read_csv(url.as_str(), CsvReadOptions::new()).await?
+            }
+        };
+
+        Ok(TimeseriesResult { data_frame })
+    }
+
+    /// Runs a read-only SQL statement over `tables`, each one referenced in the statement by
+    /// its name.
+    ///
+    /// Statements modifying the data or the engine state (e.g. `INSERT` or `CREATE TABLE`)
+    /// are rejected, as well as statements referencing unknown tables or columns, with
+    /// [`Error::BadStatement`].
+    pub async fn sql(
+        &self,
+        tables: impl IntoIterator<Item = (String, TimeseriesResult)>,
+        statement: &str,
+        batch_size: Option<usize>,
+    ) -> Result<TimeseriesResult, Error> {
+        // Deduplicated reads number the rows in scan order, the tables need to be scanned by
+        // a single partition as when read on their own
+        let mut conf = SessionConfig::new()
+            .with_target_partitions(1)
+            .with_repartition_file_scans(false);
+        if let Some(batch_size) = batch_size {
+            conf = conf.with_batch_size(batch_size);
+        }
+
+        let ctx = SessionContext::new_with_config_rt(conf, self.runtime.clone());
+
+        for (name, table) in tables {
+            ctx.register_table(TableReference::bare(name), table.data_frame.into_view())?;
+        }
+
+        let options = SQLOptions::new()
+            .with_allow_ddl(false)
+            .with_allow_dml(false)
+            .with_allow_statements(false);
+
+        let data_frame = ctx
+            .sql_with_options(statement, options)
+            .await
+            .map_err(|e| Error::BadStatement(e.strip_backtrace()))?;
+
+        Ok(TimeseriesResult { data_frame })
+    }
+
+    /// Declares that the data files at the provided paths are going to be read in order,
+    /// allowing the store to fetch them ahead of time.
+    pub fn plan_reads<P: AsRef<Path>>(&self, paths: impl IntoIterator<Item = P>) {
+        self.store.plan_reads(paths);
+    }
+
     fn datafile_url(&self, path: impl AsRef<Path>) -> Result<url::Url, Error> {
         Ok(self
             .store
@@ -114,11 +567,27 @@ impl TimeseriesEngine {
     }
 }
 
+/// Known ordering of the data files read by the [`TimeseriesEngine`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum FileOrdering {
+    /// Each file is sorted by timestamp, as assumed by the storage formats
+    #[default]
+    Timestamp,
+    /// Files are sorted by the column, both within each file and across files
+    SortedBy(String),
+    /// Files have no known ordering
+    Unsorted,
+}
+
 pub struct TimeseriesResult {
     data_frame: DataFrame,
 }
 
 impl TimeseriesResult {
+    pub fn schema(&self) -> SchemaRef {
+        self.data_frame.schema().inner().clone()
+    }
+
     pub fn schema_with_metadata(&self, metadata: HashMap<String, String>) -> SchemaRef {
         Arc::new(Schema::new_with_metadata(
             self.data_frame.schema().fields().clone(),
@@ -147,6 +616,14 @@ impl TimeseriesResult {
         Ok(self)
     }
 
+    /// Sorts the rows by timestamp, the sort is skipped by the query engine if the data is
+    /// already read in timestamp order.
+    pub fn sort_by_timestamp(self) -> Result<Self, Error> {
+        Ok(TimeseriesResult {
+            data_frame: self.data_frame.sort(vec![timestamp_order()])?,
+        })
+    }
+
     pub fn filter<V>(self, filter: OntologyExprGroup<V>) -> Result<Self, Error>
     where
         V: Into<Value>,
@@ -163,6 +640,86 @@ impl TimeseriesResult {
         Ok(TimeseriesResult { data_frame })
     }
 
+    /// Keeps a random sample of the rows, the order of the sampled rows is preserved.
+    pub fn sample(self, sampling: &Sampling) -> Result<Self, Error> {
+        let fraction = sampling.fraction();
+        if fraction >= 1.0 {
+            return Ok(self);
+        }
+
+        let data_frame = match sampling.method() {
+            SamplingMethod::Uniform => self.data_frame.filter(random().lt(lit(fraction)))?,
+            SamplingMethod::TimeStratified { stratum_ns } => {
+                // Rows of each stratum are ranked randomly, keeping the first ones up to the
+                // sampled fraction of the stratum size (at least one row per stratum)
+                let stratum =
+                    col(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP) / lit(*stratum_ns);
+                let rank = row_number()
+                    .partition_by(vec![col(SAMPLE_STRATUM)])
+                    .order_by(vec![random().sort(true, true)])
+                    .build()?;
+                let size = count_all_window()
+                    .partition_by(vec![col(SAMPLE_STRATUM)])
+                    .build()?;
+
+                self.data_frame
+                    .with_column(SAMPLE_STRATUM, stratum)?
+                    .with_column(SAMPLE_RANK, rank)?
+                    .with_column(SAMPLE_SIZE, size)?
+                    .filter(
+                        cast(col(SAMPLE_RANK) - lit(1u64), DataType::Float64)
+                            .lt(cast(col(SAMPLE_SIZE), DataType::Float64) * lit(fraction)),
+                    )?
+                    .drop_columns(&[SAMPLE_STRATUM, SAMPLE_RANK, SAMPLE_SIZE])?
+                    .sort(vec![timestamp_order()])?
+            }
+        };
+
+        Ok(TimeseriesResult { data_frame })
+    }
+
+    /// Returns the distinct non-null values of a column.
+    ///
+    /// # Errors
+    ///
+    /// This function will return a [`Error::BadField`] if the column is not found or its type
+    /// can't be used in a query filter, and a [`Error::TooManyValues`] if the column has more
+    /// than `limit` distinct values.
+    pub async fn distinct_values(self, column: &str, limit: usize) -> Result<Vec<Value>, Error> {
+        let expr = Expr::Column(Column::new_unqualified(column));
+
+        let data_frame = self
+            .data_frame
+            .select(vec![expr.clone()])
+            .map_err(|_| Error::bad_field(column.to_owned()))?;
+
+        let batches = data_frame
+            .filter(expr.is_not_null())?
+            .distinct()?
+            .limit(0, Some(limit + 1))?
+            .collect()
+            .await?;
+
+        let mut values = Vec::new();
+        for batch in batches {
+            let array = batch.column(0);
+            for i in 0..array.len() {
+                let value = scalar_value_to_value(ScalarValue::try_from_array(array, i)?)
+                    .ok_or_else(|| Error::bad_field(column.to_owned()))?;
+                values.push(value);
+            }
+        }
+
+        if values.len() > limit {
+            return Err(Error::TooManyValues {
+                field: column.to_owned(),
+                limit,
+            });
+        }
+
+        Ok(values)
+    }
+
     pub async fn stream(self) -> Result<SendableRecordBatchStream, Error> {
         self.data_frame.execute_stream().await.map_err(|e| e.into())
     }
@@ -216,6 +773,159 @@ impl TimeseriesResult {
 
         Err(Error::NotFound)
     }
+
+    /// Computes the statistics of the given columns, nested fields are separated by dots.
+    ///
+    /// Statistics are computed in a single scan of the data. Minimum, maximum and mean are
+    /// computed for numeric and boolean columns, minimum and maximum only for textual ones.
+    ///
+    /// # Errors
+    ///
+    /// This function will return a [`Error::BadField`] if a column is not found in the data
+    /// or a [`Error::DataFusion`] if backend fails.
+    pub async fn column_stats(self, columns: &[String]) -> Result<Vec<types::ColumnStats>, Error> {
+        let schema = self.data_frame.schema().clone();
+
+        let mut kinds = Vec::with_capacity(columns.len());
+        let mut aggregates = vec![count_all()];
+        for name in columns {
+            let column = unfold_path(name);
+            let data_type = column
+                .get_type(&schema)
+                .map_err(|_| Error::bad_field(name.clone()))?;
+
+            let kind = StatsKind::from(&data_type);
+            aggregates.push(count(column.clone()));
+            match kind {
+                StatsKind::Numeric => {
+                    let value = cast(column.clone(), DataType::Float64);
+                    aggregates.extend([min(value.clone()), max(value.clone()), avg(value)]);
+                }
+                StatsKind::Textual => {
+                    let value = cast(column.clone(), DataType::Utf8);
+                    aggregates.extend([min(value.clone()), max(value)]);
+                }
+                StatsKind::Unordered => {}
+            }
+            let distinct = distinct_countable(column, &data_type);
+            kinds.push((kind, distinct.is_some()));
+            aggregates.extend(distinct.map(approx_distinct));
+        }
+
+        // Aggregates are aliased by position since the same column may be requested twice
+        let aggregates = aggregates
+            .into_iter()
+            .enumerate()
+            .map(|(i, expr)| expr.alias(format!("__mosaico_stat_{i}")))
+            .collect();
+
+        let batches = self
+            .data_frame
+            .aggregate(vec![], aggregates)?
+            .collect()
+            .await?;
+        let batch = batches.first().ok_or(Error::NotFound)?;
+
+        let mut values = (0..batch.num_columns())
+            .map(|i| ScalarValue::try_from_array(batch.column(i), 0))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+        let mut next = || values.next().unwrap_or(ScalarValue::Null);
+
+        let row_count = scalar_value_to_u64(next()).unwrap_or_default();
+
+        Ok(columns
+            .iter()
+            .zip(kinds)
+            .map(|(name, (kind, countable))| {
+                let non_null_count = scalar_value_to_u64(next()).unwrap_or_default();
+                let (min, max, mean) = match kind {
+                    StatsKind::Numeric => {
+                        let numeric = |v| scalar_value_to_f64(v).map(types::ColumnValue::Numeric);
+                        (
+                            numeric(next()),
+                            numeric(next()),
+                            scalar_value_to_f64(next()),
+                        )
+                    }
+                    StatsKind::Textual => {
+                        let textual =
+                            |v| scalar_value_to_string(v).map(types::ColumnValue::Textual);
+                        (textual(next()), textual(next()), None)
+                    }
+                    StatsKind::Unordered => (None, None, None),
+                };
+                let cardinality = if countable {
+                    scalar_value_to_u64(next())
+                } else {
+                    None
+                };
+
+                types::ColumnStats {
+                    name: name.clone(),
+                    row_count,
+                    null_count: row_count.saturating_sub(non_null_count),
+                    min,
+                    max,
+                    mean,
+                    cardinality,
+                }
+            })
+            .collect())
+    }
+}
+
+/// Statistics available for a column, based on its data type.
+enum StatsKind {
+    Numeric,
+    Textual,
+    Unordered,
+}
+
+impl From<&DataType> for StatsKind {
+    fn from(value: &DataType) -> Self {
+        match value {
+            DataType::Boolean => Self::Numeric,
+            DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => Self::Textual,
+            v if v.is_numeric() => Self::Numeric,
+            _ => Self::Unordered,
+        }
+    }
+}
+
+/// Returns the expression whose distinct values can be estimated for a column, if any.
+///
+/// Floating point and boolean values are not supported by the estimator, so their textual
+/// representation is used instead.
+fn distinct_countable(column: Expr, data_type: &DataType) -> Option<Expr> {
+    match data_type {
+        DataType::Boolean | DataType::Float16 | DataType::Float32 | DataType::Float64 => {
+            Some(cast(column, DataType::Utf8))
+        }
+        DataType::Utf8
+        | DataType::LargeUtf8
+        | DataType::Utf8View
+        | DataType::Binary
+        | DataType::LargeBinary
+        | DataType::Timestamp(_, _) => Some(column),
+        v if v.is_integer() => Some(column),
+        _ => None,
+    }
+}
+
+/// Ranks the rows sharing the same `key` from the last written one, ranked `1`.
+fn last_write_rank(key: &str) -> Result<Expr, Error> {
+    Ok(row_number()
+        .partition_by(vec![Expr::Column(Column::new_unqualified(key))])
+        .order_by(vec![
+            col(WRITE_ORDER_FILE).sort(false, true),
+            col(WRITE_ORDER_ROW).sort(false, true),
+        ])
+        .build()?)
+}
+
+fn timestamp_order() -> SortExpr {
+    col(params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP).sort(true, true)
 }
 
 fn scalar_value_to_timestamp(value: ScalarValue) -> Option<types::Timestamp> {
@@ -225,8 +935,55 @@ fn scalar_value_to_timestamp(value: ScalarValue) -> Option<types::Timestamp> {
     }
 }
 
+fn scalar_value_to_u64(value: ScalarValue) -> Option<u64> {
+    match value {
+        ScalarValue::Int64(Some(v)) => Some(v as u64),
+        ScalarValue::UInt64(Some(v)) => Some(v),
+        _ => None,
+    }
+}
+
+fn scalar_value_to_f64(value: ScalarValue) -> Option<f64> {
+    match value {
+        ScalarValue::Float64(Some(v)) => Some(v),
+        _ => None,
+    }
+}
+
+fn scalar_value_to_string(value: ScalarValue) -> Option<String> {
+    match value {
+        ScalarValue::Utf8(v) => v,
+        _ => None,
+    }
+}
+
+/// Converts a value read from the data to a value usable in a query filter.
+fn scalar_value_to_value(value: ScalarValue) -> Option<Value> {
+    match value {
+        ScalarValue::Boolean(v) => v.map(Value::Boolean),
+        ScalarValue::Int8(v) => v.map(|v| Value::Integer(v.into())),
+        ScalarValue::Int16(v) => v.map(|v| Value::Integer(v.into())),
+        ScalarValue::Int32(v) => v.map(|v| Value::Integer(v.into())),
+        ScalarValue::Int64(v) => v.map(Value::Integer),
+        ScalarValue::UInt8(v) => v.map(|v| Value::Integer(v.into())),
+        ScalarValue::UInt16(v) => v.map(|v| Value::Integer(v.into())),
+        ScalarValue::UInt32(v) => v.map(|v| Value::Integer(v.into())),
+        ScalarValue::UInt64(v) => v.and_then(|v| v.try_into().ok()).map(Value::Integer),
+        ScalarValue::Float32(v) => v.map(|v| Value::Float(v.into())),
+        ScalarValue::Float64(v) => v.map(Value::Float),
+        ScalarValue::Utf8(v) | ScalarValue::LargeUtf8(v) | ScalarValue::Utf8View(v) => {
+            v.map(Value::Text)
+        }
+        _ => None,
+    }
+}
+
 fn unfold_field(field: &OntologyField) -> Expr {
-    let mut fields = field.field().split(".");
+    unfold_path(field.field())
+}
+
+fn unfold_path(path: &str) -> Expr {
+    let mut fields = path.split(".");
     // By construction fields needs to have at least a value
     let mut col = col(fields.next().unwrap());
     for s in fields {
@@ -346,4 +1103,388 @@ mod tests {
         assert_eq!(ts_range.start, 10010.into());
         assert_eq!(ts_range.end, 10020.into());
     }
+
+    /// Data read from files known to be sorted does not need to be sorted again
+    #[tokio::test]
+    async fn timeseries_ordered_read() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+        write_dummy_file(&store, "data/0.parquet").await;
+
+        let ts_gw = TimeseriesEngine::try_new((*store).clone(), 0).unwrap();
+
+        let has_sort = async |ordering: FileOrdering| {
+            let res = ts_gw
+                .read_ordered("data/", types::Format::Default, Some(4), ordering)
+                .await
+                .unwrap();
+            let plan = res.data_frame.create_physical_plan().await.unwrap();
+            datafusion::physical_plan::displayable(plan.as_ref())
+                .indent(false)
+                .to_string()
+                .contains("SortExec")
+        };
+
+        assert!(has_sort(FileOrdering::Unsorted).await);
+        assert!(has_sort(FileOrdering::SortedBy("value".to_owned())).await);
+        assert!(
+            !has_sort(FileOrdering::SortedBy(
+                params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP.to_owned()
+            ))
+            .await
+        );
+
+        let res = ts_gw
+            .read_ordered(
+                "data/",
+                types::Format::Default,
+                None,
+                FileOrdering::Unsorted,
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.count().await.unwrap(), 7);
+    }
+
+    async fn write_file(
+        store: &store::Store,
+        file_path: &str,
+        timestamps: Vec<i64>,
+        values: Vec<i64>,
+    ) {
+        use ::arrow::array::{Int64Array, RecordBatch};
+        use parquet::arrow::arrow_writer::ArrowWriter;
+
+        let schema = arrow::testing::dummy_batch().schema();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(timestamps)),
+                Arc::new(Int64Array::from(values)),
+            ],
+        )
+        .unwrap();
+
+        let mut buffer = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut buffer, schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        store.write_to_path(file_path, buffer).await.unwrap();
+    }
+
+    async fn collect_values(res: TimeseriesResult) -> Vec<(i64, i64)> {
+        use ::arrow::array::{AsArray, types::Int64Type};
+
+        let batches = res.data_frame.collect().await.unwrap();
+        batches
+            .iter()
+            .flat_map(|batch| {
+                let ts = batch.column(0).as_primitive::<Int64Type>().clone();
+                let values = batch.column(1).as_primitive::<Int64Type>().clone();
+                ts.values()
+                    .iter()
+                    .copied()
+                    .zip(values.values().iter().copied())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn timeseries_deduplicated_read() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+        write_file(
+            &store,
+            "data/00000.parquet",
+            vec![1, 2, 2, 2, 3],
+            vec![10, 20, 21, 20, 30],
+        )
+        .await;
+        write_file(&store, "data/00001.parquet", vec![3, 4], vec![31, 40]).await;
+
+        let ts_gw = TimeseriesEngine::try_new((*store).clone(), 0).unwrap();
+
+        let read = async |policy: types::DedupPolicy| {
+            let res = ts_gw
+                .read_deduplicated("data/", types::Format::Default, Some(2), &policy)
+                .await
+                .unwrap();
+            collect_values(res).await
+        };
+
+        assert_eq!(read(types::DedupPolicy::None).await.len(), 7);
+
+        let mut distinct = read(types::DedupPolicy::DropDuplicates).await;
+        distinct.sort();
+        assert_eq!(
+            distinct,
+            vec![(1, 10), (2, 20), (2, 21), (3, 30), (3, 31), (4, 40)]
+        );
+        assert_eq!(
+            read(types::DedupPolicy::LastWriteWins(
+                params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP.to_owned()
+            ))
+            .await,
+            vec![(1, 10), (2, 20), (3, 31), (4, 40)]
+        );
+    }
+
+    #[tokio::test]
+    async fn timeseries_changes_read() {
+        use ::arrow::array::{AsArray, types::Int64Type};
+
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+        write_file(
+            &store,
+            "data/00000.parquet",
+            vec![1, 2, 3],
+            vec![10, 20, 30],
+        )
+        .await;
+        write_file(&store, "data/00001.parquet", vec![2, 4], vec![21, 40]).await;
+        write_file(&store, "data/00002.parquet", vec![4, 5], vec![41, 50]).await;
+
+        let ts_gw = TimeseriesEngine::try_new((*store).clone(), 0).unwrap();
+
+        let changes = async |chunks: std::ops::Range<usize>| {
+            let res = ts_gw
+                .read_changes(
+                    "data/",
+                    types::Format::Default,
+                    None,
+                    params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+                    chunks,
+                )
+                .await
+                .unwrap();
+            let batches = res.data_frame.collect().await.unwrap();
+            batches
+                .iter()
+                .flat_map(|batch| {
+                    let ts = batch.column(0).as_primitive::<Int64Type>().clone();
+                    let changes = batch.column(2).as_string::<i32>().clone();
+                    ts.values()
+                        .iter()
+                        .copied()
+                        .zip(changes.iter().map(|c| c.unwrap().to_owned()))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            changes(1..3).await,
+            vec![
+                (2, CHANGE_UPDATE.to_owned()),
+                (4, CHANGE_INSERT.to_owned()),
+                (5, CHANGE_INSERT.to_owned())
+            ]
+        );
+        // Rows written after the requested chunks are ignored
+        assert_eq!(
+            changes(1..2).await,
+            vec![(2, CHANGE_UPDATE.to_owned()), (4, CHANGE_INSERT.to_owned())]
+        );
+        assert!(changes(3..3).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn timeseries_chunks_read() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+        write_file(&store, "data/00000.parquet", vec![1, 2], vec![10, 20]).await;
+        write_file(&store, "data/00001.parquet", vec![2, 4], vec![21, 40]).await;
+        write_file(&store, "data/00002.parquet", vec![3, 5], vec![30, 50]).await;
+
+        let ts_gw = TimeseriesEngine::try_new((*store).clone(), 0).unwrap();
+
+        let read = async |chunks: std::ops::Range<usize>| {
+            let res = ts_gw
+                .read_chunks("data/", types::Format::Default, Some(2), chunks)
+                .await
+                .unwrap();
+            collect_values(res).await
+        };
+
+        assert_eq!(read(1..3).await, vec![(2, 21), (3, 30), (4, 40), (5, 50)]);
+        assert_eq!(read(0..1).await, vec![(1, 10), (2, 20)]);
+        assert!(read(3..3).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn timeseries_files_read() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+        write_file(&store, "data/00000.parquet", vec![1, 2], vec![10, 20]).await;
+        write_file(&store, "data/00001.parquet", vec![3, 4], vec![30, 40]).await;
+        write_file(&store, "data/00002.parquet", vec![5, 6], vec![50, 60]).await;
+
+        let ts_gw = TimeseriesEngine::try_new((*store).clone(), 0).unwrap();
+
+        let read = async |files: &[&str]| {
+            let res = ts_gw
+                .read_files(
+                    "data/",
+                    types::Format::Default,
+                    Some(2),
+                    FileOrdering::default(),
+                    files,
+                )
+                .await
+                .unwrap();
+            collect_values(res).await
+        };
+
+        assert_eq!(
+            read(&["data/00002.parquet", "data/00000.parquet"]).await,
+            vec![(1, 10), (2, 20), (5, 50), (6, 60)]
+        );
+        assert_eq!(read(&["data/00001.parquet"]).await, vec![(3, 30), (4, 40)]);
+        assert!(read(&[]).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn timeseries_sql() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+        write_file(&store, "left/0.parquet", vec![1, 2, 3], vec![10, 20, 30]).await;
+        write_file(
+            &store,
+            "right/0.parquet",
+            vec![2, 3, 4],
+            vec![200, 300, 400],
+        )
+        .await;
+
+        let ts_gw = TimeseriesEngine::try_new((*store).clone(), 0).unwrap();
+
+        let tables = async || {
+            let left = ts_gw
+                .read("left/", types::Format::Default, None)
+                .await
+                .unwrap();
+            let right = ts_gw
+                .read("right/", types::Format::Default, None)
+                .await
+                .unwrap();
+            vec![("left".to_owned(), left), ("Right".to_owned(), right)]
+        };
+
+        let statement = format!(
+            r#"SELECT l.{ts}, r.value FROM left l JOIN "Right" r ON l.{ts} = r.{ts} ORDER BY 1"#,
+            ts = params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP
+        );
+        let res = ts_gw.sql(tables().await, &statement, None).await.unwrap();
+        assert_eq!(collect_values(res).await, vec![(2, 200), (3, 300)]);
+
+        for statement in [
+            "SELECT * FROM unknown",
+            "SELECT missing FROM left",
+            "CREATE TABLE other AS SELECT * FROM left",
+            "INSERT INTO left SELECT * FROM left",
+        ] {
+            let res = ts_gw.sql(tables().await, statement, None).await;
+            assert!(matches!(res, Err(Error::BadStatement(_))), "{statement}");
+        }
+    }
+
+    #[tokio::test]
+    async fn timeseries_column_stats() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+        write_dummy_file(&store, "data/0.parquet").await;
+
+        let ts_gw = TimeseriesEngine::try_new((*store).clone(), 0).unwrap();
+        let read = async || {
+            ts_gw
+                .read("data/", types::Format::Default, None)
+                .await
+                .unwrap()
+        };
+
+        let stats = read()
+            .await
+            .column_stats(&["value".to_owned()])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            stats,
+            vec![types::ColumnStats {
+                name: "value".to_owned(),
+                row_count: 7,
+                null_count: 0,
+                min: Some(types::ColumnValue::Numeric(1.0)),
+                max: Some(types::ColumnValue::Numeric(7.0)),
+                mean: Some(4.0),
+                cardinality: Some(7),
+            }]
+        );
+
+        let err = read()
+            .await
+            .column_stats(&["missing".to_owned()])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::BadField { .. }));
+    }
+
+    #[tokio::test]
+    async fn timeseries_sample() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let store = store::testing::Store::new_random_on_tmp().unwrap();
+        write_file(
+            &store,
+            "data/00000.parquet",
+            (0..100).collect(),
+            (0..100).collect(),
+        )
+        .await;
+
+        let ts_gw = TimeseriesEngine::try_new((*store).clone(), 0).unwrap();
+
+        let sample = async |fraction: f64, method: SamplingMethod| {
+            let res = ts_gw
+                .read("data/", types::Format::Default, None)
+                .await
+                .unwrap()
+                .sample(&Sampling::try_new(fraction, method).unwrap())
+                .unwrap();
+            collect_values(res).await
+        };
+
+        assert_eq!(sample(1.0, SamplingMethod::Uniform).await.len(), 100);
+
+        let uniform = sample(0.5, SamplingMethod::Uniform).await;
+        assert!(!uniform.is_empty() && uniform.len() < 100);
+
+        // Each stratum of 10 rows contributes the same number of rows
+        let stratified = sample(0.2, SamplingMethod::TimeStratified { stratum_ns: 10 }).await;
+        assert_eq!(stratified.len(), 20);
+        for stratum in 0..10 {
+            assert_eq!(
+                stratified
+                    .iter()
+                    .filter(|(ts, _)| ts / 10 == stratum)
+                    .count(),
+                2
+            );
+        }
+        assert!(stratified.is_sorted());
+
+        assert!(Sampling::try_new(0.0, SamplingMethod::Uniform).is_err());
+        assert!(Sampling::try_new(1.5, SamplingMethod::Uniform).is_err());
+        assert!(Sampling::try_new(0.5, SamplingMethod::TimeStratified { stratum_ns: 0 }).is_err());
+    }
 }
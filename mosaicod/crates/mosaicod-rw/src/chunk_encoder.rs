@@ -1,7 +1,13 @@
 use super::{Error, writer::Writer};
 use arrow::{array::RecordBatch, datatypes::Schema, datatypes::SchemaRef};
-use mosaicod_core::types;
+use mosaicod_core::{params, types};
 use mosaicod_ext;
+use parquet::errors::ParquetError;
+use parquet::file::{
+    FOOTER_SIZE,
+    metadata::{FooterTail, ParquetMetaData, ParquetMetaDataReader},
+    statistics::Statistics,
+};
 use std::sync::Arc;
 
 /// Metadata about a finalized chunk, including size and row count.
@@ -9,6 +15,96 @@ use std::sync::Arc;
 pub struct ChunkMetadata {
     pub size_bytes: usize,
     pub row_count: usize,
+    /// Byte ranges of the row groups composing the chunk
+    pub row_groups: Vec<RowGroupRange>,
+}
+
+/// Size (in bytes) of the tail of a chunk, holding the size of its footer.
+pub const FOOTER_TAIL_SIZE: u64 = FOOTER_SIZE as u64;
+
+impl ChunkMetadata {
+    /// Returns the size (in bytes) of the footer of a chunk, given the last
+    /// [`FOOTER_TAIL_SIZE`] bytes of the chunk.
+    pub fn footer_size(tail: &[u8]) -> Result<u64, Error> {
+        let tail: [u8; FOOTER_SIZE] = tail
+            .try_into()
+            .map_err(|_| ParquetError::EOF("truncated footer tail".to_owned()))?;
+        Ok(FooterTail::try_from(tail)?.metadata_length() as u64)
+    }
+
+    /// Builds the metadata of a chunk of `size_bytes` bytes from its footer, as sized by
+    /// [`ChunkMetadata::footer_size`], so that the data of the chunk does not need to be read.
+    pub fn from_footer(size_bytes: usize, footer: &[u8]) -> Result<Self, Error> {
+        let metadata = ParquetMetaDataReader::decode_metadata(footer)?;
+        Ok(Self {
+            size_bytes,
+            row_count: metadata.file_metadata().num_rows() as usize,
+            row_groups: RowGroupRange::from_parquet_metadata(&metadata),
+        })
+    }
+
+    /// Returns the timestamp range of the rows in the chunk, `None` if some row group has no
+    /// statistics on the timestamp column.
+    pub fn timestamp_range(&self) -> Option<types::TimestampRange> {
+        let covered: u64 = self.row_groups.iter().map(|rg| rg.row_count).sum();
+        if self.row_groups.is_empty() || covered != self.row_count as u64 {
+            return None;
+        }
+
+        Some(types::TimestampRange::between(
+            self.row_groups.iter().map(|rg| rg.start).min()?,
+            self.row_groups.iter().map(|rg| rg.end).max()?,
+        ))
+    }
+}
+
+/// Byte range and timestamp bounds of a single row group inside a chunk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowGroupRange {
+    pub start: types::Timestamp,
+    pub end: types::Timestamp,
+    pub offset: u64,
+    pub length: u64,
+    pub row_count: u64,
+}
+
+impl RowGroupRange {
+    /// Extracts the row group ranges from the parquet file metadata.
+    ///
+    /// Row groups without statistics on the timestamp column are skipped.
+    fn from_parquet_metadata(metadata: &ParquetMetaData) -> Vec<Self> {
+        let schema = metadata.file_metadata().schema_descr();
+        let Some(ts_column) = schema
+            .columns()
+            .iter()
+            .position(|c| c.path().string() == params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP)
+        else {
+            return Vec::new();
+        };
+
+        metadata
+            .row_groups()
+            .iter()
+            .filter_map(|rg| {
+                let Some(Statistics::Int64(stats)) = rg.column(ts_column).statistics() else {
+                    return None;
+                };
+
+                let (offset, end) = rg.columns().iter().fold((u64::MAX, 0), |(lo, hi), c| {
+                    let (start, len) = c.byte_range();
+                    (lo.min(start), hi.max(start + len))
+                });
+
+                Some(Self {
+                    start: (*stats.min_opt()?).into(),
+                    end: (*stats.max_opt()?).into(),
+                    offset,
+                    length: end - offset,
+                    row_count: rg.num_rows() as u64,
+                })
+            })
+            .collect()
+    }
 }
 
 /// The [`InMemoryChunkEncoder`] is used to encode [`RecordBatch`] instances into a single in-memory block,
@@ -23,6 +119,8 @@ pub struct InMemoryChunkEncoder {
     stats: types::OntologyModelStats,
     schema: SchemaRef,
     row_count: usize,
+    /// Number of bytes already taken out of the internal buffer by [`Self::write_parts`]
+    bytes_taken: usize,
 }
 
 impl InMemoryChunkEncoder {
@@ -37,6 +135,7 @@ impl InMemoryChunkEncoder {
             stats: mosaicod_ext::arrow::ontology_model_stats_from_schema(&schema),
             schema,
             row_count: 0,
+            bytes_taken: 0,
         })
     }
 
@@ -59,6 +158,40 @@ impl InMemoryChunkEncoder {
         Ok(())
     }
 
+    /// Writes the provided [`RecordBatch`] one row group at a time, handing the bytes of each
+    /// completed row group to `on_part` as soon as they are encoded.
+    ///
+    /// This bounds the memory used by the encoder to a single row group, the bytes handed
+    /// out are not returned by [`Self::finalize`], which returns only the remaining tail of
+    /// the chunk.
+    pub fn write_parts<F>(&mut self, batch: &RecordBatch, mut on_part: F) -> Result<(), Error>
+    where
+        F: FnMut(Vec<u8>) -> Result<(), Error>,
+    {
+        let rows_per_part = match &self.writer {
+            Writer::Parquet(writer) => writer.max_row_group_rows(),
+        }
+        .unwrap_or(batch.num_rows())
+        .max(1);
+
+        let mut offset = 0;
+        while offset < batch.num_rows() {
+            let len = rows_per_part.min(batch.num_rows() - offset);
+            self.write(&batch.slice(offset, len))?;
+            offset += len;
+
+            let part = match &mut self.writer {
+                Writer::Parquet(writer) => writer.take_completed()?,
+            };
+            if !part.is_empty() {
+                self.bytes_taken += part.len();
+                on_part(part)?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Returns a reference to the current statistics of the serialized data.
     pub fn statistics(&self) -> &types::OntologyModelStats {
         &self.stats
@@ -96,17 +229,24 @@ impl InMemoryChunkEncoder {
     /// This method must be called to complete the writing process. It consumes the writer object,
     /// preventing any further writes.
     ///
-    /// Returns the serialized buffer, column statistics, and chunk metadata (size and row count).
+    /// Returns the serialized buffer, column statistics, and chunk metadata (size, row count
+    /// and row groups). The size accounts for the bytes already taken by
+    /// [`Self::write_parts`].
     pub fn finalize(self) -> Result<(Vec<u8>, types::OntologyModelStats, ChunkMetadata), Error> {
         // We are calling `finish` since the implementation is the same as
         // close but takes no ownership of the writer. And we return the internal data buffer.
         let row_count = self.row_count;
-        let buffer = match self.writer {
-            Writer::Parquet(w) => w.buffer()?,
+        let bytes_taken = self.bytes_taken;
+        let (buffer, row_groups) = match self.writer {
+            Writer::Parquet(w) => {
+                let (buffer, file_metadata) = w.finish()?;
+                (buffer, RowGroupRange::from_parquet_metadata(&file_metadata))
+            }
         };
         let metadata = ChunkMetadata {
-            size_bytes: buffer.len(),
+            size_bytes: bytes_taken + buffer.len(),
             row_count,
+            row_groups,
         };
         Ok((buffer, self.stats, metadata))
     }
@@ -259,4 +399,114 @@ mod tests {
         assert_eq!(metadata.row_count, 3);
         assert_eq!(metadata.size_bytes, buffer.len());
     }
+
+    #[test]
+    fn chunk_writer_row_groups() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+                DataType::Int64,
+                false,
+            ),
+            Field::new("frame", DataType::Binary, false),
+        ]));
+
+        let row_count = params::params().media_segment_row_count.value * 2 + 1;
+        let timestamps: Vec<i64> = (0..row_count as i64).map(|i| i * 10).collect();
+        let frames: Vec<&[u8]> = vec![b"FRAME" as &[u8]; row_count];
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(timestamps)),
+                Arc::new(BinaryArray::from(frames)),
+            ],
+        )
+        .unwrap();
+
+        let mut writer = InMemoryChunkEncoder::try_new(schema, types::Format::Image).unwrap();
+        writer.write(&batch).unwrap();
+        let (buffer, _, metadata) = writer.finalize().unwrap();
+
+        // Image chunks are split in segments of bounded size
+        assert_eq!(metadata.row_groups.len(), 3);
+        assert_eq!(
+            metadata
+                .row_groups
+                .iter()
+                .map(|rg| rg.row_count)
+                .sum::<u64>(),
+            row_count as u64
+        );
+
+        let first = &metadata.row_groups[0];
+        let last = metadata.row_groups.last().unwrap();
+        assert_eq!(first.start, 0.into());
+        assert_eq!(last.end, ((row_count as i64 - 1) * 10).into());
+        assert!(first.offset + first.length <= last.offset);
+        assert!((last.offset + last.length) as usize <= buffer.len());
+
+        let range = metadata.timestamp_range().unwrap();
+        assert_eq!(range.start, first.start);
+        assert_eq!(range.end, last.end);
+
+        // The same metadata is decoded from the footer alone
+        let tail = &buffer[buffer.len() - FOOTER_TAIL_SIZE as usize..];
+        let footer_size = ChunkMetadata::footer_size(tail).unwrap() as usize;
+        let footer_start = buffer.len() - FOOTER_TAIL_SIZE as usize - footer_size;
+        let footer = &buffer[footer_start..buffer.len() - FOOTER_TAIL_SIZE as usize];
+        let decoded = ChunkMetadata::from_footer(buffer.len(), footer).unwrap();
+        assert_eq!(decoded.row_count, row_count);
+        assert_eq!(decoded.row_groups, metadata.row_groups);
+        assert!(ChunkMetadata::footer_size(&tail[1..]).is_err());
+    }
+
+    #[test]
+    fn chunk_writer_parts() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+                DataType::Int64,
+                false,
+            ),
+            Field::new("frame", DataType::Binary, false),
+        ]));
+
+        let row_count = params::params().media_segment_row_count.value * 2 + 1;
+        let timestamps: Vec<i64> = (0..row_count as i64).collect();
+        let frames: Vec<&[u8]> = vec![b"FRAME" as &[u8]; row_count];
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(timestamps)),
+                Arc::new(BinaryArray::from(frames)),
+            ],
+        )
+        .unwrap();
+
+        let mut writer =
+            InMemoryChunkEncoder::try_new(schema.clone(), types::Format::Image).unwrap();
+        writer.write(&batch).unwrap();
+        let (expected, _, expected_metadata) = writer.finalize().unwrap();
+
+        let mut parts: Vec<Vec<u8>> = Vec::new();
+        let mut writer = InMemoryChunkEncoder::try_new(schema, types::Format::Image).unwrap();
+        writer
+            .write_parts(&batch, |part| {
+                parts.push(part);
+                Ok(())
+            })
+            .unwrap();
+        let (tail, _, metadata) = writer.finalize().unwrap();
+
+        // A part is handed out for every completed row group, the last one is completed only
+        // when the chunk is finalized
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts.concat().len() + tail.len(), metadata.size_bytes);
+        assert_eq!([parts.concat(), tail].concat(), expected);
+        assert_eq!(metadata.row_groups, expected_metadata.row_groups);
+    }
 }
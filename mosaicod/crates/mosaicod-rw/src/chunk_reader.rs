@@ -37,3 +37,15 @@ impl ChunkReader {
         }
     }
 }
+
+impl Iterator for ChunkReader {
+    type Item = Result<arrow::array::RecordBatch, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.reader {
+            Reader::Parquet { reader, .. } => reader
+                .next()
+                .map(|batch| batch.map_err(|e| Error::ArrowError(e.into()))),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use super::*;
 use arrow::{array::RecordBatch, datatypes::SchemaRef};
-use mosaicod_core::{traits, types};
+use mosaicod_core::traits::{self, AsyncPartWriter};
+use mosaicod_core::types;
 use std::time::Instant;
 use tracing::debug;
 
@@ -50,40 +51,75 @@ impl<W> ChunkWriter<W> {
         }
     }
 
+    /// Maximum number of encoded parts waiting to be written to the target, the encoder is
+    /// paused once this limit is reached.
+    const MAX_PENDING_PARTS: usize = 2;
+
     /// Writes a [`RecordBatch`] into the chunked writer.
     ///
     /// The [`ChunkWriter`] will internally manage the creation of chunks
     /// based on the serialization format and the maximum chunk size (if any).
+    ///
+    /// The chunk is streamed to the target one row group at a time, so that the encoded chunk
+    /// does not need to be held in memory as a whole.
     pub async fn write<A>(&mut self, batch: RecordBatch) -> Result<SerializedChunk, Error>
     where
-        A: traits::AsyncWriteToPath,
+        A: traits::AsyncStreamToPath,
         W: AsRef<A>,
     {
         let mut writer = InMemoryChunkEncoder::try_new(self.schema.clone(), self.format)?;
 
-        let encoding_time = Instant::now();
+        let target_path = (self.path_provider)(self.chunk_count);
+        let mut target = self
+            .write_target
+            .as_ref()
+            .stream_to_path(&target_path)
+            .await?;
 
-        // Offload CPU-intensive parquet encoding/compression to blocking thread pool
-        let (buffer, stats, chunk_metadata) = tokio::task::spawn_blocking(move || {
-            writer.write(&batch)?;
+        let time = Instant::now();
+
+        // Offload CPU-intensive parquet encoding/compression to blocking thread pool, the
+        // encoded parts are handed back to be written while the next ones are encoded
+        let (tx, rx) = tokio::sync::mpsc::channel::<Vec<u8>>(Self::MAX_PENDING_PARTS);
+        let encoder = tokio::task::spawn_blocking(move || {
+            writer.write_parts(&batch, |part| {
+                tx.blocking_send(part).map_err(|_| {
+                    Error::BlockingOperationError("chunk write interrupted".to_owned())
+                })
+            })?;
             writer.finalize()
-        })
-        .await
-        .map_err(|e| Error::BlockingOperationError(e.to_string()))??;
+        });
+
+        let written = async {
+            let mut rx = rx;
+            while let Some(part) = rx.recv().await {
+                target.write_part(part).await?;
+            }
 
-        let buffer_len = buffer.len();
-        let encoding_time_ms = encoding_time.elapsed().as_millis();
-        let store_time = Instant::now();
+            let (tail, stats, chunk_metadata) = encoder
+                .await
+                .map_err(|e| Error::BlockingOperationError(e.to_string()))??;
+            target.write_part(tail).await?;
 
-        let target_path = self.store(buffer).await?;
+            Ok::<_, Error>((stats, chunk_metadata))
+        }
+        .await;
+
+        let (stats, chunk_metadata) = match written {
+            Ok(written) => written,
+            Err(e) => {
+                target.abort().await?;
+                return Err(e);
+            }
+        };
+        target.finish().await?;
         self.chunk_count += 1;
 
         debug!(
             target = "chunk encoding",
-            encoding_ms = encoding_time_ms,
-            store_ms = store_time.elapsed().as_millis(),
+            write_ms = time.elapsed().as_millis(),
             store_path = target_path.to_string_lossy().to_string(),
-            buffer_size_kb = buffer_len / 1000
+            buffer_size_kb = chunk_metadata.size_bytes / 1000
         );
 
         Ok(SerializedChunk {
@@ -92,20 +128,4 @@ impl<W> ChunkWriter<W> {
             metadata: chunk_metadata,
         })
     }
-
-    /// Finalize the writing process of a single chunk
-    async fn store<A>(&mut self, buffer: Vec<u8>) -> Result<std::path::PathBuf, Error>
-    where
-        A: traits::AsyncWriteToPath,
-        W: AsRef<A>,
-    {
-        let path = (self.path_provider)(self.chunk_count);
-
-        self.write_target
-            .as_ref()
-            .write_to_path(&path, buffer)
-            .await?;
-
-        Ok(path)
-    }
 }
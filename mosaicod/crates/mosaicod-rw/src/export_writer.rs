@@ -0,0 +1,126 @@
+use super::Error;
+use arrow::{array::RecordBatch, datatypes::SchemaRef};
+use bytes::Bytes;
+use parquet::arrow::ArrowWriter;
+use parquet::file::metadata::KeyValue;
+use parquet::file::properties::{WriterProperties, WriterVersion};
+use std::collections::HashMap;
+
+/// Encodes [`RecordBatch`] into a single self-contained Parquet file, handing out the encoded
+/// bytes as soon as each row group is completed, so that the file can be streamed without
+/// being buffered whole.
+///
+/// The file is the concatenation, in order, of the bytes returned by [`ExportWriter::write`]
+/// and [`ExportWriter::finish`].
+pub struct ExportWriter(ArrowWriter<Vec<u8>>);
+
+impl ExportWriter {
+    /// Creates a writer of files with row groups of at most `row_group_size` rows (the
+    /// Parquet default if not set), holding `key_value_metadata` in their footer.
+    pub fn try_new(
+        schema: SchemaRef,
+        row_group_size: Option<usize>,
+        key_value_metadata: HashMap<String, String>,
+    ) -> Result<Self, Error> {
+        let mut props = WriterProperties::builder().set_writer_version(WriterVersion::PARQUET_2_0);
+
+        if let Some(row_group_size) = row_group_size {
+            props = props.set_max_row_group_row_count(Some(row_group_size));
+        }
+
+        if !key_value_metadata.is_empty() {
+            let mut metadata: Vec<KeyValue> = key_value_metadata
+                .into_iter()
+                .map(|(key, value)| KeyValue::new(key, value))
+                .collect();
+            metadata.sort_by(|a, b| a.key.cmp(&b.key));
+            props = props.set_key_value_metadata(Some(metadata));
+        }
+
+        Ok(Self(ArrowWriter::try_new(
+            Vec::new(),
+            schema,
+            Some(props.build()),
+        )?))
+    }
+
+    /// Encodes `batch`, returning the bytes of the row groups it completed, if any.
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<Option<Bytes>, Error> {
+        let completed = self.0.flushed_row_groups().len();
+        self.0.write(batch)?;
+
+        if self.0.flushed_row_groups().len() == completed {
+            return Ok(None);
+        }
+
+        // Completed row groups are buffered by the writer until synced
+        self.0.sync()?;
+        Ok(Some(self.take()))
+    }
+
+    /// Completes the last row group and writes the footer of the file, returning the
+    /// remaining bytes.
+    pub fn finish(mut self) -> Result<Bytes, Error> {
+        self.0.finish()?;
+        Ok(self.take())
+    }
+
+    fn take(&mut self) -> Bytes {
+        Bytes::from(std::mem::take(self.0.inner_mut()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int64Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use std::sync::Arc;
+
+    #[test]
+    fn streamed_file() {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "value",
+            DataType::Int64,
+            false,
+        )]));
+        let metadata = HashMap::from([("mosaico:context".to_owned(), "topic".to_owned())]);
+        let mut writer = ExportWriter::try_new(schema.clone(), Some(4), metadata).unwrap();
+
+        let mut file = Vec::new();
+        for start in [0, 3, 6] {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int64Array::from_iter_values(start..start + 3))],
+            )
+            .unwrap();
+            if let Some(bytes) = writer.write(&batch).unwrap() {
+                file.extend_from_slice(&bytes);
+            }
+        }
+        // Row groups are handed out once completed
+        assert!(!file.is_empty());
+        file.extend_from_slice(&writer.finish().unwrap());
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(file)).unwrap();
+        let parquet_metadata = reader.metadata().clone();
+        assert_eq!(parquet_metadata.num_row_groups(), 3);
+        let key_value = parquet_metadata
+            .file_metadata()
+            .key_value_metadata()
+            .unwrap();
+        assert!(
+            key_value
+                .iter()
+                .any(|kv| kv.key == "mosaico:context" && kv.value.as_deref() == Some("topic"))
+        );
+
+        let rows: usize = reader
+            .build()
+            .unwrap()
+            .map(|batch| batch.unwrap().num_rows())
+            .sum();
+        assert_eq!(rows, 9);
+    }
+}
@@ -189,6 +189,10 @@ impl ParquetFormatProperties for ImageFormatProperties {
             .set_column_compression(ts_path.clone(), Compression::UNCOMPRESSED)
             .set_column_statistics_enabled(ts_path.clone(), EnabledStatistics::Page)
             .set_column_bloom_filter_enabled(ts_path, true)
+            // Small row groups are used as seekable segments of the media stream
+            .set_max_row_group_row_count(Some(
+                params::params().media_segment_row_count.value.max(1),
+            ))
             .build()
     }
 
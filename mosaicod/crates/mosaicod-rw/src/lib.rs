@@ -7,12 +7,18 @@ pub mod format;
 pub use format::*;
 
 pub mod chunk_encoder;
-pub use chunk_encoder::{ChunkMetadata, InMemoryChunkEncoder};
+pub use chunk_encoder::{ChunkMetadata, FOOTER_TAIL_SIZE, InMemoryChunkEncoder, RowGroupRange};
 
 mod writer;
 
+pub mod export_writer;
+pub use export_writer::ExportWriter;
+
 pub mod chunk_writer;
 pub use chunk_writer::{ChunkWriter, SerializedChunk};
 
 pub mod chunk_reader;
 pub use chunk_reader::ChunkReader;
+
+pub mod payload_reader;
+pub use payload_reader::ParquetPayload;
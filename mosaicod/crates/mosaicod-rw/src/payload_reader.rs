@@ -0,0 +1,116 @@
+use super::Error;
+use arrow::{array::RecordBatch, datatypes::SchemaRef};
+use bytes::Bytes;
+use parquet::arrow::arrow_reader::{
+    ArrowReaderMetadata, ArrowReaderOptions, ParquetRecordBatchReaderBuilder,
+};
+
+/// Decodes a Parquet file uploaded in place of Arrow IPC batches, returning a record batch
+/// for each of its row groups.
+pub struct ParquetPayload {
+    buffer: Bytes,
+    metadata: ArrowReaderMetadata,
+    /// Index of the next row group to decode
+    next: usize,
+}
+
+impl ParquetPayload {
+    /// Reads the footer of the file in `buffer`, the row groups are decoded while iterating.
+    pub fn try_new(buffer: Bytes) -> Result<Self, Error> {
+        let metadata = ArrowReaderMetadata::load(&buffer, ArrowReaderOptions::default())?;
+        Ok(Self {
+            buffer,
+            metadata,
+            next: 0,
+        })
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        self.metadata.schema().clone()
+    }
+
+    pub fn num_row_groups(&self) -> usize {
+        self.metadata.metadata().num_row_groups()
+    }
+
+    fn row_group(&self, index: usize) -> Result<RecordBatch, Error> {
+        let rows = self.metadata.metadata().row_group(index).num_rows() as usize;
+
+        let reader = ParquetRecordBatchReaderBuilder::new_with_metadata(
+            self.buffer.clone(),
+            self.metadata.clone(),
+        )
+        .with_row_groups(vec![index])
+        .with_batch_size(rows.max(1))
+        .build()?;
+
+        let batches = reader
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| Error::ArrowError(e.into()))?;
+        arrow::compute::concat_batches(&self.schema(), &batches)
+            .map_err(|e| Error::ArrowError(e.into()))
+    }
+}
+
+impl Iterator for ParquetPayload {
+    type Item = Result<RecordBatch, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.num_row_groups() {
+            return None;
+        }
+        let batch = self.row_group(self.next);
+        self.next += 1;
+        Some(batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExportWriter;
+    use arrow::array::{Int64Array, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    #[test]
+    fn row_groups() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("timestamp", DataType::Int64, false),
+            Field::new("label", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from((0..10).collect::<Vec<i64>>())),
+                Arc::new(StringArray::from(
+                    (0..10).map(|i| Some(format!("l{i}"))).collect::<Vec<_>>(),
+                )),
+            ],
+        )
+        .unwrap();
+
+        let mut writer =
+            ExportWriter::try_new(schema.clone(), Some(4), Default::default()).unwrap();
+        let mut file = Vec::new();
+        if let Some(bytes) = writer.write(&batch).unwrap() {
+            file.extend_from_slice(&bytes);
+        }
+        file.extend_from_slice(&writer.finish().unwrap());
+
+        let payload = ParquetPayload::try_new(Bytes::from(file)).unwrap();
+        assert_eq!(payload.schema().fields(), schema.fields());
+        assert_eq!(payload.num_row_groups(), 3);
+
+        let batches = payload.collect::<Result<Vec<_>, _>>().unwrap();
+        let rows: Vec<usize> = batches.iter().map(RecordBatch::num_rows).collect();
+        assert_eq!(rows, [4, 4, 2]);
+        let decoded = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+        assert_eq!(decoded.columns(), batch.columns());
+    }
+
+    #[test]
+    fn not_parquet() {
+        assert!(ParquetPayload::try_new(Bytes::from_static(b"not a parquet file")).is_err());
+    }
+}
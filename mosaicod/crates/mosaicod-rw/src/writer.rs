@@ -4,27 +4,49 @@ use super::Error;
 use arrow::datatypes::Schema;
 use mosaicod_core::types;
 use parquet::arrow::ArrowWriter;
+use parquet::file::metadata::ParquetMetaData;
 use std::sync::Arc;
 
-pub struct ParquetWriter(ArrowWriter<Vec<u8>>);
+pub struct ParquetWriter {
+    writer: ArrowWriter<Vec<u8>>,
+    /// Maximum number of rows of a row group, if bounded
+    max_row_group_rows: Option<usize>,
+}
 
 impl std::ops::Deref for ParquetWriter {
     type Target = ArrowWriter<Vec<u8>>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.writer
     }
 }
 
 impl std::ops::DerefMut for ParquetWriter {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.writer
     }
 }
 
 impl ParquetWriter {
-    pub fn buffer(self) -> Result<Vec<u8>, Error> {
-        Ok(self.0.into_inner()?)
+    /// Maximum number of rows of a row group, `None` if row groups are unbounded.
+    pub fn max_row_group_rows(&self) -> Option<usize> {
+        self.max_row_group_rows
+    }
+
+    /// Takes the bytes of the row groups completed so far, leaving the internal buffer
+    /// empty.
+    ///
+    /// Offsets are tracked by the underlying writer, so taking the buffer does not affect
+    /// the file being written.
+    pub fn take_completed(&mut self) -> Result<Vec<u8>, Error> {
+        self.writer.sync()?;
+        Ok(std::mem::take(self.writer.inner_mut()))
+    }
+
+    /// Finalizes the parquet file, returning the serialized buffer and the file metadata.
+    pub fn finish(mut self) -> Result<(Vec<u8>, ParquetMetaData), Error> {
+        let metadata = self.writer.finish()?;
+        Ok((std::mem::take(self.writer.inner_mut()), metadata))
     }
 }
 
@@ -42,10 +64,15 @@ impl Writer {
 
         let props = parquet_strategy.writer_properties();
 
-        Ok(Self::Parquet(ParquetWriter(ArrowWriter::try_new(
-            Vec::with_capacity(parquet_strategy.buffer_capacity()),
-            schema.clone(),
-            Some(props),
-        )?)))
+        let max_row_group_rows = props.max_row_group_row_count();
+
+        Ok(Self::Parquet(ParquetWriter {
+            writer: ArrowWriter::try_new(
+                Vec::with_capacity(parquet_strategy.buffer_capacity()),
+                schema.clone(),
+                Some(props),
+            )?,
+            max_row_group_rows,
+        }))
     }
 }
@@ -0,0 +1,40 @@
+//! Background publication of the catalog manifest.
+//!
+//! A task periodically rebuilds the catalog manifest and writes it to the store when the
+//! catalog changed, see [`facade::catalog`].
+use mosaicod_core::params;
+use mosaicod_facade as facade;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Spawns the task publishing the catalog manifest, returns `None` if the manifest is not
+/// published.
+pub(crate) fn spawn_catalog_publisher(
+    context: facade::Context,
+) -> Option<tokio::task::JoinHandle<()>> {
+    let interval = params::params().catalog_manifest_interval.value;
+
+    if interval == 0 {
+        return None;
+    }
+
+    let interval = Duration::from_secs(interval);
+    debug!(
+        "catalog manifest `{}` refreshed every {:?}",
+        facade::catalog::MANIFEST_PATH,
+        interval
+    );
+
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+            match facade::catalog::publish(&context).await {
+                Ok(false) => {}
+                Ok(true) => info!("catalog manifest updated"),
+                Err(e) => warn!("unable to publish the catalog manifest: {}", e),
+            }
+        }
+    }))
+}
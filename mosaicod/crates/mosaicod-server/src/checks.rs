@@ -0,0 +1,256 @@
+//! Validation of the dependencies and the configuration of the server, performed at
+//! startup before serving requests.
+//!
+//! Every check is run independently and its outcome collected in a [`Report`], so all
+//! the problems are reported at once.
+use crate::flight;
+use mosaicod_core::{params, types};
+use mosaicod_db as db;
+use mosaicod_store as store;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Maximum difference between the clock of the server and the clocks of its dependencies.
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(10);
+
+/// Prefix of the name of the objects written to probe the store.
+const STORE_PROBE_PREFIX: &str = "__mosaico_probe_";
+
+/// Outcome of a startup check.
+#[derive(Debug, Clone)]
+pub struct Check {
+    /// Name of the check (e.g. `database`)
+    pub name: &'static str,
+    /// Reason of the failure, [`None`] if the check passed
+    pub failure: Option<String>,
+    pub elapsed: Duration,
+}
+
+impl Check {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Outcomes of the startup checks.
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    /// Returns true if all the checks passed.
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(Check::passed)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &Check> {
+        self.checks.iter().filter(|check| !check.passed())
+    }
+
+    /// Records the outcome of a check started at `start`.
+    pub fn record(&mut self, name: &'static str, start: Instant, outcome: Result<(), String>) {
+        self.checks.push(Check {
+            name,
+            failure: outcome.err(),
+            elapsed: start.elapsed(),
+        });
+    }
+}
+
+/// Runs the startup checks against the provided dependencies.
+pub async fn run(store: &store::Store, database: &db::Database, config: &flight::Config) -> Report {
+    let mut report = Report::default();
+
+    let start = Instant::now();
+    let db_time = database.now().await;
+    report.record(
+        "database",
+        start,
+        db_time
+            .as_ref()
+            .map(|_| ())
+            .map_err(|err| format!("database not reachable: {err}")),
+    );
+
+    let start = Instant::now();
+    let outcome = check_migrations(database).await;
+    report.record("database_migrations", start, outcome);
+
+    let start = Instant::now();
+    let store_time = probe_store(store).await;
+    report.record(
+        "store",
+        start,
+        store_time.as_ref().map(|_| ()).map_err(Clone::clone),
+    );
+
+    let start = Instant::now();
+    report.record("config", start, check_config(config));
+
+    let start = Instant::now();
+    let outcome = check_clock(db_time.ok(), store_time.ok());
+    report.record("clock", start, outcome);
+
+    report
+}
+
+async fn check_migrations(database: &db::Database) -> Result<(), String> {
+    let pending = database
+        .pending_migrations()
+        .await
+        .map_err(|err| format!("unable to read applied migrations: {err}"))?;
+
+    if !pending.is_empty() {
+        return Err(format!("migrations not applied: {}", pending.join(", ")));
+    }
+
+    Ok(())
+}
+
+/// Writes, reads back and deletes a probe object, returning the time the object was
+/// written according to the store.
+async fn probe_store(store: &store::Store) -> Result<SystemTime, String> {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let path = format!("{STORE_PROBE_PREFIX}{nanos}");
+    let content = path.as_bytes().to_vec();
+
+    store
+        .write_bytes(&path, content.clone())
+        .await
+        .map_err(|err| format!("unable to write probe object `{path}`: {err}"))?;
+
+    let outcome = async {
+        let read = store
+            .read_bytes(&path)
+            .await
+            .map_err(|err| format!("unable to read probe object `{path}`: {err}"))?;
+        if read != content {
+            return Err(format!(
+                "probe object `{path}` read back with a different content"
+            ));
+        }
+
+        store
+            .last_modified(&path)
+            .await
+            .map_err(|err| format!("unable to read probe object `{path}`: {err}"))
+    }
+    .await;
+
+    store
+        .delete(&path)
+        .await
+        .map_err(|err| format!("unable to delete probe object `{path}`: {err}"))?;
+
+    outcome
+}
+
+fn check_config(config: &flight::Config) -> Result<(), String> {
+    let params = params::params();
+    let mut issues = Vec::new();
+
+    if params.target_message_size.value > params.max_grpc_message_size.value {
+        issues.push(format!(
+            "{} ({}) exceeds {} ({})",
+            params.target_message_size.env,
+            params.target_message_size.value,
+            params.max_grpc_message_size.env,
+            params.max_grpc_message_size.value
+        ));
+    }
+
+    for rate in [
+        &params.store_slo_max_error_rate,
+        &params.circuit_breaker_error_rate,
+    ] {
+        if !(0.0..=1.0).contains(&rate.value) {
+            issues.push(format!("{} ({}) not between 0 and 1", rate.env, rate.value));
+        }
+    }
+
+    if params.max_db_connections.value == 0 {
+        issues.push(format!("{} is 0", params.max_db_connections.env));
+    }
+
+    if let Err(e) = params.store_layout.value.parse::<types::StoreLayout>() {
+        issues.push(format!("{}: {}", params.store_layout.env, e));
+    }
+
+    if let Some(tls) = config.tls_config() {
+        let files = [&tls.certificate_file, &tls.private_key_file]
+            .into_iter()
+            .chain(tls.client_ca_file.as_ref());
+        for file in files {
+            if !file.is_file() {
+                issues.push(format!("TLS file `{}` not found", file.display()));
+            }
+        }
+    }
+
+    if !issues.is_empty() {
+        return Err(issues.join("; "));
+    }
+
+    Ok(())
+}
+
+/// Checks that the clock of the server agrees with the clocks of the dependencies
+/// reachable.
+fn check_clock(db_time: Option<SystemTime>, store_time: Option<SystemTime>) -> Result<(), String> {
+    let now = SystemTime::now();
+    let mut issues = Vec::new();
+
+    for (dependency, time) in [("database", db_time), ("store", store_time)] {
+        let Some(time) = time else {
+            continue;
+        };
+
+        let skew = match now.duration_since(time) {
+            Ok(skew) => skew,
+            Err(err) => err.duration(),
+        };
+        if skew > MAX_CLOCK_SKEW {
+            issues.push(format!(
+                "clock differs from the {dependency} clock by {:.1}s",
+                skew.as_secs_f64()
+            ));
+        }
+    }
+
+    if !issues.is_empty() {
+        return Err(issues.join("; "));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_skew() {
+        let now = SystemTime::now();
+        assert!(check_clock(Some(now), None).is_ok());
+        assert!(check_clock(None, None).is_ok());
+        assert!(check_clock(Some(now - Duration::from_secs(60)), Some(now)).is_err());
+        assert!(check_clock(Some(now), Some(now + Duration::from_secs(60))).is_err());
+    }
+
+    #[test]
+    fn report() {
+        let mut report = Report::default();
+        report.record("database", Instant::now(), Ok(()));
+        assert!(report.passed());
+
+        report.record("store", Instant::now(), Err("unreachable".to_owned()));
+        assert!(!report.passed());
+        assert_eq!(
+            report.failures().map(|c| c.name).collect::<Vec<_>>(),
+            vec!["store"]
+        );
+    }
+}
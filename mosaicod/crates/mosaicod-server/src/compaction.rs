@@ -0,0 +1,67 @@
+//! Background compaction of upsert topics.
+//!
+//! Rows upserted by later sessions are stored in additional data files and merged when the
+//! topic is read. A task periodically rewrites the merged rows of the topics stored in many
+//! data files, so that reads do not need to merge an ever growing number of files. Topics
+//! are compacted according to the compaction policy of their sequence, falling back to the
+//! server configuration.
+use mosaicod_core::params;
+use mosaicod_facade as facade;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Maximum interval between consecutive checks of the topics due for compaction, so that
+/// the sequences can be compacted more often than the server configuration.
+const MAX_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Spawns the task compacting the upsert topics, returns `None` if the compaction is
+/// disabled.
+pub(crate) fn spawn_upsert_compactor(
+    context: facade::Context,
+) -> Option<tokio::task::JoinHandle<()>> {
+    let params = params::params();
+
+    if params.upsert_compaction_interval.value == 0 {
+        return None;
+    }
+
+    let interval = Duration::from_secs(params.upsert_compaction_interval.value);
+    debug!(
+        "upsert topics with at least {} data files compacted every {:?}",
+        params.upsert_compaction_min_chunks.value.max(2),
+        interval
+    );
+
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval.min(MAX_CHECK_INTERVAL));
+        // The first tick completes immediately
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            compact_upsert_topics(&context).await;
+        }
+    }))
+}
+
+async fn compact_upsert_topics(context: &facade::Context) {
+    let topics = match facade::topic::compaction_candidates(context).await {
+        Ok(topics) => topics,
+        Err(e) => {
+            warn!("unable to retrieve the upsert topics to compact: {}", e);
+            return;
+        }
+    };
+
+    for topic in topics {
+        match facade::topic::compact(context, &topic).await {
+            Ok(true) => info!("upsert topic `{}` compacted", topic.locator()),
+            Ok(false) => debug!("upsert topic `{}` is being updated", topic.locator()),
+            Err(e) => warn!(
+                "unable to compact upsert topic `{}`: {}",
+                topic.locator(),
+                e
+            ),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::flight;
+use super::{checks, flight};
 use mosaicod_core::error::PublicResult as Result;
 use mosaicod_db as db;
 use mosaicod_store as store;
@@ -30,6 +30,12 @@ impl Server {
         }
     }
 
+    /// Runs the startup checks against the dependencies and the configuration of the
+    /// server, see [`checks::run`].
+    pub async fn checks(&self) -> checks::Report {
+        checks::run(&self.store, &self.db, &self.flight_config).await
+    }
+
     /// Start the server and wait for it to finish.
     ///
     /// The `on_start` callback is called once the server has started.
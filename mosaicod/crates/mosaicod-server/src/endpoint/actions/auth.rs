@@ -1,8 +1,8 @@
 use crate::error::*;
-use log::info;
 use mosaicod_core::types;
 use mosaicod_facade as facade;
 use mosaicod_marshal::ActionResponse;
+use tracing::info;
 
 /// Creates a new api key with the given name and metadata.
 pub async fn api_key_create(
@@ -10,9 +10,20 @@ pub async fn api_key_create(
     permissions: String,
     expires_at: Option<types::Timestamp>,
     description: String,
+    default_sequence: Option<String>,
 ) -> Result<ActionResponse> {
     info!("requested new api key");
-    let handle = facade::auth::create(ctx, permissions.parse()?, description, expires_at).await?;
+    let default_sequence = default_sequence
+        .map(|sequence| sequence.parse::<types::SequenceLocator>())
+        .transpose()?;
+    let handle = facade::auth::create(
+        ctx,
+        permissions.parse()?,
+        description,
+        expires_at,
+        default_sequence,
+    )
+    .await?;
     Ok(ActionResponse::api_key_create(handle.api_key().key.into()))
 }
 
@@ -0,0 +1,107 @@
+//! Consumer offset related actions.
+use crate::error::Result;
+use mosaicod_core::{self as core, types};
+use mosaicod_facade as facade;
+use mosaicod_marshal::{self as marshal, ActionResponse};
+use tracing::info;
+
+/// Sequence or topic consumed, topic locators are the ones containing a `/`.
+enum Consumed {
+    Sequence(facade::sequence::Handle),
+    Topic(facade::topic::Handle),
+}
+
+impl Consumed {
+    async fn try_from_locator(ctx: &facade::Context, locator: &str) -> Result<Self> {
+        if locator.contains('/') {
+            let locator = locator.parse::<types::TopicLocator>()?;
+            Ok(Self::Topic(
+                facade::topic::Handle::try_from_locator(ctx, locator).await?,
+            ))
+        } else {
+            let locator = locator.parse::<types::SequenceLocator>()?;
+            Ok(Self::Sequence(
+                facade::sequence::Handle::try_from_locator(ctx, locator).await?,
+            ))
+        }
+    }
+}
+
+/// Commits the offset processed by `consumer`, the activity feed `cursor` of a sequence or
+/// the `watermark_ns` of a topic.
+pub async fn commit(
+    ctx: &facade::Context,
+    consumer: String,
+    locator: String,
+    cursor: Option<String>,
+    watermark_ns: Option<i64>,
+) -> Result<ActionResponse> {
+    info!(
+        "committing offset of consumer `{}` on {}",
+        consumer, locator
+    );
+
+    let offset = match (
+        Consumed::try_from_locator(ctx, &locator).await?,
+        cursor,
+        watermark_ns,
+    ) {
+        (Consumed::Sequence(handle), Some(cursor), None) => {
+            let cursor = cursor.parse::<types::ActivityCursor>()?;
+            facade::consumer::commit_activity(ctx, &handle, &consumer, cursor).await?
+        }
+        (Consumed::Topic(handle), None, Some(watermark_ns)) => {
+            facade::consumer::commit_watermark(ctx, &handle, &consumer, watermark_ns.into()).await?
+        }
+        (Consumed::Sequence(_), _, _) => Err(core::Error::bad_request(
+            "the offset of a sequence is the `cursor` of its activity feed".to_owned(),
+        ))?,
+        (Consumed::Topic(_), _, _) => Err(core::Error::bad_request(
+            "the offset of a topic is its `watermark_ns`".to_owned(),
+        ))?,
+    };
+
+    Ok(ActionResponse::consumer_offset_commit(
+        marshal::responses::ConsumerOffset::new(consumer, locator, Some(offset)),
+    ))
+}
+
+/// Returns the offset committed by `consumer`.
+pub async fn get(
+    ctx: &facade::Context,
+    consumer: String,
+    locator: String,
+) -> Result<ActionResponse> {
+    info!("offset of consumer `{}` on {}", consumer, locator);
+
+    let offset = match Consumed::try_from_locator(ctx, &locator).await? {
+        Consumed::Sequence(handle) => {
+            facade::consumer::sequence_offset(ctx, &handle, &consumer).await?
+        }
+        Consumed::Topic(handle) => facade::consumer::topic_offset(ctx, &handle, &consumer).await?,
+    };
+
+    Ok(ActionResponse::consumer_offset_get(
+        marshal::responses::ConsumerOffset::new(consumer, locator, offset),
+    ))
+}
+
+/// Deletes the offset committed by `consumer`.
+pub async fn delete(
+    ctx: &facade::Context,
+    consumer: String,
+    locator: String,
+) -> Result<ActionResponse> {
+    info!("deleting offset of consumer `{}` on {}", consumer, locator);
+
+    match Consumed::try_from_locator(ctx, &locator).await? {
+        Consumed::Sequence(handle) => {
+            facade::consumer::delete_sequence_offset(ctx, &handle, &consumer).await?
+        }
+        Consumed::Topic(handle) => {
+            facade::consumer::delete_topic_offset(ctx, &handle, &consumer).await?
+        }
+    }
+
+    Ok(ActionResponse::consumer_offset_delete())
+}
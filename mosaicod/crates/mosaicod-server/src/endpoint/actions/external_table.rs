@@ -0,0 +1,48 @@
+//! External table-related actions.
+
+use crate::error::*;
+use mosaicod_core::{self as core, types};
+use mosaicod_facade as facade;
+use mosaicod_marshal::{ActionResponse, requests};
+use tracing::info;
+
+/// Registers a file as an external table of the principal.
+pub async fn register(
+    ctx: &facade::Context,
+    principal: Option<&str>,
+    request: requests::ExternalTableRegister,
+) -> Result<ActionResponse> {
+    info!(
+        "requested new external table `{}` reading `{}`",
+        request.name, request.location
+    );
+
+    let format = request
+        .format
+        .parse::<types::ExternalTableFormat>()
+        .map_err(|e| core::Error::bad_request(e.to_string()))?;
+
+    let table =
+        facade::external_table::register(ctx, principal, request.name, request.location, format)
+            .await?;
+
+    Ok(ActionResponse::external_table_register(table.into()))
+}
+
+/// Returns all the external tables of the principal.
+pub fn list(ctx: &facade::Context, principal: Option<&str>) -> Result<ActionResponse> {
+    info!("requested external table list");
+    let tables = facade::external_table::list(ctx, principal);
+    Ok(ActionResponse::external_table_list(tables.into()))
+}
+
+/// Unregisters an external table of the principal.
+pub fn unregister(
+    ctx: &facade::Context,
+    principal: Option<&str>,
+    name: &str,
+) -> Result<ActionResponse> {
+    info!("requested unregistration of external table `{}`", name);
+    facade::external_table::unregister(ctx, principal, name)?;
+    Ok(ActionResponse::external_table_unregister())
+}
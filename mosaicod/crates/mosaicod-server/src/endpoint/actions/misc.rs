@@ -1,8 +1,15 @@
 use crate::error::{Error, Result};
-use log::info;
-use mosaicod_core::params;
-use mosaicod_marshal::ActionResponse;
+use crate::ingest::IngestMetrics;
+use crate::lockout::KeyLockout;
+use crate::middleware::{RequestMetrics, SecurityFailures};
+use crate::reindex::{self, Reindexer};
+use crate::replay::{self, ReplayCapture};
+use mosaicod_core::{self as core, params};
+use mosaicod_facade as facade;
+use mosaicod_marshal::{ActionResponse, responses};
 use semver;
+use std::sync::Arc;
+use tracing::info;
 
 /// Returns the server version.
 pub fn version() -> Result<ActionResponse> {
@@ -12,6 +19,380 @@ pub fn version() -> Result<ActionResponse> {
     )?))
 }
 
+/// Returns the metrics of the operations performed on the store backend.
+pub fn store_metrics(ctx: &facade::Context) -> Result<ActionResponse> {
+    info!("requested store metrics");
+
+    let snapshot = ctx.store.metrics().snapshot();
+
+    let as_micros = |latency: Option<std::time::Duration>| {
+        latency
+            .filter(|latency| *latency != std::time::Duration::MAX)
+            .map(|latency| latency.as_micros() as u64)
+    };
+
+    Ok(ActionResponse::store_metrics(responses::StoreMetrics {
+        backend: snapshot.backend.clone(),
+        operations: snapshot
+            .operations
+            .iter()
+            .map(|op| responses::StoreOperationMetrics {
+                operation: op.operation.to_string(),
+                count: op.count,
+                errors: op.errors,
+                bytes: op.bytes,
+                latency_us_p50: as_micros(op.latency_quantile(0.5)),
+                latency_us_p99: as_micros(op.p99()),
+            })
+            .collect(),
+    }))
+}
+
+/// Default number of cold data files listed by a tiering report.
+const DEFAULT_TIERING_REPORT_LIMIT: usize = 100;
+
+/// Default number of cold data files moved by a tiering demotion.
+const DEFAULT_TIERING_DEMOTE_LIMIT: usize = 1024;
+
+fn tiering_config(cold_after_secs: Option<u64>) -> facade::tiering::TieringConfig {
+    let config = facade::tiering::TieringConfig::configured();
+    match cold_after_secs {
+        Some(secs) => config.with_cold_after(std::time::Duration::from_secs(secs)),
+        None => config,
+    }
+}
+
+/// Returns the cold data files and the estimated savings of moving them to the archive
+/// storage.
+pub async fn store_tiering_report(
+    ctx: &facade::Context,
+    cold_after_secs: Option<u64>,
+    limit: Option<usize>,
+) -> Result<ActionResponse> {
+    info!("requested store tiering report");
+
+    let config = tiering_config(cold_after_secs);
+    let report =
+        facade::tiering::report(ctx, &config, limit.unwrap_or(DEFAULT_TIERING_REPORT_LIMIT))
+            .await?;
+
+    Ok(ActionResponse::store_tiering_report(
+        responses::StoreTieringReport {
+            data_files: report.data_files,
+            size_bytes: report.size_bytes,
+            monthly_savings: report.monthly_savings,
+            candidates: report
+                .candidates
+                .into_iter()
+                .map(|cold| responses::ColdDataFile {
+                    path: cold.path,
+                    size_bytes: cold.size_bytes,
+                    read_count: cold.read_count,
+                    last_read_ns: cold.last_read_at.map(|ts| ts.as_i64()),
+                })
+                .collect(),
+        },
+    ))
+}
+
+/// Moves the cold data files to the archive storage.
+pub async fn store_tiering_demote(
+    ctx: &facade::Context,
+    cold_after_secs: Option<u64>,
+    limit: Option<usize>,
+) -> Result<ActionResponse> {
+    info!("requested store tiering demotion");
+
+    let config = tiering_config(cold_after_secs);
+    let stats =
+        facade::tiering::demote(ctx, &config, limit.unwrap_or(DEFAULT_TIERING_DEMOTE_LIMIT))
+            .await?;
+
+    Ok(ActionResponse::store_tiering_demote(
+        responses::StoreTieringDemote {
+            demoted: stats.demoted,
+            size_bytes: stats.size_bytes,
+        },
+    ))
+}
+
+/// Returns the estimated monthly storage and egress costs of the sequences whose locator
+/// starts with `scope`.
+pub async fn cost_estimate(ctx: &facade::Context, scope: String) -> Result<ActionResponse> {
+    info!("requested cost estimate of scope `{}`", scope);
+
+    let config = facade::cost::CostConfig::configured();
+    let estimate = facade::cost::estimate(ctx, &config, &scope).await?;
+
+    let into_response = |cost: facade::cost::Cost| responses::Cost {
+        standard_bytes: cost.standard_bytes,
+        archived_bytes: cost.archived_bytes,
+        storage_cost: cost.storage_cost,
+        egress_bytes: cost.egress_bytes,
+        egress_cost: cost.egress_cost,
+        total_cost: cost.total_cost(),
+    };
+
+    Ok(ActionResponse::cost_estimate(responses::CostEstimate {
+        scope,
+        total: into_response(estimate.total),
+        sequences: estimate
+            .sequences
+            .into_iter()
+            .map(|(locator, cost)| responses::SequenceCost {
+                locator: locator.to_string(),
+                cost: into_response(cost),
+            })
+            .collect(),
+    }))
+}
+
+/// Returns the latency from the data to its commit, by topic.
+pub fn ingest_metrics(metrics: &IngestMetrics) -> Result<ActionResponse> {
+    info!("requested ingest metrics");
+
+    let as_millis = |latency: Option<std::time::Duration>| {
+        latency
+            .filter(|latency| *latency != std::time::Duration::MAX)
+            .map(|latency| latency.as_millis() as u64)
+    };
+
+    Ok(ActionResponse::ingest_metrics(responses::IngestMetrics {
+        topics: metrics
+            .snapshot()
+            .into_iter()
+            .map(|(topic, latency)| responses::TopicIngestMetrics {
+                count: latency.count(),
+                skewed: latency.skewed,
+                latency_ms_p50: as_millis(latency.latency_quantile(0.5)),
+                latency_ms_p99: as_millis(latency.latency_quantile(0.99)),
+                buckets: latency
+                    .latency
+                    .iter()
+                    .enumerate()
+                    .map(|(bucket, count)| responses::LatencyBucket {
+                        le_ms: crate::ingest::TopicIngestLatency::latency_bounds()
+                            .nth(bucket)
+                            .map(|bound| bound.as_millis() as u64),
+                        count: *count,
+                    })
+                    .collect(),
+                topic,
+            })
+            .collect(),
+    }))
+}
+
+/// Returns the statistics of the server.
+pub fn server_stats(ctx: &facade::Context, requests: &RequestMetrics) -> Result<ActionResponse> {
+    info!("requested server stats");
+
+    let queries = ctx.query_admission.metrics();
+
+    Ok(ActionResponse::server_stats(responses::ServerStats {
+        queries: responses::QueryStats {
+            max_running: queries.max_running,
+            running: queries.running,
+            max_queued: queries.max_queued,
+            queued: queries.queued,
+            oldest_queued_ms: queries.oldest_queued_age.map(|age| age.as_millis() as u64),
+            admitted: queries.admitted,
+            rejected: queries.rejected,
+            timed_out: queries.timed_out,
+        },
+        requests: requests
+            .snapshot()
+            .into_iter()
+            .map(|(method, metrics)| {
+                let stats = responses::RequestStats {
+                    requests: metrics.requests,
+                    failed: metrics.failed,
+                    latency_us_avg: (metrics.total_latency.as_micros()
+                        / metrics.requests.max(1) as u128)
+                        as u64,
+                };
+                (method, stats)
+            })
+            .collect(),
+        security: security_stats(&requests.security_snapshot().0),
+    }))
+}
+
+/// Returns the requests rejected because of the credentials or of the resources requested,
+/// by peer, along with the peers locked out for sending invalid resource keys.
+pub fn security_report(requests: &RequestMetrics, lockout: &KeyLockout) -> Result<ActionResponse> {
+    info!("requested security report");
+
+    let (_, mut peers) = requests.security_snapshot();
+    let locked = lockout.locked_peers();
+
+    // Peers locked out may have been rejected only because of malformed keys
+    for peer in locked.keys() {
+        peers.entry(*peer).or_default();
+    }
+
+    let mut peers: Vec<_> = peers
+        .into_iter()
+        .map(|(peer, failures)| responses::PeerSecurityReport {
+            peer: peer.to_string(),
+            failures: security_stats(&failures),
+            last_failure_ns: failures.last_failure.map(|ts| ts.as_i64()),
+            locked_out_secs: locked
+                .get(&peer)
+                .map(|remaining| remaining.as_secs().max(1)),
+        })
+        .collect();
+
+    let total = |report: &responses::PeerSecurityReport| {
+        report.failures.unauthenticated
+            + report.failures.permission_denied
+            + report.failures.not_found
+    };
+    peers.sort_by(|a, b| total(b).cmp(&total(a)).then_with(|| a.peer.cmp(&b.peer)));
+
+    Ok(ActionResponse::security_report(responses::SecurityReport {
+        peers,
+    }))
+}
+
+/// Default number of topics processed in a batch by a reindex job.
+const DEFAULT_REINDEX_BATCH_SIZE: usize = 32;
+
+/// Maximum number of topics processed in a batch by a reindex job.
+const MAX_REINDEX_BATCH_SIZE: usize = 1000;
+
+/// Starts the jobs rebuilding the derived state selected by `scope` (`all` for every
+/// scope), returns the status of the started jobs.
+pub fn reindex(
+    ctx: &facade::Context,
+    reindexer: &Arc<Reindexer>,
+    scope: &str,
+    batch_size: Option<usize>,
+) -> Result<ActionResponse> {
+    info!("requested reindex of `{}`", scope);
+
+    let scopes = reindex::Scope::parse_many(scope)?;
+
+    let batch_size = batch_size.unwrap_or(DEFAULT_REINDEX_BATCH_SIZE);
+    if batch_size == 0 {
+        Err(core::Error::bad_request(
+            "batch size must be positive".to_owned(),
+        ))?;
+    }
+    let batch_size = batch_size.min(MAX_REINDEX_BATCH_SIZE);
+
+    let mut jobs = Vec::new();
+    for scope in scopes {
+        jobs.push(reindexer.start(ctx.clone(), scope, batch_size)?);
+    }
+
+    Ok(ActionResponse::reindex(responses::ReindexStatus {
+        jobs: jobs.iter().map(reindex_job).collect(),
+    }))
+}
+
+/// Returns the status of the last reindex job started for each scope.
+pub fn reindex_status(reindexer: &Reindexer) -> Result<ActionResponse> {
+    info!("requested reindex status");
+
+    Ok(ActionResponse::reindex_status(responses::ReindexStatus {
+        jobs: reindexer.jobs().iter().map(reindex_job).collect(),
+    }))
+}
+
+fn reindex_job(job: &reindex::Job) -> responses::ReindexJob {
+    responses::ReindexJob {
+        scope: job.scope.to_string(),
+        running: job.is_running(),
+        processed: job.processed,
+        failed: job.failed,
+        started_at_ns: job.started_at.as_i64(),
+        completed_at_ns: job.completed_at.map(|ts| ts.as_i64()),
+        error: job.error.clone(),
+    }
+}
+
+/// Starts recording the requests received in the next `duration_secs` seconds, discarding
+/// the ones recorded before.
+pub fn replay_capture_start(capture: &ReplayCapture, duration_secs: u64) -> Result<ActionResponse> {
+    info!("requested replay capture for {} seconds", duration_secs);
+
+    let snapshot = capture.start(std::time::Duration::from_secs(duration_secs))?;
+
+    Ok(ActionResponse::replay_capture_start(replay_capture(
+        snapshot,
+    )))
+}
+
+/// Returns the requests recorded by the last replay capture.
+pub fn replay_capture_dump(capture: &ReplayCapture) -> Result<ActionResponse> {
+    info!("requested replay capture dump");
+
+    Ok(ActionResponse::replay_capture_dump(replay_capture(
+        capture.snapshot(),
+    )))
+}
+
+fn replay_capture(snapshot: replay::Snapshot) -> responses::ReplayCapture {
+    responses::ReplayCapture {
+        active: snapshot.active,
+        capacity: snapshot.capacity,
+        started_at_ns: snapshot.started_at.map(|ts| ts.as_i64()),
+        until_ns: snapshot.until.map(|ts| ts.as_i64()),
+        dropped: snapshot.dropped,
+        entries: snapshot.entries.into_iter().map(replay_entry).collect(),
+    }
+}
+
+fn replay_entry(entry: replay::Entry) -> responses::ReplayEntry {
+    let mut response = responses::ReplayEntry {
+        at_ns: entry.at.as_i64(),
+        kind: String::new(),
+        peer: entry.peer,
+        duration_us: entry.duration.as_micros() as u64,
+        status: format!("{:?}", entry.code),
+        action: None,
+        payload: None,
+        payload_bytes: None,
+        locator: None,
+        messages: None,
+        bytes: None,
+    };
+
+    match entry.kind {
+        replay::EntryKind::Action {
+            action,
+            payload,
+            payload_bytes,
+        } => {
+            response.kind = "action".to_owned();
+            response.action = Some(action);
+            response.payload = payload;
+            response.payload_bytes = Some(payload_bytes);
+        }
+        replay::EntryKind::DoPut {
+            locator,
+            messages,
+            bytes,
+        } => {
+            response.kind = "do_put".to_owned();
+            response.locator = locator;
+            response.messages = Some(messages);
+            response.bytes = Some(bytes);
+        }
+    }
+
+    response
+}
+
+fn security_stats(failures: &SecurityFailures) -> responses::SecurityStats {
+    responses::SecurityStats {
+        unauthenticated: failures.unauthenticated,
+        permission_denied: failures.permission_denied,
+        not_found: failures.not_found,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,12 +1,18 @@
 //! Action handlers for Flight DoAction requests.
 //!
 //! This module contains free functions for handling Flight actions,
-//! organized by resource type (sequence, topic, query).
+//! organized by resource type (sequence, topic, query, scheduled query,
+//! query view, external table).
+pub mod consumer;
+pub mod external_table;
 pub mod query;
+pub mod query_view;
+pub mod scheduled_query;
 pub mod sequence;
 pub mod session;
 pub mod topic;
 
 pub mod misc;
+pub mod self_test;
 
 pub mod auth;
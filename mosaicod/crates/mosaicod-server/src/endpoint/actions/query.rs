@@ -1,22 +1,150 @@
 //! Query-related actions.
 
 use crate::error::*;
-use log::{info, trace};
+use mosaicod_core::{
+    self as core,
+    types::{self, auth::Permission},
+};
+use mosaicod_ext as ext;
 use mosaicod_facade as facade;
 use mosaicod_marshal::{self as marshal, ActionResponse};
+use std::collections::HashMap;
+use tracing::{info, trace};
 
 /// Executes a query and returns matching groups.
-pub async fn execute(ctx: &facade::Context, query: serde_json::Value) -> Result<ActionResponse> {
+///
+/// If `max_rows` is set, only the first rows are returned along with a handle used to
+/// fetch the others with [`fetch`]. The query is subject to the limits of the principals
+/// having `permission`, and searches `default_sequence` if it doesn't filter sequences.
+/// Views referenced by name only are resolved in `default_sequence` as well, and external
+/// tables are searched among the ones registered by `principal`.
+pub async fn execute(
+    ctx: &facade::Context,
+    query: serde_json::Value,
+    max_rows: Option<usize>,
+    permission: Permission,
+    default_sequence: Option<&types::SequenceLocator>,
+    principal: Option<&str>,
+) -> Result<ActionResponse> {
     info!("performing a query");
 
+    // Held until the query completes
+    let _permit = ctx.query_admission.admit().await?;
+
+    let query = facade::query_view::resolve(ctx, query, default_sequence).await?;
+    let mut query = facade::external_table::resolve(ctx, query, principal).await?;
+
+    if let Some(sequence) = default_sequence {
+        scope_to_sequence(&mut query, sequence);
+    }
+
+    // Object keys are sorted, so equivalent queries share the same representation
+    let normalized = query.to_string();
+
     let filter = marshal::query_filter_from_serde_value(query)?;
 
     trace!("query filter: {:?}", filter);
 
-    let groups =
-        facade::Query::query(filter, ctx.timeseries_querier.clone(), ctx.db.clone()).await?;
+    let sampling = filter.sampling.clone();
+
+    let limits = ctx.query_limits.limits_for(permission);
+    trace!("query limits: {}", limits);
+
+    let (groups, cache_hit) = facade::Query::query_cached(
+        normalized,
+        filter,
+        ctx.timeseries_querier.clone(),
+        ctx.db.clone(),
+        &ctx.query_cache,
+        &limits,
+    )
+    .await?;
 
     trace!("groups found: {:?}", groups);
 
-    Ok(ActionResponse::Query(groups.into()))
+    let response = if let Some(max_rows) = max_rows {
+        let page = facade::Query::paginate(groups, max_rows, ctx.db.clone()).await?;
+        marshal::responses::Query::from(page.groups).with_handle(page.handle)
+    } else {
+        marshal::responses::Query::from(groups)
+    };
+
+    Ok(ActionResponse::Query(
+        response
+            .with_sampling(sampling.as_ref())
+            .with_cache_hit(cache_hit),
+    ))
+}
+
+/// Restricts a query without a sequence filter to `sequence`.
+fn scope_to_sequence(query: &mut serde_json::Value, sequence: &types::SequenceLocator) {
+    let Some(query) = query.as_object_mut() else {
+        return;
+    };
+
+    let filters_sequences = query
+        .get("sequence")
+        .and_then(serde_json::Value::as_object)
+        .is_some_and(|filter| !filter.is_empty());
+
+    if !filters_sequences {
+        trace!("searching default sequence `{}`", sequence);
+        query.insert(
+            "sequence".to_owned(),
+            serde_json::json!({ "locator": { "$eq": sequence.to_string() } }),
+        );
+    }
+}
+
+/// Plans a SQL statement over the topics in `tables`, indexed by the name of their table,
+/// and returns the ticket used to stream its result with DoGet along with the schema of the
+/// result.
+///
+/// The query is subject to the limits of the principals having `permission`.
+pub async fn sql(
+    ctx: &facade::Context,
+    statement: String,
+    tables: HashMap<String, String>,
+    permission: Permission,
+) -> Result<ActionResponse> {
+    info!("planning a SQL query over {} tables", tables.len());
+
+    let mut tables = tables
+        .into_iter()
+        .map(|(name, locator)| Ok((name, locator.parse::<types::TopicLocator>()?)))
+        .collect::<Result<Vec<_>>>()?;
+    tables.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+    let limits = ctx.query_limits.limits_for(permission);
+
+    let result = facade::sql_query::plan(ctx, &tables, &statement, &limits).await?;
+    let arrow_schema = ext::arrow::schema_to_ipc(&result.schema())?;
+
+    trace!("SQL query schema: {:?}", result.schema());
+
+    let ticket = marshal::flight::ticket_sql_query_to_binary(types::flight::TicketSqlQuery {
+        statement,
+        tables,
+    })?;
+
+    Ok(ActionResponse::sql_query(
+        marshal::responses::SqlQuery::new(ticket, arrow_schema),
+    ))
+}
+
+/// Fetches the next rows of a paginated query.
+pub async fn fetch(
+    ctx: &facade::Context,
+    handle: String,
+    max_rows: usize,
+) -> Result<ActionResponse> {
+    info!("fetching rows of query result `{}`", handle);
+
+    let handle: types::Uuid = handle.parse().map_err(|_| core::Error::bad_uuid(handle))?;
+
+    let page = facade::Query::fetch(&handle, max_rows, ctx.db.clone()).await?;
+
+    Ok(ActionResponse::QueryFetch(
+        marshal::responses::Query::from(page.groups).with_handle(page.handle),
+    ))
 }
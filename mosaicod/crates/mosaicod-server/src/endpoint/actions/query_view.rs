@@ -0,0 +1,53 @@
+//! Query view-related actions.
+
+use crate::error::*;
+use mosaicod_core::types;
+use mosaicod_facade as facade;
+use mosaicod_marshal::{ActionResponse, requests};
+use tracing::info;
+
+/// Creates a new view within a sequence.
+pub async fn create(
+    ctx: &facade::Context,
+    request: requests::QueryViewCreate,
+) -> Result<ActionResponse> {
+    info!(
+        "requested new view `{}` in sequence {}",
+        request.name, request.locator
+    );
+
+    let handle = sequence_handle(ctx, request.locator).await?;
+    let view = facade::query_view::create(ctx, &handle, request.name, request.query).await?;
+
+    Ok(ActionResponse::query_view_create(view.into()))
+}
+
+/// Returns all the views of a sequence.
+pub async fn list(ctx: &facade::Context, locator: String) -> Result<ActionResponse> {
+    info!("requested view list of sequence {}", locator);
+    let handle = sequence_handle(ctx, locator).await?;
+    let views = facade::query_view::list(ctx, &handle).await?;
+    Ok(ActionResponse::query_view_list(views.into()))
+}
+
+/// Deletes a view.
+pub async fn delete(
+    ctx: &facade::Context,
+    request: requests::QueryViewName,
+) -> Result<ActionResponse> {
+    info!(
+        "requested deletion of view `{}` in sequence {}",
+        request.name, request.locator
+    );
+    let handle = sequence_handle(ctx, request.locator).await?;
+    facade::query_view::delete(ctx, &handle, &request.name).await?;
+    Ok(ActionResponse::query_view_delete())
+}
+
+async fn sequence_handle(
+    ctx: &facade::Context,
+    locator: String,
+) -> Result<facade::sequence::Handle> {
+    let locator = locator.parse::<types::SequenceLocator>()?;
+    facade::sequence::Handle::try_from_locator(ctx, locator).await
+}
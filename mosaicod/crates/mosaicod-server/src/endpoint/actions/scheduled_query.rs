@@ -0,0 +1,56 @@
+//! Scheduled query-related actions.
+
+use crate::error::*;
+use mosaicod_facade as facade;
+use mosaicod_marshal::{ActionResponse, requests};
+use tracing::info;
+
+/// Creates a new scheduled query.
+pub async fn create(
+    ctx: &facade::Context,
+    request: requests::ScheduledQueryCreate,
+) -> Result<ActionResponse> {
+    info!("requested new scheduled query `{}`", request.name);
+
+    let condition = request.condition()?;
+    let scheduled_query = facade::scheduled_query::create(
+        ctx,
+        request.name,
+        &request.schedule,
+        request.query,
+        condition,
+    )
+    .await?;
+
+    Ok(ActionResponse::scheduled_query_create(
+        scheduled_query.into(),
+    ))
+}
+
+/// Returns all the scheduled queries.
+pub async fn list(ctx: &facade::Context) -> Result<ActionResponse> {
+    info!("requested scheduled query list");
+    let scheduled_queries = facade::scheduled_query::list(ctx).await?;
+    Ok(ActionResponse::scheduled_query_list(
+        scheduled_queries.into(),
+    ))
+}
+
+/// Evaluates a scheduled query immediately.
+pub async fn run(ctx: &facade::Context, name: &str) -> Result<ActionResponse> {
+    info!("requested run of scheduled query `{}`", name);
+    let evaluation = facade::scheduled_query::run(ctx, name).await?;
+    Ok(ActionResponse::scheduled_query_run(
+        mosaicod_marshal::responses::ScheduledQueryRun {
+            match_count: evaluation.match_count,
+            triggered: evaluation.triggered,
+        },
+    ))
+}
+
+/// Deletes a scheduled query.
+pub async fn delete(ctx: &facade::Context, name: &str) -> Result<ActionResponse> {
+    info!("requested deletion of scheduled query `{}`", name);
+    facade::scheduled_query::delete(ctx, name).await?;
+    Ok(ActionResponse::scheduled_query_delete())
+}
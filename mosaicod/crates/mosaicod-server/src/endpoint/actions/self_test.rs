@@ -0,0 +1,238 @@
+//! Self-test of the server, exercising the lifecycle of a temporary sequence against the
+//! real dependencies (database, store and query engine).
+use crate::endpoint::do_put;
+use crate::error::Result;
+use arrow::{
+    array::{Int64Array, RecordBatch},
+    datatypes::{DataType, Field, Schema},
+};
+use futures::TryStreamExt;
+use mosaicod_core::{self as core, params, types};
+use mosaicod_facade as facade;
+use mosaicod_marshal::{ActionResponse, responses};
+use std::{future::Future, sync::Arc, time::Instant};
+use tracing::{info, warn};
+
+/// Number of rows uploaded and read back by the self-test.
+const ROWS: i64 = 16;
+
+/// Steps of the self-test performed so far.
+#[derive(Default)]
+struct Steps {
+    steps: Vec<responses::SelfTestStep>,
+}
+
+impl Steps {
+    fn failed(&self) -> bool {
+        self.steps.iter().any(|step| step.error.is_some())
+    }
+
+    /// Runs and times `step`, unless a previous step failed.
+    async fn run<T>(&mut self, name: &str, step: impl Future<Output = Result<T>>) -> Option<T> {
+        if self.failed() {
+            return None;
+        }
+
+        let start = Instant::now();
+        let result = step.await;
+        let elapsed_us = start.elapsed().as_micros() as u64;
+
+        let error = result.as_ref().err().map(|err| {
+            warn!("self-test step `{}` failed: {:?}", name, err);
+            err.to_string()
+        });
+
+        self.steps.push(responses::SelfTestStep {
+            step: name.to_owned(),
+            elapsed_us,
+            error,
+        });
+
+        result.ok()
+    }
+}
+
+/// Creates a temporary sequence, uploads a batch into one of its topics, reads it back and
+/// deletes the sequence, timing each step.
+///
+/// The self-test stops at the first failed step, the temporary sequence is deleted anyway.
+pub async fn self_test(ctx: &facade::Context) -> Result<ActionResponse> {
+    info!("requested self-test");
+
+    let sequence = format!("__mosaico_self_test_{}", core::random::alphabetic(10));
+    let sequence_locator: types::SequenceLocator = sequence.parse()?;
+    let topic_locator: types::TopicLocator = format!("{sequence}/probe").parse()?;
+
+    let mut steps = Steps::default();
+
+    let sequence_handle = steps
+        .run("sequence_create", async {
+            facade::sequence::try_create(ctx, sequence_locator.clone(), None).await
+        })
+        .await;
+
+    let session_handle = steps
+        .run("session_create", async {
+            facade::session::try_create(ctx, sequence_locator.clone(), None).await
+        })
+        .await;
+
+    let topic_handle = match &session_handle {
+        Some(session_handle) => {
+            steps
+                .run("topic_create", async {
+                    let metadata = types::TopicOntologyMetadata::new(
+                        types::TopicOntologyProperties {
+                            serialization_format: types::Format::Default,
+                            ontology_tag: "self_test".to_owned(),
+                            sort_key: None,
+                            dedup_policy: types::DedupPolicy::None,
+                            primary_key: None,
+                        },
+                        None,
+                    );
+                    facade::topic::try_create(ctx, topic_locator.clone(), session_handle, metadata)
+                        .await
+                })
+                .await
+        }
+        None => None,
+    };
+
+    if let Some(topic_handle) = topic_handle {
+        steps.run("upload", upload(ctx, topic_handle)).await;
+    }
+
+    if let Some(session_handle) = &session_handle {
+        steps
+            .run("session_finalize", async {
+                facade::session::finalize(ctx, session_handle).await
+            })
+            .await;
+    }
+
+    steps
+        .run("read", async {
+            let rows = read(ctx, topic_locator.clone()).await?;
+            if rows != ROWS as usize {
+                return Err(core::Error::internal(Some(format!(
+                    "read {rows} rows instead of {ROWS}"
+                ))))?;
+            }
+            Ok(())
+        })
+        .await;
+
+    // The temporary sequence is removed even if a previous step failed
+    if let Some(sequence_handle) = sequence_handle {
+        let mut cleanup = Steps::default();
+        cleanup
+            .run("delete", delete(ctx, sequence_handle, topic_locator))
+            .await;
+        steps.steps.extend(cleanup.steps);
+    }
+
+    Ok(ActionResponse::self_test(responses::SelfTest {
+        passed: !steps.failed(),
+        steps: steps.steps,
+    }))
+}
+
+/// Uploads a single batch into the topic, as done by `do_put`.
+async fn upload(ctx: &facade::Context, topic_handle: facade::topic::Handle) -> Result<()> {
+    let batch = batch()?;
+    let topic_uuid = topic_handle.uuid().clone();
+
+    let mut writer = facade::topic::writer(ctx.clone(), topic_handle, batch.schema()).await?;
+    let chunk = writer.write(batch).await?;
+
+    do_put::on_chunk_created(
+        ctx,
+        &topic_uuid,
+        writer.ontology_tag(),
+        writer.schema_version(),
+        chunk.path,
+        chunk.ontology_stats,
+        chunk.metadata,
+    )
+    .await?;
+
+    writer.finalize().await?;
+
+    Ok(())
+}
+
+/// Reads the data of the topic, as done by `do_get`, returning the number of rows read.
+async fn read(ctx: &facade::Context, locator: types::TopicLocator) -> Result<usize> {
+    let topic_handle = facade::topic::Handle::try_from_locator(ctx, locator).await?;
+
+    let path_in_store = topic_handle
+        .path_in_store()
+        .ok_or_else(|| core::Error::missing_doput(topic_handle.locator().to_string()))?;
+
+    let ordering = facade::topic::file_ordering(ctx, &topic_handle).await?;
+    let result = ctx
+        .timeseries_querier
+        .read_ordered(
+            path_in_store.data_folder_path(),
+            types::Format::Default,
+            None,
+            ordering,
+        )
+        .await?;
+
+    let batches: Vec<RecordBatch> = result
+        .stream()
+        .await?
+        .try_collect()
+        .await
+        .map_err(|err| core::Error::internal(Some(err.to_string())))?;
+
+    Ok(batches.iter().map(RecordBatch::num_rows).sum())
+}
+
+/// Deletes the temporary sequence, the data of its topic and the session manifest.
+async fn delete(
+    ctx: &facade::Context,
+    sequence_handle: facade::sequence::Handle,
+    topic_locator: types::TopicLocator,
+) -> Result<()> {
+    let path_in_store = facade::topic::Handle::try_from_locator(ctx, topic_locator)
+        .await
+        .ok()
+        .and_then(|handle| handle.path_in_store().cloned());
+    let sequence_path_in_store = facade::sequence::path_in_store(ctx, &sequence_handle).await?;
+
+    facade::sequence::delete(ctx, sequence_handle, types::allow_data_loss()).await?;
+
+    if let Some(path_in_store) = path_in_store {
+        ctx.store.delete_recursive(path_in_store.root()).await?;
+    }
+    ctx.store
+        .delete_recursive(sequence_path_in_store.root())
+        .await?;
+
+    Ok(())
+}
+
+fn batch() -> Result<RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new(
+            params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+            DataType::Int64,
+            false,
+        ),
+        Field::new("value", DataType::Int64, false),
+    ]));
+
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from_iter_values((0..ROWS).map(|i| i * 1_000))),
+            Arc::new(Int64Array::from_iter_values(0..ROWS)),
+        ],
+    )
+    .map_err(|err| core::Error::internal(Some(err.to_string())))?;
+
+    Ok(batch)
+}
@@ -1,9 +1,12 @@
 //! Sequence-related actions
 use crate::error::{Error, Result};
-use log::{info, trace, warn};
-use mosaicod_core::types::{self, MetadataBlob};
+use mosaicod_core::{
+    self as core,
+    types::{self, MetadataBlob},
+};
 use mosaicod_facade as facade;
 use mosaicod_marshal::{self as marshal, ActionResponse};
+use tracing::{info, trace, warn};
 
 /// Creates a new sequence with the given name and metadata.
 pub async fn create(
@@ -31,7 +34,7 @@ pub async fn create(
     Ok(ActionResponse::sequence_create())
 }
 
-/// Deletes an unlocked sequence.
+/// Moves an unlocked sequence to the trash.
 pub async fn delete(ctx: &facade::Context, name: String) -> Result<ActionResponse> {
     warn!("requested deletion of resource {}", name);
 
@@ -39,18 +42,384 @@ pub async fn delete(ctx: &facade::Context, name: String) -> Result<ActionRespons
 
     let handle = facade::sequence::Handle::try_from_locator(ctx, locator.clone()).await?;
 
-    facade::sequence::delete(ctx, handle, types::allow_data_loss()).await?;
-    warn!("resource {} deleted", locator);
+    facade::sequence::trash(ctx, handle).await?;
+    warn!("resource {} moved to the trash", locator);
 
     Ok(ActionResponse::sequence_delete())
 }
 
+/// Restores a sequence from the trash.
+pub async fn restore(ctx: &facade::Context, name: String) -> Result<ActionResponse> {
+    info!("requested restore of resource {}", name);
+
+    let locator = name.parse::<types::SequenceLocator>()?;
+
+    facade::sequence::restore(ctx, locator.clone()).await?;
+    info!("resource {} restored from the trash", locator);
+
+    Ok(ActionResponse::sequence_restore())
+}
+
+/// Returns the signed attestations of the sessions of a sequence.
+pub async fn attestation(ctx: &facade::Context, name: String) -> Result<ActionResponse> {
+    info!("attestations for {}", name);
+
+    let locator = name.parse::<types::SequenceLocator>()?;
+
+    let handle = facade::sequence::Handle::try_from_locator(ctx, locator).await?;
+
+    let attestations = facade::sequence::attestations(ctx, &handle).await?;
+
+    for attestation in attestations.iter().filter(|a| !a.valid) {
+        warn!(
+            "attestation of session {} does not match its manifest",
+            attestation.attestation.session_uuid
+        );
+    }
+
+    Ok(ActionResponse::sequence_attestation(
+        marshal::responses::SessionAttestationList {
+            attestations: attestations
+                .into_iter()
+                .map(|a| marshal::responses::ResponseSessionAttestation {
+                    attestation: a.attestation.into(),
+                    valid: a.valid,
+                })
+                .collect(),
+        },
+    ))
+}
+
+/// Permanently deletes a sequence in the trash.
+pub async fn purge(ctx: &facade::Context, name: String) -> Result<ActionResponse> {
+    warn!("requested purge of resource {}", name);
+
+    let locator = name.parse::<types::SequenceLocator>()?;
+
+    facade::sequence::purge(ctx, &locator, types::allow_data_loss()).await?;
+    warn!("resource {} deleted", locator);
+
+    Ok(ActionResponse::sequence_purge())
+}
+
+/// Sets or releases the legal hold of a sequence, live or in the trash.
+pub async fn legal_hold(
+    ctx: &facade::Context,
+    name: String,
+    legal_hold: bool,
+    principal: Option<&str>,
+) -> Result<ActionResponse> {
+    let locator = name.parse::<types::SequenceLocator>()?;
+
+    facade::sequence::set_legal_hold(ctx, &locator, legal_hold, principal).await?;
+    if legal_hold {
+        warn!("resource {} placed under legal hold", locator);
+    } else {
+        warn!("legal hold of resource {} released", locator);
+    }
+
+    Ok(ActionResponse::sequence_legal_hold())
+}
+
+/// Sets the compaction policy of the upsert topics of a sequence.
+pub async fn compaction_policy_set(
+    ctx: &facade::Context,
+    name: String,
+    policy: types::CompactionPolicy,
+) -> Result<ActionResponse> {
+    let locator = name.parse::<types::SequenceLocator>()?;
+
+    let handle = facade::sequence::Handle::try_from_locator(ctx, locator).await?;
+
+    facade::sequence::set_compaction_policy(ctx, &handle, &policy).await?;
+    info!(
+        "compaction policy of resource {} set to {:?}",
+        handle.locator(),
+        policy
+    );
+
+    Ok(ActionResponse::sequence_compaction_policy_set())
+}
+
+/// Returns the compaction policy of a sequence.
+pub async fn compaction_policy_get(ctx: &facade::Context, name: String) -> Result<ActionResponse> {
+    let locator = name.parse::<types::SequenceLocator>()?;
+
+    let handle = facade::sequence::Handle::try_from_locator(ctx, locator).await?;
+
+    let policy = facade::sequence::compaction_policy(ctx, &handle).await?;
+
+    Ok(ActionResponse::sequence_compaction_policy_get(
+        policy.into(),
+    ))
+}
+
+/// Compacts the upsert topics of a sequence on demand.
+pub async fn compact(ctx: &facade::Context, name: String) -> Result<ActionResponse> {
+    info!("requested compaction of resource {}", name);
+
+    let locator = name.parse::<types::SequenceLocator>()?;
+
+    let handle = facade::sequence::Handle::try_from_locator(ctx, locator).await?;
+
+    let compacted_topics = facade::sequence::compact(ctx, &handle).await?;
+    info!(
+        "{} topics of resource {} compacted",
+        compacted_topics,
+        handle.locator()
+    );
+
+    Ok(ActionResponse::sequence_compact(
+        marshal::responses::SequenceCompact { compacted_topics },
+    ))
+}
+
+/// Imports the MCAP recording stored at `path` into a new session of a sequence owned by
+/// `principal`, creating the sequence if missing.
+pub async fn import_mcap(
+    ctx: &facade::Context,
+    data: marshal::requests::SequenceImportMcap,
+    principal: Option<&str>,
+) -> Result<ActionResponse> {
+    info!(
+        "requested import of {} into resource {}",
+        data.path, data.locator
+    );
+
+    let locator = data.locator.parse::<types::SequenceLocator>()?;
+
+    let options = facade::mcap_import::ImportOptions {
+        channels: data.channels,
+        user_metadata: data.user_metadata.map(marshal::JsonMetadataBlob::from),
+        owner: principal.map(str::to_owned),
+    };
+
+    let import = facade::mcap_import::import(ctx, &data.path, locator, options).await?;
+    info!(
+        "{} channels of {} imported in session {}",
+        import.channels.len(),
+        data.path,
+        import.session
+    );
+
+    Ok(ActionResponse::sequence_import_mcap(
+        marshal::responses::SequenceImportMcap {
+            session: import.session.to_string(),
+            topics: import
+                .channels
+                .into_iter()
+                .map(|channel| marshal::responses::ImportedChannel {
+                    channel: channel.channel,
+                    locator: channel.locator.to_string(),
+                    message_count: channel.message_count,
+                })
+                .collect(),
+        },
+    ))
+}
+
+/// Writes a sequence and its published topics as a portable archive in the store, under
+/// `prefix`.
+pub async fn export(ctx: &facade::Context, name: String, prefix: &str) -> Result<ActionResponse> {
+    info!("requested export of resource {} under {}", name, prefix);
+
+    let locator = name.parse::<types::SequenceLocator>()?;
+
+    let handle = facade::sequence::Handle::try_from_locator(ctx, locator).await?;
+
+    let export = facade::sequence_archive::export(ctx, &handle, prefix).await?;
+
+    Ok(ActionResponse::sequence_export(
+        marshal::responses::SequenceExport {
+            path: export.path,
+            topics: export.topics,
+            size_bytes: export.size_bytes,
+        },
+    ))
+}
+
+/// Returns the ticket streaming some topics of a sequence time-merged with DoGet, along with
+/// the channel id of each topic.
+pub async fn replay(
+    ctx: &facade::Context,
+    request: marshal::requests::SequenceReplay,
+) -> Result<ActionResponse> {
+    info!("requested replay of {}", request.locator);
+
+    let locator = request.locator.parse::<types::SequenceLocator>()?;
+
+    let timestamp_range =
+        (request.timestamp_ns_start.is_some() || request.timestamp_ns_end.is_some()).then(|| {
+            types::TimestampRange::between(
+                request
+                    .timestamp_ns_start
+                    .map_or_else(types::Timestamp::unbounded_neg, Into::into),
+                request
+                    .timestamp_ns_end
+                    .map_or_else(types::Timestamp::unbounded_pos, Into::into),
+            )
+        });
+
+    let handle = facade::sequence::Handle::try_from_locator(ctx, locator).await?;
+
+    let topics: Vec<types::TopicLocator> =
+        facade::sequence_replay::channels(ctx, &handle, request.topics)
+            .await?
+            .iter()
+            .map(|topic| topic.locator().clone())
+            .collect();
+
+    let channels = topics
+        .iter()
+        .enumerate()
+        .map(|(id, topic)| marshal::responses::ReplayChannel {
+            channel_id: id as u32,
+            locator: topic.to_string(),
+        })
+        .collect();
+
+    let ticket = marshal::flight::ticket_replay_to_binary(types::flight::TicketReplay {
+        topics,
+        timestamp_range,
+    })?;
+
+    Ok(ActionResponse::sequence_replay(
+        marshal::responses::SequenceReplay::new(ticket, channels),
+    ))
+}
+
+/// Recreates a sequence from the archive stored at `path`, its topics are written in a
+/// session owned by `principal`.
+pub async fn import(
+    ctx: &facade::Context,
+    data: marshal::requests::SequenceImport,
+    principal: Option<&str>,
+) -> Result<ActionResponse> {
+    info!("requested import of archive {}", data.path);
+
+    let options = facade::sequence_archive::ImportOptions {
+        locator: data
+            .locator
+            .map(|locator| locator.parse::<types::SequenceLocator>())
+            .transpose()?,
+        preserve_uuids: data.preserve_uuids,
+        owner: principal.map(str::to_owned),
+    };
+
+    let import = facade::sequence_archive::import(ctx, &data.path, options).await?;
+
+    Ok(ActionResponse::sequence_import(
+        marshal::responses::SequenceImport {
+            locator: import.sequence.to_string(),
+            uuid: import.uuid.to_string(),
+            session: import.session.to_string(),
+            topics: import.topics.iter().map(ToString::to_string).collect(),
+        },
+    ))
+}
+
+/// Merges or replaces the user metadata of a sequence whose sessions are not finalized.
+pub async fn update_metadata(
+    ctx: &facade::Context,
+    name: String,
+    user_metadata_str: &str,
+    merge: bool,
+) -> Result<ActionResponse> {
+    info!("requested metadata update of resource {}", name);
+
+    let locator = name.parse::<types::SequenceLocator>()?;
+
+    let user_mdata = marshal::JsonMetadataBlob::try_from_str(user_metadata_str)?;
+
+    let handle = facade::sequence::Handle::try_from_locator(ctx, locator).await?;
+
+    facade::sequence::update_user_metadata(ctx, &handle, user_mdata, merge).await?;
+    trace!("updated metadata of resource {}", handle.locator());
+
+    Ok(ActionResponse::sequence_update_metadata())
+}
+
+/// Number of entries returned by the paged listings when no limit is requested.
+const DEFAULT_LIST_LIMIT: usize = 100;
+
+/// Maximum number of entries returned by the paged listings.
+const MAX_LIST_LIMIT: usize = 1000;
+
+/// Returns the page size for the requested `limit`.
+pub(super) fn page_limit(limit: Option<usize>) -> Result<usize> {
+    match limit {
+        Some(0) => Err(core::Error::bad_request(
+            "`limit` must be positive".to_owned(),
+        ))?,
+        Some(limit) => Ok(limit.min(MAX_LIST_LIMIT)),
+        None => Ok(DEFAULT_LIST_LIMIT),
+    }
+}
+
+/// Lists the sequences whose locator starts with `prefix`, one page at a time.
+pub async fn list(
+    ctx: &facade::Context,
+    prefix: Option<String>,
+    cursor: Option<String>,
+    limit: Option<usize>,
+) -> Result<ActionResponse> {
+    let prefix = prefix.unwrap_or_default();
+    let limit = page_limit(limit)?;
+
+    info!("listing {} sequences starting with `{}`", limit, prefix);
+
+    let page = facade::sequence::page(ctx, &prefix, cursor.as_deref(), limit).await?;
+
+    Ok(ActionResponse::sequence_list(
+        marshal::responses::SequenceList {
+            locators: page
+                .handles
+                .iter()
+                .map(|handle| handle.locator().to_string())
+                .collect(),
+            cursor: page.cursor,
+        },
+    ))
+}
+
+/// Returns a page of the activity feed of a sequence.
+pub async fn activity_feed(
+    ctx: &facade::Context,
+    name: String,
+    since_ns: Option<i64>,
+    cursor: Option<String>,
+    limit: Option<usize>,
+) -> Result<ActionResponse> {
+    info!("activity feed for {}", name);
+
+    let locator = name.parse::<types::SequenceLocator>()?;
+    let limit = page_limit(limit)?;
+    let cursor = cursor
+        .map(|cursor| cursor.parse::<types::ActivityCursor>())
+        .transpose()?;
+    let since = since_ns.map_or_else(types::Timestamp::unbounded_neg, Into::into);
+
+    let handle = facade::sequence::Handle::try_from_locator(ctx, locator).await?;
+
+    let page = facade::sequence::activity_feed(ctx, &handle, since, cursor, limit).await?;
+
+    Ok(ActionResponse::activity_feed(
+        marshal::responses::ActivityFeed {
+            activities: page.activities.into_iter().map(Into::into).collect(),
+            cursor: page.cursor.map(|cursor| cursor.to_string()),
+            end_cursor: page.end_cursor.to_string(),
+        },
+    ))
+}
+
 /// Creates a notification for a sequence.
 pub async fn notification_create(
     ctx: &facade::Context,
     name: String,
     notification_type: String,
-    msg: String,
+    severity: Option<types::NotificationSeverity>,
+    payload: types::NotificationPayload,
+    msg: Option<String>,
 ) -> Result<ActionResponse> {
     info!("new notification for {}", name);
 
@@ -62,24 +431,35 @@ pub async fn notification_create(
         .parse()
         .map_err(|_| Error::invalid_notification_type(&notification_type))?;
 
-    facade::sequence::notify(ctx, &handle, ntype, msg).await?;
+    let severity = severity.unwrap_or_else(|| ntype.default_severity());
+
+    facade::sequence::notify(ctx, &handle, ntype, severity, payload, msg).await?;
 
     Ok(ActionResponse::sequence_notification_create())
 }
 
-/// Lists all notifications for a sequence.
-pub async fn notification_list(ctx: &facade::Context, name: String) -> Result<ActionResponse> {
+/// Lists the notifications of a sequence matching `filter`, one page at a time.
+pub async fn notification_list(
+    ctx: &facade::Context,
+    name: String,
+    filter: types::NotificationFilter,
+    cursor: Option<String>,
+    limit: Option<usize>,
+) -> Result<ActionResponse> {
     info!("notification list for {}", name);
 
     let locator = name.parse::<types::SequenceLocator>()?;
+    let limit = page_limit(limit)?;
+    let cursor = cursor
+        .map(|cursor| cursor.parse::<types::NotificationCursor>())
+        .transpose()?;
 
     let handle = facade::sequence::Handle::try_from_locator(ctx, locator).await?;
 
-    let notifications = facade::sequence::notification_list(ctx, &handle).await?;
+    let page =
+        facade::sequence::notification_list(ctx, &handle, &filter, cursor.as_ref(), limit).await?;
 
-    Ok(ActionResponse::sequence_notification_list(
-        notifications.into(),
-    ))
+    Ok(ActionResponse::sequence_notification_list(page.into()))
 }
 
 /// Purges all notifications for a sequence.
@@ -94,3 +474,27 @@ pub async fn notification_purge(ctx: &facade::Context, name: String) -> Result<A
 
     Ok(ActionResponse::sequence_notification_purge())
 }
+
+/// Returns the ticket streaming the notifications of a sequence as they are created.
+pub async fn notification_stream(
+    ctx: &facade::Context,
+    name: String,
+    min_severity: types::NotificationSeverity,
+) -> Result<ActionResponse> {
+    info!("notification stream for {}", name);
+
+    let locator = name.parse::<types::SequenceLocator>()?;
+
+    // Fails early if the sequence does not exist
+    let handle = facade::sequence::Handle::try_from_locator(ctx, locator).await?;
+
+    let ticket =
+        marshal::flight::ticket_notifications_to_binary(types::flight::TicketNotifications {
+            locator: handle.locator().clone(),
+            min_severity,
+        })?;
+
+    Ok(ActionResponse::sequence_notification_stream(
+        marshal::responses::NotificationStream::new(ticket),
+    ))
+}
@@ -1,17 +1,24 @@
 //! Session related actions.
+use crate::endpoint::DoActionContext;
 use crate::error::Result;
-use log::{info, trace, warn};
 use mosaicod_core::{self as core, types};
 use mosaicod_facade as facade;
 use mosaicod_facade::session;
-use mosaicod_marshal::ActionResponse;
-
-pub async fn create(ctx: &facade::Context, sequence_locator: String) -> Result<ActionResponse> {
+use mosaicod_marshal::{self as marshal, ActionResponse};
+use tracing::{info, trace, warn};
+
+/// Creates a new session owned by `principal`.
+pub async fn create(
+    ctx: &facade::Context,
+    sequence_locator: String,
+    principal: Option<&str>,
+) -> Result<ActionResponse> {
     info!("requested resource {} creation", sequence_locator);
 
     let sequence_locator = sequence_locator.parse::<types::SequenceLocator>()?;
 
-    let session_handle = facade::session::try_create(ctx, sequence_locator).await?;
+    let session_handle =
+        facade::session::try_create(ctx, sequence_locator, principal.map(str::to_owned)).await?;
 
     trace!(
         "created session {} with uuid {}",
@@ -25,22 +32,130 @@ pub async fn create(ctx: &facade::Context, sequence_locator: String) -> Result<A
     ))
 }
 
-pub async fn finalize(ctx: &facade::Context, session_uuid: String) -> Result<ActionResponse> {
+/// Finalizes the session with the given uuid.
+///
+/// The uuid acts as the key of the session, peers sending too many invalid ones are locked
+/// out.
+pub async fn finalize(
+    ctx: &DoActionContext,
+    session_uuid: String,
+    principal: Option<&str>,
+) -> Result<ActionResponse> {
     info!("finalizing session {}", session_uuid);
 
-    let uuid: types::Uuid = session_uuid
-        .parse()
-        .map_err(|_| core::Error::bad_uuid(session_uuid))?;
+    let session_handle = ctx
+        .key_lockout
+        .guard(ctx.peer, "session_finalize", async {
+            let uuid: types::Uuid = session_uuid
+                .parse()
+                .map_err(|_| core::Error::bad_uuid(session_uuid))?;
 
-    let session_handle = session::Handle::try_from_uuid(ctx, &uuid).await?;
+            session::Handle::try_from_uuid(ctx, &uuid).await
+        })
+        .await?;
+    facade::session::check_principal(ctx, &session_handle, principal).await?;
 
     facade::session::finalize(ctx, &session_handle).await?;
 
-    trace!("session `{}` finalized", uuid);
+    trace!("session `{}` finalized", session_handle.uuid());
 
     Ok(ActionResponse::session_finalize())
 }
 
+/// Aborts the session with the given uuid, deleting the data uploaded into it.
+///
+/// The uuid acts as the key of the session, peers sending too many invalid ones are locked
+/// out.
+pub async fn abort(
+    ctx: &DoActionContext,
+    session_uuid: String,
+    principal: Option<&str>,
+) -> Result<ActionResponse> {
+    warn!("aborting session {}", session_uuid);
+
+    let session_handle = ctx
+        .key_lockout
+        .guard(ctx.peer, "session_abort", async {
+            let uuid: types::Uuid = session_uuid
+                .parse()
+                .map_err(|_| core::Error::bad_uuid(session_uuid))?;
+
+            session::Handle::try_from_uuid(ctx, &uuid).await
+        })
+        .await?;
+    facade::session::check_principal(ctx, &session_handle, principal).await?;
+
+    facade::session::abort(ctx, &session_handle, types::allow_data_loss()).await?;
+
+    warn!("session `{}` aborted", session_handle.locator());
+
+    Ok(ActionResponse::session_abort())
+}
+
+/// Allows the API key with fingerprint `fingerprint` to write into the session of
+/// `principal`.
+///
+/// The uuid acts as the key of the session, peers sending too many invalid ones are locked
+/// out.
+pub async fn share(
+    ctx: &DoActionContext,
+    session_uuid: String,
+    fingerprint: String,
+    principal: Option<&str>,
+) -> Result<ActionResponse> {
+    info!("sharing session {} with {}", session_uuid, fingerprint);
+
+    let session_handle = ctx
+        .key_lockout
+        .guard(ctx.peer, "session_share", async {
+            let uuid: types::Uuid = session_uuid
+                .parse()
+                .map_err(|_| core::Error::bad_uuid(session_uuid))?;
+
+            session::Handle::try_from_uuid(ctx, &uuid).await
+        })
+        .await?;
+    facade::session::share(ctx, &session_handle, principal, &fingerprint).await?;
+
+    Ok(ActionResponse::session_share())
+}
+
+/// Lists the sessions of a sequence in creation order, only the ones in `state` if set, one
+/// page at a time.
+pub async fn list(
+    ctx: &facade::Context,
+    sequence_name: String,
+    state: Option<marshal::requests::SessionState>,
+    cursor: Option<String>,
+    limit: Option<usize>,
+) -> Result<ActionResponse> {
+    let limit = super::sequence::page_limit(limit)?;
+
+    info!("listing {} sessions of {}", limit, sequence_name);
+
+    let locator = sequence_name.parse::<types::SequenceLocator>()?;
+    let cursor = cursor
+        .map(|cursor| cursor.parse::<types::SessionCursor>())
+        .transpose()?;
+
+    let handle = facade::sequence::Handle::try_from_locator(ctx, locator).await?;
+
+    let finalized = state.map(|state| state == marshal::requests::SessionState::Finalized);
+    let page =
+        facade::sequence::session_page(ctx, &handle, finalized, cursor.as_ref(), limit).await?;
+
+    Ok(ActionResponse::session_list(
+        marshal::responses::SessionList {
+            sessions: page
+                .sessions
+                .into_iter()
+                .map(marshal::responses::SessionListItem::from)
+                .collect(),
+            cursor: page.cursor.map(|cursor| cursor.to_string()),
+        },
+    ))
+}
+
 pub async fn delete(ctx: &facade::Context, session_locator: String) -> Result<ActionResponse> {
     warn!("deleting session `{}`", session_locator);
 
@@ -1,22 +1,28 @@
 //! Topic-related actions.
 
 use crate::error::{Error, Result};
-use log::{info, trace, warn};
+use arrow::datatypes::SchemaRef;
 use mosaicod_core::{
     self as core,
     types::{self, MetadataBlob},
 };
 use mosaicod_facade as facade;
 use mosaicod_marshal::{self as marshal, ActionResponse};
+use tracing::{info, trace, warn};
 
 /// Creates a new topic with the given name and metadata.
+///
+/// If the topic is an upsert topic created by a previous session, the rows uploaded by the
+/// new session are upserted into the existing topic, provided it is still at the base version
+/// set in `options`.
 pub async fn create(
     ctx: &facade::Context,
     name: String,
     session_uuid: String,
-    serialization_format: types::Format,
-    ontology_tag: String,
+    properties: types::TopicOntologyProperties,
     user_metadata_str: &str,
+    principal: Option<&str>,
+    options: facade::topic::CreateOptions,
 ) -> Result<ActionResponse> {
     info!("requested resource {} creation", name);
 
@@ -26,32 +32,63 @@ pub async fn create(
         .parse()
         .map_err(|_| core::Error::bad_uuid(session_uuid))?;
 
-    let ontology_metadata = types::TopicOntologyMetadata::new(
-        types::TopicOntologyProperties {
-            serialization_format,
-            ontology_tag,
-        },
-        Some(user_mdata),
-    );
+    let ontology_metadata = types::TopicOntologyMetadata::new(properties, Some(user_mdata));
 
     let topic_locator = name.parse::<types::TopicLocator>()?;
 
     let session_handle = facade::session::Handle::try_from_uuid(ctx, &received_uuid).await?;
+    facade::session::check_principal(ctx, &session_handle, principal).await?;
 
-    let topic_handle =
-        facade::topic::try_create(ctx, topic_locator, &session_handle, ontology_metadata).await?;
+    let topic_handle = facade::topic::try_create_with_options(
+        ctx,
+        topic_locator,
+        &session_handle,
+        ontology_metadata,
+        options,
+    )
+    .await?;
 
     trace!(
         "resource `{}` created with uuid {}",
         topic_handle.locator(),
-        topic_handle.uuid(),
+        topic_handle.upload_key(),
     );
 
+    // Upserts into existing topics return the key identifying the upsert
     Ok(ActionResponse::topic_create(
-        topic_handle.uuid().clone().into(),
+        topic_handle.upload_key().clone().into(),
     ))
 }
 
+/// Lists the topics of a sequence sorted by locator, one page at a time.
+pub async fn list(
+    ctx: &facade::Context,
+    sequence_name: String,
+    cursor: Option<String>,
+    limit: Option<usize>,
+) -> Result<ActionResponse> {
+    let limit = super::sequence::page_limit(limit)?;
+
+    info!("listing {} topics of {}", limit, sequence_name);
+
+    let locator = sequence_name.parse::<types::SequenceLocator>()?;
+
+    let handle = facade::sequence::Handle::try_from_locator(ctx, locator).await?;
+
+    let page = facade::sequence::topic_page(ctx, &handle, cursor.as_deref(), limit).await?;
+
+    let mut topics = Vec::with_capacity(page.handles.len());
+    for topic_handle in page.handles {
+        let metadata = facade::topic::metadata(ctx, &topic_handle).await?;
+        topics.push(marshal::responses::TopicListItem::from(metadata));
+    }
+
+    Ok(ActionResponse::topic_list(marshal::responses::TopicList {
+        topics,
+        cursor: page.cursor,
+    }))
+}
+
 /// Deletes a topic (it doesn't matter if it's still open or archived).
 pub async fn delete(ctx: &facade::Context, locator: String) -> Result<ActionResponse> {
     warn!("requested deletion of resource `{}`", locator);
@@ -72,7 +109,9 @@ pub async fn notification_create(
     ctx: &facade::Context,
     locator: String,
     notification_type: String,
-    msg: String,
+    severity: Option<types::NotificationSeverity>,
+    payload: types::NotificationPayload,
+    msg: Option<String>,
 ) -> Result<ActionResponse> {
     info!("notification for {}", locator);
 
@@ -80,28 +119,47 @@ pub async fn notification_create(
 
     let topic_handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
 
-    let notification_type = notification_type
+    let notification_type: types::NotificationType = notification_type
         .parse()
         .map_err(|_| Error::invalid_notification_type(&notification_type))?;
-
-    facade::topic::notify(ctx, &topic_handle, notification_type, msg).await?;
+    let severity = severity.unwrap_or_else(|| notification_type.default_severity());
+
+    facade::topic::notify(
+        ctx,
+        &topic_handle,
+        notification_type,
+        severity,
+        payload,
+        msg,
+    )
+    .await?;
 
     Ok(ActionResponse::topic_notification_create())
 }
 
-/// Lists all notifications for a topic.
-pub async fn notification_list(ctx: &facade::Context, locator: String) -> Result<ActionResponse> {
+/// Lists the notifications of a topic matching `filter`, one page at a time.
+pub async fn notification_list(
+    ctx: &facade::Context,
+    locator: String,
+    filter: types::NotificationFilter,
+    cursor: Option<String>,
+    limit: Option<usize>,
+) -> Result<ActionResponse> {
     info!("notification list for {}", locator);
 
     let topic_locator = locator.parse::<types::TopicLocator>()?;
+    let limit = super::sequence::page_limit(limit)?;
+    let cursor = cursor
+        .map(|cursor| cursor.parse::<types::NotificationCursor>())
+        .transpose()?;
 
     let topic_handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
 
-    let notifications = facade::topic::notification_list(ctx, &topic_handle).await?;
+    let page =
+        facade::topic::notification_list(ctx, &topic_handle, &filter, cursor.as_ref(), limit)
+            .await?;
 
-    Ok(ActionResponse::topic_notification_list(
-        notifications.into(),
-    ))
+    Ok(ActionResponse::topic_notification_list(page.into()))
 }
 
 /// Purges all notifications for a topic.
@@ -116,3 +174,264 @@ pub async fn notification_purge(ctx: &facade::Context, locator: String) -> Resul
 
     Ok(ActionResponse::topic_notification_purge())
 }
+
+/// Returns the time index of a media topic, limited to the segments overlapping the
+/// (optional) timestamp range.
+pub async fn media_index(
+    ctx: &facade::Context,
+    locator: String,
+    timestamp_ns_start: Option<i64>,
+    timestamp_ns_end: Option<i64>,
+) -> Result<ActionResponse> {
+    info!("media index for {}", locator);
+
+    let topic_locator = locator.parse::<types::TopicLocator>()?;
+
+    let topic_handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
+
+    let index = facade::topic::media_index(ctx, &topic_handle).await?;
+
+    let range = types::TimestampRange::between(
+        timestamp_ns_start.map_or_else(types::Timestamp::unbounded_neg, Into::into),
+        timestamp_ns_end.map_or_else(types::Timestamp::unbounded_pos, Into::into),
+    );
+
+    Ok(ActionResponse::topic_media_index(
+        index.range(&range).collect(),
+    ))
+}
+
+/// Exports the published data of a topic as a Parquet file. If `prefix` is set the file is
+/// written in the store, otherwise the ticket streaming it with DoGet is returned.
+pub async fn export(
+    ctx: &facade::Context,
+    request: marshal::requests::TopicExport,
+) -> Result<ActionResponse> {
+    info!("export of {}", request.locator);
+
+    let topic_locator = request.locator.parse::<types::TopicLocator>()?;
+
+    let timestamp_range =
+        (request.timestamp_ns_start.is_some() || request.timestamp_ns_end.is_some()).then(|| {
+            types::TimestampRange::between(
+                request
+                    .timestamp_ns_start
+                    .map_or_else(types::Timestamp::unbounded_neg, Into::into),
+                request
+                    .timestamp_ns_end
+                    .map_or_else(types::Timestamp::unbounded_pos, Into::into),
+            )
+        });
+    let include_metadata = request.include_metadata.unwrap_or(true);
+
+    let topic_handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
+
+    let Some(prefix) = request.prefix else {
+        let ticket =
+            marshal::flight::ticket_topic_export_to_binary(types::flight::TicketTopicExport {
+                locator: topic_handle.locator().clone(),
+                timestamp_range,
+                row_group_size: request.row_group_size,
+                include_metadata,
+            })?;
+
+        return Ok(ActionResponse::topic_export(
+            marshal::responses::TopicExport::streamed(ticket),
+        ));
+    };
+
+    let options = facade::topic_export::ExportOptions {
+        timestamp_range,
+        row_group_size: request.row_group_size,
+        include_metadata,
+    };
+    let (path, size) = facade::topic_export::write(ctx, &topic_handle, options, &prefix).await?;
+
+    Ok(ActionResponse::topic_export(
+        marshal::responses::TopicExport::written(path, size),
+    ))
+}
+
+/// Copies a topic and its data into another sequence, within a new session owned by
+/// `principal`.
+pub async fn copy(
+    ctx: &facade::Context,
+    from: String,
+    to_sequence: String,
+    principal: Option<&str>,
+) -> Result<ActionResponse> {
+    info!("requested copy of {} into {}", from, to_sequence);
+
+    let topic_locator = from.parse::<types::TopicLocator>()?;
+    let sequence_locator = to_sequence.parse::<types::SequenceLocator>()?;
+
+    let topic_handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
+
+    let copy = facade::topic::copy(
+        ctx,
+        &topic_handle,
+        sequence_locator,
+        principal.map(str::to_owned),
+    )
+    .await?;
+
+    trace!("copied topic {} into {}", from, copy.locator());
+
+    Ok(ActionResponse::topic_copy(
+        copy.locator().to_string(),
+        copy.uuid().to_string(),
+    ))
+}
+
+/// Returns the topics the data of a topic was copied from, from the nearest.
+pub async fn lineage(ctx: &facade::Context, locator: String) -> Result<ActionResponse> {
+    info!("lineage of {}", locator);
+
+    let topic_locator = locator.parse::<types::TopicLocator>()?;
+
+    let topic_handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
+
+    let lineage = facade::topic::lineage(ctx, &topic_handle).await?;
+
+    Ok(ActionResponse::topic_lineage(lineage.into()))
+}
+
+/// Returns the schema registered for a topic when it was created, if any.
+pub async fn schema_get(ctx: &facade::Context, locator: String) -> Result<ActionResponse> {
+    info!("schema for {}", locator);
+
+    let topic_locator = locator.parse::<types::TopicLocator>()?;
+
+    let topic_handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
+
+    let current = facade::topic::current_schema_version(ctx, &topic_handle).await?;
+    let arrow_schema = current
+        .as_ref()
+        .map(|current| mosaicod_ext::arrow::schema_to_ipc(&current.schema))
+        .transpose()?;
+
+    Ok(ActionResponse::topic_schema_get(
+        marshal::responses::TopicSchema::new(arrow_schema, current.map(|current| current.version)),
+    ))
+}
+
+/// Registers a new version of the schema of a topic, returning its version.
+pub async fn schema_evolve(
+    ctx: &facade::Context,
+    locator: String,
+    arrow_schema: SchemaRef,
+) -> Result<ActionResponse> {
+    info!("schema evolution for {}", locator);
+
+    let topic_locator = locator.parse::<types::TopicLocator>()?;
+
+    let topic_handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
+
+    let version = facade::topic::evolve_schema(ctx, &topic_handle, arrow_schema).await?;
+    info!("topic {} schema evolved to version {}", locator, version);
+
+    Ok(ActionResponse::topic_schema_evolve(version))
+}
+
+/// Sets the description and unit of the columns of a topic.
+pub async fn columns_update(
+    ctx: &facade::Context,
+    locator: String,
+    columns: Vec<types::ColumnDescription>,
+) -> Result<ActionResponse> {
+    info!("columns update for {}", locator);
+
+    let topic_locator = locator.parse::<types::TopicLocator>()?;
+
+    let topic_handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
+
+    facade::topic::columns_update(ctx, &topic_handle, columns).await?;
+
+    Ok(ActionResponse::topic_columns_update())
+}
+
+/// Returns the statistics of the columns of a topic.
+pub async fn column_stats(
+    ctx: &facade::Context,
+    locator: String,
+    columns: Vec<String>,
+) -> Result<ActionResponse> {
+    info!("column stats for {}", locator);
+
+    let topic_locator = locator.parse::<types::TopicLocator>()?;
+
+    let topic_handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
+
+    let stats = facade::topic::column_stats(ctx, &topic_handle, columns).await?;
+
+    Ok(ActionResponse::topic_column_stats(stats.into()))
+}
+
+/// Redacts the values of some columns of a finalized topic in a timestamp range.
+pub async fn redact(
+    ctx: &facade::Context,
+    request: marshal::requests::TopicRedact,
+    principal: Option<&str>,
+) -> Result<ActionResponse> {
+    info!("requested redaction of {}", request.locator);
+
+    let topic_locator = request.locator.parse::<types::TopicLocator>()?;
+    let mode = request
+        .mode
+        .parse::<types::RedactionMode>()
+        .map_err(|e| core::Error::bad_request(e.to_string()))?;
+
+    let range = types::TimestampRange::between(
+        request
+            .timestamp_ns_start
+            .map_or_else(types::Timestamp::unbounded_neg, Into::into),
+        request
+            .timestamp_ns_end
+            .map_or_else(types::Timestamp::unbounded_pos, Into::into),
+    );
+
+    let topic_handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
+
+    let redaction = facade::topic::redact(
+        ctx,
+        &topic_handle,
+        range,
+        request.columns,
+        mode,
+        request.reason,
+        principal,
+    )
+    .await?;
+    warn!(
+        "redacted {} rows of resource {}",
+        redaction.row_count, redaction.topic
+    );
+
+    Ok(ActionResponse::topic_redact(redaction.into()))
+}
+
+/// Returns the redactions applied to a topic, oldest first.
+pub async fn redaction_list(ctx: &facade::Context, locator: String) -> Result<ActionResponse> {
+    info!("redaction list for {}", locator);
+
+    let topic_locator = locator.parse::<types::TopicLocator>()?;
+
+    let topic_handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
+
+    let redactions = facade::topic::redactions(ctx, &topic_handle).await?;
+
+    Ok(ActionResponse::topic_redaction_list(redactions.into()))
+}
+
+/// Returns the ingestion watermark of a topic.
+pub async fn watermark(ctx: &facade::Context, locator: String) -> Result<ActionResponse> {
+    info!("watermark of {}", locator);
+
+    let topic_locator = locator.parse::<types::TopicLocator>()?;
+
+    let topic_handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
+
+    let watermark = facade::topic::watermark(ctx, &topic_handle).await?;
+
+    Ok(ActionResponse::topic_watermark(watermark.into()))
+}
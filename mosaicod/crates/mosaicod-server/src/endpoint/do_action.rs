@@ -3,23 +3,58 @@
 //! This module implements the main dispatcher for Flight DoAction requests,
 //! delegating to specialized handler functions for each action category.
 
-use super::actions::{misc, query as query_action, sequence, session, topic};
+use super::actions::{
+    consumer, external_table, misc, query as query_action, query_view, scheduled_query, self_test,
+    sequence, session, topic,
+};
 use crate::endpoint::actions::auth;
-use crate::error::Result;
-use mosaicod_core::{self as core, types::auth::Permission};
+use crate::error::{Error, Result};
+use crate::ingest::IngestMetrics;
+use crate::lockout::KeyLockout;
+use crate::middleware::{AuthContext, RequestMetrics};
+use crate::registry::ActionRegistry;
+use crate::reindex::Reindexer;
+use crate::replay::ReplayCapture;
+use mosaicod_core::{
+    self as core,
+    types::{self, auth::Permission},
+};
 use mosaicod_facade as facade;
 use mosaicod_marshal::{ActionRequest, ActionResponse};
+use std::sync::Arc;
+
+pub struct DoActionContext {
+    pub inner: facade::Context,
+    pub request_metrics: Arc<RequestMetrics>,
+    pub key_lockout: Arc<KeyLockout>,
+    pub(crate) ingest_metrics: Arc<IngestMetrics>,
+    pub(crate) reindexer: Arc<Reindexer>,
+    pub(crate) replay_capture: Arc<ReplayCapture>,
+    /// Address of the peer that sent the action, if known
+    pub peer: Option<std::net::IpAddr>,
+}
+
+impl std::ops::Deref for DoActionContext {
+    type Target = facade::Context;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
 
 /// Dispatches a Flight action request to the appropriate handler.
 ///
 /// This function serves as the main entry point for all Flight DoAction requests,
 /// routing each action type to its specialized handler function.
+#[tracing::instrument(skip_all, fields(action = %action))]
 pub async fn do_action(
-    ctx: &facade::Context,
+    ctx: &DoActionContext,
+    registry: &ActionRegistry,
     action: ActionRequest,
-    perm: &Permission,
+    auth_ctx: &AuthContext,
 ) -> Result<ActionResponse> {
-    if !has_permissions(&action, perm) {
+    let perm = auth_ctx.permissions();
+
+    if !has_permissions(registry, &action, perm) {
         let err_msg = format!(
             "provided API key has not enough permissions to execute {} action.",
             action
@@ -35,50 +70,236 @@ pub async fn do_action(
             sequence::create(ctx, data.locator, user_metadata.as_str()).await
         }
         ActionRequest::SequenceDelete(data) => sequence::delete(ctx, data.locator).await,
+        ActionRequest::SequenceRestore(data) => sequence::restore(ctx, data.locator).await,
+        ActionRequest::SequencePurge(data) => sequence::purge(ctx, data.locator).await,
+        ActionRequest::SequenceLegalHold(data) => {
+            sequence::legal_hold(ctx, data.locator, data.legal_hold, auth_ctx.principal()).await
+        }
+        ActionRequest::SequenceCompactionPolicySet(data) => {
+            let policy = data.policy();
+            sequence::compaction_policy_set(ctx, data.locator, policy).await
+        }
+        ActionRequest::SequenceCompactionPolicyGet(data) => {
+            sequence::compaction_policy_get(ctx, data.locator).await
+        }
+        ActionRequest::SequenceCompact(data) => sequence::compact(ctx, data.locator).await,
+        ActionRequest::SequenceImportMcap(data) => {
+            sequence::import_mcap(ctx, data, auth_ctx.principal()).await
+        }
+        ActionRequest::SequenceExport(data) => {
+            sequence::export(ctx, data.locator, &data.prefix).await
+        }
+        ActionRequest::SequenceImport(data) => {
+            sequence::import(ctx, data, auth_ctx.principal()).await
+        }
+        ActionRequest::SequenceReplay(data) => sequence::replay(ctx, data).await,
+        ActionRequest::SequenceUpdateMetadata(data) => {
+            let user_metadata = data.user_metadata()?;
+            sequence::update_metadata(ctx, data.locator, user_metadata.as_str(), data.merge).await
+        }
+        ActionRequest::SequenceList(data) => {
+            sequence::list(ctx, data.prefix, data.cursor, data.limit).await
+        }
+        ActionRequest::SequenceAttestation(data) => sequence::attestation(ctx, data.locator).await,
+        ActionRequest::ActivityFeed(data) => {
+            sequence::activity_feed(ctx, data.locator, data.since_ns, data.cursor, data.limit).await
+        }
         ActionRequest::SequenceNotificationCreate(data) => {
-            sequence::notification_create(ctx, data.locator, data.notification_type, data.msg).await
+            let payload = data.payload()?;
+            let severity = data.severity()?;
+            sequence::notification_create(
+                ctx,
+                data.locator,
+                data.notification_type,
+                severity,
+                payload,
+                data.msg,
+            )
+            .await
         }
         ActionRequest::SequenceNotificationList(data) => {
-            sequence::notification_list(ctx, data.locator).await
+            let filter = data.filter()?;
+            sequence::notification_list(ctx, data.locator, filter, data.cursor, data.limit).await
         }
         ActionRequest::SequenceNotificationPurge(data) => {
             sequence::notification_purge(ctx, data.locator).await
         }
+        ActionRequest::SequenceNotificationStream(data) => {
+            let min_severity = data.min_severity()?;
+            sequence::notification_stream(ctx, data.locator, min_severity).await
+        }
 
         // ///////
         // Session
-        ActionRequest::SessionCreate(data) => session::create(ctx, data.locator).await,
-        ActionRequest::SessionFinalize(data) => session::finalize(ctx, data.session_uuid).await,
+        ActionRequest::SessionCreate(data) => {
+            session::create(ctx, data.locator, auth_ctx.principal()).await
+        }
+        ActionRequest::SessionFinalize(data) => {
+            session::finalize(ctx, data.session_uuid, auth_ctx.principal()).await
+        }
+        ActionRequest::SessionAbort(data) => {
+            session::abort(ctx, data.session_uuid, auth_ctx.principal()).await
+        }
+        ActionRequest::SessionShare(data) => {
+            session::share(
+                ctx,
+                data.session_uuid,
+                data.api_key_fingerprint,
+                auth_ctx.principal(),
+            )
+            .await
+        }
         ActionRequest::SessionDelete(data) => session::delete(ctx, data.locator).await,
+        ActionRequest::SessionList(data) => {
+            session::list(ctx, data.locator, data.state, data.cursor, data.limit).await
+        }
+
+        // ///////////////
+        // Consumer Offset
+        ActionRequest::ConsumerOffsetCommit(data) => {
+            consumer::commit(
+                ctx,
+                data.consumer,
+                data.locator,
+                data.cursor,
+                data.watermark_ns,
+            )
+            .await
+        }
+        ActionRequest::ConsumerOffsetGet(data) => {
+            consumer::get(ctx, data.consumer, data.locator).await
+        }
+        ActionRequest::ConsumerOffsetDelete(data) => {
+            consumer::delete(ctx, data.consumer, data.locator).await
+        }
 
         // /////
         // Topic
         ActionRequest::TopicCreate(data) => {
             let user_metadata = data.user_metadata()?;
+            let arrow_schema = data
+                .arrow_schema()?
+                .map(|buffer| mosaicod_ext::arrow::schema_from_ipc(&buffer))
+                .transpose()?;
+            let options = facade::topic::CreateOptions {
+                base_version: data.base_version,
+                arrow_schema,
+                uuid: None,
+            };
+            let properties = types::TopicOntologyProperties {
+                serialization_format: data.serialization_format.into(),
+                ontology_tag: data.ontology_tag,
+                sort_key: data.sort_key,
+                dedup_policy: data.dedup_policy.map(Into::into).unwrap_or_default(),
+                primary_key: data.primary_key,
+            };
             topic::create(
                 ctx,
                 data.locator,
                 data.session_uuid,
-                data.serialization_format.into(),
-                data.ontology_tag,
+                properties,
                 user_metadata.as_str(),
+                auth_ctx.principal(),
+                options,
             )
             .await
         }
+        ActionRequest::TopicList(data) => {
+            topic::list(ctx, data.locator, data.cursor, data.limit).await
+        }
         ActionRequest::TopicDelete(data) => topic::delete(ctx, data.locator).await,
         ActionRequest::TopicNotificationCreate(data) => {
-            topic::notification_create(ctx, data.locator, data.notification_type, data.msg).await
+            let payload = data.payload()?;
+            let severity = data.severity()?;
+            topic::notification_create(
+                ctx,
+                data.locator,
+                data.notification_type,
+                severity,
+                payload,
+                data.msg,
+            )
+            .await
         }
         ActionRequest::TopicNotificationList(data) => {
-            topic::notification_list(ctx, data.locator).await
+            let filter = data.filter()?;
+            topic::notification_list(ctx, data.locator, filter, data.cursor, data.limit).await
         }
         ActionRequest::TopicNotificationPurge(data) => {
             topic::notification_purge(ctx, data.locator).await
         }
+        ActionRequest::TopicMediaIndex(data) => {
+            topic::media_index(
+                ctx,
+                data.locator,
+                data.timestamp_ns_start,
+                data.timestamp_ns_end,
+            )
+            .await
+        }
+        ActionRequest::TopicExport(data) => topic::export(ctx, data).await,
+        ActionRequest::TopicCopy(data) => {
+            topic::copy(ctx, data.from, data.to_sequence, auth_ctx.principal()).await
+        }
+        ActionRequest::TopicLineage(data) => topic::lineage(ctx, data.locator).await,
+        ActionRequest::TopicSchemaGet(data) => topic::schema_get(ctx, data.locator).await,
+        ActionRequest::TopicSchemaEvolve(data) => {
+            let arrow_schema = mosaicod_ext::arrow::schema_from_ipc(&data.arrow_schema()?)?;
+            topic::schema_evolve(ctx, data.locator, arrow_schema).await
+        }
+        ActionRequest::TopicColumnsUpdate(data) => {
+            let locator = data.locator.clone();
+            topic::columns_update(ctx, locator, data.columns()).await
+        }
+        ActionRequest::TopicColumnStats(data) => {
+            topic::column_stats(ctx, data.locator, data.columns).await
+        }
+        ActionRequest::TopicRedact(data) => topic::redact(ctx, data, auth_ctx.principal()).await,
+        ActionRequest::TopicRedactionList(data) => topic::redaction_list(ctx, data.locator).await,
+        ActionRequest::TopicWatermark(data) => topic::watermark(ctx, data.locator).await,
 
         // /////
         // Query
-        ActionRequest::Query(data) => query_action::execute(ctx, data.query).await,
+        ActionRequest::Query(data) => {
+            query_action::execute(
+                ctx,
+                data.query,
+                data.max_rows,
+                *perm,
+                auth_ctx.default_sequence(),
+                auth_ctx.principal(),
+            )
+            .await
+        }
+        ActionRequest::QueryFetch(data) => {
+            query_action::fetch(ctx, data.handle, data.max_rows).await
+        }
+        ActionRequest::SqlQuery(data) => {
+            query_action::sql(ctx, data.statement, data.tables, *perm).await
+        }
+
+        // ///////////////
+        // Scheduled Query
+        ActionRequest::ScheduledQueryCreate(data) => scheduled_query::create(ctx, data).await,
+        ActionRequest::ScheduledQueryList(_) => scheduled_query::list(ctx).await,
+        ActionRequest::ScheduledQueryRun(data) => scheduled_query::run(ctx, &data.name).await,
+        ActionRequest::ScheduledQueryDelete(data) => scheduled_query::delete(ctx, &data.name).await,
+
+        // //////////
+        // Query View
+        ActionRequest::QueryViewCreate(data) => query_view::create(ctx, data).await,
+        ActionRequest::QueryViewList(data) => query_view::list(ctx, data.locator).await,
+        ActionRequest::QueryViewDelete(data) => query_view::delete(ctx, data).await,
+
+        // //////////////
+        // External Table
+        ActionRequest::ExternalTableRegister(data) => {
+            external_table::register(ctx, auth_ctx.principal(), data).await
+        }
+        ActionRequest::ExternalTableList(_) => external_table::list(ctx, auth_ctx.principal()),
+        ActionRequest::ExternalTableUnregister(data) => {
+            external_table::unregister(ctx, auth_ctx.principal(), &data.name)
+        }
 
         // ////
         // Api Key
@@ -88,6 +309,7 @@ pub async fn do_action(
                 data.permissions,
                 data.expires_at_ns.map(Into::into),
                 data.description,
+                data.default_sequence,
             )
             .await
         }
@@ -103,33 +325,130 @@ pub async fn do_action(
         // /////
         // Misc
         ActionRequest::Version(_) => misc::version(),
+        ActionRequest::StoreMetrics(_) => misc::store_metrics(ctx),
+        ActionRequest::StoreTieringReport(data) => {
+            misc::store_tiering_report(ctx, data.cold_after, data.limit).await
+        }
+        ActionRequest::StoreTieringDemote(data) => {
+            misc::store_tiering_demote(ctx, data.cold_after, data.limit).await
+        }
+        ActionRequest::CostEstimate(data) => misc::cost_estimate(ctx, data.scope).await,
+        ActionRequest::IngestMetrics(_) => misc::ingest_metrics(&ctx.ingest_metrics),
+        ActionRequest::ServerStats(_) => misc::server_stats(ctx, &ctx.request_metrics),
+        ActionRequest::SelfTest(_) => self_test::self_test(ctx).await,
+        ActionRequest::SecurityReport(_) => {
+            misc::security_report(&ctx.request_metrics, &ctx.key_lockout)
+        }
+        ActionRequest::Reindex(data) => {
+            misc::reindex(ctx, &ctx.reindexer, &data.scope, data.batch_size)
+        }
+        ActionRequest::ReindexStatus(_) => misc::reindex_status(&ctx.reindexer),
+        ActionRequest::ReplayCaptureStart(data) => {
+            misc::replay_capture_start(&ctx.replay_capture, data.duration_secs)
+        }
+        ActionRequest::ReplayCaptureDump(_) => misc::replay_capture_dump(&ctx.replay_capture),
+
+        // //////
+        // Custom
+        ActionRequest::Custom(data) => {
+            let handler = registry
+                .get(&data.namespace, &data.name)
+                .ok_or_else(|| Error::unknown_custom_action(&data.to_string()))?;
+            let response = handler.handle(ctx, &data.body).await?;
+            Ok(ActionResponse::custom(response))
+        }
     }
 }
 
 /// Return true if the requested action matches the permissions, false otherwise
-fn has_permissions(action: &ActionRequest, perm: &Permission) -> bool {
+fn has_permissions(registry: &ActionRegistry, action: &ActionRequest, perm: &Permission) -> bool {
     match action {
         ActionRequest::SequenceCreate(_) => perm.can_write(),
+        ActionRequest::SequenceImportMcap(_) => perm.can_write(),
+        ActionRequest::SequenceExport(_) => perm.can_write(),
+        ActionRequest::SequenceImport(_) => perm.can_write(),
+        ActionRequest::SequenceReplay(_) => perm.can_read(),
         ActionRequest::SequenceNotificationCreate(_) => perm.can_write(),
         ActionRequest::TopicCreate(_) => perm.can_write(),
+        ActionRequest::TopicCopy(_) => perm.can_write(),
         ActionRequest::TopicNotificationCreate(_) => perm.can_write(),
+        ActionRequest::TopicColumnsUpdate(_) => perm.can_write(),
+        ActionRequest::TopicSchemaEvolve(_) => perm.can_write(),
+        ActionRequest::SequenceUpdateMetadata(_) => perm.can_write(),
         ActionRequest::SessionCreate(_) => perm.can_write(),
         ActionRequest::SessionFinalize(_) => perm.can_write(),
+        ActionRequest::SessionAbort(_) => perm.can_write(),
+        ActionRequest::SessionShare(_) => perm.can_write(),
+        ActionRequest::ConsumerOffsetCommit(_) => perm.can_write(),
+        ActionRequest::ConsumerOffsetDelete(_) => perm.can_write(),
+        ActionRequest::ScheduledQueryCreate(_) => perm.can_write(),
+        ActionRequest::ScheduledQueryRun(_) => perm.can_write(),
+        ActionRequest::QueryViewCreate(_) => perm.can_write(),
 
         ActionRequest::SequenceDelete(_) => perm.can_delete(),
+        ActionRequest::SequenceRestore(_) => perm.can_delete(),
+        ActionRequest::SequencePurge(_) => perm.can_delete(),
         ActionRequest::SequenceNotificationPurge(_) => perm.can_delete(),
         ActionRequest::TopicDelete(_) => perm.can_delete(),
         ActionRequest::TopicNotificationPurge(_) => perm.can_delete(),
         ActionRequest::SessionDelete(_) => perm.can_delete(),
+        ActionRequest::SessionList(_) => perm.can_read(),
+        ActionRequest::ConsumerOffsetGet(_) => perm.can_read(),
+        ActionRequest::ScheduledQueryDelete(_) => perm.can_delete(),
+        ActionRequest::QueryViewDelete(_) => perm.can_delete(),
 
         ActionRequest::Query(_) => perm.can_read(),
+        ActionRequest::QueryFetch(_) => perm.can_read(),
+        ActionRequest::SqlQuery(_) => perm.can_read(),
+        ActionRequest::SequenceList(_) => perm.can_read(),
+        ActionRequest::TopicList(_) => perm.can_read(),
+        ActionRequest::ActivityFeed(_) => perm.can_read(),
         ActionRequest::SequenceNotificationList(_) => perm.can_read(),
+        ActionRequest::SequenceNotificationStream(_) => perm.can_read(),
+        ActionRequest::SequenceAttestation(_) => perm.can_read(),
+        ActionRequest::SequenceCompactionPolicyGet(_) => perm.can_read(),
         ActionRequest::TopicNotificationList(_) => perm.can_read(),
+        ActionRequest::TopicMediaIndex(_) => perm.can_read(),
+        // Exports written in the store require the permission to write
+        ActionRequest::TopicExport(data) if data.prefix.is_some() => perm.can_write(),
+        ActionRequest::TopicExport(_) => perm.can_read(),
+        ActionRequest::TopicLineage(_) => perm.can_read(),
+        ActionRequest::TopicSchemaGet(_) => perm.can_read(),
+        ActionRequest::TopicColumnStats(_) => perm.can_read(),
+        ActionRequest::TopicRedactionList(_) => perm.can_read(),
+        ActionRequest::TopicWatermark(_) => perm.can_read(),
+        ActionRequest::ScheduledQueryList(_) => perm.can_read(),
+        ActionRequest::QueryViewList(_) => perm.can_read(),
+        // External tables are visible only to the principal registering them
+        ActionRequest::ExternalTableRegister(_) => perm.can_read(),
+        ActionRequest::ExternalTableList(_) => perm.can_read(),
+        ActionRequest::ExternalTableUnregister(_) => perm.can_read(),
 
+        ActionRequest::SequenceLegalHold(_) => perm.can_manage(),
+        ActionRequest::SequenceCompactionPolicySet(_) => perm.can_manage(),
+        ActionRequest::SequenceCompact(_) => perm.can_manage(),
+        ActionRequest::TopicRedact(_) => perm.can_manage(),
         ActionRequest::ApiKeyCreate(_) => perm.can_manage(),
         ActionRequest::ApiKeyStatus(_) => perm.can_manage(),
         ActionRequest::ApiKeyRevoke(_) => perm.can_manage(),
+        ActionRequest::StoreMetrics(_) => perm.can_manage(),
+        ActionRequest::StoreTieringReport(_) => perm.can_manage(),
+        ActionRequest::StoreTieringDemote(_) => perm.can_manage(),
+        ActionRequest::CostEstimate(_) => perm.can_manage(),
+        ActionRequest::IngestMetrics(_) => perm.can_manage(),
+        ActionRequest::ServerStats(_) => perm.can_manage(),
+        ActionRequest::SelfTest(_) => perm.can_manage(),
+        ActionRequest::SecurityReport(_) => perm.can_manage(),
+        ActionRequest::Reindex(_) => perm.can_manage(),
+        ActionRequest::ReindexStatus(_) => perm.can_manage(),
+        ActionRequest::ReplayCaptureStart(_) => perm.can_manage(),
+        ActionRequest::ReplayCaptureDump(_) => perm.can_manage(),
 
         ActionRequest::Version(_) => true,
+
+        // Unknown custom actions are let through and rejected by the dispatcher
+        ActionRequest::Custom(data) => registry
+            .get(&data.namespace, &data.name)
+            .is_none_or(|handler| handler.is_allowed(perm)),
     }
 }
@@ -1,4 +1,6 @@
 use crate::error::Result;
+use arrow::array::{BinaryArray, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use arrow::ipc::CompressionType;
 use arrow::ipc::writer::IpcWriteOptions;
 use arrow_flight::{
@@ -6,15 +8,39 @@ use arrow_flight::{
     encode::{FlightDataEncoder, FlightDataEncoderBuilder, GRPC_TARGET_MAX_FLIGHT_SIZE_BYTES},
     error::FlightError,
 };
-use futures::TryStreamExt;
-use log::{debug, info, trace};
-use mosaicod_core::{self as core, params};
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use mosaicod_core::{
+    self as core, params,
+    types::{self, auth::Permission},
+};
+use mosaicod_ext as ext;
 use mosaicod_facade as facade;
 use mosaicod_marshal as marshal;
+use mosaicod_query as query;
+use std::sync::Arc;
+use tracing::{debug, info, trace};
 
-pub async fn do_get(ctx: &facade::Context, ticket: Ticket) -> Result<FlightDataEncoder> {
-    let ticket = marshal::flight::ticket_topic_from_binary(&ticket.ticket)?;
+/// Streams the data served by `ticket`, queries are subject to the limits of the principals
+/// having `permission`.
+pub async fn do_get(
+    ctx: &facade::Context,
+    ticket: Ticket,
+    permission: Permission,
+) -> Result<FlightDataEncoder> {
+    match marshal::flight::ticket_from_binary(&ticket.ticket)? {
+        types::flight::Ticket::Topic(ticket) => do_get_topic(ctx, ticket).await,
+        types::flight::Ticket::SqlQuery(ticket) => do_get_sql_query(ctx, ticket, permission).await,
+        types::flight::Ticket::Notifications(ticket) => do_get_notifications(ctx, ticket).await,
+        types::flight::Ticket::TopicExport(ticket) => do_get_topic_export(ctx, ticket).await,
+        types::flight::Ticket::Replay(ticket) => do_get_replay(ctx, ticket).await,
+    }
+}
 
+async fn do_get_topic(
+    ctx: &facade::Context,
+    ticket: types::flight::TicketTopic,
+) -> Result<FlightDataEncoder> {
     info!("requesting data for ticket `{}`", ticket.locator);
 
     // Create topic handle
@@ -29,6 +55,13 @@ pub async fn do_get(ctx: &facade::Context, ticket: Ticket) -> Result<FlightDataE
         ))?
     }
 
+    // Data uploaded by a session is only visible once the session is finalized
+    if !facade::topic::is_published(ctx, &topic_handle).await? {
+        Err(core::Error::topic_not_published(
+            topic_handle.locator().to_string(),
+        ))?
+    }
+
     // Read metadata from topic
     let metadata = facade::topic::metadata(ctx, &topic_handle).await?;
 
@@ -46,23 +79,92 @@ pub async fn do_get(ctx: &facade::Context, ticket: Ticket) -> Result<FlightDataE
             topic_handle.locator()
         ))))?;
 
-    let mut query_result = ctx
-        .timeseries_querier
-        .read(
-            &path_in_store.data_folder_path(),
-            metadata.ontology_metadata.properties.serialization_format,
-            Some(batch_size),
+    let data_folder = path_in_store.data_folder_path();
+    let format = metadata.ontology_metadata.properties.serialization_format;
+
+    let query_result = if let Some(session) = ticket.session {
+        let session_handle = facade::session::Handle::try_from_locator(ctx, session).await?;
+        let chunks = facade::topic::session_chunks(ctx, &topic_handle, &session_handle).await?;
+        debug!(
+            "reading data of session `{}` from chunks {:?}",
+            session_handle.locator(),
+            chunks
+        );
+
+        ctx.timeseries_querier
+            .read_chunks(&data_folder, format, Some(batch_size), chunks)
+            .await?
+    } else if let Some(since) = ticket.changes_since {
+        let chunks = facade::topic::changed_chunks(ctx, &topic_handle, since).await?;
+        debug!("reading changes since {} from chunks {:?}", since, chunks);
+
+        let primary_key = metadata
+            .ontology_metadata
+            .properties
+            .primary_key
+            .as_deref()
+            .unwrap_or_default();
+        ctx.timeseries_querier
+            .read_changes(&data_folder, format, Some(batch_size), primary_key, chunks)
+            .await?
+    } else if let Some(ts_range) = &ticket.timestamp_range {
+        // Only the data files overlapping the range are read, rows are filtered below
+        facade::topic::read_range(
+            ctx,
+            &topic_handle,
+            &metadata.ontology_metadata.properties,
+            batch_size,
+            ts_range,
         )
-        .await?;
+        .await?
+    } else {
+        facade::topic::read(
+            ctx,
+            &topic_handle,
+            &metadata.ontology_metadata.properties,
+            batch_size,
+        )
+        .await?
+    };
 
+    stream_topic(
+        ctx,
+        query_result,
+        metadata,
+        path_in_store.clone(),
+        ticket.timestamp_range,
+        ticket.blob_references,
+        ticket.anonymization,
+    )
+    .await
+}
+
+/// Streams the data of a topic read in `query_result`, restricted to `timestamp_range`,
+/// inlining the offloaded blobs unless `blob_references` is set and applying the requested
+/// `anonymization`.
+pub(super) async fn stream_topic(
+    ctx: &facade::Context,
+    mut query_result: query::TimeseriesResult,
+    metadata: types::TopicMetadata<marshal::JsonMetadataBlob>,
+    path_in_store: types::TopicPathInStore,
+    timestamp_range: Option<types::TimestampRange>,
+    blob_references: bool,
+    anonymization: Option<types::Anonymization>,
+) -> Result<FlightDataEncoder> {
     // Append JSON metadata to original data schema
     let metadata = marshal::JsonTopicMetadata::from(metadata);
     let flatten_mdata = metadata.ontology_metadata.to_flat_hashmap()?;
 
-    let schema = query_result.schema_with_metadata(flatten_mdata);
+    let mut schema = query_result.schema_with_metadata(flatten_mdata);
+
+    // Unless references are explicitly requested, offloaded blobs are inlined in the stream
+    let inline_blobs = !blob_references;
+    if inline_blobs {
+        schema = Arc::new(ext::blob::inline_schema(&schema));
+    }
     trace!("{:?}", schema);
 
-    if let Some(ts_range) = ticket.timestamp_range {
+    if let Some(ts_range) = timestamp_range {
         debug!("requesting timestamp range {}", ts_range);
         query_result = query_result.filter_by_timestamp_range(ts_range)?;
     }
@@ -73,6 +175,243 @@ pub async fn do_get(ctx: &facade::Context, ticket: Ticket) -> Result<FlightDataE
     // Convert the data stream to a flight stream casting the returned error
     let stream = stream.map_err(|e| FlightError::ExternalError(Box::new(e)));
 
+    let stream = if inline_blobs {
+        let ctx = ctx.clone();
+        stream
+            .and_then(move |batch| {
+                let ctx = ctx.clone();
+                let path_in_store = path_in_store.clone();
+                async move {
+                    facade::topic::inline_blobs(&ctx, &path_in_store, batch)
+                        .await
+                        .map_err(|e| FlightError::ExternalError(e.to_string().into()))
+                }
+            })
+            .boxed()
+    } else {
+        stream.boxed()
+    };
+
+    // Anonymization is applied last, once the blobs are inlined
+    let stream = if let Some(anonymization) = anonymization {
+        debug!("anonymizing data with {:?}", anonymization);
+        schema = Arc::new(
+            ext::anonymize::anonymize_schema(&schema, &anonymization)
+                .map_err(core::Error::bad_request)?,
+        );
+        stream
+            .and_then(move |batch| {
+                let batch = facade::topic::anonymize(batch, &anonymization)
+                    .map_err(|e| FlightError::ExternalError(e.to_string().into()));
+                async move { batch }
+            })
+            .boxed()
+    } else {
+        stream
+    };
+
+    encode(schema, stream)
+}
+
+/// Streams the result of a SQL query, failing as soon as it returns more rows than allowed.
+async fn do_get_sql_query(
+    ctx: &facade::Context,
+    ticket: types::flight::TicketSqlQuery,
+    permission: Permission,
+) -> Result<FlightDataEncoder> {
+    info!(
+        "requesting SQL query result over {} tables",
+        ticket.tables.len()
+    );
+
+    // Held until the whole result is streamed
+    let permit = ctx.query_admission.admit().await?;
+
+    let limits = ctx.query_limits.limits_for(permission);
+    trace!("query limits: {}", limits);
+
+    let result = facade::sql_query::plan(ctx, &ticket.tables, &ticket.statement, &limits).await?;
+    let schema = result.schema();
+
+    let stream = result
+        .stream()
+        .await?
+        .map_err(|e| FlightError::ExternalError(Box::new(e)));
+
+    let stream = if let Some(limit) = limits.max_output_rows() {
+        let mut rows: u64 = 0;
+        stream
+            .and_then(move |batch| {
+                rows += batch.num_rows() as u64;
+                let batch = if rows > limit {
+                    Err(FlightError::ExternalError(
+                        core::Error::query_limit_exceeded(format!(
+                            "query returned more than {limit} rows"
+                        ))
+                        .to_string()
+                        .into(),
+                    ))
+                } else {
+                    Ok(batch)
+                };
+                async move { batch }
+            })
+            .boxed()
+    } else {
+        stream.boxed()
+    };
+
+    let stream = stream
+        .map(move |batch| {
+            let _permit = &permit;
+            batch
+        })
+        .boxed();
+
+    encode(schema, stream)
+}
+
+/// Streams the notifications of a sequence as they are created, one batch for each
+/// notification, until the server shuts down.
+async fn do_get_notifications(
+    ctx: &facade::Context,
+    ticket: types::flight::TicketNotifications,
+) -> Result<FlightDataEncoder> {
+    info!("requesting notification stream for `{}`", ticket.locator);
+
+    // The sequence may have been deleted since the ticket was issued
+    let handle = facade::sequence::Handle::try_from_locator(ctx, ticket.locator).await?;
+
+    let subscription = ctx
+        .notification_bus
+        .subscribe(handle.locator().clone(), ticket.min_severity);
+
+    let schema = notification_schema();
+
+    let stream = futures::stream::unfold(
+        (subscription, schema.clone()),
+        |(mut subscription, schema)| async move {
+            let batch = match subscription.next().await {
+                Ok(Some(notification)) => notification_batch(&schema, &notification),
+                Ok(None) => return None,
+                Err(e) => Err(FlightError::ExternalError(e.to_string().into())),
+            };
+            Some((batch, (subscription, schema)))
+        },
+    )
+    .boxed();
+
+    encode(schema, stream)
+}
+
+/// Maximum size of the pieces of an exported file carried by a single row, so that the
+/// batches can be split to fit the flight messages.
+const EXPORT_PIECE_SIZE: usize = 1024 * 1024;
+
+/// Streams a topic encoded as a Parquet file, as batches of a single binary column holding
+/// consecutive pieces of the file.
+async fn do_get_topic_export(
+    ctx: &facade::Context,
+    ticket: types::flight::TicketTopicExport,
+) -> Result<FlightDataEncoder> {
+    info!("requesting export of topic `{}`", ticket.locator);
+
+    let handle = facade::topic::Handle::try_from_locator(ctx, ticket.locator).await?;
+
+    let options = facade::topic_export::ExportOptions {
+        timestamp_range: ticket.timestamp_range,
+        row_group_size: ticket.row_group_size,
+        include_metadata: ticket.include_metadata,
+    };
+    let chunks = facade::topic_export::encode(ctx, &handle, options).await?;
+
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        params::ARROW_SCHEMA_COLUMN_NAME_EXPORT_DATA,
+        DataType::Binary,
+        false,
+    )]));
+
+    let batch_schema = schema.clone();
+    let stream = chunks
+        .map_err(|e| FlightError::ExternalError(e.to_string().into()))
+        .and_then(move |chunk| {
+            let pieces = BinaryArray::from_iter_values(chunk.chunks(EXPORT_PIECE_SIZE));
+            let batch = RecordBatch::try_new(batch_schema.clone(), vec![Arc::new(pieces)])
+                .map_err(FlightError::from);
+            futures::future::ready(batch)
+        })
+        .boxed();
+
+    encode(schema, stream)
+}
+
+/// Streams the rows of several topics time-merged, as described in
+/// [`facade::sequence_replay`].
+async fn do_get_replay(
+    ctx: &facade::Context,
+    ticket: types::flight::TicketReplay,
+) -> Result<FlightDataEncoder> {
+    info!("requesting replay of {} topics", ticket.topics.len());
+
+    let mut handles = Vec::with_capacity(ticket.topics.len());
+    for locator in ticket.topics {
+        handles.push(facade::topic::Handle::try_from_locator(ctx, locator).await?);
+    }
+
+    let options = facade::sequence_replay::ReplayOptions {
+        timestamp_range: ticket.timestamp_range,
+    };
+    let (schema, batches) = facade::sequence_replay::replay(ctx, handles, options).await?;
+
+    let stream = batches
+        .map_err(|e| FlightError::ExternalError(e.to_string().into()))
+        .boxed();
+
+    encode(schema, stream)
+}
+
+/// Schema of the streamed notifications, mirroring the items of the notification listings.
+fn notification_schema() -> SchemaRef {
+    let fields = [
+        "name",
+        "notification_type",
+        "severity",
+        "code",
+        "params",
+        "msg",
+        "created_datetime",
+    ]
+    .map(|name| Field::new(name, DataType::Utf8, false));
+
+    Arc::new(Schema::new(fields.to_vec()))
+}
+
+fn notification_batch(
+    schema: &SchemaRef,
+    notification: &facade::LiveNotification,
+) -> std::result::Result<RecordBatch, FlightError> {
+    let params = serde_json::to_string(&notification.payload.params)
+        .map_err(|e| FlightError::ExternalError(Box::new(e)))?;
+
+    let columns = [
+        notification.target.clone(),
+        notification.notification_type.to_string(),
+        notification.severity.to_string(),
+        notification.payload.code.clone(),
+        params,
+        notification.msg.clone().unwrap_or_default(),
+        notification.created_at.to_string(),
+    ]
+    .map(|value| Arc::new(StringArray::from(vec![value])) as _);
+
+    Ok(RecordBatch::try_new(schema.clone(), columns.to_vec())?)
+}
+
+/// Encodes a data stream as a flight stream.
+fn encode(
+    schema: SchemaRef,
+    stream: BoxStream<'static, std::result::Result<RecordBatch, FlightError>>,
+) -> Result<FlightDataEncoder> {
     // We enable by default LZ4_FRAME compression for all streams.
     // As `.try_with_compression()` states the function throws an error at runtime
     // if the ipc_compression feature is not enabled. So we should never see this terror.
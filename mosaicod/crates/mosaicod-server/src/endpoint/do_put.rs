@@ -1,8 +1,12 @@
 use crate::error::{Error, Result};
+use crate::ingest::IngestMetrics;
+use arrow::array::RecordBatch;
 use arrow::datatypes::SchemaRef;
+use arrow_flight::FlightData;
 use arrow_flight::decode::{DecodedFlightData, DecodedPayload, FlightDataDecoder};
+use arrow_flight::error::FlightError;
 use arrow_flight::flight_descriptor::DescriptorType;
-use futures::TryStreamExt;
+use futures::{Stream, StreamExt, TryStreamExt};
 use mosaicod_core as core;
 use mosaicod_core::types;
 use mosaicod_facade as facade;
@@ -15,6 +19,9 @@ use tracing::{debug, info};
 pub struct DoPutContext {
     pub inner: facade::Context,
     pub concurrent_writes_semaphore: Arc<tokio::sync::Semaphore>,
+    /// Principal uploading the data, see [`crate::middleware::AuthContext::principal`]
+    pub principal: Option<String>,
+    pub(crate) ingest_metrics: Arc<IngestMetrics>,
 }
 
 impl std::ops::Deref for DoPutContext {
@@ -24,22 +31,43 @@ impl std::ops::Deref for DoPutContext {
     }
 }
 
-pub async fn do_put(ctx: DoPutContext, decoder: &mut FlightDataDecoder) -> Result<()> {
-    let (cmd, schema) = extract_command_and_schema_from_header_message(decoder).await?;
-    do_put_topic_data(ctx, decoder, schema, cmd).await
+/// Receives the data of a topic, encoded as set by the payload format of the command sent
+/// in the first message of the stream.
+pub async fn do_put(
+    ctx: DoPutContext,
+    mut stream: impl Stream<Item = std::result::Result<FlightData, FlightError>>
+    + Send
+    + Unpin
+    + 'static,
+) -> Result<()> {
+    let header = stream
+        .try_next()
+        .await
+        .map_err(core::Error::stream_error)?
+        .ok_or_else(core::Error::missing_header)?;
+    let cmd = extract_command_from_flight_data(&header)?;
+
+    match cmd.payload_format {
+        types::flight::PayloadFormat::ArrowIpc => {
+            let stream = futures::stream::iter([Ok(header)]).chain(stream);
+            let mut decoder = FlightDataDecoder::new(stream);
+            let schema = extract_schema_from_header_message(&mut decoder).await?;
+            do_put_topic_data(ctx, &mut decoder, schema, cmd).await
+        }
+        types::flight::PayloadFormat::Parquet => {
+            let stream = futures::stream::iter([Ok(header)]).chain(stream);
+            do_put_topic_parquet(ctx, stream, cmd).await
+        }
+    }
 }
 
-async fn extract_command_and_schema_from_header_message(
-    decoder: &mut FlightDataDecoder,
-) -> Result<(types::flight::DoPutCmd, SchemaRef)> {
+async fn extract_schema_from_header_message(decoder: &mut FlightDataDecoder) -> Result<SchemaRef> {
     if let Some(data) = decoder
         .try_next()
         .await
         .map_err(core::Error::stream_error)?
     {
-        let cmd = extract_command_from_flight_data(&data)?;
-        let schema = extract_schema_from_flight_data(&data)?;
-        return Ok((cmd, schema));
+        return extract_schema_from_flight_data(&data);
     }
     Err(core::Error::missing_header())?
 }
@@ -51,10 +79,9 @@ fn extract_schema_from_flight_data(data: &DecodedFlightData) -> Result<SchemaRef
     Err(core::Error::missing_schema())?
 }
 
-/// Extract descriptor tag from flight decoded data
-fn extract_command_from_flight_data(data: &DecodedFlightData) -> Result<types::flight::DoPutCmd> {
+/// Extract descriptor tag from flight data
+fn extract_command_from_flight_data(data: &FlightData) -> Result<types::flight::DoPutCmd> {
     let desc = data
-        .inner
         .flight_descriptor
         .as_ref()
         .ok_or_else(core::Error::missing_descriptor)?;
@@ -75,34 +102,7 @@ async fn do_put_topic_data(
     schema: SchemaRef,
     cmd: types::flight::DoPutCmd,
 ) -> Result<()> {
-    let locator = cmd.resource_locator;
-    let uuid_str = &cmd.key;
-
-    info!(
-        target = "uploading topic",
-        locator = locator,
-        uuid = uuid_str,
-    );
-
-    mosaicod_ext::arrow::check_schema(&schema)?;
-
-    let topic_locator = locator.parse::<types::TopicLocator>()?;
-
-    let topic_handle = facade::topic::Handle::try_from_locator(&ctx, topic_locator).await?;
-
-    // perform the match between received uuid string and topic uuid
-    let topic_uuid = topic_handle.uuid().clone();
-    let received_uuid: types::Uuid = uuid_str
-        .parse()
-        .map_err(|_| core::Error::bad_uuid(uuid_str.clone()))?;
-
-    if received_uuid != topic_uuid {
-        Err(core::Error::unauthorized(
-            "received uuid does not match the topic uuid.".to_string(),
-        ))?
-    }
-
-    let mut writer = facade::topic::writer(ctx.clone(), topic_handle, schema).await?;
+    let mut upload = TopicUpload::try_new(&ctx, cmd, schema).await?;
 
     // Consume all batches
     debug!("ready to receive batches");
@@ -121,49 +121,207 @@ async fn do_put_topic_data(
                     batch_physical_size = batch.get_array_memory_size() / 1_000_000,
                 );
 
-                // Trying to acquire a semaphore to limit the total amount of concurrent writes
-                // run by this instance. This is done in order to bound memory consumption and
-                // to limit CPU-bound operations.
-                //
-                // Since the `.write()` will encode-and-serialize in a single operation it is safe
-                // to acquire the semaphore without causing deadlocks.
-                let permit = ctx
-                    .concurrent_writes_semaphore
-                    .acquire()
-                    .await
-                    .map_err(|_| Error::semaphore_closed())?;
-                let serialized_chunk = writer.write(batch).await?;
-                drop(permit);
-
-                on_chunk_created(
-                    &ctx,
-                    &topic_uuid,
-                    writer.ontology_tag(),
-                    serialized_chunk.path,
-                    serialized_chunk.ontology_stats,
-                    serialized_chunk.metadata,
-                )
-                .await?;
+                let sent_at = marshal::flight::do_put_batch_sent_at(&data.inner.app_metadata)?;
+
+                upload.write(&ctx, batch, sent_at).await?;
             }
             DecodedPayload::Schema(_) => Err(core::Error::unsupported_stream_message())?,
             DecodedPayload::None => Err(core::Error::unsupported_stream_message())?,
         }
     }
 
-    let time = Instant::now();
-    writer.finalize().await?;
-    debug!(
-        target = "topic finalization",
-        finalize_ms = time.elapsed().as_millis()
-    );
+    upload.finalize().await
+}
 
-    Ok(())
+/// Receives the data of a topic sent as Parquet files, one in the body of each message.
+///
+/// The row groups of the files are written as the batches of an Arrow IPC stream, so that
+/// the data is stored in the same layout. All the files need to share the same schema.
+async fn do_put_topic_parquet(
+    ctx: DoPutContext,
+    mut stream: impl Stream<Item = std::result::Result<FlightData, FlightError>> + Unpin,
+    cmd: types::flight::DoPutCmd,
+) -> Result<()> {
+    debug!("ready to receive parquet files");
+
+    // The schema of the topic is the one of the first file, streams without files are
+    // rejected as Arrow IPC streams without schema
+    let Some(first) = next_parquet_file(&mut stream).await? else {
+        Err(core::Error::missing_schema())?
+    };
+    let mut upload = TopicUpload::try_new(&ctx, cmd, first.0.schema()).await?;
+
+    let mut file = Some(first);
+    while let Some((payload, sent_at)) = file {
+        if upload.schema.fields() != payload.schema().fields() {
+            Err(core::Error::bad_request(
+                "parquet files uploaded in a stream need to share the same schema".to_owned(),
+            ))?;
+        }
+
+        for batch in payload {
+            let batch = batch.map_err(invalid_parquet)?;
+            upload.write(&ctx, batch, sent_at).await?;
+        }
+
+        file = next_parquet_file(&mut stream).await?;
+    }
+
+    upload.finalize().await
+}
+
+/// Returns the Parquet file carried by the next message with a body, along with the time
+/// the client sent it.
+async fn next_parquet_file(
+    stream: &mut (impl Stream<Item = std::result::Result<FlightData, FlightError>> + Unpin),
+) -> Result<Option<(rw::ParquetPayload, Option<types::Timestamp>)>> {
+    while let Some(data) = stream.try_next().await.map_err(core::Error::stream_error)? {
+        if data.data_body.is_empty() {
+            continue;
+        }
+
+        let sent_at = marshal::flight::do_put_batch_sent_at(&data.app_metadata)?;
+
+        let size = data.data_body.len();
+        let payload = rw::ParquetPayload::try_new(data.data_body).map_err(invalid_parquet)?;
+        debug!(
+            target = "received parquet file",
+            row_groups = payload.num_row_groups(),
+            msg_body_size = size / 1_000_000,
+        );
+
+        return Ok(Some((payload, sent_at)));
+    }
+    Ok(None)
+}
+
+fn invalid_parquet(e: rw::Error) -> core::Error {
+    core::Error::bad_request(format!("invalid parquet payload: {:?}", e))
+}
+
+/// Data of a topic being uploaded, after checking the upload key and the session.
+struct TopicUpload {
+    topic_uuid: types::Uuid,
+    topic_name: String,
+    session_handle: facade::session::Handle,
+    /// Schema of the uploaded data
+    schema: SchemaRef,
+    writer: facade::topic::HandleWriter,
+}
+
+impl TopicUpload {
+    async fn try_new(
+        ctx: &DoPutContext,
+        cmd: types::flight::DoPutCmd,
+        schema: SchemaRef,
+    ) -> Result<Self> {
+        let locator = cmd.resource_locator;
+        let uuid_str = &cmd.key;
+
+        info!(
+            target = "uploading topic",
+            locator = locator,
+            uuid = uuid_str,
+        );
+
+        mosaicod_ext::arrow::check_schema(&schema)?;
+
+        let topic_locator = locator.parse::<types::TopicLocator>()?;
+
+        let topic_handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
+
+        // perform the match between received uuid string and topic uuid (or the uuid of an
+        // upsert of the topic)
+        let topic_uuid = topic_handle.uuid().clone();
+        let received_uuid: types::Uuid = uuid_str
+            .parse()
+            .map_err(|_| core::Error::bad_uuid(uuid_str.clone()))?;
+
+        let topic_handle = topic_handle.with_upload_key(ctx, &received_uuid).await?;
+        let topic_name = topic_handle.locator().to_string();
+
+        // Knowing the key is not enough, the session needs to belong to the principal
+        let session_handle = facade::topic::upload_session(ctx, &topic_handle).await?;
+        facade::session::check_principal(ctx, &session_handle, ctx.principal.as_deref()).await?;
+        facade::session::touch(ctx, &session_handle).await?;
+
+        let writer = facade::topic::writer(ctx.inner.clone(), topic_handle, schema.clone()).await?;
+
+        Ok(Self {
+            topic_uuid,
+            topic_name,
+            session_handle,
+            schema,
+            writer,
+        })
+    }
+
+    /// Writes `batch` as a new chunk of the topic, `sent_at` is the time the client sent it.
+    async fn write(
+        &mut self,
+        ctx: &DoPutContext,
+        batch: RecordBatch,
+        sent_at: Option<types::Timestamp>,
+    ) -> Result<()> {
+        // Trying to acquire a semaphore to limit the total amount of concurrent writes
+        // run by this instance. This is done in order to bound memory consumption and
+        // to limit CPU-bound operations.
+        //
+        // Since the `.write()` will encode-and-serialize in a single operation it is safe
+        // to acquire the semaphore without causing deadlocks.
+        let permit = ctx
+            .concurrent_writes_semaphore
+            .acquire()
+            .await
+            .map_err(|_| Error::semaphore_closed())?;
+        let serialized_chunk = self.writer.write(batch).await?;
+        drop(permit);
+
+        let data_end = serialized_chunk
+            .metadata
+            .timestamp_range()
+            .map(|range| range.end);
+
+        on_chunk_created(
+            ctx,
+            &self.topic_uuid,
+            self.writer.ontology_tag(),
+            self.writer.schema_version(),
+            serialized_chunk.path,
+            serialized_chunk.ontology_stats,
+            serialized_chunk.metadata,
+        )
+        .await?;
+
+        if let Some(reference) = sent_at.or(data_end) {
+            ctx.ingest_metrics
+                .record(&self.topic_name, reference, types::Timestamp::now());
+        }
+
+        // Uploads postpone the expiration of the session
+        facade::session::touch(ctx, &self.session_handle).await?;
+
+        Ok(())
+    }
+
+    async fn finalize(self) -> Result<()> {
+        let time = Instant::now();
+        self.writer.finalize().await?;
+        debug!(
+            target = "topic finalization",
+            finalize_ms = time.elapsed().as_millis()
+        );
+
+        Ok(())
+    }
 }
 
-async fn on_chunk_created(
-    ctx: &DoPutContext,
+/// Registers a chunk just written into the topic, with the statistics of its data.
+pub(crate) async fn on_chunk_created(
+    ctx: &facade::Context,
     topic_uuid: &types::Uuid,
     ontology_tag: &str,
+    schema_version: Option<u32>,
     target_path: impl AsRef<std::path::Path>,
     cstats: types::OntologyModelStats,
     chunk_metadata: rw::ChunkMetadata,
@@ -173,7 +331,9 @@ async fn on_chunk_created(
         &target_path,
         chunk_metadata.size_bytes as i64,
         chunk_metadata.row_count as i64,
-        &ctx.inner,
+        schema_version,
+        chunk_metadata.timestamp_range(),
+        ctx,
     )
     .await?;
 
@@ -1,20 +1,23 @@
 use crate::error::Result;
-use arrow::datatypes::{Field, Schema};
+use arrow::datatypes::{DataType, Field, Schema};
 use arrow_flight::{
     FlightDescriptor, FlightEndpoint, FlightInfo, Ticket, flight_descriptor::DescriptorType,
 };
 use futures::stream::{self, StreamExt, TryStreamExt};
-use log::{info, trace};
 use mosaicod_core::{
     self as core,
     error::BoxPublicError,
     params,
     types::{self, TopicOntologyMetadata},
 };
+use mosaicod_ext as ext;
 use mosaicod_facade as facade;
 use mosaicod_facade::Context;
 use mosaicod_marshal as marshal;
 use mosaicod_marshal::{JsonMetadataBlob, flight};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{info, trace};
 
 /// Message provided when an error occurs when building flight info data
 const UNABLE_TO_BUILD_FLIGHT_INFO: &str = "unable to build flight info data";
@@ -43,9 +46,39 @@ async fn do_get_flight_info(
     info!("requesting info for resource {}", resource_name);
 
     return if let Ok(sequence_locator) = resource_name.parse::<types::SequenceLocator>() {
-        sequence_flight_info(ctx, desc, sequence_locator, cmd.timestamp_range).await
+        if cmd.changes_since.is_some() {
+            Err(core::Error::bad_request(
+                "changes can only be requested for upsert topics".to_owned(),
+            ))?;
+        }
+        if cmd.session.is_some() {
+            Err(core::Error::bad_request(
+                "the data of a session can only be requested for topics".to_owned(),
+            ))?;
+        }
+        sequence_flight_info(
+            ctx,
+            desc,
+            sequence_locator,
+            cmd.timestamp_range,
+            cmd.blob_references,
+            cmd.anonymization,
+        )
+        .await
     } else if let Ok(topic_locator) = resource_name.parse::<types::TopicLocator>() {
-        topic_flight_info(ctx, desc, topic_locator, cmd.timestamp_range).await
+        let changes_since = match cmd.changes_since {
+            Some(changes_since) => Some(resolve_changes_since(ctx, changes_since).await?),
+            None => None,
+        };
+        let ticket = types::flight::TicketTopic {
+            locator: topic_locator,
+            timestamp_range: cmd.timestamp_range,
+            blob_references: cmd.blob_references,
+            changes_since,
+            session: cmd.session,
+            anonymization: cmd.anonymization,
+        };
+        topic_flight_info(ctx, desc, ticket).await
     } else if let Ok(session_locator) = resource_name.parse::<types::SessionLocator>() {
         Err(core::Error::unsupported_locator(
             session_locator.to_string(),
@@ -55,12 +88,38 @@ async fn do_get_flight_info(
     };
 }
 
+/// Returns the time after which the changes of an upsert topic are requested.
+///
+/// Changes requested since a session are the ones completed after the session finalization.
+async fn resolve_changes_since(
+    ctx: &facade::Context,
+    changes_since: types::flight::ChangesSince,
+) -> Result<types::Timestamp> {
+    match changes_since {
+        types::flight::ChangesSince::Timestamp(timestamp) => Ok(timestamp),
+        types::flight::ChangesSince::Session(session_locator) => {
+            let session_handle =
+                facade::session::Handle::try_from_locator(ctx, session_locator).await?;
+            let metadata = facade::session::metadata(ctx, &session_handle).await?;
+
+            Ok(metadata.completed_at.ok_or_else(|| {
+                core::Error::bad_request(format!(
+                    "session `{}` is not finalized",
+                    session_handle.locator()
+                ))
+            })?)
+        }
+    }
+}
+
 /// Creates flight info response for the given Sequence.
 async fn sequence_flight_info(
     ctx: &facade::Context,
     desc: FlightDescriptor,
     sequence_locator: types::SequenceLocator,
     timestamp_range: Option<types::TimestampRange>,
+    blob_references: bool,
+    anonymization: Option<types::Anonymization>,
 ) -> Result<FlightInfo> {
     let sequence_handle = facade::sequence::Handle::try_from_locator(ctx, sequence_locator).await?;
 
@@ -71,17 +130,7 @@ async fn sequence_flight_info(
         sequence_handle.locator()
     );
 
-    let mut schema = Schema::new(Vec::<Field>::new());
-
-    // Collect user metadata
-    if let Some(user_metadata) = &metadata.user_metadata {
-        let user_metadata = marshal::JsonSequenceMetadata {
-            user_metadata: user_metadata.clone(),
-        };
-        let flatten_user_metadata = user_metadata.to_flat_hashmap()?;
-
-        schema = schema.with_metadata(flatten_user_metadata);
-    }
+    let schema = sequence_schema(&metadata)?;
 
     trace!("{} generating endpoints", sequence_handle.locator());
     let topics = facade::sequence::topic_list(ctx, &sequence_handle).await?;
@@ -90,21 +139,43 @@ async fn sequence_flight_info(
     let endpoints = stream::iter(topics)
         .map(async |topic_handle: facade::topic::Handle| {
             let metadata = facade::topic::metadata(ctx, &topic_handle).await?;
-            let topic_endpoint = build_topic_endpoint(
-                ctx,
-                &topic_handle,
-                timestamp_range.clone(),
-                metadata.properties,
-            )
-            .await?;
+            let anonymization = match &anonymization {
+                Some(anonymization) => {
+                    topic_anonymization(
+                        ctx,
+                        &topic_handle,
+                        metadata.ontology_metadata.properties.serialization_format,
+                        blob_references,
+                        anonymization,
+                    )
+                    .await?
+                }
+                None => None,
+            };
+            let ticket = types::flight::TicketTopic {
+                locator: topic_handle.locator().clone(),
+                timestamp_range: timestamp_range.clone(),
+                blob_references,
+                changes_since: None,
+                session: None,
+                anonymization,
+            };
+            let topic_endpoint =
+                build_topic_endpoint(ctx, &topic_handle, ticket, metadata.properties).await?;
             Ok::<FlightEndpoint, BoxPublicError>(topic_endpoint)
         })
         .buffer_unordered(params::MAX_BUFFERED_FUTURES)
         .try_collect::<Vec<FlightEndpoint>>()
         .await?;
 
+    let views = facade::query_view::list(ctx, &sequence_handle)
+        .await?
+        .into_iter()
+        .map(|view| view.name)
+        .collect();
+
     // Get sequence metadata and convert it to flight appmetadata.
-    let app_metadata: flight::SequenceAppMetadata = metadata.into();
+    let app_metadata = flight::SequenceAppMetadata::from(metadata).with_views(views);
 
     let mut flight_info = FlightInfo::new()
         .with_descriptor(desc)
@@ -120,22 +191,70 @@ async fn sequence_flight_info(
     Ok(flight_info)
 }
 
-/// Creates flight info response for the given Topic.
+/// Restricts the anonymization requested for a sequence to the columns of one of its
+/// topics, returning `None` if no column of the topic is anonymized.
+async fn topic_anonymization(
+    ctx: &facade::Context,
+    topic_handle: &facade::topic::Handle,
+    format: types::Format,
+    blob_references: bool,
+    anonymization: &types::Anonymization,
+) -> Result<Option<types::Anonymization>> {
+    let mut schema = facade::topic::arrow_schema(ctx, topic_handle, format)
+        .await?
+        .as_ref()
+        .clone();
+    if !blob_references {
+        schema = ext::blob::inline_schema(&schema);
+    }
+
+    let anonymization = anonymization.retain(|column| schema.field_with_name(column).is_ok());
+    if anonymization.is_empty() {
+        return Ok(None);
+    }
+
+    ext::anonymize::anonymize_schema(&schema, &anonymization).map_err(|e| {
+        core::Error::bad_request(format!("topic `{}`: {}", topic_handle.locator(), e))
+    })?;
+
+    Ok(Some(anonymization))
+}
+
+/// Creates flight info response for the given Topic, with an endpoint serving `ticket`.
 async fn topic_flight_info(
     ctx: &facade::Context,
     desc: FlightDescriptor,
-    topic_locator: types::TopicLocator,
-    timestamp_range: Option<types::TimestampRange>,
+    ticket: types::flight::TicketTopic,
 ) -> Result<FlightInfo> {
-    let topic_handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
+    let topic_handle = facade::topic::Handle::try_from_locator(ctx, ticket.locator.clone()).await?;
 
     let metadata = facade::topic::metadata(ctx, &topic_handle).await?;
 
-    let endpoint =
-        build_topic_endpoint(ctx, &topic_handle, timestamp_range, metadata.properties).await?;
+    // Fail early if the data of the session can't be read
+    if let Some(session) = &ticket.session {
+        let session_handle =
+            facade::session::Handle::try_from_locator(ctx, session.clone()).await?;
+        facade::topic::session_chunks(ctx, &topic_handle, &session_handle).await?;
+    }
+
+    if ticket.changes_since.is_some() && !metadata.ontology_metadata.properties.is_upsert() {
+        Err(core::Error::bad_request(format!(
+            "changes can only be requested for upsert topics, `{}` is not an upsert topic",
+            topic_handle.locator()
+        )))?;
+    }
+
+    let schema = topic_schema(
+        ctx,
+        &topic_handle,
+        metadata.ontology_metadata,
+        ticket.blob_references,
+        ticket.changes_since.is_some(),
+        ticket.anonymization.as_ref(),
+    )
+    .await?;
 
-    let schema =
-        topic_arrow_schema_with_metadata(metadata.ontology_metadata, &topic_handle, ctx).await?;
+    let endpoint = build_topic_endpoint(ctx, &topic_handle, ticket, metadata.properties).await?;
 
     let flight_info = FlightInfo::new()
         .with_descriptor(desc)
@@ -147,22 +266,74 @@ async fn topic_flight_info(
     Ok(flight_info)
 }
 
-/// Builds a [`FlightEndpoint`] for the given Topic.
+/// Builds the schema of a Sequence, an empty schema carrying the sequence metadata.
+pub(super) fn sequence_schema(
+    metadata: &types::SequenceMetadata<JsonMetadataBlob>,
+) -> Result<Schema> {
+    let mut schema = Schema::new(Vec::<Field>::new());
+
+    // Collect user metadata
+    if let Some(user_metadata) = &metadata.user_metadata {
+        let user_metadata = marshal::JsonSequenceMetadata {
+            user_metadata: user_metadata.clone(),
+        };
+        let flatten_user_metadata = user_metadata.to_flat_hashmap()?;
+
+        schema = schema.with_metadata(flatten_user_metadata);
+    }
+
+    Ok(schema)
+}
+
+/// Builds the schema of the data returned for a Topic.
+pub(super) async fn topic_schema(
+    ctx: &facade::Context,
+    topic_handle: &facade::topic::Handle,
+    ontology_metadata: TopicOntologyMetadata<JsonMetadataBlob>,
+    blob_references: bool,
+    changes: bool,
+    anonymization: Option<&types::Anonymization>,
+) -> Result<Schema> {
+    let mut schema = topic_arrow_schema_with_metadata(ontology_metadata, topic_handle, ctx).await?;
+
+    // Offloaded blobs are inlined by default in the data stream
+    if !blob_references {
+        schema = ext::blob::inline_schema(&schema);
+    }
+
+    if let Some(anonymization) = anonymization {
+        schema = ext::anonymize::anonymize_schema(&schema, anonymization)
+            .map_err(core::Error::bad_request)?;
+    }
+
+    // Changes report their kind in an additional column
+    if changes {
+        let mut fields = schema.fields().to_vec();
+        fields.push(Arc::new(Field::new(
+            params::ARROW_SCHEMA_COLUMN_NAME_CHANGE,
+            DataType::Utf8,
+            false,
+        )));
+        schema = Schema::new_with_metadata(fields, schema.metadata().clone());
+    }
+
+    Ok(schema)
+}
+
+/// Builds a [`FlightEndpoint`] serving `ticket` for the given Topic.
 async fn build_topic_endpoint(
     ctx: &facade::Context,
     topic_handle: &facade::topic::Handle,
-    timestamp_range: Option<types::TimestampRange>,
+    ticket: types::flight::TicketTopic,
     metadata: types::TopicMetadataProperties,
 ) -> Result<FlightEndpoint> {
-    let ticket = types::flight::TicketTopic {
-        locator: topic_handle.locator().clone(),
-        timestamp_range,
-    };
-
     let mut app_mdata = marshal::flight::TopicAppMetadata::new(metadata);
     if let Ok(info) = facade::topic::data_info(ctx, topic_handle).await {
         app_mdata = app_mdata.with_info(info);
     }
+    if let Some(version) = facade::topic::version(ctx, topic_handle).await? {
+        app_mdata = app_mdata.with_version(version);
+    }
 
     let endpoint = FlightEndpoint::new()
         .with_ticket(Ticket {
@@ -198,12 +369,29 @@ async fn topic_arrow_schema_with_metadata(
     )
     .await?;
 
+    // Annotate the documented columns
+    let mut columns: HashMap<String, types::ColumnDescription> =
+        facade::topic::columns(context, topic_handle)
+            .await?
+            .into_iter()
+            .map(|column| (column.name.clone(), column))
+            .collect();
+    let fields: Vec<Field> = schema
+        .fields()
+        .iter()
+        .map(|field| match columns.remove(field.name()) {
+            Some(column) => {
+                let mut metadata = field.metadata().clone();
+                metadata.extend(marshal::column_field_metadata(column));
+                field.as_ref().clone().with_metadata(metadata)
+            }
+            None => field.as_ref().clone(),
+        })
+        .collect();
+
     // Collect schema metadata
     let json_ontology_metadata = marshal::JsonTopicOntologyMetadata::from(ontology_metadata);
     let flatten_ontology_metadata = json_ontology_metadata.to_flat_hashmap()?;
 
-    Ok(Schema::new_with_metadata(
-        schema.fields().clone(),
-        flatten_ontology_metadata,
-    ))
+    Ok(Schema::new_with_metadata(fields, flatten_ontology_metadata))
 }
@@ -0,0 +1,43 @@
+use super::get_flight_info::{sequence_schema, topic_schema};
+use crate::error::Result;
+use arrow::datatypes::Schema;
+use arrow_flight::{FlightDescriptor, flight_descriptor::DescriptorType};
+use mosaicod_core::{self as core, types};
+use mosaicod_facade as facade;
+use mosaicod_marshal as marshal;
+use tracing::info;
+
+/// Returns the schema of the requested resource (Sequence or Topic).
+///
+/// The schema is the same one returned by `get_flight_info`, columns of topics carry their
+/// description and unit in the field metadata.
+pub async fn get_schema(ctx: &facade::Context, desc: FlightDescriptor) -> Result<Schema> {
+    let cmd = match desc.r#type() {
+        DescriptorType::Cmd => marshal::flight::get_flight_info_cmd(&desc.cmd)?,
+        _ => Err(core::Error::unsupported_descriptor())?,
+    };
+
+    let resource_name = &cmd.resource_locator;
+
+    info!("requesting schema for resource {}", resource_name);
+
+    if let Ok(sequence_locator) = resource_name.parse::<types::SequenceLocator>() {
+        let handle = facade::sequence::Handle::try_from_locator(ctx, sequence_locator).await?;
+        let metadata = facade::sequence::metadata(ctx, &handle).await?;
+        sequence_schema(&metadata)
+    } else if let Ok(topic_locator) = resource_name.parse::<types::TopicLocator>() {
+        let handle = facade::topic::Handle::try_from_locator(ctx, topic_locator).await?;
+        let metadata = facade::topic::metadata(ctx, &handle).await?;
+        topic_schema(
+            ctx,
+            &handle,
+            metadata.ontology_metadata,
+            cmd.blob_references,
+            cmd.changes_since.is_some(),
+            cmd.anonymization.as_ref(),
+        )
+        .await
+    } else {
+        Err(core::Error::bad_locator(resource_name.clone()))?
+    }
+}
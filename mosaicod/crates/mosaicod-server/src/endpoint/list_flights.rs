@@ -1,61 +1,116 @@
 //! Implementation of the Arrow Flight `list_flights` endpoint.
 //!
-//! Returns a stream of all available sequences when queried at the root level.
+//! Returns a stream of all available sequences when queried at the root level, or of the
+//! sequences or topics matching a locator pattern.
 use crate::error::*;
 use arrow_flight::{Criteria, FlightDescriptor, FlightEndpoint, FlightInfo, Ticket};
-use futures::stream::BoxStream;
-use log::{info, trace};
-use mosaicod_core as core;
+use futures::stream::{BoxStream, TryStreamExt};
+use mosaicod_core::types;
 use mosaicod_facade as facade;
+use mosaicod_marshal as marshal;
+use tracing::{info, trace};
 
-/// Lists all available flights (sequences) in the database.
+/// Number of resources read from the database at once while the stream is consumed.
+const PAGE_SIZE: usize = 1000;
+
+/// Lists the available flights (sequences or topics) in the database.
 ///
 /// When clients query with an empty or root path ("" or "/"), this function
-/// returns a streamed list of all sequences. Each sequence is represented
-/// as a minimal `FlightInfo` containing only the sequence identifier.
+/// returns a streamed list of all sequences. Otherwise the criteria contains a locator
+/// pattern (e.g. `my_sequence/*`), and the sequences or topics matching it are returned.
+/// Each resource is represented as a minimal `FlightInfo` containing only its locator.
+///
+/// Resources are sorted by locator and read from the database one page at a time, as the
+/// stream is consumed.
 pub async fn list_flights(
     ctx: &facade::Context,
     criteria: Criteria,
 ) -> Result<BoxStream<'static, Result<FlightInfo>>> {
-    // Validate criteria - only root-level queries are supported
-    let expression = String::from_utf8_lossy(&criteria.expression);
-    let is_root_query = expression.is_empty() || expression == "/";
+    let criteria = marshal::flight::list_flights_criteria(&criteria.expression)?;
 
-    if !is_root_query {
-        Err(core::Error::unsupported_descriptor())?
+    match &criteria.pattern {
+        None => info!("listing all sequences"),
+        Some(pattern) if pattern.kind() == types::ResourceKind::Topic => {
+            info!("listing topics matching `{}`", pattern)
+        }
+        Some(pattern) => info!("listing sequences matching `{}`", pattern),
     }
 
-    info!("listing all sequences");
-
-    // Fetch all sequences from database
-    let sequences = facade::sequence::all(ctx).await?;
+    // The state is the cursor of the next page, `None` once all the pages are read
+    let ctx = ctx.clone();
+    let pattern = criteria.pattern;
+    let pages = futures::stream::try_unfold(Some(None), move |cursor: Option<Option<String>>| {
+        let ctx = ctx.clone();
+        let pattern = pattern.clone();
+        async move {
+            let Some(cursor) = cursor else {
+                return Ok(None);
+            };
 
-    trace!("found {} sequences", sequences.len());
+            let page = locators_page(&ctx, pattern.as_ref(), cursor.as_deref()).await;
+            page.map(|(locators, cursor)| {
+                trace!("found {} resources", locators.len());
+                Some((locators, cursor.map(Some)))
+            })
+        }
+    });
 
-    // Convert each sequence locator to a minimal FlightInfo
-    let flight_infos: Vec<Result<FlightInfo>> = sequences
-        .into_iter()
-        .map(|sequence_handle| {
-            let sequence_name = sequence_handle.locator().to_string();
+    let stream = pages
+        .map_ok(|locators| futures::stream::iter(locators.into_iter().map(flight_info).map(Ok)))
+        .try_flatten();
 
-            // Create flight descriptor with the sequence path
-            let descriptor = FlightDescriptor::new_path(vec![sequence_name.clone()]);
-
-            // Create a ticket using the sequence name
-            let endpoint = FlightEndpoint::new().with_ticket(Ticket {
-                ticket: sequence_name.into(),
-            });
+    Ok(Box::pin(stream))
+}
 
-            let flight_info = FlightInfo::new()
-                .with_descriptor(descriptor)
-                .with_endpoint(endpoint);
+/// Returns the locators of a page of resources matching `pattern` following `cursor`,
+/// along with the cursor of the next page.
+async fn locators_page(
+    ctx: &facade::Context,
+    pattern: Option<&types::LocatorPattern>,
+    cursor: Option<&str>,
+) -> Result<(Vec<String>, Option<String>)> {
+    match pattern {
+        None => {
+            let page = facade::sequence::page(ctx, "", cursor, PAGE_SIZE).await?;
+            let locators = page
+                .handles
+                .iter()
+                .map(|handle| handle.locator().to_string())
+                .collect();
+            Ok((locators, page.cursor))
+        }
+        Some(pattern) if pattern.kind() == types::ResourceKind::Topic => {
+            let page = facade::topic::find_by_pattern(ctx, pattern, cursor, PAGE_SIZE).await?;
+            let locators = page
+                .handles
+                .iter()
+                .map(|handle| handle.locator().to_string())
+                .collect();
+            Ok((locators, page.cursor))
+        }
+        Some(pattern) => {
+            let page = facade::sequence::find_by_pattern(ctx, pattern, cursor, PAGE_SIZE).await?;
+            let locators = page
+                .handles
+                .iter()
+                .map(|handle| handle.locator().to_string())
+                .collect();
+            Ok((locators, page.cursor))
+        }
+    }
+}
 
-            Ok(flight_info)
-        })
-        .collect();
+/// Converts a locator to a minimal `FlightInfo`.
+fn flight_info(locator: String) -> FlightInfo {
+    // Create flight descriptor with the resource path
+    let descriptor = FlightDescriptor::new_path(vec![locator.clone()]);
 
-    // Create the stream from the vector
-    let stream = futures::stream::iter(flight_infos);
+    // Create a ticket using the resource locator
+    let endpoint = FlightEndpoint::new().with_ticket(Ticket {
+        ticket: locator.into(),
+    });
 
-    Ok(Box::pin(stream))
+    FlightInfo::new()
+        .with_descriptor(descriptor)
+        .with_endpoint(endpoint)
 }
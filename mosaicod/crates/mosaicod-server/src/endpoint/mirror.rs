@@ -0,0 +1,191 @@
+//! Read-only mirror serving [`get_flight_info`](super::get_flight_info) and
+//! [`do_get`](super::do_get) from the catalog manifest published in the store while the
+//! database is unavailable.
+//!
+//! Only the published data of sequences and topics is mirrored: the data of sessions, the
+//! changes of upsert topics and the results of SQL queries require the database.
+use super::{do_get::stream_topic, get_flight_info::sequence_schema};
+use crate::error::Result;
+use arrow::datatypes::Schema;
+use arrow_flight::{
+    FlightDescriptor, FlightEndpoint, FlightInfo, Ticket, encode::FlightDataEncoder,
+    flight_descriptor::DescriptorType,
+};
+use mosaicod_core::{self as core, params, types};
+use mosaicod_ext as ext;
+use mosaicod_facade as facade;
+use mosaicod_marshal as marshal;
+use tracing::{info, warn};
+
+/// Response header flagging the responses served by the mirror.
+pub const MIRROR_HEADER: &str = "mosaico-read-only-mirror";
+
+/// Message provided when an error occurs when building flight info data
+const UNABLE_TO_BUILD_FLIGHT_INFO: &str = "unable to build flight info data";
+
+/// Error returned for the requests the mirror can't serve.
+fn unavailable() -> core::Error {
+    core::Error::unavailable("database".to_owned())
+}
+
+/// Reads the catalog manifest, `None` if it was never published.
+async fn manifest(
+    ctx: &facade::Context,
+) -> Result<Option<types::CatalogManifest<marshal::JsonMetadataBlob>>> {
+    let manifest = facade::mirror::manifest(ctx).await?;
+    if manifest.is_none() {
+        warn!("database unavailable and no catalog manifest published, the mirror is disabled");
+    }
+    Ok(manifest)
+}
+
+/// Returns the [`FlightInfo`] for the requested resource from the catalog manifest, `None`
+/// if no manifest was published.
+pub async fn get_flight_info(
+    ctx: &facade::Context,
+    desc: FlightDescriptor,
+) -> Result<Option<FlightInfo>> {
+    if desc.r#type() != DescriptorType::Cmd {
+        Err(core::Error::unsupported_descriptor())?;
+    }
+    let cmd = marshal::flight::get_flight_info_cmd(&desc.cmd)?;
+
+    if cmd.changes_since.is_some() || cmd.session.is_some() {
+        Err(unavailable())?;
+    }
+
+    let Some(manifest) = manifest(ctx).await? else {
+        return Ok(None);
+    };
+
+    let resource_name = &cmd.resource_locator;
+    info!("requesting info for resource {} (mirror)", resource_name);
+
+    let flight_info = if let Ok(locator) = resource_name.parse::<types::SequenceLocator>() {
+        let sequence = facade::mirror::sequence(manifest, &locator)?;
+
+        let metadata = types::SequenceMetadata {
+            created_at: sequence.created_at,
+            resource_locator: sequence.locator,
+            sessions: Vec::new(),
+            user_metadata: sequence.user_metadata,
+        };
+        let schema = sequence_schema(&metadata)?;
+
+        let mut flight_info = FlightInfo::new()
+            .with_descriptor(desc)
+            .with_app_metadata(marshal::flight::SequenceAppMetadata::from(metadata))
+            .try_with_schema(&schema)
+            .map_err(|_| core::Error::internal(Some(UNABLE_TO_BUILD_FLIGHT_INFO.to_owned())))?;
+
+        for topic in sequence.topics {
+            // Restrict the anonymization to the columns of the topic
+            let anonymization = cmd
+                .anonymization
+                .as_ref()
+                .map(|anonymization| {
+                    anonymization
+                        .retain(|column| topic.fields.iter().any(|field| field.name == column))
+                })
+                .filter(|anonymization| !anonymization.is_empty());
+            let ticket = types::flight::TicketTopic {
+                locator: topic.locator,
+                timestamp_range: cmd.timestamp_range.clone(),
+                blob_references: cmd.blob_references,
+                changes_since: None,
+                session: None,
+                anonymization,
+            };
+            flight_info =
+                flight_info.with_endpoint(topic_endpoint(ticket, topic.metadata.properties)?);
+        }
+
+        flight_info
+    } else if let Ok(locator) = resource_name.parse::<types::TopicLocator>() {
+        let topic = facade::mirror::topic(manifest, &locator)?;
+
+        // The schema is the one of the data files, annotated with the ontology metadata
+        let query_result =
+            facade::mirror::read(ctx, &topic, params::params().max_batch_size.value).await?;
+        let metadata = marshal::JsonTopicOntologyMetadata::from(topic.metadata.ontology_metadata);
+        let mut schema: Schema = query_result
+            .schema_with_metadata(metadata.to_flat_hashmap()?)
+            .as_ref()
+            .clone();
+        if !cmd.blob_references {
+            schema = ext::blob::inline_schema(&schema);
+        }
+        if let Some(anonymization) = &cmd.anonymization {
+            schema = ext::anonymize::anonymize_schema(&schema, anonymization)
+                .map_err(core::Error::bad_request)?;
+        }
+
+        let ticket = types::flight::TicketTopic {
+            locator: topic.locator,
+            timestamp_range: cmd.timestamp_range,
+            blob_references: cmd.blob_references,
+            changes_since: None,
+            session: None,
+            anonymization: cmd.anonymization,
+        };
+
+        FlightInfo::new()
+            .with_descriptor(desc)
+            .with_endpoint(topic_endpoint(ticket, topic.metadata.properties)?)
+            .try_with_schema(&schema)
+            .map_err(|_| core::Error::internal(Some(UNABLE_TO_BUILD_FLIGHT_INFO.to_owned())))?
+    } else {
+        Err(core::Error::bad_locator(resource_name.clone()))?
+    };
+
+    Ok(Some(flight_info))
+}
+
+/// Builds a [`FlightEndpoint`] serving `ticket`, the data info and version of the topic
+/// are kept in the database and are not reported.
+fn topic_endpoint(
+    ticket: types::flight::TicketTopic,
+    properties: types::TopicMetadataProperties,
+) -> Result<FlightEndpoint> {
+    Ok(FlightEndpoint::new()
+        .with_ticket(Ticket {
+            ticket: marshal::flight::ticket_topic_to_binary(ticket)?.into(),
+        })
+        .with_app_metadata(marshal::flight::TopicAppMetadata::new(properties)))
+}
+
+/// Streams the data served by `ticket` from the catalog manifest, `None` if no manifest was
+/// published.
+pub async fn do_get(ctx: &facade::Context, ticket: Ticket) -> Result<Option<FlightDataEncoder>> {
+    let types::flight::Ticket::Topic(ticket) = marshal::flight::ticket_from_binary(&ticket.ticket)?
+    else {
+        Err(unavailable())?
+    };
+
+    if ticket.changes_since.is_some() || ticket.session.is_some() {
+        Err(unavailable())?;
+    }
+
+    let Some(manifest) = manifest(ctx).await? else {
+        return Ok(None);
+    };
+
+    info!("requesting data for ticket `{}` (mirror)", ticket.locator);
+
+    let topic = facade::mirror::topic(manifest, &ticket.locator)?;
+    let query_result =
+        facade::mirror::read(ctx, &topic, params::params().max_batch_size.value).await?;
+
+    let stream = stream_topic(
+        ctx,
+        query_result,
+        topic.metadata,
+        topic.path_in_store,
+        ticket.timestamp_range,
+        ticket.blob_references,
+        ticket.anonymization,
+    )
+    .await?;
+
+    Ok(Some(stream))
+}
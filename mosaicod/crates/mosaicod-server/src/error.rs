@@ -3,6 +3,16 @@ use thiserror::Error;
 
 pub type Result<T> = core::error::PublicResult<T>;
 
+/// Metadata key of the delay (in milliseconds) after which clients should retry the
+/// requests failed with an `UNAVAILABLE` status.
+pub const RETRY_AFTER_MS: &str = "retry-after-ms";
+
+/// Metadata key of the dependency that caused an `UNAVAILABLE` status.
+pub const UNAVAILABLE_DEPENDENCY: &str = "unavailable-dependency";
+
+/// Delay suggested to clients when the unavailable dependency provides none.
+const DEFAULT_RETRY_AFTER: std::time::Duration = std::time::Duration::from_secs(1);
+
 /// Extension trait used to associate gRPC information
 /// to a public error.
 pub trait PublicErrorGrpcExt {
@@ -47,9 +57,15 @@ where
             ErrorKind::MissingHeader => Code::InvalidArgument,
             ErrorKind::TopicAlreadyFinalized(_) => Code::FailedPrecondition,
             ErrorKind::TopicUploadInProgress(_) => Code::FailedPrecondition,
+            ErrorKind::TopicNotPublished(_) => Code::FailedPrecondition,
+            ErrorKind::TopicVersionConflict(_, _, _) => Code::Aborted,
+            ErrorKind::ConsumerOffsetBehind(_, _) => Code::Aborted,
             ErrorKind::MissingDoPut(_) => Code::FailedPrecondition,
             ErrorKind::SessionAlreadyFinalized(_) => Code::FailedPrecondition,
             ErrorKind::EmptySession(_) => Code::FailedPrecondition,
+            ErrorKind::LegalHold(_) => Code::FailedPrecondition,
+            ErrorKind::SequenceLocked(_) => Code::FailedPrecondition,
+            ErrorKind::TopicSchemaMismatch(..) => Code::InvalidArgument,
             ErrorKind::UnsupportedStreamMessage => Code::Aborted,
             ErrorKind::UnsupportedLocator(_) => Code::InvalidArgument,
             ErrorKind::UnsupportedOperation => Code::InvalidArgument,
@@ -58,6 +74,10 @@ where
             ErrorKind::InvalidConfiguration(_) => Code::Unknown,
             ErrorKind::UnsupportedTime(_) => Code::InvalidArgument,
             ErrorKind::InvalidFingerprint(_) => Code::InvalidArgument,
+            ErrorKind::QueryLimitExceeded(_) => Code::ResourceExhausted,
+            ErrorKind::Overloaded(_) => Code::ResourceExhausted,
+            ErrorKind::DeadlineExceeded(_) => Code::DeadlineExceeded,
+            ErrorKind::Unavailable(_, _) => Code::Unavailable,
             ErrorKind::LocatorKindMismatch(_, _) => Code::InvalidArgument,
         }
     }
@@ -71,7 +91,24 @@ where
     }
 
     fn to_status(self) -> tonic::Status {
-        tonic::Status::new(self.grpc_code(), self.as_ref().to_string())
+        let mut status = tonic::Status::new(self.grpc_code(), self.as_ref().to_string());
+
+        if let core::error::ErrorKind::Unavailable(dependency, retry_after) =
+            self.as_ref().error().kind()
+        {
+            let retry_after = retry_after.unwrap_or(DEFAULT_RETRY_AFTER);
+            status.metadata_mut().insert(
+                RETRY_AFTER_MS,
+                tonic::metadata::MetadataValue::from(retry_after.as_millis() as u64),
+            );
+            if let Ok(dependency) = dependency.parse() {
+                status
+                    .metadata_mut()
+                    .insert(UNAVAILABLE_DEPENDENCY, dependency);
+            }
+        }
+
+        status
     }
 
     fn log_to_status(self) -> tonic::Status {
@@ -109,6 +146,12 @@ pub enum ErrorKind {
     SemaphoreClosed,
     #[error("not a semver")]
     NotASemVer(String),
+    #[error("invalid custom action")]
+    InvalidCustomAction(String),
+    #[error("duplicated custom action")]
+    DuplicatedCustomAction(String),
+    #[error("unknown custom action")]
+    UnknownCustomAction(String),
 }
 
 #[derive(Debug, Clone)]
@@ -126,6 +169,18 @@ impl Error {
     pub fn not_a_semver(msg: String) -> Error {
         Self(ErrorKind::NotASemVer(msg))
     }
+
+    pub fn invalid_custom_action(action: &str) -> Self {
+        Self(ErrorKind::InvalidCustomAction(action.to_string()))
+    }
+
+    pub fn duplicated_custom_action(action: &str) -> Self {
+        Self(ErrorKind::DuplicatedCustomAction(action.to_string()))
+    }
+
+    pub fn unknown_custom_action(action: &str) -> Self {
+        Self(ErrorKind::UnknownCustomAction(action.to_string()))
+    }
 }
 
 impl core::error::PublicError for Error {
@@ -135,6 +190,44 @@ impl core::error::PublicError for Error {
                 core::Error::bad_request(format!("invalid notification type `{ntype}`"))
             }
             ErrorKind::SemaphoreClosed | ErrorKind::NotASemVer(_) => core::Error::internal(None),
+            ErrorKind::InvalidCustomAction(action) => core::Error::invalid_configuration(
+                action.to_owned(),
+                "custom action namespace or name is invalid or reserved".to_owned(),
+            ),
+            ErrorKind::DuplicatedCustomAction(action) => {
+                core::Error::already_exists(format!("custom action `{action}`"))
+            }
+            ErrorKind::UnknownCustomAction(action) => {
+                core::Error::bad_request(format!("no available action for string `{action}`"))
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unavailable_status() {
+        let err = core::Error::unavailable_for(
+            "database".to_owned(),
+            std::time::Duration::from_millis(250),
+        );
+        let status = err.to_public_error().to_status();
+        assert_eq!(status.code(), tonic::Code::Unavailable);
+        assert_eq!(status.metadata().get(RETRY_AFTER_MS).unwrap(), "250");
+        assert_eq!(
+            status.metadata().get(UNAVAILABLE_DEPENDENCY).unwrap(),
+            "database"
+        );
+
+        let status = core::Error::unavailable("store".to_owned())
+            .to_public_error()
+            .to_status();
+        assert_eq!(status.metadata().get(RETRY_AFTER_MS).unwrap(), "1000");
+
+        let status = core::Error::internal(None).to_public_error().to_status();
+        assert!(status.metadata().get(RETRY_AFTER_MS).is_none());
+    }
+}
@@ -0,0 +1,400 @@
+//! Publication of the event log to an external broker.
+//!
+//! A task periodically reads the activity feed of every sequence, see
+//! [`facade::sequence::activity_feed`], and publishes its new entries (lifecycle events,
+//! session finalizations, watermarks and notifications) through an [`EventPublisher`], to
+//! Kafka or NATS when configured from the parameters.
+//!
+//! The position reached in the feed of each sequence is committed as the offset of a named
+//! consumer, see [`facade::consumer`], once the broker acknowledged the published events.
+//! Delivery is at least once: the events published by a run failing before the commit are
+//! published again by the next one. Each event carries an identifier, the same every time
+//! it is published, letting the receivers discard the duplicates.
+use futures::future::BoxFuture;
+use mosaicod_core::{self as core, params, types};
+use mosaicod_facade as facade;
+use mosaicod_marshal as marshal;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Maximum number of sequences read from the database at once.
+const SEQUENCE_BATCH_SIZE: usize = 100;
+
+/// Maximum number of events published at once.
+const EVENT_BATCH_SIZE: usize = 500;
+
+type PublishResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+/// Event of the activity feed of a sequence published to the broker.
+#[derive(Debug, Clone)]
+pub struct SinkEvent {
+    /// Locator of the sequence whose feed reported the event
+    pub sequence: String,
+    /// Name of the event, e.g. `session_finalized`
+    pub name: &'static str,
+    /// Identifier of the event, the same every time it is published
+    pub id: String,
+    /// JSON serialization of the event
+    pub payload: Vec<u8>,
+}
+
+impl SinkEvent {
+    fn new(sequence: &types::SequenceLocator, activity: types::Activity) -> Self {
+        let name = activity.event.name();
+        let id = format!(
+            "{}/{}/{}/{}",
+            sequence,
+            activity.timestamp.as_i64(),
+            name,
+            activity.subject
+        );
+
+        let mut payload = serde_json::json!(marshal::responses::ActivityItem::from(activity));
+        payload["sequence"] = sequence.to_string().into();
+        payload["id"] = id.clone().into();
+
+        Self {
+            sequence: sequence.to_string(),
+            name,
+            id,
+            payload: payload.to_string().into_bytes(),
+        }
+    }
+}
+
+/// Destination of the events.
+pub trait EventPublisher: Send + Sync {
+    /// Name of the publisher, used in logs.
+    fn name(&self) -> &str;
+
+    /// Publishes the events in order, returning once the broker acknowledged all of them.
+    fn publish<'a>(&'a self, events: &'a [SinkEvent]) -> BoxFuture<'a, PublishResult>;
+}
+
+/// Configuration of the publication of the event log.
+#[derive(Clone)]
+pub struct EventSinkConfig {
+    pub publisher: Arc<dyn EventPublisher>,
+    /// Name of the consumer whose offsets track the published events
+    pub consumer: String,
+    /// Interval between consecutive runs
+    pub interval: Duration,
+}
+
+impl EventSinkConfig {
+    /// Reads the configuration from the parameters, returns `None` if the publication is
+    /// disabled.
+    pub fn from_params() -> core::error::PublicResult<Option<Self>> {
+        let params = params::params();
+
+        let url = &params.event_sink_url.value;
+        if url.is_empty() {
+            return Ok(None);
+        }
+
+        let invalid =
+            |e: String| core::Error::invalid_configuration(params.event_sink_url.env.to_owned(), e);
+
+        let publisher: Arc<dyn EventPublisher> = if let Some(brokers) = url.strip_prefix("kafka://")
+        {
+            Arc::new(kafka_publisher(brokers, &params.event_sink_topic.value).map_err(invalid)?)
+        } else if url.starts_with("nats://") {
+            Arc::new(nats_publisher(url, &params.event_sink_topic.value).map_err(invalid)?)
+        } else {
+            Err(invalid(format!(
+                "unsupported event sink `{url}`, expected a `kafka://` or `nats://` URL"
+            )))?
+        };
+
+        Ok(Some(Self {
+            publisher,
+            consumer: params.event_sink_consumer.value.clone(),
+            interval: Duration::from_secs(params.event_sink_interval.value),
+        }))
+    }
+}
+
+#[cfg(feature = "kafka")]
+fn kafka_publisher(brokers: &str, topic: &str) -> Result<kafka::KafkaPublisher, String> {
+    kafka::KafkaPublisher::try_new(brokers, topic)
+}
+
+#[cfg(not(feature = "kafka"))]
+fn kafka_publisher(_brokers: &str, _topic: &str) -> Result<UnsupportedPublisher, String> {
+    Err("mosaicod was built without the `kafka` feature".to_owned())
+}
+
+#[cfg(feature = "nats")]
+fn nats_publisher(url: &str, subject: &str) -> Result<nats::NatsPublisher, String> {
+    Ok(nats::NatsPublisher::new(url, subject))
+}
+
+#[cfg(not(feature = "nats"))]
+fn nats_publisher(_url: &str, _subject: &str) -> Result<UnsupportedPublisher, String> {
+    Err("mosaicod was built without the `nats` feature".to_owned())
+}
+
+/// Placeholder of the publishers of the brokers not compiled in, never constructed.
+#[cfg(not(all(feature = "kafka", feature = "nats")))]
+enum UnsupportedPublisher {}
+
+#[cfg(not(all(feature = "kafka", feature = "nats")))]
+impl EventPublisher for UnsupportedPublisher {
+    fn name(&self) -> &str {
+        match *self {}
+    }
+
+    fn publish<'a>(&'a self, _events: &'a [SinkEvent]) -> BoxFuture<'a, PublishResult> {
+        match *self {}
+    }
+}
+
+/// Spawns the task publishing the event log, returns `None` if the publication is
+/// disabled.
+pub(crate) fn spawn_event_sink(
+    context: facade::Context,
+    config: Option<EventSinkConfig>,
+) -> Option<tokio::task::JoinHandle<()>> {
+    let config = config?;
+
+    info!(
+        "event log published to {} every {:?} (consumer: `{}`)",
+        config.publisher.name(),
+        config.interval,
+        config.consumer
+    );
+
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(config.interval);
+
+        loop {
+            ticker.tick().await;
+            match publish(&context, &config).await {
+                Ok(0) => {}
+                Ok(published) => debug!(
+                    "event sink: {} events published to {}",
+                    published,
+                    config.publisher.name()
+                ),
+                Err(e) => warn!("event sink stopped: {}", e),
+            }
+        }
+    }))
+}
+
+/// Runs a publication pass over all the sequences, returns the number of published events.
+async fn publish(context: &facade::Context, config: &EventSinkConfig) -> Result<usize, String> {
+    let mut published = 0;
+    let mut cursor = None;
+
+    loop {
+        let page = facade::sequence::page(context, "", cursor.as_deref(), SEQUENCE_BATCH_SIZE)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        for handle in &page.handles {
+            published += publish_sequence(context, config, handle)
+                .await
+                .map_err(|e| {
+                    format!("unable to publish events of `{}`: {}", handle.locator(), e)
+                })?;
+        }
+
+        cursor = page.cursor;
+        if cursor.is_none() {
+            return Ok(published);
+        }
+    }
+}
+
+/// Publishes the entries of the activity feed of the sequence following the committed
+/// offset, returns the number of published events.
+async fn publish_sequence(
+    context: &facade::Context,
+    config: &EventSinkConfig,
+    handle: &facade::sequence::Handle,
+) -> Result<usize, String> {
+    let offset = facade::consumer::sequence_offset(context, handle, &config.consumer)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut cursor = offset.and_then(|offset| match offset.position {
+        types::ConsumerPosition::Activity(cursor) => Some(cursor),
+        types::ConsumerPosition::Watermark(_) => None,
+    });
+
+    let mut published = 0;
+    loop {
+        let page = facade::sequence::activity_feed(
+            context,
+            handle,
+            types::Timestamp::unbounded_neg(),
+            cursor,
+            EVENT_BATCH_SIZE,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+        if page.activities.is_empty() {
+            return Ok(published);
+        }
+
+        let events: Vec<_> = page
+            .activities
+            .into_iter()
+            .map(|activity| SinkEvent::new(handle.locator(), activity))
+            .collect();
+
+        config
+            .publisher
+            .publish(&events)
+            .await
+            .map_err(|e| format!("{} failed: {}", config.publisher.name(), e))?;
+        published += events.len();
+
+        facade::consumer::commit_activity(context, handle, &config.consumer, page.end_cursor)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if page.cursor.is_none() {
+            return Ok(published);
+        }
+        cursor = Some(page.end_cursor);
+    }
+}
+
+#[cfg(feature = "kafka")]
+mod kafka {
+    use super::{EventPublisher, PublishResult, SinkEvent};
+    use futures::future::{BoxFuture, try_join_all};
+    use rdkafka::ClientConfig;
+    use rdkafka::message::{Header, OwnedHeaders};
+    use rdkafka::producer::{FutureProducer, FutureRecord};
+    use std::time::Duration;
+
+    /// Time allowed to the broker to acknowledge an event.
+    const DELIVERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Publishes the events to a Kafka topic, keyed by sequence so that the events of a
+    /// sequence are kept in order.
+    pub struct KafkaPublisher {
+        producer: FutureProducer,
+        topic: String,
+    }
+
+    impl KafkaPublisher {
+        /// Creates a publisher connecting to the comma separated list of `brokers`.
+        pub fn try_new(brokers: &str, topic: &str) -> Result<Self, String> {
+            let producer = ClientConfig::new()
+                .set("bootstrap.servers", brokers)
+                .set("enable.idempotence", "true")
+                .set("acks", "all")
+                .create()
+                .map_err(|e| format!("invalid Kafka configuration: {e}"))?;
+
+            Ok(Self {
+                producer,
+                topic: topic.to_owned(),
+            })
+        }
+    }
+
+    impl EventPublisher for KafkaPublisher {
+        fn name(&self) -> &str {
+            "kafka"
+        }
+
+        fn publish<'a>(&'a self, events: &'a [SinkEvent]) -> BoxFuture<'a, PublishResult> {
+            Box::pin(async move {
+                let deliveries = events.iter().map(|event| {
+                    let headers = OwnedHeaders::new()
+                        .insert(Header {
+                            key: "mosaico-event-id",
+                            value: Some(&event.id),
+                        })
+                        .insert(Header {
+                            key: "mosaico-event",
+                            value: Some(event.name),
+                        });
+                    let record = FutureRecord::to(&self.topic)
+                        .key(&event.sequence)
+                        .payload(&event.payload)
+                        .headers(headers);
+
+                    self.producer.send(record, DELIVERY_TIMEOUT)
+                });
+
+                try_join_all(deliveries).await.map_err(|(e, _)| e)?;
+
+                Ok(())
+            })
+        }
+    }
+}
+
+#[cfg(feature = "nats")]
+mod nats {
+    use super::{EventPublisher, PublishResult, SinkEvent};
+    use async_nats::{HeaderMap, jetstream};
+    use futures::future::{BoxFuture, try_join_all};
+    use std::future::IntoFuture;
+    use tokio::sync::OnceCell;
+
+    /// Publishes the events to a NATS JetStream stream, on the subjects
+    /// `<prefix>.<event>`. The stream receiving the subjects must exist.
+    pub struct NatsPublisher {
+        url: String,
+        prefix: String,
+        /// Connected on the first publication, the client reconnects by itself
+        jetstream: OnceCell<jetstream::Context>,
+    }
+
+    impl NatsPublisher {
+        pub fn new(url: &str, prefix: &str) -> Self {
+            Self {
+                url: url.to_owned(),
+                prefix: prefix.to_owned(),
+                jetstream: OnceCell::new(),
+            }
+        }
+    }
+
+    impl EventPublisher for NatsPublisher {
+        fn name(&self) -> &str {
+            "nats"
+        }
+
+        fn publish<'a>(&'a self, events: &'a [SinkEvent]) -> BoxFuture<'a, PublishResult> {
+            Box::pin(async move {
+                let jetstream = self
+                    .jetstream
+                    .get_or_try_init(|| async {
+                        async_nats::connect(&self.url).await.map(jetstream::new)
+                    })
+                    .await?;
+
+                let mut acks = Vec::with_capacity(events.len());
+                for event in events {
+                    let mut headers = HeaderMap::new();
+                    // Duplicates published within the deduplication window are discarded
+                    headers.insert("Nats-Msg-Id", event.id.as_str());
+                    headers.insert("Mosaico-Sequence", event.sequence.as_str());
+
+                    acks.push(
+                        jetstream
+                            .publish_with_headers(
+                                format!("{}.{}", self.prefix, event.name),
+                                headers,
+                                event.payload.clone().into(),
+                            )
+                            .await?,
+                    );
+                }
+
+                try_join_all(acks.into_iter().map(IntoFuture::into_future)).await?;
+
+                Ok(())
+            })
+        }
+    }
+}
@@ -1,16 +1,23 @@
 use super::{
+    catalog, compaction,
     error::{PublicErrorGrpcExt, Result, ToStatusExt},
-    middleware,
+    event_sink,
+    ingest::IngestMetrics,
+    lockout::KeyLockout,
+    middleware, monitor, query_results,
+    registry::{ActionHandler, ActionRegistry},
+    reindex::Reindexer,
+    relay, relocation,
+    replay::{self, ReplayCapture},
+    scheduled_queries, sessions, shared_objects, tiering, trash,
 };
 use crate::endpoint;
 use arrow_flight::{
     Action as FlightAction, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo,
-    HandshakeRequest, HandshakeResponse, PollInfo, PutResult, SchemaResult, Ticket,
-    decode::FlightDataDecoder, flight_service_server::FlightService,
-    flight_service_server::FlightServiceServer,
+    HandshakeRequest, HandshakeResponse, PollInfo, PutResult, SchemaAsIpc, SchemaResult, Ticket,
+    flight_service_server::FlightService, flight_service_server::FlightServiceServer,
 };
 use futures::{StreamExt, TryStreamExt, stream::BoxStream};
-use log::{debug, error, info, warn};
 use mosaicod_core::{self as core, params, types};
 use mosaicod_db as db;
 use mosaicod_ext as ext;
@@ -21,6 +28,7 @@ use mosaicod_store as store;
 use std::sync::Arc;
 use tokio::sync::Notify;
 use tonic::{Request, Response, Status, Streaming, codec::CompressionEncoding, transport::Server};
+use tracing::{debug, error, info, warn};
 
 /// To stop the server use the following command on
 /// `ShutdownNotifier`
@@ -48,6 +56,8 @@ impl Default for ShutdownNotifier {
 pub struct TlsConfig {
     pub certificate_file: std::path::PathBuf,
     pub private_key_file: std::path::PathBuf,
+    /// Authority verifying the client certificates, if `Some` mutual TLS is required
+    pub client_ca_file: Option<std::path::PathBuf>,
 }
 
 #[derive(Clone)]
@@ -63,8 +73,36 @@ pub struct Config {
     /// If this option is true the server will require API keys for every operation
     enable_api_key_management: bool,
 
+    /// Validators of the bearer tokens, in addition to the static tokens of the parameters
+    token_validators: Vec<Arc<dyn middleware::TokenValidator>>,
+
     /// Enable gzip encoding in gRPC
     gzip: bool,
+
+    /// Custom action handlers available in the DoAction endpoint
+    actions: ActionRegistry,
+
+    /// Limits of the queries, if `None` they are read from the parameters
+    query_limits: Option<facade::QueryLimitPolicy>,
+
+    /// Signer of the session attestations, if `None` the key is read from the parameters
+    attestation_signer: Option<Arc<facade::AttestationSigner>>,
+
+    /// Exporter of the topics as tables, if `None` the format is read from the parameters
+    table_exporter: Option<Arc<facade::TableExporter>>,
+
+    /// Relay of the finalized sessions, if `None` it is read from the parameters
+    relay: Option<relay::RelayConfig>,
+
+    /// Publication of the event log, if `None` it is read from the parameters
+    event_sink: Option<event_sink::EventSinkConfig>,
+
+    /// Bandwidth limits of the background transfers, if `None` they are read from the
+    /// parameters
+    transfer_limits: Option<Arc<facade::TransferLimits>>,
+
+    /// Custom layers wrapping the Flight service, inside the built-in ones
+    layers: middleware::Stack,
 }
 
 impl Config {
@@ -74,7 +112,16 @@ impl Config {
             port,
             tls: None,
             enable_api_key_management: false,
+            token_validators: Vec::new(),
             gzip: false,
+            actions: ActionRegistry::new(),
+            query_limits: None,
+            attestation_signer: None,
+            table_exporter: None,
+            relay: None,
+            event_sink: None,
+            transfer_limits: None,
+            layers: middleware::Stack::new(),
         }
     }
 
@@ -83,6 +130,11 @@ impl Config {
         self.tls = Some(tls);
     }
 
+    /// Returns the TLS configuration, if enabled
+    pub fn tls_config(&self) -> Option<&TlsConfig> {
+        self.tls.as_ref()
+    }
+
     /// Enables gzip compression for both incoming and outgoing gRPC messages.
     pub fn gzip(&mut self, enable: bool) {
         self.gzip = enable;
@@ -92,6 +144,79 @@ impl Config {
     pub fn enable_api_key_management(&mut self) {
         self.enable_api_key_management = true;
     }
+
+    /// Adds a validator of the bearer tokens presented by the clients. When at least one
+    /// validator is configured the server requires authentication for every operation.
+    pub fn token_validator<V>(&mut self, validator: V)
+    where
+        V: middleware::TokenValidator + 'static,
+    {
+        self.token_validators.push(Arc::new(validator));
+    }
+
+    /// Sets the limits of the queries, overriding the ones configured in the parameters.
+    pub fn query_limits(&mut self, query_limits: facade::QueryLimitPolicy) {
+        self.query_limits = Some(query_limits);
+    }
+
+    /// Sets the bandwidth limits of the background transfers, overriding the ones
+    /// configured in the parameters.
+    pub fn transfer_limits(&mut self, transfer_limits: facade::TransferLimits) {
+        self.transfer_limits = Some(Arc::new(transfer_limits));
+    }
+
+    /// Sets the signer of the session attestations, overriding the key configured in the
+    /// parameters.
+    pub fn attestation_signer(&mut self, attestation_signer: facade::AttestationSigner) {
+        self.attestation_signer = Some(Arc::new(attestation_signer));
+    }
+
+    /// Sets the exporter of the topics as tables, overriding the format configured in the
+    /// parameters.
+    pub fn table_exporter(&mut self, table_exporter: facade::TableExporter) {
+        self.table_exporter = Some(Arc::new(table_exporter));
+    }
+
+    /// Relays the finalized sessions to a central instance, overriding the relay configured
+    /// in the parameters.
+    pub fn relay(&mut self, relay: relay::RelayConfig) {
+        self.relay = Some(relay);
+    }
+
+    /// Publishes the event log, overriding the event sink configured in the parameters.
+    pub fn event_sink(&mut self, event_sink: event_sink::EventSinkConfig) {
+        self.event_sink = Some(event_sink);
+    }
+
+    /// Registers a custom action handler, reachable by clients using the
+    /// `<namespace>.<name>` action type.
+    pub fn register_action<H>(&mut self, namespace: &str, name: &str, handler: H) -> Result<()>
+    where
+        H: ActionHandler + 'static,
+    {
+        self.actions.register(namespace, name, handler)
+    }
+
+    /// Adds a custom layer wrapping the Flight service.
+    ///
+    /// Custom layers receive the requests after the built-in ones, in the order they are
+    /// added, so the [`middleware::AuthContext`] of the request is available in its
+    /// extensions. Failures must be returned as gRPC statuses.
+    pub fn layer<L>(&mut self, layer: L)
+    where
+        L: tower::Layer<middleware::BoxService> + Send + Sync + 'static,
+        L::Service: tower::Service<
+                middleware::HttpRequest,
+                Response = middleware::HttpResponse,
+                Error = std::convert::Infallible,
+            > + Clone
+            + Send
+            + Sync
+            + 'static,
+        <L::Service as tower::Service<middleware::HttpRequest>>::Future: Send + 'static,
+    {
+        self.layers.push(layer);
+    }
 }
 
 /// Start mosaico Apache Arrow Flight service
@@ -103,22 +228,98 @@ pub async fn start(
 ) -> std::result::Result<(), Box<dyn std::error::Error>> {
     let addr = format!("{}:{}", config.host, config.port).parse()?;
 
-    let mut flight_service = MosaicodFlight::try_new(store, db.clone())?;
+    let store_monitor = monitor::spawn_store_monitor(store.clone());
+
+    let request_metrics = Arc::new(middleware::RequestMetrics::new());
+
+    let mut flight_service =
+        MosaicodFlight::try_new(store, db.clone(), config.actions, request_metrics.clone())?;
 
     if config.enable_api_key_management {
         flight_service.enable_api_key_manegement();
     }
 
-    let mut auth_layer = middleware::AuthLayer::new(flight_service.context());
+    if let Some(validator) =
+        middleware::StaticTokenValidator::from_params().map_err(|e| e.to_string())?
+    {
+        flight_service.add_token_validator(Arc::new(validator));
+    }
 
-    let mut svc = FlightServiceServer::new(flight_service);
+    for validator in config.token_validators {
+        flight_service.add_token_validator(validator);
+    }
+
+    if let Some(query_limits) = config.query_limits {
+        flight_service.set_query_limits(query_limits);
+    }
+
+    if let Some(attestation_signer) = config.attestation_signer {
+        flight_service.set_attestation_signer(attestation_signer);
+    }
 
-    // If API key management is disabled define a custom permission with all permissions
-    // and enable permissions passthrough in the auth middleware
-    if !config.enable_api_key_management {
-        auth_layer = auth_layer.with_permission_passthrough(types::auth::Permission::Manage);
+    if let Some(table_exporter) = config.table_exporter {
+        flight_service.set_table_exporter(table_exporter);
     }
-    let layer = tower::ServiceBuilder::new().layer(auth_layer).into_inner();
+
+    if let Some(transfer_limits) = config.transfer_limits {
+        flight_service.set_transfer_limits(transfer_limits);
+    }
+
+    let upsert_compactor = compaction::spawn_upsert_compactor(flight_service.context());
+
+    let query_result_purger = query_results::spawn_query_result_purger(flight_service.context());
+
+    let scheduled_query_runner =
+        scheduled_queries::spawn_scheduled_query_runner(flight_service.context());
+
+    let store_relocator = relocation::spawn_store_relocator(flight_service.context());
+
+    let catalog_publisher = catalog::spawn_catalog_publisher(flight_service.context());
+
+    let trash_purger = trash::spawn_trash_purger(flight_service.context());
+
+    let shared_object_collector =
+        shared_objects::spawn_shared_object_collector(flight_service.context());
+
+    let session_reaper = sessions::spawn_session_reaper(flight_service.context());
+
+    let access_log_flusher = tiering::spawn_access_log_flusher(flight_service.context());
+
+    let tierer = tiering::spawn_tierer(flight_service.context());
+
+    let relay_config = match config.relay {
+        Some(relay) => Some(relay),
+        None => relay::RelayConfig::from_params().map_err(|e| e.to_string())?,
+    };
+    let session_relay = relay::spawn_session_relay(flight_service.context(), relay_config)
+        .map_err(|e| e.to_string())?;
+
+    let event_sink_config = match config.event_sink {
+        Some(event_sink) => Some(event_sink),
+        None => event_sink::EventSinkConfig::from_params().map_err(|e| e.to_string())?,
+    };
+    let event_sink = event_sink::spawn_event_sink(flight_service.context(), event_sink_config);
+
+    // If authentication is disabled the auth middleware grants all permissions to every request
+    let authenticator = flight_service.authenticator();
+    let auth_enabled = authenticator.is_enabled();
+    let auth_layer = middleware::AuthLayer::new(authenticator);
+
+    // Notification streams never end on their own, they are closed on shutdown
+    let notification_bus = flight_service.notification_bus.clone();
+
+    let mut svc = FlightServiceServer::new(flight_service);
+
+    // Requests go through the layers in order, before reaching the service
+    let mut layers = middleware::Stack::new();
+    layers.push(middleware::TraceLayer::new());
+    layers.push(middleware::LoggingLayer::new());
+    layers.push(middleware::MetricsLayer::new(request_metrics));
+    layers.push(middleware::DeadlineLayer::from_params());
+    layers.push(middleware::RateLimitLayer::from_params());
+    layers.push(middleware::CircuitBreakerLayer::from_params());
+    layers.push(auth_layer);
+    layers.extend(config.layers);
 
     let mut builder = Server::builder();
 
@@ -128,18 +329,23 @@ pub async fn start(
         builder = builder.tls_config(ext::tonic::load_tls_config(
             &tls.certificate_file,
             &tls.private_key_file,
+            tls.client_ca_file.as_ref(),
         )?)?;
         tls_enabled = true;
+
+        if tls.client_ca_file.is_some() {
+            info!("mutual TLS enabled, client certificates are required");
+        }
     }
 
     if !tls_enabled {
         warn!("TLS is currently disabled. Traffic is being sent unencrypted.");
     }
-    if !config.enable_api_key_management {
-        warn!("API key management is currently disabled.");
+    if !auth_enabled {
+        warn!("API key management and token authentication are currently disabled.");
     } else if !tls_enabled {
         warn!(
-            "API key management is currently enabled but TLS is disabled. Sensitive credential are sent unencrypted and could be intercepted."
+            "Authentication is currently enabled but TLS is disabled. Sensitive credential are sent unencrypted and could be intercepted."
         );
     }
 
@@ -154,19 +360,69 @@ pub async fn start(
         info!("gzip compression for gRPC requests is enabled");
     }
 
-    let server = builder.layer(layer).add_service(svc);
+    // Eagerly initialize the ontology registry with the well-known models
+    let mut ontologies: Vec<&str> = ext::ontology::registry().tags().collect();
+    ontologies.sort_unstable();
+    info!("registered ontologies: {}", ontologies.join(", "));
+
+    let server = builder.layer(layers).add_service(svc);
 
     if let Some(shutdown_notifier) = shutdown {
         server
             .serve_with_shutdown(addr, async {
                 shutdown_notifier.wait_for_shutdown().await;
                 debug!("received shutdown notification");
+                notification_bus.close();
             })
             .await?;
     } else {
         server.serve(addr).await?;
     }
 
+    if let Some(store_monitor) = store_monitor {
+        store_monitor.abort();
+    }
+
+    if let Some(upsert_compactor) = upsert_compactor {
+        upsert_compactor.abort();
+    }
+
+    if let Some(store_relocator) = store_relocator {
+        store_relocator.abort();
+    }
+
+    if let Some(catalog_publisher) = catalog_publisher {
+        catalog_publisher.abort();
+    }
+
+    if let Some(trash_purger) = trash_purger {
+        trash_purger.abort();
+    }
+
+    if let Some(session_reaper) = session_reaper {
+        session_reaper.abort();
+    }
+
+    if let Some(access_log_flusher) = access_log_flusher {
+        access_log_flusher.abort();
+    }
+
+    if let Some(tierer) = tierer {
+        tierer.abort();
+    }
+
+    if let Some(session_relay) = session_relay {
+        session_relay.abort();
+    }
+
+    if let Some(event_sink) = event_sink {
+        event_sink.abort();
+    }
+
+    query_result_purger.abort();
+    scheduled_query_runner.abort();
+    shared_object_collector.abort();
+
     Ok(())
 }
 
@@ -175,14 +431,70 @@ struct MosaicodFlight {
     db: db::Database,
     ts_gw: query::TimeseriesEngineRef,
 
+    /// Query results shared by all the requests
+    query_cache: Arc<facade::QueryCache>,
+
+    /// Limits of the queries, depending on the permission of the principal
+    query_limits: Arc<facade::QueryLimitPolicy>,
+
+    /// Admission control shared by all the queries
+    query_admission: Arc<facade::QueryAdmission>,
+
+    /// External tables registered by the principals
+    external_tables: Arc<facade::ExternalTables>,
+
+    /// Signer of the attestations of the finalized sessions
+    attestation_signer: Arc<facade::AttestationSigner>,
+
+    /// Exporter of the topics as tables
+    table_exporter: Arc<facade::TableExporter>,
+
+    /// Bandwidth limits shared by the background transfers
+    transfer_limits: Arc<facade::TransferLimits>,
+
+    /// Sinks receiving the notifications
+    notification_dispatcher: Arc<facade::NotificationDispatcher>,
+
+    /// Broadcast of the notifications to the streaming clients
+    notification_bus: Arc<facade::NotificationBus>,
+
+    /// Counters of the requests, recorded by the metrics layer
+    request_metrics: Arc<middleware::RequestMetrics>,
+
+    /// Lockout of the peers sending invalid resource keys
+    key_lockout: Arc<KeyLockout>,
+
+    /// Latency from the data uploaded to its commit, by topic
+    ingest_metrics: Arc<IngestMetrics>,
+
+    /// Jobs rebuilding the state derived from the data of the topics
+    reindexer: Arc<Reindexer>,
+
+    /// Requests recorded to debug client issues, while a capture window is open
+    replay_capture: Arc<ReplayCapture>,
+
+    /// Layout of the folders of the resources created
+    store_layout: types::StoreLayout,
+
     api_key_management: bool,
 
+    /// Validators of the bearer tokens
+    token_validators: Vec<Arc<dyn middleware::TokenValidator>>,
+
+    /// Custom action handlers
+    actions: Arc<ActionRegistry>,
+
     /// Semaphore used to controll the maximum number of concurrent writers
     concurrent_writes_semaphore: Arc<tokio::sync::Semaphore>,
 }
 
 impl MosaicodFlight {
-    pub fn try_new(store: store::StoreRef, db: db::Database) -> std::result::Result<Self, String> {
+    pub fn try_new(
+        store: store::StoreRef,
+        db: db::Database,
+        actions: ActionRegistry,
+        request_metrics: Arc<middleware::RequestMetrics>,
+    ) -> std::result::Result<Self, String> {
         let ts_gw = Arc::new(
             query::TimeseriesEngine::try_new(
                 store.clone(),
@@ -195,7 +507,32 @@ impl MosaicodFlight {
             store,
             db,
             ts_gw,
+            query_cache: Arc::new(facade::QueryCache::from_params()),
+            query_limits: Arc::new(facade::QueryLimitPolicy::from_params()),
+            query_admission: Arc::new(facade::QueryAdmission::from_params()),
+            external_tables: Arc::new(facade::ExternalTables::from_params()),
+            attestation_signer: Arc::new(
+                facade::AttestationSigner::from_params().map_err(|e| e.to_string())?,
+            ),
+            table_exporter: Arc::new(
+                facade::TableExporter::from_params().map_err(|e| e.to_string())?,
+            ),
+            notification_dispatcher: Arc::new(
+                facade::NotificationDispatcher::from_params().map_err(|e| e.to_string())?,
+            ),
+            notification_bus: Arc::new(
+                facade::NotificationBus::from_params().map_err(|e| e.to_string())?,
+            ),
+            transfer_limits: Arc::new(facade::TransferLimits::from_params()),
+            request_metrics,
+            key_lockout: Arc::new(KeyLockout::from_params()),
+            ingest_metrics: Arc::new(IngestMetrics::new()),
+            reindexer: Arc::new(Reindexer::default()),
+            replay_capture: Arc::new(ReplayCapture::from_params()),
+            store_layout: facade::relocation::configured_layout().map_err(|e| e.to_string())?,
             api_key_management: false,
+            token_validators: Vec::new(),
+            actions: Arc::new(actions),
             concurrent_writes_semaphore: Arc::new(tokio::sync::Semaphore::new(
                 params::params().max_concurrent_writes.value,
             )),
@@ -206,8 +543,50 @@ impl MosaicodFlight {
         self.api_key_management = true;
     }
 
+    pub fn add_token_validator(&mut self, validator: Arc<dyn middleware::TokenValidator>) {
+        self.token_validators.push(validator);
+    }
+
+    pub fn set_attestation_signer(&mut self, attestation_signer: Arc<facade::AttestationSigner>) {
+        self.attestation_signer = attestation_signer;
+    }
+
+    pub fn set_table_exporter(&mut self, table_exporter: Arc<facade::TableExporter>) {
+        self.table_exporter = table_exporter;
+    }
+
+    pub fn set_query_limits(&mut self, query_limits: facade::QueryLimitPolicy) {
+        self.query_limits = Arc::new(query_limits);
+    }
+
+    pub fn set_transfer_limits(&mut self, transfer_limits: Arc<facade::TransferLimits>) {
+        self.transfer_limits = transfer_limits;
+    }
+
     pub fn context(&self) -> facade::Context {
         facade::Context::new(self.store.clone(), self.db.clone(), self.ts_gw.clone())
+            .with_query_cache(self.query_cache.clone())
+            .with_query_limits(self.query_limits.clone())
+            .with_query_admission(self.query_admission.clone())
+            .with_external_tables(self.external_tables.clone())
+            .with_notification_dispatcher(self.notification_dispatcher.clone())
+            .with_notification_bus(self.notification_bus.clone())
+            .with_store_layout(self.store_layout)
+            .with_attestation_signer(self.attestation_signer.clone())
+            .with_table_exporter(self.table_exporter.clone())
+            .with_transfer_limits(self.transfer_limits.clone())
+    }
+
+    /// Authenticator of the requests, shared by the auth middleware and the handshake
+    pub fn authenticator(&self) -> middleware::Authenticator {
+        let mut authenticator = middleware::Authenticator::new(self.context());
+        if self.api_key_management {
+            authenticator = authenticator.with_api_keys();
+        }
+        for validator in &self.token_validators {
+            authenticator = authenticator.with_token_validator(validator.clone());
+        }
+        authenticator
     }
 }
 
@@ -219,6 +598,23 @@ type DoActionStream = BoxStream<'static, std::result::Result<arrow_flight::Resul
 type ListActionsStream = BoxStream<'static, std::result::Result<ActionType, Status>>;
 type DoExchangeStream = BoxStream<'static, std::result::Result<FlightData, Status>>;
 
+/// Returns true if the read failed with `error` can be served by the read-only mirror.
+fn serve_from_mirror(error: &core::error::BoxPublicError) -> bool {
+    params::params().read_only_mirror.value && facade::mirror::is_database_unavailable(error)
+}
+
+/// Wraps a response served by the read-only mirror, flagging it with
+/// [`endpoint::mirror::MIRROR_HEADER`].
+fn mirror_response<T>(message: T) -> Response<T> {
+    warn!("database unavailable, response served by the read-only mirror");
+    let mut response = Response::new(message);
+    response.metadata_mut().insert(
+        endpoint::mirror::MIRROR_HEADER,
+        tonic::metadata::MetadataValue::from_static("true"),
+    );
+    response
+}
+
 impl MosaicodFlight {
     async fn impl_get_flight_info(
         &self,
@@ -233,12 +629,44 @@ impl MosaicodFlight {
         }
 
         let desc = request.into_inner();
-
-        let info = endpoint::get_flight_info(&self.context(), desc).await?;
+        let ctx = self.context();
+
+        let info = match endpoint::get_flight_info(&ctx, desc.clone()).await {
+            Err(e) if serve_from_mirror(&e) => {
+                match endpoint::mirror::get_flight_info(&ctx, desc).await? {
+                    Some(info) => return Ok(mirror_response(info)),
+                    None => Err(e)?,
+                }
+            }
+            result => result?,
+        };
 
         Ok(Response::new(info))
     }
 
+    async fn impl_get_schema(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<SchemaResult>> {
+        let auth_ctx = auth_context(&request)?;
+
+        if !auth_ctx.permissions().can_read() {
+            Err(core::Error::unauthorized(
+                "provided API key does not have READ permissions.".to_string(),
+            ))?;
+        }
+
+        let desc = request.into_inner();
+
+        let schema = endpoint::get_schema(&self.context(), desc).await?;
+
+        let options = arrow::ipc::writer::IpcWriteOptions::default();
+        let result = SchemaResult::try_from(SchemaAsIpc::new(&schema, &options))
+            .map_err(|e| core::Error::internal(Some(e.to_string())))?;
+
+        Ok(Response::new(result))
+    }
+
     async fn impl_list_flights(
         &self,
         request: Request<Criteria>,
@@ -269,16 +697,80 @@ impl MosaicodFlight {
             ))?;
         }
 
+        let permission = *auth_ctx.permissions();
         let ticket = request.into_inner();
-
-        let data_stream = endpoint::do_get(&self.context(), ticket).await?;
+        let ctx = self.context();
+
+        let (data_stream, mirror) = match endpoint::do_get(&ctx, ticket.clone(), permission).await {
+            Err(e) if serve_from_mirror(&e) => {
+                match endpoint::mirror::do_get(&ctx, ticket).await? {
+                    Some(data_stream) => (data_stream, true),
+                    None => Err(e)?,
+                }
+            }
+            result => (result?, false),
+        };
 
         // map data stream error (flight error) to a tonic one
-        let out_stream = data_stream
-            .inspect_err(|e| error!("flight encoding error: {}", e))
-            .map_err(|e| Status::internal(format!("flight encoding error: {}", e)));
+        let out_stream: DoGetStream = Box::pin(
+            data_stream
+                .inspect_err(|e| error!("flight encoding error: {}", e))
+                .map_err(|e| Status::internal(format!("flight encoding error: {}", e))),
+        );
+
+        if mirror {
+            return Ok(mirror_response(out_stream));
+        }
+        Ok(Response::new(out_stream))
+    }
+
+    /// Validates the bearer token sent in the payload of the handshake (or in the
+    /// `authorization` header if the payload is empty) and returns it to the client, that
+    /// presents it in the following requests.
+    async fn impl_handshake(
+        &self,
+        request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<HandshakeStream>> {
+        let headers = request.metadata().clone().into_headers();
+        let mut stream = request.into_inner();
+
+        let handshake = stream
+            .message()
+            .await
+            .map_err(core::Error::stream_error)?
+            .unwrap_or_default();
+
+        let token = if handshake.payload.is_empty() {
+            middleware::bearer_token(&headers)
+                .ok_or_else(core::Error::unauthenticated)?
+                .to_owned()
+        } else {
+            String::from_utf8(handshake.payload.to_vec()).map_err(|_| {
+                core::Error::bad_request("handshake payload is not a valid token".to_owned())
+            })?
+        };
+
+        let auth_ctx = self.authenticator().authenticate_token(&token).await?;
+        debug!(
+            "handshake completed by principal {}",
+            auth_ctx.principal().unwrap_or("<anonymous>")
+        );
+
+        let header = format!("Bearer {}", token)
+            .parse()
+            .map_err(|_| core::Error::bad_request("invalid token".to_owned()))?;
+
+        let response = HandshakeResponse {
+            protocol_version: handshake.protocol_version,
+            payload: token.into_bytes().into(),
+        };
 
-        Ok(Response::new(Box::pin(out_stream)))
+        let mut response = Response::new(
+            Box::pin(futures::stream::once(async { Ok(response) })) as HandshakeStream
+        );
+        response.metadata_mut().insert("authorization", header);
+
+        Ok(response)
     }
 
     async fn impl_do_put(
@@ -292,15 +784,36 @@ impl MosaicodFlight {
             ))?;
         }
 
-        let stream = request.into_inner();
-        let mut decoder = FlightDataDecoder::new(stream.map_err(Into::into));
+        let peer = request.remote_addr().map(|addr| addr.ip());
+        let started = std::time::Instant::now();
+
+        // Streams started while a replay capture is open are summarized as received
+        let summary = self
+            .replay_capture
+            .is_active()
+            .then(|| Arc::new(std::sync::Mutex::new(replay::PutSummary::default())));
+        let observed = summary.clone();
+        let stream = request.into_inner().inspect_ok(move |data| {
+            if let Some(summary) = &observed {
+                summary.lock().unwrap().observe(data);
+            }
+        });
 
         let ctx = endpoint::DoPutContext {
             inner: self.context(),
             concurrent_writes_semaphore: self.concurrent_writes_semaphore.clone(),
+            principal: auth_ctx.principal().map(str::to_owned),
+            ingest_metrics: self.ingest_metrics.clone(),
         };
 
-        endpoint::do_put(ctx, &mut decoder).await?;
+        let result = endpoint::do_put(ctx, stream.map_err(Into::into)).await;
+
+        if let Some(summary) = summary {
+            let summary = std::mem::take(&mut *summary.lock().unwrap());
+            self.replay_capture
+                .record_put(peer, summary, started.elapsed(), status_code(&result));
+        }
+        result?;
 
         Ok(Response::new(Box::pin(futures::stream::empty())))
     }
@@ -310,11 +823,45 @@ impl MosaicodFlight {
         request: Request<FlightAction>,
     ) -> Result<Response<DoActionStream>> {
         let auth_ctx = auth_context(&request)?;
+        let peer = request.remote_addr().map(|addr| addr.ip());
 
         let action = request.into_inner();
+        let started = std::time::Instant::now();
+
+        let result = self.dispatch_action(&auth_ctx, peer, &action).await;
+
+        if !replay::is_capture_action(&action.r#type) {
+            self.replay_capture.record_action(
+                peer,
+                &action.r#type,
+                &action.body,
+                started.elapsed(),
+                status_code(&result),
+            );
+        }
+
+        result
+    }
+
+    async fn dispatch_action(
+        &self,
+        auth_ctx: &middleware::AuthContext,
+        peer: Option<std::net::IpAddr>,
+        action: &FlightAction,
+    ) -> Result<Response<DoActionStream>> {
         let action = marshal::ActionRequest::try_new(action.r#type.as_str(), &action.body)?;
 
-        let response = endpoint::do_action(&self.context(), action, auth_ctx.permissions()).await?;
+        let ctx = endpoint::DoActionContext {
+            inner: self.context(),
+            request_metrics: self.request_metrics.clone(),
+            key_lockout: self.key_lockout.clone(),
+            ingest_metrics: self.ingest_metrics.clone(),
+            reindexer: self.reindexer.clone(),
+            replay_capture: self.replay_capture.clone(),
+            peer,
+        };
+
+        let response = endpoint::do_action(&ctx, &self.actions, action, auth_ctx).await?;
 
         let bytes = response.bytes()?;
 
@@ -338,11 +885,14 @@ impl FlightService for MosaicodFlight {
 
     async fn handshake(
         &self,
-        _request: Request<Streaming<HandshakeRequest>>,
+        request: Request<Streaming<HandshakeRequest>>,
     ) -> std::result::Result<Response<Self::HandshakeStream>, Status> {
-        Err(core::Error::unimplemented()
-            .to_public_error()
-            .log_to_status())
+        // Failed handshakes are not logged, as the other unauthenticated requests
+        let resp = self
+            .impl_handshake(request)
+            .await
+            .map_err(|e| e.to_status())?;
+        Ok(resp)
     }
 
     async fn list_flights(
@@ -372,11 +922,10 @@ impl FlightService for MosaicodFlight {
 
     async fn get_schema(
         &self,
-        _request: Request<FlightDescriptor>,
+        request: Request<FlightDescriptor>,
     ) -> std::result::Result<Response<SchemaResult>, Status> {
-        Err(core::Error::unimplemented()
-            .to_public_error()
-            .log_to_status())
+        let resp = self.impl_get_schema(request).await.log_to_status()?;
+        Ok(resp)
     }
 
     async fn do_get(
@@ -422,6 +971,14 @@ impl FlightService for MosaicodFlight {
     }
 }
 
+/// Returns the status code of the response to a request completed with `result`.
+fn status_code<T>(result: &Result<T>) -> tonic::Code {
+    match result {
+        Ok(_) => tonic::Code::Ok,
+        Err(e) => e.grpc_code(),
+    }
+}
+
 fn auth_context<T>(req: &Request<T>) -> Result<middleware::AuthContext> {
     req.extensions()
         .get::<middleware::AuthContext>()
@@ -0,0 +1,156 @@
+//! End-to-end ingest latency.
+//!
+//! The latency of a batch is the time elapsed from its reference time to the commit of the
+//! chunk storing it. The reference time is the send time declared by the client in the
+//! metadata of the batch message, see [`mosaicod_marshal::flight::do_put_batch_sent_at`], or
+//! else the latest data timestamp of the batch. Latencies are collected in a histogram for each
+//! topic, quantifying how far the stored data lags behind the producers.
+use mosaicod_core::types;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds (in milliseconds) of the latency histogram buckets. An additional bucket
+/// collects the batches committed later than the last bound.
+const LATENCY_BUCKETS_MS: [u64; 14] = [
+    10, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000, 60_000, 300_000, 900_000, 3_600_000,
+];
+
+/// Maximum number of topics whose latency is tracked, batches of further topics are
+/// ignored.
+const MAX_TRACKED_TOPICS: usize = 10_000;
+
+/// Ingest latency histogram of a topic.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TopicIngestLatency {
+    /// Number of batches in each latency bucket, see [`TopicIngestLatency::latency_bounds`]
+    pub latency: Vec<u64>,
+    /// Batches whose reference time is later than their commit, e.g. because of a clock
+    /// skew between the client and the server
+    pub skewed: u64,
+}
+
+impl TopicIngestLatency {
+    /// Upper bounds of the latency buckets, the last bucket has no upper bound
+    pub fn latency_bounds() -> impl Iterator<Item = Duration> {
+        LATENCY_BUCKETS_MS
+            .iter()
+            .map(|ms| Duration::from_millis(*ms))
+    }
+
+    /// Number of batches with a measured latency
+    pub fn count(&self) -> u64 {
+        self.latency.iter().sum()
+    }
+
+    /// Estimates the latency below which the `q` fraction of the batches was committed.
+    ///
+    /// The estimate is the upper bound of the histogram bucket containing the quantile,
+    /// batches later than the last bucket bound are reported as [`Duration::MAX`].
+    pub fn latency_quantile(&self, q: f64) -> Option<Duration> {
+        let total = self.count();
+        if total == 0 {
+            return None;
+        }
+
+        let target = (q.clamp(0.0, 1.0) * total as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.latency.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                return Some(
+                    LATENCY_BUCKETS_MS
+                        .get(bucket)
+                        .map(|ms| Duration::from_millis(*ms))
+                        .unwrap_or(Duration::MAX),
+                );
+            }
+        }
+
+        None
+    }
+
+    fn record(&mut self, latency: Option<Duration>) {
+        if self.latency.is_empty() {
+            self.latency = vec![0; LATENCY_BUCKETS_MS.len() + 1];
+        }
+
+        match latency {
+            Some(latency) => {
+                let ms = latency.as_millis() as u64;
+                let bucket = LATENCY_BUCKETS_MS.partition_point(|bound| *bound < ms);
+                self.latency[bucket] += 1;
+            }
+            None => self.skewed += 1,
+        }
+    }
+}
+
+/// Ingest latency of the batches received since startup, by topic.
+#[derive(Default)]
+pub struct IngestMetrics {
+    topics: Mutex<BTreeMap<String, TopicIngestLatency>>,
+}
+
+impl IngestMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a batch of `topic` committed at `committed_at`, whose reference time is
+    /// `reference`.
+    pub fn record(&self, topic: &str, reference: types::Timestamp, committed_at: types::Timestamp) {
+        let latency = committed_at
+            .as_i64()
+            .checked_sub(reference.as_i64())
+            .filter(|ns| *ns >= 0)
+            .map(|ns| Duration::from_nanos(ns as u64));
+
+        let mut topics = self.topics.lock().unwrap();
+        if topics.len() >= MAX_TRACKED_TOPICS && !topics.contains_key(topic) {
+            return;
+        }
+        topics.entry(topic.to_owned()).or_default().record(latency);
+    }
+
+    /// Returns the latency histograms of the topics, sorted by locator.
+    pub fn snapshot(&self) -> BTreeMap<String, TopicIngestLatency> {
+        self.topics.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_ingest_latency() {
+        let metrics = IngestMetrics::new();
+        let at = |ms: i64| types::Timestamp::from(ms * 1_000_000);
+
+        metrics.record("run/imu", at(1_000), at(1_005));
+        metrics.record("run/imu", at(1_000), at(1_200));
+        metrics.record("run/imu", at(1_000), at(1_200));
+        metrics.record("run/imu", at(1_000), at(900));
+        metrics.record("run/gps", at(0), at(10_000_000));
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.len(), 2);
+
+        let imu = &snapshot["run/imu"];
+        assert_eq!(imu.count(), 3);
+        assert_eq!(imu.skewed, 1);
+        assert_eq!(imu.latency[0], 1);
+        assert_eq!(imu.latency[3], 2);
+        assert_eq!(imu.latency_quantile(0.5), Some(Duration::from_millis(250)));
+        assert_eq!(imu.latency_quantile(0.0), Some(Duration::from_millis(10)));
+
+        let gps = &snapshot["run/gps"];
+        assert_eq!(gps.count(), 1);
+        assert_eq!(gps.latency_quantile(0.99), Some(Duration::MAX));
+        assert_eq!(
+            TopicIngestLatency::latency_bounds().count() + 1,
+            gps.latency.len()
+        );
+    }
+}
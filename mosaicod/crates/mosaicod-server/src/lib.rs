@@ -1,8 +1,28 @@
+mod catalog;
+mod compaction;
 mod core;
 mod endpoint;
-mod middleware;
+mod ingest;
+mod lockout;
+mod monitor;
+mod query_results;
+mod reindex;
+mod relocation;
+mod replay;
+mod scheduled_queries;
+mod sessions;
+mod shared_objects;
+mod tiering;
+mod trash;
 
+pub mod checks;
+pub mod event_sink;
 pub mod flight;
+pub mod middleware;
+pub mod relay;
 pub use core::Server;
 
 pub mod error;
+
+pub mod registry;
+pub use registry::{ActionHandler, ActionRegistry};
@@ -0,0 +1,263 @@
+//! Lockout of the peers guessing resource keys.
+//!
+//! Some actions are authorized by the knowledge of a resource key (e.g. the uuid of the
+//! session to finalize). Peers sending too many invalid keys within the lockout time are
+//! temporarily locked out, and every failure and lockout is reported as an audit entry.
+use crate::error::Result;
+use mosaicod_core::{self as core, error::ErrorKind, params};
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Target of the audit entries logged by the lockout.
+const AUDIT_TARGET: &str = "mosaicod::audit";
+
+#[derive(Default)]
+struct PeerFailures {
+    /// Times of the invalid keys sent by the peer within the lockout time, the oldest first
+    failed_at: VecDeque<Instant>,
+    locked_until: Option<Instant>,
+}
+
+impl PeerFailures {
+    /// Returns true if the peer has neither recent failures nor an active lockout.
+    fn is_expired(&self, now: Instant) -> bool {
+        self.failed_at.is_empty() && self.locked_until.is_none_or(|until| until <= now)
+    }
+}
+
+/// Counts the invalid resource keys sent by each peer over a sliding window as long as the
+/// lockout time, locking out the peers exceeding the allowed failures.
+pub struct KeyLockout {
+    max_failures: u32,
+    duration: Duration,
+    peers: Mutex<HashMap<IpAddr, PeerFailures>>,
+}
+
+impl KeyLockout {
+    /// Locks out for `duration` the peers sending `max_failures` invalid keys within
+    /// `duration`, a zero `max_failures` disables the lockout.
+    pub fn new(max_failures: u32, duration: Duration) -> Self {
+        Self {
+            max_failures,
+            duration,
+            peers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Creates a lockout configured with [`params::Params::key_lockout_max_failures`] and
+    /// [`params::Params::key_lockout_time`].
+    pub fn from_params() -> Self {
+        let params = params::params();
+        Self::new(
+            params.key_lockout_max_failures.value,
+            Duration::from_secs(params.key_lockout_time.value),
+        )
+    }
+
+    /// Runs `resolve`, that looks up a resource from a key sent by `peer` for `action`.
+    ///
+    /// Fails without running `resolve` if the peer is locked out. Lookups failing because
+    /// the key is malformed or does not exist are counted as failures of the peer, successful
+    /// ones do not clear them, so that valid keys cannot be interleaved with guesses.
+    pub async fn guard<T>(
+        &self,
+        peer: Option<IpAddr>,
+        action: &str,
+        resolve: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let Some(peer) = peer.filter(|_| self.max_failures > 0) else {
+            return resolve.await;
+        };
+
+        if let Some(remaining) = self.locked(peer) {
+            tracing::warn!(target: AUDIT_TARGET, %peer, action, "rejected request from locked out peer");
+            Err(core::Error::unauthorized(format!(
+                "too many invalid keys, retry in {} seconds",
+                remaining.as_secs().max(1)
+            )))?;
+        }
+
+        let result = resolve.await;
+
+        if let Err(err) = &result
+            && is_bad_key(err.as_ref().error().kind())
+        {
+            self.fail(peer, action);
+        }
+
+        result
+    }
+
+    /// Returns the peers currently locked out, with their remaining lockout time.
+    pub fn locked_peers(&self) -> HashMap<IpAddr, Duration> {
+        let now = Instant::now();
+        self.peers
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(peer, failures)| {
+                let remaining = failures.locked_until?.checked_duration_since(now)?;
+                Some((*peer, remaining))
+            })
+            .collect()
+    }
+
+    /// Returns the remaining lockout time of `peer`, if locked out.
+    fn locked(&self, peer: IpAddr) -> Option<Duration> {
+        let peers = self.peers.lock().unwrap();
+        let locked_until = peers.get(&peer)?.locked_until?;
+        locked_until.checked_duration_since(Instant::now())
+    }
+
+    fn fail(&self, peer: IpAddr, action: &str) {
+        let now = Instant::now();
+        let mut peers = self.peers.lock().unwrap();
+
+        // Failures older than the window are forgotten, along with the peers left without
+        // failures or lockout, so that the tracked peers stay bounded
+        let window_start = now.checked_sub(self.duration);
+        peers.retain(|_, failures| {
+            while failures
+                .failed_at
+                .front()
+                .is_some_and(|at| Some(*at) <= window_start)
+            {
+                failures.failed_at.pop_front();
+            }
+            !failures.is_expired(now)
+        });
+
+        let failures = peers.entry(peer).or_default();
+        failures.failed_at.push_back(now);
+        tracing::warn!(
+            target: AUDIT_TARGET,
+            %peer,
+            action,
+            failures = failures.failed_at.len(),
+            "invalid resource key"
+        );
+
+        if failures.failed_at.len() >= self.max_failures as usize {
+            // Failures after the lockout start a new count
+            failures.failed_at.clear();
+            failures.locked_until = Some(now + self.duration);
+            tracing::warn!(
+                target: AUDIT_TARGET,
+                %peer,
+                action,
+                lockout_secs = self.duration.as_secs(),
+                "peer locked out after too many invalid resource keys"
+            );
+        }
+    }
+}
+
+/// Returns true if the error is caused by an invalid resource key.
+fn is_bad_key(kind: &ErrorKind) -> bool {
+    matches!(kind, ErrorKind::BadUuid(_) | ErrorKind::NotFound(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer() -> Option<IpAddr> {
+        Some("10.0.0.1".parse().unwrap())
+    }
+
+    async fn bad_key(lockout: &KeyLockout, peer: Option<IpAddr>) -> Result<()> {
+        lockout
+            .guard(peer, "test", async {
+                Err(core::Error::bad_uuid("not a uuid".to_owned()))?
+            })
+            .await
+    }
+
+    async fn good_key(lockout: &KeyLockout, peer: Option<IpAddr>) -> Result<()> {
+        lockout.guard(peer, "test", async { Ok(()) }).await
+    }
+
+    fn is_unauthorized(err: &core::error::BoxPublicError) -> bool {
+        matches!(err.as_ref().error().kind(), ErrorKind::Unauthorized(_))
+    }
+
+    #[tokio::test]
+    async fn lockout() {
+        let lockout = KeyLockout::new(3, Duration::from_millis(200));
+
+        for _ in 0..3 {
+            bad_key(&lockout, peer()).await.unwrap_err();
+        }
+        let err = good_key(&lockout, peer()).await.unwrap_err();
+        assert!(is_unauthorized(&err));
+
+        assert_eq!(lockout.locked_peers().len(), 1);
+        assert!(lockout.locked_peers().contains_key(&peer().unwrap()));
+
+        // Other peers are not affected
+        good_key(&lockout, Some("10.0.0.2".parse().unwrap()))
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(lockout.locked_peers().is_empty());
+        good_key(&lockout, peer()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn lockout_valid_keys_between_guesses() {
+        let lockout = KeyLockout::new(3, Duration::from_secs(60));
+
+        // Valid keys do not clear the failures of the peer
+        for _ in 0..2 {
+            bad_key(&lockout, peer()).await.unwrap_err();
+            good_key(&lockout, peer()).await.unwrap();
+        }
+        let err = bad_key(&lockout, peer()).await.unwrap_err();
+        assert!(!is_unauthorized(&err));
+
+        let err = good_key(&lockout, peer()).await.unwrap_err();
+        assert!(is_unauthorized(&err));
+    }
+
+    #[tokio::test]
+    async fn lockout_sliding_window() {
+        let lockout = KeyLockout::new(3, Duration::from_millis(200));
+
+        // Failures older than the window are not counted
+        bad_key(&lockout, peer()).await.unwrap_err();
+        bad_key(&lockout, peer()).await.unwrap_err();
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        bad_key(&lockout, peer()).await.unwrap_err();
+        good_key(&lockout, peer()).await.unwrap();
+
+        // Peers with expired failures are evicted
+        bad_key(&lockout, Some("10.0.0.2".parse().unwrap()))
+            .await
+            .unwrap_err();
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        bad_key(&lockout, Some("10.0.0.3".parse().unwrap()))
+            .await
+            .unwrap_err();
+        assert_eq!(lockout.peers.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn lockout_disabled() {
+        let lockout = KeyLockout::new(0, Duration::from_secs(60));
+        for _ in 0..10 {
+            bad_key(&lockout, peer()).await.unwrap_err();
+        }
+        good_key(&lockout, peer()).await.unwrap();
+
+        // Requests without peer are not tracked
+        let lockout = KeyLockout::new(1, Duration::from_secs(60));
+        bad_key(&lockout, None).await.unwrap_err();
+        good_key(&lockout, None).await.unwrap();
+    }
+}
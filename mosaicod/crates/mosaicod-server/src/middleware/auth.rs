@@ -1,53 +1,292 @@
+use super::BoxFuture;
 use crate::error::{PublicErrorGrpcExt, Result};
-use mosaicod_core::{self as core, types};
+use mosaicod_core::{self as core, params, types};
 use mosaicod_facade as facade;
-use std::{
-    pin::Pin,
-    task::{Context, Poll},
-};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::task::{Context, Poll};
 use tower::{Layer, Service};
 
 // Skeleton from: https://github.com/hyperium/tonic/blob/master/examples/src/tower/server.rs
 
 const MOSAICO_API_KEY_TOKEN: &str = "mosaico-api-key-token";
 
+const AUTHORIZATION: &str = "authorization";
+
+const BEARER_PREFIX: &str = "Bearer ";
+
+/// Path of the Flight handshake, reached without credentials since it is used to validate them
+const HANDSHAKE_PATH: &str = "/arrow.flight.protocol.FlightService/Handshake";
+
 /// Context used to pass auth data
 #[derive(Clone)]
 pub struct AuthContext {
     permissions: types::auth::Permission,
+    default_sequence: Option<types::SequenceLocator>,
+    principal: Option<String>,
 }
 
 impl AuthContext {
+    /// Creates the context of a request authenticated as `principal`
+    pub fn new(principal: String, permissions: types::auth::Permission) -> Self {
+        Self {
+            permissions,
+            default_sequence: None,
+            principal: Some(principal),
+        }
+    }
+
+    /// Sets the sequence searched by the queries not filtering sequences
+    pub fn with_default_sequence(mut self, sequence: types::SequenceLocator) -> Self {
+        self.default_sequence = Some(sequence);
+        self
+    }
+
+    /// Context granting `permissions` to requests that are not authenticated
+    fn passthrough(permissions: types::auth::Permission) -> Self {
+        Self {
+            permissions,
+            default_sequence: None,
+            principal: None,
+        }
+    }
+
     pub fn permissions(&self) -> &types::auth::Permission {
         &self.permissions
     }
+
+    /// Sequence searched by the queries not filtering sequences
+    pub fn default_sequence(&self) -> Option<&types::SequenceLocator> {
+        self.default_sequence.as_ref()
+    }
+
+    /// Principal authenticated by the request: the name bound to the bearer token or the
+    /// fingerprint of the API key, [`None`] if authentication is disabled
+    pub fn principal(&self) -> Option<&str> {
+        self.principal.as_deref()
+    }
+}
+
+/// Validates the bearer tokens presented by the clients, either in the `authorization`
+/// header or in the payload of the Flight handshake.
+///
+/// Validators return [`None`] for the tokens they do not recognize, so that the next
+/// validator configured in the server is tried.
+pub trait TokenValidator: Send + Sync {
+    fn validate<'a>(&'a self, token: &'a str) -> BoxFuture<'a, Result<Option<AuthContext>>>;
 }
 
+/// Validator accepting a fixed set of tokens
+#[derive(Default)]
+pub struct StaticTokenValidator {
+    /// Principal and permissions bound to each token
+    tokens: HashMap<String, (String, types::auth::Permission)>,
+}
+
+impl StaticTokenValidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accepts `token`, authenticating the requests presenting it as `principal`
+    pub fn insert(
+        &mut self,
+        token: impl Into<String>,
+        principal: impl Into<String>,
+        permissions: types::auth::Permission,
+    ) {
+        self.tokens
+            .insert(token.into(), (principal.into(), permissions));
+    }
+
+    /// Builds the validator from the tokens configured in the parameters, [`None`] if no
+    /// token is configured
+    pub fn from_params() -> Result<Option<Self>> {
+        let validator = Self::parse(&params::params().auth_tokens.value)?;
+
+        if validator.tokens.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(validator))
+    }
+
+    /// Parses a comma separated list of `<principal>:<permission>:<token>` entries
+    fn parse(value: &str) -> Result<Self> {
+        let mut validator = Self::new();
+
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            // The token is not reported in the errors, since it is a secret
+            let mut parts = entry.splitn(3, ':');
+            let principal = parts.next().unwrap_or_default();
+
+            let invalid = |why: &str| {
+                core::Error::invalid_configuration(
+                    "MOSAICOD_AUTH_TOKENS".to_owned(),
+                    format!("invalid token of principal `{}`: {}", principal, why),
+                )
+            };
+
+            let permissions = parts
+                .next()
+                .ok_or_else(|| invalid("missing permission"))?
+                .parse()
+                .map_err(|_| invalid("unknown permission"))?;
+            let token = parts
+                .next()
+                .filter(|t| !t.is_empty())
+                .ok_or_else(|| invalid("missing token"))?;
+
+            if principal.is_empty() {
+                Err(invalid("missing principal"))?;
+            }
+
+            validator.insert(token, principal, permissions);
+        }
+
+        Ok(validator)
+    }
+}
+
+impl TokenValidator for StaticTokenValidator {
+    fn validate<'a>(&'a self, token: &'a str) -> BoxFuture<'a, Result<Option<AuthContext>>> {
+        let auth_ctx = self
+            .tokens
+            .get(token)
+            .map(|(principal, permissions)| AuthContext::new(principal.clone(), *permissions));
+
+        Box::pin(async move { Ok(auth_ctx) })
+    }
+}
+
+/// Authenticates the requests, using the configured token validators and API keys
 #[derive(Clone)]
-pub struct AuthLayer {
+pub struct Authenticator {
     context: facade::Context,
-
-    /// If permissions passthrough is enabled no auth check is performed
-    /// and a fake permission token with all permission is
-    /// generated for every request.
-    permissions_passthrough: Option<types::auth::Permission>,
+    validators: Vec<Arc<dyn TokenValidator>>,
+    api_keys: bool,
 }
 
-impl AuthLayer {
+impl Authenticator {
+    /// Creates an authenticator without validators, granting every permission to all the
+    /// requests until validators or API keys are enabled
     pub fn new(context: facade::Context) -> Self {
         Self {
             context,
-            permissions_passthrough: None,
+            validators: Vec::new(),
+            api_keys: false,
         }
     }
 
-    /// Enable auth passthrough. No internal check is
-    /// performed to validate api keys and a fake permissions
-    /// are generated to perform every action.
-    pub fn with_permission_passthrough(mut self, permissions: types::auth::Permission) -> Self {
-        self.permissions_passthrough = Some(permissions);
+    /// Accepts the API keys, both in their header and as bearer tokens
+    pub fn with_api_keys(mut self) -> Self {
+        self.api_keys = true;
+        self
+    }
+
+    /// Adds a validator, tried after the ones already added
+    pub fn with_token_validator(mut self, validator: Arc<dyn TokenValidator>) -> Self {
+        self.validators.push(validator);
         self
     }
+
+    /// Returns `true` if the requests are authenticated
+    pub fn is_enabled(&self) -> bool {
+        self.api_keys || !self.validators.is_empty()
+    }
+
+    /// Authenticates a request from the credentials in its headers
+    pub async fn authenticate(&self, headers: &http::HeaderMap) -> Result<AuthContext> {
+        if let Some(token) = bearer_token(headers) {
+            return self.authenticate_token(token).await;
+        }
+
+        if !self.is_enabled() {
+            return Ok(AuthContext::passthrough(types::auth::Permission::Manage));
+        }
+
+        let token = headers
+            .get(MOSAICO_API_KEY_TOKEN)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+
+        if !self.api_keys {
+            Err(core::Error::unauthorized(
+                "missing bearer token.".to_string(),
+            ))?;
+        }
+
+        if token.is_empty() {
+            Err(core::Error::missing_api_key())?
+        }
+
+        self.authenticate_api_key(token).await
+    }
+
+    /// Authenticates a bearer token, trying the validators before the API keys
+    pub async fn authenticate_token(&self, token: &str) -> Result<AuthContext> {
+        if !self.is_enabled() {
+            return Ok(AuthContext::passthrough(types::auth::Permission::Manage));
+        }
+
+        for validator in &self.validators {
+            if let Some(auth_ctx) = validator.validate(token).await? {
+                return Ok(auth_ctx);
+            }
+        }
+
+        if self.api_keys {
+            return self.authenticate_api_key(token).await;
+        }
+
+        Err(core::Error::unauthorized("invalid bearer token.".to_string()).into())
+    }
+
+    async fn authenticate_api_key(&self, token: &str) -> Result<AuthContext> {
+        let token: types::auth::Token = token.parse()?;
+
+        let handle = facade::auth::Handle::try_from_fingerprint(&self.context, token.fingerprint())
+            .await
+            .map_err(|e| match e.error().kind() {
+                core::error::ErrorKind::NotFound(_) => {
+                    core::Error::unauthorized("API key does not exist.".to_string())
+                }
+                _ => e.error(),
+            })?;
+
+        if handle.api_key().is_expired() {
+            Err(core::Error::unauthorized("API key is expired.".to_string()))?;
+        }
+
+        Ok(AuthContext {
+            permissions: handle.api_key().permission,
+            default_sequence: handle.api_key().default_sequence.clone(),
+            principal: Some(token.fingerprint().to_owned()),
+        })
+    }
+}
+
+/// Returns the token of the `authorization: Bearer <token>` header, if any
+pub fn bearer_token(headers: &http::HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix(BEARER_PREFIX))
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+}
+
+#[derive(Clone)]
+pub struct AuthLayer {
+    authenticator: Authenticator,
+}
+
+impl AuthLayer {
+    /// Creates a layer authenticating the requests with `authenticator`. If it has no
+    /// validator and API keys are disabled no auth check is performed and every request
+    /// is granted all the permissions.
+    pub fn new(authenticator: Authenticator) -> Self {
+        Self { authenticator }
+    }
 }
 
 impl<S> Layer<S> for AuthLayer {
@@ -56,8 +295,7 @@ impl<S> Layer<S> for AuthLayer {
     fn layer(&self, service: S) -> Self::Service {
         AuthMiddleware {
             inner: service,
-            context: self.context.clone(),
-            permissions_passthrough: self.permissions_passthrough,
+            authenticator: self.authenticator.clone(),
         }
     }
 }
@@ -65,12 +303,9 @@ impl<S> Layer<S> for AuthLayer {
 #[derive(Clone)]
 pub struct AuthMiddleware<S> {
     inner: S,
-    context: facade::Context,
-    permissions_passthrough: Option<types::auth::Permission>,
+    authenticator: Authenticator,
 }
 
-type BoxFuture<'a, T> = Pin<Box<dyn std::future::Future<Output = T> + Send + 'a>>;
-
 impl<S, ReqBody, ResBody> Service<http::Request<ReqBody>> for AuthMiddleware<S>
 where
     S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>> + Clone + Send + 'static,
@@ -91,66 +326,48 @@ where
         let clone = self.inner.clone();
         let mut inner = std::mem::replace(&mut self.inner, clone);
 
-        if let Some(permissions) = self.permissions_passthrough {
-            // Inject permissions to bypass api key management
-            Box::pin(async move {
-                req.extensions_mut().insert(AuthContext { permissions });
-
-                let response = inner.call(req).await?;
-
-                Ok(response)
-            })
-        } else {
-            let token = req
-                .headers()
-                .get(MOSAICO_API_KEY_TOKEN)
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or_default()
-                .to_string();
-
-            let context = self.context.clone();
-
-            Box::pin(async move {
-                let auth_ctx_result: Result<AuthContext> = async {
-                    if token.is_empty() {
-                        Err(core::Error::missing_api_key())?
-                    }
-
-                    let token: types::auth::Token = token.parse()?;
-
-                    let handle =
-                        facade::auth::Handle::try_from_fingerprint(&context, token.fingerprint())
-                            .await
-                            .map_err(|e| match e.error().kind() {
-                                core::error::ErrorKind::NotFound(_) => {
-                                    core::Error::unauthorized("API key does not exist.".to_string())
-                                }
-                                _ => e.error(),
-                            })?;
-
-                    if handle.api_key().is_expired() {
-                        Err(core::Error::unauthorized("API key is expired.".to_string()))?;
-                    }
-
-                    Ok(AuthContext {
-                        permissions: handle.api_key().permission,
-                    })
+        if req.uri().path() == HANDSHAKE_PATH {
+            // The handshake validates the credentials by itself
+            return Box::pin(async move { inner.call(req).await });
+        }
+
+        let authenticator = self.authenticator.clone();
+
+        Box::pin(async move {
+            match authenticator.authenticate(req.headers()).await {
+                Ok(auth_ctx) => {
+                    req.extensions_mut().insert(auth_ctx);
+                    let response = inner.call(req).await?;
+                    Ok(response)
                 }
-                .await;
-
-                match auth_ctx_result {
-                    Ok(auth_ctx) => {
-                        req.extensions_mut().insert(auth_ctx);
-                        let response = inner.call(req).await?;
-                        Ok(response)
-                    }
-                    Err(err) => {
-                        // Here we are calling .to_status() and not .log_to_status()
-                        // in order to avoid logging every unauthenticated request
-                        Ok(err.to_status().into_http())
-                    }
+                Err(err) => {
+                    // Here we are calling .to_status() and not .log_to_status()
+                    // in order to avoid logging every unauthenticated request
+                    Ok(err.to_status().into_http())
                 }
-            })
-        }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_static_tokens() {
+        let validator = StaticTokenValidator::parse(" ci:write:abc , admin:manage:x:y,").unwrap();
+
+        let (principal, permissions) = &validator.tokens["abc"];
+        assert_eq!(principal, "ci");
+        assert_eq!(*permissions, types::auth::Permission::Write);
+
+        // Tokens can contain colons
+        assert_eq!(validator.tokens["x:y"].0, "admin");
+
+        assert!(StaticTokenValidator::parse("ci:write").is_err());
+        assert!(StaticTokenValidator::parse("ci:owner:abc").is_err());
+        assert!(StaticTokenValidator::parse(":read:abc").is_err());
+        assert!(StaticTokenValidator::parse("").unwrap().tokens.is_empty());
     }
 }
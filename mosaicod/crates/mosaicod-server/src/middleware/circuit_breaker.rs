@@ -0,0 +1,357 @@
+use super::BoxFuture;
+use crate::error::{PublicErrorGrpcExt, UNAVAILABLE_DEPENDENCY};
+use mosaicod_core::{self as core, params};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tower::{Layer, Service};
+use tracing::{info, warn};
+
+/// Window over which the error rate of the dependencies is computed.
+const WINDOW: Duration = Duration::from_secs(10);
+
+/// Paths of the requests served by the read-only mirror while the database is unavailable.
+const MIRRORED_PATHS: [&str; 2] = [
+    "/arrow.flight.protocol.FlightService/GetFlightInfo",
+    "/arrow.flight.protocol.FlightService/DoGet",
+];
+
+/// Settings of the [`CircuitBreakerLayer`].
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// Ratio of the requests failed because of a dependency opening its circuit
+    pub error_rate: f64,
+    /// Minimum number of requests received in the window for a circuit to open
+    pub min_requests: u64,
+    /// Time an open circuit rejects requests before letting a probe through
+    pub open_time: Duration,
+}
+
+enum Circuit {
+    /// Requests are rejected until the provided instant
+    Open(Instant),
+    /// A probe request, sent at the provided instant, is checking if the dependency
+    /// recovered
+    HalfOpen(Instant),
+}
+
+struct State {
+    window_start: Instant,
+    requests: u64,
+    /// Requests failed in the window, by dependency
+    failures: BTreeMap<String, u64>,
+    /// Circuits not closed, by dependency
+    circuits: BTreeMap<String, Circuit>,
+}
+
+/// Circuits of the dependencies of the server.
+struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: Mutex<State>,
+}
+
+impl CircuitBreaker {
+    fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(State {
+                window_start: Instant::now(),
+                requests: 0,
+                failures: BTreeMap::new(),
+                circuits: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// Checks if a request can be served, returning the dependencies it probes.
+    ///
+    /// The request is rejected if a circuit is open or is already being probed.
+    fn admit(&self) -> Result<Vec<String>, core::Error> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        for (dependency, circuit) in &state.circuits {
+            match circuit {
+                Circuit::Open(until) if now < *until => {
+                    return Err(core::Error::unavailable_for(
+                        dependency.clone(),
+                        *until - now,
+                    ));
+                }
+                // A probe never answered (e.g. cancelled by the client) does not block the
+                // circuit forever
+                Circuit::HalfOpen(since) if now.duration_since(*since) < self.config.open_time => {
+                    return Err(core::Error::unavailable(dependency.clone()));
+                }
+                _ => {}
+            }
+        }
+
+        let mut probes = Vec::new();
+        for (dependency, circuit) in state.circuits.iter_mut() {
+            *circuit = Circuit::HalfOpen(now);
+            probes.push(dependency.clone());
+        }
+        Ok(probes)
+    }
+
+    /// Records the outcome of a request, `failed` is the dependency that caused its
+    /// failure, if any.
+    fn record(&self, probes: Vec<String>, failed: Option<String>) {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        for dependency in probes {
+            if failed.as_ref() == Some(&dependency) {
+                warn!("{} still unavailable, circuit opened again", dependency);
+                state
+                    .circuits
+                    .insert(dependency, Circuit::Open(now + self.config.open_time));
+            } else {
+                info!("{} recovered, circuit closed", dependency);
+                state.circuits.remove(&dependency);
+            }
+        }
+
+        if now.duration_since(state.window_start) >= WINDOW {
+            state.window_start = now;
+            state.requests = 0;
+            state.failures.clear();
+        }
+
+        state.requests += 1;
+
+        let Some(dependency) = failed else {
+            return;
+        };
+        if state.circuits.contains_key(&dependency) {
+            return;
+        }
+
+        let failures = state.failures.entry(dependency.clone()).or_default();
+        *failures += 1;
+        let error_rate = *failures as f64 / state.requests as f64;
+
+        if state.requests >= self.config.min_requests && error_rate >= self.config.error_rate {
+            warn!(
+                "{} failed {:.0}% of the last {} requests, circuit opened for {:?}",
+                dependency,
+                error_rate * 100.0,
+                state.requests,
+                self.config.open_time
+            );
+            state
+                .circuits
+                .insert(dependency, Circuit::Open(now + self.config.open_time));
+
+            // Failures collected before opening the circuit should not reopen it once closed
+            state.window_start = now;
+            state.requests = 0;
+            state.failures.clear();
+        }
+    }
+}
+
+/// Fails fast the requests while a dependency (e.g. the database or the store) is failing.
+///
+/// When the ratio of the requests failed because of a dependency exceeds the configured
+/// error rate, the circuit of the dependency opens and requests are rejected with an
+/// `UNAVAILABLE` status without reaching the endpoints. After the configured time a single
+/// probe request is let through, closing the circuit if it does not fail because of the
+/// dependency. Requests to the bypassed paths always reach the endpoints.
+#[derive(Clone)]
+pub struct CircuitBreakerLayer {
+    breaker: Option<Arc<CircuitBreaker>>,
+    bypass: Arc<[&'static str]>,
+}
+
+impl CircuitBreakerLayer {
+    /// Creates a layer with the provided settings, [`None`] disables the circuit breaker.
+    pub fn new(config: Option<CircuitBreakerConfig>) -> Self {
+        Self {
+            breaker: config.map(|config| Arc::new(CircuitBreaker::new(config))),
+            bypass: Arc::new([]),
+        }
+    }
+
+    /// Lets the requests to `paths` through, regardless of the state of the circuits.
+    pub fn with_bypass(mut self, paths: &[&'static str]) -> Self {
+        self.bypass = paths.into();
+        self
+    }
+
+    /// Creates a layer configured with [`params::Params::circuit_breaker_error_rate`],
+    /// [`params::Params::circuit_breaker_min_requests`] and
+    /// [`params::Params::circuit_breaker_open_time`].
+    ///
+    /// The requests served by the read-only mirror are let through if
+    /// [`params::Params::read_only_mirror`] is enabled.
+    pub fn from_params() -> Self {
+        let params = params::params();
+        let error_rate = params.circuit_breaker_error_rate.value;
+
+        let layer = Self::new((error_rate > 0.0).then(|| CircuitBreakerConfig {
+            error_rate,
+            min_requests: params.circuit_breaker_min_requests.value,
+            open_time: Duration::from_secs(params.circuit_breaker_open_time.value),
+        }));
+
+        if params.read_only_mirror.value {
+            layer.with_bypass(&MIRRORED_PATHS)
+        } else {
+            layer
+        }
+    }
+}
+
+impl<S> Layer<S> for CircuitBreakerLayer {
+    type Service = CircuitBreakerMiddleware<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        CircuitBreakerMiddleware {
+            inner: service,
+            breaker: self.breaker.clone(),
+            bypass: self.bypass.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CircuitBreakerMiddleware<S> {
+    inner: S,
+    breaker: Option<Arc<CircuitBreaker>>,
+    bypass: Arc<[&'static str]>,
+}
+
+impl<S, ReqBody, ResBody> Service<http::Request<ReqBody>> for CircuitBreakerMiddleware<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, std::result::Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<ReqBody>) -> Self::Future {
+        let Some(breaker) = self.breaker.clone() else {
+            return Box::pin(self.inner.call(req));
+        };
+
+        if self.bypass.contains(&req.uri().path()) {
+            return Box::pin(self.inner.call(req));
+        }
+
+        let probes = match breaker.admit() {
+            Ok(probes) => probes,
+            Err(err) => {
+                return Box::pin(async move { Ok(err.to_public_error().to_status().into_http()) });
+            }
+        };
+
+        let response = self.inner.call(req);
+
+        Box::pin(async move {
+            let response = response.await;
+
+            let failed = response.as_ref().ok().and_then(unavailable_dependency);
+            breaker.record(probes, failed);
+
+            response
+        })
+    }
+}
+
+/// Returns the dependency that caused the failure of the response, if any.
+fn unavailable_dependency<B>(response: &http::Response<B>) -> Option<String> {
+    let status = tonic::Status::from_header_map(response.headers())?;
+    if status.code() != tonic::Code::Unavailable {
+        return None;
+    }
+
+    response
+        .headers()
+        .get(UNAVAILABLE_DEPENDENCY)
+        .and_then(|dependency| dependency.to_str().ok())
+        .map(str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(open_time: Duration) -> CircuitBreaker {
+        CircuitBreaker::new(CircuitBreakerConfig {
+            error_rate: 0.5,
+            min_requests: 4,
+            open_time,
+        })
+    }
+
+    #[test]
+    fn circuit_opens_and_recovers() {
+        let breaker = breaker(Duration::from_millis(100));
+
+        // Failures below the minimum number of requests do not open the circuit
+        for _ in 0..3 {
+            assert!(breaker.admit().unwrap().is_empty());
+            breaker.record(vec![], Some("database".to_owned()));
+        }
+        assert!(breaker.admit().is_ok());
+        breaker.record(vec![], Some("database".to_owned()));
+
+        let err = breaker.admit().unwrap_err();
+        assert!(
+            matches!(err.kind(), core::error::ErrorKind::Unavailable(dep, Some(_)) if dep == "database")
+        );
+
+        // A single probe is let through once the open time elapsed
+        std::thread::sleep(Duration::from_millis(150));
+        let probes = breaker.admit().unwrap();
+        assert_eq!(probes, vec!["database".to_owned()]);
+        assert!(breaker.admit().is_err());
+
+        // A failed probe opens the circuit again
+        breaker.record(probes, Some("database".to_owned()));
+        assert!(breaker.admit().is_err());
+
+        // A successful probe closes it
+        std::thread::sleep(Duration::from_millis(150));
+        let probes = breaker.admit().unwrap();
+        breaker.record(probes, None);
+        assert!(breaker.admit().unwrap().is_empty());
+    }
+
+    #[test]
+    fn error_rate_below_threshold() {
+        let breaker = breaker(Duration::from_secs(30));
+
+        for i in 0..20 {
+            assert!(breaker.admit().is_ok());
+            let failed = (i % 3 == 2).then(|| "store".to_owned());
+            breaker.record(vec![], failed);
+        }
+        assert!(breaker.admit().is_ok());
+    }
+
+    #[test]
+    fn dependency_of_response() {
+        let status = core::Error::unavailable("store".to_owned())
+            .to_public_error()
+            .to_status();
+        let response = status.into_http::<()>();
+        assert_eq!(unavailable_dependency(&response), Some("store".to_owned()));
+
+        let status = core::Error::internal(None).to_public_error().to_status();
+        let response = status.into_http::<()>();
+        assert_eq!(unavailable_dependency(&response), None);
+    }
+}
@@ -0,0 +1,154 @@
+use super::BoxFuture;
+use crate::error::PublicErrorGrpcExt;
+use mosaicod_core::{self as core, params};
+use std::{
+    task::{Context, Poll},
+    time::Duration,
+};
+use tower::{Layer, Service};
+
+/// Header used by gRPC clients to send their deadline.
+const GRPC_TIMEOUT: &str = "grpc-timeout";
+
+/// Aborts the requests not answered before their deadline.
+///
+/// The deadline is the shortest between the `grpc-timeout` requested by the client and the
+/// configured timeout. Only the time needed to start the response is limited, streaming
+/// the response body (e.g. the data returned by `do_get`) can take longer.
+#[derive(Clone)]
+pub struct DeadlineLayer {
+    timeout: Option<Duration>,
+}
+
+impl DeadlineLayer {
+    /// Limits every request to `timeout`, [`None`] applies only the deadlines requested by
+    /// clients.
+    pub fn new(timeout: Option<Duration>) -> Self {
+        Self { timeout }
+    }
+
+    /// Creates a layer configured with [`params::Params::request_timeout`].
+    pub fn from_params() -> Self {
+        let timeout = params::params().request_timeout.value;
+        Self::new((timeout > 0).then(|| Duration::from_secs(timeout)))
+    }
+}
+
+impl<S> Layer<S> for DeadlineLayer {
+    type Service = DeadlineMiddleware<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        DeadlineMiddleware {
+            inner: service,
+            timeout: self.timeout,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct DeadlineMiddleware<S> {
+    inner: S,
+    timeout: Option<Duration>,
+}
+
+impl<S, ReqBody, ResBody> Service<http::Request<ReqBody>> for DeadlineMiddleware<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, std::result::Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<ReqBody>) -> Self::Future {
+        let requested = req
+            .headers()
+            .get(GRPC_TIMEOUT)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_grpc_timeout);
+
+        let timeout = match (requested, self.timeout) {
+            (Some(requested), Some(timeout)) => Some(requested.min(timeout)),
+            (requested, timeout) => requested.or(timeout),
+        };
+
+        let response = self.inner.call(req);
+
+        let Some(timeout) = timeout else {
+            return Box::pin(response);
+        };
+
+        Box::pin(async move {
+            match tokio::time::timeout(timeout, response).await {
+                Ok(response) => response,
+                Err(_) => {
+                    let err = core::Error::deadline_exceeded(format!(
+                        "request not completed within {} ms",
+                        timeout.as_millis()
+                    ));
+                    Ok(err.to_public_error().to_status().into_http())
+                }
+            }
+        })
+    }
+}
+
+/// Parses the value of the `grpc-timeout` header, made of at most 8 digits followed by the
+/// unit (`H`, `M`, `S`, `m`, `u` or `n`).
+fn parse_grpc_timeout(value: &str) -> Option<Duration> {
+    if value.len() < 2 || value.len() > 9 {
+        return None;
+    }
+
+    let (amount, unit) = value.split_at(value.len() - 1);
+    let amount: u64 = amount.parse().ok()?;
+
+    match unit {
+        "H" => Some(Duration::from_secs(amount * 60 * 60)),
+        "M" => Some(Duration::from_secs(amount * 60)),
+        "S" => Some(Duration::from_secs(amount)),
+        "m" => Some(Duration::from_millis(amount)),
+        "u" => Some(Duration::from_micros(amount)),
+        "n" => Some(Duration::from_nanos(amount)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grpc_timeout() {
+        assert_eq!(parse_grpc_timeout("2S"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_grpc_timeout("150m"), Some(Duration::from_millis(150)));
+        assert_eq!(parse_grpc_timeout("1H"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_grpc_timeout("10"), None);
+        assert_eq!(parse_grpc_timeout("S"), None);
+        assert_eq!(parse_grpc_timeout("123456789S"), None);
+    }
+
+    #[tokio::test]
+    async fn deadline_exceeded() {
+        let service = tower::service_fn(|_req: http::Request<()>| async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok::<_, std::convert::Infallible>(http::Response::new(()))
+        });
+        let service = DeadlineLayer::new(Some(Duration::from_secs(5))).layer(service);
+
+        let req = http::Request::builder()
+            .header(GRPC_TIMEOUT, "10m")
+            .body(())
+            .unwrap();
+        let response = tower::ServiceExt::oneshot(service, req).await.unwrap();
+
+        let status = tonic::Status::from_header_map(response.headers()).unwrap();
+        assert_eq!(status.code(), tonic::Code::DeadlineExceeded);
+    }
+}
@@ -0,0 +1,74 @@
+use super::BoxFuture;
+use std::{
+    task::{Context, Poll},
+    time::Instant,
+};
+use tower::{Layer, Service};
+use tracing::{debug, warn};
+
+/// Logs the gRPC method, status and latency of every request.
+///
+/// Successful requests are logged at debug level, failed ones as warnings.
+#[derive(Clone, Default)]
+pub struct LoggingLayer;
+
+impl LoggingLayer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for LoggingLayer {
+    type Service = LoggingMiddleware<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        LoggingMiddleware { inner: service }
+    }
+}
+
+#[derive(Clone)]
+pub struct LoggingMiddleware<S> {
+    inner: S,
+}
+
+impl<S, ResBody> Service<super::HttpRequest> for LoggingMiddleware<S>
+where
+    S: Service<super::HttpRequest, Response = http::Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, std::result::Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: super::HttpRequest) -> Self::Future {
+        let method = super::method_name(&req);
+        let start = Instant::now();
+
+        let response = self.inner.call(req);
+
+        Box::pin(async move {
+            let response = response.await;
+            let elapsed = start.elapsed();
+
+            match &response {
+                Ok(r) => match tonic::Status::from_header_map(r.headers()) {
+                    Some(status) if status.code() != tonic::Code::Ok => warn!(
+                        "{} failed in {:?}: {:?} {}",
+                        method,
+                        elapsed,
+                        status.code(),
+                        status.message()
+                    ),
+                    _ => debug!("{} answered in {:?}", method, elapsed),
+                },
+                Err(_) => warn!("{} failed in {:?}", method, elapsed),
+            }
+
+            response
+        })
+    }
+}
@@ -0,0 +1,233 @@
+use super::BoxFuture;
+use mosaicod_core::types;
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tower::{Layer, Service};
+
+/// Maximum number of peers whose failures are tracked, failures of further peers are only
+/// counted in the totals.
+const MAX_TRACKED_PEERS: usize = 10_000;
+
+/// Counters of the requests received by a gRPC method.
+#[derive(Debug, Clone, Default)]
+pub struct MethodMetrics {
+    pub requests: u64,
+    /// Requests answered with an error status
+    pub failed: u64,
+    /// Time spent answering the requests, up to the start of the responses
+    pub total_latency: Duration,
+}
+
+/// Counters of the requests rejected because of the credentials or of the resources
+/// requested, that may reveal attempts to probe API keys, resource keys or locators.
+#[derive(Debug, Clone, Default)]
+pub struct SecurityFailures {
+    /// Requests with a missing or invalid API key
+    pub unauthenticated: u64,
+    /// Requests without enough permissions or with an invalid resource key
+    pub permission_denied: u64,
+    /// Requests for resources not found
+    pub not_found: u64,
+    pub last_failure: Option<types::Timestamp>,
+}
+
+impl SecurityFailures {
+    /// Counts the failure reported by `code`, returns false if it is not a security failure.
+    fn record(&mut self, code: tonic::Code) -> bool {
+        match code {
+            tonic::Code::Unauthenticated => self.unauthenticated += 1,
+            tonic::Code::PermissionDenied => self.permission_denied += 1,
+            tonic::Code::NotFound => self.not_found += 1,
+            _ => return false,
+        }
+        self.last_failure = Some(types::Timestamp::now());
+        true
+    }
+}
+
+/// Counters of the requests received by the server, by gRPC method, and of the security
+/// failures, by peer.
+#[derive(Default)]
+pub struct RequestMetrics {
+    methods: Mutex<BTreeMap<String, MethodMetrics>>,
+    security: Mutex<(SecurityFailures, HashMap<IpAddr, SecurityFailures>)>,
+}
+
+impl RequestMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the counters of the methods requested since startup, sorted by name.
+    pub fn snapshot(&self) -> BTreeMap<String, MethodMetrics> {
+        self.methods.lock().unwrap().clone()
+    }
+
+    /// Returns the security failures since startup, in total and by peer.
+    pub fn security_snapshot(&self) -> (SecurityFailures, HashMap<IpAddr, SecurityFailures>) {
+        self.security.lock().unwrap().clone()
+    }
+
+    fn record(
+        &self,
+        method: String,
+        code: Option<tonic::Code>,
+        latency: Duration,
+        peer: Option<IpAddr>,
+    ) {
+        let mut methods = self.methods.lock().unwrap();
+        let metrics = methods.entry(method).or_default();
+        metrics.requests += 1;
+        metrics.failed += code.is_some_and(|code| code != tonic::Code::Ok) as u64;
+        metrics.total_latency += latency;
+        drop(methods);
+
+        if let Some(code) = code {
+            self.record_security(code, peer);
+        }
+    }
+
+    fn record_security(&self, code: tonic::Code, peer: Option<IpAddr>) {
+        let mut security = self.security.lock().unwrap();
+        let (total, peers) = &mut *security;
+
+        if !total.record(code) {
+            return;
+        }
+
+        let tracked = |peer: &IpAddr| peers.len() < MAX_TRACKED_PEERS || peers.contains_key(peer);
+        if let Some(peer) = peer.filter(tracked) {
+            peers.entry(peer).or_default().record(code);
+        }
+    }
+}
+
+/// Records the [`RequestMetrics`] of the requests.
+#[derive(Clone)]
+pub struct MetricsLayer {
+    metrics: Arc<RequestMetrics>,
+}
+
+impl MetricsLayer {
+    pub fn new(metrics: Arc<RequestMetrics>) -> Self {
+        Self { metrics }
+    }
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsMiddleware<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        MetricsMiddleware {
+            inner: service,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MetricsMiddleware<S> {
+    inner: S,
+    metrics: Arc<RequestMetrics>,
+}
+
+impl<S, ResBody> Service<super::HttpRequest> for MetricsMiddleware<S>
+where
+    S: Service<super::HttpRequest, Response = http::Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, std::result::Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: super::HttpRequest) -> Self::Future {
+        let method = super::method_name(&req);
+        let peer = super::peer_addr(&req);
+        let metrics = self.metrics.clone();
+        let start = Instant::now();
+
+        let response = self.inner.call(req);
+
+        Box::pin(async move {
+            let response = response.await;
+
+            let code = match &response {
+                Ok(response) => status_code(response),
+                Err(_) => Some(tonic::Code::Unknown),
+            };
+            metrics.record(method, code, start.elapsed(), peer);
+
+            response
+        })
+    }
+}
+
+/// Returns the code of the status carried by the response headers, if any.
+///
+/// Statuses sent after the response body (e.g. errors raised while streaming) are not
+/// available to the layers and are not detected.
+fn status_code<B>(response: &http::Response<B>) -> Option<tonic::Code> {
+    tonic::Status::from_header_map(response.headers()).map(|status| status.code())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_metrics() {
+        let metrics = RequestMetrics::new();
+        metrics.record("DoAction".to_owned(), None, Duration::from_millis(2), None);
+        metrics.record(
+            "DoAction".to_owned(),
+            Some(tonic::Code::Internal),
+            Duration::from_millis(4),
+            None,
+        );
+        metrics.record("DoGet".to_owned(), None, Duration::from_millis(1), None);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot["DoAction"].requests, 2);
+        assert_eq!(snapshot["DoAction"].failed, 1);
+        assert_eq!(snapshot["DoAction"].total_latency, Duration::from_millis(6));
+        assert_eq!(snapshot["DoGet"].failed, 0);
+    }
+
+    #[test]
+    fn record_security_failures() {
+        let metrics = RequestMetrics::new();
+        let peer_1: IpAddr = "10.0.0.1".parse().unwrap();
+        let peer_2: IpAddr = "10.0.0.2".parse().unwrap();
+
+        let record = |code, peer| {
+            metrics.record("DoAction".to_owned(), Some(code), Duration::ZERO, peer);
+        };
+        record(tonic::Code::Unauthenticated, Some(peer_1));
+        record(tonic::Code::NotFound, Some(peer_1));
+        record(tonic::Code::NotFound, Some(peer_1));
+        record(tonic::Code::PermissionDenied, Some(peer_2));
+        record(tonic::Code::Internal, Some(peer_2));
+        record(tonic::Code::NotFound, None);
+
+        let (total, peers) = metrics.security_snapshot();
+        assert_eq!(total.unauthenticated, 1);
+        assert_eq!(total.permission_denied, 1);
+        assert_eq!(total.not_found, 3);
+
+        assert_eq!(peers.len(), 2);
+        assert_eq!(peers[&peer_1].unauthenticated, 1);
+        assert_eq!(peers[&peer_1].not_found, 2);
+        assert_eq!(peers[&peer_2].permission_denied, 1);
+        assert!(peers[&peer_2].last_failure.is_some());
+    }
+}
@@ -1,2 +1,170 @@
+//! Tower layers wrapping the Flight service.
+//!
+//! Cross-cutting concerns (tracing, logging, metrics, deadlines, rate limiting, circuit
+//! breaking and authentication) are handled by layers before requests reach the endpoints.
+//! The layers are collected in a [`Stack`], that embedders can extend with their own layers
+//! through [`crate::flight::Config::layer`].
 mod auth;
+mod circuit_breaker;
+mod deadline;
+mod logging;
+mod metrics;
+mod rate_limit;
+mod trace;
+
 pub use auth::*;
+pub use circuit_breaker::*;
+pub use deadline::*;
+pub use logging::*;
+pub use metrics::*;
+pub use rate_limit::*;
+pub use trace::*;
+
+use std::{convert::Infallible, pin::Pin};
+use tower::{
+    Layer, Service,
+    util::{BoxCloneSyncService, BoxCloneSyncServiceLayer},
+};
+
+/// Request handled by the layers.
+pub type HttpRequest = http::Request<tonic::body::Body>;
+
+/// Response returned by the layers, failures are reported as gRPC statuses.
+pub type HttpResponse = http::Response<tonic::body::Body>;
+
+/// Type-erased service wrapped by the layers of a [`Stack`].
+pub type BoxService = BoxCloneSyncService<HttpRequest, HttpResponse, Infallible>;
+
+type BoxLayer = BoxCloneSyncServiceLayer<BoxService, HttpRequest, HttpResponse, Infallible>;
+
+/// Future returned by the layers and by the [`TokenValidator`]s.
+pub type BoxFuture<'a, T> = Pin<Box<dyn std::future::Future<Output = T> + Send + 'a>>;
+
+/// Ordered collection of layers wrapping a service.
+///
+/// The first layer pushed is the outermost one, receiving requests before the others.
+#[derive(Clone, Default)]
+pub struct Stack {
+    layers: Vec<BoxLayer>,
+}
+
+impl Stack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `layer` inside the layers already in the stack, so it receives requests after
+    /// them.
+    pub fn push<L>(&mut self, layer: L)
+    where
+        L: Layer<BoxService> + Send + Sync + 'static,
+        L::Service: Service<HttpRequest, Response = HttpResponse, Error = Infallible>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        <L::Service as Service<HttpRequest>>::Future: Send + 'static,
+    {
+        self.layers.push(BoxLayer::new(layer));
+    }
+
+    /// Adds the layers of `other` inside the layers already in the stack.
+    pub fn extend(&mut self, other: Stack) {
+        self.layers.extend(other.layers);
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+}
+
+impl<S> Layer<S> for Stack
+where
+    S: Service<HttpRequest, Response = HttpResponse, Error = Infallible>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    S::Future: Send + 'static,
+{
+    type Service = BoxService;
+
+    fn layer(&self, service: S) -> Self::Service {
+        self.layers
+            .iter()
+            .rev()
+            .fold(BoxService::new(service), |service, layer| {
+                layer.layer(service)
+            })
+    }
+}
+
+/// Returns the name of the gRPC method requested, i.e. the last segment of the path
+/// (e.g. `DoAction`).
+fn method_name(req: &HttpRequest) -> String {
+    let path = req.uri().path();
+    path.rsplit('/').next().unwrap_or(path).to_owned()
+}
+
+/// Returns the address of the peer that sent the request, if known.
+fn peer_addr(req: &HttpRequest) -> Option<std::net::IpAddr> {
+    use tonic::transport::server::{TcpConnectInfo, TlsConnectInfo};
+
+    let extensions = req.extensions();
+    extensions
+        .get::<TcpConnectInfo>()
+        .or_else(|| {
+            extensions
+                .get::<TlsConnectInfo<TcpConnectInfo>>()
+                .map(TlsConnectInfo::get_ref)
+        })
+        .and_then(TcpConnectInfo::remote_addr)
+        .map(|addr| addr.ip())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn trace(
+        name: &'static str,
+        trace: &Arc<Mutex<Vec<&'static str>>>,
+    ) -> tower::util::MapRequestLayer<
+        impl Fn(HttpRequest) -> HttpRequest + Clone + Send + Sync + 'static,
+    > {
+        let trace = trace.clone();
+        tower::util::MapRequestLayer::new(move |req| {
+            trace.lock().unwrap().push(name);
+            req
+        })
+    }
+
+    #[tokio::test]
+    async fn stack_order() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+
+        let mut stack = Stack::new();
+        stack.push(trace("outer", &calls));
+        stack.push(trace("inner", &calls));
+        assert_eq!(stack.len(), 2);
+
+        let service = tower::service_fn(|_req: HttpRequest| async {
+            Ok::<_, Infallible>(HttpResponse::default())
+        });
+
+        let mut service = stack.layer(service);
+        let req = http::Request::builder()
+            .uri("/arrow.flight.protocol.FlightService/DoAction")
+            .body(tonic::body::Body::empty())
+            .unwrap();
+        assert_eq!(method_name(&req), "DoAction");
+
+        tower::ServiceExt::oneshot(&mut service, req).await.unwrap();
+        assert_eq!(*calls.lock().unwrap(), vec!["outer", "inner"]);
+    }
+}
@@ -0,0 +1,126 @@
+use super::BoxFuture;
+use crate::error::PublicErrorGrpcExt;
+use mosaicod_core::{self as core, params};
+use std::{
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Instant,
+};
+use tower::{Layer, Service};
+
+/// Token bucket refilled at a constant rate, allowing bursts up to one second of requests.
+struct TokenBucket {
+    rate: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    fn new(rate: u64) -> Self {
+        Self {
+            rate: rate as f64,
+            state: Mutex::new((rate as f64, Instant::now())),
+        }
+    }
+
+    /// Takes a token from the bucket, returns `false` if the bucket is empty.
+    fn try_acquire(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let (tokens, last_refill) = &mut *state;
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(*last_refill).as_secs_f64();
+        *tokens = (*tokens + elapsed * self.rate).min(self.rate);
+        *last_refill = now;
+
+        if *tokens < 1.0 {
+            return false;
+        }
+        *tokens -= 1.0;
+        true
+    }
+}
+
+/// Rejects the requests exceeding the configured rate.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    bucket: Option<Arc<TokenBucket>>,
+}
+
+impl RateLimitLayer {
+    /// Accepts up to `rate` requests per second, a zero `rate` disables the limit.
+    pub fn new(rate: u64) -> Self {
+        Self {
+            bucket: (rate > 0).then(|| Arc::new(TokenBucket::new(rate))),
+        }
+    }
+
+    /// Creates a layer configured with [`params::Params::max_requests_per_second`].
+    pub fn from_params() -> Self {
+        Self::new(params::params().max_requests_per_second.value)
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitMiddleware<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        RateLimitMiddleware {
+            inner: service,
+            bucket: self.bucket.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitMiddleware<S> {
+    inner: S,
+    bucket: Option<Arc<TokenBucket>>,
+}
+
+impl<S, ReqBody, ResBody> Service<http::Request<ReqBody>> for RateLimitMiddleware<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, std::result::Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<ReqBody>) -> Self::Future {
+        let limited = self
+            .bucket
+            .as_ref()
+            .is_some_and(|bucket| !bucket.try_acquire());
+
+        if limited {
+            let err = core::Error::overloaded("request rate limit exceeded".to_owned());
+            return Box::pin(async move { Ok(err.to_public_error().to_status().into_http()) });
+        }
+
+        Box::pin(self.inner.call(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_bucket() {
+        let bucket = TokenBucket::new(3);
+        assert!(bucket.try_acquire());
+        assert!(bucket.try_acquire());
+        assert!(bucket.try_acquire());
+        assert!(!bucket.try_acquire());
+
+        std::thread::sleep(std::time::Duration::from_millis(400));
+        assert!(bucket.try_acquire());
+        assert!(!bucket.try_acquire());
+    }
+}
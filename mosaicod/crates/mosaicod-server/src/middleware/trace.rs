@@ -0,0 +1,111 @@
+use super::BoxFuture;
+use opentelemetry::propagation::Extractor;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Opens the span of every request, parent of the spans of the endpoints, the database
+/// queries and the store operations performed to serve it.
+///
+/// If the request carries a W3C `traceparent` in its metadata the span continues the
+/// trace of the caller.
+#[derive(Clone, Default)]
+pub struct TraceLayer;
+
+impl TraceLayer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for TraceLayer {
+    type Service = TraceMiddleware<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        TraceMiddleware { inner: service }
+    }
+}
+
+#[derive(Clone)]
+pub struct TraceMiddleware<S> {
+    inner: S,
+}
+
+impl<S, ResBody> Service<super::HttpRequest> for TraceMiddleware<S>
+where
+    S: Service<super::HttpRequest, Response = http::Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, std::result::Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: super::HttpRequest) -> Self::Future {
+        let span = tracing::info_span!(
+            "grpc",
+            otel.kind = "server",
+            rpc.system = "grpc",
+            rpc.method = %super::method_name(&req),
+        );
+
+        let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.extract(&MetadataExtractor(req.headers()))
+        });
+        // Fails only if no OpenTelemetry layer is installed, then there is nothing to link
+        let _ = span.set_parent(parent);
+
+        let response = span.in_scope(|| self.inner.call(req));
+
+        Box::pin(response.instrument(span))
+    }
+}
+
+/// Reads the trace context propagated in the gRPC metadata
+struct MetadataExtractor<'a>(&'a http::HeaderMap);
+
+impl Extractor for MetadataExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::TraceContextExt;
+
+    #[test]
+    fn extract_traceparent() {
+        let mut headers = http::HeaderMap::new();
+        headers.insert(
+            "traceparent",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"
+                .parse()
+                .unwrap(),
+        );
+
+        let propagator = opentelemetry_sdk::propagation::TraceContextPropagator::new();
+        let context = opentelemetry::propagation::TextMapPropagator::extract(
+            &propagator,
+            &MetadataExtractor(&headers),
+        );
+
+        let span = context.span();
+        let span_context = span.span_context();
+        assert!(span_context.is_remote());
+        assert_eq!(
+            span_context.trace_id().to_string(),
+            "0af7651916cd43dd8448eb211c80319c"
+        );
+        assert_eq!(span_context.span_id().to_string(), "b7ad6b7169203331");
+    }
+}
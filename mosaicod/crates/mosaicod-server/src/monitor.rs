@@ -0,0 +1,47 @@
+//! Background monitoring of the store backend.
+//!
+//! When store objectives are configured, a task periodically checks the backend metrics
+//! and reports every exceeded objective as a warning.
+use mosaicod_core::params;
+use mosaicod_store as store;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Minimum number of requests in a check interval required to evaluate the objectives
+const MIN_REQUESTS: u64 = 20;
+
+/// Spawns the task monitoring the objectives of the store backend, returns `None` if no
+/// objective is configured.
+pub(crate) fn spawn_store_monitor(store: store::StoreRef) -> Option<tokio::task::JoinHandle<()>> {
+    let params = params::params();
+
+    let config = store::SloConfig {
+        max_error_rate: Some(params.store_slo_max_error_rate.value).filter(|rate| *rate > 0.0),
+        max_p99_latency: Some(params.store_slo_max_p99_latency.value)
+            .filter(|ms| *ms > 0)
+            .map(Duration::from_millis),
+        min_requests: MIN_REQUESTS,
+    };
+
+    if !config.is_enabled() {
+        return None;
+    }
+
+    let interval = Duration::from_secs(params.store_slo_check_interval.value.max(1));
+    debug!("store objectives {:?} checked every {:?}", config, interval);
+
+    let monitor = store::SloMonitor::new(store.metrics().clone(), config);
+
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick completes immediately
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            for violation in monitor.check() {
+                warn!("{}", violation);
+            }
+        }
+    }))
+}
@@ -0,0 +1,30 @@
+//! Background purge of expired query results.
+//!
+//! The rows of paginated queries are stored until fetched by the client. A task
+//! periodically discards the results abandoned by clients, i.e. not fetched before their
+//! expiration.
+use mosaicod_core::params;
+use mosaicod_facade as facade;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Spawns the task purging the expired query results.
+pub(crate) fn spawn_query_result_purger(context: facade::Context) -> tokio::task::JoinHandle<()> {
+    let interval = Duration::from_secs(params::params().query_result_ttl.value.max(1));
+    debug!("expired query results purged every {:?}", interval);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick completes immediately
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            match facade::Query::purge_expired_results(context.db.clone()).await {
+                Ok(0) => {}
+                Ok(count) => info!("purged {} expired query results", count),
+                Err(e) => warn!("unable to purge expired query results: {}", e),
+            }
+        }
+    })
+}
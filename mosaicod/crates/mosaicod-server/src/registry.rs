@@ -0,0 +1,127 @@
+//! Registry of custom action handlers.
+//!
+//! Embedders can extend the Flight DoAction endpoint with organization-specific
+//! operations, without forking the server, by implementing [`ActionHandler`] and
+//! registering it in an [`ActionRegistry`]. Requests with an action type in the
+//! form `<namespace>.<name>` are routed to the matching handler.
+use crate::error::{Error, Result};
+use futures::future::BoxFuture;
+use mosaicod_core::types::auth::Permission;
+use mosaicod_facade as facade;
+use mosaicod_marshal::requests;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Namespaces reserved to the core server that can not be used by custom actions.
+const RESERVED_NAMESPACES: &[&str] = &["mosaico", "mosaicod"];
+
+/// Handler for a custom action.
+pub trait ActionHandler: Send + Sync {
+    /// Returns true if the given permissions allow to execute the action.
+    ///
+    /// By default custom actions require [`Permission::Manage`].
+    fn is_allowed(&self, perm: &Permission) -> bool {
+        perm.can_manage()
+    }
+
+    /// Executes the action. The `body` contains the raw request body sent by the client,
+    /// the returned value is sent back to the client as action response.
+    fn handle<'a>(
+        &'a self,
+        ctx: &'a facade::Context,
+        body: &'a [u8],
+    ) -> BoxFuture<'a, Result<serde_json::Value>>;
+}
+
+/// Collection of custom action handlers indexed by namespace and name.
+#[derive(Clone, Default)]
+pub struct ActionRegistry {
+    handlers: HashMap<(String, String), Arc<dyn ActionHandler>>,
+}
+
+impl ActionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a new handler for the action `<namespace>.<name>`.
+    ///
+    /// Fails if the namespace or the name are not valid identifiers, if the namespace
+    /// is reserved or if an handler is already registered for the same action.
+    pub fn register<H>(&mut self, namespace: &str, name: &str, handler: H) -> Result<()>
+    where
+        H: ActionHandler + 'static,
+    {
+        let action = format!("{namespace}{}{name}", requests::Custom::SEPARATOR);
+
+        if !requests::is_valid_identifier(namespace)
+            || !requests::is_valid_identifier(name)
+            || RESERVED_NAMESPACES.contains(&namespace)
+        {
+            return Err(Error::invalid_custom_action(&action).into());
+        }
+
+        let key = (namespace.to_owned(), name.to_owned());
+        if self.handlers.contains_key(&key) {
+            return Err(Error::duplicated_custom_action(&action).into());
+        }
+
+        self.handlers.insert(key, Arc::new(handler));
+
+        Ok(())
+    }
+
+    /// Returns the handler registered for the action `<namespace>.<name>`, if any.
+    pub fn get(&self, namespace: &str, name: &str) -> Option<Arc<dyn ActionHandler>> {
+        self.handlers
+            .get(&(namespace.to_owned(), name.to_owned()))
+            .cloned()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Echo;
+
+    impl ActionHandler for Echo {
+        fn handle<'a>(
+            &'a self,
+            _ctx: &'a facade::Context,
+            body: &'a [u8],
+        ) -> BoxFuture<'a, Result<serde_json::Value>> {
+            Box::pin(async move { Ok(serde_json::from_slice(body).unwrap_or_default()) })
+        }
+    }
+
+    #[test]
+    fn register_custom_action() {
+        let mut registry = ActionRegistry::new();
+        assert!(registry.is_empty());
+
+        registry.register("acme", "echo", Echo).unwrap();
+        assert!(registry.get("acme", "echo").is_some());
+        assert!(registry.get("acme", "missing").is_none());
+
+        // Same action can not be registered twice
+        assert!(registry.register("acme", "echo", Echo).is_err());
+
+        // Invalid or reserved namespaces are rejected
+        assert!(registry.register("", "echo", Echo).is_err());
+        assert!(registry.register("Acme", "echo", Echo).is_err());
+        assert!(registry.register("acme.corp", "echo", Echo).is_err());
+        assert!(registry.register("mosaicod", "echo", Echo).is_err());
+    }
+
+    #[test]
+    fn default_permissions() {
+        assert!(Echo.is_allowed(&Permission::Manage));
+        assert!(!Echo.is_allowed(&Permission::Delete));
+        assert!(!Echo.is_allowed(&Permission::Read));
+    }
+}
@@ -0,0 +1,222 @@
+//! Rebuild of the state derived from the data of the topics.
+//!
+//! The column statistics cached for the topics, the column statistics of the data files
+//! used to select the chunks read by queries and the data info of the topics are derived
+//! from the stored data. A reindex job computes them again for all the finalized topics,
+//! in background and in bounded batches, e.g. after fixing a bug in the code deriving them.
+use mosaicod_core::{self as core, types};
+use mosaicod_facade as facade;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+/// State derived from the data of the topics that can be rebuilt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Scope {
+    /// Column statistics cached for the topics, see [`facade::topic::column_stats`]
+    ColumnStats,
+    /// Column statistics of the data files, used to select the chunks read by queries
+    ChunkStats,
+    /// Number of data files, size and timestamp range of the topics
+    DataInfo,
+}
+
+impl Scope {
+    pub(crate) const ALL: [Scope; 3] = [Scope::ColumnStats, Scope::ChunkStats, Scope::DataInfo];
+
+    /// Parses a scope, `all` selects all the scopes.
+    pub(crate) fn parse_many(value: &str) -> Result<Vec<Self>, core::Error> {
+        if value == "all" {
+            return Ok(Self::ALL.to_vec());
+        }
+        value.parse().map(|scope| vec![scope])
+    }
+}
+
+impl std::fmt::Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ColumnStats => write!(f, "column_stats"),
+            Self::ChunkStats => write!(f, "chunk_stats"),
+            Self::DataInfo => write!(f, "data_info"),
+        }
+    }
+}
+
+impl std::str::FromStr for Scope {
+    type Err = core::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|scope| scope.to_string() == value)
+            .ok_or_else(|| core::Error::bad_request(format!("unknown reindex scope `{}`", value)))
+    }
+}
+
+/// Progress of a reindex job.
+#[derive(Debug, Clone)]
+pub(crate) struct Job {
+    pub scope: Scope,
+    pub processed: u64,
+    pub failed: u64,
+    pub started_at: types::Timestamp,
+    /// Missing if the job is running
+    pub completed_at: Option<types::Timestamp>,
+    /// Reason the job was stopped before processing all the topics
+    pub error: Option<String>,
+}
+
+impl Job {
+    fn new(scope: Scope) -> Self {
+        Self {
+            scope,
+            processed: 0,
+            failed: 0,
+            started_at: types::Timestamp::now(),
+            completed_at: None,
+            error: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.completed_at.is_none()
+    }
+}
+
+/// Runs the reindex jobs, at most one for each scope at a time.
+#[derive(Default)]
+pub(crate) struct Reindexer {
+    /// Last job started for each scope
+    jobs: Mutex<BTreeMap<Scope, Job>>,
+}
+
+impl Reindexer {
+    /// Starts in background a job rebuilding `scope` for all the finalized topics, processed
+    /// `batch_size` at a time.
+    ///
+    /// Returns an error if a job rebuilding the same scope is running.
+    pub fn start(
+        self: &Arc<Self>,
+        context: facade::Context,
+        scope: Scope,
+        batch_size: usize,
+    ) -> Result<Job, core::Error> {
+        let job = {
+            let mut jobs = self.jobs.lock().unwrap();
+            if jobs.get(&scope).is_some_and(Job::is_running) {
+                return Err(core::Error::bad_request(format!(
+                    "a reindex job for `{}` is already running",
+                    scope
+                )));
+            }
+            let job = Job::new(scope);
+            jobs.insert(scope, job.clone());
+            job
+        };
+
+        info!(
+            "reindex of `{}` started, {} topics per batch",
+            scope, batch_size
+        );
+
+        let reindexer = self.clone();
+        tokio::spawn(async move { reindexer.run(context, scope, batch_size).await });
+
+        Ok(job)
+    }
+
+    /// Returns the last job started for each scope.
+    pub fn jobs(&self) -> Vec<Job> {
+        self.jobs.lock().unwrap().values().cloned().collect()
+    }
+
+    async fn run(&self, context: facade::Context, scope: Scope, batch_size: usize) {
+        let mut after = None;
+        let mut error = None;
+
+        loop {
+            let mut topics =
+                match facade::topic::finalized_page(&context, after.as_ref(), batch_size).await {
+                    Ok(topics) => topics,
+                    Err(e) => {
+                        warn!("reindex of `{}` stopped: {}", scope, e);
+                        error = Some(e.to_string());
+                        break;
+                    }
+                };
+
+            for topic in &topics {
+                let res = rebuild(&context, topic, scope).await;
+                if let Err(e) = &res {
+                    warn!(
+                        "unable to rebuild `{}` of topic `{}`: {}",
+                        scope,
+                        topic.locator(),
+                        e
+                    );
+                }
+                self.update(scope, |job| match res {
+                    Ok(()) => job.processed += 1,
+                    Err(_) => job.failed += 1,
+                });
+            }
+
+            if topics.len() < batch_size {
+                break;
+            }
+            after = topics.pop();
+
+            // Let the requests served concurrently progress between the batches
+            tokio::task::yield_now().await;
+        }
+
+        self.update(scope, |job| {
+            job.completed_at = Some(types::Timestamp::now());
+            job.error = error;
+            info!(
+                "reindex of `{}` completed, {} topics processed, {} failed",
+                scope, job.processed, job.failed
+            );
+        });
+    }
+
+    fn update(&self, scope: Scope, f: impl FnOnce(&mut Job)) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(&scope) {
+            f(job);
+        }
+    }
+}
+
+async fn rebuild(
+    context: &facade::Context,
+    topic: &facade::topic::Handle,
+    scope: Scope,
+) -> core::error::PublicResult<()> {
+    match scope {
+        Scope::ColumnStats => facade::topic::rebuild_column_stats(context, topic).await?,
+        Scope::ChunkStats => {
+            facade::topic::rebuild_chunk_stats(context, topic).await?;
+        }
+        Scope::DataInfo => facade::topic::rebuild_data_info(context, topic).await?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes() {
+        for scope in Scope::ALL {
+            assert_eq!(scope.to_string().parse::<Scope>().unwrap(), scope);
+        }
+        assert_eq!(Scope::parse_many("all").unwrap(), Scope::ALL);
+        assert_eq!(
+            Scope::parse_many("chunk_stats").unwrap(),
+            [Scope::ChunkStats]
+        );
+        assert!(Scope::parse_many("rollups").is_err());
+    }
+}
@@ -0,0 +1,654 @@
+//! Relay of the finalized sessions to a central instance.
+//!
+//! An edge instance accepts the uploads locally and a task periodically forwards the
+//! finalized sessions to the central instance through its Flight API, under the namespace
+//! of the edge, see [`facade::relay`]. Sessions are relayed in completion order and a run
+//! stops at the first failure, the failed session is retried by the next run.
+//!
+//! Sequences and topics already existing on the central instance, not created by the relay,
+//! are conflicts resolved according to [`RelayConfig::conflict_policy`].
+//!
+//! Only the topics selected by [`RelayConfig::topic_filter`] are relayed, sessions without
+//! any selected topic are marked as relayed without contacting the central instance.
+use arrow_flight::{
+    Action, FlightDescriptor, encode::FlightDataEncoderBuilder, error::FlightError,
+    flight_service_client::FlightServiceClient,
+};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use futures::{StreamExt, TryStreamExt};
+use mosaicod_core::{self as core, error::BoxPublicError, params, types};
+use mosaicod_ext as ext;
+use mosaicod_facade as facade;
+use mosaicod_marshal as marshal;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tonic::{
+    Code, Status,
+    metadata::{AsciiMetadataValue, MetadataValue},
+    service::{Interceptor, interceptor::InterceptedService},
+    transport::{Certificate, Channel, ClientTlsConfig, Endpoint},
+};
+use tracing::{debug, info, warn};
+
+/// Maximum number of sessions relayed by each run.
+const RELAY_BATCH_SIZE: usize = 16;
+
+/// Maximum number of suffixes tried to rename a conflicting locator.
+const MAX_RENAME_ATTEMPTS: u32 = 100;
+
+/// Configuration of the relay forwarding the finalized sessions to a central instance.
+#[derive(Clone)]
+pub struct RelayConfig {
+    /// URL of the central instance, e.g. `https://central.example.com:6726`
+    pub target: String,
+    /// Namespace prefixing the relayed sequences, unique for each edge instance
+    pub namespace: String,
+    /// API key presented to the central instance
+    pub api_key: Option<String>,
+    /// Authority verifying the certificate of the central instance, required by `https`
+    /// targets
+    pub tls_ca_file: Option<std::path::PathBuf>,
+    /// Interval between consecutive runs of the relay
+    pub interval: Duration,
+    /// Resolution of the locators already existing on the central instance
+    pub conflict_policy: types::RelayConflictPolicy,
+    /// Topics of the sessions relayed to the central instance
+    pub topic_filter: types::RelayTopicFilter,
+}
+
+impl RelayConfig {
+    /// Reads the configuration from the parameters, returns `None` if the relay is disabled.
+    pub fn from_params() -> core::error::PublicResult<Option<Self>> {
+        let params = params::params();
+
+        if params.relay_target.value.is_empty() {
+            return Ok(None);
+        }
+
+        let non_empty = |value: &str| (!value.is_empty()).then(|| value.to_owned());
+
+        let conflict_policy = params
+            .relay_conflict_policy
+            .value
+            .parse::<types::RelayConflictPolicy>()
+            .map_err(|e| {
+                core::Error::invalid_configuration(
+                    params.relay_conflict_policy.env.to_owned(),
+                    e.to_string(),
+                )
+            })?;
+
+        let config = Self {
+            target: params.relay_target.value.clone(),
+            namespace: params.relay_namespace.value.clone(),
+            api_key: non_empty(&params.relay_api_key.value),
+            tls_ca_file: non_empty(&params.relay_tls_ca_file.value).map(Into::into),
+            interval: Duration::from_secs(params.relay_interval.value),
+            conflict_policy,
+            topic_filter: types::RelayTopicFilter {
+                include_locators: params.relay_include_topics.value.clone(),
+                exclude_locators: params.relay_exclude_topics.value.clone(),
+                include_ontology_tags: params.relay_include_ontology_tags.value.clone(),
+                exclude_ontology_tags: params.relay_exclude_ontology_tags.value.clone(),
+            },
+        };
+
+        if config.target.starts_with("https") && config.tls_ca_file.is_none() {
+            Err(core::Error::invalid_configuration(
+                params.relay_tls_ca_file.env.to_owned(),
+                "required by `https` relay targets".to_owned(),
+            ))?;
+        }
+
+        Ok(Some(config))
+    }
+}
+
+/// Spawns the task relaying the finalized sessions, returns `None` if the relay is
+/// disabled.
+pub(crate) fn spawn_session_relay(
+    context: facade::Context,
+    config: Option<RelayConfig>,
+) -> core::error::PublicResult<Option<tokio::task::JoinHandle<()>>> {
+    let Some(config) = config else {
+        return Ok(None);
+    };
+
+    facade::relay::check_namespace(&config.namespace).map_err(|_| {
+        core::Error::invalid_configuration(
+            params::params().relay_namespace.env.to_owned(),
+            format!(
+                "`{}` is not a valid sequence name without `{}`",
+                config.namespace,
+                facade::relay::NAMESPACE_SEPARATOR
+            ),
+        )
+    })?;
+
+    info!(
+        "finalized sessions relayed to `{}` with namespace `{}` every {:?} (conflicts: {})",
+        config.target, config.namespace, config.interval, config.conflict_policy
+    );
+
+    Ok(Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(config.interval);
+
+        loop {
+            ticker.tick().await;
+            match relay(&context, &config).await {
+                Ok(0) => {}
+                Ok(relayed) => info!(
+                    "relay: {} sessions forwarded to the central instance",
+                    relayed
+                ),
+                Err(e) => warn!("session relay stopped: {}", e),
+            }
+        }
+    })))
+}
+
+/// Error of a relay attempt.
+enum RelayError {
+    /// The session conflicts with the data of the central instance, it is not retried
+    Rejected(String),
+    /// The attempt failed and will be retried
+    Failed(String),
+}
+
+impl std::fmt::Display for RelayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rejected(msg) => write!(f, "rejected: {}", msg),
+            Self::Failed(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<Status> for RelayError {
+    fn from(status: Status) -> Self {
+        Self::Failed(format!(
+            "central instance replied {:?}: {}",
+            status.code(),
+            status.message()
+        ))
+    }
+}
+
+impl From<BoxPublicError> for RelayError {
+    fn from(e: BoxPublicError) -> Self {
+        Self::Failed(e.to_string())
+    }
+}
+
+/// Presents the API key of the relay to the central instance.
+#[derive(Clone)]
+struct ApiKeyInterceptor(Option<AsciiMetadataValue>);
+
+impl Interceptor for ApiKeyInterceptor {
+    fn call(&mut self, mut req: tonic::Request<()>) -> Result<tonic::Request<()>, Status> {
+        if let Some(key) = &self.0 {
+            req.metadata_mut()
+                .insert(params::MOSAICO_API_KEY_HEADER, key.clone());
+        }
+        Ok(req)
+    }
+}
+
+type Client = FlightServiceClient<InterceptedService<Channel, ApiKeyInterceptor>>;
+
+async fn connect(config: &RelayConfig) -> Result<Client, Box<dyn std::error::Error + Send + Sync>> {
+    let mut endpoint = Endpoint::from_shared(config.target.clone())?;
+
+    if let Some(ca_file) = &config.tls_ca_file {
+        let ca = Certificate::from_pem(std::fs::read(ca_file)?);
+        endpoint = endpoint.tls_config(ClientTlsConfig::new().ca_certificate(ca))?;
+    }
+
+    let api_key = config
+        .api_key
+        .as_deref()
+        .map(MetadataValue::try_from)
+        .transpose()?;
+
+    let max_message_size = params::params().max_grpc_message_size.value;
+    Ok(
+        FlightServiceClient::with_interceptor(
+            endpoint.connect().await?,
+            ApiKeyInterceptor(api_key),
+        )
+        .max_decoding_message_size(max_message_size)
+        .max_encoding_message_size(max_message_size),
+    )
+}
+
+/// Runs a relay pass, returns the number of relayed sessions.
+async fn relay(context: &facade::Context, config: &RelayConfig) -> Result<usize, String> {
+    let pending = facade::relay::pending(context, RELAY_BATCH_SIZE)
+        .await
+        .map_err(|e| e.to_string())?;
+    if pending.is_empty() {
+        return Ok(0);
+    }
+
+    let mut client = connect(config)
+        .await
+        .map_err(|e| format!("unable to connect to `{}`: {}", config.target, e))?;
+
+    let mut relayed = 0;
+    for session in pending {
+        let locator = session.handle.locator().to_string();
+        debug!(
+            "relaying session `{}` (previous attempts: {})",
+            locator, session.attempts
+        );
+
+        match relay_session(context, &mut client, config, &session).await {
+            Ok(()) => {
+                facade::relay::complete(context, &session.handle)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                relayed += 1;
+            }
+            Err(e) => {
+                let rejected = matches!(e, RelayError::Rejected(_));
+                facade::relay::fail(context, &session.handle, &e.to_string(), rejected)
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                if rejected {
+                    warn!("session `{}` not relayed: {}", locator, e);
+                    continue;
+                }
+                return Err(format!("unable to relay session `{}`: {}", locator, e));
+            }
+        }
+    }
+
+    Ok(relayed)
+}
+
+/// Sends an action to the central instance, returns the content of the response.
+async fn action(
+    client: &mut Client,
+    action: &str,
+    body: serde_json::Value,
+) -> Result<serde_json::Value, Status> {
+    let mut stream = client
+        .do_action(Action {
+            r#type: action.to_owned(),
+            body: body.to_string().into(),
+        })
+        .await?
+        .into_inner();
+
+    let mut response = serde_json::Value::Null;
+    while let Some(result) = stream.message().await? {
+        let mut body: serde_json::Value = serde_json::from_slice(&result.body)
+            .map_err(|e| Status::internal(format!("invalid `{}` response: {}", action, e)))?;
+        response = body["response"].take();
+    }
+
+    Ok(response)
+}
+
+fn response_field(response: &serde_json::Value, field: &str) -> Result<String, RelayError> {
+    response[field]
+        .as_str()
+        .map(ToOwned::to_owned)
+        .ok_or_else(|| RelayError::Failed(format!("missing `{}` in the response", field)))
+}
+
+/// Forwards a finalized session to the central instance.
+///
+/// The session created by the previous attempt, if any, is deleted first.
+async fn relay_session(
+    context: &facade::Context,
+    client: &mut Client,
+    config: &RelayConfig,
+    session: &facade::relay::PendingSession,
+) -> Result<(), RelayError> {
+    let handle = &session.handle;
+
+    if let Some(remote_locator) = &session.remote_locator {
+        debug!("deleting incomplete remote session `{}`", remote_locator);
+        match action(
+            client,
+            "session_delete",
+            json!({ "locator": remote_locator }),
+        )
+        .await
+        {
+            Err(status) if status.code() != Code::NotFound => Err(status)?,
+            _ => facade::relay::set_remote_locator(context, handle, None).await?,
+        }
+    }
+
+    let Some(topics) = facade::relay::topics(context, handle, &config.topic_filter).await? else {
+        return Err(RelayError::Rejected(
+            "rows upserted into topics of other sessions can not be relayed".to_owned(),
+        ));
+    };
+    if topics.is_empty() {
+        info!(
+            "session `{}` has no topic selected by the relay filters",
+            handle.locator()
+        );
+        return Ok(());
+    }
+
+    let sequence = &handle.locator().sequence;
+    let sequence_handle =
+        facade::sequence::Handle::try_from_locator(context, sequence.clone()).await?;
+
+    // Sessions of the same sequence share the remote sequence
+    let remote_sequence = match facade::relay::relayed_sequence(
+        context,
+        &config.namespace,
+        &sequence_handle,
+    )
+    .await?
+    {
+        Some(remote_sequence) => remote_sequence,
+        None => create_remote_sequence(context, client, config, &sequence_handle).await?,
+    }
+    .to_string();
+
+    let response = action(
+        client,
+        "session_create",
+        json!({ "locator": remote_sequence }),
+    )
+    .await?;
+    let remote_uuid = response_field(&response, "uuid")?;
+    let remote_locator = response_field(&response, "locator")?;
+    facade::relay::set_remote_locator(context, handle, Some(&remote_locator)).await?;
+
+    let result = async {
+        for topic in topics {
+            relay_topic(
+                context,
+                client,
+                config,
+                handle,
+                &topic,
+                &remote_sequence,
+                &remote_uuid,
+            )
+            .await?;
+        }
+        action(
+            client,
+            "session_finalize",
+            json!({ "session_uuid": remote_uuid }),
+        )
+        .await?;
+        Ok(())
+    }
+    .await;
+
+    // Rejected sessions are not retried, the remote session is deleted right away
+    if let Err(RelayError::Rejected(_)) = &result
+        && action(
+            client,
+            "session_delete",
+            json!({ "locator": remote_locator }),
+        )
+        .await
+        .is_ok()
+    {
+        facade::relay::set_remote_locator(context, handle, None).await?;
+    }
+    result?;
+
+    info!(
+        "session `{}` relayed as `{}`",
+        handle.locator(),
+        remote_locator
+    );
+    Ok(())
+}
+
+/// Creates the central sequence receiving the sessions of `sequence`, resolving the
+/// conflict with an existing central sequence according to the policy of the relay.
+async fn create_remote_sequence(
+    context: &facade::Context,
+    client: &mut Client,
+    config: &RelayConfig,
+    sequence: &facade::sequence::Handle,
+) -> Result<types::SequenceLocator, RelayError> {
+    let namespaced = facade::relay::remote_sequence(&config.namespace, sequence.locator())?;
+    let user_metadata = facade::sequence::metadata(context, sequence)
+        .await?
+        .user_metadata
+        .map(serde_json::Value::from)
+        .unwrap_or_else(|| json!({}));
+
+    let mut remote_sequence = namespaced.clone();
+    let mut attempt = 0;
+    let mut conflict = false;
+    loop {
+        let body =
+            json!({ "locator": remote_sequence.to_string(), "user_metadata": user_metadata });
+        match action(client, "sequence_create", body).await {
+            Ok(_) => break,
+            Err(status) if status.code() == Code::AlreadyExists => conflict = true,
+            Err(status) => Err(status)?,
+        }
+
+        debug!(
+            "sequence `{}` already exists on the central instance",
+            remote_sequence
+        );
+        match config.conflict_policy {
+            types::RelayConflictPolicy::Merge => break,
+            types::RelayConflictPolicy::Rename if attempt < MAX_RENAME_ATTEMPTS => {
+                attempt += 1;
+                remote_sequence = facade::relay::renamed(&namespaced.to_string(), attempt)
+                    .parse()
+                    .map_err(|e: core::Error| RelayError::Failed(e.to_string()))?;
+            }
+            _ => {
+                facade::relay::notify_sequence_conflict(
+                    context,
+                    sequence,
+                    &namespaced.to_string(),
+                    config.conflict_policy,
+                    None,
+                )
+                .await?;
+                return Err(RelayError::Rejected(format!(
+                    "sequence `{}` already exists on the central instance",
+                    namespaced
+                )));
+            }
+        }
+    }
+
+    if conflict {
+        facade::relay::notify_sequence_conflict(
+            context,
+            sequence,
+            &namespaced.to_string(),
+            config.conflict_policy,
+            Some(&remote_sequence.to_string()),
+        )
+        .await?;
+    }
+
+    facade::relay::set_relayed_sequence(context, sequence, &remote_sequence).await?;
+    Ok(remote_sequence)
+}
+
+/// Creates the topic in the remote session and uploads the data written by the session.
+async fn relay_topic(
+    context: &facade::Context,
+    client: &mut Client,
+    config: &RelayConfig,
+    session: &facade::session::Handle,
+    topic: &facade::topic::Handle,
+    remote_sequence: &str,
+    remote_session_uuid: &str,
+) -> Result<(), RelayError> {
+    // Topics without data can not be finalized on the central instance
+    let Some(path_in_store) = topic.path_in_store() else {
+        return Ok(());
+    };
+
+    let namespaced = format!("{}/{}", remote_sequence, topic.locator().name());
+    let mut remote_locator = namespaced.clone();
+    debug!(
+        "relaying topic `{}` as `{}`",
+        topic.locator(),
+        remote_locator
+    );
+
+    let metadata = facade::topic::metadata(context, topic).await?;
+    let properties = metadata.ontology_metadata.properties;
+    let arrow_schema = facade::topic::registered_schema(context, topic)
+        .await?
+        .map(|schema| ext::arrow::schema_to_ipc(&schema))
+        .transpose()
+        .map_err(|e| RelayError::Failed(e.to_string()))?
+        .map(|ipc| BASE64.encode(ipc));
+
+    let mut body = json!({
+        "locator": remote_locator,
+        "session_uuid": remote_session_uuid,
+        "serialization_format": marshal::Format::from(properties.serialization_format),
+        "ontology_tag": properties.ontology_tag,
+        "sort_key": properties.sort_key,
+        "dedup_policy": marshal::DedupPolicy::from(properties.dedup_policy),
+        "primary_key": properties.primary_key,
+        "arrow_schema": arrow_schema,
+        "user_metadata": metadata
+            .ontology_metadata
+            .user_metadata
+            .map(serde_json::Value::from)
+            .unwrap_or_else(|| json!({})),
+    });
+
+    // Topics can only be renamed, merging rows into an existing topic would mix the data
+    // of different sources
+    let mut attempt = 0;
+    let response = loop {
+        match action(client, "topic_create", body.clone()).await {
+            Err(status) if status.code() == Code::AlreadyExists => {}
+            response => break response?,
+        }
+
+        debug!(
+            "topic `{}` already exists on the central instance",
+            remote_locator
+        );
+        if config.conflict_policy != types::RelayConflictPolicy::Rename
+            || attempt == MAX_RENAME_ATTEMPTS
+        {
+            facade::relay::notify_topic_conflict(
+                context,
+                topic,
+                &namespaced,
+                config.conflict_policy,
+                None,
+            )
+            .await?;
+            return Err(RelayError::Rejected(format!(
+                "topic `{}` already exists on the central instance",
+                namespaced
+            )));
+        }
+        attempt += 1;
+        remote_locator = facade::relay::renamed(&namespaced, attempt);
+        body["locator"] = json!(remote_locator);
+    };
+    let remote_uuid = response_field(&response, "uuid")?;
+
+    if attempt > 0 {
+        facade::relay::notify_topic_conflict(
+            context,
+            topic,
+            &namespaced,
+            config.conflict_policy,
+            Some(&remote_locator),
+        )
+        .await?;
+    }
+
+    let chunks = facade::topic::session_chunks(context, topic, session).await?;
+    let batch_size = facade::topic::compute_optimal_batch_size(context, topic).await?;
+    let query_result = context
+        .timeseries_querier
+        .read_chunks(
+            &path_in_store.data_folder_path(),
+            properties.serialization_format,
+            Some(batch_size),
+            chunks,
+        )
+        .await
+        .map_err(|e| RelayError::Failed(e.to_string()))?;
+
+    // Offloaded blobs are inlined, the central instance stores them again
+    let schema = Arc::new(ext::blob::inline_schema(&query_result.schema()));
+    let transfer_limits = context.transfer_limits.clone();
+    let context = context.clone();
+    let path_in_store = path_in_store.clone();
+    let stream = query_result
+        .stream()
+        .await
+        .map_err(|e| RelayError::Failed(e.to_string()))?
+        .map_err(|e| FlightError::ExternalError(Box::new(e)))
+        .and_then(move |batch| {
+            let context = context.clone();
+            let path_in_store = path_in_store.clone();
+            async move {
+                facade::topic::inline_blobs(&context, &path_in_store, batch)
+                    .await
+                    .map_err(|e| FlightError::ExternalError(e.to_string().into()))
+            }
+        });
+
+    let cmd = json!({ "resource_locator": remote_locator, "topic_uuid": remote_uuid });
+    let encoded = FlightDataEncoderBuilder::new()
+        .with_schema(schema)
+        .with_flight_descriptor(Some(FlightDescriptor::new_cmd(cmd.to_string())))
+        .build(stream);
+
+    // A local read error ends the upload, the remote session is left incomplete and is
+    // deleted by the next attempt
+    let read_error = Arc::new(Mutex::new(None));
+    let flight_data = encoded.scan(read_error.clone(), |read_error, data| {
+        futures::future::ready(match data {
+            Ok(data) => Some(data),
+            Err(e) => {
+                *read_error.lock().unwrap() = Some(e.to_string());
+                None
+            }
+        })
+    });
+
+    // The upload is paced to the bandwidth allowed to the relay
+    let flight_data = flight_data.then(move |data| {
+        let transfer_limits = transfer_limits.clone();
+        async move {
+            let bytes = data.data_header.len() + data.data_body.len();
+            transfer_limits
+                .throttle(facade::TransferClass::Relay)
+                .acquire(bytes as u64)
+                .await;
+            data
+        }
+    });
+
+    let mut results = client.do_put(flight_data).await?.into_inner();
+    while results.message().await?.is_some() {}
+
+    if let Some(e) = read_error.lock().unwrap().take() {
+        return Err(RelayError::Failed(format!(
+            "unable to read topic `{}`: {}",
+            topic.locator(),
+            e
+        )));
+    }
+
+    Ok(())
+}
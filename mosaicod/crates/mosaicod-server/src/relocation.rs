@@ -0,0 +1,47 @@
+//! Background relocation of the store folders.
+//!
+//! When the store layout changes, the folders of the sequences and topics created before
+//! are moved to the path given by the new layout. A task periodically moves a bounded
+//! number of folders and deletes the ones moved by the previous run, see
+//! [`facade::relocation`].
+use mosaicod_core::params;
+use mosaicod_facade as facade;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Maximum number of resources processed by each run of the relocation.
+const RELOCATION_BATCH_SIZE: usize = 64;
+
+/// Spawns the task relocating the store folders, returns `None` if the relocation is
+/// disabled.
+pub(crate) fn spawn_store_relocator(
+    context: facade::Context,
+) -> Option<tokio::task::JoinHandle<()>> {
+    let params = params::params();
+
+    if params.store_relocation_interval.value == 0 {
+        return None;
+    }
+
+    let interval = Duration::from_secs(params.store_relocation_interval.value);
+    debug!(
+        "store folders relocated to the `{}` layout every {:?}",
+        context.store_layout, interval
+    );
+
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+            match facade::relocation::relocate(&context, RELOCATION_BATCH_SIZE).await {
+                Ok(stats) if stats == facade::relocation::Stats::default() => {}
+                Ok(stats) => info!(
+                    "store relocation: {} folders moved, {} completed, {} skipped",
+                    stats.relocated, stats.completed, stats.skipped
+                ),
+                Err(e) => warn!("store relocation stopped: {}", e),
+            }
+        }
+    }))
+}
@@ -0,0 +1,385 @@
+//! Capture of the requests received by the server, to debug client issues offline.
+//!
+//! The capture is disabled until a window is started with the `replay_capture_start`
+//! action. While the window is open the payloads of the actions and a summary of the
+//! DoPut streams are recorded into a ring buffer, retrieved with `replay_capture_dump`.
+//!
+//! The captured data is anonymized: every string (split on `/`, to keep the structure of
+//! the locators) is replaced by a hash salted for each window, so that equal values can
+//! still be matched within a capture without revealing them.
+use arrow_flight::FlightData;
+use mosaicod_core::{self as core, params, types};
+use std::{
+    collections::VecDeque,
+    hash::{DefaultHasher, Hash, Hasher},
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Action payloads larger than this are recorded only by size.
+const MAX_PAYLOAD_SIZE: usize = 64 * 1024;
+
+/// Longest window accepted by [`ReplayCapture::start`].
+pub(crate) const MAX_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Request recorded by the capture.
+#[derive(Debug, Clone)]
+pub(crate) struct Entry {
+    pub at: types::Timestamp,
+    /// Anonymized address of the peer
+    pub peer: Option<String>,
+    pub duration: Duration,
+    /// Status the request was answered with
+    pub code: tonic::Code,
+    pub kind: EntryKind,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum EntryKind {
+    Action {
+        action: String,
+        /// Anonymized payload, missing if not JSON or too large
+        payload: Option<serde_json::Value>,
+        payload_bytes: usize,
+    },
+    DoPut {
+        /// Anonymized locator of the uploaded topic, missing if the header was not received
+        locator: Option<String>,
+        /// Messages received, including the header
+        messages: u64,
+        /// Bytes of the data bodies received
+        bytes: u64,
+    },
+}
+
+/// Summary of a DoPut stream, updated as its messages are received.
+#[derive(Debug, Default)]
+pub(crate) struct PutSummary {
+    locator: Option<String>,
+    messages: u64,
+    bytes: u64,
+}
+
+impl PutSummary {
+    pub fn observe(&mut self, data: &FlightData) {
+        if self.messages == 0
+            && let Some(desc) = &data.flight_descriptor
+        {
+            self.locator = mosaicod_marshal::flight::do_put_cmd(&desc.cmd)
+                .ok()
+                .map(|cmd| cmd.resource_locator);
+        }
+        self.messages += 1;
+        self.bytes += data.data_body.len() as u64;
+    }
+}
+
+/// State of the capture, returned by [`ReplayCapture::snapshot`].
+#[derive(Debug, Clone)]
+pub(crate) struct Snapshot {
+    pub capacity: usize,
+    pub started_at: Option<types::Timestamp>,
+    /// End of the window, in the past if the capture is over
+    pub until: Option<types::Timestamp>,
+    pub active: bool,
+    /// Entries discarded because the buffer was full
+    pub dropped: u64,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Default)]
+struct Window {
+    started_at: Option<types::Timestamp>,
+    until: Option<types::Timestamp>,
+    deadline: Option<Instant>,
+    salt: String,
+    dropped: u64,
+    entries: VecDeque<Entry>,
+}
+
+/// Ring buffer of the requests received while a capture window is open.
+pub struct ReplayCapture {
+    capacity: usize,
+    window: Mutex<Window>,
+}
+
+impl ReplayCapture {
+    /// Keeps the last `capacity` requests of each window, a zero `capacity` disables the
+    /// capture.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            window: Mutex::new(Window::default()),
+        }
+    }
+
+    /// Creates a capture sized with [`params::Params::replay_capture_size`].
+    pub fn from_params() -> Self {
+        Self::new(params::params().replay_capture_size.value)
+    }
+
+    /// Opens a new window lasting `duration`, discarding the entries of the previous one.
+    pub(crate) fn start(&self, duration: Duration) -> Result<Snapshot, core::Error> {
+        if self.capacity == 0 {
+            return Err(core::Error::bad_request(
+                "replay capture is disabled, set `MOSAICOD_REPLAY_CAPTURE_SIZE` to enable it"
+                    .to_owned(),
+            ));
+        }
+        if duration.is_zero() || duration > MAX_WINDOW {
+            return Err(core::Error::bad_request(format!(
+                "capture duration must be between 1 and {} seconds",
+                MAX_WINDOW.as_secs()
+            )));
+        }
+
+        let now = types::Timestamp::now();
+        *self.window.lock().unwrap() = Window {
+            started_at: Some(now),
+            until: Some(now + duration),
+            deadline: Some(Instant::now() + duration),
+            salt: core::random::alphanumeric(16),
+            dropped: 0,
+            entries: VecDeque::with_capacity(self.capacity),
+        };
+
+        tracing::warn!(
+            duration_secs = duration.as_secs(),
+            "replay capture started, requests are recorded anonymized"
+        );
+
+        Ok(self.snapshot())
+    }
+
+    /// Returns true if a window is open.
+    pub fn is_active(&self) -> bool {
+        is_open(&self.window.lock().unwrap())
+    }
+
+    /// Returns the state of the capture and the entries recorded in the last window.
+    pub(crate) fn snapshot(&self) -> Snapshot {
+        let window = self.window.lock().unwrap();
+        Snapshot {
+            capacity: self.capacity,
+            started_at: window.started_at,
+            until: window.until,
+            active: is_open(&window),
+            dropped: window.dropped,
+            entries: window.entries.iter().cloned().collect(),
+        }
+    }
+
+    /// Records an action answered with `code`, if a window is open.
+    pub(crate) fn record_action(
+        &self,
+        peer: Option<IpAddr>,
+        action: &str,
+        body: &[u8],
+        duration: Duration,
+        code: tonic::Code,
+    ) {
+        self.record(peer, duration, code, |salt| {
+            let payload = (body.len() <= MAX_PAYLOAD_SIZE)
+                .then(|| serde_json::from_slice(body).ok())
+                .flatten()
+                .map(|payload| anonymize(salt, payload));
+
+            EntryKind::Action {
+                action: action.to_owned(),
+                payload,
+                payload_bytes: body.len(),
+            }
+        });
+    }
+
+    /// Records a DoPut stream answered with `code`, if a window is open.
+    pub(crate) fn record_put(
+        &self,
+        peer: Option<IpAddr>,
+        summary: PutSummary,
+        duration: Duration,
+        code: tonic::Code,
+    ) {
+        self.record(peer, duration, code, |salt| EntryKind::DoPut {
+            locator: summary.locator.map(|locator| anonymize_str(salt, &locator)),
+            messages: summary.messages,
+            bytes: summary.bytes,
+        });
+    }
+
+    fn record(
+        &self,
+        peer: Option<IpAddr>,
+        duration: Duration,
+        code: tonic::Code,
+        kind: impl FnOnce(&str) -> EntryKind,
+    ) {
+        let mut window = self.window.lock().unwrap();
+        if !is_open(&window) {
+            return;
+        }
+
+        let entry = Entry {
+            at: types::Timestamp::now(),
+            peer: peer.map(|peer| anonymize_str(&window.salt, &peer.to_string())),
+            duration,
+            code,
+            kind: kind(&window.salt),
+        };
+
+        if window.entries.len() >= self.capacity {
+            window.entries.pop_front();
+            window.dropped += 1;
+        }
+        window.entries.push_back(entry);
+    }
+}
+
+/// Returns true if the actions of type `action` drive the capture, and are not recorded.
+pub(crate) fn is_capture_action(action: &str) -> bool {
+    action.starts_with("replay_capture")
+}
+
+fn is_open(window: &Window) -> bool {
+    window
+        .deadline
+        .is_some_and(|deadline| Instant::now() < deadline)
+}
+
+/// Replaces the strings in `value` with their salted hashes, keeping the keys of the
+/// objects, the numbers and the booleans.
+fn anonymize(salt: &str, value: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+
+    match value {
+        Value::String(value) => Value::String(anonymize_str(salt, &value)),
+        Value::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(|value| anonymize(salt, value))
+                .collect(),
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key, anonymize(salt, value)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// Hashes each `/` separated segment of `value`, so that locators sharing a sequence
+/// share the hash of its name.
+fn anonymize_str(salt: &str, value: &str) -> String {
+    value
+        .split('/')
+        .map(|segment| {
+            if segment.is_empty() {
+                return String::new();
+            }
+            let mut hasher = DefaultHasher::new();
+            salt.hash(&mut hasher);
+            segment.hash(&mut hasher);
+            format!("~{:08x}", hasher.finish() as u32)
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer() -> Option<IpAddr> {
+        Some("10.0.0.1".parse().unwrap())
+    }
+
+    fn record(capture: &ReplayCapture, body: serde_json::Value) {
+        capture.record_action(
+            peer(),
+            "topic_create",
+            body.to_string().as_bytes(),
+            Duration::from_millis(1),
+            tonic::Code::Ok,
+        );
+    }
+
+    #[test]
+    fn anonymized() {
+        let capture = ReplayCapture::new(10);
+        capture.start(Duration::from_secs(60)).unwrap();
+
+        record(
+            &capture,
+            serde_json::json!({
+                "locator": "secret_sequence/topic",
+                "other": "secret_sequence/other_topic",
+                "batch_size": 42,
+                "flags": [true, "secret"],
+            }),
+        );
+
+        let snapshot = capture.snapshot();
+        let entry = &snapshot.entries[0];
+        assert_eq!(entry.code, tonic::Code::Ok);
+        assert!(!entry.peer.as_ref().unwrap().contains("10.0.0.1"));
+
+        let EntryKind::Action { payload, .. } = &entry.kind else {
+            panic!("expected an action entry");
+        };
+        let payload = payload.as_ref().unwrap();
+        assert!(!payload.to_string().contains("secret"));
+        assert_eq!(payload["batch_size"], 42);
+        assert_eq!(payload["flags"][0], true);
+
+        // Segments are hashed separately
+        let locator = payload["locator"].as_str().unwrap();
+        let other = payload["other"].as_str().unwrap();
+        assert_eq!(locator.split('/').next(), other.split('/').next());
+        assert_ne!(locator, other);
+    }
+
+    #[test]
+    fn ring_buffer() {
+        let capture = ReplayCapture::new(2);
+
+        // Nothing is recorded until a window is open
+        record(&capture, serde_json::json!({}));
+        assert!(!capture.is_active());
+        assert!(capture.snapshot().entries.is_empty());
+
+        capture.start(Duration::from_millis(100)).unwrap();
+        for i in 0..3 {
+            record(&capture, serde_json::json!({ "i": i }));
+        }
+        let snapshot = capture.snapshot();
+        assert!(snapshot.active);
+        assert_eq!(snapshot.dropped, 1);
+        assert_eq!(snapshot.entries.len(), 2);
+        let EntryKind::Action { payload, .. } = &snapshot.entries[0].kind else {
+            panic!("expected an action entry");
+        };
+        assert_eq!(payload.as_ref().unwrap()["i"], 1);
+
+        // Entries are kept after the window is over
+        std::thread::sleep(Duration::from_millis(150));
+        record(&capture, serde_json::json!({ "i": 3 }));
+        let snapshot = capture.snapshot();
+        assert!(!snapshot.active);
+        assert_eq!(snapshot.entries.len(), 2);
+    }
+
+    #[test]
+    fn disabled() {
+        let capture = ReplayCapture::new(0);
+        capture.start(Duration::from_secs(60)).unwrap_err();
+
+        let capture = ReplayCapture::new(10);
+        capture.start(Duration::ZERO).unwrap_err();
+        capture
+            .start(MAX_WINDOW + Duration::from_secs(1))
+            .unwrap_err();
+    }
+}
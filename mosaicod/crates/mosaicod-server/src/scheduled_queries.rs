@@ -0,0 +1,50 @@
+//! Background evaluation of the scheduled queries.
+//!
+//! Scheduled queries follow cron schedules with a resolution of one minute, a task
+//! periodically evaluates the queries whose run is due.
+use mosaicod_facade as facade;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Interval between consecutive checks of the scheduled queries whose run is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Spawns the task evaluating the scheduled queries.
+pub(crate) fn spawn_scheduled_query_runner(
+    context: facade::Context,
+) -> tokio::task::JoinHandle<()> {
+    debug!("scheduled queries checked every {:?}", CHECK_INTERVAL);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+
+        loop {
+            ticker.tick().await;
+            run_due_scheduled_queries(&context).await;
+        }
+    })
+}
+
+async fn run_due_scheduled_queries(context: &facade::Context) {
+    let evaluations = match facade::scheduled_query::run_due(context).await {
+        Ok(evaluations) => evaluations,
+        Err(e) => {
+            warn!("unable to retrieve the scheduled queries to run: {}", e);
+            return;
+        }
+    };
+
+    for (name, evaluation) in evaluations {
+        match evaluation {
+            Ok(evaluation) if evaluation.triggered => info!(
+                "scheduled query `{}` triggered, notified {} topics",
+                name, evaluation.match_count
+            ),
+            Ok(evaluation) => debug!(
+                "scheduled query `{}` not triggered ({} topics matched)",
+                name, evaluation.match_count
+            ),
+            Err(e) => warn!("unable to run scheduled query `{}`: {}", name, e),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! Background abort of the expired sessions.
+//!
+//! Sessions created but never finalized, e.g. because the client crashed during the upload,
+//! are aborted once they received no upload for longer than the configured TTL. Their
+//! topics are deleted and the objects written into the store are removed.
+use mosaicod_core::{params, types};
+use mosaicod_facade as facade;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Maximum interval between consecutive checks of the expired sessions.
+const MAX_REAP_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Spawns the task aborting the expired sessions, returns `None` if sessions never expire.
+pub(crate) fn spawn_session_reaper(
+    context: facade::Context,
+) -> Option<tokio::task::JoinHandle<()>> {
+    let ttl = params::params().session_ttl.value;
+
+    if ttl == 0 {
+        return None;
+    }
+
+    let ttl = Duration::from_secs(ttl);
+    let interval = ttl.min(MAX_REAP_INTERVAL);
+    debug!(
+        "sessions inactive for {:?} aborted every {:?}",
+        ttl, interval
+    );
+
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick completes immediately
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            let before = types::Timestamp::now().as_i64() - ttl.as_nanos() as i64;
+            match facade::session::abort_expired(&context, before.into(), types::allow_data_loss())
+                .await
+            {
+                Ok(0) => {}
+                Ok(count) => info!("aborted {} expired sessions", count),
+                Err(e) => warn!("unable to abort the expired sessions: {}", e),
+            }
+        }
+    }))
+}
@@ -0,0 +1,36 @@
+//! Background deletion of the unreferenced shared objects.
+//!
+//! Copied topics link their data files to objects shared with the source, which are kept
+//! while any chunk references them. A task periodically deletes the objects whose
+//! referencing chunks have all been deleted.
+use mosaicod_facade as facade;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Interval between consecutive collections of the shared objects.
+const COLLECTION_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Spawns the task deleting the unreferenced shared objects.
+pub(crate) fn spawn_shared_object_collector(
+    context: facade::Context,
+) -> tokio::task::JoinHandle<()> {
+    debug!(
+        "unreferenced shared objects collected every {:?}",
+        COLLECTION_INTERVAL
+    );
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(COLLECTION_INTERVAL);
+        // The first tick completes immediately
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            match facade::topic::collect_shared_objects(&context).await {
+                Ok(0) => {}
+                Ok(count) => info!("deleted {} unreferenced shared objects", count),
+                Err(e) => warn!("unable to delete the unreferenced shared objects: {}", e),
+            }
+        }
+    })
+}
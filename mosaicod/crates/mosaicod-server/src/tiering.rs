@@ -0,0 +1,110 @@
+//! Background tiering of the data files.
+//!
+//! The reads sampled by the store are periodically merged in the database, so that the
+//! access statistics survive a restart. When the tiering is enabled, a task periodically
+//! reports the savings of demoting the cold data files to the archive storage, then moves a
+//! bounded number of them, see [`facade::tiering`].
+use mosaicod_core::params;
+use mosaicod_facade as facade;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Interval between consecutive flushes of the sampled reads.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Maximum number of data files moved by each run of the tiering.
+const TIERING_BATCH_SIZE: usize = 1024;
+
+/// Spawns the task merging the sampled reads in the database, returns `None` if the store
+/// does not sample the reads.
+pub(crate) fn spawn_access_log_flusher(
+    context: facade::Context,
+) -> Option<tokio::task::JoinHandle<()>> {
+    let sample_rate = context.store.metrics().access().sample_rate();
+    if sample_rate == 0 {
+        return None;
+    }
+
+    debug!(
+        "one store read out of {} sampled, flushed every {:?}",
+        sample_rate, FLUSH_INTERVAL
+    );
+
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(FLUSH_INTERVAL);
+        // The first tick completes immediately
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            if let Err(e) = facade::tiering::flush_access_log(&context).await {
+                warn!("unable to flush the sampled store reads: {}", e);
+            }
+        }
+    }))
+}
+
+/// Spawns the task demoting the cold data files to the archive storage, returns `None` if
+/// the tiering is disabled.
+pub(crate) fn spawn_tierer(context: facade::Context) -> Option<tokio::task::JoinHandle<()>> {
+    let params = params::params();
+
+    if params.tiering_interval.value == 0 {
+        return None;
+    }
+
+    if !context.store.metrics().access().is_enabled() {
+        warn!(
+            "tiering disabled since `{}` is 0: without the store reads every data file is cold",
+            params.store_access_sample_rate.env
+        );
+        return None;
+    }
+
+    let interval = Duration::from_secs(params.tiering_interval.value);
+    let config = facade::tiering::TieringConfig::configured();
+    debug!(
+        "data files not read for {:?} moved to the archive storage every {:?}",
+        config.cold_after, interval
+    );
+
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick completes immediately
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            if let Err(e) = run(&context, &config).await {
+                warn!("tiering stopped: {}", e);
+            }
+        }
+    }))
+}
+
+async fn run(
+    context: &facade::Context,
+    config: &facade::tiering::TieringConfig,
+) -> mosaicod_core::error::PublicResult<()> {
+    let forgotten = facade::tiering::forget_deleted_objects(context).await?;
+    if forgotten > 0 {
+        debug!("forgot the reads of {} deleted objects", forgotten);
+    }
+
+    let report = facade::tiering::report(context, config, 0).await?;
+    if report.data_files == 0 {
+        return Ok(());
+    }
+    info!(
+        "tiering: {} cold data files ({} bytes), estimated savings of {:.2} per month",
+        report.data_files, report.size_bytes, report.monthly_savings
+    );
+
+    let stats = facade::tiering::demote(context, config, TIERING_BATCH_SIZE).await?;
+    info!(
+        "tiering: {} data files ({} bytes) moved to the archive storage",
+        stats.demoted, stats.size_bytes
+    );
+
+    Ok(())
+}
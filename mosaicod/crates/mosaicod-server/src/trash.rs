@@ -0,0 +1,47 @@
+//! Background purge of the sequences in the trash.
+//!
+//! Deleted sequences are moved to the trash, from where they can be restored. A task
+//! periodically deletes permanently the sequences kept in the trash longer than the
+//! configured retention.
+use mosaicod_core::{params, types};
+use mosaicod_facade as facade;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Maximum interval between consecutive purges of the trash.
+const MAX_PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Spawns the task purging the sequences in the trash, returns `None` if trashed sequences
+/// are kept until purged.
+pub(crate) fn spawn_trash_purger(context: facade::Context) -> Option<tokio::task::JoinHandle<()>> {
+    let retention = params::params().sequence_trash_retention.value;
+
+    if retention == 0 {
+        return None;
+    }
+
+    let retention = Duration::from_secs(retention);
+    let interval = retention.min(MAX_PURGE_INTERVAL);
+    debug!(
+        "sequences in the trash for {:?} purged every {:?}",
+        retention, interval
+    );
+
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick completes immediately
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            let before = types::Timestamp::now().as_i64() - retention.as_nanos() as i64;
+            match facade::sequence::purge_trashed(&context, before.into(), types::allow_data_loss())
+                .await
+            {
+                Ok(0) => {}
+                Ok(count) => info!("purged {} sequences from the trash", count),
+                Err(e) => warn!("unable to purge the sequences in the trash: {}", e),
+            }
+        }
+    }))
+}
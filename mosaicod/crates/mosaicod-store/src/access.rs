@@ -0,0 +1,134 @@
+//! Sampled statistics of the reads of the store objects.
+//!
+//! The [`AccessLog`] of a backend is fed by the [`crate::InstrumentedObjectStore`] with the
+//! objects read from the backend. To keep the overhead negligible only one read out of
+//! [`AccessLog::sample_rate`] is recorded, and counted as `sample_rate` reads of its object:
+//! the read counts are estimates, and objects read rarely may be missed altogether.
+//!
+//! The statistics are kept in memory until [`AccessLog::drain`]ed, usually to persist them.
+use crate::LINK_EXTENSION;
+use mosaicod_core::types;
+use object_store::path::Path;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::warn;
+
+/// Maximum number of objects tracked between two drains, the reads of further objects are
+/// dropped.
+const MAX_TRACKED_OBJECTS: usize = 100_000;
+
+/// Reads of an object recorded since the last drain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectAccess {
+    /// Estimated number of reads
+    pub reads: u64,
+    /// Time of the last sampled read
+    pub last_read_at: types::Timestamp,
+}
+
+#[derive(Debug, Default)]
+pub struct AccessLog {
+    sample_rate: AtomicU64,
+    reads: AtomicU64,
+    /// Sampled reads dropped since the last drain, because too many objects were tracked
+    dropped: AtomicU64,
+    objects: Mutex<HashMap<Path, ObjectAccess>>,
+}
+
+impl AccessLog {
+    /// Creates a disabled access log, see [`AccessLog::set_sample_rate`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records one read out of `sample_rate`, `0` disables the access log.
+    pub fn set_sample_rate(&self, sample_rate: u64) {
+        self.sample_rate.store(sample_rate, Ordering::Relaxed);
+    }
+
+    pub fn sample_rate(&self) -> u64 {
+        self.sample_rate.load(Ordering::Relaxed)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.sample_rate() != 0
+    }
+
+    /// Records a read of the object at `location`, if sampled. Reads of link objects are
+    /// not recorded, the reads of their targets are.
+    pub fn record(&self, location: &Path) {
+        let sample_rate = self.sample_rate();
+        if sample_rate == 0 || location.extension() == Some(LINK_EXTENSION) {
+            return;
+        }
+        if !self
+            .reads
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(sample_rate)
+        {
+            return;
+        }
+
+        let now = types::Timestamp::now();
+        let mut objects = self.objects.lock().unwrap();
+        if let Some(access) = objects.get_mut(location) {
+            access.reads += sample_rate;
+            access.last_read_at = now;
+        } else if objects.len() < MAX_TRACKED_OBJECTS {
+            objects.insert(
+                location.clone(),
+                ObjectAccess {
+                    reads: sample_rate,
+                    last_read_at: now,
+                },
+            );
+        } else {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the reads recorded since the previous drain, by object path.
+    pub fn drain(&self) -> Vec<(String, ObjectAccess)> {
+        let dropped = self.dropped.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            warn!(
+                "{} sampled reads dropped, more than {} objects were read since the last drain",
+                dropped, MAX_TRACKED_OBJECTS
+            );
+        }
+
+        std::mem::take(&mut *self.objects.lock().unwrap())
+            .into_iter()
+            .map(|(location, access)| (location.to_string(), access))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sampled_reads() {
+        let log = AccessLog::new();
+        let data = Path::from("tp_1/data/0.parquet");
+
+        // Disabled by default
+        log.record(&data);
+        assert!(log.drain().is_empty());
+
+        log.set_sample_rate(4);
+        for _ in 0..8 {
+            log.record(&data);
+        }
+        log.record(&Path::from("tp_1/data/1.parquet.mlink"));
+
+        let drained = log.drain();
+        assert_eq!(drained.len(), 1);
+        assert_eq!(drained[0].0, "tp_1/data/0.parquet");
+        assert_eq!(drained[0].1.reads, 8);
+
+        assert!(log.drain().is_empty());
+    }
+}
@@ -0,0 +1,777 @@
+//! Local disk cache for remote store backends.
+//!
+//! The [`CachedObjectStore`] wraps an [`ObjectStore`] and keeps a copy of the objects read
+//! from the remote backend on the local disk. Since the cache is placed in front of the
+//! object store driver, both the data streamed by the server and the data read by the query
+//! engine go through it.
+//!
+//! Cached objects are evicted in least recently used order once the configured capacity is
+//! exceeded, expire after an optional time to live and are checked against a checksum
+//! every time they are read. Objects are invalidated as soon as they are written or deleted
+//! through the cache.
+//!
+//! Whole objects are cached: ranged reads of objects not in cache are served by the backend
+//! while the object is cached in background. Concurrent fetches of the same object reach the
+//! backend only once.
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use object_store::{
+    Attributes, CopyOptions, GetOptions, GetResult, GetResultPayload, ListResult, MultipartUpload,
+    ObjectMeta, ObjectStore, ObjectStoreExt, PutMultipartOptions, PutOptions, PutPayload,
+    PutResult, RenameOptions, Result, path::Path,
+};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, trace, warn};
+
+/// Name used to identify the cache in object store errors
+const STORE_NAME: &str = "DiskCache";
+
+/// Name of the directory, inside the configured path, owned by the cache
+const CACHE_DIR: &str = "mosaicod-cache";
+
+/// Configuration of the local disk cache.
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    /// Local directory where cached objects are stored.
+    ///
+    /// Objects are stored in the [`CACHE_DIR`] subdirectory, which is wiped when the cache
+    /// is created. Other contents of the directory are left untouched.
+    pub path: std::path::PathBuf,
+
+    /// Maximum number of bytes kept in cache. Objects larger than this value are never cached.
+    pub capacity_bytes: u64,
+
+    /// Time after which a cached object is considered stale, `None` disables expiration.
+    pub ttl: Option<Duration>,
+}
+
+/// Counters describing the cache usage.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Bytes currently stored in cache
+    pub size_bytes: u64,
+}
+
+struct Entry {
+    file: std::path::PathBuf,
+    meta: ObjectMeta,
+    attributes: Attributes,
+    checksum: u32,
+    inserted_at: Instant,
+    /// Logical clock of the last access, used to find the least recently used entry
+    last_access: u64,
+}
+
+#[derive(Default)]
+struct Index {
+    entries: HashMap<Path, Entry>,
+    size_bytes: u64,
+    clock: u64,
+    /// Incremented every time an object is invalidated, used to discard objects fetched
+    /// while a concurrent write was in progress
+    generation: u64,
+}
+
+impl Index {
+    /// Removes the entry for `location` (if any), returning the file to delete
+    fn remove(&mut self, location: &Path) -> Option<std::path::PathBuf> {
+        let entry = self.entries.remove(location)?;
+        self.size_bytes -= entry.meta.size;
+        Some(entry.file)
+    }
+
+    /// Removes the least recently used entry, returning the file to delete
+    fn evict_lru(&mut self) -> Option<std::path::PathBuf> {
+        let location = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_access)
+            .map(|(location, _)| location.clone())?;
+        self.remove(&location)
+    }
+}
+
+/// Bytes-bounded LRU cache of objects stored on the local disk.
+pub struct DiskCache {
+    config: CacheConfig,
+    /// Directory holding the cached objects, owned by the cache
+    dir: std::path::PathBuf,
+    index: Mutex<Index>,
+    /// Locks of the objects being fetched from the backend
+    fetches: Mutex<HashMap<Path, Arc<tokio::sync::Mutex<()>>>>,
+
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl DiskCache {
+    pub fn try_new(config: CacheConfig) -> std::io::Result<Self> {
+        // Cached objects are not persisted across restarts
+        let dir = config.path.join(CACHE_DIR);
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        std::fs::create_dir_all(&dir)?;
+
+        Ok(Self {
+            config,
+            dir,
+            index: Mutex::new(Index::default()),
+            fetches: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        })
+    }
+
+    pub fn config(&self) -> &CacheConfig {
+        &self.config
+    }
+
+    /// Directory holding the cached objects
+    pub fn dir(&self) -> &std::path::Path {
+        &self.dir
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            size_bytes: self.index.lock().unwrap().size_bytes,
+        }
+    }
+
+    /// Returns true if an object of the given size can be cached
+    fn accepts(&self, size: u64) -> bool {
+        size <= self.config.capacity_bytes
+    }
+
+    fn generation(&self) -> u64 {
+        self.index.lock().unwrap().generation
+    }
+
+    fn contains(&self, location: &Path) -> bool {
+        self.index.lock().unwrap().entries.contains_key(location)
+    }
+
+    /// Returns the lock serializing the fetches of the object at `location`, to be released
+    /// with [`DiskCache::release_fetch_lock`].
+    fn fetch_lock(&self, location: &Path) -> Arc<tokio::sync::Mutex<()>> {
+        self.fetches
+            .lock()
+            .unwrap()
+            .entry(location.clone())
+            .or_default()
+            .clone()
+    }
+
+    fn release_fetch_lock(&self, location: &Path, lock: Arc<tokio::sync::Mutex<()>>) {
+        let mut fetches = self.fetches.lock().unwrap();
+        // Locks are cloned only while holding `fetches`, so no one else is using this one
+        if Arc::strong_count(&lock) == 2 {
+            fetches.remove(location);
+        }
+    }
+
+    /// Returns the cached object at `location`, if present, valid and not expired.
+    async fn get(&self, location: &Path) -> Option<(ObjectMeta, Attributes, Bytes)> {
+        let lookup = {
+            let mut index = self.index.lock().unwrap();
+            index.clock += 1;
+            let clock = index.clock;
+
+            match index.entries.get_mut(location) {
+                Some(entry)
+                    if self
+                        .config
+                        .ttl
+                        .is_some_and(|ttl| entry.inserted_at.elapsed() > ttl) =>
+                {
+                    Err(index.remove(location))
+                }
+                Some(entry) => {
+                    entry.last_access = clock;
+                    Ok(Some((
+                        entry.file.clone(),
+                        entry.meta.clone(),
+                        entry.attributes.clone(),
+                        entry.checksum,
+                    )))
+                }
+                None => Ok(None),
+            }
+        };
+
+        let (file, meta, attributes, checksum) = match lookup {
+            Ok(Some(found)) => found,
+            Ok(None) => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+            Err(expired) => {
+                trace!("cached object `{location}` expired");
+                remove_file(expired).await;
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+        };
+
+        match tokio::fs::read(&file).await {
+            Ok(data) if data.len() as u64 == meta.size && crc32fast::hash(&data) == checksum => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some((meta, attributes, data.into()))
+            }
+            Ok(_) => {
+                warn!("cached object `{location}` is corrupted, discarding it");
+                self.invalidate(location).await;
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            Err(e) => {
+                warn!("unable to read cached object `{location}`: {e}");
+                self.invalidate(location).await;
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Stores an object in cache, evicting the least recently used objects if required.
+    ///
+    /// The object is discarded if some object was invalidated after `generation`.
+    async fn insert(
+        &self,
+        location: &Path,
+        meta: ObjectMeta,
+        attributes: Attributes,
+        data: Bytes,
+        generation: u64,
+    ) {
+        if !self.accepts(meta.size) {
+            return;
+        }
+
+        let file = self.dir.join(ulid::Ulid::new().to_string());
+        if let Err(e) = tokio::fs::write(&file, &data).await {
+            warn!("unable to cache object `{location}`: {e}");
+            remove_file(Some(file)).await;
+            return;
+        }
+
+        let to_delete = {
+            let mut index = self.index.lock().unwrap();
+
+            if index.generation != generation {
+                vec![file]
+            } else {
+                let mut to_delete: Vec<_> = index.remove(location).into_iter().collect();
+
+                while index.size_bytes + meta.size > self.config.capacity_bytes {
+                    let Some(evicted) = index.evict_lru() else {
+                        break;
+                    };
+                    self.evictions.fetch_add(1, Ordering::Relaxed);
+                    to_delete.push(evicted);
+                }
+
+                index.clock += 1;
+                index.size_bytes += meta.size;
+                let entry = Entry {
+                    file,
+                    checksum: crc32fast::hash(&data),
+                    meta,
+                    attributes,
+                    inserted_at: Instant::now(),
+                    last_access: index.clock,
+                };
+                index.entries.insert(location.clone(), entry);
+
+                to_delete
+            }
+        };
+
+        for file in to_delete {
+            remove_file(Some(file)).await;
+        }
+    }
+
+    /// Removes the object at `location` from the cache
+    async fn invalidate(&self, location: &Path) {
+        let removed = {
+            let mut index = self.index.lock().unwrap();
+            index.generation += 1;
+            index.remove(location)
+        };
+        remove_file(removed).await;
+    }
+}
+
+impl std::fmt::Debug for DiskCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiskCache")
+            .field("config", &self.config)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+async fn remove_file(file: Option<std::path::PathBuf>) {
+    if let Some(file) = file
+        && let Err(e) = tokio::fs::remove_file(&file).await
+    {
+        debug!("unable to remove cached file {}: {e}", file.display());
+    }
+}
+
+/// Returns true if the request can be served from a local copy of the whole object, possibly
+/// reading a range of it
+pub(crate) fn is_whole_object_read(options: &GetOptions) -> bool {
+    !options.head
+        && options.if_match.is_none()
+        && options.if_none_match.is_none()
+        && options.if_modified_since.is_none()
+        && options.if_unmodified_since.is_none()
+        && options.version.is_none()
+}
+
+/// Builds the result of a get request from a local copy of the whole object
+pub(crate) fn serve_from_copy(
+    store: &'static str,
+    meta: ObjectMeta,
+    attributes: Attributes,
+    data: Bytes,
+    options: &GetOptions,
+) -> Result<GetResult> {
+    let range = match &options.range {
+        Some(range) => range
+            .as_range(meta.size)
+            .map_err(|e| object_store::Error::Generic {
+                store,
+                source: Box::new(e),
+            })?,
+        None => 0..meta.size,
+    };
+
+    let payload = data.slice(range.start as usize..range.end as usize);
+
+    Ok(GetResult {
+        payload: GetResultPayload::Stream(stream::once(async move { Ok(payload) }).boxed()),
+        meta,
+        range,
+        attributes,
+    })
+}
+
+/// Reads the whole object at `location` from `inner` and stores it in `cache`.
+///
+/// Fetches of the same object are performed one at a time, callers waiting for a fetch in
+/// progress are served from the copy cached by it.
+async fn fetch(
+    inner: &dyn ObjectStore,
+    cache: &DiskCache,
+    location: &Path,
+) -> Result<(ObjectMeta, Attributes, Bytes)> {
+    let lock = cache.fetch_lock(location);
+    let result = async {
+        let _guard = lock.lock().await;
+        if cache.contains(location)
+            && let Some(found) = cache.get(location).await
+        {
+            return Ok(found);
+        }
+        download(inner, cache, location).await
+    }
+    .await;
+    cache.release_fetch_lock(location, lock);
+    result
+}
+
+/// Caches the whole object at `location` in background, unless it is already being fetched.
+fn fetch_in_background(inner: Arc<dyn ObjectStore>, cache: Arc<DiskCache>, location: Path) {
+    tokio::spawn(async move {
+        let lock = cache.fetch_lock(&location);
+        if let Ok(_guard) = lock.try_lock()
+            && !cache.contains(&location)
+            && let Err(e) = download(inner.as_ref(), &cache, &location).await
+        {
+            debug!("unable to cache object `{location}`: {e}");
+        }
+        cache.release_fetch_lock(&location, lock);
+    });
+}
+
+/// Reads the whole object at `location` from `inner` and stores it in `cache`
+async fn download(
+    inner: &dyn ObjectStore,
+    cache: &DiskCache,
+    location: &Path,
+) -> Result<(ObjectMeta, Attributes, Bytes)> {
+    let generation = cache.generation();
+
+    let result = inner.get(location).await?;
+    let meta = result.meta.clone();
+    let attributes = result.attributes.clone();
+    let data = result.bytes().await?;
+
+    cache
+        .insert(
+            location,
+            meta.clone(),
+            attributes.clone(),
+            data.clone(),
+            generation,
+        )
+        .await;
+
+    Ok((meta, attributes, data))
+}
+
+/// [`ObjectStore`] serving reads from a [`DiskCache`] when possible.
+#[derive(Debug)]
+pub struct CachedObjectStore {
+    inner: Arc<dyn ObjectStore>,
+    cache: Arc<DiskCache>,
+}
+
+impl CachedObjectStore {
+    pub fn new(inner: Arc<dyn ObjectStore>, cache: Arc<DiskCache>) -> Self {
+        Self { inner, cache }
+    }
+
+    pub fn cache(&self) -> &Arc<DiskCache> {
+        &self.cache
+    }
+}
+
+impl std::fmt::Display for CachedObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({})", STORE_NAME, self.inner)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for CachedObjectStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.cache.invalidate(location).await;
+        let result = self.inner.put_opts(location, payload, opts).await;
+        self.cache.invalidate(location).await;
+        result
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOptions,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        // The object becomes visible only when the upload completes, invalidating it now
+        // discards objects fetched before the upload started
+        self.cache.invalidate(location).await;
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        if !is_whole_object_read(&options) {
+            return self.inner.get_opts(location, options).await;
+        }
+
+        if let Some((meta, attributes, data)) = self.cache.get(location).await {
+            trace!("cache hit for `{location}`");
+            return serve_from_copy(STORE_NAME, meta, attributes, data, &options);
+        }
+
+        // Ranges are read directly from the backend, to avoid waiting for the whole object
+        if options.range.is_some() {
+            let result = self.inner.get_opts(location, options).await?;
+            if self.cache.accepts(result.meta.size) {
+                fetch_in_background(self.inner.clone(), self.cache.clone(), location.clone());
+            }
+            return Ok(result);
+        }
+
+        // Objects too large to be cached are read directly from the backend
+        let meta = self.inner.head(location).await?;
+        if !self.cache.accepts(meta.size) {
+            return self.inner.get_opts(location, options).await;
+        }
+
+        let (meta, attributes, data) = fetch(self.inner.as_ref(), &self.cache, location).await?;
+
+        serve_from_copy(STORE_NAME, meta, attributes, data, &options)
+    }
+
+    fn delete_stream(
+        &self,
+        locations: BoxStream<'static, Result<Path>>,
+    ) -> BoxStream<'static, Result<Path>> {
+        let cache = self.cache.clone();
+        self.inner
+            .delete_stream(locations)
+            .and_then(move |location| {
+                let cache = cache.clone();
+                async move {
+                    cache.invalidate(&location).await;
+                    Ok(location)
+                }
+            })
+            .boxed()
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'static, Result<ObjectMeta>> {
+        self.inner.list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy_opts(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<()> {
+        self.cache.invalidate(to).await;
+        let result = self.inner.copy_opts(from, to, options).await;
+        self.cache.invalidate(to).await;
+        result
+    }
+
+    async fn rename_opts(&self, from: &Path, to: &Path, options: RenameOptions) -> Result<()> {
+        self.cache.invalidate(to).await;
+        let result = self.inner.rename_opts(from, to, options).await;
+        self.cache.invalidate(from).await;
+        self.cache.invalidate(to).await;
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use object_store::memory::InMemory;
+
+    fn cached_store(
+        capacity_bytes: u64,
+        ttl: Option<Duration>,
+    ) -> (CachedObjectStore, CacheConfig) {
+        let config = CacheConfig {
+            path: format!("/tmp/{}", mosaicod_core::random::alphabetic(10)).into(),
+            capacity_bytes,
+            ttl,
+        };
+        let cache = Arc::new(DiskCache::try_new(config.clone()).unwrap());
+        (
+            CachedObjectStore::new(Arc::new(InMemory::new()), cache),
+            config,
+        )
+    }
+
+    async fn read(store: &CachedObjectStore, location: &Path) -> Bytes {
+        store.get(location).await.unwrap().bytes().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn cache_hits_and_ranges() {
+        let (store, config) = cached_store(1024, None);
+        let location = Path::from("data/object");
+        store
+            .put(&location, PutPayload::from_static(b"0123456789"))
+            .await
+            .unwrap();
+
+        assert_eq!(read(&store, &location).await, "0123456789");
+        assert_eq!(read(&store, &location).await, "0123456789");
+        assert_eq!(
+            store.get_range(&location, 2..5).await.unwrap(),
+            Bytes::from_static(b"234")
+        );
+
+        let stats = store.cache().stats();
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.size_bytes, 10);
+
+        // Writes invalidate the cached copy
+        store
+            .put(&location, PutPayload::from_static(b"abc"))
+            .await
+            .unwrap();
+        assert_eq!(read(&store, &location).await, "abc");
+        assert_eq!(store.cache().stats().misses, 2);
+
+        store.delete(&location).await.unwrap();
+        assert!(store.get(&location).await.is_err());
+        assert_eq!(store.cache().stats().size_bytes, 0);
+
+        std::fs::remove_dir_all(config.path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cache_lru_eviction() {
+        let (store, config) = cached_store(25, None);
+
+        let locations: Vec<Path> = (0..3).map(|i| Path::from(format!("obj_{i}"))).collect();
+        for location in &locations {
+            store
+                .put(location, PutPayload::from(vec![0u8; 10]))
+                .await
+                .unwrap();
+        }
+
+        read(&store, &locations[0]).await;
+        read(&store, &locations[1]).await;
+        // Makes `obj_1` the least recently used object
+        read(&store, &locations[0]).await;
+        read(&store, &locations[2]).await;
+
+        let stats = store.cache().stats();
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.size_bytes, 20);
+
+        // `obj_0` and `obj_2` are still cached
+        read(&store, &locations[0]).await;
+        read(&store, &locations[2]).await;
+        assert_eq!(store.cache().stats().hits, 3);
+
+        // Objects larger than the cache are never cached
+        let large = Path::from("large");
+        store
+            .put(&large, PutPayload::from(vec![0u8; 100]))
+            .await
+            .unwrap();
+        assert_eq!(read(&store, &large).await.len(), 100);
+        assert_eq!(store.cache().stats().size_bytes, 20);
+
+        std::fs::remove_dir_all(config.path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cache_ttl() {
+        let (store, config) = cached_store(1024, Some(Duration::from_millis(10)));
+        let location = Path::from("object");
+        store
+            .put(&location, PutPayload::from_static(b"data"))
+            .await
+            .unwrap();
+
+        read(&store, &location).await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        read(&store, &location).await;
+
+        let stats = store.cache().stats();
+        assert_eq!(stats.hits, 0);
+        assert_eq!(stats.misses, 2);
+
+        std::fs::remove_dir_all(config.path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cache_integrity() {
+        let (store, config) = cached_store(1024, None);
+        let location = Path::from("object");
+        store
+            .put(&location, PutPayload::from_static(b"data"))
+            .await
+            .unwrap();
+        read(&store, &location).await;
+
+        // Corrupt the cached copy on disk
+        for file in std::fs::read_dir(store.cache().dir()).unwrap() {
+            std::fs::write(file.unwrap().path(), b"DATA").unwrap();
+        }
+
+        assert_eq!(read(&store, &location).await, "data");
+        let stats = store.cache().stats();
+        assert_eq!(stats.hits, 0);
+        assert_eq!(stats.misses, 2);
+
+        std::fs::remove_dir_all(config.path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cache_owned_dir() {
+        let path: std::path::PathBuf =
+            format!("/tmp/{}", mosaicod_core::random::alphabetic(10)).into();
+        std::fs::create_dir_all(&path).unwrap();
+        std::fs::write(path.join("other"), b"data").unwrap();
+
+        let config = CacheConfig {
+            path: path.clone(),
+            capacity_bytes: 1024,
+            ttl: None,
+        };
+        let cache = DiskCache::try_new(config.clone()).unwrap();
+        std::fs::write(cache.dir().join("stale"), b"data").unwrap();
+        drop(cache);
+
+        // Only the directory owned by the cache is wiped
+        let cache = DiskCache::try_new(config).unwrap();
+        assert!(path.join("other").exists());
+        assert_eq!(std::fs::read_dir(cache.dir()).unwrap().count(), 0);
+
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cache_ranged_miss() {
+        let (store, config) = cached_store(1024, None);
+        let location = Path::from("object");
+        store
+            .put(&location, PutPayload::from_static(b"0123456789"))
+            .await
+            .unwrap();
+
+        // The range is served by the backend, while the object is cached in background
+        assert_eq!(
+            store.get_range(&location, 2..5).await.unwrap(),
+            Bytes::from_static(b"234")
+        );
+        for _ in 0..100 {
+            if store.cache().stats().size_bytes == 10 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(store.cache().stats().size_bytes, 10);
+
+        assert_eq!(read(&store, &location).await, "0123456789");
+        assert_eq!(store.cache().stats().hits, 1);
+
+        std::fs::remove_dir_all(config.path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cache_single_flight() {
+        let (store, config) = cached_store(1024, None);
+        let location = Path::from("object");
+        store
+            .put(&location, PutPayload::from_static(b"data"))
+            .await
+            .unwrap();
+
+        let reads = futures::future::join_all((0..8).map(|_| read(&store, &location))).await;
+        assert!(reads.iter().all(|data| data == "data"));
+
+        // Only the first read reaches the backend, the others are served from its copy
+        assert_eq!(store.cache().stats().hits, 7);
+
+        std::fs::remove_dir_all(config.path).unwrap();
+    }
+}
@@ -1,2 +1,20 @@
 mod store;
 pub use store::*;
+
+mod cache;
+pub use cache::*;
+
+mod links;
+pub use links::*;
+
+mod read_ahead;
+pub use read_ahead::*;
+
+mod metrics;
+pub use metrics::*;
+
+mod access;
+pub use access::*;
+
+mod throttle;
+pub use throttle::*;
@@ -0,0 +1,476 @@
+//! Objects shared by several paths of the store.
+//!
+//! The [`LinkedObjectStore`] wraps an [`ObjectStore`] and exposes, in place of each link
+//! object (a path suffixed with [`LINK_EXTENSION`], containing the path of its target), the
+//! object it points to. Reads, listings and copies of a linked path are served from the
+//! target, so the same object can appear in several folders (e.g. the data folders of a
+//! topic and of its copies) without being duplicated in the backend.
+//!
+//! Targets are kept under [`SHARED_FOLDER`] (or [`ARCHIVE_FOLDER`] once demoted to the
+//! archive storage), and are not deleted along with the links
+//! pointing to them: the owners of the links track the references to each target and
+//! delete it once unreferenced.
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use object_store::{
+    CopyOptions, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    ObjectStoreExt, PutMultipartOptions, PutOptions, PutPayload, PutResult, RenameOptions, Result,
+    path::Path,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tracing::{trace, warn};
+
+/// Extension appended to the path of a link object.
+pub const LINK_EXTENSION: &str = "mlink";
+
+/// Folder of the objects shared through links.
+pub const SHARED_FOLDER: &str = "shared";
+
+/// Folder of the objects demoted to the archive storage, linked like the shared objects.
+///
+/// The store does not change the storage class of the objects: the folder is meant to be
+/// bound to an archive class by a lifecycle rule of the bucket.
+pub const ARCHIVE_FOLDER: &str = "archive";
+
+/// Maximum number of link targets remembered, once exceeded the targets are read again
+/// from the link objects
+const MAX_CACHED_TARGETS: usize = 100_000;
+
+/// Number of targets resolved concurrently while listing
+const RESOLVE_CONCURRENCY: usize = 16;
+
+/// Returns the path of a new object in the [`SHARED_FOLDER`], with the given extension.
+pub fn shared_object_path(extension: Option<&str>) -> std::path::PathBuf {
+    std::path::Path::new(SHARED_FOLDER).join(object_name(extension))
+}
+
+/// Returns the path of a new object in the [`ARCHIVE_FOLDER`], with the given extension.
+pub fn archived_object_path(extension: Option<&str>) -> std::path::PathBuf {
+    std::path::Path::new(ARCHIVE_FOLDER).join(object_name(extension))
+}
+
+fn object_name(extension: Option<&str>) -> String {
+    let name = format!("ob_{}", ulid::Ulid::new());
+    match extension {
+        Some(extension) => format!("{name}.{extension}"),
+        None => name,
+    }
+}
+
+fn link_path(location: &Path) -> Path {
+    // Here we use unwrap since appending the extension to a valid path keeps it valid
+    Path::parse(format!("{location}.{LINK_EXTENSION}")).unwrap()
+}
+
+/// Returns the path exposed by the link object at `location`, `None` if it is not a link.
+fn linked_path(location: &Path) -> Option<Path> {
+    let linked = location
+        .as_ref()
+        .strip_suffix(LINK_EXTENSION)?
+        .strip_suffix('.')?;
+    Path::parse(linked).ok()
+}
+
+fn is_not_found(err: &object_store::Error) -> bool {
+    matches!(err, object_store::Error::NotFound { .. })
+}
+
+#[derive(Debug, Clone)]
+pub struct LinkedObjectStore {
+    inner: Arc<dyn ObjectStore>,
+    /// Targets of the links already read, by linked path
+    targets: Arc<Mutex<HashMap<Path, Path>>>,
+}
+
+impl LinkedObjectStore {
+    pub fn new(inner: Arc<dyn ObjectStore>) -> Self {
+        Self {
+            inner,
+            targets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Makes `location` a link to `target`, replacing the object stored at `location`.
+    ///
+    /// The link is written before removing the object, so that `location` can be read at
+    /// any time.
+    pub async fn replace_with_link(&self, location: &Path, target: &Path) -> Result<()> {
+        self.link(location, target).await?;
+        match self.inner.delete(location).await {
+            Err(e) if !is_not_found(&e) => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Writes a link to `target` at `location`.
+    pub async fn link(&self, location: &Path, target: &Path) -> Result<()> {
+        trace!("linking `{location}` to `{target}`");
+        self.forget(location);
+        self.inner
+            .put(&link_path(location), PutPayload::from(target.to_string()))
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the target of `location` if it is a link, `None` if an object is stored at
+    /// `location` or if there is nothing there.
+    pub async fn target(&self, location: &Path) -> Result<Option<Path>> {
+        match self.inner.head(location).await {
+            Ok(_) => Ok(None),
+            Err(e) if is_not_found(&e) => read_target(&self.inner, &self.targets, location).await,
+            Err(e) => Err(e),
+        }
+    }
+
+    fn forget(&self, location: &Path) {
+        self.targets.lock().unwrap().remove(location);
+    }
+
+    fn cached_target(&self, location: &Path) -> Option<Path> {
+        self.targets.lock().unwrap().get(location).cloned()
+    }
+
+    /// Replaces the link objects in `objects` with the objects exposed at their linked
+    /// path. Objects stored at a linked path take precedence over the link.
+    fn resolve(
+        &self,
+        objects: BoxStream<'static, Result<ObjectMeta>>,
+    ) -> BoxStream<'static, Result<ObjectMeta>> {
+        let inner = self.inner.clone();
+        let targets = self.targets.clone();
+
+        stream::once(async move {
+            let objects: Vec<ObjectMeta> = objects.try_collect().await?;
+            resolve_all(&inner, &targets, objects).await
+        })
+        .map_ok(|objects| stream::iter(objects.into_iter().map(Ok)))
+        .try_flatten()
+        .boxed()
+    }
+}
+
+/// Reads the target of the link at `location`, `None` if there is no link.
+async fn read_target(
+    inner: &Arc<dyn ObjectStore>,
+    targets: &Mutex<HashMap<Path, Path>>,
+    location: &Path,
+) -> Result<Option<Path>> {
+    if let Some(target) = targets.lock().unwrap().get(location) {
+        return Ok(Some(target.clone()));
+    }
+
+    let bytes = match inner.get(&link_path(location)).await {
+        Ok(result) => result.bytes().await?,
+        Err(e) if is_not_found(&e) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let target = std::str::from_utf8(&bytes)
+        .ok()
+        .and_then(|target| Path::parse(target).ok())
+        .ok_or_else(|| object_store::Error::Generic {
+            store: "Linked",
+            source: format!("invalid link `{location}`").into(),
+        })?;
+
+    let mut targets = targets.lock().unwrap();
+    if targets.len() >= MAX_CACHED_TARGETS {
+        targets.clear();
+    }
+    targets.insert(location.clone(), target.clone());
+
+    Ok(Some(target))
+}
+
+async fn resolve_all(
+    inner: &Arc<dyn ObjectStore>,
+    targets: &Mutex<HashMap<Path, Path>>,
+    objects: Vec<ObjectMeta>,
+) -> Result<Vec<ObjectMeta>> {
+    let (links, mut objects): (Vec<_>, Vec<_>) = objects
+        .into_iter()
+        .partition(|object| linked_path(&object.location).is_some());
+
+    let stored: HashSet<Path> = objects.iter().map(|o| o.location.clone()).collect();
+    let linked: Vec<Path> = links
+        .iter()
+        .filter_map(|link| linked_path(&link.location))
+        .filter(|location| !stored.contains(location))
+        .collect();
+
+    let resolved: Vec<Option<ObjectMeta>> = stream::iter(linked)
+        .map(|location| async move {
+            let Some(target) = read_target(inner, targets, &location).await? else {
+                return Ok(None);
+            };
+            match inner.head(&target).await {
+                Ok(meta) => Ok(Some(ObjectMeta { location, ..meta })),
+                Err(e) if is_not_found(&e) => {
+                    warn!("link `{location}` points to missing object `{target}`");
+                    Ok(None)
+                }
+                Err(e) => Err(e),
+            }
+        })
+        .buffered(RESOLVE_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    objects.extend(resolved.into_iter().flatten());
+    objects.sort_by(|a, b| a.location.cmp(&b.location));
+
+    Ok(objects)
+}
+
+impl std::fmt::Display for LinkedObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Linked({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for LinkedObjectStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.forget(location);
+        if let Some(linked) = linked_path(location) {
+            self.forget(&linked);
+        }
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOptions,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.forget(location);
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        if let Some(target) = self.cached_target(location) {
+            let mut result = self.inner.get_opts(&target, options).await?;
+            result.meta.location = location.clone();
+            return Ok(result);
+        }
+
+        let err = match self.inner.get_opts(location, options.clone()).await {
+            Err(e) if is_not_found(&e) => e,
+            res => return res,
+        };
+        let Some(target) = read_target(&self.inner, &self.targets, location).await? else {
+            return Err(err);
+        };
+
+        let mut result = self.inner.get_opts(&target, options).await?;
+        result.meta.location = location.clone();
+        Ok(result)
+    }
+
+    /// Deletes both the objects and the links at `locations`, the targets of the links
+    /// are left in place.
+    fn delete_stream(
+        &self,
+        locations: BoxStream<'static, Result<Path>>,
+    ) -> BoxStream<'static, Result<Path>> {
+        let inner = self.inner.clone();
+        let targets = self.targets.clone();
+
+        locations
+            .and_then(move |location| {
+                let inner = inner.clone();
+                let targets = targets.clone();
+                async move {
+                    targets.lock().unwrap().remove(&location);
+                    let link = inner.delete(&link_path(&location)).await;
+                    match (inner.delete(&location).await, link) {
+                        (Ok(()), Ok(())) => Ok(location),
+                        (Ok(()), Err(e)) | (Err(e), Ok(())) if is_not_found(&e) => Ok(location),
+                        (Err(e), _) | (_, Err(e)) => Err(e),
+                    }
+                }
+            })
+            .boxed()
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, Result<ObjectMeta>> {
+        self.resolve(self.inner.list(prefix))
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'static, Result<ObjectMeta>> {
+        // Links sort after their linked path, which may be before the offset
+        let offset = offset.clone();
+        self.resolve(self.inner.list_with_offset(prefix, &offset))
+            .try_filter(move |object| std::future::ready(object.location > offset))
+            .boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let result = self.inner.list_with_delimiter(prefix).await?;
+        Ok(ListResult {
+            common_prefixes: result.common_prefixes,
+            objects: resolve_all(&self.inner, &self.targets, result.objects).await?,
+        })
+    }
+
+    /// Copies the link when `from` is a link, sharing its target.
+    async fn copy_opts(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<()> {
+        self.forget(to);
+
+        let err = match self.cached_target(from) {
+            Some(_) => None,
+            None => match self.inner.copy_opts(from, to, options).await {
+                Err(e) if is_not_found(&e) => Some(e),
+                res => return res,
+            },
+        };
+
+        match self.inner.copy(&link_path(from), &link_path(to)).await {
+            Err(e) if is_not_found(&e) => Err(err.unwrap_or(e)),
+            res => res,
+        }
+    }
+
+    /// Renames the link when `from` is a link, keeping its target.
+    async fn rename_opts(&self, from: &Path, to: &Path, options: RenameOptions) -> Result<()> {
+        self.forget(to);
+
+        let err = match self.cached_target(from) {
+            Some(_) => None,
+            None => match self.inner.rename_opts(from, to, options).await {
+                Err(e) if is_not_found(&e) => Some(e),
+                res => return res,
+            },
+        };
+
+        self.forget(from);
+        match self.inner.rename(&link_path(from), &link_path(to)).await {
+            Err(e) if is_not_found(&e) => Err(err.unwrap_or(e)),
+            res => res,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use object_store::memory::InMemory;
+
+    async fn linked_store() -> (Arc<InMemory>, LinkedObjectStore) {
+        let inner = Arc::new(InMemory::new());
+        let store = LinkedObjectStore::new(inner.clone());
+
+        store
+            .put(&Path::from("shared/ob_1"), PutPayload::from("shared data"))
+            .await
+            .unwrap();
+        store
+            .put(
+                &Path::from("a/data/00000.parquet"),
+                PutPayload::from("data"),
+            )
+            .await
+            .unwrap();
+        store
+            .link(
+                &Path::from("b/data/00000.parquet"),
+                &Path::from("shared/ob_1"),
+            )
+            .await
+            .unwrap();
+
+        (inner, store)
+    }
+
+    async fn listed(store: &LinkedObjectStore, prefix: &str) -> Vec<(String, u64)> {
+        store
+            .list(Some(&Path::from(prefix)))
+            .map_ok(|meta| (meta.location.to_string(), meta.size))
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn read_links() {
+        let (_, store) = linked_store().await;
+        let linked = Path::from("b/data/00000.parquet");
+
+        let result = store.get(&linked).await.unwrap();
+        assert_eq!(result.meta.location, linked);
+        assert_eq!(result.bytes().await.unwrap(), "shared data");
+        assert_eq!(store.head(&linked).await.unwrap().size, 11);
+        assert_eq!(
+            store.target(&linked).await.unwrap(),
+            Some(Path::from("shared/ob_1"))
+        );
+        assert_eq!(
+            store
+                .target(&Path::from("a/data/00000.parquet"))
+                .await
+                .unwrap(),
+            None
+        );
+
+        assert_eq!(
+            listed(&store, "b").await,
+            [("b/data/00000.parquet".to_owned(), 11)]
+        );
+
+        let err = store.get(&Path::from("b/data/00001.parquet")).await;
+        assert!(matches!(err, Err(object_store::Error::NotFound { .. })));
+    }
+
+    #[tokio::test]
+    async fn share_objects() {
+        let (inner, store) = linked_store().await;
+        let source = Path::from("a/data/00000.parquet");
+        let shared = Path::from("shared/ob_2");
+
+        store.copy(&source, &shared).await.unwrap();
+        store.replace_with_link(&source, &shared).await.unwrap();
+        assert!(inner.head(&source).await.is_err());
+        assert_eq!(listed(&store, "a").await, [(source.to_string(), 4)]);
+
+        // Copies of links share the target
+        let copy = Path::from("c/data/00000.parquet");
+        store.copy(&source, &copy).await.unwrap();
+        assert!(inner.head(&copy).await.is_err());
+        assert_eq!(
+            store.get(&copy).await.unwrap().bytes().await.unwrap(),
+            "data"
+        );
+
+        // Deleting the links leaves the target in place
+        store.delete(&source).await.unwrap();
+        store.delete(&copy).await.unwrap();
+        assert!(listed(&store, "a").await.is_empty());
+        assert!(listed(&store, "c").await.is_empty());
+        assert_eq!(
+            store.get(&shared).await.unwrap().bytes().await.unwrap(),
+            "data"
+        );
+    }
+
+    #[tokio::test]
+    async fn stored_objects_take_precedence() {
+        let (_, store) = linked_store().await;
+        let linked = Path::from("b/data/00000.parquet");
+
+        store.put(&linked, PutPayload::from("own")).await.unwrap();
+        assert_eq!(listed(&store, "b").await, [(linked.to_string(), 3)]);
+        assert_eq!(
+            store.get(&linked).await.unwrap().bytes().await.unwrap(),
+            "own"
+        );
+    }
+}
@@ -0,0 +1,694 @@
+//! Store backend instrumentation.
+//!
+//! The [`InstrumentedObjectStore`] wraps the object store driver of a backend and records,
+//! for each kind of [`Operation`], the number of requests, failures, transferred bytes and
+//! a latency histogram in a shared [`StoreMetrics`]. Each request to the backend is also
+//! traced in a `store` span, and the objects read are sampled in the [`AccessLog`] of the
+//! backend.
+//!
+//! A [`SloMonitor`] periodically compares the metrics recorded since its last check against
+//! a [`SloConfig`], reporting the operations that exceed the configured error rate or p99
+//! latency.
+use crate::AccessLog;
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use object_store::{
+    CopyOptions, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    PutMultipartOptions, PutOptions, PutPayload, PutResult, RenameOptions, Result, UploadPart,
+    path::Path,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+/// Upper bounds (in microseconds) of the latency histogram buckets. An additional bucket
+/// collects the requests slower than the last bound.
+const LATENCY_BUCKETS_US: [u64; 13] = [
+    1_000, 2_000, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000, 1_000_000, 2_500_000,
+    5_000_000, 10_000_000,
+];
+
+/// Kind of operation performed on a store backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Get,
+    Head,
+    Put,
+    List,
+    Delete,
+    Copy,
+    Rename,
+}
+
+impl Operation {
+    pub const ALL: [Operation; 7] = [
+        Self::Get,
+        Self::Head,
+        Self::Put,
+        Self::List,
+        Self::Delete,
+        Self::Copy,
+        Self::Rename,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Get => write!(f, "get"),
+            Self::Head => write!(f, "head"),
+            Self::Put => write!(f, "put"),
+            Self::List => write!(f, "list"),
+            Self::Delete => write!(f, "delete"),
+            Self::Copy => write!(f, "copy"),
+            Self::Rename => write!(f, "rename"),
+        }
+    }
+}
+
+#[derive(Default)]
+struct OperationCounters {
+    count: AtomicU64,
+    errors: AtomicU64,
+    bytes: AtomicU64,
+    latency: [AtomicU64; LATENCY_BUCKETS_US.len() + 1],
+}
+
+/// Metrics recorded for a store backend.
+pub struct StoreMetrics {
+    backend: String,
+    operations: [OperationCounters; Operation::ALL.len()],
+    /// Sampled reads of the objects of the backend
+    access: AccessLog,
+}
+
+impl StoreMetrics {
+    pub fn new(backend: impl Into<String>) -> Self {
+        Self {
+            backend: backend.into(),
+            operations: Default::default(),
+            access: AccessLog::new(),
+        }
+    }
+
+    /// Name of the instrumented backend
+    pub fn backend(&self) -> &str {
+        &self.backend
+    }
+
+    /// Log of the objects read from the backend, disabled unless a sample rate is set
+    pub fn access(&self) -> &AccessLog {
+        &self.access
+    }
+
+    /// Records the outcome of an operation. The latency is not recorded for operations
+    /// whose duration can't be measured individually (e.g. batched deletes).
+    pub fn record(&self, operation: Operation, latency: Option<Duration>, bytes: u64, ok: bool) {
+        let counters = &self.operations[operation.index()];
+
+        counters.count.fetch_add(1, Ordering::Relaxed);
+        counters.bytes.fetch_add(bytes, Ordering::Relaxed);
+        if !ok {
+            counters.errors.fetch_add(1, Ordering::Relaxed);
+        }
+
+        if let Some(latency) = latency {
+            let us = latency.as_micros() as u64;
+            let bucket = LATENCY_BUCKETS_US.partition_point(|bound| *bound < us);
+            counters.latency[bucket].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self) -> StoreMetricsSnapshot {
+        StoreMetricsSnapshot {
+            backend: self.backend.clone(),
+            operations: Operation::ALL
+                .iter()
+                .map(|operation| {
+                    let counters = &self.operations[operation.index()];
+                    OperationSnapshot {
+                        operation: *operation,
+                        count: counters.count.load(Ordering::Relaxed),
+                        errors: counters.errors.load(Ordering::Relaxed),
+                        bytes: counters.bytes.load(Ordering::Relaxed),
+                        latency: counters
+                            .latency
+                            .iter()
+                            .map(|c| c.load(Ordering::Relaxed))
+                            .collect(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Debug for StoreMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoreMetrics")
+            .field("backend", &self.backend)
+            .finish()
+    }
+}
+
+/// Point in time copy of the [`StoreMetrics`] of a backend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoreMetricsSnapshot {
+    pub backend: String,
+    pub operations: Vec<OperationSnapshot>,
+}
+
+impl StoreMetricsSnapshot {
+    pub fn operation(&self, operation: Operation) -> Option<&OperationSnapshot> {
+        self.operations.iter().find(|o| o.operation == operation)
+    }
+
+    /// Returns the metrics recorded after `previous` was taken
+    pub fn since(&self, previous: &StoreMetricsSnapshot) -> StoreMetricsSnapshot {
+        StoreMetricsSnapshot {
+            backend: self.backend.clone(),
+            operations: self
+                .operations
+                .iter()
+                .map(|current| match previous.operation(current.operation) {
+                    Some(previous) => current.since(previous),
+                    None => current.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Metrics recorded for a single kind of [`Operation`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationSnapshot {
+    pub operation: Operation,
+    pub count: u64,
+    pub errors: u64,
+    pub bytes: u64,
+    /// Number of requests in each latency bucket, see [`OperationSnapshot::latency_bounds`]
+    pub latency: Vec<u64>,
+}
+
+impl OperationSnapshot {
+    /// Upper bounds of the latency buckets, the last bucket has no upper bound
+    pub fn latency_bounds() -> impl Iterator<Item = Duration> {
+        LATENCY_BUCKETS_US
+            .iter()
+            .map(|us| Duration::from_micros(*us))
+    }
+
+    /// Ratio of failed requests, `None` if no request was performed
+    pub fn error_rate(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.errors as f64 / self.count as f64)
+    }
+
+    /// Estimates the latency below which the `q` fraction of the requests completed.
+    ///
+    /// The estimate is the upper bound of the histogram bucket containing the quantile,
+    /// requests slower than the last bucket bound are reported as [`Duration::MAX`].
+    pub fn latency_quantile(&self, q: f64) -> Option<Duration> {
+        let total: u64 = self.latency.iter().sum();
+        if total == 0 {
+            return None;
+        }
+
+        let target = (q.clamp(0.0, 1.0) * total as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.latency.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                return Some(
+                    LATENCY_BUCKETS_US
+                        .get(bucket)
+                        .map(|us| Duration::from_micros(*us))
+                        .unwrap_or(Duration::MAX),
+                );
+            }
+        }
+
+        None
+    }
+
+    pub fn p99(&self) -> Option<Duration> {
+        self.latency_quantile(0.99)
+    }
+
+    fn since(&self, previous: &OperationSnapshot) -> OperationSnapshot {
+        OperationSnapshot {
+            operation: self.operation,
+            count: self.count.saturating_sub(previous.count),
+            errors: self.errors.saturating_sub(previous.errors),
+            bytes: self.bytes.saturating_sub(previous.bytes),
+            latency: self
+                .latency
+                .iter()
+                .zip(previous.latency.iter())
+                .map(|(current, previous)| current.saturating_sub(*previous))
+                .collect(),
+        }
+    }
+}
+
+/// [`ObjectStore`] recording the operations performed on the wrapped backend.
+#[derive(Debug)]
+pub struct InstrumentedObjectStore {
+    inner: Arc<dyn ObjectStore>,
+    metrics: Arc<StoreMetrics>,
+}
+
+impl InstrumentedObjectStore {
+    pub fn new(inner: Arc<dyn ObjectStore>, metrics: Arc<StoreMetrics>) -> Self {
+        Self { inner, metrics }
+    }
+
+    pub fn metrics(&self) -> &Arc<StoreMetrics> {
+        &self.metrics
+    }
+
+    fn span(&self, operation: Operation, location: &str) -> tracing::Span {
+        span(&self.metrics, operation, location)
+    }
+}
+
+fn span(metrics: &StoreMetrics, operation: Operation, location: &str) -> tracing::Span {
+    tracing::debug_span!(
+        "store",
+        backend = metrics.backend(),
+        %operation,
+        location,
+    )
+}
+
+impl std::fmt::Display for InstrumentedObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Instrumented({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for InstrumentedObjectStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        let bytes = payload.content_length() as u64;
+        let start = Instant::now();
+        let result = self
+            .inner
+            .put_opts(location, payload, opts)
+            .instrument(self.span(Operation::Put, location.as_ref()))
+            .await;
+        self.metrics
+            .record(Operation::Put, Some(start.elapsed()), bytes, result.is_ok());
+        result
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOptions,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        let upload = self.inner.put_multipart_opts(location, opts).await?;
+        Ok(Box::new(InstrumentedUpload {
+            inner: upload,
+            metrics: self.metrics.clone(),
+            location: location.to_string(),
+        }))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let operation = if options.head {
+            Operation::Head
+        } else {
+            Operation::Get
+        };
+
+        let start = Instant::now();
+        let result = self
+            .inner
+            .get_opts(location, options)
+            .instrument(self.span(operation, location.as_ref()))
+            .await;
+        let bytes = match &result {
+            Ok(res) if operation == Operation::Get => {
+                self.metrics.access.record(location);
+                res.range.end - res.range.start
+            }
+            _ => 0,
+        };
+        self.metrics
+            .record(operation, Some(start.elapsed()), bytes, result.is_ok());
+        result
+    }
+
+    fn delete_stream(
+        &self,
+        locations: BoxStream<'static, Result<Path>>,
+    ) -> BoxStream<'static, Result<Path>> {
+        let metrics = self.metrics.clone();
+        self.inner
+            .delete_stream(locations)
+            .inspect(move |res| metrics.record(Operation::Delete, None, 0, res.is_ok()))
+            .boxed()
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, Result<ObjectMeta>> {
+        instrument_list(self.metrics.clone(), self.inner.list(prefix))
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'static, Result<ObjectMeta>> {
+        instrument_list(
+            self.metrics.clone(),
+            self.inner.list_with_offset(prefix, offset),
+        )
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let start = Instant::now();
+        let result = self
+            .inner
+            .list_with_delimiter(prefix)
+            .instrument(self.span(Operation::List, prefix.map_or("", Path::as_ref)))
+            .await;
+        self.metrics
+            .record(Operation::List, Some(start.elapsed()), 0, result.is_ok());
+        result
+    }
+
+    async fn copy_opts(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<()> {
+        let start = Instant::now();
+        let result = self
+            .inner
+            .copy_opts(from, to, options)
+            .instrument(self.span(Operation::Copy, from.as_ref()))
+            .await;
+        self.metrics
+            .record(Operation::Copy, Some(start.elapsed()), 0, result.is_ok());
+        result
+    }
+
+    async fn rename_opts(&self, from: &Path, to: &Path, options: RenameOptions) -> Result<()> {
+        let start = Instant::now();
+        let result = self
+            .inner
+            .rename_opts(from, to, options)
+            .instrument(self.span(Operation::Rename, from.as_ref()))
+            .await;
+        self.metrics
+            .record(Operation::Rename, Some(start.elapsed()), 0, result.is_ok());
+        result
+    }
+}
+
+/// Records a listing once the stream is consumed, failing if any listed entry failed
+fn instrument_list(
+    metrics: Arc<StoreMetrics>,
+    listing: BoxStream<'static, Result<ObjectMeta>>,
+) -> BoxStream<'static, Result<ObjectMeta>> {
+    let start = Instant::now();
+    let failed = Arc::new(Mutex::new(false));
+
+    let on_item = failed.clone();
+    let record = stream::once(async move {
+        let ok = !*failed.lock().unwrap();
+        metrics.record(Operation::List, Some(start.elapsed()), 0, ok);
+    })
+    .filter_map(|_| futures::future::ready(None));
+
+    listing
+        .inspect(move |res| {
+            if res.is_err() {
+                *on_item.lock().unwrap() = true;
+            }
+        })
+        .chain(record)
+        .boxed()
+}
+
+/// Multipart upload recording each uploaded part as a put operation
+#[derive(Debug)]
+struct InstrumentedUpload {
+    inner: Box<dyn MultipartUpload>,
+    metrics: Arc<StoreMetrics>,
+    location: String,
+}
+
+#[async_trait]
+impl MultipartUpload for InstrumentedUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        let bytes = data.content_length() as u64;
+        let metrics = self.metrics.clone();
+        let part = self.inner.put_part(data);
+        let span = span(&metrics, Operation::Put, &self.location);
+        Box::pin(
+            async move {
+                let start = Instant::now();
+                let result = part.await;
+                metrics.record(Operation::Put, Some(start.elapsed()), bytes, result.is_ok());
+                result
+            }
+            .instrument(span),
+        )
+    }
+
+    async fn complete(&mut self) -> Result<PutResult> {
+        self.inner.complete().await
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.inner.abort().await
+    }
+}
+
+/// Service level objectives of a store backend. Unset objectives are not checked.
+#[derive(Debug, Clone, Default)]
+pub struct SloConfig {
+    /// Maximum ratio of failed requests
+    pub max_error_rate: Option<f64>,
+
+    /// Maximum p99 latency of the requests
+    pub max_p99_latency: Option<Duration>,
+
+    /// Minimum number of requests required to evaluate the objectives of an operation,
+    /// avoids alarms caused by a handful of requests
+    pub min_requests: u64,
+}
+
+impl SloConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.max_error_rate.is_some() || self.max_p99_latency.is_some()
+    }
+}
+
+/// Objective exceeded by an operation of a store backend.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SloViolation {
+    ErrorRate {
+        backend: String,
+        operation: Operation,
+        error_rate: f64,
+        threshold: f64,
+    },
+    Latency {
+        backend: String,
+        operation: Operation,
+        p99: Duration,
+        threshold: Duration,
+    },
+}
+
+impl std::fmt::Display for SloViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ErrorRate {
+                backend,
+                operation,
+                error_rate,
+                threshold,
+            } => write!(
+                f,
+                "store backend `{backend}`: `{operation}` error rate {:.2}% exceeds {:.2}%",
+                error_rate * 100.0,
+                threshold * 100.0
+            ),
+            Self::Latency {
+                backend,
+                operation,
+                p99,
+                threshold,
+            } => write!(
+                f,
+                "store backend `{backend}`: `{operation}` p99 latency above {:?} exceeds {:?}",
+                // The histogram only provides a lower bound for very slow requests
+                if *p99 == Duration::MAX {
+                    Duration::from_micros(LATENCY_BUCKETS_US[LATENCY_BUCKETS_US.len() - 1])
+                } else {
+                    *p99
+                },
+                threshold
+            ),
+        }
+    }
+}
+
+/// Checks the [`SloConfig`] against the metrics recorded between consecutive checks.
+pub struct SloMonitor {
+    metrics: Arc<StoreMetrics>,
+    config: SloConfig,
+    last: Mutex<StoreMetricsSnapshot>,
+}
+
+impl SloMonitor {
+    pub fn new(metrics: Arc<StoreMetrics>, config: SloConfig) -> Self {
+        let last = Mutex::new(metrics.snapshot());
+        Self {
+            metrics,
+            config,
+            last,
+        }
+    }
+
+    /// Returns the objectives exceeded since the previous check
+    pub fn check(&self) -> Vec<SloViolation> {
+        let current = self.metrics.snapshot();
+        let window = {
+            let mut last = self.last.lock().unwrap();
+            let window = current.since(&last);
+            *last = current;
+            window
+        };
+
+        let mut violations = Vec::new();
+
+        for op in &window.operations {
+            if op.count == 0 || op.count < self.config.min_requests {
+                continue;
+            }
+
+            if let (Some(threshold), Some(error_rate)) =
+                (self.config.max_error_rate, op.error_rate())
+                && error_rate > threshold
+            {
+                violations.push(SloViolation::ErrorRate {
+                    backend: window.backend.clone(),
+                    operation: op.operation,
+                    error_rate,
+                    threshold,
+                });
+            }
+
+            if let (Some(threshold), Some(p99)) = (self.config.max_p99_latency, op.p99())
+                && p99 > threshold
+            {
+                violations.push(SloViolation::Latency {
+                    backend: window.backend.clone(),
+                    operation: op.operation,
+                    p99,
+                    threshold,
+                });
+            }
+        }
+
+        violations
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use object_store::ObjectStoreExt;
+    use object_store::memory::InMemory;
+
+    #[tokio::test]
+    async fn instrumented_operations() {
+        let metrics = Arc::new(StoreMetrics::new("memory"));
+        let store = InstrumentedObjectStore::new(Arc::new(InMemory::new()), metrics.clone());
+
+        let location = Path::from("data/object");
+        store
+            .put(&location, PutPayload::from_static(b"0123456789"))
+            .await
+            .unwrap();
+        store.get(&location).await.unwrap().bytes().await.unwrap();
+        store.get_range(&location, 0..4).await.unwrap();
+        store.head(&location).await.unwrap();
+        assert!(store.get(&Path::from("missing")).await.is_err());
+        store.delete(&location).await.unwrap();
+
+        let snapshot = metrics.snapshot();
+
+        let put = snapshot.operation(Operation::Put).unwrap();
+        assert_eq!((put.count, put.errors, put.bytes), (1, 0, 10));
+
+        let get = snapshot.operation(Operation::Get).unwrap();
+        assert_eq!((get.count, get.errors, get.bytes), (3, 1, 14));
+        assert_eq!(get.latency.iter().sum::<u64>(), 3);
+
+        assert_eq!(snapshot.operation(Operation::Head).unwrap().count, 1);
+        assert_eq!(snapshot.operation(Operation::Delete).unwrap().count, 1);
+    }
+
+    #[test]
+    fn latency_quantile() {
+        let metrics = StoreMetrics::new("test");
+        for _ in 0..98 {
+            metrics.record(Operation::Get, Some(Duration::from_micros(500)), 0, true);
+        }
+        metrics.record(Operation::Get, Some(Duration::from_millis(40)), 0, true);
+        metrics.record(Operation::Get, Some(Duration::from_secs(60)), 0, true);
+
+        let snapshot = metrics.snapshot();
+        let get = snapshot.operation(Operation::Get).unwrap();
+        assert_eq!(get.latency_quantile(0.5), Some(Duration::from_millis(1)));
+        assert_eq!(get.p99(), Some(Duration::from_millis(50)));
+        assert_eq!(get.latency_quantile(1.0), Some(Duration::MAX));
+
+        assert_eq!(snapshot.operation(Operation::Put).unwrap().p99(), None);
+    }
+
+    #[test]
+    fn slo_monitor() {
+        let metrics = Arc::new(StoreMetrics::new("test"));
+        let monitor = SloMonitor::new(
+            metrics.clone(),
+            SloConfig {
+                max_error_rate: Some(0.1),
+                max_p99_latency: Some(Duration::from_millis(100)),
+                min_requests: 10,
+            },
+        );
+
+        // Not enough requests to evaluate the objectives
+        metrics.record(Operation::Get, Some(Duration::from_secs(1)), 0, false);
+        assert!(monitor.check().is_empty());
+
+        for i in 0..20 {
+            metrics.record(Operation::Get, Some(Duration::from_secs(1)), 0, i % 2 == 0);
+        }
+        let violations = monitor.check();
+        assert_eq!(violations.len(), 2);
+        assert!(matches!(
+            violations[0],
+            SloViolation::ErrorRate { error_rate, .. } if error_rate == 0.5
+        ));
+        assert!(matches!(violations[1], SloViolation::Latency { .. }));
+
+        // Only the requests performed after the previous check are evaluated
+        for _ in 0..20 {
+            metrics.record(Operation::Get, Some(Duration::from_millis(1)), 0, true);
+        }
+        assert!(monitor.check().is_empty());
+    }
+}
@@ -0,0 +1,449 @@
+//! Read-ahead of objects scanned in sequence.
+//!
+//! The [`ReadAheadObjectStore`] wraps an [`ObjectStore`] and, when the objects of a known
+//! scan are read in order, starts fetching the beginning of the following objects of the
+//! scan concurrently. This overlaps the network latency of the backend with the decoding of
+//! the object being read.
+//!
+//! Scans (e.g. the data files searched by a query) are declared with
+//! [`ReadAheadObjectStore::plan`]. Only the first [`DEFAULT_RANGE_SIZE`] bytes of each object
+//! are fetched ahead of time, reads outside of this range are served by the backend.
+use crate::cache::{is_whole_object_read, serve_from_copy};
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
+use object_store::{
+    Attributes, CopyOptions, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta,
+    ObjectStore, PutMultipartOptions, PutOptions, PutPayload, PutResult, RenameOptions, Result,
+    path::Path,
+};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tracing::trace;
+
+/// Name used to identify the read-ahead layer in object store errors
+const STORE_NAME: &str = "ReadAhead";
+
+/// Maximum number of objects tracked across all the known scans, once exceeded the
+/// scans are forgotten
+const MAX_PLANNED_OBJECTS: usize = 100_000;
+
+/// Maximum number of recently read objects remembered to detect sequential reads
+const MAX_RECENT_READS: usize = 64;
+
+/// Default number of bytes fetched ahead of time from the beginning of each object
+pub const DEFAULT_RANGE_SIZE: u64 = 8 * 1024 * 1024;
+
+struct Object {
+    meta: ObjectMeta,
+    attributes: Attributes,
+    /// Bytes at the beginning of the object
+    data: Bytes,
+}
+
+/// An object fetched ahead of time, `None` if the fetch failed
+type Prefetch = Shared<BoxFuture<'static, Option<Arc<Object>>>>;
+
+#[derive(Default)]
+struct State {
+    /// Object following each object of the known scans
+    next: HashMap<Path, Path>,
+    /// Object preceding each object of the known scans
+    prev: HashMap<Path, Path>,
+    /// Objects of the known scans read recently, in read order
+    recent: VecDeque<Path>,
+    /// Objects fetched (or being fetched) ahead of time
+    prefetched: HashMap<Path, Prefetch>,
+    /// Prefetched objects in fetch order, used to discard the oldest ones
+    order: VecDeque<Path>,
+}
+
+impl State {
+    fn plan(&mut self, locations: &[Path]) {
+        if self.next.len() + locations.len() > MAX_PLANNED_OBJECTS {
+            self.next.clear();
+            self.prev.clear();
+        }
+
+        for pair in locations.windows(2) {
+            self.next.insert(pair[0].clone(), pair[1].clone());
+            self.prev.insert(pair[1].clone(), pair[0].clone());
+        }
+    }
+
+    /// Records a read of `location`, returning true if the object preceding it in its scan
+    /// was read recently.
+    fn record_read(&mut self, location: &Path) -> bool {
+        if !self.next.contains_key(location) && !self.prev.contains_key(location) {
+            return false;
+        }
+
+        let sequential = self
+            .prev
+            .get(location)
+            .is_some_and(|prev| self.recent.contains(prev));
+
+        if !self.recent.contains(location) {
+            if self.recent.len() == MAX_RECENT_READS {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(location.clone());
+        }
+
+        sequential
+    }
+
+    fn discard(&mut self, location: &Path) {
+        if self.prefetched.remove(location).is_some() {
+            self.order.retain(|l| l != location);
+        }
+    }
+}
+
+/// [`ObjectStore`] fetching ahead of time the beginning of the objects that follow the one
+/// being read in a known scan.
+#[derive(Debug)]
+pub struct ReadAheadObjectStore {
+    inner: Arc<dyn ObjectStore>,
+    /// Number of objects fetched concurrently ahead of the one being read
+    parallelism: usize,
+    /// Number of bytes fetched from the beginning of each object
+    range_size: u64,
+    state: Arc<Mutex<State>>,
+}
+
+impl ReadAheadObjectStore {
+    pub fn new(inner: Arc<dyn ObjectStore>, parallelism: usize) -> Self {
+        Self {
+            inner,
+            parallelism,
+            range_size: DEFAULT_RANGE_SIZE,
+            state: Arc::new(Mutex::new(State::default())),
+        }
+    }
+
+    /// Sets the number of bytes fetched ahead of time from the beginning of each object
+    pub fn with_range_size(mut self, range_size: u64) -> Self {
+        self.range_size = range_size;
+        self
+    }
+
+    pub fn parallelism(&self) -> usize {
+        self.parallelism
+    }
+
+    /// Declares that the provided objects are going to be read in order.
+    pub fn plan(&self, locations: impl IntoIterator<Item = Path>) {
+        let locations: Vec<Path> = locations.into_iter().collect();
+        self.state.lock().unwrap().plan(&locations);
+    }
+
+    /// Returns true if the object has been fetched (or is being fetched) ahead of time
+    pub fn is_prefetched(&self, location: &Path) -> bool {
+        self.state.lock().unwrap().prefetched.contains_key(location)
+    }
+
+    /// Starts fetching the objects following `location` in its scan
+    fn read_ahead(&self, location: &Path) {
+        // Fetches run in background tasks, without a runtime there is nothing to overlap
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+
+        let mut state = self.state.lock().unwrap();
+        let mut current = location.clone();
+
+        for _ in 0..self.parallelism {
+            let Some(next) = state.next.get(&current).cloned() else {
+                break;
+            };
+
+            if !state.prefetched.contains_key(&next) {
+                trace!("reading ahead `{next}`");
+
+                let inner = self.inner.clone();
+                let target = next.clone();
+                let options = GetOptions {
+                    range: Some((0..self.range_size).into()),
+                    ..Default::default()
+                };
+                let fetch = runtime.spawn(async move {
+                    let result = inner.get_opts(&target, options).await.ok()?;
+                    let meta = result.meta.clone();
+                    let attributes = result.attributes.clone();
+                    let data = result.bytes().await.ok()?;
+                    Some(Arc::new(Object {
+                        meta,
+                        attributes,
+                        data,
+                    }))
+                });
+
+                state.prefetched.insert(
+                    next.clone(),
+                    fetch.map(|res| res.ok().flatten()).boxed().shared(),
+                );
+                state.order.push_back(next.clone());
+            }
+
+            current = next;
+        }
+
+        // Keep in memory the objects fetched ahead and the ones currently being read, each
+        // one holding at most `range_size` bytes
+        while state.order.len() > 2 * self.parallelism {
+            if let Some(oldest) = state.order.pop_front() {
+                state.prefetched.remove(&oldest);
+            }
+        }
+    }
+
+    fn discard(&self, location: &Path) {
+        self.state.lock().unwrap().discard(location);
+    }
+}
+
+impl std::fmt::Display for ReadAheadObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({})", STORE_NAME, self.inner)
+    }
+}
+
+impl std::fmt::Debug for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("State")
+            .field("planned", &self.next.len())
+            .field("prefetched", &self.order)
+            .finish()
+    }
+}
+
+/// Returns true if the bytes requested by `options` were fetched ahead of time
+fn covers(object: &Object, options: &GetOptions) -> bool {
+    let range = match &options.range {
+        Some(range) => range.as_range(object.meta.size),
+        None => Ok(0..object.meta.size),
+    };
+    range.is_ok_and(|range| range.end <= object.data.len() as u64)
+}
+
+#[async_trait]
+impl ObjectStore for ReadAheadObjectStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.discard(location);
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOptions,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.discard(location);
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        if !is_whole_object_read(&options) {
+            return self.inner.get_opts(location, options).await;
+        }
+
+        let (prefetch, sequential) = {
+            let mut state = self.state.lock().unwrap();
+            (
+                state.prefetched.get(location).cloned(),
+                state.record_read(location),
+            )
+        };
+
+        // Objects are fetched ahead only once a scan is being read in order
+        if sequential {
+            self.read_ahead(location);
+        }
+
+        if let Some(prefetch) = prefetch
+            && let Some(object) = prefetch.await
+            && covers(&object, &options)
+        {
+            trace!("serving `{location}` from read-ahead");
+            return serve_from_copy(
+                STORE_NAME,
+                object.meta.clone(),
+                object.attributes.clone(),
+                object.data.clone(),
+                &options,
+            );
+        }
+
+        self.inner.get_opts(location, options).await
+    }
+
+    fn delete_stream(
+        &self,
+        locations: BoxStream<'static, Result<Path>>,
+    ) -> BoxStream<'static, Result<Path>> {
+        let state = self.state.clone();
+        self.inner
+            .delete_stream(locations)
+            .inspect_ok(move |location| state.lock().unwrap().discard(location))
+            .boxed()
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'static, Result<ObjectMeta>> {
+        self.inner.list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy_opts(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<()> {
+        self.discard(to);
+        self.inner.copy_opts(from, to, options).await
+    }
+
+    async fn rename_opts(&self, from: &Path, to: &Path, options: RenameOptions) -> Result<()> {
+        self.discard(from);
+        self.discard(to);
+        self.inner.rename_opts(from, to, options).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use object_store::{ObjectStoreExt, memory::InMemory};
+
+    async fn populated_store(count: usize) -> (Arc<InMemory>, Vec<Path>) {
+        let inner = Arc::new(InMemory::new());
+        let mut locations = Vec::new();
+        for i in 0..count {
+            let location = Path::from(format!("data/{i:05}.parquet"));
+            inner
+                .put(&location, PutPayload::from(format!("object {i}")))
+                .await
+                .unwrap();
+            locations.push(location);
+        }
+        (inner, locations)
+    }
+
+    async fn read(store: &ReadAheadObjectStore, location: &Path) -> Bytes {
+        store.get(location).await.unwrap().bytes().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn read_ahead_planned_scan() {
+        let (inner, locations) = populated_store(6).await;
+        let store = ReadAheadObjectStore::new(inner.clone(), 2);
+        store.plan(locations.clone());
+
+        // A single read is not enough to detect a scan
+        assert_eq!(read(&store, &locations[0]).await, "object 0");
+        assert!(!store.is_prefetched(&locations[1]));
+
+        assert_eq!(read(&store, &locations[1]).await, "object 1");
+        assert!(store.is_prefetched(&locations[2]));
+        assert!(store.is_prefetched(&locations[3]));
+        assert!(!store.is_prefetched(&locations[4]));
+
+        // Objects changed behind the store are served from the prefetched copy
+        let prefetch = store.state.lock().unwrap().prefetched[&locations[2]].clone();
+        assert!(prefetch.await.is_some());
+        inner
+            .put(&locations[2], PutPayload::from_static(b"changed"))
+            .await
+            .unwrap();
+        assert_eq!(read(&store, &locations[2]).await, "object 2");
+        assert!(store.is_prefetched(&locations[4]));
+
+        // Ranged reads are served from the prefetched copy too
+        assert_eq!(
+            store.get_range(&locations[3], 0..6).await.unwrap(),
+            Bytes::from_static(b"object")
+        );
+
+        // Writes through the store discard the prefetched copy
+        store
+            .put(&locations[4], PutPayload::from_static(b"new"))
+            .await
+            .unwrap();
+        assert!(!store.is_prefetched(&locations[4]));
+        assert_eq!(read(&store, &locations[4]).await, "new");
+    }
+
+    #[tokio::test]
+    async fn read_ahead_requires_sequential_reads() {
+        let (inner, locations) = populated_store(4).await;
+        let store = ReadAheadObjectStore::new(inner, 4);
+
+        // Listings do not declare scans
+        let listed: Vec<ObjectMeta> = store
+            .list(Some(&Path::from("data")))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 4);
+        read(&store, &locations[0]).await;
+        read(&store, &locations[1]).await;
+        assert!(!store.is_prefetched(&locations[2]));
+
+        // Planned objects read out of order are not fetched ahead
+        store.plan(locations.clone());
+        read(&store, &locations[3]).await;
+        read(&store, &locations[1]).await;
+        assert!(locations.iter().all(|l| !store.is_prefetched(l)));
+    }
+
+    #[tokio::test]
+    async fn read_ahead_bounded_range() {
+        let (inner, locations) = populated_store(3).await;
+        let store = ReadAheadObjectStore::new(inner.clone(), 1).with_range_size(6);
+        store.plan(locations.clone());
+
+        read(&store, &locations[0]).await;
+        read(&store, &locations[1]).await;
+        let prefetch = store.state.lock().unwrap().prefetched[&locations[2]].clone();
+        assert_eq!(prefetch.await.unwrap().data, "object");
+
+        inner
+            .put(&locations[2], PutPayload::from_static(b"changed 2"))
+            .await
+            .unwrap();
+
+        // Only the prefetched range is served from the prefetched copy
+        assert_eq!(
+            store.get_range(&locations[2], 0..6).await.unwrap(),
+            Bytes::from_static(b"object")
+        );
+        assert_eq!(read(&store, &locations[2]).await, "changed 2");
+    }
+
+    #[tokio::test]
+    async fn read_ahead_bounded() {
+        let (inner, locations) = populated_store(10).await;
+        let store = ReadAheadObjectStore::new(inner, 1);
+        store.plan(locations.clone());
+
+        for location in &locations {
+            read(&store, location).await;
+        }
+
+        let prefetched = locations.iter().filter(|l| store.is_prefetched(l)).count();
+        assert_eq!(prefetched, 2);
+    }
+}
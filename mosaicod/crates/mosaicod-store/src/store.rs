@@ -2,16 +2,22 @@
 //! with S3-compatible object storage services providing
 //! essential CRUD (Create, Read, Update, Delete) methods for byte-level data access.
 
+use crate::cache::{CacheConfig, CachedObjectStore, DiskCache};
+use crate::links::LinkedObjectStore;
+use crate::metrics::{InstrumentedObjectStore, StoreMetrics};
+use crate::read_ahead::ReadAheadObjectStore;
 use datafusion::execution::object_store::{DefaultObjectStoreRegistry, ObjectStoreRegistry};
 use futures::stream::TryStreamExt;
-use log::trace;
-use mosaicod_core::traits;
+use mosaicod_core::{params, traits};
 use object_store::{
-    ObjectStore, ObjectStoreExt, PutPayload, aws::AmazonS3Builder, local::LocalFileSystem,
+    ObjectStore, ObjectStoreExt, PutMode, PutPayload, WriteMultipart, aws::AmazonS3Builder,
+    local::LocalFileSystem, memory::InMemory, prefix::PrefixStore,
 };
 use parquet::arrow::async_reader::ParquetObjectReader;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
+use tracing::trace;
 use url::Url;
 
 #[derive(Error, Debug)]
@@ -24,8 +30,75 @@ pub enum Error {
     InvalidBucket(String),
     #[error("the provided endpoint `{0}` is not valid")]
     InvalidEndpoint(String),
+    #[error("the provided prefix `{0}` is not valid")]
+    InvalidPrefix(String),
     #[error("unable to create directory `{0}`: {1}")]
     DirCreationFailed(String, std::io::Error),
+    #[error("invalid range of {1} bytes starting at offset {0}")]
+    InvalidRange(u64, u64),
+    #[error("filesystem error on `{0}`: {1}")]
+    FilesystemError(String, std::io::Error),
+}
+
+impl Error {
+    /// Returns true if the store backend is temporarily unavailable, so the operation can
+    /// be retried.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::BackendError(err) => is_transient(err),
+            _ => false,
+        }
+    }
+}
+
+/// Returns true if `err` is caused by the store backend being temporarily unavailable
+/// (e.g. unreachable or answering with server errors once the retries are exhausted).
+pub fn is_transient(err: &object_store::Error) -> bool {
+    let object_store::Error::Generic { source, .. } = err else {
+        return false;
+    };
+
+    let mut cause: Option<&(dyn std::error::Error + 'static)> = Some(source.as_ref());
+    while let Some(err) = cause {
+        let http_failure = err
+            .downcast_ref::<object_store::client::HttpError>()
+            .is_some_and(|err| {
+                use object_store::client::HttpErrorKind;
+                matches!(
+                    err.kind(),
+                    HttpErrorKind::Connect
+                        | HttpErrorKind::Request
+                        | HttpErrorKind::Timeout
+                        | HttpErrorKind::Interrupted
+                )
+            });
+
+        let io_failure = err.downcast_ref::<std::io::Error>().is_some_and(|err| {
+            use std::io::ErrorKind;
+            matches!(
+                err.kind(),
+                ErrorKind::ConnectionRefused
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::NotConnected
+                    | ErrorKind::TimedOut
+            )
+        });
+
+        // Status codes returned by the backend are exposed only through the message
+        let message = err.to_string();
+        let server_failure = message
+            .strip_prefix("Server returned non-2xx status code: ")
+            .is_some_and(|status| status.starts_with('5') || status.starts_with("429"));
+
+        if http_failure || io_failure || server_failure {
+            return true;
+        }
+
+        cause = err.source();
+    }
+
+    false
 }
 
 impl mosaicod_core::error::PublicError for Error {
@@ -43,15 +116,28 @@ impl mosaicod_core::error::PublicError for Error {
             Self::InvalidBucket(_) => {
                 Error::invalid_configuration("object store bucket".to_owned(), self.to_string())
             }
+            Self::InvalidPrefix(_) => {
+                Error::invalid_configuration("object store prefix".to_owned(), self.to_string())
+            }
             Self::DirCreationFailed(_, _) => Error::invalid_configuration(
                 "object store local directory".to_owned(),
                 self.to_string(),
             ),
+            Self::InvalidRange(_, _) => Error::bad_request(self.to_string()),
+            _ if self.is_transient() => Error::unavailable("store".to_owned()),
             _ => Error::internal(Some("store failed".to_owned())),
         }
     }
 }
 
+/// Returns true if `name` is the name of the temporary file of a write to the local
+/// filesystem, i.e. the name of the object followed by `#` and a number.
+fn is_staged_file(name: &str) -> bool {
+    name.rsplit_once('#').is_some_and(|(object, suffix)| {
+        !object.is_empty() && !suffix.is_empty() && suffix.bytes().all(|b| b.is_ascii_digit())
+    })
+}
+
 /// Converts a filesystem path to an object_store Path.
 #[inline]
 fn to_object_path(path: impl AsRef<std::path::Path>) -> object_store::path::Path {
@@ -68,12 +154,24 @@ fn is_valid_bucket_name(bucket: &str) -> bool {
         == 0
 }
 
+/// Normalizes a prefix of the object keys, removing the leading and trailing slashes.
+///
+/// Returns `None` if the prefix contains empty, `.` or `..` segments.
+fn normalize_prefix(prefix: &str) -> Option<String> {
+    let prefix = prefix.trim_matches('/');
+    prefix
+        .split('/')
+        .all(|segment| !matches!(segment, "" | "." | ".."))
+        .then(|| prefix.to_owned())
+}
+
 /// A configuration builder for initializing a storage backend.
 #[derive(Debug, Clone)]
 pub struct Builder {
     /// The base URL of the storage service.
     ///
-    /// To configure the store to use local filesystem use `file:///`
+    /// To configure the store to use local filesystem use `file:///`, to keep the objects
+    /// in memory use `memory://`
     pub endpoint: url::Url,
 
     /// The name of the specific bucket to access.
@@ -88,6 +186,21 @@ pub struct Builder {
     ///
     /// This field is **required** to work with remote object store.
     pub secret_key: Option<String>,
+
+    /// Region of the remote object store, if not set the backend default is used.
+    pub region: Option<String>,
+
+    /// Prefix prepended to every object key, allowing multiple deployments to share the
+    /// same bucket.
+    pub prefix: Option<String>,
+
+    /// Optional local disk cache placed in front of remote object stores.
+    ///
+    /// The cache is ignored when the store works with the local filesystem.
+    pub cache: Option<CacheConfig>,
+
+    /// Number of objects fetched concurrently ahead of a scan, `0` disables read-ahead.
+    pub read_ahead: usize,
 }
 
 impl Builder {
@@ -97,6 +210,10 @@ impl Builder {
             bucket,
             access_key: None,
             secret_key: None,
+            region: None,
+            prefix: None,
+            cache: None,
+            read_ahead: 0,
         }
     }
 
@@ -107,12 +224,47 @@ impl Builder {
         self
     }
 
+    /// Configure the region of the remote object store
+    pub fn with_region(mut self, region: String) -> Self {
+        self.region = Some(region);
+        self
+    }
+
+    /// Configure the prefix prepended to every object key (e.g. `deployments/staging`)
+    pub fn with_prefix(mut self, prefix: String) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    /// Configure a local disk cache in front of the remote object store
+    pub fn with_cache(mut self, config: CacheConfig) -> Self {
+        self.cache = Some(config);
+        self
+    }
+
+    /// Configure the number of objects fetched concurrently ahead of a scan
+    pub fn with_read_ahead(mut self, parallelism: usize) -> Self {
+        self.read_ahead = parallelism;
+        self
+    }
+
     /// Create a new store backend
     pub fn build(self) -> Result<Store, Error> {
         if !is_valid_bucket_name(&self.bucket) {
             return Err(Error::InvalidBucket(self.bucket));
         }
 
+        let prefix = match self.prefix {
+            Some(prefix) => Some(normalize_prefix(&prefix).ok_or(Error::InvalidPrefix(prefix))?),
+            None => None,
+        };
+
+        // Objects of in-memory stores are not shared, so the bucket and the prefix are
+        // not used to separate them
+        if self.endpoint.scheme() == "memory" {
+            return Ok(Store::in_memory().with_read_ahead(self.read_ahead));
+        }
+
         if self.endpoint.scheme() == "file" {
             // If the user provided a `file:///some/local/path` the
             // url will contain a domain == "some"
@@ -130,8 +282,11 @@ impl Builder {
                 .to_file_path()
                 .map_err(|_| Error::InvalidEndpoint(self.endpoint.to_string()))?;
 
-            let path = path.join(self.bucket);
-            return Store::try_from_filesystem(&path);
+            let mut path = path.join(self.bucket);
+            if let Some(prefix) = prefix {
+                path = path.join(prefix);
+            }
+            return Ok(Store::try_from_filesystem(&path)?.with_read_ahead(self.read_ahead));
         }
 
         let Some(access_key) = self.access_key else {
@@ -142,7 +297,23 @@ impl Builder {
             return Err(Error::MissingCredentials("secret key".to_owned()));
         };
 
-        Store::try_from_s3_store(self.endpoint, self.bucket, access_key, secret_key)
+        let mut s3 = AmazonS3Builder::new()
+            .with_endpoint(self.endpoint.to_string())
+            .with_bucket_name(&self.bucket)
+            .with_access_key_id(access_key)
+            .with_secret_access_key(secret_key)
+            .with_allow_http(true);
+        if let Some(region) = self.region {
+            s3 = s3.with_region(region);
+        }
+
+        let mut store = Store::try_from_s3_builder(self.endpoint, self.bucket, s3, prefix)?;
+
+        if let Some(config) = self.cache {
+            store = store.with_cache(config)?;
+        }
+
+        Ok(store.with_read_ahead(self.read_ahead))
     }
 }
 
@@ -150,6 +321,8 @@ impl Builder {
 pub enum Target {
     Filesystem(std::path::PathBuf),
     S3Compatible(url::Url),
+    /// Objects kept in memory, lost when the store is dropped
+    Memory,
 }
 
 /// Implements the object storage client for the application.
@@ -162,8 +335,19 @@ pub struct Store {
 
     target: Target,
 
+    /// Prefix prepended to the object keys in the bucket, see [`Builder::with_prefix`]
+    prefix: Option<String>,
+
     driver: Arc<dyn ObjectStore>,
     registry: Arc<dyn ObjectStoreRegistry>,
+
+    /// Layer resolving the links, below the cache layers
+    links: Arc<LinkedObjectStore>,
+
+    read_ahead: Option<Arc<ReadAheadObjectStore>>,
+
+    /// Operations performed on the backend, excluding the reads served by the cache layers
+    metrics: Arc<StoreMetrics>,
 }
 
 pub type StoreRef = Arc<Store>;
@@ -176,7 +360,13 @@ impl Store {
             Error::DirCreationFailed(path.as_ref().to_string_lossy().to_string(), e)
         })?;
 
-        let storage = Arc::new(LocalFileSystem::new_with_prefix(path.as_ref())?);
+        let metrics = Arc::new(StoreMetrics::new("filesystem"));
+        let storage = Arc::new(LinkedObjectStore::new(Arc::new(
+            InstrumentedObjectStore::new(
+                Arc::new(LocalFileSystem::new_with_prefix(path.as_ref())?),
+                metrics.clone(),
+            ),
+        )));
 
         // Here we use unwrap since `file://` IS a valid url
         let bucket_url = Url::parse("file://").unwrap();
@@ -188,21 +378,71 @@ impl Store {
         Ok(Self {
             url_schema: bucket_url,
             target: Target::Filesystem(path.as_ref().to_owned()),
+            prefix: None,
             driver: storage.clone(),
+            links: storage,
             registry,
+            read_ahead: None,
+            metrics,
         })
     }
 
+    /// Create a new store keeping the objects in memory, lost when the store is dropped.
+    ///
+    /// Meant for tests and demos that do not need to provision an object storage service.
+    pub fn in_memory() -> Self {
+        let metrics = Arc::new(StoreMetrics::new("memory"));
+        let storage = Arc::new(LinkedObjectStore::new(Arc::new(
+            InstrumentedObjectStore::new(Arc::new(InMemory::new()), metrics.clone()),
+        )));
+
+        // Here we use unwrap since `memory://mosaico` IS a valid url
+        let bucket_url = Url::parse("memory://mosaico").unwrap();
+
+        // Create object store registry (for datafusion support)
+        let registry = Arc::new(DefaultObjectStoreRegistry::default());
+        registry.register_store(&bucket_url, storage.clone());
+
+        Self {
+            url_schema: bucket_url,
+            target: Target::Memory,
+            prefix: None,
+            driver: storage.clone(),
+            links: storage,
+            registry,
+            read_ahead: None,
+            metrics,
+        }
+    }
+
     /// Create a new store configured to work with an s3-compatible system
     pub fn try_from_s3_store(
         endpoint: url::Url,
         bucket: String,
         access_key: String,
         secret_key: String,
+    ) -> Result<Self, Error> {
+        let s3 = AmazonS3Builder::new()
+            .with_endpoint(endpoint.to_string())
+            .with_bucket_name(&bucket)
+            .with_access_key_id(access_key)
+            .with_secret_access_key(secret_key)
+            .with_allow_http(true);
+
+        Self::try_from_s3_builder(endpoint, bucket, s3, None)
+    }
+
+    /// Create a new store working with the s3-compatible system configured by `s3`,
+    /// storing the objects under `prefix` if set.
+    fn try_from_s3_builder(
+        endpoint: url::Url,
+        bucket: String,
+        s3: AmazonS3Builder,
+        prefix: Option<String>,
     ) -> Result<Self, Error> {
         trace!(
-            "creating object driver for a s3 compatible store, endpoint: {}",
-            endpoint
+            "creating object driver for a s3 compatible store, endpoint: {}, prefix: {:?}",
+            endpoint, prefix
         );
 
         // We map a url parse error into a bad bucket since `s3://` is a valid url
@@ -211,16 +451,14 @@ impl Store {
             .map_err(|_| Error::InvalidBucket("non URL safe string".to_owned()))?;
 
         // Setup connection with object storage service
-        // (cabba) TODO: add region support (??)
-        let storage = Arc::new(
-            AmazonS3Builder::new()
-                .with_endpoint(endpoint.to_string())
-                .with_bucket_name(&bucket)
-                .with_access_key_id(access_key)
-                .with_secret_access_key(secret_key)
-                .with_allow_http(true)
-                .build()?,
-        );
+        let driver: Arc<dyn ObjectStore> = match prefix.clone() {
+            Some(prefix) => Arc::new(PrefixStore::new(s3.build()?, prefix)),
+            None => Arc::new(s3.build()?),
+        };
+        let metrics = Arc::new(StoreMetrics::new("s3"));
+        let storage = Arc::new(LinkedObjectStore::new(Arc::new(
+            InstrumentedObjectStore::new(driver, metrics.clone()),
+        )));
 
         // Create object store registry (for datafusion support)
         let registry = Arc::new(DefaultObjectStoreRegistry::default());
@@ -229,11 +467,73 @@ impl Store {
         Ok(Self {
             url_schema: bucket_url,
             target: Target::S3Compatible(endpoint),
+            prefix,
             driver: storage.clone(),
+            links: storage,
             registry: registry.clone(),
+            read_ahead: None,
+            metrics,
         })
     }
 
+    /// Place a local disk cache in front of the store driver.
+    ///
+    /// Every read going through the store (including the ones performed by the query engine)
+    /// is served from the cache when possible.
+    pub fn with_cache(mut self, config: CacheConfig) -> Result<Self, Error> {
+        trace!(
+            "enabling store cache in {} ({} bytes)",
+            config.path.display(),
+            config.capacity_bytes
+        );
+
+        let cache = DiskCache::try_new(config.clone())
+            .map_err(|e| Error::DirCreationFailed(config.path.to_string_lossy().to_string(), e))?;
+        let driver = Arc::new(CachedObjectStore::new(self.driver, Arc::new(cache)));
+
+        self.registry
+            .register_store(&self.url_schema, driver.clone());
+        self.driver = driver;
+
+        Ok(self)
+    }
+
+    /// Fetch concurrently up to `parallelism` objects ahead of the one being read during a
+    /// scan. A `parallelism` of `0` leaves the store unchanged.
+    ///
+    /// Scans are declared with [`Store::plan_reads`], objects are fetched ahead only once
+    /// the objects of a scan are read in order, and only up to
+    /// [`crate::DEFAULT_RANGE_SIZE`] bytes of each one.
+    pub fn with_read_ahead(mut self, parallelism: usize) -> Self {
+        if parallelism == 0 {
+            return self;
+        }
+
+        trace!("enabling store read-ahead of {} objects", parallelism);
+
+        let driver = Arc::new(ReadAheadObjectStore::new(self.driver, parallelism));
+
+        self.registry
+            .register_store(&self.url_schema, driver.clone());
+        self.driver = driver.clone();
+        self.read_ahead = Some(driver);
+
+        self
+    }
+
+    /// Declares that the objects at the provided paths are going to be read in order,
+    /// allowing the store to fetch them ahead of time.
+    pub fn plan_reads<P: AsRef<std::path::Path>>(&self, paths: impl IntoIterator<Item = P>) {
+        if let Some(read_ahead) = &self.read_ahead {
+            read_ahead.plan(paths.into_iter().map(to_object_path));
+        }
+    }
+
+    /// Metrics of the operations performed on the store backend
+    pub fn metrics(&self) -> &Arc<StoreMetrics> {
+        &self.metrics
+    }
+
     pub fn registry(&self) -> Arc<dyn ObjectStoreRegistry> {
         self.registry.clone()
     }
@@ -252,6 +552,36 @@ impl Store {
             .into())
     }
 
+    /// Reads `len` bytes of the object at `path`, starting from `offset`.
+    ///
+    /// Only the requested range is fetched from the backend, which makes this method
+    /// suitable for seeking inside large objects (e.g. reading parquet footers or media
+    /// segments). Ranges exceeding the object size are truncated to the end of the object,
+    /// while an `offset` beyond the end of the object results in an error, as does a
+    /// missing object whatever the length requested.
+    pub async fn get_range(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        offset: u64,
+        len: u64,
+    ) -> Result<Vec<u8>, Error> {
+        if len == 0 {
+            // Empty ranges are not fetched, but the object must exist
+            self.driver.head(&to_object_path(&path)).await?;
+            return Ok(Vec::new());
+        }
+
+        let end = offset
+            .checked_add(len)
+            .ok_or_else(|| Error::InvalidRange(offset, len))?;
+
+        Ok(self
+            .driver
+            .get_range(&to_object_path(&path), offset..end)
+            .await?
+            .into())
+    }
+
     pub async fn write_bytes(
         &self,
         path: impl AsRef<std::path::Path>,
@@ -264,6 +594,104 @@ impl Store {
         Ok(())
     }
 
+    /// Writes `bytes` at the given `path` only if no object exists there yet, returning
+    /// whether the object has been written.
+    ///
+    /// The check and the write are performed atomically by the backend, so concurrent
+    /// writers of the same `path` are guaranteed that exactly one of them succeeds.
+    pub async fn write_bytes_if_absent(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        bytes: impl Into<bytes::Bytes>,
+    ) -> Result<bool, Error> {
+        let res = self
+            .driver
+            .put_opts(
+                &to_object_path(&path),
+                PutPayload::from_bytes(bytes.into()),
+                PutMode::Create.into(),
+            )
+            .await;
+
+        match res {
+            Ok(_) => Ok(true),
+            Err(object_store::Error::AlreadyExists { .. }) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns a [`StoreWriter`] used to incrementally write a (potentially huge) object
+    /// at the given `path`.
+    ///
+    /// Data is uploaded in parts of [`params::Params::store_multipart_part_size`] bytes
+    /// (mapped to multipart uploads on S3-compatible backends), so memory usage is bounded
+    /// regardless of the object size.
+    pub async fn writer(&self, path: impl AsRef<std::path::Path>) -> Result<StoreWriter, Error> {
+        let location = to_object_path(&path);
+        let upload = self.driver.put_multipart(&location).await?;
+
+        Ok(StoreWriter {
+            location,
+            upload: WriteMultipart::new_with_chunk_size(
+                upload,
+                params::params().store_multipart_part_size.value,
+            ),
+            bytes_written: 0,
+        })
+    }
+
+    /// Removes the temporary files left in a filesystem store by the writes interrupted by a
+    /// crash, last modified more than `older_than` ago. Returns the number of removed files.
+    ///
+    /// Objects are written to a temporary file next to their path (named `<object>#<n>`) and
+    /// renamed into place once complete, so an interrupted write never leaves a partially
+    /// written object visible. The temporary files of the writes in progress are removed if
+    /// the writer is dropped, while those of a crashed process are left behind. Stores on
+    /// other backends are left untouched.
+    pub async fn remove_staged_writes(&self, older_than: Duration) -> Result<usize, Error> {
+        let Target::Filesystem(root) = &self.target else {
+            return Ok(0);
+        };
+
+        let fs_error = |path: &std::path::Path, e| {
+            Error::FilesystemError(path.to_string_lossy().into_owned(), e)
+        };
+
+        let mut removed = 0;
+        let mut dirs = vec![root.clone()];
+        while let Some(dir) = dirs.pop() {
+            let mut entries = tokio::fs::read_dir(&dir)
+                .await
+                .map_err(|e| fs_error(&dir, e))?;
+
+            while let Some(entry) = entries.next_entry().await.map_err(|e| fs_error(&dir, e))? {
+                let path = entry.path();
+                let metadata = entry.metadata().await.map_err(|e| fs_error(&path, e))?;
+
+                if metadata.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+
+                let stale = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|modified| modified.elapsed().ok())
+                    .is_some_and(|age| age >= older_than);
+
+                if stale && is_staged_file(&entry.file_name().to_string_lossy()) {
+                    trace!("removing temporary file `{}`", path.display());
+                    tokio::fs::remove_file(&path)
+                        .await
+                        .map_err(|e| fs_error(&path, e))?;
+                    removed += 1;
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+
     /// Returns a list of elements located at the given `path`.
     ///
     /// If an extension is provided, the results will be filtered to include only
@@ -312,6 +740,16 @@ impl Store {
         Ok(head.size as usize)
     }
 
+    /// Returns the time the object at `path` was last modified, as reported by the backend.
+    pub async fn last_modified(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<std::time::SystemTime, Error> {
+        let head = self.driver.head(&to_object_path(&path)).await?;
+
+        Ok(head.last_modified.into())
+    }
+
     pub async fn delete(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
         Ok(self.driver.delete(&to_object_path(&path)).await?)
     }
@@ -327,11 +765,177 @@ impl Store {
         Ok(())
     }
 
+    /// Copies the object at `from` to `to`, overwriting an existing object.
+    ///
+    /// The object is copied backend-side, without transferring its content. On the local
+    /// filesystem the copy is a hard link to the same file.
+    pub async fn copy(
+        &self,
+        from: impl AsRef<std::path::Path>,
+        to: impl AsRef<std::path::Path>,
+    ) -> Result<(), Error> {
+        Ok(self
+            .driver
+            .copy(&to_object_path(&from), &to_object_path(&to))
+            .await?)
+    }
+
+    /// Copies recursively all objects under `from` to the same relative path under `to`,
+    /// overwriting existing objects. Returns the number of objects copied.
+    ///
+    /// Objects are copied backend-side, without transferring their content.
+    pub async fn copy_recursive(
+        &self,
+        from: impl AsRef<std::path::Path>,
+        to: impl AsRef<std::path::Path>,
+    ) -> Result<usize, Error> {
+        let from = to_object_path(&from);
+        let to = to_object_path(&to);
+        let locations: Vec<_> = self
+            .driver
+            .list(Some(&from))
+            .map_ok(|e| e.location)
+            .try_collect()
+            .await?;
+
+        for location in &locations {
+            let target = match location.prefix_match(&from) {
+                Some(parts) => parts.fold(to.clone(), |path, part| path.join(part)),
+                None => continue,
+            };
+            self.driver.copy(location, &target).await?;
+        }
+
+        Ok(locations.len())
+    }
+
+    /// Makes `path` a link to the object at `target`, reading `path` reads `target`.
+    ///
+    /// Links are deleted, copied and moved like the other objects, while their target is
+    /// left untouched, see [`LinkedObjectStore`].
+    pub async fn link(
+        &self,
+        target: impl AsRef<std::path::Path>,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), Error> {
+        Ok(self
+            .links
+            .link(&to_object_path(&path), &to_object_path(&target))
+            .await?)
+    }
+
+    /// Replaces the object at `path` with a link to `target`, which must hold the same
+    /// content.
+    pub async fn replace_with_link(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        target: impl AsRef<std::path::Path>,
+    ) -> Result<(), Error> {
+        Ok(self
+            .links
+            .replace_with_link(&to_object_path(&path), &to_object_path(&target))
+            .await?)
+    }
+
+    /// Returns the target of `path` if it is a link, `None` otherwise.
+    pub async fn link_target(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<Option<std::path::PathBuf>, Error> {
+        Ok(self
+            .links
+            .target(&to_object_path(&path))
+            .await?
+            .map(|target| std::path::PathBuf::from(target.to_string())))
+    }
+
+    /// Returns the absolute URL of the object at `path`, as seen by the engines reading the
+    /// backend directly (e.g. `s3://bucket/prefix/path`).
+    ///
+    /// Links are not resolved: the URL of a linked path refers to a missing object, see
+    /// [`Store::link_target`].
+    pub fn object_url(&self, path: impl AsRef<std::path::Path>) -> Result<Url, Error> {
+        let location = to_object_path(path);
+        let url = match &self.target {
+            Target::Filesystem(root) => {
+                let path = std::path::absolute(root.join(location.as_ref()))
+                    .map_err(|e| Error::FilesystemError(root.to_string_lossy().to_string(), e))?;
+                // Here we use unwrap since absolute paths are valid file urls
+                Url::from_file_path(path).unwrap()
+            }
+            Target::S3Compatible(_) | Target::Memory => {
+                let key = match &self.prefix {
+                    Some(prefix) => format!("{prefix}/{location}"),
+                    None => location.to_string(),
+                };
+                let mut url = self.url_schema.clone();
+                url.set_path(&key);
+                url
+            }
+        };
+        Ok(url)
+    }
+
     pub fn parquet_reader(&self, path: impl AsRef<std::path::Path>) -> ParquetObjectReader {
         ParquetObjectReader::new(self.driver.clone(), to_object_path(path))
     }
 }
 
+/// Handle used to write an object incrementally.
+///
+/// The object becomes visible in the store only after [`StoreWriter::finish`] completes.
+/// On failure [`StoreWriter::abort`] should be called to clean up the parts already
+/// uploaded, dropping the writer without finishing it leaves the object unwritten.
+pub struct StoreWriter {
+    location: object_store::path::Path,
+    upload: WriteMultipart,
+    bytes_written: usize,
+}
+
+impl StoreWriter {
+    /// Maximum number of parts uploaded concurrently, the writer waits for some part to
+    /// complete before accepting new data once this limit is reached.
+    const MAX_CONCURRENT_PARTS: usize = 4;
+
+    /// Appends a chunk of data to the object.
+    pub async fn write_chunk(&mut self, bytes: impl Into<bytes::Bytes>) -> Result<(), Error> {
+        let bytes = bytes.into();
+
+        self.upload
+            .wait_for_capacity(Self::MAX_CONCURRENT_PARTS)
+            .await?;
+
+        self.bytes_written += bytes.len();
+        self.upload.put(bytes);
+
+        Ok(())
+    }
+
+    /// Number of bytes written so far
+    pub fn bytes_written(&self) -> usize {
+        self.bytes_written
+    }
+
+    /// Flushes the remaining data and completes the upload, returning the size of the object.
+    pub async fn finish(self) -> Result<usize, Error> {
+        self.upload.finish().await?;
+
+        trace!(
+            "object `{}` written ({} bytes)",
+            self.location, self.bytes_written
+        );
+
+        Ok(self.bytes_written)
+    }
+
+    /// Aborts the upload, removing the parts already uploaded.
+    pub async fn abort(self) -> Result<(), Error> {
+        trace!("aborting write of object `{}`", self.location);
+
+        Ok(self.upload.abort().await?)
+    }
+}
+
 impl traits::AsyncWriteToPath for Store {
     #[expect(
         clippy::manual_async_fn,
@@ -354,6 +958,79 @@ impl traits::AsyncWriteToPath for Store {
     }
 }
 
+impl traits::AsyncStreamToPath for Store {
+    type Writer = StoreWriter;
+
+    #[expect(
+        clippy::manual_async_fn,
+        reason = "trait requires impl Future return type"
+    )]
+    fn stream_to_path(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> impl Future<Output = std::io::Result<Self::Writer>> {
+        async move {
+            self.writer(&path).await.map_err(|e| {
+                std::io::Error::other(format!(
+                    "unable to start writing data to store on path {}: {}",
+                    path.as_ref().display(),
+                    e
+                ))
+            })
+        }
+    }
+}
+
+impl traits::AsyncPartWriter for StoreWriter {
+    #[expect(
+        clippy::manual_async_fn,
+        reason = "trait requires impl Future return type"
+    )]
+    fn write_part(
+        &mut self,
+        buf: impl Into<bytes::Bytes>,
+    ) -> impl Future<Output = std::io::Result<()>> {
+        async move {
+            self.write_chunk(buf).await.map_err(|e| {
+                std::io::Error::other(format!(
+                    "unable to write data to store on path {}: {}",
+                    self.location, e
+                ))
+            })
+        }
+    }
+
+    #[expect(
+        clippy::manual_async_fn,
+        reason = "trait requires impl Future return type"
+    )]
+    fn finish(self) -> impl Future<Output = std::io::Result<()>> {
+        async move {
+            let location = self.location.clone();
+            StoreWriter::finish(self).await.map(|_| ()).map_err(|e| {
+                std::io::Error::other(format!(
+                    "unable to complete write to store on path {location}: {e}"
+                ))
+            })
+        }
+    }
+
+    #[expect(
+        clippy::manual_async_fn,
+        reason = "trait requires impl Future return type"
+    )]
+    fn abort(self) -> impl Future<Output = std::io::Result<()>> {
+        async move {
+            let location = self.location.clone();
+            StoreWriter::abort(self).await.map_err(|e| {
+                std::io::Error::other(format!(
+                    "unable to abort write to store on path {location}: {e}"
+                ))
+            })
+        }
+    }
+}
+
 /// Provides a temporary store wrapper for testing.
 ///
 /// This module contains a [`Store`] struct which wraps a `super::StoreRef` and manages
@@ -367,6 +1044,7 @@ pub mod testing {
 
     pub struct Store {
         inner: super::StoreRef,
+        /// Root directory of the store, empty for in-memory stores
         pub root: std::path::PathBuf,
     }
 
@@ -400,11 +1078,21 @@ pub mod testing {
             let path: std::path::PathBuf = format!("/tmp/{}", random_location).parse()?;
             Self::new(path)
         }
+
+        /// Creates a new temporary [`Store`] keeping the objects in memory.
+        pub fn new_in_memory() -> Self {
+            Self {
+                root: std::path::PathBuf::new(),
+                inner: Arc::new(super::Store::in_memory()),
+            }
+        }
     }
 
     impl Drop for Store {
         fn drop(&mut self) {
-            std::fs::remove_dir_all(&self.root).unwrap();
+            if let Target::Filesystem(_) = self.inner.target() {
+                std::fs::remove_dir_all(&self.root).unwrap();
+            }
         }
     }
 
@@ -424,6 +1112,33 @@ mod test {
 
     use super::*;
 
+    #[test]
+    fn transient_errors() {
+        let generic =
+            |source: Box<dyn std::error::Error + Send + Sync>| object_store::Error::Generic {
+                store: "S3",
+                source,
+            };
+
+        let err = generic(Box::new(std::io::Error::from(
+            std::io::ErrorKind::ConnectionRefused,
+        )));
+        assert!(is_transient(&err));
+        assert!(Error::BackendError(err).is_transient());
+
+        let err = generic("Server returned non-2xx status code: 503 Service Unavailable: ".into());
+        assert!(is_transient(&err));
+
+        let err = generic("Server returned non-2xx status code: 403 Forbidden: ".into());
+        assert!(!is_transient(&err));
+
+        let err = object_store::Error::NotFound {
+            path: "data".to_owned(),
+            source: "missing".into(),
+        };
+        assert!(!is_transient(&err));
+    }
+
     /// Checks that filesystem store works, writing and reading data to `/tmp`` directory
     ///
     /// To avoid to delete system files the test directories are created in `/tmp` and are not removed automatically
@@ -450,6 +1165,266 @@ mod test {
         assert_eq!(store.list("", None).await.unwrap().len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_filesystem_store_get_range() {
+        let store = testing::Store::new_random_on_tmp().unwrap();
+
+        let target = "ranged";
+        store
+            .write_bytes(&target, b"0123456789".as_slice())
+            .await
+            .unwrap();
+
+        assert_eq!(store.get_range(&target, 0, 4).await.unwrap(), b"0123");
+        assert_eq!(store.get_range(&target, 6, 4).await.unwrap(), b"6789");
+        assert!(store.get_range(&target, 3, 0).await.unwrap().is_empty());
+
+        // Ranges exceeding the object are truncated, out of bounds offsets are rejected
+        assert_eq!(store.get_range(&target, 8, 4).await.unwrap(), b"89");
+        assert!(store.get_range(&target, 20, 1).await.is_err());
+        assert!(matches!(
+            store.get_range(&target, u64::MAX, 2).await,
+            Err(Error::InvalidRange(_, _))
+        ));
+        assert!(matches!(
+            store.get_range("missing", 0, 1).await,
+            Err(Error::BackendError(object_store::Error::NotFound { .. }))
+        ));
+        assert!(matches!(
+            store.get_range("missing", 0, 0).await,
+            Err(Error::BackendError(object_store::Error::NotFound { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_filesystem_store_writer() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let store = testing::Store::new_random_on_tmp().unwrap();
+
+        let part_size = params::params().store_multipart_part_size.value;
+        let chunk = vec![42u8; part_size / 2 + 1];
+
+        let target = "streamed/object";
+        let mut writer = store.writer(&target).await.unwrap();
+        for _ in 0..5 {
+            writer.write_chunk(chunk.clone()).await.unwrap();
+        }
+        assert_eq!(writer.bytes_written(), chunk.len() * 5);
+
+        // Object is not visible until the writer is finished
+        assert!(!store.exists(&target).await.unwrap());
+
+        assert_eq!(writer.finish().await.unwrap(), chunk.len() * 5);
+        assert_eq!(store.size(&target).await.unwrap(), chunk.len() * 5);
+        assert_eq!(
+            store.read_bytes(&target).await.unwrap(),
+            chunk.repeat(5).as_slice()
+        );
+
+        // Aborted objects are never written
+        let target = "streamed/aborted";
+        let mut writer = store.writer(&target).await.unwrap();
+        writer.write_chunk(chunk.clone()).await.unwrap();
+        writer.abort().await.unwrap();
+        assert!(!store.exists(&target).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_memory_store() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let store = testing::Store::new_in_memory();
+        assert!(matches!(store.target(), Target::Memory));
+
+        store
+            .write_bytes(&"sequence/metadata.json", b"{}".as_slice())
+            .await
+            .unwrap();
+        let mut writer = store.writer(&"sequence/data/0.parquet").await.unwrap();
+        writer.write_chunk(b"0123456789".as_slice()).await.unwrap();
+        assert!(!store.exists(&"sequence/data/0.parquet").await.unwrap());
+        writer.finish().await.unwrap();
+
+        assert_eq!(store.size(&"sequence/data/0.parquet").await.unwrap(), 10);
+        assert_eq!(
+            store
+                .get_range(&"sequence/data/0.parquet", 2, 3)
+                .await
+                .unwrap(),
+            b"234"
+        );
+        assert_eq!(
+            store.list("sequence", Some("parquet")).await.unwrap(),
+            ["sequence/data/0.parquet"]
+        );
+        assert_eq!(store.list("", None).await.unwrap().len(), 2);
+
+        store.delete(&"sequence/metadata.json").await.unwrap();
+        assert!(!store.exists(&"sequence/metadata.json").await.unwrap());
+        store.delete_recursive(&"sequence").await.unwrap();
+        assert!(store.list("", None).await.unwrap().is_empty());
+
+        // Stores are not shared
+        store
+            .write_bytes(&"object", b"data".as_slice())
+            .await
+            .unwrap();
+        assert!(
+            !testing::Store::new_in_memory()
+                .exists(&"object")
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_store_copy_recursive() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let store = testing::Store::new_in_memory();
+        for path in [
+            "tp_1/metadata.json",
+            "tp_1/data/00000.parquet",
+            "tp_10/metadata.json",
+        ] {
+            store.write_bytes(&path, path.as_bytes()).await.unwrap();
+        }
+
+        let copied = store
+            .copy_recursive(&"tp_1", &"2026/10/17/tp_1")
+            .await
+            .unwrap();
+        assert_eq!(copied, 2);
+
+        let mut copies = store.list("2026", None).await.unwrap();
+        copies.sort();
+        assert_eq!(
+            copies,
+            [
+                "2026/10/17/tp_1/data/00000.parquet",
+                "2026/10/17/tp_1/metadata.json"
+            ]
+        );
+        assert_eq!(
+            store
+                .read_bytes(&"2026/10/17/tp_1/data/00000.parquet")
+                .await
+                .unwrap(),
+            b"tp_1/data/00000.parquet"
+        );
+        // Sources are kept
+        assert_eq!(store.list("tp_1", None).await.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_memory_store_builder() {
+        let endpoint: Url = "memory://".parse().unwrap();
+
+        let store = Builder::new(endpoint, "bucket".to_owned()).build().unwrap();
+        assert!(matches!(store.target(), Target::Memory));
+        assert_eq!(store.url_schema.as_str(), "memory://mosaico");
+        assert_eq!(
+            store.object_url("shared/ob_1.parquet").unwrap().as_str(),
+            "memory://mosaico/shared/ob_1.parquet"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filesystem_store_staged_writes() {
+        params::load_params_from_env(params::ParamsLoadOptions::testing()).unwrap();
+
+        let store = testing::Store::new_random_on_tmp().unwrap();
+        let root = store.root.clone();
+
+        store
+            .write_bytes(&"data/complete", b"data".as_slice())
+            .await
+            .unwrap();
+
+        // Simulate the temporary file of a write interrupted by a crash two hours ago
+        let interrupted = root.join("data/interrupted#3");
+        std::fs::write(&interrupted, b"partial").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&interrupted)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - Duration::from_secs(7200))
+            .unwrap();
+
+        // Temporary files are never visible as objects
+        assert_eq!(store.list("data", None).await.unwrap(), ["data/complete"]);
+        assert!(!store.exists(&"data/interrupted").await.unwrap());
+
+        // The temporary files of the writes in progress are kept
+        let mut writer = store.writer(&"data/streamed").await.unwrap();
+        writer.write_chunk(b"data".as_slice()).await.unwrap();
+
+        let max_age = Duration::from_secs(3600);
+        assert_eq!(store.remove_staged_writes(max_age).await.unwrap(), 1);
+        assert!(!interrupted.exists());
+
+        writer.finish().await.unwrap();
+        assert_eq!(store.read_bytes(&"data/streamed").await.unwrap(), b"data");
+        assert_eq!(store.remove_staged_writes(Duration::ZERO).await.unwrap(), 0);
+    }
+
+    #[test]
+    fn staged_files() {
+        assert!(is_staged_file("chunk.parquet#1"));
+        assert!(is_staged_file("a#b#42"));
+        assert!(!is_staged_file("chunk.parquet"));
+        assert!(!is_staged_file("chunk#"));
+        assert!(!is_staged_file("#1"));
+        assert!(!is_staged_file("chunk#1a"));
+    }
+
+    #[tokio::test]
+    async fn test_filesystem_store_prefix() {
+        let bucket = types::DateTime::now().fmt_to_ms();
+        let endpoint: Url = "file:///tmp".parse().unwrap();
+
+        let store = Builder::new(endpoint.clone(), bucket.clone())
+            .with_prefix("/deployments/staging/".to_owned())
+            .build()
+            .unwrap();
+        store
+            .write_bytes(&"object", b"data".as_slice())
+            .await
+            .unwrap();
+
+        let path = std::path::Path::new("/tmp")
+            .join(bucket)
+            .join("deployments/staging/object");
+        assert_eq!(std::fs::read(path).unwrap(), b"data");
+
+        for prefix in ["", "/", "a//b", "a/../b", "./a"] {
+            let res = Builder::new(endpoint.clone(), "bucket".to_owned())
+                .with_prefix(prefix.to_owned())
+                .build();
+            assert!(matches!(res, Err(Error::InvalidPrefix(_))), "{prefix}");
+        }
+    }
+
+    #[test]
+    fn test_s3_store_builder() {
+        let endpoint: Url = "http://localhost:9000".parse().unwrap();
+
+        let store = Builder::new(endpoint, "my-bucket".to_owned())
+            .with_credentials("key".to_owned(), "secret".to_owned())
+            .with_region("eu-south-1".to_owned())
+            .with_prefix("deployments/staging".to_owned())
+            .build()
+            .unwrap();
+
+        assert!(matches!(store.target(), Target::S3Compatible(_)));
+        assert_eq!(store.url_schema.as_str(), "s3://my-bucket");
+        assert_eq!(
+            store.object_url("shared/ob_1.parquet").unwrap().as_str(),
+            "s3://my-bucket/deployments/staging/shared/ob_1.parquet"
+        );
+    }
+
     #[test]
     fn test_filesystem_store_endpoint_fs_relative() {
         let bucket = types::DateTime::now().fmt_to_ms();
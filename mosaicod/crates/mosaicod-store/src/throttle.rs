@@ -0,0 +1,79 @@
+//! Throttling of the background transfers.
+//!
+//! A [`Throttle`] paces the transfers of a class of background jobs (e.g. the relay
+//! uploads) so that their average bandwidth does not exceed the limit given
+//! by a [`types::BandwidthSchedule`] at the time of the transfer. Transfers are not split:
+//! each transfer waits for the previous ones to be completed at the allowed rate, then
+//! starts at full speed.
+use mosaicod_core::types;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Limits the bandwidth used by a class of transfers, shared by all the transfers of the
+/// class.
+#[derive(Debug, Default)]
+pub struct Throttle {
+    schedule: types::BandwidthSchedule,
+
+    /// Time at which the transfers started so far are completed at the allowed rate
+    next_start: Mutex<Option<Instant>>,
+}
+
+impl Throttle {
+    pub fn new(schedule: types::BandwidthSchedule) -> Self {
+        Self {
+            schedule,
+            next_start: Mutex::new(None),
+        }
+    }
+
+    /// Returns a throttle never delaying the transfers.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub fn schedule(&self) -> &types::BandwidthSchedule {
+        &self.schedule
+    }
+
+    /// Waits until a transfer of `bytes` can start without exceeding the current limit.
+    pub async fn acquire(&self, bytes: u64) {
+        let Some(limit) = self.schedule.current_limit() else {
+            return;
+        };
+
+        let now = Instant::now();
+        let start = {
+            let mut next_start = self.next_start.lock().expect("throttle lock poisoned");
+            let start = next_start.map_or(now, |next_start| next_start.max(now));
+            *next_start = Some(start + Duration::from_secs_f64(bytes as f64 / limit as f64));
+            start
+        };
+
+        tokio::time::sleep_until(start).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn transfers_paced_at_limit() {
+        let throttle = Throttle::new("1000".parse().unwrap());
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            throttle.acquire(500).await;
+        }
+        // The third transfer starts once the first two are completed at 1000 bytes/s
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+        let throttle = Throttle::unlimited();
+        let start = Instant::now();
+        throttle.acquire(u64::MAX).await;
+        throttle.acquire(u64::MAX).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+}
@@ -1,5 +1,6 @@
 use super::common::{ActionResponse, Client};
 use arrow::array::RecordBatch;
+use arrow::datatypes::Schema;
 use arrow_flight::decode::FlightRecordBatchStream;
 use arrow_flight::encode::FlightDataEncoderBuilder;
 use arrow_flight::{Action, FlightDescriptor, FlightInfo, PutResult};
@@ -51,6 +52,83 @@ pub async fn sequence_create(
     Ok(())
 }
 
+/// Lists a page of sequences, `body` may contain the `prefix`, `limit` and `cursor`.
+pub async fn sequence_list(
+    client: &mut Client,
+    body: serde_json::Value,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(client, "sequence_list", body).await
+}
+
+/// Reads a page of the activity feed, `body` contains the `locator` and may contain
+/// `since_ns`, `limit` and `cursor`.
+pub async fn activity_feed(
+    client: &mut Client,
+    body: serde_json::Value,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(client, "activity_feed", body).await
+}
+
+/// Commits the offset of a consumer, `body` contains the `consumer`, the `locator` and
+/// either the `cursor` or the `watermark_ns`.
+pub async fn consumer_offset_commit(
+    client: &mut Client,
+    body: serde_json::Value,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(client, "consumer_offset_commit", body).await
+}
+
+/// Returns the offset committed by a consumer of a sequence or topic.
+pub async fn consumer_offset_get(
+    client: &mut Client,
+    consumer: &str,
+    locator: &str,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "consumer_offset_get",
+        serde_json::json!({ "consumer": consumer, "locator": locator }),
+    )
+    .await
+}
+
+/// Deletes the offset committed by a consumer of a sequence or topic.
+pub async fn consumer_offset_delete(
+    client: &mut Client,
+    consumer: &str,
+    locator: &str,
+) -> Result<(), tonic::Status> {
+    json_action(
+        client,
+        "consumer_offset_delete",
+        serde_json::json!({ "consumer": consumer, "locator": locator }),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Lists the topics of a sequence.
+pub async fn topic_list(
+    client: &mut Client,
+    sequence_name: &str,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "topic_list",
+        serde_json::json!({ "locator": sequence_name }),
+    )
+    .await
+}
+
+/// Lists a page of the topics of a sequence, `body` contains the `locator` and may contain
+/// `limit` and `cursor`.
+pub async fn topic_list_page(
+    client: &mut Client,
+    body: serde_json::Value,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(client, "topic_list", body).await
+}
+
 pub async fn sequence_delete(client: &mut Client, locator: &str) -> Result<(), tonic::Status> {
     let action = Action {
         r#type: "sequence_delete".to_owned(),
@@ -81,6 +159,164 @@ pub async fn sequence_delete(client: &mut Client, locator: &str) -> Result<(), t
     Ok(())
 }
 
+/// Restores a sequence from the trash.
+pub async fn sequence_restore(client: &mut Client, locator: &str) -> Result<(), tonic::Status> {
+    json_action(
+        client,
+        "sequence_restore",
+        serde_json::json!({ "locator": locator }),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Sets or releases the legal hold of a sequence.
+pub async fn sequence_legal_hold(
+    client: &mut Client,
+    locator: &str,
+    legal_hold: bool,
+) -> Result<(), tonic::Status> {
+    json_action(
+        client,
+        "sequence_legal_hold",
+        serde_json::json!({ "locator": locator, "legal_hold": legal_hold }),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Sets the compaction policy of a sequence, `body` contains the `locator` and may contain
+/// the `target_object_size`, `interval`, `min_chunks` and `cold_after` settings.
+pub async fn sequence_compaction_policy_set(
+    client: &mut Client,
+    body: serde_json::Value,
+) -> Result<(), tonic::Status> {
+    json_action(client, "sequence_compaction_policy_set", body).await?;
+    Ok(())
+}
+
+/// Returns the compaction policy of a sequence.
+pub async fn sequence_compaction_policy_get(
+    client: &mut Client,
+    locator: &str,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "sequence_compaction_policy_get",
+        serde_json::json!({ "locator": locator }),
+    )
+    .await
+}
+
+/// Compacts the upsert topics of a sequence.
+pub async fn sequence_compact(
+    client: &mut Client,
+    locator: &str,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "sequence_compact",
+        serde_json::json!({ "locator": locator }),
+    )
+    .await
+}
+
+/// Imports the MCAP recording stored at `path` into the sequence `locator`.
+pub async fn sequence_import_mcap(
+    client: &mut Client,
+    locator: &str,
+    path: &str,
+    channels: Option<&[&str]>,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "sequence_import_mcap",
+        serde_json::json!({ "locator": locator, "path": path, "channels": channels }),
+    )
+    .await
+}
+
+pub async fn sequence_export(
+    client: &mut Client,
+    locator: &str,
+    prefix: &str,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "sequence_export",
+        serde_json::json!({ "locator": locator, "prefix": prefix }),
+    )
+    .await
+}
+
+pub async fn sequence_import(
+    client: &mut Client,
+    path: &str,
+    locator: Option<&str>,
+    preserve_uuids: bool,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "sequence_import",
+        serde_json::json!({ "path": path, "locator": locator, "preserve_uuids": preserve_uuids }),
+    )
+    .await
+}
+
+/// Returns the ticket replaying the topics of a sequence, `options` are merged in the request
+/// body.
+pub async fn sequence_replay(
+    client: &mut Client,
+    locator: &str,
+    options: serde_json::Value,
+) -> Result<serde_json::Value, tonic::Status> {
+    let mut body = serde_json::json!({ "locator": locator });
+    if let (Some(body), Some(options)) = (body.as_object_mut(), options.as_object()) {
+        body.extend(options.clone());
+    }
+    json_action(client, "sequence_replay", body).await
+}
+
+/// Merges or replaces the user metadata of a sequence.
+pub async fn sequence_update_metadata(
+    client: &mut Client,
+    locator: &str,
+    user_metadata: serde_json::Value,
+    merge: bool,
+) -> Result<(), tonic::Status> {
+    json_action(
+        client,
+        "sequence_update_metadata",
+        serde_json::json!({ "locator": locator, "user_metadata": user_metadata, "merge": merge }),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Returns the attestations of the sessions of a sequence.
+pub async fn sequence_attestation(
+    client: &mut Client,
+    locator: &str,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "sequence_attestation",
+        serde_json::json!({ "locator": locator }),
+    )
+    .await
+}
+
+/// Permanently deletes a sequence in the trash.
+pub async fn sequence_purge(client: &mut Client, locator: &str) -> Result<(), tonic::Status> {
+    json_action(
+        client,
+        "sequence_purge",
+        serde_json::json!({ "locator": locator }),
+    )
+    .await?;
+    Ok(())
+}
+
 pub async fn session_create(
     client: &mut Client,
     sequence_name: &str,
@@ -161,6 +397,43 @@ pub async fn session_finalize(
     Ok(())
 }
 
+/// Lists the sessions of a sequence, only the ones in `state` (`open` or `finalized`) if set.
+/// Aborts the session, discarding the data uploaded into it.
+pub async fn session_abort(
+    client: &mut Client,
+    session_uuid: &types::Uuid,
+) -> Result<(), tonic::Status> {
+    json_action(
+        client,
+        "session_abort",
+        serde_json::json!({ "session_uuid": session_uuid.to_string() }),
+    )
+    .await
+    .map(|_| ())
+}
+
+pub async fn session_list(
+    client: &mut Client,
+    sequence_name: &str,
+    state: Option<&str>,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "session_list",
+        serde_json::json!({ "locator": sequence_name, "state": state }),
+    )
+    .await
+}
+
+/// Lists a page of the sessions of a sequence, `body` contains the `locator` and may contain
+/// `state`, `limit` and `cursor`.
+pub async fn session_list_page(
+    client: &mut Client,
+    body: serde_json::Value,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(client, "session_list", body).await
+}
+
 /// Send an action to delete the current session
 pub async fn session_delete(
     client: &mut Client,
@@ -193,6 +466,40 @@ pub async fn session_delete(
     Ok(())
 }
 
+/// Send an action to share a session with another API key
+pub async fn session_share(
+    client: &mut Client,
+    session_uuid: &types::Uuid,
+    fingerprint: &str,
+) -> Result<(), tonic::Status> {
+    let action = Action {
+        r#type: "session_share".to_owned(),
+        body: format!(
+            r#"
+        {{
+            "session_uuid": "{}",
+            "api_key_fingerprint": "{}"
+        }}
+        "#,
+            session_uuid, fingerprint
+        )
+        .into(),
+    };
+
+    dbg!(&action);
+
+    let mut stream = client.do_action(action).await?.into_inner();
+
+    while let Some(result) = stream.message().await? {
+        dbg!(&result);
+        let r = ActionResponse::from_body(&result.body);
+        assert_eq!(r.action, "session_share");
+        assert!(r.response.as_object().is_none());
+    }
+
+    Ok(())
+}
+
 /// Create a new topic.
 /// Returns the `key` of the newly created topic, this key is required to upload topic data.
 pub async fn topic_create(
@@ -201,23 +508,70 @@ pub async fn topic_create(
     topic_name: &str,
     json_metadata: Option<&str>,
 ) -> Result<types::Uuid, tonic::Status> {
-    let action = Action {
-        r#type: "topic_create".to_owned(),
-        body: format!(
-            r#"
+    topic_create_with_format(client, key, topic_name, "default", json_metadata).await
+}
+
+/// Create a new topic using the given serialization format.
+pub async fn topic_create_with_format(
+    client: &mut Client,
+    key: &types::Uuid,
+    topic_name: &str,
+    serialization_format: &str,
+    json_metadata: Option<&str>,
+) -> Result<types::Uuid, tonic::Status> {
+    let body = format!(
+        r#"
         {{
             "locator": "{name}",
             "session_uuid": "{key}",
-            "serialization_format": "default",
+            "serialization_format": "{format}",
             "ontology_tag": "mock",
             "user_metadata": {mdata}
         }}
         "#,
-            name = topic_name,
-            key = key,
-            mdata = json_metadata.unwrap_or("{}"),
-        )
-        .into(),
+        name = topic_name,
+        key = key,
+        format = serialization_format,
+        mdata = json_metadata.unwrap_or("{}"),
+    );
+
+    topic_create_with_body(client, body).await
+}
+
+/// Create a new topic setting additional properties, provided as a list of JSON fields
+/// (e.g. `"sort_key": "timestamp_ns"`).
+pub async fn topic_create_with_properties(
+    client: &mut Client,
+    key: &types::Uuid,
+    topic_name: &str,
+    properties: &str,
+) -> Result<types::Uuid, tonic::Status> {
+    let body = format!(
+        r#"
+        {{
+            "locator": "{name}",
+            "session_uuid": "{key}",
+            "serialization_format": "default",
+            "ontology_tag": "mock",
+            {properties},
+            "user_metadata": {{}}
+        }}
+        "#,
+        name = topic_name,
+        key = key,
+        properties = properties,
+    );
+
+    topic_create_with_body(client, body).await
+}
+
+async fn topic_create_with_body(
+    client: &mut Client,
+    body: String,
+) -> Result<types::Uuid, tonic::Status> {
+    let action = Action {
+        r#type: "topic_create".to_owned(),
+        body: body.into(),
     };
 
     dbg!(&action);
@@ -242,6 +596,448 @@ pub async fn topic_create(
     key.ok_or_else(|| tonic::Status::internal("Unable to return key"))
 }
 
+/// Retrieve the time index of a media topic, optionally limited to a timestamp range.
+pub async fn topic_media_index(
+    client: &mut Client,
+    locator: &str,
+    timestamp_range: Option<(i64, i64)>,
+) -> Result<serde_json::Value, tonic::Status> {
+    let range = timestamp_range
+        .map(|(start, end)| {
+            format!(r#", "timestamp_ns_start": {start}, "timestamp_ns_end": {end}"#)
+        })
+        .unwrap_or_default();
+
+    let action = Action {
+        r#type: "topic_media_index".to_owned(),
+        body: format!(r#"{{"locator": "{locator}"{range}}}"#).into(),
+    };
+
+    let mut stream = client.do_action(action).await?.into_inner();
+
+    let mut index: Option<serde_json::Value> = None;
+
+    while let Some(result) = stream.message().await? {
+        let r = ActionResponse::from_body(&result.body);
+        assert_eq!(r.action, "topic_media_index");
+        index = Some(r.response);
+    }
+
+    index.ok_or_else(|| tonic::Status::internal("Unable to return media index"))
+}
+
+/// Returns the schema registered for a topic, as a base64 Arrow IPC message, if any.
+pub async fn topic_schema_get(
+    client: &mut Client,
+    locator: &str,
+) -> Result<Option<String>, tonic::Status> {
+    let action = Action {
+        r#type: "topic_schema_get".to_owned(),
+        body: format!(r#"{{"locator": "{locator}"}}"#).into(),
+    };
+
+    let mut stream = client.do_action(action).await?.into_inner();
+
+    let mut schema: Option<Option<String>> = None;
+
+    while let Some(result) = stream.message().await? {
+        let r = ActionResponse::from_body(&result.body);
+        assert_eq!(r.action, "topic_schema_get");
+        schema = Some(r.response["arrow_schema"].as_str().map(ToOwned::to_owned));
+    }
+
+    schema.ok_or_else(|| tonic::Status::internal("Unable to return topic schema"))
+}
+
+/// Registers a new version of the schema of a topic, `arrow_schema` being a base64 Arrow
+/// IPC message. Returns the new version.
+pub async fn topic_schema_evolve(
+    client: &mut Client,
+    locator: &str,
+    arrow_schema: &str,
+) -> Result<u64, tonic::Status> {
+    let action = Action {
+        r#type: "topic_schema_evolve".to_owned(),
+        body: format!(r#"{{"locator": "{locator}", "arrow_schema": "{arrow_schema}"}}"#).into(),
+    };
+
+    let mut stream = client.do_action(action).await?.into_inner();
+
+    let mut version: Option<u64> = None;
+
+    while let Some(result) = stream.message().await? {
+        let r = ActionResponse::from_body(&result.body);
+        assert_eq!(r.action, "topic_schema_evolve");
+        version = r.response["version"].as_u64();
+    }
+
+    version.ok_or_else(|| tonic::Status::internal("Unable to return schema version"))
+}
+
+/// Sets the documentation of the columns of a topic, `columns` is the JSON object mapping
+/// each column name to its `description` and `unit`.
+pub async fn topic_columns_update(
+    client: &mut Client,
+    locator: &str,
+    columns: serde_json::Value,
+) -> Result<(), tonic::Status> {
+    let body = serde_json::json!({ "locator": locator, "columns": columns });
+
+    let action = Action {
+        r#type: "topic_columns_update".to_owned(),
+        body: body.to_string().into(),
+    };
+
+    let mut stream = client.do_action(action).await?.into_inner();
+
+    while let Some(result) = stream.message().await? {
+        let r = ActionResponse::from_body(&result.body);
+        assert_eq!(r.action, "topic_columns_update");
+    }
+
+    Ok(())
+}
+
+/// Returns the statistics of the columns of a topic.
+pub async fn topic_column_stats(
+    client: &mut Client,
+    locator: &str,
+    columns: &[&str],
+) -> Result<Vec<serde_json::Value>, tonic::Status> {
+    let body = serde_json::json!({ "locator": locator, "columns": columns });
+
+    let action = Action {
+        r#type: "topic_column_stats".to_owned(),
+        body: body.to_string().into(),
+    };
+
+    let mut stream = client.do_action(action).await?.into_inner();
+
+    let mut stats = Vec::new();
+
+    while let Some(result) = stream.message().await? {
+        let r = ActionResponse::from_body(&result.body);
+        assert_eq!(r.action, "topic_column_stats");
+        stats.extend(r.response["columns"].as_array().unwrap().iter().cloned());
+    }
+
+    Ok(stats)
+}
+
+/// Redacts the values of `columns` of a topic in the timestamp range, returning the
+/// redaction log entry.
+pub async fn topic_redact(
+    client: &mut Client,
+    locator: &str,
+    range: (Option<i64>, Option<i64>),
+    columns: &[&str],
+    mode: &str,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "topic_redact",
+        serde_json::json!({
+            "locator": locator,
+            "timestamp_ns_start": range.0,
+            "timestamp_ns_end": range.1,
+            "columns": columns,
+            "mode": mode,
+            "reason": "test",
+        }),
+    )
+    .await
+}
+
+/// Returns the redactions applied to a topic.
+pub async fn topic_redaction_list(
+    client: &mut Client,
+    locator: &str,
+) -> Result<Vec<serde_json::Value>, tonic::Status> {
+    let response = json_action(
+        client,
+        "topic_redaction_list",
+        serde_json::json!({ "locator": locator }),
+    )
+    .await?;
+    Ok(response["redactions"].as_array().unwrap().clone())
+}
+
+/// Returns the ingestion watermark of a topic.
+pub async fn topic_watermark(
+    client: &mut Client,
+    locator: &str,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "topic_watermark",
+        serde_json::json!({ "locator": locator }),
+    )
+    .await
+}
+
+/// Exports the data of a topic as a Parquet file, `options` are merged in the request body.
+pub async fn topic_export(
+    client: &mut Client,
+    locator: &str,
+    options: serde_json::Value,
+) -> Result<serde_json::Value, tonic::Status> {
+    let mut body = serde_json::json!({ "locator": locator });
+    if let (Some(body), Some(options)) = (body.as_object_mut(), options.as_object()) {
+        body.extend(options.clone());
+    }
+    json_action(client, "topic_export", body).await
+}
+
+/// Copies the topic `from` and its data into the sequence `to_sequence`.
+pub async fn topic_copy(
+    client: &mut Client,
+    from: &str,
+    to_sequence: &str,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "topic_copy",
+        serde_json::json!({ "from": from, "to_sequence": to_sequence }),
+    )
+    .await
+}
+
+/// Returns the lineage of a topic.
+pub async fn topic_lineage(
+    client: &mut Client,
+    locator: &str,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "topic_lineage",
+        serde_json::json!({ "locator": locator }),
+    )
+    .await
+}
+
+/// Performs a query, returning the locators of the matching topics.
+pub async fn query(
+    client: &mut Client,
+    query: serde_json::Value,
+) -> Result<Vec<String>, tonic::Status> {
+    let response = query_response(client, query).await?;
+
+    let mut topics = Vec::new();
+    for item in response["items"].as_array().unwrap() {
+        for topic in item["topics"].as_array().unwrap() {
+            topics.push(topic["locator"].as_str().unwrap().to_owned());
+        }
+    }
+
+    Ok(topics)
+}
+
+/// Performs a query, returning the whole response.
+pub async fn query_response(
+    client: &mut Client,
+    query: serde_json::Value,
+) -> Result<serde_json::Value, tonic::Status> {
+    let action = Action {
+        r#type: "query".to_owned(),
+        body: query.to_string().into(),
+    };
+
+    let mut stream = client.do_action(action).await?.into_inner();
+
+    let mut response = serde_json::Value::Null;
+
+    while let Some(result) = stream.message().await? {
+        let r = ActionResponse::from_body(&result.body);
+        assert_eq!(r.action, "query");
+        response = r.response;
+    }
+
+    Ok(response)
+}
+
+/// Fetches the next rows of a paginated query, returning the whole response.
+pub async fn query_fetch(
+    client: &mut Client,
+    handle: &str,
+    max_rows: usize,
+) -> Result<serde_json::Value, tonic::Status> {
+    let body = serde_json::json!({ "handle": handle, "max_rows": max_rows });
+
+    let action = Action {
+        r#type: "query_fetch".to_owned(),
+        body: body.to_string().into(),
+    };
+
+    let mut stream = client.do_action(action).await?.into_inner();
+
+    let mut response = serde_json::Value::Null;
+
+    while let Some(result) = stream.message().await? {
+        let r = ActionResponse::from_body(&result.body);
+        assert_eq!(r.action, "query_fetch");
+        response = r.response;
+    }
+
+    Ok(response)
+}
+
+/// Plans a SQL query over `tables`, given as `(name, topic locator)` pairs, returning the
+/// whole response.
+pub async fn sql_query(
+    client: &mut Client,
+    statement: &str,
+    tables: &[(&str, &str)],
+) -> Result<serde_json::Value, tonic::Status> {
+    let tables: serde_json::Map<String, serde_json::Value> = tables
+        .iter()
+        .map(|(name, locator)| ((*name).to_owned(), (*locator).into()))
+        .collect();
+    let body = serde_json::json!({ "statement": statement, "tables": tables });
+    json_action(client, "sql_query", body).await
+}
+
+/// Streams the result of a SQL query with the ticket returned by [`sql_query`].
+pub async fn sql_query_result(
+    client: &mut Client,
+    response: &serde_json::Value,
+) -> Result<Vec<RecordBatch>, tonic::Status> {
+    use base64::Engine;
+
+    let ticket = base64::engine::general_purpose::STANDARD
+        .decode(response["ticket"].as_str().unwrap())
+        .unwrap();
+
+    do_get_with_ticket(
+        client,
+        Ticket {
+            ticket: ticket.into(),
+        },
+    )
+    .await
+}
+
+/// Performs an action, returning its response.
+async fn json_action(
+    client: &mut Client,
+    r#type: &str,
+    body: serde_json::Value,
+) -> Result<serde_json::Value, tonic::Status> {
+    Ok(client.action(r#type, body).await?)
+}
+
+pub async fn scheduled_query_create(
+    client: &mut Client,
+    name: &str,
+    schedule: &str,
+    query: serde_json::Value,
+    condition: Option<serde_json::Value>,
+) -> Result<serde_json::Value, tonic::Status> {
+    let body = serde_json::json!({
+        "name": name,
+        "schedule": schedule,
+        "query": query,
+        "condition": condition,
+    });
+    json_action(client, "scheduled_query_create", body).await
+}
+
+pub async fn scheduled_query_list(client: &mut Client) -> Result<serde_json::Value, tonic::Status> {
+    json_action(client, "scheduled_query_list", serde_json::json!({})).await
+}
+
+pub async fn scheduled_query_run(
+    client: &mut Client,
+    name: &str,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "scheduled_query_run",
+        serde_json::json!({ "name": name }),
+    )
+    .await
+}
+
+pub async fn scheduled_query_delete(client: &mut Client, name: &str) -> Result<(), tonic::Status> {
+    json_action(
+        client,
+        "scheduled_query_delete",
+        serde_json::json!({ "name": name }),
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn query_view_create(
+    client: &mut Client,
+    locator: &str,
+    name: &str,
+    query: serde_json::Value,
+) -> Result<serde_json::Value, tonic::Status> {
+    let body = serde_json::json!({
+        "locator": locator,
+        "name": name,
+        "query": query,
+    });
+    json_action(client, "query_view_create", body).await
+}
+
+pub async fn query_view_list(
+    client: &mut Client,
+    locator: &str,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "query_view_list",
+        serde_json::json!({ "locator": locator }),
+    )
+    .await
+}
+
+pub async fn query_view_delete(
+    client: &mut Client,
+    locator: &str,
+    name: &str,
+) -> Result<(), tonic::Status> {
+    json_action(
+        client,
+        "query_view_delete",
+        serde_json::json!({ "locator": locator, "name": name }),
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn external_table_register(
+    client: &mut Client,
+    name: &str,
+    location: &str,
+    format: &str,
+) -> Result<serde_json::Value, tonic::Status> {
+    let body = serde_json::json!({
+        "name": name,
+        "location": location,
+        "format": format,
+    });
+    json_action(client, "external_table_register", body).await
+}
+
+pub async fn external_table_list(client: &mut Client) -> Result<serde_json::Value, tonic::Status> {
+    json_action(client, "external_table_list", serde_json::json!({})).await
+}
+
+pub async fn external_table_unregister(
+    client: &mut Client,
+    name: &str,
+) -> Result<(), tonic::Status> {
+    json_action(
+        client,
+        "external_table_unregister",
+        serde_json::json!({ "name": name }),
+    )
+    .await?;
+    Ok(())
+}
+
 pub async fn topic_delete(client: &mut Client, locator: &str) -> Result<(), tonic::Status> {
     let action = Action {
         r#type: "topic_delete".to_owned(),
@@ -303,6 +1099,62 @@ pub async fn do_put(
     client.do_put(flight_data_stream).await
 }
 
+/// Uploads the Parquet `files`, one in the body of each message.
+pub async fn do_put_parquet(
+    client: &mut Client,
+    topic_uuid: &types::Uuid,
+    topic_name: &str,
+    files: Vec<Vec<u8>>,
+) -> Result<tonic::Response<Streaming<PutResult>>, tonic::Status> {
+    let cmd = serde_json::json!({
+        "resource_locator": topic_name,
+        "topic_uuid": topic_uuid.to_string(),
+        "payload_format": "parquet",
+    });
+
+    let mut descriptor = Some(FlightDescriptor::new_cmd(cmd.to_string()));
+    let flight_data: Vec<_> = files
+        .into_iter()
+        .map(|file| {
+            let data = arrow_flight::FlightData::new().with_data_body(file);
+            match descriptor.take() {
+                Some(descriptor) => data.with_descriptor(descriptor),
+                None => data,
+            }
+        })
+        .collect();
+
+    client.do_put(futures::stream::iter(flight_data)).await
+}
+
+/// Uploads `batches` declaring `sent_at` as their send time in the app metadata.
+pub async fn do_put_sent_at(
+    client: &mut Client,
+    topic_uuid: &types::Uuid,
+    topic_name: &str,
+    batches: Vec<RecordBatch>,
+    sent_at: types::Timestamp,
+) -> Result<tonic::Response<Streaming<PutResult>>, tonic::Status> {
+    let input_stream = futures::stream::iter(batches.into_iter().map(Ok));
+
+    let cmd = serde_json::json!({
+        "resource_locator": topic_name,
+        "topic_uuid": topic_uuid.to_string(),
+    });
+    let app_metadata = serde_json::json!({ "sent_at_ns": sent_at.as_i64() }).to_string();
+
+    let flight_data_stream = FlightDataEncoderBuilder::new()
+        .with_flight_descriptor(Some(FlightDescriptor::new_cmd(cmd.to_string())))
+        .build(input_stream)
+        .map(move |v| {
+            let mut data = v.unwrap();
+            data.app_metadata = app_metadata.clone().into();
+            data
+        });
+
+    client.do_put(flight_data_stream).await
+}
+
 pub async fn do_get(
     client: &mut Client,
     topic_name: &str,
@@ -311,6 +1163,10 @@ pub async fn do_get(
     let ticket_payload = types::flight::TicketTopic {
         locator,
         timestamp_range: None,
+        blob_references: false,
+        changes_since: None,
+        session: None,
+        anonymization: None,
     };
 
     let ticket = Ticket {
@@ -337,6 +1193,29 @@ pub async fn do_get_with_ticket(
         .map_err(|e| tonic::Status::internal(format!("do_get decode error: {e}")))
 }
 
+/// Lists the resources matching `expression`, returning the locators in their descriptors.
+pub async fn list_flights(
+    client: &mut Client,
+    expression: &str,
+) -> Result<Vec<String>, tonic::Status> {
+    let criteria = arrow_flight::Criteria {
+        expression: expression.to_owned().into(),
+    };
+
+    let infos: Vec<FlightInfo> = client
+        .list_flights(criteria)
+        .await?
+        .into_inner()
+        .try_collect()
+        .await?;
+
+    Ok(infos
+        .into_iter()
+        .filter_map(|info| info.flight_descriptor)
+        .flat_map(|descriptor| descriptor.path)
+        .collect())
+}
+
 pub async fn server_version(client: &mut Client) -> Result<(), tonic::Status> {
     let action = Action {
         r#type: "version".to_owned(),
@@ -374,18 +1253,147 @@ pub async fn server_version(client: &mut Client) -> Result<(), tonic::Status> {
     Ok(())
 }
 
+pub async fn store_metrics(client: &mut Client) -> Result<serde_json::Value, tonic::Status> {
+    let action = Action {
+        r#type: "store_metrics".to_owned(),
+        body: r#"{}"#.to_string().into(),
+    };
+
+    let mut stream = client.do_action(action).await?.into_inner();
+
+    let mut metrics: Option<serde_json::Value> = None;
+
+    while let Some(result) = stream.message().await? {
+        let r = ActionResponse::from_body(&result.body);
+        assert_eq!(r.action, "store_metrics");
+        metrics = Some(r.response);
+    }
+
+    metrics.ok_or_else(|| tonic::Status::internal("Unable to return store metrics"))
+}
+
+pub async fn store_tiering_report(
+    client: &mut Client,
+    cold_after: Option<u64>,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "store_tiering_report",
+        serde_json::json!({ "cold_after": cold_after }),
+    )
+    .await
+}
+
+pub async fn store_tiering_demote(
+    client: &mut Client,
+    cold_after: Option<u64>,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "store_tiering_demote",
+        serde_json::json!({ "cold_after": cold_after }),
+    )
+    .await
+}
+
+pub async fn cost_estimate(
+    client: &mut Client,
+    scope: &str,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(
+        client,
+        "cost_estimate",
+        serde_json::json!({ "scope": scope }),
+    )
+    .await
+}
+
+/// Returns the latency from the data uploaded to its commit, by topic.
+pub async fn ingest_metrics(client: &mut Client) -> Result<serde_json::Value, tonic::Status> {
+    json_action(client, "ingest_metrics", serde_json::json!({})).await
+}
+
+pub async fn server_stats(client: &mut Client) -> Result<serde_json::Value, tonic::Status> {
+    let action = Action {
+        r#type: "server_stats".to_owned(),
+        body: r#"{}"#.to_string().into(),
+    };
+
+    let mut stream = client.do_action(action).await?.into_inner();
+
+    let mut stats: Option<serde_json::Value> = None;
+
+    while let Some(result) = stream.message().await? {
+        let r = ActionResponse::from_body(&result.body);
+        assert_eq!(r.action, "server_stats");
+        stats = Some(r.response);
+    }
+
+    stats.ok_or_else(|| tonic::Status::internal("Unable to return server stats"))
+}
+
+/// Runs the self-test of the server, returning the outcome of its steps.
+pub async fn self_test(client: &mut Client) -> Result<serde_json::Value, tonic::Status> {
+    json_action(client, "self_test", serde_json::json!({})).await
+}
+
+/// Returns the requests rejected because of the credentials or of the resources
+/// requested, by peer.
+pub async fn security_report(client: &mut Client) -> Result<serde_json::Value, tonic::Status> {
+    json_action(client, "security_report", serde_json::json!({})).await
+}
+
+/// Starts the jobs rebuilding the derived state selected by `scope`.
+pub async fn reindex(
+    client: &mut Client,
+    scope: &str,
+    batch_size: Option<usize>,
+) -> Result<serde_json::Value, tonic::Status> {
+    let body = serde_json::json!({ "scope": scope, "batch_size": batch_size });
+    json_action(client, "reindex", body).await
+}
+
+/// Returns the status of the reindex jobs.
+pub async fn reindex_status(client: &mut Client) -> Result<serde_json::Value, tonic::Status> {
+    json_action(client, "reindex_status", serde_json::json!({})).await
+}
+
+/// Starts recording the requests received in the next `duration_secs` seconds.
+pub async fn replay_capture_start(
+    client: &mut Client,
+    duration_secs: u64,
+) -> Result<serde_json::Value, tonic::Status> {
+    let body = serde_json::json!({ "duration_secs": duration_secs });
+    json_action(client, "replay_capture_start", body).await
+}
+
+/// Returns the requests recorded by the replay capture.
+pub async fn replay_capture_dump(client: &mut Client) -> Result<serde_json::Value, tonic::Status> {
+    json_action(client, "replay_capture_dump", serde_json::json!({})).await
+}
+
 /// Returns flight info data for a sequence or a topic.
 pub async fn get_flight_info(
     client: &mut Client,
     topic_name: &str,
+) -> Result<FlightInfo, tonic::Status> {
+    get_flight_info_with_options(client, topic_name, "").await
+}
+
+/// Requests the flight info of a resource, `options` is a fragment of JSON fields appended
+/// to the command (e.g. `, "changes_since_ns": 0`).
+pub async fn get_flight_info_with_options(
+    client: &mut Client,
+    resource_name: &str,
+    options: &str,
 ) -> Result<FlightInfo, tonic::Status> {
     let cmd = format!(
         r#"
         {{
-            "resource_locator": "{}"
+            "resource_locator": "{}"{}
         }}
         "#,
-        topic_name
+        resource_name, options
     );
 
     dbg!(&cmd);
@@ -397,6 +1405,17 @@ pub async fn get_flight_info(
     Ok(info)
 }
 
+/// Returns the schema of a sequence or a topic.
+pub async fn get_schema(client: &mut Client, resource_name: &str) -> Result<Schema, tonic::Status> {
+    let cmd = format!(r#"{{"resource_locator": "{}"}}"#, resource_name);
+
+    let descriptor = FlightDescriptor::new_cmd(cmd);
+
+    let result = client.get_schema(descriptor).await?.into_inner();
+
+    Schema::try_from(&result).map_err(|e| tonic::Status::internal(e.to_string()))
+}
+
 pub async fn api_key_create(
     client: &mut Client,
     permissions: types::auth::Permission,
@@ -546,6 +1565,15 @@ pub async fn sequence_notification_create(
     Ok(())
 }
 
+/// Creates a sequence notification from the JSON `body` of the action.
+pub async fn sequence_notification_create_json(
+    client: &mut Client,
+    body: serde_json::Value,
+) -> Result<(), tonic::Status> {
+    json_action(client, "sequence_notification_create", body).await?;
+    Ok(())
+}
+
 pub async fn sequence_notification_list(
     client: &mut Client,
     locator: &str,
@@ -568,6 +1596,25 @@ pub async fn sequence_notification_list(
     Ok(ret)
 }
 
+/// Lists the sequence notifications selected by the JSON `body` of the action.
+pub async fn sequence_notification_list_json(
+    client: &mut Client,
+    body: serde_json::Value,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(client, "sequence_notification_list", body).await
+}
+
+/// Opens the stream of the sequence notifications created from now on.
+pub async fn sequence_notification_stream(
+    client: &mut Client,
+    locator: &str,
+    min_severity: Option<&str>,
+) -> Result<FlightRecordBatchStream, tonic::Status> {
+    Ok(client
+        .sequence_notification_stream(locator, min_severity)
+        .await?)
+}
+
 pub async fn sequence_notification_purge(
     client: &mut Client,
     locator: &str,
@@ -638,6 +1685,14 @@ pub async fn topic_notification_list(
     Ok(ret)
 }
 
+/// Lists the topic notifications selected by the JSON `body` of the action.
+pub async fn topic_notification_list_json(
+    client: &mut Client,
+    body: serde_json::Value,
+) -> Result<serde_json::Value, tonic::Status> {
+    json_action(client, "topic_notification_list", body).await
+}
+
 pub async fn topic_notification_purge(
     client: &mut Client,
     locator: &str,
@@ -1,4 +1,3 @@
-use arrow_flight::flight_service_client::FlightServiceClient;
 use mosaicod_core::params;
 use mosaicod_core::types;
 use mosaicod_db as db;
@@ -9,14 +8,17 @@ use mosaicod_store as store;
 use serde::Deserialize;
 use std::fs;
 use std::sync::Arc;
-use tonic::service::interceptor;
 
 /// The local loopback address for testing.
 pub const HOST: &str = "127.0.0.1";
 
 pub const TLS_CERT_FILE: &str = "./data/cert.pem";
 pub const TLS_CA_FILE: &str = "./data/ca.pem";
+pub const ATTESTATION_KEY_FILE: &str = "./data/attestation_key.pem";
 pub const TLS_PRIVATE_KEY_FILE: &str = "./data/key.pem";
+pub const TLS_CLIENT_CA_FILE: &str = "./data/client_ca.pem";
+pub const TLS_CLIENT_CERT_FILE: &str = "./data/client_cert.pem";
+pub const TLS_CLIENT_PRIVATE_KEY_FILE: &str = "./data/client_key.pem";
 
 /// Generates a random port in the ephemeral range [49152, 65535].
 /// Use this in tests to avoid `Address In Use` errors during parallel execution.
@@ -39,13 +41,49 @@ pub fn format_endpoint(host: &str, port: u16, tls: bool) -> String {
     format!("http://{host}:{port}")
 }
 
+/// Creates an additional migrated database on the server of `pool`, e.g. to run a second
+/// server in the same test. Drop it with [`drop_database`].
+pub async fn create_database(pool: &sqlx::Pool<db::DatabaseType>) -> sqlx::Pool<db::DatabaseType> {
+    let name = format!("test_{}", ulid::Ulid::new().to_string().to_lowercase());
+    sqlx::query(&format!("CREATE DATABASE {name}"))
+        .execute(pool)
+        .await
+        .expect("Unable to create database");
+
+    let options = pool.connect_options().as_ref().clone().database(&name);
+    let database = sqlx::Pool::connect_with(options)
+        .await
+        .expect("Unable to connect to database");
+    db::testing::MIGRATOR
+        .run(&database)
+        .await
+        .expect("Unable to migrate database");
+
+    database
+}
+
+/// Drops a database created with [`create_database`].
+pub async fn drop_database(
+    pool: &sqlx::Pool<db::DatabaseType>,
+    database: sqlx::Pool<db::DatabaseType>,
+) {
+    let name = database
+        .connect_options()
+        .get_database()
+        .expect("Database without name")
+        .to_owned();
+    database.close().await;
+
+    sqlx::query(&format!("DROP DATABASE {name}"))
+        .execute(pool)
+        .await
+        .expect("Unable to drop database");
+}
+
 async fn start_server(
-    host: &str,
-    port: u16,
+    config: server::flight::Config,
     database: db::testing::Database,
     shutdown: ShutdownNotifier,
-    tls: Option<server::flight::TlsConfig>,
-    enable_api_key: bool,
 ) -> (
     tokio::task::JoinHandle<()>,
     db::testing::Database,
@@ -65,16 +103,6 @@ async fn start_server(
         .unwrap(),
     );
 
-    let mut config = server::flight::Config::new(host.to_owned(), port);
-
-    if let Some(tls) = tls {
-        config.tls(tls);
-    }
-
-    if enable_api_key {
-        config.enable_api_key_management();
-    }
-
     let store_clone = store.clone();
     let db_clone = database.clone();
 
@@ -98,6 +126,13 @@ pub struct ServerBuilder {
     tls: Option<server::flight::TlsConfig>,
     db: db::testing::Database,
     enable_api_key: bool,
+    static_tokens: Option<server::middleware::StaticTokenValidator>,
+    query_limits: Option<facade::QueryLimitPolicy>,
+    attestation_signer: Option<facade::AttestationSigner>,
+    table_exporter: Option<facade::TableExporter>,
+    relay: Option<server::relay::RelayConfig>,
+    event_sink: Option<server::event_sink::EventSinkConfig>,
+    layers: server::middleware::Stack,
 }
 
 impl ServerBuilder {
@@ -110,6 +145,13 @@ impl ServerBuilder {
             tls: None,
             db,
             enable_api_key: false,
+            static_tokens: None,
+            query_limits: None,
+            attestation_signer: None,
+            table_exporter: None,
+            relay: None,
+            event_sink: None,
+            layers: server::middleware::Stack::new(),
         }
     }
 
@@ -118,10 +160,108 @@ impl ServerBuilder {
         self
     }
 
+    /// Accepts `token` as bearer token, authenticating the requests as `principal`.
+    pub fn with_static_token(
+        mut self,
+        token: &str,
+        principal: &str,
+        permissions: types::auth::Permission,
+    ) -> Self {
+        self.static_tokens
+            .get_or_insert_with(server::middleware::StaticTokenValidator::new)
+            .insert(token, principal, permissions);
+        self
+    }
+
+    pub fn with_query_limits(mut self, query_limits: facade::QueryLimitPolicy) -> Self {
+        self.query_limits = Some(query_limits);
+        self
+    }
+
+    /// Signs the attestations of the finalized sessions with the test key.
+    pub fn enable_attestations(mut self) -> Self {
+        let key = fs::read(ATTESTATION_KEY_FILE).expect("Unable to read attestation key");
+        self.attestation_signer =
+            Some(facade::AttestationSigner::try_from_key(&key).expect("Invalid attestation key"));
+        self
+    }
+
+    /// Exports the topics as Delta tables.
+    pub fn enable_table_export(mut self) -> Self {
+        self.table_exporter = Some(facade::TableExporter::new(facade::TableFormat::Delta));
+        self
+    }
+
+    /// Relays the finalized sessions every 100ms to the server listening on `port`, under
+    /// `namespace`.
+    pub fn with_relay(mut self, port: u16, namespace: &str) -> Self {
+        self.relay = Some(server::relay::RelayConfig {
+            target: format_endpoint(HOST, port, false),
+            namespace: namespace.to_owned(),
+            api_key: None,
+            tls_ca_file: None,
+            interval: std::time::Duration::from_millis(100),
+            conflict_policy: types::RelayConflictPolicy::default(),
+            topic_filter: types::RelayTopicFilter::default(),
+        });
+        self
+    }
+
+    /// Resolves the conflicts of the relay configured with [`Self::with_relay`] with
+    /// `policy`.
+    pub fn with_relay_conflict_policy(mut self, policy: types::RelayConflictPolicy) -> Self {
+        if let Some(relay) = &mut self.relay {
+            relay.conflict_policy = policy;
+        }
+        self
+    }
+
+    /// Relays only the topics selected by `filter` with the relay configured with
+    /// [`Self::with_relay`].
+    pub fn with_relay_topic_filter(mut self, filter: types::RelayTopicFilter) -> Self {
+        if let Some(relay) = &mut self.relay {
+            relay.topic_filter = filter;
+        }
+        self
+    }
+
+    /// Publishes the event log every 100ms through `publisher`, tracking the published
+    /// events as the offsets of the `event-sink` consumer.
+    pub fn with_event_sink(
+        mut self,
+        publisher: std::sync::Arc<dyn server::event_sink::EventPublisher>,
+    ) -> Self {
+        self.event_sink = Some(server::event_sink::EventSinkConfig {
+            publisher,
+            consumer: "event-sink".to_owned(),
+            interval: std::time::Duration::from_millis(100),
+        });
+        self
+    }
+
+    /// Adds a custom layer wrapping the Flight service.
+    pub fn with_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<server::middleware::BoxService> + Send + Sync + 'static,
+        L::Service: tower::Service<
+                server::middleware::HttpRequest,
+                Response = server::middleware::HttpResponse,
+                Error = std::convert::Infallible,
+            > + Clone
+            + Send
+            + Sync
+            + 'static,
+        <L::Service as tower::Service<server::middleware::HttpRequest>>::Future: Send + 'static,
+    {
+        self.layers.push(layer);
+        self
+    }
+
     pub fn enable_tls(mut self) -> Self {
         self.tls = Some(server::flight::TlsConfig {
             certificate_file: TLS_CERT_FILE.to_owned().into(),
             private_key_file: TLS_PRIVATE_KEY_FILE.to_owned().into(),
+            client_ca_file: None,
         });
         self
     }
@@ -130,6 +270,18 @@ impl ServerBuilder {
         self.tls = Some(server::flight::TlsConfig {
             certificate_file: cert.to_owned().into(),
             private_key_file: private_key.to_owned().into(),
+            client_ca_file: None,
+        });
+        self
+    }
+
+    /// Enables TLS requiring the clients to present a certificate signed by
+    /// [`TLS_CLIENT_CA_FILE`].
+    pub fn enable_mtls(mut self) -> Self {
+        self.tls = Some(server::flight::TlsConfig {
+            certificate_file: TLS_CERT_FILE.to_owned().into(),
+            private_key_file: TLS_PRIVATE_KEY_FILE.to_owned().into(),
+            client_ca_file: Some(TLS_CLIENT_CA_FILE.to_owned().into()),
         });
         self
     }
@@ -137,15 +289,46 @@ impl ServerBuilder {
     pub async fn build(self) -> Server {
         let shutdown = ShutdownNotifier::default();
 
-        let (server_join_handle, db, store, ts_gw) = start_server(
-            &self.host,
-            self.port,
-            self.db,
-            shutdown.clone(),
-            self.tls,
-            self.enable_api_key,
-        )
-        .await;
+        let mut config = server::flight::Config::new(self.host, self.port);
+
+        if let Some(tls) = self.tls {
+            config.tls(tls);
+        }
+
+        if self.enable_api_key {
+            config.enable_api_key_management();
+        }
+
+        if let Some(static_tokens) = self.static_tokens {
+            config.token_validator(static_tokens);
+        }
+
+        if let Some(query_limits) = self.query_limits {
+            config.query_limits(query_limits);
+        }
+
+        if let Some(attestation_signer) = self.attestation_signer {
+            config.attestation_signer(attestation_signer);
+        }
+
+        if let Some(table_exporter) = self.table_exporter {
+            config.table_exporter(table_exporter);
+        }
+
+        if let Some(relay) = self.relay {
+            config.relay(relay);
+        }
+
+        if let Some(event_sink) = self.event_sink {
+            config.event_sink(event_sink);
+        }
+
+        if !self.layers.is_empty() {
+            config.layer(self.layers);
+        }
+
+        let (server_join_handle, db, store, ts_gw) =
+            start_server(config, self.db, shutdown.clone()).await;
 
         Server {
             server_join_handle,
@@ -200,72 +383,40 @@ impl Server {
         permissions: types::auth::Permission,
         expires_at: Option<types::Timestamp>,
     ) -> types::ApiKey {
-        let handle = facade::auth::create(&self.context(), permissions, "".to_string(), expires_at)
-            .await
-            .expect("Failed to create api.");
+        let handle = facade::auth::create(
+            &self.context(),
+            permissions,
+            "".to_string(),
+            expires_at,
+            None,
+        )
+        .await
+        .expect("Failed to create api.");
 
         handle.api_key().clone()
     }
 }
 
-type InterceptedChannel =
-    interceptor::InterceptedService<tonic::transport::Channel, ApiKeyInterceptor>;
-
-#[derive(Clone)]
-pub struct ApiKeyInterceptor {
-    api_key: Option<String>,
-}
-
-impl tonic::service::Interceptor for ApiKeyInterceptor {
-    fn call(&mut self, mut req: tonic::Request<()>) -> Result<tonic::Request<()>, tonic::Status> {
-        if let Some(key) = &self.api_key {
-            req.metadata_mut()
-                .insert("mosaico-api-key-token", key.parse().unwrap());
-        }
-
-        Ok(req)
-    }
-}
+/// Client of the tests, see [`ClientBuilder`].
+pub use mosaico_client::Client;
 
+/// Builds a [`Client`] reading the TLS certificates from files.
 pub struct ClientBuilder {
-    url: url::Url,
+    builder: mosaico_client::ClientBuilder,
     tls: Option<tonic::transport::ClientTlsConfig>,
-    api_key_interceptor: Option<ApiKeyInterceptor>,
 }
 
 impl ClientBuilder {
     pub fn new(host: &str, port: u16) -> Self {
         Self {
-            url: format_endpoint(host, port, false)
-                .parse()
-                .expect("unable to convert host"),
+            builder: mosaico_client::ClientBuilder::new(host, port),
             tls: None,
-            api_key_interceptor: None,
         }
     }
 
-    pub fn enable_tls(mut self) -> Self {
-        let cert_str = fs::read(TLS_CA_FILE).expect("Unable to read certificate");
-        let cert = tonic::transport::Certificate::from_pem(cert_str);
-
-        self.url = format_endpoint(
-            &self.url.host().unwrap().to_string(),
-            self.url.port().unwrap(),
-            true,
-        )
-        .parse()
-        .unwrap();
-
-        dbg!(&self.url.to_string());
-        dbg!(&self.url.domain());
-
-        self.tls = Some(
-            tonic::transport::ClientTlsConfig::new()
-                .ca_certificate(cert)
-                .domain_name("127.0.0.1"),
-        );
-
-        self
+    pub fn enable_tls(self) -> Self {
+        self.enable_tls_with(TLS_CA_FILE)
+            .expect("Unable to read certificate")
     }
 
     pub fn enable_tls_with(
@@ -276,16 +427,6 @@ impl ClientBuilder {
             fs::read(tls_ca_file).map_err(|e| format!("Unable to read certificate: {e}"))?;
         let cert = tonic::transport::Certificate::from_pem(cert_str);
 
-        self.url = format_endpoint(
-            &self.url.host().unwrap().to_string(),
-            self.url.port().unwrap(),
-            true,
-        )
-        .parse()?;
-
-        dbg!(&self.url.to_string());
-        dbg!(&self.url.domain());
-
         self.tls = Some(
             tonic::transport::ClientTlsConfig::new()
                 .ca_certificate(cert)
@@ -295,60 +436,45 @@ impl ClientBuilder {
         Ok(self)
     }
 
+    /// Presents the certificate in `cert` and the private key in `private_key` to the server.
+    /// Requires TLS to be enabled first.
+    pub fn with_identity(mut self, cert: &str, private_key: &str) -> Self {
+        let cert = fs::read(cert).expect("Unable to read client certificate");
+        let key = fs::read(private_key).expect("Unable to read client private key");
+
+        self.tls = self
+            .tls
+            .map(|tls| tls.identity(tonic::transport::Identity::from_pem(cert, key)));
+
+        self
+    }
+
     pub fn with_api_key(mut self, api_key: String) -> Self {
-        self.api_key_interceptor = Some(ApiKeyInterceptor {
-            api_key: Some(api_key),
-        });
+        self.builder = self.builder.with_api_key(&api_key);
+        self
+    }
+
+    /// Sends `token` in the `authorization` header of every request.
+    pub fn with_bearer_token(mut self, token: &str) -> Self {
+        self.builder = self.builder.with_bearer_token(token);
         self
     }
 
     /// Establishes a connection to a Flight server at the specified host and port.
     pub async fn build(self) -> Client {
-        let url = self.url.as_str().trim_end_matches('/').to_owned();
-
-        let mut channel = tonic::transport::Channel::from_shared(url.clone())
-            .expect("Unable to create tonic channel");
-
-        if let Some(tls_config) = self.tls {
-            channel = channel
-                .tls_config(tls_config)
-                .expect("Problem running TLS configuration");
+        let mut builder = self.builder;
+        if let Some(tls) = self.tls {
+            builder = builder.with_tls(tls);
         }
 
-        let channel = channel.connect().await.unwrap_or_else(|e| {
+        let url = builder.endpoint();
+        builder.connect().await.unwrap_or_else(|e| {
             if let Some(e) = std::error::Error::source(&e) {
                 panic!("Unable to connect to `{}`: {}", url, e)
             } else {
                 panic!("Unable to connect to `{}`: {}", url, e);
             }
-        });
-
-        let interceptor = self
-            .api_key_interceptor
-            .unwrap_or(ApiKeyInterceptor { api_key: None });
-
-        Client {
-            client: FlightServiceClient::with_interceptor(channel, interceptor),
-        }
-    }
-}
-/// A dummy client that communicates to mosaicod.
-pub struct Client<T = InterceptedChannel> {
-    client: FlightServiceClient<T>,
-}
-
-impl Client {}
-
-impl<T> std::ops::Deref for Client<T> {
-    type Target = FlightServiceClient<T>;
-    fn deref(&self) -> &Self::Target {
-        &self.client
-    }
-}
-
-impl<T> std::ops::DerefMut for Client<T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.client
+        })
     }
 }
 
@@ -2,6 +2,8 @@
 
 use mosaicod_core::types;
 use mosaicod_db as db;
+use mosaicod_ext as ext;
+use mosaicod_facade as facade;
 use tests::{self, actions, common};
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
@@ -748,8 +750,376 @@ async fn test_api_key_concurrent_same_sequence(pool: sqlx::Pool<db::DatabaseType
     };
     assert_eq!(err.code(), tonic::Code::NotFound);
 
+    // The locator is reserved until the sequence is purged from the trash
+    actions::sequence_purge(&mut client_d1, seq_name)
+        .await
+        .unwrap();
+
     let res = actions::sequence_create(&mut client_w1, seq_name, None).await;
     assert!(res.is_ok());
 
     server.shutdown().await;
 }
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_api_key_query_limits(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    // Manage API keys are unlimited
+    let query_limits =
+        facade::QueryLimitPolicy::new("scanned_bytes=1,output_rows=1".parse().unwrap())
+            .with_class_limits(
+                types::auth::Permission::Manage,
+                "scanned_bytes=0,output_rows=0".parse().unwrap(),
+            );
+
+    let mut server = common::ServerBuilder::new(common::HOST, port, pool)
+        .enable_tls()
+        .enable_api_key()
+        .with_query_limits(query_limits)
+        .build()
+        .await;
+
+    let api_key_read = server
+        .create_api_key(types::auth::Permission::Read, None)
+        .await;
+    let api_key_manage = server
+        .create_api_key(types::auth::Permission::Manage, None)
+        .await;
+
+    let mut client_read = make_client(&api_key_read.key, port).await;
+    let mut client_manage = make_client(&api_key_manage.key, port).await;
+
+    let sequence_name = "test_api_key_query_limits";
+    actions::sequence_create(&mut client_manage, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client_manage, sequence_name)
+        .await
+        .unwrap();
+    for topic_name in ["my_topic_1", "my_topic_2"] {
+        let topic_name = format!("{sequence_name}/{topic_name}");
+        let uuid = actions::topic_create(&mut client_manage, &session_uuid, &topic_name, None)
+            .await
+            .unwrap();
+        let batches = vec![ext::arrow::testing::dummy_batch()];
+        let response = actions::do_put(&mut client_manage, &uuid, &topic_name, batches, false)
+            .await
+            .unwrap();
+        assert!(response.into_inner().message().await.unwrap().is_none());
+    }
+    actions::session_finalize(&mut client_manage, &session_uuid)
+        .await
+        .unwrap();
+
+    let response = actions::query_response(
+        &mut client_manage,
+        serde_json::json!({ "ontology": { "mock.value": { "$gt": 3 } } }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response["items"][0]["topics"].as_array().unwrap().len(), 2);
+
+    // Too many topics returned
+    let err = actions::query_response(
+        &mut client_read,
+        serde_json::json!({ "sequence": { "locator": { "$eq": sequence_name } } }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::ResourceExhausted);
+    assert!(err.message().contains("query returned 2 rows"));
+
+    // Too many bytes scanned
+    let err = actions::query_response(
+        &mut client_read,
+        serde_json::json!({ "ontology": { "mock.value": { "$gt": 2 } } }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::ResourceExhausted);
+    assert!(err.message().contains("scanned bytes"));
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_api_key_default_sequence(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let mut server = common::ServerBuilder::new(common::HOST, port, pool)
+        .enable_tls()
+        .enable_api_key()
+        .build()
+        .await;
+
+    let sequences = [
+        "test_api_key_default_sequence_1",
+        "test_api_key_default_sequence_2",
+    ];
+
+    let api_key_manage = server
+        .create_api_key(types::auth::Permission::Manage, None)
+        .await;
+    let api_key_read = facade::auth::create(
+        &server.context(),
+        types::auth::Permission::Read,
+        "".to_string(),
+        None,
+        Some(sequences[0].parse().unwrap()),
+    )
+    .await
+    .unwrap()
+    .api_key()
+    .clone();
+
+    let mut client_manage = make_client(&api_key_manage.key, port).await;
+    let mut client_read = make_client(&api_key_read.key, port).await;
+
+    for sequence_name in sequences {
+        actions::sequence_create(&mut client_manage, sequence_name, None)
+            .await
+            .unwrap();
+        let (_, session_uuid) = actions::session_create(&mut client_manage, sequence_name)
+            .await
+            .unwrap();
+        let topic_name = format!("{sequence_name}/my_topic");
+        let uuid = actions::topic_create(&mut client_manage, &session_uuid, &topic_name, None)
+            .await
+            .unwrap();
+        let batches = vec![ext::arrow::testing::dummy_batch()];
+        let response = actions::do_put(&mut client_manage, &uuid, &topic_name, batches, false)
+            .await
+            .unwrap();
+        assert!(response.into_inner().message().await.unwrap().is_none());
+        actions::session_finalize(&mut client_manage, &session_uuid)
+            .await
+            .unwrap();
+    }
+
+    let filter = serde_json::json!({ "ontology": { "mock.value": { "$gt": 3 } } });
+
+    // Keys without a default sequence search all the sequences
+    let response = actions::query_response(&mut client_manage, filter.clone())
+        .await
+        .unwrap();
+    assert_eq!(response["items"].as_array().unwrap().len(), 2);
+
+    // Queries without a sequence filter are scoped to the default sequence
+    let response = actions::query_response(&mut client_read, filter.clone())
+        .await
+        .unwrap();
+    let items = response["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["sequence"], sequences[0]);
+
+    // An explicit sequence filter overrides the default sequence
+    let response = actions::query_response(
+        &mut client_read,
+        serde_json::json!({ "sequence": { "locator": { "$eq": sequences[1] } } }),
+    )
+    .await
+    .unwrap();
+    let items = response["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["sequence"], sequences[1]);
+
+    // Views referenced by name only are searched in the default sequence
+    actions::query_view_create(&mut client_manage, sequences[0], "my_view", filter.clone())
+        .await
+        .unwrap();
+    let response =
+        actions::query_response(&mut client_read, serde_json::json!({ "view": "my_view" }))
+            .await
+            .unwrap();
+    let items = response["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["sequence"], sequences[0]);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_api_key_session_ownership(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let mut server = common::ServerBuilder::new(common::HOST, port, pool)
+        .enable_tls()
+        .enable_api_key()
+        .build()
+        .await;
+
+    let api_key_owner = server
+        .create_api_key(types::auth::Permission::Write, None)
+        .await;
+    let api_key_other = server
+        .create_api_key(types::auth::Permission::Write, None)
+        .await;
+
+    let mut client_owner = make_client(&api_key_owner.key, port).await;
+    let mut client_other = make_client(&api_key_other.key, port).await;
+
+    let sequence_name = "test_api_key_session_ownership";
+    let topic_name = format!("{sequence_name}/my_topic");
+
+    actions::sequence_create(&mut client_owner, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client_owner, sequence_name)
+        .await
+        .unwrap();
+
+    // Knowing the session uuid is not enough to write into the session
+    let err = actions::topic_create(&mut client_other, &session_uuid, &topic_name, None)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+    let topic_uuid = actions::topic_create(&mut client_owner, &session_uuid, &topic_name, None)
+        .await
+        .unwrap();
+
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    let err = actions::do_put(&mut client_other, &topic_uuid, &topic_name, batches, false)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+    let err = actions::session_finalize(&mut client_other, &session_uuid)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+    // Only the owner can share the session
+    let err = actions::session_share(
+        &mut client_other,
+        &session_uuid,
+        api_key_other.key.fingerprint(),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+    actions::session_share(
+        &mut client_owner,
+        &session_uuid,
+        api_key_other.key.fingerprint(),
+    )
+    .await
+    .unwrap();
+
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    let response = actions::do_put(&mut client_other, &topic_uuid, &topic_name, batches, false)
+        .await
+        .unwrap();
+    assert!(response.into_inner().message().await.unwrap().is_none());
+
+    actions::session_finalize(&mut client_other, &session_uuid)
+        .await
+        .unwrap();
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_static_bearer_token(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .enable_tls()
+        .with_static_token("reader-token", "dashboard", types::auth::Permission::Read)
+        .with_static_token("writer-token", "ingestion", types::auth::Permission::Write)
+        .with_static_token("backfill-token", "backfill", types::auth::Permission::Write)
+        .build()
+        .await;
+
+    let sequence_name = "test_static_bearer_token";
+
+    // Tokens are required once a validator is configured
+    let mut client = common::ClientBuilder::new(common::HOST, port)
+        .enable_tls()
+        .build()
+        .await;
+    let err = actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+    let mut client = common::ClientBuilder::new(common::HOST, port)
+        .enable_tls()
+        .with_bearer_token("unknown-token")
+        .build()
+        .await;
+    let err = actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+    // The handshake validates the token and returns it to the client
+    let request = arrow_flight::HandshakeRequest {
+        protocol_version: 0,
+        payload: "writer-token".into(),
+    };
+    let response = client
+        .handshake(futures::stream::once(async { request }))
+        .await
+        .unwrap();
+    assert_eq!(
+        response.metadata().get("authorization").unwrap(),
+        "Bearer writer-token"
+    );
+    let reply = response.into_inner().message().await.unwrap().unwrap();
+    assert_eq!(reply.payload, "writer-token");
+
+    let request = arrow_flight::HandshakeRequest {
+        protocol_version: 0,
+        payload: "unknown-token".into(),
+    };
+    let err = client
+        .handshake(futures::stream::once(async { request }))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+    // Permissions are the ones bound to the token
+    let mut client_reader = common::ClientBuilder::new(common::HOST, port)
+        .enable_tls()
+        .with_bearer_token("reader-token")
+        .build()
+        .await;
+    let err = actions::sequence_create(&mut client_reader, sequence_name, None)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+    let mut client_writer = common::ClientBuilder::new(common::HOST, port)
+        .enable_tls()
+        .with_bearer_token("writer-token")
+        .build()
+        .await;
+    actions::sequence_create(&mut client_writer, sequence_name, None)
+        .await
+        .unwrap();
+
+    // The principal of the token owns the sessions it creates
+    let (_, session_uuid) = actions::session_create(&mut client_writer, sequence_name)
+        .await
+        .unwrap();
+
+    let mut client_other = common::ClientBuilder::new(common::HOST, port)
+        .enable_tls()
+        .with_bearer_token("backfill-token")
+        .build()
+        .await;
+    let topic_name = format!("{sequence_name}/my_topic");
+    let err = actions::topic_create(&mut client_other, &session_uuid, &topic_name, None)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+    actions::topic_create(&mut client_writer, &session_uuid, &topic_name, None)
+        .await
+        .unwrap();
+
+    server.shutdown().await;
+}
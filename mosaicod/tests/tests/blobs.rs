@@ -0,0 +1,208 @@
+#![allow(unused_crate_dependencies)]
+use arrow::array::{Array, AsArray, BinaryArray, Int64Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow_flight::{FlightDescriptor, Ticket};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use bytes::Bytes;
+use mosaicod_core::params;
+use mosaicod_db as db;
+use mosaicod_ext as ext;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::sync::Arc;
+use tests::{self, actions, common};
+
+/// Size (in bytes) above which blobs are offloaded in this test suite
+const BLOB_OFFLOAD_THRESHOLD: &str = "64";
+
+/// Parameters are loaded once per process, every test in this file sets the same threshold
+fn enable_blob_offloading() {
+    // SAFETY: all the tests in this binary write the same value
+    unsafe { std::env::set_var("MOSAICOD_BLOB_OFFLOAD_THRESHOLD", BLOB_OFFLOAD_THRESHOLD) };
+}
+
+fn frames_batch() -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new(
+            params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+            DataType::Int64,
+            false,
+        ),
+        Field::new("frame", DataType::Binary, true),
+    ]));
+
+    let frames: Vec<Option<&[u8]>> = vec![Some(&[1; 16]), Some(&[2; 1024]), None];
+
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![10, 20, 30])),
+            Arc::new(BinaryArray::from(frames)),
+        ],
+    )
+    .unwrap()
+}
+
+async fn upload_frames(client: &mut common::Client, sequence_name: &str, topic_name: &str) {
+    actions::sequence_create(client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(client, sequence_name)
+        .await
+        .unwrap();
+    let topic_uuid = actions::topic_create(client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+    actions::do_put(client, &topic_uuid, topic_name, vec![frames_batch()], false)
+        .await
+        .unwrap();
+    actions::session_finalize(client, &session_uuid)
+        .await
+        .unwrap();
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_blobs_are_inlined_by_default(pool: sqlx::Pool<db::DatabaseType>) {
+    enable_blob_offloading();
+
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let topic_name = "test_sequence/camera";
+    upload_frames(&mut client, "test_sequence", topic_name).await;
+
+    let info = actions::get_flight_info(&mut client, topic_name)
+        .await
+        .unwrap();
+    let ticket = info.endpoint[0].ticket.clone().unwrap();
+
+    let received = actions::do_get_with_ticket(&mut client, ticket)
+        .await
+        .unwrap();
+    assert_eq!(received.len(), 1);
+
+    let original = frames_batch();
+    let frames = received[0].column_by_name("frame").unwrap();
+    assert_eq!(frames.data_type(), &DataType::Binary);
+    assert_eq!(frames.as_ref(), original.column(1).as_ref());
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_blobs_as_references(pool: sqlx::Pool<db::DatabaseType>) {
+    enable_blob_offloading();
+
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let topic_name = "test_sequence/camera";
+    upload_frames(&mut client, "test_sequence", topic_name).await;
+
+    let cmd = format!(r#"{{"resource_locator": "{topic_name}", "blob_references": true}}"#);
+    let info = client
+        .get_flight_info(FlightDescriptor::new_cmd(cmd))
+        .await
+        .unwrap()
+        .into_inner();
+    let ticket = info.endpoint[0].ticket.clone().unwrap();
+
+    let received = actions::do_get_with_ticket(&mut client, ticket)
+        .await
+        .unwrap();
+    assert_eq!(received.len(), 1);
+
+    let frames = received[0].column_by_name("frame").unwrap();
+    assert!(ext::blob::is_blob(frames.data_type()));
+
+    let frames = ext::blob::BlobColumn::try_new(frames).unwrap();
+    assert_eq!(frames.value(0), ext::blob::BlobValue::Inline(&[1; 16]));
+    assert!(matches!(frames.value(1), ext::blob::BlobValue::External(_)));
+    assert_eq!(frames.value(2), ext::blob::BlobValue::Null);
+    assert_eq!(frames.references().count(), 1);
+
+    // Nulls are preserved in the struct column
+    assert!(
+        received[0]
+            .column_by_name("frame")
+            .unwrap()
+            .as_struct()
+            .is_null(2)
+    );
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_topic_export(pool: sqlx::Pool<db::DatabaseType>) {
+    enable_blob_offloading();
+
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let topic_name = "test_sequence/camera";
+    upload_frames(&mut client, "test_sequence", topic_name).await;
+
+    let response = actions::topic_export(
+        &mut client,
+        topic_name,
+        serde_json::json!({ "row_group_size": 2 }),
+    )
+    .await
+    .unwrap();
+    let ticket = BASE64.decode(response["ticket"].as_str().unwrap()).unwrap();
+
+    let received = actions::do_get_with_ticket(&mut client, Ticket::new(ticket))
+        .await
+        .unwrap();
+    let mut file = Vec::new();
+    for batch in &received {
+        let pieces = batch
+            .column_by_name(params::ARROW_SCHEMA_COLUMN_NAME_EXPORT_DATA)
+            .unwrap()
+            .as_binary::<i32>();
+        pieces.iter().flatten().for_each(|piece| file.extend(piece));
+    }
+
+    let reader = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(file)).unwrap();
+    let metadata = reader.metadata().clone();
+    assert_eq!(metadata.num_row_groups(), 2);
+    let key_value = metadata.file_metadata().key_value_metadata().unwrap();
+    assert!(
+        key_value
+            .iter()
+            .any(|kv| kv.key == "mosaico:locator" && kv.value.as_deref() == Some(topic_name))
+    );
+
+    // Offloaded blobs are inlined in the exported file
+    let batches: Vec<RecordBatch> = reader.build().unwrap().map(Result::unwrap).collect();
+    let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+    let original = frames_batch();
+    let frames = batch.column_by_name("frame").unwrap();
+    assert_eq!(frames.data_type(), &DataType::Binary);
+    assert_eq!(frames.as_ref(), original.column(1).as_ref());
+
+    // The file can be written in the store instead
+    let response = actions::topic_export(
+        &mut client,
+        topic_name,
+        serde_json::json!({ "prefix": "nightly", "include_metadata": false }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        response["path"],
+        "exports/nightly/test_sequence/camera.parquet"
+    );
+    assert!(response["size"].as_u64().unwrap() > 0);
+
+    server.shutdown().await;
+}
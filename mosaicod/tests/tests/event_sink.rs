@@ -0,0 +1,148 @@
+#![allow(unused_crate_dependencies)]
+use futures::future::BoxFuture;
+use mosaicod_db as db;
+use mosaicod_ext as ext;
+use mosaicod_server::event_sink::{EventPublisher, SinkEvent};
+use std::sync::{Arc, Mutex};
+use tests::{self, actions, common};
+
+/// Records the published events, failing the first `failures` publications after
+/// recording them, as a broker losing the acknowledgements would.
+#[derive(Default)]
+struct RecordingPublisher {
+    events: Mutex<Vec<SinkEvent>>,
+    failures: Mutex<usize>,
+}
+
+impl RecordingPublisher {
+    fn failing(failures: usize) -> Self {
+        Self {
+            events: Mutex::default(),
+            failures: Mutex::new(failures),
+        }
+    }
+
+    fn events(&self) -> Vec<SinkEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl EventPublisher for RecordingPublisher {
+    fn name(&self) -> &str {
+        "recording"
+    }
+
+    fn publish<'a>(
+        &'a self,
+        events: &'a [SinkEvent],
+    ) -> BoxFuture<'a, Result<(), Box<dyn std::error::Error + Send + Sync>>> {
+        Box::pin(async move {
+            self.events.lock().unwrap().extend_from_slice(events);
+
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err("acknowledgement lost".into());
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Waits until an event named `name` is published.
+async fn wait_for_event(publisher: &RecordingPublisher, name: &str) {
+    for _ in 0..100 {
+        if publisher.events().iter().any(|event| event.name == name) {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("event `{name}` not published");
+}
+
+/// The activity feed of the sequences is published at least once, the published position is
+/// committed as a consumer offset.
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_event_sink(pool: sqlx::Pool<db::DatabaseType>) {
+    let publisher = Arc::new(RecordingPublisher::failing(1));
+
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .with_event_sink(publisher.clone())
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_event_sink";
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let topic_name = format!("{sequence_name}/topic");
+    let uuid = actions::topic_create(&mut client, &session_uuid, &topic_name, None)
+        .await
+        .unwrap();
+    actions::do_put(
+        &mut client,
+        &uuid,
+        &topic_name,
+        vec![ext::arrow::testing::dummy_batch()],
+        false,
+    )
+    .await
+    .unwrap();
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+    wait_for_event(&publisher, "session_finalized").await;
+
+    actions::sequence_notification_create(
+        &mut client,
+        sequence_name,
+        "error".to_owned(),
+        "sequence error".to_owned(),
+    )
+    .await
+    .unwrap();
+    wait_for_event(&publisher, "notification").await;
+
+    // Events published by the failed attempt are published again with the same identifier
+    let events = publisher.events();
+    let mut ids: Vec<_> = events.iter().map(|event| event.id.as_str()).collect();
+    let published = ids.len();
+    ids.sort_unstable();
+    ids.dedup();
+    assert!(ids.len() < published);
+
+    let mut names: Vec<_> = events.iter().map(|event| event.name).collect();
+    names.dedup();
+    let session_created = names.iter().position(|name| *name == "session_created");
+    let session_finalized = names.iter().position(|name| *name == "session_finalized");
+    assert_eq!(names.first(), Some(&"sequence_created"));
+    assert!(session_created < session_finalized);
+    assert_eq!(names.last(), Some(&"notification"));
+
+    let payload: serde_json::Value =
+        serde_json::from_slice(&events.last().unwrap().payload).unwrap();
+    assert_eq!(payload["sequence"], sequence_name);
+    assert_eq!(payload["event"], "notification");
+    assert_eq!(payload["notification"]["msg"], "sequence error");
+    assert_eq!(payload["id"], events.last().unwrap().id);
+
+    // The offset of the sink follows the end of the feed
+    let feed = actions::activity_feed(&mut client, serde_json::json!({ "locator": sequence_name }))
+        .await
+        .unwrap();
+    let offset = actions::consumer_offset_get(&mut client, "event-sink", sequence_name)
+        .await
+        .unwrap();
+    assert_eq!(offset["cursor"], feed["end_cursor"]);
+
+    // Nothing is published again once the offset is committed
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+    assert_eq!(publisher.events().len(), events.len());
+
+    server.shutdown().await;
+}
@@ -0,0 +1,115 @@
+#![allow(unused_crate_dependencies)]
+use arrow::array::{BinaryArray, Int64Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Schema};
+use mosaicod_core::params;
+use mosaicod_db as db;
+use std::sync::Arc;
+use tests::{self, actions, common};
+
+/// Number of frames uploaded in each test
+const FRAME_COUNT: i64 = 200;
+
+fn frames_batch() -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new(
+            params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+            DataType::Int64,
+            false,
+        ),
+        Field::new("frame", DataType::Binary, false),
+    ]));
+
+    let timestamps: Vec<i64> = (0..FRAME_COUNT).map(|i| i * 100).collect();
+    let frames: Vec<&[u8]> = vec![&[7; 32]; FRAME_COUNT as usize];
+
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(timestamps)),
+            Arc::new(BinaryArray::from(frames)),
+        ],
+    )
+    .unwrap()
+}
+
+async fn upload_frames(client: &mut common::Client, topic_name: &str, format: &str) {
+    let sequence_name = "test_sequence";
+    actions::sequence_create(client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(client, sequence_name)
+        .await
+        .unwrap();
+    let topic_uuid =
+        actions::topic_create_with_format(client, &session_uuid, topic_name, format, None)
+            .await
+            .unwrap();
+    actions::do_put(client, &topic_uuid, topic_name, vec![frames_batch()], false)
+        .await
+        .unwrap();
+    actions::session_finalize(client, &session_uuid)
+        .await
+        .unwrap();
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_topic_media_index(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let topic_name = "test_sequence/camera";
+    upload_frames(&mut client, topic_name, "image").await;
+
+    let index = actions::topic_media_index(&mut client, topic_name, None)
+        .await
+        .unwrap();
+    let segments = index["segments"].as_array().unwrap();
+
+    // Frames are split in multiple segments, covering the whole stream in order
+    assert!(segments.len() > 1);
+    let row_count: u64 = segments
+        .iter()
+        .map(|s| s["row_count"].as_u64().unwrap())
+        .sum();
+    assert_eq!(row_count, FRAME_COUNT as u64);
+    assert_eq!(segments[0]["timestamp_ns_start"], 0);
+    assert_eq!(
+        segments.last().unwrap()["timestamp_ns_end"],
+        (FRAME_COUNT - 1) * 100
+    );
+    for segment in segments {
+        assert!(segment["length"].as_u64().unwrap() > 0);
+        assert!(segment["file"].as_str().unwrap().starts_with("data/"));
+    }
+
+    // Seeking inside the stream returns only the overlapping segments
+    let index = actions::topic_media_index(&mut client, topic_name, Some((150, 250)))
+        .await
+        .unwrap();
+    let segments = index["segments"].as_array().unwrap();
+    assert_eq!(segments.len(), 1);
+    assert!(segments[0]["timestamp_ns_start"].as_i64().unwrap() <= 150);
+    assert!(segments[0]["timestamp_ns_end"].as_i64().unwrap() >= 250);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_topic_media_index_not_media(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let topic_name = "test_sequence/camera";
+    upload_frames(&mut client, topic_name, "default").await;
+
+    let res = actions::topic_media_index(&mut client, topic_name, None).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+    server.shutdown().await;
+}
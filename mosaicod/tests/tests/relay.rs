@@ -0,0 +1,299 @@
+#![allow(unused_crate_dependencies)]
+use mosaicod_db as db;
+use mosaicod_ext as ext;
+use tests::{self, actions, common};
+
+/// Waits until the topic can be read from the server, returns its number of rows.
+async fn wait_for_rows(client: &mut common::Client, topic_name: &str) -> usize {
+    for _ in 0..100 {
+        if let Ok(batches) = actions::do_get(client, topic_name).await {
+            return batches.iter().map(|batch| batch.num_rows()).sum();
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("topic `{topic_name}` not available");
+}
+
+/// Uploads a session with a topic for each of `topic_names`, finalizing it if `finalize`.
+async fn upload_session(
+    client: &mut common::Client,
+    sequence_name: &str,
+    topic_names: &[&str],
+    finalize: bool,
+) -> mosaicod_core::types::Uuid {
+    let (_, session_uuid) = actions::session_create(client, sequence_name)
+        .await
+        .unwrap();
+
+    for topic_name in topic_names {
+        let uuid = actions::topic_create(client, &session_uuid, topic_name, None)
+            .await
+            .unwrap();
+        actions::do_put(
+            client,
+            &uuid,
+            topic_name,
+            vec![ext::arrow::testing::dummy_batch()],
+            false,
+        )
+        .await
+        .unwrap();
+    }
+
+    if finalize {
+        actions::session_finalize(client, &session_uuid)
+            .await
+            .unwrap();
+    }
+
+    session_uuid
+}
+
+/// Finalized sessions of an edge instance are forwarded to the central instance, under the
+/// namespace of the edge.
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_session_relay(pool: sqlx::Pool<db::DatabaseType>) {
+    let rows = ext::arrow::testing::dummy_batch().num_rows();
+
+    let central_pool = common::create_database(&pool).await;
+    let central_port = common::random_port();
+    let central = common::ServerBuilder::new(common::HOST, central_port, central_pool.clone())
+        .build()
+        .await;
+    let mut central_client = common::ClientBuilder::new(common::HOST, central_port)
+        .build()
+        .await;
+
+    let edge_port = common::random_port();
+    let edge = common::ServerBuilder::new(common::HOST, edge_port, pool.clone())
+        .with_relay(central_port, "edge_01")
+        .build()
+        .await;
+    let mut edge_client = common::ClientBuilder::new(common::HOST, edge_port)
+        .build()
+        .await;
+
+    actions::sequence_create(&mut edge_client, "run", Some(r#"{"vehicle": "rover"}"#))
+        .await
+        .unwrap();
+    upload_session(&mut edge_client, "run", &["run/imu"], true).await;
+    assert_eq!(
+        wait_for_rows(&mut central_client, "edge_01@run/imu").await,
+        rows
+    );
+
+    // Sessions are relayed only once finalized
+    let session_uuid = upload_session(&mut edge_client, "run", &["run/gps"], false).await;
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    assert!(
+        actions::do_get(&mut central_client, "edge_01@run/gps")
+            .await
+            .is_err()
+    );
+    actions::session_finalize(&mut edge_client, &session_uuid)
+        .await
+        .unwrap();
+    assert_eq!(
+        wait_for_rows(&mut central_client, "edge_01@run/gps").await,
+        rows
+    );
+
+    // Sessions conflicting with the central data are rejected, without blocking the relay
+    upload_session(
+        &mut central_client,
+        "edge_01@run",
+        &["edge_01@run/lidar"],
+        true,
+    )
+    .await;
+    upload_session(&mut edge_client, "run", &["run/lidar", "run/camera"], true).await;
+    upload_session(&mut edge_client, "run", &["run/odometry"], true).await;
+    assert_eq!(
+        wait_for_rows(&mut central_client, "edge_01@run/odometry").await,
+        rows
+    );
+    assert!(
+        actions::do_get(&mut central_client, "edge_01@run/camera")
+            .await
+            .is_err()
+    );
+
+    edge.shutdown().await;
+    central.shutdown().await;
+    common::drop_database(&pool, central_pool).await;
+}
+
+/// Returns the codes of the notifications of a sequence, or of a topic if `topic`.
+async fn notification_codes(
+    client: &mut common::Client,
+    locator: &str,
+    topic: bool,
+) -> Vec<String> {
+    let response = if topic {
+        actions::topic_notification_list(client, locator).await
+    } else {
+        actions::sequence_notification_list(client, locator).await
+    }
+    .unwrap();
+
+    response["notifications"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|notification| notification["code"].as_str().unwrap().to_owned())
+        .collect()
+}
+
+/// Sequences and topics created independently on the central instance are conflicts,
+/// resolved according to the policy of each edge and notified on the edge.
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_session_relay_conflicts(pool: sqlx::Pool<db::DatabaseType>) {
+    use mosaicod_core::types::RelayConflictPolicy;
+
+    let rows = ext::arrow::testing::dummy_batch().num_rows();
+
+    let central_pool = common::create_database(&pool).await;
+    let central_port = common::random_port();
+    let central = common::ServerBuilder::new(common::HOST, central_port, central_pool.clone())
+        .build()
+        .await;
+    let mut central_client = common::ClientBuilder::new(common::HOST, central_port)
+        .build()
+        .await;
+
+    // Locators of both edges created on the central instance
+    for sequence_name in ["edge_01@run", "edge_02@run"] {
+        actions::sequence_create(&mut central_client, sequence_name, None)
+            .await
+            .unwrap();
+    }
+    upload_session(
+        &mut central_client,
+        "edge_02@run",
+        &["edge_02@run/imu"],
+        true,
+    )
+    .await;
+
+    let rename_pool = common::create_database(&pool).await;
+    let rename_port = common::random_port();
+    let rename_edge = common::ServerBuilder::new(common::HOST, rename_port, rename_pool.clone())
+        .with_relay(central_port, "edge_01")
+        .with_relay_conflict_policy(RelayConflictPolicy::Rename)
+        .build()
+        .await;
+    let mut rename_client = common::ClientBuilder::new(common::HOST, rename_port)
+        .build()
+        .await;
+
+    let merge_port = common::random_port();
+    let merge_edge = common::ServerBuilder::new(common::HOST, merge_port, pool.clone())
+        .with_relay(central_port, "edge_02")
+        .with_relay_conflict_policy(RelayConflictPolicy::Merge)
+        .build()
+        .await;
+    let mut merge_client = common::ClientBuilder::new(common::HOST, merge_port)
+        .build()
+        .await;
+
+    // Renamed sequences receive the later sessions too
+    actions::sequence_create(&mut rename_client, "run", None)
+        .await
+        .unwrap();
+    upload_session(&mut rename_client, "run", &["run/imu"], true).await;
+    upload_session(&mut rename_client, "run", &["run/gps"], true).await;
+    assert_eq!(
+        wait_for_rows(&mut central_client, "edge_01@run_1/gps").await,
+        rows
+    );
+    assert_eq!(
+        wait_for_rows(&mut central_client, "edge_01@run_1/imu").await,
+        rows
+    );
+    assert_eq!(
+        notification_codes(&mut rename_client, "run", false).await,
+        vec!["relay.conflict"]
+    );
+
+    // Merged sessions are added to the central sequence, existing topics are rejected
+    actions::sequence_create(&mut merge_client, "run", None)
+        .await
+        .unwrap();
+    upload_session(&mut merge_client, "run", &["run/imu"], true).await;
+    upload_session(&mut merge_client, "run", &["run/gps"], true).await;
+    assert_eq!(
+        wait_for_rows(&mut central_client, "edge_02@run/gps").await,
+        rows
+    );
+    assert_eq!(
+        notification_codes(&mut merge_client, "run", false).await,
+        vec!["relay.conflict"]
+    );
+    assert_eq!(
+        notification_codes(&mut merge_client, "run/imu", true).await,
+        vec!["relay.conflict"]
+    );
+
+    merge_edge.shutdown().await;
+    rename_edge.shutdown().await;
+    central.shutdown().await;
+    common::drop_database(&pool, rename_pool).await;
+    common::drop_database(&pool, central_pool).await;
+}
+
+/// Only the topics selected by the relay filters are forwarded to the central instance.
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_session_relay_topic_filter(pool: sqlx::Pool<db::DatabaseType>) {
+    use mosaicod_core::types::RelayTopicFilter;
+
+    let rows = ext::arrow::testing::dummy_batch().num_rows();
+
+    let central_pool = common::create_database(&pool).await;
+    let central_port = common::random_port();
+    let central = common::ServerBuilder::new(common::HOST, central_port, central_pool.clone())
+        .build()
+        .await;
+    let mut central_client = common::ClientBuilder::new(common::HOST, central_port)
+        .build()
+        .await;
+
+    let edge_port = common::random_port();
+    let edge = common::ServerBuilder::new(common::HOST, edge_port, pool.clone())
+        .with_relay(central_port, "edge_01")
+        .with_relay_topic_filter(RelayTopicFilter {
+            exclude_locators: "**/camera/*".parse().unwrap(),
+            ..Default::default()
+        })
+        .build()
+        .await;
+    let mut edge_client = common::ClientBuilder::new(common::HOST, edge_port)
+        .build()
+        .await;
+
+    actions::sequence_create(&mut edge_client, "run", None)
+        .await
+        .unwrap();
+    upload_session(&mut edge_client, "run", &["run/camera/front"], true).await;
+    upload_session(
+        &mut edge_client,
+        "run",
+        &["run/imu", "run/camera/rear"],
+        true,
+    )
+    .await;
+    assert_eq!(
+        wait_for_rows(&mut central_client, "edge_01@run/imu").await,
+        rows
+    );
+    for topic_name in ["edge_01@run/camera/front", "edge_01@run/camera/rear"] {
+        assert!(
+            actions::do_get(&mut central_client, topic_name)
+                .await
+                .is_err()
+        );
+    }
+
+    edge.shutdown().await;
+    central.shutdown().await;
+    common::drop_database(&pool, central_pool).await;
+}
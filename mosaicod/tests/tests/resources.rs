@@ -3,7 +3,13 @@ use arrow_flight::Ticket;
 use mosaicod_core::types::{self, Uuid};
 use mosaicod_db as db;
 use mosaicod_ext as ext;
+use mosaicod_facade as facade;
 use mosaicod_marshal as marshal;
+use mosaicod_server as server;
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
 use tests::{self, actions, common};
 
 // ===========================================================================
@@ -231,14 +237,14 @@ async fn test_sequence_delete(pool: sqlx::Pool<db::DatabaseType>) {
         .await
         .unwrap();
 
-    assert_eq!(server.store.list("", None).await.unwrap().len(), 3);
+    assert_eq!(server.store.list("", None).await.unwrap().len(), 4);
 
     actions::sequence_delete(&mut client, sequence_name)
         .await
         .unwrap();
 
     // Make sure that delete command did not actually remove any file from Store.
-    assert_eq!(server.store.list("", None).await.unwrap().len(), 3);
+    assert_eq!(server.store.list("", None).await.unwrap().len(), 4);
 
     let res = actions::sequence_delete(&mut client, sequence_name).await;
     assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
@@ -693,7 +699,10 @@ async fn test_topic_create(pool: sqlx::Pool<db::DatabaseType>) -> sqlx::Result<(
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_topic_create_invalid_format(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_topic_sort_key(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::{Int64Array, RecordBatch};
+    use std::sync::Arc;
+
     let port = common::random_port();
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
@@ -708,241 +717,370 @@ async fn test_topic_create_invalid_format(pool: sqlx::Pool<db::DatabaseType>) {
         .await
         .unwrap();
 
-    let bad_names = [
-        "no_slash",
-        "wrong_sequence/topic",
-        "test_sequence/",
-        "/topic_only",
-    ];
+    let dummy = ext::arrow::testing::dummy_batch();
+    let batch = |timestamps: Vec<i64>, values: Vec<i64>| {
+        RecordBatch::try_new(
+            dummy.schema(),
+            vec![
+                Arc::new(Int64Array::from(timestamps)),
+                Arc::new(Int64Array::from(values)),
+            ],
+        )
+        .unwrap()
+    };
 
-    for name in bad_names {
-        let res = actions::topic_create(&mut client, &session_uuid, name, None).await;
-        assert!(res.is_err(), "topic name {:?} should be rejected", name);
-        let code = res.unwrap_err().code();
-        assert!(
-            matches!(
-                code,
-                tonic::Code::InvalidArgument
-                    | tonic::Code::NotFound
-                    | tonic::Code::PermissionDenied
-            ),
-            "name {:?} got unexpected code {:?}",
-            name,
-            code
-        );
-    }
+    // The sort key needs to be a column of the uploaded data
+    let topic_name = "test_sequence/missing_key";
+    let uuid = actions::topic_create_with_properties(
+        &mut client,
+        &session_uuid,
+        topic_name,
+        r#""sort_key": "none""#,
+    )
+    .await
+    .unwrap();
+    let res = actions::do_put(&mut client, &uuid, topic_name, vec![dummy.clone()], false).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+    // Data not sorted by the sort key across batches is rejected
+    let topic_name = "test_sequence/unsorted";
+    let uuid = actions::topic_create_with_properties(
+        &mut client,
+        &session_uuid,
+        topic_name,
+        r#""sort_key": "value""#,
+    )
+    .await
+    .unwrap();
+    let batches = vec![batch(vec![1, 2], vec![5, 6]), batch(vec![3, 4], vec![1, 2])];
+    let res = actions::do_put(&mut client, &uuid, topic_name, batches, false).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+    // Sorted data is accepted and read back in timestamp order
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let topic_name = "test_sequence/sorted";
+    let uuid = actions::topic_create_with_properties(
+        &mut client,
+        &session_uuid,
+        topic_name,
+        r#""sort_key": "timestamp_ns""#,
+    )
+    .await
+    .unwrap();
+    let batches = vec![batch(vec![1, 2], vec![9, 8]), batch(vec![2, 5], vec![7, 6])];
+    actions::do_put(&mut client, &uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let batches = actions::do_get(&mut client, topic_name).await.unwrap();
+    let timestamps: Vec<i64> = batches
+        .iter()
+        .flat_map(|b| {
+            b.column_by_name("timestamp_ns")
+                .unwrap()
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap()
+                .values()
+                .to_vec()
+        })
+        .collect();
+    assert_eq!(timestamps, vec![1, 2, 2, 5]);
 
     server.shutdown().await;
 }
 
+/// Data uploaded into topics with a registered schema must match it.
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_topic_delete(pool: sqlx::Pool<db::DatabaseType>) -> sqlx::Result<()> {
-    let port: u16 = common::random_port();
+async fn test_topic_registered_schema(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::{Float64Array, Int64Array, RecordBatch};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use base64::Engine;
 
+    let port = common::random_port();
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
         .await;
-
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
     let sequence_name = "test_sequence";
-    let topic_name = &format!("{}/my_topic", sequence_name);
-
     actions::sequence_create(&mut client, sequence_name, None)
         .await
         .unwrap();
-
     let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
-    assert!(session_uuid.is_valid());
 
-    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+    let dummy = ext::arrow::testing::dummy_batch();
+    let encoded = base64::engine::general_purpose::STANDARD
+        .encode(ext::arrow::schema_to_ipc(&dummy.schema()).unwrap());
+    let properties = format!(r#""arrow_schema": "{encoded}""#);
+
+    // Invalid schemas are rejected when the topic is created
+    let res = actions::topic_create_with_properties(
+        &mut client,
+        &session_uuid,
+        "test_sequence/bad_schema",
+        r#""arrow_schema": "not a schema""#,
+    )
+    .await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+    // Data with a different schema is rejected
+    let topic_name = "test_sequence/mismatch";
+    let uuid =
+        actions::topic_create_with_properties(&mut client, &session_uuid, topic_name, &properties)
+            .await
+            .unwrap();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("timestamp_ns", DataType::Int64, false),
+        Field::new("value", DataType::Float64, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2])),
+            Arc::new(Float64Array::from(vec![1.0, 2.0])),
+        ],
+    )
+    .unwrap();
+    let res = actions::do_put(&mut client, &uuid, topic_name, vec![batch], false).await;
+    let err = res.unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    assert!(err.message().contains("column `value`"));
+
+    // Matching data is accepted and the schema can be retrieved
+    let topic_name = "test_sequence/match";
+    let uuid =
+        actions::topic_create_with_properties(&mut client, &session_uuid, topic_name, &properties)
+            .await
+            .unwrap();
+    actions::do_put(&mut client, &uuid, topic_name, vec![dummy.clone()], false)
         .await
         .unwrap();
-    assert!(topic_uuid.is_valid());
 
-    let batches = vec![ext::arrow::testing::dummy_batch()];
-    actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
+    let registered = actions::topic_schema_get(&mut client, topic_name)
         .await
         .unwrap();
+    assert_eq!(registered.as_deref(), Some(encoded.as_str()));
 
-    actions::session_finalize(&mut client, &session_uuid)
+    // Topics created without a schema have none
+    let topic_name = "test_sequence/no_schema";
+    actions::topic_create(&mut client, &session_uuid, topic_name, None)
         .await
         .unwrap();
-
-    actions::topic_delete(&mut client, topic_name)
+    let registered = actions::topic_schema_get(&mut client, topic_name)
         .await
         .unwrap();
-
-    let res = actions::topic_delete(&mut client, topic_name).await;
-    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+    assert!(registered.is_none());
 
     server.shutdown().await;
-    Ok(())
 }
 
+/// The schema registered for an upsert topic can evolve between sessions, the rows written
+/// under previous versions are read with the added columns set to null.
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_topic_flight_info(pool: sqlx::Pool<db::DatabaseType>) {
-    let port = common::random_port();
+async fn test_topic_schema_evolution(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::{Array, AsArray, Int64Array, RecordBatch, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use base64::Engine;
 
-    let server = common::ServerBuilder::new(common::HOST, port, pool)
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool.clone())
         .build()
         .await;
-
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
     let sequence_name = "test_sequence";
-
     actions::sequence_create(&mut client, sequence_name, None)
         .await
         .unwrap();
 
-    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
-        .await
-        .unwrap();
-    assert!(session_uuid.is_valid());
-
-    // Check flight info for a locked topic without data.
-
-    let topic_name = "test_sequence/my_empty_topic";
+    let encode = |schema: &Schema| {
+        base64::engine::general_purpose::STANDARD.encode(ext::arrow::schema_to_ipc(schema).unwrap())
+    };
+    let timestamp = Field::new("timestamp_ns", DataType::Int64, false);
+    let value = Field::new("value", DataType::Int64, false);
+
+    let topic_name = "test_sequence/calibration";
+    let v1 = ext::arrow::testing::dummy_batch().schema();
+    let properties = format!(
+        r#""primary_key": "timestamp_ns", "arrow_schema": "{}""#,
+        encode(&v1)
+    );
 
-    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
-    assert!(uuid.is_valid());
-
-    // Metadata should be available even if topic is unlocked, but not all info are filled.
-    let info = actions::get_flight_info(&mut client, topic_name)
+    let uuid =
+        actions::topic_create_with_properties(&mut client, &session_uuid, topic_name, &properties)
+            .await
+            .unwrap();
+    let batch = RecordBatch::try_new(
+        v1.clone(),
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2])),
+            Arc::new(Int64Array::from(vec![10, 20])),
+        ],
+    )
+    .unwrap();
+    actions::do_put(&mut client, &uuid, topic_name, vec![batch], false)
         .await
         .unwrap();
-    assert_eq!(info.endpoint.len(), 1);
-    assert!(!info.endpoint.first().unwrap().app_metadata.is_empty());
-
-    let app_metadata: marshal::flight::TopicAppMetadata = info
-        .endpoint
-        .first()
-        .unwrap()
-        .clone()
-        .app_metadata
-        .try_into()
-        .unwrap();
-
-    assert!(!app_metadata.locked);
-    assert_eq!(app_metadata.resource_locator, topic_name);
-    assert!(app_metadata.info.is_none());
-    assert_ne!(app_metadata.created_at_ns, 0);
-    assert!(app_metadata.completed_at_ns.is_none());
-
-    let batches = vec![ext::arrow::testing::dummy_empty_batch()];
-
-    let response = actions::do_put(&mut client, &uuid, topic_name, batches, false)
+    actions::session_finalize(&mut client, &session_uuid)
         .await
         .unwrap();
 
-    if response.into_inner().message().await.unwrap().is_some() {
-        panic!("Received a not-empty response!");
+    // Incompatible evolutions are rejected
+    let incompatible = [
+        Schema::new(vec![
+            timestamp.clone(),
+            Field::new("value", DataType::Float64, false),
+        ]),
+        Schema::new(vec![timestamp.clone()]),
+        Schema::new(vec![
+            timestamp.clone(),
+            value.clone(),
+            Field::new("note", DataType::Utf8, false),
+        ]),
+    ];
+    for schema in &incompatible {
+        let res = actions::topic_schema_evolve(&mut client, topic_name, &encode(schema)).await;
+        assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
     }
 
-    let info = actions::get_flight_info(&mut client, topic_name)
+    // Nullable columns can be added
+    let v2 = Arc::new(Schema::new(vec![
+        timestamp.clone(),
+        value.clone(),
+        Field::new("note", DataType::Utf8, true),
+    ]));
+    let version = actions::topic_schema_evolve(&mut client, topic_name, &encode(&v2))
         .await
         .unwrap();
-    assert_eq!(info.endpoint.len(), 1);
-    assert!(!info.endpoint.first().unwrap().app_metadata.is_empty());
-
-    let app_metadata: marshal::flight::TopicAppMetadata = info
-        .endpoint
-        .first()
-        .unwrap()
-        .clone()
-        .app_metadata
-        .try_into()
-        .unwrap();
-
-    assert!(app_metadata.locked);
-    assert_ne!(app_metadata.created_at_ns, 0);
-    assert_ne!(app_metadata.completed_at_ns.unwrap(), 0);
-    assert_eq!(app_metadata.resource_locator, topic_name);
-
-    let info = app_metadata.info.unwrap();
-    assert_eq!(info.chunks_number, 0);
-    assert_eq!(info.total_bytes, 0);
-    assert!(info.timestamp.is_none());
-
-    // Check flight info for a locked topic with data.
-
-    let topic_name = "test_sequence/my_topic";
-
-    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+    assert_eq!(version, 2);
+    let registered = actions::topic_schema_get(&mut client, topic_name)
         .await
         .unwrap();
-    assert!(uuid.is_valid());
-
-    let batches = vec![ext::arrow::testing::dummy_batch()];
+    assert_eq!(registered, Some(encode(&v2)));
 
-    let response = actions::do_put(&mut client, &uuid, topic_name, batches, false)
+    // A later session upserts rows with the added column
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
-
-    if response.into_inner().message().await.unwrap().is_some() {
-        panic!("Received a not-empty response!");
-    }
-
-    let info = actions::get_flight_info(&mut client, topic_name)
+    let properties = format!(
+        r#""primary_key": "timestamp_ns", "arrow_schema": "{}""#,
+        encode(&v2)
+    );
+    let uuid =
+        actions::topic_create_with_properties(&mut client, &session_uuid, topic_name, &properties)
+            .await
+            .unwrap();
+    let batch = RecordBatch::try_new(
+        v2.clone(),
+        vec![
+            Arc::new(Int64Array::from(vec![2, 3])),
+            Arc::new(Int64Array::from(vec![21, 30])),
+            Arc::new(StringArray::from(vec!["updated", "added"])),
+        ],
+    )
+    .unwrap();
+    actions::do_put(&mut client, &uuid, topic_name, vec![batch], false)
         .await
         .unwrap();
-    assert_eq!(info.endpoint.len(), 1);
-    assert!(!info.endpoint.first().unwrap().app_metadata.is_empty());
-
-    let app_metadata: marshal::flight::TopicAppMetadata = info
-        .endpoint
-        .first()
-        .unwrap()
-        .clone()
-        .app_metadata
-        .try_into()
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
         .unwrap();
 
-    assert!(app_metadata.locked);
-    assert_ne!(app_metadata.created_at_ns, 0);
-    assert_ne!(app_metadata.completed_at_ns.unwrap(), 0);
-    assert_eq!(app_metadata.resource_locator, topic_name);
+    let batches = actions::do_get(&mut client, topic_name).await.unwrap();
+    let rows: Vec<(i64, Option<String>)> = batches
+        .iter()
+        .flat_map(|b| {
+            let timestamps = b
+                .column_by_name("timestamp_ns")
+                .unwrap()
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap();
+            let notes = b.column_by_name("note").unwrap().as_string_view();
+            (0..b.num_rows())
+                .map(|i| {
+                    let note = notes.is_valid(i).then(|| notes.value(i).to_owned());
+                    (timestamps.value(i), note)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            (1, None),
+            (2, Some("updated".to_owned())),
+            (3, Some("added".to_owned())),
+        ]
+    );
 
-    let info = app_metadata.info.unwrap();
-    assert_eq!(info.chunks_number, 1);
-    assert_eq!(info.total_bytes, 895);
-    let ts_range: types::TimestampRange = info.timestamp.unwrap().into();
-    assert_eq!(ts_range.start.as_i64(), 10000);
-    assert_eq!(ts_range.end.as_i64(), 10030);
+    // Chunks are tagged with the schema version they were written under
+    let versions: Vec<Option<i32>> =
+        sqlx::query_scalar("SELECT schema_version FROM chunk_t ORDER BY chunk_id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(versions, vec![Some(1), Some(2)]);
 
     server.shutdown().await;
 }
 
+/// Topics are written by a single session, data uploaded with interleaved time ranges is
+/// returned in timestamp order regardless of the order it was stored in.
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_topic_notification_create(pool: sqlx::Pool<db::DatabaseType>) {
-    let port = common::random_port();
+async fn test_topic_do_get_time_ordered(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::{Int64Array, RecordBatch};
+    use std::sync::Arc;
 
+    let port = common::random_port();
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
         .await;
-
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
-    let sequence_name = "test_sequence_topic_notification_create";
-    let topic_name = &format!("{}/my_topic", sequence_name);
-
+    let sequence_name = "test_sequence";
     actions::sequence_create(&mut client, sequence_name, None)
         .await
         .unwrap();
     let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
-    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+
+    let schema = ext::arrow::testing::dummy_batch().schema();
+    let batch = |timestamps: Vec<i64>| {
+        let values = timestamps.clone();
+        RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(timestamps)),
+                Arc::new(Int64Array::from(values)),
+            ],
+        )
+        .unwrap()
+    };
+
+    let topic_name = "test_sequence/interleaved";
+    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
         .await
         .unwrap();
-
-    let batches = vec![ext::arrow::testing::dummy_batch()];
-    actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
+    let batches = vec![
+        batch(vec![10, 30, 50]),
+        batch(vec![20, 40]),
+        batch(vec![0, 60]),
+    ];
+    actions::do_put(&mut client, &uuid, topic_name, batches, false)
         .await
         .unwrap();
 
@@ -950,99 +1088,110 @@ async fn test_topic_notification_create(pool: sqlx::Pool<db::DatabaseType>) {
         .await
         .unwrap();
 
-    let error_msg = format!("Error in {}", topic_name);
-    actions::topic_notification_create(
-        &mut client,
-        topic_name,
-        types::NotificationType::Error.to_string(),
-        error_msg,
-    )
-    .await
-    .unwrap();
+    let batches = actions::do_get(&mut client, topic_name).await.unwrap();
+    let timestamps: Vec<i64> = batches
+        .iter()
+        .flat_map(|b| {
+            b.column_by_name("timestamp_ns")
+                .unwrap()
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap()
+                .values()
+                .to_vec()
+        })
+        .collect();
+    assert_eq!(timestamps, vec![0, 10, 20, 30, 40, 50, 60]);
 
     server.shutdown().await;
 }
 
+/// Upsert topics hold the data of several sessions, each stored in its own data files. Rows
+/// written by two sessions with overlapping time ranges are merged in timestamp order.
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_topic_notification_list(pool: sqlx::Pool<db::DatabaseType>) {
-    let port: u16 = common::random_port();
+async fn test_topic_do_get_time_ordered_across_sessions(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::{Int64Array, RecordBatch};
+    use std::sync::Arc;
 
+    let port = common::random_port();
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
         .await;
-
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
-    let sequence_name = "test_sequence_topic_notification_create";
-    let topic_name = &format!("{}/my_topic", sequence_name);
-    let notification_type = types::NotificationType::Error.to_string();
-    let notifications_size = 5;
-
-    actions::setup_topic_with_notifications(
-        &mut client,
-        sequence_name,
-        topic_name,
-        notification_type.clone(),
-        notifications_size,
-    )
-    .await
-    .unwrap();
 
-    let r = actions::topic_notification_list(&mut client, topic_name)
-        .await
+    let sequence_name = "test_sequence";
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
         .unwrap();
-    let notifications = r["notifications"].as_array().unwrap();
-    assert_eq!(notifications.len(), notifications_size);
-
-    for (i, notification) in notifications.iter().enumerate() {
-        let error_msg = format!("Error {}_{}", topic_name, i + 1);
-        assert_eq!(notification["notification_type"], notification_type);
-        assert_eq!(notification["name"].as_str().unwrap(), topic_name);
-        assert_eq!(notification["msg"], error_msg);
-    }
-
-    server.shutdown().await;
-}
-
-#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_topic_notification_purge(pool: sqlx::Pool<db::DatabaseType>) {
-    let port: u16 = common::random_port();
-
-    let server = common::ServerBuilder::new(common::HOST, port, pool)
-        .build()
-        .await;
-
-    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
-    let sequence_name = "test_sequence_topic_notification_create";
-    let topic_name = &format!("{}/my_topic", sequence_name);
-    let notification_type = types::NotificationType::Error.to_string();
-    let notifications_size = 5;
 
-    actions::setup_topic_with_notifications(
-        &mut client,
-        sequence_name,
-        topic_name,
-        notification_type.clone(),
-        notifications_size,
-    )
-    .await
-    .unwrap();
+    let schema = ext::arrow::testing::dummy_batch().schema();
+    let batch = |timestamps: Vec<i64>| {
+        let values = timestamps.clone();
+        RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(timestamps)),
+                Arc::new(Int64Array::from(values)),
+            ],
+        )
+        .unwrap()
+    };
 
-    actions::topic_notification_purge(&mut client, topic_name)
+    let topic_name = "test_sequence/interleaved";
+    let primary_key = r#""primary_key": "timestamp_ns""#;
+    for timestamps in [vec![10, 30, 50], vec![0, 20, 40, 60]] {
+        let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+            .await
+            .unwrap();
+        let uuid = actions::topic_create_with_properties(
+            &mut client,
+            &session_uuid,
+            topic_name,
+            primary_key,
+        )
         .await
         .unwrap();
-    let r = actions::topic_notification_list(&mut client, topic_name)
+        actions::do_put(
+            &mut client,
+            &uuid,
+            topic_name,
+            vec![batch(timestamps)],
+            false,
+        )
         .await
         .unwrap();
-    let notifications = r["notifications"].as_array().unwrap();
-    assert_eq!(notifications.len(), 0);
+        actions::session_finalize(&mut client, &session_uuid)
+            .await
+            .unwrap();
+    }
+
+    let batches = actions::do_get(&mut client, topic_name).await.unwrap();
+    let timestamps: Vec<i64> = batches
+        .iter()
+        .flat_map(|b| {
+            b.column_by_name("timestamp_ns")
+                .unwrap()
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap()
+                .values()
+                .to_vec()
+        })
+        .collect();
+    assert_eq!(timestamps, vec![0, 10, 20, 30, 40, 50, 60]);
 
     server.shutdown().await;
 }
 
+/// Chunks record the timestamp range of their rows, reads of a time range only return the
+/// rows in the range.
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_topic_notification_create_nonexistent(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_topic_do_get_timestamp_range(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::{Int64Array, RecordBatch};
+    use std::sync::Arc;
+
     let port = common::random_port();
-    let server = common::ServerBuilder::new(common::HOST, port, pool)
+    let server = common::ServerBuilder::new(common::HOST, port, pool.clone())
         .build()
         .await;
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
@@ -1051,21 +1200,91 @@ async fn test_topic_notification_create_nonexistent(pool: sqlx::Pool<db::Databas
     actions::sequence_create(&mut client, sequence_name, None)
         .await
         .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
 
-    let res = actions::topic_notification_create(
-        &mut client,
-        "test_sequence/never_existed",
-        types::NotificationType::Error.to_string(),
-        "msg".to_string(),
+    let schema = ext::arrow::testing::dummy_batch().schema();
+    let batch = |timestamps: Vec<i64>| {
+        let values = timestamps.clone();
+        RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(timestamps)),
+                Arc::new(Int64Array::from(values)),
+            ],
+        )
+        .unwrap()
+    };
+
+    let topic_name = "test_sequence/ranges";
+    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+    let batches = vec![batch(vec![0, 10]), batch(vec![20, 30]), batch(vec![40, 50])];
+    actions::do_put(&mut client, &uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let ranges: Vec<(Option<i64>, Option<i64>)> = sqlx::query_as(
+        "SELECT start_index_timestamp, end_index_timestamp FROM chunk_t ORDER BY chunk_id",
     )
-    .await;
-    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        ranges,
+        vec![
+            (Some(0), Some(10)),
+            (Some(20), Some(30)),
+            (Some(40), Some(50))
+        ]
+    );
+
+    let read = async |client: &mut common::Client, start: i64, end: i64| {
+        let ticket = types::flight::TicketTopic {
+            locator: topic_name.parse().unwrap(),
+            timestamp_range: Some(types::TimestampRange::between(start.into(), end.into())),
+            blob_references: false,
+            changes_since: None,
+            session: None,
+            anonymization: None,
+        };
+        let ticket = Ticket {
+            ticket: marshal::flight::ticket_topic_to_binary(ticket)
+                .unwrap()
+                .into(),
+        };
+        let batches = actions::do_get_with_ticket(client, ticket).await.unwrap();
+        batches
+            .iter()
+            .flat_map(|b| {
+                b.column_by_name("timestamp_ns")
+                    .unwrap()
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap()
+                    .values()
+                    .to_vec()
+            })
+            .collect::<Vec<i64>>()
+    };
+
+    assert_eq!(read(&mut client, 20, 40).await, vec![20, 30]);
+    assert_eq!(read(&mut client, 5, 45).await, vec![10, 20, 30, 40]);
+    assert_eq!(read(&mut client, 60, 100).await, Vec::<i64>::new());
 
     server.shutdown().await;
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_topic_notification_list_empty(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_topic_dedup_policy(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::{Int64Array, RecordBatch};
+    use std::sync::Arc;
+
     let port = common::random_port();
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
@@ -1073,36 +1292,123 @@ async fn test_topic_notification_list_empty(pool: sqlx::Pool<db::DatabaseType>)
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
     let sequence_name = "test_sequence";
-    let topic_name = &format!("{}/my_topic", sequence_name);
-
     actions::sequence_create(&mut client, sequence_name, None)
         .await
         .unwrap();
     let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
-    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
-        .await
-        .unwrap();
-    let batches = vec![ext::arrow::testing::dummy_batch()];
-    actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
+
+    let schema = ext::arrow::testing::dummy_batch().schema();
+    let batches = || {
+        vec![
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from(vec![1, 2, 3])),
+                    Arc::new(Int64Array::from(vec![10, 20, 30])),
+                ],
+            )
+            .unwrap(),
+            // Overlapping re-upload of the same data, with a corrected value
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from(vec![2, 3])),
+                    Arc::new(Int64Array::from(vec![20, 31])),
+                ],
+            )
+            .unwrap(),
+        ]
+    };
+
+    let policies = [
+        ("none", r#""dedup_policy": { "policy": "none" }"#),
+        (
+            "exact",
+            r#""dedup_policy": { "policy": "drop_duplicates" }"#,
+        ),
+        (
+            "lww",
+            r#""dedup_policy": { "policy": "last_write_wins", "key": "timestamp_ns" }"#,
+        ),
+    ];
+    for (name, properties) in policies {
+        let topic_name = format!("test_sequence/{name}");
+        let uuid = actions::topic_create_with_properties(
+            &mut client,
+            &session_uuid,
+            &topic_name,
+            properties,
+        )
         .await
         .unwrap();
+        actions::do_put(&mut client, &uuid, &topic_name, batches(), false)
+            .await
+            .unwrap();
+    }
+
+    // Data is readable once the session is finalized
     actions::session_finalize(&mut client, &session_uuid)
         .await
         .unwrap();
 
-    let r = actions::topic_notification_list(&mut client, topic_name)
+    // The dedup key needs to be a column of the uploaded data
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
-    let notifications = r["notifications"].as_array().unwrap();
-    assert_eq!(notifications.len(), 0);
+    let topic_name = "test_sequence/missing_key";
+    let uuid = actions::topic_create_with_properties(
+        &mut client,
+        &session_uuid,
+        topic_name,
+        r#""dedup_policy": { "policy": "last_write_wins", "key": "none" }"#,
+    )
+    .await
+    .unwrap();
+    let res = actions::do_put(&mut client, &uuid, topic_name, batches(), false).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+    let rows = async |client: &mut common::Client, name: &str| -> Vec<(i64, i64)> {
+        let batches = actions::do_get(client, &format!("test_sequence/{name}"))
+            .await
+            .unwrap();
+        let mut rows: Vec<(i64, i64)> = batches
+            .iter()
+            .flat_map(|b| {
+                let column = |i: usize| {
+                    b.column(i)
+                        .as_any()
+                        .downcast_ref::<Int64Array>()
+                        .unwrap()
+                        .values()
+                        .to_vec()
+                };
+                column(0).into_iter().zip(column(1)).collect::<Vec<_>>()
+            })
+            .collect();
+        rows.sort();
+        rows
+    };
+
+    assert_eq!(rows(&mut client, "none").await.len(), 5);
+    assert_eq!(
+        rows(&mut client, "exact").await,
+        vec![(1, 10), (2, 20), (3, 30), (3, 31)]
+    );
+    assert_eq!(
+        rows(&mut client, "lww").await,
+        vec![(1, 10), (2, 20), (3, 31)]
+    );
 
     server.shutdown().await;
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_topic_delete_nonexistent(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_topic_upsert(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::{Int64Array, RecordBatch, StringArray};
+    use std::sync::Arc;
+
     let port = common::random_port();
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
@@ -1114,371 +1420,909 @@ async fn test_topic_delete_nonexistent(pool: sqlx::Pool<db::DatabaseType>) {
         .await
         .unwrap();
 
-    let res = actions::topic_delete(&mut client, "test_sequence/never_existed").await;
-    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+    let schema = ext::arrow::testing::dummy_batch().schema();
+    let batch = |timestamps: Vec<i64>, values: Vec<i64>| {
+        RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(timestamps)),
+                Arc::new(Int64Array::from(values)),
+            ],
+        )
+        .unwrap()
+    };
 
-    let res = actions::topic_delete(&mut client, "ghost_sequence/ghost_topic").await;
-    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+    let topic_name = "test_sequence/calibration";
+    let primary_key = r#""primary_key": "timestamp_ns""#;
 
-    server.shutdown().await;
-}
+    // The first session creates the topic
+    let (first_session, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let topic_uuid =
+        actions::topic_create_with_properties(&mut client, &session_uuid, topic_name, primary_key)
+            .await
+            .unwrap();
+    let batches = vec![batch(vec![1, 2, 3], vec![10, 20, 30])];
+    actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
+        .await
+        .unwrap();
 
-#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_topic_delete_unlocked(pool: sqlx::Pool<db::DatabaseType>) {
-    let port = common::random_port();
-    let server = common::ServerBuilder::new(common::HOST, port, pool)
-        .build()
-        .await;
-    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+    // Data is not visible until the session is finalized
+    let res = actions::do_get(&mut client, topic_name).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::FailedPrecondition);
 
-    let sequence_name = "test_sequence";
-    let topic_name = &format!("{}/unlocked_topic", sequence_name);
+    // Rows can not be upserted by the same session
+    let res =
+        actions::topic_create_with_properties(&mut client, &session_uuid, topic_name, primary_key)
+            .await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::AlreadyExists);
 
-    actions::sequence_create(&mut client, sequence_name, None)
+    actions::session_finalize(&mut client, &session_uuid)
         .await
         .unwrap();
-    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+
+    // A later session upserts rows, updating the existing ones
+    let (last_session, session_uuid) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
-    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+
+    // The primary key needs to match the one of the topic
+    let res = actions::topic_create_with_properties(
+        &mut client,
+        &session_uuid,
+        topic_name,
+        r#""primary_key": "value""#,
+    )
+    .await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+    let upsert_uuid =
+        actions::topic_create_with_properties(&mut client, &session_uuid, topic_name, primary_key)
+            .await
+            .unwrap();
+    assert_ne!(upsert_uuid, topic_uuid);
+
+    // The session can not be finalized until the rows are uploaded
+    assert!(
+        actions::session_finalize(&mut client, &session_uuid)
+            .await
+            .is_err()
+    );
+
+    let batches = vec![batch(vec![2, 4], vec![21, 40])];
+    actions::do_put(&mut client, &upsert_uuid, topic_name, batches, false)
         .await
         .unwrap();
-    assert!(topic_uuid.is_valid());
 
-    let res = actions::topic_delete(&mut client, topic_name).await;
-    assert!(res.is_ok());
-
-    let res = actions::topic_delete(&mut client, topic_name).await;
-    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+    // Upserted rows are not visible until the session is finalized
+    let batches = actions::do_get(&mut client, topic_name).await.unwrap();
+    assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 3);
 
-    server.shutdown().await;
-}
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
 
-// ===========================================================================
-// Session tests
-// ===========================================================================
+    // The upsert key can not be reused once its session is finalized
+    let batches = vec![batch(vec![5], vec![50])];
+    let res = actions::do_put(&mut client, &upsert_uuid, topic_name, batches, false).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::FailedPrecondition);
 
-#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_session_create(pool: sqlx::Pool<db::DatabaseType>) -> sqlx::Result<()> {
-    let port = common::random_port();
+    let batches = actions::do_get(&mut client, topic_name).await.unwrap();
+    let rows: Vec<(i64, i64)> = batches
+        .iter()
+        .flat_map(|b| {
+            let column = |i: usize| {
+                b.column(i)
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap()
+                    .values()
+                    .to_vec()
+            };
+            column(0).into_iter().zip(column(1)).collect::<Vec<_>>()
+        })
+        .collect();
+    assert_eq!(rows, vec![(1, 10), (2, 21), (3, 30), (4, 40)]);
+
+    // The change feed returns the rows changed after the given session
+    let changes = async |client: &mut common::Client, options: &str| {
+        let info = actions::get_flight_info_with_options(client, topic_name, options).await?;
+        let ticket = info.endpoint[0].ticket.clone().unwrap();
+        let batches = actions::do_get_with_ticket(client, ticket).await?;
+        Ok::<_, tonic::Status>(
+            batches
+                .iter()
+                .flat_map(|b| {
+                    let timestamps = b.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+                    let changes = b.column(2).as_any().downcast_ref::<StringArray>().unwrap();
+                    timestamps
+                        .values()
+                        .iter()
+                        .zip(changes.iter())
+                        .map(|(ts, change)| (*ts, change.unwrap().to_owned()))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>(),
+        )
+    };
 
-    let server = common::ServerBuilder::new(common::HOST, port, pool)
-        .build()
-        .await;
+    let options = format!(r#", "changes_since_session": "{}""#, first_session);
+    assert_eq!(
+        changes(&mut client, &options).await.unwrap(),
+        vec![(2, "update".to_owned()), (4, "insert".to_owned())]
+    );
 
-    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+    let all_inserted = changes(&mut client, r#", "changes_since_ns": 0"#)
+        .await
+        .unwrap();
+    assert_eq!(all_inserted.len(), 4);
+    assert!(all_inserted.iter().all(|(_, change)| change == "insert"));
+
+    let options = format!(r#", "changes_since_session": "{}""#, last_session);
+    assert!(changes(&mut client, &options).await.unwrap().is_empty());
+
+    // The data of a single session is returned as uploaded
+    let session_rows = async |client: &mut common::Client, session: &types::SessionLocator| {
+        let options = format!(r#", "session": "{}""#, session);
+        let info = actions::get_flight_info_with_options(client, topic_name, &options).await?;
+        let ticket = info.endpoint[0].ticket.clone().unwrap();
+        let batches = actions::do_get_with_ticket(client, ticket).await?;
+        Ok::<_, tonic::Status>(
+            batches
+                .iter()
+                .flat_map(|b| {
+                    let column = |i: usize| {
+                        b.column(i)
+                            .as_any()
+                            .downcast_ref::<Int64Array>()
+                            .unwrap()
+                            .values()
+                            .to_vec()
+                    };
+                    column(0).into_iter().zip(column(1)).collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>(),
+        )
+    };
 
-    let sequence_name = "test_sequence";
+    assert_eq!(
+        session_rows(&mut client, &first_session).await.unwrap(),
+        vec![(1, 10), (2, 20), (3, 30)]
+    );
+    assert_eq!(
+        session_rows(&mut client, &last_session).await.unwrap(),
+        vec![(2, 21), (4, 40)]
+    );
 
-    actions::sequence_create(&mut client, sequence_name, None)
-        .await
-        .unwrap();
-    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
-        .await
-        .unwrap();
-    assert!(session_uuid.is_valid());
+    // The data of a session can't be requested for a sequence or together with changes
+    let options = format!(r#", "session": "{}""#, last_session);
+    let res = actions::get_flight_info_with_options(&mut client, sequence_name, &options).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
+    let options = format!(
+        r#", "session": "{}", "changes_since_session": "{}""#,
+        last_session, first_session
+    );
+    let res = actions::get_flight_info_with_options(&mut client, topic_name, &options).await;
+    assert!(res.is_err());
 
     server.shutdown().await;
-    Ok(())
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_session_finalize(pool: sqlx::Pool<db::DatabaseType>) {
-    let port = common::random_port();
+async fn test_topic_watermark(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::{Int64Array, RecordBatch};
+    use std::sync::Arc;
 
+    let port = common::random_port();
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
         .await;
-
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
     let sequence_name = "test_sequence";
-
     actions::sequence_create(&mut client, sequence_name, None)
         .await
         .unwrap();
 
-    let (session_locator, session_uuid) = actions::session_create(&mut client, sequence_name)
+    let schema = ext::arrow::testing::dummy_batch().schema();
+    let batch = |timestamps: Vec<i64>| {
+        let values = Int64Array::from(vec![0; timestamps.len()]);
+        RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(timestamps)), Arc::new(values)],
+        )
+        .unwrap()
+    };
+
+    let topic_name = "test_sequence/calibration";
+    let primary_key = r#""primary_key": "timestamp_ns""#;
+
+    // The watermark is set once the data is published
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
-    assert!(session_uuid.is_valid());
+    let uuid =
+        actions::topic_create_with_properties(&mut client, &session_uuid, topic_name, primary_key)
+            .await
+            .unwrap();
+    actions::do_put(
+        &mut client,
+        &uuid,
+        topic_name,
+        vec![batch(vec![1, 2, 3])],
+        false,
+    )
+    .await
+    .unwrap();
 
-    let uuid = actions::topic_create(&mut client, &session_uuid, "test_sequence/my_topic", None)
+    let watermark = actions::topic_watermark(&mut client, topic_name)
         .await
         .unwrap();
-    assert!(uuid.is_valid());
+    assert!(watermark["watermark_ns"].is_null());
+    assert_eq!(watermark["pending_sessions"], 1);
+    assert_eq!(watermark["complete"], false);
 
-    // Calling finalize with unlocked topics should fail.
-    assert_eq!(
-        actions::session_finalize(&mut client, &session_uuid)
-            .await
-            .unwrap_err()
-            .code(),
-        tonic::Code::FailedPrecondition,
-    );
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
 
-    let batches = vec![ext::arrow::testing::dummy_batch()];
+    let watermark = actions::topic_watermark(&mut client, topic_name)
+        .await
+        .unwrap();
+    assert_eq!(watermark["watermark_ns"], 3);
+    assert_eq!(watermark["pending_sessions"], 0);
+    assert_eq!(watermark["complete"], true);
 
-    let response = actions::do_put(&mut client, &uuid, "test_sequence/my_topic", batches, false)
+    // Rows upserted by a later session advance the watermark when it is finalized
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
+    let uuid =
+        actions::topic_create_with_properties(&mut client, &session_uuid, topic_name, primary_key)
+            .await
+            .unwrap();
+    actions::do_put(
+        &mut client,
+        &uuid,
+        topic_name,
+        vec![batch(vec![2, 10])],
+        false,
+    )
+    .await
+    .unwrap();
 
-    let mut response_reader = response.into_inner();
-    if response_reader.message().await.unwrap().is_some() {
-        panic!("Received a not-empty response!");
-    }
+    let watermark = actions::topic_watermark(&mut client, topic_name)
+        .await
+        .unwrap();
+    assert_eq!(watermark["watermark_ns"], 3);
+    assert_eq!(watermark["pending_sessions"], 1);
 
     actions::session_finalize(&mut client, &session_uuid)
         .await
         .unwrap();
 
-    let ctx = server.context();
-    let mut cx = ctx.db.connection();
-    let db_session = db::session_find_by_locator(&mut cx, &session_locator)
+    let watermark = actions::topic_watermark(&mut client, topic_name)
         .await
         .unwrap();
-    assert!(db_session.completion_timestamp().unwrap().as_i64() > 0);
+    assert_eq!(watermark["watermark_ns"], 10);
+    assert_eq!(watermark["complete"], true);
 
-    // Finalize on an empty session should fail.
-    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+    // Each advance is reported by the activity feed
+    let feed = actions::activity_feed(&mut client, serde_json::json!({ "locator": sequence_name }))
         .await
         .unwrap();
-    assert!(session_uuid.is_valid());
-    assert_eq!(
-        actions::session_finalize(&mut client, &session_uuid)
-            .await
-            .unwrap_err()
-            .code(),
-        tonic::Code::FailedPrecondition,
-    );
+    let watermarks: Vec<_> = feed["activities"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|a| a["event"] == "topic_watermark")
+        .map(|a| {
+            (
+                a["subject"].as_str().unwrap(),
+                a["watermark_ns"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(watermarks, [(topic_name, 3), (topic_name, 10)]);
+
+    let err = actions::topic_watermark(&mut client, "test_sequence/missing")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
 
     server.shutdown().await;
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_session_delete(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_topic_copy(pool: sqlx::Pool<db::DatabaseType>) {
     let port = common::random_port();
-
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
         .await;
-
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
-    let sequence_name = "test_sequence";
-    let topic_name = &format!("{}/my_topic", sequence_name);
+    for sequence_name in ["run_a", "run_b", "calibration"] {
+        actions::sequence_create(&mut client, sequence_name, None)
+            .await
+            .unwrap();
+    }
 
-    actions::sequence_create(&mut client, sequence_name, None)
+    let topic_name = "run_a/imu";
+    let (_, session_uuid) = actions::session_create(&mut client, "run_a").await.unwrap();
+    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
         .await
         .unwrap();
+    actions::do_put(
+        &mut client,
+        &uuid,
+        topic_name,
+        vec![ext::arrow::testing::dummy_batch()],
+        false,
+    )
+    .await
+    .unwrap();
 
-    let (session_locator, session_uuid) = actions::session_create(&mut client, sequence_name)
+    // Topics are copied once published
+    let err = actions::topic_copy(&mut client, topic_name, "run_b")
         .await
-        .unwrap();
-    assert!(session_uuid.is_valid());
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
 
-    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+    actions::session_finalize(&mut client, &session_uuid)
         .await
         .unwrap();
-    assert!(topic_uuid.is_valid());
-
-    let batches = vec![ext::arrow::testing::dummy_batch()];
 
-    let response = actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
+    let copy = actions::topic_copy(&mut client, topic_name, "run_b")
         .await
         .unwrap();
+    assert_eq!(copy["locator"], "run_b/imu");
 
-    if response.into_inner().message().await.unwrap().is_some() {
-        panic!("Received a not-empty response!");
-    }
+    let original = actions::do_get(&mut client, topic_name).await.unwrap();
+    let copied = actions::do_get(&mut client, "run_b/imu").await.unwrap();
+    assert_eq!(copied, original);
 
-    // Delete must work on both open and finalized sessions.
-    actions::session_finalize(&mut client, &session_uuid)
+    // The lineage of copies of copies lists all the sources, from the nearest
+    actions::topic_copy(&mut client, "run_b/imu", "calibration")
         .await
         .unwrap();
-    actions::session_delete(&mut client, &session_locator)
+    let lineage = actions::topic_lineage(&mut client, "calibration/imu")
         .await
         .unwrap();
+    let sources: Vec<_> = lineage["lineage"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|source| source["locator"].as_str().unwrap().to_owned())
+        .collect();
+    assert_eq!(sources, ["run_b/imu", "run_a/imu"]);
+    assert_eq!(lineage["lineage"][0]["uuid"], copy["uuid"]);
 
-    let (session_locator, _) = actions::session_create(&mut client, sequence_name)
+    // Copies don't depend on their sources, which are kept in the lineage once deleted
+    actions::sequence_delete(&mut client, "run_b")
         .await
         .unwrap();
+    actions::sequence_purge(&mut client, "run_b").await.unwrap();
 
-    actions::session_delete(&mut client, &session_locator)
+    let copied = actions::do_get(&mut client, "calibration/imu")
         .await
         .unwrap();
+    assert_eq!(copied, original);
+    let lineage = actions::topic_lineage(&mut client, "calibration/imu")
+        .await
+        .unwrap();
+    assert_eq!(lineage["lineage"].as_array().unwrap().len(), 2);
 
-    //// Fake session locator and UUID test
-    let fake_session_locator = types::SessionLocator::new(sequence_name.parse().unwrap());
-    let res = actions::session_delete(&mut client, &fake_session_locator).await;
-    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
-
-    let fake_session_uuid = types::Uuid::new();
-    let res = actions::topic_create(
-        &mut client,
-        &fake_session_uuid,
-        "test_sequence/topic2",
-        None,
-    )
-    .await;
-    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+    // Topics are not overwritten
+    let err = actions::topic_copy(&mut client, topic_name, "calibration")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::AlreadyExists);
 
     server.shutdown().await;
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_session_delete_idempotent(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_sequence_compaction(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::{Int64Array, RecordBatch};
+    use std::sync::Arc;
+
     let port = common::random_port();
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
         .await;
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
-    let sequence_name = "test_sequence";
+    let sequence_name = "test_compaction";
     actions::sequence_create(&mut client, sequence_name, None)
         .await
         .unwrap();
 
-    let (session_locator, _) = actions::session_create(&mut client, sequence_name)
+    // Sequences follow the server configuration by default
+    let policy = actions::sequence_compaction_policy_get(&mut client, sequence_name)
         .await
         .unwrap();
+    assert!(policy["interval"].is_null());
+    assert!(policy["target_object_size"].is_null());
 
-    actions::session_delete(&mut client, &session_locator)
+    actions::sequence_compaction_policy_set(
+        &mut client,
+        serde_json::json!({ "locator": sequence_name, "interval": 60, "cold_after": 600 }),
+    )
+    .await
+    .unwrap();
+    let policy = actions::sequence_compaction_policy_get(&mut client, sequence_name)
         .await
         .unwrap();
+    assert_eq!(policy["interval"], 60);
+    assert_eq!(policy["cold_after"], 600);
+    assert!(policy["min_chunks"].is_null());
 
-    // NotFound
-    let res = actions::session_delete(&mut client, &session_locator).await;
-    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+    // A policy without settings restores the server configuration
+    actions::sequence_compaction_policy_set(
+        &mut client,
+        serde_json::json!({ "locator": sequence_name }),
+    )
+    .await
+    .unwrap();
+    let policy = actions::sequence_compaction_policy_get(&mut client, sequence_name)
+        .await
+        .unwrap();
+    assert!(policy["interval"].is_null());
+
+    let err = actions::sequence_compact(&mut client, "missing_sequence")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    let schema = ext::arrow::testing::dummy_batch().schema();
+    let batch = |timestamps: Vec<i64>, values: Vec<i64>| {
+        RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(timestamps)),
+                Arc::new(Int64Array::from(values)),
+            ],
+        )
+        .unwrap()
+    };
+
+    // Two sessions write the rows of an upsert topic in two data files
+    let topic_name = "test_compaction/calibration";
+    let primary_key = r#""primary_key": "timestamp_ns""#;
+    for rows in [
+        (vec![1, 2, 3], vec![10, 20, 30]),
+        (vec![2, 4], vec![21, 40]),
+    ] {
+        let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+            .await
+            .unwrap();
+        let upload_uuid = actions::topic_create_with_properties(
+            &mut client,
+            &session_uuid,
+            topic_name,
+            primary_key,
+        )
+        .await
+        .unwrap();
+        actions::do_put(
+            &mut client,
+            &upload_uuid,
+            topic_name,
+            vec![batch(rows.0, rows.1)],
+            false,
+        )
+        .await
+        .unwrap();
+        actions::session_finalize(&mut client, &session_uuid)
+            .await
+            .unwrap();
+    }
+
+    let res = actions::sequence_compact(&mut client, sequence_name)
+        .await
+        .unwrap();
+    assert_eq!(res["compacted_topics"], 1);
+
+    let batches = actions::do_get(&mut client, topic_name).await.unwrap();
+    assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 4);
+
+    // Topics stored in a single data file are not compacted again
+    let res = actions::sequence_compact(&mut client, sequence_name)
+        .await
+        .unwrap();
+    assert_eq!(res["compacted_topics"], 0);
 
     server.shutdown().await;
 }
 
+/// Writes an MCAP recording with a JSON channel and a raw channel of `n` messages each.
+fn mcap_recording(n: u64) -> Vec<u8> {
+    let mut writer = mcap::Writer::new(std::io::Cursor::new(Vec::new())).unwrap();
+
+    let schema = serde_json::json!({
+        "type": "object",
+        "properties": { "x": { "type": "number" }, "label": { "type": "string" } }
+    });
+    let schema_id = writer
+        .add_schema("Point", "jsonschema", schema.to_string().as_bytes())
+        .unwrap();
+    let points = writer
+        .add_channel(schema_id, "/points", "json", &Default::default())
+        .unwrap();
+    let raw = writer
+        .add_channel(0, "/camera/raw", "cdr", &Default::default())
+        .unwrap();
+
+    for i in 0..n {
+        let header = |channel_id| mcap::records::MessageHeader {
+            channel_id,
+            sequence: i as u32,
+            log_time: 1000 + i,
+            publish_time: 500 + i,
+        };
+        let point = serde_json::json!({ "x": i as f64 / 2.0, "label": format!("p{i}") });
+        writer
+            .write_to_known_channel(&header(points), point.to_string().as_bytes())
+            .unwrap();
+        writer
+            .write_to_known_channel(&header(raw), &[i as u8; 4])
+            .unwrap();
+    }
+
+    writer.finish().unwrap();
+    writer.into_inner().into_inner()
+}
+
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_session_delete_unlocked_with_data(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_sequence_import_mcap(pool: sqlx::Pool<db::DatabaseType>) {
     let port = common::random_port();
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
         .await;
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
-    let sequence_name = "test_sequence";
-    let topic_name = &format!("{}/unfinalized_topic", sequence_name);
-
-    actions::sequence_create(&mut client, sequence_name, None)
+    server
+        .store
+        .write_bytes("recordings/drive.mcap", mcap_recording(10))
         .await
         .unwrap();
-
-    let (session_locator, session_uuid) = actions::session_create(&mut client, sequence_name)
+    server
+        .store
+        .write_bytes("recordings/broken.mcap", b"not a recording".to_vec())
         .await
         .unwrap();
-    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+
+    // The sequence is created by the import
+    let import = actions::sequence_import_mcap(&mut client, "drive", "recordings/drive.mcap", None)
         .await
         .unwrap();
+    let topics = import["topics"].as_array().unwrap();
+    assert_eq!(topics.len(), 2);
+    assert_eq!(topics[0]["locator"], "drive/camera/raw");
+    assert_eq!(topics[0]["channel"], "/camera/raw");
+    assert_eq!(topics[0]["message_count"], 10);
+    assert_eq!(topics[1]["locator"], "drive/points");
 
-    let batches = vec![ext::arrow::testing::dummy_batch()];
-    actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
-        .await
+    let points = actions::do_get(&mut client, "drive/points").await.unwrap();
+    let points = arrow::compute::concat_batches(&points[0].schema(), &points).unwrap();
+    assert_eq!(points.num_rows(), 10);
+    for column in ["timestamp_ns", "publish_time_ns", "sequence", "x", "label"] {
+        assert!(points.column_by_name(column).is_some(), "missing {column}");
+    }
+    let timestamps = points
+        .column_by_name("timestamp_ns")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<arrow::array::Int64Array>()
         .unwrap();
+    assert_eq!(timestamps.value(0), 1000);
 
-    actions::session_delete(&mut client, &session_locator)
+    let raw = actions::do_get(&mut client, "drive/camera/raw")
         .await
         .unwrap();
+    assert!(raw[0].column_by_name("data").is_some());
 
-    let res = actions::get_flight_info(&mut client, topic_name).await;
-    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+    // Channels are filtered, the import of a broken recording leaves no topic behind
+    actions::sequence_import_mcap(
+        &mut client,
+        "filtered",
+        "recordings/drive.mcap",
+        Some(&["/points"]),
+    )
+    .await
+    .map(|import| assert_eq!(import["topics"].as_array().unwrap().len(), 1))
+    .unwrap();
 
-    server.shutdown().await;
+    let err = actions::sequence_import_mcap(&mut client, "drive", "recordings/broken.mcap", None)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    let sessions = actions::session_list(&mut client, "drive", None)
+        .await
+        .unwrap();
+    assert_eq!(sessions["sessions"].as_array().unwrap().len(), 1);
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_session_delete_cascades_to_topics(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_sequence_archive(pool: sqlx::Pool<db::DatabaseType>) {
     let port = common::random_port();
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
         .await;
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
-    let sequence_name = "test_sequence";
-    let topic_name_a = &format!("{}/topic_a", sequence_name);
-    let topic_name_b = &format!("{}/topic_b", sequence_name);
-
-    actions::sequence_create(&mut client, sequence_name, None)
+    actions::sequence_create(&mut client, "run_a", Some(r#"{"driver": "jon"}"#))
         .await
         .unwrap();
-
-    let (session_locator, session_uuid) = actions::session_create(&mut client, sequence_name)
+    let (_, session_uuid) = actions::session_create(&mut client, "run_a").await.unwrap();
+    for topic_name in ["run_a/imu", "run_a/gps"] {
+        let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+            .await
+            .unwrap();
+        actions::do_put(
+            &mut client,
+            &uuid,
+            topic_name,
+            vec![ext::arrow::testing::dummy_batch()],
+            false,
+        )
         .await
         .unwrap();
-
-    let topic_uuid_a = actions::topic_create(&mut client, &session_uuid, topic_name_a, None)
+    }
+    actions::session_finalize(&mut client, &session_uuid)
         .await
         .unwrap();
-    let topic_uuid_b = actions::topic_create(&mut client, &session_uuid, topic_name_b, None)
+    let original = actions::do_get(&mut client, "run_a/imu").await.unwrap();
+
+    let export = actions::sequence_export(&mut client, "run_a", "lab_b")
         .await
         .unwrap();
+    assert_eq!(export["path"], "archives/lab_b/run_a");
+    assert_eq!(export["topics"], 2);
 
-    let batches_a = vec![ext::arrow::testing::dummy_batch()];
-    let batches_b = vec![ext::arrow::testing::dummy_batch()];
-    actions::do_put(&mut client, &topic_uuid_a, topic_name_a, batches_a, false)
+    let manifest: serde_json::Value = serde_json::from_slice(
+        &server
+            .store
+            .read_bytes("archives/lab_b/run_a/manifest.json")
+            .await
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(manifest["locator"], "run_a");
+    assert_eq!(manifest["user_metadata"]["driver"], "jon");
+    assert_eq!(manifest["topics"][0]["locator"], "run_a/gps");
+
+    // Archives are not overwritten
+    let err = actions::sequence_export(&mut client, "run_a", "lab_b")
         .await
-        .unwrap();
-    actions::do_put(&mut client, &topic_uuid_b, topic_name_b, batches_b, false)
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::AlreadyExists);
+
+    // Imported under another locator, with new UUIDs
+    let import =
+        actions::sequence_import(&mut client, "archives/lab_b/run_a", Some("run_b"), false)
+            .await
+            .unwrap();
+    assert_eq!(import["locator"], "run_b");
+    assert_ne!(import["uuid"], manifest["uuid"]);
+    assert_eq!(
+        import["topics"],
+        serde_json::json!(["run_b/gps", "run_b/imu"])
+    );
+    let imported = actions::do_get(&mut client, "run_b/imu").await.unwrap();
+    assert_eq!(imported, original);
+
+    // UUIDs can only be preserved once the original sequence is gone
+    let err = actions::sequence_import(&mut client, "archives/lab_b/run_a", Some("run_c"), true)
         .await
-        .unwrap();
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::AlreadyExists);
 
-    actions::session_finalize(&mut client, &session_uuid)
+    actions::sequence_delete(&mut client, "run_a")
         .await
         .unwrap();
-    actions::session_delete(&mut client, &session_locator)
+    actions::sequence_purge(&mut client, "run_a").await.unwrap();
+
+    let import = actions::sequence_import(&mut client, "archives/lab_b/run_a", None, true)
         .await
         .unwrap();
+    assert_eq!(import["locator"], "run_a");
+    assert_eq!(import["uuid"], manifest["uuid"]);
+    let imported = actions::do_get(&mut client, "run_a/imu").await.unwrap();
+    assert_eq!(imported, original);
 
-    let res = actions::get_flight_info(&mut client, topic_name_a).await;
-    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+    server.shutdown().await;
+}
 
-    let res = actions::get_flight_info(&mut client, topic_name_b).await;
-    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+/// Returns a batch with the given timestamps and a `value` column numbering the rows.
+fn timestamped_batch(timestamps: Vec<i64>) -> arrow::array::RecordBatch {
+    use arrow::array::Int64Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    let values: Vec<i64> = (1..=timestamps.len() as i64).collect();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new(
+            mosaicod_core::params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+            DataType::Int64,
+            false,
+        ),
+        Field::new("value", DataType::Int64, false),
+    ]));
+
+    arrow::array::RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(timestamps)),
+            Arc::new(Int64Array::from(values)),
+        ],
+    )
+    .unwrap()
+}
 
-    let res = actions::topic_delete(&mut client, topic_name_a).await;
-    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+/// Decodes the ticket of a replay and returns the replayed batches.
+async fn replay_batches(
+    client: &mut common::Client,
+    response: &serde_json::Value,
+) -> Vec<arrow::array::RecordBatch> {
+    use base64::Engine;
 
-    server.shutdown().await;
+    let ticket = base64::engine::general_purpose::STANDARD
+        .decode(response["ticket"].as_str().unwrap())
+        .unwrap();
+    actions::do_get_with_ticket(client, Ticket::new(ticket))
+        .await
+        .unwrap()
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_session_delete_preserves_sequence(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_sequence_replay(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::{Array, AsArray, Int64Array, StructArray};
+    use arrow::datatypes::{Int64Type, UInt32Type};
+
     let port = common::random_port();
+
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
         .await;
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
-    let sequence_name = "test_sequence";
-    actions::sequence_create(&mut client, sequence_name, None)
+    actions::sequence_create(&mut client, "run_a", None)
         .await
         .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, "run_a").await.unwrap();
+    let topics = [
+        ("run_a/imu", ext::arrow::testing::dummy_batch()),
+        ("run_a/gps", timestamped_batch(vec![10000, 10012, 10030])),
+    ];
+    for (topic_name, batch) in topics {
+        let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+            .await
+            .unwrap();
+        actions::do_put(&mut client, &uuid, topic_name, vec![batch], false)
+            .await
+            .unwrap();
+    }
 
-    let (session_locator, _) = actions::session_create(&mut client, sequence_name)
+    // Topics of sessions not finalized yet can't be replayed
+    let err = actions::sequence_replay(
+        &mut client,
+        "run_a",
+        serde_json::json!({ "topics": ["imu"] }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+
+    actions::session_finalize(&mut client, &session_uuid)
         .await
         .unwrap();
-    actions::session_delete(&mut client, &session_locator)
+
+    // All the topics are replayed by default, sorted by locator
+    let response = actions::sequence_replay(&mut client, "run_a", serde_json::json!({}))
         .await
         .unwrap();
+    assert_eq!(response["channels"][0]["channel_id"], 0);
+    assert_eq!(response["channels"][0]["locator"], "run_a/gps");
+    assert_eq!(response["channels"][1]["channel_id"], 1);
+    assert_eq!(response["channels"][1]["locator"], "run_a/imu");
 
-    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
-        .await
-        .unwrap();
-    assert!(session_uuid.is_valid());
+    let batches = replay_batches(&mut client, &response).await;
+    let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
 
-    let info = actions::get_flight_info(&mut client, sequence_name).await;
-    assert!(info.is_ok());
+    let timestamps = batch.column_by_name("timestamp_ns").unwrap();
+    let channels = batch.column_by_name("channel_id").unwrap();
+    // Rows sharing a timestamp are ordered by channel
+    assert_eq!(
+        timestamps.as_primitive::<Int64Type>().values().to_vec(),
+        [
+            10000, 10000, 10005, 10010, 10012, 10015, 10020, 10025, 10030, 10030
+        ]
+    );
+    assert_eq!(
+        channels.as_primitive::<UInt32Type>().values().to_vec(),
+        [0, 1, 1, 1, 0, 1, 1, 1, 0, 1]
+    );
+
+    // Each row carries the row of its topic in the column of the topic
+    let gps = batch.column_by_name("run_a/gps").unwrap().as_struct();
+    let imu = batch.column_by_name("run_a/imu").unwrap().as_struct();
+    for row in 0..batch.num_rows() {
+        let gps_row = channels.as_primitive::<UInt32Type>().value(row) == 0;
+        assert_eq!(gps.is_valid(row), gps_row);
+        assert_eq!(imu.is_valid(row), !gps_row);
+    }
+    let values = |column: &StructArray| -> Vec<i64> {
+        let values: &Int64Array = column.column_by_name("value").unwrap().as_primitive();
+        (0..column.len())
+            .filter(|row| column.is_valid(*row))
+            .map(|row| values.value(row))
+            .collect()
+    };
+    assert_eq!(values(gps), [1, 2, 3]);
+    assert_eq!(values(imu), [1, 2, 3, 4, 5, 6, 7]);
+
+    // Selected topics are replayed in the requested order, within the range
+    let response = actions::sequence_replay(
+        &mut client,
+        "run_a",
+        serde_json::json!({
+            "topics": ["imu", "gps"],
+            "timestamp_ns_start": 10010,
+            "timestamp_ns_end": 10030,
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response["channels"][0]["locator"], "run_a/imu");
+
+    let batches = replay_batches(&mut client, &response).await;
+    let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+    assert_eq!(
+        batch
+            .column_by_name("timestamp_ns")
+            .unwrap()
+            .as_primitive::<Int64Type>()
+            .values()
+            .to_vec(),
+        [10010, 10012, 10015, 10020, 10025]
+    );
+    assert_eq!(
+        batch
+            .column_by_name("channel_id")
+            .unwrap()
+            .as_primitive::<UInt32Type>()
+            .values()
+            .to_vec(),
+        [0, 1, 0, 0, 0]
+    );
+
+    let err = actions::sequence_replay(
+        &mut client,
+        "run_a",
+        serde_json::json!({ "topics": ["imu", "imu"] }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    let err = actions::sequence_replay(
+        &mut client,
+        "run_a",
+        serde_json::json!({ "topics": ["lidar"] }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
 
     server.shutdown().await;
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_do_get_basic(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_topic_upsert_base_version(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::{Int64Array, RecordBatch};
+    use std::sync::Arc;
+
     let port = common::random_port();
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
@@ -1486,109 +2330,154 @@ async fn test_do_get_basic(pool: sqlx::Pool<db::DatabaseType>) {
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
     let sequence_name = "test_sequence";
-    let topic_name = &format!("{}/my_topic", sequence_name);
-
     actions::sequence_create(&mut client, sequence_name, None)
         .await
         .unwrap();
+
+    let schema = ext::arrow::testing::dummy_batch().schema();
+    let batch = |timestamps: Vec<i64>, values: Vec<i64>| {
+        RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(timestamps)),
+                Arc::new(Int64Array::from(values)),
+            ],
+        )
+        .unwrap()
+    };
+
+    let topic_name = "test_sequence/map_layer";
+    let properties = |base_version: Option<u64>| match base_version {
+        Some(version) => format!(
+            r#""primary_key": "timestamp_ns", "base_version": {}"#,
+            version
+        ),
+        None => r#""primary_key": "timestamp_ns""#.to_owned(),
+    };
+    let version = async |client: &mut common::Client| {
+        let info = actions::get_flight_info(client, topic_name).await.unwrap();
+        let metadata: marshal::flight::TopicAppMetadata =
+            info.endpoint[0].clone().app_metadata.try_into().unwrap();
+        metadata.version
+    };
+
+    // A base version can't be asserted on a new topic
     let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
-    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
-        .await
-        .unwrap();
+    let res = actions::topic_create_with_properties(
+        &mut client,
+        &session_uuid,
+        topic_name,
+        &properties(Some(0)),
+    )
+    .await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
 
-    let original_batch = ext::arrow::testing::dummy_batch();
-    let batches = vec![original_batch.clone()];
-    actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
+    let uuid = actions::topic_create_with_properties(
+        &mut client,
+        &session_uuid,
+        topic_name,
+        &properties(None),
+    )
+    .await
+    .unwrap();
+    let batches = vec![batch(vec![1, 2, 3], vec![10, 20, 30])];
+    actions::do_put(&mut client, &uuid, topic_name, batches, false)
         .await
         .unwrap();
     actions::session_finalize(&mut client, &session_uuid)
         .await
         .unwrap();
+    assert_eq!(version(&mut client).await, Some(0));
 
-    let info = actions::get_flight_info(&mut client, topic_name)
+    // Two clients compute their changes from the same version
+    let (_, first_session) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
-    let ticket = info.endpoint[0].ticket.clone().unwrap();
-
-    let received_batches = actions::do_get_with_ticket(&mut client, ticket)
+    let first_uuid = actions::topic_create_with_properties(
+        &mut client,
+        &first_session,
+        topic_name,
+        &properties(Some(0)),
+    )
+    .await
+    .unwrap();
+    let (_, second_session) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
-    assert_eq!(received_batches.len(), 1);
-    assert_eq!(received_batches[0].num_rows(), original_batch.num_rows());
-    assert_eq!(
-        received_batches[0].num_columns(),
-        original_batch.num_columns()
-    );
-
-    server.shutdown().await;
-}
-
-#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_do_get_unlocked_topic(pool: sqlx::Pool<db::DatabaseType>) {
-    let port = common::random_port();
-    let server = common::ServerBuilder::new(common::HOST, port, pool)
-        .build()
-        .await;
-    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
-
-    let sequence_name = "test_sequence";
-    let topic_name = &format!("{}/unlocked", sequence_name);
+    let second_uuid = actions::topic_create_with_properties(
+        &mut client,
+        &second_session,
+        topic_name,
+        &properties(Some(0)),
+    )
+    .await
+    .unwrap();
 
-    actions::sequence_create(&mut client, sequence_name, None)
+    let batches = vec![batch(vec![2], vec![21])];
+    actions::do_put(&mut client, &first_uuid, topic_name, batches, false)
         .await
         .unwrap();
-    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+    actions::session_finalize(&mut client, &first_session)
         .await
         .unwrap();
-    let _ = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+    assert_eq!(version(&mut client).await, Some(1));
+
+    // The second upload is based on a stale version and is rejected
+    let batches = vec![batch(vec![2, 3], vec![22, 32])];
+    let res = actions::do_put(&mut client, &second_uuid, topic_name, batches, false).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::Aborted);
+
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
+    let res = actions::topic_create_with_properties(
+        &mut client,
+        &session_uuid,
+        topic_name,
+        &properties(Some(0)),
+    )
+    .await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::Aborted);
 
-    let info = actions::get_flight_info(&mut client, topic_name)
+    // Once rebased on the current version the changes are applied
+    let uuid = actions::topic_create_with_properties(
+        &mut client,
+        &session_uuid,
+        topic_name,
+        &properties(Some(1)),
+    )
+    .await
+    .unwrap();
+    let batches = vec![batch(vec![3], vec![32])];
+    actions::do_put(&mut client, &uuid, topic_name, batches, false)
         .await
         .unwrap();
-    let ticket = info.endpoint[0].ticket.clone().unwrap();
-
-    let res = actions::do_get_with_ticket(&mut client, ticket).await;
-    assert_eq!(
-        res.unwrap_err().code(),
-        tonic::Code::FailedPrecondition,
-        "reading from an unlocked topic should fail"
-    );
-
-    server.shutdown().await;
-}
-
-#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_do_get_nonexistent_topic(pool: sqlx::Pool<db::DatabaseType>) {
-    let port = common::random_port();
-    let server = common::ServerBuilder::new(common::HOST, port, pool)
-        .build()
-        .await;
-    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
-
-    let fake_locator = "ghost_sequence/ghost_topic".parse().unwrap();
-    let ticket_payload = types::flight::TicketTopic {
-        locator: fake_locator,
-        timestamp_range: None,
-    };
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+    assert_eq!(version(&mut client).await, Some(2));
 
-    let fake_ticket = Ticket {
-        ticket: marshal::flight::ticket_topic_to_binary(ticket_payload)
-            .unwrap()
-            .into(),
-    };
+    let batches = actions::do_get(&mut client, topic_name).await.unwrap();
+    let values: Vec<i64> = batches
+        .iter()
+        .flat_map(|b| {
+            b.column(1)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap()
+                .values()
+                .to_vec()
+        })
+        .collect();
+    assert_eq!(values, vec![10, 21, 32]);
 
-    let res = actions::do_get_with_ticket(&mut client, fake_ticket).await;
-    dbg!(&res);
-    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
     server.shutdown().await;
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_do_get_empty_topic(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_topic_create_invalid_format(pool: sqlx::Pool<db::DatabaseType>) {
     let port = common::random_port();
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
@@ -1596,40 +2485,43 @@ async fn test_do_get_empty_topic(pool: sqlx::Pool<db::DatabaseType>) {
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
     let sequence_name = "test_sequence";
-    let topic_name = &format!("{}/empty_topic", sequence_name);
-
     actions::sequence_create(&mut client, sequence_name, None)
         .await
         .unwrap();
     let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
-    let _ = actions::topic_create(&mut client, &session_uuid, topic_name, None)
-        .await
-        .unwrap();
 
-    let info = actions::get_flight_info(&mut client, topic_name)
-        .await
-        .unwrap();
-    let ticket = info.endpoint[0].ticket.clone().unwrap();
+    let bad_names = [
+        "no_slash",
+        "wrong_sequence/topic",
+        "test_sequence/",
+        "/topic_only",
+    ];
 
-    assert_eq!(
-        actions::do_get_with_ticket(&mut client, ticket)
-            .await
-            .unwrap_err()
-            .code(),
-        tonic::Code::FailedPrecondition
-    );
+    for name in bad_names {
+        let res = actions::topic_create(&mut client, &session_uuid, name, None).await;
+        assert!(res.is_err(), "topic name {:?} should be rejected", name);
+        let code = res.unwrap_err().code();
+        assert!(
+            matches!(
+                code,
+                tonic::Code::InvalidArgument
+                    | tonic::Code::NotFound
+                    | tonic::Code::PermissionDenied
+            ),
+            "name {:?} got unexpected code {:?}",
+            name,
+            code
+        );
+    }
 
     server.shutdown().await;
 }
 
-// ===========================================================================
-// Do put
-// ===========================================================================
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_do_put(pool: sqlx::Pool<db::DatabaseType>) {
-    let port = common::random_port();
+async fn test_topic_delete(pool: sqlx::Pool<db::DatabaseType>) -> sqlx::Result<()> {
+    let port: u16 = common::random_port();
 
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
@@ -1638,6 +2530,7 @@ async fn test_do_put(pool: sqlx::Pool<db::DatabaseType>) {
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
     let sequence_name = "test_sequence";
+    let topic_name = &format!("{}/my_topic", sequence_name);
 
     actions::sequence_create(&mut client, sequence_name, None)
         .await
@@ -1648,85 +2541,4414 @@ async fn test_do_put(pool: sqlx::Pool<db::DatabaseType>) {
         .unwrap();
     assert!(session_uuid.is_valid());
 
-    let uuid = actions::topic_create(&mut client, &session_uuid, "test_sequence/my_topic", None)
+    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
         .await
         .unwrap();
-    assert!(uuid.is_valid());
+    assert!(topic_uuid.is_valid());
 
     let batches = vec![ext::arrow::testing::dummy_batch()];
-
-    let response = actions::do_put(&mut client, &uuid, "test_sequence/my_topic", batches, false)
+    actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
         .await
         .unwrap();
 
-    let mut response_reader = response.into_inner();
-    if response_reader.message().await.unwrap().is_some() {
-        panic!("Received a not-empty response!");
-    }
-
-    // Check do_put() without descriptor.
-    let batches = vec![ext::arrow::testing::dummy_batch()];
-    assert_eq!(
-        actions::do_put(&mut client, &uuid, "test_sequence/my_topic", batches, true)
-            .await
-            .unwrap_err()
-            .code(),
-        tonic::Code::InvalidArgument,
-    );
-
-    server.shutdown().await;
-}
-
-#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_do_put_nonexistent_topic_uuid(pool: sqlx::Pool<db::DatabaseType>) {
-    let port = common::random_port();
-    let server = common::ServerBuilder::new(common::HOST, port, pool)
-        .build()
-        .await;
-    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
-
-    let sequence_name = "test_sequence";
-    actions::sequence_create(&mut client, sequence_name, None)
+    actions::session_finalize(&mut client, &session_uuid)
         .await
         .unwrap();
 
-    let fake_uuid = Uuid::new();
-    let batches = vec![ext::arrow::testing::dummy_batch()];
+    actions::topic_delete(&mut client, topic_name)
+        .await
+        .unwrap();
 
-    let res = actions::do_put(
-        &mut client,
-        &fake_uuid,
-        "test_sequence/ghost",
-        batches,
-        false,
-    )
-    .await;
+    let res = actions::topic_delete(&mut client, topic_name).await;
     assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
 
     server.shutdown().await;
+    Ok(())
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_do_put_on_locked_topic(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_topic_flight_info(pool: sqlx::Pool<db::DatabaseType>) {
     let port = common::random_port();
+
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
         .await;
+
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
     let sequence_name = "test_sequence";
-    let topic_name = &format!("{}/locked", sequence_name);
 
     actions::sequence_create(&mut client, sequence_name, None)
         .await
         .unwrap();
+
     let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
-    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+    assert!(session_uuid.is_valid());
+
+    // Check flight info for a locked topic without data.
+
+    let topic_name = "test_sequence/my_empty_topic";
+
+    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+    assert!(uuid.is_valid());
+
+    // Metadata should be available even if topic is unlocked, but not all info are filled.
+    let info = actions::get_flight_info(&mut client, topic_name)
+        .await
+        .unwrap();
+    assert_eq!(info.endpoint.len(), 1);
+    assert!(!info.endpoint.first().unwrap().app_metadata.is_empty());
+
+    let app_metadata: marshal::flight::TopicAppMetadata = info
+        .endpoint
+        .first()
+        .unwrap()
+        .clone()
+        .app_metadata
+        .try_into()
+        .unwrap();
+
+    assert!(!app_metadata.locked);
+    assert_eq!(app_metadata.resource_locator, topic_name);
+    assert!(app_metadata.info.is_none());
+    assert_ne!(app_metadata.created_at_ns, 0);
+    assert!(app_metadata.completed_at_ns.is_none());
+
+    let batches = vec![ext::arrow::testing::dummy_empty_batch()];
+
+    let response = actions::do_put(&mut client, &uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+
+    if response.into_inner().message().await.unwrap().is_some() {
+        panic!("Received a not-empty response!");
+    }
+
+    let info = actions::get_flight_info(&mut client, topic_name)
+        .await
+        .unwrap();
+    assert_eq!(info.endpoint.len(), 1);
+    assert!(!info.endpoint.first().unwrap().app_metadata.is_empty());
+
+    let app_metadata: marshal::flight::TopicAppMetadata = info
+        .endpoint
+        .first()
+        .unwrap()
+        .clone()
+        .app_metadata
+        .try_into()
+        .unwrap();
+
+    assert!(app_metadata.locked);
+    assert_ne!(app_metadata.created_at_ns, 0);
+    assert_ne!(app_metadata.completed_at_ns.unwrap(), 0);
+    assert_eq!(app_metadata.resource_locator, topic_name);
+
+    let info = app_metadata.info.unwrap();
+    assert_eq!(info.chunks_number, 0);
+    assert_eq!(info.total_bytes, 0);
+    assert!(info.timestamp.is_none());
+
+    // Check flight info for a locked topic with data.
+
+    let topic_name = "test_sequence/my_topic";
+
+    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+    assert!(uuid.is_valid());
+
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+
+    let response = actions::do_put(&mut client, &uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+
+    if response.into_inner().message().await.unwrap().is_some() {
+        panic!("Received a not-empty response!");
+    }
+
+    // Data is published once the session is finalized
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let info = actions::get_flight_info(&mut client, topic_name)
+        .await
+        .unwrap();
+    assert_eq!(info.endpoint.len(), 1);
+    assert!(!info.endpoint.first().unwrap().app_metadata.is_empty());
+
+    let app_metadata: marshal::flight::TopicAppMetadata = info
+        .endpoint
+        .first()
+        .unwrap()
+        .clone()
+        .app_metadata
+        .try_into()
+        .unwrap();
+
+    assert!(app_metadata.locked);
+    assert_ne!(app_metadata.created_at_ns, 0);
+    assert_ne!(app_metadata.completed_at_ns.unwrap(), 0);
+    assert_eq!(app_metadata.resource_locator, topic_name);
+
+    let info = app_metadata.info.unwrap();
+    assert_eq!(info.chunks_number, 1);
+    assert_eq!(info.total_bytes, 895);
+    let ts_range: types::TimestampRange = info.timestamp.unwrap().into();
+    assert_eq!(ts_range.start.as_i64(), 10000);
+    assert_eq!(ts_range.end.as_i64(), 10030);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_topic_columns(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = "test_sequence/my_topic";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+
+    // Columns can be documented before uploading data
+    actions::topic_columns_update(
+        &mut client,
+        topic_name,
+        serde_json::json!({ "value": { "description": "Wheel speed", "unit": "km/h" } }),
+    )
+    .await
+    .unwrap();
+
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    let response = actions::do_put(&mut client, &uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+    assert!(response.into_inner().message().await.unwrap().is_none());
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    // Once data is uploaded, only columns of the schema can be documented
+    let err = actions::topic_columns_update(
+        &mut client,
+        topic_name,
+        serde_json::json!({ "missing": { "description": "Missing column" } }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    // Updating a column replaces its documentation
+    actions::topic_columns_update(
+        &mut client,
+        topic_name,
+        serde_json::json!({ "value": { "description": "Left wheel speed", "unit": "m/s" } }),
+    )
+    .await
+    .unwrap();
+
+    let schema = actions::get_schema(&mut client, topic_name).await.unwrap();
+    let field = schema.field_with_name("value").unwrap();
+    assert_eq!(
+        field.metadata().get("mosaico:description").unwrap(),
+        "Left wheel speed"
+    );
+    assert_eq!(field.metadata().get("mosaico:unit").unwrap(), "m/s");
+    assert!(
+        schema
+            .field_with_name(mosaicod_core::params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP)
+            .unwrap()
+            .metadata()
+            .is_empty()
+    );
+    assert_eq!(schema.metadata().get("mosaico:context").unwrap(), "topic");
+
+    // The schema returned by get_flight_info is annotated as well
+    let info = actions::get_flight_info(&mut client, topic_name)
+        .await
+        .unwrap();
+    let info_schema = info.try_decode_schema().unwrap();
+    assert_eq!(info_schema.field_with_name("value").unwrap(), field);
+
+    // Documented columns can be searched
+    let topics = actions::query(
+        &mut client,
+        serde_json::json!({ "topic": { "column": { "unit": { "$eq": "m/s" } } } }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(topics, vec![topic_name.to_owned()]);
+
+    let topics = actions::query(
+        &mut client,
+        serde_json::json!({ "topic": { "column": { "description": { "$match": "%temperature%" } } } }),
+    )
+    .await
+    .unwrap();
+    assert!(topics.is_empty());
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_topic_column_stats(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = "test_sequence/my_topic";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+
+    // Statistics are available only once the topic is finalized
+    let err = actions::topic_column_stats(&mut client, topic_name, &["value"])
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    let response = actions::do_put(&mut client, &uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+    assert!(response.into_inner().message().await.unwrap().is_none());
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let expected = serde_json::json!({
+        "name": "value",
+        "row_count": 7,
+        "null_ratio": 0.0,
+        "min": 1.0,
+        "max": 7.0,
+        "mean": 4.0,
+        "cardinality": 7,
+    });
+
+    // Statistics are returned from the cache on the following requests
+    for _ in 0..2 {
+        let stats = actions::topic_column_stats(&mut client, topic_name, &["value"])
+            .await
+            .unwrap();
+        assert_eq!(stats, vec![expected.clone()]);
+    }
+
+    // All the top-level columns are returned if none is selected
+    let stats = actions::topic_column_stats(&mut client, topic_name, &[])
+        .await
+        .unwrap();
+    let names: Vec<&str> = stats.iter().map(|s| s["name"].as_str().unwrap()).collect();
+    assert_eq!(
+        names,
+        vec![
+            mosaicod_core::params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+            "value"
+        ]
+    );
+
+    let err = actions::topic_column_stats(&mut client, topic_name, &["missing"])
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_topic_redact(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::{AsArray, Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = "test_sequence/my_topic";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new(
+            mosaicod_core::params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+            DataType::Int64,
+            false,
+        ),
+        Field::new("name", DataType::Utf8, true),
+        Field::new("value", DataType::Int64, false),
+    ]));
+    let batch = arrow::array::RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![10000, 10005, 10010, 10015])),
+            Arc::new(StringArray::from(vec!["ann", "bob", "bob", "eve"])),
+            Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+        ],
+    )
+    .unwrap();
+
+    // Only finalized topics can be redacted
+    let err = actions::topic_redact(&mut client, topic_name, (None, None), &["name"], "null")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    let response = actions::do_put(&mut client, &uuid, topic_name, vec![batch], false)
+        .await
+        .unwrap();
+    assert!(response.into_inner().message().await.unwrap().is_none());
+
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    for (columns, mode) in [
+        (["value"], "null"),
+        (["value"], "hash"),
+        (["missing"], "null"),
+        (["name"], "unknown"),
+    ] {
+        let err = actions::topic_redact(&mut client, topic_name, (None, None), &columns, mode)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    let redaction = actions::topic_redact(
+        &mut client,
+        topic_name,
+        (Some(10005), Some(10010)),
+        &["name"],
+        "hash",
+    )
+    .await
+    .unwrap();
+    assert_eq!(redaction["row_count"], 2);
+    assert_eq!(redaction["object_count"], 1);
+
+    let batches = actions::do_get(&mut client, topic_name).await.unwrap();
+    let names: Vec<&str> = batches
+        .iter()
+        .flat_map(|b| b.column_by_name("name").unwrap().as_string_view().iter())
+        .map(Option::unwrap)
+        .collect();
+    let digest = "81b637d8fcd2c6da6359e6963113a1170de795e4b725b84d1e0b4cfd9ec58ce9";
+    assert_eq!(names, vec!["ann", digest, digest, "eve"]);
+
+    actions::topic_redact(
+        &mut client,
+        topic_name,
+        (None, Some(10000)),
+        &["name"],
+        "null",
+    )
+    .await
+    .unwrap();
+
+    let batches = actions::do_get(&mut client, topic_name).await.unwrap();
+    let names = batches[0].column_by_name("name").unwrap();
+    assert!(names.is_null(0));
+    assert_eq!(batches[0].num_rows(), 4);
+
+    let log = actions::topic_redaction_list(&mut client, topic_name)
+        .await
+        .unwrap();
+    let modes: Vec<&str> = log.iter().map(|r| r["mode"].as_str().unwrap()).collect();
+    assert_eq!(modes, vec!["hash", "null"]);
+    assert_eq!(log[0]["uuid"], redaction["uuid"]);
+    assert_eq!(log[1]["reason"], "test");
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_topic_anonymization(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::{Array, AsArray, Float64Array, Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Float64Type, Schema};
+
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = "test_sequence/my_topic";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new(
+            mosaicod_core::params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+            DataType::Int64,
+            false,
+        ),
+        Field::new("latitude", DataType::Float64, false),
+        Field::new("driver", DataType::Utf8, true),
+        Field::new("value", DataType::Int64, false),
+    ]));
+    let batch = arrow::array::RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![10000, 10005])),
+            Arc::new(Float64Array::from(vec![45.123456, 45.987654])),
+            Arc::new(StringArray::from(vec![Some("ann"), None])),
+            Arc::new(Int64Array::from(vec![1, 2])),
+        ],
+    )
+    .unwrap();
+
+    let response = actions::do_put(&mut client, &uuid, topic_name, vec![batch], false)
+        .await
+        .unwrap();
+    assert!(response.into_inner().message().await.unwrap().is_none());
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let options =
+        r#", "anonymization": {"drop": ["value"], "hash": ["driver"], "coarsen": {"latitude": 1}}"#;
+    let info = actions::get_flight_info_with_options(&mut client, topic_name, options)
+        .await
+        .unwrap();
+
+    let schema = info.clone().try_decode_schema().unwrap();
+    let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            mosaicod_core::params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+            "latitude",
+            "driver"
+        ]
+    );
+
+    let ticket = info.endpoint[0].ticket.clone().unwrap();
+    let batches = actions::do_get_with_ticket(&mut client, ticket)
+        .await
+        .unwrap();
+    assert_eq!(batches[0].num_columns(), 3);
+    let latitude = batches[0].column(1).as_primitive::<Float64Type>();
+    assert_eq!(latitude.values(), &[45.1, 46.0]);
+    let driver = batches[0].column(2).as_string::<i32>();
+    assert_eq!(
+        driver.value(0),
+        "49915e0d7d4b402e3017d010bc1c0e83cac6c797d6c16e66340fe3268693a6a1"
+    );
+    assert!(driver.is_null(1));
+
+    // Anonymization requested for a sequence applies to the topics having the columns
+    let options = r#", "anonymization": {"drop": ["driver", "speed"]}"#;
+    let info = actions::get_flight_info_with_options(&mut client, sequence_name, options)
+        .await
+        .unwrap();
+    let ticket = info.endpoint[0].ticket.clone().unwrap();
+    let batches = actions::do_get_with_ticket(&mut client, ticket)
+        .await
+        .unwrap();
+    assert!(batches[0].column_by_name("driver").is_none());
+
+    for options in [
+        r#", "anonymization": {"drop": ["speed"]}"#,
+        r#", "anonymization": {"coarsen": {"driver": 2}}"#,
+        r#", "anonymization": {"hash": ["timestamp_ns"]}"#,
+    ] {
+        let err = actions::get_flight_info_with_options(&mut client, topic_name, options)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    server.shutdown().await;
+}
+
+/// Returns the dummy batch with the given metadata on the `value` field.
+fn dummy_batch_with_field_metadata(metadata: &[(&str, &str)]) -> arrow::array::RecordBatch {
+    let batch = ext::arrow::testing::dummy_batch();
+    let metadata: std::collections::HashMap<String, String> = metadata
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    let fields: Vec<arrow::datatypes::Field> = batch
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| match f.name().as_str() {
+            "value" => f.as_ref().clone().with_metadata(metadata.clone()),
+            _ => f.as_ref().clone(),
+        })
+        .collect();
+    batch
+        .with_schema(std::sync::Arc::new(arrow::datatypes::Schema::new(fields)))
+        .unwrap()
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_topic_column_units(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+
+    // Units declared in the field metadata must be known
+    let topic_name = "test_sequence/bad_unit";
+    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+    let batches = vec![dummy_batch_with_field_metadata(&[(
+        "mosaico:unit",
+        "furlong",
+    )])];
+    let response = actions::do_put(&mut client, &uuid, topic_name, batches, false).await;
+    assert!(response.is_err());
+    actions::topic_delete(&mut client, topic_name)
+        .await
+        .unwrap();
+
+    // Units set with the action must be known
+    let topic_kmh = "test_sequence/speed_kmh";
+    let uuid_kmh = actions::topic_create(&mut client, &session_uuid, topic_kmh, None)
+        .await
+        .unwrap();
+    let err = actions::topic_columns_update(
+        &mut client,
+        topic_kmh,
+        serde_json::json!({ "value": { "unit": "furlong" } }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    // Speed in km/h documented with the action
+    actions::topic_columns_update(
+        &mut client,
+        topic_kmh,
+        serde_json::json!({ "value": { "description": "Speed", "unit": "km/h" } }),
+    )
+    .await
+    .unwrap();
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    let response = actions::do_put(&mut client, &uuid_kmh, topic_kmh, batches, false)
+        .await
+        .unwrap();
+    assert!(response.into_inner().message().await.unwrap().is_none());
+
+    // Speed in m/s documented in the field metadata
+    let topic_ms = "test_sequence/speed_ms";
+    let uuid_ms = actions::topic_create(&mut client, &session_uuid, topic_ms, None)
+        .await
+        .unwrap();
+    let batches = vec![dummy_batch_with_field_metadata(&[("mosaico:unit", "m/s")])];
+    let response = actions::do_put(&mut client, &uuid_ms, topic_ms, batches, false)
+        .await
+        .unwrap();
+    assert!(response.into_inner().message().await.unwrap().is_none());
+
+    // Values without unit are never matched by converted fields
+    let topic_none = "test_sequence/no_unit";
+    let uuid_none = actions::topic_create(&mut client, &session_uuid, topic_none, None)
+        .await
+        .unwrap();
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    let response = actions::do_put(&mut client, &uuid_none, topic_none, batches, false)
+        .await
+        .unwrap();
+    assert!(response.into_inner().message().await.unwrap().is_none());
+
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let schema = actions::get_schema(&mut client, topic_ms).await.unwrap();
+    let field = schema.field_with_name("value").unwrap();
+    assert_eq!(field.metadata().get("mosaico:unit").unwrap(), "m/s");
+
+    // Values range from 1 to 7 in every topic, 7 m/s = 25.2 km/h
+    let topics = actions::query(
+        &mut client,
+        serde_json::json!({ "ontology": { "convert(mock.value, 'km/h')": { "$gt": 21.0 } } }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(topics, vec![topic_ms.to_owned()]);
+
+    // 1 km/h = 0.28 m/s
+    let mut topics = actions::query(
+        &mut client,
+        serde_json::json!({ "ontology": { "convert(mock.value, 'm/s')": { "$lt": 1.5 } } }),
+    )
+    .await
+    .unwrap();
+    topics.sort();
+    assert_eq!(topics, vec![topic_kmh.to_owned(), topic_ms.to_owned()]);
+
+    let topics = actions::query(
+        &mut client,
+        serde_json::json!({ "ontology": { "convert(mock.value, 'm/s')": { "$lt": 0.5 } } }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(topics, vec![topic_kmh.to_owned()]);
+
+    // Units of different dimensions can't be converted
+    let err = actions::query(
+        &mut client,
+        serde_json::json!({ "ontology": { "convert(mock.value, 'degC')": { "$gt": 0 } } }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_query_approximate(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = "test_sequence/my_topic";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    let response = actions::do_put(&mut client, &uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+    assert!(response.into_inner().message().await.unwrap().is_none());
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    // Exact queries don't report any sampling
+    let response = actions::query_response(
+        &mut client,
+        serde_json::json!({ "ontology": { "mock.value": { "$gt": 3 } } }),
+    )
+    .await
+    .unwrap();
+    assert!(response.get("sampling").is_none());
+
+    // Each stratum holds a single row, which is always sampled
+    let response = actions::query_response(
+        &mut client,
+        serde_json::json!({
+            "ontology": { "mock.value": { "$gt": 3 }, "include_timestamp_range": true },
+            "approximate": { "fraction": 0.5, "method": "time_stratified", "stratum_ns": 5 },
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        response["sampling"],
+        serde_json::json!({ "fraction": 0.5, "method": "time_stratified", "stratum_ns": 5 })
+    );
+    let topic = &response["items"][0]["topics"][0];
+    assert_eq!(topic["locator"], topic_name);
+    assert_eq!(topic["timestamp_range"], serde_json::json!([10015, 10030]));
+
+    let response = actions::query_response(
+        &mut client,
+        serde_json::json!({
+            "ontology": { "mock.value": { "$gt": 3 } },
+            "approximate": { "fraction": 1.0, "method": "uniform" },
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        response["sampling"],
+        serde_json::json!({ "fraction": 1.0, "method": "uniform" })
+    );
+    assert_eq!(response["items"][0]["topics"][0]["locator"], topic_name);
+
+    let err = actions::query(
+        &mut client,
+        serde_json::json!({
+            "ontology": { "mock.value": { "$gt": 3 } },
+            "approximate": { "fraction": 0.0, "method": "uniform" },
+        }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_query_cache(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = "test_sequence/my_topic";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    let response = actions::do_put(&mut client, &uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+    assert!(response.into_inner().message().await.unwrap().is_none());
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let response = actions::query_response(
+        &mut client,
+        serde_json::json!({ "ontology": { "mock.value": { "$gt": 3 } } }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response["cache_hit"], false);
+
+    // Equivalent queries on unchanged data are served from cache
+    let response = actions::query_response(
+        &mut client,
+        serde_json::json!({ "ontology": { "mock.value": { "$gt": 3 } } }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response["cache_hit"], true);
+    assert_eq!(response["items"][0]["topics"][0]["locator"], topic_name);
+
+    // Any change to the data invalidates cached results
+    actions::sequence_create(&mut client, "other_sequence", None)
+        .await
+        .unwrap();
+    let response = actions::query_response(
+        &mut client,
+        serde_json::json!({ "ontology": { "mock.value": { "$gt": 3 } } }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response["cache_hit"], false);
+
+    // Approximate queries are never cached
+    for _ in 0..2 {
+        let response = actions::query_response(
+            &mut client,
+            serde_json::json!({
+                "ontology": { "mock.value": { "$gt": 3 } },
+                "approximate": { "fraction": 0.5, "method": "uniform" },
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response["cache_hit"], false);
+    }
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_query_pagination(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    for topic in ["topic_a", "topic_b", "topic_c"] {
+        let topic_name = format!("{sequence_name}/{topic}");
+        let uuid = actions::topic_create(&mut client, &session_uuid, &topic_name, None)
+            .await
+            .unwrap();
+        let batches = vec![ext::arrow::testing::dummy_batch()];
+        let response = actions::do_put(&mut client, &uuid, &topic_name, batches, false)
+            .await
+            .unwrap();
+        assert!(response.into_inner().message().await.unwrap().is_none());
+    }
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let topics = |response: &serde_json::Value| -> Vec<String> {
+        response["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|item| item["topics"].as_array().unwrap())
+            .map(|topic| topic["locator"].as_str().unwrap().to_owned())
+            .collect()
+    };
+    let query = |max_rows: usize| {
+        serde_json::json!({
+            "sequence": { "locator": { "$eq": sequence_name } },
+            "max_rows": max_rows,
+        })
+    };
+
+    // Results fitting in a single page don't need a handle
+    let response = actions::query_response(&mut client, query(3))
+        .await
+        .unwrap();
+    assert_eq!(topics(&response).len(), 3);
+    assert!(response.get("handle").is_none());
+
+    let response = actions::query_response(&mut client, query(2))
+        .await
+        .unwrap();
+    let mut fetched = topics(&response);
+    assert_eq!(fetched.len(), 2);
+    let handle = response["handle"].as_str().unwrap().to_owned();
+
+    let response = actions::query_fetch(&mut client, &handle, 2).await.unwrap();
+    fetched.extend(topics(&response));
+    assert!(response.get("handle").is_none());
+    assert_eq!(
+        fetched,
+        vec![
+            "test_sequence/topic_a",
+            "test_sequence/topic_b",
+            "test_sequence/topic_c"
+        ]
+    );
+
+    // Results are discarded once all the rows are fetched
+    let err = actions::query_fetch(&mut client, &handle, 2)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    let err = actions::query_fetch(&mut client, "not-a-uuid", 2)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    let err = actions::query_response(&mut client, query(0))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_scheduled_query(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = "test_sequence/my_topic";
+    actions::setup_topic_with_notifications(
+        &mut client,
+        sequence_name,
+        topic_name,
+        "error".to_owned(),
+        0,
+    )
+    .await
+    .unwrap();
+
+    let query = serde_json::json!({ "ontology": { "mock.value": { "$gt": 3 } } });
+
+    let created = actions::scheduled_query_create(
+        &mut client,
+        "high_values",
+        "*/5 * * * *",
+        query.clone(),
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(created["schedule"], "*/5 * * * *");
+    assert_eq!(created["condition"], serde_json::json!({ "$gt": 0 }));
+    assert!(created["last_run_ns"].is_null());
+
+    let err = actions::scheduled_query_create(
+        &mut client,
+        "high_values",
+        "* * * * *",
+        query.clone(),
+        None,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::AlreadyExists);
+
+    // Bad schedules, queries and conditions are rejected
+    let err = actions::scheduled_query_create(&mut client, "bad", "* * *", query.clone(), None)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    let err = actions::scheduled_query_create(
+        &mut client,
+        "bad",
+        "* * * * *",
+        serde_json::json!({ "ontology": { "mock.value": { "$unknown": 3 } } }),
+        None,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    let err = actions::scheduled_query_create(
+        &mut client,
+        "bad",
+        "* * * * *",
+        query.clone(),
+        Some(serde_json::json!({ "$between": 3 })),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    // The matching topic is notified when the condition is satisfied
+    let run = actions::scheduled_query_run(&mut client, "high_values")
+        .await
+        .unwrap();
+    assert_eq!(
+        run,
+        serde_json::json!({ "match_count": 1, "triggered": true })
+    );
+
+    let notifications = actions::topic_notification_list(&mut client, topic_name)
+        .await
+        .unwrap();
+    let notifications = notifications["notifications"].as_array().unwrap();
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0]["notification_type"], "alert");
+    assert_eq!(notifications[0]["code"], "scheduled_query_triggered");
+    assert_eq!(notifications[0]["params"]["name"], "high_values");
+    assert_eq!(notifications[0]["params"]["match_count"], "1");
+
+    actions::scheduled_query_create(
+        &mut client,
+        "many_values",
+        "0 0 * * *",
+        query,
+        Some(serde_json::json!({ "$geq": 2 })),
+    )
+    .await
+    .unwrap();
+    let run = actions::scheduled_query_run(&mut client, "many_values")
+        .await
+        .unwrap();
+    assert_eq!(
+        run,
+        serde_json::json!({ "match_count": 1, "triggered": false })
+    );
+
+    let notifications = actions::topic_notification_list(&mut client, topic_name)
+        .await
+        .unwrap();
+    assert_eq!(notifications["notifications"].as_array().unwrap().len(), 1);
+
+    let list = actions::scheduled_query_list(&mut client).await.unwrap();
+    let list = list["scheduled_queries"].as_array().unwrap();
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[0]["name"], "high_values");
+    assert_eq!(list[0]["last_match_count"], 1);
+    assert!(list[0]["last_run_ns"].is_i64());
+
+    actions::scheduled_query_delete(&mut client, "high_values")
+        .await
+        .unwrap();
+    let err = actions::scheduled_query_run(&mut client, "high_values")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+    let err = actions::scheduled_query_delete(&mut client, "high_values")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_query_view(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    for sequence_name in ["sequence_a", "sequence_b"] {
+        let topic_name = format!("{sequence_name}/my_topic");
+        actions::setup_topic_with_notifications(
+            &mut client,
+            sequence_name,
+            &topic_name,
+            "error".to_owned(),
+            0,
+        )
+        .await
+        .unwrap();
+    }
+
+    let query = serde_json::json!({ "ontology": { "mock.value": { "$gt": 3 } } });
+
+    let created =
+        actions::query_view_create(&mut client, "sequence_a", "high_values", query.clone())
+            .await
+            .unwrap();
+    assert_eq!(created["name"], "high_values");
+    assert_eq!(created["query"], query);
+
+    let err = actions::query_view_create(&mut client, "sequence_a", "high_values", query.clone())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::AlreadyExists);
+
+    // Bad names and queries are rejected
+    let err = actions::query_view_create(&mut client, "sequence_a", "a/b", query.clone())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    let err = actions::query_view_create(
+        &mut client,
+        "sequence_a",
+        "other_sequence",
+        serde_json::json!({ "sequence": { "locator": { "$eq": "sequence_b" } } }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    // Views search their sequence only
+    let response = actions::query_response(&mut client, query.clone())
+        .await
+        .unwrap();
+    assert_eq!(response["items"].as_array().unwrap().len(), 2);
+    let response = actions::query_response(
+        &mut client,
+        serde_json::json!({ "view": "sequence_a/high_values" }),
+    )
+    .await
+    .unwrap();
+    let items = response["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["sequence"], "sequence_a");
+
+    // Queries add their filters to the ones of the view
+    let response = actions::query_response(
+        &mut client,
+        serde_json::json!({
+            "view": "sequence_a/high_values",
+            "topic": { "locator": { "$eq": "sequence_a/other_topic" } },
+        }),
+    )
+    .await
+    .unwrap();
+    assert!(response["items"].as_array().unwrap().is_empty());
+
+    let err = actions::query_response(
+        &mut client,
+        serde_json::json!({
+            "view": "sequence_a/high_values",
+            "ontology": { "mock.value": { "$lt": 1 } },
+        }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    // Views referenced by name only require a default sequence
+    let err = actions::query_response(&mut client, serde_json::json!({ "view": "high_values" }))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    let list = actions::query_view_list(&mut client, "sequence_a")
+        .await
+        .unwrap();
+    let list = list["views"].as_array().unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0]["name"], "high_values");
+    let list = actions::query_view_list(&mut client, "sequence_b")
+        .await
+        .unwrap();
+    assert!(list["views"].as_array().unwrap().is_empty());
+
+    // Views are listed in the catalog
+    let info = actions::get_flight_info(&mut client, "sequence_a")
+        .await
+        .unwrap();
+    let app_metadata: marshal::flight::SequenceAppMetadata = info.app_metadata.try_into().unwrap();
+    assert_eq!(app_metadata.views(), ["high_values"]);
+
+    actions::query_view_delete(&mut client, "sequence_a", "high_values")
+        .await
+        .unwrap();
+    let err = actions::query_response(
+        &mut client,
+        serde_json::json!({ "view": "sequence_a/high_values" }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+    let err = actions::query_view_delete(&mut client, "sequence_a", "high_values")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_external_table(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    actions::setup_topic_with_notifications(
+        &mut client,
+        "test_sequence",
+        "test_sequence/my_topic",
+        "error".to_owned(),
+        0,
+    )
+    .await
+    .unwrap();
+
+    server
+        .store
+        .write_bytes("reference/matching.csv", "value,label\n3,three\n42,other\n")
+        .await
+        .unwrap();
+    server
+        .store
+        .write_bytes("reference/missing.csv", "value\n9\n12\n")
+        .await
+        .unwrap();
+
+    let table =
+        actions::external_table_register(&mut client, "matching", "reference/matching.csv", "csv")
+            .await
+            .unwrap();
+    assert_eq!(table["format"], "csv");
+    assert_eq!(
+        table["columns"],
+        serde_json::json!([
+            { "name": "value", "data_type": "Int64" },
+            { "name": "label", "data_type": "Utf8" },
+        ])
+    );
+    actions::external_table_register(&mut client, "missing", "reference/missing.csv", "csv")
+        .await
+        .unwrap();
+
+    let err =
+        actions::external_table_register(&mut client, "matching", "reference/matching.csv", "csv")
+            .await
+            .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::AlreadyExists);
+
+    // Unknown formats and unreadable files are rejected
+    let err = actions::external_table_register(&mut client, "bad", "reference/matching.csv", "xml")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    let err = actions::external_table_register(&mut client, "bad", "reference/unknown.csv", "csv")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    // Topics are matched against the values of the external column
+    let query = |table: &str, column: &str| {
+        serde_json::json!({
+            "ontology": { "mock.value": { "$in": { "table": table, "column": column } } }
+        })
+    };
+    let response = actions::query_response(&mut client, query("matching", "value"))
+        .await
+        .unwrap();
+    assert_eq!(response["items"].as_array().unwrap().len(), 1);
+    let response = actions::query_response(&mut client, query("missing", "value"))
+        .await
+        .unwrap();
+    assert!(response["items"].as_array().unwrap().is_empty());
+
+    let err = actions::query_response(&mut client, query("matching", "unknown"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    let err = actions::query_response(&mut client, query("unknown", "value"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    let list = actions::external_table_list(&mut client).await.unwrap();
+    let list = list["tables"].as_array().unwrap();
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[0]["name"], "matching");
+
+    actions::external_table_unregister(&mut client, "matching")
+        .await
+        .unwrap();
+    let list = actions::external_table_list(&mut client).await.unwrap();
+    assert_eq!(list["tables"].as_array().unwrap().len(), 1);
+    let err = actions::external_table_unregister(&mut client, "matching")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_sql_query(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::{AsArray, types::Int64Type};
+    use base64::Engine;
+
+    let port = common::random_port();
+
+    let query_limits = facade::QueryLimitPolicy::new("output_rows=5".parse().unwrap());
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .with_query_limits(query_limits)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    for topic_name in ["test_sequence/left", "test_sequence/right"] {
+        let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+            .await
+            .unwrap();
+        let batches = vec![ext::arrow::testing::dummy_batch()];
+        actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
+            .await
+            .unwrap();
+    }
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let tables = [("l", "test_sequence/left"), ("r", "test_sequence/right")];
+    let statement = format!(
+        "SELECT l.value, l.value + r.value AS total FROM l JOIN r ON l.{ts} = r.{ts} \
+         WHERE l.value > 4 ORDER BY l.value",
+        ts = mosaicod_core::params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP
+    );
+
+    // The schema of the result is returned along with the ticket
+    let response = actions::sql_query(&mut client, &statement, &tables)
+        .await
+        .unwrap();
+    let arrow_schema = base64::engine::general_purpose::STANDARD
+        .decode(response["arrow_schema"].as_str().unwrap())
+        .unwrap();
+    let schema = ext::arrow::schema_from_ipc(&arrow_schema).unwrap();
+    assert_eq!(schema.field(0).name(), "value");
+    assert_eq!(schema.field(1).name(), "total");
+
+    let batches = actions::sql_query_result(&mut client, &response)
+        .await
+        .unwrap();
+    let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+    assert_eq!(
+        batch.column(0).as_primitive::<Int64Type>().values(),
+        &[5, 6, 7]
+    );
+    assert_eq!(
+        batch.column(1).as_primitive::<Int64Type>().values(),
+        &[10, 12, 14]
+    );
+
+    // The stream fails once the result exceeds the query limits
+    let response = actions::sql_query(&mut client, "SELECT * FROM l", &tables)
+        .await
+        .unwrap();
+    let err = actions::sql_query_result(&mut client, &response)
+        .await
+        .unwrap_err();
+    assert!(err.message().contains("more than 5 rows"), "{err}");
+
+    // Bad statements are reported when the query is planned
+    for statement in [
+        "SELECT * FROM unknown",
+        "SELECT missing FROM l",
+        "DROP TABLE l",
+        "not a statement",
+    ] {
+        let err = actions::sql_query(&mut client, statement, &tables)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument, "{statement}");
+    }
+
+    let err = actions::sql_query(&mut client, "SELECT 1", &[("t", "test_sequence/unknown")])
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_topic_notification_create(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence_topic_notification_create";
+    let topic_name = &format!("{}/my_topic", sequence_name);
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let error_msg = format!("Error in {}", topic_name);
+    actions::topic_notification_create(
+        &mut client,
+        topic_name,
+        types::NotificationType::Error.to_string(),
+        error_msg,
+    )
+    .await
+    .unwrap();
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_topic_notification_list(pool: sqlx::Pool<db::DatabaseType>) {
+    let port: u16 = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+    let sequence_name = "test_sequence_topic_notification_create";
+    let topic_name = &format!("{}/my_topic", sequence_name);
+    let notification_type = types::NotificationType::Error.to_string();
+    let notifications_size = 5;
+
+    actions::setup_topic_with_notifications(
+        &mut client,
+        sequence_name,
+        topic_name,
+        notification_type.clone(),
+        notifications_size,
+    )
+    .await
+    .unwrap();
+
+    let r = actions::topic_notification_list(&mut client, topic_name)
+        .await
+        .unwrap();
+    let notifications = r["notifications"].as_array().unwrap();
+    assert_eq!(notifications.len(), notifications_size);
+
+    for (i, notification) in notifications.iter().enumerate() {
+        let error_msg = format!("Error {}_{}", topic_name, i + 1);
+        assert_eq!(notification["notification_type"], notification_type);
+        assert_eq!(notification["name"].as_str().unwrap(), topic_name);
+        assert_eq!(notification["msg"], error_msg);
+    }
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_topic_notification_purge(pool: sqlx::Pool<db::DatabaseType>) {
+    let port: u16 = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+    let sequence_name = "test_sequence_topic_notification_create";
+    let topic_name = &format!("{}/my_topic", sequence_name);
+    let notification_type = types::NotificationType::Error.to_string();
+    let notifications_size = 5;
+
+    actions::setup_topic_with_notifications(
+        &mut client,
+        sequence_name,
+        topic_name,
+        notification_type.clone(),
+        notifications_size,
+    )
+    .await
+    .unwrap();
+
+    actions::topic_notification_purge(&mut client, topic_name)
+        .await
+        .unwrap();
+    let r = actions::topic_notification_list(&mut client, topic_name)
+        .await
+        .unwrap();
+    let notifications = r["notifications"].as_array().unwrap();
+    assert_eq!(notifications.len(), 0);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_topic_notification_create_nonexistent(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+
+    let res = actions::topic_notification_create(
+        &mut client,
+        "test_sequence/never_existed",
+        types::NotificationType::Error.to_string(),
+        "msg".to_string(),
+    )
+    .await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_topic_notification_list_empty(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = &format!("{}/my_topic", sequence_name);
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let r = actions::topic_notification_list(&mut client, topic_name)
+        .await
+        .unwrap();
+    let notifications = r["notifications"].as_array().unwrap();
+    assert_eq!(notifications.len(), 0);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_topic_delete_nonexistent(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+
+    let res = actions::topic_delete(&mut client, "test_sequence/never_existed").await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    let res = actions::topic_delete(&mut client, "ghost_sequence/ghost_topic").await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_topic_delete_unlocked(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = &format!("{}/unlocked_topic", sequence_name);
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+    assert!(topic_uuid.is_valid());
+
+    let res = actions::topic_delete(&mut client, topic_name).await;
+    assert!(res.is_ok());
+
+    let res = actions::topic_delete(&mut client, topic_name).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    server.shutdown().await;
+}
+
+// ===========================================================================
+// Session tests
+// ===========================================================================
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_session_create(pool: sqlx::Pool<db::DatabaseType>) -> sqlx::Result<()> {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    assert!(session_uuid.is_valid());
+
+    server.shutdown().await;
+    Ok(())
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_session_finalize(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+
+    let (session_locator, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    assert!(session_uuid.is_valid());
+
+    let uuid = actions::topic_create(&mut client, &session_uuid, "test_sequence/my_topic", None)
+        .await
+        .unwrap();
+    assert!(uuid.is_valid());
+
+    // Calling finalize with unlocked topics should fail.
+    assert_eq!(
+        actions::session_finalize(&mut client, &session_uuid)
+            .await
+            .unwrap_err()
+            .code(),
+        tonic::Code::FailedPrecondition,
+    );
+
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+
+    let response = actions::do_put(&mut client, &uuid, "test_sequence/my_topic", batches, false)
+        .await
+        .unwrap();
+
+    let mut response_reader = response.into_inner();
+    if response_reader.message().await.unwrap().is_some() {
+        panic!("Received a not-empty response!");
+    }
+
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let ctx = server.context();
+    let mut cx = ctx.db.connection();
+    let db_session = db::session_find_by_locator(&mut cx, &session_locator)
+        .await
+        .unwrap();
+    assert!(db_session.completion_timestamp().unwrap().as_i64() > 0);
+
+    // Finalize on an empty session should fail.
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    assert!(session_uuid.is_valid());
+    assert_eq!(
+        actions::session_finalize(&mut client, &session_uuid)
+            .await
+            .unwrap_err()
+            .code(),
+        tonic::Code::FailedPrecondition,
+    );
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_session_delete(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = &format!("{}/my_topic", sequence_name);
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+
+    let (session_locator, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    assert!(session_uuid.is_valid());
+
+    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+    assert!(topic_uuid.is_valid());
+
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+
+    let response = actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+
+    if response.into_inner().message().await.unwrap().is_some() {
+        panic!("Received a not-empty response!");
+    }
+
+    // Delete must work on both open and finalized sessions.
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+    actions::session_delete(&mut client, &session_locator)
+        .await
+        .unwrap();
+
+    let (session_locator, _) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+
+    actions::session_delete(&mut client, &session_locator)
+        .await
+        .unwrap();
+
+    //// Fake session locator and UUID test
+    let fake_session_locator = types::SessionLocator::new(sequence_name.parse().unwrap());
+    let res = actions::session_delete(&mut client, &fake_session_locator).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    let fake_session_uuid = types::Uuid::new();
+    let res = actions::topic_create(
+        &mut client,
+        &fake_session_uuid,
+        "test_sequence/topic2",
+        None,
+    )
+    .await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_session_delete_idempotent(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+
+    let (session_locator, _) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+
+    actions::session_delete(&mut client, &session_locator)
+        .await
+        .unwrap();
+
+    // NotFound
+    let res = actions::session_delete(&mut client, &session_locator).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_session_delete_unlocked_with_data(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = &format!("{}/unfinalized_topic", sequence_name);
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+
+    let (session_locator, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+
+    actions::session_delete(&mut client, &session_locator)
+        .await
+        .unwrap();
+
+    let res = actions::get_flight_info(&mut client, topic_name).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_session_delete_cascades_to_topics(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name_a = &format!("{}/topic_a", sequence_name);
+    let topic_name_b = &format!("{}/topic_b", sequence_name);
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+
+    let (session_locator, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+
+    let topic_uuid_a = actions::topic_create(&mut client, &session_uuid, topic_name_a, None)
+        .await
+        .unwrap();
+    let topic_uuid_b = actions::topic_create(&mut client, &session_uuid, topic_name_b, None)
+        .await
+        .unwrap();
+
+    let batches_a = vec![ext::arrow::testing::dummy_batch()];
+    let batches_b = vec![ext::arrow::testing::dummy_batch()];
+    actions::do_put(&mut client, &topic_uuid_a, topic_name_a, batches_a, false)
+        .await
+        .unwrap();
+    actions::do_put(&mut client, &topic_uuid_b, topic_name_b, batches_b, false)
+        .await
+        .unwrap();
+
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+    actions::session_delete(&mut client, &session_locator)
+        .await
+        .unwrap();
+
+    let res = actions::get_flight_info(&mut client, topic_name_a).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    let res = actions::get_flight_info(&mut client, topic_name_b).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    let res = actions::topic_delete(&mut client, topic_name_a).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_session_delete_preserves_sequence(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+
+    let (session_locator, _) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    actions::session_delete(&mut client, &session_locator)
+        .await
+        .unwrap();
+
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    assert!(session_uuid.is_valid());
+
+    let info = actions::get_flight_info(&mut client, sequence_name).await;
+    assert!(info.is_ok());
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_do_get_basic(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = &format!("{}/my_topic", sequence_name);
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+
+    let original_batch = ext::arrow::testing::dummy_batch();
+    let batches = vec![original_batch.clone()];
+    actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let info = actions::get_flight_info(&mut client, topic_name)
+        .await
+        .unwrap();
+    let ticket = info.endpoint[0].ticket.clone().unwrap();
+
+    let received_batches = actions::do_get_with_ticket(&mut client, ticket)
+        .await
+        .unwrap();
+    assert_eq!(received_batches.len(), 1);
+    assert_eq!(received_batches[0].num_rows(), original_batch.num_rows());
+    assert_eq!(
+        received_batches[0].num_columns(),
+        original_batch.num_columns()
+    );
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_do_get_unlocked_topic(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = &format!("{}/unlocked", sequence_name);
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let _ = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+
+    let info = actions::get_flight_info(&mut client, topic_name)
+        .await
+        .unwrap();
+    let ticket = info.endpoint[0].ticket.clone().unwrap();
+
+    let res = actions::do_get_with_ticket(&mut client, ticket).await;
+    assert_eq!(
+        res.unwrap_err().code(),
+        tonic::Code::FailedPrecondition,
+        "reading from an unlocked topic should fail"
+    );
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_do_get_nonexistent_topic(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let fake_locator = "ghost_sequence/ghost_topic".parse().unwrap();
+    let ticket_payload = types::flight::TicketTopic {
+        locator: fake_locator,
+        timestamp_range: None,
+        blob_references: false,
+        changes_since: None,
+        session: None,
+        anonymization: None,
+    };
+
+    let fake_ticket = Ticket {
+        ticket: marshal::flight::ticket_topic_to_binary(ticket_payload)
+            .unwrap()
+            .into(),
+    };
+
+    let res = actions::do_get_with_ticket(&mut client, fake_ticket).await;
+    dbg!(&res);
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_do_get_empty_topic(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = &format!("{}/empty_topic", sequence_name);
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let _ = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+
+    let info = actions::get_flight_info(&mut client, topic_name)
+        .await
+        .unwrap();
+    let ticket = info.endpoint[0].ticket.clone().unwrap();
+
+    assert_eq!(
+        actions::do_get_with_ticket(&mut client, ticket)
+            .await
+            .unwrap_err()
+            .code(),
+        tonic::Code::FailedPrecondition
+    );
+
+    server.shutdown().await;
+}
+
+// ===========================================================================
+// Do put
+// ===========================================================================
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_do_put(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    assert!(session_uuid.is_valid());
+
+    let uuid = actions::topic_create(&mut client, &session_uuid, "test_sequence/my_topic", None)
+        .await
+        .unwrap();
+    assert!(uuid.is_valid());
+
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+
+    let response = actions::do_put(&mut client, &uuid, "test_sequence/my_topic", batches, false)
+        .await
+        .unwrap();
+
+    let mut response_reader = response.into_inner();
+    if response_reader.message().await.unwrap().is_some() {
+        panic!("Received a not-empty response!");
+    }
+
+    // Check do_put() without descriptor.
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    assert_eq!(
+        actions::do_put(&mut client, &uuid, "test_sequence/my_topic", batches, true)
+            .await
+            .unwrap_err()
+            .code(),
+        tonic::Code::InvalidArgument,
+    );
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_do_put_nonexistent_topic_uuid(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+
+    let fake_uuid = Uuid::new();
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+
+    let res = actions::do_put(
+        &mut client,
+        &fake_uuid,
+        "test_sequence/ghost",
+        batches,
+        false,
+    )
+    .await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_do_put_on_locked_topic(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = &format!("{}/locked", sequence_name);
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    let res = actions::do_put(&mut client, &topic_uuid, topic_name, batches, false).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::FailedPrecondition);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_do_put_descriptor_mismatch(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_a = &format!("{}/topic_a", sequence_name);
+    let topic_b = &format!("{}/topic_b", sequence_name);
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let uuid_a = actions::topic_create(&mut client, &session_uuid, topic_a, None)
+        .await
+        .unwrap();
+    let _uuid_b = actions::topic_create(&mut client, &session_uuid, topic_b, None)
+        .await
+        .unwrap();
+
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    let res = actions::do_put(&mut client, &uuid_a, topic_b, batches, false).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::PermissionDenied);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_do_put_empty_batches(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = &format!("{}/no_batches", sequence_name);
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+
+    let res = actions::do_put(&mut client, &topic_uuid, topic_name, vec![], false).await;
+    assert!(res.is_err(), "do_put with no batches should error");
+
+    server.shutdown().await;
+}
+
+// ===========================================================================
+// Get server version  tests
+// ===========================================================================
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_get_server_version(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    actions::server_version(&mut client).await.unwrap();
+
+    server.shutdown().await;
+}
+
+// ===========================================================================
+// Store metrics tests
+// ===========================================================================
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_store_metrics(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    actions::sequence_create(&mut client, "test_sequence", None)
+        .await
+        .unwrap();
+
+    let metrics = actions::store_metrics(&mut client).await.unwrap();
+    assert_eq!(metrics["backend"], "filesystem");
+
+    let operations = metrics["operations"].as_array().unwrap();
+    let put = operations
+        .iter()
+        .find(|op| op["operation"] == "put")
+        .unwrap();
+    assert!(put["count"].as_u64().unwrap() > 0);
+    assert!(put["bytes"].as_u64().unwrap() > 0);
+    assert_eq!(put["errors"], 0);
+    assert!(put["latency_us_p99"].is_u64());
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_store_tiering(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .enable_table_export()
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    actions::sequence_create(&mut client, "run_a", None)
+        .await
+        .unwrap();
+    let topic_name = "run_a/imu";
+    let (_, session_uuid) = actions::session_create(&mut client, "run_a").await.unwrap();
+    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+    actions::do_put(
+        &mut client,
+        &uuid,
+        topic_name,
+        vec![ext::arrow::testing::dummy_batch()],
+        false,
+    )
+    .await
+    .unwrap();
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    // Freshly published data files are not cold
+    let report = actions::store_tiering_report(&mut client, None)
+        .await
+        .unwrap();
+    assert_eq!(report["data_files"], 0);
+
+    let report = actions::store_tiering_report(&mut client, Some(0))
+        .await
+        .unwrap();
+    assert!(report["data_files"].as_u64().unwrap() >= 1);
+    assert!(report["size_bytes"].as_u64().unwrap() > 0);
+    assert!(report["monthly_savings"].as_f64().unwrap() > 0.0);
+    assert!(report["candidates"][0]["last_read_ns"].is_null());
+
+    let original = actions::do_get(&mut client, topic_name).await.unwrap();
+
+    let demoted = actions::store_tiering_demote(&mut client, Some(0))
+        .await
+        .unwrap();
+    assert_eq!(demoted["demoted"], report["data_files"]);
+    assert_eq!(demoted["size_bytes"], report["size_bytes"]);
+    assert!(!server.store.list("archive", None).await.unwrap().is_empty());
+
+    // Tables reference the archived data files by their target
+    let context = server.context();
+    let handle = facade::topic::Handle::try_from_locator(&context, topic_name.parse().unwrap())
+        .await
+        .unwrap();
+    let root = handle.path_in_store().unwrap().root();
+    let commit = server
+        .store
+        .read_bytes(root.join(format!("_delta_log/{:020}.json", 1)))
+        .await
+        .unwrap();
+    let added: Vec<String> = commit
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| {
+            let action: serde_json::Value = serde_json::from_slice(line).unwrap();
+            action["add"]["path"].as_str().map(str::to_owned)
+        })
+        .collect();
+    assert!(!added.is_empty());
+    for path in added {
+        let file = std::path::PathBuf::from(path.strip_prefix("file://").unwrap());
+        assert!(file.starts_with(server.store.root.join("archive")));
+        assert!(file.exists());
+    }
+
+    // Archived data files are still readable, and no longer cold
+    let archived = actions::do_get(&mut client, topic_name).await.unwrap();
+    assert_eq!(archived, original);
+
+    let report = actions::store_tiering_report(&mut client, Some(0))
+        .await
+        .unwrap();
+    assert_eq!(report["data_files"], 0);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_cost_estimate(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    for sequence in ["lab_a_run_1", "lab_a_run_2", "lab_b_run_1"] {
+        actions::sequence_create(&mut client, sequence, None)
+            .await
+            .unwrap();
+    }
+    let topic_name = "lab_a_run_1/imu";
+    let (_, session_uuid) = actions::session_create(&mut client, "lab_a_run_1")
+        .await
+        .unwrap();
+    let uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+    actions::do_put(
+        &mut client,
+        &uuid,
+        topic_name,
+        vec![ext::arrow::testing::dummy_batch()],
+        false,
+    )
+    .await
+    .unwrap();
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let estimate = actions::cost_estimate(&mut client, "lab_a_").await.unwrap();
+    assert_eq!(estimate["scope"], "lab_a_");
+    let sequences = estimate["sequences"].as_array().unwrap();
+    assert_eq!(sequences.len(), 2);
+    assert_eq!(sequences[0]["locator"], "lab_a_run_1");
+    assert_eq!(sequences[1]["locator"], "lab_a_run_2");
+
+    // Only the published data is stored, in the standard storage
+    assert!(sequences[0]["standard_bytes"].as_u64().unwrap() > 0);
+    assert!(sequences[0]["storage_cost"].as_f64().unwrap() > 0.0);
+    assert_eq!(sequences[0]["archived_bytes"], 0);
+    assert_eq!(sequences[1]["standard_bytes"], 0);
+    assert_eq!(estimate["standard_bytes"], sequences[0]["standard_bytes"]);
+
+    // Archived data is accounted at the archive price
+    actions::store_tiering_demote(&mut client, Some(0))
+        .await
+        .unwrap();
+    let archived = actions::cost_estimate(&mut client, "lab_a_run_1")
+        .await
+        .unwrap();
+    assert_eq!(archived["standard_bytes"], 0);
+    assert_eq!(archived["archived_bytes"], estimate["standard_bytes"]);
+    assert!(
+        archived["storage_cost"].as_f64().unwrap() < estimate["storage_cost"].as_f64().unwrap()
+    );
+
+    let all = actions::cost_estimate(&mut client, "").await.unwrap();
+    assert_eq!(all["sequences"].as_array().unwrap().len(), 3);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_ingest_metrics(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    actions::sequence_create(&mut client, "test_sequence", None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, "test_sequence")
+        .await
+        .unwrap();
+
+    let batch = ext::arrow::testing::dummy_batch;
+    let in_an_hour = types::Timestamp::from(types::Timestamp::now().as_i64() + 3_600_000_000_000);
+    for (topic_name, sent_at) in [
+        ("test_sequence/data", None),
+        ("test_sequence/sent", Some(types::Timestamp::now())),
+        ("test_sequence/skewed", Some(in_an_hour)),
+    ] {
+        let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+            .await
+            .unwrap();
+        match sent_at {
+            Some(sent_at) => actions::do_put_sent_at(
+                &mut client,
+                &topic_uuid,
+                topic_name,
+                vec![batch(), batch()],
+                sent_at,
+            )
+            .await
+            .unwrap(),
+            None => actions::do_put(&mut client, &topic_uuid, topic_name, vec![batch()], false)
+                .await
+                .unwrap(),
+        };
+    }
+
+    let metrics = actions::ingest_metrics(&mut client).await.unwrap();
+    let topics = metrics["topics"].as_array().unwrap();
+    assert_eq!(
+        topics.iter().map(|t| &t["topic"]).collect::<Vec<_>>(),
+        vec![
+            "test_sequence/data",
+            "test_sequence/sent",
+            "test_sequence/skewed"
+        ]
+    );
+
+    // Latency measured from the data timestamps
+    assert_eq!(topics[0]["count"], 1);
+
+    // Batches sent just before their commit fall in the first buckets
+    let sent = &topics[1];
+    assert_eq!(sent["count"], 2);
+    assert_eq!(sent["skewed"], 0);
+    assert!(sent["latency_ms_p99"].as_u64().unwrap() <= 5_000);
+    let buckets = sent["buckets"].as_array().unwrap();
+    assert!(buckets.last().unwrap()["le_ms"].is_null());
+    assert_eq!(
+        buckets
+            .iter()
+            .map(|bucket| bucket["count"].as_u64().unwrap())
+            .sum::<u64>(),
+        2
+    );
+
+    // Batches sent after their commit are not measured
+    assert_eq!(topics[2]["count"], 0);
+    assert_eq!(topics[2]["skewed"], 2);
+    assert!(topics[2]["latency_ms_p50"].is_null());
+
+    server.shutdown().await;
+}
+
+/// Encodes `batch` as a Parquet file with row groups of at most `row_group_size` rows.
+fn parquet_file(batch: &arrow::array::RecordBatch, row_group_size: usize) -> Vec<u8> {
+    let props = parquet::file::properties::WriterProperties::builder()
+        .set_max_row_group_row_count(Some(row_group_size))
+        .build();
+    let mut writer =
+        parquet::arrow::ArrowWriter::try_new(Vec::new(), batch.schema(), Some(props)).unwrap();
+    writer.write(batch).unwrap();
+    writer.into_inner().unwrap()
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_do_put_parquet(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_do_put_parquet";
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+
+    // The data is split in two files, the first one with two row groups
+    let batch = ext::arrow::testing::dummy_batch();
+    let files = vec![
+        parquet_file(&batch.slice(0, 4), 2),
+        parquet_file(&batch.slice(4, 3), 10),
+    ];
+
+    let topic_name = format!("{sequence_name}/topic");
+    let uuid = actions::topic_create(&mut client, &session_uuid, &topic_name, None)
+        .await
+        .unwrap();
+    actions::do_put_parquet(&mut client, &uuid, &topic_name, files)
+        .await
+        .unwrap();
+
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    // The data is read back as if it was uploaded with Arrow IPC
+    let batches = actions::do_get(&mut client, &topic_name).await.unwrap();
+    let data = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+    assert_eq!(data.num_rows(), batch.num_rows());
+    assert_eq!(data.column(0), batch.column(0));
+    assert_eq!(data.column(1), batch.column(1));
+
+    let (_, failed_session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+
+    // Files not sharing the schema of the first one are rejected
+    let other_name = format!("{sequence_name}/other");
+    let other_uuid = actions::topic_create(&mut client, &failed_session_uuid, &other_name, None)
+        .await
+        .unwrap();
+    let files = vec![
+        parquet_file(&batch, 10),
+        parquet_file(&batch.project(&[0]).unwrap(), 10),
+    ];
+    let err = actions::do_put_parquet(&mut client, &other_uuid, &other_name, files)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    // Bodies are required to be Parquet files
+    let invalid_name = format!("{sequence_name}/invalid");
+    let invalid_uuid =
+        actions::topic_create(&mut client, &failed_session_uuid, &invalid_name, None)
+            .await
+            .unwrap();
+    let err = actions::do_put_parquet(
+        &mut client,
+        &invalid_uuid,
+        &invalid_name,
+        vec![b"not a parquet file".to_vec()],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_server_stats(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    actions::query_response(&mut client, serde_json::json!({}))
+        .await
+        .unwrap();
+
+    let stats = actions::server_stats(&mut client).await.unwrap();
+    let queries = &stats["queries"];
+    assert_eq!(queries["admitted"], 1);
+    assert_eq!(queries["running"], 0);
+    assert_eq!(queries["queued"], 0);
+    assert!(queries["oldest_queued_ms"].is_null());
+    assert_eq!(queries["rejected"], 0);
+    assert_eq!(queries["timed_out"], 0);
+
+    let stats = actions::server_stats(&mut client).await.unwrap();
+    let requests = &stats["requests"]["DoAction"];
+    assert_eq!(requests["requests"], 2);
+    assert_eq!(requests["failed"], 0);
+
+    actions::query_response(&mut client, serde_json::json!({ "view": "unknown/view" }))
+        .await
+        .unwrap_err();
+    let stats = actions::server_stats(&mut client).await.unwrap();
+    assert_eq!(stats["requests"]["DoAction"]["failed"], 1);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_custom_layer(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    // Counts the authenticated requests reaching the layer
+    let authenticated = Arc::new(AtomicUsize::new(0));
+    let counter = authenticated.clone();
+    let layer = tower::util::MapRequestLayer::new(move |req: server::middleware::HttpRequest| {
+        if req
+            .extensions()
+            .get::<server::middleware::AuthContext>()
+            .is_some()
+        {
+            counter.fetch_add(1, Ordering::SeqCst);
+        }
+        req
+    });
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .with_layer(layer)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    actions::server_version(&mut client).await.unwrap();
+    actions::query_response(&mut client, serde_json::json!({}))
+        .await
+        .unwrap();
+    assert_eq!(authenticated.load(Ordering::SeqCst), 2);
+
+    server.shutdown().await;
+}
+
+// ===========================================================================
+// Concurrent tests
+// ===========================================================================
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_concurrent_sequence_create(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let sequence_name = "concurrent_seq";
+
+    let mut client1 = common::ClientBuilder::new(common::HOST, port).build().await;
+    let mut client2 = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let h1 = tokio::spawn(async move {
+        actions::sequence_create(&mut client1, "concurrent_seq", None).await
+    });
+    let h2 = tokio::spawn(async move {
+        actions::sequence_create(&mut client2, "concurrent_seq", None).await
+    });
+
+    let r1 = h1.await.unwrap();
+    let r2 = h2.await.unwrap();
+
+    let (success_count, already_exists_count) =
+        [&r1, &r2]
+            .iter()
+            .fold((0usize, 0usize), |(succ, ae), r| match r {
+                Ok(_) => (succ + 1, ae),
+                Err(e) if e.code() == tonic::Code::AlreadyExists => (succ, ae + 1),
+                Err(e) => panic!("unexpected error: {:?}", e),
+            });
+
+    assert_eq!(success_count, 1);
+    assert_eq!(already_exists_count, 1);
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+    let info = actions::get_flight_info(&mut client, sequence_name).await;
+    assert!(info.is_ok());
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_concurrent_session_finalize(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = &format!("{}/my_topic", sequence_name);
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+
+    let mut c1 = common::ClientBuilder::new(common::HOST, port).build().await;
+    let mut c2 = common::ClientBuilder::new(common::HOST, port).build().await;
+    let s1 = session_uuid.clone();
+    let s2 = session_uuid.clone();
+
+    let h1 = tokio::spawn(async move { actions::session_finalize(&mut c1, &s1).await });
+    let h2 = tokio::spawn(async move { actions::session_finalize(&mut c2, &s2).await });
+
+    let r1 = h1.await.unwrap();
+    let r2 = h2.await.unwrap();
+
+    let success_count = [&r1, &r2].iter().filter(|r| r.is_ok()).count();
+    assert_eq!(success_count, 1);
+
+    for r in [&r1, &r2] {
+        if let Err(e) = r {
+            assert!(
+                matches!(e.code(), tonic::Code::FailedPrecondition),
+                "unexpected error code: {:?}",
+                e.code()
+            );
+        }
+    }
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_concurrent_do_put_same_topic(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = &format!("{}/concurrent_topic", sequence_name);
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+
+    let mut c1 = common::ClientBuilder::new(common::HOST, port).build().await;
+    let mut c2 = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let t1 = topic_uuid.clone();
+    let t2 = topic_uuid.clone();
+    let n1 = topic_name.clone();
+    let n2 = topic_name.clone();
+
+    let h1 = tokio::spawn(async move {
+        let batches = vec![ext::arrow::testing::dummy_batch()];
+        actions::do_put(&mut c1, &t1, &n1, batches, false).await
+    });
+    let h2 = tokio::spawn(async move {
+        let batches = vec![ext::arrow::testing::dummy_batch()];
+        actions::do_put(&mut c2, &t2, &n2, batches, false).await
+    });
+
+    let r1 = h1.await.unwrap();
+    let r2 = h2.await.unwrap();
+
+    let success_count = [&r1, &r2].iter().filter(|r| r.is_ok()).count();
+    assert!(success_count >= 1, "at least one writer must succeed");
+
+    for r in [&r1, &r2] {
+        if let Err(e) = r {
+            assert!(
+                matches!(
+                    e.code(),
+                    tonic::Code::FailedPrecondition | tonic::Code::Aborted
+                ),
+                "unexpected error code: {:?}",
+                e.code()
+            );
+        }
+    }
+
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let info = actions::get_flight_info(&mut client, topic_name)
+        .await
+        .unwrap();
+    let app_metadata: marshal::flight::TopicAppMetadata =
+        info.endpoint[0].clone().app_metadata.try_into().unwrap();
+    let chunks = app_metadata.info.unwrap().chunks_number;
+
+    assert_eq!(chunks as usize, success_count);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_concurrent_topic_create_during_finalize(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let existing_topic = &format!("{}/existing", sequence_name);
+    let new_topic = &format!("{}/new", sequence_name);
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+
+    let topic_uuid = actions::topic_create(&mut client, &session_uuid, existing_topic, None)
+        .await
+        .unwrap();
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    actions::do_put(&mut client, &topic_uuid, existing_topic, batches, false)
+        .await
+        .unwrap();
+
+    let mut c1 = common::ClientBuilder::new(common::HOST, port).build().await;
+    let mut c2 = common::ClientBuilder::new(common::HOST, port).build().await;
+    let s1 = session_uuid.clone();
+    let s2 = session_uuid.clone();
+    let new_topic_owned = new_topic.clone();
+
+    let h_finalize = tokio::spawn(async move { actions::session_finalize(&mut c1, &s1).await });
+    let h_create =
+        tokio::spawn(
+            async move { actions::topic_create(&mut c2, &s2, &new_topic_owned, None).await },
+        );
+
+    let r_fin = h_finalize.await.unwrap();
+    let r_create = h_create.await.unwrap();
+
+    // Expected: one of two coherent outcomes
+    //  A) finalize wins -> create gets FailedPrecondition
+    //  B) create wins -> finalize succeeds afterwards
+    match (r_fin.is_ok(), r_create.is_ok()) {
+        (true, false) => {
+            assert_eq!(
+                r_create.unwrap_err().code(),
+                tonic::Code::FailedPrecondition
+            );
+        }
+        (true, true) => {}
+        (false, true) => {
+            assert_eq!(r_fin.unwrap_err().code(), tonic::Code::FailedPrecondition);
+        }
+        (false, false) => {
+            panic!("not possible");
+        }
+    }
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_concurrent_read_during_write(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    let topic_name = &format!("{}/read_during_write", sequence_name);
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
+
+    let mut writer = common::ClientBuilder::new(common::HOST, port).build().await;
+    let mut reader = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let t = topic_uuid.clone();
+    let n = topic_name.clone();
+    let writer_task = tokio::spawn(async move {
+        let batches = vec![ext::arrow::testing::dummy_batch()];
+        actions::do_put(&mut writer, &t, &n, batches, false).await
+    });
+
+    let n_read = topic_name.clone();
+    let reader_task = tokio::spawn(async move {
+        let mut results = Vec::new();
+        for _ in 0..10 {
+            let info = actions::get_flight_info(&mut reader, &n_read).await;
+            results.push(info);
+            tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+        }
+        results
+    });
+
+    let _ = writer_task.await.unwrap().unwrap();
+    let read_results = reader_task.await.unwrap();
+
+    for info in read_results {
+        let info = info.unwrap();
+        let app_metadata: marshal::flight::TopicAppMetadata =
+            info.endpoint[0].clone().app_metadata.try_into().unwrap();
+
+        if app_metadata.locked {
+            assert!(
+                app_metadata.completed_at_ns.is_some(),
+                "locked topic must have completed_at_ns"
+            );
+        }
+    }
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_concurrent_notification_create(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence";
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+
+    let n_notifications = 20;
+    let mut handles = Vec::with_capacity(n_notifications);
+
+    for i in 0..n_notifications {
+        let mut c = common::ClientBuilder::new(common::HOST, port).build().await;
+        let name = sequence_name.to_string();
+        handles.push(tokio::spawn(async move {
+            actions::sequence_notification_create(
+                &mut c,
+                &name,
+                types::NotificationType::Error.to_string(),
+                format!("concurrent msg {}", i),
+            )
+            .await
+        }));
+    }
+
+    for h in handles {
+        h.await.unwrap().unwrap();
+    }
+
+    let r = actions::sequence_notification_list(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let notifications = r["notifications"].as_array().unwrap();
+    assert_eq!(
+        notifications.len(),
+        n_notifications,
+        "no notifications must be lost"
+    );
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_concurrent_sequence_create_and_delete(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut setup = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "race_seq";
+    actions::sequence_create(&mut setup, sequence_name, None)
+        .await
+        .unwrap();
+
+    let mut c1 = common::ClientBuilder::new(common::HOST, port).build().await;
+    let mut c2 = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    // c1 deletes, c2 tries to create a session in it.
+    let h_del = tokio::spawn(async move { actions::sequence_delete(&mut c1, "race_seq").await });
+    let h_session = tokio::spawn(async move { actions::session_create(&mut c2, "race_seq").await });
+
+    let r_del = h_del.await.unwrap();
+    let r_session = h_session.await.unwrap();
+
+    r_del.unwrap();
+
+    if let Err(e) = r_session {
+        assert_eq!(e.code(), tonic::Code::NotFound);
+    }
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_stress_many_sequences_in_parallel(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let n_sequences = 50;
+    let mut handles = Vec::with_capacity(n_sequences);
+
+    for i in 0..n_sequences {
+        let mut c = common::ClientBuilder::new(common::HOST, port).build().await;
+        handles.push(tokio::spawn(async move {
+            let name = format!("stress_seq_{}", i);
+            actions::sequence_create(&mut c, &name, None).await
+        }));
+    }
+
+    for h in handles {
+        h.await.unwrap().unwrap();
+    }
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_startup_checks(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut config = server::flight::Config::new(common::HOST.to_owned(), port);
+
+    let report = server::checks::run(&server.store, &server.db, &config).await;
+    assert!(report.passed(), "{:?}", report);
+    let names: Vec<_> = report.checks.iter().map(|check| check.name).collect();
+    assert_eq!(
+        names,
+        vec![
+            "database",
+            "database_migrations",
+            "store",
+            "config",
+            "clock"
+        ]
+    );
+
+    // The probe object is removed from the store
+    let objects = server.store.list("", None).await.unwrap();
+    assert!(objects.is_empty(), "{objects:?}");
+
+    config.tls(server::flight::TlsConfig {
+        certificate_file: "/not/existing/cert.pem".into(),
+        private_key_file: "/not/existing/key.pem".into(),
+        client_ca_file: None,
+    });
+    let report = server::checks::run(&server.store, &server.db, &config).await;
+    let failures: Vec<_> = report.failures().map(|check| check.name).collect();
+    assert_eq!(failures, vec!["config"]);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_self_test(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let report = actions::self_test(&mut client).await.unwrap();
+    assert_eq!(report["passed"], true, "{report}");
+
+    let steps: Vec<_> = report["steps"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|step| step["step"].as_str().unwrap().to_owned())
+        .collect();
+    assert_eq!(
+        steps,
+        vec![
+            "sequence_create",
+            "session_create",
+            "topic_create",
+            "upload",
+            "session_finalize",
+            "read",
+            "delete"
+        ]
+    );
+    assert!(report["steps"][0]["elapsed_us"].is_u64());
+    assert!(report["steps"][0].get("error").is_none());
+
+    // The temporary sequence is removed, together with its data
+    let sequences = mosaicod_facade::sequence::page(&server.context(), "", None, 10)
+        .await
+        .unwrap();
+    assert!(sequences.handles.is_empty());
+    let objects = server.store.list("", None).await.unwrap();
+    assert!(objects.is_empty(), "{objects:?}");
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_list_flights_pattern(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    for sequence_name in ["run_1", "run_2", "other"] {
+        actions::sequence_create(&mut client, sequence_name, None)
+            .await
+            .unwrap();
+        let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+            .await
+            .unwrap();
+        for topic in ["camera/front", "camera/back", "imu"] {
+            let topic_name = format!("{sequence_name}/{topic}");
+            actions::topic_create(&mut client, &session_uuid, &topic_name, None)
+                .await
+                .unwrap();
+        }
+    }
+
+    // The root lists all the sequences
+    let mut all = actions::list_flights(&mut client, "/").await.unwrap();
+    all.sort();
+    assert_eq!(all, vec!["other", "run_1", "run_2"]);
+
+    assert_eq!(
+        actions::list_flights(&mut client, "run_*").await.unwrap(),
+        vec!["run_1", "run_2"]
+    );
+    assert_eq!(
+        actions::list_flights(&mut client, "run_1/*").await.unwrap(),
+        vec!["run_1/imu"]
+    );
+    assert_eq!(
+        actions::list_flights(&mut client, r#"{"pattern": "run_1/**"}"#)
+            .await
+            .unwrap(),
+        vec!["run_1/camera/back", "run_1/camera/front", "run_1/imu"]
+    );
+    assert_eq!(
+        actions::list_flights(&mut client, "*/camera/f*")
+            .await
+            .unwrap(),
+        vec![
+            "other/camera/front",
+            "run_1/camera/front",
+            "run_2/camera/front"
+        ]
+    );
+    assert!(
+        actions::list_flights(&mut client, "missing_*")
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let res = actions::list_flights(&mut client, "run.1").await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_session_finalize_lockout(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_session_finalize_lockout";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+
+    let max_failures = mosaicod_core::params::params()
+        .key_lockout_max_failures
+        .value;
+    for _ in 0..max_failures {
+        let err = actions::session_finalize(&mut client, &types::Uuid::new())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+    }
+
+    // Once locked out, even the valid key is rejected
+    let err = actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_session_share_lockout(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_session_share_lockout";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+
+    let max_failures = mosaicod_core::params::params()
+        .key_lockout_max_failures
+        .value;
+    for _ in 0..max_failures {
+        let err = actions::session_share(&mut client, &types::Uuid::new(), "fingerprint")
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+    }
+
+    // Once locked out, even the valid key is rejected
+    let err = actions::session_share(&mut client, &session_uuid, "fingerprint")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_security_report(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let report = actions::security_report(&mut client).await.unwrap();
+    assert!(report["peers"].as_array().unwrap().is_empty());
+
+    // Probing unknown keys and locators
+    let err = actions::session_finalize(&mut client, &types::Uuid::new())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+    let err = actions::get_flight_info(&mut client, "unknown_sequence/topic")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    let stats = actions::server_stats(&mut client).await.unwrap();
+    assert_eq!(stats["security"]["not_found"], 2);
+    assert_eq!(stats["security"]["permission_denied"], 0);
+    assert_eq!(stats["security"]["unauthenticated"], 0);
+
+    let report = actions::security_report(&mut client).await.unwrap();
+    let peers = report["peers"].as_array().unwrap();
+    assert_eq!(peers.len(), 1);
+    assert_eq!(peers[0]["peer"], "127.0.0.1");
+    assert_eq!(peers[0]["not_found"], 2);
+    assert!(peers[0]["last_failure_ns"].as_i64().unwrap() > 0);
+    assert!(peers[0]["locked_out_secs"].is_null());
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_sequence_notification_payload(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence_notification_payload";
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+
+    // Structured notification without rendered text
+    actions::sequence_notification_create_json(
+        &mut client,
+        serde_json::json!({
+            "locator": sequence_name,
+            "notification_type": "error",
+            "code": "ingest.missing_column",
+            "params": { "column": "speed" },
+        }),
+    )
+    .await
+    .unwrap();
+
+    // Free text notification
+    actions::sequence_notification_create(
+        &mut client,
+        sequence_name,
+        "error".to_owned(),
+        "upload interrupted".to_owned(),
+    )
+    .await
+    .unwrap();
+
+    let invalid = [
+        serde_json::json!({ "code": "Not A Code" }),
+        serde_json::json!({ "params": { "column": "speed" } }),
+        serde_json::json!({}),
+    ];
+    for mut body in invalid {
+        body["locator"] = sequence_name.into();
+        body["notification_type"] = "error".into();
+        let err = actions::sequence_notification_create_json(&mut client, body)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    let list = actions::sequence_notification_list(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let notifications = list["notifications"].as_array().unwrap();
+    assert_eq!(notifications.len(), 2);
+
+    let structured = notifications
+        .iter()
+        .find(|n| n["code"] == "ingest.missing_column")
+        .unwrap();
+    assert_eq!(
+        structured["params"],
+        serde_json::json!({ "column": "speed" })
+    );
+    assert_eq!(structured["msg"], "");
+
+    let message = notifications
+        .iter()
+        .find(|n| n["code"] == "message")
+        .unwrap();
+    assert_eq!(message["params"], serde_json::json!({}));
+    assert_eq!(message["msg"], "upload interrupted");
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_notification_severity(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_notification_severity";
+    let topic_name = "test_notification_severity/topic";
+    actions::setup_topic_with_notifications(
+        &mut client,
+        sequence_name,
+        topic_name,
+        "alert".to_owned(),
+        1,
+    )
+    .await
+    .unwrap();
+
+    // Without an explicit severity the notification takes the one of its type
+    let topic_list = actions::topic_notification_list(&mut client, topic_name)
+        .await
+        .unwrap();
+    assert_eq!(topic_list["notifications"][0]["severity"], "warning");
+
+    let created = [
+        ("error", None, "disk full"),
+        ("alert", Some("info"), "upload resumed"),
+        ("alert", Some("warning"), "upload slow"),
+    ];
+    let mut created_ns = Vec::new();
+    for (notification_type, severity, msg) in created {
+        created_ns.push(types::Timestamp::now().as_i64());
+        let mut body = serde_json::json!({
+            "locator": sequence_name,
+            "notification_type": notification_type,
+            "msg": msg,
+        });
+        if let Some(severity) = severity {
+            body["severity"] = severity.into();
+        }
+        actions::sequence_notification_create_json(&mut client, body)
+            .await
+            .unwrap();
+    }
+
+    let err = actions::sequence_notification_create_json(
+        &mut client,
+        serde_json::json!({
+            "locator": sequence_name,
+            "notification_type": "error",
+            "severity": "critical",
+            "msg": "unknown severity",
+        }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    // Notifications are listed in creation order, one page at a time
+    let mut msgs = Vec::new();
+    let mut cursor = None;
+    loop {
+        let mut body = serde_json::json!({ "locator": sequence_name, "limit": 2 });
+        if let Some(cursor) = cursor.take() {
+            body["cursor"] = cursor;
+        }
+        let page = actions::sequence_notification_list_json(&mut client, body)
+            .await
+            .unwrap();
+        for notification in page["notifications"].as_array().unwrap() {
+            msgs.push(notification["msg"].as_str().unwrap().to_owned());
+        }
+        match page.get("cursor") {
+            Some(next) => cursor = Some(next.clone()),
+            None => break,
+        }
+    }
+    assert_eq!(msgs, ["disk full", "upload resumed", "upload slow"]);
+
+    let list = actions::sequence_notification_list_json(
+        &mut client,
+        serde_json::json!({ "locator": sequence_name, "min_severity": "warning" }),
+    )
+    .await
+    .unwrap();
+    let severities: Vec<_> = list["notifications"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n["severity"].as_str().unwrap())
+        .collect();
+    assert_eq!(severities, ["error", "warning"]);
+
+    let list = actions::sequence_notification_list_json(
+        &mut client,
+        serde_json::json!({
+            "locator": sequence_name,
+            "since_ns": created_ns[1],
+            "until_ns": created_ns[2],
+        }),
+    )
+    .await
+    .unwrap();
+    let notifications = list["notifications"].as_array().unwrap();
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0]["msg"], "upload resumed");
+
+    let list = actions::topic_notification_list_json(
+        &mut client,
+        serde_json::json!({ "locator": topic_name, "min_severity": "error" }),
+    )
+    .await
+    .unwrap();
+    assert!(list["notifications"].as_array().unwrap().is_empty());
+
+    for body in [
+        serde_json::json!({ "locator": sequence_name, "min_severity": "critical" }),
+        serde_json::json!({ "locator": sequence_name, "cursor": "not-a-cursor" }),
+        serde_json::json!({ "locator": sequence_name, "limit": 0 }),
+    ] {
+        let err = actions::sequence_notification_list_json(&mut client, body)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_sequence_notification_stream(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::AsArray;
+    use futures::StreamExt;
+
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_sequence_notification_stream";
+    let topic_name = "test_sequence_notification_stream/topic";
+    let other_name = "test_sequence_notification_stream_other";
+    actions::setup_topic_with_notifications(
+        &mut client,
+        sequence_name,
+        topic_name,
+        "alert".to_owned(),
+        0,
+    )
+    .await
+    .unwrap();
+    actions::sequence_create(&mut client, other_name, None)
+        .await
+        .unwrap();
+
+    let err = actions::sequence_notification_stream(&mut client, "ghost_sequence", None)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    let err = actions::sequence_notification_stream(&mut client, sequence_name, Some("critical"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    let mut stream =
+        actions::sequence_notification_stream(&mut client, sequence_name, Some("warning"))
+            .await
+            .unwrap();
+
+    // Only the warnings and errors of the sequence and of its topics are streamed
+    let created = [
+        (sequence_name, "info", "upload resumed"),
+        (other_name, "error", "other sequence"),
+        (sequence_name, "warning", "upload slow"),
+    ];
+    for (locator, severity, msg) in created {
+        let body = serde_json::json!({
+            "locator": locator,
+            "notification_type": "alert",
+            "severity": severity,
+            "msg": msg,
+        });
+        actions::sequence_notification_create_json(&mut client, body)
+            .await
+            .unwrap();
+    }
+    actions::topic_notification_create(
+        &mut client,
+        topic_name,
+        "error".to_owned(),
+        "disk full".to_owned(),
+    )
+    .await
+    .unwrap();
+
+    let mut received = Vec::new();
+    for _ in 0..2 {
+        let batch = tokio::time::timeout(std::time::Duration::from_secs(10), stream.next())
+            .await
+            .expect("notification not streamed")
+            .unwrap()
+            .unwrap();
+        assert_eq!(batch.num_rows(), 1);
+        let column = |name: &str| {
+            batch
+                .column_by_name(name)
+                .unwrap()
+                .as_string::<i32>()
+                .value(0)
+                .to_owned()
+        };
+        assert_eq!(column("code"), "message");
+        received.push((column("name"), column("severity"), column("msg")));
+    }
+    assert_eq!(
+        received,
+        [
+            (
+                sequence_name.to_owned(),
+                "warning".to_owned(),
+                "upload slow".to_owned()
+            ),
+            (
+                topic_name.to_owned(),
+                "error".to_owned(),
+                "disk full".to_owned()
+            ),
+        ]
+    );
+
+    // Streams end when the server shuts down
+    server.shutdown().await;
+    let next = tokio::time::timeout(std::time::Duration::from_secs(10), stream.next())
+        .await
+        .expect("notification stream not closed");
+    assert!(next.is_none());
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_sequence_list(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    for name in ["run_3", "run_1", "other", "run_2", "run_4", "run_5"] {
+        actions::sequence_create(&mut client, name, None)
+            .await
+            .unwrap();
+    }
+
+    // Walk the pages of the sequences with prefix `run_`
+    let mut locators = Vec::new();
+    let mut cursor = serde_json::Value::Null;
+    let mut pages = 0;
+    loop {
+        let page = actions::sequence_list(
+            &mut client,
+            serde_json::json!({ "prefix": "run_", "limit": 2, "cursor": cursor }),
+        )
+        .await
+        .unwrap();
+        pages += 1;
+
+        for locator in page["locators"].as_array().unwrap() {
+            locators.push(locator.as_str().unwrap().to_owned());
+        }
+
+        cursor = page["cursor"].clone();
+        if cursor.is_null() {
+            break;
+        }
+    }
+    assert_eq!(pages, 3);
+    assert_eq!(locators, ["run_1", "run_2", "run_3", "run_4", "run_5"]);
+
+    // Without parameters all the sequences are returned in a single page
+    let page = actions::sequence_list(&mut client, serde_json::json!({}))
+        .await
+        .unwrap();
+    assert_eq!(page["locators"].as_array().unwrap().len(), 6);
+    assert_eq!(page["locators"][0], "other");
+    assert!(page["cursor"].is_null());
+
+    let err = actions::sequence_list(&mut client, serde_json::json!({ "limit": 0 }))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_activity_feed(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_activity_feed";
+    let topic_name = format!("{sequence_name}/topic");
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    actions::topic_create(&mut client, &session_uuid, &topic_name, None)
+        .await
+        .unwrap();
+    actions::sequence_notification_create(
+        &mut client,
+        sequence_name,
+        "error".to_owned(),
+        "sequence error".to_owned(),
+    )
+    .await
+    .unwrap();
+    actions::topic_notification_create(
+        &mut client,
+        &topic_name,
+        "error".to_owned(),
+        "topic error".to_owned(),
+    )
+    .await
+    .unwrap();
+
+    // Walk the pages of the feed
+    let mut activities = Vec::new();
+    let mut cursor = serde_json::Value::Null;
+    loop {
+        let page = actions::activity_feed(
+            &mut client,
+            serde_json::json!({ "locator": sequence_name, "limit": 2, "cursor": cursor }),
+        )
+        .await
+        .unwrap();
+
+        activities.extend(page["activities"].as_array().unwrap().iter().cloned());
+
+        cursor = page["cursor"].clone();
+        if cursor.is_null() {
+            break;
+        }
+    }
+
+    let events: Vec<_> = activities
+        .iter()
+        .map(|a| (a["event"].as_str().unwrap(), a["subject"].as_str().unwrap()))
+        .collect();
+    let session = session_uuid.to_string();
+    assert_eq!(
+        events,
+        [
+            ("sequence_created", sequence_name),
+            ("session_created", session.as_str()),
+            ("topic_created", topic_name.as_str()),
+            ("notification", sequence_name),
+            ("notification", topic_name.as_str()),
+        ]
+    );
+    assert!(activities[0]["notification"].is_null());
+    assert_eq!(activities[3]["notification"]["msg"], "sequence error");
+    assert_eq!(activities[4]["notification"]["code"], "message");
+    assert_eq!(activities[4]["notification"]["notification_type"], "error");
+
+    // Only the entries created since the timestamp are returned
+    let since_ns = activities[3]["timestamp_ns"].clone();
+    let page = actions::activity_feed(
+        &mut client,
+        serde_json::json!({ "locator": sequence_name, "since_ns": since_ns }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(page["activities"].as_array().unwrap().len(), 2);
+    assert!(page["cursor"].is_null());
+
+    let err = actions::activity_feed(
+        &mut client,
+        serde_json::json!({ "locator": sequence_name, "cursor": "invalid" }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    let err = actions::activity_feed(&mut client, serde_json::json!({ "locator": "missing" }))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_consumer_offset(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::{Int64Array, RecordBatch};
+    use std::sync::Arc;
+
+    let port = common::random_port();
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_consumer_offset";
+    let topic_name = format!("{sequence_name}/topic");
+    let consumer = "indexer";
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let uuid = actions::topic_create(&mut client, &session_uuid, &topic_name, None)
+        .await
+        .unwrap();
+    let batch = RecordBatch::try_new(
+        ext::arrow::testing::dummy_batch().schema(),
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3])),
+            Arc::new(Int64Array::from(vec![0, 0, 0])),
+        ],
+    )
+    .unwrap();
+    actions::do_put(&mut client, &uuid, &topic_name, vec![batch], false)
+        .await
+        .unwrap();
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    // Nothing committed yet
+    let offset = actions::consumer_offset_get(&mut client, consumer, sequence_name)
+        .await
+        .unwrap();
+    assert_eq!(offset["consumer"], consumer);
+    assert!(offset["cursor"].is_null());
+    assert!(offset["committed_at_ns"].is_null());
+
+    // Commit the end of the feed, committing it again has no effect
+    let page = actions::activity_feed(&mut client, serde_json::json!({ "locator": sequence_name }))
+        .await
+        .unwrap();
+    assert!(page["cursor"].is_null());
+    let end_cursor = page["end_cursor"].clone();
+
+    let commit = serde_json::json!({
+        "consumer": consumer,
+        "locator": sequence_name,
+        "cursor": end_cursor,
+    });
+    let committed = actions::consumer_offset_commit(&mut client, commit.clone())
         .await
         .unwrap();
+    assert_eq!(committed["cursor"], end_cursor);
+    assert!(committed["committed_at_ns"].is_i64());
 
+    let recommitted = actions::consumer_offset_commit(&mut client, commit)
+        .await
+        .unwrap();
+    assert_eq!(recommitted, committed);
+
+    // Resuming from the committed offset returns the new entries only
+    actions::sequence_notification_create(
+        &mut client,
+        sequence_name,
+        "error".to_owned(),
+        "sequence error".to_owned(),
+    )
+    .await
+    .unwrap();
+
+    let offset = actions::consumer_offset_get(&mut client, consumer, sequence_name)
+        .await
+        .unwrap();
+    let page = actions::activity_feed(
+        &mut client,
+        serde_json::json!({ "locator": sequence_name, "cursor": offset["cursor"] }),
+    )
+    .await
+    .unwrap();
+    let events: Vec<_> = page["activities"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a["event"].as_str().unwrap())
+        .collect();
+    assert_eq!(events, ["notification"]);
+
+    // Offsets can not move backward
+    let first_page = actions::activity_feed(
+        &mut client,
+        serde_json::json!({ "locator": sequence_name, "limit": 1 }),
+    )
+    .await
+    .unwrap();
+    let err = actions::consumer_offset_commit(
+        &mut client,
+        serde_json::json!({
+            "consumer": consumer,
+            "locator": sequence_name,
+            "cursor": first_page["cursor"],
+        }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::Aborted);
+
+    // Topics commit their watermark, which can not be past the one of the topic
+    let commit_watermark = |watermark_ns: i64| {
+        serde_json::json!({
+            "consumer": consumer,
+            "locator": topic_name,
+            "watermark_ns": watermark_ns,
+        })
+    };
+    let committed = actions::consumer_offset_commit(&mut client, commit_watermark(3))
+        .await
+        .unwrap();
+    assert_eq!(committed["watermark_ns"], 3);
+    assert!(committed["cursor"].is_null());
+
+    let err = actions::consumer_offset_commit(&mut client, commit_watermark(10))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    let err = actions::consumer_offset_commit(&mut client, commit_watermark(2))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::Aborted);
+
+    let err = actions::consumer_offset_commit(
+        &mut client,
+        serde_json::json!({ "consumer": consumer, "locator": topic_name, "cursor": end_cursor }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    // Offsets of different consumers are independent
+    let offset = actions::consumer_offset_get(&mut client, "exporter", &topic_name)
+        .await
+        .unwrap();
+    assert!(offset["watermark_ns"].is_null());
+
+    let err = actions::consumer_offset_commit(
+        &mut client,
+        serde_json::json!({ "consumer": "bad name", "locator": topic_name, "watermark_ns": 3 }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    // Deleting the offset resets the consumer
+    actions::consumer_offset_delete(&mut client, consumer, &topic_name)
+        .await
+        .unwrap();
+    let offset = actions::consumer_offset_get(&mut client, consumer, &topic_name)
+        .await
+        .unwrap();
+    assert!(offset["watermark_ns"].is_null());
+
+    let err = actions::consumer_offset_delete(&mut client, consumer, &topic_name)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_topic_list(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_topic_list";
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+
+    let list = actions::topic_list(&mut client, sequence_name)
+        .await
+        .unwrap();
+    assert!(list["topics"].as_array().unwrap().is_empty());
+
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    for (name, format) in [("topic_b", "ragged"), ("topic_a", "default")] {
+        actions::topic_create_with_format(
+            &mut client,
+            &session_uuid,
+            &format!("{sequence_name}/{name}"),
+            format,
+            None,
+        )
+        .await
+        .unwrap();
+    }
+
+    let list = actions::topic_list(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let topics = list["topics"].as_array().unwrap();
+    assert_eq!(topics.len(), 2);
+    assert_eq!(topics[0]["locator"], "test_topic_list/topic_a");
+    assert_eq!(topics[0]["serialization_format"], "default");
+    assert_eq!(topics[1]["locator"], "test_topic_list/topic_b");
+    assert_eq!(topics[1]["serialization_format"], "ragged");
+    for topic in topics {
+        assert_eq!(topic["ontology_tag"], "mock");
+        assert!(topic["created_at_ns"].as_i64().unwrap() > 0);
+        assert!(topic["completed_at_ns"].is_null());
+    }
+
+    let err = actions::topic_list(&mut client, "missing")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_reindex(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_reindex";
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+
+    // Only finalized topics are reindexed
+    for name in ["topic_a", "topic_b", "topic_c"] {
+        let topic_name = format!("{sequence_name}/{name}");
+        let uuid = actions::topic_create(&mut client, &session_uuid, &topic_name, None)
+            .await
+            .unwrap();
+        let batches = vec![ext::arrow::testing::dummy_batch()];
+        actions::do_put(&mut client, &uuid, &topic_name, batches, false)
+            .await
+            .unwrap();
+    }
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    actions::topic_create(
+        &mut client,
+        &session_uuid,
+        &format!("{sequence_name}/pending"),
+        None,
+    )
+    .await
+    .unwrap();
+
+    let topic_name = "test_reindex/topic_a";
+    let stats = actions::topic_column_stats(&mut client, topic_name, &["value"])
+        .await
+        .unwrap();
+
+    let status = actions::reindex_status(&mut client).await.unwrap();
+    assert!(status["jobs"].as_array().unwrap().is_empty());
+
+    let started = actions::reindex(&mut client, "all", Some(2)).await.unwrap();
+    let scopes: Vec<&str> = started["jobs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|job| job["scope"].as_str().unwrap())
+        .collect();
+    assert_eq!(scopes, ["column_stats", "chunk_stats", "data_info"]);
+
+    let mut status = actions::reindex_status(&mut client).await.unwrap();
+    for _ in 0..100 {
+        let running = status["jobs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|job| job["running"].as_bool().unwrap());
+        if !running {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        status = actions::reindex_status(&mut client).await.unwrap();
+    }
+    for job in status["jobs"].as_array().unwrap() {
+        assert_eq!(job["running"], false, "{job}");
+        assert_eq!(job["processed"], 3, "{job}");
+        assert_eq!(job["failed"], 0, "{job}");
+        assert!(job["completed_at_ns"].as_i64().unwrap() >= job["started_at_ns"].as_i64().unwrap());
+        assert!(job.get("error").is_none());
+    }
+
+    // The rebuilt state matches the one computed when the data was written
+    assert_eq!(
+        actions::topic_column_stats(&mut client, topic_name, &["value"])
+            .await
+            .unwrap(),
+        stats
+    );
+    let mut topics = actions::query(
+        &mut client,
+        serde_json::json!({ "ontology": { "mock.value": { "$gt": 6 } } }),
+    )
+    .await
+    .unwrap();
+    topics.sort();
+    assert_eq!(
+        topics,
+        [
+            "test_reindex/topic_a",
+            "test_reindex/topic_b",
+            "test_reindex/topic_c"
+        ]
+    );
+    let topics = actions::query(
+        &mut client,
+        serde_json::json!({ "ontology": { "mock.value": { "$gt": 7 } } }),
+    )
+    .await
+    .unwrap();
+    assert!(topics.is_empty());
+
+    let err = actions::reindex(&mut client, "rollups", None)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    let err = actions::reindex(&mut client, "data_info", Some(0))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_replay_capture(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let capture = actions::replay_capture_dump(&mut client).await.unwrap();
+    assert_eq!(capture["active"], false);
+    assert!(capture.get("started_at_ns").is_none());
+    assert!(capture["entries"].as_array().unwrap().is_empty());
+
+    // Requests received before the capture is started are not recorded
+    let sequence_name = "test_replay_capture";
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+
+    let capture = actions::replay_capture_start(&mut client, 60)
+        .await
+        .unwrap();
+    assert_eq!(capture["active"], true);
+    assert!(capture["until_ns"].as_i64().unwrap() > capture["started_at_ns"].as_i64().unwrap());
+
+    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let topic_name = format!("{sequence_name}/topic");
+    let uuid = actions::topic_create(&mut client, &session_uuid, &topic_name, None)
+        .await
+        .unwrap();
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    actions::do_put(&mut client, &uuid, &topic_name, batches, false)
+        .await
+        .unwrap();
+    let err = actions::session_finalize(&mut client, &types::Uuid::new())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    let capture = actions::replay_capture_dump(&mut client).await.unwrap();
+    let entries = capture["entries"].as_array().unwrap();
+    let kinds: Vec<&str> = entries
+        .iter()
+        .map(|entry| entry["action"].as_str().unwrap_or("do_put"))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            "session_create",
+            "topic_create",
+            "do_put",
+            "session_finalize"
+        ]
+    );
+
+    // Locators are anonymized, keeping the segments shared by the requests
+    let session_locator = entries[0]["payload"]["locator"].as_str().unwrap();
+    let topic_locator = entries[1]["payload"]["locator"].as_str().unwrap();
+    assert!(!capture.to_string().contains(sequence_name));
+    assert_eq!(topic_locator.split('/').next(), Some(session_locator));
+    assert_eq!(entries[2]["kind"], "do_put");
+    assert_eq!(entries[2]["locator"], topic_locator);
+    assert_eq!(entries[2]["status"], "Ok");
+    assert!(entries[2]["messages"].as_u64().unwrap() >= 2);
+    assert!(entries[2]["bytes"].as_u64().unwrap() > 0);
+    assert_eq!(entries[3]["status"], "NotFound");
+
+    let err = actions::replay_capture_start(&mut client, 0)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_sequence_trash_restore_and_purge(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .build()
+        .await;
+
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
+
+    let sequence_name = "test_trash";
+    let topic_name = &format!("{}/my_topic", sequence_name);
+
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let (session_locator, session_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+        .await
+        .unwrap();
     let batches = vec![ext::arrow::testing::dummy_batch()];
     actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
         .await
@@ -1735,55 +6957,159 @@ async fn test_do_put_on_locked_topic(pool: sqlx::Pool<db::DatabaseType>) {
         .await
         .unwrap();
 
-    let batches = vec![ext::arrow::testing::dummy_batch()];
-    let res = actions::do_put(&mut client, &topic_uuid, topic_name, batches, false).await;
-    assert_eq!(res.unwrap_err().code(), tonic::Code::FailedPrecondition);
+    // Only sequences in the trash can be restored or purged
+    let err = actions::sequence_restore(&mut client, sequence_name)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+    let err = actions::sequence_purge(&mut client, sequence_name)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    actions::sequence_delete(&mut client, sequence_name)
+        .await
+        .unwrap();
+
+    // The sequence and its resources are hidden while in the trash
+    let page = actions::sequence_list(&mut client, serde_json::json!({}))
+        .await
+        .unwrap();
+    assert!(page["locators"].as_array().unwrap().is_empty());
+    let res = actions::get_flight_info(&mut client, topic_name).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+    let res = actions::session_create(&mut client, sequence_name).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    actions::sequence_restore(&mut client, sequence_name)
+        .await
+        .unwrap();
+
+    let page = actions::sequence_list(&mut client, serde_json::json!({}))
+        .await
+        .unwrap();
+    assert_eq!(page["locators"][0], sequence_name);
+    actions::get_flight_info(&mut client, topic_name)
+        .await
+        .unwrap();
+
+    actions::sequence_delete(&mut client, sequence_name)
+        .await
+        .unwrap();
+    actions::sequence_purge(&mut client, sequence_name)
+        .await
+        .unwrap();
+
+    // Purged sequences can not be restored
+    let err = actions::sequence_restore(&mut client, sequence_name)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+    let res = actions::session_delete(&mut client, &session_locator).await;
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    // The name is available again once purged
+    actions::sequence_create(&mut client, sequence_name, None)
+        .await
+        .unwrap();
 
     server.shutdown().await;
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_do_put_descriptor_mismatch(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_sequence_attestation(pool: sqlx::Pool<db::DatabaseType>) {
     let port = common::random_port();
+
     let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .enable_attestations()
         .build()
         .await;
+
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
-    let sequence_name = "test_sequence";
-    let topic_a = &format!("{}/topic_a", sequence_name);
-    let topic_b = &format!("{}/topic_b", sequence_name);
+    let sequence_name = "test_sequence_attestation";
+    let topic_name = &format!("{}/my_topic", sequence_name);
 
     actions::sequence_create(&mut client, sequence_name, None)
         .await
         .unwrap();
+
+    // Sessions not finalized are not attested
     let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
-    let uuid_a = actions::topic_create(&mut client, &session_uuid, topic_a, None)
+    let response = actions::sequence_attestation(&mut client, sequence_name)
         .await
         .unwrap();
-    let _uuid_b = actions::topic_create(&mut client, &session_uuid, topic_b, None)
+    assert!(response["attestations"].as_array().unwrap().is_empty());
+
+    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
         .await
         .unwrap();
-
     let batches = vec![ext::arrow::testing::dummy_batch()];
-    let res = actions::do_put(&mut client, &uuid_a, topic_b, batches, false).await;
-    assert_eq!(res.unwrap_err().code(), tonic::Code::PermissionDenied);
+    actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
+        .await
+        .unwrap();
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
+
+    let response = actions::sequence_attestation(&mut client, sequence_name)
+        .await
+        .unwrap();
+    let attestations = response["attestations"].as_array().unwrap();
+    assert_eq!(attestations.len(), 1);
+    assert_eq!(attestations[0]["session_uuid"], session_uuid.to_string());
+    assert_eq!(attestations[0]["algorithm"], "ed25519");
+    assert_eq!(
+        attestations[0]["manifest_sha256"].as_str().unwrap().len(),
+        64
+    );
+    assert_eq!(attestations[0]["valid"], true);
+
+    // Altering the manifest invalidates the attestation
+    let context = server.context();
+    let handle =
+        facade::sequence::Handle::try_from_locator(&context, sequence_name.parse().unwrap())
+            .await
+            .unwrap();
+    let manifest_path = facade::sequence::path_in_store(&context, &handle)
+        .await
+        .unwrap()
+        .path_session_manifest(&session_uuid);
+    let mut manifest: serde_json::Value =
+        serde_json::from_slice(&server.store.read_bytes(&manifest_path).await.unwrap()).unwrap();
+    manifest["completed_at"] = serde_json::json!(0);
+    server
+        .store
+        .write_bytes(&manifest_path, serde_json::to_vec(&manifest).unwrap())
+        .await
+        .unwrap();
+
+    let response = actions::sequence_attestation(&mut client, sequence_name)
+        .await
+        .unwrap();
+    assert_eq!(response["attestations"][0]["valid"], false);
 
     server.shutdown().await;
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_do_put_empty_batches(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_topic_table_export(pool: sqlx::Pool<db::DatabaseType>) {
+    use arrow::array::{Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+
     let port = common::random_port();
+
     let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .enable_table_export()
         .build()
         .await;
+
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
-    let sequence_name = "test_sequence";
-    let topic_name = &format!("{}/no_batches", sequence_name);
+    let sequence_name = "test_topic_table_export";
+    let topic_name = &format!("{}/my_topic", sequence_name);
 
     actions::sequence_create(&mut client, sequence_name, None)
         .await
@@ -1794,427 +7120,624 @@ async fn test_do_put_empty_batches(pool: sqlx::Pool<db::DatabaseType>) {
     let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
         .await
         .unwrap();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new(
+            mosaicod_core::params::ARROW_SCHEMA_COLUMN_NAME_INDEX_TIMESTAMP,
+            DataType::Int64,
+            false,
+        ),
+        Field::new("name", DataType::Utf8, true),
+    ]));
+    let batch = arrow::array::RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![10000, 10005])),
+            Arc::new(StringArray::from(vec!["ann", "bob"])),
+        ],
+    )
+    .unwrap();
+    actions::do_put(&mut client, &topic_uuid, topic_name, vec![batch], false)
+        .await
+        .unwrap();
 
-    let res = actions::do_put(&mut client, &topic_uuid, topic_name, vec![], false).await;
-    assert!(res.is_err(), "do_put with no batches should error");
-
-    server.shutdown().await;
-}
-
-// ===========================================================================
-// Get server version  tests
-// ===========================================================================
-
-#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_get_server_version(pool: sqlx::Pool<db::DatabaseType>) {
-    let port = common::random_port();
-
-    let server = common::ServerBuilder::new(common::HOST, port, pool)
-        .build()
-        .await;
-
-    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
-
-    actions::server_version(&mut client).await.unwrap();
-
-    server.shutdown().await;
-}
+    let context = server.context();
+    let handle = facade::topic::Handle::try_from_locator(&context, topic_name.parse().unwrap())
+        .await
+        .unwrap();
+    let log_folder = handle.path_in_store().unwrap().root().join("_delta_log");
+    let commit = |version: u64| log_folder.join(format!("{version:020}.json"));
+    let actions = |bytes: Vec<u8>| -> Vec<serde_json::Value> {
+        bytes
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect()
+    };
 
-// ===========================================================================
-// Concurrent tests
-// ===========================================================================
+    // Tables are exported when the session is finalized
+    assert!(!server.store.exists(commit(0)).await.unwrap());
+    actions::session_finalize(&mut client, &session_uuid)
+        .await
+        .unwrap();
 
-#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_concurrent_sequence_create(pool: sqlx::Pool<db::DatabaseType>) {
-    let port = common::random_port();
-    let server = common::ServerBuilder::new(common::HOST, port, pool)
-        .build()
-        .await;
+    let version = actions(server.store.read_bytes(commit(0)).await.unwrap());
+    assert_eq!(version.len(), 4);
+    assert_eq!(version[0]["commitInfo"]["operation"], "WRITE");
+    assert_eq!(version[1]["protocol"]["minReaderVersion"], 1);
+    assert_eq!(version[2]["metaData"]["id"], topic_uuid.to_string());
+    let schema: serde_json::Value =
+        serde_json::from_str(version[2]["metaData"]["schemaString"].as_str().unwrap()).unwrap();
+    assert_eq!(schema["fields"][1]["name"], "name");
+    assert_eq!(schema["fields"][1]["type"], "string");
 
-    let sequence_name = "concurrent_seq";
+    let path = version[3]["add"]["path"].as_str().unwrap();
+    assert!(path.starts_with("data/"));
+    let size = server
+        .store
+        .size(handle.path_in_store().unwrap().root().join(path))
+        .await
+        .unwrap();
+    assert_eq!(version[3]["add"]["size"], size);
 
-    let mut client1 = common::ClientBuilder::new(common::HOST, port).build().await;
-    let mut client2 = common::ClientBuilder::new(common::HOST, port).build().await;
+    // Tables already up to date are left untouched
+    let exporter = facade::TableExporter::new(facade::TableFormat::Delta);
+    assert_eq!(exporter.export(&context, &handle).await.unwrap(), None);
 
-    let h1 = tokio::spawn(async move {
-        actions::sequence_create(&mut client1, "concurrent_seq", None).await
-    });
-    let h2 = tokio::spawn(async move {
-        actions::sequence_create(&mut client2, "concurrent_seq", None).await
-    });
+    // Data files rewritten by a redaction are added again
+    actions::topic_redact(&mut client, topic_name, (None, None), &["name"], "null")
+        .await
+        .unwrap();
 
-    let r1 = h1.await.unwrap();
-    let r2 = h2.await.unwrap();
+    let version = actions(server.store.read_bytes(commit(1)).await.unwrap());
+    assert_eq!(version.len(), 2);
+    assert_eq!(version[1]["add"]["path"], path);
+    assert!(!server.store.exists(commit(2)).await.unwrap());
 
-    let (success_count, already_exists_count) =
-        [&r1, &r2]
-            .iter()
-            .fold((0usize, 0usize), |(succ, ae), r| match r {
-                Ok(_) => (succ + 1, ae),
-                Err(e) if e.code() == tonic::Code::AlreadyExists => (succ, ae + 1),
-                Err(e) => panic!("unexpected error: {:?}", e),
-            });
+    // Data files shared by a copy are replaced with links, referenced by their target
+    actions::sequence_create(&mut client, "test_topic_table_export_copy", None)
+        .await
+        .unwrap();
+    actions::topic_copy(&mut client, topic_name, "test_topic_table_export_copy")
+        .await
+        .unwrap();
 
-    assert_eq!(success_count, 1);
-    assert_eq!(already_exists_count, 1);
+    let data_file = handle.path_in_store().unwrap().root().join(path);
+    let target = server.store.link_target(&data_file).await.unwrap().unwrap();
+    let url = server.store.object_url(&target).unwrap();
 
-    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
-    let info = actions::get_flight_info(&mut client, sequence_name).await;
-    assert!(info.is_ok());
+    let version = actions(server.store.read_bytes(commit(2)).await.unwrap());
+    assert_eq!(version.len(), 3);
+    assert_eq!(version[1]["add"]["path"], url.as_str());
+    assert_eq!(version[2]["remove"]["path"], path);
+    assert_eq!(url.to_file_path().unwrap(), server.store.root.join(&target));
 
     server.shutdown().await;
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_concurrent_session_finalize(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_sequence_legal_hold(pool: sqlx::Pool<db::DatabaseType>) {
     let port = common::random_port();
+
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
         .await;
+
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
-    let sequence_name = "test_sequence";
+    let sequence_name = "test_legal_hold";
     let topic_name = &format!("{}/my_topic", sequence_name);
 
     actions::sequence_create(&mut client, sequence_name, None)
         .await
         .unwrap();
-    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
-        .await
-        .unwrap();
-    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+    let (session_locator, session_uuid) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
-    let batches = vec![ext::arrow::testing::dummy_batch()];
-    actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
+    actions::topic_create(&mut client, &session_uuid, topic_name, None)
         .await
         .unwrap();
 
-    let mut c1 = common::ClientBuilder::new(common::HOST, port).build().await;
-    let mut c2 = common::ClientBuilder::new(common::HOST, port).build().await;
-    let s1 = session_uuid.clone();
-    let s2 = session_uuid.clone();
+    let err = actions::sequence_legal_hold(&mut client, "missing_sequence", true)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
 
-    let h1 = tokio::spawn(async move { actions::session_finalize(&mut c1, &s1).await });
-    let h2 = tokio::spawn(async move { actions::session_finalize(&mut c2, &s2).await });
+    actions::sequence_legal_hold(&mut client, sequence_name, true)
+        .await
+        .unwrap();
 
-    let r1 = h1.await.unwrap();
-    let r2 = h2.await.unwrap();
+    // No resource of a held sequence can be deleted
+    let err = actions::topic_delete(&mut client, topic_name)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    let err = actions::session_delete(&mut client, &session_locator)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    let err = actions::sequence_delete(&mut client, sequence_name)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
 
-    let success_count = [&r1, &r2].iter().filter(|r| r.is_ok()).count();
-    assert_eq!(success_count, 1);
+    // The hold is kept while in the trash
+    actions::sequence_legal_hold(&mut client, sequence_name, false)
+        .await
+        .unwrap();
+    actions::sequence_delete(&mut client, sequence_name)
+        .await
+        .unwrap();
+    actions::sequence_legal_hold(&mut client, sequence_name, true)
+        .await
+        .unwrap();
+    let err = actions::sequence_purge(&mut client, sequence_name)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
 
-    for r in [&r1, &r2] {
-        if let Err(e) = r {
-            assert!(
-                matches!(e.code(), tonic::Code::FailedPrecondition),
-                "unexpected error code: {:?}",
-                e.code()
-            );
-        }
-    }
+    actions::sequence_legal_hold(&mut client, sequence_name, false)
+        .await
+        .unwrap();
+    actions::sequence_purge(&mut client, sequence_name)
+        .await
+        .unwrap();
 
     server.shutdown().await;
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_concurrent_do_put_same_topic(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_sequence_update_metadata(pool: sqlx::Pool<db::DatabaseType>) {
     let port = common::random_port();
+
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
         .await;
+
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
-    let sequence_name = "test_sequence";
-    let topic_name = &format!("{}/concurrent_topic", sequence_name);
+    let sequence_name = "test_update_metadata";
+    let topic_name = &format!("{}/my_topic", sequence_name);
 
-    actions::sequence_create(&mut client, sequence_name, None)
+    actions::sequence_create(&mut client, sequence_name, Some(r#"{"driver": "john"}"#))
         .await
         .unwrap();
+
+    let err = actions::sequence_update_metadata(
+        &mut client,
+        "missing_sequence",
+        serde_json::json!({}),
+        true,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    actions::sequence_update_metadata(
+        &mut client,
+        sequence_name,
+        serde_json::json!({"weather": "sunny"}),
+        true,
+    )
+    .await
+    .unwrap();
+    actions::sequence_update_metadata(
+        &mut client,
+        sequence_name,
+        serde_json::json!({"driver": "jane"}),
+        false,
+    )
+    .await
+    .unwrap();
+
     let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
     let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
         .await
         .unwrap();
-
-    let mut c1 = common::ClientBuilder::new(common::HOST, port).build().await;
-    let mut c2 = common::ClientBuilder::new(common::HOST, port).build().await;
-
-    let t1 = topic_uuid.clone();
-    let t2 = topic_uuid.clone();
-    let n1 = topic_name.clone();
-    let n2 = topic_name.clone();
-
-    let h1 = tokio::spawn(async move {
-        let batches = vec![ext::arrow::testing::dummy_batch()];
-        actions::do_put(&mut c1, &t1, &n1, batches, false).await
-    });
-    let h2 = tokio::spawn(async move {
-        let batches = vec![ext::arrow::testing::dummy_batch()];
-        actions::do_put(&mut c2, &t2, &n2, batches, false).await
-    });
-
-    let r1 = h1.await.unwrap();
-    let r2 = h2.await.unwrap();
-
-    let success_count = [&r1, &r2].iter().filter(|r| r.is_ok()).count();
-    assert!(success_count >= 1, "at least one writer must succeed");
-
-    for r in [&r1, &r2] {
-        if let Err(e) = r {
-            assert!(
-                matches!(
-                    e.code(),
-                    tonic::Code::FailedPrecondition | tonic::Code::Aborted
-                ),
-                "unexpected error code: {:?}",
-                e.code()
-            );
-        }
-    }
-
-    actions::session_finalize(&mut client, &session_uuid)
+    let batches = vec![ext::arrow::testing::dummy_batch()];
+    actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
         .await
         .unwrap();
 
-    let info = actions::get_flight_info(&mut client, topic_name)
+    // Still allowed while the session is open
+    actions::sequence_update_metadata(
+        &mut client,
+        sequence_name,
+        serde_json::json!({"track": "monza"}),
+        true,
+    )
+    .await
+    .unwrap();
+
+    actions::session_finalize(&mut client, &session_uuid)
         .await
         .unwrap();
-    let app_metadata: marshal::flight::TopicAppMetadata =
-        info.endpoint[0].clone().app_metadata.try_into().unwrap();
-    let chunks = app_metadata.info.unwrap().chunks_number;
 
-    assert_eq!(chunks as usize, success_count);
+    let err = actions::sequence_update_metadata(
+        &mut client,
+        sequence_name,
+        serde_json::json!({"weather": "rainy"}),
+        true,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
 
     server.shutdown().await;
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_concurrent_topic_create_during_finalize(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_session_list(pool: sqlx::Pool<db::DatabaseType>) {
     let port = common::random_port();
+
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
         .await;
-    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
-    let sequence_name = "test_sequence";
-    let existing_topic = &format!("{}/existing", sequence_name);
-    let new_topic = &format!("{}/new", sequence_name);
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
+    let sequence_name = "test_session_list";
     actions::sequence_create(&mut client, sequence_name, None)
         .await
         .unwrap();
-    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+
+    let list = actions::session_list(&mut client, sequence_name, None)
         .await
         .unwrap();
+    assert!(list["sessions"].as_array().unwrap().is_empty());
 
-    let topic_uuid = actions::topic_create(&mut client, &session_uuid, existing_topic, None)
+    // A finalized session with two topics
+    let (finalized_locator, finalized_uuid) = actions::session_create(&mut client, sequence_name)
         .await
         .unwrap();
-    let batches = vec![ext::arrow::testing::dummy_batch()];
-    actions::do_put(&mut client, &topic_uuid, existing_topic, batches, false)
+    for name in ["topic_a", "topic_b"] {
+        let topic_name = &format!("{sequence_name}/{name}");
+        let topic_uuid = actions::topic_create(&mut client, &finalized_uuid, topic_name, None)
+            .await
+            .unwrap();
+        let batches = vec![ext::arrow::testing::dummy_batch()];
+        actions::do_put(&mut client, &topic_uuid, topic_name, batches, false)
+            .await
+            .unwrap();
+    }
+    actions::session_finalize(&mut client, &finalized_uuid)
         .await
         .unwrap();
 
-    let mut c1 = common::ClientBuilder::new(common::HOST, port).build().await;
-    let mut c2 = common::ClientBuilder::new(common::HOST, port).build().await;
-    let s1 = session_uuid.clone();
-    let s2 = session_uuid.clone();
-    let new_topic_owned = new_topic.clone();
+    // An open session without topics
+    let (open_locator, open_uuid) = actions::session_create(&mut client, sequence_name)
+        .await
+        .unwrap();
 
-    let h_finalize = tokio::spawn(async move { actions::session_finalize(&mut c1, &s1).await });
-    let h_create =
-        tokio::spawn(
-            async move { actions::topic_create(&mut c2, &s2, &new_topic_owned, None).await },
-        );
+    let list = actions::session_list(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    let sessions = list["sessions"].as_array().unwrap();
+    assert_eq!(sessions.len(), 2);
 
-    let r_fin = h_finalize.await.unwrap();
-    let r_create = h_create.await.unwrap();
+    assert_eq!(sessions[0]["uuid"], finalized_uuid.to_string());
+    assert_eq!(sessions[0]["locator"], finalized_locator.to_string());
+    assert_eq!(sessions[0]["locked"], true);
+    assert_eq!(sessions[0]["topic_count"], 2);
+    assert!(
+        sessions[0]["completed_at_ns"].as_i64().unwrap()
+            >= sessions[0]["created_at_ns"].as_i64().unwrap()
+    );
 
-    // Expected: one of two coherent outcomes
-    //  A) finalize wins -> create gets FailedPrecondition
-    //  B) create wins -> finalize succeeds afterwards
-    match (r_fin.is_ok(), r_create.is_ok()) {
-        (true, false) => {
-            assert_eq!(
-                r_create.unwrap_err().code(),
-                tonic::Code::FailedPrecondition
-            );
-        }
-        (true, true) => {}
-        (false, true) => {
-            assert_eq!(r_fin.unwrap_err().code(), tonic::Code::FailedPrecondition);
-        }
-        (false, false) => {
-            panic!("not possible");
-        }
-    }
+    assert_eq!(sessions[1]["uuid"], open_uuid.to_string());
+    assert_eq!(sessions[1]["locator"], open_locator.to_string());
+    assert_eq!(sessions[1]["locked"], false);
+    assert_eq!(sessions[1]["topic_count"], 0);
+    assert!(sessions[1]["created_at_ns"].as_i64().unwrap() > 0);
+    assert!(sessions[1]["completed_at_ns"].is_null());
+
+    let list = actions::session_list(&mut client, sequence_name, Some("open"))
+        .await
+        .unwrap();
+    let sessions = list["sessions"].as_array().unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0]["uuid"], open_uuid.to_string());
+
+    let list = actions::session_list(&mut client, sequence_name, Some("finalized"))
+        .await
+        .unwrap();
+    let sessions = list["sessions"].as_array().unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0]["uuid"], finalized_uuid.to_string());
+
+    let err = actions::session_list(&mut client, sequence_name, Some("closed"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    let err = actions::session_list(&mut client, "missing", None)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
 
     server.shutdown().await;
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_concurrent_read_during_write(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_topic_and_session_list_pages(pool: sqlx::Pool<db::DatabaseType>) {
     let port = common::random_port();
+
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
         .await;
-    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
-    let sequence_name = "test_sequence";
-    let topic_name = &format!("{}/read_during_write", sequence_name);
+    let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
+    let sequence_name = "test_list_pages";
     actions::sequence_create(&mut client, sequence_name, None)
         .await
         .unwrap();
-    let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+
+    let mut session_uuids = Vec::new();
+    for _ in 0..3 {
+        let (_, session_uuid) = actions::session_create(&mut client, sequence_name)
+            .await
+            .unwrap();
+        session_uuids.push(session_uuid);
+    }
+    for name in ["topic_c", "topic_a", "topic_b"] {
+        actions::topic_create(
+            &mut client,
+            &session_uuids[0],
+            &format!("{sequence_name}/{name}"),
+            None,
+        )
         .await
         .unwrap();
-    let topic_uuid = actions::topic_create(&mut client, &session_uuid, topic_name, None)
+    }
+
+    // Topics are paged by locator
+    let mut locators = Vec::new();
+    let mut cursor = serde_json::Value::Null;
+    loop {
+        let page = actions::topic_list_page(
+            &mut client,
+            serde_json::json!({ "locator": sequence_name, "limit": 2, "cursor": cursor }),
+        )
         .await
         .unwrap();
+        let topics = page["topics"].as_array().unwrap();
+        assert!(topics.len() <= 2);
+        locators.extend(
+            topics
+                .iter()
+                .map(|t| t["locator"].as_str().unwrap().to_owned()),
+        );
 
-    let mut writer = common::ClientBuilder::new(common::HOST, port).build().await;
-    let mut reader = common::ClientBuilder::new(common::HOST, port).build().await;
-
-    let t = topic_uuid.clone();
-    let n = topic_name.clone();
-    let writer_task = tokio::spawn(async move {
-        let batches = vec![ext::arrow::testing::dummy_batch()];
-        actions::do_put(&mut writer, &t, &n, batches, false).await
-    });
-
-    let n_read = topic_name.clone();
-    let reader_task = tokio::spawn(async move {
-        let mut results = Vec::new();
-        for _ in 0..10 {
-            let info = actions::get_flight_info(&mut reader, &n_read).await;
-            results.push(info);
-            tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+        cursor = page["cursor"].clone();
+        if cursor.is_null() {
+            break;
         }
-        results
-    });
-
-    let _ = writer_task.await.unwrap().unwrap();
-    let read_results = reader_task.await.unwrap();
+    }
+    assert_eq!(
+        locators,
+        [
+            "test_list_pages/topic_a",
+            "test_list_pages/topic_b",
+            "test_list_pages/topic_c"
+        ]
+    );
 
-    for info in read_results {
-        let info = info.unwrap();
-        let app_metadata: marshal::flight::TopicAppMetadata =
-            info.endpoint[0].clone().app_metadata.try_into().unwrap();
+    // Sessions are paged by creation time
+    let mut uuids: Vec<types::Uuid> = Vec::new();
+    let mut cursor = serde_json::Value::Null;
+    loop {
+        let page = actions::session_list_page(
+            &mut client,
+            serde_json::json!({ "locator": sequence_name, "limit": 1, "cursor": cursor }),
+        )
+        .await
+        .unwrap();
+        let sessions = page["sessions"].as_array().unwrap();
+        assert_eq!(sessions.len(), 1);
+        uuids.push(sessions[0]["uuid"].as_str().unwrap().parse().unwrap());
 
-        if app_metadata.locked {
-            assert!(
-                app_metadata.completed_at_ns.is_some(),
-                "locked topic must have completed_at_ns"
-            );
+        cursor = page["cursor"].clone();
+        if cursor.is_null() {
+            break;
         }
     }
+    assert_eq!(uuids, session_uuids);
+
+    let err = actions::session_list_page(
+        &mut client,
+        serde_json::json!({ "locator": sequence_name, "cursor": "invalid" }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
 
     server.shutdown().await;
 }
 
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_concurrent_notification_create(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_session_abort(pool: sqlx::Pool<db::DatabaseType>) {
     let port = common::random_port();
+
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
         .await;
+
     let mut client = common::ClientBuilder::new(common::HOST, port).build().await;
 
-    let sequence_name = "test_sequence";
+    let sequence_name = "test_session_abort";
+    let topic_name = &format!("{sequence_name}/topic");
     actions::sequence_create(&mut client, sequence_name, None)
         .await
         .unwrap();
 
-    let n_notifications = 20;
-    let mut handles = Vec::with_capacity(n_notifications);
-
-    for i in 0..n_notifications {
-        let mut c = common::ClientBuilder::new(common::HOST, port).build().await;
-        let name = sequence_name.to_string();
-        handles.push(tokio::spawn(async move {
-            actions::sequence_notification_create(
-                &mut c,
-                &name,
-                types::NotificationType::Error.to_string(),
-                format!("concurrent msg {}", i),
-            )
+    async fn upload(
+        client: &mut common::Client,
+        sequence_name: &str,
+        topic_name: &str,
+    ) -> types::Uuid {
+        let (_, session_uuid) = actions::session_create(client, sequence_name)
             .await
-        }));
+            .unwrap();
+        let topic_uuid = actions::topic_create(client, &session_uuid, topic_name, None)
+            .await
+            .unwrap();
+        let batches = vec![ext::arrow::testing::dummy_batch()];
+        actions::do_put(client, &topic_uuid, topic_name, batches, false)
+            .await
+            .unwrap();
+        session_uuid
     }
 
-    for h in handles {
-        h.await.unwrap().unwrap();
-    }
+    // The session and its topic are discarded
+    let session_uuid = upload(&mut client, sequence_name, topic_name).await;
+    actions::session_abort(&mut client, &session_uuid)
+        .await
+        .unwrap();
+    let list = actions::session_list(&mut client, sequence_name, None)
+        .await
+        .unwrap();
+    assert!(list["sessions"].as_array().unwrap().is_empty());
+    let err = actions::do_get(&mut client, topic_name).await.unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
 
-    let r = actions::sequence_notification_list(&mut client, sequence_name)
+    let err = actions::session_abort(&mut client, &session_uuid)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    // The topic can be uploaded again, finalized sessions can not be aborted
+    let session_uuid = upload(&mut client, sequence_name, topic_name).await;
+    actions::session_finalize(&mut client, &session_uuid)
         .await
         .unwrap();
-    let notifications = r["notifications"].as_array().unwrap();
-    assert_eq!(
-        notifications.len(),
-        n_notifications,
-        "no notifications must be lost"
+    let err = actions::session_abort(&mut client, &session_uuid)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    assert!(
+        !actions::do_get(&mut client, topic_name)
+            .await
+            .unwrap()
+            .is_empty()
     );
 
     server.shutdown().await;
 }
 
+// ===========================================================================
+// Client tests
+// ===========================================================================
+
+/// The typed methods of the client cover the lifecycle of a sequence, from its creation to
+/// the download of its data, and map the errors returned by the server.
 #[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_concurrent_sequence_create_and_delete(pool: sqlx::Pool<db::DatabaseType>) {
+async fn test_client_lifecycle(pool: sqlx::Pool<db::DatabaseType>) {
+    use mosaico_client::{
+        ErrorKind, NotificationCreate, NotificationFilter, TimestampRange, TopicCreate,
+    };
+
     let port = common::random_port();
     let server = common::ServerBuilder::new(common::HOST, port, pool)
         .build()
         .await;
-    let mut setup = common::ClientBuilder::new(common::HOST, port).build().await;
+    let mut client = mosaico_client::ClientBuilder::new(common::HOST, port)
+        .connect()
+        .await
+        .unwrap();
 
-    let sequence_name = "race_seq";
-    actions::sequence_create(&mut setup, sequence_name, None)
+    client
+        .sequence_create("test_sequence", serde_json::json!({ "robot": "r1" }))
         .await
         .unwrap();
+    let err = client
+        .sequence_create("test_sequence", serde_json::json!({}))
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
 
-    let mut c1 = common::ClientBuilder::new(common::HOST, port).build().await;
-    let mut c2 = common::ClientBuilder::new(common::HOST, port).build().await;
+    let session = client.session_create("test_sequence").await.unwrap();
+    let topic_uuid = client
+        .topic_create(
+            &session.uuid,
+            TopicCreate::new("test_sequence/imu", "mock").with_sort_key("timestamp_ns"),
+        )
+        .await
+        .unwrap();
 
-    // c1 deletes, c2 tries to create a session in it.
-    let h_del = tokio::spawn(async move { actions::sequence_delete(&mut c1, "race_seq").await });
-    let h_session = tokio::spawn(async move { actions::session_create(&mut c2, "race_seq").await });
+    let batch = ext::arrow::testing::dummy_batch();
+    client
+        .upload("test_sequence/imu", &topic_uuid, vec![batch.clone()])
+        .await
+        .unwrap();
+    client.session_finalize(&session.uuid).await.unwrap();
 
-    let r_del = h_del.await.unwrap();
-    let r_session = h_session.await.unwrap();
+    let sessions = client
+        .session_list("test_sequence", None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(sessions.sessions.len(), 1);
+    assert!(sessions.sessions[0].locked);
 
-    r_del.unwrap();
+    let topics = client
+        .topic_list("test_sequence", None, None)
+        .await
+        .unwrap();
+    let locators: Vec<_> = topics.topics.iter().map(|t| t.locator.as_str()).collect();
+    assert_eq!(locators, ["test_sequence/imu"]);
 
-    if let Err(e) = r_session {
-        assert_eq!(e.code(), tonic::Code::NotFound);
-    }
+    let batches = client
+        .download("test_sequence/imu", TimestampRange::default())
+        .await
+        .unwrap();
+    let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+    assert_eq!(rows, batch.num_rows());
 
-    server.shutdown().await;
-}
+    let batches = client
+        .download(
+            "test_sequence/imu",
+            TimestampRange {
+                start_ns: Some(10010),
+                end_ns: Some(10020),
+            },
+        )
+        .await
+        .unwrap();
+    let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+    assert!(rows > 0 && rows < batch.num_rows());
 
-#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
-async fn test_stress_many_sequences_in_parallel(pool: sqlx::Pool<db::DatabaseType>) {
-    let port = common::random_port();
-    let server = common::ServerBuilder::new(common::HOST, port, pool)
-        .build()
-        .await;
+    let page = client.sequence_list(None, None, None).await.unwrap();
+    assert_eq!(page.locators, ["test_sequence"]);
 
-    let n_sequences = 50;
-    let mut handles = Vec::with_capacity(n_sequences);
+    let err = client.topic_list("missing", None, None).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
 
-    for i in 0..n_sequences {
-        let mut c = common::ClientBuilder::new(common::HOST, port).build().await;
-        handles.push(tokio::spawn(async move {
-            let name = format!("stress_seq_{}", i);
-            actions::sequence_create(&mut c, &name, None).await
-        }));
-    }
+    let watermark = client.topic_watermark("test_sequence/imu").await.unwrap();
+    assert!(watermark.complete);
+    assert_eq!(watermark.pending_sessions, 0);
 
-    for h in handles {
-        h.await.unwrap().unwrap();
-    }
+    client
+        .sequence_notification_create(
+            NotificationCreate::new("test_sequence", "error").with_msg("battery low"),
+        )
+        .await
+        .unwrap();
+    let notifications = client
+        .sequence_notification_list("test_sequence", NotificationFilter::default(), None, None)
+        .await
+        .unwrap();
+    assert_eq!(notifications.notifications.len(), 1);
+    assert_eq!(notifications.notifications[0].msg, "battery low");
+
+    let err = client
+        .sequence_update_metadata("test_sequence", serde_json::json!({ "site": "lab" }), true)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::FailedPrecondition);
+
+    let version = client.version().await.unwrap();
+    assert!(!version.version.is_empty());
 
     server.shutdown().await;
 }
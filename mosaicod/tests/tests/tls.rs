@@ -94,3 +94,73 @@ async fn test_tls_client_with_invalid_cert(pool: sqlx::Pool<db::DatabaseType>) {
 
     server.shutdown().await;
 }
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_mtls_with_valid_client_cert(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .enable_mtls()
+        .build()
+        .await;
+
+    let res = server.is_shutdown().await;
+    assert!(!res);
+
+    let mut client = common::ClientBuilder::new(common::HOST, port)
+        .enable_tls()
+        .with_identity(
+            common::TLS_CLIENT_CERT_FILE,
+            common::TLS_CLIENT_PRIVATE_KEY_FILE,
+        )
+        .build()
+        .await;
+
+    actions::sequence_create(&mut client, "test_sequence", None)
+        .await
+        .unwrap();
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_mtls_without_client_cert(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .enable_mtls()
+        .build()
+        .await;
+
+    // With TLS 1.3 the client certificate is verified after the client completes the
+    // handshake, so the connection is refused on the first request
+    let mut client = common::ClientBuilder::new(common::HOST, port)
+        .enable_tls()
+        .build()
+        .await;
+    let res = actions::sequence_create(&mut client, "test_sequence", None).await;
+    assert!(res.is_err());
+
+    server.shutdown().await;
+}
+
+#[sqlx::test(migrator = "mosaicod_db::testing::MIGRATOR")]
+async fn test_mtls_with_untrusted_client_cert(pool: sqlx::Pool<db::DatabaseType>) {
+    let port = common::random_port();
+
+    let server = common::ServerBuilder::new(common::HOST, port, pool)
+        .enable_mtls()
+        .build()
+        .await;
+
+    // The certificate of the server is not signed by the client authority
+    let mut client = common::ClientBuilder::new(common::HOST, port)
+        .enable_tls()
+        .with_identity(common::TLS_CERT_FILE, common::TLS_PRIVATE_KEY_FILE)
+        .build()
+        .await;
+    let res = actions::sequence_create(&mut client, "test_sequence", None).await;
+    assert!(res.is_err());
+
+    server.shutdown().await;
+}